@@ -0,0 +1,43 @@
+use tracing_subscriber::fmt::init;
+
+use trainingground_api::{
+    config::Config,
+    services::{
+        archive_worker::ArchiveWorker, session_archive_service::SessionArchiveService, AppState,
+    },
+};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    init();
+
+    let config = Config::load().expect("Failed to load configuration");
+
+    let mongo_client = mongodb::Client::with_uri_str(&config.mongo_uri)
+        .await
+        .expect("Failed to connect to MongoDB");
+
+    let redis_client =
+        redis::Client::open(config.redis_uri.clone()).expect("Failed to create Redis client");
+
+    let app_state = AppState::new(config.clone(), mongo_client, redis_client)
+        .await
+        .expect("Failed to initialize app state");
+
+    let archive_storage = app_state
+        .archive_storage
+        .clone()
+        .expect("Object storage must be configured for archive worker");
+
+    let service = SessionArchiveService::new(app_state.mongo.clone());
+    let worker = ArchiveWorker::new(
+        service,
+        archive_storage,
+        app_state.redis.clone(),
+        config.archive,
+    );
+
+    worker.run().await?;
+
+    Ok(())
+}
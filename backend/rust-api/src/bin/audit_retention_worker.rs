@@ -0,0 +1,44 @@
+use tracing_subscriber::fmt::init;
+
+use trainingground_api::{
+    config::Config,
+    services::{
+        audit_archive_service::AuditArchiveService, audit_retention_worker::AuditRetentionWorker,
+        AppState,
+    },
+};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    init();
+
+    let config = Config::load().expect("Failed to load configuration");
+
+    let mongo_client = mongodb::Client::with_uri_str(&config.mongo_uri)
+        .await
+        .expect("Failed to connect to MongoDB");
+
+    let redis_client =
+        redis::Client::open(config.redis_uri.clone()).expect("Failed to create Redis client");
+
+    let app_state = AppState::new(config.clone(), mongo_client, redis_client)
+        .await
+        .expect("Failed to initialize app state");
+
+    let archive_storage = app_state
+        .archive_storage
+        .clone()
+        .expect("Object storage must be configured for audit retention worker");
+
+    let service = AuditArchiveService::new(app_state.mongo.clone());
+    let worker = AuditRetentionWorker::new(
+        service,
+        archive_storage,
+        app_state.redis.clone(),
+        config.audit,
+    );
+
+    worker.run().await?;
+
+    Ok(())
+}
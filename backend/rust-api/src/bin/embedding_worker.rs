@@ -0,0 +1,38 @@
+use std::sync::Arc;
+
+use tracing_subscriber::fmt::init;
+
+use trainingground_api::{
+    config::Config,
+    services::{
+        embedding_worker::{EmbeddingJobWorker, StreamEmbeddingBackend},
+        AppState,
+    },
+};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    init();
+
+    let config = Config::load().expect("Failed to load configuration");
+
+    let mongo_client = mongodb::Client::with_uri_str(&config.mongo_uri)
+        .await
+        .expect("Failed to connect to MongoDB");
+
+    let redis_client =
+        redis::Client::open(config.redis_uri.clone()).expect("Failed to create Redis client");
+
+    let app_state = AppState::new(config.clone(), mongo_client, redis_client)
+        .await
+        .expect("Failed to initialize app state");
+
+    let backend =
+        StreamEmbeddingBackend::new(app_state.redis.clone(), config.content.stream_name.clone());
+    let worker =
+        EmbeddingJobWorker::new(app_state.mongo.clone(), Arc::new(backend), config.content);
+
+    worker.run().await?;
+
+    Ok(())
+}
@@ -1,8 +1,14 @@
+use std::sync::Arc;
+
 use tracing_subscriber::fmt::init;
 
 use trainingground_api::{
     config::Config,
-    services::{export_worker::ExportWorker, reporting_service::ReportingService, AppState},
+    services::{
+        block_expiry_worker::BlockExpiryWorker, email_service::EmailService,
+        email_worker::EmailWorker, export_schedule_worker::ExportScheduleWorker,
+        export_worker::ExportWorker, reporting_service::ReportingService, AppState,
+    },
 };
 
 #[tokio::main]
@@ -27,8 +33,40 @@ async fn main() -> anyhow::Result<()> {
         .clone()
         .expect("Object storage must be configured for export worker");
 
+    // Снятие истёкших блокировок не требует отдельного процесса
+    let block_expiry = BlockExpiryWorker::new(app_state.mongo.clone(), config.accounts.clone());
+    tokio::spawn(async move {
+        if let Err(err) = block_expiry.run().await {
+            tracing::error!(error = %err, "block expiry worker stopped");
+        }
+    });
+
+    // Письма учителей ученикам: обработчик только кладёт их в email_outbox
+    let email_worker = EmailWorker::new(
+        app_state.mongo.clone(),
+        Arc::new(EmailService::new(app_state.mongo.clone())),
+        config.email_outbox.clone(),
+    );
+    tokio::spawn(async move {
+        if let Err(err) = email_worker.run().await {
+            tracing::error!(error = %err, "email worker stopped");
+        }
+    });
+
+    // Расписания только ставят выгрузки в очередь, файлы собирает основной воркер
+    let schedule_worker = ExportScheduleWorker::new(
+        ReportingService::new(app_state.mongo.clone(), app_state.redis.clone()),
+        app_state.export_links.clone(),
+        config.clone(),
+    );
+    tokio::spawn(async move {
+        if let Err(err) = schedule_worker.run().await {
+            tracing::error!(error = %err, "export schedule worker stopped");
+        }
+    });
+
     let reporting_service = ReportingService::new(app_state.mongo.clone(), app_state.redis.clone());
-    let worker = ExportWorker::new(reporting_service, object_storage, config);
+    let worker = ExportWorker::new(reporting_service, Arc::new(object_storage), config);
 
     worker.run().await?;
 
@@ -0,0 +1,493 @@
+//! Типизированный HTTP-клиент API для внутренних сервисов и скриптов.
+//!
+//! Тела запросов и ответов - те же модели из `models`, что используют хендлеры
+//! (обратные serde-derive включает фича `client`), поэтому изменение формы ответа
+//! ломает сборку клиента, а не его вызовы в рантайме.
+//!
+//! Клиент хранит access- и refresh-токен после `login`: на 401 он один раз обновляет
+//! access-токен через `POST /api/v1/auth/refresh` и повторяет запрос. Для изменяющих
+//! запросов сам выставляет CSRF-токен (double-submit cookie), nonce и timestamp.
+
+use chrono::Utc;
+use reqwest::{header, Method, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::models::{
+    answer::{SubmitAnswerRequest, SubmitAnswerResponse},
+    refresh_token::RefreshTokenResponse,
+    reporting::{
+        ExportListResponse, ExportRequest, ExportResponse, ExportScheduleRequest,
+        ExportScheduleResponse, ExportStatusResponse, GroupStatsResponse, TopicComparisonResponse,
+        TopicStatsResponse, UserStatsResponse,
+    },
+    user::{AuthResponseCookie, LoginRequest, UserProfile},
+    CreateSessionRequest, CreateSessionResponse,
+};
+
+const AUTH_PREFIX: &str = "/api/v1/auth";
+const REFRESH_COOKIE_NAME: &str = "refresh_token";
+const CSRF_COOKIE_NAME: &str = "csrf_token";
+
+pub type ClientResult<T> = Result<T, ClientError>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    /// Сеть, таймаут или тело ответа не разобралось в ожидаемую модель
+    #[error("HTTP request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    /// API ответил ошибкой. `code` есть у ответов в формате `ErrorResponse`;
+    /// у эндпоинтов с ошибкой-строкой (`/stats`) и у отказов middleware его нет
+    #[error("API error {status}: {message}")]
+    Api {
+        status: StatusCode,
+        code: Option<String>,
+        message: String,
+        details: Option<Value>,
+    },
+    /// Запрос требует входа, а токенов нет или refresh-токен отклонён
+    #[error("Not authenticated")]
+    NotAuthenticated,
+    #[error("Failed to encode request body: {0}")]
+    Encode(#[from] serde_json::Error),
+}
+
+impl ClientError {
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            ClientError::Http(error) => error.status(),
+            ClientError::Api { status, .. } => Some(*status),
+            ClientError::NotAuthenticated => Some(StatusCode::UNAUTHORIZED),
+            ClientError::Encode(_) => None,
+        }
+    }
+
+    /// Машиночитаемый код ошибки API, например `SESSION_EXPIRED`
+    pub fn code(&self) -> Option<&str> {
+        match self {
+            ClientError::Api { code, .. } => code.as_deref(),
+            _ => None,
+        }
+    }
+
+    async fn from_response(response: reqwest::Response) -> Self {
+        #[derive(Deserialize)]
+        struct ErrorBody {
+            code: String,
+            message: String,
+            details: Option<Value>,
+        }
+
+        let status = response.status();
+        let text = match response.text().await {
+            Ok(text) => text,
+            Err(error) => return ClientError::Http(error),
+        };
+        if let Ok(body) = serde_json::from_str::<ErrorBody>(&text) {
+            return ClientError::Api {
+                status,
+                code: Some(body.code),
+                message: body.message,
+                details: body.details,
+            };
+        }
+        let message = serde_json::from_str::<String>(&text).unwrap_or(text);
+        ClientError::Api {
+            status,
+            code: None,
+            message,
+            details: None,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Credentials {
+    access_token: Option<String>,
+    refresh_token: Option<String>,
+}
+
+pub struct ApiClient {
+    http: reqwest::Client,
+    base_url: String,
+    csrf_token: String,
+    credentials: RwLock<Credentials>,
+}
+
+impl ApiClient {
+    /// `base_url` - адрес API без завершающего `/`, например `http://localhost:8081`
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self::with_http_client(reqwest::Client::new(), base_url)
+    }
+
+    pub fn with_http_client(http: reqwest::Client, base_url: impl Into<String>) -> Self {
+        Self {
+            http,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            csrf_token: Uuid::new_v4().to_string(),
+            credentials: RwLock::new(Credentials::default()),
+        }
+    }
+
+    /// Клиент с уже выданными токенами (например, сервисная учётная запись).
+    /// Без refresh-токена истёкший access-токен даёт `NotAuthenticated`
+    pub fn with_tokens(
+        base_url: impl Into<String>,
+        access_token: impl Into<String>,
+        refresh_token: Option<String>,
+    ) -> Self {
+        let client = Self::new(base_url);
+        Self {
+            credentials: RwLock::new(Credentials {
+                access_token: Some(access_token.into()),
+                refresh_token,
+            }),
+            ..client
+        }
+    }
+
+    pub async fn access_token(&self) -> Option<String> {
+        self.credentials.read().await.access_token.clone()
+    }
+
+    // --- auth ---
+
+    /// `POST /api/v1/auth/login`; сохраняет access-токен и refresh-токен из cookie
+    pub async fn login(&self, email: &str, password: &str) -> ClientResult<UserProfile> {
+        let body = LoginRequest {
+            email: email.to_string(),
+            password: password.to_string(),
+            remember_me: false,
+        };
+        let response = self
+            .build(Method::POST, "/api/v1/auth/login", Some(json_body(&body)?))
+            .await
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(ClientError::from_response(response).await);
+        }
+
+        let refresh_token = response
+            .headers()
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .find_map(|cookie| cookie_value(cookie, REFRESH_COOKIE_NAME));
+        let auth: AuthResponseCookie = response.json().await?;
+
+        let mut credentials = self.credentials.write().await;
+        credentials.access_token = Some(auth.access_token);
+        credentials.refresh_token = refresh_token;
+        Ok(auth.user)
+    }
+
+    /// `POST /api/v1/auth/refresh`. Обычно вызывать не нужно - клиент обновляет
+    /// токен сам при ответе 401
+    pub async fn refresh(&self) -> ClientResult<()> {
+        let refresh_token = self
+            .credentials
+            .read()
+            .await
+            .refresh_token
+            .clone()
+            .ok_or(ClientError::NotAuthenticated)?;
+        let response = self
+            .http
+            .post(self.url("/api/v1/auth/refresh"))
+            .header(
+                header::COOKIE,
+                format!("{}={}", REFRESH_COOKIE_NAME, refresh_token),
+            )
+            .send()
+            .await?;
+        match response.status() {
+            status if status.is_success() => {
+                let body: RefreshTokenResponse = response.json().await?;
+                self.credentials.write().await.access_token = Some(body.access_token);
+                Ok(())
+            }
+            StatusCode::UNAUTHORIZED => Err(ClientError::NotAuthenticated),
+            _ => Err(ClientError::from_response(response).await),
+        }
+    }
+
+    /// `POST /api/v1/auth/logout`; отзывает refresh-токен и забывает оба токена
+    pub async fn logout(&self) -> ClientResult<()> {
+        self.send_empty(Method::POST, "/api/v1/auth/logout", None)
+            .await?;
+        *self.credentials.write().await = Credentials::default();
+        Ok(())
+    }
+
+    pub async fn current_user(&self) -> ClientResult<UserProfile> {
+        self.send(Method::GET, "/api/v1/auth/me", None).await
+    }
+
+    // --- sessions ---
+
+    pub async fn create_session(
+        &self,
+        request: &CreateSessionRequest,
+    ) -> ClientResult<CreateSessionResponse> {
+        self.send(Method::POST, "/api/v1/sessions", Some(json_body(request)?))
+            .await
+    }
+
+    pub async fn submit_answer(
+        &self,
+        session_id: &str,
+        request: &SubmitAnswerRequest,
+    ) -> ClientResult<SubmitAnswerResponse> {
+        self.send(
+            Method::POST,
+            &format!("/api/v1/sessions/{}/answers", session_id),
+            Some(json_body(request)?),
+        )
+        .await
+    }
+
+    /// Истёкшая сессия - ошибка 409 с кодом `SESSION_EXPIRED` и итогом в `details`
+    pub async fn complete_session(&self, session_id: &str) -> ClientResult<()> {
+        self.send_empty(
+            Method::POST,
+            &format!("/api/v1/sessions/{}/complete", session_id),
+            None,
+        )
+        .await
+    }
+
+    // --- reporting ---
+
+    pub async fn group_stats(&self, group_id: &str) -> ClientResult<GroupStatsResponse> {
+        self.send(Method::GET, &format!("/stats/groups/{}", group_id), None)
+            .await
+    }
+
+    pub async fn user_stats(&self, user_id: &str) -> ClientResult<UserStatsResponse> {
+        self.send(Method::GET, &format!("/stats/users/{}", user_id), None)
+            .await
+    }
+
+    pub async fn topic_stats(&self, topic_id: &str) -> ClientResult<TopicStatsResponse> {
+        self.send(Method::GET, &format!("/stats/topics/{}", topic_id), None)
+            .await
+    }
+
+    /// Сравнение темы по группам (`?group_id=` до 5 раз)
+    pub async fn compare_topic(
+        &self,
+        topic_id: &str,
+        group_ids: &[&str],
+    ) -> ClientResult<TopicComparisonResponse> {
+        let query = group_ids
+            .iter()
+            .map(|id| format!("group_id={}", id))
+            .collect::<Vec<_>>()
+            .join("&");
+        self.send(
+            Method::GET,
+            &format!("/stats/topics/{}?{}", topic_id, query),
+            None,
+        )
+        .await
+    }
+
+    pub async fn request_group_export(
+        &self,
+        group_id: &str,
+        request: &ExportRequest,
+    ) -> ClientResult<ExportResponse> {
+        self.send(
+            Method::POST,
+            &format!("/stats/groups/{}/export", group_id),
+            Some(json_body(request)?),
+        )
+        .await
+    }
+
+    pub async fn request_user_export(
+        &self,
+        user_id: &str,
+        request: &ExportRequest,
+    ) -> ClientResult<ExportResponse> {
+        self.send(
+            Method::POST,
+            &format!("/stats/users/{}/export", user_id),
+            Some(json_body(request)?),
+        )
+        .await
+    }
+
+    pub async fn export_status(&self, export_id: &str) -> ClientResult<ExportStatusResponse> {
+        self.send(Method::GET, &format!("/stats/exports/{}", export_id), None)
+            .await
+    }
+
+    pub async fn list_group_exports(
+        &self,
+        group_id: &str,
+        limit: u32,
+        offset: u32,
+    ) -> ClientResult<ExportListResponse> {
+        self.send(
+            Method::GET,
+            &format!(
+                "/stats/groups/{}/exports?limit={}&offset={}",
+                group_id, limit, offset
+            ),
+            None,
+        )
+        .await
+    }
+
+    pub async fn create_export_schedule(
+        &self,
+        group_id: &str,
+        request: &ExportScheduleRequest,
+    ) -> ClientResult<ExportScheduleResponse> {
+        self.send(
+            Method::POST,
+            &format!("/stats/groups/{}/export-schedules", group_id),
+            Some(json_body(request)?),
+        )
+        .await
+    }
+
+    pub async fn list_export_schedules(
+        &self,
+        group_id: &str,
+    ) -> ClientResult<Vec<ExportScheduleResponse>> {
+        self.send(
+            Method::GET,
+            &format!("/stats/groups/{}/export-schedules", group_id),
+            None,
+        )
+        .await
+    }
+
+    pub async fn delete_export_schedule(
+        &self,
+        group_id: &str,
+        schedule_id: &str,
+    ) -> ClientResult<()> {
+        self.send_empty(
+            Method::DELETE,
+            &format!(
+                "/stats/groups/{}/export-schedules/{}",
+                group_id, schedule_id
+            ),
+            None,
+        )
+        .await
+    }
+
+    // --- транспорт ---
+
+    async fn send<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Vec<u8>>,
+    ) -> ClientResult<T> {
+        Ok(self.execute(method, path, body).await?.json().await?)
+    }
+
+    async fn send_empty(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Vec<u8>>,
+    ) -> ClientResult<()> {
+        self.execute(method, path, body).await.map(|_| ())
+    }
+
+    /// Отправляет запрос; на 401 один раз обновляет access-токен и повторяет
+    async fn execute(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Vec<u8>>,
+    ) -> ClientResult<reqwest::Response> {
+        let response = self
+            .build(method.clone(), path, body.clone())
+            .await
+            .send()
+            .await?;
+        let can_refresh = self.credentials.read().await.refresh_token.is_some();
+        let response = if response.status() == StatusCode::UNAUTHORIZED && can_refresh {
+            self.refresh().await?;
+            self.build(method, path, body).await.send().await?
+        } else {
+            response
+        };
+
+        if response.status().is_success() {
+            Ok(response)
+        } else {
+            Err(ClientError::from_response(response).await)
+        }
+    }
+
+    async fn build(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Vec<u8>>,
+    ) -> reqwest::RequestBuilder {
+        let builder = self.request(method, path).await;
+        match body {
+            Some(body) => builder
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(body),
+            None => builder,
+        }
+    }
+
+    /// Заголовки авторизации и CSRF. Refresh-токен уходит только на `/api/v1/auth`,
+    /// как и cookie в браузере
+    async fn request(&self, method: Method, path: &str) -> reqwest::RequestBuilder {
+        let credentials = self.credentials.read().await;
+        let mut builder = self.http.request(method.clone(), self.url(path));
+
+        if let Some(token) = credentials.access_token.as_deref() {
+            builder = builder.bearer_auth(token);
+        }
+
+        let mut cookies = Vec::new();
+        if !matches!(method, Method::GET | Method::HEAD | Method::OPTIONS) {
+            cookies.push(format!("{}={}", CSRF_COOKIE_NAME, self.csrf_token));
+            builder = builder
+                .header("x-csrf-token", &self.csrf_token)
+                .header("x-request-nonce", Uuid::new_v4().to_string())
+                .header("x-request-timestamp", Utc::now().timestamp().to_string());
+        }
+        if let Some(refresh_token) = credentials
+            .refresh_token
+            .as_deref()
+            .filter(|_| path.starts_with(AUTH_PREFIX))
+        {
+            cookies.push(format!("{}={}", REFRESH_COOKIE_NAME, refresh_token));
+        }
+        if !cookies.is_empty() {
+            builder = builder.header(header::COOKIE, cookies.join("; "));
+        }
+        builder
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+}
+
+fn json_body<T: Serialize>(body: &T) -> ClientResult<Vec<u8>> {
+    Ok(serde_json::to_vec(body)?)
+}
+
+/// Значение cookie `name` из заголовка `Set-Cookie`
+fn cookie_value(set_cookie: &str, name: &str) -> Option<String> {
+    let (key, value) = set_cookie.split(';').next()?.trim().split_once('=')?;
+    (key == name && !value.is_empty()).then(|| value.to_string())
+}
@@ -1,20 +1,47 @@
-use serde::Deserialize;
-use std::{env, time::Duration};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, env, fmt, time::Duration};
+
+use crate::utils::secure_compare::{constant_time_eq, verify_secret};
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct Config {
+    /// Профиль из `APP_ENV`: в production нарушения инвариантов не дают стартовать
+    pub profile: AppProfile,
     pub mongo_uri: String,
     pub redis_uri: String,
     pub mongo_database: String,
     pub jwt_secret: String,
     pub jwt_fallback_secrets: Vec<String>,
+    /// Именованные ключи подписи JWT (`kid` -> секрет). Пустая карта - только `jwt_secret`
+    pub jwt_keys: BTreeMap<String, String>,
+    /// Ключ, которым подписываются новые токены; без него - последний по имени
+    pub jwt_active_key_id: Option<String>,
     pub python_api_url: String,
     pub reporting: ReportingSettings,
     pub content: ContentSettings,
+    pub hints: HintSettings,
+    pub yandexgpt: YandexGptConfig,
+    pub llm: LlmConfig,
+    pub sessions: SessionSettings,
+    pub anticheat_signals: AnticheatSignalSettings,
+    pub accounts: AccountSettings,
+    pub email_outbox: EmailOutboxSettings,
     pub logging: LoggingSettings,
     pub cookie: CookieSettings,
+    pub cors: CorsSettings,
     pub superuser_seed_file: Option<String>,
+    /// `SUPERUSER_ROTATE=1` - при старте сменить пароль суперпользователя и отозвать его сессии
+    pub superuser_rotate: bool,
+    /// `SUPERUSER_ROTATE_PRINT_PASSWORD=1` - вывести новый пароль в лог (один раз, при ротации)
+    pub superuser_rotate_print_password: bool,
     pub object_storage: Option<ObjectStorageSettings>,
+    pub archive: ArchiveSettings,
+    pub audit: AuditSettings,
+    pub tracing: TracingSettings,
+    pub rate_limit: RateLimitSettings,
+    pub body_limits: BodyLimitSettings,
+    pub metrics: MetricsSettings,
     pub enable_sso: bool,
 }
 
@@ -32,8 +59,28 @@ pub struct ReportingSettings {
     pub worker_interval_secs: u64,
     #[serde(default)]
     pub enable_live_updates: bool,
+    /// Выгрузки отчётов; требуют настроенного объектного хранилища
+    #[serde(default = "ReportingSettings::default_exports_enabled")]
+    pub exports_enabled: bool,
     #[serde(default = "ReportingSettings::default_export_worker_interval_secs")]
     pub export_worker_interval_secs: u64,
+    /// Сколько выгрузок воркер собирает одновременно
+    #[serde(default = "ReportingSettings::default_export_concurrency")]
+    pub export_concurrency: usize,
+    /// После стольких неудачных попыток выгрузка помечается failed
+    #[serde(default = "ReportingSettings::default_export_max_attempts")]
+    pub export_max_attempts: u32,
+    /// Базовая задержка повтора; удваивается с каждой попыткой
+    #[serde(default = "ReportingSettings::default_export_retry_base_secs")]
+    pub export_retry_base_secs: u64,
+    /// Размер части составной загрузки NDJSON; столько байт выгрузки держится в памяти.
+    /// S3 требует не меньше 5 МиБ на часть, кроме последней
+    #[serde(default = "ReportingSettings::default_export_part_size_bytes")]
+    pub export_part_size_bytes: usize,
+    /// Ключ HMAC для псевдонимов учеников в сырых выгрузках; отдельно от JWT_SECRET,
+    /// чтобы ротация ключей подписи не меняла псевдонимы
+    #[serde(default = "ReportingSettings::default_export_pseudonym_secret")]
+    pub export_pseudonym_secret: String,
 }
 
 impl ReportingSettings {
@@ -57,10 +104,34 @@ impl ReportingSettings {
         3600
     }
 
+    const fn default_exports_enabled() -> bool {
+        true
+    }
+
     const fn default_export_worker_interval_secs() -> u64 {
         60
     }
 
+    const fn default_export_concurrency() -> usize {
+        4
+    }
+
+    const fn default_export_max_attempts() -> u32 {
+        5
+    }
+
+    const fn default_export_retry_base_secs() -> u64 {
+        30
+    }
+
+    const fn default_export_part_size_bytes() -> usize {
+        8 * 1024 * 1024
+    }
+
+    fn default_export_pseudonym_secret() -> String {
+        DEV_EXPORT_PSEUDONYM_SECRET.to_string()
+    }
+
     pub fn from_env() -> Self {
         let signed_url_ttl_hours = env::var("REPORTING_SIGNED_URL_TTL_HOURS")
             .ok()
@@ -86,6 +157,26 @@ impl ReportingSettings {
             .ok()
             .and_then(|v| v.parse().ok())
             .unwrap_or(Self::default_export_worker_interval_secs());
+        let export_concurrency = env::var("REPORTING_EXPORT_CONCURRENCY")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(Self::default_export_concurrency());
+        let export_max_attempts = env::var("REPORTING_EXPORT_MAX_ATTEMPTS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(Self::default_export_max_attempts());
+        let export_retry_base_secs = env::var("REPORTING_EXPORT_RETRY_BASE_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(Self::default_export_retry_base_secs());
+        let export_part_size_bytes = env::var("REPORTING_EXPORT_PART_SIZE_BYTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(Self::default_export_part_size_bytes());
+        let export_pseudonym_secret = env::var("REPORTING_EXPORT_PSEUDONYM_SECRET")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(Self::default_export_pseudonym_secret);
 
         Self {
             signed_url_ttl_hours,
@@ -94,8 +185,15 @@ impl ReportingSettings {
             live_polling_interval_secs,
             worker_interval_secs,
             export_worker_interval_secs,
+            export_concurrency,
+            export_max_attempts,
+            export_retry_base_secs,
+            export_part_size_bytes,
+            export_pseudonym_secret,
             enable_live_updates: parse_bool_env_var("REPORTING_ENABLE_LIVE_UPDATES")
                 .unwrap_or(false),
+            exports_enabled: parse_bool_env_var("REPORTING_EXPORTS_ENABLED")
+                .unwrap_or(Self::default_exports_enabled()),
         }
     }
 
@@ -112,6 +210,38 @@ impl ReportingSettings {
 pub struct ContentSettings {
     #[serde(default = "ContentSettings::default_stream_name_string")]
     pub stream_name: String,
+    /// Событие, не подтверждённое (XACK) дольше этого времени, считается зависшим
+    #[serde(default = "ContentSettings::default_dead_letter_idle_secs")]
+    pub dead_letter_idle_secs: u64,
+    /// Сколько шаблонов воркер эмбеддингов обрабатывает между проверками отмены
+    #[serde(default = "ContentSettings::default_embedding_batch_size")]
+    pub embedding_batch_size: usize,
+    #[serde(default = "ContentSettings::default_embedding_worker_interval_secs")]
+    pub embedding_worker_interval_secs: u64,
+    /// Consumer group, в которой API читает стрим изменений
+    #[serde(default = "ContentSettings::default_consumer_group")]
+    pub consumer_group: String,
+    /// Имя консьюмера в группе; по умолчанию `HOSTNAME`
+    #[serde(default = "ContentSettings::default_consumer_name")]
+    pub consumer_name: String,
+    /// Стрим, куда переносятся события, исчерпавшие попытки обработки
+    #[serde(default = "ContentSettings::default_dead_letter_stream")]
+    pub dead_letter_stream: String,
+    /// После стольких неудачных попыток событие уходит в `dead_letter_stream`
+    #[serde(default = "ContentSettings::default_consumer_max_attempts")]
+    pub consumer_max_attempts: u32,
+    /// Через сколько секунд без XACK событие выдаётся повторно
+    #[serde(default = "ContentSettings::default_consumer_retry_idle_secs")]
+    pub consumer_retry_idle_secs: u64,
+    /// Сколько XREADGROUP ждёт новых событий
+    #[serde(default = "ContentSettings::default_consumer_block_ms")]
+    pub consumer_block_ms: u64,
+    /// Как часто диспетчер outbox ищет неотправленные записи
+    #[serde(default = "ContentSettings::default_outbox_interval_ms")]
+    pub outbox_interval_ms: u64,
+    /// Сколько запись outbox закреплена за диспетчером, прежде чем её заберёт другой
+    #[serde(default = "ContentSettings::default_outbox_lease_secs")]
+    pub outbox_lease_secs: u64,
 }
 
 impl ContentSettings {
@@ -123,10 +253,101 @@ impl ContentSettings {
         Self::default_stream_name().to_string()
     }
 
+    const fn default_dead_letter_idle_secs() -> u64 {
+        300
+    }
+
+    const fn default_embedding_batch_size() -> usize {
+        50
+    }
+
+    const fn default_embedding_worker_interval_secs() -> u64 {
+        5
+    }
+
+    fn default_consumer_group() -> String {
+        "rust-api".to_string()
+    }
+
+    fn default_consumer_name() -> String {
+        std::env::var("HOSTNAME").unwrap_or_else(|_| "rust-api".to_string())
+    }
+
+    fn default_dead_letter_stream() -> String {
+        format!("{}:dlq", Self::default_stream_name())
+    }
+
+    const fn default_consumer_max_attempts() -> u32 {
+        5
+    }
+
+    const fn default_consumer_retry_idle_secs() -> u64 {
+        30
+    }
+
+    const fn default_consumer_block_ms() -> u64 {
+        2000
+    }
+
+    const fn default_outbox_interval_ms() -> u64 {
+        1000
+    }
+
+    const fn default_outbox_lease_secs() -> u64 {
+        30
+    }
+
     pub fn from_env() -> Self {
         let stream_name = std::env::var("CONTENT_STREAM_NAME")
             .unwrap_or_else(|_| Self::default_stream_name().to_string());
-        Self { stream_name }
+        let dead_letter_idle_secs = std::env::var("CONTENT_DEAD_LETTER_IDLE_SECS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(Self::default_dead_letter_idle_secs());
+        let embedding_batch_size = std::env::var("CONTENT_EMBEDDING_BATCH_SIZE")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .filter(|size| *size > 0)
+            .unwrap_or(Self::default_embedding_batch_size());
+        let embedding_worker_interval_secs =
+            std::env::var("CONTENT_EMBEDDING_WORKER_INTERVAL_SECS")
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(Self::default_embedding_worker_interval_secs());
+        let dead_letter_stream = std::env::var("CONTENT_DEAD_LETTER_STREAM")
+            .unwrap_or_else(|_| format!("{}:dlq", stream_name));
+        Self {
+            stream_name,
+            dead_letter_idle_secs,
+            embedding_batch_size,
+            embedding_worker_interval_secs,
+            consumer_group: std::env::var("CONTENT_CONSUMER_GROUP")
+                .unwrap_or_else(|_| Self::default_consumer_group()),
+            consumer_name: std::env::var("CONTENT_CONSUMER_NAME")
+                .unwrap_or_else(|_| Self::default_consumer_name()),
+            dead_letter_stream,
+            consumer_max_attempts: std::env::var("CONTENT_CONSUMER_MAX_ATTEMPTS")
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .filter(|attempts| *attempts > 0)
+                .unwrap_or(Self::default_consumer_max_attempts()),
+            consumer_retry_idle_secs: std::env::var("CONTENT_CONSUMER_RETRY_IDLE_SECS")
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(Self::default_consumer_retry_idle_secs()),
+            consumer_block_ms: std::env::var("CONTENT_CONSUMER_BLOCK_MS")
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(Self::default_consumer_block_ms()),
+            outbox_interval_ms: std::env::var("CONTENT_OUTBOX_INTERVAL_MS")
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(Self::default_outbox_interval_ms()),
+            outbox_lease_secs: std::env::var("CONTENT_OUTBOX_LEASE_SECS")
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(Self::default_outbox_lease_secs()),
+        }
     }
 }
 
@@ -134,6 +355,407 @@ impl Default for ContentSettings {
     fn default() -> Self {
         Self {
             stream_name: Self::default_stream_name().to_string(),
+            dead_letter_idle_secs: Self::default_dead_letter_idle_secs(),
+            embedding_batch_size: Self::default_embedding_batch_size(),
+            embedding_worker_interval_secs: Self::default_embedding_worker_interval_secs(),
+            consumer_group: Self::default_consumer_group(),
+            consumer_name: Self::default_consumer_name(),
+            dead_letter_stream: Self::default_dead_letter_stream(),
+            consumer_max_attempts: Self::default_consumer_max_attempts(),
+            consumer_retry_idle_secs: Self::default_consumer_retry_idle_secs(),
+            consumer_block_ms: Self::default_consumer_block_ms(),
+            outbox_interval_ms: Self::default_outbox_interval_ms(),
+            outbox_lease_secs: Self::default_outbox_lease_secs(),
+        }
+    }
+}
+
+/// Политика подсказок по умолчанию (задание может переопределить её своими полями)
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct HintSettings {
+    /// Максимум подсказок за сессию; 0 - без ограничения
+    #[serde(default = "HintSettings::default_max_per_session")]
+    pub max_per_session: u32,
+    /// Штраф к счёту сессии в процентах за 1-ю, 2-ю, ... подсказку; последний повторяется
+    #[serde(default = "HintSettings::default_penalty_schedule")]
+    pub penalty_schedule: Vec<u32>,
+}
+
+impl HintSettings {
+    const fn default_max_per_session() -> u32 {
+        2
+    }
+
+    fn default_penalty_schedule() -> Vec<u32> {
+        vec![10, 20, 40]
+    }
+
+    pub fn from_env() -> Self {
+        let max_per_session = env::var("HINTS_MAX_PER_SESSION")
+            .ok()
+            .and_then(|value| value.parse::<i64>().ok())
+            .map(|value| value.max(0) as u32)
+            .unwrap_or(Self::default_max_per_session());
+        let penalty_schedule = parse_csv_env_var("HINTS_PENALTY_SCHEDULE")
+            .iter()
+            .map(|value| value.parse::<u32>())
+            .collect::<Result<Vec<_>, _>>()
+            .ok()
+            .filter(|schedule| !schedule.is_empty())
+            .unwrap_or_else(Self::default_penalty_schedule);
+        Self {
+            max_per_session,
+            penalty_schedule,
+        }
+    }
+}
+
+impl Default for HintSettings {
+    fn default() -> Self {
+        Self {
+            max_per_session: Self::default_max_per_session(),
+            penalty_schedule: Self::default_penalty_schedule(),
+        }
+    }
+}
+
+/// Вызов LLM (YandexGPT через Explanation API) при генерации подсказок
+#[derive(Debug, Clone, Deserialize)]
+pub struct YandexGptConfig {
+    /// Таймаут одного запроса; по его истечении подсказка берётся из следующего источника
+    #[serde(default = "YandexGptConfig::default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+impl YandexGptConfig {
+    const fn default_timeout_ms() -> u64 {
+        2000
+    }
+
+    pub fn from_env() -> Self {
+        Self {
+            timeout_ms: env::var("YANDEXGPT_TIMEOUT_MS")
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(Self::default_timeout_ms()),
+        }
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+}
+
+impl Default for YandexGptConfig {
+    fn default() -> Self {
+        Self {
+            timeout_ms: Self::default_timeout_ms(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LlmProviderKind {
+    /// YandexGPT Foundation Models API
+    #[default]
+    YandexGpt,
+    /// Любой сервер с OpenAI-совместимым `/chat/completions` (OpenAI, vLLM, Ollama и т.п.)
+    OpenAi,
+}
+
+impl LlmProviderKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::YandexGpt => "yandexgpt",
+            Self::OpenAi => "openai",
+        }
+    }
+}
+
+impl std::str::FromStr for LlmProviderKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "yandexgpt" => Ok(Self::YandexGpt),
+            "openai" => Ok(Self::OpenAi),
+            other => Err(format!("unknown LLM provider: {}", other)),
+        }
+    }
+}
+
+/// Выбор LLM-провайдера. Ключи и модели провайдеров задаются в `/admin/settings`
+#[derive(Debug, Clone, Deserialize)]
+pub struct LlmConfig {
+    #[serde(default)]
+    pub provider: LlmProviderKind,
+    /// Таймаут одного HTTP-запроса к провайдеру
+    #[serde(default = "LlmConfig::default_request_timeout_ms")]
+    pub request_timeout_ms: u64,
+    /// Сколько раз повторить запрос после ответа 429
+    #[serde(default = "LlmConfig::default_max_retries")]
+    pub max_retries: u32,
+    /// Базовая задержка перед повтором; удваивается с каждой попыткой, плюс случайная добавка
+    #[serde(default = "LlmConfig::default_retry_base_delay_ms")]
+    pub retry_base_delay_ms: u64,
+    #[serde(default = "LlmConfig::default_yandexgpt_url")]
+    pub yandexgpt_url: String,
+}
+
+impl LlmConfig {
+    const fn default_request_timeout_ms() -> u64 {
+        10_000
+    }
+
+    const fn default_max_retries() -> u32 {
+        3
+    }
+
+    const fn default_retry_base_delay_ms() -> u64 {
+        200
+    }
+
+    fn default_yandexgpt_url() -> String {
+        "https://llm.api.cloud.yandex.net/foundationModels/v1/completion".to_string()
+    }
+
+    pub fn from_env() -> Self {
+        fn parse<T: std::str::FromStr>(key: &str, default: T) -> T {
+            env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+
+        Self {
+            provider: parse("LLM_PROVIDER", LlmProviderKind::default()),
+            request_timeout_ms: parse("LLM_REQUEST_TIMEOUT_MS", Self::default_request_timeout_ms()),
+            max_retries: parse("LLM_MAX_RETRIES", Self::default_max_retries()),
+            retry_base_delay_ms: parse(
+                "LLM_RETRY_BASE_DELAY_MS",
+                Self::default_retry_base_delay_ms(),
+            ),
+            yandexgpt_url: env::var("YANDEXGPT_COMPLETION_URL")
+                .unwrap_or_else(|_| Self::default_yandexgpt_url()),
+        }
+    }
+
+    pub fn request_timeout(&self) -> Duration {
+        Duration::from_millis(self.request_timeout_ms)
+    }
+
+    pub fn retry_base_delay(&self) -> Duration {
+        Duration::from_millis(self.retry_base_delay_ms)
+    }
+}
+
+impl Default for LlmConfig {
+    fn default() -> Self {
+        Self {
+            provider: LlmProviderKind::default(),
+            request_timeout_ms: Self::default_request_timeout_ms(),
+            max_retries: Self::default_max_retries(),
+            retry_base_delay_ms: Self::default_retry_base_delay_ms(),
+            yandexgpt_url: Self::default_yandexgpt_url(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SessionSettings {
+    /// Сколько секунд после `expires_at` ещё принимаются ответы (задержка сети, медленный клиент)
+    #[serde(default = "SessionSettings::default_grace_seconds")]
+    pub grace_seconds: u64,
+    /// Сколько последних событий SSE-стрима хранится для повторной отправки по `Last-Event-ID`
+    #[serde(default = "SessionSettings::default_event_buffer_size")]
+    pub event_buffer_size: usize,
+    /// Считать `ё` и `е` одной буквой при проверке ответа (кроме заданий со `strict_mode`)
+    #[serde(default = "SessionSettings::default_answer_yo_equivalence")]
+    pub answer_yo_equivalence: bool,
+    /// Часовой пояс (IANA) для границ дней в серии, если у ученика свой не указан
+    #[serde(default = "SessionSettings::default_timezone")]
+    pub default_timezone: String,
+    /// За сколько секунд до конца сессии ученику приходит уведомление (0 - не предупреждать)
+    #[serde(default = "SessionSettings::default_expiry_warning_seconds")]
+    pub expiry_warning_seconds: u32,
+}
+
+impl SessionSettings {
+    const fn default_grace_seconds() -> u64 {
+        5
+    }
+
+    const fn default_event_buffer_size() -> usize {
+        100
+    }
+
+    const fn default_answer_yo_equivalence() -> bool {
+        true
+    }
+
+    fn default_timezone() -> String {
+        "Europe/Moscow".to_string()
+    }
+
+    const fn default_expiry_warning_seconds() -> u32 {
+        60
+    }
+
+    /// Часовой пояс по умолчанию; нераспознанное значение считается UTC
+    pub fn default_tz(&self) -> Tz {
+        self.default_timezone.parse().unwrap_or(Tz::UTC)
+    }
+
+    pub fn from_env() -> Self {
+        Self {
+            grace_seconds: env::var("SESSION_GRACE_SECONDS")
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(Self::default_grace_seconds()),
+            event_buffer_size: env::var("SESSION_EVENT_BUFFER_SIZE")
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .filter(|size| *size > 0)
+                .unwrap_or(Self::default_event_buffer_size()),
+            answer_yo_equivalence: parse_bool_env_var("SESSION_ANSWER_YO_EQUIVALENCE")
+                .unwrap_or(Self::default_answer_yo_equivalence()),
+            default_timezone: env::var("SESSION_DEFAULT_TIMEZONE")
+                .ok()
+                .filter(|value| value.parse::<Tz>().is_ok())
+                .unwrap_or_else(Self::default_timezone),
+            expiry_warning_seconds: env::var("SESSION_EXPIRY_WARNING_SECONDS")
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(Self::default_expiry_warning_seconds()),
+        }
+    }
+}
+
+impl Default for SessionSettings {
+    fn default() -> Self {
+        Self {
+            grace_seconds: Self::default_grace_seconds(),
+            event_buffer_size: Self::default_event_buffer_size(),
+            answer_yo_equivalence: Self::default_answer_yo_equivalence(),
+            default_timezone: Self::default_timezone(),
+            expiry_warning_seconds: Self::default_expiry_warning_seconds(),
+        }
+    }
+}
+
+/// Сигналы клиента (переключение вкладок, вставка, devtools): лимиты приёма и пороги,
+/// после которых по сессии открывается инцидент. Порог срабатывает, когда число
+/// сигналов одного типа за сессию становится больше него.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AnticheatSignalSettings {
+    /// Сколько сигналов можно прислать одним запросом
+    #[serde(default = "AnticheatSignalSettings::default_max_batch_size")]
+    pub max_batch_size: usize,
+    /// Сколько запросов с сигналами принимается по одной сессии в минуту
+    #[serde(default = "AnticheatSignalSettings::default_max_batches_per_minute")]
+    pub max_batches_per_minute: u32,
+    #[serde(default = "AnticheatSignalSettings::default_tab_switch_threshold")]
+    pub tab_switch_threshold: u32,
+    #[serde(default = "AnticheatSignalSettings::default_paste_threshold")]
+    pub paste_threshold: u32,
+    #[serde(default = "AnticheatSignalSettings::default_devtools_threshold")]
+    pub devtools_threshold: u32,
+}
+
+impl AnticheatSignalSettings {
+    const fn default_max_batch_size() -> usize {
+        50
+    }
+
+    const fn default_max_batches_per_minute() -> u32 {
+        30
+    }
+
+    const fn default_tab_switch_threshold() -> u32 {
+        10
+    }
+
+    const fn default_paste_threshold() -> u32 {
+        5
+    }
+
+    const fn default_devtools_threshold() -> u32 {
+        0
+    }
+
+    pub fn from_env() -> Self {
+        fn parse<T: std::str::FromStr>(key: &str) -> Option<T> {
+            env::var(key).ok().and_then(|value| value.parse().ok())
+        }
+
+        Self {
+            max_batch_size: parse("ANTICHEAT_SIGNAL_MAX_BATCH")
+                .filter(|size| *size > 0)
+                .unwrap_or(Self::default_max_batch_size()),
+            max_batches_per_minute: parse("ANTICHEAT_SIGNAL_BATCHES_PER_MINUTE")
+                .filter(|limit| *limit > 0)
+                .unwrap_or(Self::default_max_batches_per_minute()),
+            tab_switch_threshold: parse("ANTICHEAT_TAB_SWITCH_THRESHOLD")
+                .unwrap_or(Self::default_tab_switch_threshold()),
+            paste_threshold: parse("ANTICHEAT_PASTE_THRESHOLD")
+                .unwrap_or(Self::default_paste_threshold()),
+            devtools_threshold: parse("ANTICHEAT_DEVTOOLS_THRESHOLD")
+                .unwrap_or(Self::default_devtools_threshold()),
+        }
+    }
+}
+
+impl Default for AnticheatSignalSettings {
+    fn default() -> Self {
+        Self {
+            max_batch_size: Self::default_max_batch_size(),
+            max_batches_per_minute: Self::default_max_batches_per_minute(),
+            tab_switch_threshold: Self::default_tab_switch_threshold(),
+            paste_threshold: Self::default_paste_threshold(),
+            devtools_threshold: Self::default_devtools_threshold(),
+        }
+    }
+}
+
+/// Учётные записи: снятие временных блокировок по истечении `blocked_until`
+#[derive(Debug, Clone, Deserialize)]
+pub struct AccountSettings {
+    /// Как часто фоновая задача снимает истёкшие блокировки
+    #[serde(default = "AccountSettings::default_block_sweep_interval_secs")]
+    pub block_sweep_interval_secs: u64,
+    /// Проверять метку отзыва access-токенов в Redis на каждом запросе
+    #[serde(default = "AccountSettings::default_token_revocation_enabled")]
+    pub token_revocation_enabled: bool,
+}
+
+impl AccountSettings {
+    const fn default_block_sweep_interval_secs() -> u64 {
+        60
+    }
+
+    const fn default_token_revocation_enabled() -> bool {
+        true
+    }
+
+    pub fn from_env() -> Self {
+        Self {
+            block_sweep_interval_secs: env::var("ACCOUNT_BLOCK_SWEEP_INTERVAL_SECS")
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .filter(|secs| *secs > 0)
+                .unwrap_or(Self::default_block_sweep_interval_secs()),
+            token_revocation_enabled: parse_bool_env_var("ACCESS_TOKEN_REVOCATION_ENABLED")
+                .unwrap_or(Self::default_token_revocation_enabled()),
+        }
+    }
+}
+
+impl Default for AccountSettings {
+    fn default() -> Self {
+        Self {
+            block_sweep_interval_secs: Self::default_block_sweep_interval_secs(),
+            token_revocation_enabled: Self::default_token_revocation_enabled(),
         }
     }
 }
@@ -171,6 +793,15 @@ impl LoggingSettings {
     }
 }
 
+impl Default for LoggingSettings {
+    fn default() -> Self {
+        Self {
+            level: Self::default_level(),
+            format: Self::default_format(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct CookieSettings {
     #[serde(default = "CookieSettings::default_secure")]
@@ -219,6 +850,42 @@ impl Default for CookieSettings {
     }
 }
 
+/// Origin-ы, которым отчётные эндпоинты отвечают на CORS-запросы.
+/// Пустой список - любой origin (только для разработки)
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct CorsSettings {
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
+}
+
+impl CorsSettings {
+    pub fn from_env() -> Self {
+        Self {
+            allowed_origins: parse_csv_env_var("CORS_ALLOWED_ORIGINS"),
+        }
+    }
+
+    pub fn allows_any(&self) -> bool {
+        self.allowed_origins.is_empty() || self.allowed_origins.iter().any(|origin| origin == "*")
+    }
+
+    /// Записи, которые не являются origin-ом вида `https://host[:port]`
+    pub fn invalid_origins(&self) -> Vec<&str> {
+        self.allowed_origins
+            .iter()
+            .map(String::as_str)
+            .filter(|origin| *origin != "*")
+            .filter(|origin| {
+                let host = origin
+                    .strip_prefix("https://")
+                    .or_else(|| origin.strip_prefix("http://"));
+                !matches!(host, Some(host) if !host.is_empty() && !host.contains('/'))
+                    || axum::http::HeaderValue::from_str(origin).is_err()
+            })
+            .collect()
+    }
+}
+
 impl Default for ReportingSettings {
     fn default() -> Self {
         Self {
@@ -228,37 +895,717 @@ impl Default for ReportingSettings {
             live_polling_interval_secs: Self::default_polling_interval(),
             worker_interval_secs: Self::default_worker_interval_secs(),
             export_worker_interval_secs: Self::default_export_worker_interval_secs(),
+            export_concurrency: Self::default_export_concurrency(),
+            export_max_attempts: Self::default_export_max_attempts(),
+            export_retry_base_secs: Self::default_export_retry_base_secs(),
+            export_part_size_bytes: Self::default_export_part_size_bytes(),
+            export_pseudonym_secret: Self::default_export_pseudonym_secret(),
             enable_live_updates: false,
+            exports_enabled: Self::default_exports_enabled(),
         }
     }
 }
 
+/// Очередь писем (`email_outbox`), которую разбирает `EmailWorker`
 #[derive(Debug, Clone, Deserialize)]
-pub struct ObjectStorageSettings {
-    pub bucket: String,
-    pub region: String,
-    #[serde(default)]
-    pub endpoint: Option<String>,
-    pub access_key: String,
-    pub secret_key: String,
-    #[serde(default = "ObjectStorageSettings::default_reports_prefix")]
-    pub reports_prefix: String,
+pub struct EmailOutboxSettings {
+    #[serde(default = "EmailOutboxSettings::default_worker_interval_secs")]
+    pub worker_interval_secs: u64,
+    /// Сколько писем воркер забирает за тик
+    #[serde(default = "EmailOutboxSettings::default_batch_size")]
+    pub batch_size: usize,
+    /// После стольких неудачных попыток письмо помечается failed
+    #[serde(default = "EmailOutboxSettings::default_max_attempts")]
+    pub max_attempts: u32,
+    /// Базовая задержка повтора; удваивается с каждой попыткой
+    #[serde(default = "EmailOutboxSettings::default_retry_base_secs")]
+    pub retry_base_secs: u64,
+    /// На сколько воркер закрепляет за собой письмо; если он упал посреди
+    /// отправки, по истечении срока письмо заберёт следующий
+    #[serde(default = "EmailOutboxSettings::default_lease_secs")]
+    pub lease_secs: u64,
 }
 
-impl ObjectStorageSettings {
-    fn default_reports_prefix() -> String {
-        "reports".to_string()
+impl EmailOutboxSettings {
+    const fn default_worker_interval_secs() -> u64 {
+        10
     }
 
-    pub fn from_env() -> Option<Self> {
-        let bucket = env::var("OBJECT_STORAGE_BUCKET").ok()?;
-        let access_key = env::var("OBJECT_STORAGE_ACCESS_KEY").ok()?;
-        let secret_key = env::var("OBJECT_STORAGE_SECRET_KEY").ok()?;
-        let region =
-            env::var("OBJECT_STORAGE_REGION").unwrap_or_else(|_| "ru-central1".to_string());
-        let endpoint = env::var("OBJECT_STORAGE_ENDPOINT").ok();
-        let reports_prefix = env::var("OBJECT_STORAGE_REPORTS_PREFIX")
-            .unwrap_or_else(|_| Self::default_reports_prefix());
+    const fn default_batch_size() -> usize {
+        20
+    }
+
+    const fn default_max_attempts() -> u32 {
+        5
+    }
+
+    const fn default_retry_base_secs() -> u64 {
+        30
+    }
+
+    const fn default_lease_secs() -> u64 {
+        300
+    }
+
+    pub fn from_env() -> Self {
+        Self {
+            worker_interval_secs: env::var("EMAIL_OUTBOX_WORKER_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|secs| *secs > 0)
+                .unwrap_or(Self::default_worker_interval_secs()),
+            batch_size: env::var("EMAIL_OUTBOX_BATCH_SIZE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|size| *size > 0)
+                .unwrap_or(Self::default_batch_size()),
+            max_attempts: env::var("EMAIL_OUTBOX_MAX_ATTEMPTS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(Self::default_max_attempts()),
+            retry_base_secs: env::var("EMAIL_OUTBOX_RETRY_BASE_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(Self::default_retry_base_secs()),
+            lease_secs: env::var("EMAIL_OUTBOX_LEASE_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|secs| *secs > 0)
+                .unwrap_or(Self::default_lease_secs()),
+        }
+    }
+}
+
+impl Default for EmailOutboxSettings {
+    fn default() -> Self {
+        Self {
+            worker_interval_secs: Self::default_worker_interval_secs(),
+            batch_size: Self::default_batch_size(),
+            max_attempts: Self::default_max_attempts(),
+            retry_base_secs: Self::default_retry_base_secs(),
+            lease_secs: Self::default_lease_secs(),
+        }
+    }
+}
+
+/// Архивация старых сессий в объектное хранилище
+#[derive(Debug, Clone, Deserialize)]
+pub struct ArchiveSettings {
+    /// Сессии старше этого возраста переносятся в архив
+    #[serde(default = "ArchiveSettings::default_session_age_days")]
+    pub session_age_days: i64,
+    #[serde(default = "ArchiveSettings::default_batch_size")]
+    pub batch_size: i64,
+    /// Максимум батчей за один запуск (остальное - в следующий запуск)
+    #[serde(default = "ArchiveSettings::default_max_batches_per_run")]
+    pub max_batches_per_run: u32,
+    #[serde(default = "ArchiveSettings::default_worker_interval_secs")]
+    pub worker_interval_secs: u64,
+    /// TTL блокировки задачи; продлевается heartbeat'ом после каждого батча
+    #[serde(default = "ArchiveSettings::default_lock_ttl_secs")]
+    pub lock_ttl_secs: u64,
+    /// Сколько хранится восстановленная из архива копия сессии
+    #[serde(default = "ArchiveSettings::default_rehydration_ttl_hours")]
+    pub rehydration_ttl_hours: i64,
+}
+
+impl ArchiveSettings {
+    const fn default_session_age_days() -> i64 {
+        365
+    }
+
+    const fn default_batch_size() -> i64 {
+        200
+    }
+
+    const fn default_max_batches_per_run() -> u32 {
+        50
+    }
+
+    const fn default_worker_interval_secs() -> u64 {
+        86400
+    }
+
+    const fn default_lock_ttl_secs() -> u64 {
+        300
+    }
+
+    const fn default_rehydration_ttl_hours() -> i64 {
+        72
+    }
+
+    pub fn from_env() -> Self {
+        fn parse<T: std::str::FromStr>(key: &str, default: T) -> T {
+            env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+
+        Self {
+            session_age_days: parse("ARCHIVE_SESSION_AGE_DAYS", Self::default_session_age_days()),
+            batch_size: parse("ARCHIVE_BATCH_SIZE", Self::default_batch_size()),
+            max_batches_per_run: parse(
+                "ARCHIVE_MAX_BATCHES_PER_RUN",
+                Self::default_max_batches_per_run(),
+            ),
+            worker_interval_secs: parse(
+                "ARCHIVE_WORKER_INTERVAL_SECS",
+                Self::default_worker_interval_secs(),
+            ),
+            lock_ttl_secs: parse("ARCHIVE_LOCK_TTL_SECS", Self::default_lock_ttl_secs()),
+            rehydration_ttl_hours: parse(
+                "ARCHIVE_REHYDRATION_TTL_HOURS",
+                Self::default_rehydration_ttl_hours(),
+            ),
+        }
+    }
+
+    pub fn session_age(&self) -> chrono::Duration {
+        chrono::Duration::days(self.session_age_days)
+    }
+
+    pub fn rehydration_ttl(&self) -> chrono::Duration {
+        chrono::Duration::hours(self.rehydration_ttl_hours)
+    }
+}
+
+impl Default for ArchiveSettings {
+    fn default() -> Self {
+        Self {
+            session_age_days: Self::default_session_age_days(),
+            batch_size: Self::default_batch_size(),
+            max_batches_per_run: Self::default_max_batches_per_run(),
+            worker_interval_secs: Self::default_worker_interval_secs(),
+            lock_ttl_secs: Self::default_lock_ttl_secs(),
+            rehydration_ttl_hours: Self::default_rehydration_ttl_hours(),
+        }
+    }
+}
+
+/// Хранение журнала аудита: старые записи переносятся в объектное хранилище
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuditSettings {
+    /// Записи старше этого числа дней архивируются и удаляются из `audit_log`
+    #[serde(default = "AuditSettings::default_retention_days")]
+    pub retention_days: i64,
+    /// Размер батча удаления из MongoDB
+    #[serde(default = "AuditSettings::default_batch_size")]
+    pub batch_size: i64,
+    /// Максимум суток журнала за один запуск (остальное - в следующий запуск)
+    #[serde(default = "AuditSettings::default_max_days_per_run")]
+    pub max_days_per_run: u32,
+    #[serde(default = "AuditSettings::default_worker_interval_secs")]
+    pub worker_interval_secs: u64,
+    /// TTL блокировки задачи; продлевается после каждых заархивированных суток
+    #[serde(default = "AuditSettings::default_lock_ttl_secs")]
+    pub lock_ttl_secs: u64,
+    /// Максимум строк в CSV-выгрузке журнала; больше - ответ 413
+    #[serde(default = "AuditSettings::default_export_max_rows")]
+    pub export_max_rows: u64,
+}
+
+impl AuditSettings {
+    const fn default_retention_days() -> i64 {
+        365
+    }
+
+    const fn default_batch_size() -> i64 {
+        1000
+    }
+
+    const fn default_max_days_per_run() -> u32 {
+        31
+    }
+
+    const fn default_worker_interval_secs() -> u64 {
+        86400
+    }
+
+    const fn default_lock_ttl_secs() -> u64 {
+        300
+    }
+
+    const fn default_export_max_rows() -> u64 {
+        100_000
+    }
+
+    pub fn from_env() -> Self {
+        fn parse<T: std::str::FromStr>(key: &str, default: T) -> T {
+            env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+
+        Self {
+            retention_days: parse("AUDIT_RETENTION_DAYS", Self::default_retention_days()),
+            batch_size: parse("AUDIT_ARCHIVE_BATCH_SIZE", Self::default_batch_size()),
+            max_days_per_run: parse(
+                "AUDIT_ARCHIVE_MAX_DAYS_PER_RUN",
+                Self::default_max_days_per_run(),
+            ),
+            worker_interval_secs: parse(
+                "AUDIT_ARCHIVE_WORKER_INTERVAL_SECS",
+                Self::default_worker_interval_secs(),
+            ),
+            lock_ttl_secs: parse("AUDIT_ARCHIVE_LOCK_TTL_SECS", Self::default_lock_ttl_secs()),
+            export_max_rows: parse("AUDIT_EXPORT_MAX_ROWS", Self::default_export_max_rows()),
+        }
+    }
+
+    pub fn retention(&self) -> chrono::Duration {
+        chrono::Duration::days(self.retention_days)
+    }
+}
+
+impl Default for AuditSettings {
+    fn default() -> Self {
+        Self {
+            retention_days: Self::default_retention_days(),
+            batch_size: Self::default_batch_size(),
+            max_days_per_run: Self::default_max_days_per_run(),
+            worker_interval_secs: Self::default_worker_interval_secs(),
+            lock_ttl_secs: Self::default_lock_ttl_secs(),
+            export_max_rows: Self::default_export_max_rows(),
+        }
+    }
+}
+
+/// Максимальный размер тела запроса по группам маршрутов, в байтах.
+/// Больше - ответ 413 `PAYLOAD_TOO_LARGE`
+#[derive(Debug, Clone, Deserialize)]
+pub struct BodyLimitSettings {
+    /// `/api/v1/auth`: вход, регистрация, смена пароля
+    #[serde(default = "BodyLimitSettings::default_auth_bytes")]
+    pub auth_bytes: usize,
+    /// Контент в `/admin`: шаблоны, темы, уровни, правила
+    #[serde(default = "BodyLimitSettings::default_content_bytes")]
+    pub content_bytes: usize,
+    /// Все остальные маршруты
+    #[serde(default = "BodyLimitSettings::default_default_bytes")]
+    pub default_bytes: usize,
+}
+
+impl BodyLimitSettings {
+    const fn default_auth_bytes() -> usize {
+        16 * 1024
+    }
+
+    const fn default_content_bytes() -> usize {
+        4 * 1024 * 1024
+    }
+
+    const fn default_default_bytes() -> usize {
+        1024 * 1024
+    }
+
+    pub fn from_env() -> Self {
+        fn parse(key: &str, default: usize) -> usize {
+            env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+
+        Self {
+            auth_bytes: parse("BODY_LIMIT_AUTH_BYTES", Self::default_auth_bytes()),
+            content_bytes: parse("BODY_LIMIT_CONTENT_BYTES", Self::default_content_bytes()),
+            default_bytes: parse("BODY_LIMIT_DEFAULT_BYTES", Self::default_default_bytes()),
+        }
+    }
+}
+
+impl Default for BodyLimitSettings {
+    fn default() -> Self {
+        Self {
+            auth_bytes: Self::default_auth_bytes(),
+            content_bytes: Self::default_content_bytes(),
+            default_bytes: Self::default_default_bytes(),
+        }
+    }
+}
+
+/// Учётные данные Basic Auth для `/metrics`
+#[derive(Debug, Clone, Deserialize)]
+pub struct MetricsSettings {
+    pub username: String,
+    /// Пароль открытым текстом или bcrypt-хеш (`$2b$...`), чтобы не хранить
+    /// сам пароль в окружении
+    pub password: String,
+}
+
+impl MetricsSettings {
+    /// METRICS_USERNAME/METRICS_PASSWORD, либо устаревшая METRICS_AUTH=user:password
+    pub fn from_env() -> Option<Self> {
+        if let (Ok(username), Ok(password)) =
+            (env::var("METRICS_USERNAME"), env::var("METRICS_PASSWORD"))
+        {
+            return Some(Self { username, password });
+        }
+
+        let (username, password) = env::var("METRICS_AUTH")
+            .ok()?
+            .split_once(':')
+            .map(|(user, pass)| (user.to_string(), pass.to_string()))?;
+        Some(Self { username, password })
+    }
+
+    /// Учётка по умолчанию только для локальной разработки
+    fn dev_default() -> Self {
+        Self {
+            username: "admin".to_string(),
+            password: "changeme".to_string(),
+        }
+    }
+
+    fn is_dev_default(&self) -> bool {
+        let default = Self::dev_default();
+        self.username == default.username && self.password == default.password
+    }
+
+    pub fn verify(&self, username: &str, password: &str) -> bool {
+        let username_ok = constant_time_eq(username.as_bytes(), self.username.as_bytes());
+        let password_ok = verify_secret(password, &self.password);
+        username_ok & password_ok
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TracingSettings {
+    /// Доля «обычных» трейсов, которые экспортируются (0.0..=1.0)
+    #[serde(default = "TracingSettings::default_sample_ratio")]
+    pub sample_ratio: f64,
+    /// Трейсы дольше порога экспортируются всегда
+    #[serde(default = "TracingSettings::default_slow_trace_ms")]
+    pub slow_trace_ms: u64,
+    /// Трейсы со статусом ошибки экспортируются всегда
+    #[serde(default = "TracingSettings::default_always_sample_errors")]
+    pub always_sample_errors: bool,
+    /// Пути, трейсы которых не экспортируются никогда (health-check, scrape метрик)
+    #[serde(default = "TracingSettings::default_ignored_paths")]
+    pub ignored_paths: Vec<String>,
+    /// Очередь трейсов на экспорт; при переполнении спаны отбрасываются
+    #[serde(default = "TracingSettings::default_export_queue_size")]
+    pub export_queue_size: usize,
+    #[serde(default = "TracingSettings::default_export_timeout_secs")]
+    pub export_timeout_secs: u64,
+    /// Ошибки экспорта пишутся в лог не чаще раза за интервал
+    #[serde(default = "TracingSettings::default_export_error_log_interval_secs")]
+    pub export_error_log_interval_secs: u64,
+}
+
+impl TracingSettings {
+    const fn default_sample_ratio() -> f64 {
+        0.1
+    }
+
+    const fn default_slow_trace_ms() -> u64 {
+        1000
+    }
+
+    const fn default_always_sample_errors() -> bool {
+        true
+    }
+
+    fn default_ignored_paths() -> Vec<String> {
+        vec!["/health".to_string(), "/metrics".to_string()]
+    }
+
+    const fn default_export_queue_size() -> usize {
+        2048
+    }
+
+    const fn default_export_timeout_secs() -> u64 {
+        5
+    }
+
+    const fn default_export_error_log_interval_secs() -> u64 {
+        60
+    }
+
+    pub fn from_env() -> Self {
+        fn parse<T: std::str::FromStr>(key: &str, default: T) -> T {
+            env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+
+        let ignored_paths = parse_csv_env_var("OTEL_IGNORED_PATHS");
+
+        Self {
+            sample_ratio: parse("OTEL_SAMPLE_RATIO", Self::default_sample_ratio()),
+            slow_trace_ms: parse("OTEL_SLOW_TRACE_MS", Self::default_slow_trace_ms()),
+            always_sample_errors: parse(
+                "OTEL_ALWAYS_SAMPLE_ERRORS",
+                Self::default_always_sample_errors(),
+            ),
+            ignored_paths: if ignored_paths.is_empty() {
+                Self::default_ignored_paths()
+            } else {
+                ignored_paths
+            },
+            export_queue_size: parse("OTEL_EXPORT_QUEUE_SIZE", Self::default_export_queue_size()),
+            export_timeout_secs: parse(
+                "OTEL_EXPORT_TIMEOUT_SECS",
+                Self::default_export_timeout_secs(),
+            ),
+            export_error_log_interval_secs: parse(
+                "OTEL_EXPORT_ERROR_LOG_INTERVAL_SECS",
+                Self::default_export_error_log_interval_secs(),
+            ),
+        }
+    }
+}
+
+impl Default for TracingSettings {
+    fn default() -> Self {
+        Self {
+            sample_ratio: Self::default_sample_ratio(),
+            slow_trace_ms: Self::default_slow_trace_ms(),
+            always_sample_errors: Self::default_always_sample_errors(),
+            ignored_paths: Self::default_ignored_paths(),
+            export_queue_size: Self::default_export_queue_size(),
+            export_timeout_secs: Self::default_export_timeout_secs(),
+            export_error_log_interval_secs: Self::default_export_error_log_interval_secs(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitAlgorithm {
+    /// Счётчик на окно (старый вариант, оставлен для отката)
+    Fixed,
+    /// Скользящее окно по отметкам времени в ZSET
+    Sliding,
+}
+
+impl std::str::FromStr for RateLimitAlgorithm {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "fixed" => Ok(Self::Fixed),
+            "sliding" => Ok(Self::Sliding),
+            other => Err(format!("unknown rate limit algorithm: {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RateLimitWindow {
+    pub limit: u32,
+    pub window_secs: u64,
+}
+
+impl RateLimitWindow {
+    const fn new(limit: u32, window_secs: u64) -> Self {
+        Self { limit, window_secs }
+    }
+}
+
+/// Лимиты по группам маршрутов. Аутентифицированные запросы считаются по
+/// пользователю, остальные - по IP.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RateLimitSettings {
+    #[serde(default = "RateLimitSettings::default_algorithm")]
+    pub algorithm: RateLimitAlgorithm,
+    #[serde(default = "RateLimitSettings::default_per_user")]
+    pub per_user: RateLimitWindow,
+    #[serde(default = "RateLimitSettings::default_per_ip")]
+    pub per_ip: RateLimitWindow,
+    #[serde(default = "RateLimitSettings::default_admin_per_user")]
+    pub admin_per_user: RateLimitWindow,
+    #[serde(default = "RateLimitSettings::default_admin_per_ip")]
+    pub admin_per_ip: RateLimitWindow,
+    #[serde(default = "RateLimitSettings::default_login")]
+    pub login: RateLimitWindow,
+    #[serde(default = "RateLimitSettings::default_register")]
+    pub register: RateLimitWindow,
+}
+
+impl RateLimitSettings {
+    const fn default_algorithm() -> RateLimitAlgorithm {
+        RateLimitAlgorithm::Sliding
+    }
+
+    const fn default_per_user() -> RateLimitWindow {
+        RateLimitWindow::new(100, 60)
+    }
+
+    const fn default_per_ip() -> RateLimitWindow {
+        RateLimitWindow::new(200, 60)
+    }
+
+    const fn default_admin_per_user() -> RateLimitWindow {
+        RateLimitWindow::new(200, 60)
+    }
+
+    const fn default_admin_per_ip() -> RateLimitWindow {
+        RateLimitWindow::new(300, 60)
+    }
+
+    const fn default_login() -> RateLimitWindow {
+        RateLimitWindow::new(10, 300)
+    }
+
+    const fn default_register() -> RateLimitWindow {
+        RateLimitWindow::new(5, 3600)
+    }
+
+    pub fn from_env() -> Self {
+        fn parse<T: std::str::FromStr>(key: &str, default: T) -> T {
+            env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+
+        fn window(limit_key: &str, window_key: &str, default: RateLimitWindow) -> RateLimitWindow {
+            RateLimitWindow {
+                limit: parse(limit_key, default.limit),
+                window_secs: parse(window_key, default.window_secs),
+            }
+        }
+
+        Self {
+            algorithm: parse("RATE_LIMIT_ALGORITHM", Self::default_algorithm()),
+            per_user: window(
+                "RATE_LIMIT_PER_USER",
+                "RATE_LIMIT_WINDOW_SECS",
+                Self::default_per_user(),
+            ),
+            per_ip: window(
+                "RATE_LIMIT_PER_IP",
+                "RATE_LIMIT_WINDOW_SECS",
+                Self::default_per_ip(),
+            ),
+            admin_per_user: window(
+                "ADMIN_RATE_LIMIT_PER_USER",
+                "ADMIN_RATE_LIMIT_WINDOW_SECS",
+                Self::default_admin_per_user(),
+            ),
+            admin_per_ip: window(
+                "ADMIN_RATE_LIMIT_PER_IP",
+                "ADMIN_RATE_LIMIT_WINDOW_SECS",
+                Self::default_admin_per_ip(),
+            ),
+            login: window(
+                "RATE_LIMIT_LOGIN_ATTEMPTS",
+                "RATE_LIMIT_LOGIN_WINDOW_SECS",
+                Self::default_login(),
+            ),
+            register: window(
+                "RATE_LIMIT_REGISTER_ATTEMPTS",
+                "RATE_LIMIT_REGISTER_WINDOW_SECS",
+                Self::default_register(),
+            ),
+        }
+    }
+}
+
+impl Default for RateLimitSettings {
+    fn default() -> Self {
+        Self {
+            algorithm: Self::default_algorithm(),
+            per_user: Self::default_per_user(),
+            per_ip: Self::default_per_ip(),
+            admin_per_user: Self::default_admin_per_user(),
+            admin_per_ip: Self::default_admin_per_ip(),
+            login: Self::default_login(),
+            register: Self::default_register(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ObjectStorageSettings {
+    pub bucket: String,
+    pub region: String,
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    pub access_key: String,
+    pub secret_key: String,
+    #[serde(default = "ObjectStorageSettings::default_reports_prefix")]
+    pub reports_prefix: String,
+    /// Срок жизни подписанной ссылки на скачивание выгрузки
+    #[serde(default = "ObjectStorageSettings::default_presign_ttl_secs")]
+    pub presign_ttl_secs: u64,
+    /// Шифрование на стороне хранилища для всех загрузок
+    /// (`x-amz-server-side-encryption`): `AES256` или `aws:kms`
+    #[serde(default)]
+    pub sse: Option<String>,
+    /// Ключ KMS для `sse = "aws:kms"`; без него хранилище берёт ключ по умолчанию
+    #[serde(default)]
+    pub sse_kms_key_id: Option<String>,
+    /// Сколько раз отправлять запрос при 5xx и таймаутах, включая первую попытку
+    #[serde(default = "ObjectStorageSettings::default_max_attempts")]
+    pub max_attempts: u32,
+    /// Пауза перед первым повтором, дальше она удваивается
+    #[serde(default = "ObjectStorageSettings::default_retry_base_delay_ms")]
+    pub retry_base_delay_ms: u64,
+    /// Таймаут одного запроса к хранилищу
+    #[serde(default = "ObjectStorageSettings::default_request_timeout_secs")]
+    pub request_timeout_secs: u64,
+    /// Объекты больше порога загружаются по частям (S3 multipart)
+    #[serde(default = "ObjectStorageSettings::default_multipart_threshold_bytes")]
+    pub multipart_threshold_bytes: usize,
+    /// Размер части составной загрузки; S3 не принимает части меньше 5 МиБ
+    #[serde(default = "ObjectStorageSettings::default_multipart_part_size_bytes")]
+    pub multipart_part_size_bytes: usize,
+}
+
+impl ObjectStorageSettings {
+    pub const DEFAULT_PRESIGN_TTL_SECS: u64 = 3600;
+
+    fn default_reports_prefix() -> String {
+        "reports".to_string()
+    }
+
+    const fn default_presign_ttl_secs() -> u64 {
+        Self::DEFAULT_PRESIGN_TTL_SECS
+    }
+
+    const fn default_max_attempts() -> u32 {
+        4
+    }
+
+    const fn default_retry_base_delay_ms() -> u64 {
+        200
+    }
+
+    const fn default_request_timeout_secs() -> u64 {
+        120
+    }
+
+    const fn default_multipart_threshold_bytes() -> usize {
+        32 * 1024 * 1024
+    }
+
+    const fn default_multipart_part_size_bytes() -> usize {
+        8 * 1024 * 1024
+    }
+
+    pub fn presign_ttl(&self) -> Duration {
+        Duration::from_secs(self.presign_ttl_secs)
+    }
+
+    pub fn from_env() -> Option<Self> {
+        let bucket = env::var("OBJECT_STORAGE_BUCKET").ok()?;
+        let access_key = env::var("OBJECT_STORAGE_ACCESS_KEY").ok()?;
+        let secret_key = env::var("OBJECT_STORAGE_SECRET_KEY").ok()?;
+        let region =
+            env::var("OBJECT_STORAGE_REGION").unwrap_or_else(|_| "ru-central1".to_string());
+        let endpoint = env::var("OBJECT_STORAGE_ENDPOINT").ok();
+        let reports_prefix = env::var("OBJECT_STORAGE_REPORTS_PREFIX")
+            .unwrap_or_else(|_| Self::default_reports_prefix());
+        let presign_ttl_secs = env::var("OBJECT_STORAGE_PRESIGN_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(Self::default_presign_ttl_secs());
+        let env_number = |name: &str| env::var(name).ok().and_then(|v| v.parse().ok());
 
         Some(Self {
             bucket,
@@ -267,11 +1614,154 @@ impl ObjectStorageSettings {
             access_key,
             secret_key,
             reports_prefix,
+            presign_ttl_secs,
+            sse: env::var("OBJECT_STORAGE_SSE")
+                .ok()
+                .filter(|v| !v.is_empty()),
+            sse_kms_key_id: env::var("OBJECT_STORAGE_SSE_KMS_KEY_ID")
+                .ok()
+                .filter(|v| !v.is_empty()),
+            max_attempts: env_number("OBJECT_STORAGE_MAX_ATTEMPTS")
+                .unwrap_or(Self::default_max_attempts() as u64) as u32,
+            retry_base_delay_ms: env_number("OBJECT_STORAGE_RETRY_BASE_DELAY_MS")
+                .unwrap_or(Self::default_retry_base_delay_ms()),
+            request_timeout_secs: env_number("OBJECT_STORAGE_REQUEST_TIMEOUT_SECS")
+                .unwrap_or(Self::default_request_timeout_secs()),
+            multipart_threshold_bytes: env_number("OBJECT_STORAGE_MULTIPART_THRESHOLD_BYTES")
+                .unwrap_or(Self::default_multipart_threshold_bytes() as u64)
+                as usize,
+            multipart_part_size_bytes: env_number("OBJECT_STORAGE_MULTIPART_PART_SIZE_BYTES")
+                .unwrap_or(Self::default_multipart_part_size_bytes() as u64)
+                as usize,
         })
     }
 }
 
+/// Секрет, которым подписываются токены, если JWT_SECRET не задан (только разработка)
+const DEV_JWT_SECRET: &str = "dev-secret-only-for-local-testing";
+
+/// Минимальная длина JWT_SECRET в production
+pub const MIN_JWT_SECRET_LEN: usize = 32;
+
+/// Ключ псевдонимов в выгрузках, если REPORTING_EXPORT_PSEUDONYM_SECRET не задан (только разработка)
+const DEV_EXPORT_PSEUDONYM_SECRET: &str = "dev-export-pseudonym-secret";
+
+/// Минимальный размер части составной загрузки в S3 (кроме последней)
+pub const MIN_MULTIPART_PART_SIZE_BYTES: usize = 5 * 1024 * 1024;
+
+/// Профиль окружения из `APP_ENV`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AppProfile {
+    Dev,
+    Test,
+    Prod,
+}
+
+impl AppProfile {
+    pub fn from_app_env(value: &str) -> Self {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "prod" | "production" => Self::Prod,
+            "test" => Self::Test,
+            _ => Self::Dev,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Dev => "dev",
+            Self::Test => "test",
+            Self::Prod => "prod",
+        }
+    }
+
+    /// Нарушения инвариантов - ошибка запуска, а не предупреждение
+    pub fn is_strict(&self) -> bool {
+        matches!(self, Self::Prod)
+    }
+}
+
+/// Нарушенный инвариант конфигурации: переменная окружения и ключ файла, которые надо поправить
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigViolation {
+    pub env_var: &'static str,
+    pub file_key: &'static str,
+    pub message: String,
+}
+
+impl ConfigViolation {
+    fn new(env_var: &'static str, file_key: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            env_var,
+            file_key,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}): {}", self.env_var, self.file_key, self.message)
+    }
+}
+
+/// Все нарушения сразу, чтобы не чинить конфигурацию по одной ошибке за запуск
+#[derive(Debug, Clone)]
+pub struct ConfigValidationError {
+    pub profile: AppProfile,
+    pub violations: Vec<ConfigViolation>,
+}
+
+impl fmt::Display for ConfigValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid configuration for APP_ENV={} ({} problem(s)):",
+            self.profile.as_str(),
+            self.violations.len()
+        )?;
+        for violation in &self.violations {
+            write!(f, "\n  - {}", violation)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigValidationError {}
+
 impl Config {
+    /// config/{env}.toml, поверх него CONFIG_FILE, поверх всего - переменные APP__*
+    fn settings(
+        env: &str,
+        config_file: Option<&str>,
+    ) -> Result<config::Config, config::ConfigError> {
+        let mut config_builder = config::Config::builder()
+            // Load base config from TOML file
+            .add_source(
+                config::File::with_name(&format!("config/{}", env)).required(false), // Allow missing config file, fallback to ENV
+            );
+        // Явно указанный файл обязан существовать
+        if let Some(path) = config_file.map(str::trim).filter(|path| !path.is_empty()) {
+            config_builder =
+                config_builder.add_source(config::File::new(path, config::FileFormat::Toml));
+        }
+        // Override with environment variables (prefix: APP_)
+        config_builder
+            .add_source(config::Environment::with_prefix("APP").separator("__"))
+            .build()
+    }
+
+    /// Срок жизни подписанных ссылок на скачивание (выгрузки, архивы аудита, рассылки
+    /// по расписанию): `object_storage.presign_ttl_secs`, без хранилища - значение по умолчанию
+    pub fn presign_ttl(&self) -> Duration {
+        self.object_storage
+            .as_ref()
+            .map(ObjectStorageSettings::presign_ttl)
+            .unwrap_or(Duration::from_secs(
+                ObjectStorageSettings::DEFAULT_PRESIGN_TTL_SECS,
+            ))
+    }
+
     pub fn load() -> Result<Self, config::ConfigError> {
         // Load environment variables from root .env file (two levels up)
         // Try root .env first, then fallback to local .env
@@ -285,17 +1775,10 @@ impl Config {
 
         // Determine environment (defaults to dev)
         let env = env::var("APP_ENV").unwrap_or_else(|_| "dev".to_string());
+        let profile = AppProfile::from_app_env(&env);
 
-        // Build configuration from config/*.toml + ENV overrides
-        let config_builder = config::Config::builder()
-            // Load base config from TOML file
-            .add_source(
-                config::File::with_name(&format!("config/{}", env)).required(false), // Allow missing config file, fallback to ENV
-            )
-            // Override with environment variables (prefix: APP_)
-            .add_source(config::Environment::with_prefix("APP").separator("__"));
-
-        let settings = config_builder.build()?;
+        let config_file = env::var("CONFIG_FILE").ok();
+        let settings = Self::settings(&env, config_file.as_deref())?;
 
         // Extract values with fallbacks to ENV or defaults
         let mongo_uri = settings
@@ -328,39 +1811,115 @@ impl Config {
             .or_else(|_| env::var("MONGO_DATABASE"))
             .unwrap_or_else(|_| "trainingground".to_string());
 
+        // Незаполненный `${JWT_SECRET}` из config/*.toml не считается секретом
         let jwt_secret = settings
             .get_string("auth.jwt_secret")
-            .or_else(|_| env::var("JWT_SECRET"))
-            .unwrap_or_else(|_| {
-                if env == "prod" {
-                    panic!("FATAL: JWT_SECRET must be set in production!");
-                }
-                eprintln!("WARNING: Using default JWT_SECRET (dev mode only!)");
-                "dev-secret-only-for-local-testing".to_string()
-            });
+            .ok()
+            .filter(|secret| !is_unresolved_placeholder(secret))
+            .or_else(|| env::var("JWT_SECRET").ok())
+            .unwrap_or_else(|| DEV_JWT_SECRET.to_string());
 
         let jwt_fallback_secrets = settings
             .get::<Vec<String>>("auth.jwt_fallback_secrets")
             .unwrap_or_else(|_| parse_csv_env_var("JWT_FALLBACK_SECRETS"));
 
+        let jwt_keys = settings
+            .get::<BTreeMap<String, String>>("auth.jwt_keys")
+            .unwrap_or_else(|_| parse_jwt_keys_env_var("JWT_KEYS"));
+
+        let jwt_active_key_id = settings
+            .get_string("auth.jwt_active_key_id")
+            .ok()
+            .or_else(|| env::var("JWT_ACTIVE_KEY_ID").ok())
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty());
+        // Без явного активного ключа подписываем последним по имени; неизвестный
+        // `kid` отклоняет `validate`
+        let jwt_active_key_id = jwt_active_key_id.or_else(|| jwt_keys.keys().next_back().cloned());
+
         let python_api_url = settings
             .get_string("python_api.url")
             .or_else(|_| env::var("PYTHON_API_URL"))
             .unwrap_or_else(|_| "http://localhost:8000".to_string());
 
-        let reporting = settings
+        let mut reporting = settings
             .get::<ReportingSettings>("reporting")
             .unwrap_or_else(|_| ReportingSettings::from_env());
+        // Секрет псевдонимов не хранится в config/*.toml: незаполненный - из окружения
+        if reporting.export_pseudonym_secret == DEV_EXPORT_PSEUDONYM_SECRET
+            || is_unresolved_placeholder(&reporting.export_pseudonym_secret)
+        {
+            if let Some(secret) = env::var("REPORTING_EXPORT_PSEUDONYM_SECRET")
+                .ok()
+                .filter(|secret| !secret.is_empty())
+            {
+                reporting.export_pseudonym_secret = secret;
+            }
+        }
 
         let object_storage = match settings.get::<ObjectStorageSettings>("object_storage") {
             Ok(cfg) => Some(cfg),
             Err(_) => ObjectStorageSettings::from_env(),
         };
 
+        let archive = settings
+            .get::<ArchiveSettings>("archive")
+            .unwrap_or_else(|_| ArchiveSettings::from_env());
+
+        let audit = settings
+            .get::<AuditSettings>("audit")
+            .unwrap_or_else(|_| AuditSettings::from_env());
+
+        let tracing = settings
+            .get::<TracingSettings>("tracing")
+            .unwrap_or_else(|_| TracingSettings::from_env());
+
+        // Лимиты задаются только через ENV: тесты и деплой переопределяют их
+        // переменными RATE_LIMIT_* без правки config/*.toml
+        let rate_limit = RateLimitSettings::from_env();
+
+        let body_limits = settings
+            .get::<BodyLimitSettings>("body_limits")
+            .unwrap_or_else(|_| BodyLimitSettings::from_env());
+
+        let metrics = settings
+            .get::<MetricsSettings>("metrics")
+            .ok()
+            .or_else(MetricsSettings::from_env)
+            .unwrap_or_else(MetricsSettings::dev_default);
+
         let content = settings
             .get::<ContentSettings>("content")
             .unwrap_or_else(|_| ContentSettings::from_env());
 
+        let hints = settings
+            .get::<HintSettings>("hints")
+            .unwrap_or_else(|_| HintSettings::from_env());
+
+        let yandexgpt = settings
+            .get::<YandexGptConfig>("yandexgpt")
+            .unwrap_or_else(|_| YandexGptConfig::from_env());
+
+        let llm = settings
+            .get::<LlmConfig>("llm")
+            .unwrap_or_else(|_| LlmConfig::from_env());
+
+        let sessions = settings
+            .get::<SessionSettings>("session")
+            .unwrap_or_else(|_| SessionSettings::from_env());
+
+        let anticheat_signals = settings
+            .get::<AnticheatSignalSettings>("anticheat.signals")
+            .unwrap_or_else(|_| AnticheatSignalSettings::from_env());
+
+        let accounts = settings
+            .get::<AccountSettings>("accounts")
+            .unwrap_or_else(|_| AccountSettings::from_env());
+
+        let email_outbox = settings
+            .get::<EmailOutboxSettings>("email_outbox")
+            .unwrap_or_else(|_| EmailOutboxSettings::from_env());
+
         let cookie = settings
             .get::<CookieSettings>("cookie")
             .unwrap_or_else(|_| CookieSettings::from_env());
@@ -369,33 +1928,238 @@ impl Config {
             .get::<LoggingSettings>("logging")
             .unwrap_or_else(|_| LoggingSettings::from_env());
 
+        let cors = settings
+            .get::<CorsSettings>("cors")
+            .unwrap_or_else(|_| CorsSettings::from_env());
+
         let superuser_seed_file = settings
             .get_string("superuser_seed_file")
             .ok()
             .or_else(|| env::var("ADMIN_SEED_FILE").ok());
 
+        let superuser_rotate = settings
+            .get_bool("superuser_rotate")
+            .map(Some)
+            .unwrap_or_else(|_| parse_bool_env_var("SUPERUSER_ROTATE"))
+            .unwrap_or(false);
+        let superuser_rotate_print_password =
+            parse_bool_env_var("SUPERUSER_ROTATE_PRINT_PASSWORD").unwrap_or(false);
+
         let enable_sso = settings
             .get_bool("sso.enabled")
             .map(Some)
             .unwrap_or_else(|_| parse_bool_env_var("ENABLE_SSO"))
             .unwrap_or(false);
 
-        Ok(Config {
+        let config = Config {
+            profile,
             mongo_uri,
             redis_uri,
             mongo_database,
             jwt_secret,
             jwt_fallback_secrets,
+            jwt_keys,
+            jwt_active_key_id,
             python_api_url,
             reporting,
             content,
+            hints,
+            yandexgpt,
+            llm,
+            sessions,
+            anticheat_signals,
+            accounts,
+            email_outbox,
             logging,
             cookie,
+            cors,
             superuser_seed_file,
+            superuser_rotate,
+            superuser_rotate_print_password,
             object_storage,
+            archive,
+            audit,
+            tracing,
+            rate_limit,
+            body_limits,
+            metrics,
             enable_sso,
+        };
+
+        match config.validate() {
+            Ok(warnings) => {
+                for warning in warnings {
+                    eprintln!("WARNING: {} (allowed outside production)", warning);
+                }
+                Ok(config)
+            }
+            Err(err) => Err(config::ConfigError::Message(err.to_string())),
+        }
+    }
+
+    /// Проверить согласованность настроек для профиля. В production любое нарушение -
+    /// ошибка со списком всех нарушений; в dev и test они возвращаются как предупреждения
+    /// Недопустимые значения (`invalid_values`) - ошибка в любом профиле
+    pub fn validate(&self) -> Result<Vec<ConfigViolation>, ConfigValidationError> {
+        let mut invalid = self.invalid_values();
+        let violations = self.violations();
+        if self.profile.is_strict() {
+            invalid.extend(violations);
+            if invalid.is_empty() {
+                return Ok(Vec::new());
+            }
+        } else if invalid.is_empty() {
+            return Ok(violations);
+        }
+        Err(ConfigValidationError {
+            profile: self.profile,
+            violations: invalid,
         })
     }
+
+    /// Значения, с которыми сервис не работает ни в одном профиле
+    fn invalid_values(&self) -> Vec<ConfigViolation> {
+        let mut invalid = Vec::new();
+
+        if self.reporting.export_part_size_bytes < MIN_MULTIPART_PART_SIZE_BYTES {
+            invalid.push(ConfigViolation::new(
+                "REPORTING_EXPORT_PART_SIZE_BYTES",
+                "reporting.export_part_size_bytes",
+                format!(
+                    "must be at least {} bytes (5 MiB) for S3 multipart upload (got {})",
+                    MIN_MULTIPART_PART_SIZE_BYTES, self.reporting.export_part_size_bytes
+                ),
+            ));
+        }
+
+        if let Some(kid) = &self.jwt_active_key_id {
+            if !self.jwt_keys.contains_key(kid) {
+                invalid.push(ConfigViolation::new(
+                    "JWT_ACTIVE_KEY_ID",
+                    "auth.jwt_active_key_id",
+                    format!("key '{}' is not listed in JWT_KEYS", kid),
+                ));
+            }
+        }
+
+        invalid
+    }
+
+    fn violations(&self) -> Vec<ConfigViolation> {
+        let mut violations = Vec::new();
+
+        if self.jwt_secret == DEV_JWT_SECRET || is_unresolved_placeholder(&self.jwt_secret) {
+            violations.push(ConfigViolation::new(
+                "JWT_SECRET",
+                "auth.jwt_secret",
+                "must be set; the built-in development secret is in use",
+            ));
+        } else if self.jwt_secret.len() < MIN_JWT_SECRET_LEN {
+            violations.push(ConfigViolation::new(
+                "JWT_SECRET",
+                "auth.jwt_secret",
+                format!(
+                    "must be at least {} characters long (got {})",
+                    MIN_JWT_SECRET_LEN,
+                    self.jwt_secret.len()
+                ),
+            ));
+        }
+        for (kid, secret) in &self.jwt_keys {
+            if secret == DEV_JWT_SECRET || is_unresolved_placeholder(secret) {
+                violations.push(ConfigViolation::new(
+                    "JWT_KEYS",
+                    "auth.jwt_keys",
+                    format!(
+                        "key '{}' must be set; the built-in development secret is in use",
+                        kid
+                    ),
+                ));
+            } else if secret.len() < MIN_JWT_SECRET_LEN {
+                violations.push(ConfigViolation::new(
+                    "JWT_KEYS",
+                    "auth.jwt_keys",
+                    format!(
+                        "key '{}' must be at least {} characters long (got {})",
+                        kid,
+                        MIN_JWT_SECRET_LEN,
+                        secret.len()
+                    ),
+                ));
+            }
+        }
+
+        let pseudonym_secret = &self.reporting.export_pseudonym_secret;
+        if pseudonym_secret == DEV_EXPORT_PSEUDONYM_SECRET
+            || is_unresolved_placeholder(pseudonym_secret)
+        {
+            violations.push(ConfigViolation::new(
+                "REPORTING_EXPORT_PSEUDONYM_SECRET",
+                "reporting.export_pseudonym_secret",
+                "must be set; the built-in development key is in use",
+            ));
+        }
+
+        if !self.cookie.secure {
+            violations.push(ConfigViolation::new(
+                "COOKIE_SECURE",
+                "cookie.secure",
+                "must be true so auth cookies are only sent over HTTPS",
+            ));
+        }
+
+        if self.cors.allows_any() {
+            violations.push(ConfigViolation::new(
+                "CORS_ALLOWED_ORIGINS",
+                "cors.allowed_origins",
+                "must list explicit origins instead of allowing any origin",
+            ));
+        }
+        let invalid_origins = self.cors.invalid_origins();
+        if !invalid_origins.is_empty() {
+            violations.push(ConfigViolation::new(
+                "CORS_ALLOWED_ORIGINS",
+                "cors.allowed_origins",
+                format!(
+                    "origins must look like https://host[:port], got {}",
+                    invalid_origins.join(", ")
+                ),
+            ));
+        }
+
+        if self.reporting.exports_enabled && self.object_storage.is_none() {
+            violations.push(ConfigViolation::new(
+                "OBJECT_STORAGE_BUCKET",
+                "object_storage",
+                "object storage must be configured while report exports are enabled \
+                 (set OBJECT_STORAGE_* or REPORTING_EXPORTS_ENABLED=false)",
+            ));
+        }
+
+        if !self.logging.is_json() {
+            violations.push(ConfigViolation::new(
+                "LOG_FORMAT",
+                "logging.format",
+                format!("must be \"json\" (got \"{}\")", self.logging.format),
+            ));
+        }
+
+        if self.metrics.is_dev_default() {
+            violations.push(ConfigViolation::new(
+                "METRICS_USERNAME/METRICS_PASSWORD",
+                "metrics",
+                "must be set; the default /metrics credentials are in use",
+            ));
+        }
+
+        violations
+    }
+}
+
+/// Значение вида `${VAR}` в config/*.toml, которое никто не подставил
+fn is_unresolved_placeholder(value: &str) -> bool {
+    let value = value.trim();
+    value.starts_with("${") && value.ends_with('}')
 }
 
 fn parse_bool_env_var(key: &str) -> Option<bool> {
@@ -407,6 +2171,18 @@ fn parse_bool_env_var(key: &str) -> Option<bool> {
     })
 }
 
+/// `JWT_KEYS=2024-06:secret1,2024-09:secret2`; двоеточие отделяет `kid` от секрета
+fn parse_jwt_keys_env_var(key: &str) -> BTreeMap<String, String> {
+    parse_csv_env_var(key)
+        .into_iter()
+        .filter_map(|entry| {
+            let (kid, secret) = entry.split_once(':')?;
+            let (kid, secret) = (kid.trim(), secret.trim());
+            (!kid.is_empty() && !secret.is_empty()).then(|| (kid.to_string(), secret.to_string()))
+        })
+        .collect()
+}
+
 fn parse_csv_env_var(key: &str) -> Vec<String> {
     env::var(key)
         .map(|value| {
@@ -424,3 +2200,282 @@ fn parse_csv_env_var(key: &str) -> Vec<String> {
         })
         .unwrap_or_default()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object_storage() -> ObjectStorageSettings {
+        ObjectStorageSettings {
+            bucket: "reports".into(),
+            region: "ru-central1".into(),
+            endpoint: None,
+            access_key: "key".into(),
+            secret_key: "secret".into(),
+            reports_prefix: "reports".into(),
+            presign_ttl_secs: ObjectStorageSettings::DEFAULT_PRESIGN_TTL_SECS,
+            sse: None,
+            sse_kms_key_id: None,
+            max_attempts: 4,
+            retry_base_delay_ms: 200,
+            request_timeout_secs: 120,
+            multipart_threshold_bytes: 32 * 1024 * 1024,
+            multipart_part_size_bytes: 8 * 1024 * 1024,
+        }
+    }
+
+    /// Конфигурация, которая проходит все проверки production
+    fn prod_config() -> Config {
+        Config {
+            profile: AppProfile::Prod,
+            mongo_uri: "mongodb://mongo-prod:27017".into(),
+            redis_uri: "redis://redis-prod:6379/0".into(),
+            mongo_database: "trainingground".into(),
+            jwt_secret: "x".repeat(MIN_JWT_SECRET_LEN),
+            jwt_fallback_secrets: vec![],
+            jwt_keys: BTreeMap::new(),
+            jwt_active_key_id: None,
+            python_api_url: "http://python-api:8000".into(),
+            reporting: ReportingSettings {
+                export_pseudonym_secret: "p".repeat(32),
+                ..ReportingSettings::default()
+            },
+            content: ContentSettings::default(),
+            hints: HintSettings::default(),
+            yandexgpt: YandexGptConfig::default(),
+            llm: LlmConfig::default(),
+            sessions: SessionSettings::default(),
+            anticheat_signals: AnticheatSignalSettings::default(),
+            accounts: AccountSettings::default(),
+            email_outbox: EmailOutboxSettings::default(),
+            logging: LoggingSettings {
+                level: "info".into(),
+                format: "json".into(),
+            },
+            cookie: CookieSettings::default(),
+            cors: CorsSettings {
+                allowed_origins: vec!["https://app.trainingground.ru".into()],
+            },
+            superuser_seed_file: None,
+            superuser_rotate: false,
+            superuser_rotate_print_password: false,
+            object_storage: Some(object_storage()),
+            archive: ArchiveSettings::default(),
+            audit: AuditSettings::default(),
+            tracing: TracingSettings::default(),
+            rate_limit: RateLimitSettings::default(),
+            body_limits: BodyLimitSettings::default(),
+            metrics: MetricsSettings {
+                username: "prometheus".into(),
+                password: "scrape-password".into(),
+            },
+            enable_sso: false,
+        }
+    }
+
+    /// Всё, что production не допускает, одновременно
+    fn misconfigured(profile: AppProfile) -> Config {
+        Config {
+            profile,
+            jwt_secret: DEV_JWT_SECRET.into(),
+            reporting: ReportingSettings::default(),
+            cookie: CookieSettings {
+                secure: false,
+                ..CookieSettings::default()
+            },
+            cors: CorsSettings::default(),
+            object_storage: None,
+            logging: LoggingSettings::default(),
+            metrics: MetricsSettings::dev_default(),
+            ..prod_config()
+        }
+    }
+
+    #[test]
+    fn production_ready_config_passes() {
+        assert_eq!(prod_config().validate().unwrap(), vec![]);
+    }
+
+    #[test]
+    fn production_reports_every_violation_at_once() {
+        let err = misconfigured(AppProfile::Prod).validate().unwrap_err();
+        assert_eq!(err.violations.len(), 7, "{err}");
+
+        let message = err.to_string();
+        assert!(message.contains("APP_ENV=prod (7 problem(s))"), "{message}");
+        for key in [
+            "JWT_SECRET (auth.jwt_secret)",
+            "REPORTING_EXPORT_PSEUDONYM_SECRET (reporting.export_pseudonym_secret)",
+            "COOKIE_SECURE (cookie.secure)",
+            "CORS_ALLOWED_ORIGINS (cors.allowed_origins)",
+            "OBJECT_STORAGE_BUCKET (object_storage)",
+            "LOG_FORMAT (logging.format)",
+            "METRICS_USERNAME/METRICS_PASSWORD (metrics)",
+        ] {
+            assert!(message.contains(key), "{key} missing from: {message}");
+        }
+    }
+
+    #[test]
+    fn dev_and_test_profiles_only_warn() {
+        for profile in [AppProfile::Dev, AppProfile::Test] {
+            let warnings = misconfigured(profile).validate().unwrap();
+            assert_eq!(warnings.len(), 7);
+        }
+    }
+
+    #[test]
+    fn undersized_export_part_fails_in_every_profile() {
+        for profile in [AppProfile::Dev, AppProfile::Test, AppProfile::Prod] {
+            let config = Config {
+                profile,
+                reporting: ReportingSettings {
+                    export_part_size_bytes: MIN_MULTIPART_PART_SIZE_BYTES - 1,
+                    export_pseudonym_secret: "p".repeat(32),
+                    ..ReportingSettings::default()
+                },
+                ..prod_config()
+            };
+            let err = config.validate().unwrap_err();
+            assert_eq!(err.violations.len(), 1, "{err}");
+            assert_eq!(
+                err.violations[0].env_var,
+                "REPORTING_EXPORT_PART_SIZE_BYTES"
+            );
+        }
+    }
+
+    #[test]
+    fn short_secret_wildcard_and_malformed_origins_are_rejected() {
+        let config = Config {
+            jwt_secret: "short-secret".into(),
+            cors: CorsSettings {
+                allowed_origins: vec![
+                    "https://app.trainingground.ru".into(),
+                    "*".into(),
+                    "app.trainingground.ru".into(),
+                    "https://admin.trainingground.ru/path".into(),
+                ],
+            },
+            ..prod_config()
+        };
+
+        let message = config.validate().unwrap_err().to_string();
+        assert!(
+            message.contains("at least 32 characters long (got 12)"),
+            "{message}"
+        );
+        assert!(
+            message.contains("instead of allowing any origin"),
+            "{message}"
+        );
+        assert!(
+            message.contains("got app.trainingground.ru, https://admin.trainingground.ru/path"),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn jwt_keys_follow_the_secret_rules() {
+        let config = Config {
+            jwt_keys: BTreeMap::from([
+                ("2024-06".to_string(), DEV_JWT_SECRET.to_string()),
+                ("2024-09".to_string(), "short-key".to_string()),
+                ("2024-12".to_string(), "k".repeat(MIN_JWT_SECRET_LEN)),
+            ]),
+            jwt_active_key_id: Some("2024-12".into()),
+            ..prod_config()
+        };
+        let err = config.validate().unwrap_err();
+        assert_eq!(err.violations.len(), 2, "{err}");
+        let message = err.to_string();
+        assert!(message.contains("key '2024-06' must be set"), "{message}");
+        assert!(
+            message.contains("key '2024-09' must be at least 32 characters long (got 9)"),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn unknown_active_key_id_fails_in_every_profile() {
+        for profile in [AppProfile::Dev, AppProfile::Test, AppProfile::Prod] {
+            let config = Config {
+                profile,
+                jwt_keys: BTreeMap::from([("2024-09".to_string(), "k".repeat(32))]),
+                jwt_active_key_id: Some("2024-12".into()),
+                ..prod_config()
+            };
+            let err = config.validate().unwrap_err();
+            assert_eq!(err.violations.len(), 1, "{err}");
+            assert_eq!(err.violations[0].env_var, "JWT_ACTIVE_KEY_ID");
+        }
+    }
+
+    #[test]
+    fn unresolved_placeholder_is_not_a_secret() {
+        let config = Config {
+            jwt_secret: "${JWT_SECRET_WITH_A_LONG_ENOUGH_NAME}".into(),
+            ..prod_config()
+        };
+        let err = config.validate().unwrap_err();
+        assert_eq!(err.violations[0].env_var, "JWT_SECRET");
+        assert!(err.violations[0].message.contains("must be set"));
+    }
+
+    #[test]
+    fn object_storage_is_optional_when_exports_are_disabled() {
+        let config = Config {
+            object_storage: None,
+            reporting: ReportingSettings {
+                exports_enabled: false,
+                ..prod_config().reporting
+            },
+            ..prod_config()
+        };
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn app_env_selects_profile() {
+        assert_eq!(AppProfile::from_app_env("prod"), AppProfile::Prod);
+        assert_eq!(AppProfile::from_app_env("Production"), AppProfile::Prod);
+        assert_eq!(AppProfile::from_app_env("test"), AppProfile::Test);
+        assert_eq!(AppProfile::from_app_env("dev"), AppProfile::Dev);
+        assert_eq!(AppProfile::from_app_env("staging"), AppProfile::Dev);
+    }
+
+    #[test]
+    fn config_file_is_layered_between_profile_file_and_env() {
+        let path = env::temp_dir().join(format!("tg-config-{}.toml", uuid::Uuid::new_v4()));
+        std::fs::write(
+            &path,
+            "[logging]\nformat = \"json\"\n\n[config_file_test]\nfrom_file = \"file\"\noverridden = \"file\"\n",
+        )
+        .unwrap();
+        env::set_var("APP__CONFIG_FILE_TEST__OVERRIDDEN", "env");
+
+        let settings = Config::settings("dev", path.to_str()).unwrap();
+        std::fs::remove_file(&path).ok();
+        env::remove_var("APP__CONFIG_FILE_TEST__OVERRIDDEN");
+
+        // Ключ из CONFIG_FILE перекрывает config/dev.toml ...
+        assert_eq!(settings.get_string("logging.format").unwrap(), "json");
+        // ... остальные ключи config/dev.toml сохраняются ...
+        assert_eq!(settings.get_string("logging.level").unwrap(), "debug");
+        assert_eq!(
+            settings.get_string("config_file_test.from_file").unwrap(),
+            "file"
+        );
+        // ... а переменные окружения перекрывают CONFIG_FILE
+        assert_eq!(
+            settings.get_string("config_file_test.overridden").unwrap(),
+            "env"
+        );
+    }
+
+    #[test]
+    fn missing_config_file_is_an_error() {
+        let err = Config::settings("dev", Some("/nonexistent/trainingground.toml")).unwrap_err();
+        assert!(err.to_string().contains("trainingground.toml"), "{err}");
+    }
+}
@@ -1,11 +1,14 @@
 use axum::{
-    extract::{FromRequest, Request},
-    http::StatusCode,
+    extract::{FromRequest, FromRequestParts, RawPathParams, Request},
+    http::{request::Parts, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
+use mongodb::bson::oid::ObjectId;
 use serde_json::json;
 
+use crate::{handlers::error::ErrorResponse, middlewares::body_limit::payload_too_large};
+
 /// Custom JSON extractor that returns JSON error responses instead of HTML
 pub struct AppJson<T>(pub T);
 
@@ -19,6 +22,9 @@ where
     async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
         match Json::<T>::from_request(req, state).await {
             Ok(Json(value)) => Ok(AppJson(value)),
+            Err(rejection) if rejection.status() == StatusCode::PAYLOAD_TOO_LARGE => {
+                Err(payload_too_large().into_response())
+            }
             Err(rejection) => {
                 let message = format!("Failed to parse JSON request body: {}", rejection);
                 tracing::warn!("{}", message);
@@ -31,3 +37,163 @@ where
         }
     }
 }
+
+/// Разобрать ObjectId (пробелы по краям допускаются).
+///
+/// Ошибка - 400 `INVALID_OBJECT_ID` с именем поля в `details.field`.
+pub fn parse_object_id(value: &str, field: &str) -> Result<ObjectId, ErrorResponse> {
+    ObjectId::parse_str(value.trim())
+        .map_err(|_| invalid_object_id(format!("Invalid {}: must be ObjectId", field), field))
+}
+
+fn invalid_object_id(message: String, field: &str) -> ErrorResponse {
+    ErrorResponse::bad_request("INVALID_OBJECT_ID", message).with_details(json!({ "field": field }))
+}
+
+/// Path-параметр с ObjectId для маршрутов с одним параметром (`/groups/{id}`)
+pub struct ObjectIdParam(pub ObjectId);
+
+impl<S> FromRequestParts<S> for ObjectIdParam
+where
+    S: Send + Sync,
+{
+    type Rejection = ErrorResponse;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let [id] = object_id_params::<1, S>(parts, state).await?;
+        Ok(ObjectIdParam(id))
+    }
+}
+
+/// Два ObjectId из path (`/groups/{group_id}/students/{student_id}`), в порядке маршрута
+pub struct ObjectIdParams(pub ObjectId, pub ObjectId);
+
+impl<S> FromRequestParts<S> for ObjectIdParams
+where
+    S: Send + Sync,
+{
+    type Rejection = ErrorResponse;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let [first, second] = object_id_params::<2, S>(parts, state).await?;
+        Ok(ObjectIdParams(first, second))
+    }
+}
+
+async fn object_id_params<const N: usize, S>(
+    parts: &mut Parts,
+    state: &S,
+) -> Result<[ObjectId; N], ErrorResponse>
+where
+    S: Send + Sync,
+{
+    let params = RawPathParams::from_request_parts(parts, state)
+        .await
+        .map_err(|rejection| ErrorResponse::bad_request("INVALID_PATH", rejection.body_text()))?;
+
+    let ids = params
+        .iter()
+        .map(|(name, value)| {
+            ObjectId::parse_str(value.trim()).map_err(|_| {
+                invalid_object_id(
+                    format!("Invalid path parameter {}: must be ObjectId", name),
+                    name,
+                )
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    ids.try_into().map_err(|ids: Vec<ObjectId>| {
+        ErrorResponse::internal(format!(
+            "Route has {} path parameters, expected {}",
+            ids.len(),
+            N
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::get, Router};
+    use tower::ServiceExt;
+
+    const HEX: &str = "65a1b2c3d4e5f60718293a4b";
+
+    #[test]
+    fn parses_valid_object_id() {
+        assert_eq!(parse_object_id(HEX, "group_id").unwrap().to_hex(), HEX);
+    }
+
+    #[test]
+    fn trims_surrounding_whitespace() {
+        let padded = format!("  {}\t\n", HEX);
+        assert_eq!(parse_object_id(&padded, "group_id").unwrap().to_hex(), HEX);
+    }
+
+    #[test]
+    fn rejects_invalid_object_id_with_field_name() {
+        for value in [
+            "not-an-id",
+            "",
+            "65a1b2c3d4e5f6071829",
+            "65a1 b2c3d4e5f60718293a4b",
+        ] {
+            let error = parse_object_id(value, "group_id").unwrap_err();
+            assert_eq!(error.status, StatusCode::BAD_REQUEST);
+            assert_eq!(error.code, "INVALID_OBJECT_ID");
+            assert_eq!(error.message, "Invalid group_id: must be ObjectId");
+            assert_eq!(error.details.unwrap()["field"], "group_id");
+        }
+    }
+
+    async fn call(router: Router, uri: &str) -> (StatusCode, serde_json::Value) {
+        let response = router
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null);
+        (status, body)
+    }
+
+    #[tokio::test]
+    async fn path_extractor_reports_route_param_name() {
+        let router = || {
+            Router::new()
+                .route(
+                    "/groups/{group_id}",
+                    get(|ObjectIdParam(id): ObjectIdParam| async move { id.to_hex() }),
+                )
+                .route(
+                    "/groups/{group_id}/students/{student_id}",
+                    get(|ObjectIdParams(_, student): ObjectIdParams| async move {
+                        student.to_hex()
+                    }),
+                )
+        };
+
+        let (status, _) = call(router(), &format!("/groups/%20{}%20", HEX)).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, body) = call(router(), "/groups/oops").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "INVALID_OBJECT_ID");
+        assert_eq!(
+            body["message"],
+            "Invalid path parameter group_id: must be ObjectId"
+        );
+        assert_eq!(body["details"]["field"], "group_id");
+
+        let (status, body) = call(router(), &format!("/groups/{}/students/oops", HEX)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body["message"],
+            "Invalid path parameter student_id: must be ObjectId"
+        );
+        assert_eq!(body["details"]["field"], "student_id");
+    }
+}
@@ -0,0 +1,31 @@
+use std::sync::Arc;
+
+use axum::{extract::State, Extension, Json};
+use chrono::Utc;
+
+use crate::{
+    handlers::error::ErrorResponse,
+    middlewares::auth::JwtClaims,
+    models::achievement::AchievementsResponse,
+    services::{achievement_service::AchievementService, AppState},
+};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/me/achievements",
+    tag = "achievements",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Текущая серия дней и открытые достижения", body = AchievementsResponse),
+    )
+)]
+pub async fn get_achievements(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<Json<AchievementsResponse>, ErrorResponse> {
+    AchievementService::new(state.mongo.clone(), &state.config.sessions)
+        .summary(&claims.sub, Utc::now())
+        .await
+        .map(Json)
+        .map_err(|e| ErrorResponse::internal(e.to_string()))
+}
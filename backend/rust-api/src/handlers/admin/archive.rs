@@ -0,0 +1,55 @@
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    Json,
+};
+use std::sync::Arc;
+
+use crate::{
+    handlers::error::ErrorResponse,
+    middlewares::auth::JwtClaims,
+    models::session_archive::RehydrateSessionResponse,
+    services::{
+        audit_service::AuditService, session_archive_service::SessionArchiveService, AppState,
+    },
+};
+
+/// POST /admin/sessions/:id/rehydrate - Временно восстановить сессию из архива (например, для разбора спора)
+pub async fn rehydrate_session(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    Path(session_id): Path<String>,
+) -> Result<Json<RehydrateSessionResponse>, ErrorResponse> {
+    let storage = state.archive_storage.clone().ok_or_else(|| {
+        ErrorResponse::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "ARCHIVE_STORAGE_UNAVAILABLE",
+            "Archive storage is not configured",
+        )
+    })?;
+
+    let service = SessionArchiveService::new(state.mongo.clone());
+    let copy = service
+        .rehydrate(
+            storage.as_ref(),
+            &session_id,
+            state.config.archive.rehydration_ttl(),
+        )
+        .await
+        .map_err(|e| {
+            let msg = e.to_string();
+            if msg.contains("not found") {
+                ErrorResponse::not_found("ARCHIVED_SESSION_NOT_FOUND", msg)
+            } else {
+                tracing::error!("Failed to rehydrate session {}: {:#}", session_id, e);
+                ErrorResponse::internal(msg)
+            }
+        })?;
+
+    let audit_service = AuditService::new(state.mongo.clone());
+    let _ = audit_service
+        .log_session_rehydrate(&claims.sub, &session_id, &copy.archive_key)
+        .await;
+
+    Ok(Json(RehydrateSessionResponse::from(&copy)))
+}
@@ -1,53 +1,142 @@
 use axum::{
-    extract::{Query, State},
-    http::{header, HeaderValue},
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, HeaderValue, StatusCode},
     response::Response,
     Json,
 };
+use chrono::Utc;
+use futures::{stream, StreamExt};
 use std::sync::Arc;
+use tracing::warn;
 
 use crate::{
-    models::audit_log::{AuditLog, AuditLogQuery},
-    services::{audit_service::AuditService, AppState},
+    handlers::error::ErrorResponse,
+    models::{
+        audit_archive::{AuditArchiveListQuery, AuditArchiveResponse},
+        audit_log::{AuditActorSummary, AuditActorSummaryQuery, AuditLogEntry, AuditLogQuery},
+    },
+    services::{
+        audit_archive_service::AuditArchiveService,
+        audit_service::{AuditService, AUDIT_LOG_ORDER},
+        AppState,
+    },
+    utils::{
+        csv::escape_csv_field,
+        pagination::{ensure_single_mode, paged_response, PageCursor},
+    },
 };
 
 use super::ApiError;
 
+const TOTAL_COUNT_HEADER: &str = "x-total-count";
+const DEFAULT_SUMMARY_DAYS: i64 = 30;
+const CSV_HEADER: &str =
+    "timestamp,event_type,user_id,email,success,ip,target,target_id,reason,details\n";
+
+/// GET /admin/audit - Журнал аудита с фильтрами; общее число записей в `X-Total-Count`,
+/// курсор следующей страницы - в `X-Next-Cursor`
+#[utoipa::path(
+    get,
+    path = "/admin/audit",
+    tag = "admin-audit",
+    params(AuditLogQuery),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Записи журнала, новые первыми", body = Vec<AuditLogEntry>,
+            headers(
+                ("x-total-count" = u64, description = "Число записей по фильтрам"),
+                ("x-next-cursor" = String, description = "Курсор следующей страницы; нет заголовка - страница последняя"),
+            )),
+        (status = 400, description = "Некорректный курсор или курсор вместе с offset", body = ErrorResponse),
+    )
+)]
 pub async fn list_audit_logs(
     State(state): State<Arc<AppState>>,
     Query(query): Query<AuditLogQuery>,
-) -> Result<Json<Vec<AuditLog>>, ApiError> {
+) -> Result<Response, ApiError> {
+    ensure_single_mode(query.cursor.as_deref(), query.offset)?;
+    let cursor = query
+        .cursor
+        .as_deref()
+        .map(|raw| PageCursor::decode(raw, &AUDIT_LOG_ORDER))
+        .transpose()
+        .map_err(ErrorResponse::from)?;
+
     let service = AuditService::new(state.mongo.clone());
-    let logs = service.list_logs(query).await.map_err(ApiError::from)?;
-    Ok(Json(logs))
+    let total = service.count_logs(&query).await.map_err(ApiError::from)?;
+    let page = service
+        .list_logs(query, cursor.as_ref())
+        .await
+        .map_err(ApiError::from)?;
+
+    let mut response = paged_response(page);
+    response
+        .headers_mut()
+        .insert(TOTAL_COUNT_HEADER, HeaderValue::from(total));
+    Ok(response)
+}
+
+/// GET /admin/audit/actors/{id}/summary - Число действий пользователя по типам за период
+pub async fn audit_actor_summary(
+    State(state): State<Arc<AppState>>,
+    Path(actor_id): Path<String>,
+    Query(query): Query<AuditActorSummaryQuery>,
+) -> Result<Json<AuditActorSummary>, ApiError> {
+    let to = query.to.unwrap_or_else(Utc::now);
+    let from = query
+        .from
+        .unwrap_or_else(|| to - chrono::Duration::days(DEFAULT_SUMMARY_DAYS));
+    if from > to {
+        return Err(ApiError::bad_request(
+            "INVALID_PERIOD",
+            "`from` must not be later than `to`",
+        ));
+    }
+
+    let summary = AuditService::new(state.mongo.clone())
+        .actor_summary(&actor_id, from, to)
+        .await
+        .map_err(ApiError::from)?;
+    Ok(Json(summary))
 }
 
+/// GET /admin/audit/export - CSV с теми же фильтрами, что и список.
+///
+/// Строки отдаются по мере чтения курсора, без сборки файла в памяти; выгрузка
+/// больше `audit.export_max_rows` строк отклоняется с 413.
 pub async fn export_audit_logs(
     State(state): State<Arc<AppState>>,
     Query(query): Query<AuditLogQuery>,
 ) -> Result<Response, ApiError> {
+    let max_rows = state.config.audit.export_max_rows;
     let service = AuditService::new(state.mongo.clone());
-    let logs = service
-        .export_logs(query, 1000)
-        .await
-        .map_err(ApiError::from)?;
 
-    let mut csv = String::from("timestamp,event_type,user_id,email,success,ip,details\n");
-    for log in logs {
-        let timestamp = log.created_at.to_rfc3339();
-        let event = log.event_type.as_str();
-        let user_id = log.user_id.unwrap_or_default();
-        let email = log.email.unwrap_or_default();
-        let success = log.success;
-        let ip = log.ip.unwrap_or_default();
-        let details = log.details.unwrap_or_default().replace('\n', " ");
-
-        csv.push_str(&format!(
-            "\"{timestamp}\",\"{event}\",\"{user_id}\",\"{email}\",{success},\"{ip}\",\"{details}\"\n"
-        ));
+    let total = service.count_logs(&query).await.map_err(ApiError::from)?;
+    if total > max_rows {
+        return Err(ErrorResponse::new(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "EXPORT_TOO_LARGE",
+            format!(
+                "Export matches {} audit entries, the limit is {}; narrow the filters",
+                total, max_rows
+            ),
+        )
+        .into());
     }
 
-    let mut response = Response::new(csv.into());
+    let rows = service
+        .stream_logs(&query, max_rows)
+        .await
+        .map_err(ApiError::from)?
+        .map(|entry| {
+            entry
+                .map(|entry| audit_csv_row(&entry))
+                .inspect_err(|err| warn!("Audit log export interrupted: {:#}", err))
+        });
+    let body = Body::from_stream(stream::once(async { Ok(CSV_HEADER.to_string()) }).chain(rows));
+
+    let mut response = Response::new(body);
     response.headers_mut().insert(
         header::CONTENT_TYPE,
         HeaderValue::from_static("text/csv; charset=utf-8"),
@@ -59,3 +148,63 @@ pub async fn export_audit_logs(
 
     Ok(response)
 }
+
+fn audit_csv_row(entry: &AuditLogEntry) -> String {
+    let details = match &entry.details {
+        Some(serde_json::Value::String(details)) => details.clone(),
+        Some(details) => details.to_string(),
+        None => String::new(),
+    };
+    let text = |value: &Option<String>| escape_csv_field(value.as_deref().unwrap_or_default());
+
+    format!(
+        "{},{},{},{},{},{},{},{},{},{}\n",
+        entry.created_at.to_rfc3339(),
+        escape_csv_field(&entry.event_type),
+        text(&entry.actor_id),
+        text(&entry.email),
+        entry.success,
+        text(&entry.ip),
+        text(&entry.target),
+        text(&entry.target_id),
+        text(&entry.reason),
+        escape_csv_field(&details),
+    )
+}
+
+/// GET /admin/audit/archives - Архивные файлы журнала аудита со ссылками на скачивание
+pub async fn list_audit_archives(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AuditArchiveListQuery>,
+) -> Result<Json<Vec<AuditArchiveResponse>>, ApiError> {
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    let files = AuditArchiveService::new(state.mongo.clone())
+        .list_archives(limit)
+        .await
+        .map_err(ApiError::from)?;
+
+    let ttl = state.config.presign_ttl();
+
+    let mut archives = Vec::with_capacity(files.len());
+    for file in files {
+        let download_url =
+            match state.export_links.as_ref() {
+                Some(signer) => Some(signer.presigned_download_url(&file.key, ttl).map_err(
+                    |err| ApiError::Internal(format!("Failed to sign download URL: {}", err)),
+                )?),
+                None => None,
+            };
+        archives.push(AuditArchiveResponse {
+            key: file.key,
+            day: file.day,
+            part: file.part,
+            status: file.status,
+            entry_count: file.entry_count,
+            size_bytes: file.size_bytes,
+            archived_at: file.archived_at,
+            download_url,
+        });
+    }
+
+    Ok(Json(archives))
+}
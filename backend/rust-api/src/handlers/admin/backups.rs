@@ -1,13 +1,18 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Extension, State},
     http::StatusCode,
     response::IntoResponse,
     Json,
 };
+use mongodb::bson::oid::ObjectId;
 use std::sync::Arc;
 
 use crate::{
-    models::backup::BackupCreateRequest,
+    extractors::ObjectIdParam,
+    middlewares::auth::JwtClaims,
+    models::backup::{
+        BackupCreateOutcome, BackupCreateRequest, BackupRestoreOutcome, BackupRestoreRequest,
+    },
     services::{backup_service::BackupService, AppState},
 };
 
@@ -22,26 +27,114 @@ pub async fn list_backups(
     Ok(Json(records))
 }
 
+/// GET /admin/backups/{id} - Задача резервного копирования с прогрессом по коллекциям
+pub async fn get_backup(
+    State(state): State<Arc<AppState>>,
+    ObjectIdParam(id): ObjectIdParam,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let record = BackupService::new(state.mongo.clone())
+        .get_backup(id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Backup not found".to_string()))?;
+    Ok(Json(record))
+}
+
+/// POST /admin/backups - Создать задачу и выгрузить коллекции в объектное хранилище в фоне
 pub async fn create_backup(
     State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
     Json(payload): Json<BackupCreateRequest>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let storage = state.archive_storage.clone().ok_or_else(|| {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Object storage is not configured".to_string(),
+        )
+    })?;
+
     let service = BackupService::new(state.mongo.clone());
-    let response = service
-        .create_backup(payload, "admin")
+    let response = match service
+        .create_backup(payload, &claims.sub)
         .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    {
+        BackupCreateOutcome::Created(response) => response,
+        BackupCreateOutcome::UnknownCollections(unknown) if unknown.is_empty() => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "collections must not be empty".to_string(),
+            ))
+        }
+        BackupCreateOutcome::UnknownCollections(unknown) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Unknown collections: {}", unknown.join(", ")),
+            ))
+        }
+    };
+
+    let backup_id = ObjectId::parse_str(&response.id)
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let mongo = state.mongo.clone();
+    tokio::spawn(async move {
+        if let Err(err) = BackupService::new(mongo)
+            .run_backup(backup_id, storage.as_ref())
+            .await
+        {
+            tracing::error!("Backup {} failed: {:#}", backup_id, err);
+        }
+    });
+
     Ok((StatusCode::CREATED, Json(response)))
 }
 
+/// POST /admin/backups/{id}/restore - Восстановить бэкап целиком или выбранные коллекции.
+///
+/// `dry_run: true` только считает, сколько документов будет вставлено и перезаписано.
 pub async fn restore_backup(
     State(state): State<Arc<AppState>>,
-    Path(backup_id): Path<String>,
+    Extension(claims): Extension<JwtClaims>,
+    ObjectIdParam(id): ObjectIdParam,
+    payload: Option<Json<BackupRestoreRequest>>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let storage = state.archive_storage.clone().ok_or_else(|| {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Object storage is not configured".to_string(),
+        )
+    })?;
+    let request = payload.map(|Json(request)| request).unwrap_or_default();
+
     let service = BackupService::new(state.mongo.clone());
-    let response = service
-        .restore_backup(&backup_id, "admin")
+    let outcome = service
+        .restore_backup(storage.as_ref(), &state.redis, id, request, &claims.sub)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-    Ok((StatusCode::OK, Json(response)))
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)))?;
+
+    match outcome {
+        BackupRestoreOutcome::Restored(response) => Ok((StatusCode::OK, Json(response))),
+        BackupRestoreOutcome::NotFound => {
+            Err((StatusCode::NOT_FOUND, "Backup not found".to_string()))
+        }
+        BackupRestoreOutcome::NotRestorable(status) => Err((
+            StatusCode::CONFLICT,
+            format!(
+                "Backup is {:?}, only completed backups can be restored",
+                status
+            ),
+        )),
+        BackupRestoreOutcome::UnknownCollections(unknown) if unknown.is_empty() => Err((
+            StatusCode::BAD_REQUEST,
+            "collections must not be empty".to_string(),
+        )),
+        BackupRestoreOutcome::UnknownCollections(unknown) => Err((
+            StatusCode::BAD_REQUEST,
+            format!("Collections not in backup: {}", unknown.join(", ")),
+        )),
+        BackupRestoreOutcome::InProgress => Err((
+            StatusCode::CONFLICT,
+            "Another restore is already in progress".to_string(),
+        )),
+    }
 }
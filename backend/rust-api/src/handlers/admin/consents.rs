@@ -0,0 +1,156 @@
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use std::sync::Arc;
+use validator::Validate;
+
+use crate::{
+    extractors::{parse_object_id, AppJson, ObjectIdParam},
+    middlewares::auth::JwtClaims,
+    models::consent::{
+        ConsentCoverageReport, ConsentResponse, CreateGuardianLinkRequest, RecordConsentRequest,
+        UserConsentStatus,
+    },
+    services::{
+        audit_service::AuditService,
+        consent_service::{ConsentService, GuardianLinkError},
+        AppState,
+    },
+};
+
+use super::ApiError;
+
+/// GET /admin/users/:id/consents - История согласий ученика и недостающие согласия
+pub async fn list_user_consents(
+    State(state): State<Arc<AppState>>,
+    ObjectIdParam(user_obj): ObjectIdParam,
+) -> Result<Json<UserConsentStatus>, ApiError> {
+    let user_id = user_obj.to_hex();
+    let service = ConsentService::new(state.mongo.clone());
+
+    let consents = service.list_consents(&user_obj).await?;
+    let missing = service.missing_for_student(&user_id).await?;
+
+    Ok(Json(UserConsentStatus {
+        user_id,
+        consents: consents.into_iter().map(ConsentResponse::from).collect(),
+        missing,
+    }))
+}
+
+/// POST /admin/users/:id/consents - Записать согласие (от имени родителя или школы)
+pub async fn record_user_consent(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    ObjectIdParam(user_obj): ObjectIdParam,
+    AppJson(payload): AppJson<RecordConsentRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user_id = user_obj.to_hex();
+    payload.validate().map_err(ApiError::Validation)?;
+
+    let service = ConsentService::new(state.mongo.clone());
+    let record = service
+        .record_consent(&user_obj, payload, &claims.sub)
+        .await
+        .map_err(|e| {
+            let msg = e.to_string();
+            if msg.contains("not found") {
+                ApiError::not_found("USER_NOT_FOUND", msg)
+            } else if msg.contains("required") {
+                ApiError::bad_request("GUARDIAN_NAME_REQUIRED", msg)
+            } else {
+                ApiError::Internal(msg)
+            }
+        })?;
+
+    let audit_service = AuditService::new(state.mongo.clone());
+    let _ = audit_service
+        .log_consent_record(
+            &claims.sub,
+            &user_id,
+            &record.consent_type,
+            &record.document_version,
+        )
+        .await;
+
+    Ok((StatusCode::CREATED, Json(ConsentResponse::from(record))))
+}
+
+/// DELETE /admin/users/:id/consents/:consent_type - Отозвать согласие
+pub async fn revoke_user_consent(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    Path((user_id, consent_type)): Path<(String, String)>,
+) -> Result<StatusCode, ApiError> {
+    let user_obj = parse_object_id(&user_id, "user_id")?;
+
+    let service = ConsentService::new(state.mongo.clone());
+    service
+        .revoke_consent(&user_obj, &consent_type, &claims.sub)
+        .await
+        .map_err(|e| {
+            let msg = e.to_string();
+            if msg.contains("not found") {
+                ApiError::not_found("CONSENT_NOT_FOUND", msg)
+            } else {
+                ApiError::Internal(msg)
+            }
+        })?;
+
+    let audit_service = AuditService::new(state.mongo.clone());
+    let _ = audit_service
+        .log_consent_revoke(&claims.sub, &user_id, &consent_type)
+        .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /admin/users/:id/consents/guardian-link - Ссылка, по которой родитель сам
+/// даёт и отзывает согласия (`/api/v1/guardian/consents/{token}`)
+pub async fn create_guardian_link(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    ObjectIdParam(user_obj): ObjectIdParam,
+    AppJson(payload): AppJson<CreateGuardianLinkRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    payload.validate().map_err(ApiError::Validation)?;
+
+    let service = ConsentService::new(state.mongo.clone());
+    let link = service
+        .create_guardian_link(&user_obj, payload, &claims.sub)
+        .await
+        .map_err(|e| {
+            if let Some(GuardianLinkError::NotStudent) = e.downcast_ref::<GuardianLinkError>() {
+                return ApiError::bad_request("NOT_A_STUDENT", e.to_string());
+            }
+            let msg = e.to_string();
+            if msg.contains("not found") {
+                ApiError::not_found("USER_NOT_FOUND", msg)
+            } else {
+                ApiError::Internal(msg)
+            }
+        })?;
+
+    Ok((StatusCode::CREATED, Json(link)))
+}
+
+/// GET /admin/groups/:id/consent-coverage - Покрытие согласиями по группе
+pub async fn group_consent_coverage(
+    State(state): State<Arc<AppState>>,
+    ObjectIdParam(group_obj): ObjectIdParam,
+) -> Result<Json<ConsentCoverageReport>, ApiError> {
+    let service = ConsentService::new(state.mongo.clone());
+    let report = service.coverage_report(&group_obj).await.map_err(|e| {
+        let msg = e.to_string();
+        if msg.contains("not found") {
+            ApiError::not_found("GROUP_NOT_FOUND", msg)
+        } else {
+            ApiError::Internal(msg)
+        }
+    })?;
+
+    Ok(Json(report))
+}
@@ -1,20 +1,33 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Extension, Path, Query, State},
     http::StatusCode,
     response::IntoResponse,
     Json,
 };
 use chrono::Utc;
-use mongodb::bson::{doc, Document};
-use redis::AsyncCommands;
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, Bson, Document},
+    options::ReturnDocument,
+};
 use serde_json::json;
 use std::sync::Arc;
 use tracing::{info, warn};
+use validator::Validate;
 
 use crate::{
-    extractors::AppJson, models::feature_flag::FeatureFlagCreateRequest, services::AppState,
+    extractors::AppJson,
+    handlers::error::ErrorResponse,
+    middlewares::auth::JwtClaims,
+    models::feature_flag::{
+        FeatureFlagCreateRequest, FeatureFlagHistoryEntry, FeatureFlagHistoryQuery,
+        FeatureFlagHistoryResponse,
+    },
+    services::{feature_flag_service::invalidate_flag_cache, AppState},
 };
 
+const FEATURE_FLAG_HISTORY_COLLECTION: &str = "feature_flag_history";
+
 /// Dependencies between feature flags
 /// If a flag requires another flag to be enabled
 const FLAG_DEPENDENCIES: &[(&str, &str)] = &[
@@ -77,6 +90,10 @@ pub async fn create_feature_flag(
     State(state): State<Arc<AppState>>,
     AppJson(req): AppJson<FeatureFlagCreateRequest>,
 ) -> impl IntoResponse {
+    if let Err(errors) = req.validate() {
+        return ErrorResponse::validation(&errors).into_response();
+    }
+
     // Validate flag_key format
     if !req
         .flag_key
@@ -122,9 +139,12 @@ pub async fn create_feature_flag(
         "enabled": req.enabled,
         "scope": &req.scope,
         "target_ids": &req.target_ids,
+        "enabled_for_roles": &req.enabled_for_roles,
+        "enabled_for_user_ids": &req.enabled_for_user_ids,
+        "rollout_percentage": req.rollout_percentage,
         "config": mongodb::bson::to_bson(&req.config).unwrap_or_default(),
         "version": 1,
-        "updated_at": now.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        "updated_at": mongodb::bson::DateTime::from_millis(now.timestamp_millis()),
         "updated_by": "system",
         "change_reason": &req.change_reason,
     };
@@ -148,9 +168,14 @@ pub async fn create_feature_flag(
 /// PUT /admin/feature-flags/:flag_key - Update flag
 pub async fn update_feature_flag(
     State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
     Path(flag_key): Path<String>,
     AppJson(req): AppJson<FeatureFlagCreateRequest>,
 ) -> impl IntoResponse {
+    if let Err(errors) = req.validate() {
+        return ErrorResponse::validation(&errors).into_response();
+    }
+
     // Validate scope
     if !["global", "group", "user"].contains(&req.scope.as_str()) {
         return (
@@ -188,38 +213,59 @@ pub async fn update_feature_flag(
     }
 
     let now = Utc::now();
+    let set_doc = doc! {
+        "description": &req.description,
+        "enabled": req.enabled,
+        "scope": &req.scope,
+        "target_ids": &req.target_ids,
+        "enabled_for_roles": &req.enabled_for_roles,
+        "enabled_for_user_ids": &req.enabled_for_user_ids,
+        "rollout_percentage": req.rollout_percentage,
+        "config": mongodb::bson::to_bson(&req.config).unwrap_or_default(),
+        "updated_at": mongodb::bson::DateTime::from_millis(now.timestamp_millis()),
+        "updated_by": &claims.sub,
+        "change_reason": &req.change_reason,
+    };
     let update_doc = doc! {
-        "$set": doc! {
-            "description": &req.description,
-            "enabled": req.enabled,
-            "scope": &req.scope,
-            "target_ids": &req.target_ids,
-            "config": mongodb::bson::to_bson(&req.config).unwrap_or_default(),
-            "version": doc! { "$add": [doc! { "$ifNull": ["$version", 0] }, 1] },
-            "updated_at": now.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
-            "updated_by": "system",
-            "change_reason": &req.change_reason,
-        }
+        "$set": set_doc.clone(),
+        "$inc": { "version": 1 },
     };
 
+    // Previous document comes from the same atomic operation, so concurrent
+    // updates can't interleave their history entries
     match collection
-        .update_one(doc! { "flag_key": &flag_key }, update_doc)
+        .find_one_and_update(doc! { "flag_key": &flag_key }, update_doc)
+        .return_document(ReturnDocument::Before)
         .await
     {
-        Ok(result) => {
-            if result.matched_count == 0 {
-                return (
-                    StatusCode::NOT_FOUND,
-                    Json(json!({ "error": "Flag not found" })),
-                )
-                    .into_response();
-            }
-
+        Ok(Some(previous)) => {
             info!(
                 "Feature flag updated: {}, reason: {}",
                 flag_key, req.change_reason
             );
 
+            let current = apply_update(&previous, set_doc);
+            let history_doc = doc! {
+                "flag_key": &flag_key,
+                "version": current.get("version").cloned().unwrap_or(Bson::Int32(1)),
+                "actor_id": &claims.sub,
+                "change_reason": &req.change_reason,
+                "old": previous,
+                "new": current,
+                "changed_at": mongodb::bson::DateTime::from_millis(now.timestamp_millis()),
+            };
+            if let Err(e) = state
+                .mongo
+                .collection::<Document>(FEATURE_FLAG_HISTORY_COLLECTION)
+                .insert_one(history_doc)
+                .await
+            {
+                warn!(
+                    "Failed to record history for feature flag {}: {}",
+                    flag_key, e
+                );
+            }
+
             // Log to audit_log
             let audit_collection = state.mongo.collection("audit_log");
             let audit_doc = doc! {
@@ -229,18 +275,26 @@ pub async fn update_feature_flag(
                 "changes": {
                     "enabled": req.enabled,
                     "scope": &req.scope,
+                    "enabled_for_roles": &req.enabled_for_roles,
+                    "enabled_for_user_ids": &req.enabled_for_user_ids,
+                    "rollout_percentage": req.rollout_percentage,
                 },
                 "reason": &req.change_reason,
                 "timestamp": now.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
-                "admin_id": "system",
+                "admin_id": &claims.sub,
             };
             let _ = audit_collection.insert_one(audit_doc).await;
 
             // Invalidate cache
-            let _: Result<(), _> = state.redis.clone().del(format!("ff:{}:*", flag_key)).await;
+            invalidate_flag_cache(&state.redis, &flag_key).await;
 
             (StatusCode::OK, Json(json!(req))).into_response()
         }
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Flag not found" })),
+        )
+            .into_response(),
         Err(e) => {
             warn!("Failed to update feature flag: {}", e);
             (
@@ -252,6 +306,85 @@ pub async fn update_feature_flag(
     }
 }
 
+/// Flag document after `$set` + `$inc: { version: 1 }`, derived from the previous one
+fn apply_update(previous: &Document, set_doc: Document) -> Document {
+    let mut current = previous.clone();
+    let version = match previous.get("version") {
+        Some(Bson::Int32(v)) => Bson::Int32(v + 1),
+        Some(Bson::Int64(v)) => Bson::Int64(v + 1),
+        Some(Bson::Double(v)) => Bson::Double(v + 1.0),
+        _ => Bson::Int32(1),
+    };
+    current.extend(set_doc);
+    current.insert("version", version);
+    current
+}
+
+/// GET /admin/feature-flags/:flag_key/history - Change history, newest first
+pub async fn get_feature_flag_history(
+    State(state): State<Arc<AppState>>,
+    Path(flag_key): Path<String>,
+    Query(query): Query<FeatureFlagHistoryQuery>,
+) -> impl IntoResponse {
+    let collection = state
+        .mongo
+        .collection::<FeatureFlagHistoryEntry>(FEATURE_FLAG_HISTORY_COLLECTION);
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let offset = query.offset.unwrap_or(0);
+    let filter = doc! { "flag_key": &flag_key };
+
+    let total = match collection.count_documents(filter.clone()).await {
+        Ok(total) => total,
+        Err(e) => {
+            warn!(
+                "Failed to count history for feature flag {}: {}",
+                flag_key, e
+            );
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to load feature flag history" })),
+            )
+                .into_response();
+        }
+    };
+
+    let entries: Result<Vec<FeatureFlagHistoryEntry>, _> = match collection
+        .find(filter)
+        .sort(doc! { "version": -1, "changed_at": -1 })
+        .skip(offset as u64)
+        .limit(limit as i64)
+        .await
+    {
+        Ok(cursor) => cursor.try_collect().await,
+        Err(e) => Err(e),
+    };
+
+    match entries {
+        Ok(entries) => (
+            StatusCode::OK,
+            Json(FeatureFlagHistoryResponse {
+                flag_key,
+                total,
+                limit,
+                offset,
+                entries,
+            }),
+        )
+            .into_response(),
+        Err(e) => {
+            warn!(
+                "Failed to load history for feature flag {}: {}",
+                flag_key, e
+            );
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to load feature flag history" })),
+            )
+                .into_response()
+        }
+    }
+}
+
 /// DELETE /admin/feature-flags/:flag_key - Delete flag
 pub async fn delete_feature_flag(
     State(state): State<Arc<AppState>>,
@@ -272,7 +405,7 @@ pub async fn delete_feature_flag(
             info!("Feature flag deleted: {}", flag_key);
 
             // Invalidate cache
-            let _: Result<(), _> = state.redis.clone().del(format!("ff:{}:*", flag_key)).await;
+            invalidate_flag_cache(&state.redis, &flag_key).await;
 
             (StatusCode::NO_CONTENT).into_response()
         }
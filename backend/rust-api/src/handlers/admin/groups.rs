@@ -4,31 +4,55 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
+use serde_json::json;
 use std::sync::Arc;
 use validator::Validate;
 
 use crate::{
+    handlers::{
+        error::ErrorResponse,
+        teacher::{group_student_summaries, StudentSummary},
+    },
     middlewares::auth::JwtClaims,
-    models::group::{CreateGroupRequest, ListGroupsQuery, UpdateGroupRequest},
-    services::{audit_service::AuditService, group_service::GroupService, AppState},
+    models::group::{
+        AddGroupMemberRequest, CreateGroupRequest, CreateInviteCodeRequest, GroupResponse,
+        ListGroupsQuery, ReassignCuratorRequest, UpdateGroupRequest,
+    },
+    services::{
+        audit_service::AuditService,
+        group_invite_service::{GroupInviteService, InviteCodeError},
+        group_service::{GroupMembershipError, GroupService},
+        AppState,
+    },
 };
 
 /// POST /admin/groups - Создать группу
+#[utoipa::path(
+    post,
+    path = "/admin/groups",
+    tag = "admin-groups",
+    request_body = CreateGroupRequest,
+    security(("bearer_auth" = [], "csrf_token" = [])),
+    responses(
+        (status = 201, description = "Группа создана", body = GroupResponse),
+        (status = 400, description = "Ошибка валидации", body = ErrorResponse),
+    )
+)]
 pub async fn create_group(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
     Json(req): Json<CreateGroupRequest>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, ErrorResponse> {
     // Валидация
     req.validate()
-        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+        .map_err(|errors| ErrorResponse::validation(&errors))?;
 
     // Создание группы
     let group_service = GroupService::new(state.mongo.clone());
     let created_group = group_service
         .create_group(req.clone())
         .await
-        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+        .map_err(group_error)?;
 
     // Audit log
     let audit_service = AuditService::new(state.mongo.clone());
@@ -47,66 +71,91 @@ pub async fn create_group(
 }
 
 /// GET /admin/groups - Список групп
+#[utoipa::path(
+    get,
+    path = "/admin/groups",
+    tag = "admin-groups",
+    params(ListGroupsQuery),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Группы по фильтрам", body = Vec<GroupResponse>),
+    )
+)]
 pub async fn list_groups(
     State(state): State<Arc<AppState>>,
     Query(query): Query<ListGroupsQuery>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, ErrorResponse> {
     let group_service = GroupService::new(state.mongo.clone());
 
     let groups = group_service
         .list_groups(query)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(|e| ErrorResponse::internal(e.to_string()))?;
 
     Ok(Json(groups))
 }
 
 /// GET /admin/groups/:id - Получить группу
+#[utoipa::path(
+    get,
+    path = "/admin/groups/{id}",
+    tag = "admin-groups",
+    params(("id" = String, Path, description = "Id группы")),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Группа", body = GroupResponse),
+        (status = 404, description = "Группа не найдена", body = ErrorResponse),
+    )
+)]
 pub async fn get_group(
     State(state): State<Arc<AppState>>,
     Path(group_id): Path<String>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, ErrorResponse> {
     let group_service = GroupService::new(state.mongo.clone());
 
-    let group = group_service.get_group(&group_id).await.map_err(|e| {
-        if e.to_string().contains("not found") {
-            (StatusCode::NOT_FOUND, e.to_string())
-        } else {
-            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
-        }
-    })?;
+    let group = group_service
+        .get_group(&group_id)
+        .await
+        .map_err(group_error)?;
 
     Ok(Json(group))
 }
 
 /// PATCH /admin/groups/:id - Обновить группу
+#[utoipa::path(
+    patch,
+    path = "/admin/groups/{id}",
+    tag = "admin-groups",
+    params(("id" = String, Path, description = "Id группы")),
+    request_body = UpdateGroupRequest,
+    security(("bearer_auth" = [], "csrf_token" = [])),
+    responses(
+        (status = 200, description = "Обновлённая группа", body = GroupResponse),
+        (status = 400, description = "Ошибка валидации", body = ErrorResponse),
+        (status = 404, description = "Группа не найдена", body = ErrorResponse),
+    )
+)]
 pub async fn update_group(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
     Path(group_id): Path<String>,
     Json(req): Json<UpdateGroupRequest>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, ErrorResponse> {
     // Валидация
     req.validate()
-        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+        .map_err(|errors| ErrorResponse::validation(&errors))?;
 
     // Обновление группы
     let group_service = GroupService::new(state.mongo.clone());
     let updated_group = group_service
         .update_group(&group_id, req.clone())
         .await
-        .map_err(|e| {
-            if e.to_string().contains("not found") {
-                (StatusCode::NOT_FOUND, e.to_string())
-            } else {
-                (StatusCode::BAD_REQUEST, e.to_string())
-            }
-        })?;
+        .map_err(group_error)?;
 
     // Audit log
     let audit_service = AuditService::new(state.mongo.clone());
     let changes = format!(
-        "name: {}, school: {}, curator_id: {}, description: {}",
+        "name: {}, school: {}, curator_id: {}, description: {}, max_students: {}",
         req.name.as_deref().unwrap_or("unchanged"),
         req.school.as_deref().unwrap_or("unchanged"),
         req.curator_id
@@ -117,6 +166,9 @@ pub async fn update_group(
             .as_ref()
             .map(|_| "updated")
             .unwrap_or("unchanged"),
+        req.max_students
+            .map(|max| max.to_string())
+            .unwrap_or_else(|| "unchanged".to_string()),
     );
 
     let _ = audit_service
@@ -127,17 +179,28 @@ pub async fn update_group(
 }
 
 /// DELETE /admin/groups/:id - Удалить группу
+#[utoipa::path(
+    delete,
+    path = "/admin/groups/{id}",
+    tag = "admin-groups",
+    params(("id" = String, Path, description = "Id группы")),
+    security(("bearer_auth" = [], "csrf_token" = [])),
+    responses(
+        (status = 204, description = "Группа удалена"),
+        (status = 404, description = "Группа не найдена", body = ErrorResponse),
+    )
+)]
 pub async fn delete_group(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
     Path(group_id): Path<String>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, ErrorResponse> {
     // Получение имени для audit log (до удаления)
     let group_service = GroupService::new(state.mongo.clone());
     let group = group_service
         .get_group(&group_id)
         .await
-        .map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))?;
+        .map_err(group_error)?;
 
     let group_name = group.name.clone();
 
@@ -145,7 +208,7 @@ pub async fn delete_group(
     group_service
         .delete_group(&group_id)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(group_error)?;
 
     // Audit log
     let audit_service = AuditService::new(state.mongo.clone());
@@ -156,15 +219,310 @@ pub async fn delete_group(
     Ok(StatusCode::NO_CONTENT)
 }
 
-/// GET /admin/groups/export - Экспорт всех групп в CSV
-pub async fn export_groups(
+/// POST /admin/groups/:id/archive - Архивировать группу.
+/// Статистика и выгрузки по группе продолжают работать
+#[utoipa::path(
+    post,
+    path = "/admin/groups/{id}/archive",
+    tag = "admin-groups",
+    params(("id" = String, Path, description = "Id группы")),
+    security(("bearer_auth" = [], "csrf_token" = [])),
+    responses(
+        (status = 200, description = "Группа архивирована", body = GroupResponse),
+        (status = 404, description = "Группа не найдена", body = ErrorResponse),
+    )
+)]
+pub async fn archive_group(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    Path(group_id): Path<String>,
+) -> Result<impl IntoResponse, ErrorResponse> {
+    let group_service = GroupService::new(state.mongo.clone());
+    let group = group_service
+        .archive_group(&group_id)
+        .await
+        .map_err(group_error)?;
+
+    let audit_service = AuditService::new(state.mongo.clone());
+    let _ = audit_service
+        .log_group_archive(&claims.sub, &group_id, &group.name)
+        .await;
+
+    Ok(Json(group))
+}
+
+/// PUT /admin/groups/:id/curator - Сменить куратора группы
+#[utoipa::path(
+    put,
+    path = "/admin/groups/{id}/curator",
+    tag = "admin-groups",
+    params(("id" = String, Path, description = "Id группы")),
+    request_body = ReassignCuratorRequest,
+    security(("bearer_auth" = [], "csrf_token" = [])),
+    responses(
+        (status = 200, description = "Группа с новым куратором", body = GroupResponse),
+        (status = 400, description = "Куратор не учитель", body = ErrorResponse),
+        (status = 404, description = "Группа не найдена", body = ErrorResponse),
+    )
+)]
+pub async fn reassign_group_curator(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    Path(group_id): Path<String>,
+    Json(req): Json<ReassignCuratorRequest>,
+) -> Result<impl IntoResponse, ErrorResponse> {
+    let group_service = GroupService::new(state.mongo.clone());
+    let change = group_service
+        .reassign_curator(&group_id, req.curator_id.as_deref())
+        .await
+        .map_err(group_error)?;
+
+    if change.previous != change.current {
+        let audit_service = AuditService::new(state.mongo.clone());
+        if let Some(previous) = &change.previous {
+            let _ = audit_service
+                .log_curator_change(&claims.sub, previous, &group_id, false)
+                .await;
+        }
+        if let Some(current) = &change.current {
+            let _ = audit_service
+                .log_curator_change(&claims.sub, current, &group_id, true)
+                .await;
+        }
+    }
+
+    Ok(Json(change.group))
+}
+
+/// POST /admin/groups/:id/invite-codes - Создать код приглашения в группу
+pub async fn create_invite_code(
     State(state): State<Arc<AppState>>,
-) -> Result<Response, (StatusCode, String)> {
+    Extension(claims): Extension<JwtClaims>,
+    Path(group_id): Path<String>,
+    body: Option<Json<CreateInviteCodeRequest>>,
+) -> Result<impl IntoResponse, ErrorResponse> {
+    let req = body.map(|Json(req)| req).unwrap_or_default();
+    req.validate()
+        .map_err(|errors| ErrorResponse::validation(&errors))?;
+
+    let invite_service = GroupInviteService::new(state.mongo.clone());
+    let invite = invite_service
+        .create_code(&group_id, req, &claims.sub)
+        .await
+        .map_err(group_error)?;
+
+    let audit_service = AuditService::new(state.mongo.clone());
+    let _ = audit_service
+        .log_group_invite_create(&claims.sub, &group_id, &invite.code)
+        .await;
+
+    Ok((StatusCode::CREATED, Json(invite)))
+}
+
+/// GET /admin/groups/:id/invite-codes - Действующие коды приглашения
+pub async fn list_invite_codes(
+    State(state): State<Arc<AppState>>,
+    Path(group_id): Path<String>,
+) -> Result<impl IntoResponse, ErrorResponse> {
+    let invite_service = GroupInviteService::new(state.mongo.clone());
+    let codes = invite_service
+        .list_active_codes(&group_id)
+        .await
+        .map_err(group_error)?;
+
+    Ok(Json(codes))
+}
+
+/// DELETE /admin/groups/:id/invite-codes/:code_id - Отозвать код приглашения
+pub async fn revoke_invite_code(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    Path((group_id, code_id)): Path<(String, String)>,
+) -> Result<impl IntoResponse, ErrorResponse> {
+    let invite_service = GroupInviteService::new(state.mongo.clone());
+    let code = invite_service
+        .revoke_code(&group_id, &code_id)
+        .await
+        .map_err(group_error)?;
+
+    let audit_service = AuditService::new(state.mongo.clone());
+    let _ = audit_service
+        .log_group_invite_revoke(&claims.sub, &group_id, &code)
+        .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /admin/groups/:id/members - Ученики группы со статистикой
+#[utoipa::path(
+    get,
+    path = "/admin/groups/{id}/members",
+    tag = "admin-groups",
+    params(("id" = String, Path, description = "Id группы")),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Ученики группы со статистикой", body = Vec<StudentSummary>),
+        (status = 404, description = "Группа не найдена", body = ErrorResponse),
+    )
+)]
+pub async fn list_group_members(
+    State(state): State<Arc<AppState>>,
+    Path(group_id): Path<String>,
+) -> Result<impl IntoResponse, ErrorResponse> {
+    let group_service = GroupService::new(state.mongo.clone());
+    let group = group_service
+        .get_group(&group_id)
+        .await
+        .map_err(group_error)?;
+
+    let members = group_student_summaries(&state.mongo, &group.id).await?;
+
+    Ok(Json(members))
+}
+
+/// POST /admin/groups/:id/members - Добавить ученика в группу.
+/// Повторное добавление ничего не меняет и отвечает 200 вместо 201
+#[utoipa::path(
+    post,
+    path = "/admin/groups/{id}/members",
+    tag = "admin-groups",
+    params(("id" = String, Path, description = "Id группы")),
+    request_body = AddGroupMemberRequest,
+    security(("bearer_auth" = [], "csrf_token" = [])),
+    responses(
+        (status = 201, description = "Ученик добавлен", body = GroupResponse),
+        (status = 200, description = "Ученик уже в группе", body = GroupResponse),
+        (status = 400, description = "Пользователь не ученик", body = ErrorResponse),
+        (status = 404, description = "Группа не найдена", body = ErrorResponse),
+        (status = 409, description = "Группа заполнена или архивирована", body = ErrorResponse),
+    )
+)]
+pub async fn add_group_member(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    Path(group_id): Path<String>,
+    Json(req): Json<AddGroupMemberRequest>,
+) -> Result<impl IntoResponse, ErrorResponse> {
+    let group_service = GroupService::new(state.mongo.clone());
+    let added = group_service
+        .add_member(&group_id, &req.user_id)
+        .await
+        .map_err(group_error)?;
+
+    if added {
+        let audit_service = AuditService::new(state.mongo.clone());
+        let _ = audit_service
+            .log_group_member_add(&claims.sub, &group_id, &req.user_id)
+            .await;
+    }
+
+    let group = group_service
+        .get_group(&group_id)
+        .await
+        .map_err(group_error)?;
+    let status = if added {
+        StatusCode::CREATED
+    } else {
+        StatusCode::OK
+    };
+
+    Ok((status, Json(group)))
+}
+
+/// DELETE /admin/groups/:id/members/:user_id - Исключить ученика из группы
+#[utoipa::path(
+    delete,
+    path = "/admin/groups/{id}/members/{user_id}",
+    tag = "admin-groups",
+    params(
+        ("id" = String, Path, description = "Id группы"),
+        ("user_id" = String, Path, description = "Id ученика"),
+    ),
+    security(("bearer_auth" = [], "csrf_token" = [])),
+    responses(
+        (status = 204, description = "Ученик исключён"),
+        (status = 404, description = "Группа не найдена или ученик не в группе", body = ErrorResponse),
+    )
+)]
+pub async fn remove_group_member(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    Path((group_id, user_id)): Path<(String, String)>,
+) -> Result<impl IntoResponse, ErrorResponse> {
+    let group_service = GroupService::new(state.mongo.clone());
+    group_service
+        .remove_member(&group_id, &user_id)
+        .await
+        .map_err(group_error)?;
+
+    let audit_service = AuditService::new(state.mongo.clone());
+    let _ = audit_service
+        .log_group_member_remove(&claims.sub, &group_id, &user_id)
+        .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub(crate) fn group_error(err: anyhow::Error) -> ErrorResponse {
+    if let Some(invite) = err.downcast_ref::<InviteCodeError>() {
+        let message = invite.to_string();
+        return match invite {
+            InviteCodeError::NotFound => ErrorResponse::not_found("INVITE_CODE_NOT_FOUND", message),
+            InviteCodeError::Revoked => {
+                ErrorResponse::new(StatusCode::GONE, "INVITE_CODE_REVOKED", message)
+            }
+            InviteCodeError::Expired => {
+                ErrorResponse::new(StatusCode::GONE, "INVITE_CODE_EXPIRED", message)
+            }
+            InviteCodeError::Exhausted => {
+                ErrorResponse::new(StatusCode::CONFLICT, "INVITE_CODE_EXHAUSTED", message)
+            }
+            InviteCodeError::GroupArchived => {
+                ErrorResponse::new(StatusCode::CONFLICT, "GROUP_ARCHIVED", message)
+            }
+        };
+    }
+
+    if let Some(membership) = err.downcast_ref::<GroupMembershipError>() {
+        let message = membership.to_string();
+        return match membership {
+            GroupMembershipError::Full { max_students } => {
+                ErrorResponse::new(StatusCode::CONFLICT, "GROUP_FULL", message)
+                    .with_details(json!({ "max_students": max_students }))
+            }
+            GroupMembershipError::NotStudent => {
+                ErrorResponse::bad_request("NOT_A_STUDENT", message)
+            }
+            GroupMembershipError::NotMember => {
+                ErrorResponse::not_found("GROUP_MEMBER_NOT_FOUND", message)
+            }
+        };
+    }
+
+    let message = err.to_string();
+    if message.contains("Group not found") {
+        ErrorResponse::not_found("GROUP_NOT_FOUND", message)
+    } else if message.contains("User not found") {
+        ErrorResponse::not_found("USER_NOT_FOUND", message)
+    } else if message.contains("Curator not found") {
+        ErrorResponse::not_found("CURATOR_NOT_FOUND", message)
+    } else if message.contains("teacher role") {
+        ErrorResponse::bad_request("INVALID_CURATOR", message)
+    } else if message.contains("Invalid") {
+        ErrorResponse::bad_request("INVALID_ID", message)
+    } else {
+        tracing::error!("Group membership request failed: {:#}", err);
+        ErrorResponse::internal(message)
+    }
+}
+
+/// GET /admin/groups/export - Экспорт всех групп в CSV
+pub async fn export_groups(State(state): State<Arc<AppState>>) -> Result<Response, ErrorResponse> {
     let group_service = GroupService::new(state.mongo.clone());
     let groups = group_service
         .export_groups()
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(|e| ErrorResponse::internal(e.to_string()))?;
 
     let mut csv = String::from("id,name,school,curator_name,student_count,created_at\n");
 
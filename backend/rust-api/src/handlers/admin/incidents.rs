@@ -4,26 +4,34 @@ use axum::{
     response::IntoResponse,
     Json,
 };
+use serde_json::json;
 use std::sync::Arc;
 
 use crate::{
+    handlers::error::ErrorResponse,
     middlewares::auth::JwtClaims,
-    models::anticheat::{ListIncidentsQuery, UpdateIncidentRequest},
+    models::anticheat::{
+        AssignIncidentRequest, CreateIncidentCommentRequest, IncidentTimelineQuery,
+        IncidentTimelineResponse, ListIncidentsQuery, UpdateIncidentRequest,
+    },
     services::{
-        audit_service::AuditService, incidents_service::IncidentsService,
-        user_management_service::UserManagementService, AppState,
+        anticheat_service::AnticheatService,
+        audit_service::AuditService,
+        incidents_service::{IncidentWorkflowError, IncidentsService},
+        user_management_service::UserManagementService,
+        AppState,
     },
 };
 
 pub async fn list_incidents(
     State(state): State<Arc<AppState>>,
     Query(query): Query<ListIncidentsQuery>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, ErrorResponse> {
     let service = IncidentsService::new(state.mongo.clone());
     let incidents = service
         .list_incidents(query)
         .await
-        .map_err(internal_error)?;
+        .map_err(incident_error)?;
 
     Ok(Json(incidents))
 }
@@ -31,51 +39,162 @@ pub async fn list_incidents(
 pub async fn get_incident(
     State(state): State<Arc<AppState>>,
     Path(incident_id): Path<String>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, ErrorResponse> {
     let service = IncidentsService::new(state.mongo.clone());
     let incident = service
         .get_incident(&incident_id)
         .await
-        .map_err(not_found_or_internal)?;
+        .map_err(incident_error)?;
 
     Ok(Json(incident))
 }
 
+/// Допустимый порог паузы для хронологии, секунды
+const MIN_INACTIVITY_SECONDS: u32 = 1;
+const MAX_INACTIVITY_SECONDS: u32 = 86_400;
+
+/// GET /admin/incidents/:id/timeline - Хронология сессии, в которой открыт инцидент
+pub async fn get_incident_timeline(
+    State(state): State<Arc<AppState>>,
+    Path(incident_id): Path<String>,
+    Query(query): Query<IncidentTimelineQuery>,
+) -> Result<Json<IncidentTimelineResponse>, ErrorResponse> {
+    if !(MIN_INACTIVITY_SECONDS..=MAX_INACTIVITY_SECONDS).contains(&query.inactivity_seconds) {
+        return Err(ErrorResponse::bad_request(
+            "INVALID_INACTIVITY_THRESHOLD",
+            format!(
+                "inactivity_seconds must be between {} and {}",
+                MIN_INACTIVITY_SECONDS, MAX_INACTIVITY_SECONDS
+            ),
+        ));
+    }
+
+    let incident = IncidentsService::new(state.mongo.clone())
+        .get_incident(&incident_id)
+        .await
+        .map_err(incident_error)?
+        .incident;
+    let session_id = incident.session_id.ok_or_else(|| {
+        ErrorResponse::not_found(
+            "INCIDENT_SESSION_NOT_FOUND",
+            "Incident is not linked to a session",
+        )
+    })?;
+
+    let timeline = AnticheatService::new(state.mongo.clone(), state.redis.clone())
+        .session_timeline(
+            &session_id,
+            chrono::Duration::seconds(i64::from(query.inactivity_seconds)),
+        )
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to build incident timeline: {:#}", e);
+            ErrorResponse::internal(e.to_string())
+        })?;
+
+    Ok(Json(IncidentTimelineResponse {
+        incident_id: incident.id,
+        session_id,
+        user_id: incident.user_id,
+        started_at: timeline.started_at,
+        inactivity_seconds: query.inactivity_seconds,
+        truncated: timeline.truncated,
+        events: timeline.events,
+    }))
+}
+
+/// PUT /admin/incidents/:id - Перевести инцидент в другой статус
 pub async fn update_incident(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
     Path(incident_id): Path<String>,
     Json(payload): Json<UpdateIncidentRequest>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, ErrorResponse> {
+    let to = payload.target_state().ok_or_else(|| {
+        ErrorResponse::bad_request("MISSING_INCIDENT_STATE", "Field 'state' is required")
+    })?;
+
+    let service = IncidentsService::new(state.mongo.clone());
+    let (updated, from) = service
+        .transition_incident(&incident_id, to, payload.note, payload.force, &claims.sub)
+        .await
+        .map_err(incident_error)?;
+
+    let audit_service = AuditService::new(state.mongo.clone());
+    let _ = audit_service
+        .log_incident_update(&claims.sub, &incident_id, from, to, payload.force)
+        .await;
+
+    Ok(Json(updated))
+}
+
+/// POST /admin/incidents/:id/assign - Назначить ответственного администратора
+pub async fn assign_incident(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    Path(incident_id): Path<String>,
+    Json(payload): Json<AssignIncidentRequest>,
+) -> Result<impl IntoResponse, ErrorResponse> {
     let service = IncidentsService::new(state.mongo.clone());
     let updated = service
-        .update_incident_status(&incident_id, payload.action, payload.note, &claims.sub)
+        .assign_incident(&incident_id, payload.assigned_to.clone())
         .await
-        .map_err(not_found_or_internal)?;
+        .map_err(incident_error)?;
+
+    let audit_service = AuditService::new(state.mongo.clone());
+    let _ = audit_service
+        .log_incident_assign(&claims.sub, &incident_id, payload.assigned_to.as_deref())
+        .await;
 
     Ok(Json(updated))
 }
 
+/// POST /admin/incidents/:id/comments - Добавить комментарий или ответ на комментарий
+pub async fn add_incident_comment(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    Path(incident_id): Path<String>,
+    Json(payload): Json<CreateIncidentCommentRequest>,
+) -> Result<impl IntoResponse, ErrorResponse> {
+    let service = IncidentsService::new(state.mongo.clone());
+    let comment = service
+        .add_comment(&incident_id, payload, &claims.sub)
+        .await
+        .map_err(incident_error)?;
+
+    let audit_service = AuditService::new(state.mongo.clone());
+    let _ = audit_service
+        .log_incident_comment(&claims.sub, &incident_id, &comment.id)
+        .await;
+
+    Ok((StatusCode::CREATED, Json(comment)))
+}
+
+/// POST /admin/incidents/:id/unblock - Снять блокировку пользователя и отметить это в инциденте
 pub async fn unblock_incident_user(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
     Path(incident_id): Path<String>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, ErrorResponse> {
     let incidents_service = IncidentsService::new(state.mongo.clone());
     let incident = incidents_service
         .get_incident(&incident_id)
         .await
-        .map_err(not_found_or_internal)?;
+        .map_err(incident_error)?;
     let user_id = incident.incident.user_id.clone();
 
     let user_service = UserManagementService::new(state.mongo.clone(), state.redis.clone());
     let unblocked_user = user_service.unblock_user(&user_id).await.map_err(|e| {
         if e.to_string().contains("not found") {
-            (StatusCode::NOT_FOUND, e.to_string())
+            ErrorResponse::not_found("USER_NOT_FOUND", e.to_string())
         } else {
-            (StatusCode::BAD_REQUEST, e.to_string())
+            ErrorResponse::bad_request("UNBLOCK_FAILED", e.to_string())
         }
     })?;
+    incidents_service
+        .mark_unblocked(&incident_id, &claims.sub)
+        .await
+        .map_err(incident_error)?;
 
     let audit_service = AuditService::new(state.mongo.clone());
     let _ = audit_service
@@ -85,14 +204,37 @@ pub async fn unblock_incident_user(
     Ok(Json(unblocked_user))
 }
 
-fn internal_error(err: anyhow::Error) -> (StatusCode, String) {
-    (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
-}
+fn incident_error(err: anyhow::Error) -> ErrorResponse {
+    if let Some(workflow) = err.downcast_ref::<IncidentWorkflowError>() {
+        let message = workflow.to_string();
+        return match workflow {
+            IncidentWorkflowError::InvalidTransition { from, to } => {
+                ErrorResponse::new(StatusCode::CONFLICT, "INVALID_INCIDENT_TRANSITION", message)
+                    .with_details(json!({ "from": from, "to": to }))
+            }
+            IncidentWorkflowError::Unassigned => {
+                ErrorResponse::new(StatusCode::CONFLICT, "INCIDENT_UNASSIGNED", message)
+            }
+            IncidentWorkflowError::Closed => {
+                ErrorResponse::new(StatusCode::CONFLICT, "INCIDENT_CLOSED", message)
+            }
+            IncidentWorkflowError::InvalidAssignee => {
+                ErrorResponse::bad_request("INVALID_ASSIGNEE", message)
+            }
+            IncidentWorkflowError::InvalidComment => {
+                ErrorResponse::bad_request("INVALID_COMMENT", message)
+            }
+            IncidentWorkflowError::InvalidParentComment => {
+                ErrorResponse::bad_request("INVALID_PARENT_COMMENT", message)
+            }
+        };
+    }
 
-fn not_found_or_internal(err: anyhow::Error) -> (StatusCode, String) {
-    if err.to_string().to_lowercase().contains("not found") {
-        (StatusCode::NOT_FOUND, err.to_string())
+    let message = err.to_string();
+    if message.to_lowercase().contains("not found") {
+        ErrorResponse::not_found("INCIDENT_NOT_FOUND", message)
     } else {
-        (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+        tracing::error!("Incident request failed: {:#}", err);
+        ErrorResponse::internal(message)
     }
 }
@@ -0,0 +1,73 @@
+use axum::{
+    extract::{Extension, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use std::sync::Arc;
+
+use crate::{
+    extractors::{parse_object_id, AppJson, ObjectIdParam},
+    handlers::error::ErrorResponse,
+    middlewares::auth::JwtClaims,
+    models::maintenance::{MaintenanceJobResponse, RecomputeProgressRequest},
+    services::{
+        maintenance_service::{MaintenanceService, RecomputeOutcome},
+        AppState,
+    },
+};
+
+use super::ApiError;
+
+/// POST /admin/maintenance/recompute-progress - Пересчитать progress_summary_v2 по ответам
+/// (ученик, группа или все) в фоне; отчёт о расхождениях - в `GET /admin/maintenance/jobs/{id}`
+pub async fn recompute_progress(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    AppJson(mut payload): AppJson<RecomputeProgressRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    if payload.user_id.is_some() && payload.group_id.is_some() {
+        return Err(ApiError::bad_request(
+            "INVALID_SCOPE",
+            "Specify either user_id or group_id, not both",
+        ));
+    }
+    if let Some(user_id) = payload.user_id.take() {
+        payload.user_id = Some(parse_object_id(&user_id, "user_id")?.to_hex());
+    }
+    if let Some(group_id) = payload.group_id.take() {
+        payload.group_id = Some(parse_object_id(&group_id, "group_id")?.to_hex());
+    }
+
+    let service = MaintenanceService::new(state.mongo.clone(), state.redis.clone());
+    match service
+        .start_progress_recompute(payload, &claims.sub)
+        .await?
+    {
+        RecomputeOutcome::Started(job) => Ok((
+            StatusCode::ACCEPTED,
+            Json(MaintenanceJobResponse::from(*job)),
+        )),
+        RecomputeOutcome::AlreadyRunning => Err(ErrorResponse::new(
+            StatusCode::CONFLICT,
+            "RECOMPUTE_IN_PROGRESS",
+            "Progress recomputation is already running",
+        )
+        .into()),
+        RecomputeOutcome::GroupNotFound => {
+            Err(ApiError::not_found("GROUP_NOT_FOUND", "Group not found"))
+        }
+    }
+}
+
+/// GET /admin/maintenance/jobs/{id} - Статус служебной задачи и отчёт о расхождениях
+pub async fn get_maintenance_job(
+    State(state): State<Arc<AppState>>,
+    ObjectIdParam(job_id): ObjectIdParam,
+) -> Result<Json<MaintenanceJobResponse>, ApiError> {
+    let job = MaintenanceService::new(state.mongo.clone(), state.redis.clone())
+        .get_job(job_id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("JOB_NOT_FOUND", "Maintenance job not found"))?;
+    Ok(Json(job.into()))
+}
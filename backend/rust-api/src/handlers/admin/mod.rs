@@ -1,17 +1,27 @@
+mod archive;
 mod audit;
 mod backups;
+mod consents;
 mod feature_flags;
 mod groups;
 mod incidents;
+mod maintenance;
+mod rate_limits;
+mod roles;
 mod settings;
 mod system;
 mod users;
 
+pub use archive::*;
 pub use audit::*;
 pub use backups::*;
+pub use consents::*;
 pub use feature_flags::*;
 pub use groups::*;
 pub use incidents::*;
+pub use maintenance::*;
+pub use rate_limits::*;
+pub use roles::*;
 pub use settings::*;
 pub use system::*;
 pub use users::*;
@@ -22,28 +32,58 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
-use mongodb::bson::oid::ObjectId;
+use std::str::FromStr;
 use std::sync::Arc;
 
 use crate::{
-    extractors::AppJson,
+    extractors::{parse_object_id, AppJson, ObjectIdParam, ObjectIdParams},
+    handlers::error::ErrorResponse,
     middlewares::auth::JwtClaims,
     models::content::{
-        EmbeddingConsistencyReport, EmbeddingJobSummary, EmbeddingRebuildRequest,
-        LevelCreateRequest, LevelRecord, LevelReorderRequest, LevelSummary, LevelUpdateRequest,
-        QueueStatus, RuleCoverage, RuleCreateRequest, RuleRecord, RuleSummary, RuleUpdateRequest,
-        TemplateCreateRequest, TemplateDetail, TemplateDuplicate, TemplateEnrichmentRequest,
-        TemplateEnrichmentRunSummary, TemplateEnrichmentTaskView, TemplateListQuery,
-        TemplateRevertRequest, TemplateSummary, TemplateUpdateRequest, TemplateValidationIssue,
+        BlacklistEntryRequest, BlacklistEntryView, ContentTreeQuery, ContentTreeTopic,
+        EmbeddingConsistencyReport, EmbeddingJobCancelOutcome, EmbeddingJobListQuery,
+        EmbeddingJobListResponse, EmbeddingJobRetryOutcome, EmbeddingJobSummary,
+        EmbeddingRebuildRequest, LevelCreateRequest, LevelRecord, LevelReorderRequest,
+        LevelSummary, LevelUpdateRequest, ModerationQueueItem, ModerationQueueQuery,
+        QueueClaimResult, QueueStatus, QueueStatusQuery, ReviewerAssignOutcome,
+        ReviewerAssignRequest, RuleCoverage, RuleCoverageQuery, RuleCreateRequest,
+        RuleMergeOutcome, RuleMergeResult, RuleRecord, RuleSummary, RuleUpdateRequest,
+        TemplateArchiveOutcome, TemplateBulkStatusRequest, TemplateBulkStatusResult,
+        TemplateBundle, TemplateCreateRequest, TemplateDetail, TemplateDuplicate,
+        TemplateEnrichmentRequest, TemplateEnrichmentRunSummary, TemplateEnrichmentTaskView,
+        TemplateExportQuery, TemplateImportOptions, TemplateImportReport, TemplateLintReport,
+        TemplateListQuery, TemplatePublishOutcome, TemplateRevertRequest, TemplateStatus,
+        TemplateSummary, TemplateUpdateRequest, TemplateValidationIssue, TemplateVersionDiff,
         TemplateVersionSummary, TopicCreateRequest, TopicRecord, TopicSummary, TopicUpdateRequest,
+        TEMPLATE_BUNDLE_FORMAT,
     },
+    models::content_search::{ContentSearchQuery, ContentSearchResponse},
     services::{
-        content_service::ContentService, template_enrichment_service::TemplateEnrichmentService,
+        content_blacklist::InvalidBlacklistEntryError,
+        content_search_service::ContentSearchService,
+        content_service::{
+            BlacklistViolationError, ContentService, DuplicateApprovalError, InvalidAnswersError,
+            InvalidLocaleError, JsonTooDeepError, LevelPrerequisiteError, PiiDetectedError,
+            TemplateLintError, TemplatesNotFoundError,
+        },
+        template_enrichment_service::TemplateEnrichmentService,
         AppState,
     },
 };
 use serde::Deserialize;
+use serde_json::json;
+use validator::ValidationErrors;
 
+#[utoipa::path(
+    get,
+    path = "/admin/templates",
+    tag = "admin-templates",
+    params(TemplateListQuery),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Шаблоны по фильтрам", body = Vec<TemplateSummary>),
+    )
+)]
 pub async fn list_templates(
     State(state): State<Arc<AppState>>,
     Query(query): Query<TemplateListQuery>,
@@ -55,14 +95,13 @@ pub async fn list_templates(
 
 pub async fn get_template(
     State(state): State<Arc<AppState>>,
-    Path(template_id): Path<String>,
+    ObjectIdParam(template_obj): ObjectIdParam,
 ) -> Result<Json<TemplateDetail>, ApiError> {
     let service = ContentService::new(&state);
-    let template_obj = parse_object_id(&template_id, "template_id")?;
     let detail = service
         .get_template(&template_obj)
         .await?
-        .ok_or_else(|| ApiError::not_found("Template not found"))?;
+        .ok_or_else(|| ApiError::not_found("TEMPLATE_NOT_FOUND", "Template not found"))?;
     Ok(Json(detail))
 }
 
@@ -76,7 +115,7 @@ pub async fn create_template(
         Ok(summary) => Ok(Json(summary)),
         Err(e) => {
             tracing::error!("Failed to create template: {:?}", e);
-            Err(ApiError::Internal(e.to_string()))
+            Err(template_error(e))
         }
     }
 }
@@ -84,17 +123,156 @@ pub async fn create_template(
 pub async fn update_template(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
-    Path(template_id): Path<String>,
+    ObjectIdParam(template_obj): ObjectIdParam,
     AppJson(payload): AppJson<TemplateUpdateRequest>,
 ) -> Result<Json<TemplateSummary>, ApiError> {
     let service = ContentService::new(&state);
-    let template_obj = parse_object_id(&template_id, "template_id")?;
     let summary = service
         .update_template(&template_obj, payload, &claims)
-        .await?;
+        .await
+        .map_err(template_error)?;
     Ok(Json(summary))
 }
 
+fn template_error(err: anyhow::Error) -> ApiError {
+    if let Some(too_deep) = err.downcast_ref::<JsonTooDeepError>() {
+        return ApiError::bad_request("JSON_TOO_DEEP", too_deep.to_string());
+    }
+    if let Some(invalid) = err.downcast_ref::<InvalidLocaleError>() {
+        return ApiError::bad_request("INVALID_LOCALE", invalid.to_string());
+    }
+    if let Some(detected) = err.downcast_ref::<PiiDetectedError>() {
+        return ApiError::bad_request("PII_DETECTED", detected.to_string());
+    }
+    if let Some(violation) = err.downcast_ref::<BlacklistViolationError>() {
+        return ApiError::bad_request("BLACKLIST_VIOLATION", violation.to_string());
+    }
+    if let Some(lint) = err.downcast_ref::<TemplateLintError>() {
+        return ApiError::bad_request("LINT_ERRORS", lint.to_string());
+    }
+    match err.downcast_ref::<InvalidAnswersError>() {
+        Some(invalid) => ApiError::bad_request("INVALID_ANSWERS", invalid.to_string()),
+        None => err.into(),
+    }
+}
+
+/// POST /admin/templates/bulk-status - Массовая публикация/депрекация шаблонов
+pub async fn bulk_update_template_status(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    AppJson(payload): AppJson<TemplateBulkStatusRequest>,
+) -> Result<Json<TemplateBulkStatusResult>, ApiError> {
+    if payload.template_ids.is_empty() {
+        return Err(ApiError::bad_request(
+            "INVALID_TEMPLATE_IDS",
+            "template_ids cannot be empty",
+        ));
+    }
+    let status = payload
+        .status
+        .parse::<TemplateStatus>()
+        .map_err(|message| ApiError::bad_request("INVALID_STATUS", message))?;
+    // Ревью массово не проходится: только черновик, публикация (из ready) и депрекация
+    if !status.is_bulk_target() {
+        return Err(ApiError::bad_request(
+            "INVALID_STATUS",
+            format!(
+                "Bulk status change supports only draft, published and deprecated, got {}",
+                status.as_str()
+            ),
+        ));
+    }
+
+    let service = ContentService::new(&state);
+    let result = service
+        .bulk_update_template_status(payload.template_ids, status, payload.reason, &claims)
+        .await?;
+    Ok(Json(result))
+}
+
+/// GET /admin/templates/export?ids=a,b - Пакет шаблонов со связанными темами, уровнями и правилами
+pub async fn export_templates(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<TemplateExportQuery>,
+) -> Result<Json<TemplateBundle>, ApiError> {
+    let mut template_ids = Vec::new();
+    for id in query.ids.split(',').filter(|id| !id.trim().is_empty()) {
+        let template_obj = parse_object_id(id, "ids")?;
+        if !template_ids.contains(&template_obj) {
+            template_ids.push(template_obj);
+        }
+    }
+    if template_ids.is_empty() {
+        return Err(ApiError::bad_request(
+            "INVALID_TEMPLATE_IDS",
+            "ids cannot be empty",
+        ));
+    }
+
+    let service = ContentService::new(&state);
+    let bundle = service
+        .export_templates(&template_ids)
+        .await
+        .map_err(|err| match err.downcast_ref::<TemplatesNotFoundError>() {
+            Some(missing) => ApiError::not_found("TEMPLATE_NOT_FOUND", missing.to_string()),
+            None => err.into(),
+        })?;
+    Ok(Json(bundle))
+}
+
+/// POST /admin/templates/import?create_missing=&overwrite= - Импорт пакета шаблонов в статусе draft
+pub async fn import_templates(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    Query(options): Query<TemplateImportOptions>,
+    AppJson(bundle): AppJson<TemplateBundle>,
+) -> Result<Json<TemplateImportReport>, ApiError> {
+    if bundle.format != TEMPLATE_BUNDLE_FORMAT {
+        return Err(ApiError::bad_request(
+            "UNSUPPORTED_BUNDLE_FORMAT",
+            format!("Unsupported bundle format: {}", bundle.format),
+        ));
+    }
+
+    let service = ContentService::new(&state);
+    let report = service.import_templates(bundle, options, &claims).await?;
+    Ok(Json(report))
+}
+
+/// GET /admin/content/tree - Темы, уровни и счётчики шаблонов для навигации
+pub async fn content_tree(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ContentTreeQuery>,
+) -> Result<Json<Vec<ContentTreeTopic>>, ApiError> {
+    let service = ContentService::new(&state);
+    let tree = service.content_tree(query).await?;
+    Ok(Json(tree))
+}
+
+/// GET /admin/content/search?q=... - Полнотекстовый поиск по шаблонам, правилам и темам
+pub async fn search_content(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ContentSearchQuery>,
+) -> Result<Json<ContentSearchResponse>, ApiError> {
+    let q = query.q.trim();
+    if q.is_empty() {
+        return Err(ApiError::bad_request(
+            "INVALID_QUERY",
+            "Search query cannot be empty",
+        ));
+    }
+    if q.chars().count() > 200 {
+        return Err(ApiError::bad_request(
+            "INVALID_QUERY",
+            "Search query is too long (max 200 characters)",
+        ));
+    }
+
+    let service = ContentSearchService::new(state.mongo.clone());
+    let results = service.search(q, query.limit).await?;
+    Ok(Json(results))
+}
+
 pub async fn list_topics(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Vec<TopicSummary>>, ApiError> {
@@ -117,11 +295,10 @@ pub async fn create_topic(
 pub async fn update_topic(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
-    Path(topic_id): Path<String>,
+    ObjectIdParam(topic_obj): ObjectIdParam,
     Json(payload): Json<TopicUpdateRequest>,
 ) -> Result<Json<TopicRecord>, ApiError> {
     let service = ContentService::new(&state);
-    let topic_obj = parse_object_id(&topic_id, "topic_id")?;
     let topic = service.update_topic(&topic_obj, payload, &claims).await?;
     Ok(Json(topic))
 }
@@ -129,20 +306,18 @@ pub async fn update_topic(
 pub async fn delete_topic(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
-    Path(topic_id): Path<String>,
+    ObjectIdParam(topic_obj): ObjectIdParam,
 ) -> Result<Json<()>, ApiError> {
     let service = ContentService::new(&state);
-    let topic_obj = parse_object_id(&topic_id, "topic_id")?;
     service.delete_topic(&topic_obj, &claims).await?;
     Ok(Json(()))
 }
 
 pub async fn list_levels(
     State(state): State<Arc<AppState>>,
-    Path(topic_id): Path<String>,
+    ObjectIdParam(topic_obj): ObjectIdParam,
 ) -> Result<Json<Vec<LevelSummary>>, ApiError> {
     let service = ContentService::new(&state);
-    let topic_obj = parse_object_id(&topic_id, "topic_id")?;
     let levels = service.list_levels_for_topic(&topic_obj).await?;
     let summaries: Vec<LevelSummary> = levels.iter().map(LevelSummary::from_level).collect();
     Ok(Json(summaries))
@@ -154,29 +329,42 @@ pub async fn create_level(
     AppJson(payload): AppJson<LevelCreateRequest>,
 ) -> Result<Json<LevelRecord>, ApiError> {
     let service = ContentService::new(&state);
-    let level = service.create_level(payload, &claims).await?;
+    let level = service
+        .create_level(payload, &claims)
+        .await
+        .map_err(level_error)?;
     Ok(Json(level))
 }
 
 pub async fn update_level(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
-    Path(level_id): Path<String>,
+    ObjectIdParam(level_obj): ObjectIdParam,
     Json(payload): Json<LevelUpdateRequest>,
 ) -> Result<Json<LevelRecord>, ApiError> {
     let service = ContentService::new(&state);
-    let level_obj = parse_object_id(&level_id, "level_id")?;
-    let level = service.update_level(&level_obj, payload, &claims).await?;
+    let level = service
+        .update_level(&level_obj, payload, &claims)
+        .await
+        .map_err(level_error)?;
     Ok(Json(level))
 }
 
+fn level_error(err: anyhow::Error) -> ApiError {
+    match err.downcast_ref::<LevelPrerequisiteError>() {
+        Some(prerequisite_error) => {
+            ApiError::bad_request("INVALID_PREREQUISITES", prerequisite_error.to_string())
+        }
+        None => err.into(),
+    }
+}
+
 pub async fn delete_level(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
-    Path(level_id): Path<String>,
+    ObjectIdParam(level_obj): ObjectIdParam,
 ) -> Result<Json<()>, ApiError> {
     let service = ContentService::new(&state);
-    let level_obj = parse_object_id(&level_id, "level_id")?;
     service.delete_level(&level_obj, &claims).await?;
     Ok(Json(()))
 }
@@ -212,11 +400,10 @@ pub async fn create_rule(
 pub async fn update_rule(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
-    Path(rule_id): Path<String>,
+    ObjectIdParam(rule_obj): ObjectIdParam,
     Json(payload): Json<RuleUpdateRequest>,
 ) -> Result<Json<RuleRecord>, ApiError> {
     let service = ContentService::new(&state);
-    let rule_obj = parse_object_id(&rule_id, "rule_id")?;
     let rule = service.update_rule(&rule_obj, payload, &claims).await?;
     Ok(Json(rule))
 }
@@ -224,30 +411,137 @@ pub async fn update_rule(
 pub async fn delete_rule(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
-    Path(rule_id): Path<String>,
+    ObjectIdParam(rule_obj): ObjectIdParam,
 ) -> Result<Json<()>, ApiError> {
     let service = ContentService::new(&state);
-    let rule_obj = parse_object_id(&rule_id, "rule_id")?;
     service.delete_rule(&rule_obj, &claims).await?;
     Ok(Json(()))
 }
 
+/// GET /admin/content/blacklist - Стоп-лист содержимого шаблонов
+pub async fn list_blacklist(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<BlacklistEntryView>>, ApiError> {
+    let service = ContentService::new(&state);
+    let entries = service.list_blacklist().await?;
+    Ok(Json(entries.iter().map(BlacklistEntryView::from).collect()))
+}
+
+/// POST /admin/content/blacklist - Добавить термин или выражение
+pub async fn create_blacklist_entry(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    AppJson(payload): AppJson<BlacklistEntryRequest>,
+) -> Result<Json<BlacklistEntryView>, ApiError> {
+    let service = ContentService::new(&state);
+    let entry = service
+        .create_blacklist_entry(payload, &claims)
+        .await
+        .map_err(blacklist_error)?;
+    Ok(Json((&entry).into()))
+}
+
+/// PUT /admin/content/blacklist/{id} - Изменить запись (например, понизить до `warn`)
+pub async fn update_blacklist_entry(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    ObjectIdParam(entry_id): ObjectIdParam,
+    AppJson(payload): AppJson<BlacklistEntryRequest>,
+) -> Result<Json<BlacklistEntryView>, ApiError> {
+    let service = ContentService::new(&state);
+    match service
+        .update_blacklist_entry(&entry_id, payload, &claims)
+        .await
+        .map_err(blacklist_error)?
+    {
+        Some(entry) => Ok(Json((&entry).into())),
+        None => Err(blacklist_entry_not_found()),
+    }
+}
+
+/// DELETE /admin/content/blacklist/{id}
+pub async fn delete_blacklist_entry(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    ObjectIdParam(entry_id): ObjectIdParam,
+) -> Result<StatusCode, ApiError> {
+    let service = ContentService::new(&state);
+    if service.delete_blacklist_entry(&entry_id, &claims).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(blacklist_entry_not_found())
+    }
+}
+
+fn blacklist_error(err: anyhow::Error) -> ApiError {
+    match err.downcast_ref::<InvalidBlacklistEntryError>() {
+        Some(invalid) => ApiError::bad_request("INVALID_BLACKLIST_ENTRY", invalid.to_string()),
+        None => err.into(),
+    }
+}
+
+fn blacklist_entry_not_found() -> ApiError {
+    ApiError::not_found("BLACKLIST_ENTRY_NOT_FOUND", "Blacklist entry not found")
+}
+
+/// POST /admin/rules/{id}/merge-into/{target_id} - Влить правило-дубликат в другое
+pub async fn merge_rule(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    ObjectIdParams(source_obj, target_obj): ObjectIdParams,
+) -> Result<Json<RuleMergeResult>, ApiError> {
+    let service = ContentService::new(&state);
+    match service
+        .merge_rule(&source_obj, &target_obj, &claims)
+        .await?
+    {
+        RuleMergeOutcome::Merged(result) => Ok(Json(result)),
+        RuleMergeOutcome::SameRule => Err(ApiError::bad_request(
+            "INVALID_MERGE_TARGET",
+            "Rule cannot be merged into itself",
+        )),
+        RuleMergeOutcome::NotFound => Err(ApiError::not_found(
+            "RULE_NOT_FOUND",
+            "Source or target rule not found",
+        )),
+        RuleMergeOutcome::TargetDeprecated => Err(ApiError::bad_request(
+            "INVALID_MERGE_TARGET",
+            "Cannot merge into a deprecated rule",
+        )),
+    }
+}
+
 pub async fn rule_coverage(
     State(state): State<Arc<AppState>>,
+    Query(query): Query<RuleCoverageQuery>,
 ) -> Result<Json<Vec<RuleCoverage>>, ApiError> {
+    let status = query
+        .status
+        .map(|status| {
+            TemplateStatus::from_str(&status).map_err(|_| {
+                ApiError::bad_request(
+                    "INVALID_STATUS",
+                    format!("Invalid status filter: {}", status),
+                )
+            })
+        })
+        .transpose()?;
+    let topic_id = query
+        .topic_id
+        .map(|topic_id| parse_object_id(&topic_id, "topic_id"))
+        .transpose()?;
     let service = ContentService::new(&state);
-    let coverage = service.rule_coverage().await?;
+    let coverage = service.rule_coverage(status, topic_id).await?;
     Ok(Json(coverage))
 }
 
 pub async fn revert_template(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
-    Path(template_id): Path<String>,
+    ObjectIdParam(template_obj): ObjectIdParam,
     Json(payload): Json<TemplateRevertRequest>,
 ) -> Result<Json<TemplateSummary>, ApiError> {
     let service = ContentService::new(&state);
-    let template_obj = parse_object_id(&template_id, "template_id")?;
     let summary = service
         .revert_template(&template_obj, payload, &claims)
         .await?;
@@ -256,59 +550,249 @@ pub async fn revert_template(
 
 pub async fn list_template_versions(
     State(state): State<Arc<AppState>>,
-    Path(template_id): Path<String>,
+    ObjectIdParam(template_obj): ObjectIdParam,
 ) -> Result<Json<Vec<TemplateVersionSummary>>, ApiError> {
     let service = ContentService::new(&state);
-    let template_obj = parse_object_id(&template_id, "template_id")?;
     let versions = service.list_template_versions(&template_obj).await?;
     Ok(Json(versions))
 }
 
+/// GET /admin/templates/{id}/versions/{version}/diff - Что изменилось по сравнению с предыдущей версией
+pub async fn template_version_diff(
+    State(state): State<Arc<AppState>>,
+    Path((template_id, version)): Path<(String, i32)>,
+) -> Result<Json<TemplateVersionDiff>, ApiError> {
+    let template_obj = parse_object_id(&template_id, "id")?;
+    let service = ContentService::new(&state);
+    let diff = service
+        .template_version_diff(&template_obj, version)
+        .await?
+        .ok_or_else(|| {
+            ApiError::not_found("TEMPLATE_VERSION_NOT_FOUND", "Template version not found")
+        })?;
+    Ok(Json(diff))
+}
+
 pub async fn submit_template_for_moderation(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
-    Path(template_id): Path<String>,
+    ObjectIdParam(template_obj): ObjectIdParam,
 ) -> Result<Json<TemplateSummary>, ApiError> {
     let service = ContentService::new(&state);
-    let template_obj = parse_object_id(&template_id, "template_id")?;
     let summary = service
         .submit_template_for_moderation(&template_obj, &claims)
-        .await?;
+        .await
+        .map_err(template_error)?;
     Ok(Json(summary))
 }
 
+/// POST /admin/templates/{id}/lint - Структурные проверки шаблона без сохранения
+pub async fn lint_template(
+    State(state): State<Arc<AppState>>,
+    ObjectIdParam(template_obj): ObjectIdParam,
+) -> Result<Json<TemplateLintReport>, ApiError> {
+    let service = ContentService::new(&state);
+    let report = service
+        .lint_template(&template_obj)
+        .await?
+        .ok_or_else(|| ApiError::not_found("TEMPLATE_NOT_FOUND", "Template not found"))?;
+    Ok(Json(report))
+}
+
 pub async fn approve_template(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
-    Path(template_id): Path<String>,
+    ObjectIdParam(template_obj): ObjectIdParam,
 ) -> Result<Json<TemplateSummary>, ApiError> {
     let service = ContentService::new(&state);
-    let template_obj = parse_object_id(&template_id, "template_id")?;
-    let summary = service.approve_template(&template_obj, &claims).await?;
+    let summary = service
+        .approve_template(&template_obj, &claims)
+        .await
+        .map_err(|err| match err.downcast_ref::<DuplicateApprovalError>() {
+            Some(duplicate) => ErrorResponse::new(
+                StatusCode::CONFLICT,
+                "DUPLICATE_APPROVAL",
+                duplicate.to_string(),
+            )
+            .into(),
+            None => ApiError::from(err),
+        })?;
     Ok(Json(summary))
 }
 
+/// GET /admin/moderation/queue?topic_id=&age=&assigned_reviewer= - Шаблоны на модерации,
+/// дольше всех ждущие первыми
+pub async fn moderation_queue(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ModerationQueueQuery>,
+) -> Result<Json<Vec<ModerationQueueItem>>, ApiError> {
+    let topic_id = query
+        .topic_id
+        .map(|topic_id| parse_object_id(&topic_id, "topic_id"))
+        .transpose()?;
+    let service = ContentService::new(&state);
+    let queue = service
+        .moderation_queue(
+            topic_id,
+            query.age,
+            query.assigned_reviewer.as_deref(),
+            query.limit,
+        )
+        .await?;
+    Ok(Json(queue))
+}
+
+/// POST /admin/templates/{id}/assign-reviewer - Назначить модератора на текущий круг ревью
+pub async fn assign_template_reviewer(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    ObjectIdParam(template_obj): ObjectIdParam,
+    AppJson(payload): AppJson<ReviewerAssignRequest>,
+) -> Result<Json<TemplateSummary>, ApiError> {
+    let reviewer_id = payload.reviewer_id.trim();
+    if reviewer_id.is_empty() {
+        return Err(ApiError::bad_request(
+            "INVALID_REVIEWER",
+            "reviewer_id cannot be empty",
+        ));
+    }
+    let service = ContentService::new(&state);
+    match service
+        .assign_reviewer(&template_obj, reviewer_id, &claims)
+        .await?
+    {
+        ReviewerAssignOutcome::Assigned(summary) => Ok(Json(*summary)),
+        ReviewerAssignOutcome::NotFound => Err(ApiError::not_found(
+            "TEMPLATE_NOT_FOUND",
+            "Template not found",
+        )),
+        ReviewerAssignOutcome::NotInReview(status) => Err(ErrorResponse::new(
+            StatusCode::CONFLICT,
+            "TEMPLATE_NOT_IN_REVIEW",
+            format!("Template is {}, not awaiting review", status.as_str()),
+        )
+        .into()),
+        ReviewerAssignOutcome::AlreadyApproved => Err(ApiError::bad_request(
+            "INVALID_REVIEWER",
+            "Reviewer has already approved this template",
+        )),
+    }
+}
+
+/// POST /admin/templates/{id}/publish - Опубликовать одобренный шаблон
+pub async fn publish_template(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    ObjectIdParam(template_obj): ObjectIdParam,
+) -> Result<Json<TemplateSummary>, ApiError> {
+    let service = ContentService::new(&state);
+    let outcome = service.publish_template(&template_obj, &claims).await?;
+    publish_response(outcome)
+}
+
+/// POST /admin/templates/{id}/rollback-publish/{version} - Вернуть в публикацию
+/// содержимое ранее опубликованной версии (разрешение `content.rollback`)
+pub async fn rollback_template_publish(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    Path((template_id, version)): Path<(String, i32)>,
+) -> Result<Json<TemplateSummary>, ApiError> {
+    let template_obj = parse_object_id(&template_id, "id")?;
+    let service = ContentService::new(&state);
+    let outcome = service
+        .rollback_published_version(&template_obj, version, &claims)
+        .await?;
+    publish_response(outcome)
+}
+
+fn publish_response(outcome: TemplatePublishOutcome) -> Result<Json<TemplateSummary>, ApiError> {
+    match outcome {
+        TemplatePublishOutcome::Published(summary) => Ok(Json(*summary)),
+        TemplatePublishOutcome::NotFound => Err(ApiError::not_found(
+            "TEMPLATE_NOT_FOUND",
+            "Template not found",
+        )),
+        TemplatePublishOutcome::NotReady(status) => Err(ErrorResponse::new(
+            StatusCode::CONFLICT,
+            "TEMPLATE_NOT_READY",
+            format!(
+                "Template is {}, only ready templates can be published",
+                status.as_str()
+            ),
+        )
+        .into()),
+        TemplatePublishOutcome::VersionNotFound => Err(ApiError::not_found(
+            "PUBLISHED_VERSION_NOT_FOUND",
+            "Published version not found",
+        )),
+        TemplatePublishOutcome::Conflict => Err(ErrorResponse::new(
+            StatusCode::CONFLICT,
+            "TEMPLATE_CHANGED",
+            "Template was changed concurrently, reload it and retry",
+        )
+        .into()),
+    }
+}
+
 pub async fn reject_template(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
-    Path(template_id): Path<String>,
+    ObjectIdParam(template_obj): ObjectIdParam,
     Json(payload): Json<TemplateRevertRequest>,
 ) -> Result<Json<TemplateSummary>, ApiError> {
     let service = ContentService::new(&state);
-    let template_obj = parse_object_id(&template_id, "template_id")?;
     let summary = service
         .reject_template(&template_obj, payload, &claims)
         .await?;
     Ok(Json(summary))
 }
 
+/// POST /admin/templates/{id}/archive - Скрыть шаблон из списков;
+/// 409, если шаблон входит в открытое домашнее задание или активную сессию
+pub async fn archive_template(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    ObjectIdParam(template_obj): ObjectIdParam,
+) -> Result<Json<TemplateSummary>, ApiError> {
+    let service = ContentService::new(&state);
+    let outcome = service.archive_template(&template_obj, &claims).await?;
+    archive_response(outcome)
+}
+
+/// POST /admin/templates/{id}/unarchive - Вернуть шаблон в списки
+pub async fn unarchive_template(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    ObjectIdParam(template_obj): ObjectIdParam,
+) -> Result<Json<TemplateSummary>, ApiError> {
+    let service = ContentService::new(&state);
+    let outcome = service.unarchive_template(&template_obj, &claims).await?;
+    archive_response(outcome)
+}
+
+fn archive_response(outcome: TemplateArchiveOutcome) -> Result<Json<TemplateSummary>, ApiError> {
+    match outcome {
+        TemplateArchiveOutcome::Archived(summary) => Ok(Json(*summary)),
+        TemplateArchiveOutcome::NotFound => Err(ApiError::not_found(
+            "TEMPLATE_NOT_FOUND",
+            "Template not found",
+        )),
+        TemplateArchiveOutcome::Blocked(references) => Err(ErrorResponse::new(
+            StatusCode::CONFLICT,
+            "TEMPLATE_IN_USE",
+            "Template is referenced by open assignments or active sessions",
+        )
+        .with_details(json!({ "references": references }))
+        .into()),
+    }
+}
+
 pub async fn start_template_enrichment_run(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
-    Path(template_id): Path<String>,
+    ObjectIdParam(template_obj): ObjectIdParam,
     AppJson(payload): AppJson<TemplateEnrichmentRequest>,
 ) -> Result<Json<TemplateEnrichmentRunSummary>, ApiError> {
-    let template_obj = parse_object_id(&template_id, "template_id")?;
     let service = TemplateEnrichmentService::new(&state);
     let summary = service.start_run(&template_obj, &claims, payload).await?;
     Ok(Json(summary))
@@ -316,10 +800,9 @@ pub async fn start_template_enrichment_run(
 
 pub async fn list_template_enrichment_runs(
     State(state): State<Arc<AppState>>,
-    Path(template_id): Path<String>,
+    ObjectIdParam(template_obj): ObjectIdParam,
     Query(query): Query<TemplateRunsQuery>,
 ) -> Result<Json<Vec<TemplateEnrichmentRunSummary>>, ApiError> {
-    let template_obj = parse_object_id(&template_id, "template_id")?;
     let service = TemplateEnrichmentService::new(&state);
     let limit = query.limit.unwrap_or(25).clamp(1, 100);
     let runs = service.list_runs(&template_obj, limit).await?;
@@ -328,10 +811,9 @@ pub async fn list_template_enrichment_runs(
 
 pub async fn list_template_enrichment_tasks(
     State(state): State<Arc<AppState>>,
-    Path(template_id): Path<String>,
+    ObjectIdParam(template_obj): ObjectIdParam,
     Query(query): Query<TemplateTasksQuery>,
 ) -> Result<Json<Vec<TemplateEnrichmentTaskView>>, ApiError> {
-    let template_obj = parse_object_id(&template_id, "template_id")?;
     let service = TemplateEnrichmentService::new(&state);
     let limit = query.limit.unwrap_or(50).clamp(1, 200);
     let tasks = service
@@ -342,10 +824,8 @@ pub async fn list_template_enrichment_tasks(
 
 pub async fn delete_template_enrichment_task(
     State(state): State<Arc<AppState>>,
-    Path((template_id, task_id)): Path<(String, String)>,
+    ObjectIdParams(template_obj, task_obj): ObjectIdParams,
 ) -> Result<Json<()>, ApiError> {
-    let template_obj = parse_object_id(&template_id, "template_id")?;
-    let task_obj = parse_object_id(&task_id, "task_id")?;
     let service = TemplateEnrichmentService::new(&state);
     service.delete_task(&template_obj, &task_obj).await?;
     Ok(Json(()))
@@ -354,10 +834,8 @@ pub async fn delete_template_enrichment_task(
 pub async fn regenerate_template_enrichment_task(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
-    Path((template_id, task_id)): Path<(String, String)>,
+    ObjectIdParams(template_obj, task_obj): ObjectIdParams,
 ) -> Result<Json<TemplateEnrichmentTaskView>, ApiError> {
-    let template_obj = parse_object_id(&template_id, "template_id")?;
-    let task_obj = parse_object_id(&task_id, "task_id")?;
     let service = TemplateEnrichmentService::new(&state);
     let view = service
         .regenerate_task(&template_obj, &task_obj, &claims)
@@ -399,6 +877,61 @@ pub async fn embedding_progress(
     Ok(Json(summary))
 }
 
+pub async fn list_embedding_jobs(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<EmbeddingJobListQuery>,
+) -> Result<Json<EmbeddingJobListResponse>, ApiError> {
+    let service = ContentService::new(&state);
+    let jobs = service.list_embedding_jobs(query).await?;
+    Ok(Json(jobs))
+}
+
+pub async fn cancel_embedding_job(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    ObjectIdParam(job_obj): ObjectIdParam,
+) -> Result<Json<EmbeddingJobSummary>, ApiError> {
+    let service = ContentService::new(&state);
+    match service.cancel_embedding_job(&job_obj, &claims).await? {
+        EmbeddingJobCancelOutcome::Cancelling(summary) => Ok(Json(*summary)),
+        EmbeddingJobCancelOutcome::NotFound => Err(ApiError::not_found(
+            "EMBEDDING_JOB_NOT_FOUND",
+            "Embedding job not found",
+        )),
+        EmbeddingJobCancelOutcome::NotActive(status) => Err(ErrorResponse::new(
+            StatusCode::CONFLICT,
+            "EMBEDDING_JOB_NOT_ACTIVE",
+            format!("Embedding job is already {}", status),
+        )
+        .into()),
+    }
+}
+
+pub async fn retry_failed_embeddings(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    ObjectIdParam(job_obj): ObjectIdParam,
+) -> Result<Json<EmbeddingJobSummary>, ApiError> {
+    let service = ContentService::new(&state);
+    match service.retry_failed_embeddings(&job_obj, &claims).await? {
+        EmbeddingJobRetryOutcome::Enqueued(summary) => Ok(Json(*summary)),
+        EmbeddingJobRetryOutcome::NotFound => Err(ApiError::not_found(
+            "EMBEDDING_JOB_NOT_FOUND",
+            "Embedding job not found",
+        )),
+        EmbeddingJobRetryOutcome::NotFinished(status) => Err(ErrorResponse::new(
+            StatusCode::CONFLICT,
+            "EMBEDDING_JOB_NOT_FINISHED",
+            format!("Embedding job is still {}", status),
+        )
+        .into()),
+        EmbeddingJobRetryOutcome::NoFailedTemplates => Err(ApiError::bad_request(
+            "NO_FAILED_TEMPLATES",
+            "Embedding job has no failed templates",
+        )),
+    }
+}
+
 pub async fn embedding_consistency(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<EmbeddingConsistencyReport>, ApiError> {
@@ -409,32 +942,59 @@ pub async fn embedding_consistency(
 
 pub async fn queue_status(
     State(state): State<Arc<AppState>>,
+    Query(query): Query<QueueStatusQuery>,
 ) -> Result<Json<QueueStatus>, ApiError> {
     let service = ContentService::new(&state);
-    let status = service.queue_status().await?;
+    let status = service.queue_status(query.idle_secs).await?;
     Ok(Json(status))
 }
 
-fn parse_object_id(value: &str, field: &str) -> Result<ObjectId, ApiError> {
-    ObjectId::parse_str(value)
-        .map_err(|_| ApiError::bad_request(format!("Invalid {}: must be ObjectId", field)))
+/// POST /admin/queue/claim/{entry_id} - Переназначить зависшее событие новому консьюмеру
+pub async fn claim_queue_entry(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    Path(entry_id): Path<String>,
+) -> Result<Json<QueueClaimResult>, ApiError> {
+    if !is_stream_entry_id(&entry_id) {
+        return Err(ApiError::bad_request(
+            "INVALID_ENTRY_ID",
+            "Entry id must look like <ms>-<seq>",
+        ));
+    }
+    let service = ContentService::new(&state);
+    service
+        .claim_queue_entry(&entry_id, &claims)
+        .await?
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found("ENTRY_NOT_PENDING", "Entry is not pending"))
+}
+
+fn is_stream_entry_id(value: &str) -> bool {
+    value.split_once('-').is_some_and(|(ms, seq)| {
+        !ms.is_empty()
+            && !seq.is_empty()
+            && ms.chars().all(|c| c.is_ascii_digit())
+            && seq.chars().all(|c| c.is_ascii_digit())
+    })
 }
 
 #[derive(Debug)]
 pub enum ApiError {
-    BadRequest(String),
+    Response(ErrorResponse),
+    BadRequest(&'static str, String),
     Forbidden(String),
-    NotFound(String),
+    NotFound(&'static str, String),
     Internal(String),
+    Validation(ValidationErrors),
 }
 
 impl ApiError {
-    fn bad_request(message: impl Into<String>) -> Self {
-        ApiError::BadRequest(message.into())
+    fn bad_request(code: &'static str, message: impl Into<String>) -> Self {
+        ApiError::BadRequest(code, message.into())
     }
 
-    fn not_found(message: impl Into<String>) -> Self {
-        ApiError::NotFound(message.into())
+    fn not_found(code: &'static str, message: impl Into<String>) -> Self {
+        ApiError::NotFound(code, message.into())
     }
 }
 
@@ -444,15 +1004,23 @@ impl From<anyhow::Error> for ApiError {
     }
 }
 
+impl From<ErrorResponse> for ApiError {
+    fn from(err: ErrorResponse) -> Self {
+        ApiError::Response(err)
+    }
+}
+
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
-        let (status, message) = match self {
-            ApiError::BadRequest(message) => (StatusCode::BAD_REQUEST, message),
-            ApiError::Forbidden(message) => (StatusCode::FORBIDDEN, message),
-            ApiError::NotFound(message) => (StatusCode::NOT_FOUND, message),
-            ApiError::Internal(message) => (StatusCode::INTERNAL_SERVER_ERROR, message),
+        let error = match self {
+            ApiError::Response(error) => error,
+            ApiError::BadRequest(code, message) => ErrorResponse::bad_request(code, message),
+            ApiError::Forbidden(message) => ErrorResponse::forbidden("FORBIDDEN", message),
+            ApiError::NotFound(code, message) => ErrorResponse::not_found(code, message),
+            ApiError::Internal(message) => ErrorResponse::internal(message),
+            ApiError::Validation(errors) => ErrorResponse::validation(&errors),
         };
-        (status, Json(message)).into_response()
+        error.into_response()
     }
 }
 
@@ -0,0 +1,89 @@
+use axum::{
+    extract::{Extension, Path, Query, State},
+    Json,
+};
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::{
+    handlers::error::ErrorResponse,
+    middlewares::auth::JwtClaims,
+    models::rate_limit::{RateLimitInspection, RateLimitQuery, RateLimitResetResponse},
+    services::{audit_service::AuditService, rate_limit_service::RateLimitService, AppState},
+};
+
+fn validate_key(key: &str) -> Result<String, ErrorResponse> {
+    let key = key.trim();
+    if key.is_empty() || key.len() > 128 {
+        return Err(ErrorResponse::bad_request(
+            "INVALID_RATE_LIMIT_KEY",
+            "Key must be a user id or IP address",
+        )
+        .with_details(json!({ "field": "key" })));
+    }
+    Ok(key.to_string())
+}
+
+fn rate_limit_service(state: &AppState) -> RateLimitService {
+    RateLimitService::new(
+        state.redis.clone(),
+        state.runtime.snapshot().rate_limit.clone(),
+    )
+}
+
+/// GET /admin/rate-limits?key=... - Счётчики лимитеров для пользователя или IP
+pub async fn get_rate_limits(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    Query(query): Query<RateLimitQuery>,
+) -> Result<Json<RateLimitInspection>, ErrorResponse> {
+    let key = validate_key(&query.key)?;
+
+    let counters = rate_limit_service(&state)
+        .inspect(&key)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to inspect rate limits for {}: {:#}", key, e);
+            ErrorResponse::internal("Failed to inspect rate limits")
+        })?;
+
+    let audit_service = AuditService::new(state.mongo.clone());
+    let _ = audit_service
+        .log_rate_limit_inspect(&claims.sub, &key, counters.len())
+        .await;
+
+    Ok(Json(RateLimitInspection {
+        key,
+        algorithm: state.runtime.snapshot().rate_limit.algorithm,
+        counters,
+    }))
+}
+
+/// DELETE /admin/rate-limits/:key - Сбросить счётчики (разблокировать пользователя или IP)
+pub async fn reset_rate_limits(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    Path(key): Path<String>,
+) -> Result<Json<RateLimitResetResponse>, ErrorResponse> {
+    let key = validate_key(&key)?;
+
+    let cleared = rate_limit_service(&state).reset(&key).await.map_err(|e| {
+        tracing::error!("Failed to reset rate limits for {}: {:#}", key, e);
+        ErrorResponse::internal("Failed to reset rate limits")
+    })?;
+
+    let cleared_keys: Vec<String> = cleared.iter().map(|c| c.redis_key.clone()).collect();
+    let audit_service = AuditService::new(state.mongo.clone());
+    let _ = audit_service
+        .log_rate_limit_reset(&claims.sub, &key, &cleared_keys)
+        .await;
+
+    tracing::info!(
+        admin = %claims.sub,
+        key = %key,
+        cleared = cleared_keys.len(),
+        "Rate limit counters reset"
+    );
+
+    Ok(Json(RateLimitResetResponse { key, cleared }))
+}
@@ -0,0 +1,103 @@
+use axum::{
+    extract::{Extension, Path, State},
+    Json,
+};
+use std::sync::Arc;
+
+use crate::{
+    extractors::AppJson,
+    handlers::error::ErrorResponse,
+    middlewares::auth::JwtClaims,
+    models::permission::{RolePermissionsResponse, UpdateRolePermissionsRequest},
+    services::{
+        audit_service::AuditService,
+        permission_service::{PermissionService, RolePermissionsError},
+        AppState,
+    },
+};
+
+/// GET /admin/roles - Роли и их разрешения
+#[utoipa::path(
+    get,
+    path = "/admin/roles",
+    tag = "admin-users",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Встроенные и настроенные роли", body = Vec<RolePermissionsResponse>),
+        (status = 403, description = "Нет разрешения `users.manage`", body = ErrorResponse),
+    )
+)]
+pub async fn list_roles(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<RolePermissionsResponse>>, ErrorResponse> {
+    let roles = PermissionService::new(state.mongo.clone(), &state.role_permissions)
+        .list_roles()
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to list roles: {:#}", e);
+            ErrorResponse::internal("Failed to list roles")
+        })?;
+    Ok(Json(roles))
+}
+
+/// PUT /admin/roles/:role - Задать разрешения роли (в том числе новой)
+#[utoipa::path(
+    put,
+    path = "/admin/roles/{role}",
+    tag = "admin-users",
+    params(("role" = String, Path, description = "Имя роли")),
+    request_body = UpdateRolePermissionsRequest,
+    security(("bearer_auth" = [], "csrf_token" = [])),
+    responses(
+        (status = 200, description = "Разрешения роли сохранены", body = RolePermissionsResponse),
+        (status = 400, description = "`INVALID_ROLE_NAME` или `ADMIN_LOCKOUT`", body = ErrorResponse),
+        (status = 403, description = "Выдаётся разрешение, которого нет у самого админа", body = ErrorResponse),
+    )
+)]
+pub async fn update_role_permissions(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    Path(role): Path<String>,
+    AppJson(req): AppJson<UpdateRolePermissionsRequest>,
+) -> Result<Json<RolePermissionsResponse>, ErrorResponse> {
+    // Выдать можно только то, что есть у себя - иначе роль станет способом повысить права
+    let own = state
+        .role_permissions
+        .permissions(&state.mongo, &claims.role)
+        .await
+        .map_err(|e| ErrorResponse::internal(e.to_string()))?;
+    if let Some(missing) = req.permissions.iter().find(|p| !own.contains(p)) {
+        return Err(ErrorResponse::forbidden(
+            "PERMISSION_ESCALATION",
+            format!("Cannot grant {} without holding it", missing.as_str()),
+        )
+        .with_details(serde_json::json!({ "permission": missing })));
+    }
+
+    let updated = PermissionService::new(state.mongo.clone(), &state.role_permissions)
+        .set_role_permissions(&role, &req.permissions, &claims.sub)
+        .await
+        .map_err(|e| match e.downcast_ref::<RolePermissionsError>() {
+            Some(RolePermissionsError::InvalidRoleName) => {
+                ErrorResponse::bad_request("INVALID_ROLE_NAME", e.to_string())
+            }
+            Some(RolePermissionsError::AdminLockout) => {
+                ErrorResponse::bad_request("ADMIN_LOCKOUT", e.to_string())
+            }
+            None => {
+                tracing::error!("Failed to update role {}: {:#}", role, e);
+                ErrorResponse::internal("Failed to update role permissions")
+            }
+        })?;
+
+    let _ = AuditService::new(state.mongo.clone())
+        .log_role_permissions_update(&claims.sub, &updated.role, &updated.permissions)
+        .await;
+    tracing::info!(
+        admin = %claims.sub,
+        role = %updated.role,
+        "Role permissions updated"
+    );
+
+    Ok(Json(updated))
+}
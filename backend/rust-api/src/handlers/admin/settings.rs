@@ -1,18 +1,34 @@
 use axum::{
     extract::{Extension, State},
-    response::IntoResponse,
     Json,
 };
 use std::sync::Arc;
+use validator::Validate;
 
 use crate::{
     extractors::AppJson,
-    middlewares::auth::JwtClaims,
-    models::system_settings::{
-        AnticheatSettings, EmailSettings, SettingsTestResponse, SsoSettings,
-        SystemSettingsResponse, YandexGptSettings,
+    middlewares::auth::{self, JwtClaims, JwtService, FALLBACK_KEY_LABEL},
+    models::{
+        anticheat::{AnticheatPreviewRequest, AnticheatPreviewResponse},
+        system_settings::{
+            mask_secret, AnticheatSettings, ConsentSettings, EmailSettingsUpdate,
+            EmailSettingsView, EmailTestRequest, EmailTestResponse, EmailTestStatus, JwtKeyUsage,
+            JwtKeysResponse, OpenAiSettingsUpdate, OpenAiSettingsView, PiiSettings,
+            RateLimitOverrides, RuntimeSettingsView, SettingsTestResponse, SsoSettingsUpdate,
+            SsoSettingsView, SystemSettingsResponse, YandexGptSettingsUpdate,
+            YandexGptSettingsView,
+        },
+    },
+    services::{
+        anticheat_preview_service::{AnticheatPreviewService, MAX_PREVIEW_LOOKBACK_DAYS},
+        email_service::EmailService,
+        llm_provider::{
+            LlmParams, LlmProvider, OpenAiCompatibleProvider, RetryingProvider, YandexGptProvider,
+        },
+        runtime_settings,
+        system_settings_service::SystemSettingsService,
+        AppState,
     },
-    services::{system_settings_service::SystemSettingsService, AppState},
 };
 
 use super::ApiError;
@@ -21,47 +37,132 @@ pub async fn get_system_settings(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<SystemSettingsResponse>, ApiError> {
     let service = SystemSettingsService::new(state.mongo.clone());
-    let settings = service.get_all().await.map_err(ApiError::from)?;
+    let mut settings = service.get_all().await.map_err(ApiError::from)?;
+    settings.llm_provider = state.config.llm.provider.as_str().to_string();
+    settings.runtime = Some(state.runtime.snapshot().view());
     Ok(Json(settings))
 }
 
-pub async fn update_yandexgpt_settings(
+/// POST /admin/settings/reload - Перечитать конфигурацию и переопределения из
+/// `system_settings` и применить их на этом экземпляре без перезапуска
+pub async fn reload_runtime_settings(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<Json<RuntimeSettingsView>, ApiError> {
+    let settings = runtime_settings::reload(&state).await.map_err(|err| {
+        tracing::error!("Runtime settings reload failed: {:#}", err);
+        ApiError::bad_request("RELOAD_FAILED", format!("{:#}", err))
+    })?;
+    tracing::info!(admin = %claims.sub, "Runtime settings reloaded");
+    Ok(Json(settings.view()))
+}
+
+/// PUT /admin/settings/rate-limits - Сохранить лимиты запросов поверх RATE_LIMIT_*;
+/// применяются при `POST /admin/settings/reload`
+pub async fn update_rate_limit_settings(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
-    AppJson(payload): AppJson<YandexGptSettings>,
-) -> Result<Json<YandexGptSettings>, ApiError> {
+    AppJson(payload): AppJson<RateLimitOverrides>,
+) -> Result<Json<RateLimitOverrides>, ApiError> {
+    payload.validate().map_err(ApiError::Validation)?;
+
     let service = SystemSettingsService::new(state.mongo.clone());
     let updated = service
-        .update_yandexgpt(payload, &claims.sub)
+        .update_rate_limits(payload, &claims.sub)
         .await
         .map_err(ApiError::from)?;
     Ok(Json(updated))
 }
 
+/// GET /admin/settings/jwt-keys - Ключи подписи JWT (без секретов) и сколько
+/// токенов каждым выпущено и проверено; помогает понять, когда старый ключ можно убрать
+pub async fn list_jwt_keys(State(state): State<Arc<AppState>>) -> Json<JwtKeysResponse> {
+    let service = JwtService::from_config(&state.config);
+    let active_kid = service.active_key_id().to_string();
+    let mut keys: Vec<JwtKeyUsage> = service
+        .key_ids()
+        .map(|kid| JwtKeyUsage {
+            kid: kid.to_string(),
+            active: kid == active_kid,
+            issued: auth::key_usage(kid, "issued"),
+            verified: auth::key_usage(kid, "verified"),
+        })
+        .collect();
+    keys.sort_by(|a, b| a.kid.cmp(&b.kid));
+
+    Json(JwtKeysResponse {
+        active_kid,
+        keys,
+        fallback_verified: auth::key_usage(FALLBACK_KEY_LABEL, "verified"),
+    })
+}
+
+pub async fn update_yandexgpt_settings(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    AppJson(payload): AppJson<YandexGptSettingsUpdate>,
+) -> Result<Json<YandexGptSettingsView>, ApiError> {
+    let service = SystemSettingsService::new(state.mongo.clone());
+    let stored = service.get_yandexgpt_settings().await?;
+    let settings = payload
+        .into_settings(stored.as_ref())
+        .map_err(ApiError::Validation)?;
+    let updated = service
+        .update_yandexgpt(settings, &claims.sub)
+        .await
+        .map_err(ApiError::from)?;
+    Ok(Json((&updated).into()))
+}
+
+pub async fn update_openai_settings(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    AppJson(payload): AppJson<OpenAiSettingsUpdate>,
+) -> Result<Json<OpenAiSettingsView>, ApiError> {
+    let service = SystemSettingsService::new(state.mongo.clone());
+    let stored = service.get_openai_settings().await?;
+    let settings = payload
+        .into_settings(stored.as_ref())
+        .map_err(ApiError::Validation)?;
+    let updated = service
+        .update_openai(settings, &claims.sub)
+        .await
+        .map_err(ApiError::from)?;
+    Ok(Json((&updated).into()))
+}
+
 pub async fn update_sso_settings(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
-    AppJson(payload): AppJson<SsoSettings>,
-) -> Result<Json<SsoSettings>, ApiError> {
+    AppJson(payload): AppJson<SsoSettingsUpdate>,
+) -> Result<Json<SsoSettingsView>, ApiError> {
     let service = SystemSettingsService::new(state.mongo.clone());
+    let stored = service.get_sso_settings().await?;
+    let settings = payload
+        .into_settings(stored.as_ref())
+        .map_err(ApiError::Validation)?;
     let updated = service
-        .update_sso(payload, &claims.sub)
+        .update_sso(settings, &claims.sub)
         .await
         .map_err(ApiError::from)?;
-    Ok(Json(updated))
+    Ok(Json((&updated).into()))
 }
 
 pub async fn update_email_settings(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
-    AppJson(payload): AppJson<EmailSettings>,
-) -> Result<Json<EmailSettings>, ApiError> {
+    AppJson(payload): AppJson<EmailSettingsUpdate>,
+) -> Result<Json<EmailSettingsView>, ApiError> {
     let service = SystemSettingsService::new(state.mongo.clone());
+    let stored = service.get_email_settings().await?;
+    let settings = payload
+        .into_settings(stored.as_ref())
+        .map_err(ApiError::Validation)?;
     let updated = service
-        .update_email(payload, &claims.sub)
+        .update_email(settings, &claims.sub)
         .await
         .map_err(ApiError::from)?;
-    Ok(Json(updated))
+    Ok(Json((&updated).into()))
 }
 
 pub async fn update_anticheat_settings(
@@ -69,31 +170,231 @@ pub async fn update_anticheat_settings(
     Extension(claims): Extension<JwtClaims>,
     AppJson(payload): AppJson<AnticheatSettings>,
 ) -> Result<Json<AnticheatSettings>, ApiError> {
+    payload.validate().map_err(ApiError::Validation)?;
+
     let service = SystemSettingsService::new(state.mongo.clone());
     let updated = service
         .update_anticheat(payload, &claims.sub)
         .await
         .map_err(ApiError::from)?;
+    state.anticheat_settings.invalidate().await;
     Ok(Json(updated))
 }
 
-pub async fn test_yandexgpt_settings() -> impl IntoResponse {
-    Json(SettingsTestResponse {
-        success: true,
-        message: Some("YandexGPT connection test is not configured; assuming success".into()),
-    })
+/// POST /admin/settings/anticheat/preview - Сколько пользователей и сессий
+/// отметили бы кандидатные пороги за прошедшие дни
+pub async fn preview_anticheat_settings(
+    State(state): State<Arc<AppState>>,
+    AppJson(payload): AppJson<AnticheatPreviewRequest>,
+) -> Result<Json<AnticheatPreviewResponse>, ApiError> {
+    payload.settings.validate().map_err(ApiError::Validation)?;
+    if !(1..=MAX_PREVIEW_LOOKBACK_DAYS).contains(&payload.lookback_days) {
+        return Err(ApiError::bad_request(
+            "VALIDATION_ERROR",
+            format!(
+                "lookback_days must be between 1 and {}",
+                MAX_PREVIEW_LOOKBACK_DAYS
+            ),
+        ));
+    }
+
+    let service = AnticheatPreviewService::new(state.mongo.clone());
+    let preview = service.preview(&payload).await.map_err(ApiError::from)?;
+    Ok(Json(preview))
 }
 
-pub async fn test_sso_settings() -> impl IntoResponse {
-    Json(SettingsTestResponse {
-        success: true,
-        message: Some("SSO test endpoint placeholder".into()),
-    })
+pub async fn update_consent_settings(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    AppJson(payload): AppJson<ConsentSettings>,
+) -> Result<Json<ConsentSettings>, ApiError> {
+    if payload.document_version.trim().is_empty() {
+        return Err(ApiError::bad_request(
+            "VALIDATION_ERROR",
+            "document_version must not be empty",
+        ));
+    }
+    if payload
+        .required_consents
+        .iter()
+        .any(|consent| consent.trim().is_empty())
+    {
+        return Err(ApiError::bad_request(
+            "VALIDATION_ERROR",
+            "required_consents must not be empty",
+        ));
+    }
+
+    let service = SystemSettingsService::new(state.mongo.clone());
+    let updated = service
+        .update_consent(payload, &claims.sub)
+        .await
+        .map_err(ApiError::from)?;
+    Ok(Json(updated))
 }
 
-pub async fn test_email_settings() -> impl IntoResponse {
-    Json(SettingsTestResponse {
-        success: true,
-        message: Some("Email test endpoint placeholder".into()),
-    })
+/// PUT /admin/settings/pii-patterns - Шаблоны ПДн для проверки шаблонов заданий.
+/// Некорректные и слишком тяжёлые выражения отклоняются до сохранения
+pub async fn update_pii_settings(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    AppJson(payload): AppJson<PiiSettings>,
+) -> Result<Json<PiiSettings>, ApiError> {
+    payload.validate().map_err(ApiError::Validation)?;
+
+    let service = SystemSettingsService::new(state.mongo.clone());
+    let updated = service
+        .update_pii(payload, &claims.sub)
+        .await
+        .map_err(ApiError::from)?;
+    state.pii_patterns.invalidate().await;
+    Ok(Json(updated))
+}
+
+/// POST /admin/settings/test/yandexgpt - Отправить короткий запрос к YandexGPT
+/// с настройками из тела запроса (или сохранёнными, если тела нет); маска вместо
+/// ключа означает сохранённый ключ
+pub async fn test_yandexgpt_settings(
+    State(state): State<Arc<AppState>>,
+    payload: Option<Json<YandexGptSettingsUpdate>>,
+) -> Result<Json<SettingsTestResponse>, ApiError> {
+    let service = SystemSettingsService::new(state.mongo.clone());
+    let stored = service.get_yandexgpt_settings().await?;
+    let settings = match payload {
+        Some(Json(update)) => Some(
+            update
+                .into_settings(stored.as_ref())
+                .map_err(ApiError::Validation)?,
+        ),
+        None => stored,
+    };
+
+    let Some(settings) = settings else {
+        return Ok(Json(not_configured("YandexGPT")));
+    };
+    let provider = YandexGptProvider::new(settings, &state.config.llm)?;
+    Ok(Json(run_llm_test(state.as_ref(), Arc::new(provider)).await))
+}
+
+/// POST /admin/settings/test/openai - То же для OpenAI-совместимого сервера
+pub async fn test_openai_settings(
+    State(state): State<Arc<AppState>>,
+    payload: Option<Json<OpenAiSettingsUpdate>>,
+) -> Result<Json<SettingsTestResponse>, ApiError> {
+    let service = SystemSettingsService::new(state.mongo.clone());
+    let stored = service.get_openai_settings().await?;
+    let settings = match payload {
+        Some(Json(update)) => Some(
+            update
+                .into_settings(stored.as_ref())
+                .map_err(ApiError::Validation)?,
+        ),
+        None => stored,
+    };
+
+    let Some(settings) = settings else {
+        return Ok(Json(not_configured("OpenAI")));
+    };
+    let provider = OpenAiCompatibleProvider::new(settings, &state.config.llm)?;
+    Ok(Json(run_llm_test(state.as_ref(), Arc::new(provider)).await))
+}
+
+/// Пробный запрос через тот же слой повторов и метрик, что и у подсказок
+async fn run_llm_test(state: &AppState, provider: Arc<dyn LlmProvider>) -> SettingsTestResponse {
+    let provider = RetryingProvider::from_config(provider, &state.config.llm);
+    let params = LlmParams {
+        max_tokens: Some(16),
+        ..LlmParams::default()
+    };
+    match provider
+        .generate("Ответь одним словом: готово", &params)
+        .await
+    {
+        Ok(response) => {
+            let reply: String = response.text.trim().chars().take(100).collect();
+            SettingsTestResponse {
+                success: true,
+                message: Some(format!(
+                    "{} replied \"{}\" ({} prompt + {} completion tokens)",
+                    provider.name(),
+                    reply,
+                    response.prompt_tokens,
+                    response.completion_tokens
+                )),
+            }
+        }
+        Err(err) => SettingsTestResponse {
+            success: false,
+            message: Some(format!("{} request failed: {:#}", provider.name(), err)),
+        },
+    }
+}
+
+/// POST /admin/settings/test/sso
+pub async fn test_sso_settings(
+    State(state): State<Arc<AppState>>,
+    payload: Option<Json<SsoSettingsUpdate>>,
+) -> Result<Json<SettingsTestResponse>, ApiError> {
+    let service = SystemSettingsService::new(state.mongo.clone());
+    let stored = service.get_sso_settings().await?;
+    let settings = match payload {
+        Some(Json(update)) => Some(
+            update
+                .into_settings(stored.as_ref())
+                .map_err(ApiError::Validation)?,
+        ),
+        None => stored,
+    };
+
+    Ok(Json(match settings {
+        Some(settings) => SettingsTestResponse {
+            success: true,
+            message: Some(format!(
+                "SSO settings for {} are valid (client secret {})",
+                settings.provider,
+                mask_secret(&settings.client_secret)
+            )),
+        },
+        None => not_configured("SSO"),
+    }))
+}
+
+/// POST /admin/settings/test/email - Подключиться к SMTP с настройками из тела запроса
+/// (или сохранёнными) и отправить тестовое письмо на `recipient`
+pub async fn test_email_settings(
+    State(state): State<Arc<AppState>>,
+    AppJson(payload): AppJson<EmailTestRequest>,
+) -> Result<Json<EmailTestResponse>, ApiError> {
+    payload.validate().map_err(ApiError::Validation)?;
+
+    let service = SystemSettingsService::new(state.mongo.clone());
+    let stored = service.get_email_settings().await?;
+    let settings = match payload.settings {
+        Some(update) => Some(
+            update
+                .into_settings(stored.as_ref())
+                .map_err(ApiError::Validation)?,
+        ),
+        None => stored,
+    };
+
+    let Some(settings) = settings else {
+        return Ok(Json(EmailTestResponse {
+            success: false,
+            status: EmailTestStatus::NotConfigured,
+            message: Some("Email settings are not configured".into()),
+            smtp_code: None,
+        }));
+    };
+
+    Ok(Json(
+        EmailService::send_test_email(&settings, &payload.recipient).await,
+    ))
+}
+
+fn not_configured(section: &str) -> SettingsTestResponse {
+    SettingsTestResponse {
+        success: false,
+        message: Some(format!("{} settings are not configured", section)),
+    }
 }
@@ -1,104 +1,134 @@
 use std::sync::Arc;
 
-use anyhow::Context;
-use axum::{extract::State, Json};
-use chrono::{Duration, Utc};
-use mongodb::bson::{doc, DateTime as BsonDateTime};
-use redis::aio::ConnectionManager;
-
-use crate::{models::system_metrics::SystemMetricsResponse, services::AppState};
+use axum::{
+    extract::{Extension, State},
+    Json,
+};
+
+use crate::{
+    extractors::AppJson,
+    handlers::error::ErrorResponse,
+    middlewares::auth::JwtClaims,
+    models::{
+        dashboard::DashboardSummary,
+        db_index::IndexReport,
+        maintenance::{MaintenanceMode, MaintenanceModeRequest},
+        superuser::SuperuserStatusResponse,
+        system_metrics::SystemMetricsResponse,
+    },
+    services::{
+        dashboard_service::DashboardService,
+        index_registry,
+        maintenance_mode::{self, MaintenanceModeError},
+        superuser_seed, AppState,
+    },
+    telemetry::{sampling_control, SamplingRules},
+};
 
 use super::ApiError;
 
+/// GET /admin/dashboard - Счётчики для главной страницы админки одним запросом
+pub async fn get_dashboard(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<DashboardSummary>, ApiError> {
+    let service = DashboardService::new(
+        state.mongo.clone(),
+        state.redis.clone(),
+        state.config.content.stream_name.clone(),
+    );
+    Ok(Json(service.summary().await?))
+}
+
+/// GET /admin/system/metrics - Состояние процесса, MongoDB и Redis
 pub async fn get_system_metrics(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<SystemMetricsResponse>, ApiError> {
-    let metrics = gather_system_metrics(&state).await?;
+    let metrics = state
+        .system_metrics
+        .metrics(&state.mongo, &state.redis, state.start_time)
+        .await?;
     Ok(Json(metrics))
 }
 
-async fn gather_system_metrics(state: &AppState) -> anyhow::Result<SystemMetricsResponse> {
-    let users_collection = state.mongo.collection::<mongodb::bson::Document>("users");
-    let groups_collection = state.mongo.collection::<mongodb::bson::Document>("groups");
-    let incidents_collection = state
-        .mongo
-        .collection::<mongodb::bson::Document>("incidents");
-    let audit_collection = state
-        .mongo
-        .collection::<mongodb::bson::Document>("audit_log");
-
-    let total_users = users_collection
-        .estimated_document_count()
-        .await
-        .context("Failed to count users")?;
-    let blocked_users = users_collection
-        .count_documents(doc! { "is_blocked": true })
-        .await
-        .context("Failed to count blocked users")?;
+/// GET /admin/system/indexes - Объявленные индексы MongoDB, их состояние и дрейф.
+/// Проверка только читает базу; недостающие индексы создаются при старте API
+pub async fn get_index_report(State(state): State<Arc<AppState>>) -> Json<IndexReport> {
+    let report = index_registry::inspect_indexes(&state.mongo).await;
+    Json(index_registry::with_startup_errors(
+        report,
+        &state.index_report,
+    ))
+}
 
-    let total_groups = groups_collection
-        .estimated_document_count()
-        .await
-        .context("Failed to count groups")?;
+/// GET /admin/system/superuser-status - Не остался ли у суперпользователя пароль из
+/// seed-файла и когда он входил последний раз
+pub async fn get_superuser_status(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<SuperuserStatusResponse>, ApiError> {
+    let status = superuser_seed::status(&state.mongo).await?;
+    Ok(Json(status))
+}
 
-    let total_incidents = incidents_collection
-        .estimated_document_count()
-        .await
-        .context("Failed to count incidents")?;
-    let open_incidents = incidents_collection
-        .count_documents(doc! { "status": "open" })
-        .await
-        .context("Failed to count open incidents")?;
-    let critical_incidents = incidents_collection
-        .count_documents(doc! { "severity": "critical", "status": "open" })
-        .await
-        .context("Failed to count critical incidents")?;
+/// GET /admin/system/maintenance - Состояние режима обслуживания
+pub async fn get_maintenance_mode(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<MaintenanceMode>, ApiError> {
+    let mode = maintenance_mode::load(&state.redis).await?;
+    Ok(Json(mode.unwrap_or_else(maintenance_mode::disabled)))
+}
 
-    let last_24h = Utc::now() - Duration::hours(24);
-    let audit_events_24h = audit_collection
-        .count_documents(doc! { "createdAt": { "$gte": BsonDateTime::from_millis(last_24h.timestamp_millis()) } })
+/// POST /admin/system/maintenance - Включить или выключить режим обслуживания на всех
+/// репликах сразу
+pub async fn update_maintenance_mode(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    AppJson(request): AppJson<MaintenanceModeRequest>,
+) -> Result<Json<MaintenanceMode>, ApiError> {
+    let mode = maintenance_mode::update(&state.redis, request, &claims.sub, &claims.role)
         .await
-        .context("Failed to count audit events")?;
-
-    let active_sessions = count_active_sessions(&state.redis).await?;
-
-    Ok(SystemMetricsResponse {
-        uptime_seconds: state.start_time.elapsed().as_secs(),
-        total_users,
-        blocked_users,
-        total_groups,
-        total_incidents,
-        open_incidents,
-        critical_incidents,
-        audit_events_24h,
-        active_sessions,
-    })
+        .map_err(|e| match e.downcast_ref::<MaintenanceModeError>() {
+            Some(MaintenanceModeError::SelfLockout(_)) => {
+                ErrorResponse::bad_request("MAINTENANCE_LOCKOUT", e.to_string()).into()
+            }
+            Some(_) => ApiError::bad_request("INVALID_MAINTENANCE_MODE", e.to_string()),
+            None => ApiError::from(e),
+        })?;
+
+    tracing::warn!(
+        admin_id = %claims.sub,
+        enabled = mode.enabled,
+        allow_roles = ?mode.allow_roles,
+        "Maintenance mode updated"
+    );
+    Ok(Json(mode))
 }
 
-async fn count_active_sessions(redis: &ConnectionManager) -> anyhow::Result<u64> {
-    let mut conn = redis.clone();
-    let mut cursor = "0".to_string();
-    let mut total = 0u64;
-
-    loop {
-        let (next_cursor, keys): (String, Vec<String>) = redis::cmd("SCAN")
-            .arg(&cursor)
-            .arg("MATCH")
-            .arg("session:*")
-            .arg("COUNT")
-            .arg(1000)
-            .query_async(&mut conn)
-            .await
-            .context("Failed to scan Redis for sessions")?;
-
-        total += keys.len() as u64;
-
-        if next_cursor == "0" {
-            break;
-        }
+/// GET /admin/system/trace-sampling - Текущие правила выборки трейсов
+pub async fn get_trace_sampling() -> Json<SamplingRules> {
+    Json(sampling_control().get())
+}
 
-        cursor = next_cursor;
+/// PUT /admin/system/trace-sampling - Поменять правила выборки без перезапуска
+/// (действует до рестарта процесса, затем снова берутся значения из конфигурации)
+pub async fn update_trace_sampling(
+    Extension(claims): Extension<JwtClaims>,
+    AppJson(rules): AppJson<SamplingRules>,
+) -> Result<Json<SamplingRules>, ApiError> {
+    if !(0.0..=1.0).contains(&rules.ratio) {
+        return Err(ApiError::bad_request(
+            "INVALID_SAMPLE_RATIO",
+            "ratio must be between 0.0 and 1.0",
+        ));
     }
 
-    Ok(total)
+    sampling_control().set(rules);
+    tracing::info!(
+        admin_id = %claims.sub,
+        ratio = rules.ratio,
+        slow_trace_ms = rules.slow_trace_ms,
+        always_sample_errors = rules.always_sample_errors,
+        "Trace sampling rules updated"
+    );
+
+    Ok(Json(rules))
 }
@@ -1,33 +1,43 @@
 use axum::{
     extract::{Extension, Path, Query, State},
     http::StatusCode,
-    response::IntoResponse,
+    response::{IntoResponse, Response},
     Json,
 };
 use std::sync::Arc;
-use validator::Validate;
+use validator::{Validate, ValidationErrors};
 
 use crate::{
-    extractors::AppJson,
+    extractors::{AppJson, ObjectIdParam},
+    handlers::error::ErrorResponse,
     middlewares::auth::JwtClaims,
+    models::login_history::{LoginHistoryEntry, LoginHistoryQuery},
     models::user::{
-        BlockUserRequest, BulkUserActionRequest, CreateUserRequest, ListUsersQuery,
-        UpdateUserRequest, UserDetailResponse,
+        BlockUserRequest, BulkUserActionRequest, BulkUserActionResult, CreateUserRequest,
+        ListUsersQuery, UpdateUserRequest, UserDetailResponse,
     },
+    models::user_purge::{PurgeUserRequest, PurgeUserResponse},
     services::{
-        audit_service::AuditService, email_service::EmailService,
-        user_management_service::UserManagementService, AppState,
+        audit_service::AuditService,
+        email_service::EmailService,
+        login_history_service::{LoginHistoryService, NEWEST_FIRST},
+        user_management_service::UserManagementService,
+        user_purge_service::{UserPurgeError, UserPurgeService},
+        AppState,
     },
+    utils::pagination::{ensure_single_mode, paged_response, PageCursor},
 };
 use rand::{distr::Alphanumeric, Rng};
 
 #[derive(Debug)]
 pub enum ApiError {
+    Response(ErrorResponse),
     BadRequest(String),
     Unauthorized(String),
     Forbidden(String),
     NotFound(String),
     Internal(String),
+    Validation(ValidationErrors),
 }
 
 impl ApiError {
@@ -40,6 +50,12 @@ impl ApiError {
     }
 }
 
+impl From<ErrorResponse> for ApiError {
+    fn from(err: ErrorResponse) -> Self {
+        ApiError::Response(err)
+    }
+}
+
 impl From<anyhow::Error> for ApiError {
     fn from(err: anyhow::Error) -> Self {
         ApiError::Internal(err.to_string())
@@ -48,31 +64,39 @@ impl From<anyhow::Error> for ApiError {
 
 impl IntoResponse for ApiError {
     fn into_response(self) -> axum::response::Response {
-        let (status, message) = match self {
-            ApiError::BadRequest(message) => (StatusCode::BAD_REQUEST, message),
-            ApiError::Unauthorized(message) => (StatusCode::UNAUTHORIZED, message),
-            ApiError::Forbidden(message) => (StatusCode::FORBIDDEN, message),
-            ApiError::NotFound(message) => (StatusCode::NOT_FOUND, message),
-            ApiError::Internal(message) => (StatusCode::INTERNAL_SERVER_ERROR, message),
+        let error = match self {
+            ApiError::Response(error) => error,
+            ApiError::BadRequest(message) => ErrorResponse::bad_request("BAD_REQUEST", message),
+            ApiError::Unauthorized(message) => ErrorResponse::unauthorized("UNAUTHORIZED", message),
+            ApiError::Forbidden(message) => ErrorResponse::forbidden("FORBIDDEN", message),
+            ApiError::NotFound(message) => ErrorResponse::not_found("USER_NOT_FOUND", message),
+            ApiError::Internal(message) => ErrorResponse::internal(message),
+            ApiError::Validation(errors) => ErrorResponse::validation(&errors),
         };
-        let json_response = serde_json::json!({
-            "message": message,
-            "status": status.as_u16()
-        });
-        (status, Json(json_response)).into_response()
+        error.into_response()
     }
 }
 
 /// POST /admin/users - Создать пользователя (Admin)
 /// POST /admin/users - Создать пользователя (Admin)
+#[utoipa::path(
+    post,
+    path = "/admin/users",
+    tag = "admin-users",
+    request_body = CreateUserRequest,
+    security(("bearer_auth" = [], "csrf_token" = [])),
+    responses(
+        (status = 201, description = "Пользователь создан", body = UserDetailResponse),
+        (status = 400, description = "Ошибка валидации или email занят", body = ErrorResponse),
+    )
+)]
 pub async fn create_user(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
     AppJson(req): AppJson<CreateUserRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
     // Валидация
-    req.validate()
-        .map_err(|e| ApiError::bad_request(format!("Validation error: {}", e)))?;
+    req.validate().map_err(ApiError::Validation)?;
 
     // Создание пользователя
     let user_service = UserManagementService::new(state.mongo.clone(), state.redis.clone());
@@ -98,21 +122,52 @@ pub async fn create_user(
 }
 
 /// GET /admin/users - Список пользователей
+#[utoipa::path(
+    get,
+    path = "/admin/users",
+    tag = "admin-users",
+    params(ListUsersQuery),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Пользователи по фильтрам", body = Vec<UserDetailResponse>,
+            headers(("x-next-cursor" = String, description = "Курсор следующей страницы; нет заголовка - страница последняя"))),
+        (status = 400, description = "Некорректный курсор или курсор вместе с offset", body = ErrorResponse),
+    )
+)]
 pub async fn list_users(
     State(state): State<Arc<AppState>>,
     Query(query): Query<ListUsersQuery>,
-) -> Result<Json<Vec<UserDetailResponse>>, ApiError> {
-    let user_service = UserManagementService::new(state.mongo.clone(), state.redis.clone());
+) -> Result<Response, ApiError> {
+    ensure_single_mode(query.cursor.as_deref(), query.offset)?;
+    let sort = query.sort.unwrap_or_default().key();
+    let cursor = query
+        .cursor
+        .as_deref()
+        .map(|raw| PageCursor::decode(raw, &sort))
+        .transpose()
+        .map_err(ErrorResponse::from)?;
 
-    let users = user_service
-        .list_users(query)
+    let user_service = UserManagementService::new(state.mongo.clone(), state.redis.clone());
+    let page = user_service
+        .list_users(query, cursor.as_ref())
         .await
         .map_err(|e| ApiError::Internal(e.to_string()))?;
 
-    Ok(Json(users))
+    Ok(paged_response(page))
 }
 
 /// GET /admin/users/:id - Получить пользователя
+#[utoipa::path(
+    get,
+    path = "/admin/users/{id}",
+    tag = "admin-users",
+    params(("id" = String, Path, description = "Id пользователя")),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Пользователь", body = UserDetailResponse),
+        (status = 404, description = "Пользователь не найден", body = ErrorResponse),
+    )
+)]
 pub async fn get_user(
     State(state): State<Arc<AppState>>,
     Path(user_id): Path<String>,
@@ -131,6 +186,19 @@ pub async fn get_user(
 }
 
 /// PATCH /admin/users/:id - Обновить пользователя
+#[utoipa::path(
+    patch,
+    path = "/admin/users/{id}",
+    tag = "admin-users",
+    params(("id" = String, Path, description = "Id пользователя")),
+    request_body = UpdateUserRequest,
+    security(("bearer_auth" = [], "csrf_token" = [])),
+    responses(
+        (status = 200, description = "Обновлённый пользователь", body = UserDetailResponse),
+        (status = 400, description = "Ошибка валидации", body = ErrorResponse),
+        (status = 404, description = "Пользователь не найден", body = ErrorResponse),
+    )
+)]
 pub async fn update_user(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
@@ -138,8 +206,7 @@ pub async fn update_user(
     AppJson(req): AppJson<UpdateUserRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
     // Валидация
-    req.validate()
-        .map_err(|e| ApiError::bad_request(e.to_string()))?;
+    req.validate().map_err(ApiError::Validation)?;
 
     // Обновление пользователя
     let user_service = UserManagementService::new(state.mongo.clone(), state.redis.clone());
@@ -178,6 +245,17 @@ pub async fn update_user(
 }
 
 /// DELETE /admin/users/:id - Удалить пользователя
+#[utoipa::path(
+    delete,
+    path = "/admin/users/{id}",
+    tag = "admin-users",
+    params(("id" = String, Path, description = "Id пользователя")),
+    security(("bearer_auth" = [], "csrf_token" = [])),
+    responses(
+        (status = 204, description = "Пользователь удалён, его сессии отозваны"),
+        (status = 404, description = "Пользователь не найден", body = ErrorResponse),
+    )
+)]
 pub async fn delete_user(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
@@ -208,6 +286,19 @@ pub async fn delete_user(
 }
 
 /// POST /admin/users/:id/block - Заблокировать пользователя
+#[utoipa::path(
+    post,
+    path = "/admin/users/{id}/block",
+    tag = "admin-users",
+    params(("id" = String, Path, description = "Id пользователя")),
+    request_body = BlockUserRequest,
+    security(("bearer_auth" = [], "csrf_token" = [])),
+    responses(
+        (status = 200, description = "Пользователь заблокирован, его сессии отозваны", body = UserDetailResponse),
+        (status = 400, description = "Ошибка валидации", body = ErrorResponse),
+        (status = 404, description = "Пользователь не найден", body = ErrorResponse),
+    )
+)]
 pub async fn block_user(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
@@ -215,8 +306,7 @@ pub async fn block_user(
     AppJson(req): AppJson<BlockUserRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
     // Валидация
-    req.validate()
-        .map_err(|e| ApiError::bad_request(e.to_string()))?;
+    req.validate().map_err(ApiError::Validation)?;
 
     // Блокировка пользователя
     let user_service = UserManagementService::new(state.mongo.clone(), state.redis.clone());
@@ -247,7 +337,136 @@ pub async fn block_user(
     Ok(Json(blocked_user))
 }
 
+/// GET /admin/users/:id/logins - История входов пользователя, новые первыми
+#[utoipa::path(
+    get,
+    path = "/admin/users/{id}/logins",
+    tag = "admin-users",
+    params(("id" = String, Path, description = "Id пользователя"), LoginHistoryQuery),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Входы пользователя", body = Vec<LoginHistoryEntry>,
+            headers(("x-next-cursor" = String, description = "Курсор следующей страницы; нет заголовка - страница последняя"))),
+        (status = 400, description = "Некорректный id или курсор", body = ErrorResponse),
+    )
+)]
+pub async fn list_user_logins(
+    State(state): State<Arc<AppState>>,
+    ObjectIdParam(user_id): ObjectIdParam,
+    Query(query): Query<LoginHistoryQuery>,
+) -> Result<Response, ApiError> {
+    let cursor = query
+        .cursor
+        .as_deref()
+        .map(|raw| PageCursor::decode(raw, &NEWEST_FIRST))
+        .transpose()
+        .map_err(ErrorResponse::from)?;
+
+    let page = LoginHistoryService::new(state.mongo.clone())
+        .list(user_id, query.limit, cursor.as_ref())
+        .await
+        .map_err(|e| ApiError::Internal(e.to_string()))?;
+
+    Ok(paged_response(page))
+}
+
+/// POST /admin/users/:id/force-logout - Завершить все сессии пользователя: отзываются
+/// refresh tokens и уже выданные access tokens
+#[utoipa::path(
+    post,
+    path = "/admin/users/{id}/force-logout",
+    tag = "admin-users",
+    params(("id" = String, Path, description = "Id пользователя")),
+    security(("bearer_auth" = [], "csrf_token" = [])),
+    responses(
+        (status = 200, description = "`{\"status\": \"ok\", \"revoked_sessions\": n}`", body = Object),
+        (status = 404, description = "Пользователь не найден", body = ErrorResponse),
+    )
+)]
+pub async fn force_logout_user(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    Path(user_id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user_service = UserManagementService::new(state.mongo.clone(), state.redis.clone());
+    let revoked_sessions = user_service.force_logout(&user_id).await.map_err(|e| {
+        if e.to_string().contains("not found") {
+            ApiError::not_found(e.to_string())
+        } else {
+            ApiError::bad_request(e.to_string())
+        }
+    })?;
+
+    let audit_service = AuditService::new(state.mongo.clone());
+    let _ = audit_service
+        .log_force_logout(&claims.sub, &user_id, revoked_sessions, None, None)
+        .await;
+
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "revoked_sessions": revoked_sessions,
+    })))
+}
+
+/// POST /admin/users/:id/purge - Необратимо удалить персональные данные пользователя
+#[utoipa::path(
+    post,
+    path = "/admin/users/{id}/purge",
+    tag = "admin-users",
+    params(("id" = String, Path, description = "Id пользователя")),
+    request_body = PurgeUserRequest,
+    security(("bearer_auth" = [], "csrf_token" = [])),
+    responses(
+        (status = 200, description = "Что удалено и переписано на псевдоним; для уже очищенного пользователя - `already_purged` и пустой отчёт", body = PurgeUserResponse),
+        (status = 400, description = "`confirm` не совпадает с email или попытка очистить себя", body = ErrorResponse),
+        (status = 403, description = "Нет разрешения `users.purge`", body = ErrorResponse),
+        (status = 404, description = "Пользователь не найден", body = ErrorResponse),
+    )
+)]
+pub async fn purge_user(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    ObjectIdParam(user_id): ObjectIdParam,
+    AppJson(req): AppJson<PurgeUserRequest>,
+) -> Result<Json<PurgeUserResponse>, ApiError> {
+    let report = UserPurgeService::new(state.mongo.clone(), state.redis.clone())
+        .purge_user(user_id, &req.confirm, &claims.sub)
+        .await
+        .map_err(|e| match e.downcast_ref::<UserPurgeError>() {
+            Some(UserPurgeError::UserNotFound) => ApiError::not_found(e.to_string()),
+            Some(UserPurgeError::ConfirmationMismatch) => {
+                ErrorResponse::bad_request("PURGE_CONFIRMATION_MISMATCH", e.to_string()).into()
+            }
+            Some(UserPurgeError::SelfPurge) => {
+                ErrorResponse::bad_request("CANNOT_PURGE_SELF", e.to_string()).into()
+            }
+            None => {
+                tracing::error!("Failed to purge user {}: {:#}", user_id, e);
+                ApiError::Internal("Failed to purge user".to_string())
+            }
+        })?;
+
+    if !report.already_purged {
+        let _ = AuditService::new(state.mongo.clone())
+            .log_user_purge(&claims.sub, &report.user_id, &report.removed)
+            .await;
+    }
+
+    Ok(Json(report))
+}
+
 /// POST /admin/users/:id/unblock - Разблокировать пользователя
+#[utoipa::path(
+    post,
+    path = "/admin/users/{id}/unblock",
+    tag = "admin-users",
+    params(("id" = String, Path, description = "Id пользователя")),
+    security(("bearer_auth" = [], "csrf_token" = [])),
+    responses(
+        (status = 200, description = "Пользователь разблокирован", body = UserDetailResponse),
+        (status = 404, description = "Пользователь не найден", body = ErrorResponse),
+    )
+)]
 pub async fn unblock_user(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
@@ -273,6 +492,17 @@ pub async fn unblock_user(
 }
 
 /// POST /admin/users/:id/reset-password - Сбросить пароль и отправить по email
+#[utoipa::path(
+    post,
+    path = "/admin/users/{id}/reset-password",
+    tag = "admin-users",
+    params(("id" = String, Path, description = "Id пользователя")),
+    security(("bearer_auth" = [], "csrf_token" = [])),
+    responses(
+        (status = 200, description = "`{\"status\": \"ok\"}`; при отключённой отправке писем - ещё `temporary_password`", body = Object),
+        (status = 404, description = "Пользователь не найден", body = ErrorResponse),
+    )
+)]
 pub async fn reset_user_password(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
@@ -328,6 +558,17 @@ fn generate_temp_password() -> String {
 }
 
 /// POST /admin/users/bulk - Массовые операции (блокировка, разблокировка, смена групп)
+#[utoipa::path(
+    post,
+    path = "/admin/users/bulk",
+    tag = "admin-users",
+    request_body = BulkUserActionRequest,
+    security(("bearer_auth" = [], "csrf_token" = [])),
+    responses(
+        (status = 200, description = "Итог массовой операции", body = BulkUserActionResult),
+        (status = 400, description = "Пустой список или ошибка операции", body = ErrorResponse),
+    )
+)]
 pub async fn bulk_user_action(
     State(state): State<Arc<AppState>>,
     AppJson(req): AppJson<BulkUserActionRequest>,
@@ -0,0 +1,226 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Extension, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use chrono::Utc;
+use mongodb::bson::oid::ObjectId;
+use validator::Validate;
+
+use crate::{
+    extractors::{AppJson, ObjectIdParam, ObjectIdParams},
+    handlers::error::ErrorResponse,
+    middlewares::auth::JwtClaims,
+    models::assignment::{
+        AssignmentProgressResponse, AssignmentResponse, CreateAssignmentRequest,
+        ExtendAssignmentRequest, StudentAssignmentsResponse,
+    },
+    services::{
+        assignment_service::{AssignmentError, AssignmentService},
+        reporting_service::ReportingService,
+        AppState,
+    },
+};
+
+/// Ошибки заданий в ответ API (в том числе при старте сессии по заданию)
+pub(crate) fn assignment_error(e: anyhow::Error) -> ErrorResponse {
+    match e.downcast_ref::<AssignmentError>() {
+        Some(err @ AssignmentError::NotFound) => {
+            ErrorResponse::not_found("ASSIGNMENT_NOT_FOUND", err.to_string())
+        }
+        Some(err @ AssignmentError::GroupNotFound) => {
+            ErrorResponse::not_found("GROUP_NOT_FOUND", err.to_string())
+        }
+        Some(err @ AssignmentError::GroupArchived) => {
+            ErrorResponse::new(StatusCode::CONFLICT, "GROUP_ARCHIVED", err.to_string())
+        }
+        Some(err @ AssignmentError::AlreadyCompleted) => ErrorResponse::new(
+            StatusCode::CONFLICT,
+            "ASSIGNMENT_COMPLETED",
+            err.to_string(),
+        ),
+        Some(err @ AssignmentError::NotGroupMember) => {
+            ErrorResponse::forbidden("NOT_GROUP_MEMBER", err.to_string())
+        }
+        Some(err @ AssignmentError::PastDue { due_at }) => {
+            ErrorResponse::forbidden("ASSIGNMENT_PAST_DUE", err.to_string())
+                .with_details(serde_json::json!({ "due_at": due_at }))
+        }
+        Some(err @ AssignmentError::DueDateInPast) => {
+            ErrorResponse::bad_request("INVALID_DUE_DATE", err.to_string())
+        }
+        Some(err @ AssignmentError::DeadlineNotExtended { due_at }) => {
+            ErrorResponse::bad_request("DEADLINE_NOT_EXTENDED", err.to_string())
+                .with_details(serde_json::json!({ "due_at": due_at }))
+        }
+        Some(err @ AssignmentError::UnknownTemplates(ids)) => {
+            ErrorResponse::bad_request("UNKNOWN_TEMPLATES", err.to_string())
+                .with_details(serde_json::json!({ "template_ids": ids }))
+        }
+        Some(err @ AssignmentError::UnpublishedTemplates(ids)) => {
+            ErrorResponse::bad_request("UNPUBLISHED_TEMPLATES", err.to_string())
+                .with_details(serde_json::json!({ "template_ids": ids }))
+        }
+        None => {
+            tracing::error!("Assignment operation failed: {:#}", e);
+            ErrorResponse::internal(e.to_string())
+        }
+    }
+}
+
+async fn guard_group(
+    state: &AppState,
+    claims: &JwtClaims,
+    group_id: &ObjectId,
+) -> Result<(), ErrorResponse> {
+    ReportingService::new(state.mongo.clone(), state.redis.clone())
+        .guard_group_access(&state.role_permissions, claims, group_id)
+        .await
+        .map_err(|_| {
+            ErrorResponse::forbidden("GROUP_ACCESS_DENIED", "Access denied for this group")
+        })
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/teacher/groups/{group_id}/assignments",
+    tag = "assignments",
+    params(("group_id" = String, Path, description = "Id группы")),
+    request_body = CreateAssignmentRequest,
+    security(("bearer_auth" = [], "csrf_token" = [])),
+    responses(
+        (status = 201, description = "Задание создано", body = AssignmentResponse),
+        (status = 400, description = "Срок в прошлом, неизвестные или неопубликованные шаблоны", body = ErrorResponse),
+        (status = 403, description = "Нет доступа к группе", body = ErrorResponse),
+        (status = 409, description = "Группа архивирована", body = ErrorResponse),
+    )
+)]
+pub async fn create_assignment(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    ObjectIdParam(group_id): ObjectIdParam,
+    AppJson(req): AppJson<CreateAssignmentRequest>,
+) -> Result<impl IntoResponse, ErrorResponse> {
+    req.validate()
+        .map_err(|errors| ErrorResponse::validation(&errors))?;
+    guard_group(&state, &claims, &group_id).await?;
+
+    let assignment = AssignmentService::new(state.mongo.clone())
+        .create(&group_id, req, &claims.sub, Utc::now())
+        .await
+        .map_err(assignment_error)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(AssignmentResponse::from(assignment)),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/teacher/groups/{group_id}/assignments",
+    tag = "assignments",
+    params(("group_id" = String, Path, description = "Id группы")),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Задания группы по сроку сдачи", body = Vec<AssignmentResponse>),
+        (status = 403, description = "Нет доступа к группе", body = ErrorResponse),
+    )
+)]
+pub async fn list_group_assignments(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    ObjectIdParam(group_id): ObjectIdParam,
+) -> Result<Json<Vec<AssignmentResponse>>, ErrorResponse> {
+    guard_group(&state, &claims, &group_id).await?;
+
+    let assignments = AssignmentService::new(state.mongo.clone())
+        .list_for_group(&group_id)
+        .await
+        .map_err(assignment_error)?;
+
+    Ok(Json(assignments.into_iter().map(Into::into).collect()))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/v1/teacher/groups/{group_id}/assignments/{assignment_id}",
+    tag = "assignments",
+    params(
+        ("group_id" = String, Path, description = "Id группы"),
+        ("assignment_id" = String, Path, description = "Id задания"),
+    ),
+    request_body = ExtendAssignmentRequest,
+    security(("bearer_auth" = [], "csrf_token" = [])),
+    responses(
+        (status = 200, description = "Срок продлён", body = AssignmentResponse),
+        (status = 400, description = "Новый срок не позже текущего - `DEADLINE_NOT_EXTENDED`", body = ErrorResponse),
+        (status = 404, description = "Задание не найдено", body = ErrorResponse),
+    )
+)]
+pub async fn extend_assignment(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    ObjectIdParams(group_id, assignment_id): ObjectIdParams,
+    AppJson(req): AppJson<ExtendAssignmentRequest>,
+) -> Result<Json<AssignmentResponse>, ErrorResponse> {
+    guard_group(&state, &claims, &group_id).await?;
+
+    let assignment = AssignmentService::new(state.mongo.clone())
+        .extend(&group_id, &assignment_id, req, Utc::now())
+        .await
+        .map_err(assignment_error)?;
+
+    Ok(Json(assignment.into()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/teacher/groups/{group_id}/assignments/{assignment_id}/progress",
+    tag = "assignments",
+    params(
+        ("group_id" = String, Path, description = "Id группы"),
+        ("assignment_id" = String, Path, description = "Id задания"),
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Выполнение задания учениками группы", body = AssignmentProgressResponse),
+        (status = 404, description = "Задание не найдено", body = ErrorResponse),
+    )
+)]
+pub async fn get_assignment_progress(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    ObjectIdParams(group_id, assignment_id): ObjectIdParams,
+) -> Result<Json<AssignmentProgressResponse>, ErrorResponse> {
+    guard_group(&state, &claims, &group_id).await?;
+
+    AssignmentService::new(state.mongo.clone())
+        .progress(&group_id, &assignment_id, Utc::now())
+        .await
+        .map(Json)
+        .map_err(assignment_error)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/assignments",
+    tag = "assignments",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Задания групп ученика с отметками о выполнении", body = StudentAssignmentsResponse),
+    )
+)]
+pub async fn list_my_assignments(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<Json<StudentAssignmentsResponse>, ErrorResponse> {
+    AssignmentService::new(state.mongo.clone())
+        .for_student(&claims.sub, Utc::now())
+        .await
+        .map(|assignments| Json(StudentAssignmentsResponse { assignments }))
+        .map_err(assignment_error)
+}
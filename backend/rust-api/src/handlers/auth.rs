@@ -9,35 +9,46 @@ use std::sync::Arc;
 use validator::Validate;
 
 use crate::{
-    extractors::AppJson,
-    middlewares::auth::{JwtClaims, JwtService},
+    extractors::{AppJson, ObjectIdParam},
+    handlers::error::ErrorResponse,
+    middlewares::{
+        auth::{JwtClaims, JwtService},
+        body_limit::read_body,
+    },
     models::{
-        refresh_token::RefreshTokenResponse,
+        refresh_token::{ActiveSession, RefreshTokenResponse},
         user::{
             AuthResponseCookie, ChangePasswordRequest, ListUsersQuery, LoginRequest,
-            RegisterRequest, UpdateUserRequest, User, UserProfile,
+            RegisterRequest, UpdateProfileRequest, UpdateUserRequest, User, UserProfile,
         },
     },
     services::{audit_service::AuditService, auth_service::AuthService, AppState},
 };
 
 /// POST /api/v1/auth/register - Register a new user
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/register",
+    tag = "auth",
+    request_body = RegisterRequest,
+    responses(
+        (status = 201, description = "Пользователь создан, refresh_token - в HTTP-only cookie", body = AuthResponseCookie),
+        (status = 400, description = "Ошибка валидации или email занят", body = ErrorResponse),
+    )
+)]
 pub async fn register(
     State(state): State<Arc<AppState>>,
     jar: CookieJar,
     AppJson(req): AppJson<RegisterRequest>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, ErrorResponse> {
     // Validate request
     if let Err(e) = req.validate() {
-        return Err((StatusCode::BAD_REQUEST, format!("Validation error: {}", e)));
+        return Err(ErrorResponse::validation(&e));
     }
 
     tracing::info!("Registering new user: {}", req.email);
 
-    let jwt_service = JwtService::new_with_fallbacks(
-        &state.config.jwt_secret,
-        &state.config.jwt_fallback_secrets,
-    );
+    let jwt_service = JwtService::from_config(&state.config);
     let service = AuthService::new(state.mongo.clone(), state.redis.clone(), jwt_service);
     let audit_service = AuditService::new(state.mongo.clone());
 
@@ -80,17 +91,33 @@ pub async fn register(
                 .log_register_failed(&email, None, None, &e.to_string())
                 .await;
 
-            Err((StatusCode::BAD_REQUEST, e.to_string()))
+            Err(ErrorResponse::bad_request(
+                "REGISTRATION_FAILED",
+                e.to_string(),
+            ))
         }
     }
 }
 
 /// POST /api/v1/auth/login - Login with email and password
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/login",
+    tag = "auth",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Успешный вход, refresh_token - в HTTP-only cookie", body = AuthResponseCookie),
+        (status = 400, description = "Некорректное тело запроса", body = ErrorResponse),
+        (status = 401, description = "Неверный email или пароль", body = ErrorResponse),
+        (status = 413, description = "Тело запроса больше лимита", body = ErrorResponse),
+        (status = 429, description = "Слишком много неудачных попыток", body = ErrorResponse),
+    )
+)]
 pub async fn login(
     State(state): State<Arc<AppState>>,
     jar: CookieJar,
     request: Request,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, ErrorResponse> {
     // Extract IP and User-Agent from headers
     let headers = request.headers();
     let ip = headers
@@ -105,29 +132,19 @@ pub async fn login(
         .map(|s| s.to_string());
 
     // Extract JSON body
-    let body_bytes = axum::body::to_bytes(request.into_body(), usize::MAX)
-        .await
-        .map_err(|e| {
-            (
-                StatusCode::BAD_REQUEST,
-                format!("Failed to read body: {}", e),
-            )
-        })?;
+    let body_bytes = read_body(request.into_body(), state.config.body_limits.auth_bytes).await?;
 
     let req: LoginRequest = serde_json::from_slice(&body_bytes)
-        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid JSON: {}", e)))?;
+        .map_err(|e| ErrorResponse::bad_request("INVALID_JSON", format!("Invalid JSON: {}", e)))?;
 
     // Validate request
     if let Err(e) = req.validate() {
-        return Err((StatusCode::BAD_REQUEST, format!("Validation error: {}", e)));
+        return Err(ErrorResponse::validation(&e));
     }
 
     tracing::info!("Login attempt for user: {}", req.email);
 
-    let jwt_service = JwtService::new_with_fallbacks(
-        &state.config.jwt_secret,
-        &state.config.jwt_fallback_secrets,
-    );
+    let jwt_service = JwtService::from_config(&state.config);
     let service = AuthService::new(state.mongo.clone(), state.redis.clone(), jwt_service);
     let audit_service = AuditService::new(state.mongo.clone());
 
@@ -147,9 +164,10 @@ pub async fn login(
                 "Account temporarily locked due to too many failed attempts",
             )
             .await;
-        return Err((
+        return Err(ErrorResponse::new(
             StatusCode::TOO_MANY_REQUESTS,
-            "Too many failed login attempts. Please try again later.".to_string(),
+            "TOO_MANY_LOGIN_ATTEMPTS",
+            "Too many failed login attempts. Please try again later.",
         ));
     }
 
@@ -201,16 +219,29 @@ pub async fn login(
                 .log_login_failed(&email, ip, user_agent, &e.to_string())
                 .await;
 
-            Err((StatusCode::UNAUTHORIZED, e.to_string()))
+            Err(ErrorResponse::unauthorized(
+                "INVALID_CREDENTIALS",
+                e.to_string(),
+            ))
         }
     }
 }
 
 /// POST /api/v1/auth/refresh - Refresh access token
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/refresh",
+    tag = "auth",
+    security(("refresh_cookie" = [])),
+    responses(
+        (status = 200, description = "Новый access-токен", body = RefreshTokenResponse),
+        (status = 401, description = "Refresh-токен отсутствует, отозван или истёк", body = ErrorResponse),
+    )
+)]
 pub async fn refresh_token(
     State(state): State<Arc<AppState>>,
     jar: CookieJar,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, ErrorResponse> {
     tracing::debug!("Refreshing access token");
 
     // Read refresh_token from HTTP-only cookie
@@ -218,16 +249,10 @@ pub async fn refresh_token(
         .get("refresh_token")
         .map(|cookie| cookie.value().to_string())
         .ok_or_else(|| {
-            (
-                StatusCode::UNAUTHORIZED,
-                "Missing refresh token cookie".to_string(),
-            )
+            ErrorResponse::unauthorized("MISSING_REFRESH_TOKEN", "Missing refresh token cookie")
         })?;
 
-    let jwt_service = JwtService::new_with_fallbacks(
-        &state.config.jwt_secret,
-        &state.config.jwt_fallback_secrets,
-    );
+    let jwt_service = JwtService::from_config(&state.config);
     let service = AuthService::new(state.mongo.clone(), state.redis.clone(), jwt_service);
 
     match service.refresh_token(&refresh_token).await {
@@ -237,16 +262,29 @@ pub async fn refresh_token(
         }
         Err(e) => {
             tracing::warn!("Failed to refresh token: {}", e);
-            Err((StatusCode::UNAUTHORIZED, e.to_string()))
+            Err(ErrorResponse::unauthorized(
+                "INVALID_REFRESH_TOKEN",
+                e.to_string(),
+            ))
         }
     }
 }
 
 /// POST /api/v1/auth/logout - Logout (revoke refresh token)
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/logout",
+    tag = "auth",
+    security(("bearer_auth" = [], "csrf_token" = [], "refresh_cookie" = [])),
+    responses(
+        (status = 204, description = "Refresh-токен отозван, cookie очищена"),
+        (status = 401, description = "Нет refresh-токена", body = ErrorResponse),
+    )
+)]
 pub async fn logout(
     State(state): State<Arc<AppState>>,
     jar: CookieJar,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, ErrorResponse> {
     tracing::info!("Logging out user");
 
     // Read refresh_token from HTTP-only cookie
@@ -254,16 +292,10 @@ pub async fn logout(
         .get("refresh_token")
         .map(|cookie| cookie.value().to_string())
         .ok_or_else(|| {
-            (
-                StatusCode::UNAUTHORIZED,
-                "Missing refresh token cookie".to_string(),
-            )
+            ErrorResponse::unauthorized("MISSING_REFRESH_TOKEN", "Missing refresh token cookie")
         })?;
 
-    let jwt_service = JwtService::new_with_fallbacks(
-        &state.config.jwt_secret,
-        &state.config.jwt_fallback_secrets,
-    );
+    let jwt_service = JwtService::from_config(&state.config);
     let service = AuthService::new(state.mongo.clone(), state.redis.clone(), jwt_service);
     let audit_service = AuditService::new(state.mongo.clone());
 
@@ -289,22 +321,30 @@ pub async fn logout(
         }
         Err(e) => {
             tracing::error!("Failed to logout: {}", e);
-            Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+            Err(ErrorResponse::internal(e.to_string()))
         }
     }
 }
 
 /// GET /api/v1/auth/me - Get current user profile (protected)
+#[utoipa::path(
+    get,
+    path = "/api/v1/auth/me",
+    tag = "auth",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Профиль текущего пользователя", body = UserProfile),
+        (status = 401, description = "Нет или недействителен access-токен"),
+        (status = 404, description = "Пользователь не найден", body = ErrorResponse),
+    )
+)]
 pub async fn get_current_user(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, ErrorResponse> {
     tracing::debug!("Getting current user profile for user_id: {}", claims.sub);
 
-    let jwt_service = JwtService::new_with_fallbacks(
-        &state.config.jwt_secret,
-        &state.config.jwt_fallback_secrets,
-    );
+    let jwt_service = JwtService::from_config(&state.config);
     let service = AuthService::new(state.mongo.clone(), state.redis.clone(), jwt_service);
 
     match service.get_user_by_id(&claims.sub).await {
@@ -314,39 +354,98 @@ pub async fn get_current_user(
         }
         Err(e) => {
             tracing::error!("Failed to get user: {}", e);
-            Err((StatusCode::NOT_FOUND, e.to_string()))
+            Err(ErrorResponse::not_found("USER_NOT_FOUND", e.to_string()))
+        }
+    }
+}
+
+/// PATCH /api/v1/auth/me - Update current user profile (protected)
+#[utoipa::path(
+    patch,
+    path = "/api/v1/auth/me",
+    tag = "auth",
+    request_body = UpdateProfileRequest,
+    security(("bearer_auth" = [], "csrf_token" = [])),
+    responses(
+        (status = 200, description = "Обновлённый профиль", body = UserProfile),
+        (status = 400, description = "Ошибка валидации", body = ErrorResponse),
+        (status = 401, description = "Нет или недействителен access-токен"),
+        (status = 404, description = "Пользователь не найден", body = ErrorResponse),
+    )
+)]
+pub async fn update_current_user(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    AppJson(req): AppJson<UpdateProfileRequest>,
+) -> Result<impl IntoResponse, ErrorResponse> {
+    if let Err(e) = req.validate() {
+        return Err(ErrorResponse::validation(&e));
+    }
+
+    let jwt_service = JwtService::from_config(&state.config);
+    let service = AuthService::new(state.mongo.clone(), state.redis.clone(), jwt_service);
+
+    match service.update_profile(&claims.sub, req).await {
+        Ok(user) => Ok((StatusCode::OK, Json(UserProfile::from(user)))),
+        Err(e) => {
+            tracing::error!("Failed to update profile: {}", e);
+            Err(ErrorResponse::not_found("USER_NOT_FOUND", e.to_string()))
         }
     }
 }
 
 /// GET /api/v1/auth/sessions - Get active sessions (protected)
+#[utoipa::path(
+    get,
+    path = "/api/v1/auth/sessions",
+    tag = "auth",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Активные сессии, последние использованные - первыми", body = Vec<ActiveSession>),
+        (status = 401, description = "Нет или недействителен access-токен"),
+    )
+)]
 pub async fn get_active_sessions(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+    jar: CookieJar,
+) -> Result<impl IntoResponse, ErrorResponse> {
     tracing::debug!("Getting active sessions for user_id: {}", claims.sub);
 
-    let jwt_service = JwtService::new_with_fallbacks(
-        &state.config.jwt_secret,
-        &state.config.jwt_fallback_secrets,
-    );
+    let jwt_service = JwtService::from_config(&state.config);
     let service = AuthService::new(state.mongo.clone(), state.redis.clone(), jwt_service);
 
-    match service.get_active_sessions(&claims.sub, None).await {
+    // Текущая сессия определяется по refresh_token из cookie
+    let current_token = jar.get("refresh_token").map(|cookie| cookie.value());
+
+    match service
+        .get_active_sessions(&claims.sub, current_token)
+        .await
+    {
         Ok(sessions) => Ok((StatusCode::OK, Json(sessions))),
         Err(e) => {
             tracing::error!("Failed to get sessions: {}", e);
-            Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+            Err(ErrorResponse::internal(e.to_string()))
         }
     }
 }
 
 /// POST /api/v1/auth/sessions/revoke - Revoke all sessions except current (protected)
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/sessions/revoke",
+    tag = "auth",
+    security(("bearer_auth" = [], "csrf_token" = [], "refresh_cookie" = [])),
+    responses(
+        (status = 200, description = "Число отозванных сессий: `{\"revoked_count\": n}`", body = Object),
+        (status = 401, description = "Нет refresh-токена", body = ErrorResponse),
+    )
+)]
 pub async fn revoke_other_sessions(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
     jar: CookieJar,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, ErrorResponse> {
     tracing::info!("Revoking other sessions for user_id: {}", claims.sub);
 
     // Read refresh_token from HTTP-only cookie
@@ -354,16 +453,10 @@ pub async fn revoke_other_sessions(
         .get("refresh_token")
         .map(|cookie| cookie.value().to_string())
         .ok_or_else(|| {
-            (
-                StatusCode::UNAUTHORIZED,
-                "Missing refresh token cookie".to_string(),
-            )
+            ErrorResponse::unauthorized("MISSING_REFRESH_TOKEN", "Missing refresh token cookie")
         })?;
 
-    let jwt_service = JwtService::new_with_fallbacks(
-        &state.config.jwt_secret,
-        &state.config.jwt_fallback_secrets,
-    );
+    let jwt_service = JwtService::from_config(&state.config);
     let service = AuthService::new(state.mongo.clone(), state.redis.clone(), jwt_service);
 
     match service
@@ -379,28 +472,80 @@ pub async fn revoke_other_sessions(
         }
         Err(e) => {
             tracing::error!("Failed to revoke sessions: {}", e);
-            Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+            Err(ErrorResponse::internal(e.to_string()))
+        }
+    }
+}
+
+/// POST /api/v1/auth/sessions/{session_id}/revoke - Revoke one session (protected)
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/sessions/{session_id}/revoke",
+    tag = "auth",
+    params(("session_id" = String, Path, description = "Id сессии из `GET /api/v1/auth/sessions`")),
+    security(("bearer_auth" = [], "csrf_token" = [])),
+    responses(
+        (status = 200, description = "Сессия отозвана: `{\"revoked_count\": 1}`", body = Object),
+        (status = 400, description = "Некорректный id", body = ErrorResponse),
+        (status = 404, description = "Сессия не найдена или уже отозвана", body = ErrorResponse),
+    )
+)]
+pub async fn revoke_session(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    ObjectIdParam(session_id): ObjectIdParam,
+) -> Result<impl IntoResponse, ErrorResponse> {
+    tracing::info!(
+        "Revoking session {} for user_id: {}",
+        session_id.to_hex(),
+        claims.sub
+    );
+
+    let jwt_service = JwtService::from_config(&state.config);
+    let service = AuthService::new(state.mongo.clone(), state.redis.clone(), jwt_service);
+
+    match service.revoke_session(&claims.sub, session_id).await {
+        Ok(true) => Ok((
+            StatusCode::OK,
+            Json(serde_json::json!({ "revoked_count": 1 })),
+        )),
+        Ok(false) => Err(ErrorResponse::not_found(
+            "SESSION_NOT_FOUND",
+            "Session not found or already revoked",
+        )),
+        Err(e) => {
+            tracing::error!("Failed to revoke session: {}", e);
+            Err(ErrorResponse::internal(e.to_string()))
         }
     }
 }
 
 /// POST /api/v1/auth/change-password - Change password (protected)
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/change-password",
+    tag = "auth",
+    request_body = ChangePasswordRequest,
+    security(("bearer_auth" = [], "csrf_token" = [])),
+    responses(
+        (status = 200, description = "Пароль изменён", body = Object),
+        (status = 400, description = "Ошибка валидации", body = ErrorResponse),
+        (status = 401, description = "Неверный текущий пароль", body = ErrorResponse),
+    )
+)]
 pub async fn change_password(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
     AppJson(req): AppJson<ChangePasswordRequest>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, ErrorResponse> {
     // Validate request
     if let Err(e) = req.validate() {
-        return Err((StatusCode::BAD_REQUEST, format!("Validation error: {}", e)));
+        return Err(ErrorResponse::validation(&e));
     }
 
     tracing::info!("Changing password for user_id: {}", claims.sub);
 
-    let jwt_service = JwtService::new_with_fallbacks(
-        &state.config.jwt_secret,
-        &state.config.jwt_fallback_secrets,
-    );
+    let jwt_service = JwtService::from_config(&state.config);
     let service = AuthService::new(state.mongo.clone(), state.redis.clone(), jwt_service);
     let audit_service = AuditService::new(state.mongo.clone());
 
@@ -408,12 +553,12 @@ pub async fn change_password(
     let user = service
         .get_user_by_id(&claims.sub)
         .await
-        .map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))?;
+        .map_err(|e| ErrorResponse::not_found("USER_NOT_FOUND", e.to_string()))?;
 
     // Verify old password
     if !service
         .verify_password(&req.old_password, &user.password_hash)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| ErrorResponse::internal(e.to_string()))?
     {
         // Log failed password change
         let _ = audit_service
@@ -426,18 +571,21 @@ pub async fn change_password(
             )
             .await;
 
-        return Err((StatusCode::UNAUTHORIZED, "Invalid old password".to_string()));
+        return Err(ErrorResponse::unauthorized(
+            "INVALID_PASSWORD",
+            "Invalid old password",
+        ));
     }
 
     // Hash new password
     let new_password_hash = service
         .hash_password(&req.new_password)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(|e| ErrorResponse::internal(e.to_string()))?;
 
     // Update password in database
     use mongodb::bson::{doc, oid::ObjectId};
-    let user_id = ObjectId::parse_str(&claims.sub)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let user_id =
+        ObjectId::parse_str(&claims.sub).map_err(|e| ErrorResponse::internal(e.to_string()))?;
 
     let users_collection = state.mongo.collection::<mongodb::bson::Document>("users");
     users_collection
@@ -447,16 +595,13 @@ pub async fn change_password(
                 "$set": {
                     "password_hash": new_password_hash,
                     "updatedAt": mongodb::bson::DateTime::now()
-                }
+                },
+                // Пароль выбран владельцем - seed-пароль больше не действует
+                "$unset": { crate::services::superuser_seed::SEED_PASSWORD_FIELD: "" }
             },
         )
         .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to update password: {}", e),
-            )
-        })?;
+        .map_err(|e| ErrorResponse::internal(format!("Failed to update password: {}", e)))?;
 
     tracing::info!("Password changed successfully for user_id: {}", claims.sub);
 
@@ -475,7 +620,7 @@ pub async fn change_password(
 pub async fn list_users(
     State(state): State<Arc<AppState>>,
     axum::extract::Query(query): axum::extract::Query<ListUsersQuery>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, ErrorResponse> {
     use mongodb::bson::{doc, Document};
 
     tracing::debug!("Listing users with filters: {:?}", query);
@@ -518,14 +663,14 @@ pub async fn list_users(
         .await
         .map_err(|e| {
             tracing::error!("Failed to query users: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+            ErrorResponse::internal(e.to_string())
         })?;
 
     use futures::stream::TryStreamExt;
     let mut users = Vec::new();
     while let Some(user) = cursor.try_next().await.map_err(|e| {
         tracing::error!("Failed to read user from cursor: {}", e);
-        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        ErrorResponse::internal(e.to_string())
     })? {
         users.push(UserProfile::from(user));
     }
@@ -537,19 +682,16 @@ pub async fn list_users(
 pub async fn get_user_by_id_admin(
     State(state): State<Arc<AppState>>,
     axum::extract::Path(user_id): axum::extract::Path<String>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, ErrorResponse> {
     tracing::debug!("Getting user by ID: {}", user_id);
 
-    let jwt_service = JwtService::new_with_fallbacks(
-        &state.config.jwt_secret,
-        &state.config.jwt_fallback_secrets,
-    );
+    let jwt_service = JwtService::from_config(&state.config);
     let service = AuthService::new(state.mongo.clone(), state.redis.clone(), jwt_service);
 
     let user = service
         .get_user_by_id(&user_id)
         .await
-        .map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))?;
+        .map_err(|e| ErrorResponse::not_found("USER_NOT_FOUND", e.to_string()))?;
 
     Ok(Json(UserProfile::from(user)))
 }
@@ -557,20 +699,17 @@ pub async fn get_user_by_id_admin(
 /// PATCH /api/v1/users/:id - Update user (admin only)
 pub async fn update_user(
     State(state): State<Arc<AppState>>,
-    axum::extract::Path(user_id): axum::extract::Path<String>,
+    ObjectIdParam(object_id): ObjectIdParam,
     AppJson(req): AppJson<UpdateUserRequest>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, ErrorResponse> {
     // Validate request
     if let Err(e) = req.validate() {
-        return Err((StatusCode::BAD_REQUEST, format!("Validation error: {}", e)));
+        return Err(ErrorResponse::validation(&e));
     }
 
-    tracing::info!("Updating user: {}", user_id);
+    tracing::info!("Updating user: {}", object_id);
 
-    use mongodb::bson::{doc, oid::ObjectId, Document};
-
-    let object_id = ObjectId::parse_str(&user_id)
-        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid user ID: {}", e)))?;
+    use mongodb::bson::{doc, Document};
 
     // Build update document
     let mut update_fields = Document::new();
@@ -591,10 +730,17 @@ pub async fn update_user(
         update_fields.insert("is_blocked", is_blocked);
     }
 
+    if let Some(birth_year) = req.birth_year {
+        update_fields.insert("birthYear", birth_year);
+    }
+
     update_fields.insert("updatedAt", mongodb::bson::DateTime::now());
 
     if update_fields.len() <= 1 {
-        return Err((StatusCode::BAD_REQUEST, "No fields to update".to_string()));
+        return Err(ErrorResponse::bad_request(
+            "NO_FIELDS_TO_UPDATE",
+            "No fields to update",
+        ));
     }
 
     let users_collection = state.mongo.collection::<User>("users");
@@ -603,33 +749,38 @@ pub async fn update_user(
         .await
         .map_err(|e| {
             tracing::error!("Failed to update user: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+            ErrorResponse::internal(e.to_string())
         })?;
 
     if result.matched_count == 0 {
-        return Err((StatusCode::NOT_FOUND, "User not found".to_string()));
+        return Err(ErrorResponse::not_found("USER_NOT_FOUND", "User not found"));
     }
 
-    tracing::info!("User updated successfully: {}", user_id);
+    tracing::info!("User updated successfully: {}", object_id);
 
     // Fetch and return updated user
-    let jwt_service = JwtService::new_with_fallbacks(
-        &state.config.jwt_secret,
-        &state.config.jwt_fallback_secrets,
-    );
+    let jwt_service = JwtService::from_config(&state.config);
     let service = AuthService::new(state.mongo.clone(), state.redis.clone(), jwt_service);
 
     let updated_user = service
-        .get_user_by_id(&user_id)
+        .get_user_by_id(&object_id.to_hex())
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(|e| ErrorResponse::internal(e.to_string()))?;
 
     Ok(Json(UserProfile::from(updated_user)))
 }
 
 /// GET /api/v1/auth/csrf-token - Get CSRF token for authenticated requests
 /// Returns CSRF token in both JSON response and as a cookie
-pub async fn get_csrf_token() -> Result<impl IntoResponse, (StatusCode, String)> {
+#[utoipa::path(
+    get,
+    path = "/api/v1/auth/csrf-token",
+    tag = "auth",
+    responses(
+        (status = 200, description = "CSRF-токен: `{\"csrf_token\": \"...\"}`, он же - в cookie `csrf_token`", body = Object),
+    )
+)]
+pub async fn get_csrf_token() -> Result<impl IntoResponse, ErrorResponse> {
     use crate::middlewares::csrf::{generate_csrf_token, set_csrf_cookie};
     use axum::response::Response;
 
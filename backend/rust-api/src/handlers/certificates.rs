@@ -0,0 +1,89 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+
+use crate::{
+    handlers::error::ErrorResponse,
+    middlewares::auth::JwtClaims,
+    models::certificate::CertificatesResponse,
+    services::{
+        certificate_service::{CertificateNotEarnedError, CertificateService},
+        AppState,
+    },
+};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/me/certificates",
+    tag = "certificates",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Уровни, пройденные не ниже проходного процента", body = CertificatesResponse),
+    )
+)]
+pub async fn list_certificates(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<Json<CertificatesResponse>, ErrorResponse> {
+    CertificateService::new(state.mongo.clone())
+        .list(&claims.sub)
+        .await
+        .map(|certificates| Json(CertificatesResponse { certificates }))
+        .map_err(|e| ErrorResponse::internal(e.to_string()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/me/certificates/{level_id}/pdf",
+    tag = "certificates",
+    params(("level_id" = String, Path, description = "Id уровня")),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Сертификат на одну страницу", content_type = "application/pdf", body = Vec<u8>),
+        (status = 403, description = "Уровень не пройден", body = ErrorResponse),
+    )
+)]
+pub async fn download_certificate(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    Path(level_id): Path<String>,
+) -> Result<Response, ErrorResponse> {
+    let service = CertificateService::new(state.mongo.clone());
+    let certificate = service
+        .certificate(&claims.sub, &level_id)
+        .await
+        .map_err(|e| match e.downcast_ref::<CertificateNotEarnedError>() {
+            Some(not_earned) => {
+                ErrorResponse::forbidden("CERTIFICATE_NOT_EARNED", not_earned.to_string())
+            }
+            None => ErrorResponse::internal(e.to_string()),
+        })?;
+    let bytes = service
+        .pdf(
+            &claims.sub,
+            &certificate,
+            state.certificate_storage.as_deref(),
+        )
+        .await
+        .map_err(|e| ErrorResponse::internal(e.to_string()))?;
+
+    let mut response = (StatusCode::OK, bytes).into_response();
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/pdf"),
+    );
+    if let Ok(disposition) = HeaderValue::from_str(&format!(
+        "attachment; filename=\"certificate-{}.pdf\"",
+        certificate.level_id
+    )) {
+        response
+            .headers_mut()
+            .insert(header::CONTENT_DISPOSITION, disposition);
+    }
+    Ok(response)
+}
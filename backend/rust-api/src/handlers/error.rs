@@ -0,0 +1,134 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use utoipa::ToSchema;
+use validator::ValidationErrors;
+
+/// Единый формат ошибки API: `{"code": "...", "message": "...", "details": ...}`.
+///
+/// `code` - стабильный машиночитаемый идентификатор (например, `TEMPLATE_NOT_FOUND`),
+/// `message` - человекочитаемое описание, `details` - необязательные подробности
+/// (например, ошибки по полям для `VALIDATION_ERROR`).
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorResponse {
+    #[serde(skip)]
+    pub status: StatusCode,
+    pub code: &'static str,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+}
+
+impl ErrorResponse {
+    pub fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code,
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    pub fn bad_request(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, code, message)
+    }
+
+    pub fn unauthorized(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(StatusCode::UNAUTHORIZED, code, message)
+    }
+
+    pub fn forbidden(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(StatusCode::FORBIDDEN, code, message)
+    }
+
+    pub fn not_found(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, code, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "INTERNAL_ERROR", message)
+    }
+
+    /// 400 `VALIDATION_ERROR` with per-field messages from `validator` in `details`.
+    pub fn validation(errors: &ValidationErrors) -> Self {
+        Self::bad_request("VALIDATION_ERROR", "Validation failed")
+            .with_details(validation_details(errors))
+    }
+}
+
+impl IntoResponse for ErrorResponse {
+    fn into_response(self) -> Response {
+        (self.status, Json(self)).into_response()
+    }
+}
+
+/// `{"field": ["message", ...]}`; falls back to the validator code when no message is set.
+fn validation_details(errors: &ValidationErrors) -> Value {
+    let mut fields = Map::new();
+    for (field, field_errors) in errors.field_errors() {
+        let messages = field_errors
+            .iter()
+            .map(|error| {
+                let text = error
+                    .message
+                    .as_ref()
+                    .map(|message| message.to_string())
+                    .unwrap_or_else(|| error.code.to_string());
+                Value::String(text)
+            })
+            .collect();
+        fields.insert(field.to_string(), Value::Array(messages));
+    }
+    Value::Object(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use validator::Validate;
+
+    #[derive(Validate)]
+    struct Payload {
+        #[validate(email(message = "Invalid email format"))]
+        email: String,
+        #[validate(length(min = 8))]
+        password: String,
+    }
+
+    #[test]
+    fn validation_error_lists_each_field() {
+        let payload = Payload {
+            email: "not-an-email".into(),
+            password: "short".into(),
+        };
+        let error = ErrorResponse::validation(&payload.validate().unwrap_err());
+
+        assert_eq!(error.status, StatusCode::BAD_REQUEST);
+        assert_eq!(error.code, "VALIDATION_ERROR");
+        let details = error.details.unwrap();
+        assert_eq!(details["email"][0], "Invalid email format");
+        assert_eq!(details["password"][0], "length");
+    }
+
+    #[test]
+    fn details_are_omitted_when_absent() {
+        let body = serde_json::to_value(ErrorResponse::not_found(
+            "TEMPLATE_NOT_FOUND",
+            "Template not found",
+        ))
+        .unwrap();
+        assert_eq!(body["code"], "TEMPLATE_NOT_FOUND");
+        assert_eq!(body["message"], "Template not found");
+        assert!(body.get("details").is_none());
+        assert!(body.get("status").is_none());
+    }
+}
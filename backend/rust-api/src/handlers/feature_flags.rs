@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Query, State},
+    extract::{Extension, Query, State},
     http::StatusCode,
     response::IntoResponse,
     Json,
@@ -9,7 +9,10 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use tracing::warn;
 
-use crate::services::AppState;
+use crate::handlers::error::ErrorResponse;
+use crate::middlewares::auth::JwtClaims;
+use crate::services::{feature_flag_service::FeatureFlagService, AppState};
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 #[derive(Debug, Deserialize)]
@@ -30,6 +33,26 @@ pub struct FlagInfo {
     pub config: serde_json::Value,
 }
 
+#[derive(Debug, Serialize)]
+pub struct ResolvedFeatureFlagsResponse {
+    pub flags: BTreeMap<String, bool>,
+}
+
+/// GET /api/v1/feature-flags - Resolved flag_key -> enabled map for the current user
+/// (user ID, role and percentage targeting are applied on the server)
+pub async fn get_user_feature_flags(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<Json<ResolvedFeatureFlagsResponse>, ErrorResponse> {
+    let service = FeatureFlagService::new(state.mongo.clone(), Some(state.redis.clone()));
+    let flags = service.resolve_all(&claims).await.map_err(|e| {
+        warn!("Failed to resolve feature flags: {}", e);
+        ErrorResponse::internal("Failed to fetch feature flags")
+    })?;
+
+    Ok(Json(ResolvedFeatureFlagsResponse { flags }))
+}
+
 /// GET /api/feature-flags - Get active feature flags for user
 /// Query parameters:
 /// - user_id: Optional user ID for user-scoped flags
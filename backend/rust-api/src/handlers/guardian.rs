@@ -0,0 +1,139 @@
+//! Согласия, которые родитель даёт и отзывает сам по ссылке из
+//! `POST /admin/users/{id}/consents/guardian-link`. Учётной записи у родителя нет:
+//! доступ даёт только токен ссылки, поэтому маршруты публичные.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use mongodb::bson::oid::ObjectId;
+use validator::Validate;
+
+use crate::{
+    extractors::AppJson,
+    handlers::error::ErrorResponse,
+    models::consent::{
+        ConsentResponse, GuardianConsentLink, GuardianConsentRequest, UserConsentStatus,
+    },
+    services::{
+        audit_service::AuditService,
+        consent_service::{ConsentService, GuardianLinkError},
+        AppState,
+    },
+};
+
+/// GET /api/v1/guardian/consents/{token} - История согласий ученика и недостающие согласия
+pub async fn get_guardian_consents(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+) -> Result<Json<UserConsentStatus>, ErrorResponse> {
+    let service = ConsentService::new(state.mongo.clone());
+    let link = resolve_link(&service, &token).await?;
+    let user_obj = link_user_id(&link)?;
+
+    let consents = service
+        .list_consents(&user_obj)
+        .await
+        .map_err(|e| ErrorResponse::internal(e.to_string()))?;
+    let missing = service
+        .missing_for_student(&link.user_id)
+        .await
+        .map_err(|e| ErrorResponse::internal(e.to_string()))?;
+
+    Ok(Json(UserConsentStatus {
+        user_id: link.user_id,
+        consents: consents.into_iter().map(ConsentResponse::from).collect(),
+        missing,
+    }))
+}
+
+/// POST /api/v1/guardian/consents/{token} - Родитель даёт согласие
+pub async fn record_guardian_consent(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+    AppJson(payload): AppJson<GuardianConsentRequest>,
+) -> Result<impl IntoResponse, ErrorResponse> {
+    payload
+        .validate()
+        .map_err(|errors| ErrorResponse::validation(&errors))?;
+
+    let service = ConsentService::new(state.mongo.clone());
+    let link = resolve_link(&service, &token).await?;
+    let record = service
+        .record_guardian_consent(&link, payload)
+        .await
+        .map_err(|e| {
+            let msg = e.to_string();
+            if msg.contains("not found") {
+                ErrorResponse::not_found("USER_NOT_FOUND", msg)
+            } else {
+                ErrorResponse::internal(msg)
+            }
+        })?;
+
+    let audit_service = AuditService::new(state.mongo.clone());
+    let _ = audit_service
+        .log_consent_record(
+            &link.actor(),
+            &link.user_id,
+            &record.consent_type,
+            &record.document_version,
+        )
+        .await;
+
+    Ok((StatusCode::CREATED, Json(ConsentResponse::from(record))))
+}
+
+/// DELETE /api/v1/guardian/consents/{token}/{consent_type} - Родитель отзывает согласие
+pub async fn revoke_guardian_consent(
+    State(state): State<Arc<AppState>>,
+    Path((token, consent_type)): Path<(String, String)>,
+) -> Result<StatusCode, ErrorResponse> {
+    let service = ConsentService::new(state.mongo.clone());
+    let link = resolve_link(&service, &token).await?;
+    let user_obj = link_user_id(&link)?;
+
+    service
+        .revoke_consent(&user_obj, &consent_type, &link.actor())
+        .await
+        .map_err(|e| {
+            let msg = e.to_string();
+            if msg.contains("not found") {
+                ErrorResponse::not_found("CONSENT_NOT_FOUND", msg)
+            } else {
+                ErrorResponse::internal(msg)
+            }
+        })?;
+
+    let audit_service = AuditService::new(state.mongo.clone());
+    let _ = audit_service
+        .log_consent_revoke(&link.actor(), &link.user_id, &consent_type)
+        .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn resolve_link(
+    service: &ConsentService,
+    token: &str,
+) -> Result<GuardianConsentLink, ErrorResponse> {
+    service.resolve_guardian_link(token).await.map_err(|e| {
+        let message = e.to_string();
+        match e.downcast_ref::<GuardianLinkError>() {
+            Some(GuardianLinkError::Expired) => {
+                ErrorResponse::new(StatusCode::GONE, "GUARDIAN_LINK_EXPIRED", message)
+            }
+            Some(_) => ErrorResponse::not_found("GUARDIAN_LINK_NOT_FOUND", message),
+            None => ErrorResponse::internal(message),
+        }
+    })
+}
+
+fn link_user_id(link: &GuardianConsentLink) -> Result<ObjectId, ErrorResponse> {
+    ObjectId::parse_str(&link.user_id)
+        .map_err(|_| ErrorResponse::internal("Guardian link has an invalid user ID"))
+}
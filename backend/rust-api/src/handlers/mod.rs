@@ -1,51 +1,136 @@
 use axum::{
-    extract::{Request, State},
+    extract::{Query, Request, State},
     http::{header, HeaderMap, StatusCode},
     middleware::Next,
     response::{IntoResponse, Response},
     Json,
 };
 use base64::{engine::general_purpose, Engine as _};
+use chrono::Utc;
+use serde::Deserialize;
 use serde_json::json;
 use std::sync::Arc;
 
 use crate::metrics;
-use crate::services::AppState;
+use crate::middlewares::auth::JwtService;
+use crate::services::{maintenance_mode, reporting_service::ReportingService, AppState};
+use crate::utils::mongo_retry;
 
-pub async fn health_check(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    let mut status = "healthy";
+/// Параметры `/health`
+#[derive(Debug, Deserialize)]
+pub struct HealthQuery {
+    /// `false` - только общий статус, для дешёвых проб балансировщика
+    #[serde(default = "default_health_verbose")]
+    pub verbose: bool,
+}
+
+fn default_health_verbose() -> bool {
+    true
+}
+
+pub async fn health_check(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<HealthQuery>,
+) -> impl IntoResponse {
     let mut dependencies = serde_json::Map::new();
-    let mut all_healthy = true;
-
-    // Check MongoDB
-    let mongo_health = check_mongodb(&state).await;
-    dependencies.insert("mongodb".to_string(), json!(mongo_health));
-    if mongo_health.get("status").and_then(|v| v.as_str()) != Some("healthy") {
-        all_healthy = false;
-        status = "degraded";
-    }
+    dependencies.insert("mongodb".to_string(), json!(check_mongodb(&state).await));
+    dependencies.insert("redis".to_string(), json!(check_redis(&state).await));
+    dependencies.insert(
+        "object_storage".to_string(),
+        json!(check_object_storage(&state).await),
+    );
+    dependencies.insert(
+        "export_worker".to_string(),
+        json!(check_export_worker(&state).await),
+    );
 
-    // Check Redis
-    let redis_health = check_redis(&state).await;
-    dependencies.insert("redis".to_string(), json!(redis_health));
-    if redis_health.get("status").and_then(|v| v.as_str()) != Some("healthy") {
-        all_healthy = false;
-        status = "degraded";
-    }
+    let status_of = |name: &str| {
+        dependencies
+            .get(name)
+            .and_then(|dependency| dependency.get("status"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("unhealthy")
+    };
+    // 503 - только без Mongo или Redis; хранилище и export worker лишь деградируют
+    // сервис, и балансировщик не должен выводить из-за них реплику
+    let critical_down = ["mongodb", "redis"]
+        .iter()
+        .any(|name| status_of(name) == "unhealthy");
+    // Ненастроенная зависимость не считается сбоем
+    let all_healthy = dependencies
+        .keys()
+        .all(|name| matches!(status_of(name), "healthy" | "not_configured"));
 
-    let status_code = if all_healthy {
-        StatusCode::OK
+    let (status_code, status) = if critical_down {
+        (StatusCode::SERVICE_UNAVAILABLE, "unhealthy")
+    } else if all_healthy {
+        (StatusCode::OK, "healthy")
     } else {
-        StatusCode::SERVICE_UNAVAILABLE
+        (StatusCode::OK, "degraded")
     };
 
+    if !query.verbose {
+        return (status_code, Json(json!({ "status": status })));
+    }
+    // Режим обслуживания не делает реплику нездоровой: балансировщик не должен её выводить
+    let maintenance = matches!(maintenance_mode::load(&state.redis).await, Ok(Some(_)));
+
     (
         status_code,
         Json(json!({
             "status": status,
+            "maintenance": maintenance,
             "service": "trainingground-api",
             "version": env!("CARGO_PKG_VERSION"),
-            "dependencies": dependencies
+            "dependencies": dependencies,
+            // Ключ, которым подписываются новые токены: подтверждает, что ротация применилась
+            "jwt": {
+                "active_kid": JwtService::from_config(&state.config).active_key_id(),
+            }
+        })),
+    )
+}
+
+/// Liveness: процесс жив и роутер отвечает. Зависимости не проверяются, чтобы
+/// кратковременный сбой Mongo не приводил к перезапуску пода
+pub async fn liveness_check() -> impl IntoResponse {
+    (StatusCode::OK, Json(json!({ "status": "alive" })))
+}
+
+/// Readiness: стартовые задачи завершены и Mongo/Redis доступны
+pub async fn readiness_check(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let initialized = state.is_ready();
+    // Хранилище настроено, но клиент не собрался - выгрузки и архивы не работают
+    let object_storage_client =
+        state.config.object_storage.is_none() || state.object_storage.is_some();
+    let mongodb = check_mongodb(&state).await;
+    let redis = check_redis(&state).await;
+
+    // degraded у Mongo - доступна, но недавние операции исчерпали ретраи; трафик принимаем
+    let reachable = |dependency: &serde_json::Map<String, serde_json::Value>| {
+        matches!(
+            dependency.get("status").and_then(|v| v.as_str()),
+            Some("healthy") | Some("degraded")
+        )
+    };
+    let ready = initialized && object_storage_client && reachable(&mongodb) && reachable(&redis);
+
+    let (status_code, status) = if ready {
+        (StatusCode::OK, "ready")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "not_ready")
+    };
+
+    (
+        status_code,
+        Json(json!({
+            "status": status,
+            "checks": {
+                "initialized": initialized,
+                "object_storage_client": object_storage_client,
+                "mongodb": mongodb,
+                "redis": redis,
+            }
         })),
     )
 }
@@ -59,6 +144,13 @@ async fn check_mongodb(state: &AppState) -> serde_json::Map<String, serde_json::
     )
     .await
     {
+        Ok(Ok(_)) if mongo_retry::is_degraded() => {
+            result.insert("status".to_string(), json!("degraded"));
+            result.insert(
+                "message".to_string(),
+                json!("MongoDB reachable but recent operations exhausted retries"),
+            );
+        }
         Ok(Ok(_)) => {
             result.insert("status".to_string(), json!("healthy"));
             result.insert(
@@ -106,6 +198,88 @@ async fn check_redis(state: &AppState) -> serde_json::Map<String, serde_json::Va
     result
 }
 
+async fn check_object_storage(state: &AppState) -> serde_json::Map<String, serde_json::Value> {
+    let mut result = serde_json::Map::new();
+
+    let Some(storage) = state.object_storage.as_ref() else {
+        result.insert("status".to_string(), json!("not_configured"));
+        return result;
+    };
+
+    match tokio::time::timeout(std::time::Duration::from_secs(1), storage.head_bucket()).await {
+        Ok(Ok(())) => {
+            result.insert("status".to_string(), json!("healthy"));
+            result.insert("message".to_string(), json!("Bucket is reachable"));
+        }
+        Ok(Err(e)) => {
+            result.insert("status".to_string(), json!("degraded"));
+            result.insert(
+                "error".to_string(),
+                json!(format!("Object storage error: {:#}", e)),
+            );
+        }
+        Err(_) => {
+            result.insert("status".to_string(), json!("degraded"));
+            result.insert(
+                "error".to_string(),
+                json!("Object storage timeout after 1s"),
+            );
+        }
+    }
+
+    result
+}
+
+/// Живость export worker по heartbeat в Redis: воркер пишет его каждый тик
+async fn check_export_worker(state: &AppState) -> serde_json::Map<String, serde_json::Value> {
+    let mut result = serde_json::Map::new();
+
+    let reporting = ReportingService::new(state.mongo.clone(), state.redis.clone());
+    let heartbeat = match reporting.export_worker_heartbeat().await {
+        Ok(heartbeat) => heartbeat,
+        Err(e) => {
+            result.insert("status".to_string(), json!("degraded"));
+            result.insert("error".to_string(), json!(format!("{:#}", e)));
+            return result;
+        }
+    };
+
+    let Some(last_heartbeat) = heartbeat else {
+        // Без объектного хранилища воркер не запускается, отсутствие heartbeat ожидаемо
+        if state.object_storage.is_none() {
+            result.insert("status".to_string(), json!("not_configured"));
+        } else {
+            result.insert("status".to_string(), json!("degraded"));
+            result.insert(
+                "error".to_string(),
+                json!("Export worker has not reported a heartbeat"),
+            );
+        }
+        return result;
+    };
+
+    // Пропуск трёх тиков подряд считается остановкой воркера
+    let max_age = chrono::Duration::seconds(
+        (state.config.reporting.export_worker_interval_secs.max(1) * 3) as i64,
+    );
+    let age = Utc::now() - last_heartbeat;
+    result.insert("last_heartbeat".to_string(), json!(last_heartbeat));
+    if age > max_age {
+        result.insert("status".to_string(), json!("degraded"));
+        result.insert(
+            "error".to_string(),
+            json!(format!(
+                "Export worker heartbeat is stale ({}s old)",
+                age.num_seconds()
+            )),
+        );
+    } else {
+        result.insert("status".to_string(), json!("healthy"));
+    }
+
+    result
+}
+
 pub async fn metrics_handler() -> impl IntoResponse {
     match metrics::render_metrics() {
         Ok(metrics_text) => (StatusCode::OK, metrics_text),
@@ -118,6 +292,7 @@ pub async fn metrics_handler() -> impl IntoResponse {
 
 /// Metrics authentication middleware - protects /metrics endpoint with HTTP Basic Auth
 pub async fn metrics_auth_middleware(
+    State(state): State<Arc<AppState>>,
     headers: HeaderMap,
     request: Request,
     next: Next,
@@ -129,23 +304,21 @@ pub async fn metrics_auth_middleware(
         .ok_or(StatusCode::UNAUTHORIZED)?;
 
     // Check if it's Basic auth
-    if !auth_header.starts_with("Basic ") {
-        return Err(StatusCode::UNAUTHORIZED);
-    }
+    let encoded = auth_header
+        .strip_prefix("Basic ")
+        .ok_or(StatusCode::UNAUTHORIZED)?;
 
     // Decode base64 credentials
-    let encoded = &auth_header[6..];
     let decoded = general_purpose::STANDARD
         .decode(encoded)
         .map_err(|_| StatusCode::UNAUTHORIZED)?;
     let credentials = String::from_utf8(decoded).map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let (username, password) = credentials
+        .split_once(':')
+        .ok_or(StatusCode::UNAUTHORIZED)?;
 
-    // Get expected credentials from environment variable
-    // Format: username:password
-    let expected = std::env::var("METRICS_AUTH").unwrap_or_else(|_| "admin:changeme".to_string());
-
-    // Compare credentials
-    if credentials != expected {
+    // Constant-time comparison against Config (plaintext or bcrypt hash)
+    if !state.config.metrics.verify(username, password) {
         return Err(StatusCode::UNAUTHORIZED);
     }
 
@@ -153,10 +326,18 @@ pub async fn metrics_auth_middleware(
     Ok(next.run(request).await)
 }
 
+pub mod achievements;
 pub mod admin;
+pub mod assignments;
 pub mod auth;
+pub mod certificates;
+pub mod error;
 pub mod feature_flags;
+pub mod guardian;
+pub mod notifications;
+pub mod prefetch;
 pub mod reporting;
+pub mod review;
 pub mod sessions;
 pub mod sse;
 pub mod student;
@@ -0,0 +1,144 @@
+use std::{convert::Infallible, sync::Arc, time::Duration};
+
+use axum::{
+    extract::{Query, State},
+    response::sse::{Event, KeepAlive, Sse},
+    Extension, Json,
+};
+use futures::stream::{Stream, StreamExt};
+
+use crate::{
+    extractors::ObjectIdParam,
+    handlers::error::ErrorResponse,
+    middlewares::auth::JwtClaims,
+    models::notification::{
+        InAppNotificationListQuery, InAppNotificationListResponse, InAppNotificationResponse,
+        MarkAllReadResponse,
+    },
+    services::{
+        notification_center_service::{user_notifications_channel, NotificationCenterService},
+        AppState,
+    },
+};
+
+const DEFAULT_LIMIT: u32 = 20;
+const MAX_LIMIT: u32 = 100;
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+fn service(state: &AppState) -> NotificationCenterService {
+    NotificationCenterService::new(state.mongo.clone(), state.redis.clone())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/notifications",
+    tag = "notifications",
+    params(InAppNotificationListQuery),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Уведомления пользователя, новые первыми, и число непрочитанных", body = InAppNotificationListResponse),
+    )
+)]
+pub async fn list_notifications(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    Query(query): Query<InAppNotificationListQuery>,
+) -> Result<Json<InAppNotificationListResponse>, ErrorResponse> {
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let offset = query.offset.unwrap_or(0);
+    let page = service(&state)
+        .list(&claims.sub, query.unread_only, limit, offset)
+        .await
+        .map_err(|e| ErrorResponse::internal(e.to_string()))?;
+
+    Ok(Json(InAppNotificationListResponse {
+        total: page.total,
+        unread_count: page.unread_count,
+        limit,
+        offset,
+        items: page.items.into_iter().map(Into::into).collect(),
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/notifications/{id}/read",
+    tag = "notifications",
+    params(("id" = String, Path, description = "Id уведомления")),
+    security(("bearer_auth" = [], "csrf_token" = [])),
+    responses(
+        (status = 200, description = "Уведомление отмечено прочитанным", body = InAppNotificationResponse),
+        (status = 400, description = "Некорректный id", body = ErrorResponse),
+        (status = 404, description = "Уведомления нет среди уведомлений пользователя", body = ErrorResponse),
+    )
+)]
+pub async fn mark_notification_read(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    ObjectIdParam(notification_id): ObjectIdParam,
+) -> Result<Json<InAppNotificationResponse>, ErrorResponse> {
+    service(&state)
+        .mark_read(&claims.sub, &notification_id)
+        .await
+        .map_err(|e| ErrorResponse::internal(e.to_string()))?
+        .map(|notification| Json(notification.into()))
+        .ok_or_else(|| ErrorResponse::not_found("NOTIFICATION_NOT_FOUND", "Notification not found"))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/notifications/read-all",
+    tag = "notifications",
+    security(("bearer_auth" = [], "csrf_token" = [])),
+    responses(
+        (status = 200, description = "Сколько уведомлений стало прочитанными", body = MarkAllReadResponse),
+    )
+)]
+pub async fn mark_all_notifications_read(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<Json<MarkAllReadResponse>, ErrorResponse> {
+    let updated = service(&state)
+        .mark_all_read(&claims.sub)
+        .await
+        .map_err(|e| ErrorResponse::internal(e.to_string()))?;
+    Ok(Json(MarkAllReadResponse { updated }))
+}
+
+/// SSE с новыми уведомлениями пользователя
+/// GET /api/v1/notifications/stream
+///
+/// Каждое событие `notification` содержит `InAppNotificationResponse`. Пропущенные
+/// за время разрыва уведомления клиент получает через `GET /api/v1/notifications`.
+pub async fn notifications_stream(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ErrorResponse> {
+    let subscribe = async {
+        let client = redis::Client::open(state.config.redis_uri.clone())?;
+        let mut pubsub = client.get_async_pubsub().await?;
+        pubsub
+            .subscribe(user_notifications_channel(&claims.sub))
+            .await?;
+        Ok::<_, redis::RedisError>(pubsub)
+    };
+    let pubsub = subscribe.await.map_err(|err| {
+        tracing::warn!(
+            "Failed to subscribe to notifications: user={}, error={}",
+            claims.sub,
+            err
+        );
+        ErrorResponse::internal("Notification stream is unavailable")
+    })?;
+
+    let stream = pubsub.into_on_message().filter_map(|message| async move {
+        let payload: String = message.get_payload().ok()?;
+        Some(Ok(Event::default().event("notification").data(payload)))
+    });
+
+    Ok(Sse::new(stream).keep_alive(
+        KeepAlive::new()
+            .interval(KEEPALIVE_INTERVAL)
+            .text("keepalive"),
+    ))
+}
@@ -0,0 +1,32 @@
+use axum::{
+    extract::{Extension, State},
+    http::header,
+    response::IntoResponse,
+    Json,
+};
+use std::sync::Arc;
+
+use crate::{
+    middlewares::auth::JwtClaims,
+    services::{
+        prefetch_service::{build_manifest, StoreSignalSource, MANIFEST_MAX_AGE_SECS},
+        AppState,
+    },
+};
+
+/// GET /api/v1/prefetch-manifest - Что SPA стоит загрузить заранее для текущего пользователя
+pub async fn get_prefetch_manifest(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+) -> impl IntoResponse {
+    let source = StoreSignalSource::new(state.mongo.clone(), state.redis.clone());
+    let manifest = build_manifest(&source, &claims).await;
+
+    (
+        [(
+            header::CACHE_CONTROL,
+            format!("private, max-age={}", MANIFEST_MAX_AGE_SECS),
+        )],
+        Json(manifest),
+    )
+}
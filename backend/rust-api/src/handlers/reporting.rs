@@ -1,43 +1,65 @@
 use std::{sync::Arc, time::Duration};
 
 use axum::{
-    extract::{Extension, Path, State},
+    extract::{Extension, Query, RawQuery, State},
     http::StatusCode,
-    response::IntoResponse,
+    response::{IntoResponse, Response},
     Json,
 };
-use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use chrono::{Duration as ChronoDuration, NaiveTime, Utc};
 use mongodb::bson::{doc, oid::ObjectId};
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
+use serde_json::json;
+use url::form_urlencoded;
+
+use validator::ValidateEmail;
 
 use crate::{
-    extractors::AppJson,
+    extractors::{parse_object_id, AppJson, ObjectIdParam, ObjectIdParams},
+    handlers::error::ErrorResponse,
     middlewares::auth::JwtClaims,
     models::{
+        permission::Permission,
         reporting::{
-            ExportFormat, ExportStatus, LeaderboardDocument, LeaderboardScope, MaterializedStat,
-            NewReportExport, ReportFilters, TimeRange,
+            ExportFormatRequest, ExportListResponse, ExportRequest, ExportResponse, ExportSchedule,
+            ExportScheduleRequest, ExportScheduleResponse, ExportScope, ExportStatus,
+            ExportStatusResponse, GroupStatsResponse, LeaderboardScope, NewReportExport,
+            ReportExport, ReportFilters, TimeRange, TimeRangeRequest, TopicComparisonResponse,
+            TopicGroupBreakdown, TopicStatsResponse, UserStatsResponse, NDJSON_ANSWER_FIELDS,
         },
-        ProgressSummary,
     },
-    services::{reporting_service::ReportingService, AppState},
+    services::{group_service::GroupService, reporting_service::ReportingService, AppState},
 };
-
+use utoipa::IntoParams;
+
+#[utoipa::path(
+    get,
+    path = "/stats/groups/{id}",
+    tag = "reporting",
+    params(("id" = String, Path, description = "Id группы")),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Статистика и рейтинг группы", body = GroupStatsResponse),
+        (status = 403, description = "Нет доступа", body = ErrorResponse),
+        (status = 404, description = "Статистика ещё не посчитана", body = ErrorResponse),
+    )
+)]
 pub(crate) async fn get_group_stats(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
-    Path(group_id): Path<String>,
+    ObjectIdParam(group_obj): ObjectIdParam,
 ) -> Result<Json<GroupStatsResponse>, ApiError> {
-    let group_obj = parse_object_id(&group_id, "group_id")?;
+    let group_id = group_obj.to_hex();
     let service = ReportingService::new(state.mongo.clone(), state.redis.clone());
     service
-        .guard_group_access(&claims, &group_obj)
+        .guard_group_access(&state.role_permissions, &claims, &group_obj)
+        .await
         .map_err(|_| ApiError::forbidden("Access denied for this group"))?;
 
     let stats = service
         .load_group_snapshot(&group_obj)
         .await?
-        .ok_or_else(|| ApiError::not_found("Group statistics not found"))?;
+        .ok_or_else(|| ApiError::not_found("STATS_NOT_FOUND", "Group statistics not found"))?;
     let leaderboard = service
         .load_leaderboard(LeaderboardScope::Group, Some(&group_obj))
         .await?;
@@ -49,14 +71,25 @@ pub(crate) async fn get_group_stats(
     }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/stats/users/{id}",
+    tag = "reporting",
+    params(("id" = String, Path, description = "Id ученика")),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Прогресс ученика по уровням", body = UserStatsResponse),
+        (status = 403, description = "Нет доступа", body = ErrorResponse),
+    )
+)]
 pub(crate) async fn get_user_stats(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
-    Path(user_id): Path<String>,
+    ObjectIdParam(user_obj): ObjectIdParam,
 ) -> Result<Json<UserStatsResponse>, ApiError> {
-    let user_obj = parse_object_id(&user_id, "user_id")?;
+    let user_id = user_obj.to_hex();
     let service = ReportingService::new(state.mongo.clone(), state.redis.clone());
-    if claims.role != "admin" {
+    if !manages_all_groups(&state, &claims).await {
         let group_ids = parse_group_ids(&claims.group_ids)?;
         let allowed = service
             .user_belongs_to_groups(&user_obj, &group_ids)
@@ -71,35 +104,182 @@ pub(crate) async fn get_user_stats(
     Ok(Json(UserStatsResponse { user_id, progress }))
 }
 
+/// Максимум групп для сравнения в `GET /stats/topics/{id}`
+const MAX_COMPARED_GROUPS: usize = 5;
+
+/// Статистика темы. С параметрами `group_id` (повторяемый, до 5) к общим цифрам
+/// добавляется разбивка по группам; доступ нужен к каждой из них.
+#[utoipa::path(
+    get,
+    path = "/stats/topics/{id}",
+    tag = "reporting",
+    params(
+        ("id" = String, Path, description = "Id темы"),
+        ("group_id" = Option<Vec<String>>, Query, description = "Группы для сравнения (до 5); с ними ответ - `TopicComparisonResponse`"),
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Статистика темы; с `group_id` - `TopicComparisonResponse`", body = TopicStatsResponse),
+        (status = 400, description = "Некорректный или лишний group_id", body = ErrorResponse),
+        (status = 403, description = "Нет доступа", body = ErrorResponse),
+        (status = 404, description = "Статистика или группа не найдены", body = ErrorResponse),
+    )
+)]
 pub(crate) async fn get_topic_stats(
     State(state): State<Arc<AppState>>,
-    Path(topic_id): Path<String>,
-) -> Result<Json<TopicStatsResponse>, ApiError> {
-    let topic_obj = parse_object_id(&topic_id, "topic_id")?;
+    Extension(claims): Extension<JwtClaims>,
+    ObjectIdParam(topic_obj): ObjectIdParam,
+    RawQuery(query): RawQuery,
+) -> Result<Response, ApiError> {
+    let topic_id = topic_obj.to_hex();
+    let group_ids = parse_compared_groups(query.as_deref())?;
     let service = ReportingService::new(state.mongo.clone(), state.redis.clone());
+    for group_id in &group_ids {
+        service
+            .guard_group_access(&state.role_permissions, &claims, group_id)
+            .await
+            .map_err(|_| ApiError::forbidden("Access denied for this group"))?;
+    }
 
     let stats = service
         .load_topic_snapshot(&topic_obj)
         .await?
-        .ok_or_else(|| ApiError::not_found("Topic statistics not found"))?;
+        .ok_or_else(|| ApiError::not_found("STATS_NOT_FOUND", "Topic statistics not found"))?;
+
+    if group_ids.is_empty() {
+        return Ok(Json(TopicStatsResponse { topic_id, stats }).into_response());
+    }
+
+    let group_ids = group_ids
+        .iter()
+        .map(|group_id| group_id.to_hex())
+        .collect::<Vec<_>>();
+    let group_service = GroupService::new(state.mongo.clone());
+    let groups = group_service.fetch_groups_by_ids(&group_ids, true).await?;
+    if groups.len() != group_ids.len() {
+        return Err(ApiError::not_found("GROUP_NOT_FOUND", "Group not found"));
+    }
+    let students = group_service.students_by_group(&group_ids).await?;
+    let mut group_stats = service.topic_stats_by_group(&topic_obj, &students).await?;
 
-    Ok(Json(TopicStatsResponse { topic_id, stats }))
+    let by_group = groups
+        .into_iter()
+        .map(|group| {
+            let stats = group_stats.remove(&group.id).unwrap_or_default();
+            TopicGroupBreakdown {
+                group_id: group.id,
+                group_name: group.name,
+                avg_percentage: stats.avg_percentage,
+                total_attempts: stats.total_attempts,
+            }
+        })
+        .collect();
+
+    Ok(Json(TopicComparisonResponse {
+        topic_id,
+        overall: stats,
+        by_group,
+    })
+    .into_response())
 }
 
+/// Повторяемый `group_id` из строки запроса, без дублей
+fn parse_compared_groups(query: Option<&str>) -> Result<Vec<ObjectId>, ApiError> {
+    let mut group_ids = Vec::new();
+    for (key, value) in form_urlencoded::parse(query.unwrap_or_default().as_bytes()) {
+        if key != "group_id" {
+            continue;
+        }
+        let group_id = parse_object_id(&value, "group_id")?;
+        if !group_ids.contains(&group_id) {
+            group_ids.push(group_id);
+        }
+    }
+
+    if group_ids.len() > MAX_COMPARED_GROUPS {
+        return Err(ApiError::bad_request(
+            "TOO_MANY_GROUPS",
+            format!(
+                "At most {} group_id values can be compared",
+                MAX_COMPARED_GROUPS
+            ),
+        ));
+    }
+    Ok(group_ids)
+}
+
+/// Белый список полей сырой выгрузки: только для `ndjson` и только известные поля
+fn parse_export_fields(
+    format: &ExportFormatRequest,
+    fields: Option<Vec<String>>,
+) -> Result<Vec<String>, ApiError> {
+    let Some(fields) = fields else {
+        return Ok(Vec::new());
+    };
+    if !matches!(format, ExportFormatRequest::Ndjson) {
+        return Err(ApiError::bad_request(
+            "INVALID_EXPORT_FIELDS",
+            "fields can only be selected for ndjson exports",
+        ));
+    }
+    let mut selected = Vec::new();
+    for field in fields {
+        if !NDJSON_ANSWER_FIELDS.contains(&field.as_str()) {
+            return Err(ApiError::bad_request(
+                "INVALID_EXPORT_FIELDS",
+                format!("Unknown export field: {}", field),
+            ));
+        }
+        if !selected.contains(&field) {
+            selected.push(field);
+        }
+    }
+    if selected.is_empty() {
+        return Err(ApiError::bad_request(
+            "INVALID_EXPORT_FIELDS",
+            "fields must not be empty",
+        ));
+    }
+    Ok(selected)
+}
+
+/// Выгрузка по группе. Учитель выгружает только свои группы, админ - любые.
+/// Формат `ndjson` - сырые ответы учеников построчно; id учеников в ней
+/// псевдонимизируются, если выгрузку запросил не админ. Пока такая же выгрузка
+/// (формат, период, поля) ещё собирается, повторный запрос получает 409 с её id.
+#[utoipa::path(
+    post,
+    path = "/stats/groups/{id}/export",
+    tag = "reporting",
+    params(("id" = String, Path, description = "Id группы")),
+    request_body = ExportRequest,
+    security(("bearer_auth" = [], "csrf_token" = [])),
+    responses(
+        (status = 200, description = "Выгрузка поставлена в очередь", body = ExportResponse),
+        (status = 400, description = "Неверный период или список полей", body = ErrorResponse),
+        (status = 403, description = "Нет доступа", body = ErrorResponse),
+        (status = 409, description = "Такая же выгрузка уже в очереди", body = ErrorResponse),
+        (status = 429, description = "Превышен лимит выгрузок в час", body = ErrorResponse),
+        (status = 503, description = "Выгрузки отключены", body = ErrorResponse),
+    )
+)]
 pub(crate) async fn request_group_export(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
-    Path(group_id): Path<String>,
+    ObjectIdParam(group_obj): ObjectIdParam,
     AppJson(payload): AppJson<ExportRequest>,
 ) -> Result<Json<ExportResponse>, ApiError> {
-    let group_obj = parse_object_id(&group_id, "group_id")?;
+    ensure_exports_enabled(&state)?;
     let teacher_id = parse_object_id(&claims.sub, "teacher_id")?;
 
     let service = ReportingService::new(state.mongo.clone(), state.redis.clone());
 
     service
-        .guard_group_access(&claims, &group_obj)
+        .guard_group_access(&state.role_permissions, &claims, &group_obj)
+        .await
         .map_err(|_| ApiError::forbidden("Access denied for export"))?;
+    let fields = parse_export_fields(&payload.format, payload.fields)?;
+    let period = export_period(payload.period)?;
 
     let recent_exports = service
         .count_exports_in_window(&teacher_id, Duration::from_secs(3600))
@@ -118,10 +298,97 @@ pub(crate) async fn request_group_export(
 
     let filters = ReportFilters {
         topic_ids,
-        period: TimeRange {
-            from: payload.period.from,
-            to: payload.period.to,
-        },
+        period,
+        fields,
+        reveal_user_ids: manages_all_groups(&state, &claims).await,
+        locale: payload.locale,
+    };
+
+    let expires_at = Utc::now()
+        + ChronoDuration::from_std(state.config.reporting.export_expiration())
+            .map_err(|_| ApiError::internal("Invalid export expiration configured"))?;
+
+    let request = NewReportExport {
+        scope: ExportScope::Group,
+        subject_id: group_obj,
+        teacher_id,
+        format: payload.format.into(),
+        filters,
+        expires_at,
+    };
+    if let Some(existing) = service.find_pending_duplicate(&request).await? {
+        let export_id = existing.id.to_hex();
+        return Err(ErrorResponse::new(
+            StatusCode::CONFLICT,
+            "EXPORT_ALREADY_PENDING",
+            "The same export is already queued",
+        )
+        .with_details(json!({
+            "export_id": export_id,
+            "status_url": export_status_url(&existing.id),
+        }))
+        .into());
+    }
+
+    let export = service.create_export_request(request).await?;
+    Ok(Json(export_response(&export)))
+}
+
+/// Личный отчёт ученика. Ученик может запросить только свой отчёт,
+/// учитель — отчёт ученика из своих групп, админ — любой.
+#[utoipa::path(
+    post,
+    path = "/stats/users/{id}/export",
+    tag = "reporting",
+    params(("id" = String, Path, description = "Id ученика")),
+    request_body = ExportRequest,
+    security(("bearer_auth" = [], "csrf_token" = [])),
+    responses(
+        (status = 200, description = "Выгрузка поставлена в очередь", body = ExportResponse),
+        (status = 403, description = "Нет доступа", body = ErrorResponse),
+        (status = 429, description = "Превышен лимит выгрузок в час", body = ErrorResponse),
+        (status = 503, description = "Выгрузки отключены", body = ErrorResponse),
+    )
+)]
+pub(crate) async fn request_user_export(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    ObjectIdParam(user_obj): ObjectIdParam,
+    AppJson(payload): AppJson<ExportRequest>,
+) -> Result<Json<ExportResponse>, ApiError> {
+    ensure_exports_enabled(&state)?;
+    let requester_id = parse_object_id(&claims.sub, "user_id")?;
+
+    let service = ReportingService::new(state.mongo.clone(), state.redis.clone());
+    guard_user_report_access(&state, &service, &claims, &user_obj).await?;
+    if matches!(payload.format, ExportFormatRequest::Ndjson) || payload.fields.is_some() {
+        return Err(ApiError::bad_request(
+            "INVALID_EXPORT_FORMAT",
+            "Raw ndjson exports are only available for groups",
+        ));
+    }
+
+    let recent_exports = service
+        .count_exports_in_window(&requester_id, Duration::from_secs(3600))
+        .await?;
+    if recent_exports >= state.config.reporting.export_rate_limit_per_hour.into() {
+        return Err(ApiError::too_many_requests(
+            "Export rate limit exceeded for the current hour",
+        ));
+    }
+
+    let topic_ids = payload
+        .topic_ids
+        .into_iter()
+        .filter_map(|value| ObjectId::parse_str(&value).ok())
+        .collect::<Vec<_>>();
+
+    let filters = ReportFilters {
+        topic_ids,
+        period: export_period(payload.period)?,
+        fields: Vec::new(),
+        reveal_user_ids: false,
+        locale: payload.locale,
     };
 
     let expires_at = Utc::now()
@@ -130,164 +397,410 @@ pub(crate) async fn request_group_export(
 
     let export = service
         .create_export_request(NewReportExport {
-            group_id: group_obj,
-            teacher_id,
+            scope: ExportScope::User,
+            subject_id: user_obj,
+            teacher_id: requester_id,
             format: payload.format.into(),
             filters,
             expires_at,
         })
         .await?;
 
-    Ok(Json(ExportResponse {
-        export_id: export.id.to_string(),
-        status: export.status,
+    Ok(Json(export_response(&export)))
+}
+
+fn export_response(export: &ReportExport) -> ExportResponse {
+    ExportResponse {
+        export_id: export.id.to_hex(),
+        status: export.status.clone(),
         expires_at: export.expires_at,
-    }))
+        status_url: export_status_url(&export.id),
+    }
 }
 
+fn export_status_url(export_id: &ObjectId) -> String {
+    format!("/stats/exports/{}", export_id.to_hex())
+}
+
+/// Период выгрузки по умолчанию - 30 суток до конца текущего дня (UTC).
+/// Границы по суткам, чтобы повторный запрос в тот же день совпал с первым
+/// и попал под проверку дублей.
+const DEFAULT_EXPORT_PERIOD_DAYS: i64 = 30;
+
+fn export_period(period: Option<TimeRangeRequest>) -> Result<TimeRange, ApiError> {
+    let Some(period) = period else {
+        let to = (Utc::now().date_naive() + ChronoDuration::days(1))
+            .and_time(NaiveTime::MIN)
+            .and_utc();
+        return Ok(TimeRange {
+            from: to - ChronoDuration::days(DEFAULT_EXPORT_PERIOD_DAYS),
+            to,
+        });
+    };
+    if period.from >= period.to {
+        return Err(ApiError::bad_request(
+            "INVALID_PERIOD",
+            "period.from must be before period.to",
+        ));
+    }
+    Ok(TimeRange {
+        from: period.from,
+        to: period.to,
+    })
+}
+
+/// Статус выгрузки; для готовой — подписанная ссылка на скачивание.
+/// Доступ — как к самой группе (`guard_group_access`), а для личного отчёта —
+/// как при его запросе.
+#[utoipa::path(
+    get,
+    path = "/stats/exports/{id}",
+    tag = "reporting",
+    params(("id" = String, Path, description = "Id выгрузки")),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Статус выгрузки; у готовой - ссылка на скачивание", body = ExportStatusResponse),
+        (status = 403, description = "Нет доступа", body = ErrorResponse),
+        (status = 404, description = "Выгрузка не найдена", body = ErrorResponse),
+    )
+)]
 pub(crate) async fn get_export_status(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
-    Path(export_id): Path<String>,
+    ObjectIdParam(export_obj): ObjectIdParam,
 ) -> Result<Json<ExportStatusResponse>, ApiError> {
-    let export_obj = parse_object_id(&export_id, "export_id")?;
     let service = ReportingService::new(state.mongo.clone(), state.redis.clone());
     let export = service
         .get_export_by_id(&export_obj)
         .await?
-        .ok_or_else(|| ApiError::not_found("Export not found"))?;
-
-    if claims.role != "admin" {
-        let teacher_obj = parse_object_id(&claims.sub, "teacher_id")?;
-        if export.teacher_id != teacher_obj {
-            return Err(ApiError::forbidden("Export not found"));
+        .ok_or_else(|| ApiError::not_found("EXPORT_NOT_FOUND", "Export not found"))?;
+
+    let allowed = match (export.scope, export.subject_id()) {
+        (ExportScope::Group, Some(group_id)) => service
+            .guard_group_access(&state.role_permissions, &claims, &group_id)
+            .await
+            .is_ok(),
+        (ExportScope::User, Some(user_id)) => {
+            match guard_user_report_access(&state, &service, &claims, &user_id).await {
+                Ok(()) => true,
+                Err(ApiError::Forbidden(_)) => false,
+                Err(err) => return Err(err),
+            }
         }
+        (_, None) => manages_all_groups(&state, &claims).await,
+    };
+    if !allowed {
+        return Err(ApiError::forbidden("Access denied for this export"));
     }
 
+    Ok(Json(export_status_response(&state, export)?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/stats/groups/{id}/exports",
+    tag = "reporting",
+    params(("id" = String, Path, description = "Id группы"), ExportListQuery),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Выгрузки группы, новые - первыми", body = ExportListResponse),
+        (status = 403, description = "Нет доступа", body = ErrorResponse),
+    )
+)]
+pub(crate) async fn list_group_exports(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    ObjectIdParam(group_obj): ObjectIdParam,
+    Query(query): Query<ExportListQuery>,
+) -> Result<Json<ExportListResponse>, ApiError> {
+    let service = ReportingService::new(state.mongo.clone(), state.redis.clone());
+    service
+        .guard_group_access(&state.role_permissions, &claims, &group_obj)
+        .await
+        .map_err(|_| ApiError::forbidden("Access denied for this group"))?;
+
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let offset = query.offset.unwrap_or(0);
+    let (total, exports) = service
+        .list_group_exports(&group_obj, limit, offset)
+        .await?;
+
+    let exports = exports
+        .into_iter()
+        .map(|export| export_status_response(&state, export))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Json(ExportListResponse {
+        group_id: group_obj.to_hex(),
+        total,
+        limit,
+        offset,
+        exports,
+    }))
+}
+
+fn export_status_response(
+    state: &AppState,
+    export: ReportExport,
+) -> Result<ExportStatusResponse, ApiError> {
     let mut download_url = None;
     if export.status == ExportStatus::Ready {
-        if let Some(key) = &export.storage_key {
-            if let Some(storage) = state.object_storage.as_ref() {
-                download_url = Some(
-                    storage
-                        .generate_presigned_download_url(
-                            key,
-                            state.config.reporting.signed_url_ttl(),
-                        )
-                        .map_err(|err| {
-                            ApiError::internal(format!("Failed to sign download URL: {}", err))
-                        })?,
-                );
-            }
+        if let (Some(key), Some(signer)) = (&export.storage_key, state.export_links.as_ref()) {
+            let ttl = state.config.presign_ttl();
+            download_url = Some(signer.presigned_download_url(key, ttl).map_err(|err| {
+                ApiError::internal(format!("Failed to sign download URL: {}", err))
+            })?);
         }
     }
 
-    Ok(Json(ExportStatusResponse {
+    Ok(ExportStatusResponse {
         export_id: export.id.to_hex(),
         status: export.status,
         format: export.format,
+        created_at: export.created_at,
         expires_at: export.expires_at,
         completed_at: export.completed_at,
         download_url,
         error: export.error,
-    }))
+    })
 }
 
-#[derive(Debug, Serialize)]
-pub(crate) struct GroupStatsResponse {
-    group_id: String,
-    stats: MaterializedStat,
-    leaderboard: Option<LeaderboardDocument>,
+/// Отчёты по всем группам и ученикам - у ролей с `users.manage`
+async fn manages_all_groups(state: &AppState, claims: &JwtClaims) -> bool {
+    state
+        .role_permissions
+        .has_permission(&state.mongo, &claims.role, Permission::UsersManage)
+        .await
 }
 
-#[derive(Debug, Serialize)]
-pub(crate) struct UserStatsResponse {
-    user_id: String,
-    progress: Vec<ProgressSummary>,
+/// Доступ к отчёту по ученику: сам ученик, роль с `users.manage` или роль
+/// с `reports.view` из его группы
+async fn guard_user_report_access(
+    state: &AppState,
+    service: &ReportingService,
+    claims: &JwtClaims,
+    user_id: &ObjectId,
+) -> Result<(), ApiError> {
+    if claims.sub == user_id.to_hex() || manages_all_groups(state, claims).await {
+        return Ok(());
+    }
+    if state
+        .role_permissions
+        .has_permission(&state.mongo, &claims.role, Permission::ReportsView)
+        .await
+    {
+        let group_ids = parse_group_ids(&claims.group_ids)?;
+        if service.user_belongs_to_groups(user_id, &group_ids).await? {
+            return Ok(());
+        }
+    }
+    Err(ApiError::forbidden("User does not belong to your groups"))
 }
 
-#[derive(Debug, Serialize)]
-pub(crate) struct TopicStatsResponse {
-    topic_id: String,
-    stats: MaterializedStat,
-}
+/// Максимум адресатов в одном расписании выгрузки
+const MAX_SCHEDULE_RECIPIENTS: usize = 10;
+
+#[utoipa::path(
+    post,
+    path = "/stats/groups/{id}/export-schedules",
+    tag = "reporting",
+    params(("id" = String, Path, description = "Id группы")),
+    request_body = ExportScheduleRequest,
+    security(("bearer_auth" = [], "csrf_token" = [])),
+    responses(
+        (status = 201, description = "Расписание создано", body = ExportScheduleResponse),
+        (status = 400, description = "Некорректный день недели или адресаты", body = ErrorResponse),
+        (status = 403, description = "Нет доступа", body = ErrorResponse),
+        (status = 503, description = "Выгрузки отключены", body = ErrorResponse),
+    )
+)]
+pub(crate) async fn create_export_schedule(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    ObjectIdParam(group_obj): ObjectIdParam,
+    AppJson(payload): AppJson<ExportScheduleRequest>,
+) -> Result<(StatusCode, Json<ExportScheduleResponse>), ApiError> {
+    ensure_exports_enabled(&state)?;
+    let teacher_id = parse_object_id(&claims.sub, "teacher_id")?;
+    let service = ReportingService::new(state.mongo.clone(), state.redis.clone());
+    service
+        .guard_group_access(&state.role_permissions, &claims, &group_obj)
+        .await
+        .map_err(|_| ApiError::forbidden("Access denied for this group"))?;
 
-#[derive(Debug, Serialize)]
-pub(crate) struct ExportResponse {
-    export_id: String,
-    status: ExportStatus,
-    expires_at: DateTime<Utc>,
-}
+    if !(1..=7).contains(&payload.day_of_week) {
+        return Err(ApiError::bad_request(
+            "INVALID_DAY_OF_WEEK",
+            "day_of_week must be between 1 (Monday) and 7 (Sunday)",
+        ));
+    }
+    let recipients = normalize_recipients(payload.recipients)?;
+
+    let schedule = ExportSchedule {
+        id: ObjectId::new(),
+        group_id: group_obj,
+        teacher_id,
+        format: payload.format.into(),
+        cadence: payload.cadence,
+        day_of_week: payload.day_of_week,
+        recipients,
+        last_run_at: None,
+        last_export_id: None,
+        notified_export_id: None,
+        created_at: Utc::now(),
+    };
+    service.create_export_schedule(&schedule).await?;
 
-#[derive(Debug, Serialize)]
-pub(crate) struct ExportStatusResponse {
-    export_id: String,
-    status: ExportStatus,
-    format: ExportFormat,
-    expires_at: DateTime<Utc>,
-    completed_at: Option<DateTime<Utc>>,
-    download_url: Option<String>,
-    error: Option<String>,
+    Ok((StatusCode::CREATED, Json(schedule.into())))
 }
 
-#[derive(Debug, Deserialize)]
-pub(crate) struct ExportRequest {
-    #[serde(default)]
-    topic_ids: Vec<String>,
-    period: TimeRangeRequest,
-    format: ExportFormatRequest,
+#[utoipa::path(
+    get,
+    path = "/stats/groups/{id}/export-schedules",
+    tag = "reporting",
+    params(("id" = String, Path, description = "Id группы")),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Расписания выгрузок (учитель видит только свои)", body = Vec<ExportScheduleResponse>),
+        (status = 403, description = "Нет доступа", body = ErrorResponse),
+    )
+)]
+pub(crate) async fn list_export_schedules(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    ObjectIdParam(group_obj): ObjectIdParam,
+) -> Result<Json<Vec<ExportScheduleResponse>>, ApiError> {
+    let service = ReportingService::new(state.mongo.clone(), state.redis.clone());
+    service
+        .guard_group_access(&state.role_permissions, &claims, &group_obj)
+        .await
+        .map_err(|_| ApiError::forbidden("Access denied for this group"))?;
+
+    let owner = schedule_owner(&state, &claims).await?;
+    let schedules = service
+        .list_export_schedules(&group_obj, owner.as_ref())
+        .await?;
+
+    Ok(Json(schedules.into_iter().map(Into::into).collect()))
 }
 
-#[derive(Debug, Deserialize)]
-pub(crate) struct TimeRangeRequest {
-    from: DateTime<Utc>,
-    to: DateTime<Utc>,
+#[utoipa::path(
+    delete,
+    path = "/stats/groups/{id}/export-schedules/{schedule_id}",
+    tag = "reporting",
+    params(
+        ("id" = String, Path, description = "Id группы"),
+        ("schedule_id" = String, Path, description = "Id расписания"),
+    ),
+    security(("bearer_auth" = [], "csrf_token" = [])),
+    responses(
+        (status = 204, description = "Расписание удалено"),
+        (status = 403, description = "Нет доступа", body = ErrorResponse),
+        (status = 404, description = "Расписание не найдено", body = ErrorResponse),
+    )
+)]
+pub(crate) async fn delete_export_schedule(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    ObjectIdParams(group_obj, schedule_obj): ObjectIdParams,
+) -> Result<StatusCode, ApiError> {
+    let service = ReportingService::new(state.mongo.clone(), state.redis.clone());
+    service
+        .guard_group_access(&state.role_permissions, &claims, &group_obj)
+        .await
+        .map_err(|_| ApiError::forbidden("Access denied for this group"))?;
+
+    let owner = schedule_owner(&state, &claims).await?;
+    let deleted = service
+        .delete_export_schedule(&group_obj, &schedule_obj, owner.as_ref())
+        .await?;
+    if !deleted {
+        return Err(ApiError::not_found(
+            "EXPORT_SCHEDULE_NOT_FOUND",
+            "Export schedule not found",
+        ));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "lowercase")]
-enum ExportFormatRequest {
-    Csv,
-    Pdf,
-    Xlsx,
+/// Учитель видит и удаляет только свои расписания, роль с `users.manage` — любые
+async fn schedule_owner(
+    state: &AppState,
+    claims: &JwtClaims,
+) -> Result<Option<ObjectId>, ApiError> {
+    if manages_all_groups(state, claims).await {
+        return Ok(None);
+    }
+    Ok(Some(parse_object_id(&claims.sub, "teacher_id")?))
 }
 
-impl From<ExportFormatRequest> for ExportFormat {
-    fn from(value: ExportFormatRequest) -> Self {
-        match value {
-            ExportFormatRequest::Csv => ExportFormat::Csv,
-            ExportFormatRequest::Pdf => ExportFormat::Pdf,
-            ExportFormatRequest::Xlsx => ExportFormat::Xlsx,
+fn normalize_recipients(values: Vec<String>) -> Result<Vec<String>, ApiError> {
+    let mut recipients: Vec<String> = Vec::new();
+    for value in values {
+        let email = value.trim().to_lowercase();
+        if !email.validate_email() {
+            return Err(ApiError::bad_request(
+                "INVALID_RECIPIENTS",
+                format!("Invalid recipient email: {}", value),
+            ));
+        }
+        if !recipients.contains(&email) {
+            recipients.push(email);
         }
     }
+
+    if recipients.is_empty() || recipients.len() > MAX_SCHEDULE_RECIPIENTS {
+        return Err(ApiError::bad_request(
+            "INVALID_RECIPIENTS",
+            format!(
+                "recipients must contain between 1 and {} emails",
+                MAX_SCHEDULE_RECIPIENTS
+            ),
+        ));
+    }
+    Ok(recipients)
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct ExportListQuery {
+    limit: Option<u32>,
+    offset: Option<u32>,
 }
 
 #[derive(Debug)]
 pub(crate) enum ApiError {
-    BadRequest(String),
+    Response(ErrorResponse),
+    BadRequest(&'static str, String),
     Forbidden(String),
-    NotFound(String),
+    NotFound(&'static str, String),
     TooManyRequests(String),
+    ServiceUnavailable(String),
     Internal(String),
 }
 
 impl ApiError {
-    fn bad_request(message: impl Into<String>) -> Self {
-        ApiError::BadRequest(message.into())
+    fn bad_request(code: &'static str, message: impl Into<String>) -> Self {
+        ApiError::BadRequest(code, message.into())
     }
 
     fn forbidden(message: impl Into<String>) -> Self {
         ApiError::Forbidden(message.into())
     }
 
-    fn not_found(message: impl Into<String>) -> Self {
-        ApiError::NotFound(message.into())
+    fn not_found(code: &'static str, message: impl Into<String>) -> Self {
+        ApiError::NotFound(code, message.into())
     }
 
     fn too_many_requests(message: impl Into<String>) -> Self {
         ApiError::TooManyRequests(message.into())
     }
 
+    fn service_unavailable(message: impl Into<String>) -> Self {
+        ApiError::ServiceUnavailable(message.into())
+    }
+
     fn internal(message: impl Into<String>) -> Self {
         ApiError::Internal(message.into())
     }
@@ -299,23 +812,42 @@ impl From<anyhow::Error> for ApiError {
     }
 }
 
+impl From<ErrorResponse> for ApiError {
+    fn from(err: ErrorResponse) -> Self {
+        ApiError::Response(err)
+    }
+}
+
 impl IntoResponse for ApiError {
     fn into_response(self) -> axum::response::Response {
-        let (status, message) = match self {
-            ApiError::BadRequest(message) => (StatusCode::BAD_REQUEST, message),
-            ApiError::Forbidden(message) => (StatusCode::FORBIDDEN, message),
-            ApiError::NotFound(message) => (StatusCode::NOT_FOUND, message),
-            ApiError::TooManyRequests(message) => (StatusCode::TOO_MANY_REQUESTS, message),
-            ApiError::Internal(message) => (StatusCode::INTERNAL_SERVER_ERROR, message),
+        let error = match self {
+            ApiError::Response(error) => error,
+            ApiError::BadRequest(code, message) => ErrorResponse::bad_request(code, message),
+            ApiError::Forbidden(message) => ErrorResponse::forbidden("FORBIDDEN", message),
+            ApiError::NotFound(code, message) => ErrorResponse::not_found(code, message),
+            ApiError::TooManyRequests(message) => ErrorResponse::new(
+                StatusCode::TOO_MANY_REQUESTS,
+                "EXPORT_RATE_LIMITED",
+                message,
+            ),
+            ApiError::ServiceUnavailable(message) => {
+                ErrorResponse::new(StatusCode::SERVICE_UNAVAILABLE, "EXPORTS_DISABLED", message)
+            }
+            ApiError::Internal(message) => ErrorResponse::internal(message),
         };
-
-        (status, Json(message)).into_response()
+        error.into_response()
     }
 }
 
-fn parse_object_id(value: &str, field: &str) -> Result<ObjectId, ApiError> {
-    ObjectId::parse_str(value)
-        .map_err(|_| ApiError::bad_request(format!("Invalid {}: must be ObjectId", field)))
+/// REPORTING_EXPORTS_ENABLED=false - новые выгрузки и расписания не принимаются
+fn ensure_exports_enabled(state: &AppState) -> Result<(), ApiError> {
+    if state.config.reporting.exports_enabled {
+        Ok(())
+    } else {
+        Err(ApiError::service_unavailable(
+            "Report exports are disabled on this server",
+        ))
+    }
 }
 
 fn parse_group_ids(values: &[String]) -> Result<Vec<ObjectId>, ApiError> {
@@ -0,0 +1,39 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, State},
+    Extension, Json,
+};
+
+use crate::{
+    handlers::error::ErrorResponse,
+    middlewares::auth::JwtClaims,
+    models::review::{ReviewNextQuery, ReviewNextResponse},
+    services::{review_service::ReviewService, AppState},
+};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/review/next",
+    tag = "review",
+    params(ReviewNextQuery),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Шаблоны, которые пора повторить (может быть пусто)", body = ReviewNextResponse),
+    )
+)]
+pub async fn next_review(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    Query(query): Query<ReviewNextQuery>,
+) -> Result<Json<ReviewNextResponse>, ErrorResponse> {
+    let (items, total_due) = ReviewService::new(state.mongo.clone())
+        .next_due(&claims.sub, query.limit.unwrap_or(1))
+        .await
+        .map_err(|e| ErrorResponse::internal(e.to_string()))?;
+
+    Ok(Json(ReviewNextResponse {
+        items: items.into_iter().map(Into::into).collect(),
+        total_due,
+    }))
+}
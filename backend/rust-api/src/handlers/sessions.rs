@@ -2,28 +2,79 @@ use axum::{
     extract::{Path, State},
     http::StatusCode,
     response::IntoResponse,
-    Json,
+    Extension, Json,
 };
+use mongodb::bson::doc;
+use rand::{rngs::StdRng, SeedableRng};
 use std::sync::Arc;
 
 use crate::{
     extractors::AppJson,
-    models::{answer::SubmitAnswerRequest, hint::RequestHintRequest, *},
+    handlers::{assignments::assignment_error, error::ErrorResponse},
+    middlewares::auth::JwtClaims,
+    models::{
+        answer::{SubmitAnswerRequest, SubmitAnswerResponse},
+        anticheat::{SignalBatchRequest, SignalBatchResponse},
+        content::AgeBand,
+        hint::{RequestHintRequest, RequestHintResponse},
+        *,
+    },
     services::{
-        answer_service::AnswerService, hint_service::HintService, session_service::SessionService,
+        answer_service::{
+            AnswerFormatError, AnswerService, AttemptsExhaustedError, SessionExpiredError,
+        },
+        anticheat_service::{AnticheatService, SignalRateLimited},
+        assignment_service::AssignmentError,
+        consent_service::ConsentService,
+        feature_flag_service::{FeatureFlagService, HINTS_ENABLED_FLAG},
+        hint_service::{HintBudgetExhausted, HintService},
+        llm_provider::ConfiguredLlmProvider,
+        review_service::ReviewQueueEmptyError,
+        session_archive_service::{SessionArchiveService, SessionLookup},
+        session_service::{
+            AgeBandRestrictedError, GroupArchivedError, LevelLockedError, SessionCompletion,
+            SessionService,
+        },
+        task_selection_service::{NoTemplatesAvailableError, TaskSelectionService},
         AppState,
     },
 };
 
+/// Сколько последних ответов сессии берётся в сводку времени
+const SESSION_TIMING_LIMIT: i64 = 500;
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/sessions",
+    tag = "sessions",
+    request_body = CreateSessionRequest,
+    security(("bearer_auth" = [], "csrf_token" = [])),
+    responses(
+        (status = 201, description = "Сессия создана", body = CreateSessionResponse),
+        (status = 403, description = "`USER_MISMATCH` (`user_id` не совпадает с токеном), `CONSENT_REQUIRED`, `AGE_BAND_RESTRICTED` (задание выше возрастной категории ученика), `LEVEL_LOCKED` (непройденные уровни - в `details.missing`), `NOT_GROUP_MEMBER` или `ASSIGNMENT_PAST_DUE`", body = ErrorResponse),
+        (status = 404, description = "Задание не найдено, `ASSIGNMENT_NOT_FOUND` или (`mode: review`) повторять нечего - `REVIEW_QUEUE_EMPTY`", body = ErrorResponse),
+        (status = 409, description = "Группа архивирована или домашнее задание уже выполнено - `ASSIGNMENT_COMPLETED`", body = ErrorResponse),
+    )
+)]
 pub async fn create_session(
     State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
     AppJson(req): AppJson<CreateSessionRequest>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, ErrorResponse> {
     tracing::info!(
         "Creating session for user_id={}, task_id={}",
         req.user_id,
         req.task_id
     );
+    if req.user_id != claims.sub {
+        return Err(ErrorResponse::forbidden(
+            "USER_MISMATCH",
+            "Sessions can only be started for the authenticated user",
+        ));
+    }
+
+    ensure_consents(&state, &req.user_id).await?;
+    let age_band = student_age_band(&state, &claims).await?;
 
     let service = SessionService::new(
         state.mongo.clone(),
@@ -31,25 +82,129 @@ pub async fn create_session(
         state.config.python_api_url.clone(),
     );
 
-    match service.create_session(req).await {
+    match service.create_session(&claims.sub, req, age_band).await {
         Ok(response) => Ok((StatusCode::CREATED, Json(response))),
-        Err(e) => {
-            tracing::error!("Failed to create session: {}", e);
-            let msg = e.to_string();
-            let status = if msg.contains("Task not found") {
-                StatusCode::NOT_FOUND
-            } else {
-                StatusCode::INTERNAL_SERVER_ERROR
-            };
-            Err((status, msg))
-        }
+        Err(e) => Err(session_creation_error(e)),
+    }
+}
+
+/// POST /api/v1/sessions/auto - сессия с заданием по слабым темам ученика
+#[utoipa::path(
+    post,
+    path = "/api/v1/sessions/auto",
+    tag = "sessions",
+    request_body = AutoSessionRequest,
+    security(("bearer_auth" = [], "csrf_token" = [])),
+    responses(
+        (status = 201, description = "Сессия создана", body = CreateSessionResponse),
+        (status = 403, description = "`CONSENT_REQUIRED`", body = ErrorResponse),
+        (status = 404, description = "Нет доступных опубликованных шаблонов - `NO_TEMPLATES_AVAILABLE`", body = ErrorResponse),
+        (status = 409, description = "Группа архивирована", body = ErrorResponse),
+    )
+)]
+pub async fn create_auto_session(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    AppJson(req): AppJson<AutoSessionRequest>,
+) -> Result<impl IntoResponse, ErrorResponse> {
+    ensure_consents(&state, &claims.sub).await?;
+
+    let age_band = student_age_band(&state, &claims).await?;
+
+    let service = SessionService::new(
+        state.mongo.clone(),
+        state.redis.clone(),
+        state.config.python_api_url.clone(),
+    );
+    let mut rng = StdRng::from_os_rng();
+    match service
+        .create_auto_session(&claims.sub, req, age_band, &mut rng)
+        .await
+    {
+        Ok(response) => Ok((StatusCode::CREATED, Json(response))),
+        Err(e) => Err(session_creation_error(e)),
     }
 }
 
+/// Возрастные ограничения контента действуют только для учеников
+async fn student_age_band(
+    state: &AppState,
+    claims: &JwtClaims,
+) -> Result<Option<AgeBand>, ErrorResponse> {
+    if claims.role != "student" {
+        return Ok(None);
+    }
+    TaskSelectionService::new(state.mongo.clone())
+        .student_age_band(&claims.sub)
+        .await
+        .map(Some)
+        .map_err(|e| ErrorResponse::internal(e.to_string()))
+}
+
+/// Ученик может войти в систему без согласий, но начать сессию - нет
+async fn ensure_consents(state: &AppState, user_id: &str) -> Result<(), ErrorResponse> {
+    let missing_consents = ConsentService::new(state.mongo.clone())
+        .missing_for_student(user_id)
+        .await
+        .map_err(|e| ErrorResponse::internal(e.to_string()))?;
+    if !missing_consents.is_empty() {
+        return Err(ErrorResponse::forbidden(
+            "CONSENT_REQUIRED",
+            format!("Missing required consent: {}", missing_consents.join(", ")),
+        )
+        .with_details(serde_json::json!({ "missing": missing_consents })));
+    }
+    Ok(())
+}
+
+fn session_creation_error(e: anyhow::Error) -> ErrorResponse {
+    if let Some(locked) = e.downcast_ref::<LevelLockedError>() {
+        return ErrorResponse::forbidden("LEVEL_LOCKED", "Complete the prerequisite levels first")
+            .with_details(serde_json::json!({
+                "level_id": locked.level_id,
+                "missing": locked.missing,
+            }));
+    }
+    if let Some(restricted) = e.downcast_ref::<AgeBandRestrictedError>() {
+        return ErrorResponse::forbidden("AGE_BAND_RESTRICTED", restricted.to_string());
+    }
+    if let Some(archived) = e.downcast_ref::<GroupArchivedError>() {
+        return ErrorResponse::new(StatusCode::CONFLICT, "GROUP_ARCHIVED", archived.to_string())
+            .with_details(serde_json::json!({ "group_id": archived.group_id }));
+    }
+    if e.downcast_ref::<AssignmentError>().is_some() {
+        return assignment_error(e);
+    }
+    if let Some(empty) = e.downcast_ref::<ReviewQueueEmptyError>() {
+        return ErrorResponse::not_found("REVIEW_QUEUE_EMPTY", empty.to_string());
+    }
+    if let Some(none) = e.downcast_ref::<NoTemplatesAvailableError>() {
+        return ErrorResponse::not_found("NO_TEMPLATES_AVAILABLE", none.to_string());
+    }
+    tracing::error!("Failed to create session: {}", e);
+    let msg = e.to_string();
+    if msg.contains("Task not found") {
+        ErrorResponse::not_found("TASK_NOT_FOUND", msg)
+    } else {
+        ErrorResponse::internal(msg)
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/sessions/{id}",
+    tag = "sessions",
+    params(("id" = String, Path, description = "Id сессии")),
+    responses(
+        (status = 200, description = "Сессия (активная или завершённая)", body = SessionDetail),
+        (status = 404, description = "Сессия не найдена", body = ErrorResponse),
+        (status = 409, description = "Сессия в архиве, нужна регидратация", body = ErrorResponse),
+    )
+)]
 pub async fn get_session(
     State(state): State<Arc<AppState>>,
     Path(session_id): Path<String>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, ErrorResponse> {
     tracing::info!("Getting session: {}", session_id);
 
     let service = SessionService::new(
@@ -58,16 +213,66 @@ pub async fn get_session(
         state.config.python_api_url.clone(),
     );
 
-    match service.get_session(&session_id).await {
-        Ok(session) => Ok((StatusCode::OK, Json(session))),
-        Err(_) => Err((StatusCode::NOT_FOUND, "Session not found".to_string())),
+    if let Ok(session) = service.get_session(&session_id).await {
+        return Ok((StatusCode::OK, Json(session_detail(&state, session).await)));
+    }
+
+    // Активной сессии нет - ищем завершённую, в том числе в архиве
+    let lookup = SessionArchiveService::new(state.mongo.clone())
+        .find_session(&session_id)
+        .await
+        .map_err(|e| ErrorResponse::internal(e.to_string()))?;
+
+    match lookup {
+        SessionLookup::Found(session) => {
+            Ok((StatusCode::OK, Json(session_detail(&state, *session).await)))
+        }
+        SessionLookup::Archived(stub) => Err(ErrorResponse::new(
+            StatusCode::CONFLICT,
+            "ARCHIVED_PENDING_REHYDRATION",
+            "Session has been archived. Ask an administrator to rehydrate it and retry.",
+        )
+        .with_details(serde_json::json!({
+            "archived_at": stub.archived_at,
+            "rehydrate": format!("POST /admin/sessions/{}/rehydrate", session_id),
+        }))),
+        SessionLookup::Missing => Err(ErrorResponse::not_found(
+            "SESSION_NOT_FOUND",
+            "Session not found",
+        )),
+    }
+}
+
+/// Сводка времени ответов необязательна: ошибка чтения не ломает карточку сессии
+async fn session_detail(state: &AppState, session: Session) -> SessionDetail {
+    let answer_timing = AnticheatService::new(state.mongo.clone(), state.redis.clone())
+        .answer_timing(doc! { "session_id": &session.id }, SESSION_TIMING_LIMIT)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!("Failed to load answer timing for {}: {}", session.id, e);
+            None
+        });
+    SessionDetail {
+        session,
+        answer_timing,
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/sessions/{id}/complete",
+    tag = "sessions",
+    params(("id" = String, Path, description = "Id сессии")),
+    security(("csrf_token" = [])),
+    responses(
+        (status = 204, description = "Сессия завершена"),
+        (status = 409, description = "Время сессии истекло", body = ErrorResponse),
+    )
+)]
 pub async fn complete_session(
     State(state): State<Arc<AppState>>,
     Path(session_id): Path<String>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, ErrorResponse> {
     tracing::info!("Completing session: {}", session_id);
 
     let service = SessionService::new(
@@ -76,20 +281,48 @@ pub async fn complete_session(
         state.config.python_api_url.clone(),
     );
 
-    match service.complete_session(&session_id).await {
-        Ok(_) => Ok((StatusCode::NO_CONTENT, ())),
+    match service
+        .complete_session(&session_id, &state.config.sessions)
+        .await
+    {
+        Ok(SessionCompletion::Completed(_)) => Ok((StatusCode::NO_CONTENT, ())),
+        // Сессия уже зафиксирована как истёкшая - сообщаем итог клиенту
+        Ok(SessionCompletion::Expired(final_score)) => Err(ErrorResponse::new(
+            StatusCode::CONFLICT,
+            "SESSION_EXPIRED",
+            "Session time is over; it was finalized as expired",
+        )
+        .with_details(serde_json::json!({
+            "status": SessionStatus::Expired,
+            "score": final_score.score,
+            "hints_used": final_score.hints_used,
+        }))),
         Err(e) => {
             tracing::error!("Failed to complete session: {}", e);
-            Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+            Err(ErrorResponse::internal(e.to_string()))
         }
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/sessions/{id}/answers",
+    tag = "sessions",
+    params(("id" = String, Path, description = "Id сессии")),
+    request_body = SubmitAnswerRequest,
+    security(("csrf_token" = [])),
+    responses(
+        (status = 200, description = "Результат проверки ответа", body = SubmitAnswerResponse),
+        (status = 400, description = "Число частей ответа не совпадает с заданием", body = ErrorResponse),
+        (status = 404, description = "Сессия не найдена", body = ErrorResponse),
+        (status = 409, description = "Время сессии истекло или попытки задания закончились", body = ErrorResponse),
+    )
+)]
 pub async fn submit_answer(
     State(state): State<Arc<AppState>>,
     Path(session_id): Path<String>,
     AppJson(req): AppJson<SubmitAnswerRequest>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, ErrorResponse> {
     tracing::info!("Submitting answer for session: {}", session_id);
 
     // Get session to verify it exists and get user_id, task_id
@@ -101,28 +334,99 @@ pub async fn submit_answer(
     let session = session_service
         .get_session(&session_id)
         .await
-        .map_err(|_| (StatusCode::NOT_FOUND, "Session not found".to_string()))?;
+        .map_err(|_| ErrorResponse::not_found("SESSION_NOT_FOUND", "Session not found"))?;
 
     // Process answer
-    let answer_service = AnswerService::new(state.mongo.clone(), state.redis.clone());
+    let answer_service = AnswerService::new(
+        state.mongo.clone(),
+        state.redis.clone(),
+        state.config.sessions.clone(),
+    )
+    .with_anticheat_settings(state.anticheat_settings.get(&state.mongo).await);
 
     match answer_service
         .submit_answer(&session_id, &session.user_id, &session.task_id, &req)
         .await
     {
-        Ok(response) => Ok((StatusCode::OK, Json(response))),
+        Ok(mut response) => {
+            // Последняя попытка задания с `max_attempts` завершает сессию; при сбое
+            // сессия остаётся открытой, и клиент может завершить её сам
+            if let (Some(max_attempts), Some(0)) =
+                (response.max_attempts, response.remaining_attempts)
+            {
+                match session_service
+                    .complete_out_of_attempts(&session_id, max_attempts, &state.config.sessions)
+                    .await
+                {
+                    Ok(_) => response.session_completed = true,
+                    Err(e) => tracing::error!(
+                        "Failed to complete session {} after last attempt: {:#}",
+                        session_id,
+                        e
+                    ),
+                }
+            }
+            Ok((StatusCode::OK, Json(response)))
+        }
         Err(e) => {
+            if let Some(exhausted) = e.downcast_ref::<AttemptsExhaustedError>() {
+                return Err(ErrorResponse::new(
+                    StatusCode::CONFLICT,
+                    "ATTEMPTS_EXHAUSTED",
+                    exhausted.to_string(),
+                )
+                .with_details(serde_json::json!({
+                    "session_id": exhausted.session_id,
+                    "max_attempts": exhausted.max_attempts,
+                })));
+            }
+            if let Some(format) = e.downcast_ref::<AnswerFormatError>() {
+                return Err(ErrorResponse::bad_request(
+                    "INVALID_ANSWER_FORMAT",
+                    format.to_string(),
+                )
+                .with_details(serde_json::json!({
+                    "expected_parts": format.expected,
+                    "received_parts": format.received,
+                })));
+            }
+            if let Some(expired) = e.downcast_ref::<SessionExpiredError>() {
+                return Err(ErrorResponse::new(
+                    StatusCode::CONFLICT,
+                    "SESSION_EXPIRED",
+                    expired.to_string(),
+                )
+                .with_details(serde_json::json!({
+                    "session_id": expired.session_id,
+                    "expires_at": expired.expires_at,
+                    "grace_seconds": state.config.sessions.grace_seconds,
+                })));
+            }
             tracing::error!("Failed to submit answer: {}", e);
-            Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+            Err(ErrorResponse::internal(e.to_string()))
         }
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/sessions/{id}/hints",
+    tag = "sessions",
+    params(("id" = String, Path, description = "Id сессии")),
+    request_body = RequestHintRequest,
+    security(("csrf_token" = [])),
+    responses(
+        (status = 200, description = "Подсказка и штраф к счёту", body = RequestHintResponse),
+        (status = 403, description = "Подсказки отключены флагом `hints_enabled`", body = ErrorResponse),
+        (status = 404, description = "Сессия не найдена", body = ErrorResponse),
+        (status = 409, description = "Подсказки закончились", body = ErrorResponse),
+    )
+)]
 pub async fn request_hint(
     State(state): State<Arc<AppState>>,
     Path(session_id): Path<String>,
     AppJson(req): AppJson<RequestHintRequest>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, ErrorResponse> {
     tracing::info!("Requesting hint for session: {}", session_id);
 
     // Get session to extract user_id and task_id
@@ -134,13 +438,29 @@ pub async fn request_hint(
     let session = session_service
         .get_session(&session_id)
         .await
-        .map_err(|_| (StatusCode::NOT_FOUND, "Session not found".to_string()))?;
+        .map_err(|_| ErrorResponse::not_found("SESSION_NOT_FOUND", "Session not found"))?;
+
+    // Флаг проверяется для владельца сессии: маршрут открыт без токена
+    let hints_enabled = FeatureFlagService::new(state.mongo.clone(), Some(state.redis.clone()))
+        .is_enabled_for_user(HINTS_ENABLED_FLAG, &session.user_id, true)
+        .await;
+    if !hints_enabled {
+        return Err(ErrorResponse::forbidden(
+            "HINTS_DISABLED",
+            "Hints are disabled",
+        ));
+    }
 
     // Request hint
     let hint_service = HintService::new(
         state.mongo.clone(),
         state.redis.clone(),
-        state.config.python_api_url.clone(),
+        Arc::new(ConfiguredLlmProvider::new(
+            state.mongo.clone(),
+            state.config.llm.clone(),
+        )),
+        state.runtime.snapshot().hints.clone(),
+        state.config.yandexgpt.timeout(),
     );
 
     match hint_service
@@ -149,14 +469,116 @@ pub async fn request_hint(
     {
         Ok(response) => Ok((StatusCode::OK, Json(response))),
         Err(e) => {
+            if let Some(exhausted) = e.downcast_ref::<HintBudgetExhausted>() {
+                return Err(ErrorResponse::new(
+                    StatusCode::CONFLICT,
+                    "HINT_LIMIT_REACHED",
+                    exhausted.to_string(),
+                )
+                .with_details(serde_json::json!({
+                    "max_hints": exhausted.max_hints,
+                    "hints_used": exhausted.hints_used,
+                    "remaining_hints": 0,
+                })));
+            }
             tracing::error!("Failed to request hint: {}", e);
-            let msg = e.to_string();
-            let status = if msg.contains("Maximum hints limit reached") {
-                StatusCode::BAD_REQUEST
-            } else {
-                StatusCode::INTERNAL_SERVER_ERROR
-            };
-            Err((status, msg))
+            Err(ErrorResponse::internal(e.to_string()))
+        }
+    }
+}
+
+/// POST /api/v1/sessions/{id}/signals - пакет сигналов античита от клиента
+#[utoipa::path(
+    post,
+    path = "/api/v1/sessions/{id}/signals",
+    tag = "sessions",
+    params(("id" = String, Path, description = "Id сессии")),
+    request_body = SignalBatchRequest,
+    security(("bearer_auth" = [], "csrf_token" = [])),
+    responses(
+        (status = 202, description = "Сигналы приняты", body = SignalBatchResponse),
+        (status = 400, description = "Пустой или слишком большой пакет, серверный тип сигнала", body = ErrorResponse),
+        (status = 403, description = "Сессия принадлежит другому пользователю", body = ErrorResponse),
+        (status = 404, description = "Сессия не найдена", body = ErrorResponse),
+        (status = 429, description = "Слишком частая отправка сигналов", body = ErrorResponse),
+    )
+)]
+pub async fn submit_signals(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    Path(session_id): Path<String>,
+    AppJson(req): AppJson<SignalBatchRequest>,
+) -> Result<impl IntoResponse, ErrorResponse> {
+    let runtime = state.runtime.snapshot();
+    let settings = &runtime.anticheat_signals;
+    if req.signals.is_empty() {
+        return Err(ErrorResponse::bad_request(
+            "EMPTY_SIGNAL_BATCH",
+            "At least one signal is required",
+        ));
+    }
+    if req.signals.len() > settings.max_batch_size {
+        return Err(ErrorResponse::bad_request(
+            "SIGNAL_BATCH_TOO_LARGE",
+            format!("At most {} signals per request", settings.max_batch_size),
+        )
+        .with_details(serde_json::json!({ "max_batch_size": settings.max_batch_size })));
+    }
+    if let Some(signal) = req
+        .signals
+        .iter()
+        .find(|signal| signal.signal_type.is_server_side())
+    {
+        return Err(ErrorResponse::bad_request(
+            "INVALID_SIGNAL_TYPE",
+            format!(
+                "Signal type {} is recorded by the server",
+                signal.signal_type.as_str()
+            ),
+        ));
+    }
+
+    let session_service = SessionService::new(
+        state.mongo.clone(),
+        state.redis.clone(),
+        state.config.python_api_url.clone(),
+    );
+    let session = session_service
+        .get_session(&session_id)
+        .await
+        .map_err(|_| ErrorResponse::not_found("SESSION_NOT_FOUND", "Session not found"))?;
+    if session.user_id != claims.sub {
+        tracing::warn!(
+            "Rejected anticheat signals for foreign session: session={}, caller={}",
+            session_id,
+            claims.sub
+        );
+        return Err(ErrorResponse::forbidden(
+            "SESSION_FORBIDDEN",
+            "Session belongs to another user",
+        ));
+    }
+
+    let service = AnticheatService::new(state.mongo.clone(), state.redis.clone())
+        .with_settings(state.anticheat_settings.get(&state.mongo).await);
+    match service
+        .ingest_signals(&session_id, &session.user_id, req.signals, settings)
+        .await
+    {
+        Ok(response) => Ok((StatusCode::ACCEPTED, Json(response))),
+        Err(e) => {
+            if let Some(limited) = e.downcast_ref::<SignalRateLimited>() {
+                return Err(ErrorResponse::new(
+                    StatusCode::TOO_MANY_REQUESTS,
+                    "RATE_LIMITED",
+                    limited.to_string(),
+                )
+                .with_details(serde_json::json!({
+                    "retry_after_seconds": limited.retry_after_secs,
+                })));
+            }
+            tracing::error!("Failed to ingest anticheat signals: {}", e);
+            Err(ErrorResponse::internal(e.to_string()))
         }
     }
 }
@@ -1,30 +1,54 @@
 use axum::{
     extract::{Path, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::{
         sse::{Event, KeepAlive, Sse},
         IntoResponse,
     },
 };
 use chrono::Utc;
-use futures::stream::{self, Stream};
+use futures::stream::{self, BoxStream, Stream, StreamExt};
+use std::collections::VecDeque;
 use std::convert::Infallible;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::sleep;
 
 use crate::{
-    models::timer::{TimeExpired, TimerEvent, TimerTick},
-    services::{session_service::SessionService, AppState},
+    models::{
+        notification::InAppNotificationKind,
+        timer::{TimeExpired, TimerEvent, TimerTick},
+    },
+    services::{
+        notification_center_service::{NewNotification, NotificationCenterService},
+        session_events::{session_events_channel, LoggedEvent, SessionEventLog},
+        session_service::SessionService,
+        AppState,
+    },
 };
 
+/// Комментарий `:keepalive`, чтобы прокси не закрывали простаивающее соединение
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
 /// SSE endpoint for timer events
 /// GET /api/v1/sessions/{id}/stream
+///
+/// Каждое событие получает `id:` из журнала сессии. При переподключении браузер
+/// присылает `Last-Event-ID`, и пропущенные события из журнала отправляются первыми.
 pub async fn session_stream(
     State(state): State<Arc<AppState>>,
     Path(session_id): Path<String>,
+    headers: HeaderMap,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
-    tracing::info!("Client connected to SSE stream: session={}", session_id);
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok());
+    tracing::info!(
+        "Client connected to SSE stream: session={}, last_event_id={:?}",
+        session_id,
+        last_event_id
+    );
 
     // Verify session exists
     let session_service = SessionService::new(
@@ -37,22 +61,74 @@ pub async fn session_stream(
         .await
         .map_err(|_| (StatusCode::NOT_FOUND, "Session not found".to_string()))?;
 
-    // Calculate timer duration from session
-    let started_at = session.started_at;
-    let expires_at = session.expires_at;
-    let total_seconds = (expires_at - started_at).num_seconds() as u32;
+    // Calculate timer duration from session; после переподключения таймер продолжается
+    let total_seconds = (session.expires_at - session.started_at)
+        .num_seconds()
+        .max(0) as u32;
+    let elapsed = (Utc::now() - session.started_at)
+        .num_seconds()
+        .clamp(0, i64::from(total_seconds)) as u32;
 
-    let capped_seconds = std::cmp::min(total_seconds, max_stream_duration_seconds());
+    let capped_seconds = std::cmp::min(
+        total_seconds,
+        elapsed.saturating_add(max_stream_duration_seconds()),
+    );
     let tick_interval = tick_interval_ms();
     tracing::info!(
-        "Starting SSE stream: session={}, configured_duration={}s, tick_interval={}ms",
+        "Starting SSE stream: session={}, configured_duration={}s, elapsed={}s, tick_interval={}ms",
         session_id,
         capped_seconds,
+        elapsed,
         tick_interval
     );
-    let stream = create_timer_stream(session_id.clone(), capped_seconds, tick_interval);
 
-    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+    let log = SessionEventLog::new(state.redis.clone(), state.config.sessions.event_buffer_size);
+    // Подписываемся до чтения журнала, чтобы не потерять событие между ними
+    let server_events = subscribe_session_events(&state, &session_id).await;
+    let replay = match last_event_id {
+        Some(last_id) => log.since(&session_id, last_id).await.unwrap_or_else(|err| {
+            tracing::warn!(
+                "Failed to load missed SSE events: session={}, error={}",
+                session_id,
+                err
+            );
+            Vec::new()
+        }),
+        None => Vec::new(),
+    };
+
+    // Короткие сессии целиком укладываются в порог - предупреждать о них незачем;
+    // стрим, обрезанный `SSE_MAX_STREAM_SECONDS`, до конца сессии не доживает
+    let warning_seconds = state.config.sessions.expiry_warning_seconds;
+    let expiry_warning = (warning_seconds > 0
+        && total_seconds > warning_seconds
+        && capped_seconds == total_seconds)
+        .then(|| ExpiryWarning {
+            user_id: session.user_id.clone(),
+            threshold_seconds: warning_seconds,
+            notifications: NotificationCenterService::new(state.mongo.clone(), state.redis.clone()),
+            sent: false,
+        });
+
+    let stream = create_timer_stream(TimerStreamState {
+        session_id,
+        elapsed,
+        total: capped_seconds,
+        tick_interval_ms: tick_interval,
+        log,
+        replay: replay.into(),
+        last_sent_id: last_event_id.unwrap_or(0),
+        server_events,
+        expiry_warning,
+        started: false,
+        finished: false,
+    });
+
+    Ok(Sse::new(stream).keep_alive(
+        KeepAlive::new()
+            .interval(KEEPALIVE_INTERVAL)
+            .text("keepalive"),
+    ))
 }
 
 fn max_stream_duration_seconds() -> u32 {
@@ -71,57 +147,204 @@ fn tick_interval_ms() -> u64 {
         .unwrap_or(1000)
 }
 
-/// Create a stream of timer events
-fn create_timer_stream(
+/// Серверные события сессии (например, `expired` после отклонённого ответа).
+/// Без Pub/Sub стрим работает как обычный таймер
+async fn subscribe_session_events(
+    state: &AppState,
+    session_id: &str,
+) -> Option<BoxStream<'static, LoggedEvent>> {
+    let subscribe = async {
+        let client = redis::Client::open(state.config.redis_uri.clone())?;
+        let mut pubsub = client.get_async_pubsub().await?;
+        pubsub.subscribe(session_events_channel(session_id)).await?;
+        Ok::<_, redis::RedisError>(pubsub)
+    };
+    match subscribe.await {
+        Ok(pubsub) => Some(
+            pubsub
+                .into_on_message()
+                .filter_map(|message| async move {
+                    let payload: String = message.get_payload().ok()?;
+                    serde_json::from_str::<LoggedEvent>(&payload).ok()
+                })
+                .boxed(),
+        ),
+        Err(err) => {
+            tracing::warn!(
+                "Failed to subscribe to session events: session={}, error={}",
+                session_id,
+                err
+            );
+            None
+        }
+    }
+}
+
+struct TimerStreamState {
     session_id: String,
-    total_seconds: u32,
+    elapsed: u32,
+    total: u32,
     tick_interval_ms: u64,
-) -> impl Stream<Item = Result<Event, Infallible>> {
-    stream::unfold(
-        (session_id.clone(), 0u32, total_seconds, false),
-        move |(sid, elapsed, total, final_sent)| async move {
-            if final_sent {
-                return None;
-            }
+    log: SessionEventLog,
+    /// Пропущенные клиентом события, отправляются до живых
+    replay: VecDeque<LoggedEvent>,
+    /// Живые события с id не больше этого уже ушли клиенту
+    last_sent_id: u64,
+    server_events: Option<BoxStream<'static, LoggedEvent>>,
+    expiry_warning: Option<ExpiryWarning>,
+    started: bool,
+    finished: bool,
+}
 
-            if elapsed >= total {
-                // Send final time-expired event once
-                let expired_event = TimerEvent::TimeExpired(TimeExpired {
-                    session_id: sid.clone(),
-                    timestamp: Utc::now(),
-                    message: "Time limit exceeded".to_string(),
-                });
+/// Уведомление в центр уведомлений, когда до конца сессии остаётся порог
+struct ExpiryWarning {
+    user_id: String,
+    threshold_seconds: u32,
+    notifications: NotificationCenterService,
+    sent: bool,
+}
 
-                let event = Event::default()
-                    .event(expired_event.event_name())
-                    .data(expired_event.to_sse_data());
+impl ExpiryWarning {
+    /// Один раз на сессию: после переподключения повтор отсекается `dedup_key`
+    async fn check(&mut self, session_id: &str, remaining_seconds: u32) {
+        if self.sent || remaining_seconds == 0 || remaining_seconds > self.threshold_seconds {
+            return;
+        }
+        self.sent = true;
+        let notification = NewNotification {
+            recipient_id: self.user_id.clone(),
+            kind: InAppNotificationKind::SessionExpiring,
+            title: "Сессия скоро закончится".to_string(),
+            body: format!(
+                "До конца сессии осталось {} сек. Успейте отправить ответ.",
+                remaining_seconds
+            ),
+            dedup_key: Some(format!("session-expiring:{}", session_id)),
+        };
+        if let Err(err) = self.notifications.create(notification).await {
+            tracing::warn!(
+                "Failed to store expiry notification: session={}, error={:#}",
+                session_id,
+                err
+            );
+        }
+    }
+}
+
+impl TimerStreamState {
+    /// Событие для отправки; завершающее событие закрывает стрим
+    fn emit(&mut self, logged: Option<LoggedEvent>, event: TimerEvent) -> Event {
+        self.finished = event.is_terminal();
+        let mut sse = Event::default()
+            .event(event.event_name())
+            .data(event.to_sse_data());
+        if let Some(logged) = logged {
+            self.last_sent_id = self.last_sent_id.max(logged.id);
+            sse = sse.id(logged.id.to_string());
+        }
+        sse
+    }
 
-                tracing::info!("Timer expired: session={}", sid);
-                return Some((Ok(event), (sid, elapsed, total, true)));
+    /// Записывает событие в журнал сессии, чтобы его можно было повторить
+    async fn record(&mut self, event: TimerEvent) -> Event {
+        match self.log.append(&self.session_id, &event).await {
+            Ok(logged) => self.emit(Some(logged), event),
+            Err(err) => {
+                tracing::warn!(
+                    "Failed to record SSE event: session={}, error={}",
+                    self.session_id,
+                    err
+                );
+                self.emit(None, event)
             }
+        }
+    }
+}
 
-            if elapsed > total {
-                return None;
+/// Create a stream of timer events
+fn create_timer_stream(state: TimerStreamState) -> impl Stream<Item = Result<Event, Infallible>> {
+    stream::unfold(state, |mut st| async move {
+        if let Some(logged) = st.replay.pop_front() {
+            let event = logged.event.clone();
+            let sse = st.emit(Some(logged), event);
+            if st.finished {
+                st.replay.clear();
+            }
+            return Some((Ok(sse), st));
+        }
+
+        if st.finished {
+            return None;
+        }
+
+        if st.started {
+            // Wait before next tick; серверное событие прерывает ожидание и стрим
+            let server_event =
+                wait_next_tick(&mut st.server_events, st.tick_interval_ms, st.last_sent_id).await;
+            if let Some(logged) = server_event {
+                tracing::info!(
+                    "Session event: session={}, event={}",
+                    st.session_id,
+                    logged.event.event_name()
+                );
+                let event = logged.event.clone();
+                let sse = st.emit(Some(logged), event);
+                return Some((Ok(sse), st));
             }
+            st.elapsed += 1;
+        }
+        st.started = true;
 
-            // Send timer-tick event
-            let remaining = total.saturating_sub(elapsed);
-            let tick_event = TimerEvent::TimerTick(TimerTick {
-                session_id: sid.clone(),
-                remaining_seconds: remaining,
-                elapsed_seconds: elapsed,
-                total_seconds: total,
+        if st.elapsed >= st.total {
+            // Send final time-expired event once
+            let expired_event = TimerEvent::TimeExpired(TimeExpired {
+                session_id: st.session_id.clone(),
                 timestamp: Utc::now(),
+                message: "Time limit exceeded".to_string(),
             });
+            tracing::info!("Timer expired: session={}", st.session_id);
+            let sse = st.record(expired_event).await;
+            return Some((Ok(sse), st));
+        }
 
-            let event = Event::default()
-                .event(tick_event.event_name())
-                .data(tick_event.to_sse_data());
+        let remaining_seconds = st.total.saturating_sub(st.elapsed);
+        if let Some(warning) = st.expiry_warning.as_mut() {
+            warning.check(&st.session_id, remaining_seconds).await;
+        }
 
-            // Wait 1 second before next tick
-            sleep(Duration::from_millis(tick_interval_ms)).await;
+        // Send timer-tick event
+        let tick_event = TimerEvent::TimerTick(TimerTick {
+            session_id: st.session_id.clone(),
+            remaining_seconds,
+            elapsed_seconds: st.elapsed,
+            total_seconds: st.total,
+            timestamp: Utc::now(),
+        });
+        let sse = st.record(tick_event).await;
+        Some((Ok(sse), st))
+    })
+}
 
-            Some((Ok(event), (sid, elapsed + 1, total, false)))
-        },
-    )
+/// Ждёт интервал тика; если раньше пришло новое серверное событие - возвращает его
+async fn wait_next_tick(
+    server_events: &mut Option<BoxStream<'static, LoggedEvent>>,
+    tick_interval_ms: u64,
+    last_sent_id: u64,
+) -> Option<LoggedEvent> {
+    let tick = sleep(Duration::from_millis(tick_interval_ms));
+    tokio::pin!(tick);
+    while let Some(events) = server_events.as_mut() {
+        tokio::select! {
+            event = events.next() => match event {
+                // Уже отправлено при повторе из журнала
+                Some(logged) if logged.id <= last_sent_id => continue,
+                Some(logged) => return Some(logged),
+                // Подписка закрылась - дальше работаем как обычный таймер
+                None => *server_events = None,
+            },
+            _ = &mut tick => return None,
+        }
+    }
+    tick.await;
+    None
 }
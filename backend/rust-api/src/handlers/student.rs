@@ -5,10 +5,10 @@ use std::{
 
 use axum::{
     extract::State,
-    http::StatusCode,
     response::{IntoResponse, Response},
     Extension, Json,
 };
+use chrono::{Datelike, Utc};
 use futures::TryStreamExt;
 use mongodb::{
     bson::{doc, oid::ObjectId, Document},
@@ -20,12 +20,26 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     extractors::AppJson,
+    handlers::admin::group_error,
+    handlers::error::ErrorResponse,
     middlewares::auth::JwtClaims,
     models::{
-        content::{LevelRecord, TemplateDocument, TemplateStatus, TopicRecord},
-        CreateSessionRequest, CreateSessionResponse, ProgressSummary,
+        content::{
+            pick_locale_variants, AgeBand, LevelRecord, TemplateDocument, TemplateStatus,
+            TopicRecord, DEFAULT_LOCALE,
+        },
+        group::JoinGroupRequest,
+        user::User,
+        CreateSessionRequest, CreateSessionResponse, ProgressSummary, SessionMode,
+    },
+    services::{
+        audit_service::AuditService,
+        consent_service::ConsentService,
+        group_invite_service::GroupInviteService,
+        session_service::{AgeBandRestrictedError, SessionService},
+        task_selection_service::TaskSelectionService,
+        AppState,
     },
-    services::{session_service::SessionService, AppState},
 };
 
 const DEFAULT_TASKS_PER_COURSE: i32 = 10;
@@ -57,6 +71,8 @@ pub enum StudentCourseStatus {
 pub struct StudentCourseSummary {
     pub id: String,
     pub slug: String,
+    /// Язык, на котором выдан курс (язык ученика или язык по умолчанию)
+    pub locale: String,
     pub title: String,
     pub description: String,
     pub difficulty: String,
@@ -83,7 +99,12 @@ pub async fn list_courses(
 ) -> Result<Json<StudentCoursesResponse>, StudentApiError> {
     ensure_student_role(&claims)?;
 
-    let templates = load_published_templates(&state.mongo).await?;
+    let preferences = load_student_preferences(&state.mongo, &claims).await?;
+    // Из вариантов шаблона на разных языках ученик видит один
+    let templates = pick_locale_variants(
+        load_published_templates(&state.mongo).await?,
+        &preferences.locale,
+    );
     if templates.is_empty() {
         return Ok(Json(StudentCoursesResponse {
             courses: Vec::new(),
@@ -93,19 +114,28 @@ pub async fn list_courses(
     let levels_map = load_levels(&state.mongo, &templates).await?;
     let topics_map = load_topics(&state.mongo, &levels_map).await?;
     let progress_map = load_progress(&state.mongo, &claims.sub).await?;
+    let student_band = preferences.age_band;
 
     let courses = templates
         .into_iter()
         .filter_map(|template| {
             let level = levels_map.get(&template.level_id)?;
             let topic = topics_map.get(&level.topic_id);
+            if let Some(band) = student_band {
+                let content_band =
+                    AgeBand::effective(template.age_band, topic.and_then(|doc| doc.age_band));
+                if !band.allows(content_band) {
+                    return None;
+                }
+            }
             let level_id = template.level_id.to_hex();
             let progress = progress_map.get(&level_id);
+            let localized = template.localized(&preferences.locale);
 
-            let title = normalize_text(metadata_string(&template.metadata, "title"))
+            let title = normalize_text(metadata_string(localized.metadata, "title"))
                 .or_else(|| normalize_text(Some(level.name.clone())))
                 .unwrap_or_else(|| template.slug.clone());
-            let description = normalize_text(metadata_string(&template.metadata, "description"))
+            let description = normalize_text(metadata_string(localized.metadata, "description"))
                 .or_else(|| {
                     topic
                         .map(|doc| doc.description.clone())
@@ -128,6 +158,7 @@ pub async fn list_courses(
             let course = StudentCourseSummary {
                 id: template.id.to_hex(),
                 slug: template.slug.clone(),
+                locale: localized.locale.to_string(),
                 title,
                 description,
                 difficulty: map_difficulty(
@@ -212,8 +243,17 @@ pub async fn start_session(
 ) -> Result<Json<CreateSessionResponse>, StudentApiError> {
     ensure_student_role(&claims)?;
 
+    // Ученик может войти в систему без согласий, но начать сессию - нет
+    let missing_consents = ConsentService::new(state.mongo.clone())
+        .missing_for_student(&claims.sub)
+        .await
+        .map_err(|err| StudentApiError::internal(format!("Failed to check consents: {}", err)))?;
+    if !missing_consents.is_empty() {
+        return Err(StudentApiError::ConsentRequired(missing_consents));
+    }
+
     let template_id = ObjectId::parse_str(&payload.template_id)
-        .map_err(|_| StudentApiError::bad_request("Invalid template_id"))?;
+        .map_err(|_| StudentApiError::bad_request("INVALID_OBJECT_ID", "Invalid template_id"))?;
 
     let templates_collection = state.mongo.collection::<TemplateDocument>("templates");
     let template = templates_collection
@@ -223,7 +263,27 @@ pub async fn start_session(
         })
         .await
         .map_err(|err| StudentApiError::internal(format!("Failed to load template: {}", err)))?
-        .ok_or_else(|| StudentApiError::not_found("Template not found or not published"))?;
+        .ok_or_else(|| {
+            StudentApiError::not_found("TEMPLATE_NOT_FOUND", "Template not found or not published")
+        })?;
+
+    let age_band = load_student_preferences(&state.mongo, &claims)
+        .await?
+        .age_band;
+    if let Some(band) = age_band {
+        let content_band = TaskSelectionService::new(state.mongo.clone())
+            .template_age_band(&template_id)
+            .await
+            .map_err(|err| {
+                StudentApiError::internal(format!("Failed to load template age band: {}", err))
+            })?;
+        if !band.allows(content_band) {
+            return Err(StudentApiError::forbidden(
+                "AGE_BAND_RESTRICTED",
+                "Template is not available for your age group",
+            ));
+        }
+    }
 
     let session_service = SessionService::new(
         state.mongo.clone(),
@@ -242,35 +302,45 @@ pub async fn start_session(
         group_id,
         level_id: Some(template.level_id.to_hex()),
         session_duration_seconds: Some(duration_seconds as i64),
+        mode: SessionMode::Normal,
+        assignment_id: None,
     };
 
     let response = session_service
-        .create_session(request)
+        .create_session(&claims.sub, request, age_band)
         .await
-        .map_err(|err| StudentApiError::internal(format!("Failed to create session: {}", err)))?;
+        .map_err(|err| {
+            // Генератор может выбрать другой шаблон уровня - он тоже проверяется по возрасту
+            if let Some(restricted) = err.downcast_ref::<AgeBandRestrictedError>() {
+                return StudentApiError::forbidden("AGE_BAND_RESTRICTED", restricted.to_string());
+            }
+            StudentApiError::internal(format!("Failed to create session: {}", err))
+        })?;
 
     Ok(Json(response))
 }
 
 #[derive(Debug)]
 pub enum StudentApiError {
-    BadRequest(String),
-    Forbidden(String),
-    NotFound(String),
+    BadRequest(&'static str, String),
+    Forbidden(&'static str, String),
+    NotFound(&'static str, String),
     Internal(String),
+    /// Не хватает обязательных согласий (перечислены типы согласий)
+    ConsentRequired(Vec<String>),
 }
 
 impl StudentApiError {
-    fn bad_request(message: impl Into<String>) -> Self {
-        StudentApiError::BadRequest(message.into())
+    fn bad_request(code: &'static str, message: impl Into<String>) -> Self {
+        StudentApiError::BadRequest(code, message.into())
     }
 
-    fn forbidden(message: impl Into<String>) -> Self {
-        StudentApiError::Forbidden(message.into())
+    fn forbidden(code: &'static str, message: impl Into<String>) -> Self {
+        StudentApiError::Forbidden(code, message.into())
     }
 
-    fn not_found(message: impl Into<String>) -> Self {
-        StudentApiError::NotFound(message.into())
+    fn not_found(code: &'static str, message: impl Into<String>) -> Self {
+        StudentApiError::NotFound(code, message.into())
     }
 
     fn internal(message: impl Into<String>) -> Self {
@@ -280,14 +350,19 @@ impl StudentApiError {
 
 impl IntoResponse for StudentApiError {
     fn into_response(self) -> Response {
-        let (status, message) = match self {
-            StudentApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
-            StudentApiError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
-            StudentApiError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
-            StudentApiError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
+        let error = match self {
+            StudentApiError::BadRequest(code, msg) => ErrorResponse::bad_request(code, msg),
+            StudentApiError::Forbidden(code, msg) => ErrorResponse::forbidden(code, msg),
+            StudentApiError::NotFound(code, msg) => ErrorResponse::not_found(code, msg),
+            StudentApiError::Internal(msg) => ErrorResponse::internal(msg),
+            StudentApiError::ConsentRequired(missing) => ErrorResponse::forbidden(
+                "CONSENT_REQUIRED",
+                format!("Missing required consent: {}", missing.join(", ")),
+            )
+            .with_details(serde_json::json!({ "missing": missing })),
         };
 
-        (status, Json(message)).into_response()
+        error.into_response()
     }
 }
 
@@ -450,11 +525,50 @@ async fn fetch_user_streak(
     Ok(value.unwrap_or(0))
 }
 
+/// Настройки ученика, от которых зависит выдача контента
+struct StudentPreferences {
+    /// `None` - ограничений нет (не ученик)
+    age_band: Option<AgeBand>,
+    locale: String,
+}
+
+async fn load_student_preferences(
+    mongo: &Database,
+    claims: &JwtClaims,
+) -> Result<StudentPreferences, StudentApiError> {
+    if claims.role != "student" {
+        return Ok(StudentPreferences {
+            age_band: None,
+            locale: DEFAULT_LOCALE.to_string(),
+        });
+    }
+
+    let user_id = ObjectId::parse_str(&claims.sub).map_err(|_| {
+        StudentApiError::bad_request("INVALID_OBJECT_ID", "Invalid user id in token")
+    })?;
+    let user = mongo
+        .collection::<User>("users")
+        .find_one(doc! { "_id": user_id })
+        .await
+        .map_err(|err| StudentApiError::internal(format!("Failed to load user: {}", err)))?;
+    let birth_year = user.as_ref().and_then(|user| user.birth_year);
+
+    Ok(StudentPreferences {
+        age_band: Some(AgeBand::from_birth_year(birth_year, Utc::now().year())),
+        locale: user
+            .and_then(|user| user.preferred_locale)
+            .unwrap_or_else(|| DEFAULT_LOCALE.to_string()),
+    })
+}
+
 fn ensure_student_role(claims: &JwtClaims) -> Result<(), StudentApiError> {
     if matches!(claims.role.as_str(), "student" | "content_admin" | "admin") {
         Ok(())
     } else {
-        Err(StudentApiError::forbidden("Student role required"))
+        Err(StudentApiError::forbidden(
+            "STUDENT_ROLE_REQUIRED",
+            "Student role required",
+        ))
     }
 }
 
@@ -505,3 +619,33 @@ fn map_difficulty(value: Option<&str>) -> String {
         _ => "medium".to_string(),
     }
 }
+
+/// POST /api/v1/groups/join - Вступить в группу по коду приглашения.
+/// Новая группа попадёт в group_ids токена после его обновления
+pub async fn join_group(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    AppJson(req): AppJson<JoinGroupRequest>,
+) -> Result<impl IntoResponse, ErrorResponse> {
+    if claims.role != "student" {
+        return Err(ErrorResponse::forbidden(
+            "STUDENT_ROLE_REQUIRED",
+            "Only students can join groups with invite codes",
+        ));
+    }
+
+    let invite_service = GroupInviteService::new(state.mongo.clone());
+    let outcome = invite_service
+        .join(&req.code, &claims.sub)
+        .await
+        .map_err(group_error)?;
+
+    if outcome.joined {
+        let audit_service = AuditService::new(state.mongo.clone());
+        let _ = audit_service
+            .log_group_join(&claims.sub, &outcome.group.id, req.code.trim())
+            .await;
+    }
+
+    Ok(Json(outcome))
+}
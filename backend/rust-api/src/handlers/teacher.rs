@@ -1,33 +1,54 @@
 use std::{
     collections::{HashMap, HashSet},
-    convert::TryFrom,
+    convert::{Infallible, TryFrom},
     sync::Arc,
 };
 
 use anyhow::Context;
 use axum::{
-    extract::{Extension, Path, Query, State},
-    http::StatusCode,
-    response::IntoResponse,
+    body::Body,
+    extract::{Extension, Query, State},
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
 use chrono::{DateTime, Utc};
-use futures::TryStreamExt;
+use futures::{stream, TryStreamExt};
 use mongodb::bson::{doc, from_document, oid::ObjectId, Document};
 use mongodb::{options::FindOptions, Database};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    extractors::AppJson,
+    extractors::{parse_object_id, AppJson, ObjectIdParam, ObjectIdParams},
+    handlers::error::ErrorResponse,
     middlewares::auth::JwtClaims,
-    models::{notification::NotificationTemplate, notification::SentNotification, ProgressSummary},
+    models::{
+        answer::AnswerTimingSummary,
+        group::TeacherGroupResponse,
+        notification::{
+            EmailOutboxMessage, InAppNotificationKind, NotificationDeliveryResponse,
+            NotificationTemplate, RecipientDeliveryStatus, SentNotification,
+        },
+        ProgressSummary,
+    },
     services::{
-        email_service::EmailService, group_service::GroupService,
-        reporting_service::ReportingService, AppState,
+        anticheat_service::AnticheatService,
+        email_outbox_service::{aggregate_status, EmailOutboxService, OutgoingEmail},
+        email_service::EmailService,
+        gradebook_service::{gradebook_csv_header, gradebook_csv_row, GradebookService},
+        group_service::GroupService,
+        notification_center_service::{NewNotification, NotificationCenterService},
+        notification_template_service::{
+            render, unknown_variables, NotificationTemplateService, TemplateContext,
+            TEMPLATE_VARIABLES,
+        },
+        reporting_service::ReportingService,
+        AppState,
     },
 };
+use utoipa::ToSchema;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct StudentSummary {
     pub id: String,
     pub name: String,
@@ -43,15 +64,25 @@ pub struct StudentSummary {
 pub struct StudentDetailResponse {
     pub summary: StudentSummary,
     pub progress: Vec<ProgressSummary>,
+    /// Время ответов по последним попыткам ученика; None - данных о времени нет
+    pub answer_timing: Option<AnswerTimingSummary>,
 }
 
+/// Сколько последних попыток ученика берётся в сводку времени
+const STUDENT_TIMING_LIMIT: i64 = 200;
+
 #[derive(Debug, Deserialize)]
 struct StudentRecord {
     #[serde(rename = "_id")]
     id: ObjectId,
     name: String,
     email: String,
-    #[serde(rename = "lastLoginAt")]
+    /// В MongoDB это BSON-дата, а не строка: без конвертера поле не читалось
+    #[serde(
+        rename = "lastLoginAt",
+        default,
+        with = "crate::models::user::bson_datetime_as_chrono_option"
+    )]
     last_login_at: Option<DateTime<Utc>>,
 }
 
@@ -69,6 +100,23 @@ struct StudentStatRow {
     last_updated: Option<DateTime<Utc>>,
 }
 
+#[derive(Deserialize)]
+pub struct TeacherGroupsQuery {
+    /// Показывать архивные группы (по умолчанию скрыты)
+    #[serde(default)]
+    pub include_archived: bool,
+    /// Дополнительные поля через запятую; `stats` добавляет показатели активности
+    pub include: Option<String>,
+}
+
+impl TeacherGroupsQuery {
+    fn include_stats(&self) -> bool {
+        self.include
+            .as_deref()
+            .is_some_and(|include| include.split(',').any(|part| part.trim() == "stats"))
+    }
+}
+
 #[derive(Deserialize)]
 pub struct GroupQuery {
     #[serde(rename = "groupId")]
@@ -102,14 +150,37 @@ struct Recommendation {
 pub async fn list_teacher_groups(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    ensure_teacher_role(&claims)?;
-
+    Query(query): Query<TeacherGroupsQuery>,
+) -> Result<impl IntoResponse, ErrorResponse> {
     let group_service = GroupService::new(state.mongo.clone());
     let groups = group_service
-        .fetch_groups_by_ids(&claims.group_ids)
+        .fetch_groups_by_ids(&claims.group_ids, query.include_archived)
         .await
-        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?;
+
+    let mut health = HashMap::new();
+    if query.include_stats() {
+        let group_ids = groups
+            .iter()
+            .map(|group| group.id.clone())
+            .collect::<Vec<_>>();
+        let students = group_service
+            .students_by_group(&group_ids)
+            .await
+            .map_err(|err| ErrorResponse::internal(err.to_string()))?;
+        health = ReportingService::new(state.mongo.clone(), state.redis.clone())
+            .group_health_stats(&students)
+            .await
+            .map_err(|err| ErrorResponse::internal(err.to_string()))?;
+    }
+
+    let groups = groups
+        .into_iter()
+        .map(|group| {
+            let stats = health.remove(&group.id);
+            TeacherGroupResponse { group, stats }
+        })
+        .collect::<Vec<_>>();
 
     Ok(Json(groups))
 }
@@ -117,61 +188,115 @@ pub async fn list_teacher_groups(
 pub async fn list_group_students(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
-    Path(group_id): Path<String>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    ensure_teacher_role(&claims)?;
-
-    let group_obj = parse_object_id(&group_id, "group_id")?;
+    ObjectIdParam(group_obj): ObjectIdParam,
+) -> Result<impl IntoResponse, ErrorResponse> {
     let reporting_service = ReportingService::new(state.mongo.clone(), state.redis.clone());
     reporting_service
-        .guard_group_access(&claims, &group_obj)
+        .guard_group_access(&state.role_permissions, &claims, &group_obj)
+        .await
         .map_err(|_| {
-            (
-                StatusCode::FORBIDDEN,
-                "Access denied for this group".to_string(),
-            )
+            ErrorResponse::forbidden("GROUP_ACCESS_DENIED", "Access denied for this group")
         })?;
 
-    let group_id_str = group_obj.to_hex();
-    let students = fetch_students_in_group(&state.mongo, &group_id_str).await?;
+    let summaries = group_student_summaries(&state.mongo, &group_obj.to_hex()).await?;
+
+    Ok(Json(summaries))
+}
+
+/// Ученики группы со статистикой прогресса (общий код для учителя и админки)
+pub(crate) async fn group_student_summaries(
+    db: &Database,
+    group_id: &str,
+) -> Result<Vec<StudentSummary>, ErrorResponse> {
+    let students = fetch_students_in_group(db, group_id).await?;
     let user_ids = students
         .iter()
         .map(|student| student.id.to_hex())
         .collect::<Vec<_>>();
-    let stats_map = aggregate_student_stats(&state.mongo, &user_ids)
+    let stats_map = aggregate_student_stats(db, &user_ids)
         .await
-        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?;
 
-    let summaries = students
+    Ok(students
         .into_iter()
         .map(|record| {
             let stats = stats_map.get(&record.id.to_hex());
             student_summary_from_record(record, stats)
         })
+        .collect())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GradebookQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// GET /api/v1/teacher/groups/{group_id}/gradebook.csv - журнал оценок группы:
+/// лучший балл и дата выполнения по каждому шаблону заданий и занятий за период
+pub async fn export_gradebook(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    ObjectIdParam(group_obj): ObjectIdParam,
+    Query(query): Query<GradebookQuery>,
+) -> Result<Response, ErrorResponse> {
+    if let (Some(from), Some(to)) = (query.from, query.to) {
+        if from > to {
+            return Err(ErrorResponse::bad_request(
+                "INVALID_PERIOD",
+                "`from` must not be later than `to`",
+            ));
+        }
+    }
+    let reporting_service = ReportingService::new(state.mongo.clone(), state.redis.clone());
+    reporting_service
+        .guard_group_access(&state.role_permissions, &claims, &group_obj)
+        .await
+        .map_err(|_| {
+            ErrorResponse::forbidden("GROUP_ACCESS_DENIED", "Access denied for this group")
+        })?;
+
+    let gradebook = GradebookService::new(state.mongo.clone())
+        .build(&group_obj, query.from, query.to)
+        .await
+        .map_err(|e| ErrorResponse::internal(e.to_string()))?;
+
+    let lines = std::iter::once(gradebook_csv_header(&gradebook.columns))
+        .chain(gradebook.rows.iter().map(gradebook_csv_row))
         .collect::<Vec<_>>();
+    let body = Body::from_stream(stream::iter(lines.into_iter().map(Ok::<_, Infallible>)));
+
+    let mut response = Response::new(body);
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("text/csv; charset=utf-8"),
+    );
+    let disposition = format!(
+        "attachment; filename=\"gradebook-{}.csv\"",
+        group_obj.to_hex()
+    );
+    if let Ok(value) = HeaderValue::from_str(&disposition) {
+        response
+            .headers_mut()
+            .insert(header::CONTENT_DISPOSITION, value);
+    }
 
-    Ok(Json(summaries))
+    Ok(response)
 }
 
 pub async fn get_student_detail(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
-    Path((group_id, student_id)): Path<(String, String)>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    ensure_teacher_role(&claims)?;
-
-    let group_obj = parse_object_id(&group_id, "group_id")?;
+    ObjectIdParams(group_obj, student_obj): ObjectIdParams,
+) -> Result<impl IntoResponse, ErrorResponse> {
     let reporting_service = ReportingService::new(state.mongo.clone(), state.redis.clone());
     reporting_service
-        .guard_group_access(&claims, &group_obj)
+        .guard_group_access(&state.role_permissions, &claims, &group_obj)
+        .await
         .map_err(|_| {
-            (
-                StatusCode::FORBIDDEN,
-                "Access denied for this group".to_string(),
-            )
+            ErrorResponse::forbidden("GROUP_ACCESS_DENIED", "Access denied for this group")
         })?;
 
-    let student_obj = parse_object_id(&student_id, "student_id")?;
     let group_id_str = group_obj.to_hex();
 
     let student_record = fetch_single_student(&state.mongo, &student_obj, &group_id_str).await?;
@@ -179,29 +304,40 @@ pub async fn get_student_detail(
     let progress = reporting_service
         .load_user_progress(&student_obj)
         .await
-        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?;
 
     let stats_map = aggregate_student_stats(&state.mongo, &[student_record.id.to_hex()])
         .await
-        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?;
 
-    let summary = student_summary_from_record(student_record, stats_map.get(&student_id));
+    let summary = student_summary_from_record(student_record, stats_map.get(&student_obj.to_hex()));
 
-    Ok(Json(StudentDetailResponse { summary, progress }))
+    let answer_timing = AnticheatService::new(state.mongo.clone(), state.redis.clone())
+        .answer_timing(
+            doc! { "user_id": student_obj.to_hex() },
+            STUDENT_TIMING_LIMIT,
+        )
+        .await
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?;
+
+    Ok(Json(StudentDetailResponse {
+        summary,
+        progress,
+        answer_timing,
+    }))
 }
 
 pub async fn list_group_topic_analytics(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
     Query(query): Query<GroupQuery>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    ensure_teacher_role(&claims)?;
-
+) -> Result<impl IntoResponse, ErrorResponse> {
     let group_obj = parse_object_id(&query.group_id, "groupId")?;
     let service = ReportingService::new(state.mongo.clone(), state.redis.clone());
     service
-        .guard_group_access(&claims, &group_obj)
-        .map_err(|_| (StatusCode::FORBIDDEN, "Access denied".into()))?;
+        .guard_group_access(&state.role_permissions, &claims, &group_obj)
+        .await
+        .map_err(|_| ErrorResponse::forbidden("GROUP_ACCESS_DENIED", "Access denied"))?;
 
     let students = fetch_students_in_group(&state.mongo, &group_obj.to_hex()).await?;
     let student_ids = students
@@ -215,7 +351,7 @@ pub async fn list_group_topic_analytics(
     let topic_rows = service
         .aggregate_topic_stats(&student_ids)
         .await
-        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?;
 
     let payload = topic_rows
         .into_iter()
@@ -235,14 +371,13 @@ pub async fn get_group_activity(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
     Query(query): Query<GroupQuery>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    ensure_teacher_role(&claims)?;
-
+) -> Result<impl IntoResponse, ErrorResponse> {
     let group_obj = parse_object_id(&query.group_id, "groupId")?;
     let service = ReportingService::new(state.mongo.clone(), state.redis.clone());
     service
-        .guard_group_access(&claims, &group_obj)
-        .map_err(|_| (StatusCode::FORBIDDEN, "Access denied".into()))?;
+        .guard_group_access(&state.role_permissions, &claims, &group_obj)
+        .await
+        .map_err(|_| ErrorResponse::forbidden("GROUP_ACCESS_DENIED", "Access denied"))?;
 
     let students = fetch_students_in_group(&state.mongo, &group_obj.to_hex()).await?;
     let student_ids = students
@@ -256,7 +391,7 @@ pub async fn get_group_activity(
     let activity_points = service
         .aggregate_activity(&student_ids)
         .await
-        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?;
 
     let payload = activity_points
         .into_iter()
@@ -275,14 +410,13 @@ pub async fn get_recommendations(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
     Query(query): Query<GroupQuery>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    ensure_teacher_role(&claims)?;
-
+) -> Result<impl IntoResponse, ErrorResponse> {
     let group_obj = parse_object_id(&query.group_id, "groupId")?;
     let service = ReportingService::new(state.mongo.clone(), state.redis.clone());
     service
-        .guard_group_access(&claims, &group_obj)
-        .map_err(|_| (StatusCode::FORBIDDEN, "Access denied".into()))?;
+        .guard_group_access(&state.role_permissions, &claims, &group_obj)
+        .await
+        .map_err(|_| ErrorResponse::forbidden("GROUP_ACCESS_DENIED", "Access denied"))?;
 
     let students = fetch_students_in_group(&state.mongo, &group_obj.to_hex()).await?;
     let student_ids = students
@@ -296,7 +430,7 @@ pub async fn get_recommendations(
     let recs = service
         .aggregate_recommendations(&student_ids)
         .await
-        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?;
 
     let payload = recs
         .into_iter()
@@ -317,6 +451,31 @@ pub struct CreateTemplateRequest {
     body: String,
 }
 
+/// Изменение шаблона: отсутствующие поля остаются прежними
+#[derive(Debug, Deserialize)]
+pub struct UpdateTemplateRequest {
+    name: Option<String>,
+    subject: Option<String>,
+    body: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct PreviewTemplateRequest {
+    #[serde(default, rename = "groupId")]
+    group_id: Option<String>,
+    /// Ученик группы `groupId`; без него - вымышленный ученик
+    #[serde(default, rename = "studentId")]
+    student_id: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct TemplatePreviewResponse {
+    subject: String,
+    body: String,
+    #[serde(rename = "studentId")]
+    student_id: Option<String>,
+}
+
 #[derive(Debug, Serialize)]
 struct TemplateResponse {
     id: String,
@@ -339,7 +498,10 @@ pub struct SendNotificationRequest {
 
 #[derive(Debug, Serialize)]
 struct SendNotificationResponse {
-    sent: usize,
+    #[serde(rename = "notificationId")]
+    notification_id: String,
+    /// Писем поставлено в очередь; отправляет их `EmailWorker`
+    queued: usize,
     #[serde(rename = "emailDisabled")]
     email_disabled: bool,
 }
@@ -356,13 +518,14 @@ struct NotificationHistoryEntry {
     #[serde(rename = "recipientsCount")]
     recipients_count: usize,
     status: String,
+    /// Доставка по каждому получателю (пусто у рассылок до очереди писем)
+    deliveries: Vec<RecipientDeliveryStatus>,
 }
 
 pub async fn list_notification_templates(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    ensure_teacher_role(&claims)?;
+) -> Result<impl IntoResponse, ErrorResponse> {
     let teacher_id = parse_object_id(&claims.sub, "teacher_id")?;
 
     let collection = state
@@ -376,13 +539,13 @@ pub async fn list_notification_templates(
                 .build(),
         )
         .await
-        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?;
 
     let mut templates = Vec::new();
     while let Some(template) = cursor
         .try_next()
         .await
-        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?
     {
         templates.push(template_to_response(&template));
     }
@@ -394,18 +557,19 @@ pub async fn create_notification_template(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
     AppJson(payload): AppJson<CreateTemplateRequest>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    ensure_teacher_role(&claims)?;
+) -> Result<impl IntoResponse, ErrorResponse> {
     if payload.name.trim().is_empty()
         || payload.subject.trim().is_empty()
         || payload.body.trim().is_empty()
     {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            "Name, subject and body are required".into(),
+        return Err(ErrorResponse::bad_request(
+            "VALIDATION_ERROR",
+            "Name, subject and body are required",
         ));
     }
 
+    validate_template_variables(&payload.subject, &payload.body)?;
+
     let teacher_id = parse_object_id(&claims.sub, "teacher_id")?;
     let collection = state
         .mongo
@@ -424,16 +588,144 @@ pub async fn create_notification_template(
     collection
         .insert_one(template.clone())
         .await
-        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?;
 
     Ok(Json(template_to_response(&template)))
 }
 
+/// PUT /teacher/notifications/templates/{id} - изменить свой шаблон
+pub async fn update_notification_template(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    ObjectIdParam(template_id): ObjectIdParam,
+    AppJson(payload): AppJson<UpdateTemplateRequest>,
+) -> Result<impl IntoResponse, ErrorResponse> {
+    let teacher_id = parse_object_id(&claims.sub, "teacher_id")?;
+    let template = load_own_template(&state.mongo, &template_id, &teacher_id).await?;
+
+    let name = payload.name.unwrap_or(template.name);
+    let subject = payload.subject.unwrap_or(template.subject);
+    let body = payload.body.unwrap_or(template.body);
+    if name.trim().is_empty() || subject.trim().is_empty() || body.trim().is_empty() {
+        return Err(ErrorResponse::bad_request(
+            "VALIDATION_ERROR",
+            "Name, subject and body are required",
+        ));
+    }
+    validate_template_variables(&subject, &body)?;
+
+    let updated = NotificationTemplate {
+        name: name.trim().to_string(),
+        subject: subject.trim().to_string(),
+        body: body.trim().to_string(),
+        updated_at: Utc::now(),
+        ..template
+    };
+    state
+        .mongo
+        .collection::<NotificationTemplate>("notification_templates")
+        .replace_one(
+            doc! { "_id": &template_id, "teacher_id": &teacher_id },
+            &updated,
+        )
+        .await
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?;
+
+    Ok(Json(template_to_response(&updated)))
+}
+
+/// DELETE /teacher/notifications/templates/{id} - удалить свой шаблон.
+/// История рассылок остаётся, название шаблона в ней пропадает
+pub async fn delete_notification_template(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    ObjectIdParam(template_id): ObjectIdParam,
+) -> Result<StatusCode, ErrorResponse> {
+    let teacher_id = parse_object_id(&claims.sub, "teacher_id")?;
+    let deleted = state
+        .mongo
+        .collection::<NotificationTemplate>("notification_templates")
+        .delete_one(doc! { "_id": &template_id, "teacher_id": &teacher_id })
+        .await
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?;
+    if deleted.deleted_count == 0 {
+        return Err(template_not_found());
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /teacher/notifications/templates/{id}/preview - письмо так, как его получит
+/// ученик группы; без `studentId` - вымышленный ученик с примерным прогрессом
+pub async fn preview_notification_template(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    ObjectIdParam(template_id): ObjectIdParam,
+    AppJson(payload): AppJson<PreviewTemplateRequest>,
+) -> Result<impl IntoResponse, ErrorResponse> {
+    let teacher_id = parse_object_id(&claims.sub, "teacher_id")?;
+    let template = load_own_template(&state.mongo, &template_id, &teacher_id).await?;
+    let teacher_name = load_user_name(&state.mongo, &teacher_id).await?;
+
+    let group = match payload.group_id.as_deref() {
+        Some(group_id) => {
+            let group_obj = parse_object_id(group_id, "groupId")?;
+            ReportingService::new(state.mongo.clone(), state.redis.clone())
+                .guard_group_access(&state.role_permissions, &claims, &group_obj)
+                .await
+                .map_err(|_| {
+                    ErrorResponse::forbidden("GROUP_ACCESS_DENIED", "Access denied for this group")
+                })?;
+            let group = GroupService::new(state.mongo.clone())
+                .get_group(&group_obj.to_hex())
+                .await
+                .map_err(|err| ErrorResponse::internal(err.to_string()))?;
+            Some((group_obj, group.name))
+        }
+        None => None,
+    };
+    let group_name = group
+        .as_ref()
+        .map(|(_, name)| name.clone())
+        .unwrap_or_else(|| "7А".to_string());
+
+    let (student_id, context) = match (payload.student_id.as_deref(), &group) {
+        (Some(student_id), Some((group_obj, _))) => {
+            let student_obj = parse_object_id(student_id, "studentId")?;
+            let student =
+                fetch_single_student(&state.mongo, &student_obj, &group_obj.to_hex()).await?;
+            let progress = NotificationTemplateService::new(state.mongo.clone())
+                .latest_progress(&[student.id.to_hex()])
+                .await
+                .map_err(|err| ErrorResponse::internal(err.to_string()))?;
+            let context = TemplateContext {
+                student_name: student.name.clone(),
+                group_name,
+                teacher_name,
+                ..TemplateContext::default()
+            }
+            .with_progress(progress.get(&student.id.to_hex()));
+            (Some(student.id.to_hex()), context)
+        }
+        (Some(_), None) => {
+            return Err(ErrorResponse::bad_request(
+                "VALIDATION_ERROR",
+                "groupId is required to preview for a student",
+            ));
+        }
+        (None, _) => (None, TemplateContext::sample(&group_name, &teacher_name)),
+    };
+
+    Ok(Json(TemplatePreviewResponse {
+        subject: render(&template.subject, &context),
+        body: render(&template.body, &context),
+        student_id,
+    }))
+}
+
 pub async fn list_notification_history(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    ensure_teacher_role(&claims)?;
+) -> Result<impl IntoResponse, ErrorResponse> {
     let teacher_id = parse_object_id(&claims.sub, "teacher_id")?;
 
     let collection = state
@@ -448,31 +740,40 @@ pub async fn list_notification_history(
                 .build(),
         )
         .await
-        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?;
 
     let mut rows = Vec::new();
     let mut template_ids = HashSet::new();
     while let Some(entry) = cursor
         .try_next()
         .await
-        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?
     {
         template_ids.insert(entry.template_id);
         rows.push(entry);
     }
 
     let template_names = load_template_names(&state.mongo, &template_ids).await?;
+    let notification_ids = rows.iter().map(|entry| entry.id).collect::<Vec<_>>();
+    let mut deliveries = EmailOutboxService::new(state.mongo.clone())
+        .list_for_notifications(&notification_ids)
+        .await
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?;
 
     let payload = rows
         .into_iter()
-        .map(|entry| NotificationHistoryEntry {
-            id: entry.id.to_hex(),
-            template_id: entry.template_id.to_hex(),
-            template_name: template_names.get(&entry.template_id).cloned(),
-            subject: entry.subject,
-            sent_at: entry.sent_at,
-            recipients_count: entry.recipients.len(),
-            status: entry.status,
+        .map(|entry| {
+            let messages = deliveries.remove(&entry.id).unwrap_or_default();
+            NotificationHistoryEntry {
+                id: entry.id.to_hex(),
+                template_id: entry.template_id.to_hex(),
+                template_name: template_names.get(&entry.template_id).cloned(),
+                subject: entry.subject,
+                sent_at: entry.sent_at,
+                recipients_count: entry.recipients.len(),
+                status: delivery_status(&entry.status, &messages),
+                deliveries: messages.into_iter().map(Into::into).collect(),
+            }
         })
         .collect::<Vec<_>>();
 
@@ -483,8 +784,7 @@ pub async fn send_notifications(
     State(state): State<Arc<AppState>>,
     Extension(claims): Extension<JwtClaims>,
     AppJson(payload): AppJson<SendNotificationRequest>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    ensure_teacher_role(&claims)?;
+) -> Result<impl IntoResponse, ErrorResponse> {
     let teacher_id = parse_object_id(&claims.sub, "teacher_id")?;
     let group_obj = parse_object_id(&payload.group_id, "groupId")?;
     let template_obj = parse_object_id(&payload.template_id, "templateId")?;
@@ -493,8 +793,11 @@ pub async fn send_notifications(
 
     let reporting_service = ReportingService::new(state.mongo.clone(), state.redis.clone());
     reporting_service
-        .guard_group_access(&claims, &group_obj)
-        .map_err(|_| (StatusCode::FORBIDDEN, "Access denied for this group".into()))?;
+        .guard_group_access(&state.role_permissions, &claims, &group_obj)
+        .await
+        .map_err(|_| {
+            ErrorResponse::forbidden("GROUP_ACCESS_DENIED", "Access denied for this group")
+        })?;
 
     let template_collection = state
         .mongo
@@ -502,14 +805,14 @@ pub async fn send_notifications(
     let template = template_collection
         .find_one(doc! { "_id": &template_obj, "teacher_id": &teacher_id })
         .await
-        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?
-        .ok_or_else(|| (StatusCode::NOT_FOUND, "Template not found".into()))?;
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?
+        .ok_or_else(template_not_found)?;
 
     let group_service = GroupService::new(state.mongo.clone());
     let group = group_service
         .get_group(&group_obj.to_hex())
         .await
-        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?;
     let group_name = group.name;
 
     let students = fetch_students_in_group(&state.mongo, &group_obj.to_hex()).await?;
@@ -523,55 +826,134 @@ pub async fn send_notifications(
     };
 
     if recipients.is_empty() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            "No recipients found for this notification".into(),
+        return Err(ErrorResponse::bad_request(
+            "NO_RECIPIENTS",
+            "No recipients found for this notification",
         ));
     }
 
-    let email_service = EmailService::new(state.mongo.clone());
+    let teacher_name = load_user_name(&state.mongo, &teacher_id).await?;
+    let recipient_ids = recipients
+        .iter()
+        .map(|student| student.id.to_hex())
+        .collect::<Vec<_>>();
+    let progress = NotificationTemplateService::new(state.mongo.clone())
+        .latest_progress(&recipient_ids)
+        .await
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?;
+
     let email_disabled = EmailService::sending_disabled();
-    let mut sent = 0usize;
-    for student in &recipients {
-        let subject = apply_template(&template.subject, student, &group_name);
-        let body = apply_template(&template.body, student, &group_name);
-        if !email_disabled {
-            email_service
-                .send_notification_email(&student.email, &student.name, &subject, &body)
-                .await
-                .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
-        }
-        sent += 1;
-    }
+    let notification_id = ObjectId::new();
+    let emails = recipients
+        .iter()
+        .map(|student| {
+            let context = TemplateContext {
+                student_name: student.name.clone(),
+                group_name: group_name.clone(),
+                teacher_name: teacher_name.clone(),
+                ..TemplateContext::default()
+            }
+            .with_progress(progress.get(&student.id.to_hex()));
+            OutgoingEmail {
+                recipient_id: student.id,
+                recipient_email: student.email.clone(),
+                recipient_name: student.name.clone(),
+                subject: render(&template.subject, &context),
+                body: render(&template.body, &context),
+            }
+        })
+        .collect::<Vec<_>>();
 
+    // Сначала запись истории: по ней учитель найдёт статус, даже если очередь
+    // заполнится не целиком
     let history_collection = state
         .mongo
         .collection::<SentNotification>("sent_notifications");
-    let recipient_ids = recipients
-        .iter()
-        .map(|student| student.id)
-        .collect::<Vec<_>>();
     let history_entry = SentNotification {
-        id: ObjectId::new(),
+        id: notification_id,
         teacher_id,
         template_id: template_obj,
-        recipients: recipient_ids,
+        recipients: recipients.iter().map(|student| student.id).collect(),
         subject: template.subject.clone(),
         body: template.body.clone(),
         sent_at: Utc::now(),
-        status: if email_disabled { "skipped" } else { "sent" }.to_string(),
+        status: if email_disabled { "skipped" } else { "queued" }.to_string(),
     };
     history_collection
         .insert_one(history_entry)
         .await
-        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?;
+
+    // Копия в центре уведомлений приходит независимо от отправки писем
+    let in_app = emails
+        .iter()
+        .map(|email| NewNotification {
+            recipient_id: email.recipient_id.to_hex(),
+            kind: InAppNotificationKind::TeacherMessage,
+            title: email.subject.clone(),
+            body: email.body.clone(),
+            dedup_key: None,
+        })
+        .collect::<Vec<_>>();
+
+    let queued = EmailOutboxService::new(state.mongo.clone())
+        .enqueue(notification_id, emails, email_disabled)
+        .await
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?;
+
+    NotificationCenterService::new(state.mongo.clone(), state.redis.clone())
+        .create_many(in_app)
+        .await
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?;
 
     Ok(Json(SendNotificationResponse {
-        sent,
+        notification_id: notification_id.to_hex(),
+        queued,
         email_disabled,
     }))
 }
 
+/// GET /teacher/notifications/{id}/status - доставка рассылки по получателям
+pub async fn get_notification_status(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JwtClaims>,
+    ObjectIdParam(notification_id): ObjectIdParam,
+) -> Result<Json<NotificationDeliveryResponse>, ErrorResponse> {
+    let teacher_id = parse_object_id(&claims.sub, "teacher_id")?;
+    let notification = state
+        .mongo
+        .collection::<SentNotification>("sent_notifications")
+        .find_one(doc! { "_id": &notification_id, "teacher_id": &teacher_id })
+        .await
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?
+        .ok_or_else(|| {
+            ErrorResponse::not_found("NOTIFICATION_NOT_FOUND", "Notification not found")
+        })?;
+
+    let messages = EmailOutboxService::new(state.mongo.clone())
+        .list_for_notification(&notification.id)
+        .await
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?;
+
+    Ok(Json(NotificationDeliveryResponse {
+        id: notification.id.to_hex(),
+        status: delivery_status(&notification.status, &messages),
+        recipients: messages.into_iter().map(Into::into).collect(),
+    }))
+}
+
+/// Статус рассылки по её письмам; у старых записей без очереди - сохранённый
+fn delivery_status(stored: &str, messages: &[EmailOutboxMessage]) -> String {
+    if messages.is_empty() {
+        return stored.to_string();
+    }
+    let statuses = messages
+        .iter()
+        .map(|message| message.status)
+        .collect::<Vec<_>>();
+    aggregate_status(&statuses).to_string()
+}
+
 fn template_to_response(template: &NotificationTemplate) -> TemplateResponse {
     TemplateResponse {
         id: template.id.to_hex(),
@@ -583,19 +965,13 @@ fn template_to_response(template: &NotificationTemplate) -> TemplateResponse {
     }
 }
 
-fn parse_student_ids(ids: &[String]) -> Result<Option<HashSet<ObjectId>>, (StatusCode, String)> {
+fn parse_student_ids(ids: &[String]) -> Result<Option<HashSet<ObjectId>>, ErrorResponse> {
     if ids.is_empty() {
         return Ok(None);
     }
     let mut parsed = HashSet::new();
     for value in ids {
-        let id = ObjectId::parse_str(value).map_err(|_| {
-            (
-                StatusCode::BAD_REQUEST,
-                format!("Invalid student ID: {}", value),
-            )
-        })?;
-        parsed.insert(id);
+        parsed.insert(parse_object_id(value, "studentIds")?);
     }
     Ok(Some(parsed))
 }
@@ -603,7 +979,7 @@ fn parse_student_ids(ids: &[String]) -> Result<Option<HashSet<ObjectId>>, (Statu
 async fn load_template_names(
     db: &Database,
     ids: &HashSet<ObjectId>,
-) -> Result<HashMap<ObjectId, String>, (StatusCode, String)> {
+) -> Result<HashMap<ObjectId, String>, ErrorResponse> {
     if ids.is_empty() {
         return Ok(HashMap::new());
     }
@@ -612,45 +988,67 @@ async fn load_template_names(
     let mut cursor = collection
         .find(doc! { "_id": { "$in": id_list } })
         .await
-        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?;
 
     let mut map = HashMap::new();
     while let Some(template) = cursor
         .try_next()
         .await
-        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?
     {
         map.insert(template.id, template.name);
     }
     Ok(map)
 }
 
-fn apply_template(text: &str, student: &StudentRecord, group_name: &str) -> String {
-    text.replace("{student_name}", &student.name)
-        .replace("{group_name}", group_name)
+/// 400 `UNKNOWN_TEMPLATE_VARIABLES`, если в теме или тексте есть неизвестный плейсхолдер
+fn validate_template_variables(subject: &str, body: &str) -> Result<(), ErrorResponse> {
+    let unknown = unknown_variables(&[subject, body]);
+    if unknown.is_empty() {
+        return Ok(());
+    }
+    Err(ErrorResponse::bad_request(
+        "UNKNOWN_TEMPLATE_VARIABLES",
+        format!("Unknown template variables: {}", unknown.join(", ")),
+    )
+    .with_details(serde_json::json!({
+        "unknown": unknown,
+        "allowed": TEMPLATE_VARIABLES,
+    })))
 }
 
-fn ensure_teacher_role(claims: &JwtClaims) -> Result<(), (StatusCode, String)> {
-    if claims.role == "teacher" || claims.role == "admin" {
-        Ok(())
-    } else {
-        Err((StatusCode::FORBIDDEN, "Teacher role required".to_string()))
-    }
+fn template_not_found() -> ErrorResponse {
+    ErrorResponse::not_found("NOTIFICATION_TEMPLATE_NOT_FOUND", "Template not found")
 }
 
-fn parse_object_id(value: &str, field: &str) -> Result<ObjectId, (StatusCode, String)> {
-    ObjectId::parse_str(value).map_err(|_| {
-        (
-            StatusCode::BAD_REQUEST,
-            format!("Invalid {}: must be ObjectId", field),
-        )
-    })
+/// Шаблон учителя; чужой шаблон неотличим от несуществующего
+async fn load_own_template(
+    db: &Database,
+    template_id: &ObjectId,
+    teacher_id: &ObjectId,
+) -> Result<NotificationTemplate, ErrorResponse> {
+    db.collection::<NotificationTemplate>("notification_templates")
+        .find_one(doc! { "_id": template_id, "teacher_id": teacher_id })
+        .await
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?
+        .ok_or_else(template_not_found)
+}
+
+async fn load_user_name(db: &Database, user_id: &ObjectId) -> Result<String, ErrorResponse> {
+    let user = db
+        .collection::<Document>("users")
+        .find_one(doc! { "_id": user_id })
+        .await
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?;
+    Ok(user
+        .and_then(|user| user.get_str("name").ok().map(str::to_string))
+        .unwrap_or_default())
 }
 
 async fn fetch_students_in_group(
     db: &Database,
     group_id: &str,
-) -> Result<Vec<StudentRecord>, (StatusCode, String)> {
+) -> Result<Vec<StudentRecord>, ErrorResponse> {
     let users_collection = db.collection::<Document>("users");
     let filter = doc! {
         "group_ids": group_id,
@@ -659,16 +1057,16 @@ async fn fetch_students_in_group(
     let mut cursor = users_collection
         .find(filter)
         .await
-        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?;
 
     let mut records = Vec::new();
     while let Some(doc) = cursor
         .try_next()
         .await
-        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?
     {
         let record = from_document::<StudentRecord>(doc)
-            .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+            .map_err(|err| ErrorResponse::internal(err.to_string()))?;
         records.push(record);
     }
 
@@ -679,7 +1077,7 @@ async fn fetch_single_student(
     db: &Database,
     student_id: &ObjectId,
     group_id: &str,
-) -> Result<StudentRecord, (StatusCode, String)> {
+) -> Result<StudentRecord, ErrorResponse> {
     let users_collection = db.collection::<Document>("users");
     let filter = doc! {
         "_id": student_id,
@@ -689,16 +1087,13 @@ async fn fetch_single_student(
     let student_doc = users_collection
         .find_one(filter)
         .await
-        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?
+        .map_err(|err| ErrorResponse::internal(err.to_string()))?
         .ok_or_else(|| {
-            (
-                StatusCode::NOT_FOUND,
-                "Student not found in this group".to_string(),
-            )
+            ErrorResponse::not_found("STUDENT_NOT_FOUND", "Student not found in this group")
         })?;
 
     from_document::<StudentRecord>(student_doc)
-        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))
+        .map_err(|err| ErrorResponse::internal(err.to_string()))
 }
 
 fn student_summary_from_record(
@@ -1,30 +1,39 @@
 #![allow(dead_code)]
 
 use axum::{
-    extract::Request,
+    extract::{DefaultBodyLimit, Request},
     http::{header, HeaderValue, Method},
     middleware::{self, Next},
     response::Response,
-    routing::{delete, get, post, put},
+    routing::{delete, get, patch, post, put},
     Router,
 };
 use std::time::Duration;
-use tower_http::{cors::CorsLayer, trace::TraceLayer};
+use tower_http::{
+    cors::{AllowOrigin, CorsLayer},
+    trace::TraceLayer,
+};
 use tracing::{field, Span};
 use uuid::Uuid;
 
+#[cfg(feature = "client")]
+pub mod client;
 pub mod config;
 pub mod extractors;
 pub mod handlers;
 pub mod metrics;
 pub mod middlewares;
 pub mod models;
+pub mod openapi;
 pub mod services;
+pub mod telemetry;
 pub mod utils;
 
 pub use config::Config;
 pub use services::AppState;
 
+use models::permission::Permission;
+
 /// CSP middleware adds Content-Security-Policy header to all responses
 async fn csp_middleware(request: Request, next: Next) -> Response {
     let mut response = next.run(request).await;
@@ -50,15 +59,23 @@ async fn csp_middleware(request: Request, next: Next) -> Response {
 }
 
 pub fn create_router(app_state: std::sync::Arc<services::AppState>) -> Router {
-    // CORS configuration for reporting endpoints
+    // CORS configuration for reporting endpoints; origin-ы берутся из снимка настроек,
+    // поэтому перезагрузка применяется без пересборки роутера
+    let cors_state = app_state.clone();
     let cors = CorsLayer::new()
-        .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
+        .allow_methods([Method::GET, Method::POST, Method::DELETE, Method::OPTIONS])
         .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE])
-        .allow_origin(tower_http::cors::Any); // TODO: restrict to specific origins in production
+        .allow_origin(AllowOrigin::predicate(move |origin, _| {
+            cors_state.runtime.snapshot().allows_origin(origin)
+        }));
+    let default_body_limit = app_state.config.body_limits.default_bytes;
+    let maintenance_state = app_state.clone();
 
     Router::new()
         // Public endpoints (no auth required)
         .route("/health", get(handlers::health_check))
+        .route("/health/live", get(handlers::liveness_check))
+        .route("/health/ready", get(handlers::readiness_check))
         .route(
             "/api/feature-flags",
             get(handlers::feature_flags::get_feature_flags),
@@ -66,15 +83,48 @@ pub fn create_router(app_state: std::sync::Arc<services::AppState>) -> Router {
         // Metrics endpoint with Basic Auth protection
         .route(
             "/metrics",
-            get(handlers::metrics_handler)
-                .layer(middleware::from_fn(handlers::metrics_auth_middleware)),
+            get(handlers::metrics_handler).layer(middleware::from_fn_with_state(
+                app_state.clone(),
+                handlers::metrics_auth_middleware,
+            )),
+        )
+        .route(
+            "/api/v1/feature-flags",
+            get(handlers::feature_flags::get_user_feature_flags).layer(
+                middleware::from_fn_with_state(
+                    app_state.clone(),
+                    middlewares::auth::auth_middleware,
+                ),
+            ),
+        )
+        .route(
+            "/api/v1/prefetch-manifest",
+            get(handlers::prefetch::get_prefetch_manifest).layer(middleware::from_fn_with_state(
+                app_state.clone(),
+                middlewares::auth::auth_middleware,
+            )),
+        )
+        // OpenAPI: спецификация публичная, Swagger UI - только для админов
+        .merge(openapi::openapi_routes())
+        .merge(
+            openapi::swagger_ui()
+                .route_layer(middleware::from_fn_with_state(
+                    (app_state.clone(), Permission::SettingsManage),
+                    middlewares::permissions::require_permission,
+                ))
+                .route_layer(middleware::from_fn_with_state(
+                    app_state.clone(),
+                    middlewares::auth::auth_middleware,
+                )),
         )
         // Auth endpoints (mixed: some public, some protected)
         .nest("/api/v1/auth", auth_routes(app_state.clone()))
+        // Согласия по ссылке родителя: доступ даёт только токен ссылки
+        .nest("/api/v1/guardian", guardian_routes())
         // Protected endpoints (require JWT)
         .nest(
             "/api/v1/sessions",
-            sessions_routes()
+            sessions_routes(app_state.clone())
                 .layer(middleware::from_fn(middlewares::csrf::csrf_middleware))
                 .layer(middleware::from_fn_with_state(
                     app_state.clone(),
@@ -94,6 +144,10 @@ pub fn create_router(app_state: std::sync::Arc<services::AppState>) -> Router {
         .nest(
             "/api/v1/teacher",
             teacher_routes()
+                .route_layer(middleware::from_fn_with_state(
+                    (app_state.clone(), Permission::ReportsView),
+                    middlewares::permissions::require_permission,
+                ))
                 .layer(middleware::from_fn(middlewares::csrf::csrf_middleware))
                 .layer(middleware::from_fn_with_state(
                     app_state.clone(),
@@ -109,6 +163,51 @@ pub fn create_router(app_state: std::sync::Arc<services::AppState>) -> Router {
                     middlewares::auth::auth_middleware,
                 )),
         )
+        .nest(
+            "/api/v1/me",
+            me_routes()
+                .layer(middleware::from_fn(middlewares::csrf::csrf_middleware))
+                .layer(middleware::from_fn_with_state(
+                    app_state.clone(),
+                    middlewares::auth::auth_middleware,
+                )),
+        )
+        .nest(
+            "/api/v1/assignments",
+            assignments_routes()
+                .layer(middleware::from_fn(middlewares::csrf::csrf_middleware))
+                .layer(middleware::from_fn_with_state(
+                    app_state.clone(),
+                    middlewares::auth::auth_middleware,
+                )),
+        )
+        .nest(
+            "/api/v1/notifications",
+            notifications_routes()
+                .layer(middleware::from_fn(middlewares::csrf::csrf_middleware))
+                .layer(middleware::from_fn_with_state(
+                    app_state.clone(),
+                    middlewares::auth::auth_middleware,
+                )),
+        )
+        .nest(
+            "/api/v1/review",
+            review_routes()
+                .layer(middleware::from_fn(middlewares::csrf::csrf_middleware))
+                .layer(middleware::from_fn_with_state(
+                    app_state.clone(),
+                    middlewares::auth::auth_middleware,
+                )),
+        )
+        .nest(
+            "/api/v1/groups",
+            groups_routes()
+                .layer(middleware::from_fn(middlewares::csrf::csrf_middleware))
+                .layer(middleware::from_fn_with_state(
+                    app_state.clone(),
+                    middlewares::auth::auth_middleware,
+                )),
+        )
         .nest(
             "/admin",
             admin_routes(app_state.clone())
@@ -119,6 +218,16 @@ pub fn create_router(app_state: std::sync::Arc<services::AppState>) -> Router {
                 )),
         )
         .with_state(app_state)
+        // Режим обслуживания: 503 всем, кроме разрешённых ролей и проб
+        .layer(middleware::from_fn_with_state(
+            maintenance_state,
+            middlewares::maintenance::maintenance_middleware,
+        ))
+        // Группы маршрутов с другим лимитом задают свой DefaultBodyLimit - внутренний важнее
+        .layer(DefaultBodyLimit::max(default_body_limit))
+        .layer(middleware::from_fn(
+            middlewares::body_limit::payload_too_large_middleware,
+        ))
         .layer(middleware::from_fn(csp_middleware)) // Apply CSP to all responses
         .layer(middleware::from_fn(
             middlewares::metrics::metrics_middleware,
@@ -140,11 +249,16 @@ pub fn create_router(app_state: std::sync::Arc<services::AppState>) -> Router {
                         method = %method,
                         path = %path,
                         status = field::Empty,
-                        user_id = field::Empty
+                        user_id = field::Empty,
+                        otel.status_code = field::Empty
                     )
                 })
                 .on_response(|response: &Response, latency: Duration, span: &Span| {
                     span.record("status", field::display(response.status().as_u16()));
+                    // 5xx помечает трейс ошибкой - такие трейсы экспортируются всегда
+                    if response.status().is_server_error() {
+                        span.record("otel.status_code", "ERROR");
+                    }
                     tracing::info!(
                         parent: span,
                         latency_ms = latency.as_millis(),
@@ -157,25 +271,66 @@ pub fn create_router(app_state: std::sync::Arc<services::AppState>) -> Router {
         ))
 }
 
-fn sessions_routes() -> Router<std::sync::Arc<services::AppState>> {
+fn sessions_routes(
+    app_state: std::sync::Arc<services::AppState>,
+) -> Router<std::sync::Arc<services::AppState>> {
     Router::new()
-        .route("/", post(handlers::sessions::create_session))
+        // Сессия открывается только для пользователя из токена
+        .route(
+            "/",
+            post(handlers::sessions::create_session).layer(middleware::from_fn_with_state(
+                app_state.clone(),
+                middlewares::auth::auth_middleware,
+            )),
+        )
+        .route(
+            "/auto",
+            post(handlers::sessions::create_auto_session).layer(middleware::from_fn_with_state(
+                app_state.clone(),
+                middlewares::auth::auth_middleware,
+            )),
+        )
         .route("/{id}", get(handlers::sessions::get_session))
         .route("/{id}/complete", post(handlers::sessions::complete_session))
         .route("/{id}/answers", post(handlers::sessions::submit_answer))
         .route("/{id}/hints", post(handlers::sessions::request_hint))
         .route("/{id}/stream", get(handlers::sse::session_stream))
+        // Сигналы принимаются только от владельца сессии
+        .route(
+            "/{id}/signals",
+            post(handlers::sessions::submit_signals).layer(middleware::from_fn_with_state(
+                app_state,
+                middlewares::auth::auth_middleware,
+            )),
+        )
 }
 
 fn reporting_routes() -> Router<std::sync::Arc<services::AppState>> {
     Router::new()
         .route("/groups/{id}", get(handlers::reporting::get_group_stats))
         .route("/users/{id}", get(handlers::reporting::get_user_stats))
+        .route(
+            "/users/{id}/export",
+            post(handlers::reporting::request_user_export),
+        )
         .route("/topics/{id}", get(handlers::reporting::get_topic_stats))
         .route(
             "/groups/{id}/export",
             post(handlers::reporting::request_group_export),
         )
+        .route(
+            "/groups/{id}/exports",
+            get(handlers::reporting::list_group_exports),
+        )
+        .route(
+            "/groups/{id}/export-schedules",
+            get(handlers::reporting::list_export_schedules)
+                .post(handlers::reporting::create_export_schedule),
+        )
+        .route(
+            "/groups/{id}/export-schedules/{schedule_id}",
+            delete(handlers::reporting::delete_export_schedule),
+        )
         .route("/exports/{id}", get(handlers::reporting::get_export_status))
 }
 
@@ -190,6 +345,23 @@ fn teacher_routes() -> Router<std::sync::Arc<services::AppState>> {
             "/groups/{group_id}/students/{student_id}",
             get(handlers::teacher::get_student_detail),
         )
+        .route(
+            "/groups/{group_id}/gradebook.csv",
+            get(handlers::teacher::export_gradebook),
+        )
+        .route(
+            "/groups/{group_id}/assignments",
+            get(handlers::assignments::list_group_assignments)
+                .post(handlers::assignments::create_assignment),
+        )
+        .route(
+            "/groups/{group_id}/assignments/{assignment_id}",
+            patch(handlers::assignments::extend_assignment),
+        )
+        .route(
+            "/groups/{group_id}/assignments/{assignment_id}/progress",
+            get(handlers::assignments::get_assignment_progress),
+        )
         .route(
             "/analytics/topics",
             get(handlers::teacher::list_group_topic_analytics),
@@ -207,6 +379,15 @@ fn teacher_routes() -> Router<std::sync::Arc<services::AppState>> {
             get(handlers::teacher::list_notification_templates)
                 .post(handlers::teacher::create_notification_template),
         )
+        .route(
+            "/notifications/templates/{id}",
+            put(handlers::teacher::update_notification_template)
+                .delete(handlers::teacher::delete_notification_template),
+        )
+        .route(
+            "/notifications/templates/{id}/preview",
+            post(handlers::teacher::preview_notification_template),
+        )
         .route(
             "/notifications/send",
             post(handlers::teacher::send_notifications),
@@ -215,6 +396,10 @@ fn teacher_routes() -> Router<std::sync::Arc<services::AppState>> {
             "/notifications/history",
             get(handlers::teacher::list_notification_history),
         )
+        .route(
+            "/notifications/{id}/status",
+            get(handlers::teacher::get_notification_status),
+        )
 }
 
 fn student_routes() -> Router<std::sync::Arc<services::AppState>> {
@@ -224,104 +409,190 @@ fn student_routes() -> Router<std::sync::Arc<services::AppState>> {
         .route("/stats", get(handlers::student::get_stats))
 }
 
+fn me_routes() -> Router<std::sync::Arc<services::AppState>> {
+    Router::new()
+        .route(
+            "/achievements",
+            get(handlers::achievements::get_achievements),
+        )
+        .route(
+            "/certificates",
+            get(handlers::certificates::list_certificates),
+        )
+        .route(
+            "/certificates/{level_id}/pdf",
+            get(handlers::certificates::download_certificate),
+        )
+}
+
+fn assignments_routes() -> Router<std::sync::Arc<services::AppState>> {
+    Router::new().route("/", get(handlers::assignments::list_my_assignments))
+}
+
+fn notifications_routes() -> Router<std::sync::Arc<services::AppState>> {
+    Router::new()
+        .route("/", get(handlers::notifications::list_notifications))
+        .route(
+            "/read-all",
+            post(handlers::notifications::mark_all_notifications_read),
+        )
+        .route(
+            "/stream",
+            get(handlers::notifications::notifications_stream),
+        )
+        .route(
+            "/{id}/read",
+            post(handlers::notifications::mark_notification_read),
+        )
+}
+
+fn review_routes() -> Router<std::sync::Arc<services::AppState>> {
+    Router::new().route("/next", get(handlers::review::next_review))
+}
+
+fn guardian_routes() -> Router<std::sync::Arc<services::AppState>> {
+    Router::new()
+        .route(
+            "/consents/{token}",
+            get(handlers::guardian::get_guardian_consents)
+                .post(handlers::guardian::record_guardian_consent),
+        )
+        .route(
+            "/consents/{token}/{consent_type}",
+            delete(handlers::guardian::revoke_guardian_consent),
+        )
+}
+
+fn groups_routes() -> Router<std::sync::Arc<services::AppState>> {
+    Router::new().route("/join", post(handlers::student::join_group))
+}
+
 fn admin_routes(
     app_state: std::sync::Arc<services::AppState>,
 ) -> Router<std::sync::Arc<services::AppState>> {
-    Router::new()
-        // Content management
+    let content = admin_content_routes(app_state.config.body_limits.content_bytes)
+        .route("/queue", get(handlers::admin::queue_status))
         .route(
-            "/templates",
-            get(handlers::admin::list_templates).post(handlers::admin::create_template),
+            "/queue/claim/{entry_id}",
+            post(handlers::admin::claim_queue_entry),
         )
+        // Метрики только для чтения - их видит и контент-админ (docs/rbac.md)
+        .route("/system/metrics", get(handlers::admin::get_system_metrics));
+
+    let users = Router::new()
+        // User management
         .route(
-            "/templates/{id}",
-            get(handlers::admin::get_template).patch(handlers::admin::update_template),
+            "/users",
+            get(handlers::admin::list_users).post(handlers::admin::create_user),
         )
+        .route("/users/bulk", post(handlers::admin::bulk_user_action))
         .route(
-            "/templates/{id}/revert",
-            post(handlers::admin::revert_template),
+            "/users/{id}",
+            get(handlers::admin::get_user)
+                .patch(handlers::admin::update_user)
+                .delete(handlers::admin::delete_user),
         )
+        .route("/users/{id}/logins", get(handlers::admin::list_user_logins))
+        .route("/users/{id}/block", post(handlers::admin::block_user))
+        .route("/users/{id}/unblock", post(handlers::admin::unblock_user))
         .route(
-            "/templates/{id}/versions",
-            get(handlers::admin::list_template_versions),
+            "/users/{id}/force-logout",
+            post(handlers::admin::force_logout_user),
         )
         .route(
-            "/templates/{id}/submit",
-            post(handlers::admin::submit_template_for_moderation),
+            "/users/{id}/reset-password",
+            post(handlers::admin::reset_user_password),
         )
         .route(
-            "/templates/{id}/approve",
-            post(handlers::admin::approve_template),
+            "/users/{id}/consents",
+            get(handlers::admin::list_user_consents).post(handlers::admin::record_user_consent),
         )
         .route(
-            "/templates/{id}/reject",
-            post(handlers::admin::reject_template),
+            "/users/{id}/consents/guardian-link",
+            post(handlers::admin::create_guardian_link),
         )
         .route(
-            "/templates/{id}/enrichment/run",
-            post(handlers::admin::start_template_enrichment_run),
+            "/users/{id}/consents/{consent_type}",
+            delete(handlers::admin::revoke_user_consent),
         )
+        // Group management
         .route(
-            "/templates/{id}/enrichment/runs",
-            get(handlers::admin::list_template_enrichment_runs),
+            "/groups",
+            get(handlers::admin::list_groups).post(handlers::admin::create_group),
         )
+        .route("/groups/export", get(handlers::admin::export_groups))
         .route(
-            "/templates/{id}/enrichment/tasks",
-            get(handlers::admin::list_template_enrichment_tasks),
+            "/groups/{id}",
+            get(handlers::admin::get_group)
+                .patch(handlers::admin::update_group)
+                .delete(handlers::admin::delete_group),
         )
+        .route("/groups/{id}/archive", post(handlers::admin::archive_group))
         .route(
-            "/templates/{id}/enrichment/tasks/{task_id}",
-            delete(handlers::admin::delete_template_enrichment_task),
+            "/groups/{id}/curator",
+            put(handlers::admin::reassign_group_curator),
         )
         .route(
-            "/templates/{id}/enrichment/tasks/{task_id}/regenerate",
-            post(handlers::admin::regenerate_template_enrichment_task),
+            "/groups/{id}/invite-codes",
+            get(handlers::admin::list_invite_codes).post(handlers::admin::create_invite_code),
         )
         .route(
-            "/templates/validate",
-            post(handlers::admin::validate_templates),
+            "/groups/{id}/invite-codes/{code_id}",
+            delete(handlers::admin::revoke_invite_code),
         )
         .route(
-            "/templates/duplicates",
-            get(handlers::admin::list_duplicates),
+            "/groups/{id}/members",
+            get(handlers::admin::list_group_members).post(handlers::admin::add_group_member),
         )
         .route(
-            "/embeddings/rebuild",
-            post(handlers::admin::rebuild_embeddings),
+            "/groups/{id}/members/{user_id}",
+            delete(handlers::admin::remove_group_member),
         )
         .route(
-            "/embeddings/progress",
-            get(handlers::admin::embedding_progress),
+            "/groups/{id}/consent-coverage",
+            get(handlers::admin::group_consent_coverage),
         )
+        // Session archive
         .route(
-            "/embeddings/consistency",
-            get(handlers::admin::embedding_consistency),
+            "/sessions/{id}/rehydrate",
+            post(handlers::admin::rehydrate_session),
         )
+        // Anticheat incidents
+        .route("/incidents", get(handlers::admin::list_incidents))
         .route(
-            "/topics",
-            get(handlers::admin::list_topics).post(handlers::admin::create_topic),
+            "/incidents/{id}",
+            get(handlers::admin::get_incident).put(handlers::admin::update_incident),
         )
         .route(
-            "/topics/{id}",
-            put(handlers::admin::update_topic).delete(handlers::admin::delete_topic),
+            "/incidents/{id}/timeline",
+            get(handlers::admin::get_incident_timeline),
         )
-        .route("/topics/{id}/levels", get(handlers::admin::list_levels))
-        .route("/levels", post(handlers::admin::create_level))
         .route(
-            "/levels/{id}",
-            put(handlers::admin::update_level).delete(handlers::admin::delete_level),
+            "/incidents/{id}/unblock",
+            post(handlers::admin::unblock_incident_user),
         )
-        .route("/levels/reorder", post(handlers::admin::reorder_levels))
         .route(
-            "/rules",
-            get(handlers::admin::list_rules).post(handlers::admin::create_rule),
+            "/incidents/{id}/assign",
+            post(handlers::admin::assign_incident),
         )
         .route(
-            "/rules/{id}",
-            put(handlers::admin::update_rule).delete(handlers::admin::delete_rule),
+            "/incidents/{id}/comments",
+            post(handlers::admin::add_incident_comment),
         )
-        .route("/rules/coverage", get(handlers::admin::rule_coverage))
-        .route("/queue", get(handlers::admin::queue_status))
+        // Rate limit counters (поддержка разблокирует пользователей)
+        .route("/rate-limits", get(handlers::admin::get_rate_limits))
+        .route(
+            "/rate-limits/{key}",
+            delete(handlers::admin::reset_rate_limits),
+        )
+        // Roles and permissions
+        .route("/roles", get(handlers::admin::list_roles))
+        .route(
+            "/roles/{role}",
+            put(handlers::admin::update_role_permissions),
+        );
+
+    let system = Router::new()
         .route(
             "/feature-flags",
             get(handlers::admin::list_feature_flags).post(handlers::admin::create_feature_flag),
@@ -332,63 +603,63 @@ fn admin_routes(
                 .put(handlers::admin::update_feature_flag)
                 .delete(handlers::admin::delete_feature_flag),
         )
+        .route(
+            "/feature-flags/{flag_key}/history",
+            get(handlers::admin::get_feature_flag_history),
+        )
         // Backups
         .route(
             "/backups",
             get(handlers::admin::list_backups).post(handlers::admin::create_backup),
         )
+        .route("/backups/{id}", get(handlers::admin::get_backup))
         .route(
             "/backups/{id}/restore",
             post(handlers::admin::restore_backup),
         )
-        // User management
+        .route("/dashboard", get(handlers::admin::get_dashboard))
+        // Maintenance
         .route(
-            "/users",
-            get(handlers::admin::list_users).post(handlers::admin::create_user),
+            "/maintenance/recompute-progress",
+            post(handlers::admin::recompute_progress),
         )
-        .route("/users/bulk", post(handlers::admin::bulk_user_action))
         .route(
-            "/users/{id}",
-            get(handlers::admin::get_user)
-                .patch(handlers::admin::update_user)
-                .delete(handlers::admin::delete_user),
+            "/maintenance/jobs/{id}",
+            get(handlers::admin::get_maintenance_job),
         )
-        .route("/users/{id}/block", post(handlers::admin::block_user))
-        .route("/users/{id}/unblock", post(handlers::admin::unblock_user))
+        .route("/system/indexes", get(handlers::admin::get_index_report))
         .route(
-            "/users/{id}/reset-password",
-            post(handlers::admin::reset_user_password),
+            "/system/superuser-status",
+            get(handlers::admin::get_superuser_status),
         )
-        // Group management
         .route(
-            "/groups",
-            get(handlers::admin::list_groups).post(handlers::admin::create_group),
+            "/system/maintenance",
+            get(handlers::admin::get_maintenance_mode)
+                .post(handlers::admin::update_maintenance_mode),
         )
-        .route("/groups/export", get(handlers::admin::export_groups))
         .route(
-            "/groups/{id}",
-            get(handlers::admin::get_group)
-                .patch(handlers::admin::update_group)
-                .delete(handlers::admin::delete_group),
+            "/system/trace-sampling",
+            get(handlers::admin::get_trace_sampling).put(handlers::admin::update_trace_sampling),
         )
-        // Anticheat incidents
-        .route("/incidents", get(handlers::admin::list_incidents))
+        // System settings
+        .route("/settings", get(handlers::admin::get_system_settings))
         .route(
-            "/incidents/{id}",
-            get(handlers::admin::get_incident).put(handlers::admin::update_incident),
+            "/settings/reload",
+            post(handlers::admin::reload_runtime_settings),
         )
         .route(
-            "/incidents/{id}/unblock",
-            post(handlers::admin::unblock_incident_user),
+            "/settings/rate-limits",
+            put(handlers::admin::update_rate_limit_settings),
         )
-        // System metrics
-        .route("/system/metrics", get(handlers::admin::get_system_metrics))
-        // System settings
-        .route("/settings", get(handlers::admin::get_system_settings))
+        .route("/settings/jwt-keys", get(handlers::admin::list_jwt_keys))
         .route(
             "/settings/yandexgpt",
             put(handlers::admin::update_yandexgpt_settings),
         )
+        .route(
+            "/settings/openai",
+            put(handlers::admin::update_openai_settings),
+        )
         .route("/settings/sso", put(handlers::admin::update_sso_settings))
         .route(
             "/settings/email",
@@ -398,10 +669,26 @@ fn admin_routes(
             "/settings/anticheat",
             put(handlers::admin::update_anticheat_settings),
         )
+        .route(
+            "/settings/anticheat/preview",
+            post(handlers::admin::preview_anticheat_settings),
+        )
+        .route(
+            "/settings/consent",
+            put(handlers::admin::update_consent_settings),
+        )
+        .route(
+            "/settings/pii-patterns",
+            put(handlers::admin::update_pii_settings),
+        )
         .route(
             "/settings/test/yandexgpt",
             post(handlers::admin::test_yandexgpt_settings),
         )
+        .route(
+            "/settings/test/openai",
+            post(handlers::admin::test_openai_settings),
+        )
         .route(
             "/settings/test/sso",
             post(handlers::admin::test_sso_settings),
@@ -413,15 +700,207 @@ fn admin_routes(
         // Audit logs
         .route("/audit", get(handlers::admin::list_audit_logs))
         .route("/audit/export", get(handlers::admin::export_audit_logs))
+        .route(
+            "/audit/actors/{id}/summary",
+            get(handlers::admin::audit_actor_summary),
+        )
+        .route("/audit/archives", get(handlers::admin::list_audit_archives));
+
+    // Отдельное разрешение: очистку можно не выдавать всем, кто управляет пользователями
+    let purge = Router::new().route("/users/{id}/purge", post(handlers::admin::purge_user));
+    // Откат публикации минует ревью, поэтому он не входит в `content.moderate`
+    let rollback = Router::new().route(
+        "/templates/{id}/rollback-publish/{version}",
+        post(handlers::admin::rollback_template_publish),
+    );
+
+    Router::new()
+        .merge(admin_group(
+            content,
+            &app_state,
+            Permission::ContentModerate,
+        ))
+        .merge(admin_group(users, &app_state, Permission::UsersManage))
+        .merge(admin_group(purge, &app_state, Permission::UsersPurge))
+        .merge(admin_group(
+            rollback,
+            &app_state,
+            Permission::ContentRollback,
+        ))
+        .merge(admin_group(system, &app_state, Permission::SettingsManage))
+}
+
+/// Группа `/admin`-маршрутов, доступная ролям с разрешением `permission`
+fn admin_group(
+    routes: Router<std::sync::Arc<services::AppState>>,
+    app_state: &std::sync::Arc<services::AppState>,
+    permission: Permission,
+) -> Router<std::sync::Arc<services::AppState>> {
+    routes
         .layer(middleware::from_fn_with_state(
             app_state.clone(),
             middlewares::rate_limit::admin_rate_limit_middleware,
         ))
-        .route_layer(middleware::from_fn(
-            middlewares::auth::admin_guard_middleware,
+        .route_layer(middleware::from_fn_with_state(
+            (app_state.clone(), permission),
+            middlewares::permissions::require_permission,
         ))
 }
 
+/// Шаблоны, темы, уровни и правила: тела больше, чем у остальных `/admin`
+fn admin_content_routes(body_limit: usize) -> Router<std::sync::Arc<services::AppState>> {
+    Router::new()
+        // Content management
+        .route(
+            "/templates",
+            get(handlers::admin::list_templates).post(handlers::admin::create_template),
+        )
+        .route(
+            "/templates/{id}",
+            get(handlers::admin::get_template).patch(handlers::admin::update_template),
+        )
+        .route(
+            "/templates/{id}/revert",
+            post(handlers::admin::revert_template),
+        )
+        .route(
+            "/templates/{id}/versions",
+            get(handlers::admin::list_template_versions),
+        )
+        .route(
+            "/templates/{id}/versions/{version}/diff",
+            get(handlers::admin::template_version_diff),
+        )
+        .route(
+            "/templates/{id}/submit",
+            post(handlers::admin::submit_template_for_moderation),
+        )
+        .route("/templates/{id}/lint", post(handlers::admin::lint_template))
+        .route(
+            "/templates/{id}/approve",
+            post(handlers::admin::approve_template),
+        )
+        .route(
+            "/templates/{id}/reject",
+            post(handlers::admin::reject_template),
+        )
+        .route(
+            "/templates/{id}/assign-reviewer",
+            post(handlers::admin::assign_template_reviewer),
+        )
+        .route(
+            "/templates/{id}/publish",
+            post(handlers::admin::publish_template),
+        )
+        .route("/moderation/queue", get(handlers::admin::moderation_queue))
+        .route(
+            "/templates/{id}/archive",
+            post(handlers::admin::archive_template),
+        )
+        .route(
+            "/templates/{id}/unarchive",
+            post(handlers::admin::unarchive_template),
+        )
+        .route(
+            "/templates/{id}/enrichment/run",
+            post(handlers::admin::start_template_enrichment_run),
+        )
+        .route(
+            "/templates/{id}/enrichment/runs",
+            get(handlers::admin::list_template_enrichment_runs),
+        )
+        .route(
+            "/templates/{id}/enrichment/tasks",
+            get(handlers::admin::list_template_enrichment_tasks),
+        )
+        .route(
+            "/templates/{id}/enrichment/tasks/{task_id}",
+            delete(handlers::admin::delete_template_enrichment_task),
+        )
+        .route(
+            "/templates/{id}/enrichment/tasks/{task_id}/regenerate",
+            post(handlers::admin::regenerate_template_enrichment_task),
+        )
+        .route("/content/search", get(handlers::admin::search_content))
+        .route("/content/tree", get(handlers::admin::content_tree))
+        .route(
+            "/content/blacklist",
+            get(handlers::admin::list_blacklist).post(handlers::admin::create_blacklist_entry),
+        )
+        .route(
+            "/content/blacklist/{id}",
+            put(handlers::admin::update_blacklist_entry)
+                .delete(handlers::admin::delete_blacklist_entry),
+        )
+        .route(
+            "/templates/bulk-status",
+            post(handlers::admin::bulk_update_template_status),
+        )
+        .route(
+            "/templates/validate",
+            post(handlers::admin::validate_templates),
+        )
+        .route(
+            "/templates/duplicates",
+            get(handlers::admin::list_duplicates),
+        )
+        .route("/templates/export", get(handlers::admin::export_templates))
+        .route("/templates/import", post(handlers::admin::import_templates))
+        .route(
+            "/embeddings/rebuild",
+            post(handlers::admin::rebuild_embeddings),
+        )
+        .route(
+            "/embeddings/progress",
+            get(handlers::admin::embedding_progress),
+        )
+        .route(
+            "/embeddings/consistency",
+            get(handlers::admin::embedding_consistency),
+        )
+        .route(
+            "/embeddings/jobs",
+            get(handlers::admin::list_embedding_jobs),
+        )
+        .route(
+            "/embeddings/jobs/{id}/cancel",
+            post(handlers::admin::cancel_embedding_job),
+        )
+        .route(
+            "/embeddings/jobs/{id}/retry-failed",
+            post(handlers::admin::retry_failed_embeddings),
+        )
+        .route(
+            "/topics",
+            get(handlers::admin::list_topics).post(handlers::admin::create_topic),
+        )
+        .route(
+            "/topics/{id}",
+            put(handlers::admin::update_topic).delete(handlers::admin::delete_topic),
+        )
+        .route("/topics/{id}/levels", get(handlers::admin::list_levels))
+        .route("/levels", post(handlers::admin::create_level))
+        .route(
+            "/levels/{id}",
+            put(handlers::admin::update_level).delete(handlers::admin::delete_level),
+        )
+        .route("/levels/reorder", post(handlers::admin::reorder_levels))
+        .route(
+            "/rules",
+            get(handlers::admin::list_rules).post(handlers::admin::create_rule),
+        )
+        .route(
+            "/rules/{id}",
+            put(handlers::admin::update_rule).delete(handlers::admin::delete_rule),
+        )
+        .route(
+            "/rules/{id}/merge-into/{target_id}",
+            post(handlers::admin::merge_rule),
+        )
+        .route("/rules/coverage", get(handlers::admin::rule_coverage))
+        .layer(DefaultBodyLimit::max(body_limit))
+}
+
 fn auth_routes(
     app_state: std::sync::Arc<services::AppState>,
 ) -> Router<std::sync::Arc<services::AppState>> {
@@ -452,13 +931,20 @@ fn auth_routes(
 
     // Protected routes (require JWT auth + CSRF protection)
     let protected_routes = Router::new()
-        .route("/me", get(handlers::auth::get_current_user))
+        .route(
+            "/me",
+            get(handlers::auth::get_current_user).patch(handlers::auth::update_current_user),
+        )
         .route("/logout", post(handlers::auth::logout))
         .route("/sessions", get(handlers::auth::get_active_sessions))
         .route(
             "/sessions/revoke",
             post(handlers::auth::revoke_other_sessions),
         )
+        .route(
+            "/sessions/{session_id}/revoke",
+            post(handlers::auth::revoke_session),
+        )
         .route("/change-password", post(handlers::auth::change_password))
         .route_layer(middleware::from_fn(middlewares::csrf::csrf_middleware))
         .route_layer(middleware::from_fn_with_state(
@@ -467,5 +953,9 @@ fn auth_routes(
         ));
 
     // Merge public and protected routes
-    public_routes.merge(protected_routes)
+    public_routes
+        .merge(protected_routes)
+        .layer(DefaultBodyLimit::max(
+            app_state.config.body_limits.auth_bytes,
+        ))
 }
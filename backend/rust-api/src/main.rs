@@ -7,28 +7,35 @@ use tracing_subscriber::{
     EnvFilter,
 };
 use trainingground_api::{
-    config::{Config, LoggingSettings},
+    config::{Config, LoggingSettings, TracingSettings},
     create_router,
-    services::AppState,
+    services::{
+        content_outbox::ContentOutboxDispatcher, content_stream_consumer::ContentStreamConsumer,
+        AppState,
+    },
 };
 
 #[tokio::main]
 async fn main() {
     // Load configuration (before tracing initialization to respect logging settings)
-    let config = Config::load().expect("Failed to load configuration");
+    // Все нарушения конфигурации печатаются разом, а не паникой по первому
+    let config = Config::load().unwrap_or_else(|err| {
+        eprintln!("Failed to load configuration: {}", err);
+        std::process::exit(1);
+    });
     let logging = config.logging.clone();
 
     // Initialize OpenTelemetry tracer (optional, can be disabled)
-    let _tracer = init_telemetry();
+    let tracer = init_telemetry(&config.tracing);
 
     // Initialize tracing with OpenTelemetry layer and project defaults
-    init_tracing(&logging);
+    init_tracing(&logging, tracer);
 
     tracing::info!("Starting TrainingGround Rust API");
 
     tracing::info!(
-        "Configuration loaded for environment: {:?}",
-        std::env::var("APP_ENV").unwrap_or_else(|_| "dev".to_string())
+        "Configuration loaded for environment: {}",
+        config.profile.as_str()
     );
 
     // Initialize database connections
@@ -42,31 +49,59 @@ async fn main() {
 
     // Build application state
     let app_state = Arc::new(
-        AppState::new(config, mongo_client, redis_client)
+        AppState::new(config, mongo_client, redis_client.clone())
             .await
             .expect("Failed to initialize application state"),
     );
 
+    // Публикация событий и аудита изменений шаблонов из outbox
+    let outbox = ContentOutboxDispatcher::new(
+        app_state.mongo.clone(),
+        app_state.redis.clone(),
+        &app_state.config.content,
+    );
+    tokio::spawn(async move {
+        if let Err(err) = outbox.run().await {
+            tracing::error!(error = %err, "content outbox dispatcher stopped");
+        }
+    });
+
+    // Консьюмер стрима изменений контента: инвалидация кэша и эмбеддинги
+    let consumer_redis = ContentStreamConsumer::connect(redis_client, &app_state.config.content)
+        .await
+        .expect("Failed to connect content stream consumer");
+    let consumer = ContentStreamConsumer::from_state(&app_state, consumer_redis);
+    tokio::spawn(async move {
+        if let Err(err) = consumer.run().await {
+            tracing::error!(error = %err, "content stream consumer stopped");
+        }
+    });
+
     // Build router
-    let app = create_router(app_state);
+    let app = create_router(app_state.clone());
 
     // Start server
     let listener = tokio::net::TcpListener::bind("0.0.0.0:8081").await.unwrap();
 
     tracing::info!("Server listening on {}", listener.local_addr().unwrap());
 
+    // До этого момента /health/ready отвечает not_ready, даже если зависимости доступны
+    app_state.mark_ready();
+
     axum::serve(listener, app).await.unwrap();
 
     // Shutdown OpenTelemetry gracefully
     shutdown_telemetry();
 }
 
-fn init_telemetry() -> opentelemetry_sdk::trace::Tracer {
+fn init_telemetry(settings: &TracingSettings) -> opentelemetry_sdk::trace::Tracer {
     use opentelemetry::trace::TracerProvider as _;
     use opentelemetry::KeyValue;
     use opentelemetry_otlp::WithExportConfig;
-    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
     use opentelemetry_sdk::Resource;
+    use std::time::Duration;
+    use trainingground_api::telemetry::{sampling_control, SamplingRules, TailSamplingProcessor};
 
     // Check if OTLP endpoint is configured
     let otlp_endpoint = std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
@@ -81,6 +116,7 @@ fn init_telemetry() -> opentelemetry_sdk::trace::Tracer {
     let exporter = opentelemetry_otlp::SpanExporter::builder()
         .with_http()
         .with_endpoint(otlp_endpoint)
+        .with_timeout(Duration::from_secs(settings.export_timeout_secs))
         .build()
         .expect("Failed to create OTLP exporter");
 
@@ -93,9 +129,15 @@ fn init_telemetry() -> opentelemetry_sdk::trace::Tracer {
         )])
         .build();
 
+    // Все спаны записываются; что экспортировать, решает TailSamplingProcessor
+    let control = sampling_control();
+    control.set(SamplingRules::from(settings));
+    let processor = TailSamplingProcessor::new(exporter, settings, control.clone());
+
     // Create tracer provider
     let provider = SdkTracerProvider::builder()
-        .with_batch_exporter(exporter)
+        .with_sampler(Sampler::AlwaysOn)
+        .with_span_processor(processor)
         .with_resource(resource)
         .build();
 
@@ -112,7 +154,7 @@ fn shutdown_telemetry() {
     // In opentelemetry 0.31, shutdown is handled by dropping the provider
 }
 
-fn init_tracing(logging: &LoggingSettings) {
+fn init_tracing(logging: &LoggingSettings, tracer: opentelemetry_sdk::trace::Tracer) {
     let env_filter =
         EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(logging.directive()));
 
@@ -132,6 +174,6 @@ fn init_tracing(logging: &LoggingSettings) {
     tracing_subscriber::registry()
         .with(env_filter)
         .with(fmt_layer)
-        .with(tracing_opentelemetry::layer())
+        .with(tracing_opentelemetry::layer().with_tracer(tracer))
         .init();
 }
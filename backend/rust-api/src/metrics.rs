@@ -1,7 +1,8 @@
 use lazy_static::lazy_static;
 use prometheus::{
     register_counter_vec, register_histogram_vec, register_int_counter_vec, register_int_gauge,
-    CounterVec, Encoder, HistogramVec, IntCounterVec, IntGauge, TextEncoder,
+    register_int_gauge_vec, CounterVec, Encoder, HistogramVec, IntCounterVec, IntGauge,
+    IntGaugeVec, TextEncoder,
 };
 
 lazy_static! {
@@ -37,6 +38,33 @@ lazy_static! {
     )
     .unwrap();
 
+    pub static ref MONGO_TRANSIENT_ERRORS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "mongo_transient_errors_total",
+        "Total number of transient MongoDB errors observed by retry wrappers",
+        &["operation"]
+    )
+    .unwrap();
+
+    pub static ref MONGO_RETRIES_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "mongo_retries_total",
+        "Total number of MongoDB operations retried after a transient error",
+        &["operation"]
+    )
+    .unwrap();
+
+    pub static ref MONGO_RETRIES_EXHAUSTED_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "mongo_retries_exhausted_total",
+        "Total number of MongoDB operations that failed after the retry",
+        &["operation"]
+    )
+    .unwrap();
+
+    pub static ref MONGO_DEGRADED: IntGauge = register_int_gauge!(
+        "mongo_degraded",
+        "Whether MongoDB is considered degraded after consecutive retry exhaustions (1/0)"
+    )
+    .unwrap();
+
     // Cache Metrics (Redis)
     pub static ref CACHE_OPERATIONS_TOTAL: IntCounterVec = register_int_counter_vec!(
         "cache_operations_total",
@@ -60,17 +88,24 @@ lazy_static! {
     )
     .unwrap();
 
+    pub static ref CONTENT_CACHE_LOOKUPS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "content_cache_lookups_total",
+        "Topic, level and template lookups served from the Redis cache (hit) or MongoDB (miss)",
+        &["kind", "result"]
+    )
+    .unwrap();
+
     // Business Metrics
     pub static ref SESSIONS_TOTAL: IntCounterVec = register_int_counter_vec!(
         "sessions_total",
-        "Total number of training sessions",
+        "Training sessions by lifecycle transition (started/completed/expired)",
         &["status"]
     )
     .unwrap();
 
-    pub static ref SESSIONS_ACTIVE: IntGauge = register_int_gauge!(
-        "sessions_active",
-        "Number of currently active sessions"
+    pub static ref ACTIVE_SESSIONS: IntGauge = register_int_gauge!(
+        "active_sessions",
+        "Sessions started by this instance and not yet completed or expired"
     )
     .unwrap();
 
@@ -83,8 +118,37 @@ lazy_static! {
 
     pub static ref HINTS_REQUESTED_TOTAL: IntCounterVec = register_int_counter_vec!(
         "hints_requested_total",
-        "Total number of hints requested",
-        &["hint_level"]
+        "Total number of hints served, by the provider that produced the hint",
+        &["provider"]
+    )
+    .unwrap();
+
+    pub static ref HINT_PROVIDER_DURATION_SECONDS: HistogramVec = register_histogram_vec!(
+        "hint_provider_duration_seconds",
+        "Hint provider attempt duration in seconds",
+        &["provider", "outcome"],
+        vec![0.005, 0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.0, 5.0]
+    )
+    .unwrap();
+
+    pub static ref JWT_KEY_USAGE_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "jwt_key_usage_total",
+        "JWT tokens issued and verified, by signing key id",
+        &["kid", "operation"]
+    )
+    .unwrap();
+
+    pub static ref LLM_REQUESTS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "llm_requests_total",
+        "LLM provider requests by outcome (success, rate_limited, error)",
+        &["provider", "outcome"]
+    )
+    .unwrap();
+
+    pub static ref LLM_TOKENS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "llm_tokens_total",
+        "Tokens reported by LLM providers, by kind (prompt, completion)",
+        &["provider", "kind"]
     )
     .unwrap();
 
@@ -111,11 +175,95 @@ lazy_static! {
 
     pub static ref EXPORT_WORKER_TICKS_TOTAL: IntCounterVec = register_int_counter_vec!(
         "export_worker_ticks_total",
-        "Total number of export worker ticks",
+        "Export worker ticks (success/error) and export attempts (retried/failed)",
+        &["status"]
+    )
+    .unwrap();
+
+    pub static ref EMAIL_OUTBOX_MESSAGES_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "email_outbox_messages_total",
+        "Outbox email attempts by outcome (sent/retried/failed)",
+        &["status"]
+    )
+    .unwrap();
+
+    pub static ref ARCHIVE_WORKER_TICKS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "archive_worker_ticks_total",
+        "Total number of session archive worker ticks",
+        &["status"]
+    )
+    .unwrap();
+
+    pub static ref BLOCK_EXPIRY_WORKER_TICKS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "block_expiry_worker_ticks_total",
+        "Total number of expired user block sweeps",
+        &["status"]
+    )
+    .unwrap();
+
+    pub static ref EXPORT_SCHEDULE_WORKER_TICKS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "export_schedule_worker_ticks_total",
+        "Total number of scheduled export worker ticks",
+        &["status"]
+    )
+    .unwrap();
+
+    pub static ref EMBEDDING_WORKER_TICKS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "embedding_worker_ticks_total",
+        "Total number of embedding job worker ticks",
         &["status"]
     )
     .unwrap();
 
+    pub static ref CONTENT_STREAM_EVENTS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "content_stream_events_total",
+        "Content change events handled by the API consumer (processed, failed, dead_lettered)",
+        &["outcome"]
+    )
+    .unwrap();
+
+    pub static ref CONTENT_OUTBOX_DISPATCHED_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "content_outbox_dispatched_total",
+        "Template change outbox entries published to the content stream and audit log",
+        &["status"]
+    )
+    .unwrap();
+
+    pub static ref MODERATION_QUEUE_DEPTH: IntGaugeVec = register_int_gauge_vec!(
+        "moderation_queue_depth",
+        "Templates awaiting moderation by time in the current status (under_1d, 1d_3d, over_3d)",
+        &["age_bucket"]
+    )
+    .unwrap();
+
+    pub static ref AUDIT_RETENTION_WORKER_TICKS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "audit_retention_worker_ticks_total",
+        "Total number of audit log retention worker ticks",
+        &["status"]
+    )
+    .unwrap();
+
+    pub static ref SESSIONS_ARCHIVED_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "sessions_archived_total",
+        "Total number of sessions moved to or restored from the archive",
+        &["operation"]
+    )
+    .unwrap();
+
+    pub static ref TRACES_SAMPLED_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "traces_sampled_total",
+        "Total number of completed traces by sampling decision",
+        &["decision"]
+    )
+    .unwrap();
+
+    pub static ref TRACE_SPANS_DROPPED_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "trace_spans_dropped_total",
+        "Total number of sampled spans that were not exported",
+        &["reason"]
+    )
+    .unwrap();
+
     pub static ref EXPORTS_GENERATED_TOTAL: IntCounterVec = register_int_counter_vec!(
         "exports_generated_total",
         "Total number of exports generated",
@@ -190,6 +338,20 @@ where
     result
 }
 
+/// Record content cache lookups of one kind (topic, level, template)
+pub fn record_content_cache_lookups(kind: &str, hits: usize, misses: usize) {
+    if hits > 0 {
+        CONTENT_CACHE_LOOKUPS_TOTAL
+            .with_label_values(&[kind, "hit"])
+            .inc_by(hits as u64);
+    }
+    if misses > 0 {
+        CONTENT_CACHE_LOOKUPS_TOTAL
+            .with_label_values(&[kind, "miss"])
+            .inc_by(misses as u64);
+    }
+}
+
 /// Record cache hit
 pub fn record_cache_hit() {
     CACHE_HIT_RATIO.with_label_values(&["hit"]).inc();
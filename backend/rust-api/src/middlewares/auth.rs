@@ -4,12 +4,17 @@ use axum::{
     middleware::Next,
     response::Response,
 };
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use jsonwebtoken::{
+    decode, decode_header, encode, errors::ErrorKind, DecodingKey, EncodingKey, Header, Validation,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use tracing::{field, Span};
 
-use crate::services::AppState;
+use crate::config::Config;
+use crate::metrics::JWT_KEY_USAGE_TOTAL;
+use crate::services::{token_revocation, AppState};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct JwtClaims {
@@ -41,9 +46,21 @@ impl std::fmt::Display for AuthError {
 
 impl std::error::Error for AuthError {}
 
+/// `kid` токенов, подписанных `JWT_SECRET`, когда карта ключей не задана
+pub const DEFAULT_KEY_ID: &str = "default";
+/// Метка в счётчиках для токенов, подошедших к одному из `JWT_FALLBACK_SECRETS`
+pub const FALLBACK_KEY_LABEL: &str = "fallback";
+
+struct NamedKey {
+    kid: String,
+    decoding_key: DecodingKey,
+}
+
 pub struct JwtService {
+    active_kid: String,
     encoding_key: EncodingKey,
-    decoding_key: DecodingKey,
+    /// Активный ключ первым: токены без `kid` чаще всего подписаны им
+    keys: Vec<NamedKey>,
     fallback_decoding_keys: Vec<DecodingKey>,
 }
 
@@ -53,6 +70,45 @@ impl JwtService {
     }
 
     pub fn new_with_fallbacks(secret: &str, fallback_secrets: &[String]) -> Self {
+        let keys = BTreeMap::from([(DEFAULT_KEY_ID.to_string(), secret.to_string())]);
+        Self::with_keys(&keys, DEFAULT_KEY_ID, fallback_secrets)
+    }
+
+    /// Ключи из конфигурации. При заданной карте ключей `JWT_SECRET` остаётся
+    /// запасным: им проверяются токены, выпущенные до перехода на `kid`
+    pub fn from_config(config: &Config) -> Self {
+        let Some(active_kid) = config
+            .jwt_active_key_id
+            .as_deref()
+            .filter(|kid| config.jwt_keys.contains_key(*kid))
+        else {
+            return Self::new_with_fallbacks(&config.jwt_secret, &config.jwt_fallback_secrets);
+        };
+
+        let mut fallback_secrets = vec![config.jwt_secret.clone()];
+        fallback_secrets.extend(config.jwt_fallback_secrets.iter().cloned());
+        Self::with_keys(&config.jwt_keys, active_kid, &fallback_secrets)
+    }
+
+    /// `active_kid` должен быть в `keys`
+    pub fn with_keys(
+        keys: &BTreeMap<String, String>,
+        active_kid: &str,
+        fallback_secrets: &[String],
+    ) -> Self {
+        let active_secret = keys
+            .get(active_kid)
+            .expect("active JWT key id must be present in the key map");
+
+        let mut named_keys: Vec<NamedKey> = keys
+            .iter()
+            .map(|(kid, secret)| NamedKey {
+                kid: kid.clone(),
+                decoding_key: DecodingKey::from_secret(secret.as_bytes()),
+            })
+            .collect();
+        named_keys.sort_by_key(|key| key.kid != active_kid);
+
         let fallback_decoding_keys = fallback_secrets
             .iter()
             .filter(|value| !value.trim().is_empty())
@@ -60,45 +116,95 @@ impl JwtService {
             .collect();
 
         Self {
-            encoding_key: EncodingKey::from_secret(secret.as_bytes()),
-            decoding_key: DecodingKey::from_secret(secret.as_bytes()),
+            active_kid: active_kid.to_string(),
+            encoding_key: EncodingKey::from_secret(active_secret.as_bytes()),
+            keys: named_keys,
             fallback_decoding_keys,
         }
     }
 
+    pub fn active_key_id(&self) -> &str {
+        &self.active_kid
+    }
+
+    /// Имена ключей в порядке проверки, без секретов
+    pub fn key_ids(&self) -> impl Iterator<Item = &str> {
+        self.keys.iter().map(|key| key.kid.as_str())
+    }
+
     pub fn generate_token(&self, claims: JwtClaims) -> Result<String, AuthError> {
-        encode(&Header::default(), &claims, &self.encoding_key).map_err(|_| AuthError::InvalidToken)
+        let header = Header {
+            kid: Some(self.active_kid.clone()),
+            ..Header::default()
+        };
+        let token =
+            encode(&header, &claims, &self.encoding_key).map_err(|_| AuthError::InvalidToken)?;
+        record_key_usage(&self.active_kid, "issued");
+        Ok(token)
     }
 
+    /// Токен с `kid` проверяется своим ключом, токены без `kid` (выпущенные до ротации) -
+    /// всеми ключами по очереди. Запасные секреты `JWT_FALLBACK_SECRETS` проверяются последними
     pub fn validate_token(&self, token: &str) -> Result<JwtClaims, AuthError> {
-        let validation = Validation::default();
-
-        match decode::<JwtClaims>(token, &self.decoding_key, &validation) {
-            Ok(data) => Ok(data.claims),
-            Err(e) => {
-                let error_str = e.to_string();
-                tracing::error!("JWT decode error details: {:?}", error_str);
+        let header = decode_header(token).map_err(|_| AuthError::InvalidToken)?;
 
-                if error_str.contains("ExpiredSignature") {
-                    return Err(AuthError::ExpiredToken);
-                }
+        let named = self
+            .keys
+            .iter()
+            .filter(|key| header.kid.as_deref().is_none_or(|kid| kid == key.kid))
+            .map(|key| (key.kid.as_str(), &key.decoding_key));
+        let fallbacks = self
+            .fallback_decoding_keys
+            .iter()
+            .map(|key| (FALLBACK_KEY_LABEL, key));
 
-                if error_str.contains("InvalidSignature") {
-                    for key in &self.fallback_decoding_keys {
-                        if let Ok(data) = decode::<JwtClaims>(token, key, &validation) {
-                            tracing::info!("JWT validated using fallback secret (rotation window)");
-                            return Ok(data.claims);
-                        }
+        for (label, key) in named.chain(fallbacks) {
+            match decode_claims(token, key) {
+                Ok(claims) => {
+                    if label == FALLBACK_KEY_LABEL {
+                        tracing::info!("JWT validated using fallback secret (rotation window)");
                     }
-                    return Err(AuthError::InvalidSignature);
+                    record_key_usage(label, "verified");
+                    return Ok(claims);
                 }
-
-                Err(AuthError::InvalidToken)
+                Err(AuthError::InvalidSignature) => continue,
+                Err(err) => return Err(err),
             }
         }
+
+        if let Some(kid) = header.kid.as_deref() {
+            tracing::warn!(kid, "JWT signed with unknown or retired key");
+        }
+        Err(AuthError::InvalidSignature)
     }
 }
 
+fn decode_claims(token: &str, key: &DecodingKey) -> Result<JwtClaims, AuthError> {
+    decode::<JwtClaims>(token, key, &Validation::default())
+        .map(|data| data.claims)
+        .map_err(|e| {
+            tracing::debug!("JWT decode error details: {:?}", e);
+            match e.kind() {
+                ErrorKind::ExpiredSignature => AuthError::ExpiredToken,
+                ErrorKind::InvalidSignature => AuthError::InvalidSignature,
+                _ => AuthError::InvalidToken,
+            }
+        })
+}
+
+fn record_key_usage(kid: &str, operation: &str) {
+    JWT_KEY_USAGE_TOTAL
+        .with_label_values(&[kid, operation])
+        .inc();
+}
+
+/// Сколько токенов выпущено и проверено ключом с момента запуска процесса
+pub fn key_usage(kid: &str, operation: &str) -> u64 {
+    JWT_KEY_USAGE_TOTAL
+        .with_label_values(&[kid, operation])
+        .get()
+}
+
 /// Middleware для проверки JWT токена
 pub async fn auth_middleware(
     State(state): State<Arc<AppState>>,
@@ -114,14 +220,15 @@ pub async fn auth_middleware(
         .ok_or(StatusCode::UNAUTHORIZED)?;
 
     // Validate token
-    let jwt_service = JwtService::new_with_fallbacks(
-        &state.config.jwt_secret,
-        &state.config.jwt_fallback_secrets,
-    );
+    let jwt_service = JwtService::from_config(&state.config);
     let claims = jwt_service.validate_token(token).map_err(|e| {
         tracing::warn!("JWT validation failed: {}", e);
         StatusCode::UNAUTHORIZED
     })?;
+    if is_token_revoked(&state, &claims).await {
+        tracing::warn!("Rejected revoked access token of user {}", claims.sub);
+        return Err(StatusCode::UNAUTHORIZED);
+    }
 
     tracing::debug!("Authenticated user: {} (role: {})", claims.sub, claims.role);
     Span::current().record("user_id", field::display(&claims.sub));
@@ -142,11 +249,12 @@ pub async fn optional_auth_middleware(
     if let Some(auth_header) = headers.get("authorization") {
         if let Ok(auth_str) = auth_header.to_str() {
             if let Some(token) = auth_str.strip_prefix("Bearer ") {
-                let jwt_service = JwtService::new_with_fallbacks(
-                    &state.config.jwt_secret,
-                    &state.config.jwt_fallback_secrets,
-                );
-                if let Ok(claims) = jwt_service.validate_token(token) {
+                let jwt_service = JwtService::from_config(&state.config);
+                let claims = match jwt_service.validate_token(token) {
+                    Ok(claims) if !is_token_revoked(&state, &claims).await => Some(claims),
+                    _ => None,
+                };
+                if let Some(claims) = claims {
                     Span::current().record("user_id", field::display(&claims.sub));
                     request.extensions_mut().insert(claims);
                 }
@@ -157,15 +265,21 @@ pub async fn optional_auth_middleware(
     next.run(request).await
 }
 
-pub async fn admin_guard_middleware(request: Request, next: Next) -> Result<Response, StatusCode> {
-    let claims = request.extensions().get::<JwtClaims>();
-    if let Some(claims) = claims {
-        if claims.role == "admin" || claims.role == "content_admin" {
-            return Ok(next.run(request).await);
+/// Токен выпущен до отзыва токенов пользователя (блокировка, удаление, принудительный
+/// выход). Если Redis недоступен, токен принимается: отзыв - дополнительная защита
+/// поверх короткого срока жизни токена, а не повод отключать весь API
+async fn is_token_revoked(state: &AppState, claims: &JwtClaims) -> bool {
+    if !state.config.accounts.token_revocation_enabled {
+        return false;
+    }
+    match token_revocation::revoked_at(&state.redis, &claims.sub).await {
+        Ok(Some(revoked_at)) => token_revocation::is_revoked(claims.iat, revoked_at),
+        Ok(None) => false,
+        Err(err) => {
+            tracing::warn!("Token revocation check skipped: {:#}", err);
+            false
         }
     }
-    tracing::warn!("Access denied: admin role required");
-    Err(StatusCode::FORBIDDEN)
 }
 
 #[cfg(test)]
@@ -210,4 +324,78 @@ mod tests {
         let validated = service.validate_token(&token).unwrap();
         assert_eq!(validated.sub, claims.sub);
     }
+
+    fn claims(sub: &str) -> JwtClaims {
+        JwtClaims {
+            sub: sub.to_string(),
+            role: "student".to_string(),
+            group_ids: vec![],
+            exp: (chrono::Utc::now().timestamp() + 3600) as usize,
+            iat: chrono::Utc::now().timestamp() as usize,
+        }
+    }
+
+    fn kid_of(token: &str) -> Option<String> {
+        decode_header(token).unwrap().kid
+    }
+
+    #[test]
+    fn test_rotation_keeps_old_tokens_valid_and_signs_with_new_kid() {
+        let mut keys = BTreeMap::from([("rot-a".to_string(), "secret-a".to_string())]);
+        let before = JwtService::with_keys(&keys, "rot-a", &[]);
+        let token_a = before.generate_token(claims("user-a")).unwrap();
+        assert_eq!(kid_of(&token_a).as_deref(), Some("rot-a"));
+
+        keys.insert("rot-b".to_string(), "secret-b".to_string());
+        let after = JwtService::with_keys(&keys, "rot-b", &[]);
+        assert_eq!(after.active_key_id(), "rot-b");
+        assert_eq!(after.key_ids().collect::<Vec<_>>(), ["rot-b", "rot-a"]);
+
+        let token_b = after.generate_token(claims("user-b")).unwrap();
+        assert_eq!(kid_of(&token_b).as_deref(), Some("rot-b"));
+        assert_eq!(after.validate_token(&token_a).unwrap().sub, "user-a");
+        assert_eq!(after.validate_token(&token_b).unwrap().sub, "user-b");
+
+        assert_eq!(key_usage("rot-a", "issued"), 1);
+        assert_eq!(key_usage("rot-a", "verified"), 1);
+        assert_eq!(key_usage("rot-b", "issued"), 1);
+        assert_eq!(key_usage("rot-b", "verified"), 1);
+
+        // Ключ A выведен из ротации - его токены больше не принимаются
+        keys.remove("rot-a");
+        let retired = JwtService::with_keys(&keys, "rot-b", &[]);
+        assert!(matches!(
+            retired.validate_token(&token_a),
+            Err(AuthError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn test_token_without_kid_is_checked_against_all_keys() {
+        let legacy = encode(
+            &Header::default(),
+            &claims("legacy"),
+            &EncodingKey::from_secret(b"old-secret"),
+        )
+        .unwrap();
+        let keys = BTreeMap::from([
+            ("kid-old".to_string(), "old-secret".to_string()),
+            ("kid-new".to_string(), "new-secret".to_string()),
+        ]);
+        let service = JwtService::with_keys(&keys, "kid-new", &[]);
+
+        assert_eq!(service.validate_token(&legacy).unwrap().sub, "legacy");
+        assert_eq!(key_usage("kid-old", "verified"), 1);
+
+        let forged = encode(
+            &Header::default(),
+            &claims("forged"),
+            &EncodingKey::from_secret(b"unknown"),
+        )
+        .unwrap();
+        assert!(matches!(
+            service.validate_token(&forged),
+            Err(AuthError::InvalidSignature)
+        ));
+    }
 }
@@ -0,0 +1,103 @@
+//! Лимиты размера тела запроса.
+//!
+//! Сами лимиты задаёт `DefaultBodyLimit` на группах маршрутов (см. `create_router`),
+//! его учитывают экстракторы `Json`, `AppJson`, `Bytes` и `String`. Хендлеры, которые
+//! читают тело вручную, используют `read_body` с тем же лимитом.
+
+use axum::{
+    body::{Body, Bytes},
+    extract::Request,
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use http_body_util::LengthLimitError;
+
+use crate::handlers::error::ErrorResponse;
+
+pub const PAYLOAD_TOO_LARGE: &str = "PAYLOAD_TOO_LARGE";
+
+pub fn payload_too_large() -> ErrorResponse {
+    ErrorResponse::new(
+        StatusCode::PAYLOAD_TOO_LARGE,
+        PAYLOAD_TOO_LARGE,
+        "Request body is too large",
+    )
+}
+
+/// Прочитать тело не больше `limit` байт: превышение - 413, обрыв соединения - 400
+pub async fn read_body(body: Body, limit: usize) -> Result<Bytes, ErrorResponse> {
+    axum::body::to_bytes(body, limit).await.map_err(|error| {
+        let error = error.into_inner();
+        if error.downcast_ref::<LengthLimitError>().is_some() {
+            payload_too_large()
+        } else {
+            ErrorResponse::bad_request("INVALID_BODY", format!("Failed to read body: {}", error))
+        }
+    })
+}
+
+/// Текстовый 413 от экстракторов axum заменяется на `ErrorResponse`
+pub async fn payload_too_large_middleware(request: Request, next: Next) -> Response {
+    let response = next.run(request).await;
+    if response.status() != StatusCode::PAYLOAD_TOO_LARGE {
+        return response;
+    }
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    if is_json {
+        response
+    } else {
+        payload_too_large().into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::DefaultBodyLimit, routing::post, Json, Router};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn read_body_rejects_oversized_body_with_413() {
+        let bytes = read_body(Body::from("12345"), 5).await.unwrap();
+        assert_eq!(&bytes[..], b"12345");
+
+        let error = read_body(Body::from("123456"), 5).await.unwrap_err();
+        assert_eq!(error.status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(error.code, PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn extractor_413_becomes_error_response() {
+        let app = Router::new()
+            .route(
+                "/",
+                post(|Json(value): Json<serde_json::Value>| async { Json(value) }),
+            )
+            .layer(DefaultBodyLimit::max(16))
+            .layer(axum::middleware::from_fn(payload_too_large_middleware));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(r#"{"text": "longer than sixteen bytes"}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["code"], PAYLOAD_TOO_LARGE);
+    }
+}
@@ -0,0 +1,119 @@
+//! Ответ 503 на время режима обслуживания (`services::maintenance_mode`).
+//!
+//! Проходят роли из `allow_roles` (по access-токену; остальные проверки токена делает
+//! `auth_middleware` дальше), пробы `/health*`, `/metrics` и вход: без него разрешённые
+//! роли не получили бы токен. Preflight-запросы CORS тоже не блокируются.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+
+use crate::{
+    handlers::error::ErrorResponse,
+    middlewares::auth::JwtService,
+    models::maintenance::MaintenanceMode,
+    services::{maintenance_mode, AppState},
+};
+
+pub const MAINTENANCE_CODE: &str = "MAINTENANCE";
+
+/// Пути, доступные во время обслуживания без токена
+const ALWAYS_OPEN: [&str; 4] = [
+    "/metrics",
+    "/api/v1/auth/login",
+    "/api/v1/auth/refresh",
+    "/api/v1/auth/csrf-token",
+];
+
+fn is_always_open(path: &str) -> bool {
+    path == "/health" || path.starts_with("/health/") || ALWAYS_OPEN.contains(&path)
+}
+
+pub async fn maintenance_middleware(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if request.method() == Method::OPTIONS || is_always_open(request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    // Недоступный Redis не должен останавливать весь API
+    let mode = match maintenance_mode::load(&state.redis).await {
+        Ok(Some(mode)) => mode,
+        Ok(None) => return next.run(request).await,
+        Err(err) => {
+            tracing::warn!("Maintenance mode check skipped: {:#}", err);
+            return next.run(request).await;
+        }
+    };
+
+    let role = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .and_then(|token| {
+            JwtService::from_config(&state.config)
+                .validate_token(token)
+                .ok()
+        })
+        .map(|claims| claims.role);
+    if role.is_some_and(|role| mode.allow_roles.contains(&role)) {
+        return next.run(request).await;
+    }
+
+    maintenance_response(&mode)
+}
+
+fn maintenance_response(mode: &MaintenanceMode) -> Response {
+    let mut response = ErrorResponse::new(
+        StatusCode::SERVICE_UNAVAILABLE,
+        MAINTENANCE_CODE,
+        mode.message.clone(),
+    )
+    .with_details(json!({ "retry_after_seconds": mode.retry_after_seconds }))
+    .into_response();
+    response.headers_mut().insert(
+        header::RETRY_AFTER,
+        HeaderValue::from(mode.retry_after_seconds),
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn probes_and_login_stay_open() {
+        assert!(is_always_open("/health"));
+        assert!(is_always_open("/health/ready"));
+        assert!(is_always_open("/metrics"));
+        assert!(is_always_open("/api/v1/auth/login"));
+        assert!(!is_always_open("/healthcheck"));
+        assert!(!is_always_open("/api/v1/auth/register"));
+        assert!(!is_always_open("/api/v1/sessions"));
+    }
+
+    #[test]
+    fn response_carries_retry_after() {
+        let mode = MaintenanceMode {
+            enabled: true,
+            message: "Migrating".to_string(),
+            allow_roles: vec!["admin".to_string()],
+            retry_after_seconds: 120,
+            updated_by: None,
+            updated_at: None,
+        };
+        let response = maintenance_response(&mode);
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "120");
+    }
+}
@@ -1,6 +1,9 @@
 // Middleware modules
 pub mod auth;
+pub mod body_limit;
 pub mod csrf;
+pub mod maintenance;
 pub mod metrics;
+pub mod permissions;
 pub mod rate_limit;
 pub mod trace;
@@ -0,0 +1,54 @@
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::Arc;
+
+use crate::{
+    handlers::error::ErrorResponse, middlewares::auth::JwtClaims, models::permission::Permission,
+    services::AppState,
+};
+
+/// Проверить разрешение роли из токена (403 `PERMISSION_DENIED`, если его нет)
+pub async fn ensure_permission(
+    state: &AppState,
+    claims: &JwtClaims,
+    permission: Permission,
+) -> Result<(), ErrorResponse> {
+    if state
+        .role_permissions
+        .has_permission(&state.mongo, &claims.role, permission)
+        .await
+    {
+        return Ok(());
+    }
+    tracing::warn!(
+        user_id = %claims.sub,
+        role = %claims.role,
+        permission = permission.as_str(),
+        "Access denied: permission required"
+    );
+    Err(ErrorResponse::forbidden(
+        "PERMISSION_DENIED",
+        format!("Permission {} required", permission.as_str()),
+    )
+    .with_details(serde_json::json!({ "permission": permission })))
+}
+
+/// Пропускает запрос, если у роли есть разрешение. Ставится после `auth_middleware`:
+/// `middleware::from_fn_with_state((app_state, Permission::UsersManage), require_permission)`
+pub async fn require_permission(
+    State((state, permission)): State<(Arc<AppState>, Permission)>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ErrorResponse> {
+    let Some(claims) = request.extensions().get::<JwtClaims>() else {
+        return Err(ErrorResponse::forbidden(
+            "PERMISSION_DENIED",
+            format!("Permission {} required", permission.as_str()),
+        ));
+    };
+    ensure_permission(&state, claims, permission).await?;
+    Ok(next.run(request).await)
+}
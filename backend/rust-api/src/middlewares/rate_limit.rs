@@ -1,29 +1,20 @@
 use axum::{
     extract::{ConnectInfo, Request, State},
-    http::{HeaderMap, StatusCode},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
 };
 use redis::aio::ConnectionManager;
+use serde_json::json;
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
+use crate::config::{RateLimitAlgorithm, RateLimitSettings};
+use crate::handlers::error::ErrorResponse;
+use crate::models::rate_limit::{storage_key, RateLimitNamespace, RateLimitSubject};
 use crate::services::AppState;
 
-const RATE_LIMIT_PER_USER: u32 = 100; // requests per minute
-const RATE_LIMIT_PER_IP: u32 = 200; // requests per minute
-const RATE_WINDOW_SECONDS: u64 = 60; // 1 minute
-
-const ADMIN_RATE_LIMIT_PER_USER: u32 = 200;
-const ADMIN_RATE_LIMIT_PER_IP: u32 = 300;
-const ADMIN_RATE_WINDOW_SECONDS: u64 = 60;
-
-// Auth-specific rate limits
-const LOGIN_RATE_LIMIT: u32 = 10; // 10 attempts per 5 minutes
-const LOGIN_RATE_WINDOW_SECONDS: u64 = 300; // 5 minutes
-const REGISTER_RATE_LIMIT: u32 = 5; // 5 registrations per hour
-const REGISTER_RATE_WINDOW_SECONDS: u64 = 3600; // 1 hour
-
 fn extract_client_ip_from(headers: &HeaderMap, extensions: &axum::http::Extensions) -> String {
     // Preferred order: X-Forwarded-For, Forwarded, X-Real-IP, ConnectInfo
     if let Some(v) = headers.get("x-forwarded-for") {
@@ -60,277 +51,297 @@ fn extract_client_ip_from(headers: &HeaderMap, extensions: &axum::http::Extensio
     "unknown".to_string()
 }
 
-pub async fn rate_limit_middleware(
-    State(state): State<Arc<AppState>>,
-    request: Request,
-    next: Next,
-) -> Result<Response, StatusCode> {
-    let headers = request.headers();
-    let extensions = request.extensions();
-
-    let client_ip = extract_client_ip_from(headers, extensions);
-
-    // Extract user_id from JWT claims if available
-    let user_id = request
-        .extensions()
-        .get::<super::auth::JwtClaims>()
-        .map(|claims| claims.sub.clone());
-
-    // Check rate limits
-    if let Some(uid) = &user_id {
-        // Allow overriding per-user limit via env RATE_LIMIT_PER_USER
-        let user_limit = std::env::var("RATE_LIMIT_PER_USER")
-            .ok()
-            .and_then(|s| s.parse::<u32>().ok())
-            .unwrap_or(RATE_LIMIT_PER_USER);
-
-        let allowed =
-            check_rate_limit(&state.redis, &format!("ratelimit:user:{}", uid), user_limit)
-                .await
-                .map_err(|e| {
-                    tracing::error!("Rate limit check failed: {}", e);
-                    StatusCode::INTERNAL_SERVER_ERROR
-                })?;
-
-        if !allowed {
-            tracing::warn!("Rate limit exceeded for user: {}", uid);
-            return Err(StatusCode::TOO_MANY_REQUESTS);
+/// Один лимит: ключ счётчика в Redis, порог и окно
+struct RateLimitRule {
+    key: String,
+    limit: u32,
+    window_seconds: u64,
+    label: &'static str,
+}
+
+impl RateLimitRule {
+    fn new(
+        namespace: RateLimitNamespace,
+        subject: &RateLimitSubject,
+        settings: &RateLimitSettings,
+    ) -> Self {
+        let window = namespace.window(settings, subject);
+        Self {
+            key: namespace.key(subject),
+            limit: window.limit,
+            window_seconds: window.window_secs,
+            label: namespace.as_str(),
         }
     }
+}
 
-    // Check IP rate limit
-    // Allow disabling rate limits in local perf runs by setting RATE_LIMIT_DISABLED=1
-    let rate_limit_disabled = std::env::var("RATE_LIMIT_DISABLED").unwrap_or_default() == "1";
-
-    if !rate_limit_disabled {
-        // allow overriding per-IP limit via env RATE_LIMIT_PER_IP
-        let ip_limit = std::env::var("RATE_LIMIT_PER_IP")
-            .ok()
-            .and_then(|s| s.parse::<u32>().ok())
-            .unwrap_or(RATE_LIMIT_PER_IP);
-
-        let allowed = check_rate_limit(
-            &state.redis,
-            &format!("ratelimit:ip:{}", client_ip),
-            ip_limit,
+/// Состояние счётчика после проверки
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitStatus {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    /// Секунды до сброса окна
+    pub reset_after_secs: u64,
+}
+
+impl RateLimitStatus {
+    /// Добавить X-RateLimit-* (Reset - секунды до сброса окна)
+    fn apply_headers(&self, headers: &mut HeaderMap) {
+        headers.insert("x-ratelimit-limit", HeaderValue::from(self.limit));
+        headers.insert("x-ratelimit-remaining", HeaderValue::from(self.remaining));
+        headers.insert(
+            "x-ratelimit-reset",
+            HeaderValue::from(self.reset_after_secs),
+        );
+    }
+
+    /// 429 с Retry-After и JSON-телом в общем формате ошибок
+    fn rejection(&self) -> Response {
+        let mut response = ErrorResponse::new(
+            StatusCode::TOO_MANY_REQUESTS,
+            "RATE_LIMITED",
+            "Too many requests, please retry later",
         )
-        .await
-        .map_err(|e| {
-            tracing::error!("Rate limit check failed: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
-
-        if !allowed {
-            tracing::warn!("Rate limit exceeded for IP: {}", client_ip);
-            return Err(StatusCode::TOO_MANY_REQUESTS);
+        .with_details(json!({ "retry_after_seconds": self.reset_after_secs }))
+        .into_response();
+
+        let headers = response.headers_mut();
+        self.apply_headers(headers);
+        headers.insert(
+            header::RETRY_AFTER,
+            HeaderValue::from(self.reset_after_secs),
+        );
+        response
+    }
+}
+
+/// Проверить все правила по порядку и пропустить запрос дальше.
+///
+/// Первый превышенный лимит отвечает 429; иначе в ответ попадают заголовки
+/// самого «тесного» лимита (с наименьшим остатком).
+async fn run_with_limits(
+    redis: &ConnectionManager,
+    algorithm: RateLimitAlgorithm,
+    rules: Vec<RateLimitRule>,
+    client_ip: &str,
+    request: Request,
+    next: Next,
+) -> Response {
+    let mut tightest: Option<RateLimitStatus> = None;
+
+    for rule in rules {
+        let status = match check_rate_limit(redis, algorithm, &rule).await {
+            Ok(status) => status,
+            Err(e) => {
+                tracing::error!("{} rate limit check failed: {}", rule.label, e);
+                return ErrorResponse::internal("Rate limit check failed").into_response();
+            }
+        };
+
+        if !status.allowed {
+            tracing::warn!(
+                "{} rate limit exceeded for key {} (IP: {})",
+                rule.label,
+                rule.key,
+                client_ip
+            );
+            return status.rejection();
+        }
+
+        if tightest.is_none_or(|current| status.remaining < current.remaining) {
+            tightest = Some(status);
         }
-    } else {
-        tracing::debug!("Rate limiting disabled via RATE_LIMIT_DISABLED=1");
     }
 
-    Ok(next.run(request).await)
+    let mut response = next.run(request).await;
+    if let Some(status) = tightest {
+        status.apply_headers(response.headers_mut());
+    }
+    response
 }
 
-/// Check rate limit using Redis with Lua script for atomicity
-async fn check_rate_limit(
-    redis: &ConnectionManager,
-    key: &str,
-    limit: u32,
-) -> anyhow::Result<bool> {
-    let mut conn = redis.clone();
+fn rate_limit_disabled() -> bool {
+    std::env::var("RATE_LIMIT_DISABLED").unwrap_or_default() == "1"
+}
 
-    // Lua script for atomic increment with sliding window
-    let lua_script = r#"
-        local key = KEYS[1]
-        local limit = tonumber(ARGV[1])
-        local window = tonumber(ARGV[2])
-        
-        local current = redis.call('GET', key)
-        
-        if current == false then
-            redis.call('SET', key, 1, 'EX', window)
-            return 1
-        end
-        
-        current = tonumber(current)
-        
-        if current >= limit then
-            return 0
-        end
-        
-        redis.call('INCR', key)
-        return 1
-    "#;
+/// Субъект лимита: пользователь, если запрос аутентифицирован, иначе IP
+fn request_subject(request: &Request, client_ip: &str) -> RateLimitSubject {
+    match request.extensions().get::<super::auth::JwtClaims>() {
+        Some(claims) => RateLimitSubject::User(claims.sub.clone()),
+        None => RateLimitSubject::Ip(client_ip.to_string()),
+    }
+}
 
-    let allowed: u32 = redis::Script::new(lua_script)
-        .key(key)
-        .arg(limit)
-        .arg(RATE_WINDOW_SECONDS)
-        .invoke_async(&mut conn)
-        .await?;
+/// Субъект лимита сессий. Лимитер стоит до auth (часть маршрутов, например SSE,
+/// открыта без него), поэтому пользователь берётся из Bearer-токена самого запроса.
+/// Отзыв токена здесь не проверяется - его отклонит auth дальше по цепочке
+fn bearer_subject(state: &AppState, request: &Request, client_ip: &str) -> RateLimitSubject {
+    let claims = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .and_then(|token| {
+            super::auth::JwtService::from_config(&state.config)
+                .validate_token(token)
+                .ok()
+        });
+    match claims {
+        Some(claims) => RateLimitSubject::User(claims.sub),
+        None => request_subject(request, client_ip),
+    }
+}
+
+async fn limit_request(
+    state: &AppState,
+    namespace: RateLimitNamespace,
+    subject: RateLimitSubject,
+    client_ip: &str,
+    request: Request,
+    next: Next,
+) -> Response {
+    // Снимок на запрос: после перезагрузки лимиты меняются без пересборки роутера
+    let runtime = state.runtime.snapshot();
+    let settings = &runtime.rate_limit;
+    let rule = RateLimitRule::new(namespace, &subject, settings);
+    run_with_limits(
+        &state.redis,
+        settings.algorithm,
+        vec![rule],
+        client_ip,
+        request,
+        next,
+    )
+    .await
+}
+
+pub async fn rate_limit_middleware(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    // Allow disabling rate limits in local perf runs by setting RATE_LIMIT_DISABLED=1
+    if rate_limit_disabled() {
+        tracing::debug!("Rate limiting disabled via RATE_LIMIT_DISABLED=1");
+        return next.run(request).await;
+    }
 
-    Ok(allowed == 1)
+    let client_ip = extract_client_ip_from(request.headers(), request.extensions());
+    let subject = bearer_subject(&state, &request, &client_ip);
+    limit_request(
+        &state,
+        RateLimitNamespace::Sessions,
+        subject,
+        &client_ip,
+        request,
+        next,
+    )
+    .await
 }
 
 /// Rate limit middleware for login endpoint
-/// Allows 10 attempts per 5 minutes per IP
+/// Defaults to 10 attempts per 5 minutes per IP
 pub async fn login_rate_limit_middleware(
     State(state): State<Arc<AppState>>,
     request: Request,
     next: Next,
-) -> Result<Response, StatusCode> {
-    let headers = request.headers();
-    let extensions = request.extensions();
-    let client_ip = extract_client_ip_from(headers, extensions);
-
-    let rate_limit_disabled = std::env::var("RATE_LIMIT_DISABLED").unwrap_or_default() == "1";
-
-    if !rate_limit_disabled {
-        // Allow overriding login limit via env RATE_LIMIT_LOGIN_ATTEMPTS
-        let login_limit = std::env::var("RATE_LIMIT_LOGIN_ATTEMPTS")
-            .ok()
-            .and_then(|s| s.parse::<u32>().ok())
-            .unwrap_or(LOGIN_RATE_LIMIT);
-
-        let allowed = check_rate_limit_with_window(
-            &state.redis,
-            &format!("ratelimit:login:{}", client_ip),
-            login_limit,
-            LOGIN_RATE_WINDOW_SECONDS,
-        )
-        .await
-        .map_err(|e| {
-            tracing::error!("Login rate limit check failed: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
-
-        if !allowed {
-            tracing::warn!("Login rate limit exceeded for IP: {}", client_ip);
-            return Err(StatusCode::TOO_MANY_REQUESTS);
-        }
+) -> Response {
+    if rate_limit_disabled() {
+        return next.run(request).await;
     }
 
-    Ok(next.run(request).await)
+    let client_ip = extract_client_ip_from(request.headers(), request.extensions());
+    let subject = RateLimitSubject::Ip(client_ip.clone());
+    limit_request(
+        &state,
+        RateLimitNamespace::Login,
+        subject,
+        &client_ip,
+        request,
+        next,
+    )
+    .await
 }
 
 /// Rate limit middleware for register endpoint
-/// Allows 5 registrations per hour per IP
+/// Defaults to 5 registrations per hour per IP
 pub async fn register_rate_limit_middleware(
     State(state): State<Arc<AppState>>,
     request: Request,
     next: Next,
-) -> Result<Response, StatusCode> {
-    let headers = request.headers();
-    let extensions = request.extensions();
-    let client_ip = extract_client_ip_from(headers, extensions);
-
-    let rate_limit_disabled = std::env::var("RATE_LIMIT_DISABLED").unwrap_or_default() == "1";
-
-    if !rate_limit_disabled {
-        // Allow overriding register limit via env RATE_LIMIT_REGISTER_ATTEMPTS
-        let register_limit = std::env::var("RATE_LIMIT_REGISTER_ATTEMPTS")
-            .ok()
-            .and_then(|s| s.parse::<u32>().ok())
-            .unwrap_or(REGISTER_RATE_LIMIT);
-
-        let allowed = check_rate_limit_with_window(
-            &state.redis,
-            &format!("ratelimit:register:{}", client_ip),
-            register_limit,
-            REGISTER_RATE_WINDOW_SECONDS,
-        )
-        .await
-        .map_err(|e| {
-            tracing::error!("Register rate limit check failed: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
-
-        if !allowed {
-            tracing::warn!("Register rate limit exceeded for IP: {}", client_ip);
-            return Err(StatusCode::TOO_MANY_REQUESTS);
-        }
+) -> Response {
+    if rate_limit_disabled() {
+        return next.run(request).await;
     }
 
-    Ok(next.run(request).await)
+    let client_ip = extract_client_ip_from(request.headers(), request.extensions());
+    let subject = RateLimitSubject::Ip(client_ip.clone());
+    limit_request(
+        &state,
+        RateLimitNamespace::Register,
+        subject,
+        &client_ip,
+        request,
+        next,
+    )
+    .await
 }
 
 pub async fn admin_rate_limit_middleware(
     State(state): State<Arc<AppState>>,
     request: Request,
     next: Next,
-) -> Result<Response, StatusCode> {
+) -> Response {
     if std::env::var("ADMIN_RATE_LIMIT_DISABLED").unwrap_or_default() == "1" {
-        return Ok(next.run(request).await);
-    }
-
-    let headers = request.headers();
-    let extensions = request.extensions();
-    let client_ip = extract_client_ip_from(headers, extensions);
-
-    let user_id = request
-        .extensions()
-        .get::<super::auth::JwtClaims>()
-        .map(|c| c.sub.clone());
-
-    if let Some(uid) = &user_id {
-        let limit = std::env::var("ADMIN_RATE_LIMIT_PER_USER")
-            .ok()
-            .and_then(|s| s.parse::<u32>().ok())
-            .unwrap_or(ADMIN_RATE_LIMIT_PER_USER);
-
-        let allowed = check_rate_limit_with_window(
-            &state.redis,
-            &format!("ratelimit:admin:user:{uid}"),
-            limit,
-            ADMIN_RATE_WINDOW_SECONDS,
-        )
-        .await
-        .map_err(|e| {
-            tracing::error!("Admin rate limit check failed: {e}");
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
-
-        if !allowed {
-            tracing::warn!("Admin user rate limit exceeded: {uid}");
-            return Err(StatusCode::TOO_MANY_REQUESTS);
-        }
+        return next.run(request).await;
     }
 
-    let ip_limit = std::env::var("ADMIN_RATE_LIMIT_PER_IP")
-        .ok()
-        .and_then(|s| s.parse::<u32>().ok())
-        .unwrap_or(ADMIN_RATE_LIMIT_PER_IP);
-
-    let allowed = check_rate_limit_with_window(
-        &state.redis,
-        &format!("ratelimit:admin:ip:{client_ip}"),
-        ip_limit,
-        ADMIN_RATE_WINDOW_SECONDS,
+    let client_ip = extract_client_ip_from(request.headers(), request.extensions());
+    let subject = request_subject(&request, &client_ip);
+    limit_request(
+        &state,
+        RateLimitNamespace::Admin,
+        subject,
+        &client_ip,
+        request,
+        next,
     )
     .await
-    .map_err(|e| {
-        tracing::error!("Admin rate limit check failed: {e}");
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-
-    if !allowed {
-        tracing::warn!("Admin IP rate limit exceeded: {client_ip}");
-        return Err(StatusCode::TOO_MANY_REQUESTS);
-    }
+}
 
-    Ok(next.run(request).await)
+async fn check_rate_limit(
+    redis: &ConnectionManager,
+    algorithm: RateLimitAlgorithm,
+    rule: &RateLimitRule,
+) -> anyhow::Result<RateLimitStatus> {
+    match algorithm {
+        RateLimitAlgorithm::Fixed => {
+            check_rate_limit_with_window(redis, &rule.key, rule.limit, rule.window_seconds).await
+        }
+        RateLimitAlgorithm::Sliding => {
+            check_sliding_window(
+                redis,
+                &rule.key,
+                rule.limit,
+                rule.window_seconds,
+                chrono::Utc::now().timestamp_millis().max(0) as u64,
+            )
+            .await
+        }
+    }
 }
 
-/// Check rate limit with custom window
+/// Check rate limit using Redis with Lua script for atomicity (fixed window)
 async fn check_rate_limit_with_window(
     redis: &ConnectionManager,
     key: &str,
     limit: u32,
     window_seconds: u64,
-) -> anyhow::Result<bool> {
+) -> anyhow::Result<RateLimitStatus> {
     let mut conn = redis.clone();
 
-    // Lua script for atomic increment with sliding window
+    // Lua script for atomic increment with fixed window; returns {allowed, count, ttl}
     let lua_script = r#"
         local key = KEYS[1]
         local limit = tonumber(ARGV[1])
@@ -340,27 +351,128 @@ async fn check_rate_limit_with_window(
 
         if current == false then
             redis.call('SET', key, 1, 'EX', window)
-            return 1
+            return {1, 1, window}
         end
 
         current = tonumber(current)
+        local ttl = redis.call('TTL', key)
+        if ttl < 0 then
+            redis.call('EXPIRE', key, window)
+            ttl = window
+        end
 
         if current >= limit then
-            return 0
+            return {0, current, ttl}
         end
 
-        redis.call('INCR', key)
-        return 1
+        current = redis.call('INCR', key)
+        return {1, current, ttl}
     "#;
 
-    let allowed: u32 = redis::Script::new(lua_script)
+    let (allowed, count, ttl): (u32, u32, u64) = redis::Script::new(lua_script)
         .key(key)
         .arg(limit)
         .arg(window_seconds)
         .invoke_async(&mut conn)
         .await?;
 
-    Ok(allowed == 1)
+    Ok(RateLimitStatus {
+        allowed: allowed == 1,
+        limit,
+        remaining: limit.saturating_sub(count),
+        reset_after_secs: ttl,
+    })
+}
+
+// Скользящее окно: ZSET с отметками времени (мс) успешных запросов.
+// Отклонённые запросы не записываются, чтобы повторы не продлевали блокировку.
+// Возвращает {allowed, count, oldest_ms}.
+const SLIDING_WINDOW_SCRIPT: &str = r#"
+    local key = KEYS[1]
+    local limit = tonumber(ARGV[1])
+    local cutoff = tonumber(ARGV[2])
+    local now = tonumber(ARGV[3])
+    local window_ms = tonumber(ARGV[4])
+    local member = ARGV[5]
+
+    redis.call('ZREMRANGEBYSCORE', key, '-inf', cutoff)
+    local count = redis.call('ZCARD', key)
+    local allowed = 0
+
+    if count < limit then
+        redis.call('ZADD', key, now, member)
+        redis.call('PEXPIRE', key, window_ms)
+        count = count + 1
+        allowed = 1
+    end
+
+    local oldest = redis.call('ZRANGE', key, 0, 0, 'WITHSCORES')
+    local oldest_ms = now
+    if oldest[2] then
+        oldest_ms = tonumber(oldest[2])
+    end
+    return {allowed, count, oldest_ms}
+"#;
+
+/// Различает запросы с одинаковой меткой времени внутри процесса
+static SLIDING_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// Граница окна: отметки `<= cutoff` уже не учитываются
+pub(crate) fn window_cutoff(now_ms: u64, window_ms: u64) -> u64 {
+    now_ms.saturating_sub(window_ms)
+}
+
+/// Остаток и время до освобождения слота по данным ZSET
+fn sliding_status(
+    allowed: bool,
+    count: u32,
+    oldest_ms: u64,
+    now_ms: u64,
+    limit: u32,
+    window_ms: u64,
+) -> RateLimitStatus {
+    // Самая старая отметка выпадет из окна через (oldest + window - now)
+    let reset_ms = (oldest_ms + window_ms).saturating_sub(now_ms);
+    RateLimitStatus {
+        allowed,
+        limit,
+        remaining: limit.saturating_sub(count),
+        reset_after_secs: reset_ms.div_ceil(1000).max(1),
+    }
+}
+
+/// Check rate limit with a sliding window of request timestamps.
+/// `now_ms` передаётся снаружи, чтобы тесты гоняли скрипт на своих часах
+pub async fn check_sliding_window(
+    redis: &ConnectionManager,
+    key: &str,
+    limit: u32,
+    window_seconds: u64,
+    now_ms: u64,
+) -> anyhow::Result<RateLimitStatus> {
+    let mut conn = redis.clone();
+    let window_ms = window_seconds * 1000;
+    let member = format!("{}-{}", now_ms, SLIDING_SEQ.fetch_add(1, Ordering::Relaxed));
+
+    // redis::Script вызывает EVALSHA и подгружает скрипт при NOSCRIPT
+    let (allowed, count, oldest_ms): (u32, u32, u64) = redis::Script::new(SLIDING_WINDOW_SCRIPT)
+        .key(storage_key(key, RateLimitAlgorithm::Sliding))
+        .arg(limit)
+        .arg(window_cutoff(now_ms, window_ms))
+        .arg(now_ms)
+        .arg(window_ms)
+        .arg(member)
+        .invoke_async(&mut conn)
+        .await?;
+
+    Ok(sliding_status(
+        allowed == 1,
+        count,
+        oldest_ms,
+        now_ms,
+        limit,
+        window_ms,
+    ))
 }
 
 #[cfg(test)]
@@ -370,6 +482,39 @@ mod tests {
     use axum::http::HeaderMap;
     use std::net::SocketAddr;
 
+    fn status(remaining: u32, reset_after_secs: u64) -> RateLimitStatus {
+        RateLimitStatus {
+            allowed: remaining > 0,
+            limit: 10,
+            remaining,
+            reset_after_secs,
+        }
+    }
+
+    #[test]
+    fn test_status_headers() {
+        let mut headers = HeaderMap::new();
+        status(7, 42).apply_headers(&mut headers);
+        assert_eq!(headers["x-ratelimit-limit"], "10");
+        assert_eq!(headers["x-ratelimit-remaining"], "7");
+        assert_eq!(headers["x-ratelimit-reset"], "42");
+    }
+
+    #[tokio::test]
+    async fn test_rejection_body_and_retry_after() {
+        let response = status(0, 30).rejection();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "30");
+        assert_eq!(response.headers()["x-ratelimit-remaining"], "0");
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["code"], "RATE_LIMITED");
+        assert_eq!(body["details"]["retry_after_seconds"], 30);
+    }
+
     #[test]
     fn test_extract_client_ip_x_forwarded_for() {
         let mut headers = HeaderMap::new();
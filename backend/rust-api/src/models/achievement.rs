@@ -0,0 +1,108 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::user::bson_datetime_as_chrono;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AchievementKind {
+    /// Первая завершённая сессия
+    FirstSession,
+    /// 100 верных ответов за всё время
+    HundredCorrectAnswers,
+    /// Завершённые сессии 7 дней подряд
+    SevenDayStreak,
+}
+
+impl AchievementKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AchievementKind::FirstSession => "first_session",
+            AchievementKind::HundredCorrectAnswers => "hundred_correct_answers",
+            AchievementKind::SevenDayStreak => "seven_day_streak",
+        }
+    }
+
+    /// Название для центра уведомлений
+    pub fn title(self) -> &'static str {
+        match self {
+            AchievementKind::FirstSession => "Первая сессия",
+            AchievementKind::HundredCorrectAnswers => "100 верных ответов",
+            AchievementKind::SevenDayStreak => "7 дней подряд",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnlockedAchievement {
+    pub kind: AchievementKind,
+    #[serde(with = "bson_datetime_as_chrono")]
+    pub unlocked_at: DateTime<Utc>,
+}
+
+/// Документ `user_achievements`: серия дней и открытые достижения ученика
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UserAchievements {
+    #[serde(rename = "_id")]
+    pub user_id: String,
+    #[serde(default)]
+    pub current_streak: u32,
+    #[serde(default)]
+    pub longest_streak: u32,
+    /// Последний день с завершённой сессией, по часовому поясу ученика
+    #[serde(default)]
+    pub last_active_day: Option<NaiveDate>,
+    #[serde(default)]
+    pub achievements: Vec<UnlockedAchievement>,
+    /// Растёт при каждой записи; запись с устаревшей версией повторяется
+    #[serde(default)]
+    pub version: i64,
+}
+
+impl UserAchievements {
+    pub fn is_unlocked(&self, kind: AchievementKind) -> bool {
+        self.achievements
+            .iter()
+            .any(|achievement| achievement.kind == kind)
+    }
+
+    /// Открыть достижение; `false`, если оно уже открыто
+    pub fn unlock(&mut self, kind: AchievementKind, at: DateTime<Utc>) -> bool {
+        if self.is_unlocked(kind) {
+            return false;
+        }
+        self.achievements.push(UnlockedAchievement {
+            kind,
+            unlocked_at: at,
+        });
+        true
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AchievementResponse {
+    pub kind: AchievementKind,
+    pub unlocked_at: DateTime<Utc>,
+}
+
+impl From<UnlockedAchievement> for AchievementResponse {
+    fn from(achievement: UnlockedAchievement) -> Self {
+        Self {
+            kind: achievement.kind,
+            unlocked_at: achievement.unlocked_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AchievementsResponse {
+    /// Дней подряд с завершённой сессией; пропущенный вчерашний день обнуляет серию
+    pub current_streak: u32,
+    pub longest_streak: u32,
+    pub last_active_day: Option<NaiveDate>,
+    /// Часовой пояс, по которому считаются границы дней
+    pub timezone: String,
+    /// Открытые достижения в порядке получения
+    pub achievements: Vec<AchievementResponse>,
+}
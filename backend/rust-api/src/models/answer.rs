@@ -1,13 +1,30 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
+#[cfg_attr(feature = "client", derive(Serialize))]
 pub struct SubmitAnswerRequest {
+    /// Ответ целиком; у заданий с `scoring: "per_part"` вместо него передаётся `parts`
+    #[serde(default)]
     pub answer: String,
     pub idempotency_key: Option<String>,
+    /// Ответы по пропускам задания, по порядку
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parts: Option<Vec<String>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl SubmitAnswerRequest {
+    /// Ответ одной строкой для журнала попыток и антифрода; части разделяются ` | `
+    pub fn submitted_text(&self) -> String {
+        match &self.parts {
+            Some(parts) => parts.join(" | "),
+            None => self.answer.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct SubmitAnswerResponse {
     pub correct: bool,
     pub score_awarded: i32,
@@ -15,6 +32,58 @@ pub struct SubmitAnswerResponse {
     pub total_score: i32,
     pub current_streak: u32,
     pub feedback: Option<String>,
+    /// Шаги нормализации, изменившие ответ ученика или эталон (для отладки проверки)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub normalization: Vec<NormalizationStep>,
+    /// Результат по каждому пропуску (только для заданий с `scoring: "per_part"`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parts: Vec<AnswerPartResult>,
+    /// Лимит ответов задания (`max_attempts`); `null` - без ограничения
+    #[serde(default)]
+    pub max_attempts: Option<u32>,
+    /// Сколько ответов ещё примет сессия; `null`, если у задания нет `max_attempts`
+    #[serde(default)]
+    pub remaining_attempts: Option<u32>,
+    /// Попытки закончились, и сессия завершена с лучшим результатом
+    #[serde(default)]
+    pub session_completed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AnswerPartResult {
+    pub index: usize,
+    pub correct: bool,
+}
+
+/// Допустимые ответы задания в дополнение к `correct_answer`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct TaskAnswers {
+    /// Точные варианты; сравниваются с той же нормализацией, что и `correct_answer`
+    #[serde(default)]
+    pub accepted: Vec<String>,
+    /// Regex для всего ответа: `^` и `$` добавляются автоматически
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    /// Правильные ответы по пропускам для `scoring: "per_part"`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parts: Vec<TaskAnswers>,
+}
+
+/// Шаг нормализации ответа перед сравнением с `correct_answer`; варианты идут
+/// в порядке применения
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NormalizationStep {
+    /// Unicode NFC: составные символы (`и` + `\u{306}`) приводятся к `й`
+    Nfc,
+    /// Пробелы по краям убраны, NBSP и серии пробелов внутри заменены одним пробелом
+    Whitespace,
+    /// Дефисы, тире и минус приведены к `-`
+    Dashes,
+    /// Регистр не учитывается
+    CaseFold,
+    /// `ё` считается равной `е`
+    YoToYe,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,6 +97,55 @@ pub struct AttemptRecord {
     pub score: i32,
     pub timestamp: DateTime<Utc>,
     pub reason: Option<AttemptFailureReason>,
+    /// Зачтённая доля ответа (как в `progress_summary_v2.credit`); в старых
+    /// записях поля нет - тогда доля 1 за верный ответ и 0 за неверный
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credit: Option<f64>,
+    /// Время ответа по часам сервера; в старых записях поля нет
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timing: Option<AnswerTiming>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnswerTiming {
+    /// С начала сессии
+    pub since_session_start_ms: i64,
+    /// С предыдущего ответа сессии, для первого ответа - с начала сессии
+    pub since_previous_ms: i64,
+}
+
+/// Сводка по времени ответов (`since_previous_ms`) для карточки сессии и ученика
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct AnswerTimingSummary {
+    pub answers: u32,
+    pub median_latency_ms: i64,
+    pub fastest_latency_ms: i64,
+}
+
+impl AnswerTimingSummary {
+    /// `None`, если ответов со временем нет
+    pub fn from_latencies(latencies: &[i64]) -> Option<Self> {
+        Some(Self {
+            answers: latencies.len() as u32,
+            median_latency_ms: median_ms(latencies)?,
+            fastest_latency_ms: latencies.iter().copied().min()?,
+        })
+    }
+}
+
+/// Медиана; при чётном числе значений - среднее двух центральных с округлением вниз
+pub fn median_ms(latencies: &[i64]) -> Option<i64> {
+    if latencies.is_empty() {
+        return None;
+    }
+    let mut sorted = latencies.to_vec();
+    sorted.sort_unstable();
+    let middle = sorted.len() / 2;
+    Some(if sorted.len().is_multiple_of(2) {
+        (sorted[middle - 1] + sorted[middle]).div_euclid(2)
+    } else {
+        sorted[middle]
+    })
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1,12 +1,22 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
 use chrono::{DateTime, Utc};
+use mongodb::bson;
 use serde::{Deserialize, Serialize};
 
+use super::system_settings::AnticheatSettings;
 use super::user::UserRole;
+use super::SessionStatus;
+use utoipa::ToSchema;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IncidentRecord {
     pub id: String,
     pub user_id: String,
+    /// Сессия, в которой замечено нарушение
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
     pub incident_type: IncidentType,
     pub severity: IncidentSeverity,
     pub details: IncidentDetails,
@@ -14,23 +24,48 @@ pub struct IncidentRecord {
     pub action_taken: ActionTaken,
     #[serde(default)]
     pub status: IncidentStatus,
+    /// Администратор, который разбирает инцидент
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assigned_to: Option<String>,
+    /// Последнее изменение статуса, ответственного или комментариев
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub resolved_by: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub resolved_at: Option<DateTime<Utc>>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub resolution_note: Option<String>,
+    /// Пользователь заблокирован автоматически по этому инциденту
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_block: Option<IncidentAutoBlock>,
+    /// Администратор снял блокировку через `POST /admin/incidents/{id}/unblock`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unblocked_by: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unblocked_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Автоблокировка, выполненная при открытии инцидента
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IncidentAutoBlock {
+    pub blocked_at: DateTime<Utc>,
+    /// `block_duration_hours` из настроек античита
+    pub blocked_until: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
 #[serde(rename_all = "snake_case")]
 pub enum IncidentType {
     SpeedViolation,
     RepeatedAnswers,
     SuspiciousPattern,
+    /// Пороги по сигналам клиента (вкладки, вставка, devtools)
+    ClientSignals,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Порядок вариантов задаёт сравнение: Low < Medium < High < Critical
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 #[serde(rename_all = "snake_case")]
 pub enum IncidentSeverity {
     Low,
@@ -39,7 +74,7 @@ pub enum IncidentSeverity {
     Critical,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum ActionTaken {
     None,
@@ -48,13 +83,53 @@ pub enum ActionTaken {
     Blocked,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+/// Жизненный цикл инцидента: open → investigating → resolved / dismissed
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
 #[serde(rename_all = "snake_case")]
 pub enum IncidentStatus {
     #[default]
     Open,
+    Investigating,
     Resolved,
-    FalsePositive,
+    /// Ложное срабатывание; старые записи хранят `false_positive`
+    #[serde(alias = "false_positive")]
+    Dismissed,
+}
+
+impl IncidentStatus {
+    pub const ALL: [IncidentStatus; 4] = [
+        IncidentStatus::Open,
+        IncidentStatus::Investigating,
+        IncidentStatus::Resolved,
+        IncidentStatus::Dismissed,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            IncidentStatus::Open => "open",
+            IncidentStatus::Investigating => "investigating",
+            IncidentStatus::Resolved => "resolved",
+            IncidentStatus::Dismissed => "dismissed",
+        }
+    }
+
+    pub fn is_closed(self) -> bool {
+        matches!(self, IncidentStatus::Resolved | IncidentStatus::Dismissed)
+    }
+
+    /// Значения поля `status` в MongoDB, включая устаревшие
+    pub fn stored_values(self) -> Vec<&'static str> {
+        match self {
+            IncidentStatus::Dismissed => vec!["dismissed", "false_positive"],
+            status => vec![status.as_str()],
+        }
+    }
+}
+
+impl fmt::Display for IncidentStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -63,6 +138,9 @@ pub struct IncidentDetails {
     pub repeated_hits: Option<u32>,
     pub time_window_seconds: Option<u32>,
     pub additional_info: Option<String>,
+    /// Число сигналов клиента по типам за сессию
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signal_counts: Option<BTreeMap<String, u32>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -89,27 +167,316 @@ pub struct IncidentWithUser {
     pub incident: IncidentRecord,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub user: Option<IncidentUserInfo>,
+    /// Обсуждение инцидента; заполняется только в карточке инцидента
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comments: Option<Vec<IncidentComment>>,
+}
+
+/// Комментарий администратора; `parent_id` - ответ на другой комментарий того же инцидента
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncidentComment {
+    pub id: String,
+    pub incident_id: String,
+    pub author_id: String,
+    pub body: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum IncidentSort {
+    #[default]
+    Timestamp,
+    UpdatedAt,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct ListIncidentsQuery {
     pub incident_type: Option<IncidentType>,
     pub severity: Option<IncidentSeverity>,
+    #[serde(alias = "state")]
     pub status: Option<IncidentStatus>,
     pub user_id: Option<String>,
+    pub session_id: Option<String>,
+    pub assigned_to: Option<String>,
+    /// Сортировка всегда по убыванию
+    pub sort: Option<IncidentSort>,
     pub limit: Option<u32>,
     pub offset: Option<u32>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct UpdateIncidentRequest {
-    pub action: IncidentResolutionAction,
+    pub state: Option<IncidentStatus>,
+    /// Прежний формат запроса; `state` имеет приоритет
+    pub action: Option<IncidentResolutionAction>,
     pub note: Option<String>,
+    /// Закрыть инцидент без ответственного
+    #[serde(default)]
+    pub force: bool,
 }
 
-#[derive(Debug, Deserialize)]
+impl UpdateIncidentRequest {
+    pub fn target_state(&self) -> Option<IncidentStatus> {
+        self.state.or(match self.action {
+            Some(IncidentResolutionAction::Resolve) => Some(IncidentStatus::Resolved),
+            Some(IncidentResolutionAction::FalsePositive) => Some(IncidentStatus::Dismissed),
+            None => None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum IncidentResolutionAction {
     Resolve,
     FalsePositive,
 }
+
+/// `assigned_to: null` снимает ответственного
+#[derive(Debug, Deserialize)]
+pub struct AssignIncidentRequest {
+    pub assigned_to: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateIncidentCommentRequest {
+    pub body: String,
+    pub parent_id: Option<String>,
+}
+
+/// Подозрительное действие, замеченное клиентом во время сессии
+#[derive(
+    Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum SignalType {
+    /// Уход со вкладки или сворачивание окна
+    TabSwitch,
+    Paste,
+    /// Открыты инструменты разработчика
+    Devtools,
+    /// Ставит сервер: медиана времени ответа ниже порога сложности задания
+    FastAnswers,
+}
+
+impl SignalType {
+    /// Типы, которые присылает клиент
+    pub const ALL: [SignalType; 3] = [
+        SignalType::TabSwitch,
+        SignalType::Paste,
+        SignalType::Devtools,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SignalType::TabSwitch => "tab_switch",
+            SignalType::Paste => "paste",
+            SignalType::Devtools => "devtools",
+            SignalType::FastAnswers => "fast_answers",
+        }
+    }
+
+    pub fn is_server_side(self) -> bool {
+        matches!(self, SignalType::FastAnswers)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ClientSignal {
+    #[serde(rename = "type")]
+    pub signal_type: SignalType,
+    /// Время события на клиенте
+    pub timestamp: DateTime<Utc>,
+    /// Подробности от клиента (например, длина вставленного текста)
+    #[serde(default)]
+    pub payload: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SignalBatchRequest {
+    pub signals: Vec<ClientSignal>,
+}
+
+/// Сигнал в коллекции anticheat_signals
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredSignal {
+    pub session_id: String,
+    pub user_id: String,
+    #[serde(rename = "type")]
+    pub signal_type: SignalType,
+    pub timestamp: bson::DateTime,
+    pub payload: bson::Bson,
+    pub received_at: bson::DateTime,
+    /// Только у серверных сигналов
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub severity: Option<IncidentSeverity>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SignalBatchResponse {
+    pub accepted: usize,
+    /// Число сигналов по типам за всю сессию
+    pub counts: BTreeMap<SignalType, u32>,
+    /// Инцидент, открытый этим пакетом
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub incident_id: Option<String>,
+}
+
+/// Окна, для которых analytics worker хранит пиковое число ответов сессии
+pub const SESSION_STATS_WINDOWS_SECONDS: [u32; 5] = [60, 300, 900, 3600, 86_400];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowPeak {
+    pub window_seconds: u32,
+    pub answers: u32,
+}
+
+/// Агрегат времени ответов одной сессии (коллекция anticheat_session_stats).
+/// Пересчитывается analytics worker; предпросмотр настроек читает только его.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionTimingStats {
+    #[serde(rename = "_id")]
+    pub session_id: String,
+    pub user_id: String,
+    pub answers: u32,
+    /// Максимум ответов в любом окне длины window_seconds
+    pub peaks: Vec<WindowPeak>,
+    /// Сколько раз встречался самый частый ответ
+    pub max_repeated_answers: u32,
+    pub first_answer_at: bson::DateTime,
+    pub last_answer_at: bson::DateTime,
+}
+
+impl SessionTimingStats {
+    /// Пик для окна: берётся ближайшее сохранённое окно не короче запрошенного,
+    /// поэтому оценка не занижает число срабатываний
+    pub fn peak_for_window(&self, window_seconds: u32) -> u32 {
+        self.peaks
+            .iter()
+            .filter(|peak| peak.window_seconds >= window_seconds)
+            .min_by_key(|peak| peak.window_seconds)
+            .or_else(|| self.peaks.iter().max_by_key(|peak| peak.window_seconds))
+            .map(|peak| peak.answers)
+            .unwrap_or(self.answers)
+    }
+}
+
+fn default_preview_lookback_days() -> u32 {
+    7
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AnticheatPreviewRequest {
+    pub settings: AnticheatSettings,
+    #[serde(default = "default_preview_lookback_days")]
+    pub lookback_days: u32,
+}
+
+/// Срабатывания одного правила
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RulePreview {
+    pub rule: IncidentType,
+    pub sessions: u64,
+    pub users: u64,
+    pub blocked_sessions: u64,
+}
+
+/// Пример срабатывания; идентификаторы заменены на одноразовые псевдонимы
+#[derive(Debug, Clone, Serialize)]
+pub struct PreviewExample {
+    pub user_ref: String,
+    pub session_ref: String,
+    pub rule: IncidentType,
+    pub severity: IncidentSeverity,
+    pub action: ActionTaken,
+    pub answers: u32,
+    pub peak_answers: u32,
+    pub max_repeated_answers: u32,
+}
+
+/// Что сделали бы кандидатные пороги. flagged_* включает и заблокированных.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PreviewOutcome {
+    pub sessions_evaluated: u64,
+    pub flagged_sessions: u64,
+    pub flagged_users: u64,
+    pub blocked_sessions: u64,
+    pub blocked_users: u64,
+    pub by_rule: Vec<RulePreview>,
+    pub examples: Vec<PreviewExample>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnticheatPreviewResponse {
+    pub lookback_days: u32,
+    /// true, если в окне больше сессий, чем обрабатывает предпросмотр
+    pub truncated: bool,
+    #[serde(flatten)]
+    pub outcome: PreviewOutcome,
+    /// Инциденты, реально созданные за то же окно, по типам
+    pub recorded_incidents: BTreeMap<String, u64>,
+}
+
+fn default_inactivity_seconds() -> u32 {
+    120
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IncidentTimelineQuery {
+    /// Паузы длиннее этого порога попадают в хронологию как `inactivity`
+    #[serde(default = "default_inactivity_seconds")]
+    pub inactivity_seconds: u32,
+}
+
+/// Что произошло в сессии
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TimelineEventKind {
+    SessionStarted,
+    Answer {
+        task_id: String,
+        correct: bool,
+        score: i32,
+        /// Сумма баллов за ответы сессии после этого ответа
+        total_score: i32,
+    },
+    Hint {
+        task_id: String,
+        cost: i32,
+    },
+    Signal {
+        signal_type: SignalType,
+        payload: serde_json::Value,
+    },
+    Inactivity {
+        duration_seconds: i64,
+    },
+    SessionEnded {
+        status: SessionStatus,
+    },
+}
+
+/// Событие хронологии; `offset_ms` отсчитывается от начала сессии
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TimelineEvent {
+    pub timestamp: DateTime<Utc>,
+    pub offset_ms: i64,
+    #[serde(flatten)]
+    pub kind: TimelineEventKind,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IncidentTimelineResponse {
+    pub incident_id: String,
+    pub session_id: String,
+    pub user_id: String,
+    /// Начало сессии; если сессия уже не найдена - время первого события
+    pub started_at: Option<DateTime<Utc>>,
+    pub inactivity_seconds: u32,
+    /// true, если событий больше, чем отдаёт хронология
+    pub truncated: bool,
+    pub events: Vec<TimelineEvent>,
+}
@@ -0,0 +1,210 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+use super::user::bson_datetime_as_chrono;
+
+/// Домашнее задание группы: шаблоны со сроком сдачи (коллекция "assignments")
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Assignment {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+
+    #[serde(rename = "groupId")]
+    pub group_id: ObjectId,
+
+    /// Шаблоны, по каждому из которых нужна завершённая сессия
+    #[serde(rename = "templateIds")]
+    pub template_ids: Vec<ObjectId>,
+
+    #[serde(rename = "dueAt", with = "bson_datetime_as_chrono")]
+    pub due_at: DateTime<Utc>,
+
+    #[serde(default)]
+    pub instructions: String,
+
+    /// Разрешены ли сессии после срока (выполнение помечается как просроченное)
+    #[serde(rename = "allowLate", default)]
+    pub allow_late: bool,
+
+    /// Минимальный итоговый счёт сессии, при котором шаблон засчитывается
+    #[serde(rename = "minScore")]
+    pub min_score: i32,
+
+    /// ID учителя или администратора, создавшего задание
+    #[serde(rename = "createdBy")]
+    pub created_by: String,
+
+    #[serde(rename = "createdAt", with = "bson_datetime_as_chrono")]
+    pub created_at: DateTime<Utc>,
+
+    #[serde(rename = "updatedAt", with = "bson_datetime_as_chrono")]
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Выполненный пункт задания (коллекция "assignment_completions").
+/// `_id` = `{assignment_id}:{user_id}:{template_id}`, засчитывается первая проходная сессия
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssignmentCompletion {
+    #[serde(rename = "_id")]
+    pub id: String,
+
+    #[serde(rename = "assignmentId")]
+    pub assignment_id: ObjectId,
+
+    #[serde(rename = "userId")]
+    pub user_id: String,
+
+    #[serde(rename = "templateId")]
+    pub template_id: ObjectId,
+
+    #[serde(rename = "sessionId")]
+    pub session_id: String,
+
+    pub score: i32,
+
+    /// Сессия завершена после срока сдачи
+    #[serde(default)]
+    pub late: bool,
+
+    #[serde(rename = "completedAt", with = "bson_datetime_as_chrono")]
+    pub completed_at: DateTime<Utc>,
+}
+
+impl AssignmentCompletion {
+    pub fn key(assignment_id: &ObjectId, user_id: &str, template_id: &ObjectId) -> String {
+        format!(
+            "{}:{}:{}",
+            assignment_id.to_hex(),
+            user_id,
+            template_id.to_hex()
+        )
+    }
+}
+
+/// Request to create an assignment for a group
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateAssignmentRequest {
+    #[validate(length(
+        min = 1,
+        max = 50,
+        message = "Assignment must contain 1 to 50 templates"
+    ))]
+    pub template_ids: Vec<String>,
+
+    pub due_at: DateTime<Utc>,
+
+    #[serde(default)]
+    #[validate(length(max = 2000, message = "Instructions must be at most 2000 characters"))]
+    pub instructions: String,
+
+    #[serde(default)]
+    pub allow_late: bool,
+
+    /// По умолчанию засчитывается любая сессия с положительным счётом
+    #[validate(range(
+        min = 1,
+        max = 10000,
+        message = "min_score must be between 1 and 10000"
+    ))]
+    pub min_score: Option<i32>,
+}
+
+impl CreateAssignmentRequest {
+    pub const DEFAULT_MIN_SCORE: i32 = 1;
+}
+
+/// Продление срока сдачи (срок можно только отодвинуть)
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ExtendAssignmentRequest {
+    pub due_at: DateTime<Utc>,
+    pub allow_late: Option<bool>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AssignmentResponse {
+    pub id: String,
+    pub group_id: String,
+    pub template_ids: Vec<String>,
+    pub due_at: DateTime<Utc>,
+    pub instructions: String,
+    pub allow_late: bool,
+    pub min_score: i32,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<Assignment> for AssignmentResponse {
+    fn from(assignment: Assignment) -> Self {
+        Self {
+            id: assignment.id.to_hex(),
+            group_id: assignment.group_id.to_hex(),
+            template_ids: assignment
+                .template_ids
+                .iter()
+                .map(|id| id.to_hex())
+                .collect(),
+            due_at: assignment.due_at,
+            instructions: assignment.instructions,
+            allow_late: assignment.allow_late,
+            min_score: assignment.min_score,
+            created_by: assignment.created_by,
+            created_at: assignment.created_at,
+            updated_at: assignment.updated_at,
+        }
+    }
+}
+
+/// Состояние задания для ученика
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AssignmentStatus {
+    Pending,
+    Completed,
+    /// Срок прошёл, а выполнены не все шаблоны
+    Overdue,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AssignmentItemStatus {
+    pub template_id: String,
+    pub completed: bool,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub late: bool,
+}
+
+/// Задание в списке ученика (GET /api/v1/assignments)
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StudentAssignment {
+    #[serde(flatten)]
+    pub assignment: AssignmentResponse,
+    pub status: AssignmentStatus,
+    pub items: Vec<AssignmentItemStatus>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StudentAssignmentsResponse {
+    pub assignments: Vec<StudentAssignment>,
+}
+
+/// Выполнение задания одним учеником группы
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StudentAssignmentProgress {
+    pub student_id: String,
+    pub name: String,
+    pub status: AssignmentStatus,
+    pub completed_items: usize,
+    pub late_items: usize,
+    pub last_completed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AssignmentProgressResponse {
+    pub assignment: AssignmentResponse,
+    pub total_items: usize,
+    pub completed_students: usize,
+    pub students: Vec<StudentAssignmentProgress>,
+}
@@ -0,0 +1,87 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+
+use super::user::{bson_datetime_as_chrono, bson_datetime_as_chrono_option};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditArchiveStatus {
+    /// Файл загружен, записи ещё удаляются из `audit_log`
+    Uploaded,
+    /// Записи файла удалены из `audit_log`
+    Completed,
+}
+
+/// Файл архива журнала аудита в MongoDB "audit_archives": записи за одни сутки (UTC)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditArchiveFile {
+    /// Ключ в объектном хранилище, `audit/YYYY/MM/DD.ndjson.gz`
+    #[serde(rename = "_id")]
+    pub key: String,
+    /// Сутки в формате `YYYY-MM-DD`
+    pub day: String,
+    /// Номер файла за сутки; больше 1, только если записи за эти сутки появились после архивации
+    pub part: u32,
+    pub status: AuditArchiveStatus,
+    pub entry_count: u64,
+    pub size_bytes: u64,
+    pub run_id: ObjectId,
+    #[serde(with = "bson_datetime_as_chrono")]
+    pub archived_at: DateTime<Utc>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "bson_datetime_as_chrono_option"
+    )]
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// Итог одного запуска архивации журнала ("audit_archive_runs")
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditArchiveRun {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    #[serde(with = "bson_datetime_as_chrono")]
+    pub started_at: DateTime<Utc>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "bson_datetime_as_chrono_option"
+    )]
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Архивируются записи, созданные раньше этой границы (полночь UTC)
+    #[serde(with = "bson_datetime_as_chrono")]
+    pub cutoff: DateTime<Utc>,
+    pub days_archived: u32,
+    pub files_written: u64,
+    pub entries_archived: u64,
+    pub entries_deleted: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Результат архивации одних суток
+#[derive(Debug, Clone, Default)]
+pub struct AuditDayArchive {
+    pub files_written: u64,
+    pub entries_archived: u64,
+    pub entries_deleted: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditArchiveListQuery {
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditArchiveResponse {
+    pub key: String,
+    pub day: String,
+    pub part: u32,
+    pub status: AuditArchiveStatus,
+    pub entry_count: u64,
+    pub size_bytes: u64,
+    pub archived_at: DateTime<Utc>,
+    pub download_url: Option<String>,
+}
@@ -1,6 +1,7 @@
 use chrono::{DateTime, Utc};
-use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{oid::ObjectId, Bson, Document};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
 /// Audit log entry for authentication and authorization events
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,7 +39,7 @@ pub struct AuditLog {
 }
 
 /// Types of audit events
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum AuditEventType {
     Login,
@@ -59,11 +60,46 @@ pub enum AuditEventType {
     DeleteUser,
     BlockUser,
     UnblockUser,
+    /// Admin revoked all sessions and issued access tokens of a user
+    ForceLogout,
+    /// Временная блокировка снята по истечении срока
+    AutoUnblockUser,
+    /// Персональные данные удалены по запросу, ответы переписаны на псевдоним
+    PurgeUser,
 
     // Admin actions для управления группами
     CreateGroup,
     UpdateGroup,
     DeleteGroup,
+    AddGroupMember,
+    RemoveGroupMember,
+    ArchiveGroup,
+    ReassignCurator,
+    CreateGroupInvite,
+    RevokeGroupInvite,
+    JoinGroup,
+
+    // Согласия на обработку данных учеников
+    RecordConsent,
+    RevokeConsent,
+
+    // Архив сессий
+    RehydrateSession,
+
+    // Резервные копии
+    RestoreBackup,
+
+    // Лимиты запросов
+    InspectRateLimit,
+    ResetRateLimit,
+
+    // Разрешения ролей
+    UpdateRolePermissions,
+
+    // Разбор инцидентов античита
+    UpdateIncident,
+    AssignIncident,
+    CommentIncident,
 }
 
 impl AuditEventType {
@@ -85,23 +121,152 @@ impl AuditEventType {
             AuditEventType::DeleteUser => "delete_user",
             AuditEventType::BlockUser => "block_user",
             AuditEventType::UnblockUser => "unblock_user",
+            AuditEventType::ForceLogout => "force_logout",
+            AuditEventType::AutoUnblockUser => "auto_unblock_user",
+            AuditEventType::PurgeUser => "purge_user",
             AuditEventType::CreateGroup => "create_group",
             AuditEventType::UpdateGroup => "update_group",
             AuditEventType::DeleteGroup => "delete_group",
+            AuditEventType::AddGroupMember => "add_group_member",
+            AuditEventType::RemoveGroupMember => "remove_group_member",
+            AuditEventType::ArchiveGroup => "archive_group",
+            AuditEventType::ReassignCurator => "reassign_curator",
+            AuditEventType::CreateGroupInvite => "create_group_invite",
+            AuditEventType::RevokeGroupInvite => "revoke_group_invite",
+            AuditEventType::JoinGroup => "join_group",
+            AuditEventType::RecordConsent => "record_consent",
+            AuditEventType::RevokeConsent => "revoke_consent",
+            AuditEventType::RehydrateSession => "rehydrate_session",
+            AuditEventType::RestoreBackup => "restore_backup",
+            AuditEventType::InspectRateLimit => "inspect_rate_limit",
+            AuditEventType::ResetRateLimit => "reset_rate_limit",
+            AuditEventType::UpdateRolePermissions => "update_role_permissions",
+            AuditEventType::UpdateIncident => "update_incident",
+            AuditEventType::AssignIncident => "assign_incident",
+            AuditEventType::CommentIncident => "comment_incident",
         }
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct AuditLogQuery {
     pub event_type: Option<AuditEventType>,
     pub user_id: Option<String>,
+    /// Actor of the event: `actor_id`, `user_id` or `admin_id` depending on the writer
+    pub actor_id: Option<String>,
+    /// Exact action (`template.create`) or a prefix ending with `*` (`template.*`)
+    pub action: Option<String>,
+    /// Target collection, e.g. `templates`
+    pub target: Option<String>,
+    pub target_id: Option<String>,
     pub success: Option<bool>,
     pub search: Option<String>,
     pub from: Option<DateTime<Utc>>,
     pub to: Option<DateTime<Utc>>,
     pub limit: Option<u32>,
     pub offset: Option<u32>,
+    /// Cursor from the `X-Next-Cursor` header of the previous page (instead of `offset`)
+    pub cursor: Option<String>,
+}
+
+/// Audit entry in a single shape.
+///
+/// Modules write to `audit_log` with different fields (auth events use `event_type` and
+/// `user_id`, content changes use `action`, `actor_id` and `target`), so entries are
+/// read as documents and normalized here.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AuditLogEntry {
+    pub id: Option<String>,
+    /// `event_type` for auth and admin events, `action` for content changes
+    pub event_type: String,
+    pub actor_id: Option<String>,
+    pub actor_role: Option<String>,
+    pub target: Option<String>,
+    pub target_id: Option<String>,
+    pub user_id: Option<String>,
+    pub email: Option<String>,
+    pub success: bool,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    /// A string for auth events, a document for content changes
+    pub details: Option<serde_json::Value>,
+    pub error_message: Option<String>,
+    pub reason: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+impl AuditLogEntry {
+    pub fn from_document(doc: &Document) -> Self {
+        let text = |field: &str| doc.get_str(field).ok().map(|s| s.to_string());
+        let first_text = |fields: &[&str]| fields.iter().find_map(|field| text(field));
+        let object_id = doc.get_object_id("_id").ok();
+
+        let created_at = ["createdAt", "created_at", "timestamp"]
+            .iter()
+            .find_map(|field| match doc.get(*field) {
+                Some(Bson::DateTime(dt)) => DateTime::from_timestamp_millis(dt.timestamp_millis()),
+                Some(Bson::String(value)) => DateTime::parse_from_rfc3339(value)
+                    .ok()
+                    .map(|dt| dt.with_timezone(&Utc)),
+                _ => None,
+            })
+            .or_else(|| {
+                object_id.and_then(|id| {
+                    DateTime::from_timestamp_millis(id.timestamp().timestamp_millis())
+                })
+            })
+            .unwrap_or_default();
+
+        let details = match doc.get("details").or_else(|| doc.get("changes")) {
+            None | Some(Bson::Null) => None,
+            Some(Bson::String(value)) => Some(serde_json::Value::String(value.clone())),
+            Some(value) => Some(value.clone().into_relaxed_extjson()),
+        };
+
+        Self {
+            id: object_id.map(|id| id.to_hex()),
+            event_type: first_text(&["event_type", "action"]).unwrap_or_default(),
+            actor_id: first_text(&["actor_id", "user_id", "admin_id"]),
+            actor_role: text("actor_role"),
+            target: first_text(&["target", "entity_type"]),
+            target_id: first_text(&["target_id", "entity_id"]),
+            user_id: text("user_id"),
+            email: text("email"),
+            success: doc.get_bool("success").unwrap_or(true),
+            ip: text("ip"),
+            user_agent: text("user_agent"),
+            details,
+            error_message: text("error_message"),
+            reason: text("reason"),
+            created_at,
+        }
+    }
+}
+
+/// Period of the actor drill-down; defaults to the last 30 days
+#[derive(Debug, Deserialize)]
+pub struct AuditActorSummaryQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditActionCount {
+    pub action: String,
+    pub count: u64,
+    pub last_at: Option<DateTime<Utc>>,
+}
+
+/// Per-action counts of one actor, most frequent first
+#[derive(Debug, Serialize)]
+pub struct AuditActorSummary {
+    pub actor_id: String,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub total: u64,
+    pub actions: Vec<AuditActionCount>,
 }
 
 // Serde converter for chrono::DateTime <-> mongodb::bson::DateTime
@@ -2,13 +2,32 @@ use chrono::{DateTime, Utc};
 use mongodb::bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use super::user::bson_datetime_as_chrono_option;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BackupStatus {
     Pending,
+    /// Коллекции выгружаются в объектное хранилище
+    Running,
     Completed,
     Failed,
 }
 
+/// Прогресс выгрузки одной коллекции
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BackupCollectionProgress {
+    pub name: String,
+    /// Число документов на момент создания задачи
+    pub total_documents: u64,
+    pub documents_written: u64,
+    /// Размер загруженных частей (gzip)
+    pub bytes_written: u64,
+    /// Ключи частей `backups/{id}/{collection}/part-NNNNN.ndjson.gz`
+    #[serde(default)]
+    pub parts: Vec<String>,
+    pub completed: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BackupRecord {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
@@ -20,11 +39,35 @@ pub struct BackupRecord {
     pub error: Option<String>,
     #[serde(with = "bson_datetime_as_chrono")]
     pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<String>,
+    #[serde(default)]
+    pub total_collections: u32,
+    #[serde(default)]
+    pub collections: Vec<BackupCollectionProgress>,
+    #[serde(default)]
+    pub total_documents: u64,
+    #[serde(default)]
+    pub documents_written: u64,
+    #[serde(default)]
+    pub bytes_written: u64,
+    /// Доля выгруженных документов, 0-100
+    #[serde(default)]
+    pub progress_percent: f64,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "bson_datetime_as_chrono_option"
+    )]
+    pub finished_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BackupCreateRequest {
     pub label: Option<String>,
+    /// Коллекции для выгрузки; без списка выгружается вся база
+    #[serde(default)]
+    pub collections: Option<Vec<String>>,
 }
 
 #[derive(Debug, Serialize)]
@@ -32,6 +75,38 @@ pub struct BackupCreateResponse {
     pub id: String,
     pub status: BackupStatus,
     pub storage_path: Option<String>,
+    pub collections: Vec<String>,
+}
+
+#[derive(Debug)]
+pub enum BackupCreateOutcome {
+    Created(BackupCreateResponse),
+    /// В запросе есть коллекции, которых нет в базе
+    UnknownCollections(Vec<String>),
+}
+
+/// Тело `POST /admin/backups/{id}/restore`; без тела восстанавливаются все коллекции бэкапа
+#[derive(Debug, Default, Deserialize)]
+pub struct BackupRestoreRequest {
+    /// Только посчитать изменения, ничего не записывая
+    #[serde(default)]
+    pub dry_run: bool,
+    #[serde(default)]
+    pub collections: Option<Vec<String>>,
+}
+
+/// Что восстановление делает (или сделало бы) с одной коллекцией.
+///
+/// Документы сопоставляются по `_id`; документы, созданные после бэкапа, не удаляются.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BackupRestoreCollectionPlan {
+    pub name: String,
+    pub documents_in_backup: u64,
+    /// Документов с таким `_id` в коллекции нет
+    pub to_insert: u64,
+    /// Конфликты: документ с таким `_id` есть, но отличается от копии в бэкапе
+    pub to_overwrite: u64,
+    pub unchanged: u64,
 }
 
 #[derive(Debug, Serialize)]
@@ -40,6 +115,23 @@ pub struct BackupRestoreResponse {
     pub status: BackupStatus,
     pub storage_path: Option<String>,
     pub message: String,
+    pub dry_run: bool,
+    pub collections: Vec<BackupRestoreCollectionPlan>,
+    /// Префикс снимка затронутых коллекций до восстановления, `rollback/{restore_id}/`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rollback_key: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum BackupRestoreOutcome {
+    Restored(BackupRestoreResponse),
+    NotFound,
+    /// Бэкап ещё не завершён или завершился ошибкой
+    NotRestorable(BackupStatus),
+    /// Коллекций нет в этом бэкапе
+    UnknownCollections(Vec<String>),
+    /// Уже идёт другое восстановление
+    InProgress,
 }
 
 pub(super) mod bson_datetime_as_chrono {
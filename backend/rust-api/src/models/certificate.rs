@@ -0,0 +1,22 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// Сертификат за уровень, пройденный не ниже `min_pass_percent`
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct Certificate {
+    pub level_id: String,
+    pub level_name: String,
+    pub topic_id: String,
+    pub topic_name: String,
+    pub percentage: f64,
+    pub min_pass_percent: i32,
+    /// Последнее обновление прогресса по уровню
+    pub earned_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CertificatesResponse {
+    /// От свежих к давним
+    pub certificates: Vec<Certificate>,
+}
@@ -0,0 +1,204 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use super::user::{bson_datetime_as_chrono, bson_datetime_as_chrono_option};
+
+/// Кто дал согласие на обработку данных ученика
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConsentGrantor {
+    /// Родитель или законный представитель
+    Guardian,
+    /// Администратор школы (например, на основании бумажного согласия)
+    Admin,
+}
+
+/// Consent record stored in MongoDB "consent_records" collection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsentRecord {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+
+    /// ID ученика (ref: users)
+    pub user_id: String,
+
+    pub consent_type: String,
+
+    pub granted_by: ConsentGrantor,
+
+    /// Имя родителя/представителя, если согласие дал он
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guardian_name: Option<String>,
+
+    /// ID администратора, внёсшего запись, или `guardian_link:<id>` для записи по ссылке родителя
+    pub recorded_by: String,
+
+    /// Версия документа, на которую получено согласие
+    pub document_version: String,
+
+    #[serde(rename = "grantedAt", with = "bson_datetime_as_chrono")]
+    pub granted_at: DateTime<Utc>,
+
+    #[serde(
+        rename = "revokedAt",
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "bson_datetime_as_chrono_option"
+    )]
+    pub revoked_at: Option<DateTime<Utc>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revoked_by: Option<String>,
+}
+
+/// Request для записи согласия (Admin, от имени родителя или школы)
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct RecordConsentRequest {
+    #[validate(length(
+        min = 1,
+        max = 64,
+        message = "Consent type must be between 1 and 64 characters"
+    ))]
+    pub consent_type: String,
+
+    pub granted_by: ConsentGrantor,
+
+    #[validate(length(max = 200, message = "Guardian name is too long"))]
+    pub guardian_name: Option<String>,
+
+    /// Версия документа (по умолчанию текущая версия из настроек)
+    pub document_version: Option<String>,
+}
+
+/// Ссылка, по которой родитель сам даёт и отзывает согласия ученика.
+/// Хранится только SHA-256 токена - сам токен выдаётся один раз при создании
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuardianConsentLink {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+
+    /// ID ученика (ref: users)
+    pub user_id: String,
+
+    #[serde(rename = "tokenHash")]
+    pub token_hash: String,
+
+    #[serde(rename = "createdBy")]
+    pub created_by: String,
+
+    #[serde(rename = "expiresAt", with = "bson_datetime_as_chrono")]
+    pub expires_at: DateTime<Utc>,
+
+    #[serde(rename = "createdAt", with = "bson_datetime_as_chrono")]
+    pub created_at: DateTime<Utc>,
+}
+
+impl GuardianConsentLink {
+    /// Кто внёс запись, в `recorded_by` / `revoked_by`
+    pub fn actor(&self) -> String {
+        format!(
+            "guardian_link:{}",
+            self.id.map(|id| id.to_hex()).unwrap_or_default()
+        )
+    }
+}
+
+/// Request для ссылки родителя (Admin)
+#[derive(Debug, Clone, Default, Deserialize, Validate)]
+pub struct CreateGuardianLinkRequest {
+    /// Срок действия в часах (по умолчанию неделя)
+    #[validate(range(
+        min = 1,
+        max = 720,
+        message = "expires_in_hours must be between 1 and 720"
+    ))]
+    pub expires_in_hours: Option<u32>,
+}
+
+impl CreateGuardianLinkRequest {
+    pub const DEFAULT_EXPIRES_IN_HOURS: u32 = 168;
+}
+
+#[derive(Debug, Serialize)]
+pub struct GuardianLinkResponse {
+    pub id: String,
+    pub user_id: String,
+    /// Токен для `/api/v1/guardian/consents/{token}`; повторно не показывается
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Request для записи согласия родителем по ссылке
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct GuardianConsentRequest {
+    #[validate(length(
+        min = 1,
+        max = 64,
+        message = "Consent type must be between 1 and 64 characters"
+    ))]
+    pub consent_type: String,
+
+    #[validate(length(min = 1, max = 200, message = "Guardian name is required"))]
+    pub guardian_name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConsentResponse {
+    pub id: String,
+    pub user_id: String,
+    pub consent_type: String,
+    pub granted_by: ConsentGrantor,
+    pub guardian_name: Option<String>,
+    pub recorded_by: String,
+    pub document_version: String,
+    pub granted_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub revoked_by: Option<String>,
+}
+
+impl From<ConsentRecord> for ConsentResponse {
+    fn from(record: ConsentRecord) -> Self {
+        ConsentResponse {
+            id: record.id.map(|id| id.to_hex()).unwrap_or_default(),
+            user_id: record.user_id,
+            consent_type: record.consent_type,
+            granted_by: record.granted_by,
+            guardian_name: record.guardian_name,
+            recorded_by: record.recorded_by,
+            document_version: record.document_version,
+            granted_at: record.granted_at,
+            revoked_at: record.revoked_at,
+            revoked_by: record.revoked_by,
+        }
+    }
+}
+
+/// История согласий ученика и список недостающих
+#[derive(Debug, Serialize)]
+pub struct UserConsentStatus {
+    pub user_id: String,
+    pub consents: Vec<ConsentResponse>,
+    pub missing: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConsentTypeCoverage {
+    pub consent_type: String,
+    pub granted: usize,
+}
+
+/// Покрытие согласиями учеников группы
+#[derive(Debug, Serialize)]
+pub struct ConsentCoverageReport {
+    pub group_id: String,
+    pub document_version: String,
+    pub required_consents: Vec<String>,
+    pub total_students: usize,
+    pub fully_covered: usize,
+    pub coverage_percent: f64,
+    pub by_consent: Vec<ConsentTypeCoverage>,
+    /// ID учеников, которым не хватает хотя бы одного согласия
+    pub missing_students: Vec<String>,
+}
@@ -1,9 +1,13 @@
 use chrono::{LocalResult, TimeZone, Utc};
-use mongodb::bson::{doc, oid::ObjectId, Document};
+use mongodb::bson::{doc, oid::ObjectId, Bson, Document};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, str::FromStr};
+use std::{
+    collections::{BTreeMap, HashMap},
+    str::FromStr,
+};
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum TemplateStatus {
     Draft,
@@ -26,17 +30,25 @@ impl TemplateStatus {
         }
     }
 
+    /// Переходы через общие пути (PUT шаблона, массовая смена статуса).
+    /// ReviewedOnce и Ready выставляет только `approve_template` с проверкой модераторов.
     pub fn can_transition_to(&self, next: TemplateStatus) -> bool {
         matches!(
             (self, next),
             (TemplateStatus::Draft, TemplateStatus::PendingReview)
-                | (TemplateStatus::PendingReview, TemplateStatus::ReviewedOnce)
-                | (TemplateStatus::ReviewedOnce, TemplateStatus::Ready)
                 | (TemplateStatus::Ready, TemplateStatus::Published)
                 | (TemplateStatus::Published, TemplateStatus::Deprecated)
                 | (_, TemplateStatus::Draft)
         )
     }
+
+    /// Статусы, которые можно выставить массово: откат в черновик, публикация и депрекация
+    pub fn is_bulk_target(&self) -> bool {
+        matches!(
+            self,
+            TemplateStatus::Draft | TemplateStatus::Published | TemplateStatus::Deprecated
+        )
+    }
 }
 
 impl FromStr for TemplateStatus {
@@ -56,6 +68,100 @@ impl FromStr for TemplateStatus {
     }
 }
 
+/// Возрастная категория контента. Варианты упорядочены по возрастанию возраста,
+/// поэтому сравнение `content <= student` означает «контент подходит ученику».
+#[derive(
+    Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum AgeBand {
+    /// 6-10 лет (начальная школа)
+    Primary,
+    /// 11-14 лет
+    Middle,
+    /// 15 лет и старше
+    Senior,
+}
+
+impl AgeBand {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AgeBand::Primary => "primary",
+            AgeBand::Middle => "middle",
+            AgeBand::Senior => "senior",
+        }
+    }
+
+    /// Band derived from the birth year. Unknown birth year means the most restrictive band.
+    pub fn from_birth_year(birth_year: Option<i32>, current_year: i32) -> Self {
+        match birth_year.map(|year| current_year - year) {
+            Some(age) if age >= 15 => AgeBand::Senior,
+            Some(age) if age >= 11 => AgeBand::Middle,
+            _ => AgeBand::Primary,
+        }
+    }
+
+    /// Effective band of a template: the stricter of the template's and its topic's rating.
+    /// Unrated content is available to everyone.
+    pub fn effective(template: Option<AgeBand>, topic: Option<AgeBand>) -> Option<AgeBand> {
+        template.max(topic)
+    }
+
+    pub fn allows(&self, content: Option<AgeBand>) -> bool {
+        content.is_none_or(|band| band <= *self)
+    }
+}
+
+impl FromStr for AgeBand {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "primary" => Ok(AgeBand::Primary),
+            "middle" => Ok(AgeBand::Middle),
+            "senior" => Ok(AgeBand::Senior),
+            _ => Err(format!("Invalid age band: {}", value)),
+        }
+    }
+}
+
+/// Язык шаблонов по умолчанию: к нему откатывается выдача, если нужного варианта нет
+pub const DEFAULT_LOCALE: &str = "ru";
+
+fn default_locale() -> String {
+    DEFAULT_LOCALE.to_string()
+}
+
+/// Код языка: две-три строчные латинские буквы и необязательный регион (`en`, `en-GB`)
+pub fn is_valid_locale(locale: &str) -> bool {
+    let (language, region) = match locale.split_once('-') {
+        Some((language, region)) => (language, Some(region)),
+        None => (locale, None),
+    };
+    (2..=3).contains(&language.len())
+        && language.chars().all(|c| c.is_ascii_lowercase())
+        && region.is_none_or(|region| {
+            region.len() == 2 && region.chars().all(|c| c.is_ascii_uppercase())
+        })
+}
+
+/// Перевод шаблона: заданные поля заменяют основные для своего языка
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct TemplateTranslation {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Document>,
+}
+
+/// Содержимое шаблона на языке ученика
+#[derive(Debug, PartialEq)]
+pub struct LocalizedTemplate<'a> {
+    pub locale: &'a str,
+    pub content: &'a str,
+    pub metadata: &'a Document,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TemplateDocument {
     #[serde(rename = "_id")]
@@ -72,6 +178,14 @@ pub struct TemplateDocument {
     pub content: String,
     #[serde(default)]
     pub difficulty: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub age_band: Option<AgeBand>,
+    /// Язык основного содержимого; slug уникален в пределах уровня и языка
+    #[serde(default = "default_locale")]
+    pub locale: String,
+    /// Переводы по коду языка
+    #[serde(default)]
+    pub translations: BTreeMap<String, TemplateTranslation>,
     pub status: TemplateStatus,
     #[serde(default)]
     pub version: i32,
@@ -79,30 +193,104 @@ pub struct TemplateDocument {
     pub source_refs: Vec<String>,
     #[serde(default)]
     pub pii_flags: Vec<String>,
+    /// Совпадения со стоп-листом уровня `warn`: `поле:термин`
+    #[serde(default)]
+    pub blacklist_warnings: Vec<String>,
+    /// Находки линтера на момент последнего сохранения
+    #[serde(default)]
+    pub lint_findings: Vec<LintFinding>,
     #[serde(default)]
     pub reviewers: Vec<String>,
+    /// Модератор, назначенный на текущий круг ревью
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assigned_reviewer: Option<String>,
     #[serde(default)]
     pub created_by: Option<String>,
     #[serde(default)]
     pub published_at: Option<mongodb::bson::DateTime>,
+    /// Время последней смены статуса; у шаблонов, созданных раньше, его нет
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status_changed_at: Option<mongodb::bson::DateTime>,
+    /// Архивный шаблон скрыт из списков, статус при этом не меняется
+    #[serde(default)]
+    pub archived: bool,
     #[serde(rename = "createdAt", alias = "created_at")]
     pub created_at: mongodb::bson::DateTime,
     #[serde(rename = "updatedAt", alias = "updated_at")]
     pub updated_at: mongodb::bson::DateTime,
 }
 
-#[derive(Debug, Serialize)]
+impl TemplateDocument {
+    /// Вариант для языка: перевод (поля без перевода берутся из основного), иначе основной
+    pub fn localized(&self, locale: &str) -> LocalizedTemplate<'_> {
+        match self.translations.get_key_value(locale) {
+            Some((locale, translation)) if locale != &self.locale => LocalizedTemplate {
+                locale,
+                content: translation.content.as_deref().unwrap_or(&self.content),
+                metadata: translation.metadata.as_ref().unwrap_or(&self.metadata),
+            },
+            _ => LocalizedTemplate {
+                locale: &self.locale,
+                content: &self.content,
+                metadata: &self.metadata,
+            },
+        }
+    }
+}
+
+/// Один шаблон на пару (уровень, slug): на языке ученика, иначе на языке по умолчанию.
+/// Порядок - по первому шаблону каждой пары
+pub fn pick_locale_variants(
+    templates: Vec<TemplateDocument>,
+    locale: &str,
+) -> Vec<TemplateDocument> {
+    fn rank(template: &TemplateDocument, locale: &str) -> u8 {
+        if template.locale == locale {
+            0
+        } else if template.locale == DEFAULT_LOCALE {
+            1
+        } else {
+            2
+        }
+    }
+
+    let mut picked: Vec<TemplateDocument> = Vec::with_capacity(templates.len());
+    let mut positions: HashMap<(ObjectId, String), usize> = HashMap::new();
+    for template in templates {
+        let key = (template.level_id, template.slug.clone());
+        match positions.get(&key) {
+            Some(&position) => {
+                if rank(&template, locale) < rank(&picked[position], locale) {
+                    picked[position] = template;
+                }
+            }
+            None => {
+                positions.insert(key, picked.len());
+                picked.push(template);
+            }
+        }
+    }
+    picked
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct TemplateSummary {
     pub id: String,
     pub slug: String,
     pub status: TemplateStatus,
     pub version: i32,
     pub difficulty: Option<String>,
+    pub age_band: Option<AgeBand>,
+    pub locale: String,
     pub level: Option<LevelSummary>,
     pub topic: Option<TopicSummary>,
     pub pii_flags: Vec<String>,
+    pub blacklist_warnings: Vec<String>,
+    pub lint_findings: Vec<LintFinding>,
     pub source_refs: Vec<String>,
     pub reviewers: Vec<String>,
+    pub assigned_reviewer: Option<String>,
+    pub archived: bool,
     #[serde(rename = "updatedAt")]
     pub updated_at: String,
 }
@@ -125,11 +313,17 @@ impl TemplateSummary {
             status: doc.status,
             version: doc.version,
             difficulty: doc.difficulty.clone(),
+            age_band: doc.age_band,
+            locale: doc.locale.clone(),
             level,
             topic,
             pii_flags: doc.pii_flags.clone(),
+            blacklist_warnings: doc.blacklist_warnings.clone(),
+            lint_findings: doc.lint_findings.clone(),
             source_refs: doc.source_refs.clone(),
             reviewers: doc.reviewers.clone(),
+            assigned_reviewer: doc.assigned_reviewer.clone(),
+            archived: doc.archived,
             updated_at: bson_to_iso(&doc.updated_at),
         }
     }
@@ -142,19 +336,27 @@ pub struct TemplateDetail {
     pub status: TemplateStatus,
     pub version: i32,
     pub difficulty: Option<String>,
+    pub age_band: Option<AgeBand>,
+    pub locale: String,
+    pub translations: BTreeMap<String, TemplateTranslation>,
     pub params: Document,
     pub metadata: Document,
     pub content: String,
     pub rule_ids: Vec<String>,
     pub source_refs: Vec<String>,
     pub pii_flags: Vec<String>,
+    pub blacklist_warnings: Vec<String>,
+    pub lint_findings: Vec<LintFinding>,
     pub level: Option<LevelSummary>,
     pub topic: Option<TopicSummary>,
     pub created_at: String,
     pub updated_at: String,
     pub reviewers: Vec<String>,
+    pub assigned_reviewer: Option<String>,
     pub created_by: Option<String>,
     pub published_at: Option<String>,
+    pub status_changed_at: Option<String>,
+    pub archived: bool,
 }
 
 impl TemplateDetail {
@@ -175,6 +377,9 @@ impl TemplateDetail {
             status: doc.status,
             version: doc.version,
             difficulty: doc.difficulty.clone(),
+            age_band: doc.age_band,
+            locale: doc.locale.clone(),
+            translations: doc.translations.clone(),
             params: doc.params.clone(),
             metadata: doc.metadata.clone(),
             content: doc.content.clone(),
@@ -185,13 +390,18 @@ impl TemplateDetail {
                 .collect::<Vec<_>>(),
             source_refs: doc.source_refs.clone(),
             pii_flags: doc.pii_flags.clone(),
+            blacklist_warnings: doc.blacklist_warnings.clone(),
+            lint_findings: doc.lint_findings.clone(),
             level,
             topic,
             created_at: bson_to_iso(&doc.created_at),
             updated_at: bson_to_iso(&doc.updated_at),
             reviewers: doc.reviewers.clone(),
+            assigned_reviewer: doc.assigned_reviewer.clone(),
             created_by: doc.created_by.clone(),
             published_at: doc.published_at.as_ref().map(bson_to_iso),
+            status_changed_at: doc.status_changed_at.as_ref().map(bson_to_iso),
+            archived: doc.archived,
         }
     }
 }
@@ -207,7 +417,8 @@ pub struct TemplateVersionSummary {
 impl TemplateVersionSummary {
     pub fn from_record(doc: &Document) -> Self {
         let created_at = doc
-            .get_datetime("created_at")
+            .get_datetime("createdAt")
+            .or_else(|_| doc.get_datetime("created_at"))
             .map(bson_to_iso)
             .unwrap_or_else(|_| Utc::now().to_rfc3339());
         Self {
@@ -219,6 +430,91 @@ impl TemplateVersionSummary {
     }
 }
 
+/// Поля шаблона, которые сохраняются в снимке версии (`template_versions.snapshot`)
+pub const TEMPLATE_SNAPSHOT_FIELDS: [&str; 9] = [
+    "content",
+    "translations",
+    "difficulty",
+    "age_band",
+    "params",
+    "metadata",
+    "source_refs",
+    "rule_ids",
+    "level_id",
+];
+
+/// Поля, которые сохраняются в снимке опубликованной версии (`published_versions.snapshot`)
+/// и возвращаются при откате публикации: содержимое и результаты его проверок
+pub const PUBLISHED_SNAPSHOT_FIELDS: [&str; 12] = [
+    "content",
+    "translations",
+    "difficulty",
+    "age_band",
+    "params",
+    "metadata",
+    "source_refs",
+    "rule_ids",
+    "level_id",
+    "pii_flags",
+    "blacklist_warnings",
+    "lint_findings",
+];
+
+/// Изменение одного поля шаблона между версиями (кроме `content`, он идёт в diff)
+#[derive(Debug, Serialize)]
+pub struct TemplateFieldChange {
+    pub field: String,
+    pub old: Option<Bson>,
+    pub new: Option<Bson>,
+}
+
+/// Разница между версией шаблона и предыдущей
+#[derive(Debug, Serialize)]
+pub struct TemplateVersionDiff {
+    pub template_id: String,
+    pub version: i32,
+    pub previous_version: Option<i32>,
+    /// false для старых записей без снимка содержимого
+    pub available: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Построчный unified diff поля `content`
+    pub diff: Option<String>,
+    pub changed_fields: Vec<TemplateFieldChange>,
+}
+
+/// Уровень находки линтера: `error` не пускает шаблон на модерацию, черновик сохраняется
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LintSeverity {
+    Error,
+    Warning,
+}
+
+impl LintSeverity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LintSeverity::Error => "error",
+            LintSeverity::Warning => "warning",
+        }
+    }
+}
+
+/// Находка линтера шаблона: `placeholder_unknown`, `content_too_short`, ...
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct LintFinding {
+    pub code: String,
+    pub severity: LintSeverity,
+    pub message: String,
+}
+
+/// Ответ `POST /admin/templates/{id}/lint`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TemplateLintReport {
+    pub template_id: String,
+    pub findings: Vec<LintFinding>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct TemplateValidationIssue {
     pub template_id: String,
@@ -227,7 +523,7 @@ pub struct TemplateValidationIssue {
     pub severity: String,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum TopicStatus {
     Active,
@@ -253,13 +549,15 @@ pub struct TopicRecord {
     pub icon_url: Option<String>,
     pub sort_order: i32,
     pub status: TopicStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub age_band: Option<AgeBand>,
     #[serde(rename = "createdAt", alias = "created_at")]
     pub created_at: mongodb::bson::DateTime,
     #[serde(rename = "updatedAt", alias = "updated_at")]
     pub updated_at: mongodb::bson::DateTime,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct TopicSummary {
     pub id: String,
     pub slug: String,
@@ -268,6 +566,7 @@ pub struct TopicSummary {
     pub icon_url: Option<String>,
     pub sort_order: i32,
     pub status: TopicStatus,
+    pub age_band: Option<AgeBand>,
 }
 
 impl TopicSummary {
@@ -280,11 +579,12 @@ impl TopicSummary {
             icon_url: topic.icon_url.clone(),
             sort_order: topic.sort_order,
             status: topic.status,
+            age_band: topic.age_band,
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum LevelStatus {
     Active,
@@ -300,7 +600,7 @@ impl LevelStatus {
     }
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum LevelDifficulty {
     A1,
@@ -331,13 +631,16 @@ pub struct LevelRecord {
     pub description: String,
     pub min_pass_percent: i32,
     pub status: LevelStatus,
+    /// Уровни той же темы, которые нужно пройти до начала этого
+    #[serde(default)]
+    pub prerequisite_level_ids: Vec<ObjectId>,
     #[serde(rename = "createdAt", alias = "created_at")]
     pub created_at: mongodb::bson::DateTime,
     #[serde(rename = "updatedAt", alias = "updated_at")]
     pub updated_at: mongodb::bson::DateTime,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct LevelSummary {
     pub id: String,
     pub name: String,
@@ -345,6 +648,7 @@ pub struct LevelSummary {
     pub order: i32,
     pub status: LevelStatus,
     pub topic_id: String,
+    pub prerequisite_level_ids: Vec<String>,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -378,16 +682,158 @@ pub struct RuleRecord {
     #[serde(default)]
     pub sources: Vec<String>,
     pub status: RuleStatus,
+    /// Правило, в которое влито это (дубликат помечается `deprecated`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merged_into: Option<ObjectId>,
     #[serde(rename = "createdAt", alias = "created_at")]
     pub created_at: mongodb::bson::DateTime,
     #[serde(rename = "updatedAt", alias = "updated_at")]
     pub updated_at: mongodb::bson::DateTime,
 }
 
+#[derive(Debug, Default, Deserialize)]
+pub struct ContentTreeQuery {
+    /// Включать темы и уровни со статусом `deprecated`
+    #[serde(default)]
+    pub include_deprecated: bool,
+}
+
+/// Тема из агрегации дерева контента: `topics` → `$lookup` уровней → `$lookup` счётчиков
+#[derive(Debug, Deserialize)]
+pub struct ContentTreeTopicRow {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    pub slug: String,
+    pub name: String,
+    pub status: TopicStatus,
+    #[serde(default)]
+    pub levels: Vec<ContentTreeLevelRow>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ContentTreeLevelRow {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    pub name: String,
+    pub order: i32,
+    pub difficulty: LevelDifficulty,
+    pub status: LevelStatus,
+    #[serde(default)]
+    pub template_counts: Vec<TemplateStatusCountRow>,
+}
+
+/// Результат `$group` шаблонов уровня по статусу
+#[derive(Debug, Deserialize)]
+pub struct TemplateStatusCountRow {
+    #[serde(rename = "_id")]
+    pub status: Option<String>,
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ContentTreeTopic {
+    pub id: String,
+    pub slug: String,
+    pub name: String,
+    pub status: TopicStatus,
+    /// Сумма счётчиков по всем уровням темы
+    pub template_counts: BTreeMap<String, i64>,
+    pub template_total: i64,
+    pub levels: Vec<ContentTreeLevel>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ContentTreeLevel {
+    pub id: String,
+    pub name: String,
+    pub order: i32,
+    pub difficulty: LevelDifficulty,
+    pub status: LevelStatus,
+    /// Количество шаблонов по статусам (`draft`, `published`, ...)
+    pub template_counts: BTreeMap<String, i64>,
+    pub template_total: i64,
+}
+
+impl ContentTreeTopic {
+    pub fn from_row(row: ContentTreeTopicRow) -> Self {
+        let levels: Vec<ContentTreeLevel> = row
+            .levels
+            .into_iter()
+            .map(ContentTreeLevel::from_row)
+            .collect();
+
+        let mut template_counts = BTreeMap::new();
+        for level in &levels {
+            for (status, count) in &level.template_counts {
+                *template_counts.entry(status.clone()).or_insert(0) += count;
+            }
+        }
+
+        Self {
+            id: row.id.to_hex(),
+            slug: row.slug,
+            name: row.name,
+            status: row.status,
+            template_total: template_counts.values().sum(),
+            template_counts,
+            levels,
+        }
+    }
+}
+
+impl ContentTreeLevel {
+    fn from_row(row: ContentTreeLevelRow) -> Self {
+        let mut template_counts = BTreeMap::new();
+        for entry in row.template_counts {
+            let status = entry.status.unwrap_or_else(|| "unknown".to_string());
+            *template_counts.entry(status).or_insert(0) += entry.count;
+        }
+
+        Self {
+            id: row.id.to_hex(),
+            name: row.name,
+            order: row.order,
+            difficulty: row.difficulty,
+            status: row.status,
+            template_total: template_counts.values().sum(),
+            template_counts,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RuleMergeResult {
+    pub source_id: String,
+    pub target_id: String,
+    pub templates_updated: usize,
+}
+
+#[derive(Debug)]
+pub enum RuleMergeOutcome {
+    Merged(RuleMergeResult),
+    SameRule,
+    NotFound,
+    TargetDeprecated,
+}
+
 #[derive(Debug, Serialize)]
 pub struct RuleCoverage {
     pub rule_id: String,
     pub linked_templates: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slug: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// Фильтры покрытия правил: учитывать только шаблоны в статусе `status`
+/// и/или шаблоны уровней темы `topic_id`
+#[derive(Debug, Default, Deserialize)]
+pub struct RuleCoverageQuery {
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub topic_id: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -401,6 +847,7 @@ pub struct RuleSummary {
     pub exceptions: Vec<String>,
     pub sources: Vec<String>,
     pub status: String,
+    pub merged_into: Option<String>,
 }
 
 impl RuleSummary {
@@ -415,6 +862,7 @@ impl RuleSummary {
             exceptions: rule.exceptions.clone(),
             sources: rule.sources.clone(),
             status: rule.status.as_str().to_string(),
+            merged_into: rule.merged_into.map(|id| id.to_hex()),
         }
     }
 }
@@ -438,6 +886,8 @@ pub struct EmbeddingRebuildRequest {
     pub template_ids: Option<Vec<String>>,
 }
 
+/// Статусы задания: queued -> running -> completed | cancelled | failed.
+/// Отмена переводит queued/running в cancelling, воркер завершает её между батчами.
 #[derive(Debug, Serialize)]
 pub struct EmbeddingJobSummary {
     pub id: String,
@@ -445,7 +895,78 @@ pub struct EmbeddingJobSummary {
     pub status: String,
     pub total: i64,
     pub processed: i64,
+    /// Шаблоны, которые бэкенд не смог обработать (их можно перезапустить через retry-failed)
+    pub failed_count: i64,
     pub created_at: String,
+    pub cancel_requested_at: Option<String>,
+    pub cancelled_by: Option<String>,
+    pub finished_at: Option<String>,
+    /// Задание, неудавшиеся шаблоны которого перезапускает это задание
+    pub retry_of: Option<String>,
+}
+
+impl EmbeddingJobSummary {
+    /// Сводка по документу из `embedding_jobs`
+    pub fn from_document(doc: &Document) -> Self {
+        let optional_iso = |field: &str| doc.get_datetime(field).ok().map(bson_to_iso);
+        Self {
+            id: doc
+                .get_object_id("_id")
+                .map(|oid| oid.to_hex())
+                .unwrap_or_default(),
+            mode: doc
+                .get_str("mode")
+                .map(|s| s.to_string())
+                .unwrap_or_else(|_| "unknown".to_string()),
+            status: doc
+                .get_str("status")
+                .map(|s| s.to_string())
+                .unwrap_or_else(|_| "queued".to_string()),
+            total: doc.get_i64("total").unwrap_or(0),
+            processed: doc.get_i64("processed").unwrap_or(0),
+            failed_count: doc
+                .get_array("failed_template_ids")
+                .map(|ids| ids.len() as i64)
+                .unwrap_or(0),
+            created_at: optional_iso("createdAt").unwrap_or_else(|| Utc::now().to_rfc3339()),
+            cancel_requested_at: optional_iso("cancelRequestedAt"),
+            cancelled_by: doc.get_str("cancelledBy").ok().map(|s| s.to_string()),
+            finished_at: optional_iso("finishedAt"),
+            retry_of: doc.get_object_id("retryOf").ok().map(|oid| oid.to_hex()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EmbeddingJobListQuery {
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+/// Задания эмбеддингов, новые первыми
+#[derive(Debug, Serialize)]
+pub struct EmbeddingJobListResponse {
+    pub total: u64,
+    pub limit: u32,
+    pub offset: u32,
+    pub jobs: Vec<EmbeddingJobSummary>,
+}
+
+#[derive(Debug)]
+pub enum EmbeddingJobCancelOutcome {
+    Cancelling(Box<EmbeddingJobSummary>),
+    NotFound,
+    /// Задание уже завершено или отменяется; внутри текущий статус
+    NotActive(String),
+}
+
+#[derive(Debug)]
+pub enum EmbeddingJobRetryOutcome {
+    Enqueued(Box<EmbeddingJobSummary>),
+    NotFound,
+    /// Исходное задание ещё выполняется; внутри текущий статус
+    NotFinished(String),
+    NoFailedTemplates,
 }
 
 #[derive(Debug, Serialize)]
@@ -472,11 +993,17 @@ impl LevelSummary {
             order: level.order,
             status: level.status,
             topic_id: level.topic_id.to_hex(),
+            prerequisite_level_ids: level
+                .prerequisite_level_ids
+                .iter()
+                .map(|id| id.to_hex())
+                .collect(),
         }
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct TemplateListQuery {
     #[serde(default)]
     pub status: Option<String>,
@@ -490,8 +1017,14 @@ pub struct TemplateListQuery {
     pub version: Option<i32>,
     #[serde(default)]
     pub q: Option<String>,
+    /// Язык основного содержимого шаблона
+    #[serde(default)]
+    pub locale: Option<String>,
     #[serde(default)]
     pub limit: Option<u32>,
+    /// Показывать архивные шаблоны (по умолчанию скрыты)
+    #[serde(default)]
+    pub include_archived: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -605,6 +1138,352 @@ pub struct TemplateCreateRequest {
     pub difficulty: Option<String>,
     #[serde(default)]
     pub source_refs: Vec<String>,
+    #[serde(default)]
+    pub age_band: Option<AgeBand>,
+    /// По умолчанию [`DEFAULT_LOCALE`]
+    #[serde(default)]
+    pub locale: Option<String>,
+    #[serde(default)]
+    pub translations: BTreeMap<String, TemplateTranslationRequest>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct TemplateTranslationRequest {
+    #[serde(default)]
+    pub content: Option<String>,
+    #[serde(default)]
+    pub metadata: Option<serde_json::Value>,
+}
+
+/// Массовая смена статуса шаблонов после ревью контента
+#[derive(Debug, Deserialize)]
+pub struct TemplateBulkStatusRequest {
+    pub template_ids: Vec<String>,
+    pub status: String,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TemplateBulkStatusResult {
+    pub processed: usize,
+    pub failed: Vec<TemplateBulkStatusError>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TemplateBulkStatusError {
+    pub id: String,
+    pub error: String,
+}
+
+/// Версия формата пакета шаблонов, которую понимает импорт
+pub const TEMPLATE_BUNDLE_FORMAT: u32 = 1;
+
+/// Пакет шаблонов для переноса между окружениями: ссылки на темы, уровни
+/// и правила заданы slug'ами, определения связанных сущностей лежат рядом
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateBundle {
+    pub format: u32,
+    pub exported_at: String,
+    #[serde(default)]
+    pub topics: Vec<BundleTopic>,
+    #[serde(default)]
+    pub levels: Vec<BundleLevel>,
+    #[serde(default)]
+    pub rules: Vec<BundleRule>,
+    pub templates: Vec<BundleTemplate>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BundleTopic {
+    pub slug: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub icon_url: Option<String>,
+    #[serde(default)]
+    pub status: Option<TopicStatus>,
+    #[serde(default)]
+    pub age_band: Option<AgeBand>,
+}
+
+/// У уровня нет slug: внутри темы он определяется названием.
+/// Пререквизиты в пакет не попадают
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BundleLevel {
+    pub topic_slug: String,
+    pub name: String,
+    pub difficulty: LevelDifficulty,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub min_pass_percent: Option<i32>,
+    #[serde(default)]
+    pub order: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BundleRule {
+    pub slug: String,
+    pub name: String,
+    pub category: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub examples: Vec<String>,
+    #[serde(default)]
+    pub exceptions: Vec<String>,
+    #[serde(default)]
+    pub sources: Vec<String>,
+    #[serde(default)]
+    pub status: Option<RuleStatus>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BundleTemplate {
+    pub slug: String,
+    pub topic_slug: String,
+    pub level_name: String,
+    pub rule_slugs: Vec<String>,
+    #[serde(default)]
+    pub params: serde_json::Value,
+    #[serde(default)]
+    pub metadata: serde_json::Value,
+    #[serde(default)]
+    pub content: String,
+    #[serde(default)]
+    pub difficulty: Option<String>,
+    #[serde(default)]
+    pub source_refs: Vec<String>,
+    #[serde(default)]
+    pub age_band: Option<AgeBand>,
+    #[serde(default = "default_locale")]
+    pub locale: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub translations: BTreeMap<String, TemplateTranslationRequest>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TemplateExportQuery {
+    /// ID шаблонов через запятую
+    pub ids: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct TemplateImportOptions {
+    /// Создавать темы, уровни и правила из пакета, которых нет в базе
+    #[serde(default)]
+    pub create_missing: bool,
+    /// Обновлять шаблон с тем же slug на уровне (новая версия) вместо пропуска
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TemplateImportOutcome {
+    Created,
+    Updated,
+    /// Шаблон с таким slug уже есть, а `overwrite` не задан
+    Skipped,
+    Failed,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TemplateImportItem {
+    pub slug: String,
+    pub outcome: TemplateImportOutcome,
+    pub template_id: Option<String>,
+    pub version: Option<i32>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct TemplateImportReport {
+    pub created_topics: Vec<String>,
+    /// `тема/название` созданных уровней
+    pub created_levels: Vec<String>,
+    pub created_rules: Vec<String>,
+    pub items: Vec<TemplateImportItem>,
+}
+
+/// Как запись стоп-листа ищется в тексте шаблона
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BlacklistKind {
+    /// Подстрока без учёта регистра
+    #[default]
+    Term,
+    /// Регулярное выражение без учёта регистра, совпадение только целым словом
+    Regex,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BlacklistSeverity {
+    /// Шаблон не сохраняется
+    #[default]
+    Block,
+    /// Шаблон сохраняется, совпадение записывается в `blacklist_warnings`
+    Warn,
+}
+
+/// Запись стоп-листа содержимого (`content_blacklist`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlacklistEntry {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    pub term: String,
+    #[serde(default)]
+    pub kind: BlacklistKind,
+    #[serde(default)]
+    pub severity: BlacklistSeverity,
+    #[serde(default)]
+    pub created_by: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: mongodb::bson::DateTime,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: mongodb::bson::DateTime,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BlacklistEntryView {
+    pub id: String,
+    pub term: String,
+    pub kind: BlacklistKind,
+    pub severity: BlacklistSeverity,
+    pub created_by: Option<String>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: String,
+}
+
+impl From<&BlacklistEntry> for BlacklistEntryView {
+    fn from(entry: &BlacklistEntry) -> Self {
+        Self {
+            id: entry.id.to_hex(),
+            term: entry.term.clone(),
+            kind: entry.kind,
+            severity: entry.severity,
+            created_by: entry.created_by.clone(),
+            updated_at: bson_to_iso(&entry.updated_at),
+        }
+    }
+}
+
+/// Тело POST и PUT `/admin/content/blacklist`
+#[derive(Debug, Clone, Deserialize)]
+pub struct BlacklistEntryRequest {
+    pub term: String,
+    #[serde(default)]
+    pub kind: BlacklistKind,
+    #[serde(default)]
+    pub severity: BlacklistSeverity,
+}
+
+/// Активная ссылка на шаблон, из-за которой его нельзя архивировать
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateReference {
+    /// Тип ссылки (`assignment` или `session`)
+    pub kind: String,
+    pub id: String,
+    /// Ученик сессии; у задания не задан
+    pub user_id: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum TemplateArchiveOutcome {
+    Archived(Box<TemplateSummary>),
+    NotFound,
+    Blocked(Vec<TemplateReference>),
+}
+
+/// Сколько шаблон ждёт в очереди модерации: фильтр очереди и метка метрики её глубины
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, ToSchema)]
+pub enum ModerationQueueAge {
+    #[serde(rename = "under_1d")]
+    UnderOneDay,
+    #[serde(rename = "1d_3d")]
+    OneToThreeDays,
+    #[serde(rename = "over_3d")]
+    OverThreeDays,
+}
+
+impl ModerationQueueAge {
+    pub const ALL: [ModerationQueueAge; 3] = [
+        ModerationQueueAge::UnderOneDay,
+        ModerationQueueAge::OneToThreeDays,
+        ModerationQueueAge::OverThreeDays,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModerationQueueAge::UnderOneDay => "under_1d",
+            ModerationQueueAge::OneToThreeDays => "1d_3d",
+            ModerationQueueAge::OverThreeDays => "over_3d",
+        }
+    }
+
+    pub fn from_secs(secs: i64) -> Self {
+        const DAY: i64 = 24 * 60 * 60;
+        if secs < DAY {
+            ModerationQueueAge::UnderOneDay
+        } else if secs < 3 * DAY {
+            ModerationQueueAge::OneToThreeDays
+        } else {
+            ModerationQueueAge::OverThreeDays
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ModerationQueueQuery {
+    #[serde(default)]
+    pub topic_id: Option<String>,
+    #[serde(default)]
+    pub age: Option<ModerationQueueAge>,
+    /// ID модератора; `none` - шаблоны без назначения
+    #[serde(default)]
+    pub assigned_reviewer: Option<String>,
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+/// Шаблон в статусе `pendingreview` или `reviewedonce`
+#[derive(Debug, Serialize)]
+pub struct ModerationQueueItem {
+    #[serde(flatten)]
+    pub template: TemplateSummary,
+    /// Время входа в текущий статус (`status_changed_at`, для старых шаблонов `updatedAt`)
+    pub status_changed_at: String,
+    pub time_in_queue_secs: i64,
+    pub age: ModerationQueueAge,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReviewerAssignRequest {
+    pub reviewer_id: String,
+}
+
+#[derive(Debug)]
+pub enum ReviewerAssignOutcome {
+    Assigned(Box<TemplateSummary>),
+    NotFound,
+    NotInReview(TemplateStatus),
+    /// Модератор уже одобрил шаблон и второе одобрение дать не может
+    AlreadyApproved,
+}
+
+#[derive(Debug)]
+pub enum TemplatePublishOutcome {
+    Published(Box<TemplateSummary>),
+    NotFound,
+    /// Публикуется только шаблон в статусе `ready`
+    NotReady(TemplateStatus),
+    /// Нет записи `published_versions` для запрошенной версии
+    VersionNotFound,
+    /// Шаблон изменили между чтением и записью; публикацию надо повторить
+    Conflict,
 }
 
 #[derive(Debug, Deserialize)]
@@ -621,6 +1500,14 @@ pub struct TemplateUpdateRequest {
     pub difficulty: Option<String>,
     #[serde(default)]
     pub source_refs: Option<Vec<String>>,
+    /// `null` снимает возрастную категорию
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub age_band: Option<Option<AgeBand>>,
+    #[serde(default)]
+    pub locale: Option<String>,
+    /// Полная замена переводов
+    #[serde(default)]
+    pub translations: Option<BTreeMap<String, TemplateTranslationRequest>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -638,6 +1525,8 @@ pub struct TopicCreateRequest {
     pub icon_url: Option<String>,
     #[serde(default)]
     pub status: Option<TopicStatus>,
+    #[serde(default)]
+    pub age_band: Option<AgeBand>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -650,6 +1539,18 @@ pub struct TopicUpdateRequest {
     pub icon_url: Option<String>,
     #[serde(default)]
     pub status: Option<TopicStatus>,
+    /// `null` снимает возрастную категорию
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub age_band: Option<Option<AgeBand>>,
+}
+
+/// Поле PATCH, которое можно очистить: нет поля - `None`, `null` - `Some(None)`
+fn deserialize_nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
 }
 
 #[derive(Debug, Deserialize)]
@@ -663,6 +1564,8 @@ pub struct LevelCreateRequest {
     pub min_pass_percent: Option<i32>,
     #[serde(default)]
     pub order: Option<i32>,
+    #[serde(default)]
+    pub prerequisite_level_ids: Vec<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -677,6 +1580,9 @@ pub struct LevelUpdateRequest {
     pub min_pass_percent: Option<i32>,
     #[serde(default)]
     pub status: Option<LevelStatus>,
+    /// Полная замена списка пререквизитов; пустой список снимает ограничения
+    #[serde(default)]
+    pub prerequisite_level_ids: Option<Vec<String>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -736,10 +1642,100 @@ pub struct FeatureFlagUpdateRequest {
     pub enabled: bool,
 }
 
+#[derive(Debug, Default, Deserialize)]
+pub struct QueueStatusQuery {
+    /// Переопределяет порог зависания из `content.dead_letter_idle_secs`
+    #[serde(default)]
+    pub idle_secs: Option<u64>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct QueueStatus {
     pub length: i64,
     pub last_event: Option<ContentChangeEvent>,
+    pub consumer_groups: Vec<QueueConsumerGroup>,
+    /// Порог, после которого неподтверждённое событие попадает в `dead_letters`
+    pub dead_letter_idle_secs: u64,
+    pub dead_letters: Vec<QueueDeadLetter>,
+    /// События, которые консьюмер API так и не смог обработать
+    pub dead_letter_stream: QueueDeadLetterStream,
+}
+
+/// Стрим `content.dead_letter_stream`: длина и последние записи, новые первыми
+#[derive(Debug, Serialize)]
+pub struct QueueDeadLetterStream {
+    pub name: String,
+    pub length: i64,
+    pub entries: Vec<DeadLetteredEvent>,
+}
+
+/// Запись стрима недоставленных событий: исходное событие и причина переноса
+#[derive(Debug, Serialize)]
+pub struct DeadLetteredEvent {
+    pub id: String,
+    pub group: String,
+    pub attempts: i64,
+    /// Последняя ошибка обработчика; `None`, если консьюмер упал, не дождавшись ответа
+    pub error: Option<String>,
+    pub failed_at: Option<String>,
+    /// Событие с id из исходного стрима
+    pub event: ContentChangeEvent,
+}
+
+impl DeadLetteredEvent {
+    /// Служебные поля записи; остальные поля - копия исходного события
+    pub const SOURCE_ID: &'static str = "dlq_source_id";
+    pub const GROUP: &'static str = "dlq_group";
+    pub const ATTEMPTS: &'static str = "dlq_attempts";
+    pub const ERROR: &'static str = "dlq_error";
+    pub const FAILED_AT: &'static str = "dlq_failed_at";
+
+    pub fn from_fields(id: String, fields: &HashMap<String, String>) -> Self {
+        let source_id = fields.get(Self::SOURCE_ID).cloned().unwrap_or_default();
+        Self {
+            id,
+            group: fields.get(Self::GROUP).cloned().unwrap_or_default(),
+            attempts: fields
+                .get(Self::ATTEMPTS)
+                .and_then(|value| value.parse().ok())
+                .unwrap_or_default(),
+            error: fields.get(Self::ERROR).cloned(),
+            failed_at: fields.get(Self::FAILED_AT).cloned(),
+            event: ContentChangeEvent::from_fields(source_id, fields),
+        }
+    }
+}
+
+/// Состояние consumer group по `XINFO GROUPS` / `XINFO CONSUMERS`
+#[derive(Debug, Serialize)]
+pub struct QueueConsumerGroup {
+    pub name: String,
+    pub consumers: i64,
+    /// Консьюмеры без активности дольше порога зависания
+    pub idle_consumers: i64,
+    /// Выданные, но не подтверждённые события
+    pub pending: i64,
+    pub last_delivered_id: String,
+    /// Сколько событий ещё не выдано группе (`None`, если Redis не может посчитать)
+    pub lag: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QueueDeadLetter {
+    pub group: String,
+    pub id: String,
+    pub consumer: String,
+    pub idle_ms: i64,
+    pub delivery_count: i64,
+    /// Поля события; `None`, если запись уже удалена из стрима (XTRIM)
+    pub payload: Option<BTreeMap<String, String>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QueueClaimResult {
+    pub entry_id: String,
+    pub group: String,
+    pub consumer: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -751,6 +1747,18 @@ pub struct ContentChangeEvent {
     pub timestamp: Option<String>,
 }
 
+impl ContentChangeEvent {
+    pub fn from_fields(id: String, fields: &HashMap<String, String>) -> Self {
+        Self {
+            id,
+            template_id: fields.get("template_id").cloned().unwrap_or_default(),
+            action: fields.get("action").cloned().unwrap_or_default(),
+            version: fields.get("version").cloned(),
+            timestamp: fields.get("timestamp").cloned(),
+        }
+    }
+}
+
 fn bson_to_iso(dt: &mongodb::bson::DateTime) -> String {
     match Utc.timestamp_millis_opt(dt.timestamp_millis()) {
         LocalResult::Single(value) => value.to_rfc3339(),
@@ -762,7 +1770,9 @@ fn bson_to_iso(dt: &mongodb::bson::DateTime) -> String {
 #[cfg(test)]
 mod tests {
     use super::{
-        LevelDifficulty, LevelRecord, RuleRecord, TemplateDocument, TemplateStatus, TopicRecord,
+        is_valid_locale, pick_locale_variants, AgeBand, ContentTreeTopic, ContentTreeTopicRow,
+        EmbeddingJobSummary, LevelDifficulty, LevelRecord, LevelStatus, ModerationQueueAge,
+        RuleRecord, TemplateDocument, TemplateStatus, TemplateTranslation, TopicRecord,
         TopicStatus,
     };
     use mongodb::bson::{doc, oid::ObjectId, DateTime as BsonDateTime};
@@ -770,9 +1780,57 @@ mod tests {
     #[test]
     fn template_status_transitions() {
         assert!(TemplateStatus::Draft.can_transition_to(TemplateStatus::PendingReview));
-        assert!(TemplateStatus::PendingReview.can_transition_to(TemplateStatus::ReviewedOnce));
-        assert!(TemplateStatus::ReviewedOnce.can_transition_to(TemplateStatus::Ready));
+        assert!(TemplateStatus::Ready.can_transition_to(TemplateStatus::Published));
         assert!(!TemplateStatus::PendingReview.can_transition_to(TemplateStatus::Published));
+        // Одобрения проходят только через approve_template
+        assert!(!TemplateStatus::PendingReview.can_transition_to(TemplateStatus::ReviewedOnce));
+        assert!(!TemplateStatus::ReviewedOnce.can_transition_to(TemplateStatus::Ready));
+    }
+
+    #[test]
+    fn bulk_status_targets() {
+        assert!(TemplateStatus::Draft.is_bulk_target());
+        assert!(TemplateStatus::Published.is_bulk_target());
+        assert!(TemplateStatus::Deprecated.is_bulk_target());
+        assert!(!TemplateStatus::PendingReview.is_bulk_target());
+        assert!(!TemplateStatus::ReviewedOnce.is_bulk_target());
+        assert!(!TemplateStatus::Ready.is_bulk_target());
+    }
+
+    #[test]
+    fn age_band_from_birth_year_defaults_to_most_restrictive() {
+        assert_eq!(AgeBand::from_birth_year(None, 2026), AgeBand::Primary);
+        assert_eq!(AgeBand::from_birth_year(Some(2018), 2026), AgeBand::Primary);
+        assert_eq!(AgeBand::from_birth_year(Some(2014), 2026), AgeBand::Middle);
+        assert_eq!(AgeBand::from_birth_year(Some(2008), 2026), AgeBand::Senior);
+    }
+
+    #[test]
+    fn age_band_filters_content() {
+        let effective = AgeBand::effective(Some(AgeBand::Primary), Some(AgeBand::Middle));
+        assert_eq!(effective, Some(AgeBand::Middle));
+        assert!(!AgeBand::Primary.allows(effective));
+        assert!(AgeBand::Middle.allows(effective));
+        assert!(AgeBand::Primary.allows(None));
+        assert!("junior".parse::<AgeBand>().is_err());
+    }
+
+    #[test]
+    fn moderation_queue_age_buckets() {
+        assert_eq!(
+            ModerationQueueAge::from_secs(0),
+            ModerationQueueAge::UnderOneDay
+        );
+        assert_eq!(
+            ModerationQueueAge::from_secs(86_400),
+            ModerationQueueAge::OneToThreeDays
+        );
+        assert_eq!(
+            ModerationQueueAge::from_secs(3 * 86_400),
+            ModerationQueueAge::OverThreeDays
+        );
+        let parsed: ModerationQueueAge = serde_json::from_str("\"1d_3d\"").unwrap();
+        assert_eq!(parsed.as_str(), "1d_3d");
     }
 
     #[test]
@@ -877,6 +1935,175 @@ mod tests {
         assert_eq!(parsed.created_at, now);
         assert_eq!(parsed.updated_at, now);
     }
+
+    #[test]
+    fn content_tree_row_maps_counts_per_level_and_topic() {
+        let topic_id = ObjectId::new();
+        let (first_level, second_level) = (ObjectId::new(), ObjectId::new());
+        let row = doc! {
+            "_id": topic_id,
+            "slug": "orthography",
+            "name": "Орфография",
+            "status": "active",
+            "levels": [
+                {
+                    "_id": first_level,
+                    "name": "Уровень 1",
+                    "order": 1,
+                    "difficulty": "a1",
+                    "status": "active",
+                    "template_counts": [
+                        { "_id": "draft", "count": 2 },
+                        { "_id": "published", "count": 3_i64 },
+                    ],
+                },
+                {
+                    "_id": second_level,
+                    "name": "Уровень 2",
+                    "order": 2,
+                    "difficulty": "a2",
+                    "status": "deprecated",
+                    "template_counts": [
+                        { "_id": "published", "count": 1 },
+                        { "_id": null, "count": 1 },
+                    ],
+                },
+            ],
+        };
+
+        let row: ContentTreeTopicRow =
+            mongodb::bson::from_document(row).expect("aggregation row should deserialize");
+        let topic = ContentTreeTopic::from_row(row);
+
+        assert_eq!(topic.id, topic_id.to_hex());
+        assert_eq!(topic.levels.len(), 2);
+        assert_eq!(topic.levels[0].template_counts["draft"], 2);
+        assert_eq!(topic.levels[0].template_counts["published"], 3);
+        assert_eq!(topic.levels[0].template_total, 5);
+        assert_eq!(topic.levels[1].status, LevelStatus::Deprecated);
+        assert_eq!(topic.levels[1].template_counts["unknown"], 1);
+        assert_eq!(topic.template_counts["published"], 4);
+        assert_eq!(topic.template_total, 7);
+    }
+
+    #[test]
+    fn content_tree_row_without_levels_has_zero_total() {
+        let row: ContentTreeTopicRow = mongodb::bson::from_document(doc! {
+            "_id": ObjectId::new(),
+            "slug": "empty",
+            "name": "Пустая тема",
+            "status": "deprecated",
+        })
+        .unwrap();
+        let topic = ContentTreeTopic::from_row(row);
+        assert!(topic.levels.is_empty());
+        assert!(topic.template_counts.is_empty());
+        assert_eq!(topic.template_total, 0);
+        assert_eq!(topic.status, TopicStatus::Deprecated);
+    }
+
+    #[test]
+    fn embedding_job_summary_reads_failures_and_cancellation() {
+        let id = ObjectId::new();
+        let retry_of = ObjectId::new();
+        let requested = BsonDateTime::from_millis(1_700_000_000_000);
+        let summary = EmbeddingJobSummary::from_document(&doc! {
+            "_id": id,
+            "mode": "retry_failed",
+            "status": "cancelling",
+            "total": 3_i64,
+            "processed": 1_i64,
+            "failed_template_ids": [ObjectId::new(), ObjectId::new()],
+            "createdAt": requested,
+            "cancelRequestedAt": requested,
+            "cancelledBy": "admin-1",
+            "retryOf": retry_of,
+        });
+        assert_eq!(summary.id, id.to_hex());
+        assert_eq!(summary.failed_count, 2);
+        assert_eq!(summary.processed, 1);
+        assert_eq!(summary.cancelled_by.as_deref(), Some("admin-1"));
+        assert!(summary.cancel_requested_at.is_some());
+        assert!(summary.finished_at.is_none());
+        assert_eq!(summary.retry_of, Some(retry_of.to_hex()));
+
+        let legacy = EmbeddingJobSummary::from_document(&doc! { "_id": id, "mode": "all" });
+        assert_eq!(legacy.failed_count, 0);
+        assert!(legacy.retry_of.is_none());
+    }
+
+    fn localized_template(slug: &str, level_id: ObjectId, locale: &str) -> TemplateDocument {
+        let now = BsonDateTime::now();
+        mongodb::bson::from_document(doc! {
+            "_id": ObjectId::new(),
+            "slug": slug,
+            "level_id": level_id,
+            "status": "published",
+            "locale": locale,
+            "content": format!("{} {}", slug, locale),
+            "metadata": { "title": format!("Title {}", locale) },
+            "createdAt": now,
+            "updatedAt": now,
+        })
+        .expect("template should deserialize")
+    }
+
+    #[test]
+    fn locale_codes() {
+        assert!(is_valid_locale("ru"));
+        assert!(is_valid_locale("en-GB"));
+        assert!(is_valid_locale("tat"));
+        assert!(!is_valid_locale("EN"));
+        assert!(!is_valid_locale("en-gb"));
+        assert!(!is_valid_locale("english"));
+        assert!(!is_valid_locale(""));
+    }
+
+    #[test]
+    fn localized_falls_back_to_base_fields() {
+        let mut template = localized_template("demo", ObjectId::new(), "ru");
+        template.translations.insert(
+            "en".to_string(),
+            TemplateTranslation {
+                content: Some("English".to_string()),
+                metadata: None,
+            },
+        );
+
+        let english = template.localized("en");
+        assert_eq!(english.locale, "en");
+        assert_eq!(english.content, "English");
+        assert_eq!(english.metadata, &template.metadata);
+
+        let german = template.localized("de");
+        assert_eq!(german.locale, "ru");
+        assert_eq!(german.content, "demo ru");
+    }
+
+    #[test]
+    fn pick_locale_variants_prefers_requested_then_default() {
+        let level_id = ObjectId::new();
+        let templates = vec![
+            localized_template("a", level_id, "ru"),
+            localized_template("b", level_id, "de"),
+            localized_template("a", level_id, "en"),
+            localized_template("b", level_id, "ru"),
+            localized_template("c", level_id, "de"),
+        ];
+
+        let picked: Vec<(String, String)> = pick_locale_variants(templates, "en")
+            .into_iter()
+            .map(|template| (template.slug, template.locale))
+            .collect();
+        assert_eq!(
+            picked,
+            vec![
+                ("a".to_string(), "en".to_string()),
+                ("b".to_string(), "ru".to_string()),
+                ("c".to_string(), "de".to_string()),
+            ]
+        );
+    }
 }
 
 #[derive(Debug, Serialize)]
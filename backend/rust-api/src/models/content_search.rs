@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+/// Верхняя граница результатов на одну коллекцию
+pub const MAX_CONTENT_SEARCH_LIMIT: u32 = 50;
+pub const DEFAULT_CONTENT_SEARCH_LIMIT: u32 = 10;
+
+#[derive(Debug, Deserialize)]
+pub struct ContentSearchQuery {
+    pub q: String,
+    /// Сколько результатов вернуть из каждой коллекции
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentEntityType {
+    Template,
+    Rule,
+    Topic,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ContentSearchHit {
+    pub entity_type: ContentEntityType,
+    pub id: String,
+    pub title: String,
+    /// Поле, из которого взят фрагмент (`content`, `description`, ...)
+    pub field: String,
+    /// HTML-экранированный фрагмент, совпадение обёрнуто в `<mark>`
+    pub snippet: String,
+    /// Релевантность MongoDB text search (`textScore`)
+    pub score: f64,
+}
+
+/// Результаты по группам, внутри группы - по убыванию релевантности
+#[derive(Debug, Serialize)]
+pub struct ContentSearchResponse {
+    pub query: String,
+    pub templates: Vec<ContentSearchHit>,
+    pub rules: Vec<ContentSearchHit>,
+    pub topics: Vec<ContentSearchHit>,
+}
@@ -0,0 +1,38 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Сводка для главной страницы админки (`GET /admin/dashboard`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct DashboardSummary {
+    pub users: DashboardUserCounts,
+    pub groups: u64,
+    pub templates: TemplateStatusCounts,
+    pub open_incidents: u64,
+    /// Выгрузки в статусах `pending` и `processing`
+    pub pending_exports: u64,
+    /// Длина стрима изменений контента
+    pub queue_length: u64,
+    /// Завершённые сессии, начатые с полуночи UTC (идущие сейчас хранятся в Redis)
+    pub sessions_started_today: u64,
+    /// Когда сводка посчитана; ответ кэшируется на 30 секунд
+    pub generated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct DashboardUserCounts {
+    pub total: u64,
+    /// Входили за последние 30 дней
+    pub active: u64,
+    pub blocked: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct TemplateStatusCounts {
+    pub draft: u64,
+    pub pending_review: u64,
+    pub reviewed_once: u64,
+    pub ready: u64,
+    pub published: u64,
+    pub deprecated: u64,
+}
@@ -0,0 +1,57 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::Document;
+use serde::Serialize;
+
+/// Состояние объявленного индекса
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndexState {
+    /// Индекс уже был в базе
+    Present,
+    /// Создан при этой проверке
+    Created,
+    /// Индекса нет (проверка только на чтение)
+    Missing,
+    /// Есть индекс с теми же полями или именем, но с другими опциями
+    Conflict,
+    /// Создать не удалось, причина - в `error`
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeclaredIndexStatus {
+    pub collection: String,
+    pub name: String,
+    pub keys: Document,
+    pub unique: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttl_seconds: Option<u64>,
+    pub state: IndexState,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Индекс, который есть в базе, но не объявлен в коде
+#[derive(Debug, Clone, Serialize)]
+pub struct UndeclaredIndex {
+    pub collection: String,
+    pub name: String,
+    pub keys: Document,
+}
+
+/// Ответ `GET /admin/system/indexes`
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexReport {
+    pub checked_at: DateTime<Utc>,
+    pub indexes: Vec<DeclaredIndexStatus>,
+    pub drift: Vec<UndeclaredIndex>,
+}
+
+impl IndexReport {
+    /// Все объявленные индексы есть в базе
+    pub fn is_healthy(&self) -> bool {
+        self.indexes
+            .iter()
+            .all(|index| matches!(index.state, IndexState::Present | IndexState::Created))
+    }
+}
@@ -1,6 +1,12 @@
 use chrono::{DateTime, Utc};
 use mongodb::bson::{doc, Document};
 use serde::{Deserialize, Serialize};
+use validator::{Validate, ValidationError};
+
+use crate::models::user::UserRole;
+
+/// Роли, которые можно указывать в таргетинге флага
+const TARGETABLE_ROLES: [UserRole; 5] = UserRole::ALL;
 
 /// Scope for feature flag targeting
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -40,6 +46,18 @@ pub struct FeatureFlag {
     #[serde(default)]
     pub target_ids: Vec<String>,
 
+    /// Roles that always get the flag (e.g., "teacher")
+    #[serde(default)]
+    pub enabled_for_roles: Vec<String>,
+
+    /// User IDs that always get the flag
+    #[serde(default)]
+    pub enabled_for_user_ids: Vec<String>,
+
+    /// Percentage rollout (0-100), bucketed by a stable hash of user ID
+    #[serde(default)]
+    pub rollout_percentage: Option<i32>,
+
     /// JSON configuration for the flag (e.g., parameters)
     #[serde(default)]
     pub config: Document,
@@ -60,8 +78,17 @@ pub struct FeatureFlag {
     pub change_reason: String,
 }
 
+impl FeatureFlag {
+    /// Flag uses role/user/percentage targeting instead of the legacy scope
+    pub fn has_targeting(&self) -> bool {
+        !self.enabled_for_roles.is_empty()
+            || !self.enabled_for_user_ids.is_empty()
+            || self.rollout_percentage.is_some()
+    }
+}
+
 /// Request to create/update feature flag
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
 pub struct FeatureFlagCreateRequest {
     pub flag_key: String,
     #[serde(default)]
@@ -71,11 +98,38 @@ pub struct FeatureFlagCreateRequest {
     #[serde(default)]
     pub target_ids: Vec<String>,
     #[serde(default)]
+    #[validate(custom(function = "validate_known_roles"))]
+    pub enabled_for_roles: Vec<String>,
+    #[serde(default)]
+    pub enabled_for_user_ids: Vec<String>,
+    #[serde(default)]
+    #[validate(range(
+        min = 0,
+        max = 100,
+        message = "rollout_percentage must be between 0 and 100"
+    ))]
+    pub rollout_percentage: Option<i32>,
+    #[serde(default)]
     pub config: Document,
     #[serde(default)]
     pub change_reason: String,
 }
 
+fn validate_known_roles(roles: &[String]) -> Result<(), ValidationError> {
+    match roles.iter().find(|role| {
+        !TARGETABLE_ROLES
+            .iter()
+            .any(|known| known.as_str() == role.as_str())
+    }) {
+        Some(unknown) => {
+            let mut error = ValidationError::new("unknown_role");
+            error.message = Some(format!("Unknown role: {}", unknown).into());
+            Err(error)
+        }
+        None => Ok(()),
+    }
+}
+
 /// Response for feature flag
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FeatureFlagResponse {
@@ -84,6 +138,9 @@ pub struct FeatureFlagResponse {
     pub enabled: bool,
     pub scope: String,
     pub target_ids: Vec<String>,
+    pub enabled_for_roles: Vec<String>,
+    pub enabled_for_user_ids: Vec<String>,
+    pub rollout_percentage: Option<i32>,
     pub config: Document,
     pub version: i32,
     pub updated_at: DateTime<Utc>,
@@ -98,6 +155,9 @@ impl From<FeatureFlag> for FeatureFlagResponse {
             enabled: flag.enabled,
             scope: flag.scope,
             target_ids: flag.target_ids,
+            enabled_for_roles: flag.enabled_for_roles,
+            enabled_for_user_ids: flag.enabled_for_user_ids,
+            rollout_percentage: flag.rollout_percentage,
             config: flag.config,
             version: flag.version,
             updated_at: flag.updated_at,
@@ -106,6 +166,49 @@ impl From<FeatureFlag> for FeatureFlagResponse {
     }
 }
 
+/// Snapshot of a flag before and after one change ("feature_flag_history" collection)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeatureFlagHistoryEntry {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<mongodb::bson::oid::ObjectId>,
+
+    pub flag_key: String,
+
+    /// Flag version produced by this change
+    pub version: i64,
+
+    /// Admin who made the change
+    pub actor_id: String,
+
+    #[serde(default)]
+    pub change_reason: String,
+
+    /// Flag document before the change
+    pub old: Document,
+
+    /// Flag document after the change
+    pub new: Document,
+
+    #[serde(with = "bson_datetime_as_chrono")]
+    pub changed_at: DateTime<Utc>,
+}
+
+/// Pagination for flag history (newest first)
+#[derive(Debug, Deserialize)]
+pub struct FeatureFlagHistoryQuery {
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FeatureFlagHistoryResponse {
+    pub flag_key: String,
+    pub total: u64,
+    pub limit: u32,
+    pub offset: u32,
+    pub entries: Vec<FeatureFlagHistoryEntry>,
+}
+
 // Datetime serialization helper
 mod bson_datetime_as_chrono {
     use chrono::{DateTime, Utc};
@@ -133,20 +236,47 @@ mod bson_datetime_as_chrono {
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_flag_scope_serialization() {
-        let flag = FeatureFlagCreateRequest {
+    fn create_request() -> FeatureFlagCreateRequest {
+        FeatureFlagCreateRequest {
             flag_key: "test_flag".to_string(),
             description: "Test flag".to_string(),
             enabled: true,
             scope: "global".to_string(),
             target_ids: vec![],
+            enabled_for_roles: vec![],
+            enabled_for_user_ids: vec![],
+            rollout_percentage: None,
             config: Document::default(),
             change_reason: "Testing".to_string(),
-        };
+        }
+    }
+
+    #[test]
+    fn test_flag_scope_serialization() {
+        let flag = create_request();
 
         let json = serde_json::to_string(&flag).expect("Failed to serialize");
         assert!(json.contains("test_flag"));
         assert!(json.contains("global"));
     }
+
+    #[test]
+    fn test_targeting_validation() {
+        let mut request = create_request();
+        request.enabled_for_roles = vec!["teacher".to_string(), "content_admin".to_string()];
+        request.rollout_percentage = Some(100);
+        assert!(request.validate().is_ok());
+
+        request.rollout_percentage = Some(101);
+        let errors = request.validate().unwrap_err();
+        assert!(errors.field_errors().contains_key("rollout_percentage"));
+
+        request.rollout_percentage = Some(-1);
+        assert!(request.validate().is_err());
+
+        request.rollout_percentage = Some(0);
+        request.enabled_for_roles = vec!["superuser".to_string()];
+        let errors = request.validate().unwrap_err();
+        assert!(errors.field_errors().contains_key("enabled_for_roles"));
+    }
 }
@@ -3,7 +3,8 @@ use mongodb::bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
-use super::user::bson_datetime_as_chrono;
+use super::user::{bson_datetime_as_chrono, bson_datetime_as_chrono_option};
+use utoipa::{IntoParams, ToSchema};
 
 /// Group model stored in MongoDB "groups" collection
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,6 +26,33 @@ pub struct Group {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
 
+    /// Число учеников в группе (денормализовано из users.group_ids).
+    /// У групп, созданных до появления поля, заполняется при первом изменении состава
+    #[serde(
+        rename = "studentCount",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub student_count: Option<u32>,
+
+    /// Максимум учеников; без значения группа не ограничена
+    #[serde(
+        rename = "maxStudents",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_students: Option<u32>,
+
+    /// Когда группа архивирована. Архивная группа скрыта из списков и закрыта
+    /// для новых сессий, но её статистика и выгрузки остаются доступны
+    #[serde(
+        rename = "archivedAt",
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "bson_datetime_as_chrono_option"
+    )]
+    pub archived_at: Option<DateTime<Utc>>,
+
     #[serde(rename = "createdAt", with = "bson_datetime_as_chrono")]
     pub created_at: DateTime<Utc>,
 
@@ -33,7 +61,7 @@ pub struct Group {
 }
 
 /// Group response для API (с populated данными)
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct GroupResponse {
     pub id: String,
     pub name: String,
@@ -52,6 +80,12 @@ pub struct GroupResponse {
     /// Количество учеников в группе
     pub student_count: usize,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_students: Option<u32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub archived_at: Option<DateTime<Utc>>,
+
     pub created_at: DateTime<Utc>,
 }
 
@@ -65,13 +99,35 @@ impl From<Group> for GroupResponse {
             curator_name: None, // будет заполнено в service
             description: group.description,
             student_count: 0, // будет заполнено в service
+            max_students: group.max_students,
+            archived_at: group.archived_at,
             created_at: group.created_at,
         }
     }
 }
 
+/// Показатели активности группы для списка учителя (`?include=stats`)
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct GroupHealthStats {
+    /// Средний процент по строкам progress_summary учеников группы
+    pub avg_accuracy: Option<f64>,
+    /// Ученики, у которых прогресс обновлялся за последние 7 дней
+    pub active_students_last_7d: u32,
+    pub last_activity_at: Option<DateTime<Utc>>,
+}
+
+/// Группа в списке учителя; без `include=stats` совпадает с GroupResponse
+#[derive(Debug, Serialize)]
+pub struct TeacherGroupResponse {
+    #[serde(flatten)]
+    pub group: GroupResponse,
+
+    #[serde(flatten)]
+    pub stats: Option<GroupHealthStats>,
+}
+
 /// Request для создания группы
-#[derive(Debug, Clone, Deserialize, Validate)]
+#[derive(Debug, Clone, Deserialize, Validate, ToSchema)]
 pub struct CreateGroupRequest {
     #[validate(length(
         min = 1,
@@ -91,10 +147,17 @@ pub struct CreateGroupRequest {
     pub curator_id: Option<String>,
 
     pub description: Option<String>,
+
+    #[validate(range(
+        min = 1,
+        max = 1000,
+        message = "max_students must be between 1 and 1000"
+    ))]
+    pub max_students: Option<u32>,
 }
 
 /// Request для обновления группы
-#[derive(Debug, Clone, Deserialize, Validate)]
+#[derive(Debug, Clone, Deserialize, Validate, ToSchema)]
 pub struct UpdateGroupRequest {
     #[validate(length(
         min = 1,
@@ -114,10 +177,131 @@ pub struct UpdateGroupRequest {
     pub curator_id: Option<String>,
 
     pub description: Option<String>,
+
+    #[validate(range(
+        min = 1,
+        max = 1000,
+        message = "max_students must be between 1 and 1000"
+    ))]
+    pub max_students: Option<u32>,
+}
+
+/// Request для смены куратора; `null` снимает куратора
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct ReassignCuratorRequest {
+    pub curator_id: Option<String>,
+}
+
+/// Request для добавления ученика в группу
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct AddGroupMemberRequest {
+    pub user_id: String,
+}
+
+/// Код приглашения в группу (коллекция group_invite_codes)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupInviteCode {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+
+    #[serde(rename = "groupId")]
+    pub group_id: ObjectId,
+
+    /// Короткий код, который ученик вводит сам (уникален)
+    pub code: String,
+
+    #[serde(rename = "maxUses")]
+    pub max_uses: u32,
+
+    /// Сколько учеников уже вступило по коду
+    #[serde(default)]
+    pub uses: u32,
+
+    /// Кто вступил по коду - повторный вход не расходует использование
+    #[serde(rename = "usedBy", default)]
+    pub used_by: Vec<String>,
+
+    #[serde(rename = "createdBy")]
+    pub created_by: String,
+
+    #[serde(rename = "expiresAt", with = "bson_datetime_as_chrono")]
+    pub expires_at: DateTime<Utc>,
+
+    #[serde(
+        rename = "revokedAt",
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "bson_datetime_as_chrono_option"
+    )]
+    pub revoked_at: Option<DateTime<Utc>>,
+
+    #[serde(rename = "createdAt", with = "bson_datetime_as_chrono")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Код приглашения для API
+#[derive(Debug, Serialize)]
+pub struct GroupInviteCodeResponse {
+    pub id: String,
+    pub group_id: String,
+    pub code: String,
+    pub max_uses: u32,
+    pub uses: u32,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<GroupInviteCode> for GroupInviteCodeResponse {
+    fn from(invite: GroupInviteCode) -> Self {
+        GroupInviteCodeResponse {
+            id: invite.id.map(|id| id.to_hex()).unwrap_or_default(),
+            group_id: invite.group_id.to_hex(),
+            code: invite.code,
+            max_uses: invite.max_uses,
+            uses: invite.uses,
+            expires_at: invite.expires_at,
+            created_at: invite.created_at,
+        }
+    }
+}
+
+/// Request для создания кода приглашения
+#[derive(Debug, Clone, Default, Deserialize, Validate)]
+pub struct CreateInviteCodeRequest {
+    /// Срок действия в часах (по умолчанию неделя)
+    #[validate(range(
+        min = 1,
+        max = 720,
+        message = "expires_in_hours must be between 1 and 720"
+    ))]
+    pub expires_in_hours: Option<u32>,
+
+    /// Сколько учеников может вступить по коду (по умолчанию 30)
+    #[validate(range(min = 1, max = 1000, message = "max_uses must be between 1 and 1000"))]
+    pub max_uses: Option<u32>,
+}
+
+impl CreateInviteCodeRequest {
+    pub const DEFAULT_EXPIRES_IN_HOURS: u32 = 168;
+    pub const DEFAULT_MAX_USES: u32 = 30;
+}
+
+/// Request ученика на вступление в группу по коду
+#[derive(Debug, Clone, Deserialize)]
+pub struct JoinGroupRequest {
+    pub code: String,
+}
+
+/// Результат вступления; `joined = false`, если ученик уже состоял в группе
+#[derive(Debug, Serialize)]
+pub struct JoinGroupResponse {
+    pub group: GroupResponse,
+    pub joined: bool,
 }
 
 /// Query параметры для списка групп
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct ListGroupsQuery {
     /// Фильтр по школе
     pub school: Option<String>,
@@ -125,6 +309,10 @@ pub struct ListGroupsQuery {
     /// Поиск по названию (case-insensitive)
     pub search: Option<String>,
 
+    /// Показывать архивные группы (по умолчанию скрыты)
+    #[serde(default)]
+    pub include_archived: bool,
+
     pub limit: Option<u32>,
     pub offset: Option<u32>,
 }
@@ -1,7 +1,11 @@
 use chrono::{DateTime, Utc};
+use mongodb::bson::{Bson, Document};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::config::HintSettings;
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct RequestHintRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub idempotency_key: Option<String>,
@@ -17,14 +21,80 @@ pub struct RequestHintRequest {
     pub language: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct RequestHintResponse {
     pub hint: String,
     pub hint_text: String,
     pub hints_used: u32,
     pub hints_remaining: u32,
+    /// Сколько подсказок ещё доступно; `null` - без ограничения
+    pub remaining_hints: Option<u32>,
     pub cost: i32,
     pub new_score: i32,
+    /// Штраф к счёту сессии за эту подсказку, %
+    pub penalty_percent: u32,
+    /// Суммарный штраф, который будет применён при завершении сессии, %
+    pub total_penalty_percent: u32,
+    /// Штраф за следующую подсказку; `null`, если подсказки закончились
+    pub next_penalty_percent: Option<u32>,
+    /// Источник текста подсказки - по нему отслеживается доля фолбэков
+    pub provider: HintSource,
+}
+
+/// Лимит подсказок и шкала штрафов для задания.
+///
+/// Берётся из полей задания `max_hints` / `hint_penalties`, затем из `params` его шаблона,
+/// иначе из настроек. Явный `max_hints: 0` в задании запрещает подсказки.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HintPolicy {
+    pub max_hints: Option<u32>,
+    pub penalty_schedule: Vec<u32>,
+}
+
+impl HintPolicy {
+    pub fn resolve(
+        task: Option<&Document>,
+        template_params: Option<&Document>,
+        defaults: &HintSettings,
+    ) -> Self {
+        let sources = [task, template_params];
+        let max_hints = sources
+            .iter()
+            .flatten()
+            .find_map(|source| read_u32(source.get("max_hints")?))
+            .or((defaults.max_per_session > 0).then_some(defaults.max_per_session));
+        let penalty_schedule = sources
+            .iter()
+            .flatten()
+            .find_map(|source| match source.get("hint_penalties")? {
+                Bson::Array(values) => values.iter().map(read_u32).collect(),
+                _ => None,
+            })
+            .unwrap_or_else(|| defaults.penalty_schedule.clone());
+        Self {
+            max_hints,
+            penalty_schedule,
+        }
+    }
+
+    /// Штраф за `n`-ю подсказку (с 1); после конца шкалы повторяется последнее значение
+    pub fn penalty_for(&self, n: u32) -> u32 {
+        let index = (n.max(1) as usize - 1).min(self.penalty_schedule.len().saturating_sub(1));
+        self.penalty_schedule.get(index).copied().unwrap_or(0)
+    }
+
+    pub fn remaining(&self, hints_used: u32) -> Option<u32> {
+        self.max_hints.map(|limit| limit.saturating_sub(hints_used))
+    }
+}
+
+fn read_u32(value: &Bson) -> Option<u32> {
+    match value {
+        Bson::Int32(value) => u32::try_from(*value).ok(),
+        Bson::Int64(value) => u32::try_from(*value).ok(),
+        Bson::Double(value) if *value >= 0.0 && value.fract() == 0.0 => Some(*value as u32),
+        _ => None,
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -39,10 +109,91 @@ pub struct HintRecord {
     pub source: HintSource,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Источник подсказки; старые записи `python_api` / `fallback` читаются как `llm` / `rule`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum HintSource {
-    PythonApi,
-    Fallback,
     Cache,
+    #[serde(alias = "python_api")]
+    Llm,
+    #[serde(alias = "fallback")]
+    Rule,
+    Generic,
+}
+
+impl HintSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HintSource::Cache => "cache",
+            HintSource::Llm => "llm",
+            HintSource::Rule => "rule",
+            HintSource::Generic => "generic",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::doc;
+
+    fn defaults() -> HintSettings {
+        HintSettings {
+            max_per_session: 2,
+            penalty_schedule: vec![10, 20, 40],
+        }
+    }
+
+    #[test]
+    fn policy_prefers_task_then_template_then_config() {
+        let task = doc! { "max_hints": 3 };
+        let params = doc! { "max_hints": 1, "hint_penalties": [5, 15] };
+        let policy = HintPolicy::resolve(Some(&task), Some(&params), &defaults());
+        assert_eq!(policy.max_hints, Some(3));
+        assert_eq!(policy.penalty_schedule, vec![5, 15]);
+
+        let policy = HintPolicy::resolve(Some(&doc! {}), None, &defaults());
+        assert_eq!(
+            policy,
+            HintPolicy {
+                max_hints: Some(2),
+                penalty_schedule: vec![10, 20, 40],
+            }
+        );
+
+        let unlimited = HintSettings {
+            max_per_session: 0,
+            ..defaults()
+        };
+        assert_eq!(HintPolicy::resolve(None, None, &unlimited).max_hints, None);
+        let disabled = doc! { "max_hints": 0 };
+        assert_eq!(
+            HintPolicy::resolve(Some(&disabled), None, &unlimited).max_hints,
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn penalty_schedule_repeats_last_step() {
+        let policy = HintPolicy::resolve(None, None, &defaults());
+        assert_eq!(policy.penalty_for(1), 10);
+        assert_eq!(policy.penalty_for(3), 40);
+        assert_eq!(policy.penalty_for(5), 40);
+        assert_eq!(policy.remaining(1), Some(1));
+        assert_eq!(policy.remaining(4), Some(0));
+
+        let free = HintPolicy {
+            max_hints: None,
+            penalty_schedule: vec![],
+        };
+        assert_eq!(free.penalty_for(1), 0);
+        assert_eq!(free.remaining(10), None);
+    }
+
+    #[test]
+    fn invalid_penalties_fall_back_to_config() {
+        let task = doc! { "hint_penalties": [10, "x"] };
+        let policy = HintPolicy::resolve(Some(&task), None, &defaults());
+        assert_eq!(policy.penalty_schedule, vec![10, 20, 40]);
+    }
 }
@@ -0,0 +1,60 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use super::user::bson_datetime_as_chrono;
+
+/// Способ входа
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LoginMethod {
+    Password,
+    Sso,
+}
+
+/// Запись коллекции `login_history`; удаляется TTL-индексом по `at`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoginHistoryRecord {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    #[serde(rename = "userId")]
+    pub user_id: ObjectId,
+    #[serde(with = "bson_datetime_as_chrono")]
+    pub at: DateTime<Utc>,
+    /// IP, усечённый до сети (`utils::user_agent::truncate_ip`)
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub method: LoginMethod,
+}
+
+/// Вход пользователя в ответе `GET /admin/users/{id}/logins`
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LoginHistoryEntry {
+    pub id: String,
+    pub at: DateTime<Utc>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub method: LoginMethod,
+}
+
+impl From<LoginHistoryRecord> for LoginHistoryEntry {
+    fn from(record: LoginHistoryRecord) -> Self {
+        Self {
+            id: record.id.map(|id| id.to_hex()).unwrap_or_default(),
+            at: record.at,
+            ip: record.ip,
+            user_agent: record.user_agent,
+            method: record.method,
+        }
+    }
+}
+
+/// Query params for `GET /admin/users/{id}/logins` (новые входы первыми)
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LoginHistoryQuery {
+    pub limit: Option<u32>,
+    /// Курсор из заголовка `X-Next-Cursor` предыдущей страницы
+    pub cursor: Option<String>,
+}
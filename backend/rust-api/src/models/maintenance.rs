@@ -0,0 +1,162 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::user::{bson_datetime_as_chrono, bson_datetime_as_chrono_option};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MaintenanceJobKind {
+    /// Пересчёт `progress_summary_v2` по `attempt_records`
+    RecomputeProgress,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MaintenanceJobStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+}
+
+/// Тело `POST /admin/maintenance/recompute-progress`; без полей пересчитываются все ученики
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct RecomputeProgressRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    /// Ученики группы (по `users.group_ids`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_id: Option<String>,
+}
+
+/// Расхождение одного поля по всем исправленным строкам
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct FieldDrift {
+    /// Сколько строк разошлось по этому полю
+    pub rows: u64,
+    /// Сумма `|было - стало|`
+    pub total_abs_delta: f64,
+    pub max_abs_delta: f64,
+}
+
+/// Меньшие расхождения - погрешность сложения долей в другом порядке
+const DRIFT_TOLERANCE: f64 = 1e-6;
+
+impl FieldDrift {
+    pub fn record(&mut self, before: f64, after: f64) {
+        let delta = (before - after).abs();
+        if delta < DRIFT_TOLERANCE {
+            return;
+        }
+        self.rows += 1;
+        self.total_abs_delta += delta;
+        self.max_abs_delta = self.max_abs_delta.max(delta);
+    }
+}
+
+/// Отчёт о расхождениях `progress_summary_v2` с ответами
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ProgressDriftReport {
+    pub users_scanned: u64,
+    /// Ученики с архивированными или ненайденными сессиями: их ответы нельзя
+    /// разложить по уровням полностью, поэтому строки не трогаются
+    pub users_skipped: u64,
+    /// Пары ученик/уровень, посчитанные по ответам
+    pub rows_checked: u64,
+    /// Строки, где разошлось хотя бы одно поле (включая отсутствовавшие)
+    pub rows_drifted: u64,
+    /// Строки, которых не было вовсе
+    pub rows_created: u64,
+    /// Строки без единого ответа в `attempt_records`; оставлены как есть
+    pub rows_orphaned: u64,
+    pub attempts_total: FieldDrift,
+    pub correct_count: FieldDrift,
+    pub percentage: FieldDrift,
+    pub score: FieldDrift,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceJob {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    pub kind: MaintenanceJobKind,
+    pub status: MaintenanceJobStatus,
+    #[serde(default)]
+    pub scope: RecomputeProgressRequest,
+    pub created_by: String,
+    #[serde(with = "bson_datetime_as_chrono")]
+    pub created_at: DateTime<Utc>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "bson_datetime_as_chrono_option"
+    )]
+    pub finished_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Заполняется по ходу работы, после каждого батча учеников
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drift: Option<ProgressDriftReport>,
+}
+
+/// Ответ `GET /admin/maintenance/jobs/{id}` и `POST /admin/maintenance/recompute-progress`
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MaintenanceJobResponse {
+    pub id: String,
+    pub kind: MaintenanceJobKind,
+    pub status: MaintenanceJobStatus,
+    pub scope: RecomputeProgressRequest,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+    pub drift: Option<ProgressDriftReport>,
+}
+
+impl From<MaintenanceJob> for MaintenanceJobResponse {
+    fn from(job: MaintenanceJob) -> Self {
+        Self {
+            id: job.id.to_hex(),
+            kind: job.kind,
+            status: job.status,
+            scope: job.scope,
+            created_by: job.created_by,
+            created_at: job.created_at,
+            finished_at: job.finished_at,
+            error: job.error,
+            drift: job.drift,
+        }
+    }
+}
+
+/// Режим обслуживания (ключ `maintenance:mode` в Redis): пока он включён, API отвечает
+/// 503 всем, чья роль не в `allow_roles`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct MaintenanceMode {
+    pub enabled: bool,
+    /// Текст ответа 503
+    pub message: String,
+    pub allow_roles: Vec<String>,
+    /// Значение заголовка `Retry-After`
+    pub retry_after_seconds: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_by: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Тело `POST /admin/system/maintenance`
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+#[cfg_attr(feature = "client", derive(Serialize))]
+pub struct MaintenanceModeRequest {
+    pub enabled: bool,
+    #[serde(default)]
+    pub message: Option<String>,
+    /// По умолчанию `["admin"]`; роль того, кто включает режим, должна быть в списке
+    #[serde(default)]
+    pub allow_roles: Option<Vec<String>>,
+    #[serde(default)]
+    pub retry_after_seconds: Option<u64>,
+}
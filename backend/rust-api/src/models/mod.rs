@@ -1,7 +1,8 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Session {
     pub id: String,
     pub user_id: String,
@@ -14,9 +15,29 @@ pub struct Session {
     pub score: i32,
     #[serde(default)]
     pub level_id: Option<String>,
+    #[serde(default)]
+    pub mode: SessionMode,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Сессия для `GET /sessions/{id}` вместе со сводкой времени ответов
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SessionDetail {
+    #[serde(flatten)]
+    pub session: Session,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub answer_timing: Option<answer::AnswerTimingSummary>,
+}
+
+/// Режим сессии: `review` берёт шаблон из очереди повторения вместо обычного выбора задания
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionMode {
+    #[default]
+    Normal,
+    Review,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum SessionStatus {
     Active,
@@ -25,25 +46,54 @@ pub enum SessionStatus {
     Abandoned,
 }
 
-#[derive(Debug, Deserialize)]
+/// Непройденный пререквизит уровня (ответ 403 `LEVEL_LOCKED`)
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct MissingPrerequisite {
+    pub level_id: String,
+    pub name: String,
+    pub min_pass_percent: i32,
+    /// Текущий процент правильных ответов (0, если уровень не начат)
+    pub percentage: f64,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[cfg_attr(feature = "client", derive(Serialize))]
 pub struct CreateSessionRequest {
     pub user_id: String,
+    /// В режиме `review` не используется
+    #[serde(default)]
     pub task_id: String,
     pub group_id: Option<String>,
     /// Опциональный level_id для генерации заданий через Template Generator
     pub level_id: Option<String>,
     /// Дополнительное ограничение по времени для всей сессии в секундах
     pub session_duration_seconds: Option<i64>,
+    #[serde(default)]
+    pub mode: SessionMode,
+    /// Сессия по домашнему заданию группы: шаблон выбирается из задания,
+    /// `task_id` и `level_id` не используются
+    pub assignment_id: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+/// Сессия с заданием, подобранным по слабым темам ученика (ученик - из токена)
+#[derive(Debug, Default, Deserialize, ToSchema)]
+#[cfg_attr(feature = "client", derive(Serialize))]
+pub struct AutoSessionRequest {
+    pub group_id: Option<String>,
+    /// Дополнительное ограничение по времени для всей сессии в секундах
+    pub session_duration_seconds: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(feature = "client", derive(Deserialize))]
 pub struct CreateSessionResponse {
     pub session_id: String,
     pub task: TaskInfo,
     pub expires_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(feature = "client", derive(Deserialize))]
 pub struct TaskInfo {
     pub id: String,
     pub title: String,
@@ -51,21 +101,38 @@ pub struct TaskInfo {
     pub time_limit_seconds: u32,
 }
 
+pub mod achievement;
 pub mod answer;
 pub mod anticheat;
+pub mod assignment;
+pub mod audit_archive;
 pub mod audit_log;
 pub mod backup;
+pub mod certificate;
+pub mod consent;
 pub mod content;
+pub mod content_search;
+pub mod dashboard;
+pub mod db_index;
 pub mod feature_flag;
 pub mod group;
 pub mod hint;
+pub mod login_history;
+pub mod maintenance;
 pub mod notification;
+pub mod permission;
+pub mod prefetch;
+pub mod rate_limit;
 pub mod refresh_token;
 pub mod reporting;
+pub mod review;
+pub mod session_archive;
+pub mod superuser;
 pub mod system_metrics;
 pub mod system_settings;
 pub mod timer;
 pub mod user;
+pub mod user_purge;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Task {
@@ -73,12 +140,22 @@ pub struct Task {
     pub id: String,
     pub title: String,
     pub description: String,
+    /// Основной ответ; у заданий только с `answers` может быть пустым
+    #[serde(default)]
     pub correct_answer: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub answers: Option<answer::TaskAnswers>,
     pub time_limit_seconds: u32,
     pub difficulty: Option<String>,
+    /// Сравнивать ответ без нормализации регистра, `ё`, тире и пробелов
+    #[serde(default)]
+    pub strict_mode: bool,
+    /// Сколько ответов принимается в сессии; без поля (или 0) - без ограничения
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_attempts: Option<u32>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ProgressSummary {
     #[serde(rename = "_id")]
     pub id: String,
@@ -86,6 +163,10 @@ pub struct ProgressSummary {
     pub level_id: String,
     pub attempts_total: u32,
     pub correct_count: u32,
+    /// Сумма зачтённых долей ответов (частично верный ответ даёт долю меньше 1).
+    /// В старых документах поля нет - тогда оно равно `correct_count`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credit: Option<f64>,
     pub percentage: f64,
     pub score: i32,
     pub updated_at: DateTime<Utc>,
@@ -1,8 +1,9 @@
 use chrono::{DateTime, Utc};
 use mongodb::bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
-use super::user::bson_datetime_as_chrono;
+use super::user::{bson_datetime_as_chrono, bson_datetime_as_chrono_option};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NotificationTemplate {
@@ -31,3 +32,191 @@ pub struct SentNotification {
     pub sent_at: DateTime<Utc>,
     pub status: String,
 }
+
+/// Статус доставки письма из `email_outbox`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EmailDeliveryStatus {
+    /// Ждёт отправки (в том числе повторной)
+    Queued,
+    /// Взято воркером
+    Sending,
+    Sent,
+    /// Попытки исчерпаны
+    Failed,
+    /// Отправка отключена `EMAIL_SEND_DISABLED`
+    Skipped,
+}
+
+impl EmailDeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EmailDeliveryStatus::Queued => "queued",
+            EmailDeliveryStatus::Sending => "sending",
+            EmailDeliveryStatus::Sent => "sent",
+            EmailDeliveryStatus::Failed => "failed",
+            EmailDeliveryStatus::Skipped => "skipped",
+        }
+    }
+}
+
+/// Письмо одному получателю рассылки (`notification_id` - запись `sent_notifications`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailOutboxMessage {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    pub notification_id: ObjectId,
+    pub recipient_id: ObjectId,
+    pub recipient_email: String,
+    pub recipient_name: String,
+    pub subject: String,
+    pub body: String,
+    pub status: EmailDeliveryStatus,
+    #[serde(default)]
+    pub attempts: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    #[serde(
+        rename = "nextAttemptAt",
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "bson_datetime_as_chrono_option"
+    )]
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    /// До какого момента письмо в `sending` закреплено за воркером
+    #[serde(
+        rename = "leaseUntil",
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "bson_datetime_as_chrono_option"
+    )]
+    pub lease_until: Option<DateTime<Utc>>,
+    #[serde(rename = "createdAt", with = "bson_datetime_as_chrono")]
+    pub created_at: DateTime<Utc>,
+    #[serde(
+        rename = "sentAt",
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "bson_datetime_as_chrono_option"
+    )]
+    pub sent_at: Option<DateTime<Utc>>,
+}
+
+/// Доставка рассылки одному ученику
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RecipientDeliveryStatus {
+    #[serde(rename = "studentId")]
+    pub student_id: String,
+    pub email: String,
+    pub name: String,
+    pub status: EmailDeliveryStatus,
+    pub attempts: u32,
+    #[serde(rename = "lastError", skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    #[serde(rename = "sentAt", skip_serializing_if = "Option::is_none")]
+    pub sent_at: Option<DateTime<Utc>>,
+}
+
+impl From<EmailOutboxMessage> for RecipientDeliveryStatus {
+    fn from(message: EmailOutboxMessage) -> Self {
+        Self {
+            student_id: message.recipient_id.to_hex(),
+            email: message.recipient_email,
+            name: message.recipient_name,
+            status: message.status,
+            attempts: message.attempts,
+            last_error: message.last_error,
+            sent_at: message.sent_at,
+        }
+    }
+}
+
+/// Ответ `GET /teacher/notifications/{id}/status`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct NotificationDeliveryResponse {
+    pub id: String,
+    /// `queued`, `sent`, `skipped`, `failed` или `partial` (часть писем не дошла)
+    pub status: String,
+    pub recipients: Vec<RecipientDeliveryStatus>,
+}
+
+/// Тип уведомления в центре уведомлений пользователя
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum InAppNotificationKind {
+    /// Рассылка учителя группе
+    TeacherMessage,
+    AchievementUnlocked,
+    /// До конца сессии осталось меньше `session.expiry_warning_seconds`
+    SessionExpiring,
+}
+
+/// Документ коллекции `notifications`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InAppNotification {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    pub recipient_id: String,
+    #[serde(rename = "type")]
+    pub kind: InAppNotificationKind,
+    pub title: String,
+    pub body: String,
+    pub read: bool,
+    /// Ключ для событий, которые могут сработать повторно (переподключение SSE)
+    #[serde(rename = "dedupKey", default, skip_serializing_if = "Option::is_none")]
+    pub dedup_key: Option<String>,
+    #[serde(rename = "createdAt", with = "bson_datetime_as_chrono")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct InAppNotificationResponse {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: InAppNotificationKind,
+    pub title: String,
+    pub body: String,
+    pub read: bool,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<InAppNotification> for InAppNotificationResponse {
+    fn from(notification: InAppNotification) -> Self {
+        Self {
+            id: notification.id.to_hex(),
+            kind: notification.kind,
+            title: notification.title,
+            body: notification.body,
+            read: notification.read,
+            created_at: notification.created_at,
+        }
+    }
+}
+
+/// Query параметры `GET /api/v1/notifications`
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct InAppNotificationListQuery {
+    /// Только непрочитанные
+    #[serde(default)]
+    pub unread_only: bool,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct InAppNotificationListResponse {
+    pub total: u64,
+    #[serde(rename = "unreadCount")]
+    pub unread_count: u64,
+    pub limit: u32,
+    pub offset: u32,
+    pub items: Vec<InAppNotificationResponse>,
+}
+
+/// Ответ `POST /api/v1/notifications/read-all`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MarkAllReadResponse {
+    pub updated: u64,
+}
@@ -0,0 +1,80 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::user::bson_datetime_as_chrono;
+
+/// Разрешение на группу административных действий
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, ToSchema,
+)]
+pub enum Permission {
+    /// Пользователи, группы, согласия, инциденты, роли
+    #[serde(rename = "users.manage")]
+    UsersManage,
+    /// Необратимое удаление персональных данных пользователя
+    #[serde(rename = "users.purge")]
+    UsersPurge,
+    /// Шаблоны, темы, уровни, правила и очередь модерации
+    #[serde(rename = "content.moderate")]
+    ContentModerate,
+    /// Возврат в публикацию прошлой версии шаблона без ревью
+    #[serde(rename = "content.rollback")]
+    ContentRollback,
+    /// Кабинет учителя: группы, аналитика, уведомления
+    #[serde(rename = "reports.view")]
+    ReportsView,
+    /// Системные настройки, флаги, бэкапы, аудит и метрики
+    #[serde(rename = "settings.manage")]
+    SettingsManage,
+}
+
+impl Permission {
+    pub const ALL: [Permission; 6] = [
+        Permission::UsersManage,
+        Permission::UsersPurge,
+        Permission::ContentModerate,
+        Permission::ContentRollback,
+        Permission::ReportsView,
+        Permission::SettingsManage,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::UsersManage => "users.manage",
+            Permission::UsersPurge => "users.purge",
+            Permission::ContentModerate => "content.moderate",
+            Permission::ContentRollback => "content.rollback",
+            Permission::ReportsView => "reports.view",
+            Permission::SettingsManage => "settings.manage",
+        }
+    }
+}
+
+/// Документ `role_permissions`: переопределение разрешений роли
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RolePermissionsRecord {
+    #[serde(rename = "_id")]
+    pub role: String,
+    pub permissions: Vec<Permission>,
+    #[serde(rename = "updatedAt", with = "bson_datetime_as_chrono")]
+    pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub updated_by: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RolePermissionsResponse {
+    pub role: String,
+    pub permissions: Vec<Permission>,
+    /// Роль из `UserRole` (её можно назначать пользователям)
+    pub built_in: bool,
+    /// Разрешения заданы в `role_permissions`, а не взяты по умолчанию
+    pub customized: bool,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[cfg_attr(feature = "client", derive(Serialize))]
+pub struct UpdateRolePermissionsRequest {
+    pub permissions: Vec<Permission>,
+}
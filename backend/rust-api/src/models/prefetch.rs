@@ -0,0 +1,49 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+/// Версия формата манифеста; клиент пропускает дескрипторы неизвестных типов
+pub const PREFETCH_MANIFEST_VERSION: u32 = 1;
+
+/// Манифест ресурсов, которые SPA стоит загрузить заранее (по убыванию приоритета)
+#[derive(Debug, Clone, Serialize)]
+pub struct PrefetchManifest {
+    pub version: u32,
+    pub role: String,
+    pub max_age_secs: u64,
+    pub resources: Vec<ResourceDescriptor>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceKind {
+    /// GET-запрос к API по шаблону маршрута
+    Api,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ResourceDescriptor {
+    #[serde(rename = "type")]
+    pub kind: ResourceKind,
+    /// Шаблон маршрута, например `/api/v1/sessions/{id}`
+    pub route: String,
+    pub params: BTreeMap<String, String>,
+    /// Сколько секунд клиент может считать загруженный ответ свежим
+    pub ttl_secs: u64,
+}
+
+impl ResourceDescriptor {
+    pub fn api(route: &str, ttl_secs: u64) -> Self {
+        Self {
+            kind: ResourceKind::Api,
+            route: route.to_string(),
+            params: BTreeMap::new(),
+            ttl_secs,
+        }
+    }
+
+    pub fn with_param(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.params.insert(name.to_string(), value.into());
+        self
+    }
+}
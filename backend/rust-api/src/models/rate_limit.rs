@@ -0,0 +1,124 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{RateLimitAlgorithm, RateLimitSettings, RateLimitWindow};
+
+/// Пространства имён лимитеров в Redis. Список общий для middleware и
+/// админского сервиса: новый лимитер сразу виден в /admin/rate-limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitNamespace {
+    Login,
+    Register,
+    Sessions,
+    Admin,
+}
+
+impl RateLimitNamespace {
+    pub const ALL: [RateLimitNamespace; 4] = [
+        RateLimitNamespace::Login,
+        RateLimitNamespace::Register,
+        RateLimitNamespace::Sessions,
+        RateLimitNamespace::Admin,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RateLimitNamespace::Login => "login",
+            RateLimitNamespace::Register => "register",
+            RateLimitNamespace::Sessions => "sessions",
+            RateLimitNamespace::Admin => "admin",
+        }
+    }
+
+    /// Лимит группы маршрутов для субъекта
+    pub fn window(
+        self,
+        settings: &RateLimitSettings,
+        subject: &RateLimitSubject,
+    ) -> RateLimitWindow {
+        match (self, subject) {
+            (RateLimitNamespace::Login, _) => settings.login,
+            (RateLimitNamespace::Register, _) => settings.register,
+            (RateLimitNamespace::Sessions, RateLimitSubject::User(_)) => settings.per_user,
+            (RateLimitNamespace::Sessions, RateLimitSubject::Ip(_)) => settings.per_ip,
+            (RateLimitNamespace::Admin, RateLimitSubject::User(_)) => settings.admin_per_user,
+            (RateLimitNamespace::Admin, RateLimitSubject::Ip(_)) => settings.admin_per_ip,
+        }
+    }
+
+    /// Ключ счётчика, например `ratelimit:login:ip:10.0.0.1`
+    pub fn key(self, subject: &RateLimitSubject) -> String {
+        format!("ratelimit:{}:{}", self.as_str(), subject)
+    }
+}
+
+/// Кого ограничиваем: пользователя (по id из JWT) или клиента по IP
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RateLimitSubject {
+    User(String),
+    Ip(String),
+}
+
+impl RateLimitSubject {
+    pub fn scope(&self) -> &'static str {
+        match self {
+            RateLimitSubject::User(_) => "user",
+            RateLimitSubject::Ip(_) => "ip",
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        match self {
+            RateLimitSubject::User(id) | RateLimitSubject::Ip(id) => id,
+        }
+    }
+}
+
+impl fmt::Display for RateLimitSubject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.scope(), self.id())
+    }
+}
+
+/// Фактический ключ в Redis: скользящее окно хранится в отдельном ZSET,
+/// чтобы не пересекаться со строковыми счётчиками fixed-режима
+pub fn storage_key(key: &str, algorithm: RateLimitAlgorithm) -> String {
+    match algorithm {
+        RateLimitAlgorithm::Fixed => key.to_string(),
+        RateLimitAlgorithm::Sliding => format!("{}:sw", key),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RateLimitQuery {
+    pub key: String,
+}
+
+/// Текущее состояние одного счётчика
+#[derive(Debug, Clone, Serialize)]
+pub struct RateLimitCounter {
+    pub namespace: RateLimitNamespace,
+    pub scope: &'static str,
+    pub redis_key: String,
+    pub count: u32,
+    pub limit: u32,
+    pub window_secs: u64,
+    /// Секунды до истечения ключа в Redis
+    pub ttl_secs: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RateLimitInspection {
+    pub key: String,
+    pub algorithm: RateLimitAlgorithm,
+    pub counters: Vec<RateLimitCounter>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RateLimitResetResponse {
+    pub key: String,
+    /// Счётчики, которые были активны до сброса
+    pub cleared: Vec<RateLimitCounter>,
+}
@@ -4,6 +4,8 @@ use serde::{Deserialize, Serialize};
 
 // Import serde helpers from user module
 use super::user::bson_datetime_as_chrono;
+use crate::utils::user_agent::{parse_browser, parse_os, truncate_ip, UNKNOWN};
+use utoipa::ToSchema;
 
 /// Refresh token stored in MongoDB "refresh_tokens" collection
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,27 +37,51 @@ pub struct RefreshToken {
     /// Whether this token has been revoked
     #[serde(default)]
     pub revoked: bool,
+
+    /// Устройство, разобранное из User-Agent при входе (у старых токенов отсутствует)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<SessionDevice>,
+}
+
+/// Описание устройства сессии: браузер и ОС из User-Agent, усечённый IP
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SessionDevice {
+    pub browser: String,
+    pub os: String,
+    pub ip: Option<String>,
+}
+
+impl SessionDevice {
+    pub fn new(user_agent: Option<&str>, ip: Option<&str>) -> Self {
+        SessionDevice {
+            browser: user_agent.map(parse_browser).unwrap_or(UNKNOWN).to_string(),
+            os: user_agent.map(parse_os).unwrap_or(UNKNOWN).to_string(),
+            ip: ip.and_then(truncate_ip),
+        }
+    }
 }
 
 /// Active session information (for user profile page)
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ActiveSession {
+    /// Id refresh-токена; по нему сессию можно отозвать
     pub id: String,
+    pub device: SessionDevice,
     pub created_at: DateTime<Utc>,
     pub last_used_at: DateTime<Utc>,
-    pub user_agent: Option<String>,
-    pub ip: Option<String>,
     pub is_current: bool,
 }
 
 impl From<RefreshToken> for ActiveSession {
     fn from(token: RefreshToken) -> Self {
+        let device = token.device.unwrap_or_else(|| {
+            SessionDevice::new(token.user_agent.as_deref(), token.ip.as_deref())
+        });
         ActiveSession {
             id: token.id.map(|id| id.to_hex()).unwrap_or_default(),
+            device,
             created_at: token.created_at,
             last_used_at: token.last_used_at,
-            user_agent: token.user_agent,
-            ip: token.ip,
             is_current: false, // Will be set by handler based on current token
         }
     }
@@ -68,7 +94,8 @@ pub struct RefreshTokenRequest {
 }
 
 /// Response after refreshing access token
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(feature = "client", derive(Deserialize))]
 pub struct RefreshTokenResponse {
     pub access_token: String,
 }
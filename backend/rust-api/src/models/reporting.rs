@@ -1,23 +1,28 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, Utc};
 use mongodb::bson::{oid::ObjectId, Document};
 use serde::{Deserialize, Serialize};
 
+use super::user::{bson_datetime_as_chrono, bson_datetime_as_chrono_option};
 use super::ProgressSummary;
+use utoipa::ToSchema;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct MaterializedStat {
     #[serde(rename = "_id")]
+    #[schema(value_type = Object)]
     pub id: ObjectId,
     #[serde(rename = "type")]
     pub stat_type: StatType,
     #[serde(rename = "entity_id")]
+    #[schema(value_type = Object)]
     pub entity_id: ObjectId,
+    #[schema(value_type = Object)]
     pub metrics: Document,
     #[serde(rename = "calculatedAt")]
     pub calculated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum StatType {
     Group,
@@ -35,18 +40,28 @@ impl StatType {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Показатели темы в одной группе (`GET /stats/topics/{id}?group_id=…`)
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TopicGroupStats {
+    /// Средний процент по строкам progress_summary учеников группы на уровнях темы
+    pub avg_percentage: Option<f64>,
+    pub total_attempts: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct LeaderboardDocument {
     #[serde(rename = "_id")]
+    #[schema(value_type = Object)]
     pub id: ObjectId,
     pub scope: LeaderboardScope,
+    #[schema(value_type = Object)]
     pub scope_id: Option<ObjectId>,
     pub rankings: Vec<LeaderboardEntry>,
     #[serde(rename = "generatedAt")]
     pub generated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum LeaderboardScope {
     Global,
@@ -64,9 +79,10 @@ impl LeaderboardScope {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct LeaderboardEntry {
     #[serde(rename = "user_id")]
+    #[schema(value_type = Object)]
     pub user_id: ObjectId,
     pub score: i64,
     pub rank: u32,
@@ -77,8 +93,17 @@ pub struct LeaderboardEntry {
 pub struct ReportExport {
     #[serde(rename = "_id")]
     pub id: ObjectId,
-    #[serde(rename = "group_id")]
-    pub group_id: ObjectId,
+    /// Старые записи без поля — выгрузки по группе
+    #[serde(default)]
+    pub scope: ExportScope,
+    /// Группа отчёта; заполнена для `ExportScope::Group`
+    #[serde(rename = "group_id", default, skip_serializing_if = "Option::is_none")]
+    pub group_id: Option<ObjectId>,
+    /// Ученик отчёта; заполнен для `ExportScope::User`
+    #[serde(rename = "user_id", default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<ObjectId>,
+    /// Кто запросил выгрузку (по нему считается rate limit); для личного
+    /// отчёта это может быть сам ученик
     #[serde(rename = "teacher_id")]
     pub teacher_id: ObjectId,
     pub status: ExportStatus,
@@ -86,13 +111,124 @@ pub struct ReportExport {
     #[serde(rename = "storage_key")]
     pub storage_key: Option<String>,
     pub filters: ReportFilters,
-    #[serde(rename = "createdAt")]
+    #[serde(rename = "createdAt", with = "export_datetime")]
     pub created_at: DateTime<Utc>,
-    #[serde(rename = "expiresAt")]
+    #[serde(rename = "expiresAt", with = "export_datetime")]
     pub expires_at: DateTime<Utc>,
-    #[serde(rename = "completedAt")]
+    #[serde(rename = "completedAt", default, with = "export_datetime_option")]
     pub completed_at: Option<DateTime<Utc>>,
     pub error: Option<String>,
+    /// Число начатых попыток сборки
+    #[serde(default)]
+    pub attempts: u32,
+    /// Ошибка последней попытки (сохраняется и пока выгрузка ждёт повтора)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// Раньше этого момента выгрузку не берут в работу повторно
+    #[serde(
+        rename = "nextAttemptAt",
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "bson_datetime_as_chrono_option"
+    )]
+    pub next_attempt_at: Option<DateTime<Utc>>,
+}
+
+impl ReportExport {
+    /// Идентификатор группы или ученика, по которому строится отчёт
+    pub fn subject_id(&self) -> Option<ObjectId> {
+        match self.scope {
+            ExportScope::Group => self.group_id,
+            ExportScope::User => self.user_id,
+        }
+    }
+}
+
+/// Чему посвящена выгрузка
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportScope {
+    #[default]
+    Group,
+    User,
+}
+
+impl ExportScope {
+    /// Префикс ключа в объектном хранилище
+    pub fn storage_prefix(&self) -> &'static str {
+        match self {
+            ExportScope::Group => "groups",
+            ExportScope::User => "users",
+        }
+    }
+}
+
+/// Даты выгрузок пишутся как BSON datetime, чтобы по ним работали запросы
+/// (`createdAt` в rate limit, `expiresAt` в очистке). Старые записи хранят
+/// строки RFC 3339 — они по-прежнему читаются.
+mod export_datetime {
+    use chrono::{DateTime, Utc};
+    use mongodb::bson;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    pub(super) enum StoredDateTime {
+        Bson(bson::DateTime),
+        Text(DateTime<Utc>),
+    }
+
+    impl From<StoredDateTime> for DateTime<Utc> {
+        fn from(value: StoredDateTime) -> Self {
+            match value {
+                StoredDateTime::Bson(value) => {
+                    DateTime::from_timestamp_millis(value.timestamp_millis()).unwrap_or_default()
+                }
+                StoredDateTime::Text(value) => value,
+            }
+        }
+    }
+
+    pub fn serialize<S>(date: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        bson::DateTime::from_millis(date.timestamp_millis()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        StoredDateTime::deserialize(deserializer).map(Into::into)
+    }
+}
+
+mod export_datetime_option {
+    use chrono::{DateTime, Utc};
+    use mongodb::bson;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::export_datetime::StoredDateTime;
+
+    pub fn serialize<S>(date: &Option<DateTime<Utc>>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match date {
+            Some(date) => {
+                serializer.serialize_some(&bson::DateTime::from_millis(date.timestamp_millis()))
+            }
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Option::<StoredDateTime>::deserialize(deserializer)?.map(Into::into))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -100,8 +236,41 @@ pub struct ReportFilters {
     #[serde(default)]
     pub topic_ids: Vec<ObjectId>,
     pub period: TimeRange,
+    /// Белый список полей строки NDJSON; пустой - все поля из `NDJSON_ANSWER_FIELDS`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<String>,
+    /// Настоящие id учеников в сырой выгрузке (её запросил админ);
+    /// иначе id заменяются псевдонимами
+    #[serde(default)]
+    pub reveal_user_ids: bool,
+    /// Язык подписей и форматы чисел и дат в файле
+    #[serde(default)]
+    pub locale: ExportLocale,
 }
 
+/// Язык выгрузки
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportLocale {
+    #[default]
+    Ru,
+    En,
+}
+
+/// Поля строки сырой выгрузки ответов группы (`ExportFormat::Ndjson`).
+/// `user_name` попадает в файл только вместе с настоящими id.
+pub const NDJSON_ANSWER_FIELDS: &[&str] = &[
+    "user_id",
+    "user_name",
+    "session_id",
+    "task_id",
+    "answer",
+    "correct",
+    "score",
+    "timestamp",
+    "reason",
+];
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TimeRange {
     pub from: DateTime<Utc>,
@@ -110,7 +279,9 @@ pub struct TimeRange {
 
 #[derive(Debug, Clone)]
 pub struct NewReportExport {
-    pub group_id: ObjectId,
+    pub scope: ExportScope,
+    /// Группа или ученик — в зависимости от `scope`
+    pub subject_id: ObjectId,
     pub teacher_id: ObjectId,
     pub format: ExportFormat,
     pub filters: ReportFilters,
@@ -120,9 +291,15 @@ pub struct NewReportExport {
 impl NewReportExport {
     pub fn into_record(self) -> ReportExport {
         let now = Utc::now();
+        let (group_id, user_id) = match self.scope {
+            ExportScope::Group => (Some(self.subject_id), None),
+            ExportScope::User => (None, Some(self.subject_id)),
+        };
         ReportExport {
             id: ObjectId::new(),
-            group_id: self.group_id,
+            scope: self.scope,
+            group_id,
+            user_id,
             teacher_id: self.teacher_id,
             status: ExportStatus::Pending,
             format: self.format,
@@ -132,11 +309,14 @@ impl NewReportExport {
             expires_at: self.expires_at,
             completed_at: None,
             error: None,
+            attempts: 0,
+            last_error: None,
+            next_attempt_at: None,
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExportStatus {
     Pending,
@@ -151,12 +331,14 @@ impl ExportStatus {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExportFormat {
     Csv,
     Pdf,
     Xlsx,
+    /// Сырые ответы группы, по JSON-объекту на строку
+    Ndjson,
 }
 
 impl ExportFormat {
@@ -165,6 +347,7 @@ impl ExportFormat {
             ExportFormat::Csv => "CSV",
             ExportFormat::Pdf => "PDF",
             ExportFormat::Xlsx => "XLSX",
+            ExportFormat::Ndjson => "NDJSON",
         }
     }
 
@@ -175,6 +358,107 @@ impl ExportFormat {
             ExportFormat::Xlsx => {
                 "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"
             }
+            ExportFormat::Ndjson => "application/x-ndjson",
+        }
+    }
+}
+
+/// Расписание регулярной выгрузки отчёта по группе (коллекция `report_export_schedules`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportSchedule {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    pub group_id: ObjectId,
+    pub teacher_id: ObjectId,
+    pub format: ExportFormat,
+    pub cadence: ScheduleCadence,
+    /// День недели запуска, 1 = понедельник … 7 = воскресенье (ISO 8601)
+    pub day_of_week: u8,
+    pub recipients: Vec<String>,
+    /// Момент последнего запуска; по нему воркер не запускает период повторно
+    #[serde(
+        rename = "lastRunAt",
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "bson_datetime_as_chrono_option"
+    )]
+    pub last_run_at: Option<DateTime<Utc>>,
+    #[serde(
+        rename = "lastExportId",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub last_export_id: Option<ObjectId>,
+    /// Выгрузка, о готовности которой получатели уже уведомлены
+    #[serde(
+        rename = "notifiedExportId",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub notified_export_id: Option<ObjectId>,
+    #[serde(rename = "createdAt", with = "bson_datetime_as_chrono")]
+    pub created_at: DateTime<Utc>,
+}
+
+impl ExportSchedule {
+    /// Последний плановый момент запуска, не позже `now` (время UTC, начало дня).
+    ///
+    /// Еженедельное расписание срабатывает в `day_of_week` каждой недели,
+    /// ежемесячное — в первый такой день недели месяца.
+    pub fn latest_run_slot(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let today = now.date_naive();
+        let slot = match self.cadence {
+            ScheduleCadence::Weekly => {
+                let current = today.weekday().number_from_monday() as i64;
+                let days_back = (current - self.day_of_week as i64).rem_euclid(7);
+                today - Duration::days(days_back)
+            }
+            ScheduleCadence::Monthly => {
+                let this_month = first_weekday_of_month(today, self.day_of_week);
+                if this_month <= today {
+                    this_month
+                } else {
+                    let previous = today
+                        .with_day(1)
+                        .and_then(|day| day.checked_sub_months(Months::new(1)))
+                        .unwrap_or(today);
+                    first_weekday_of_month(previous, self.day_of_week)
+                }
+            }
+        };
+        slot.and_hms_opt(0, 0, 0)
+            .map(|value| value.and_utc())
+            .unwrap_or(now)
+    }
+
+    /// Наступил ли период, в котором расписание ещё не запускалось
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        let slot = self.latest_run_slot(now);
+        self.last_run_at.is_none_or(|last_run| last_run < slot)
+    }
+}
+
+fn first_weekday_of_month(date: NaiveDate, day_of_week: u8) -> NaiveDate {
+    let first = date.with_day(1).unwrap_or(date);
+    let offset = (day_of_week as i64 - first.weekday().number_from_monday() as i64).rem_euclid(7);
+    first + Duration::days(offset)
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduleCadence {
+    Weekly,
+    Monthly,
+}
+
+impl ScheduleCadence {
+    /// Начало периода отчёта, который закрывается запуском в `slot`
+    pub fn period_start(&self, slot: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            ScheduleCadence::Weekly => slot - Duration::days(7),
+            ScheduleCadence::Monthly => slot
+                .checked_sub_months(Months::new(1))
+                .unwrap_or(slot - Duration::days(30)),
         }
     }
 }
@@ -193,3 +477,306 @@ pub struct UserAnalyticsSnapshot {
     pub progress: Vec<ProgressSummary>,
     pub last_activity_at: Option<DateTime<Utc>>,
 }
+
+// Тела запросов и ответов `/stats`. Обратные derive под фичей `client` нужны
+// типизированному клиенту (`crate::client`)
+
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(feature = "client", derive(Deserialize))]
+pub struct GroupStatsResponse {
+    pub group_id: String,
+    pub stats: MaterializedStat,
+    pub leaderboard: Option<LeaderboardDocument>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(feature = "client", derive(Deserialize))]
+pub struct UserStatsResponse {
+    pub user_id: String,
+    pub progress: Vec<ProgressSummary>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(feature = "client", derive(Deserialize))]
+pub struct TopicStatsResponse {
+    pub topic_id: String,
+    pub stats: MaterializedStat,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(feature = "client", derive(Deserialize))]
+pub struct TopicComparisonResponse {
+    pub topic_id: String,
+    pub overall: MaterializedStat,
+    pub by_group: Vec<TopicGroupBreakdown>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(feature = "client", derive(Deserialize))]
+pub struct TopicGroupBreakdown {
+    pub group_id: String,
+    pub group_name: String,
+    pub avg_percentage: Option<f64>,
+    pub total_attempts: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(feature = "client", derive(Deserialize))]
+pub struct ExportResponse {
+    pub export_id: String,
+    pub status: ExportStatus,
+    pub expires_at: DateTime<Utc>,
+    /// Куда опрашивать статус: `/stats/exports/{export_id}`
+    pub status_url: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(feature = "client", derive(Deserialize))]
+pub struct ExportStatusResponse {
+    pub export_id: String,
+    pub status: ExportStatus,
+    pub format: ExportFormat,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub download_url: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(feature = "client", derive(Deserialize))]
+pub struct ExportListResponse {
+    pub group_id: String,
+    pub total: u64,
+    pub limit: u32,
+    pub offset: u32,
+    pub exports: Vec<ExportStatusResponse>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(feature = "client", derive(Deserialize))]
+pub struct ExportScheduleResponse {
+    pub schedule_id: String,
+    pub group_id: String,
+    pub format: ExportFormat,
+    pub cadence: ScheduleCadence,
+    pub day_of_week: u8,
+    pub recipients: Vec<String>,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub last_export_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<ExportSchedule> for ExportScheduleResponse {
+    fn from(schedule: ExportSchedule) -> Self {
+        Self {
+            schedule_id: schedule.id.to_hex(),
+            group_id: schedule.group_id.to_hex(),
+            format: schedule.format,
+            cadence: schedule.cadence,
+            day_of_week: schedule.day_of_week,
+            recipients: schedule.recipients,
+            last_run_at: schedule.last_run_at,
+            last_export_id: schedule.last_export_id.map(|id| id.to_hex()),
+            created_at: schedule.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[cfg_attr(feature = "client", derive(Serialize))]
+pub struct ExportScheduleRequest {
+    pub format: ExportFormatRequest,
+    pub cadence: ScheduleCadence,
+    pub day_of_week: u8,
+    pub recipients: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[cfg_attr(feature = "client", derive(Serialize))]
+pub struct ExportRequest {
+    #[serde(default)]
+    pub topic_ids: Vec<String>,
+    /// По умолчанию - последние 30 дней
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub period: Option<TimeRangeRequest>,
+    pub format: ExportFormatRequest,
+    /// Только для `ndjson`: какие поля оставить в строках
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fields: Option<Vec<String>>,
+    /// Язык отчёта, по умолчанию `ru`
+    #[serde(default)]
+    pub locale: ExportLocale,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[cfg_attr(feature = "client", derive(Serialize))]
+pub struct TimeRangeRequest {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[cfg_attr(feature = "client", derive(Serialize))]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormatRequest {
+    Csv,
+    Pdf,
+    Xlsx,
+    Ndjson,
+}
+
+impl From<ExportFormatRequest> for ExportFormat {
+    fn from(value: ExportFormatRequest) -> Self {
+        match value {
+            ExportFormatRequest::Csv => ExportFormat::Csv,
+            ExportFormatRequest::Pdf => ExportFormat::Pdf,
+            ExportFormatRequest::Xlsx => ExportFormat::Xlsx,
+            ExportFormatRequest::Ndjson => ExportFormat::Ndjson,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn schedule(cadence: ScheduleCadence, day_of_week: u8) -> ExportSchedule {
+        ExportSchedule {
+            id: ObjectId::new(),
+            group_id: ObjectId::new(),
+            teacher_id: ObjectId::new(),
+            format: ExportFormat::Pdf,
+            cadence,
+            day_of_week,
+            recipients: vec!["teacher@example.com".to_string()],
+            last_run_at: None,
+            last_export_id: None,
+            notified_export_id: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn weekly_slot_is_latest_matching_weekday() {
+        // 2026-10-14 — среда
+        let now = Utc.with_ymd_and_hms(2026, 10, 14, 9, 30, 0).unwrap();
+        let monday = schedule(ScheduleCadence::Weekly, 1);
+        assert_eq!(
+            monday.latest_run_slot(now),
+            Utc.with_ymd_and_hms(2026, 10, 12, 0, 0, 0).unwrap()
+        );
+        let friday = schedule(ScheduleCadence::Weekly, 5);
+        assert_eq!(
+            friday.latest_run_slot(now),
+            Utc.with_ymd_and_hms(2026, 10, 9, 0, 0, 0).unwrap()
+        );
+        let wednesday = schedule(ScheduleCadence::Weekly, 3);
+        assert_eq!(
+            wednesday.latest_run_slot(now),
+            Utc.with_ymd_and_hms(2026, 10, 14, 0, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn monthly_slot_falls_back_to_previous_month() {
+        // Первый четверг октября 2026 — 1-е, первый вторник — 6-е
+        let now = Utc.with_ymd_and_hms(2026, 10, 3, 12, 0, 0).unwrap();
+        let thursday = schedule(ScheduleCadence::Monthly, 4);
+        assert_eq!(
+            thursday.latest_run_slot(now),
+            Utc.with_ymd_and_hms(2026, 10, 1, 0, 0, 0).unwrap()
+        );
+        let tuesday = schedule(ScheduleCadence::Monthly, 2);
+        assert_eq!(
+            tuesday.latest_run_slot(now),
+            Utc.with_ymd_and_hms(2026, 9, 1, 0, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn export_dates_read_bson_and_legacy_strings() {
+        use mongodb::bson::{from_document, to_document};
+
+        let now = Utc.with_ymd_and_hms(2026, 10, 14, 9, 30, 0).unwrap();
+        let mut export = NewReportExport {
+            scope: ExportScope::Group,
+            subject_id: ObjectId::new(),
+            teacher_id: ObjectId::new(),
+            format: ExportFormat::Csv,
+            filters: ReportFilters {
+                topic_ids: Vec::new(),
+                period: TimeRange { from: now, to: now },
+                fields: Vec::new(),
+                reveal_user_ids: false,
+                locale: ExportLocale::default(),
+            },
+            expires_at: now,
+        }
+        .into_record();
+        export.created_at = now;
+        export.completed_at = Some(now);
+
+        let mut stored = to_document(&export).unwrap();
+        assert!(stored.get_datetime("createdAt").is_ok());
+        assert!(stored.get_datetime("completedAt").is_ok());
+        let restored: ReportExport = from_document(stored.clone()).unwrap();
+        assert_eq!(restored.completed_at, Some(now));
+
+        // Записи, созданные до перехода на BSON datetime
+        stored.insert("createdAt", now.to_rfc3339());
+        stored.insert("completedAt", now.to_rfc3339());
+        let legacy: ReportExport = from_document(stored).unwrap();
+        assert_eq!(legacy.created_at, now);
+        assert_eq!(legacy.completed_at, Some(now));
+    }
+
+    #[test]
+    fn user_exports_store_user_id_and_legacy_records_are_group_scoped() {
+        use mongodb::bson::{from_document, to_document};
+
+        let now = Utc.with_ymd_and_hms(2026, 10, 14, 9, 30, 0).unwrap();
+        let user_id = ObjectId::new();
+        let export = NewReportExport {
+            scope: ExportScope::User,
+            subject_id: user_id,
+            teacher_id: user_id,
+            format: ExportFormat::Pdf,
+            filters: ReportFilters {
+                topic_ids: Vec::new(),
+                period: TimeRange { from: now, to: now },
+                fields: Vec::new(),
+                reveal_user_ids: false,
+                locale: ExportLocale::default(),
+            },
+            expires_at: now,
+        }
+        .into_record();
+        assert_eq!(export.subject_id(), Some(user_id));
+        assert_eq!(export.group_id, None);
+
+        let mut stored = to_document(&export).unwrap();
+        assert_eq!(stored.get_str("scope").unwrap(), "user");
+        assert!(!stored.contains_key("group_id"));
+
+        let group_id = ObjectId::new();
+        stored.remove("scope");
+        stored.remove("user_id");
+        stored.insert("group_id", group_id);
+        let legacy: ReportExport = from_document(stored).unwrap();
+        assert_eq!(legacy.scope, ExportScope::Group);
+        assert_eq!(legacy.subject_id(), Some(group_id));
+    }
+
+    #[test]
+    fn schedule_runs_once_per_period() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 14, 9, 30, 0).unwrap();
+        let mut weekly = schedule(ScheduleCadence::Weekly, 3);
+        assert!(weekly.is_due(now));
+
+        weekly.last_run_at = Some(now);
+        assert!(!weekly.is_due(now + Duration::days(6)));
+        assert!(weekly.is_due(now + Duration::days(7)));
+    }
+}
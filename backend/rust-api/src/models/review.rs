@@ -0,0 +1,72 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use super::user::bson_datetime_as_chrono;
+
+/// Запись очереди повторения (`review_queue`): шаблон, в задании по которому ученик ошибся.
+/// Одна запись на пару (ученик, шаблон)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewItem {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    pub user_id: String,
+    pub template_id: ObjectId,
+    /// Задание, в котором была последняя ошибка; по нему строится сессия, если
+    /// генератор не может выдать новое задание по шаблону
+    pub task_id: String,
+    pub interval_days: u32,
+    pub ease_factor: f64,
+    /// Верных повторений подряд
+    pub repetitions: u32,
+    pub lapses: u32,
+    #[serde(with = "bson_datetime_as_chrono")]
+    pub last_seen: DateTime<Utc>,
+    #[serde(with = "bson_datetime_as_chrono")]
+    pub due_at: DateTime<Utc>,
+    #[serde(rename = "createdAt", with = "bson_datetime_as_chrono")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ReviewItemResponse {
+    pub template_id: String,
+    pub task_id: String,
+    pub interval_days: u32,
+    pub ease_factor: f64,
+    pub repetitions: u32,
+    pub lapses: u32,
+    pub last_seen: DateTime<Utc>,
+    pub due_at: DateTime<Utc>,
+}
+
+impl From<ReviewItem> for ReviewItemResponse {
+    fn from(item: ReviewItem) -> Self {
+        Self {
+            template_id: item.template_id.to_hex(),
+            task_id: item.task_id,
+            interval_days: item.interval_days,
+            ease_factor: item.ease_factor,
+            repetitions: item.repetitions,
+            lapses: item.lapses,
+            last_seen: item.last_seen,
+            due_at: item.due_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ReviewNextQuery {
+    /// Сколько шаблонов вернуть (1-50, по умолчанию 1)
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ReviewNextResponse {
+    /// Шаблоны к повторению, самые просроченные первыми
+    pub items: Vec<ReviewItemResponse>,
+    /// Всего шаблонов, срок повторения которых наступил
+    pub total_due: u64,
+}
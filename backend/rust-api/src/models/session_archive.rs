@@ -0,0 +1,149 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::answer::AttemptRecord;
+use super::user::{bson_datetime_as_chrono, bson_datetime_as_chrono_option};
+use super::{Session, SessionMode, SessionStatus};
+
+/// Завершённая сессия в MongoDB "sessions"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionRecord {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub user_id: String,
+    pub task_id: String,
+    #[serde(default)]
+    pub group_id: Option<String>,
+    #[serde(default)]
+    pub level_id: Option<String>,
+    #[serde(with = "bson_datetime_as_chrono")]
+    pub started_at: DateTime<Utc>,
+    #[serde(with = "bson_datetime_as_chrono")]
+    pub expires_at: DateTime<Utc>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "bson_datetime_as_chrono_option"
+    )]
+    pub completed_at: Option<DateTime<Utc>>,
+    pub status: SessionStatus,
+    pub hints_used: u32,
+    pub score: i32,
+    #[serde(default)]
+    pub mode: SessionMode,
+    /// Принятые ответы задания с `max_attempts`; счётчик ведёт `$inc` при каждом ответе
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attempts_used: Option<u32>,
+}
+
+impl SessionRecord {
+    pub fn from_session(session: Session, completed_at: Option<DateTime<Utc>>) -> Self {
+        SessionRecord {
+            id: session.id,
+            user_id: session.user_id,
+            task_id: session.task_id,
+            group_id: session.group_id,
+            level_id: session.level_id,
+            started_at: session.started_at,
+            expires_at: session.expires_at,
+            completed_at,
+            status: session.status,
+            hints_used: session.hints_used,
+            score: session.score,
+            mode: session.mode,
+            attempts_used: None,
+        }
+    }
+}
+
+impl From<SessionRecord> for Session {
+    fn from(record: SessionRecord) -> Self {
+        Session {
+            id: record.id,
+            user_id: record.user_id,
+            task_id: record.task_id,
+            group_id: record.group_id,
+            started_at: record.started_at,
+            expires_at: record.expires_at,
+            status: record.status,
+            hints_used: record.hints_used,
+            score: record.score,
+            level_id: record.level_id,
+            mode: record.mode,
+        }
+    }
+}
+
+/// Одна строка NDJSON-архива: сессия вместе с её ответами
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedSession {
+    pub session: Session,
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Utc>>,
+    pub attempts: Vec<AttemptRecord>,
+}
+
+/// Заглушка архивированной сессии в MongoDB "session_archive_stubs".
+///
+/// `offset`/`length` указывают на gzip-member сессии внутри архива, поэтому
+/// восстановление читает из хранилища только нужный диапазон байт.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionArchiveStub {
+    #[serde(rename = "_id")]
+    pub session_id: String,
+    pub user_id: String,
+    #[serde(default)]
+    pub group_id: Option<String>,
+    #[serde(with = "bson_datetime_as_chrono")]
+    pub started_at: DateTime<Utc>,
+    pub archive_key: String,
+    pub offset: u64,
+    pub length: u64,
+    pub attempt_count: u64,
+    #[serde(with = "bson_datetime_as_chrono")]
+    pub archived_at: DateTime<Utc>,
+}
+
+/// Временная копия сессии, восстановленная из архива ("sessions_rehydrated", TTL по `expires_at`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RehydratedSession {
+    #[serde(rename = "_id")]
+    pub session_id: String,
+    pub session: SessionRecord,
+    pub attempts: Vec<AttemptRecord>,
+    pub archive_key: String,
+    #[serde(with = "bson_datetime_as_chrono")]
+    pub rehydrated_at: DateTime<Utc>,
+    #[serde(with = "bson_datetime_as_chrono")]
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Итог одного запуска архивации
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ArchiveRunSummary {
+    pub batches: u32,
+    pub sessions_archived: u64,
+    pub attempts_archived: u64,
+    pub bundles_written: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RehydrateSessionResponse {
+    pub session_id: String,
+    pub archive_key: String,
+    pub attempt_count: usize,
+    pub rehydrated_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl From<&RehydratedSession> for RehydrateSessionResponse {
+    fn from(copy: &RehydratedSession) -> Self {
+        RehydrateSessionResponse {
+            session_id: copy.session_id.clone(),
+            archive_key: copy.archive_key.clone(),
+            attempt_count: copy.attempts.len(),
+            rehydrated_at: copy.rehydrated_at,
+            expires_at: copy.expires_at,
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Учётные данные суперпользователя из seed-файла
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SuperuserStatus {
+    pub id: String,
+    pub email: String,
+    /// Пароль из seed-файла или после ротации ещё не сменён владельцем
+    pub seed_password_in_use: bool,
+    pub last_login_at: Option<DateTime<Utc>>,
+    /// Последняя ротация через `SUPERUSER_ROTATE=1`
+    pub password_rotated_at: Option<DateTime<Utc>>,
+}
+
+/// GET /admin/system/superuser-status
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SuperuserStatusResponse {
+    /// Хотя бы один суперпользователь входит с паролем, который знает не только он
+    pub seed_password_in_use: bool,
+    pub superusers: Vec<SuperuserStatus>,
+    pub checked_at: DateTime<Utc>,
+}
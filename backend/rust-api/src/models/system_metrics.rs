@@ -1,6 +1,7 @@
+use chrono::{DateTime, Utc};
 use serde::Serialize;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SystemMetricsResponse {
     pub uptime_seconds: u64,
     pub total_users: u64,
@@ -11,4 +12,44 @@ pub struct SystemMetricsResponse {
     pub critical_incidents: u64,
     pub audit_events_24h: u64,
     pub active_sessions: u64,
+    pub process: ProcessMetrics,
+    pub database: DatabaseMetrics,
+    pub redis: RedisMetrics,
+    /// Когда были сняты данные MongoDB и Redis (они кэшируются на несколько секунд)
+    pub collected_at: DateTime<Utc>,
+}
+
+/// Ресурсы процесса API из `/proc/self`; на других ОС поля пустые
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProcessMetrics {
+    pub pid: u32,
+    pub rss_bytes: Option<u64>,
+    pub threads: Option<u64>,
+    /// Процессорное время (user + system) с момента запуска
+    pub cpu_seconds_total: Option<f64>,
+    /// Загрузка CPU с предыдущего замера (при первом замере - в среднем с момента запуска)
+    pub cpu_percent: Option<f64>,
+}
+
+/// Статистика базы по `dbStats`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DatabaseMetrics {
+    pub name: String,
+    pub collections: u64,
+    pub documents: u64,
+    pub data_size_bytes: u64,
+    pub storage_size_bytes: u64,
+    pub indexes: u64,
+    pub index_size_bytes: u64,
+}
+
+/// Память и клиенты Redis по `INFO memory` и `INFO clients`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RedisMetrics {
+    pub used_memory_bytes: u64,
+    pub used_memory_peak_bytes: u64,
+    /// 0 - лимит памяти не задан
+    pub max_memory_bytes: u64,
+    pub connected_clients: u64,
+    pub blocked_clients: u64,
 }
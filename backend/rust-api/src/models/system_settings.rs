@@ -1,6 +1,10 @@
 use chrono::{DateTime, Utc};
 use mongodb::bson::{oid::ObjectId, Document};
 use serde::{Deserialize, Serialize};
+use validator::{Validate, ValidationError, ValidationErrors};
+
+use super::anticheat::IncidentSeverity;
+use crate::config::{AnticheatSignalSettings, HintSettings, RateLimitSettings, RateLimitWindow};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SystemSetting {
@@ -15,6 +19,7 @@ pub struct SystemSetting {
     pub updated_at: DateTime<Utc>,
 }
 
+/// Хранимые настройки YandexGPT; наружу отдаются только через `YandexGptSettingsView`
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct YandexGptSettings {
     pub api_key: String,
@@ -27,6 +32,20 @@ pub struct YandexGptSettings {
     pub max_tokens: u32,
 }
 
+/// Хранимые настройки OpenAI-совместимого провайдера. Ключ может быть пустым
+/// для локальных серверов (vLLM, Ollama), которым авторизация не нужна
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenAiSettings {
+    pub base_url: String,
+    #[serde(default)]
+    pub api_key: String,
+    pub model: String,
+    #[serde(default = "default_temperature")]
+    pub temperature: f32,
+    #[serde(default = "default_max_tokens")]
+    pub max_tokens: u32,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SsoSettings {
     pub enabled: bool,
@@ -48,6 +67,329 @@ pub struct EmailSettings {
     pub use_tls: bool,
 }
 
+/// Начало замаскированного секрета в ответах API, например `••••1234`
+pub const SECRET_MASK: &str = "••••";
+
+/// Маска секрета: видны только последние 4 символа, короткие секреты скрываются целиком
+pub fn mask_secret(secret: &str) -> String {
+    let chars: Vec<char> = secret.chars().collect();
+    if chars.is_empty() {
+        return String::new();
+    }
+    if chars.len() <= 8 {
+        return SECRET_MASK.to_string();
+    }
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}{}", SECRET_MASK, tail)
+}
+
+/// Секрет из запроса или сохранённый: пустое значение и маска, вернувшаяся из GET,
+/// означают "не менять"
+fn resolve_secret(
+    errors: &mut ValidationErrors,
+    field: &'static str,
+    provided: Option<String>,
+    stored: Option<&str>,
+) -> String {
+    if let Some(secret) = provided
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty() && !value.starts_with(SECRET_MASK))
+    {
+        return secret;
+    }
+    match stored.filter(|value| !value.is_empty()) {
+        Some(secret) => secret.to_string(),
+        None => {
+            violation(errors, field, "required", "must not be empty");
+            String::new()
+        }
+    }
+}
+
+fn validation_errors<T: Validate>(value: &T) -> ValidationErrors {
+    value.validate().err().unwrap_or_default()
+}
+
+fn into_result<T>(value: T, errors: ValidationErrors) -> Result<T, ValidationErrors> {
+    if errors.is_empty() {
+        Ok(value)
+    } else {
+        Err(errors)
+    }
+}
+
+/// PUT /admin/settings/yandexgpt. Без `api_key` (или с маской) остаётся сохранённый ключ
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct YandexGptSettingsUpdate {
+    #[serde(default)]
+    pub api_key: Option<String>,
+    #[validate(length(min = 1, message = "must not be empty"))]
+    pub folder_id: String,
+    #[serde(default = "default_yandex_model")]
+    #[validate(length(min = 1, message = "must not be empty"))]
+    pub model: String,
+    #[serde(default = "default_temperature")]
+    #[validate(range(min = 0.0, max = 1.0, message = "must be between 0 and 1"))]
+    pub temperature: f32,
+    #[serde(default = "default_max_tokens")]
+    #[validate(range(min = 1, max = 8000, message = "must be between 1 and 8000"))]
+    pub max_tokens: u32,
+}
+
+impl YandexGptSettingsUpdate {
+    /// Проверить запрос и подставить сохранённый ключ; все ошибки возвращаются разом
+    pub fn into_settings(
+        self,
+        stored: Option<&YandexGptSettings>,
+    ) -> Result<YandexGptSettings, ValidationErrors> {
+        let mut errors = validation_errors(&self);
+        let api_key = resolve_secret(
+            &mut errors,
+            "api_key",
+            self.api_key,
+            stored.map(|settings| settings.api_key.as_str()),
+        );
+        into_result(
+            YandexGptSettings {
+                api_key,
+                folder_id: self.folder_id.trim().to_string(),
+                model: self.model.trim().to_string(),
+                temperature: self.temperature,
+                max_tokens: self.max_tokens,
+            },
+            errors,
+        )
+    }
+}
+
+/// PUT /admin/settings/openai. Без `api_key` (или с маской) остаётся сохранённый ключ,
+/// пустая строка при отсутствии сохранённого ключа допустима
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct OpenAiSettingsUpdate {
+    #[validate(url(message = "must be a valid URL"))]
+    pub base_url: String,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    #[validate(length(min = 1, message = "must not be empty"))]
+    pub model: String,
+    #[serde(default = "default_temperature")]
+    #[validate(range(min = 0.0, max = 2.0, message = "must be between 0 and 2"))]
+    pub temperature: f32,
+    #[serde(default = "default_max_tokens")]
+    #[validate(range(min = 1, max = 32000, message = "must be between 1 and 32000"))]
+    pub max_tokens: u32,
+}
+
+impl OpenAiSettingsUpdate {
+    pub fn into_settings(
+        self,
+        stored: Option<&OpenAiSettings>,
+    ) -> Result<OpenAiSettings, ValidationErrors> {
+        let errors = validation_errors(&self);
+        let api_key = self
+            .api_key
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty() && !value.starts_with(SECRET_MASK))
+            .or_else(|| stored.map(|settings| settings.api_key.clone()))
+            .unwrap_or_default();
+        into_result(
+            OpenAiSettings {
+                base_url: self.base_url.trim().trim_end_matches('/').to_string(),
+                api_key,
+                model: self.model.trim().to_string(),
+                temperature: self.temperature,
+                max_tokens: self.max_tokens,
+            },
+            errors,
+        )
+    }
+}
+
+/// PUT /admin/settings/sso. Без `client_secret` (или с маской) остаётся сохранённый секрет
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct SsoSettingsUpdate {
+    pub enabled: bool,
+    #[validate(length(min = 1, message = "must not be empty"))]
+    pub provider: String,
+    #[validate(length(min = 1, message = "must not be empty"))]
+    pub client_id: String,
+    #[serde(default)]
+    pub client_secret: Option<String>,
+    #[validate(url(message = "must be a valid URL"))]
+    pub redirect_uri: String,
+}
+
+impl SsoSettingsUpdate {
+    pub fn into_settings(
+        self,
+        stored: Option<&SsoSettings>,
+    ) -> Result<SsoSettings, ValidationErrors> {
+        let mut errors = validation_errors(&self);
+        let client_secret = resolve_secret(
+            &mut errors,
+            "client_secret",
+            self.client_secret,
+            stored.map(|settings| settings.client_secret.as_str()),
+        );
+        into_result(
+            SsoSettings {
+                enabled: self.enabled,
+                provider: self.provider.trim().to_string(),
+                client_id: self.client_id.trim().to_string(),
+                client_secret,
+                redirect_uri: self.redirect_uri,
+            },
+            errors,
+        )
+    }
+}
+
+/// PUT /admin/settings/email. Без `password` (или с маской) остаётся сохранённый пароль
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct EmailSettingsUpdate {
+    #[validate(length(min = 1, max = 253, message = "must be a host name"))]
+    pub server: String,
+    #[validate(range(min = 1, message = "must be between 1 and 65535"))]
+    pub port: u16,
+    #[validate(length(min = 1, message = "must not be empty"))]
+    pub login: String,
+    #[serde(default)]
+    pub password: Option<String>,
+    #[validate(email(message = "Invalid email format"))]
+    pub from_email: String,
+    #[validate(length(min = 1, max = 200, message = "must be 1 to 200 characters"))]
+    pub from_name: String,
+    #[serde(default)]
+    pub use_tls: bool,
+}
+
+impl EmailSettingsUpdate {
+    pub fn into_settings(
+        self,
+        stored: Option<&EmailSettings>,
+    ) -> Result<EmailSettings, ValidationErrors> {
+        let mut errors = validation_errors(&self);
+        if self.server.trim().contains(char::is_whitespace) {
+            violation(&mut errors, "server", "host", "must be a host name");
+        }
+        let password = resolve_secret(
+            &mut errors,
+            "password",
+            self.password,
+            stored.map(|settings| settings.password.as_str()),
+        );
+        into_result(
+            EmailSettings {
+                server: self.server.trim().to_string(),
+                port: self.port,
+                login: self.login.trim().to_string(),
+                password,
+                from_email: self.from_email.trim().to_string(),
+                from_name: self.from_name.trim().to_string(),
+                use_tls: self.use_tls,
+            },
+            errors,
+        )
+    }
+}
+
+/// Настройки YandexGPT в ответах API: ключ только в виде маски
+#[derive(Debug, Clone, Serialize)]
+pub struct YandexGptSettingsView {
+    pub api_key: String,
+    pub has_secret: bool,
+    pub folder_id: String,
+    pub model: String,
+    pub temperature: f32,
+    pub max_tokens: u32,
+}
+
+impl From<&YandexGptSettings> for YandexGptSettingsView {
+    fn from(settings: &YandexGptSettings) -> Self {
+        Self {
+            api_key: mask_secret(&settings.api_key),
+            has_secret: !settings.api_key.is_empty(),
+            folder_id: settings.folder_id.clone(),
+            model: settings.model.clone(),
+            temperature: settings.temperature,
+            max_tokens: settings.max_tokens,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenAiSettingsView {
+    pub base_url: String,
+    pub api_key: String,
+    pub has_secret: bool,
+    pub model: String,
+    pub temperature: f32,
+    pub max_tokens: u32,
+}
+
+impl From<&OpenAiSettings> for OpenAiSettingsView {
+    fn from(settings: &OpenAiSettings) -> Self {
+        Self {
+            base_url: settings.base_url.clone(),
+            api_key: mask_secret(&settings.api_key),
+            has_secret: !settings.api_key.is_empty(),
+            model: settings.model.clone(),
+            temperature: settings.temperature,
+            max_tokens: settings.max_tokens,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SsoSettingsView {
+    pub enabled: bool,
+    pub provider: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub has_secret: bool,
+    pub redirect_uri: String,
+}
+
+impl From<&SsoSettings> for SsoSettingsView {
+    fn from(settings: &SsoSettings) -> Self {
+        Self {
+            enabled: settings.enabled,
+            provider: settings.provider.clone(),
+            client_id: settings.client_id.clone(),
+            client_secret: mask_secret(&settings.client_secret),
+            has_secret: !settings.client_secret.is_empty(),
+            redirect_uri: settings.redirect_uri.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EmailSettingsView {
+    pub server: String,
+    pub port: u16,
+    pub login: String,
+    pub password: String,
+    pub has_secret: bool,
+    pub from_email: String,
+    pub from_name: String,
+    pub use_tls: bool,
+}
+
+impl From<&EmailSettings> for EmailSettingsView {
+    fn from(settings: &EmailSettings) -> Self {
+        Self {
+            server: settings.server.clone(),
+            port: settings.port,
+            login: settings.login.clone(),
+            password: mask_secret(&settings.password),
+            has_secret: !settings.password.is_empty(),
+            from_email: settings.from_email.clone(),
+            from_name: settings.from_name.clone(),
+            use_tls: settings.use_tls,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnticheatSettings {
     pub speed_threshold_seconds: u32,
@@ -56,14 +398,508 @@ pub struct AnticheatSettings {
     pub block_duration_hours: u32,
     pub captcha_enabled: bool,
     pub captcha_threshold: u32,
+    /// С какого числа ответов в окне пользователь помечается как подозрительный
+    #[serde(default = "default_suspicious_speed_hits")]
+    pub suspicious_speed_hits: u32,
+    /// Окно подсчёта ответов в секундах
+    #[serde(default = "default_anticheat_window_seconds")]
+    pub time_window_seconds: u32,
+    #[serde(default = "default_auto_block")]
+    pub auto_block: bool,
+    /// Минимальная серьёзность инцидента, при которой срабатывает автоблокировка
+    #[serde(default = "default_auto_block_min_severity")]
+    pub auto_block_min_severity: Option<IncidentSeverity>,
+    /// Пороги сигналов клиента; без них действуют `ANTICHEAT_*_THRESHOLD` из конфигурации
+    #[serde(default)]
+    pub signal_thresholds: Option<AnticheatSignalThresholds>,
+    /// Группы, ученики которых не блокируются автоматически (инциденты только помечаются)
+    #[serde(default)]
+    pub whitelisted_group_ids: Vec<String>,
+    /// Пороги медианного времени ответа, ниже которых сессия получает сигнал `fast_answers`
+    #[serde(default)]
+    pub answer_latency: AnswerLatencyThresholds,
+}
+
+/// Медиана времени ответа сессии (мс) ниже порога сложности задания даёт сигнал
+/// низкой серьёзности; инцидент не открывается. 0 выключает проверку для сложности
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnswerLatencyThresholds {
+    pub a1_ms: u32,
+    pub a2_ms: u32,
+    pub b1_ms: u32,
+    pub b2_ms: u32,
+    /// Меньше ответов - медиана не считается
+    pub min_answers: u32,
+}
+
+impl Default for AnswerLatencyThresholds {
+    fn default() -> Self {
+        Self {
+            a1_ms: 1000,
+            a2_ms: 1500,
+            b1_ms: 2000,
+            b2_ms: 3000,
+            min_answers: 3,
+        }
+    }
+}
+
+impl AnswerLatencyThresholds {
+    /// Порог по сложности задания (`a1`..`b2`); неизвестная сложность - самый мягкий порог `a1`
+    pub fn threshold_ms(&self, difficulty: Option<&str>) -> u32 {
+        match difficulty.map(str::to_lowercase).as_deref() {
+            Some("a2") => self.a2_ms,
+            Some("b1") => self.b1_ms,
+            Some("b2") => self.b2_ms,
+            _ => self.a1_ms,
+        }
+    }
+}
+
+/// Инцидент открывается, когда сигналов типа за сессию больше порога
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnticheatSignalThresholds {
+    pub tab_switch: u32,
+    pub paste: u32,
+    pub devtools: u32,
+}
+
+/// Значения, которые действовали до появления настроек в админке
+impl Default for AnticheatSettings {
+    fn default() -> Self {
+        Self {
+            speed_threshold_seconds: 5,
+            max_speed_hits: 10,
+            max_repeated_hits: 8,
+            block_duration_hours: 24,
+            captcha_enabled: false,
+            captcha_threshold: 3,
+            suspicious_speed_hits: default_suspicious_speed_hits(),
+            time_window_seconds: default_anticheat_window_seconds(),
+            auto_block: default_auto_block(),
+            auto_block_min_severity: default_auto_block_min_severity(),
+            signal_thresholds: None,
+            whitelisted_group_ids: Vec::new(),
+            answer_latency: AnswerLatencyThresholds::default(),
+        }
+    }
+}
+
+pub const ANTICHEAT_MIN_WINDOW_SECONDS: u32 = 60;
+pub const ANTICHEAT_MAX_WINDOW_SECONDS: u32 = 86_400;
+
+fn check_range(errors: &mut ValidationErrors, field: &'static str, value: u32, min: u32, max: u32) {
+    if !(min..=max).contains(&value) {
+        errors.add(
+            field,
+            ValidationError::new("range")
+                .with_message(format!("must be between {} and {}", min, max).into()),
+        );
+    }
+}
+
+fn violation(
+    errors: &mut ValidationErrors,
+    field: &'static str,
+    code: &'static str,
+    message: &str,
+) {
+    errors.add(
+        field,
+        ValidationError::new(code).with_message(message.to_string().into()),
+    );
+}
+
+/// Диапазоны и согласованность полей проверяются вместе, чтобы админ
+/// увидел все проблемы за один запрос
+impl Validate for AnticheatSettings {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+
+        check_range(
+            &mut errors,
+            "speed_threshold_seconds",
+            self.speed_threshold_seconds,
+            1,
+            300,
+        );
+        check_range(&mut errors, "max_speed_hits", self.max_speed_hits, 1, 1000);
+        check_range(
+            &mut errors,
+            "max_repeated_hits",
+            self.max_repeated_hits,
+            1,
+            1000,
+        );
+        check_range(
+            &mut errors,
+            "suspicious_speed_hits",
+            self.suspicious_speed_hits,
+            1,
+            1000,
+        );
+        check_range(
+            &mut errors,
+            "block_duration_hours",
+            self.block_duration_hours,
+            1,
+            720,
+        );
+        check_range(
+            &mut errors,
+            "time_window_seconds",
+            self.time_window_seconds,
+            ANTICHEAT_MIN_WINDOW_SECONDS,
+            ANTICHEAT_MAX_WINDOW_SECONDS,
+        );
+
+        if self.suspicious_speed_hits >= self.max_speed_hits {
+            violation(
+                &mut errors,
+                "suspicious_speed_hits",
+                "threshold_order",
+                "must be lower than max_speed_hits",
+            );
+        }
+        if self.auto_block && self.auto_block_min_severity.is_none() {
+            violation(
+                &mut errors,
+                "auto_block_min_severity",
+                "required",
+                "auto_block requires a severity threshold",
+            );
+        }
+        if let Some(thresholds) = &self.signal_thresholds {
+            for (field, value) in [
+                ("signal_thresholds.tab_switch", thresholds.tab_switch),
+                ("signal_thresholds.paste", thresholds.paste),
+                ("signal_thresholds.devtools", thresholds.devtools),
+            ] {
+                check_range(&mut errors, field, value, 1, 1000);
+            }
+        }
+        for (field, value) in [
+            ("answer_latency.a1_ms", self.answer_latency.a1_ms),
+            ("answer_latency.a2_ms", self.answer_latency.a2_ms),
+            ("answer_latency.b1_ms", self.answer_latency.b1_ms),
+            ("answer_latency.b2_ms", self.answer_latency.b2_ms),
+        ] {
+            check_range(&mut errors, field, value, 0, 60_000);
+        }
+        check_range(
+            &mut errors,
+            "answer_latency.min_answers",
+            self.answer_latency.min_answers,
+            1,
+            100,
+        );
+        if self
+            .whitelisted_group_ids
+            .iter()
+            .any(|group_id| ObjectId::parse_str(group_id).is_err())
+        {
+            violation(
+                &mut errors,
+                "whitelisted_group_ids",
+                "invalid_group_id",
+                "must contain group ObjectIds",
+            );
+        }
+        if self.captcha_enabled {
+            check_range(
+                &mut errors,
+                "captcha_threshold",
+                self.captcha_threshold,
+                1,
+                1000,
+            );
+            if self.captcha_threshold > self.max_speed_hits {
+                violation(
+                    &mut errors,
+                    "captcha_threshold",
+                    "threshold_order",
+                    "must not exceed max_speed_hits",
+                );
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Лимиты запросов из админки поверх RATE_LIMIT_*; пустое поле - значение из конфигурации.
+/// Вступают в силу после `POST /admin/settings/reload`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimitOverrides {
+    #[serde(default)]
+    pub per_user: Option<RateLimitWindow>,
+    #[serde(default)]
+    pub per_ip: Option<RateLimitWindow>,
+    #[serde(default)]
+    pub admin_per_user: Option<RateLimitWindow>,
+    #[serde(default)]
+    pub admin_per_ip: Option<RateLimitWindow>,
+    #[serde(default)]
+    pub login: Option<RateLimitWindow>,
+    #[serde(default)]
+    pub register: Option<RateLimitWindow>,
+}
+
+pub const RATE_LIMIT_MAX_WINDOW_SECS: u64 = 86_400;
+
+impl RateLimitOverrides {
+    fn windows(&self) -> [(&'static str, Option<RateLimitWindow>); 6] {
+        [
+            ("per_user", self.per_user),
+            ("per_ip", self.per_ip),
+            ("admin_per_user", self.admin_per_user),
+            ("admin_per_ip", self.admin_per_ip),
+            ("login", self.login),
+            ("register", self.register),
+        ]
+    }
+
+    /// Наложить заданные окна на лимиты из конфигурации
+    pub fn apply(&self, settings: &mut RateLimitSettings) {
+        for (target, window) in [
+            (&mut settings.per_user, self.per_user),
+            (&mut settings.per_ip, self.per_ip),
+            (&mut settings.admin_per_user, self.admin_per_user),
+            (&mut settings.admin_per_ip, self.admin_per_ip),
+            (&mut settings.login, self.login),
+            (&mut settings.register, self.register),
+        ] {
+            if let Some(window) = window {
+                *target = window;
+            }
+        }
+    }
+}
+
+impl Validate for RateLimitOverrides {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        for (field, window) in self.windows() {
+            let Some(window) = window else {
+                continue;
+            };
+            if window.limit == 0 {
+                violation(&mut errors, field, "range", "limit must be at least 1");
+            }
+            if !(1..=RATE_LIMIT_MAX_WINDOW_SECS).contains(&window.window_secs) {
+                errors.add(
+                    field,
+                    ValidationError::new("range").with_message(
+                        format!(
+                            "window_secs must be between 1 and {}",
+                            RATE_LIMIT_MAX_WINDOW_SECS
+                        )
+                        .into(),
+                    ),
+                );
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Настройка, которую `POST /admin/settings/reload` перечитывает без перезапуска
+#[derive(Debug, Clone, Serialize)]
+pub struct ReloadableSetting {
+    pub key: &'static str,
+    /// Откуда берётся значение при перезагрузке
+    pub source: &'static str,
+}
+
+/// Действующий снимок перезагружаемых настроек этого экземпляра API
+#[derive(Debug, Clone, Serialize)]
+pub struct RuntimeSettingsView {
+    pub loaded_at: DateTime<Utc>,
+    pub reloadable: Vec<ReloadableSetting>,
+    pub rate_limit: RateLimitSettings,
+    pub anticheat_signals: AnticheatSignalSettings,
+    pub hints: HintSettings,
+    pub cors_allowed_origins: Vec<String>,
+}
+
+/// Согласия на обработку данных, обязательные для учеников в данной инсталляции
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsentSettings {
+    /// Типы согласий, без которых ученик не может начать сессию
+    #[serde(default)]
+    pub required_consents: Vec<String>,
+    /// Текущая версия документа согласия
+    #[serde(default = "default_consent_document_version")]
+    pub document_version: String,
+    /// Если true, согласия на предыдущие версии документа перестают действовать
+    #[serde(default)]
+    pub require_reconsent_on_version_bump: bool,
+}
+
+impl Default for ConsentSettings {
+    fn default() -> Self {
+        Self {
+            required_consents: Vec::new(),
+            document_version: default_consent_document_version(),
+            require_reconsent_on_version_bump: false,
+        }
+    }
+}
+
+/// Сколько шаблонов ПДн можно завести и насколько они могут быть сложными
+pub const PII_MAX_PATTERNS: usize = 50;
+pub const PII_MAX_REGEX_LEN: usize = 500;
+/// Предел размера скомпилированного выражения: `regex` работает за линейное время,
+/// но огромные повторения вида `a{1000}{1000}` раздувают автомат
+const PII_REGEX_SIZE_LIMIT: usize = 256 * 1024;
+
+/// Что делать с найденными персональными данными
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PiiSeverity {
+    /// Шаблон не сохраняется
+    Block,
+    /// Шаблон сохраняется, совпадение попадает в `pii_flags`
+    Warn,
+}
+
+/// Шаблон поиска персональных данных в содержимом шаблонов заданий
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PiiPattern {
+    /// Имя в отчётах и `pii_flags`: строчные латинские буквы, цифры и `_`
+    pub name: String,
+    pub regex: String,
+    pub severity: PiiSeverity,
+}
+
+impl PiiPattern {
+    fn new(name: &str, regex: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            regex: regex.to_string(),
+            severity: PiiSeverity::Block,
+        }
+    }
+
+    /// Компиляция с ограничением размера; выражения, совпадающие с пустой строкой,
+    /// отклоняются - они отметили бы любое поле
+    pub fn compile(&self) -> Result<regex::Regex, String> {
+        if self.regex.len() > PII_MAX_REGEX_LEN {
+            return Err(format!(
+                "regex must be at most {} characters",
+                PII_MAX_REGEX_LEN
+            ));
+        }
+        let regex = regex::RegexBuilder::new(&self.regex)
+            .size_limit(PII_REGEX_SIZE_LIMIT)
+            .dfa_size_limit(PII_REGEX_SIZE_LIMIT)
+            .build()
+            .map_err(|err| err.to_string())?;
+        if regex.is_match("") {
+            return Err("regex must not match an empty string".to_string());
+        }
+        Ok(regex)
+    }
+}
+
+/// Шаблоны ПДн, которыми проверяется содержимое, параметры и метаданные шаблонов заданий
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PiiSettings {
+    #[serde(default)]
+    pub patterns: Vec<PiiPattern>,
+}
+
+impl Default for PiiSettings {
+    fn default() -> Self {
+        Self {
+            patterns: vec![
+                PiiPattern::new("email", r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}"),
+                PiiPattern::new("phone", r"\b\d{10,}\b"),
+            ],
+        }
+    }
+}
+
+impl Validate for PiiSettings {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        if self.patterns.len() > PII_MAX_PATTERNS {
+            violation(
+                &mut errors,
+                "patterns",
+                "length",
+                &format!("at most {} patterns are allowed", PII_MAX_PATTERNS),
+            );
+        }
+
+        let mut names = std::collections::HashSet::new();
+        for pattern in &self.patterns {
+            let valid_name = !pattern.name.is_empty()
+                && pattern.name.len() <= 64
+                && pattern
+                    .name
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+            if !valid_name {
+                violation(
+                    &mut errors,
+                    "patterns",
+                    "name",
+                    &format!(
+                        "pattern name '{}' must be 1-64 lowercase letters, digits or '_'",
+                        pattern.name
+                    ),
+                );
+            } else if !names.insert(pattern.name.as_str()) {
+                violation(
+                    &mut errors,
+                    "patterns",
+                    "duplicate",
+                    &format!("pattern name '{}' is used twice", pattern.name),
+                );
+            }
+            if let Err(message) = pattern.compile() {
+                violation(
+                    &mut errors,
+                    "patterns",
+                    "regex",
+                    &format!("pattern '{}': {}", pattern.name, message),
+                );
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Default)]
 pub struct SystemSettingsResponse {
-    pub yandexgpt: Option<YandexGptSettings>,
-    pub sso: Option<SsoSettings>,
-    pub email: Option<EmailSettings>,
+    /// Провайдер LLM, выбранный в конфигурации (`LLM_PROVIDER`)
+    pub llm_provider: String,
+    pub yandexgpt: Option<YandexGptSettingsView>,
+    pub openai: Option<OpenAiSettingsView>,
+    pub sso: Option<SsoSettingsView>,
+    pub email: Option<EmailSettingsView>,
     pub anticheat: Option<AnticheatSettings>,
+    pub consent: Option<ConsentSettings>,
+    pub pii: Option<PiiSettings>,
+    /// Сохранённые переопределения лимитов (действуют после перезагрузки)
+    pub rate_limits: Option<RateLimitOverrides>,
+    /// Что действует сейчас и что из этого перезагружается
+    pub runtime: Option<RuntimeSettingsView>,
 }
 
 #[derive(Debug, Serialize)]
@@ -72,6 +908,62 @@ pub struct SettingsTestResponse {
     pub message: Option<String>,
 }
 
+/// GET /admin/settings/jwt-keys: ключи подписи JWT без секретов. Счётчики -
+/// с момента запуска этого экземпляра API
+#[derive(Debug, Serialize)]
+pub struct JwtKeysResponse {
+    pub active_kid: String,
+    pub keys: Vec<JwtKeyUsage>,
+    /// Токены, принятые по `JWT_FALLBACK_SECRETS` (или `JWT_SECRET` при заданной карте ключей)
+    pub fallback_verified: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JwtKeyUsage {
+    pub kid: String,
+    pub active: bool,
+    pub issued: u64,
+    pub verified: u64,
+}
+
+/// POST /admin/settings/test/email. Без `settings` проверяются сохранённые настройки
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct EmailTestRequest {
+    #[validate(email(message = "Invalid email format"))]
+    pub recipient: String,
+    #[serde(default)]
+    pub settings: Option<EmailSettingsUpdate>,
+}
+
+/// Итог проверки SMTP: на каком шаге и почему она остановилась
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmailTestStatus {
+    /// Тестовое письмо принято сервером
+    Sent,
+    /// Подключение и авторизация прошли, отправка отключена `EMAIL_SEND_DISABLED`
+    HandshakeOk,
+    NotConfigured,
+    InvalidAddress,
+    DnsResolutionFailed,
+    ConnectionFailed,
+    TlsError,
+    AuthRejected,
+    RecipientRefused,
+    SendFailed,
+    Timeout,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EmailTestResponse {
+    pub success: bool,
+    pub status: EmailTestStatus,
+    pub message: Option<String>,
+    /// Код ответа SMTP-сервера, если ошибку вернул сервер
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub smtp_code: Option<u16>,
+}
+
 fn default_yandex_model() -> String {
     "yandexgpt".to_string()
 }
@@ -84,6 +976,26 @@ fn default_max_tokens() -> u32 {
     500
 }
 
+fn default_suspicious_speed_hits() -> u32 {
+    5
+}
+
+fn default_anticheat_window_seconds() -> u32 {
+    3600
+}
+
+fn default_auto_block() -> bool {
+    true
+}
+
+fn default_auto_block_min_severity() -> Option<IncidentSeverity> {
+    Some(IncidentSeverity::Critical)
+}
+
+fn default_consent_document_version() -> String {
+    "1".to_string()
+}
+
 // Reuse chrono conversion helpers
 pub(super) mod bson_datetime_as_chrono {
     use chrono::{DateTime, Utc};
@@ -106,3 +1018,210 @@ pub(super) mod bson_datetime_as_chrono {
         Ok(DateTime::from_timestamp_millis(bson_dt.timestamp_millis()).expect("valid timestamp"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn anticheat() -> AnticheatSettings {
+        AnticheatSettings::default()
+    }
+
+    #[test]
+    fn test_default_pii_settings_are_valid() {
+        assert!(PiiSettings::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_pii_settings_reject_invalid_patterns() {
+        let settings = PiiSettings {
+            patterns: vec![
+                PiiPattern::new("student_id", r"\bST-\d{6}\b"),
+                PiiPattern::new("student_id", r"\bID\d+"),
+                PiiPattern::new("Broken Name", r"[unclosed"),
+                PiiPattern::new("anything", r"\d*"),
+                PiiPattern::new("huge", r"(\w{100}){100}"),
+            ],
+        };
+        let errors = settings.validate().unwrap_err();
+        let messages: Vec<String> = errors.field_errors()["patterns"]
+            .iter()
+            .map(|error| error.message.clone().unwrap().to_string())
+            .collect();
+        assert_eq!(messages.len(), 5, "{messages:?}");
+        assert!(messages[0].contains("used twice"));
+        assert!(messages[2].contains("pattern 'Broken Name'"));
+        assert!(messages[3].contains("empty string"));
+        assert!(messages[4].starts_with("pattern 'huge'"));
+    }
+
+    #[test]
+    fn test_default_anticheat_settings_are_valid() {
+        assert!(anticheat().validate().is_ok());
+    }
+
+    #[test]
+    fn test_legacy_anticheat_document_gets_defaults() {
+        let legacy = serde_json::json!({
+            "speed_threshold_seconds": 5,
+            "max_speed_hits": 10,
+            "max_repeated_hits": 8,
+            "block_duration_hours": 24,
+            "captcha_enabled": false,
+            "captcha_threshold": 3,
+        });
+        let settings: AnticheatSettings = serde_json::from_value(legacy).unwrap();
+        assert!(settings.auto_block);
+        assert_eq!(
+            settings.auto_block_min_severity,
+            Some(IncidentSeverity::Critical)
+        );
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    fn test_anticheat_validation_reports_all_violations() {
+        let settings = AnticheatSettings {
+            suspicious_speed_hits: 12,
+            time_window_seconds: 10,
+            auto_block_min_severity: None,
+            captcha_enabled: true,
+            captcha_threshold: 50,
+            ..anticheat()
+        };
+
+        let errors = settings.validate().unwrap_err();
+        let fields = errors.field_errors();
+        assert!(fields.contains_key("suspicious_speed_hits"));
+        assert!(fields.contains_key("time_window_seconds"));
+        assert!(fields.contains_key("auto_block_min_severity"));
+        assert!(fields.contains_key("captcha_threshold"));
+        assert_eq!(fields.len(), 4);
+    }
+
+    fn email_update(password: Option<&str>) -> EmailSettingsUpdate {
+        EmailSettingsUpdate {
+            server: "smtp.example.com".into(),
+            port: 587,
+            login: "mailer".into(),
+            password: password.map(str::to_string),
+            from_email: "noreply@example.com".into(),
+            from_name: "TrainingGround".into(),
+            use_tls: true,
+        }
+    }
+
+    #[test]
+    fn test_secret_mask_shows_only_last_four_chars() {
+        assert_eq!(mask_secret("AQVN-secret-key-1234"), "••••1234");
+        assert_eq!(mask_secret("short"), "••••");
+        assert_eq!(mask_secret(""), "");
+    }
+
+    #[test]
+    fn test_masked_or_missing_secret_keeps_stored_value() {
+        let stored = email_update(Some("stored-password-9876"))
+            .into_settings(None)
+            .unwrap();
+
+        for provided in [None, Some("••••9876"), Some("  ")] {
+            let settings = email_update(provided).into_settings(Some(&stored)).unwrap();
+            assert_eq!(settings.password, "stored-password-9876");
+        }
+        let settings = email_update(Some("new-password"))
+            .into_settings(Some(&stored))
+            .unwrap();
+        assert_eq!(settings.password, "new-password");
+    }
+
+    #[test]
+    fn test_settings_update_reports_field_errors() {
+        let update = EmailSettingsUpdate {
+            server: "smtp example".into(),
+            port: 0,
+            from_email: "not-an-email".into(),
+            ..email_update(None)
+        };
+        let errors = update.into_settings(None).unwrap_err();
+        let fields = errors.field_errors();
+        assert!(fields.contains_key("server"));
+        assert!(fields.contains_key("port"));
+        assert!(fields.contains_key("from_email"));
+        assert!(fields.contains_key("password"));
+
+        let sso: SsoSettingsUpdate = serde_json::from_value(serde_json::json!({
+            "enabled": true,
+            "provider": "",
+            "client_id": "client",
+            "redirect_uri": "not a url",
+        }))
+        .unwrap();
+        let errors = sso.into_settings(None).unwrap_err();
+        let fields = errors.field_errors();
+        assert!(fields.contains_key("provider"));
+        assert!(fields.contains_key("redirect_uri"));
+        assert!(fields.contains_key("client_secret"));
+    }
+
+    #[test]
+    fn test_signal_thresholds_and_whitelist_are_validated() {
+        let settings = AnticheatSettings {
+            signal_thresholds: Some(AnticheatSignalThresholds {
+                tab_switch: 0,
+                paste: 5,
+                devtools: 2000,
+            }),
+            whitelisted_group_ids: vec!["not-a-group".to_string()],
+            ..anticheat()
+        };
+        let errors = settings.validate().unwrap_err();
+        let fields = errors.field_errors();
+        assert!(fields.contains_key("signal_thresholds.tab_switch"));
+        assert!(fields.contains_key("signal_thresholds.devtools"));
+        assert!(!fields.contains_key("signal_thresholds.paste"));
+        assert!(fields.contains_key("whitelisted_group_ids"));
+
+        let settings = AnticheatSettings {
+            signal_thresholds: Some(AnticheatSignalThresholds {
+                tab_switch: 1,
+                paste: 1,
+                devtools: 1,
+            }),
+            whitelisted_group_ids: vec![ObjectId::new().to_hex()],
+            ..anticheat()
+        };
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    fn test_answer_latency_thresholds_are_validated() {
+        let settings = AnticheatSettings {
+            answer_latency: AnswerLatencyThresholds {
+                b2_ms: 120_000,
+                min_answers: 0,
+                ..AnswerLatencyThresholds::default()
+            },
+            ..anticheat()
+        };
+        let errors = settings.validate().unwrap_err();
+        let fields = errors.field_errors();
+        assert!(fields.contains_key("answer_latency.b2_ms"));
+        assert!(fields.contains_key("answer_latency.min_answers"));
+        assert_eq!(fields.len(), 2);
+
+        let thresholds = AnswerLatencyThresholds::default();
+        assert_eq!(thresholds.threshold_ms(Some("B2")), thresholds.b2_ms);
+        assert_eq!(thresholds.threshold_ms(Some("medium")), thresholds.a1_ms);
+        assert_eq!(thresholds.threshold_ms(None), thresholds.a1_ms);
+    }
+
+    #[test]
+    fn test_auto_block_disabled_needs_no_severity() {
+        let settings = AnticheatSettings {
+            auto_block: false,
+            auto_block_min_severity: None,
+            ..anticheat()
+        };
+        assert!(settings.validate().is_ok());
+    }
+}
@@ -1,11 +1,19 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 
+use super::achievement::AchievementKind;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(tag = "type", rename_all = "kebab-case")]
 pub enum TimerEvent {
     TimerTick(TimerTick),
     TimeExpired(TimeExpired),
+    /// Сервер отклонил ответ после истечения сессии и перевёл её в `expired`
+    Expired(SessionExpired),
+    /// Ученик получил достижение (во время сессии или при её завершении)
+    AchievementUnlocked(AchievementUnlocked),
+    /// Исчерпан лимит ответов задания; сессия завершена с лучшим результатом
+    AttemptsExhausted(AttemptsExhausted),
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -24,6 +32,29 @@ pub struct TimeExpired {
     pub message: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SessionExpired {
+    pub session_id: String,
+    pub expires_at: DateTime<Utc>,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AchievementUnlocked {
+    pub session_id: String,
+    pub achievement: AchievementKind,
+    pub unlocked_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AttemptsExhausted {
+    pub session_id: String,
+    pub max_attempts: u32,
+    /// Итоговый счёт сессии с учётом штрафа за подсказки
+    pub score: i32,
+    pub timestamp: DateTime<Utc>,
+}
+
 impl TimerEvent {
     pub fn to_sse_data(&self) -> String {
         serde_json::to_string(self).unwrap_or_else(|_| "{}".to_string())
@@ -33,6 +64,66 @@ impl TimerEvent {
         match self {
             TimerEvent::TimerTick(_) => "timer-tick",
             TimerEvent::TimeExpired(_) => "time-expired",
+            TimerEvent::Expired(_) => "expired",
+            TimerEvent::AchievementUnlocked(_) => "achievement-unlocked",
+            TimerEvent::AttemptsExhausted(_) => "attempts-exhausted",
         }
     }
+
+    /// После такого события стрим сессии закрывается
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            TimerEvent::TimeExpired(_) | TimerEvent::Expired(_) | TimerEvent::AttemptsExhausted(_)
+        )
+    }
+}
+
+/// Последний момент, когда ещё принимаются ответы: `expires_at` плюс льготный период
+pub fn submission_deadline(expires_at: DateTime<Utc>, grace_seconds: u64) -> DateTime<Utc> {
+    let grace = i64::try_from(grace_seconds).unwrap_or(i64::MAX);
+    expires_at
+        .checked_add_signed(Duration::try_seconds(grace).unwrap_or(Duration::MAX))
+        .unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
+
+/// Сессия истекла с учётом льготного периода (сам дедлайн ещё допустим)
+pub fn is_past_deadline(expires_at: DateTime<Utc>, grace_seconds: u64, now: DateTime<Utc>) -> bool {
+    now > submission_deadline(expires_at, grace_seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grace_window_extends_deadline() {
+        let expires_at = Utc::now();
+        let at = |seconds: i64| expires_at + Duration::seconds(seconds);
+
+        assert!(!is_past_deadline(expires_at, 0, at(0)));
+        assert!(is_past_deadline(expires_at, 0, at(1)));
+        assert!(!is_past_deadline(expires_at, 5, at(3)));
+        assert!(!is_past_deadline(expires_at, 5, at(5)));
+        assert!(is_past_deadline(expires_at, 5, at(6)));
+        assert!(!is_past_deadline(expires_at, 5, at(-60)));
+        assert_eq!(submission_deadline(expires_at, 5), at(5));
+        assert_eq!(
+            submission_deadline(expires_at, u64::MAX),
+            DateTime::<Utc>::MAX_UTC
+        );
+    }
+
+    #[test]
+    fn expired_event_is_tagged_for_sse() {
+        let event = TimerEvent::Expired(SessionExpired {
+            session_id: "s-1".to_string(),
+            expires_at: Utc::now(),
+            timestamp: Utc::now(),
+        });
+        assert_eq!(event.event_name(), "expired");
+        let data: serde_json::Value = serde_json::from_str(&event.to_sse_data()).unwrap();
+        assert_eq!(data["type"], "expired");
+        assert_eq!(data["session_id"], "s-1");
+    }
 }
@@ -1,7 +1,11 @@
 use chrono::{DateTime, Utc};
 use mongodb::bson::{oid::ObjectId, Document};
 use serde::{Deserialize, Serialize};
-use validator::Validate;
+use utoipa::{IntoParams, ToSchema};
+use validator::{Validate, ValidationError};
+
+use crate::models::content::is_valid_locale;
+use crate::utils::pagination::SortKey;
 
 /// User model stored in MongoDB "users" collection
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -47,10 +51,33 @@ pub struct User {
         skip_serializing_if = "Option::is_none"
     )]
     pub block_reason: Option<String>,
+
+    /// Год рождения ученика (используется для определения возрастной категории контента)
+    #[serde(rename = "birthYear", default, skip_serializing_if = "Option::is_none")]
+    pub birth_year: Option<i32>,
+
+    /// Часовой пояс (IANA), по которому считаются дни серии занятий
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+
+    /// Язык контента; без него шаблоны выдаются на языке по умолчанию
+    #[serde(
+        rename = "preferredLocale",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub preferred_locale: Option<String>,
+}
+
+impl User {
+    /// Блокировка действует: бессрочная или её срок ещё не истёк
+    pub fn block_active(&self, now: DateTime<Utc>) -> bool {
+        self.is_blocked && self.blocked_until.is_none_or(|until| until > now)
+    }
 }
 
 // Serde converters for chrono::DateTime <-> mongodb::bson::DateTime
-pub(super) mod bson_datetime_as_chrono {
+pub(crate) mod bson_datetime_as_chrono {
     use chrono::{DateTime, Utc};
     use mongodb::bson;
     use serde::{Deserialize, Deserializer, Serialize, Serializer};
@@ -72,7 +99,7 @@ pub(super) mod bson_datetime_as_chrono {
     }
 }
 
-pub(super) mod bson_datetime_as_chrono_option {
+pub(crate) mod bson_datetime_as_chrono_option {
     use chrono::{DateTime, Utc};
     use mongodb::bson;
     use serde::{Deserialize, Deserializer, Serializer};
@@ -104,7 +131,10 @@ pub(super) mod bson_datetime_as_chrono_option {
 /// - Student: ученик
 /// - Teacher: учитель/куратор
 /// - ContentAdmin: администратор контента (шаблоны, темы, правила)
+/// - ContentModerator: модератор шаблонов без доступа к пользователям
 /// - Admin: системный администратор (пользователи, группы, настройки)
+/// - Custom: роль, настроенная через `PUT /admin/roles/{role}`; назначается, только если
+///   для неё есть документ в `role_permissions`
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum UserRole {
@@ -113,22 +143,56 @@ pub enum UserRole {
     Teacher,
     #[serde(rename = "content_admin")]
     ContentAdmin,
+    #[serde(rename = "content_moderator")]
+    ContentModerator,
     Admin,
+    #[serde(untagged)]
+    Custom(String),
+}
+
+// Схема - строка: кроме встроенных ролей допустимы настроенные
+impl utoipa::PartialSchema for UserRole {
+    fn schema() -> utoipa::openapi::RefOr<utoipa::openapi::schema::Schema> {
+        utoipa::openapi::schema::ObjectBuilder::new()
+            .schema_type(utoipa::openapi::schema::Type::String)
+            .description(Some(
+                "student, teacher, content_admin, content_moderator, admin или роль из /admin/roles",
+            ))
+            .examples([serde_json::json!("student")])
+            .into()
+    }
 }
 
+impl ToSchema for UserRole {}
+
 impl UserRole {
+    pub const ALL: [UserRole; 5] = [
+        UserRole::Student,
+        UserRole::Teacher,
+        UserRole::ContentAdmin,
+        UserRole::ContentModerator,
+        UserRole::Admin,
+    ];
+
     pub fn as_str(&self) -> &str {
         match self {
             UserRole::Student => "student",
             UserRole::Teacher => "teacher",
             UserRole::ContentAdmin => "content_admin",
+            UserRole::ContentModerator => "content_moderator",
             UserRole::Admin => "admin",
+            UserRole::Custom(role) => role,
         }
     }
+
+    pub fn is_built_in(&self) -> bool {
+        !matches!(self, UserRole::Custom(_))
+    }
 }
 
 /// User profile returned to client (without sensitive data)
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(feature = "client", derive(Deserialize))]
 pub struct UserProfile {
     pub id: String,
     pub email: String,
@@ -137,6 +201,7 @@ pub struct UserProfile {
     pub group_ids: Vec<String>,
     pub created_at: DateTime<Utc>,
     pub last_login_at: Option<DateTime<Utc>>,
+    pub preferred_locale: Option<String>,
 }
 
 impl From<User> for UserProfile {
@@ -149,12 +214,13 @@ impl From<User> for UserProfile {
             group_ids: user.group_ids,
             created_at: user.created_at,
             last_login_at: user.last_login_at,
+            preferred_locale: user.preferred_locale,
         }
     }
 }
 
 /// Request to register a new user
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct RegisterRequest {
     #[validate(email(message = "Invalid email format"))]
     pub email: String,
@@ -174,10 +240,15 @@ pub struct RegisterRequest {
 
     /// Optional group IDs (for students/teachers)
     pub group_ids: Option<Vec<String>>,
+
+    /// Optional birth year (students only), used to pick the content age band
+    #[validate(range(min = 1900, max = 2100, message = "Invalid birth year"))]
+    pub birth_year: Option<i32>,
 }
 
 /// Request to login
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+#[cfg_attr(feature = "client", derive(Serialize))]
 pub struct LoginRequest {
     #[validate(email(message = "Invalid email format"))]
     pub email: String,
@@ -198,14 +269,15 @@ pub struct AuthResponse {
 }
 
 /// Response after successful login or registration (refresh_token in HTTP-only cookie)
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
+#[cfg_attr(feature = "client", derive(Deserialize))]
 pub struct AuthResponseCookie {
     pub access_token: String,
     pub user: UserProfile,
 }
 
 /// Request to change password
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct ChangePasswordRequest {
     pub old_password: String,
 
@@ -213,17 +285,49 @@ pub struct ChangePasswordRequest {
     pub new_password: String,
 }
 
+/// Request to update own profile
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct UpdateProfileRequest {
+    /// Код языка контента, например `ru` или `en`
+    #[validate(custom(function = "validate_locale"))]
+    pub preferred_locale: Option<String>,
+}
+
+fn validate_locale(locale: &str) -> Result<(), ValidationError> {
+    if is_valid_locale(locale) {
+        return Ok(());
+    }
+    let mut error = ValidationError::new("invalid_locale");
+    error.message = Some(format!("Invalid locale: {}", locale).into());
+    Err(error)
+}
+
 /// Request to update user (admin only)
-#[derive(Debug, Clone, Deserialize, Validate)]
+#[derive(Debug, Clone, Deserialize, Validate, ToSchema)]
 pub struct UpdateUserRequest {
     pub name: Option<String>,
     pub role: Option<UserRole>,
     pub group_ids: Option<Vec<String>>,
     pub is_blocked: Option<bool>,
+    #[validate(range(min = 1900, max = 2100, message = "Invalid birth year"))]
+    pub birth_year: Option<i32>,
+    /// Часовой пояс IANA, например `Asia/Yekaterinburg`
+    #[validate(custom(function = "validate_timezone"))]
+    pub timezone: Option<String>,
+}
+
+fn validate_timezone(timezone: &str) -> Result<(), ValidationError> {
+    if timezone.parse::<chrono_tz::Tz>().is_ok() {
+        return Ok(());
+    }
+    let mut error = ValidationError::new("unknown_timezone");
+    error.message = Some(format!("Unknown timezone: {}", timezone).into());
+    Err(error)
 }
 
 /// Query params for listing users
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct ListUsersQuery {
     pub role: Option<String>,
     pub group_id: Option<String>,
@@ -231,10 +335,38 @@ pub struct ListUsersQuery {
     pub search: Option<String>, // search by email or name
     pub limit: Option<u32>,
     pub offset: Option<u32>,
+    pub sort: Option<UserSort>,
+    /// Курсор из заголовка `X-Next-Cursor` предыдущей страницы (вместо `offset`)
+    pub cursor: Option<String>,
+}
+
+/// Порядок списка пользователей
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+pub enum UserSort {
+    #[default]
+    #[serde(rename = "created_at")]
+    CreatedAt,
+    #[serde(rename = "-created_at")]
+    CreatedAtDesc,
+    #[serde(rename = "email")]
+    Email,
+    #[serde(rename = "name")]
+    Name,
+}
+
+impl UserSort {
+    pub fn key(self) -> SortKey {
+        match self {
+            UserSort::CreatedAt => SortKey::new("created_at", "createdAt", 1),
+            UserSort::CreatedAtDesc => SortKey::new("-created_at", "createdAt", -1),
+            UserSort::Email => SortKey::new("email", "email", 1),
+            UserSort::Name => SortKey::new("name", "name", 1),
+        }
+    }
 }
 
 /// Request для создания пользователя (Admin)
-#[derive(Debug, Clone, Deserialize, Validate)]
+#[derive(Debug, Clone, Deserialize, Validate, ToSchema)]
 pub struct CreateUserRequest {
     #[validate(email(message = "Invalid email format"))]
     pub email: String,
@@ -254,10 +386,15 @@ pub struct CreateUserRequest {
 
     /// Группы
     pub group_ids: Option<Vec<String>>,
+
+    /// Год рождения (только для учеников)
+    #[serde(default)]
+    #[validate(range(min = 1900, max = 2100, message = "Invalid birth year"))]
+    pub birth_year: Option<i32>,
 }
 
 /// Request для блокировки пользователя
-#[derive(Debug, Clone, Deserialize, Validate)]
+#[derive(Debug, Clone, Deserialize, Validate, ToSchema)]
 pub struct BlockUserRequest {
     #[validate(length(min = 1, message = "Reason is required"))]
     pub reason: String,
@@ -266,13 +403,13 @@ pub struct BlockUserRequest {
     pub duration_hours: Option<u32>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct BulkUserActionRequest {
     pub user_ids: Vec<String>,
     pub operation: BulkUserOperation,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum BulkUserOperation {
     Block {
@@ -285,20 +422,20 @@ pub enum BulkUserOperation {
     },
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct BulkUserActionResult {
     pub processed: usize,
     pub failed: Vec<BulkUserActionError>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct BulkUserActionError {
     pub user_id: String,
     pub error: String,
 }
 
 /// User detail response для админа (полная информация)
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct UserDetailResponse {
     pub id: String,
     pub email: String,
@@ -306,8 +443,13 @@ pub struct UserDetailResponse {
     pub role: UserRole,
     pub group_ids: Vec<String>,
     pub is_blocked: bool,
+    /// `is_blocked` с учётом `blocked_until`: истёкшая блокировка ещё может
+    /// значиться до следующего прохода фоновой задачи
+    pub block_active: bool,
     pub blocked_until: Option<DateTime<Utc>>,
     pub block_reason: Option<String>,
+    pub birth_year: Option<i32>,
+    pub timezone: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub last_login_at: Option<DateTime<Utc>>,
@@ -315,6 +457,7 @@ pub struct UserDetailResponse {
 
 impl From<User> for UserDetailResponse {
     fn from(user: User) -> Self {
+        let block_active = user.block_active(Utc::now());
         UserDetailResponse {
             id: user.id.map(|id| id.to_hex()).unwrap_or_default(),
             email: user.email,
@@ -322,8 +465,11 @@ impl From<User> for UserDetailResponse {
             role: user.role,
             group_ids: user.group_ids,
             is_blocked: user.is_blocked,
+            block_active,
             blocked_until: user.blocked_until,
             block_reason: user.block_reason,
+            birth_year: user.birth_year,
+            timezone: user.timezone,
             created_at: user.created_at,
             updated_at: user.updated_at,
             last_login_at: user.last_login_at,
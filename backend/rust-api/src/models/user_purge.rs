@@ -0,0 +1,62 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::user::{bson_datetime_as_chrono, bson_datetime_as_chrono_option};
+
+/// Тело `POST /admin/users/{id}/purge`: второе подтверждение - email удаляемой учётной записи
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+#[cfg_attr(feature = "client", derive(Serialize))]
+pub struct PurgeUserRequest {
+    pub confirm: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UserPurgeStatus {
+    /// Очистка прервалась; повторный вызов продолжит её с тем же псевдонимом
+    InProgress,
+    Completed,
+}
+
+/// Документ `user_purges` (`_id` - исходный id пользователя). Хранится бессрочно:
+/// по нему повторная очистка ничего не делает
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserPurgeRecord {
+    #[serde(rename = "_id")]
+    pub user_id: ObjectId,
+    pub status: UserPurgeStatus,
+    /// Новый id ответов и прогресса. Стирается по завершении, после этого связь
+    /// с исходным id не восстановить
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pseudonym_id: Option<ObjectId>,
+    pub purged_by: String,
+    #[serde(with = "bson_datetime_as_chrono")]
+    pub started_at: DateTime<Utc>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "bson_datetime_as_chrono_option"
+    )]
+    pub purged_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub removed: BTreeMap<String, u64>,
+    #[serde(default)]
+    pub reattributed: BTreeMap<String, u64>,
+}
+
+/// Ответ `POST /admin/users/{id}/purge`: что сделал именно этот вызов
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PurgeUserResponse {
+    pub user_id: String,
+    /// Пользователь уже был очищен раньше; вызов ничего не изменил
+    pub already_purged: bool,
+    pub purged_at: DateTime<Utc>,
+    /// Удалённые документы по коллекциям
+    pub removed: BTreeMap<String, u64>,
+    /// Документы, переписанные на псевдоним, по коллекциям
+    pub reattributed: BTreeMap<String, u64>,
+}
@@ -0,0 +1,168 @@
+//! OpenAPI-спецификация API, собранная из аннотаций `#[utoipa::path]` у хендлеров.
+//!
+//! JSON отдаётся на `GET /api-docs/openapi.json`, Swagger UI - на `/api-docs/swagger-ui`
+//! (только для админов). Новый хендлер из перечисленных ниже разделов нужно добавить
+//! в `paths(...)`, иначе тест `openapi_tests` упадёт.
+
+use axum::{routing::get, Json, Router};
+use utoipa::{
+    openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme},
+    Modify, OpenApi,
+};
+use utoipa_swagger_ui::{Config, SwaggerUi};
+
+use crate::{
+    handlers::{self, error::ErrorResponse},
+    models::reporting::TopicComparisonResponse,
+};
+
+pub const OPENAPI_JSON_PATH: &str = "/api-docs/openapi.json";
+pub const SWAGGER_UI_PATH: &str = "/api-docs/swagger-ui";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "TrainingGround API",
+        description = "Ошибки возвращаются в формате `ErrorResponse` (`code`, `message`, `details`), \
+                       кроме отмеченных как `text/plain` или JSON-строка."
+    ),
+    paths(
+        handlers::auth::register,
+        handlers::auth::login,
+        handlers::auth::refresh_token,
+        handlers::auth::logout,
+        handlers::auth::get_current_user,
+        handlers::auth::update_current_user,
+        handlers::auth::get_active_sessions,
+        handlers::auth::revoke_other_sessions,
+        handlers::auth::revoke_session,
+        handlers::auth::change_password,
+        handlers::auth::get_csrf_token,
+        handlers::sessions::create_session,
+        handlers::sessions::create_auto_session,
+        handlers::sessions::get_session,
+        handlers::sessions::complete_session,
+        handlers::sessions::submit_answer,
+        handlers::sessions::request_hint,
+        handlers::sessions::submit_signals,
+        handlers::achievements::get_achievements,
+        handlers::certificates::list_certificates,
+        handlers::certificates::download_certificate,
+        handlers::assignments::list_my_assignments,
+        handlers::assignments::create_assignment,
+        handlers::assignments::list_group_assignments,
+        handlers::assignments::extend_assignment,
+        handlers::assignments::get_assignment_progress,
+        handlers::notifications::list_notifications,
+        handlers::notifications::mark_notification_read,
+        handlers::notifications::mark_all_notifications_read,
+        handlers::review::next_review,
+        handlers::reporting::get_group_stats,
+        handlers::reporting::get_user_stats,
+        handlers::reporting::get_topic_stats,
+        handlers::reporting::request_group_export,
+        handlers::reporting::request_user_export,
+        handlers::reporting::get_export_status,
+        handlers::reporting::list_group_exports,
+        handlers::reporting::create_export_schedule,
+        handlers::reporting::list_export_schedules,
+        handlers::reporting::delete_export_schedule,
+        handlers::admin::list_templates,
+        handlers::admin::create_user,
+        handlers::admin::list_users,
+        handlers::admin::get_user,
+        handlers::admin::update_user,
+        handlers::admin::delete_user,
+        handlers::admin::block_user,
+        handlers::admin::unblock_user,
+        handlers::admin::list_user_logins,
+        handlers::admin::force_logout_user,
+        handlers::admin::purge_user,
+        handlers::admin::reset_user_password,
+        handlers::admin::bulk_user_action,
+        handlers::admin::create_group,
+        handlers::admin::list_groups,
+        handlers::admin::get_group,
+        handlers::admin::update_group,
+        handlers::admin::delete_group,
+        handlers::admin::archive_group,
+        handlers::admin::reassign_group_curator,
+        handlers::admin::list_group_members,
+        handlers::admin::add_group_member,
+        handlers::admin::remove_group_member,
+        handlers::admin::list_audit_logs,
+        handlers::admin::list_roles,
+        handlers::admin::update_role_permissions,
+    ),
+    components(schemas(ErrorResponse, TopicComparisonResponse)),
+    modifiers(&SecuritySchemes),
+    tags(
+        (name = "auth", description = "Вход, токены и сессии пользователя"),
+        (name = "sessions", description = "Сессии прохождения заданий"),
+        (name = "achievements", description = "Серия дней с занятиями и достижения"),
+        (name = "certificates", description = "Сертификаты за пройденные уровни"),
+        (name = "assignments", description = "Домашние задания групп со сроком сдачи"),
+        (name = "notifications", description = "Центр уведомлений пользователя"),
+        (name = "review", description = "Очередь повторения шаблонов с ошибками"),
+        (name = "reporting", description = "Статистика и выгрузки отчётов"),
+        (name = "admin-users", description = "Управление пользователями (admin)"),
+        (name = "admin-groups", description = "Управление группами (admin)"),
+        (name = "admin-audit", description = "Журнал аудита (admin)"),
+        (name = "admin-templates", description = "Шаблоны заданий (admin, content_admin)"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Схемы авторизации: access-токен в `Authorization: Bearer`, CSRF-токен в заголовке
+/// `X-CSRF-Token` (парный cookie `csrf_token` выдаёт `GET /api/v1/auth/csrf-token`)
+/// и refresh-токен в HTTP-only cookie
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+        components.add_security_scheme(
+            "csrf_token",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::with_description(
+                "X-CSRF-Token",
+                "Значение совпадает с cookie `csrf_token`; нужен для POST/PUT/PATCH/DELETE",
+            ))),
+        );
+        components.add_security_scheme(
+            "refresh_cookie",
+            SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::new("refresh_token"))),
+        );
+    }
+}
+
+/// `GET /api-docs/openapi.json`
+pub async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+/// Swagger UI; спецификацию он загружает с `OPENAPI_JSON_PATH`
+pub fn swagger_ui<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    SwaggerUi::new(SWAGGER_UI_PATH)
+        .config(Config::from(OPENAPI_JSON_PATH))
+        .into()
+}
+
+/// Маршрут спецификации без авторизации - по нему фронтенд генерирует типы
+pub fn openapi_routes<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new().route(OPENAPI_JSON_PATH, get(openapi_json))
+}
@@ -0,0 +1,363 @@
+//! Серия дней с занятиями и достижения ученика.
+//!
+//! Серия - дни подряд, в которые ученик завершил хотя бы одну сессию. Дни считаются
+//! по календарю часового пояса ученика (поле `timezone` пользователя или настройка
+//! `SESSION_DEFAULT_TIMEZONE`), поэтому переход на летнее время не рвёт серию и не
+//! засчитывает один день дважды.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, oid::ObjectId, Document},
+    ClientSession, Database,
+};
+
+use crate::config::SessionSettings;
+use crate::models::achievement::{
+    AchievementKind, AchievementsResponse, UnlockedAchievement, UserAchievements,
+};
+
+pub const USER_ACHIEVEMENTS_COLLECTION: &str = "user_achievements";
+pub const CORRECT_ANSWERS_GOAL: u32 = 100;
+pub const STREAK_GOAL_DAYS: u32 = 7;
+/// Сколько раз повторяется запись, если документ успели изменить параллельно
+const MAX_UPDATE_ATTEMPTS: usize = 5;
+
+/// Календарный день момента `at` в часовом поясе `tz`
+pub fn local_day(at: DateTime<Utc>, tz: Tz) -> NaiveDate {
+    at.with_timezone(&tz).date_naive()
+}
+
+/// Серия после занятия в день `today`: повторное занятие в тот же день её не меняет,
+/// занятие на следующий день продлевает, после пропуска серия начинается заново
+pub fn advance_streak(last_day: Option<NaiveDate>, current: u32, today: NaiveDate) -> u32 {
+    match last_day {
+        Some(day) if day == today => current.max(1),
+        Some(day) if day.succ_opt() == Some(today) => current + 1,
+        // Смена часового пояса на более западный: день уже учтён
+        Some(day) if day > today => current.max(1),
+        _ => 1,
+    }
+}
+
+/// Серия на день `today`: если ни сегодня, ни вчера занятий не было, она прервана
+pub fn streak_on(last_day: Option<NaiveDate>, current: u32, today: NaiveDate) -> u32 {
+    match last_day {
+        Some(day) if day >= today || day.succ_opt() == Some(today) => current,
+        _ => 0,
+    }
+}
+
+/// Завершённая сессия: продлевает серию и открывает достижения, возвращает новые
+pub fn apply_completed_session(
+    state: &mut UserAchievements,
+    today: NaiveDate,
+    at: DateTime<Utc>,
+) -> Vec<AchievementKind> {
+    state.current_streak = advance_streak(state.last_active_day, state.current_streak, today);
+    state.longest_streak = state.longest_streak.max(state.current_streak);
+    state.last_active_day = Some(state.last_active_day.map_or(today, |day| day.max(today)));
+
+    let mut unlocked = Vec::new();
+    if state.unlock(AchievementKind::FirstSession, at) {
+        unlocked.push(AchievementKind::FirstSession);
+    }
+    if state.current_streak >= STREAK_GOAL_DAYS && state.unlock(AchievementKind::SevenDayStreak, at)
+    {
+        unlocked.push(AchievementKind::SevenDayStreak);
+    }
+    unlocked
+}
+
+pub struct AchievementService {
+    mongo: Database,
+    default_timezone: Tz,
+}
+
+impl AchievementService {
+    pub fn new(mongo: Database, settings: &SessionSettings) -> Self {
+        Self {
+            mongo,
+            default_timezone: settings.default_tz(),
+        }
+    }
+
+    fn collection(&self) -> mongodb::Collection<UserAchievements> {
+        self.mongo.collection(USER_ACHIEVEMENTS_COLLECTION)
+    }
+
+    /// Часовой пояс ученика; без своего (или с нераспознанным) - пояс по умолчанию
+    pub async fn user_timezone(&self, user_id: &str) -> Result<Tz> {
+        let Ok(object_id) = ObjectId::parse_str(user_id) else {
+            return Ok(self.default_timezone);
+        };
+        let user = self
+            .mongo
+            .collection::<Document>("users")
+            .find_one(doc! { "_id": object_id })
+            .projection(doc! { "timezone": 1 })
+            .await
+            .context("Failed to load user timezone")?;
+        Ok(user
+            .as_ref()
+            .and_then(|user| user.get_str("timezone").ok())
+            .and_then(|timezone| timezone.parse().ok())
+            .unwrap_or(self.default_timezone))
+    }
+
+    pub async fn load(&self, user_id: &str) -> Result<UserAchievements> {
+        Ok(self
+            .collection()
+            .find_one(doc! { "_id": user_id })
+            .await
+            .context("Failed to load achievements")?
+            .unwrap_or_else(|| UserAchievements {
+                user_id: user_id.to_string(),
+                ..Default::default()
+            }))
+    }
+
+    /// Изменить документ ученика целиком: запись проходит, только если с момента чтения
+    /// версия не поменялась, иначе изменение применяется к свежему документу заново
+    async fn update<F>(&self, user_id: &str, apply: F) -> Result<Vec<UnlockedAchievement>>
+    where
+        F: Fn(&mut UserAchievements) -> Vec<AchievementKind>,
+    {
+        self.collection()
+            .update_one(
+                doc! { "_id": user_id },
+                doc! { "$setOnInsert": { "version": 0_i64 } },
+            )
+            .upsert(true)
+            .await
+            .context("Failed to initialize achievements")?;
+
+        for _ in 0..MAX_UPDATE_ATTEMPTS {
+            let mut state = self.load(user_id).await?;
+            let version = state.version;
+            let unlocked = apply(&mut state);
+            state.version = version + 1;
+
+            let result = self
+                .collection()
+                .replace_one(doc! { "_id": user_id, "version": version }, &state)
+                .await
+                .context("Failed to save achievements")?;
+            if result.matched_count == 1 {
+                return Ok(state
+                    .achievements
+                    .into_iter()
+                    .filter(|achievement| unlocked.contains(&achievement.kind))
+                    .collect());
+            }
+        }
+        Err(anyhow!(
+            "Achievements of user {} are being updated concurrently",
+            user_id
+        ))
+    }
+
+    /// Учесть завершённую сессию в транзакции `session`, которой записывается сама сессия;
+    /// возвращает только что открытые достижения. Параллельное изменение документа даёт
+    /// конфликт записи, и вызывающий повторяет транзакцию целиком
+    pub async fn record_completed_session(
+        &self,
+        session: &mut ClientSession,
+        user_id: &str,
+        completed_at: DateTime<Utc>,
+        timezone: Tz,
+    ) -> mongodb::error::Result<Vec<UnlockedAchievement>> {
+        let mut state = self
+            .collection()
+            .find_one(doc! { "_id": user_id })
+            .session(&mut *session)
+            .await?
+            .unwrap_or_else(|| UserAchievements {
+                user_id: user_id.to_string(),
+                ..Default::default()
+            });
+        let unlocked =
+            apply_completed_session(&mut state, local_day(completed_at, timezone), completed_at);
+        // Версия растёт, чтобы параллельный `update` вне транзакции перечитал документ
+        state.version += 1;
+        self.collection()
+            .replace_one(doc! { "_id": user_id }, &state)
+            .upsert(true)
+            .session(&mut *session)
+            .await?;
+
+        Ok(state
+            .achievements
+            .into_iter()
+            .filter(|achievement| unlocked.contains(&achievement.kind))
+            .collect())
+    }
+
+    /// Проверить цель по верным ответам после очередного верного ответа
+    pub async fn record_correct_answer(
+        &self,
+        user_id: &str,
+        at: DateTime<Utc>,
+    ) -> Result<Option<UnlockedAchievement>> {
+        let kind = AchievementKind::HundredCorrectAnswers;
+        if self.load(user_id).await?.is_unlocked(kind) {
+            return Ok(None);
+        }
+        if self.correct_answers(user_id).await? < u64::from(CORRECT_ANSWERS_GOAL) {
+            return Ok(None);
+        }
+        let unlocked = self
+            .update(user_id, |state| {
+                if state.unlock(kind, at) {
+                    vec![kind]
+                } else {
+                    Vec::new()
+                }
+            })
+            .await?;
+        Ok(unlocked.into_iter().next())
+    }
+
+    /// Верные ответы ученика по всем уровням
+    async fn correct_answers(&self, user_id: &str) -> Result<u64> {
+        let mut cursor = self
+            .mongo
+            .collection::<Document>("progress_summary_v2")
+            .aggregate(vec![
+                doc! { "$match": { "user_id": user_id } },
+                doc! { "$group": { "_id": null, "total": { "$sum": "$correct_count" } } },
+            ])
+            .await
+            .context("Failed to count correct answers")?;
+        let total = cursor
+            .try_next()
+            .await
+            .context("Failed to read correct answers")?
+            .and_then(|row| match row.get("total") {
+                Some(mongodb::bson::Bson::Int32(value)) => Some(i64::from(*value)),
+                Some(mongodb::bson::Bson::Int64(value)) => Some(*value),
+                _ => None,
+            })
+            .unwrap_or(0);
+        Ok(total.max(0) as u64)
+    }
+
+    pub async fn summary(&self, user_id: &str, now: DateTime<Utc>) -> Result<AchievementsResponse> {
+        let tz = self.user_timezone(user_id).await?;
+        let state = self.load(user_id).await?;
+        Ok(AchievementsResponse {
+            current_streak: streak_on(
+                state.last_active_day,
+                state.current_streak,
+                local_day(now, tz),
+            ),
+            longest_streak: state.longest_streak,
+            last_active_day: state.last_active_day,
+            timezone: tz.name().to_string(),
+            achievements: state.achievements.into_iter().map(Into::into).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn day(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn local(tz: Tz, y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        tz.with_ymd_and_hms(y, m, d, h, min, 0)
+            .single()
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn streak_counts_calendar_days_around_midnight() {
+        let tz = chrono_tz::Europe::Moscow;
+        let before = local_day(local(tz, 2024, 5, 10, 23, 59), tz);
+        let after = local_day(local(tz, 2024, 5, 11, 0, 1), tz);
+        assert_eq!(before, day(2024, 5, 10));
+        assert_eq!(after, day(2024, 5, 11));
+
+        assert_eq!(advance_streak(None, 0, before), 1);
+        assert_eq!(advance_streak(Some(before), 1, after), 2);
+        // Две сессии за один день серию не удлиняют
+        assert_eq!(advance_streak(Some(after), 2, after), 2);
+
+        // 22:30 UTC - уже следующий день в Москве
+        let utc_evening = Utc.with_ymd_and_hms(2024, 5, 10, 22, 30, 0).unwrap();
+        assert_eq!(local_day(utc_evening, tz), day(2024, 5, 11));
+        assert_eq!(local_day(utc_evening, Tz::UTC), day(2024, 5, 10));
+    }
+
+    #[test]
+    fn missed_day_resets_streak() {
+        assert_eq!(
+            advance_streak(Some(day(2024, 5, 8)), 5, day(2024, 5, 10)),
+            1
+        );
+        assert_eq!(streak_on(Some(day(2024, 5, 9)), 5, day(2024, 5, 10)), 5);
+        assert_eq!(streak_on(Some(day(2024, 5, 10)), 5, day(2024, 5, 10)), 5);
+        assert_eq!(streak_on(Some(day(2024, 5, 8)), 5, day(2024, 5, 10)), 0);
+        assert_eq!(streak_on(None, 0, day(2024, 5, 10)), 0);
+        // Месяц и год переходятся по календарю
+        assert_eq!(
+            advance_streak(Some(day(2023, 12, 31)), 3, day(2024, 1, 1)),
+            4
+        );
+    }
+
+    #[test]
+    fn dst_transitions_keep_day_boundaries() {
+        let tz = chrono_tz::Europe::Berlin;
+        // 31 марта 2024 в Берлине длится 23 часа: сессии с разницей в 23 часа - соседние дни
+        let saturday = local(tz, 2024, 3, 30, 23, 30);
+        let sunday = local(tz, 2024, 3, 31, 23, 30);
+        assert_eq!((sunday - saturday).num_hours(), 23);
+        let streak = advance_streak(Some(local_day(saturday, tz)), 1, local_day(sunday, tz));
+        assert_eq!(streak, 2);
+
+        // 27 октября 2024 длится 25 часов: сессии с разницей больше суток - один день
+        let morning = local(tz, 2024, 10, 27, 0, 10);
+        let night = local(tz, 2024, 10, 27, 23, 50);
+        assert!((night - morning).num_hours() >= 24);
+        assert_eq!(local_day(morning, tz), local_day(night, tz));
+        assert_eq!(
+            advance_streak(Some(local_day(morning, tz)), 1, local_day(night, tz)),
+            1
+        );
+    }
+
+    #[test]
+    fn completed_sessions_unlock_first_session_and_week_streak() {
+        let mut state = UserAchievements::default();
+        let start = day(2024, 5, 1);
+        let at = Utc::now();
+
+        assert_eq!(
+            apply_completed_session(&mut state, start, at),
+            vec![AchievementKind::FirstSession]
+        );
+        for offset in 1..6 {
+            let today = start + chrono::Duration::days(offset);
+            assert!(apply_completed_session(&mut state, today, at).is_empty());
+        }
+        assert_eq!(
+            apply_completed_session(&mut state, day(2024, 5, 7), at),
+            vec![AchievementKind::SevenDayStreak]
+        );
+        assert_eq!(state.current_streak, 7);
+
+        // После пропуска серия начинается заново, рекорд сохраняется
+        assert!(apply_completed_session(&mut state, day(2024, 5, 9), at).is_empty());
+        assert_eq!(state.current_streak, 1);
+        assert_eq!(state.longest_streak, 7);
+        assert_eq!(state.last_active_day, Some(day(2024, 5, 9)));
+    }
+}
@@ -1,18 +1,30 @@
 use std::{collections::HashMap, time::Duration};
 
 use anyhow::{Context, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
 use futures::TryStreamExt;
-use mongodb::bson::{doc, oid::ObjectId, Bson, Document};
+use mongodb::bson::{doc, oid::ObjectId, Bson, DateTime as BsonDateTime, Document};
 use tokio::time::sleep;
 use tracing::{info, warn};
 
 use crate::{
     config::Config,
     metrics::ANALYTICS_WORKER_TICKS_TOTAL,
-    models::reporting::{LeaderboardEntry, LeaderboardScope, StatType},
-    services::reporting_service::ReportingService,
+    models::{
+        anticheat::{SessionTimingStats, WindowPeak, SESSION_STATS_WINDOWS_SECONDS},
+        reporting::{LeaderboardEntry, LeaderboardScope, StatType},
+    },
+    services::{
+        anticheat_preview_service::SESSION_STATS_COLLECTION, anticheat_service::answer_fingerprint,
+        reporting_service::ReportingService,
+    },
 };
 
+/// Сколько сессий с новыми ответами пересчитывается за один тик
+const ANTICHEAT_STATS_SESSIONS_PER_TICK: i64 = 500;
+/// Минимальная глубина поиска новых ответов (с запасом на пропущенные тики)
+const ANTICHEAT_STATS_MIN_LOOKBACK_SECS: u64 = 3600;
+
 /// Sanitize user names to prevent CSV injection and limit special characters
 /// - Filters out dangerous characters
 /// - Limits length to 100 characters
@@ -67,6 +79,80 @@ impl AnalyticsWorker {
     async fn run_once(&self) -> Result<()> {
         let groups = self.refresh_materialized_stats().await?;
         self.refresh_leaderboards(&groups).await?;
+        self.refresh_anticheat_session_stats().await?;
+        Ok(())
+    }
+
+    /// Пересчитать агрегаты времени ответов для сессий, где появились новые попытки.
+    /// По ним работает предпросмотр настроек античита, не трогая attempt_records.
+    async fn refresh_anticheat_session_stats(&self) -> Result<()> {
+        let mongo = self.reporting_service.mongo();
+        let attempts = mongo.collection::<Document>("attempt_records");
+
+        let lookback =
+            (self.config.reporting.worker_interval_secs * 2).max(ANTICHEAT_STATS_MIN_LOOKBACK_SECS);
+        // timestamp попыток хранится строкой RFC 3339
+        let since = (Utc::now() - chrono::Duration::seconds(lookback as i64))
+            .to_rfc3339_opts(SecondsFormat::Secs, true);
+
+        let mut touched = attempts
+            .aggregate(vec![
+                doc! { "$match": { "timestamp": { "$gte": since } } },
+                doc! { "$group": { "_id": "$session_id" } },
+                doc! { "$limit": ANTICHEAT_STATS_SESSIONS_PER_TICK },
+            ])
+            .await
+            .context("Failed to find sessions with new attempts")?;
+
+        let mut session_ids = Vec::new();
+        while let Some(row) = touched.try_next().await? {
+            if let Ok(session_id) = row.get_str("_id") {
+                session_ids.push(session_id.to_string());
+            }
+        }
+        if session_ids.is_empty() {
+            return Ok(());
+        }
+
+        let mut cursor = attempts
+            .find(doc! { "session_id": { "$in": &session_ids } })
+            .projection(doc! { "session_id": 1, "user_id": 1, "answer": 1, "timestamp": 1 })
+            .await
+            .context("Failed to load attempts for anticheat stats")?;
+
+        let mut by_session: HashMap<String, (String, Vec<(i64, String)>)> = HashMap::new();
+        while let Some(attempt) = cursor.try_next().await? {
+            let (Ok(session_id), Ok(user_id)) =
+                (attempt.get_str("session_id"), attempt.get_str("user_id"))
+            else {
+                continue;
+            };
+            let Some(at_ms) = attempt.get("timestamp").and_then(timestamp_millis) else {
+                continue;
+            };
+            let fingerprint = answer_fingerprint(attempt.get_str("answer").unwrap_or_default());
+
+            by_session
+                .entry(session_id.to_string())
+                .or_insert_with(|| (user_id.to_string(), Vec::new()))
+                .1
+                .push((at_ms, fingerprint));
+        }
+
+        let stats_collection = mongo.collection::<SessionTimingStats>(SESSION_STATS_COLLECTION);
+        let refreshed = by_session.len();
+        for (session_id, (user_id, attempts)) in by_session {
+            let Some(stats) = build_session_timing_stats(&session_id, &user_id, attempts) else {
+                continue;
+            };
+            stats_collection
+                .replace_one(doc! { "_id": &session_id }, &stats)
+                .upsert(true)
+                .await
+                .with_context(|| format!("Failed to upsert anticheat stats for {}", session_id))?;
+        }
+
+        info!(sessions = refreshed, "Anticheat session stats refreshed");
         Ok(())
     }
 
@@ -295,10 +381,97 @@ impl AnalyticsWorker {
     }
 }
 
+fn timestamp_millis(value: &Bson) -> Option<i64> {
+    match value {
+        Bson::DateTime(dt) => Some(dt.timestamp_millis()),
+        Bson::String(s) => DateTime::parse_from_rfc3339(s)
+            .ok()
+            .map(|dt| dt.timestamp_millis()),
+        _ => None,
+    }
+}
+
+/// Агрегат сессии из попыток `(время в мс, отпечаток ответа)`
+fn build_session_timing_stats(
+    session_id: &str,
+    user_id: &str,
+    mut attempts: Vec<(i64, String)>,
+) -> Option<SessionTimingStats> {
+    attempts.sort_by_key(|(at_ms, _)| *at_ms);
+    let first = attempts.first()?.0;
+    let last = attempts.last()?.0;
+    let timestamps: Vec<i64> = attempts.iter().map(|(at_ms, _)| *at_ms).collect();
+
+    let peaks = SESSION_STATS_WINDOWS_SECONDS
+        .iter()
+        .map(|&window_seconds| WindowPeak {
+            window_seconds,
+            answers: peak_in_window(&timestamps, window_seconds as i64 * 1000),
+        })
+        .collect();
+
+    let mut repeats: HashMap<&str, u32> = HashMap::new();
+    for (_, fingerprint) in &attempts {
+        *repeats.entry(fingerprint.as_str()).or_default() += 1;
+    }
+
+    Some(SessionTimingStats {
+        session_id: session_id.to_string(),
+        user_id: user_id.to_string(),
+        answers: attempts.len() as u32,
+        peaks,
+        max_repeated_answers: repeats.values().copied().max().unwrap_or(0),
+        first_answer_at: BsonDateTime::from_millis(first),
+        last_answer_at: BsonDateTime::from_millis(last),
+    })
+}
+
+/// Максимум отметок в полуинтервале `[t, t + window)` по отсортированным временам
+fn peak_in_window(sorted_ms: &[i64], window_ms: i64) -> u32 {
+    let mut peak = 0;
+    let mut start = 0;
+    for end in 0..sorted_ms.len() {
+        while sorted_ms[end] - sorted_ms[start] >= window_ms {
+            start += 1;
+        }
+        peak = peak.max(end - start + 1);
+    }
+    peak as u32
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_peak_in_window() {
+        let minute = 60_000;
+        let times = [0, 10_000, 20_000, minute, minute + 5_000, 10 * minute];
+        assert_eq!(peak_in_window(&times, minute), 4);
+        assert_eq!(peak_in_window(&times, 5 * minute), 5);
+        assert_eq!(peak_in_window(&times, 1), 1);
+        assert_eq!(peak_in_window(&[], minute), 0);
+    }
+
+    #[test]
+    fn test_build_session_timing_stats() {
+        let attempts = vec![
+            (120_000, answer_fingerprint("b")),
+            (0, answer_fingerprint("A")),
+            (30_000, answer_fingerprint(" a ")),
+        ];
+        let stats = build_session_timing_stats("s1", "u1", attempts).unwrap();
+
+        assert_eq!(stats.answers, 3);
+        assert_eq!(stats.max_repeated_answers, 2);
+        assert_eq!(stats.first_answer_at.timestamp_millis(), 0);
+        assert_eq!(stats.last_answer_at.timestamp_millis(), 120_000);
+        assert_eq!(stats.peak_for_window(60), 2);
+        assert_eq!(stats.peak_for_window(3600), 3);
+
+        assert!(build_session_timing_stats("s2", "u1", Vec::new()).is_none());
+    }
+
     #[test]
     fn test_sanitize_user_name_normal() {
         assert_eq!(sanitize_user_name("John Doe"), "John Doe");
@@ -1,25 +1,513 @@
+use crate::config::SessionSettings;
 use crate::metrics::{record_cache_hit, record_cache_miss, ANSWERS_SUBMITTED_TOTAL};
+use crate::models::achievement::UnlockedAchievement;
 use crate::models::answer::{
-    AttemptFailureReason, AttemptRecord, SubmitAnswerRequest, SubmitAnswerResponse,
+    AnswerPartResult, AnswerTiming, AttemptFailureReason, AttemptRecord, NormalizationStep,
+    SubmitAnswerRequest, SubmitAnswerResponse, TaskAnswers,
 };
-use crate::models::{ProgressSummary, Session};
+use crate::models::notification::InAppNotificationKind;
+use crate::models::session_archive::SessionRecord;
+use crate::models::system_settings::AnticheatSettings;
+use crate::models::timer::{is_past_deadline, AchievementUnlocked, SessionExpired, TimerEvent};
+use crate::models::{ProgressSummary, Session, SessionMode, SessionStatus};
 use anyhow::{Context, Result};
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use mongodb::bson::{oid::ObjectId, Document};
+use mongodb::error::{ErrorKind, WriteFailure};
+use mongodb::options::ReturnDocument;
 use mongodb::Database;
 use redis::aio::ConnectionManager;
+use unicode_normalization::UnicodeNormalization;
 use uuid::Uuid;
 
+use super::achievement_service::AchievementService;
 use super::anticheat_service::AnticheatService;
+use super::hint_service::{hint_penalty_key, hints_used_key};
+use super::notification_center_service::{NewNotification, NotificationCenterService};
+use super::review_service::ReviewService;
+use super::session_events::SessionEventLog;
+use super::session_service::record_session_finished;
+use crate::utils::answer_pattern::compile_answer_pattern;
 use crate::utils::retry::{retry_async_with_config, RetryConfig};
 
+pub fn session_score_key(session_id: &str) -> String {
+    format!("session_score:{}", session_id)
+}
+
+/// Отметка, что сессия повторения уже обновила расписание своего шаблона
+pub fn review_graded_key(session_id: &str) -> String {
+    format!("review_graded:{}", session_id)
+}
+
+/// Смещения ответов сессии от её начала в миллисекундах, в порядке поступления
+pub fn session_answer_offsets_key(session_id: &str) -> String {
+    format!("session_answer_offsets:{}", session_id)
+}
+
+/// Время каждого ответа с предыдущего (первого - с начала сессии). Одновременные
+/// ответы могут лечь в список не по порядку, отрицательный интервал считается нулём
+pub fn answer_latencies(offsets: &[i64]) -> Vec<i64> {
+    let mut previous = 0;
+    offsets
+        .iter()
+        .map(|offset| {
+            let latency = (offset - previous).max(0);
+            previous = *offset;
+            latency
+        })
+        .collect()
+}
+
+const DUPLICATE_KEY: i32 = 11000;
+
+/// Для заданий с лимитом попыток счёт сессии - лучшая попытка, а не сумма
+const SESSION_BEST_SCORE_SCRIPT: &str = r#"
+    local current = tonumber(redis.call('GET', KEYS[1]) or '0')
+    if tonumber(ARGV[1]) > current then
+        redis.call('SET', KEYS[1], ARGV[1])
+    end
+    redis.call('EXPIRE', KEYS[1], ARGV[2])
+    return 1
+"#;
+
+/// Сообщить SSE-стриму сессии о новом достижении и записать его в центр уведомлений;
+/// сбой только логируется
+pub(crate) async fn publish_achievement(
+    log: &SessionEventLog,
+    notifications: &NotificationCenterService,
+    user_id: &str,
+    session_id: &str,
+    achievement: UnlockedAchievement,
+) {
+    let event = TimerEvent::AchievementUnlocked(AchievementUnlocked {
+        session_id: session_id.to_string(),
+        achievement: achievement.kind,
+        unlocked_at: achievement.unlocked_at,
+    });
+    if let Err(e) = log.publish(session_id, &event).await {
+        tracing::warn!(
+            "Failed to publish achievement for session {}: {:#}",
+            session_id,
+            e
+        );
+    }
+
+    let notification = NewNotification {
+        recipient_id: user_id.to_string(),
+        kind: InAppNotificationKind::AchievementUnlocked,
+        title: "Новое достижение".to_string(),
+        body: achievement.kind.title().to_string(),
+        dedup_key: Some(format!("achievement:{}", achievement.kind.as_str())),
+    };
+    if let Err(e) = notifications.create(notification).await {
+        tracing::warn!(
+            "Failed to store achievement notification for user {}: {:#}",
+            user_id,
+            e
+        );
+    }
+}
+
+/// Ответ пришёл после `expires_at` и льготного периода (ответ 409 `SESSION_EXPIRED`)
+#[derive(Debug, thiserror::Error)]
+#[error("Session has expired")]
+pub struct SessionExpiredError {
+    pub session_id: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Число частей ответа не совпадает с числом пропусков задания (ответ 400)
+#[derive(Debug, thiserror::Error)]
+#[error("Expected {expected} answer parts, got {received}")]
+pub struct AnswerFormatError {
+    pub expected: usize,
+    pub received: usize,
+}
+
+/// Лимит ответов задания уже исчерпан (ответ 409 `ATTEMPTS_EXHAUSTED`)
+#[derive(Debug, thiserror::Error)]
+#[error("No attempts left for this task ({max_attempts} allowed)")]
+pub struct AttemptsExhaustedError {
+    pub session_id: String,
+    pub max_attempts: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FinalSessionScore {
+    pub raw_score: i32,
+    pub penalty_percent: u32,
+    pub hints_used: u32,
+    pub score: i32,
+}
+
+/// Штраф уменьшает только положительный счёт, результат округляется вниз
+pub fn apply_hint_penalty(raw_score: i32, penalty_percent: u32) -> i32 {
+    if raw_score <= 0 {
+        return raw_score;
+    }
+    let kept = 100 - i64::from(penalty_percent.min(100));
+    (i64::from(raw_score) * kept / 100) as i32
+}
+
+/// Баллы за правильный ответ (правило S1)
+pub const CORRECT_ANSWER_POINTS: i32 = 10;
+
+/// Правильные ответы задания: `correct_answer` (старые документы), варианты
+/// `answers.accepted`, шаблон `answers.pattern` и ответы по пропускам `answers.parts`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AnswerKey {
+    pub accepted: Vec<String>,
+    pub pattern: Option<String>,
+    pub parts: Vec<AnswerKey>,
+}
+
+impl AnswerKey {
+    /// `None`, если в задании нет ни одного способа проверить ответ
+    pub fn from_task(task: &Document) -> Option<Self> {
+        let correct_answer = task.get_str("correct_answer").ok().or_else(|| {
+            task.get_document("content")
+                .ok()
+                .and_then(|content| content.get_str("correct_answer").ok())
+        });
+        let answers = task
+            .get_document("answers")
+            .ok()
+            .and_then(|answers| mongodb::bson::from_document::<TaskAnswers>(answers.clone()).ok())
+            .unwrap_or_default();
+        Self::from_answers(correct_answer, answers)
+    }
+
+    fn from_answers(correct_answer: Option<&str>, answers: TaskAnswers) -> Option<Self> {
+        let mut accepted: Vec<String> = correct_answer
+            .filter(|answer| !answer.trim().is_empty())
+            .map(str::to_string)
+            .into_iter()
+            .collect();
+        for answer in answers.accepted {
+            if !answer.trim().is_empty() && !accepted.contains(&answer) {
+                accepted.push(answer);
+            }
+        }
+        let pattern = answers.pattern.filter(|pattern| !pattern.trim().is_empty());
+        // Пустая часть остаётся на своём месте, чтобы не сдвигать номера пропусков
+        let parts: Vec<AnswerKey> = answers
+            .parts
+            .into_iter()
+            .map(|part| Self::from_answers(None, part).unwrap_or_default())
+            .collect();
+
+        (!accepted.is_empty() || pattern.is_some() || !parts.is_empty()).then_some(Self {
+            accepted,
+            pattern,
+            parts,
+        })
+    }
+}
+
+/// Как начисляются баллы за задание
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScoringMode {
+    /// Всё или ничего
+    Exact,
+    /// Каждый пропуск проверяется отдельно, баллы пропорциональны числу верных
+    PerPart { points_per_part: Option<u32> },
+}
+
+impl ScoringMode {
+    /// `scoring` и `points_per_part` берутся из задания, затем из `params` шаблона
+    pub fn resolve(task: &Document, template_params: Option<&Document>) -> Self {
+        let sources = [Some(task), template_params];
+        let per_part = sources
+            .iter()
+            .flatten()
+            .find_map(|source| source.get_str("scoring").ok())
+            == Some("per_part");
+        if !per_part {
+            return ScoringMode::Exact;
+        }
+        let points_per_part = find_u32(&sources, "points_per_part");
+        ScoringMode::PerPart { points_per_part }
+    }
+
+    /// Баллы за `correct` верных частей из `total` без комбо-бонуса. Единственное место
+    /// округления: с `points_per_part` начисляется столько за каждую верную часть,
+    /// иначе S1 делится пропорционально и дробная часть отбрасывается (2 из 3 - 6 баллов)
+    pub fn points(&self, correct: u32, total: u32) -> i32 {
+        if total == 0 {
+            return 0;
+        }
+        let correct = correct.min(total);
+        match self {
+            ScoringMode::PerPart {
+                points_per_part: Some(points),
+            } => i32::try_from(u64::from(*points) * u64::from(correct)).unwrap_or(i32::MAX),
+            _ => (i64::from(CORRECT_ANSWER_POINTS) * i64::from(correct) / i64::from(total)) as i32,
+        }
+    }
+}
+
+/// Лимит ответов в сессии: `max_attempts` из задания, затем из `params` шаблона.
+/// 0 или отсутствие поля - без ограничения
+pub fn resolve_max_attempts(task: &Document, template_params: Option<&Document>) -> Option<u32> {
+    find_u32(&[Some(task), template_params], "max_attempts").filter(|max| *max > 0)
+}
+
+/// Первое целое неотрицательное значение поля среди источников
+fn find_u32(sources: &[Option<&Document>], field: &str) -> Option<u32> {
+    sources
+        .iter()
+        .flatten()
+        .find_map(|source| match source.get(field)? {
+            mongodb::bson::Bson::Int32(value) => u32::try_from(*value).ok(),
+            mongodb::bson::Bson::Int64(value) => u32::try_from(*value).ok(),
+            _ => None,
+        })
+}
+
+fn is_duplicate_key(err: &mongodb::error::Error) -> bool {
+    match err.kind.as_ref() {
+        ErrorKind::Command(command) => command.code == DUPLICATE_KEY,
+        ErrorKind::Write(WriteFailure::WriteError(write)) => write.code == DUPLICATE_KEY,
+        _ => false,
+    }
+}
+
+/// Процент прогресса по сумме зачтённых долей ответов
+pub fn progress_percentage(credit: f64, attempts_total: u32) -> f64 {
+    if attempts_total == 0 {
+        return 0.0;
+    }
+    credit / f64::from(attempts_total) * 100.0
+}
+
+/// Результат проверки ответа до начисления комбо-бонуса
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnswerCheck {
+    /// Верно всё, включая каждую часть
+    pub correct: bool,
+    /// Доля верного ответа от 0 до 1 для процента прогресса
+    pub credit: f64,
+    pub points: i32,
+    pub normalization: Vec<NormalizationStep>,
+    pub parts: Vec<AnswerPartResult>,
+}
+
+/// Проверяет ответ по правильным ответам задания и считает баллы
+pub fn check_answer(
+    req: &SubmitAnswerRequest,
+    key: &AnswerKey,
+    normalization: &AnswerNormalization,
+    scoring: ScoringMode,
+) -> Result<AnswerCheck, AnswerFormatError> {
+    if matches!(scoring, ScoringMode::Exact) || key.parts.is_empty() {
+        let (correct, steps) = normalization.check(&req.answer, key);
+        return Ok(AnswerCheck {
+            correct,
+            credit: if correct { 1.0 } else { 0.0 },
+            points: ScoringMode::Exact.points(u32::from(correct), 1),
+            normalization: steps,
+            parts: Vec::new(),
+        });
+    }
+
+    let submitted = req.parts.as_deref().unwrap_or_default();
+    if submitted.len() != key.parts.len() {
+        return Err(AnswerFormatError {
+            expected: key.parts.len(),
+            received: submitted.len(),
+        });
+    }
+
+    let mut steps = Vec::new();
+    let parts: Vec<AnswerPartResult> = submitted
+        .iter()
+        .zip(&key.parts)
+        .enumerate()
+        .map(|(index, (answer, part_key))| {
+            let (correct, part_steps) = normalization.check(answer, part_key);
+            steps.extend(part_steps);
+            AnswerPartResult { index, correct }
+        })
+        .collect();
+    steps.sort();
+    steps.dedup();
+
+    let total = parts.len() as u32;
+    let correct = parts.iter().filter(|part| part.correct).count() as u32;
+    Ok(AnswerCheck {
+        correct: correct == total,
+        credit: f64::from(correct) / f64::from(total),
+        points: scoring.points(correct, total),
+        normalization: steps,
+        parts,
+    })
+}
+
+/// Как сравнивать ответ с эталоном для конкретного задания
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnswerNormalization {
+    /// Только NFC и пробелы по краям - регистр, `ё`, тире и пробелы внутри значимы
+    pub strict_mode: bool,
+    pub yo_equivalence: bool,
+}
+
+impl AnswerNormalization {
+    /// `strict_mode` берётся из задания, затем из `params` шаблона; по умолчанию выключен
+    pub fn resolve(
+        task: &Document,
+        template_params: Option<&Document>,
+        settings: &SessionSettings,
+    ) -> Self {
+        let strict_mode = [Some(task), template_params]
+            .into_iter()
+            .flatten()
+            .find_map(|source| source.get_bool("strict_mode").ok())
+            .unwrap_or(false);
+        Self {
+            strict_mode,
+            yo_equivalence: settings.answer_yo_equivalence,
+        }
+    }
+
+    /// Нормализованная строка и шаги, которые её изменили
+    pub fn normalize(&self, input: &str) -> (String, Vec<NormalizationStep>) {
+        let mut value = input.to_string();
+        let mut steps = Vec::new();
+        let mut apply = |step: NormalizationStep, transform: &dyn Fn(&str) -> String| {
+            let next = transform(&value);
+            if next != value {
+                steps.push(step);
+                value = next;
+            }
+        };
+
+        apply(NormalizationStep::Nfc, &|value| value.nfc().collect());
+        if self.strict_mode {
+            apply(NormalizationStep::Whitespace, &|value| {
+                value.trim().to_string()
+            });
+        } else {
+            apply(NormalizationStep::Whitespace, &collapse_whitespace);
+            apply(NormalizationStep::Dashes, &|value| {
+                value.chars().map(unify_dash).collect()
+            });
+            apply(NormalizationStep::CaseFold, &str::to_lowercase);
+            if self.yo_equivalence {
+                apply(NormalizationStep::YoToYe, &|value| value.replace('ё', "е"));
+            }
+        }
+        (value, steps)
+    }
+
+    /// Совпадает ли ответ с эталоном и какие шаги изменили хотя бы одну из строк
+    pub fn compare(&self, answer: &str, correct_answer: &str) -> (bool, Vec<NormalizationStep>) {
+        let (answer, mut steps) = self.normalize(answer);
+        let (correct_answer, correct_steps) = self.normalize(correct_answer);
+        steps.extend(correct_steps);
+        steps.sort();
+        steps.dedup();
+        (answer == correct_answer, steps)
+    }
+
+    /// Проверка по всем правильным ответам задания. Шаги - от ответа ученика и
+    /// совпавшего варианта; если ничего не совпало - от всех вариантов
+    pub fn check(&self, answer: &str, key: &AnswerKey) -> (bool, Vec<NormalizationStep>) {
+        let mut mismatch_steps = Vec::new();
+        for accepted in &key.accepted {
+            let (is_correct, steps) = self.compare(answer, accepted);
+            if is_correct {
+                return (true, steps);
+            }
+            mismatch_steps.extend(steps);
+        }
+        if let Some(steps) = key
+            .pattern
+            .as_deref()
+            .and_then(|pattern| self.match_pattern(answer, pattern))
+        {
+            return (true, steps);
+        }
+
+        let (_, steps) = self.normalize(answer);
+        mismatch_steps.extend(steps);
+        mismatch_steps.sort();
+        mismatch_steps.dedup();
+        (false, mismatch_steps)
+    }
+
+    /// Шаги, после которых ответ совпал с шаблоном. Сначала проверяется ответ только
+    /// после NFC и обрезки (для шаблонов с `ё` и заглавными), затем полностью
+    /// нормализованный; вне строгого режима регистр не учитывается
+    fn match_pattern(&self, answer: &str, pattern: &str) -> Option<Vec<NormalizationStep>> {
+        let regex = match compile_answer_pattern(pattern, !self.strict_mode) {
+            Ok(regex) => regex,
+            Err(error) => {
+                tracing::warn!("Skipping invalid answer pattern {:?}: {}", pattern, error);
+                return None;
+            }
+        };
+        let minimal = Self {
+            strict_mode: true,
+            ..*self
+        };
+        [minimal.normalize(answer), self.normalize(answer)]
+            .into_iter()
+            .find(|(value, _)| regex.is_match(value))
+            .map(|(_, steps)| steps)
+    }
+}
+
+/// Пробелы по краям убираются, любые пробельные символы внутри (включая NBSP)
+/// сворачиваются в один пробел, пробелы нулевой ширины удаляются
+fn collapse_whitespace(value: &str) -> String {
+    value
+        .split(char::is_whitespace)
+        .map(|part| part.replace(['\u{200B}', '\u{2060}', '\u{FEFF}'], ""))
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn unify_dash(c: char) -> char {
+    match c {
+        '\u{2010}'..='\u{2015}' | '\u{2212}' | '\u{FE58}' | '\u{FE63}' | '\u{FF0D}' => '-',
+        other => other,
+    }
+}
+
+/// Ключ ответа задания и правила его проверки
+struct TaskAnswerSpec {
+    key: AnswerKey,
+    normalization: AnswerNormalization,
+    scoring: ScoringMode,
+    max_attempts: Option<u32>,
+    /// Сложность из задания, затем из шаблона (для эвристики скорости ответов)
+    difficulty: Option<String>,
+    /// Шаблон, по которому сгенерировано задание
+    template_id: Option<ObjectId>,
+}
+
 pub struct AnswerService {
     mongo: Database,
     redis: ConnectionManager,
+    settings: SessionSettings,
+    anticheat: AnticheatSettings,
 }
 
 impl AnswerService {
-    pub fn new(mongo: Database, redis: ConnectionManager) -> Self {
-        Self { mongo, redis }
+    pub fn new(mongo: Database, redis: ConnectionManager, settings: SessionSettings) -> Self {
+        Self {
+            mongo,
+            redis,
+            settings,
+            anticheat: AnticheatSettings::default(),
+        }
+    }
+
+    /// Настройки античита из админки (`AppState::anticheat_settings`)
+    pub fn with_anticheat_settings(mut self, anticheat: AnticheatSettings) -> Self {
+        self.anticheat = anticheat;
+        self
     }
 
     pub async fn submit_answer(
@@ -29,12 +517,13 @@ impl AnswerService {
         task_id: &str,
         req: &SubmitAnswerRequest,
     ) -> Result<SubmitAnswerResponse> {
+        let submitted = req.submitted_text();
         tracing::info!(
             "Processing answer submission: session={}, user={}, task={}, answer={}",
             session_id,
             user_id,
             task_id,
-            req.answer
+            submitted
         );
 
         let retry_cfg = RetryConfig::default();
@@ -65,29 +554,42 @@ impl AnswerService {
             self.get_session(session_id).await
         })
         .await?;
-        if session.expires_at < Utc::now() {
+        let expired = matches!(session.status, SessionStatus::Expired)
+            || is_past_deadline(session.expires_at, self.settings.grace_seconds, Utc::now());
+        if expired {
             tracing::warn!("Session {} expired, recording timeout", session_id);
             let attempt = AttemptRecord {
                 id: Uuid::new_v4().to_string(),
                 session_id: session_id.to_string(),
                 user_id: user_id.to_string(),
                 task_id: task_id.to_string(),
-                answer: req.answer.clone(),
+                answer: submitted.clone(),
                 correct: false,
                 score: 0,
                 timestamp: Utc::now(),
                 reason: Some(AttemptFailureReason::Timeout),
+                credit: None,
+                timing: None,
             };
             // save attempt (may be background)
             self.save_attempt(&attempt).await?;
 
-            anyhow::bail!("Session has expired");
+            if matches!(session.status, SessionStatus::Active) {
+                self.expire_session(session.clone()).await?;
+            }
+
+            return Err(SessionExpiredError {
+                session_id: session_id.to_string(),
+                expires_at: session.expires_at,
+            }
+            .into());
         }
 
         // Anticheat check
-        let anticheat = AnticheatService::new(self.mongo.clone(), self.redis.clone());
+        let anticheat = AnticheatService::new(self.mongo.clone(), self.redis.clone())
+            .with_settings(self.anticheat.clone());
         let status = anticheat
-            .track_answer(user_id, &req.answer, session_id)
+            .track_answer(user_id, &submitted, session_id)
             .await?;
 
         if status.is_blocked {
@@ -95,16 +597,67 @@ impl AnswerService {
                 "Anticheat flagged user {} for session {} (answer={}), but allowing submission",
                 user_id,
                 session_id,
-                req.answer
+                submitted
             );
         }
 
         // Get correct answer from MongoDB tasks collection
-        let correct_answer = retry_async_with_config(aggressive_cfg.clone(), || async {
-            self.get_correct_answer(task_id).await
+        let spec = retry_async_with_config(aggressive_cfg.clone(), || async {
+            self.get_answer_key(task_id).await
         })
         .await?;
-        let is_correct = req.answer.trim() == correct_answer.trim();
+        let check = check_answer(req, &spec.key, &spec.normalization, spec.scoring)?;
+        let is_correct = check.correct;
+
+        // Попытка засчитывается атомарным `$inc` документа сессии с условием
+        // `attempts_used < max_attempts`: из двух одновременных ответов на последнюю
+        // попытку проходит один, отклонённый ответ попытку не тратит. Без повторов:
+        // `$inc` не идемпотентен
+        let remaining_attempts = match spec.max_attempts {
+            Some(max_attempts) => match self.count_attempt(&session, max_attempts).await? {
+                Some(used) => Some(max_attempts - used),
+                None => {
+                    return Err(AttemptsExhaustedError {
+                        session_id: session_id.to_string(),
+                        max_attempts,
+                    }
+                    .into());
+                }
+            },
+            None => None,
+        };
+
+        // Ошибка ставит шаблон в очередь повторения; в сессии повторения расписание
+        // обновляет первый проверенный ответ сессии
+        match (session.mode, spec.template_id) {
+            (SessionMode::Normal, Some(template_id)) if !is_correct => {
+                if let Err(e) = ReviewService::new(self.mongo.clone())
+                    .record_mistake(user_id, template_id, task_id)
+                    .await
+                {
+                    tracing::warn!(
+                        "Failed to add template {} to review queue for user {}: {:#}",
+                        template_id,
+                        user_id,
+                        e
+                    );
+                }
+            }
+            (SessionMode::Review, Some(template_id)) => {
+                if let Err(e) = self
+                    .record_review_result(session_id, user_id, template_id, is_correct)
+                    .await
+                {
+                    tracing::warn!(
+                        "Failed to update review schedule of template {} for user {}: {:#}",
+                        template_id,
+                        user_id,
+                        e
+                    );
+                }
+            }
+            _ => {}
+        }
 
         // Record answer submission metric
         let correct_label = if is_correct { "true" } else { "false" };
@@ -116,16 +669,42 @@ impl AnswerService {
         let (score_awarded, combo_bonus, current_streak) = if is_correct {
             tracing::info!("Processing correct answer for user: {}", user_id);
             retry_async_with_config(retry_cfg.clone(), || async {
-                self.process_correct_answer(user_id).await
+                self.process_correct_answer(user_id, check.points).await
             })
             .await?
         } else {
             tracing::info!("Processing incorrect answer for user: {}", user_id);
-            retry_async_with_config(retry_cfg.clone(), || async {
+            let (_, _, streak) = retry_async_with_config(retry_cfg.clone(), || async {
                 self.process_incorrect_answer(user_id).await
             })
-            .await?
+            .await?;
+            // Частично верный ответ по пропускам приносит свою долю баллов, но серию обнуляет
+            (check.points, 0, streak)
+        };
+
+        // Время ответа по часам сервера; без него ответ всё равно засчитывается
+        let answered_at = Utc::now();
+        let latencies = match self
+            .record_answer_offset(
+                session_id,
+                (answered_at - session.started_at).num_milliseconds(),
+            )
+            .await
+        {
+            Ok(offsets) => answer_latencies(&offsets),
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to record answer timing for session {}: {:#}",
+                    session_id,
+                    e
+                );
+                Vec::new()
+            }
         };
+        let timing = latencies.last().map(|since_previous_ms| AnswerTiming {
+            since_session_start_ms: (answered_at - session.started_at).num_milliseconds().max(0),
+            since_previous_ms: *since_previous_ms,
+        });
 
         // Save attempt to MongoDB (may be background)
         let attempt = AttemptRecord {
@@ -133,15 +712,17 @@ impl AnswerService {
             session_id: session_id.to_string(),
             user_id: user_id.to_string(),
             task_id: task_id.to_string(),
-            answer: req.answer.clone(),
+            answer: submitted.clone(),
             correct: is_correct,
             score: score_awarded + combo_bonus,
-            timestamp: Utc::now(),
+            timestamp: answered_at,
             reason: if !is_correct {
                 Some(AttemptFailureReason::WrongAnswer)
             } else {
                 None
             },
+            credit: Some(check.credit),
+            timing,
         };
 
         // Save attempt: prefer background async save; if configured to save synchronously, use aggressive retries
@@ -157,6 +738,17 @@ impl AnswerService {
             .await?;
         }
 
+        if let Err(e) = anticheat
+            .check_answer_latency(session_id, user_id, spec.difficulty.as_deref(), &latencies)
+            .await
+        {
+            tracing::warn!(
+                "Failed to check answer latency for session {}: {:#}",
+                session_id,
+                e
+            );
+        }
+
         let score_delta = score_awarded + combo_bonus;
 
         // Update total score in Redis
@@ -165,6 +757,16 @@ impl AnswerService {
         })
         .await?;
 
+        retry_async_with_config(aggressive_cfg.clone(), || async {
+            if spec.max_attempts.is_some() {
+                self.update_session_best_score(session_id, score_delta)
+                    .await
+            } else {
+                self.update_session_score(session_id, score_delta).await
+            }
+        })
+        .await?;
+
         let session_level_id = session.level_id.clone();
 
         // Update progress summary for S5 rule (80% threshold)
@@ -174,12 +776,34 @@ impl AnswerService {
                 session_level_id.as_deref(),
                 task_id,
                 is_correct,
+                check.credit,
                 score_delta,
             )
             .await
         })
         .await?;
 
+        // Цель по верным ответам может быть достигнута посреди сессии
+        if is_correct {
+            match AchievementService::new(self.mongo.clone(), &self.settings)
+                .record_correct_answer(user_id, Utc::now())
+                .await
+            {
+                Ok(Some(achievement)) => {
+                    let log =
+                        SessionEventLog::new(self.redis.clone(), self.settings.event_buffer_size);
+                    let notifications =
+                        NotificationCenterService::new(self.mongo.clone(), self.redis.clone());
+                    publish_achievement(&log, &notifications, user_id, session_id, achievement)
+                        .await;
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::warn!("Failed to check achievements for user {}: {:#}", user_id, e)
+                }
+            }
+        }
+
         tracing::info!(
             "Answer processed: session={}, correct={}, score={}, streak={}",
             session_id,
@@ -199,6 +823,11 @@ impl AnswerService {
             } else {
                 Some("Incorrect answer".to_string())
             },
+            normalization: check.normalization.clone(),
+            parts: check.parts.clone(),
+            max_attempts: spec.max_attempts,
+            remaining_attempts,
+            session_completed: false,
         };
 
         // Cache response for idempotency
@@ -210,9 +839,13 @@ impl AnswerService {
         Ok(response)
     }
 
-    // Rule S1: +10 points for correct answer
+    // Rule S1: +10 points for correct answer (`base_score` from the task's scoring mode)
     // Rule S4: +5 combo bonus after streak >= 3
-    async fn process_correct_answer(&self, user_id: &str) -> Result<(i32, i32, u32)> {
+    async fn process_correct_answer(
+        &self,
+        user_id: &str,
+        base_score: i32,
+    ) -> Result<(i32, i32, u32)> {
         let mut conn = self.redis.clone();
         let streak_key = format!("score:series:{}", user_id);
 
@@ -230,7 +863,6 @@ impl AnswerService {
             .query_async::<()>(&mut conn)
             .await?;
 
-        let base_score = 10; // S1
         let combo_bonus = if streak >= 3 { 5 } else { 0 }; // S4
 
         tracing::debug!(
@@ -329,6 +961,156 @@ impl AnswerService {
         Ok(total)
     }
 
+    /// Счёт сессии до штрафов за подсказки
+    async fn update_session_score(&self, session_id: &str, score_delta: i32) -> Result<()> {
+        let mut conn = self.redis.clone();
+        redis::pipe()
+            .cmd("INCRBY")
+            .arg(session_score_key(session_id))
+            .arg(score_delta)
+            .ignore()
+            .cmd("EXPIRE")
+            .arg(session_score_key(session_id))
+            .arg(3600)
+            .ignore()
+            .query_async::<()>(&mut conn)
+            .await
+            .context("Failed to update session score")?;
+        Ok(())
+    }
+
+    async fn update_session_best_score(&self, session_id: &str, score: i32) -> Result<()> {
+        let mut conn = self.redis.clone();
+        let _: i32 = redis::Script::new(SESSION_BEST_SCORE_SCRIPT)
+            .key(session_score_key(session_id))
+            .arg(score)
+            .arg(3600)
+            .invoke_async(&mut conn)
+            .await
+            .context("Failed to update session best score")?;
+        Ok(())
+    }
+
+    /// Добавить смещение ответа в список сессии и вернуть весь список
+    async fn record_answer_offset(&self, session_id: &str, offset_ms: i64) -> Result<Vec<i64>> {
+        let mut conn = self.redis.clone();
+        let key = session_answer_offsets_key(session_id);
+        let (offsets,): (Vec<i64>,) = redis::pipe()
+            .atomic()
+            .cmd("RPUSH")
+            .arg(&key)
+            .arg(offset_ms.max(0))
+            .ignore()
+            .cmd("EXPIRE")
+            .arg(&key)
+            .arg(3600)
+            .ignore()
+            .cmd("LRANGE")
+            .arg(&key)
+            .arg(0)
+            .arg(-1)
+            .query_async(&mut conn)
+            .await
+            .context("Failed to record answer offset")?;
+        Ok(offsets)
+    }
+
+    /// Засчитать попытку и вернуть их число с начала сессии
+    /// Результат повторения по проверенному ответу; повторные ответы той же сессии
+    /// расписание не двигают
+    async fn record_review_result(
+        &self,
+        session_id: &str,
+        user_id: &str,
+        template_id: ObjectId,
+        correct: bool,
+    ) -> Result<()> {
+        let mut conn = self.redis.clone();
+        let first: Option<String> = redis::cmd("SET")
+            .arg(review_graded_key(session_id))
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(3600)
+            .query_async(&mut conn)
+            .await
+            .context("Failed to mark review result")?;
+        if first.is_none() {
+            return Ok(());
+        }
+        ReviewService::new(self.mongo.clone())
+            .record_result(user_id, template_id, correct)
+            .await?;
+        Ok(())
+    }
+
+    /// Засчитать попытку в документе сессии в MongoDB "sessions" и вернуть их число
+    /// с начала сессии; `None` - лимит уже исчерпан. Документ идущей сессии создаётся
+    /// первым ответом со статусом `active` и перезаписывается при завершении
+    async fn count_attempt(&self, session: &Session, max_attempts: u32) -> Result<Option<u32>> {
+        use mongodb::bson::doc;
+
+        let mut fields =
+            mongodb::bson::to_document(&SessionRecord::from_session(session.clone(), None))
+                .context("Failed to serialize session record")?;
+        fields.remove("_id");
+        let sessions = self.mongo.collection::<Document>("sessions");
+        // Документ есть, но лимит исчерпан: upsert пытается вставить дубль `_id`.
+        // Первый дубль может дать и гонка двух первых ответов, поэтому он повторяется
+        for _ in 0..2 {
+            let updated = sessions
+                .find_one_and_update(
+                    doc! { "_id": &session.id, "attempts_used": { "$lt": max_attempts as i64 } },
+                    doc! {
+                        "$inc": { "attempts_used": 1_i32 },
+                        "$setOnInsert": fields.clone(),
+                    },
+                )
+                .upsert(true)
+                .return_document(ReturnDocument::After)
+                .await;
+            match updated {
+                Ok(Some(record)) => {
+                    let used = record
+                        .get_i32("attempts_used")
+                        .ok()
+                        .and_then(|used| u32::try_from(used).ok())
+                        .context("Session record has an invalid attempts_used")?;
+                    return Ok(Some(used));
+                }
+                Ok(None) => return Ok(None),
+                Err(err) if is_duplicate_key(&err) => continue,
+                Err(err) => return Err(err).context("Failed to count answer attempt"),
+            }
+        }
+        Ok(None)
+    }
+
+    /// Итог сессии при завершении: набранные очки минус накопленный штраф за подсказки
+    pub async fn final_session_score(&self, session_id: &str) -> Result<FinalSessionScore> {
+        let mut conn = self.redis.clone();
+        let (raw_score, penalty_percent, hints_used): (Option<i32>, Option<u32>, Option<u32>) =
+            redis::pipe()
+                .cmd("GET")
+                .arg(session_score_key(session_id))
+                .cmd("GET")
+                .arg(hint_penalty_key(session_id))
+                .cmd("GET")
+                .arg(hints_used_key(session_id))
+                .query_async(&mut conn)
+                .await
+                .context("Failed to read session score")?;
+
+        let raw_score = raw_score.unwrap_or(0);
+        let penalty_percent = penalty_percent.unwrap_or(0).min(100);
+        Ok(FinalSessionScore {
+            raw_score,
+            penalty_percent,
+            hints_used: hints_used.unwrap_or(0),
+            score: apply_hint_penalty(raw_score, penalty_percent),
+        })
+    }
+
     // Check if this request was already processed (idempotency)
     async fn check_idempotency(
         &self,
@@ -373,6 +1155,37 @@ impl AnswerService {
         Ok(())
     }
 
+    /// Переводит сессию в `expired` (TTL ключа сохраняется) и сообщает об этом в SSE-стрим
+    async fn expire_session(&self, mut session: Session) -> Result<()> {
+        let mut conn = self.redis.clone();
+        let session_id = session.id.clone();
+        session.status = SessionStatus::Expired;
+
+        let updated: Option<String> = redis::cmd("SET")
+            .arg(format!("session:{}", session_id))
+            .arg(serde_json::to_string(&session)?)
+            .arg("XX")
+            .arg("KEEPTTL")
+            .query_async(&mut conn)
+            .await
+            .context("Failed to mark session as expired")?;
+        if updated.is_some() {
+            record_session_finished(SessionStatus::Expired);
+        }
+
+        let event = TimerEvent::Expired(SessionExpired {
+            session_id: session_id.clone(),
+            expires_at: session.expires_at,
+            timestamp: Utc::now(),
+        });
+        SessionEventLog::new(self.redis.clone(), self.settings.event_buffer_size)
+            .publish(&session_id, &event)
+            .await?;
+
+        tracing::info!("Session {} marked as expired", session_id);
+        Ok(())
+    }
+
     // Get session from Redis
     async fn get_session(&self, session_id: &str) -> Result<Session> {
         let mut conn = self.redis.clone();
@@ -392,9 +1205,9 @@ impl AnswerService {
         Ok(session)
     }
 
-    // Get correct answer from MongoDB tasks collection
-    async fn get_correct_answer(&self, task_id: &str) -> Result<String> {
-        use mongodb::bson::{doc, oid::ObjectId, Document};
+    // Get correct answers from MongoDB tasks collection and how to compare with them
+    async fn get_answer_key(&self, task_id: &str) -> Result<TaskAnswerSpec> {
+        use mongodb::bson::doc;
 
         let collection: mongodb::Collection<Document> = self.mongo.collection("tasks");
 
@@ -412,15 +1225,41 @@ impl AnswerService {
             .context("Failed to query tasks collection")?
             .ok_or_else(|| anyhow::anyhow!("Task {} not found", task_id))?;
 
-        let answer = task.get_str("correct_answer").or_else(|_| {
-            task.get_document("content")
-                .ok()
-                .and_then(|doc| doc.get_str("correct_answer").ok())
-                .ok_or_else(|| anyhow::anyhow!("Task {} missing correct_answer", task_id))
-        })?;
+        let answer_key = AnswerKey::from_task(&task)
+            .ok_or_else(|| anyhow::anyhow!("Task {} missing correct_answer", task_id))?;
+
+        let template_id = task.get_object_id("template_id").ok();
+        let template = match template_id {
+            Some(template_id) => self
+                .mongo
+                .collection::<Document>("templates")
+                .find_one(doc! { "_id": template_id })
+                .projection(doc! { "params": 1, "difficulty": 1 })
+                .await
+                .context("Failed to load template for answer check")?,
+            None => None,
+        };
+        let params = template
+            .as_ref()
+            .and_then(|template| template.get_document("params").ok());
+        let normalization = AnswerNormalization::resolve(&task, params, &self.settings);
+        let scoring = ScoringMode::resolve(&task, params);
+        let max_attempts = resolve_max_attempts(&task, params);
+        let difficulty = [Some(&task), template.as_ref()]
+            .into_iter()
+            .flatten()
+            .find_map(|source| source.get_str("difficulty").ok())
+            .map(str::to_string);
 
         tracing::info!("Retrieved correct answer for task {}", task_id);
-        Ok(answer.to_string())
+        Ok(TaskAnswerSpec {
+            key: answer_key,
+            normalization,
+            scoring,
+            max_attempts,
+            difficulty,
+            template_id,
+        })
     }
 
     // Update progress summary with attempt result (Rule S5)
@@ -430,6 +1269,7 @@ impl AnswerService {
         level_id: Option<&str>,
         fallback_task_id: &str,
         is_correct: bool,
+        credit: f64,
         score_delta: i32,
     ) -> Result<()> {
         // Используем реальный level_id, если он был указан при создании сессии
@@ -449,12 +1289,13 @@ impl AnswerService {
 
         let new_summary = if let Some(mut summary) = existing {
             // Update existing
+            let total_credit = summary.credit.unwrap_or(f64::from(summary.correct_count)) + credit;
             summary.attempts_total += 1;
             if is_correct {
                 summary.correct_count += 1;
             }
-            summary.percentage =
-                (summary.correct_count as f64 / summary.attempts_total as f64) * 100.0;
+            summary.credit = Some(total_credit);
+            summary.percentage = progress_percentage(total_credit, summary.attempts_total);
             summary.updated_at = Utc::now();
             summary.score = summary.score.saturating_add(score_delta);
             summary
@@ -466,7 +1307,8 @@ impl AnswerService {
                 level_id: level_key.clone(),
                 attempts_total: 1,
                 correct_count: if is_correct { 1 } else { 0 },
-                percentage: if is_correct { 100.0 } else { 0.0 },
+                credit: Some(credit),
+                percentage: progress_percentage(credit, 1),
                 score: score_delta,
                 updated_at: Utc::now(),
             }
@@ -505,6 +1347,402 @@ impl AnswerService {
 mod tests {
     use super::*;
 
+    #[test]
+    fn hint_penalty_reduces_positive_score() {
+        assert_eq!(apply_hint_penalty(10, 40), 6);
+        assert_eq!(apply_hint_penalty(15, 10), 13);
+        assert_eq!(apply_hint_penalty(10, 0), 10);
+        assert_eq!(apply_hint_penalty(10, 250), 0);
+        assert_eq!(apply_hint_penalty(0, 40), 0);
+        assert_eq!(apply_hint_penalty(-5, 40), -5);
+    }
+
+    const LENIENT: AnswerNormalization = AnswerNormalization {
+        strict_mode: false,
+        yo_equivalence: true,
+    };
+    const STRICT: AnswerNormalization = AnswerNormalization {
+        strict_mode: true,
+        yo_equivalence: true,
+    };
+
+    #[test]
+    fn normalization_accepts_yo_dash_and_spacing_variants() {
+        let cases = [
+            ("ёлка", "елка"),
+            ("Ёжик", "ежик"),
+            ("ВЕСЁЛЫЙ", "весёлый"),
+            ("северо–западный", "северо-западный"),
+            ("кто‑то", "кто-то"),
+            ("из—за", "из-за"),
+            ("−5", "-5"),
+            ("не\u{a0}был", "не был"),
+            ("  в  течение\tдня ", "в течение дня"),
+            ("пол\u{200b}года", "полгода"),
+            ("и\u{306}од", "йод"),
+            ("е\u{308}ж", "еж"),
+        ];
+        for (answer, correct) in cases {
+            let (is_correct, steps) = LENIENT.compare(answer, correct);
+            assert!(is_correct, "{answer:?} should match {correct:?}");
+            assert!(!steps.is_empty());
+        }
+
+        assert!(!LENIENT.compare("предать", "придать").0);
+        assert!(!LENIENT.compare("в течении", "в течение").0);
+    }
+
+    #[test]
+    fn normalization_reports_applied_steps_in_order() {
+        let (is_correct, steps) = LENIENT.compare("  Чёрно–белый ", "черно-белый");
+        assert!(is_correct);
+        assert_eq!(
+            steps,
+            vec![
+                NormalizationStep::Whitespace,
+                NormalizationStep::Dashes,
+                NormalizationStep::CaseFold,
+                NormalizationStep::YoToYe,
+            ]
+        );
+
+        let (is_correct, steps) = LENIENT.compare("42", "42");
+        assert!(is_correct);
+        assert!(steps.is_empty());
+
+        let (value, steps) = LENIENT.normalize("и\u{306}");
+        assert_eq!(value, "й");
+        assert_eq!(steps, vec![NormalizationStep::Nfc]);
+    }
+
+    #[test]
+    fn yo_equivalence_can_be_disabled() {
+        let normalization = AnswerNormalization {
+            yo_equivalence: false,
+            ..LENIENT
+        };
+        assert!(!normalization.compare("елка", "ёлка").0);
+        assert!(normalization.compare("ЁЛКА", "ёлка").0);
+    }
+
+    #[test]
+    fn strict_mode_only_trims_and_composes() {
+        assert!(STRICT.compare(" ёлка\n", "ёлка").0);
+        assert!(STRICT.compare("и\u{306}од", "йод").0);
+
+        assert!(!STRICT.compare("елка", "ёлка").0);
+        assert!(!STRICT.compare("Ёлка", "ёлка").0);
+        assert!(!STRICT.compare("из—за", "из-за").0);
+        assert!(!STRICT.compare("не\u{a0}был", "не был").0);
+
+        let (_, steps) = STRICT.compare(" ЁЛКА ", "ёлка");
+        assert_eq!(steps, vec![NormalizationStep::Whitespace]);
+    }
+
+    #[test]
+    fn strict_mode_resolves_from_task_then_template_params() {
+        use mongodb::bson::doc;
+
+        let settings = SessionSettings::default();
+        let resolve = |task: Document, params: Option<Document>| {
+            AnswerNormalization::resolve(&task, params.as_ref(), &settings).strict_mode
+        };
+
+        assert!(!resolve(doc! {}, None));
+        assert!(resolve(doc! { "strict_mode": true }, None));
+        assert!(resolve(doc! {}, Some(doc! { "strict_mode": true })));
+        assert!(!resolve(
+            doc! { "strict_mode": false },
+            Some(doc! { "strict_mode": true })
+        ));
+        assert!(AnswerNormalization::resolve(&doc! {}, None, &settings).yo_equivalence);
+    }
+
+    fn key(accepted: &[&str], pattern: Option<&str>) -> AnswerKey {
+        AnswerKey {
+            accepted: accepted.iter().map(|answer| answer.to_string()).collect(),
+            pattern: pattern.map(str::to_string),
+            parts: Vec::new(),
+        }
+    }
+
+    fn parts_request(parts: &[&str]) -> SubmitAnswerRequest {
+        SubmitAnswerRequest {
+            answer: String::new(),
+            idempotency_key: None,
+            parts: Some(parts.iter().map(|part| part.to_string()).collect()),
+        }
+    }
+
+    const PER_PART: ScoringMode = ScoringMode::PerPart {
+        points_per_part: None,
+    };
+
+    #[test]
+    fn partial_points_round_down_in_one_place() {
+        assert_eq!(PER_PART.points(2, 3), 6);
+        assert_eq!(PER_PART.points(1, 3), 3);
+        assert_eq!(PER_PART.points(3, 3), CORRECT_ANSWER_POINTS);
+        assert_eq!(PER_PART.points(0, 3), 0);
+        assert_eq!(PER_PART.points(1, 2), 5);
+        assert_eq!(PER_PART.points(5, 7), 7);
+        assert_eq!(PER_PART.points(1, 0), 0);
+        assert_eq!(PER_PART.points(4, 3), CORRECT_ANSWER_POINTS);
+
+        let fixed = ScoringMode::PerPart {
+            points_per_part: Some(4),
+        };
+        assert_eq!(fixed.points(2, 3), 8);
+        assert_eq!(fixed.points(3, 3), 12);
+
+        assert_eq!(ScoringMode::Exact.points(1, 1), CORRECT_ANSWER_POINTS);
+        assert_eq!(ScoringMode::Exact.points(0, 1), 0);
+    }
+
+    #[test]
+    fn progress_percentage_counts_fractional_credit() {
+        assert_eq!(progress_percentage(0.0, 0), 0.0);
+        assert_eq!(progress_percentage(1.0, 1), 100.0);
+        let two_of_three = 2.0 / 3.0;
+        assert!((progress_percentage(1.0 + two_of_three, 2) - 83.333).abs() < 0.001);
+    }
+
+    #[test]
+    fn per_part_answers_are_checked_independently() {
+        let answer_key = AnswerKey {
+            parts: vec![
+                key(&["о"], None),
+                key(&["е", "ё"], None),
+                key(&[], Some("н{1,2}")),
+            ],
+            ..AnswerKey::default()
+        };
+
+        let check = check_answer(
+            &parts_request(&["О", "и", "нн"]),
+            &answer_key,
+            &LENIENT,
+            PER_PART,
+        )
+        .unwrap();
+        assert!(!check.correct);
+        assert_eq!(check.points, 6);
+        assert!((check.credit - 2.0 / 3.0).abs() < f64::EPSILON);
+        assert_eq!(
+            check.parts,
+            vec![
+                AnswerPartResult {
+                    index: 0,
+                    correct: true
+                },
+                AnswerPartResult {
+                    index: 1,
+                    correct: false
+                },
+                AnswerPartResult {
+                    index: 2,
+                    correct: true
+                },
+            ]
+        );
+        // Неверная часть сообщает шаги всех своих вариантов (`ё` -> `е`)
+        assert_eq!(
+            check.normalization,
+            vec![NormalizationStep::CaseFold, NormalizationStep::YoToYe]
+        );
+
+        let check = check_answer(
+            &parts_request(&["о", "ё", "н"]),
+            &answer_key,
+            &LENIENT,
+            PER_PART,
+        )
+        .unwrap();
+        assert!(check.correct);
+        assert_eq!(check.points, CORRECT_ANSWER_POINTS);
+        assert_eq!(check.credit, 1.0);
+    }
+
+    #[test]
+    fn per_part_rejects_wrong_number_of_parts() {
+        let answer_key = AnswerKey {
+            parts: vec![key(&["о"], None), key(&["е"], None)],
+            ..AnswerKey::default()
+        };
+
+        let error =
+            check_answer(&parts_request(&["о"]), &answer_key, &LENIENT, PER_PART).unwrap_err();
+        assert_eq!((error.expected, error.received), (2, 1));
+
+        let text_only = SubmitAnswerRequest {
+            answer: "о е".to_string(),
+            idempotency_key: None,
+            parts: None,
+        };
+        let error = check_answer(&text_only, &answer_key, &LENIENT, PER_PART).unwrap_err();
+        assert_eq!((error.expected, error.received), (2, 0));
+    }
+
+    #[test]
+    fn exact_scoring_ignores_parts() {
+        let request = SubmitAnswerRequest {
+            answer: "42".to_string(),
+            idempotency_key: None,
+            parts: None,
+        };
+        let check =
+            check_answer(&request, &key(&["42"], None), &LENIENT, ScoringMode::Exact).unwrap();
+        assert!(check.correct);
+        assert_eq!(check.points, CORRECT_ANSWER_POINTS);
+        assert!(check.parts.is_empty());
+
+        // Режим per_part без частей в задании проверяет ответ целиком
+        let check = check_answer(&request, &key(&["42"], None), &LENIENT, PER_PART).unwrap();
+        assert!(check.correct);
+    }
+
+    #[test]
+    fn scoring_mode_resolves_from_task_then_template_params() {
+        use mongodb::bson::doc;
+
+        assert_eq!(ScoringMode::resolve(&doc! {}, None), ScoringMode::Exact);
+        assert_eq!(
+            ScoringMode::resolve(&doc! {}, Some(&doc! { "scoring": "per_part" })),
+            PER_PART
+        );
+        assert_eq!(
+            ScoringMode::resolve(
+                &doc! { "scoring": "per_part" },
+                Some(&doc! { "points_per_part": 3 })
+            ),
+            ScoringMode::PerPart {
+                points_per_part: Some(3)
+            }
+        );
+        assert_eq!(
+            ScoringMode::resolve(
+                &doc! { "scoring": "exact" },
+                Some(&doc! { "scoring": "per_part" })
+            ),
+            ScoringMode::Exact
+        );
+    }
+
+    #[test]
+    fn answer_latencies_are_gaps_between_offsets() {
+        assert!(answer_latencies(&[]).is_empty());
+        assert_eq!(answer_latencies(&[4000]), vec![4000]);
+        assert_eq!(
+            answer_latencies(&[4000, 5500, 12_000]),
+            vec![4000, 1500, 6500]
+        );
+        // Одновременные ответы легли не по порядку
+        assert_eq!(answer_latencies(&[3000, 2900, 3100]), vec![3000, 0, 200]);
+    }
+
+    #[test]
+    fn max_attempts_resolves_from_task_then_template_params() {
+        use mongodb::bson::doc;
+
+        assert_eq!(resolve_max_attempts(&doc! {}, None), None);
+        assert_eq!(
+            resolve_max_attempts(&doc! {}, Some(&doc! { "max_attempts": 3_i64 })),
+            Some(3)
+        );
+        assert_eq!(
+            resolve_max_attempts(
+                &doc! { "max_attempts": 2 },
+                Some(&doc! { "max_attempts": 5 })
+            ),
+            Some(2)
+        );
+        // 0 и отрицательные значения лимит не задают
+        assert_eq!(
+            resolve_max_attempts(&doc! { "max_attempts": 0 }, None),
+            None
+        );
+        assert_eq!(
+            resolve_max_attempts(&doc! { "max_attempts": -1 }, None),
+            None
+        );
+    }
+
+    #[test]
+    fn any_accepted_answer_is_correct() {
+        let answers = key(&["не знаю", "не знаю."], None);
+        assert!(LENIENT.check("Не знаю", &answers).0);
+        assert!(LENIENT.check("не знаю.", &answers).0);
+        assert!(!LENIENT.check("не знаю!", &answers).0);
+
+        // Шаги - только от совпавшего варианта
+        let (is_correct, steps) = LENIENT.check("НЕ ЗНАЮ.", &answers);
+        assert!(is_correct);
+        assert_eq!(steps, vec![NormalizationStep::CaseFold]);
+    }
+
+    #[test]
+    fn pattern_matches_whole_answer() {
+        let answers = key(&[], Some(r"(в\s)?\d{4}(\sг(оду?|\.)?)?"));
+        assert!(LENIENT.check("1812", &answers).0);
+        assert!(LENIENT.check("в 1812 году", &answers).0);
+        assert!(LENIENT.check("В  1812 г.", &answers).0);
+        assert!(!LENIENT.check("около 1812", &answers).0);
+
+        // Шаблон с `ё` и заглавной буквой работает и при ё→е, и в строгом режиме
+        let answers = key(&[], Some("Пётр( I| Первый)?"));
+        assert!(LENIENT.check("пётр первый", &answers).0);
+        assert!(LENIENT.check("Пётр I", &answers).0);
+        assert!(STRICT.check("Пётр I", &answers).0);
+        assert!(!STRICT.check("пётр I", &answers).0);
+        assert!(!STRICT.check("Петр I", &answers).0);
+    }
+
+    #[test]
+    fn invalid_stored_pattern_is_ignored() {
+        let answers = key(&["да"], Some("(a{1000}){1000}"));
+        assert!(LENIENT.check("да", &answers).0);
+        assert!(!LENIENT.check("aaa", &answers).0);
+    }
+
+    #[test]
+    fn answer_key_falls_back_to_legacy_correct_answer() {
+        use mongodb::bson::doc;
+
+        let legacy = AnswerKey::from_task(&doc! { "correct_answer": "42" }).unwrap();
+        assert_eq!(legacy, key(&["42"], None));
+
+        let nested =
+            AnswerKey::from_task(&doc! { "content": { "correct_answer": "ученика" } }).unwrap();
+        assert_eq!(nested, key(&["ученика"], None));
+
+        let combined = AnswerKey::from_task(&doc! {
+            "correct_answer": "не знаю",
+            "answers": { "accepted": ["не знаю", "не знаю.", " "], "pattern": r"не\s?знаю" },
+        })
+        .unwrap();
+        assert_eq!(combined, key(&["не знаю", "не знаю."], Some(r"не\s?знаю")));
+
+        let pattern_only =
+            AnswerKey::from_task(&doc! { "answers": { "pattern": r"\d+" } }).unwrap();
+        assert_eq!(pattern_only, key(&[], Some(r"\d+")));
+
+        let parts = AnswerKey::from_task(&doc! {
+            "answers": { "parts": [{ "accepted": ["о"] }, { "accepted": [] }, { "pattern": "н+" }] },
+        })
+        .unwrap();
+        assert_eq!(
+            parts.parts,
+            vec![
+                key(&["о"], None),
+                AnswerKey::default(),
+                key(&[], Some("н+"))
+            ]
+        );
+
+        assert!(AnswerKey::from_task(&doc! { "title": "Без ответа" }).is_none());
+        assert!(AnswerKey::from_task(&doc! { "correct_answer": "" }).is_none());
+    }
+
     #[test]
     #[serial_test::serial]
     fn answers_save_async_default_enabled() {
@@ -0,0 +1,325 @@
+use std::collections::{BTreeMap, HashSet};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, DateTime as BsonDateTime, Document},
+    Database,
+};
+use sha2::{Digest, Sha256};
+
+use crate::models::anticheat::{
+    ActionTaken, AnticheatPreviewRequest, AnticheatPreviewResponse, IncidentType, PreviewExample,
+    PreviewOutcome, RulePreview, SessionTimingStats,
+};
+use crate::services::anticheat_service::{evaluate, DetectionThresholds};
+
+pub const SESSION_STATS_COLLECTION: &str = "anticheat_session_stats";
+pub const MAX_PREVIEW_LOOKBACK_DAYS: u32 = 30;
+/// Верхняя граница числа агрегатов за один предпросмотр
+pub const MAX_PREVIEW_SESSIONS: usize = 20_000;
+const PREVIEW_EXAMPLES: usize = 5;
+
+/// Прогон кандидатных настроек античита по агрегатам прошлых сессий.
+/// Сырые ответы (attempt_records) не читаются: только anticheat_session_stats и incidents.
+pub struct AnticheatPreviewService {
+    mongo: Database,
+}
+
+impl AnticheatPreviewService {
+    pub fn new(mongo: Database) -> Self {
+        Self { mongo }
+    }
+
+    pub async fn preview(
+        &self,
+        request: &AnticheatPreviewRequest,
+    ) -> Result<AnticheatPreviewResponse> {
+        let since = Utc::now() - Duration::days(request.lookback_days as i64);
+
+        let mut stats = self.load_session_stats(since).await?;
+        let truncated = stats.len() > MAX_PREVIEW_SESSIONS;
+        stats.truncate(MAX_PREVIEW_SESSIONS);
+
+        let recorded_incidents = self.recorded_incidents(since).await?;
+
+        // Соль на запрос: псевдонимы из разных ответов нельзя сопоставить
+        let salt: [u8; 16] = rand::random();
+        let outcome = simulate(
+            &DetectionThresholds::from(&request.settings),
+            request.settings.time_window_seconds,
+            &stats,
+            &salt,
+        );
+
+        Ok(AnticheatPreviewResponse {
+            lookback_days: request.lookback_days,
+            truncated,
+            outcome,
+            recorded_incidents,
+        })
+    }
+
+    async fn load_session_stats(&self, since: DateTime<Utc>) -> Result<Vec<SessionTimingStats>> {
+        let collection = self
+            .mongo
+            .collection::<SessionTimingStats>(SESSION_STATS_COLLECTION);
+
+        let cursor = collection
+            .find(doc! {
+                "last_answer_at": { "$gte": BsonDateTime::from_millis(since.timestamp_millis()) }
+            })
+            .sort(doc! { "last_answer_at": -1 })
+            .limit(MAX_PREVIEW_SESSIONS as i64 + 1)
+            .await
+            .context("Failed to query anticheat session stats")?;
+
+        cursor
+            .try_collect()
+            .await
+            .context("Failed to read anticheat session stats")
+    }
+
+    async fn recorded_incidents(&self, since: DateTime<Utc>) -> Result<BTreeMap<String, u64>> {
+        let collection = self.mongo.collection::<Document>("incidents");
+        // timestamp инцидентов хранится строкой RFC 3339
+        let since = since.to_rfc3339_opts(SecondsFormat::Secs, true);
+
+        let mut cursor = collection
+            .aggregate(vec![
+                doc! { "$match": { "timestamp": { "$gte": since } } },
+                doc! { "$group": { "_id": "$incident_type", "count": { "$sum": 1 } } },
+            ])
+            .await
+            .context("Failed to aggregate incidents for preview")?;
+
+        let mut counts = BTreeMap::new();
+        while let Some(row) = cursor.try_next().await? {
+            if let Ok(incident_type) = row.get_str("_id") {
+                let count = row
+                    .get_i32("count")
+                    .map(|v| v as i64)
+                    .or_else(|_| row.get_i64("count"))
+                    .unwrap_or(0);
+                counts.insert(incident_type.to_string(), count.max(0) as u64);
+            }
+        }
+        Ok(counts)
+    }
+}
+
+/// Одноразовый псевдоним идентификатора в рамках одного ответа
+pub fn anonymize(prefix: &str, id: &str, salt: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(salt);
+    hasher.update(id.as_bytes());
+    let digest = hex::encode(hasher.finalize());
+    format!("{}-{}", prefix, &digest[..10])
+}
+
+#[derive(Default)]
+struct RuleTally {
+    sessions: u64,
+    users: HashSet<String>,
+    blocked_sessions: u64,
+}
+
+/// Применить пороги к агрегатам сессий и посчитать срабатывания по правилам
+pub fn simulate(
+    thresholds: &DetectionThresholds,
+    window_seconds: u32,
+    stats: &[SessionTimingStats],
+    salt: &[u8],
+) -> PreviewOutcome {
+    let mut outcome = PreviewOutcome {
+        sessions_evaluated: stats.len() as u64,
+        ..PreviewOutcome::default()
+    };
+    let mut flagged_users = HashSet::new();
+    let mut blocked_users = HashSet::new();
+    let mut by_rule: BTreeMap<IncidentType, RuleTally> = BTreeMap::new();
+    let mut hits = Vec::new();
+
+    for session in stats {
+        let peak = session.peak_for_window(window_seconds);
+        let Some(detection) = evaluate(thresholds, peak, session.max_repeated_answers) else {
+            continue;
+        };
+
+        let blocked = detection.action == ActionTaken::Blocked;
+        outcome.flagged_sessions += 1;
+        flagged_users.insert(session.user_id.as_str());
+        if blocked {
+            outcome.blocked_sessions += 1;
+            blocked_users.insert(session.user_id.as_str());
+        }
+
+        let tally = by_rule.entry(detection.rule.clone()).or_default();
+        tally.sessions += 1;
+        tally.users.insert(session.user_id.clone());
+        if blocked {
+            tally.blocked_sessions += 1;
+        }
+
+        hits.push((detection, peak, session));
+    }
+
+    outcome.flagged_users = flagged_users.len() as u64;
+    outcome.blocked_users = blocked_users.len() as u64;
+    outcome.by_rule = by_rule
+        .into_iter()
+        .map(|(rule, tally)| RulePreview {
+            rule,
+            sessions: tally.sessions,
+            users: tally.users.len() as u64,
+            blocked_sessions: tally.blocked_sessions,
+        })
+        .collect();
+
+    // Самые показательные случаи: сначала по серьёзности, затем по интенсивности
+    hits.sort_by(|(a, a_peak, a_session), (b, b_peak, b_session)| {
+        b.severity
+            .cmp(&a.severity)
+            .then(b_peak.cmp(a_peak))
+            .then(
+                b_session
+                    .max_repeated_answers
+                    .cmp(&a_session.max_repeated_answers),
+            )
+            .then(a_session.session_id.cmp(&b_session.session_id))
+    });
+    outcome.examples = hits
+        .into_iter()
+        .take(PREVIEW_EXAMPLES)
+        .map(|(detection, peak, session)| PreviewExample {
+            user_ref: anonymize("user", &session.user_id, salt),
+            session_ref: anonymize("session", &session.session_id, salt),
+            rule: detection.rule,
+            severity: detection.severity,
+            action: detection.action,
+            answers: session.answers,
+            peak_answers: peak,
+            max_repeated_answers: session.max_repeated_answers,
+        })
+        .collect();
+
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::anticheat::{IncidentSeverity, WindowPeak};
+
+    fn session(id: &str, user: &str, peak_per_hour: u32, repeated: u32) -> SessionTimingStats {
+        SessionTimingStats {
+            session_id: id.to_string(),
+            user_id: user.to_string(),
+            answers: peak_per_hour.max(repeated),
+            peaks: vec![
+                WindowPeak {
+                    window_seconds: 300,
+                    answers: peak_per_hour / 2,
+                },
+                WindowPeak {
+                    window_seconds: 3600,
+                    answers: peak_per_hour,
+                },
+            ],
+            max_repeated_answers: repeated,
+            first_answer_at: BsonDateTime::from_millis(0),
+            last_answer_at: BsonDateTime::from_millis(0),
+        }
+    }
+
+    fn seeded() -> Vec<SessionTimingStats> {
+        vec![
+            session("s1", "u1", 3, 1),   // чисто
+            session("s2", "u1", 7, 1),   // подозрительно
+            session("s3", "u2", 12, 2),  // скорость -> блок
+            session("s4", "u3", 4, 9),   // повторы -> блок
+            session("s5", "u2", 11, 10), // скорость -> блок
+        ]
+    }
+
+    #[test]
+    fn test_simulate_counts_match_hand_computed_expectation() {
+        let outcome = simulate(&DetectionThresholds::default(), 3600, &seeded(), b"salt");
+
+        assert_eq!(outcome.sessions_evaluated, 5);
+        assert_eq!(outcome.flagged_sessions, 4);
+        assert_eq!(outcome.flagged_users, 3);
+        assert_eq!(outcome.blocked_sessions, 3);
+        assert_eq!(outcome.blocked_users, 2);
+
+        assert_eq!(
+            outcome.by_rule,
+            vec![
+                RulePreview {
+                    rule: IncidentType::SpeedViolation,
+                    sessions: 2,
+                    users: 1,
+                    blocked_sessions: 2,
+                },
+                RulePreview {
+                    rule: IncidentType::RepeatedAnswers,
+                    sessions: 1,
+                    users: 1,
+                    blocked_sessions: 1,
+                },
+                RulePreview {
+                    rule: IncidentType::SuspiciousPattern,
+                    sessions: 1,
+                    users: 1,
+                    blocked_sessions: 0,
+                },
+            ]
+        );
+
+        let first = &outcome.examples[0];
+        assert_eq!(first.severity, IncidentSeverity::Critical);
+        assert_eq!(first.peak_answers, 12);
+    }
+
+    #[test]
+    fn test_simulate_uses_candidate_window_and_auto_block() {
+        let thresholds = DetectionThresholds {
+            auto_block_severity: None,
+            ..DetectionThresholds::default()
+        };
+        // В 5-минутном окне пики вдвое ниже: s3 (12/2 = 6) станет подозрительной,
+        // s4 и s5 сработают по повторам
+        let outcome = simulate(&thresholds, 300, &seeded(), b"salt");
+
+        assert_eq!(outcome.flagged_sessions, 3);
+        assert_eq!(outcome.blocked_sessions, 0);
+    }
+
+    #[test]
+    fn test_examples_are_anonymized_and_consistent() {
+        let outcome = simulate(&DetectionThresholds::default(), 3600, &seeded(), b"salt");
+
+        for example in &outcome.examples {
+            assert!(example.user_ref.starts_with("user-"));
+            assert!(example.session_ref.starts_with("session-"));
+            assert!(!example.user_ref.contains("u1") && !example.user_ref.contains("u2"));
+        }
+
+        // Один и тот же пользователь получает один псевдоним внутри ответа
+        let u2_refs: HashSet<_> = outcome
+            .examples
+            .iter()
+            .filter(|e| e.rule == IncidentType::SpeedViolation)
+            .map(|e| e.user_ref.clone())
+            .collect();
+        assert_eq!(u2_refs.len(), 1);
+
+        // С другой солью псевдонимы другие
+        assert_ne!(
+            anonymize("user", "u2", b"salt"),
+            anonymize("user", "u2", b"other")
+        );
+        assert!(outcome.examples.len() <= PREVIEW_EXAMPLES);
+    }
+}
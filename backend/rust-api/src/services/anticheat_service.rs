@@ -1,26 +1,330 @@
+use std::collections::BTreeMap;
+use std::pin::Pin;
+
 use anyhow::{Context, Result};
-use chrono::Utc;
-use mongodb::Database;
+use chrono::{DateTime, Duration, Utc};
+use futures::stream::{BoxStream, StreamExt, TryStreamExt};
+use mongodb::{
+    bson::{self, doc, oid::ObjectId, Document},
+    Database,
+};
 use redis::aio::ConnectionManager;
 use reqwest::Client;
 use uuid::Uuid;
 
+use crate::config::AnticheatSignalSettings;
+use crate::models::answer::{median_ms, AnswerTimingSummary, AttemptRecord};
 use crate::models::anticheat::{
-    ActionTaken, AnticheatStatus, IncidentDetails, IncidentRecord, IncidentSeverity,
-    IncidentStatus, IncidentType,
+    ActionTaken, AnticheatStatus, ClientSignal, IncidentAutoBlock, IncidentDetails, IncidentRecord,
+    IncidentSeverity, IncidentStatus, IncidentType, SignalBatchResponse, SignalType, StoredSignal,
+    TimelineEvent, TimelineEventKind,
 };
+use crate::models::hint::HintRecord;
+use crate::models::session_archive::SessionRecord;
+use crate::models::system_settings::{AnswerLatencyThresholds, AnticheatSettings};
+use crate::models::user::BlockUserRequest;
+use crate::models::{Session, SessionStatus};
+use crate::services::user_management_service::UserManagementService;
 
 use crate::utils::retry::{retry_async_with_config, RetryConfig};
 
 const SPEED_THRESHOLD_SUSPICIOUS: u32 = 5; // >5 attempts per hour = suspicious
 const SPEED_THRESHOLD_BLOCKED: u32 = 10; // >10 attempts per hour = blocked
 const REPEATED_THRESHOLD_BLOCKED: u32 = 8; // >8 repeated answers = blocked
-const TIME_WINDOW_SECONDS: u64 = 3600; // 1 hour
+const SIGNAL_RATE_WINDOW_SECONDS: u64 = 60;
+/// Счётчики сигналов переживают любую сессию
+const SIGNAL_COUNTERS_TTL_SECONDS: u64 = 86_400;
+
+/// Счёт сигналов сессии по типам. Поле `reported:<тип>` ставится один раз, когда
+/// счётчик впервые превысил порог, чтобы инцидент по типу не открывался повторно.
+/// ARGV[1] - TTL, далее тройки (тип, прирост, порог) в порядке SignalType::ALL.
+/// Возвращает пары {итог, 1 если порог превышен впервые}.
+const SIGNAL_COUNTERS_SCRIPT: &str = r#"
+    local result = {}
+    for i = 2, #ARGV, 3 do
+        local signal = ARGV[i]
+        local total = redis.call('HINCRBY', KEYS[1], signal, tonumber(ARGV[i + 1]))
+        local crossed = 0
+        if total > tonumber(ARGV[i + 2]) then
+            crossed = redis.call('HSETNX', KEYS[1], 'reported:' .. signal, 1)
+        end
+        table.insert(result, total)
+        table.insert(result, crossed)
+    end
+    redis.call('EXPIRE', KEYS[1], tonumber(ARGV[1]))
+    return result
+"#;
+
+/// Лимит пакетов сигналов по сессии превышен (ответ 429 `RATE_LIMITED`)
+#[derive(Debug, thiserror::Error)]
+#[error("Too many signal batches for session {session_id}")]
+pub struct SignalRateLimited {
+    pub session_id: String,
+    pub retry_after_secs: u64,
+}
+
+/// Порог для типа сигнала: инцидент открывается, когда сигналов больше него
+pub fn signal_threshold(settings: &AnticheatSignalSettings, signal_type: SignalType) -> u32 {
+    match signal_type {
+        SignalType::TabSwitch => settings.tab_switch_threshold,
+        SignalType::Paste => settings.paste_threshold,
+        SignalType::Devtools => settings.devtools_threshold,
+        // Серверный сигнал инцидентов не открывает
+        SignalType::FastAnswers => u32::MAX,
+    }
+}
+
+/// Пороги сигналов с учётом настроек античита из админки
+pub fn effective_signal_settings(
+    base: &AnticheatSignalSettings,
+    settings: &AnticheatSettings,
+) -> AnticheatSignalSettings {
+    let mut effective = base.clone();
+    if let Some(thresholds) = &settings.signal_thresholds {
+        effective.tab_switch_threshold = thresholds.tab_switch;
+        effective.paste_threshold = thresholds.paste;
+        effective.devtools_threshold = thresholds.devtools;
+    }
+    effective
+}
+
+fn signal_counters_key(session_id: &str) -> String {
+    format!("anticheat:signals:{}", session_id)
+}
+
+fn signal_rate_key(session_id: &str) -> String {
+    format!("anticheat:signals:rate:{}", session_id)
+}
+
+/// Метка «сигнал `fast_answers` по сессии уже записан»
+fn fast_answers_key(session_id: &str) -> String {
+    format!("anticheat:fast_answers:{}", session_id)
+}
+
+/// Медиана времени ответов сессии ниже порога сложности задания
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FastAnswers {
+    pub answers: u32,
+    pub median_ms: i64,
+    pub threshold_ms: u32,
+}
+
+/// Проверить время ответов сессии (`since_previous_ms` по порядку). None - ответов
+/// меньше `min_answers`, порог для сложности выключен или медиана не ниже него
+pub fn evaluate_answer_latency(
+    thresholds: &AnswerLatencyThresholds,
+    difficulty: Option<&str>,
+    latencies: &[i64],
+) -> Option<FastAnswers> {
+    let threshold_ms = thresholds.threshold_ms(difficulty);
+    if threshold_ms == 0 || latencies.len() < thresholds.min_answers as usize {
+        return None;
+    }
+    let median = median_ms(latencies)?;
+    (median < i64::from(threshold_ms)).then_some(FastAnswers {
+        answers: latencies.len() as u32,
+        median_ms: median,
+        threshold_ms,
+    })
+}
+
+/// Пороги детекции. Live-путь и предпросмотр настроек считают по одним правилам.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetectionThresholds {
+    pub suspicious_speed_hits: u32,
+    pub blocked_speed_hits: u32,
+    pub blocked_repeated_hits: u32,
+    /// None - автоблокировка выключена, нарушения только помечаются
+    pub auto_block_severity: Option<IncidentSeverity>,
+}
+
+impl Default for DetectionThresholds {
+    fn default() -> Self {
+        Self {
+            suspicious_speed_hits: SPEED_THRESHOLD_SUSPICIOUS,
+            blocked_speed_hits: SPEED_THRESHOLD_BLOCKED,
+            blocked_repeated_hits: REPEATED_THRESHOLD_BLOCKED,
+            auto_block_severity: Some(IncidentSeverity::Critical),
+        }
+    }
+}
+
+impl From<&AnticheatSettings> for DetectionThresholds {
+    fn from(settings: &AnticheatSettings) -> Self {
+        Self {
+            suspicious_speed_hits: settings.suspicious_speed_hits,
+            blocked_speed_hits: settings.max_speed_hits,
+            blocked_repeated_hits: settings.max_repeated_hits,
+            auto_block_severity: if settings.auto_block {
+                settings.auto_block_min_severity.clone()
+            } else {
+                None
+            },
+        }
+    }
+}
+
+impl DetectionThresholds {
+    /// Реакция на инцидент с такой серьёзностью
+    pub fn action_for(&self, severity: &IncidentSeverity) -> ActionTaken {
+        match &self.auto_block_severity {
+            Some(min) if severity >= min => ActionTaken::Blocked,
+            _ => ActionTaken::Flagged,
+        }
+    }
+}
+
+/// Сработавшее правило и реакция на него
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Detection {
+    pub rule: IncidentType,
+    pub severity: IncidentSeverity,
+    pub action: ActionTaken,
+}
+
+/// Оценить счётчики попаданий за окно. None - нарушений нет.
+pub fn evaluate(
+    thresholds: &DetectionThresholds,
+    speed_hits: u32,
+    repeated_hits: u32,
+) -> Option<Detection> {
+    let (rule, severity) = if speed_hits > thresholds.blocked_speed_hits {
+        (IncidentType::SpeedViolation, IncidentSeverity::Critical)
+    } else if repeated_hits > thresholds.blocked_repeated_hits {
+        (IncidentType::RepeatedAnswers, IncidentSeverity::Critical)
+    } else if speed_hits > thresholds.suspicious_speed_hits {
+        (IncidentType::SuspiciousPattern, IncidentSeverity::Medium)
+    } else {
+        return None;
+    };
+
+    let action = thresholds.action_for(&severity);
+
+    Some(Detection {
+        rule,
+        severity,
+        action,
+    })
+}
+
+/// Отпечаток ответа для поиска повторов (без учёта регистра и пробелов по краям)
+pub fn answer_fingerprint(answer: &str) -> String {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = DefaultHasher::new();
+    answer.trim().to_lowercase().hash(&mut hasher);
+    format!("{:x}", hasher.finish())
+}
+
+/// Сколько событий отдаёт хронология сессии
+pub const MAX_TIMELINE_EVENTS: usize = 2000;
+
+/// Хронология сессии без привязки к инциденту
+#[derive(Debug, Clone)]
+pub struct SessionTimeline {
+    pub started_at: Option<DateTime<Utc>>,
+    pub truncated: bool,
+    pub events: Vec<TimelineEvent>,
+}
+
+struct SessionBounds {
+    started_at: DateTime<Utc>,
+    /// Время и статус завершения; у активной сессии нет
+    ended: Option<(DateTime<Utc>, SessionStatus)>,
+}
+
+type TimedEvent = (DateTime<Utc>, TimelineEventKind);
+
+/// Слить отсортированные по времени потоки в один, прочитав не больше `limit` событий.
+/// Второе значение - остались ли непрочитанные события
+async fn merge_by_time(
+    sources: Vec<BoxStream<'_, Result<TimedEvent>>>,
+    limit: usize,
+) -> Result<(Vec<TimedEvent>, bool)> {
+    let mut sources: Vec<_> = sources.into_iter().map(StreamExt::peekable).collect();
+    let mut merged = Vec::new();
+    loop {
+        let mut earliest: Option<(usize, DateTime<Utc>)> = None;
+        let mut failed = None;
+        for (index, source) in sources.iter_mut().enumerate() {
+            match Pin::new(source).peek().await {
+                Some(Ok((at, _))) if earliest.is_none_or(|(_, min)| *at < min) => {
+                    earliest = Some((index, *at));
+                }
+                Some(Err(_)) => {
+                    failed = Some(index);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        // Ошибку забираем из потока, чтобы вернуть владеющее значение
+        if let Some(index) = failed {
+            if let Some(Err(err)) = sources[index].next().await {
+                return Err(err);
+            }
+        }
+        let Some((index, _)) = earliest else {
+            return Ok((merged, false));
+        };
+        if merged.len() == limit {
+            return Ok((merged, true));
+        }
+        if let Some(Ok(event)) = sources[index].next().await {
+            merged.push(event);
+        }
+    }
+}
+
+/// Смещения от начала сессии, события начала и завершения и паузы длиннее `inactivity`
+fn build_timeline(
+    started_at: Option<DateTime<Utc>>,
+    ended: Option<(DateTime<Utc>, SessionStatus)>,
+    events: Vec<TimedEvent>,
+    inactivity: Duration,
+) -> Vec<TimelineEvent> {
+    let Some(origin) = started_at else {
+        return Vec::new();
+    };
+
+    let mut timed = Vec::with_capacity(events.len() + 2);
+    timed.push((origin, TimelineEventKind::SessionStarted));
+    timed.extend(events);
+    if let Some((at, status)) = ended {
+        timed.push((at, TimelineEventKind::SessionEnded { status }));
+    }
+
+    let mut timeline = Vec::with_capacity(timed.len());
+    let mut previous: Option<DateTime<Utc>> = None;
+    for (at, kind) in timed {
+        if let Some(previous) = previous {
+            let gap = at - previous;
+            if gap > inactivity {
+                timeline.push(TimelineEvent {
+                    timestamp: previous,
+                    offset_ms: (previous - origin).num_milliseconds(),
+                    kind: TimelineEventKind::Inactivity {
+                        duration_seconds: gap.num_seconds(),
+                    },
+                });
+            }
+        }
+        previous = Some(previous.map_or(at, |previous| previous.max(at)));
+        timeline.push(TimelineEvent {
+            timestamp: at,
+            offset_ms: (at - origin).num_milliseconds(),
+            kind,
+        });
+    }
+    timeline
+}
 
 pub struct AnticheatService {
     mongo: Database,
     redis: ConnectionManager,
     http_client: Client,
+    settings: AnticheatSettings,
 }
 
 impl AnticheatService {
@@ -29,9 +333,20 @@ impl AnticheatService {
             mongo,
             redis,
             http_client: Client::new(),
+            settings: AnticheatSettings::default(),
         }
     }
 
+    /// Настройки из админки (`AppState::anticheat_settings`); без них - значения по умолчанию
+    pub fn with_settings(mut self, settings: AnticheatSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    fn thresholds(&self) -> DetectionThresholds {
+        DetectionThresholds::from(&self.settings)
+    }
+
     /// Track answer submission and check for violations
     pub async fn track_answer(
         &self,
@@ -71,30 +386,19 @@ impl AnticheatService {
         );
 
         // Check thresholds
-        let is_blocked =
-            speed_hits > SPEED_THRESHOLD_BLOCKED || repeated_hits > REPEATED_THRESHOLD_BLOCKED;
-        let is_suspicious = !is_blocked && speed_hits > SPEED_THRESHOLD_SUSPICIOUS;
-
-        // Create incident if threshold exceeded
-        if is_blocked {
-            self.create_incident(
-                user_id,
-                speed_hits,
-                repeated_hits,
-                IncidentSeverity::Critical,
-                ActionTaken::Blocked,
-            )
-            .await?;
-        } else if is_suspicious {
-            self.create_incident(
-                user_id,
-                speed_hits,
-                repeated_hits,
-                IncidentSeverity::Medium,
-                ActionTaken::Flagged,
-            )
-            .await?;
-        }
+        let detection = evaluate(&self.thresholds(), speed_hits, repeated_hits);
+        let is_suspicious = detection.is_some();
+
+        // Create incident if threshold exceeded; автоблокировка может не состояться
+        let is_blocked = match detection {
+            Some(detection) => {
+                self.create_incident(user_id, session_id, speed_hits, repeated_hits, detection)
+                    .await?
+                    == ActionTaken::Blocked
+            }
+            None => false,
+        };
+        let is_suspicious = is_suspicious && !is_blocked;
 
         Ok(AnticheatStatus {
             user_id: user_id.to_string(),
@@ -124,7 +428,7 @@ impl AnticheatService {
         let repeated_key = format!(
             "anticheat:repeated:{}:{}",
             user_id,
-            self.hash_answer(answer)
+            answer_fingerprint(answer)
         );
 
         // Lua script for atomic increment with TTL
@@ -151,7 +455,7 @@ impl AnticheatService {
         let result: Vec<u32> = redis::Script::new(lua_script)
             .key(&speed_key)
             .key(&repeated_key)
-            .arg(TIME_WINDOW_SECONDS)
+            .arg(self.settings.time_window_seconds)
             .invoke_async(&mut conn)
             .await
             .context("Failed to increment anticheat counters")?;
@@ -159,54 +463,138 @@ impl AnticheatService {
         Ok((result[0], result[1]))
     }
 
-    /// Create incident record and publish to Redis Pub/Sub
+    /// Create incident record and publish to Redis Pub/Sub; возвращает итоговую реакцию
     async fn create_incident(
         &self,
         user_id: &str,
+        session_id: &str,
         speed_hits: u32,
         repeated_hits: u32,
-        severity: IncidentSeverity,
-        action: ActionTaken,
-    ) -> Result<()> {
+        detection: Detection,
+    ) -> Result<ActionTaken> {
         // Respect global disable flag for perf/debug runs
         if Self::anticheat_disabled() {
             tracing::warn!(
                 "Anticheat incident creation skipped (ANTICHEAT_DISABLED=1): user={}",
                 user_id
             );
-            return Ok(());
+            return Ok(ActionTaken::None);
         }
 
-        let incident_type = if speed_hits > SPEED_THRESHOLD_BLOCKED {
-            IncidentType::SpeedViolation
-        } else if repeated_hits > REPEATED_THRESHOLD_BLOCKED {
-            IncidentType::RepeatedAnswers
-        } else {
-            IncidentType::SuspiciousPattern
-        };
-
         let incident = IncidentRecord {
             id: Uuid::new_v4().to_string(),
             user_id: user_id.to_string(),
-            incident_type,
-            severity,
+            session_id: Some(session_id.to_string()),
+            incident_type: detection.rule,
+            severity: detection.severity,
             details: IncidentDetails {
                 speed_hits: Some(speed_hits),
                 repeated_hits: Some(repeated_hits),
-                time_window_seconds: Some(TIME_WINDOW_SECONDS as u32),
+                time_window_seconds: Some(self.settings.time_window_seconds),
                 additional_info: None,
+                signal_counts: None,
             },
             timestamp: Utc::now(),
-            action_taken: action,
+            action_taken: detection.action,
             status: IncidentStatus::Open,
+            assigned_to: None,
+            updated_at: None,
             resolved_by: None,
             resolved_at: None,
             resolution_note: None,
+            auto_block: None,
+            unblocked_by: None,
+            unblocked_at: None,
         };
 
+        let action = incident.action_taken.clone();
+        self.record_incident(incident).await?;
+        Ok(action)
+    }
+
+    /// Заблокировать пользователя, если инцидент этого требует. Ученики групп из
+    /// `whitelisted_group_ids` и неудачная блокировка оставляют инцидент помеченным
+    async fn apply_auto_block(&self, incident: &mut IncidentRecord) {
+        if incident.action_taken != ActionTaken::Blocked {
+            return;
+        }
+        match self.is_whitelisted(&incident.user_id).await {
+            Ok(false) => {}
+            Ok(true) => {
+                tracing::info!(
+                    "Auto-block skipped for whitelisted user {} (incident {})",
+                    incident.user_id,
+                    incident.id
+                );
+                incident.action_taken = ActionTaken::Flagged;
+                return;
+            }
+            Err(err) => {
+                tracing::warn!(
+                    "Whitelist check failed for user {}, auto-block skipped: {:#}",
+                    incident.user_id,
+                    err
+                );
+                incident.action_taken = ActionTaken::Flagged;
+                return;
+            }
+        }
+
+        let request = BlockUserRequest {
+            reason: format!("Anticheat auto-block: incident {}", incident.id),
+            duration_hours: Some(self.settings.block_duration_hours),
+        };
+        let blocked_at = Utc::now();
+        match UserManagementService::new(self.mongo.clone(), self.redis.clone())
+            .block_user(&incident.user_id, request)
+            .await
+        {
+            Ok(_) => {
+                incident.auto_block = Some(IncidentAutoBlock {
+                    blocked_at,
+                    blocked_until: blocked_at
+                        + Duration::hours(i64::from(self.settings.block_duration_hours)),
+                });
+            }
+            Err(err) => {
+                tracing::warn!(
+                    "Auto-block failed for user {} (incident {}): {:#}",
+                    incident.user_id,
+                    incident.id,
+                    err
+                );
+                incident.action_taken = ActionTaken::Flagged;
+            }
+        }
+    }
+
+    /// Состоит ли пользователь в группе из `whitelisted_group_ids`
+    async fn is_whitelisted(&self, user_id: &str) -> Result<bool> {
+        if self.settings.whitelisted_group_ids.is_empty() {
+            return Ok(false);
+        }
+        let Ok(object_id) = ObjectId::parse_str(user_id) else {
+            return Ok(false);
+        };
+        let count = self
+            .mongo
+            .collection::<Document>("users")
+            .count_documents(doc! {
+                "_id": object_id,
+                "group_ids": { "$in": &self.settings.whitelisted_group_ids },
+            })
+            .await
+            .context("Failed to check anticheat whitelist")?;
+        Ok(count > 0)
+    }
+
+    /// Publish, save and notify about a new incident
+    async fn record_incident(&self, mut incident: IncidentRecord) -> Result<()> {
+        self.apply_auto_block(&mut incident).await;
+
         tracing::warn!(
             "Creating anticheat incident: user={}, type={:?}, severity={:?}, action={:?}",
-            user_id,
+            incident.user_id,
             incident.incident_type,
             incident.severity,
             incident.action_taken
@@ -258,11 +646,288 @@ impl AnticheatService {
             retry_async_with_config(cfg, || async { self.save_incident(&incident).await }).await?;
         }
 
-        self.dispatch_notifications(incident.clone());
+        self.dispatch_notifications(incident);
 
         Ok(())
     }
 
+    /// Принять пакет сигналов клиента по сессии.
+    ///
+    /// Сигналы сохраняются в anticheat_signals, счётчики сессии лежат в Redis.
+    /// Когда счётчик типа впервые превышает порог, открывается инцидент по сессии.
+    /// Пороги из `AnticheatSettings::signal_thresholds` заменяют пороги из конфига.
+    pub async fn ingest_signals(
+        &self,
+        session_id: &str,
+        user_id: &str,
+        signals: Vec<ClientSignal>,
+        settings: &AnticheatSignalSettings,
+    ) -> Result<SignalBatchResponse> {
+        let settings = &effective_signal_settings(settings, &self.settings);
+        self.check_signal_rate(session_id, settings.max_batches_per_minute)
+            .await?;
+
+        let accepted = signals.len();
+        let mut batch_counts: BTreeMap<SignalType, u32> = BTreeMap::new();
+        for signal in &signals {
+            *batch_counts.entry(signal.signal_type).or_default() += 1;
+        }
+
+        let received_at = bson::DateTime::now();
+        let stored = signals
+            .into_iter()
+            .map(|signal| {
+                Ok(StoredSignal {
+                    session_id: session_id.to_string(),
+                    user_id: user_id.to_string(),
+                    signal_type: signal.signal_type,
+                    timestamp: bson::DateTime::from_millis(signal.timestamp.timestamp_millis()),
+                    payload: bson::to_bson(&signal.payload)?,
+                    received_at,
+                    severity: None,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if !stored.is_empty() {
+            self.mongo
+                .collection::<StoredSignal>("anticheat_signals")
+                .insert_many(&stored)
+                .await
+                .context("Failed to save anticheat signals")?;
+        }
+
+        let (counts, crossed) = self
+            .increment_signal_counters(session_id, &batch_counts, settings)
+            .await?;
+        tracing::debug!(
+            "Anticheat signals: session={}, accepted={}, counts={:?}",
+            session_id,
+            accepted,
+            counts
+        );
+
+        let mut incident_id = None;
+        if !crossed.is_empty() && !Self::anticheat_disabled() {
+            // Сигналы присылает клиент: блокировка только при включённом auto_block
+            let action = self.thresholds().action_for(&IncidentSeverity::Medium);
+            let incident =
+                Self::signals_incident(session_id, user_id, &counts, &crossed, settings, action);
+            incident_id = Some(incident.id.clone());
+            self.record_incident(incident).await?;
+        }
+
+        Ok(SignalBatchResponse {
+            accepted,
+            counts,
+            incident_id,
+        })
+    }
+
+    /// Эвристика скорости ответов: при медиане ниже порога сложности задания пишет
+    /// в anticheat_signals сигнал `fast_answers` низкой серьёзности, один раз на сессию.
+    /// Инцидент не открывается. Возвращает, записан ли сигнал этим вызовом
+    pub async fn check_answer_latency(
+        &self,
+        session_id: &str,
+        user_id: &str,
+        difficulty: Option<&str>,
+        latencies: &[i64],
+    ) -> Result<bool> {
+        if Self::anticheat_disabled() {
+            return Ok(false);
+        }
+        let Some(fast) =
+            evaluate_answer_latency(&self.settings.answer_latency, difficulty, latencies)
+        else {
+            return Ok(false);
+        };
+
+        let mut conn = self.redis.clone();
+        let first: Option<String> = redis::cmd("SET")
+            .arg(fast_answers_key(session_id))
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(SIGNAL_COUNTERS_TTL_SECONDS)
+            .query_async(&mut conn)
+            .await
+            .context("Failed to mark fast answers signal")?;
+        if first.is_none() {
+            return Ok(false);
+        }
+
+        let now = bson::DateTime::now();
+        let signal = StoredSignal {
+            session_id: session_id.to_string(),
+            user_id: user_id.to_string(),
+            signal_type: SignalType::FastAnswers,
+            timestamp: now,
+            payload: bson::to_bson(&serde_json::json!({
+                "answers": fast.answers,
+                "median_ms": fast.median_ms,
+                "threshold_ms": fast.threshold_ms,
+                "difficulty": difficulty,
+            }))?,
+            received_at: now,
+            severity: Some(IncidentSeverity::Low),
+        };
+        self.mongo
+            .collection::<StoredSignal>("anticheat_signals")
+            .insert_one(&signal)
+            .await
+            .context("Failed to save fast answers signal")?;
+
+        tracing::info!(
+            "Fast answers: session={}, user={}, median={}ms, threshold={}ms",
+            session_id,
+            user_id,
+            fast.median_ms,
+            fast.threshold_ms
+        );
+        Ok(true)
+    }
+
+    /// Сводка по времени последних `limit` ответов с `timing`, подходящих под фильтр
+    pub async fn answer_timing(
+        &self,
+        filter: Document,
+        limit: i64,
+    ) -> Result<Option<AnswerTimingSummary>> {
+        let mut filter = filter;
+        filter.insert("timing", doc! { "$exists": true });
+        let latencies: Vec<i64> = self
+            .mongo
+            .collection::<AttemptRecord>("attempt_records")
+            .find(filter)
+            .sort(doc! { "timestamp": -1 })
+            .limit(limit)
+            .await
+            .context("Failed to query answer timing")?
+            .try_collect::<Vec<_>>()
+            .await
+            .context("Failed to read answer timing")?
+            .into_iter()
+            .filter_map(|attempt| attempt.timing)
+            .map(|timing| timing.since_previous_ms)
+            .collect();
+        Ok(AnswerTimingSummary::from_latencies(&latencies))
+    }
+
+    /// Fixed window per session; rejected batches are counted too
+    async fn check_signal_rate(&self, session_id: &str, limit: u32) -> Result<()> {
+        let mut conn = self.redis.clone();
+
+        let lua_script = r#"
+            local count = redis.call('INCR', KEYS[1])
+            if count == 1 then
+                redis.call('EXPIRE', KEYS[1], tonumber(ARGV[1]))
+            end
+            return {count, redis.call('TTL', KEYS[1])}
+        "#;
+
+        let (count, ttl): (u32, i64) = redis::Script::new(lua_script)
+            .key(signal_rate_key(session_id))
+            .arg(SIGNAL_RATE_WINDOW_SECONDS)
+            .invoke_async(&mut conn)
+            .await
+            .context("Failed to check anticheat signal rate")?;
+
+        if count > limit {
+            return Err(SignalRateLimited {
+                session_id: session_id.to_string(),
+                retry_after_secs: ttl.max(1) as u64,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Итоговые счётчики сессии и типы, впервые превысившие порог
+    async fn increment_signal_counters(
+        &self,
+        session_id: &str,
+        batch_counts: &BTreeMap<SignalType, u32>,
+        settings: &AnticheatSignalSettings,
+    ) -> Result<(BTreeMap<SignalType, u32>, Vec<SignalType>)> {
+        let mut conn = self.redis.clone();
+        let script = redis::Script::new(SIGNAL_COUNTERS_SCRIPT);
+        let mut invocation = script.key(signal_counters_key(session_id));
+        invocation.arg(SIGNAL_COUNTERS_TTL_SECONDS);
+        for signal_type in SignalType::ALL {
+            invocation
+                .arg(signal_type.as_str())
+                .arg(batch_counts.get(&signal_type).copied().unwrap_or(0))
+                .arg(signal_threshold(settings, signal_type));
+        }
+        let result: Vec<u32> = invocation
+            .invoke_async(&mut conn)
+            .await
+            .context("Failed to increment anticheat signal counters")?;
+
+        let mut counts = BTreeMap::new();
+        let mut crossed = Vec::new();
+        for (signal_type, pair) in SignalType::ALL.into_iter().zip(result.chunks(2)) {
+            counts.insert(signal_type, pair[0]);
+            if pair.get(1) == Some(&1) {
+                crossed.push(signal_type);
+            }
+        }
+        Ok((counts, crossed))
+    }
+
+    fn signals_incident(
+        session_id: &str,
+        user_id: &str,
+        counts: &BTreeMap<SignalType, u32>,
+        crossed: &[SignalType],
+        settings: &AnticheatSignalSettings,
+        action: ActionTaken,
+    ) -> IncidentRecord {
+        let summary = crossed
+            .iter()
+            .map(|signal_type| {
+                format!(
+                    "{} ({} > {})",
+                    signal_type.as_str(),
+                    counts.get(signal_type).copied().unwrap_or(0),
+                    signal_threshold(settings, *signal_type)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        IncidentRecord {
+            id: Uuid::new_v4().to_string(),
+            user_id: user_id.to_string(),
+            session_id: Some(session_id.to_string()),
+            incident_type: IncidentType::ClientSignals,
+            severity: IncidentSeverity::Medium,
+            details: IncidentDetails {
+                speed_hits: None,
+                repeated_hits: None,
+                time_window_seconds: None,
+                additional_info: Some(format!("Client signal thresholds exceeded: {}", summary)),
+                signal_counts: Some(
+                    counts
+                        .iter()
+                        .map(|(signal_type, count)| (signal_type.as_str().to_string(), *count))
+                        .collect(),
+                ),
+            },
+            timestamp: Utc::now(),
+            action_taken: action,
+            status: IncidentStatus::Open,
+            assigned_to: None,
+            updated_at: None,
+            resolved_by: None,
+            resolved_at: None,
+            resolution_note: None,
+            auto_block: None,
+            unblocked_by: None,
+            unblocked_at: None,
+        }
+    }
+
     async fn save_incident(&self, incident: &IncidentRecord) -> Result<()> {
         let collection: mongodb::Collection<IncidentRecord> = self.mongo.collection("incidents");
 
@@ -316,8 +981,11 @@ impl AnticheatService {
         // For now, estimate based on speed hits
         let repeated_hits = 0; // Simplified
 
-        let is_blocked = speed_hits > SPEED_THRESHOLD_BLOCKED;
-        let is_suspicious = !is_blocked && speed_hits > SPEED_THRESHOLD_SUSPICIOUS;
+        let detection = evaluate(&self.thresholds(), speed_hits, repeated_hits);
+        let is_blocked = detection
+            .as_ref()
+            .is_some_and(|d| d.action == ActionTaken::Blocked);
+        let is_suspicious = detection.is_some() && !is_blocked;
 
         Ok(AnticheatStatus {
             user_id: user_id.to_string(),
@@ -354,14 +1022,124 @@ impl AnticheatService {
         Ok(())
     }
 
-    /// Simple hash for answer deduplication
-    fn hash_answer(&self, answer: &str) -> String {
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
+    /// Хронология сессии для разбора инцидента: ответы, подсказки и сигналы клиента.
+    ///
+    /// Коллекции читаются курсорами по времени и сливаются по мере чтения; событий
+    /// берётся не больше `MAX_TIMELINE_EVENTS`. Паузы длиннее `inactivity` отмечаются
+    /// событием `inactivity`.
+    pub async fn session_timeline(
+        &self,
+        session_id: &str,
+        inactivity: Duration,
+    ) -> Result<SessionTimeline> {
+        let bounds = self.session_bounds(session_id).await?;
+
+        let attempts = self
+            .mongo
+            .collection::<AttemptRecord>("attempt_records")
+            .find(doc! { "session_id": session_id })
+            .sort(doc! { "timestamp": 1 })
+            .await
+            .context("Failed to load session attempts")?
+            .scan(0, |total, attempt| {
+                let event = attempt.map(|attempt| {
+                    *total += attempt.score;
+                    (
+                        attempt.timestamp,
+                        TimelineEventKind::Answer {
+                            task_id: attempt.task_id,
+                            correct: attempt.correct,
+                            score: attempt.score,
+                            total_score: *total,
+                        },
+                    )
+                });
+                futures::future::ready(Some(event.map_err(anyhow::Error::from)))
+            });
+        let hints = self
+            .mongo
+            .collection::<HintRecord>("hint_records")
+            .find(doc! { "session_id": session_id })
+            .sort(doc! { "timestamp": 1 })
+            .await
+            .context("Failed to load session hints")?
+            .map_ok(|hint| {
+                (
+                    hint.timestamp,
+                    TimelineEventKind::Hint {
+                        task_id: hint.task_id,
+                        cost: hint.cost,
+                    },
+                )
+            })
+            .map_err(anyhow::Error::from);
+        let signals = self
+            .mongo
+            .collection::<StoredSignal>("anticheat_signals")
+            .find(doc! { "session_id": session_id })
+            .sort(doc! { "timestamp": 1 })
+            .await
+            .context("Failed to load session signals")?
+            .map_ok(|signal| {
+                (
+                    DateTime::from_timestamp_millis(signal.timestamp.timestamp_millis())
+                        .unwrap_or_default(),
+                    TimelineEventKind::Signal {
+                        signal_type: signal.signal_type,
+                        payload: signal.payload.into_relaxed_extjson(),
+                    },
+                )
+            })
+            .map_err(anyhow::Error::from);
+
+        let (events, truncated) = merge_by_time(
+            vec![attempts.boxed(), hints.boxed(), signals.boxed()],
+            MAX_TIMELINE_EVENTS,
+        )
+        .await?;
+
+        let started_at = bounds
+            .as_ref()
+            .map(|bounds| bounds.started_at)
+            .or_else(|| events.first().map(|(at, _)| *at));
+        let ended = bounds.and_then(|bounds| bounds.ended);
+        Ok(SessionTimeline {
+            started_at,
+            truncated,
+            events: build_timeline(started_at, ended, events, inactivity),
+        })
+    }
 
-        let mut hasher = DefaultHasher::new();
-        answer.trim().to_lowercase().hash(&mut hasher);
-        format!("{:x}", hasher.finish())
+    /// Начало и завершение сессии: активная лежит в Redis, завершённая - в "sessions"
+    async fn session_bounds(&self, session_id: &str) -> Result<Option<SessionBounds>> {
+        let mut conn = self.redis.clone();
+        let cached: Option<String> = redis::cmd("GET")
+            .arg(format!("session:{}", session_id))
+            .query_async(&mut conn)
+            .await
+            .context("Failed to load session from Redis")?;
+        if let Some(session) = cached.and_then(|raw| serde_json::from_str::<Session>(&raw).ok()) {
+            return Ok(Some(SessionBounds {
+                started_at: session.started_at,
+                ended: None,
+            }));
+        }
+
+        let record = self
+            .mongo
+            .collection::<SessionRecord>("sessions")
+            .find_one(doc! { "_id": session_id })
+            .await
+            .context("Failed to load archived session")?;
+        Ok(record.map(|record| SessionBounds {
+            started_at: record.started_at,
+            ended: (record.status != SessionStatus::Active).then(|| {
+                (
+                    record.completed_at.unwrap_or(record.expires_at),
+                    record.status,
+                )
+            }),
+        }))
     }
 
     fn dispatch_notifications(&self, incident: IncidentRecord) {
@@ -444,6 +1222,236 @@ impl AnticheatService {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::system_settings::AnticheatSignalThresholds;
+
+    #[test]
+    fn evaluate_matches_live_thresholds() {
+        let thresholds = DetectionThresholds::default();
+
+        assert_eq!(evaluate(&thresholds, 5, 8), None);
+
+        let suspicious = evaluate(&thresholds, 6, 0).unwrap();
+        assert_eq!(suspicious.rule, IncidentType::SuspiciousPattern);
+        assert_eq!(suspicious.action, ActionTaken::Flagged);
+
+        let speed = evaluate(&thresholds, 11, 0).unwrap();
+        assert_eq!(speed.rule, IncidentType::SpeedViolation);
+        assert_eq!(speed.action, ActionTaken::Blocked);
+
+        let repeated = evaluate(&thresholds, 1, 9).unwrap();
+        assert_eq!(repeated.rule, IncidentType::RepeatedAnswers);
+        assert_eq!(repeated.severity, IncidentSeverity::Critical);
+    }
+
+    #[test]
+    fn median_latency_handles_odd_and_even_sequences() {
+        assert_eq!(median_ms(&[]), None);
+        assert_eq!(median_ms(&[900]), Some(900));
+        assert_eq!(median_ms(&[4000, 300, 1200]), Some(1200));
+        // Среднее двух центральных, вниз
+        assert_eq!(median_ms(&[1000, 200, 1501, 9000]), Some(1250));
+
+        let summary = AnswerTimingSummary::from_latencies(&[2500, 700, 1800]).unwrap();
+        assert_eq!(summary.answers, 3);
+        assert_eq!(summary.median_latency_ms, 1800);
+        assert_eq!(summary.fastest_latency_ms, 700);
+        assert!(AnswerTimingSummary::from_latencies(&[]).is_none());
+    }
+
+    #[test]
+    fn answer_latency_threshold_depends_on_difficulty() {
+        let thresholds = AnswerLatencyThresholds::default();
+        // Ответы по 1.5 с: слишком быстро для b2 (3 с), нормально для a1 (1 с)
+        let steady = [1500, 1400, 1600, 1500];
+
+        assert_eq!(
+            evaluate_answer_latency(&thresholds, Some("b2"), &steady),
+            Some(FastAnswers {
+                answers: 4,
+                median_ms: 1500,
+                threshold_ms: 3000,
+            })
+        );
+        assert_eq!(
+            evaluate_answer_latency(&thresholds, Some("a1"), &steady),
+            None
+        );
+        assert_eq!(evaluate_answer_latency(&thresholds, None, &steady), None);
+
+        // Один долгий ответ медиану не сдвигает, а один быстрый не делает её быстрой
+        assert!(evaluate_answer_latency(&thresholds, Some("b1"), &[400, 600, 60_000]).is_some());
+        assert!(evaluate_answer_latency(&thresholds, Some("b1"), &[400, 5000, 6000]).is_none());
+    }
+
+    #[test]
+    fn answer_latency_needs_enough_answers_and_enabled_threshold() {
+        let thresholds = AnswerLatencyThresholds {
+            b2_ms: 0,
+            ..AnswerLatencyThresholds::default()
+        };
+        assert!(evaluate_answer_latency(&thresholds, Some("b1"), &[100, 100]).is_none());
+        assert!(evaluate_answer_latency(&thresholds, Some("b1"), &[100, 100, 100]).is_some());
+        assert!(evaluate_answer_latency(&thresholds, Some("b2"), &[100, 100, 100]).is_none());
+        // Медиана, равная порогу, не срабатывает
+        assert!(evaluate_answer_latency(&thresholds, Some("b1"), &[2000, 2000, 2000]).is_none());
+    }
+
+    #[test]
+    fn evaluate_respects_auto_block_severity() {
+        let flag_only = DetectionThresholds {
+            auto_block_severity: None,
+            ..DetectionThresholds::default()
+        };
+        assert_eq!(
+            evaluate(&flag_only, 20, 0).unwrap().action,
+            ActionTaken::Flagged
+        );
+
+        let block_medium = DetectionThresholds {
+            auto_block_severity: Some(IncidentSeverity::Medium),
+            ..DetectionThresholds::default()
+        };
+        assert_eq!(
+            evaluate(&block_medium, 6, 0).unwrap().action,
+            ActionTaken::Blocked
+        );
+    }
+
+    #[test]
+    fn signals_incident_lists_crossed_thresholds() {
+        let settings = AnticheatSignalSettings::default();
+        let counts = BTreeMap::from([
+            (SignalType::TabSwitch, 2),
+            (SignalType::Paste, 6),
+            (SignalType::Devtools, 0),
+        ]);
+
+        let incident = AnticheatService::signals_incident(
+            "session-1",
+            "user-1",
+            &counts,
+            &[SignalType::Paste],
+            &settings,
+            ActionTaken::Flagged,
+        );
+        assert_eq!(incident.incident_type, IncidentType::ClientSignals);
+        assert_eq!(incident.session_id.as_deref(), Some("session-1"));
+        assert_eq!(incident.action_taken, ActionTaken::Flagged);
+        assert_eq!(
+            incident.details.additional_info.as_deref(),
+            Some("Client signal thresholds exceeded: paste (6 > 5)")
+        );
+        assert_eq!(incident.details.signal_counts.unwrap()["paste"], 6);
+    }
+
+    #[test]
+    fn admin_signal_thresholds_override_config() {
+        let base = AnticheatSignalSettings::default();
+        assert_eq!(
+            effective_signal_settings(&base, &AnticheatSettings::default()).paste_threshold,
+            base.paste_threshold
+        );
+
+        let settings = AnticheatSettings {
+            signal_thresholds: Some(AnticheatSignalThresholds {
+                tab_switch: 2,
+                paste: 1,
+                devtools: 3,
+            }),
+            ..AnticheatSettings::default()
+        };
+        let effective = effective_signal_settings(&base, &settings);
+        assert_eq!(signal_threshold(&effective, SignalType::Paste), 1);
+        assert_eq!(signal_threshold(&effective, SignalType::TabSwitch), 2);
+        assert_eq!(
+            effective.max_batches_per_minute,
+            base.max_batches_per_minute
+        );
+    }
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000 + seconds, 0).unwrap()
+    }
+
+    fn hint(seconds: i64) -> TimedEvent {
+        (
+            at(seconds),
+            TimelineEventKind::Hint {
+                task_id: "task".to_string(),
+                cost: 5,
+            },
+        )
+    }
+
+    fn signal(seconds: i64) -> TimedEvent {
+        (
+            at(seconds),
+            TimelineEventKind::Signal {
+                signal_type: SignalType::Paste,
+                payload: serde_json::Value::Null,
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn merge_by_time_interleaves_sorted_sources() {
+        let hints = futures::stream::iter(vec![Ok(hint(10)), Ok(hint(30))]).boxed();
+        let signals = futures::stream::iter(vec![Ok(signal(5)), Ok(signal(20))]).boxed();
+
+        let (merged, truncated) = merge_by_time(vec![hints, signals], 10).await.unwrap();
+        let seconds: Vec<_> = merged
+            .iter()
+            .map(|(timestamp, _)| (*timestamp - at(0)).num_seconds())
+            .collect();
+        assert_eq!(seconds, vec![5, 10, 20, 30]);
+        assert!(!truncated);
+
+        let hints = futures::stream::iter(vec![Ok(hint(10)), Ok(hint(30))]).boxed();
+        let (merged, truncated) = merge_by_time(vec![hints], 1).await.unwrap();
+        assert_eq!(merged.len(), 1);
+        assert!(truncated);
+    }
+
+    #[test]
+    fn build_timeline_adds_offsets_and_inactivity() {
+        let timeline = build_timeline(
+            Some(at(0)),
+            Some((at(400), SessionStatus::Completed)),
+            vec![signal(30), hint(300)],
+            Duration::seconds(120),
+        );
+        let kinds: Vec<_> = timeline
+            .iter()
+            .map(|event| (event.offset_ms, event.kind.clone()))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (0, TimelineEventKind::SessionStarted),
+                (30_000, signal(0).1),
+                (
+                    30_000,
+                    TimelineEventKind::Inactivity {
+                        duration_seconds: 270
+                    }
+                ),
+                (300_000, hint(0).1),
+                (
+                    400_000,
+                    TimelineEventKind::SessionEnded {
+                        status: SessionStatus::Completed
+                    }
+                ),
+            ]
+        );
+        assert!(build_timeline(None, None, Vec::new(), Duration::seconds(60)).is_empty());
+    }
+
+    #[test]
+    fn answer_fingerprint_ignores_case_and_padding() {
+        assert_eq!(answer_fingerprint(" Ответ "), answer_fingerprint("ответ"));
+        assert_ne!(answer_fingerprint("a"), answer_fingerprint("b"));
+    }
 
     #[test]
     #[serial_test::serial]
@@ -0,0 +1,186 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use redis::aio::ConnectionManager;
+use tokio::time::sleep;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{
+    config::ArchiveSettings,
+    metrics::ARCHIVE_WORKER_TICKS_TOTAL,
+    models::session_archive::ArchiveRunSummary,
+    services::session_archive_service::{ArchiveStorage, SessionArchiveService},
+};
+
+const LOCK_KEY: &str = "lock:session_archive";
+
+/// Продлить блокировку, только если она всё ещё наша
+pub(crate) const HEARTBEAT_SCRIPT: &str = r#"
+    if redis.call('GET', KEYS[1]) == ARGV[1] then
+        return redis.call('PEXPIRE', KEYS[1], ARGV[2])
+    end
+    return 0
+"#;
+
+pub(crate) const RELEASE_SCRIPT: &str = r#"
+    if redis.call('GET', KEYS[1]) == ARGV[1] then
+        return redis.call('DEL', KEYS[1])
+    end
+    return 0
+"#;
+
+/// Периодически переносит старые сессии и их ответы в объектное хранилище.
+///
+/// Одновременно работает только один экземпляр (блокировка в Redis); после каждого
+/// батча блокировка продлевается, и если она потеряна, запуск прерывается.
+pub struct ArchiveWorker {
+    service: SessionArchiveService,
+    storage: Arc<dyn ArchiveStorage>,
+    redis: ConnectionManager,
+    settings: ArchiveSettings,
+}
+
+impl ArchiveWorker {
+    pub fn new(
+        service: SessionArchiveService,
+        storage: Arc<dyn ArchiveStorage>,
+        redis: ConnectionManager,
+        settings: ArchiveSettings,
+    ) -> Self {
+        Self {
+            service,
+            storage,
+            redis,
+            settings,
+        }
+    }
+
+    pub async fn run(&self) -> Result<()> {
+        let interval = Duration::from_secs(self.settings.worker_interval_secs);
+        info!("Starting archive worker (interval={}s)", interval.as_secs());
+
+        loop {
+            match self.run_once().await {
+                Ok(Some(summary)) => {
+                    ARCHIVE_WORKER_TICKS_TOTAL
+                        .with_label_values(&["success"])
+                        .inc();
+                    info!(
+                        sessions = summary.sessions_archived,
+                        attempts = summary.attempts_archived,
+                        bundles = summary.bundles_written,
+                        "Archive worker tick completed"
+                    );
+                }
+                Ok(None) => {
+                    ARCHIVE_WORKER_TICKS_TOTAL
+                        .with_label_values(&["skipped"])
+                        .inc();
+                    info!("Archive worker tick skipped: another instance holds the lock");
+                }
+                Err(err) => {
+                    ARCHIVE_WORKER_TICKS_TOTAL
+                        .with_label_values(&["error"])
+                        .inc();
+                    warn!(error = %err, "archive worker tick failed");
+                }
+            }
+
+            sleep(interval).await;
+        }
+    }
+
+    /// Один запуск архивации. `None`, если блокировку держит другой экземпляр.
+    pub async fn run_once(&self) -> Result<Option<ArchiveRunSummary>> {
+        let token = Uuid::new_v4().to_string();
+        if !self.acquire_lock(&token).await? {
+            return Ok(None);
+        }
+
+        let result = self.archive_batches(&token).await;
+
+        if let Err(err) = self.release_lock(&token).await {
+            warn!(error = %err, "failed to release archive lock");
+        }
+
+        result.map(Some)
+    }
+
+    async fn archive_batches(&self, token: &str) -> Result<ArchiveRunSummary> {
+        let purged = self.service.purge_expired_rehydrated().await?;
+        if purged > 0 {
+            info!(purged, "Removed expired rehydrated sessions");
+        }
+
+        let cutoff = Utc::now() - self.settings.session_age();
+        let mut summary = ArchiveRunSummary::default();
+
+        while summary.batches < self.settings.max_batches_per_run {
+            let batch = self
+                .service
+                .archive_batch(self.storage.as_ref(), cutoff, self.settings.batch_size)
+                .await?;
+            if batch.sessions_archived == 0 {
+                break;
+            }
+
+            summary.batches += batch.batches;
+            summary.sessions_archived += batch.sessions_archived;
+            summary.attempts_archived += batch.attempts_archived;
+            summary.bundles_written += batch.bundles_written;
+
+            if !self.heartbeat(token).await? {
+                bail!(
+                    "Archive lock lost after {} batches, stopping",
+                    summary.batches
+                );
+            }
+        }
+
+        Ok(summary)
+    }
+
+    async fn acquire_lock(&self, token: &str) -> Result<bool> {
+        let mut conn = self.redis.clone();
+        let acquired: Option<String> = redis::cmd("SET")
+            .arg(LOCK_KEY)
+            .arg(token)
+            .arg("NX")
+            .arg("PX")
+            .arg(self.lock_ttl_ms())
+            .query_async(&mut conn)
+            .await
+            .context("Failed to acquire archive lock")?;
+        Ok(acquired.is_some())
+    }
+
+    async fn heartbeat(&self, token: &str) -> Result<bool> {
+        let mut conn = self.redis.clone();
+        let extended: i32 = redis::Script::new(HEARTBEAT_SCRIPT)
+            .key(LOCK_KEY)
+            .arg(token)
+            .arg(self.lock_ttl_ms())
+            .invoke_async(&mut conn)
+            .await
+            .context("Failed to extend archive lock")?;
+        Ok(extended == 1)
+    }
+
+    async fn release_lock(&self, token: &str) -> Result<()> {
+        let mut conn = self.redis.clone();
+        let _: i32 = redis::Script::new(RELEASE_SCRIPT)
+            .key(LOCK_KEY)
+            .arg(token)
+            .invoke_async(&mut conn)
+            .await
+            .context("Failed to release archive lock")?;
+        Ok(())
+    }
+
+    fn lock_ttl_ms(&self) -> u64 {
+        self.settings.lock_ttl_secs * 1000
+    }
+}
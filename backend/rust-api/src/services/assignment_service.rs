@@ -0,0 +1,594 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use mongodb::bson::{doc, oid::ObjectId, to_document, DateTime as BsonDateTime, Document};
+use mongodb::Database;
+use thiserror::Error;
+
+use crate::models::assignment::{
+    Assignment, AssignmentCompletion, AssignmentItemStatus, AssignmentProgressResponse,
+    AssignmentStatus, CreateAssignmentRequest, ExtendAssignmentRequest, StudentAssignment,
+    StudentAssignmentProgress,
+};
+use crate::models::group::Group;
+
+pub const ASSIGNMENTS_COLLECTION: &str = "assignments";
+pub const ASSIGNMENT_COMPLETIONS_COLLECTION: &str = "assignment_completions";
+
+/// Redis-ключ связи активной сессии с заданием: `{assignment_id}:{template_id}`
+pub fn session_assignment_key(session_id: &str) -> String {
+    format!("session_assignment:{}", session_id)
+}
+
+pub fn session_assignment_value(assignment_id: &ObjectId, template_id: &ObjectId) -> String {
+    format!("{}:{}", assignment_id.to_hex(), template_id.to_hex())
+}
+
+pub fn parse_session_assignment(value: &str) -> Option<(ObjectId, ObjectId)> {
+    let (assignment_id, template_id) = value.split_once(':')?;
+    Some((
+        ObjectId::parse_str(assignment_id).ok()?,
+        ObjectId::parse_str(template_id).ok()?,
+    ))
+}
+
+/// Почему с заданием нельзя выполнить действие
+#[derive(Debug, Error)]
+pub enum AssignmentError {
+    #[error("Assignment not found")]
+    NotFound,
+    #[error("Group not found")]
+    GroupNotFound,
+    #[error("Group is archived")]
+    GroupArchived,
+    #[error("User is not a member of the assignment group")]
+    NotGroupMember,
+    #[error("Assignment deadline has passed")]
+    PastDue { due_at: DateTime<Utc> },
+    #[error("All assignment templates are already completed")]
+    AlreadyCompleted,
+    #[error("Due date must be in the future")]
+    DueDateInPast,
+    #[error("New due date must be later than the current one")]
+    DeadlineNotExtended { due_at: DateTime<Utc> },
+    #[error("Unknown templates: {}", .0.join(", "))]
+    UnknownTemplates(Vec<String>),
+    #[error("Templates are not published: {}", .0.join(", "))]
+    UnpublishedTemplates(Vec<String>),
+}
+
+/// Статус каждого шаблона и задания в целом по выполненным пунктам ученика
+pub fn assignment_progress(
+    assignment: &Assignment,
+    completions: &HashMap<ObjectId, &AssignmentCompletion>,
+    now: DateTime<Utc>,
+) -> (AssignmentStatus, Vec<AssignmentItemStatus>) {
+    let items: Vec<AssignmentItemStatus> = assignment
+        .template_ids
+        .iter()
+        .map(|template_id| {
+            let completion = completions.get(template_id);
+            AssignmentItemStatus {
+                template_id: template_id.to_hex(),
+                completed: completion.is_some(),
+                completed_at: completion.map(|c| c.completed_at),
+                late: completion.is_some_and(|c| c.late),
+            }
+        })
+        .collect();
+
+    let status = if items.iter().all(|item| item.completed) {
+        AssignmentStatus::Completed
+    } else if now > assignment.due_at {
+        AssignmentStatus::Overdue
+    } else {
+        AssignmentStatus::Pending
+    };
+    (status, items)
+}
+
+/// Первый шаблон задания, по которому ещё нет засчитанной сессии
+pub fn next_template(assignment: &Assignment, completed: &HashSet<ObjectId>) -> Option<ObjectId> {
+    assignment
+        .template_ids
+        .iter()
+        .find(|template_id| !completed.contains(template_id))
+        .copied()
+}
+
+pub struct AssignmentService {
+    mongo: Database,
+}
+
+impl AssignmentService {
+    pub fn new(mongo: Database) -> Self {
+        Self { mongo }
+    }
+
+    fn collection(&self) -> mongodb::Collection<Assignment> {
+        self.mongo.collection::<Assignment>(ASSIGNMENTS_COLLECTION)
+    }
+
+    fn completions(&self) -> mongodb::Collection<AssignmentCompletion> {
+        self.mongo
+            .collection::<AssignmentCompletion>(ASSIGNMENT_COMPLETIONS_COLLECTION)
+    }
+
+    /// Создать задание для группы
+    pub async fn create(
+        &self,
+        group_id: &ObjectId,
+        req: CreateAssignmentRequest,
+        created_by: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Assignment> {
+        let group = self
+            .mongo
+            .collection::<Group>("groups")
+            .find_one(doc! { "_id": group_id })
+            .await
+            .context("Failed to query group")?
+            .ok_or(AssignmentError::GroupNotFound)?;
+        if group.archived_at.is_some() {
+            return Err(AssignmentError::GroupArchived.into());
+        }
+        if req.due_at <= now {
+            return Err(AssignmentError::DueDateInPast.into());
+        }
+
+        let template_ids = self.resolve_templates(&req.template_ids).await?;
+        let assignment = Assignment {
+            id: ObjectId::new(),
+            group_id: *group_id,
+            template_ids,
+            due_at: req.due_at,
+            instructions: req.instructions.trim().to_string(),
+            allow_late: req.allow_late,
+            min_score: req
+                .min_score
+                .unwrap_or(CreateAssignmentRequest::DEFAULT_MIN_SCORE),
+            created_by: created_by.to_string(),
+            created_at: now,
+            updated_at: now,
+        };
+        self.collection()
+            .insert_one(&assignment)
+            .await
+            .context("Failed to insert assignment")?;
+
+        tracing::info!(
+            "Assignment {} created for group {} by {}",
+            assignment.id,
+            group_id,
+            created_by
+        );
+        Ok(assignment)
+    }
+
+    /// Шаблоны задания без повторов, в порядке запроса. Все должны существовать
+    /// и быть опубликованы: черновик или архивный шаблон ученику не выдаётся
+    async fn resolve_templates(&self, raw_ids: &[String]) -> Result<Vec<ObjectId>> {
+        let mut seen = HashSet::new();
+        let mut ids = Vec::new();
+        let mut invalid = Vec::new();
+        for raw in raw_ids {
+            match ObjectId::parse_str(raw) {
+                Ok(id) if seen.insert(id) => ids.push(id),
+                Ok(_) => {}
+                Err(_) => invalid.push(raw.clone()),
+            }
+        }
+        if !invalid.is_empty() {
+            return Err(AssignmentError::UnknownTemplates(invalid).into());
+        }
+
+        let templates = self
+            .mongo
+            .collection::<Document>("templates")
+            .find(doc! { "_id": { "$in": &ids } })
+            .projection(doc! { "_id": 1, "status": 1, "archived": 1 })
+            .await
+            .context("Failed to query assignment templates")?
+            .try_collect::<Vec<Document>>()
+            .await
+            .context("Failed to collect assignment templates")?;
+        let found: HashSet<ObjectId> = templates
+            .iter()
+            .filter_map(|template| template.get_object_id("_id").ok())
+            .collect();
+        let published: HashSet<ObjectId> = templates
+            .iter()
+            .filter(|template| {
+                template.get_str("status").ok() == Some("published")
+                    && !template.get_bool("archived").unwrap_or(false)
+            })
+            .filter_map(|template| template.get_object_id("_id").ok())
+            .collect();
+        let missing: Vec<String> = ids
+            .iter()
+            .filter(|id| !found.contains(id))
+            .map(|id| id.to_hex())
+            .collect();
+        if !missing.is_empty() {
+            return Err(AssignmentError::UnknownTemplates(missing).into());
+        }
+        let unpublished: Vec<String> = ids
+            .iter()
+            .filter(|id| !published.contains(id))
+            .map(|id| id.to_hex())
+            .collect();
+        if !unpublished.is_empty() {
+            return Err(AssignmentError::UnpublishedTemplates(unpublished).into());
+        }
+        Ok(ids)
+    }
+
+    /// Задания группы по возрастанию срока сдачи
+    pub async fn list_for_group(&self, group_id: &ObjectId) -> Result<Vec<Assignment>> {
+        self.collection()
+            .find(doc! { "groupId": group_id })
+            .sort(doc! { "dueAt": 1, "_id": 1 })
+            .await
+            .context("Failed to query group assignments")?
+            .try_collect()
+            .await
+            .context("Failed to collect group assignments")
+    }
+
+    async fn get_in_group(
+        &self,
+        group_id: &ObjectId,
+        assignment_id: &ObjectId,
+    ) -> Result<Assignment> {
+        self.collection()
+            .find_one(doc! { "_id": assignment_id, "groupId": group_id })
+            .await
+            .context("Failed to query assignment")?
+            .ok_or_else(|| AssignmentError::NotFound.into())
+    }
+
+    /// Продлить срок сдачи. Сократить срок нельзя: ученики могли планировать по нему
+    pub async fn extend(
+        &self,
+        group_id: &ObjectId,
+        assignment_id: &ObjectId,
+        req: ExtendAssignmentRequest,
+        now: DateTime<Utc>,
+    ) -> Result<Assignment> {
+        let mut assignment = self.get_in_group(group_id, assignment_id).await?;
+        if req.due_at <= assignment.due_at {
+            return Err(AssignmentError::DeadlineNotExtended {
+                due_at: assignment.due_at,
+            }
+            .into());
+        }
+
+        assignment.due_at = req.due_at;
+        if let Some(allow_late) = req.allow_late {
+            assignment.allow_late = allow_late;
+        }
+        assignment.updated_at = now;
+        self.collection()
+            .update_one(
+                doc! { "_id": assignment_id },
+                doc! { "$set": {
+                    "dueAt": BsonDateTime::from_millis(assignment.due_at.timestamp_millis()),
+                    "allowLate": assignment.allow_late,
+                    "updatedAt": BsonDateTime::from_millis(now.timestamp_millis()),
+                } },
+            )
+            .await
+            .context("Failed to extend assignment")?;
+        Ok(assignment)
+    }
+
+    /// Проверить, что ученик может начать сессию по заданию, и выбрать шаблон
+    pub async fn start(
+        &self,
+        assignment_id: &str,
+        user_id: &str,
+        now: DateTime<Utc>,
+    ) -> Result<(Assignment, ObjectId)> {
+        let assignment_id =
+            ObjectId::parse_str(assignment_id).map_err(|_| AssignmentError::NotFound)?;
+        let assignment = self
+            .collection()
+            .find_one(doc! { "_id": assignment_id })
+            .await
+            .context("Failed to query assignment")?
+            .ok_or(AssignmentError::NotFound)?;
+
+        let groups = self.user_group_ids(user_id).await?;
+        if !groups.contains(&assignment.group_id) {
+            return Err(AssignmentError::NotGroupMember.into());
+        }
+        if now > assignment.due_at && !assignment.allow_late {
+            return Err(AssignmentError::PastDue {
+                due_at: assignment.due_at,
+            }
+            .into());
+        }
+
+        let completed: HashSet<ObjectId> = self
+            .completions()
+            .find(doc! { "assignmentId": assignment_id, "userId": user_id })
+            .await
+            .context("Failed to query assignment completions")?
+            .try_collect::<Vec<_>>()
+            .await
+            .context("Failed to collect assignment completions")?
+            .into_iter()
+            .map(|completion| completion.template_id)
+            .collect();
+        let template_id =
+            next_template(&assignment, &completed).ok_or(AssignmentError::AlreadyCompleted)?;
+        Ok((assignment, template_id))
+    }
+
+    /// Засчитать шаблон задания по завершённой сессии. Повторные сессии
+    /// по уже засчитанному шаблону первую запись не перезаписывают
+    pub async fn record_completion(
+        &self,
+        assignment_id: &ObjectId,
+        template_id: &ObjectId,
+        user_id: &str,
+        session_id: &str,
+        score: i32,
+        now: DateTime<Utc>,
+    ) -> Result<bool> {
+        let Some(assignment) = self
+            .collection()
+            .find_one(doc! { "_id": assignment_id })
+            .await
+            .context("Failed to query assignment")?
+        else {
+            return Ok(false);
+        };
+        if score < assignment.min_score {
+            return Ok(false);
+        }
+
+        let completion = AssignmentCompletion {
+            id: AssignmentCompletion::key(assignment_id, user_id, template_id),
+            assignment_id: *assignment_id,
+            user_id: user_id.to_string(),
+            template_id: *template_id,
+            session_id: session_id.to_string(),
+            score,
+            late: now > assignment.due_at,
+            completed_at: now,
+        };
+        let mut fields =
+            to_document(&completion).context("Failed to serialize assignment completion")?;
+        fields.remove("_id");
+        self.completions()
+            .update_one(
+                doc! { "_id": &completion.id },
+                doc! { "$setOnInsert": fields },
+            )
+            .upsert(true)
+            .await
+            .context("Failed to record assignment completion")?;
+        Ok(true)
+    }
+
+    /// Задания всех групп ученика с отметками о выполнении
+    pub async fn for_student(
+        &self,
+        user_id: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<StudentAssignment>> {
+        let groups = self.user_group_ids(user_id).await?;
+        if groups.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let assignments: Vec<Assignment> = self
+            .collection()
+            .find(doc! { "groupId": { "$in": &groups } })
+            .sort(doc! { "dueAt": 1, "_id": 1 })
+            .await
+            .context("Failed to query student assignments")?
+            .try_collect()
+            .await
+            .context("Failed to collect student assignments")?;
+        let ids: Vec<ObjectId> = assignments.iter().map(|a| a.id).collect();
+        let completions: Vec<AssignmentCompletion> = self
+            .completions()
+            .find(doc! { "assignmentId": { "$in": &ids }, "userId": user_id })
+            .await
+            .context("Failed to query assignment completions")?
+            .try_collect()
+            .await
+            .context("Failed to collect assignment completions")?;
+
+        Ok(assignments
+            .into_iter()
+            .map(|assignment| {
+                let done: HashMap<ObjectId, &AssignmentCompletion> = completions
+                    .iter()
+                    .filter(|c| c.assignment_id == assignment.id)
+                    .map(|c| (c.template_id, c))
+                    .collect();
+                let (status, items) = assignment_progress(&assignment, &done, now);
+                StudentAssignment {
+                    assignment: assignment.into(),
+                    status,
+                    items,
+                }
+            })
+            .collect())
+    }
+
+    /// Выполнение задания каждым учеником группы
+    pub async fn progress(
+        &self,
+        group_id: &ObjectId,
+        assignment_id: &ObjectId,
+        now: DateTime<Utc>,
+    ) -> Result<AssignmentProgressResponse> {
+        let assignment = self.get_in_group(group_id, assignment_id).await?;
+        let students: Vec<Document> = self
+            .mongo
+            .collection::<Document>("users")
+            .find(doc! { "group_ids": group_id.to_hex(), "role": "student" })
+            .projection(doc! { "_id": 1, "name": 1 })
+            .await
+            .context("Failed to query group students")?
+            .try_collect()
+            .await
+            .context("Failed to collect group students")?;
+        let completions: Vec<AssignmentCompletion> = self
+            .completions()
+            .find(doc! { "assignmentId": assignment_id })
+            .await
+            .context("Failed to query assignment completions")?
+            .try_collect()
+            .await
+            .context("Failed to collect assignment completions")?;
+
+        let mut rows: Vec<StudentAssignmentProgress> = students
+            .iter()
+            .filter_map(|student| {
+                let student_id = student.get_object_id("_id").ok()?.to_hex();
+                let done: HashMap<ObjectId, &AssignmentCompletion> = completions
+                    .iter()
+                    .filter(|c| c.user_id == student_id)
+                    .map(|c| (c.template_id, c))
+                    .collect();
+                let (status, items) = assignment_progress(&assignment, &done, now);
+                Some(StudentAssignmentProgress {
+                    name: student.get_str("name").unwrap_or_default().to_string(),
+                    student_id,
+                    status,
+                    completed_items: items.iter().filter(|item| item.completed).count(),
+                    late_items: items.iter().filter(|item| item.late).count(),
+                    last_completed_at: items.iter().filter_map(|item| item.completed_at).max(),
+                })
+            })
+            .collect();
+        rows.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(AssignmentProgressResponse {
+            total_items: assignment.template_ids.len(),
+            completed_students: rows
+                .iter()
+                .filter(|row| row.status == AssignmentStatus::Completed)
+                .count(),
+            assignment: assignment.into(),
+            students: rows,
+        })
+    }
+
+    async fn user_group_ids(&self, user_id: &str) -> Result<Vec<ObjectId>> {
+        let Ok(user_obj) = ObjectId::parse_str(user_id) else {
+            return Ok(Vec::new());
+        };
+        let user = self
+            .mongo
+            .collection::<Document>("users")
+            .find_one(doc! { "_id": user_obj })
+            .projection(doc! { "group_ids": 1 })
+            .await
+            .context("Failed to query user groups")?;
+        Ok(user
+            .as_ref()
+            .and_then(|user| user.get_array("group_ids").ok())
+            .map(|ids| {
+                ids.iter()
+                    .filter_map(|id| id.as_str())
+                    .filter_map(|id| ObjectId::parse_str(id).ok())
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn assignment(templates: &[ObjectId], due_at: DateTime<Utc>) -> Assignment {
+        Assignment {
+            id: ObjectId::new(),
+            group_id: ObjectId::new(),
+            template_ids: templates.to_vec(),
+            due_at,
+            instructions: String::new(),
+            allow_late: false,
+            min_score: 1,
+            created_by: "teacher".to_string(),
+            created_at: due_at - Duration::days(7),
+            updated_at: due_at - Duration::days(7),
+        }
+    }
+
+    fn completion(
+        assignment: &Assignment,
+        template_id: ObjectId,
+        late: bool,
+    ) -> AssignmentCompletion {
+        AssignmentCompletion {
+            id: AssignmentCompletion::key(&assignment.id, "student", &template_id),
+            assignment_id: assignment.id,
+            user_id: "student".to_string(),
+            template_id,
+            session_id: "session".to_string(),
+            score: 10,
+            late,
+            completed_at: assignment.due_at,
+        }
+    }
+
+    #[test]
+    fn status_follows_completed_items_and_deadline() {
+        let templates = [ObjectId::new(), ObjectId::new()];
+        let due_at = Utc::now();
+        let assignment = assignment(&templates, due_at);
+        let first = completion(&assignment, templates[0], false);
+        let second = completion(&assignment, templates[1], true);
+
+        let mut done = HashMap::new();
+        done.insert(templates[0], &first);
+        let (status, items) = assignment_progress(&assignment, &done, due_at - Duration::hours(1));
+        assert_eq!(status, AssignmentStatus::Pending);
+        assert!(items[0].completed && !items[1].completed);
+
+        let (status, _) = assignment_progress(&assignment, &done, due_at + Duration::hours(1));
+        assert_eq!(status, AssignmentStatus::Overdue);
+
+        done.insert(templates[1], &second);
+        let (status, items) = assignment_progress(&assignment, &done, due_at + Duration::hours(1));
+        assert_eq!(status, AssignmentStatus::Completed);
+        assert!(items[1].late);
+    }
+
+    #[test]
+    fn next_template_skips_completed_in_order() {
+        let templates = [ObjectId::new(), ObjectId::new(), ObjectId::new()];
+        let assignment = assignment(&templates, Utc::now());
+
+        let mut completed = HashSet::new();
+        assert_eq!(next_template(&assignment, &completed), Some(templates[0]));
+        completed.insert(templates[0]);
+        completed.insert(templates[2]);
+        assert_eq!(next_template(&assignment, &completed), Some(templates[1]));
+        completed.insert(templates[1]);
+        assert_eq!(next_template(&assignment, &completed), None);
+    }
+
+    #[test]
+    fn session_link_roundtrip() {
+        let assignment_id = ObjectId::new();
+        let template_id = ObjectId::new();
+        let value = session_assignment_value(&assignment_id, &template_id);
+        assert_eq!(
+            parse_session_assignment(&value),
+            Some((assignment_id, template_id))
+        );
+        assert_eq!(parse_session_assignment("broken"), None);
+    }
+}
@@ -0,0 +1,301 @@
+use std::io::Write;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use flate2::{write::GzEncoder, Compression};
+use futures::TryStreamExt;
+use mongodb::bson::{doc, oid::ObjectId, Bson, DateTime as BsonDateTime, Document};
+use mongodb::{Collection, Database};
+
+use crate::models::audit_archive::{
+    AuditArchiveFile, AuditArchiveRun, AuditArchiveStatus, AuditDayArchive,
+};
+use crate::services::session_archive_service::ArchiveStorage;
+
+const AUDIT_LOG: &str = "audit_log";
+const ARCHIVES: &str = "audit_archives";
+const RUNS: &str = "audit_archive_runs";
+
+/// Перенос старых записей `audit_log` в объектное хранилище.
+///
+/// Записи пишутся в журнал разными модулями и с разными полями времени, поэтому сутки
+/// записи определяются по времени создания её ObjectId. Новые записи API получают
+/// `_id` с текущим временем и никогда не попадают в уже прошедшие сутки - архивация
+/// не конфликтует с записью в журнал, а диапазон `_id` за сутки после выгрузки
+/// больше не меняется.
+pub struct AuditArchiveService {
+    mongo: Database,
+}
+
+impl AuditArchiveService {
+    pub fn new(mongo: Database) -> Self {
+        Self { mongo }
+    }
+
+    /// Самые ранние сутки, в которых остались записи старше `cutoff`
+    pub async fn oldest_pending_day(&self, cutoff: DateTime<Utc>) -> Result<Option<NaiveDate>> {
+        let oldest = self
+            .audit_log()
+            .find_one(doc! { "_id": { "$lt": object_id_at(cutoff) } })
+            .sort(doc! { "_id": 1 })
+            .projection(doc! { "_id": 1 })
+            .await
+            .context("Failed to find oldest audit entry")?;
+
+        Ok(oldest
+            .and_then(|entry| entry.get_object_id("_id").ok())
+            .and_then(|id| DateTime::from_timestamp_millis(id.timestamp().timestamp_millis()))
+            .map(|created_at| created_at.date_naive()))
+    }
+
+    /// Заархивировать записи за одни сутки.
+    ///
+    /// Порядок шагов (загрузка файла, запись в "audit_archives" со статусом `uploaded`,
+    /// удаление батчами, статус `completed`) делает повторный запуск после сбоя
+    /// безопасным: если файл уже загружен, оставшиеся записи суток в нём есть и
+    /// просто дочищаются, ничего не теряется и не дублируется.
+    pub async fn archive_day(
+        &self,
+        storage: &dyn ArchiveStorage,
+        day: NaiveDate,
+        run_id: ObjectId,
+        batch_size: i64,
+    ) -> Result<AuditDayArchive> {
+        let day_label = day.format("%Y-%m-%d").to_string();
+        let range = day_range(day);
+        let mut result = AuditDayArchive::default();
+
+        let files: Vec<AuditArchiveFile> = self
+            .archives()
+            .find(doc! { "day": &day_label })
+            .sort(doc! { "part": 1 })
+            .await
+            .context("Failed to load audit archive files")?
+            .try_collect()
+            .await
+            .context("Failed to read audit archive files")?;
+
+        if let Some(pending) = files
+            .iter()
+            .find(|file| file.status == AuditArchiveStatus::Uploaded)
+        {
+            result.entries_deleted = self.delete_range(&range, batch_size).await?;
+            self.mark_completed(&pending.key).await?;
+            return Ok(result);
+        }
+
+        let entries: Vec<Document> = self
+            .audit_log()
+            .find(range.clone())
+            .sort(doc! { "_id": 1 })
+            .await
+            .context("Failed to query audit entries for archival")?
+            .try_collect()
+            .await
+            .context("Failed to collect audit entries for archival")?;
+        if entries.is_empty() {
+            return Ok(result);
+        }
+
+        let part = files.len() as u32 + 1;
+        let key = archive_key(day, part);
+        let bytes = encode_entries(&entries)?;
+        let size_bytes = bytes.len() as u64;
+        storage
+            .put_bundle(&key, bytes)
+            .await
+            .with_context(|| format!("Failed to upload audit archive {}", key))?;
+
+        let file = AuditArchiveFile {
+            key: key.clone(),
+            day: day_label,
+            part,
+            status: AuditArchiveStatus::Uploaded,
+            entry_count: entries.len() as u64,
+            size_bytes,
+            run_id,
+            archived_at: Utc::now(),
+            completed_at: None,
+        };
+        self.archives()
+            .replace_one(doc! { "_id": &key }, &file)
+            .upsert(true)
+            .await
+            .context("Failed to record audit archive file")?;
+
+        result.files_written = 1;
+        result.entries_archived = file.entry_count;
+        result.entries_deleted = self.delete_range(&range, batch_size).await?;
+        self.mark_completed(&key).await?;
+
+        Ok(result)
+    }
+
+    pub async fn record_run(&self, run: &AuditArchiveRun) -> Result<()> {
+        self.runs()
+            .replace_one(doc! { "_id": run.id }, run)
+            .upsert(true)
+            .await
+            .context("Failed to record audit archive run")?;
+        Ok(())
+    }
+
+    /// Файлы архива, новые сутки первыми
+    pub async fn list_archives(&self, limit: i64) -> Result<Vec<AuditArchiveFile>> {
+        self.archives()
+            .find(doc! {})
+            .sort(doc! { "day": -1, "part": -1 })
+            .limit(limit)
+            .await
+            .context("Failed to list audit archives")?
+            .try_collect()
+            .await
+            .context("Failed to read audit archives")
+    }
+
+    async fn delete_range(&self, range: &Document, batch_size: i64) -> Result<u64> {
+        let mut deleted = 0;
+        loop {
+            let ids: Vec<Bson> = self
+                .audit_log()
+                .find(range.clone())
+                .projection(doc! { "_id": 1 })
+                .limit(batch_size.max(1))
+                .await
+                .context("Failed to query archived audit entries")?
+                .try_collect::<Vec<Document>>()
+                .await
+                .context("Failed to collect archived audit entries")?
+                .into_iter()
+                .filter_map(|entry| entry.get("_id").cloned())
+                .collect();
+            if ids.is_empty() {
+                return Ok(deleted);
+            }
+
+            let result = self
+                .audit_log()
+                .delete_many(doc! { "_id": { "$in": ids } })
+                .await
+                .context("Failed to delete archived audit entries")?;
+            deleted += result.deleted_count;
+        }
+    }
+
+    async fn mark_completed(&self, key: &str) -> Result<()> {
+        self.archives()
+            .update_one(
+                doc! { "_id": key },
+                doc! { "$set": {
+                    "status": "completed",
+                    "completed_at": BsonDateTime::now(),
+                } },
+            )
+            .await
+            .context("Failed to complete audit archive file")?;
+        Ok(())
+    }
+
+    fn audit_log(&self) -> Collection<Document> {
+        self.mongo.collection(AUDIT_LOG)
+    }
+
+    fn archives(&self) -> Collection<AuditArchiveFile> {
+        self.mongo.collection(ARCHIVES)
+    }
+
+    fn runs(&self) -> Collection<AuditArchiveRun> {
+        self.mongo.collection(RUNS)
+    }
+}
+
+/// Наименьший ObjectId, созданный в момент `at`: граница диапазона по `_id`
+pub fn object_id_at(at: DateTime<Utc>) -> ObjectId {
+    let mut bytes = [0u8; 12];
+    bytes[..4].copy_from_slice(&(at.timestamp().max(0) as u32).to_be_bytes());
+    ObjectId::from_bytes(bytes)
+}
+
+/// Фильтр записей, созданных в сутки `day` (UTC)
+fn day_range(day: NaiveDate) -> Document {
+    let start = day.and_hms_opt(0, 0, 0).unwrap().and_utc();
+    let end = start + Duration::days(1);
+    doc! { "_id": { "$gte": object_id_at(start), "$lt": object_id_at(end) } }
+}
+
+/// `audit/YYYY/MM/DD.ndjson.gz`; дополнительные файлы за те же сутки - `DD-2.ndjson.gz` и т.д.
+pub fn archive_key(day: NaiveDate, part: u32) -> String {
+    if part <= 1 {
+        format!("audit/{}.ndjson.gz", day.format("%Y/%m/%d"))
+    } else {
+        format!("audit/{}-{}.ndjson.gz", day.format("%Y/%m/%d"), part)
+    }
+}
+
+/// NDJSON в gzip: одна запись журнала в relaxed Extended JSON на строку.
+///
+/// Записи сохраняются как есть, со всеми полями, а не через модель `AuditLog`.
+pub fn encode_entries(entries: &[Document]) -> Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    for entry in entries {
+        let json = Bson::Document(entry.clone()).into_relaxed_extjson();
+        serde_json::to_writer(&mut encoder, &json).context("Failed to serialize audit entry")?;
+        encoder.write_all(b"\n")?;
+    }
+    encoder.finish().context("Failed to compress audit archive")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn archive_key_follows_date_layout() {
+        let day = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+        assert_eq!(archive_key(day, 1), "audit/2024/03/05.ndjson.gz");
+        assert_eq!(archive_key(day, 2), "audit/2024/03/05-2.ndjson.gz");
+    }
+
+    #[test]
+    fn object_id_bounds_follow_creation_time() {
+        let start = Utc.with_ymd_and_hms(2024, 3, 5, 0, 0, 0).unwrap();
+        let lower = object_id_at(start);
+        let upper = object_id_at(start + Duration::days(1));
+        let inside = ObjectId::from_bytes({
+            let mut bytes = [0xffu8; 12];
+            bytes[..4].copy_from_slice(&((start.timestamp() + 3600) as u32).to_be_bytes());
+            bytes
+        });
+
+        assert!(lower <= inside && inside < upper);
+        assert_eq!(
+            lower.timestamp().timestamp_millis(),
+            start.timestamp_millis()
+        );
+    }
+
+    #[test]
+    fn entries_are_encoded_as_gzipped_ndjson() {
+        let entries = vec![
+            doc! { "_id": ObjectId::new(), "event_type": "login", "createdAt": BsonDateTime::now() },
+            doc! { "_id": ObjectId::new(), "action": "update", "timestamp": "2024-03-05T10:00:00Z" },
+        ];
+        let bytes = encode_entries(&entries).unwrap();
+
+        let mut text = String::new();
+        GzDecoder::new(bytes.as_slice())
+            .read_to_string(&mut text)
+            .unwrap();
+        let lines: Vec<serde_json::Value> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["event_type"], "login");
+        assert!(lines[0]["_id"]["$oid"].is_string());
+        assert_eq!(lines[1]["action"], "update");
+    }
+}
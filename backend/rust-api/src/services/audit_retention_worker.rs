@@ -0,0 +1,214 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, NaiveTime, Utc};
+use mongodb::bson::oid::ObjectId;
+use redis::aio::ConnectionManager;
+use tokio::time::sleep;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{
+    config::AuditSettings,
+    metrics::AUDIT_RETENTION_WORKER_TICKS_TOTAL,
+    models::audit_archive::AuditArchiveRun,
+    services::{
+        archive_worker::{HEARTBEAT_SCRIPT, RELEASE_SCRIPT},
+        audit_archive_service::AuditArchiveService,
+        session_archive_service::ArchiveStorage,
+    },
+};
+
+const LOCK_KEY: &str = "lock:audit_retention";
+
+/// Раз в сутки переносит записи журнала аудита старше `retention_days` в архив.
+///
+/// Как и архивация сессий, работает в одном экземпляре под блокировкой в Redis;
+/// итог каждого запуска (в том числе неудачного) пишется в "audit_archive_runs".
+pub struct AuditRetentionWorker {
+    service: AuditArchiveService,
+    storage: Arc<dyn ArchiveStorage>,
+    redis: ConnectionManager,
+    settings: AuditSettings,
+}
+
+impl AuditRetentionWorker {
+    pub fn new(
+        service: AuditArchiveService,
+        storage: Arc<dyn ArchiveStorage>,
+        redis: ConnectionManager,
+        settings: AuditSettings,
+    ) -> Self {
+        Self {
+            service,
+            storage,
+            redis,
+            settings,
+        }
+    }
+
+    pub async fn run(&self) -> Result<()> {
+        let interval = Duration::from_secs(self.settings.worker_interval_secs);
+        info!(
+            "Starting audit retention worker (interval={}s, retention={}d)",
+            interval.as_secs(),
+            self.settings.retention_days
+        );
+
+        loop {
+            match self.run_once().await {
+                Ok(Some(run)) if run.error.is_none() => {
+                    AUDIT_RETENTION_WORKER_TICKS_TOTAL
+                        .with_label_values(&["success"])
+                        .inc();
+                    info!(
+                        days = run.days_archived,
+                        files = run.files_written,
+                        entries = run.entries_archived,
+                        deleted = run.entries_deleted,
+                        "Audit retention tick completed"
+                    );
+                }
+                Ok(Some(run)) => {
+                    AUDIT_RETENTION_WORKER_TICKS_TOTAL
+                        .with_label_values(&["error"])
+                        .inc();
+                    warn!(
+                        error = run.error.as_deref().unwrap_or_default(),
+                        "audit retention tick failed"
+                    );
+                }
+                Ok(None) => {
+                    AUDIT_RETENTION_WORKER_TICKS_TOTAL
+                        .with_label_values(&["skipped"])
+                        .inc();
+                    info!("Audit retention tick skipped: another instance holds the lock");
+                }
+                Err(err) => {
+                    AUDIT_RETENTION_WORKER_TICKS_TOTAL
+                        .with_label_values(&["error"])
+                        .inc();
+                    warn!(error = %err, "audit retention tick failed");
+                }
+            }
+
+            sleep(interval).await;
+        }
+    }
+
+    /// Один запуск архивации. `None`, если блокировку держит другой экземпляр.
+    pub async fn run_once(&self) -> Result<Option<AuditArchiveRun>> {
+        let token = Uuid::new_v4().to_string();
+        if !self.acquire_lock(&token).await? {
+            return Ok(None);
+        }
+
+        let mut run = AuditArchiveRun {
+            id: ObjectId::new(),
+            started_at: Utc::now(),
+            finished_at: None,
+            cutoff: self.cutoff(Utc::now()),
+            days_archived: 0,
+            files_written: 0,
+            entries_archived: 0,
+            entries_deleted: 0,
+            error: None,
+        };
+
+        if let Err(err) = self.archive_days(&token, &mut run).await {
+            run.error = Some(format!("{:#}", err));
+        }
+        run.finished_at = Some(Utc::now());
+        let recorded = self.service.record_run(&run).await;
+
+        if let Err(err) = self.release_lock(&token).await {
+            warn!(error = %err, "failed to release audit retention lock");
+        }
+
+        recorded?;
+        Ok(Some(run))
+    }
+
+    /// Граница архивации - полночь UTC, чтобы файл всегда содержал целые сутки
+    fn cutoff(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        (now - self.settings.retention())
+            .date_naive()
+            .and_time(NaiveTime::MIN)
+            .and_utc()
+    }
+
+    async fn archive_days(&self, token: &str, run: &mut AuditArchiveRun) -> Result<()> {
+        let mut previous = None;
+        while run.days_archived < self.settings.max_days_per_run {
+            let Some(day) = self.service.oldest_pending_day(run.cutoff).await? else {
+                break;
+            };
+            if previous == Some(day) {
+                bail!("Audit entries for {} were not removed after archival", day);
+            }
+
+            let archived = self
+                .service
+                .archive_day(self.storage.as_ref(), day, run.id, self.settings.batch_size)
+                .await
+                .with_context(|| format!("Failed to archive audit entries for {}", day))?;
+
+            run.days_archived += 1;
+            run.files_written += archived.files_written;
+            run.entries_archived += archived.entries_archived;
+            run.entries_deleted += archived.entries_deleted;
+            previous = Some(day);
+
+            if !self.heartbeat(token).await? {
+                bail!(
+                    "Audit retention lock lost after {} days, stopping",
+                    run.days_archived
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn acquire_lock(&self, token: &str) -> Result<bool> {
+        let mut conn = self.redis.clone();
+        let acquired: Option<String> = redis::cmd("SET")
+            .arg(LOCK_KEY)
+            .arg(token)
+            .arg("NX")
+            .arg("PX")
+            .arg(self.lock_ttl_ms())
+            .query_async(&mut conn)
+            .await
+            .context("Failed to acquire audit retention lock")?;
+        Ok(acquired.is_some())
+    }
+
+    async fn heartbeat(&self, token: &str) -> Result<bool> {
+        let mut conn = self.redis.clone();
+        let extended: i32 = redis::Script::new(HEARTBEAT_SCRIPT)
+            .key(LOCK_KEY)
+            .arg(token)
+            .arg(self.lock_ttl_ms())
+            .invoke_async(&mut conn)
+            .await
+            .context("Failed to extend audit retention lock")?;
+        Ok(extended == 1)
+    }
+
+    async fn release_lock(&self, token: &str) -> Result<()> {
+        let mut conn = self.redis.clone();
+        let _: i32 = redis::Script::new(RELEASE_SCRIPT)
+            .key(LOCK_KEY)
+            .arg(token)
+            .invoke_async(&mut conn)
+            .await
+            .context("Failed to release audit retention lock")?;
+        Ok(())
+    }
+
+    fn lock_ttl_ms(&self) -> u64 {
+        self.settings.lock_ttl_secs * 1000
+    }
+}
@@ -1,11 +1,34 @@
+use std::collections::BTreeMap;
+
 use anyhow::{Context, Result};
-use chrono::Utc;
+use chrono::{DateTime, Duration, Utc};
+use futures::{Stream, StreamExt, TryStreamExt};
 use mongodb::{
-    bson::{doc, DateTime as BsonDateTime, Regex},
+    bson::{doc, Bson, Document, Regex},
     Database,
 };
 
-use crate::models::audit_log::{AuditEventType, AuditLog, AuditLogQuery};
+use crate::models::{
+    anticheat::IncidentStatus,
+    audit_log::{
+        AuditActionCount, AuditActorSummary, AuditEventType, AuditLog, AuditLogEntry, AuditLogQuery,
+    },
+    permission::Permission,
+};
+use crate::services::audit_archive_service::object_id_at;
+use crate::utils::pagination::{Page, PageCursor, SortKey};
+
+pub const AUDIT_LOG_COLLECTION: &str = "audit_log";
+/// Newest first: `_id` grows with the write time and exists in entries of every shape
+pub const AUDIT_LOG_ORDER: SortKey = SortKey::new("newest", "_id", -1);
+/// Cursor batch size of the CSV export: rows are sent as soon as a batch arrives
+const EXPORT_BATCH_SIZE: u32 = 500;
+
+/// The same entry field is named differently by different writers
+const ACTOR_FIELDS: [&str; 3] = ["actor_id", "user_id", "admin_id"];
+const ACTION_FIELDS: [&str; 2] = ["action", "event_type"];
+const TARGET_FIELDS: [&str; 2] = ["target", "entity_type"];
+const TARGET_ID_FIELDS: [&str; 2] = ["target_id", "entity_id"];
 
 /// Parameters for audit event logging
 #[derive(Debug)]
@@ -48,7 +71,7 @@ impl AuditService {
             created_at: Utc::now(),
         };
 
-        let collection = self.mongo.collection::<AuditLog>("audit_log");
+        let collection = self.mongo.collection::<AuditLog>(AUDIT_LOG_COLLECTION);
         collection.insert_one(audit_log).await?;
 
         Ok(())
@@ -379,6 +402,81 @@ impl AuditService {
         .await
     }
 
+    /// Log forced logout of a user (admin action)
+    pub async fn log_force_logout(
+        &self,
+        admin_user_id: &str,
+        target_user_id: &str,
+        revoked_sessions: u64,
+        ip: Option<String>,
+        user_agent: Option<String>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.log_event(AuditEventParams {
+            event_type: AuditEventType::ForceLogout,
+            user_id: Some(admin_user_id.to_string()),
+            email: None,
+            success: true,
+            ip,
+            user_agent,
+            details: Some(format!(
+                "Forced logout of user {} ({} sessions revoked)",
+                target_user_id, revoked_sessions
+            )),
+            error_message: None,
+        })
+        .await
+    }
+
+    /// Log automatic unblock after `blocked_until` has passed (no admin involved)
+    pub async fn log_user_auto_unblock(
+        &self,
+        user_id: &str,
+        blocked_until: Option<DateTime<Utc>>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.log_event(AuditEventParams {
+            event_type: AuditEventType::AutoUnblockUser,
+            user_id: Some(user_id.to_string()),
+            email: None,
+            success: true,
+            ip: None,
+            user_agent: None,
+            details: Some(match blocked_until {
+                Some(until) => format!("Block expired at {}", until.to_rfc3339()),
+                None => "Block expired".to_string(),
+            }),
+            error_message: None,
+        })
+        .await
+    }
+
+    /// Log a personal data purge (admin action). The email is not logged on purpose
+    pub async fn log_user_purge(
+        &self,
+        admin_user_id: &str,
+        purged_user_id: &str,
+        removed: &BTreeMap<String, u64>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let removed = removed
+            .iter()
+            .map(|(collection, count)| format!("{}={}", collection, count))
+            .collect::<Vec<_>>()
+            .join(", ");
+        self.log_event(AuditEventParams {
+            event_type: AuditEventType::PurgeUser,
+            user_id: Some(admin_user_id.to_string()),
+            email: None,
+            success: true,
+            ip: None,
+            user_agent: None,
+            details: Some(format!(
+                "Purged personal data of user {} (removed: {})",
+                purged_user_id, removed
+            )),
+            error_message: None,
+        })
+        .await
+    }
+
     /// Log group creation (admin action)
     pub async fn log_group_create(
         &self,
@@ -449,51 +547,567 @@ impl AuditService {
         .await
     }
 
-    pub async fn list_logs(&self, query: AuditLogQuery) -> Result<Vec<AuditLog>> {
-        self.fetch_logs(query, None).await
+    /// Log adding a student to a group
+    pub async fn log_group_member_add(
+        &self,
+        admin_user_id: &str,
+        group_id: &str,
+        user_id: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.log_event(AuditEventParams {
+            event_type: AuditEventType::AddGroupMember,
+            user_id: Some(admin_user_id.to_string()),
+            email: None,
+            success: true,
+            ip: None,
+            user_agent: None,
+            details: Some(format!("Added user {} to group {}", user_id, group_id)),
+            error_message: None,
+        })
+        .await
     }
 
-    pub async fn export_logs(&self, query: AuditLogQuery, max_limit: u32) -> Result<Vec<AuditLog>> {
-        self.fetch_logs(query, Some(max_limit)).await
+    /// Log removing a student from a group
+    pub async fn log_group_member_remove(
+        &self,
+        admin_user_id: &str,
+        group_id: &str,
+        user_id: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.log_event(AuditEventParams {
+            event_type: AuditEventType::RemoveGroupMember,
+            user_id: Some(admin_user_id.to_string()),
+            email: None,
+            success: true,
+            ip: None,
+            user_agent: None,
+            details: Some(format!("Removed user {} from group {}", user_id, group_id)),
+            error_message: None,
+        })
+        .await
     }
 
-    async fn fetch_logs(
+    /// Log group archiving
+    pub async fn log_group_archive(
         &self,
-        query: AuditLogQuery,
-        override_limit: Option<u32>,
-    ) -> Result<Vec<AuditLog>> {
-        let collection = self.mongo.collection::<AuditLog>("audit_log");
-        let filter = build_filter(&query);
+        admin_user_id: &str,
+        group_id: &str,
+        group_name: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.log_event(AuditEventParams {
+            event_type: AuditEventType::ArchiveGroup,
+            user_id: Some(admin_user_id.to_string()),
+            email: None,
+            success: true,
+            ip: None,
+            user_agent: None,
+            details: Some(format!("Archived group {} '{}'", group_id, group_name)),
+            error_message: None,
+        })
+        .await
+    }
 
-        let limit = override_limit.unwrap_or_else(|| query.limit.unwrap_or(50).min(500)) as i64;
-        let skip = query.offset.unwrap_or(0) as u64;
+    /// Log a curator change for one of the affected teachers.
+    /// The entry is attributed to the teacher so it shows up in their history
+    pub async fn log_curator_change(
+        &self,
+        admin_user_id: &str,
+        teacher_id: &str,
+        group_id: &str,
+        assigned: bool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let action = if assigned {
+            "Assigned as curator of"
+        } else {
+            "Removed as curator of"
+        };
+        self.log_event(AuditEventParams {
+            event_type: AuditEventType::ReassignCurator,
+            user_id: Some(teacher_id.to_string()),
+            email: None,
+            success: true,
+            ip: None,
+            user_agent: None,
+            details: Some(format!(
+                "{} group {} by admin {}",
+                action, group_id, admin_user_id
+            )),
+            error_message: None,
+        })
+        .await
+    }
+
+    /// Log invite code creation
+    pub async fn log_group_invite_create(
+        &self,
+        admin_user_id: &str,
+        group_id: &str,
+        code: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.log_event(AuditEventParams {
+            event_type: AuditEventType::CreateGroupInvite,
+            user_id: Some(admin_user_id.to_string()),
+            email: None,
+            success: true,
+            ip: None,
+            user_agent: None,
+            details: Some(format!(
+                "Created invite code {} for group {}",
+                code, group_id
+            )),
+            error_message: None,
+        })
+        .await
+    }
+
+    /// Log invite code revocation
+    pub async fn log_group_invite_revoke(
+        &self,
+        admin_user_id: &str,
+        group_id: &str,
+        code: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.log_event(AuditEventParams {
+            event_type: AuditEventType::RevokeGroupInvite,
+            user_id: Some(admin_user_id.to_string()),
+            email: None,
+            success: true,
+            ip: None,
+            user_agent: None,
+            details: Some(format!(
+                "Revoked invite code {} for group {}",
+                code, group_id
+            )),
+            error_message: None,
+        })
+        .await
+    }
+
+    /// Log a student joining a group with an invite code
+    pub async fn log_group_join(
+        &self,
+        user_id: &str,
+        group_id: &str,
+        code: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.log_event(AuditEventParams {
+            event_type: AuditEventType::JoinGroup,
+            user_id: Some(user_id.to_string()),
+            email: None,
+            success: true,
+            ip: None,
+            user_agent: None,
+            details: Some(format!(
+                "Joined group {} with invite code {}",
+                group_id, code
+            )),
+            error_message: None,
+        })
+        .await
+    }
 
-        let mut cursor = collection
+    /// Log consent recording (admin action on behalf of guardian or school)
+    pub async fn log_consent_record(
+        &self,
+        admin_user_id: &str,
+        student_id: &str,
+        consent_type: &str,
+        document_version: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.log_event(AuditEventParams {
+            event_type: AuditEventType::RecordConsent,
+            user_id: Some(admin_user_id.to_string()),
+            email: None,
+            success: true,
+            ip: None,
+            user_agent: None,
+            details: Some(format!(
+                "Recorded consent '{}' (version {}) for user {}",
+                consent_type, document_version, student_id
+            )),
+            error_message: None,
+        })
+        .await
+    }
+
+    /// Log consent revocation (admin action)
+    pub async fn log_consent_revoke(
+        &self,
+        admin_user_id: &str,
+        student_id: &str,
+        consent_type: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.log_event(AuditEventParams {
+            event_type: AuditEventType::RevokeConsent,
+            user_id: Some(admin_user_id.to_string()),
+            email: None,
+            success: true,
+            ip: None,
+            user_agent: None,
+            details: Some(format!(
+                "Revoked consent '{}' for user {}",
+                consent_type, student_id
+            )),
+            error_message: None,
+        })
+        .await
+    }
+
+    /// Log rehydration of an archived session (admin action)
+    pub async fn log_session_rehydrate(
+        &self,
+        admin_user_id: &str,
+        session_id: &str,
+        archive_key: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.log_event(AuditEventParams {
+            event_type: AuditEventType::RehydrateSession,
+            user_id: Some(admin_user_id.to_string()),
+            email: None,
+            success: true,
+            ip: None,
+            user_agent: None,
+            details: Some(format!(
+                "Rehydrated session {} from {}",
+                session_id, archive_key
+            )),
+            error_message: None,
+        })
+        .await
+    }
+
+    /// Log a backup restore together with the rollback snapshot location
+    pub async fn log_backup_restore(
+        &self,
+        admin_user_id: &str,
+        backup_id: &str,
+        collections: &[String],
+        rollback_key: &str,
+        error_message: Option<String>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.log_event(AuditEventParams {
+            event_type: AuditEventType::RestoreBackup,
+            user_id: Some(admin_user_id.to_string()),
+            email: None,
+            success: error_message.is_none(),
+            ip: None,
+            user_agent: None,
+            details: Some(format!(
+                "Restored backup {} (collections: {}), rollback snapshot {}",
+                backup_id,
+                collections.join(", "),
+                rollback_key
+            )),
+            error_message,
+        })
+        .await
+    }
+
+    /// Log inspection of rate limit counters (admin action)
+    pub async fn log_rate_limit_inspect(
+        &self,
+        admin_user_id: &str,
+        key: &str,
+        active_counters: usize,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.log_event(AuditEventParams {
+            event_type: AuditEventType::InspectRateLimit,
+            user_id: Some(admin_user_id.to_string()),
+            email: None,
+            success: true,
+            ip: None,
+            user_agent: None,
+            details: Some(format!(
+                "Inspected rate limits for {} ({} active counters)",
+                key, active_counters
+            )),
+            error_message: None,
+        })
+        .await
+    }
+
+    /// Log reset of rate limit counters (admin action)
+    pub async fn log_rate_limit_reset(
+        &self,
+        admin_user_id: &str,
+        key: &str,
+        cleared_keys: &[String],
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.log_event(AuditEventParams {
+            event_type: AuditEventType::ResetRateLimit,
+            user_id: Some(admin_user_id.to_string()),
+            email: None,
+            success: true,
+            ip: None,
+            user_agent: None,
+            details: Some(format!(
+                "Reset rate limits for {}: [{}]",
+                key,
+                cleared_keys.join(", ")
+            )),
+            error_message: None,
+        })
+        .await
+    }
+
+    pub async fn log_role_permissions_update(
+        &self,
+        admin_user_id: &str,
+        role: &str,
+        permissions: &[Permission],
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let names: Vec<&str> = permissions.iter().map(Permission::as_str).collect();
+        self.log_event(AuditEventParams {
+            event_type: AuditEventType::UpdateRolePermissions,
+            user_id: Some(admin_user_id.to_string()),
+            email: None,
+            success: true,
+            ip: None,
+            user_agent: None,
+            details: Some(format!(
+                "Set permissions of role {}: [{}]",
+                role,
+                names.join(", ")
+            )),
+            error_message: None,
+        })
+        .await
+    }
+
+    pub async fn log_incident_update(
+        &self,
+        admin_user_id: &str,
+        incident_id: &str,
+        from: IncidentStatus,
+        to: IncidentStatus,
+        forced: bool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.log_event(AuditEventParams {
+            event_type: AuditEventType::UpdateIncident,
+            user_id: Some(admin_user_id.to_string()),
+            email: None,
+            success: true,
+            ip: None,
+            user_agent: None,
+            details: Some(format!(
+                "Incident {}: {} -> {}{}",
+                incident_id,
+                from,
+                to,
+                if forced { " (forced)" } else { "" }
+            )),
+            error_message: None,
+        })
+        .await
+    }
+
+    pub async fn log_incident_assign(
+        &self,
+        admin_user_id: &str,
+        incident_id: &str,
+        assignee: Option<&str>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.log_event(AuditEventParams {
+            event_type: AuditEventType::AssignIncident,
+            user_id: Some(admin_user_id.to_string()),
+            email: None,
+            success: true,
+            ip: None,
+            user_agent: None,
+            details: Some(match assignee {
+                Some(assignee) => format!("Incident {} assigned to {}", incident_id, assignee),
+                None => format!("Incident {} unassigned", incident_id),
+            }),
+            error_message: None,
+        })
+        .await
+    }
+
+    pub async fn log_incident_comment(
+        &self,
+        admin_user_id: &str,
+        incident_id: &str,
+        comment_id: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.log_event(AuditEventParams {
+            event_type: AuditEventType::CommentIncident,
+            user_id: Some(admin_user_id.to_string()),
+            email: None,
+            success: true,
+            ip: None,
+            user_agent: None,
+            details: Some(format!(
+                "Comment {} on incident {}",
+                comment_id, incident_id
+            )),
+            error_message: None,
+        })
+        .await
+    }
+
+    /// One page of entries, newest first: by `offset` or after `cursor`
+    pub async fn list_logs(
+        &self,
+        query: AuditLogQuery,
+        cursor: Option<&PageCursor>,
+    ) -> Result<Page<AuditLogEntry>> {
+        let collection = self.mongo.collection::<Document>(AUDIT_LOG_COLLECTION);
+        let filter = AUDIT_LOG_ORDER.apply(build_filter(&query), cursor);
+
+        let limit = query.limit.unwrap_or(50).min(500) as usize;
+        let mut find = collection
             .find(filter)
-            .sort(doc! { "createdAt": -1 })
-            .skip(skip)
-            .limit(limit)
+            .sort(AUDIT_LOG_ORDER.sort_document())
+            .limit(limit as i64 + 1);
+        if cursor.is_none() {
+            find = find.skip(query.offset.unwrap_or(0) as u64);
+        }
+
+        let documents: Vec<Document> = find
+            .await
+            .context("Failed to query audit logs")?
+            .try_collect()
             .await
-            .context("Failed to query audit logs")?;
+            .context("Failed to read audit logs")?;
+        let (documents, next_cursor) = AUDIT_LOG_ORDER.split_page(documents, limit);
 
-        let mut result = Vec::new();
-        while cursor
-            .advance()
+        Ok(Page {
+            items: documents.iter().map(AuditLogEntry::from_document).collect(),
+            next_cursor,
+        })
+    }
+
+    /// Entries matching the filters, newest first, read lazily from the cursor.
+    ///
+    /// Pagination params are ignored; at most `max_rows` entries are returned.
+    pub async fn stream_logs(
+        &self,
+        query: &AuditLogQuery,
+        max_rows: u64,
+    ) -> Result<impl Stream<Item = Result<AuditLogEntry>> + Send + 'static> {
+        let cursor = self
+            .mongo
+            .collection::<Document>(AUDIT_LOG_COLLECTION)
+            .find(build_filter(query))
+            .sort(doc! { "_id": -1 })
+            .limit(max_rows.min(i64::MAX as u64) as i64)
+            .batch_size(EXPORT_BATCH_SIZE)
             .await
-            .context("Failed to advance audit log cursor")?
-        {
-            let log = cursor
-                .deserialize_current()
-                .context("Failed to deserialize audit log")?;
-            result.push(log);
-        }
+            .context("Failed to query audit logs for export")?;
 
-        Ok(result)
+        Ok(cursor.map(|document| {
+            document
+                .map(|document| AuditLogEntry::from_document(&document))
+                .context("Failed to read audit log for export")
+        }))
+    }
+
+    /// Total number of entries matching the filters, ignoring pagination
+    pub async fn count_logs(&self, query: &AuditLogQuery) -> Result<u64> {
+        self.mongo
+            .collection::<Document>(AUDIT_LOG_COLLECTION)
+            .count_documents(build_filter(query))
+            .await
+            .context("Failed to count audit logs")
+    }
+
+    /// Per-action counts of one actor over a period
+    pub async fn actor_summary(
+        &self,
+        actor_id: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<AuditActorSummary> {
+        let mut filter = actor_filter(actor_id);
+        filter.insert("_id", id_range(Some(from), Some(to)));
+
+        let pipeline = vec![
+            doc! { "$match": filter },
+            doc! { "$group": {
+                "_id": { "$ifNull": ["$action", "$event_type"] },
+                "count": { "$sum": 1 },
+                "last_id": { "$max": "$_id" },
+            } },
+            doc! { "$sort": { "count": -1, "_id": 1 } },
+        ];
+        let rows: Vec<Document> = self
+            .mongo
+            .collection::<Document>(AUDIT_LOG_COLLECTION)
+            .aggregate(pipeline)
+            .await
+            .context("Failed to aggregate audit actions")?
+            .try_collect()
+            .await
+            .context("Failed to read audit action counts")?;
+
+        let actions: Vec<AuditActionCount> = rows
+            .iter()
+            .map(|row| AuditActionCount {
+                action: row.get_str("_id").unwrap_or("unknown").to_string(),
+                count: match row.get("count") {
+                    Some(Bson::Int32(count)) => *count as u64,
+                    Some(Bson::Int64(count)) => *count as u64,
+                    _ => 0,
+                },
+                last_at: row.get_object_id("last_id").ok().and_then(|id| {
+                    DateTime::from_timestamp_millis(id.timestamp().timestamp_millis())
+                }),
+            })
+            .collect();
+
+        Ok(AuditActorSummary {
+            actor_id: actor_id.to_string(),
+            from,
+            to,
+            total: actions.iter().map(|action| action.count).sum(),
+            actions,
+        })
     }
 }
 
-fn build_filter(query: &AuditLogQuery) -> mongodb::bson::Document {
+/// Match entries where any of the aliased fields satisfies the condition
+fn any_field(fields: &[&str], condition: Bson) -> Document {
+    match fields {
+        [field] => doc! { *field: condition },
+        _ => doc! {
+            "$or": fields
+                .iter()
+                .map(|field| doc! { *field: condition.clone() })
+                .collect::<Vec<_>>()
+        },
+    }
+}
+
+fn actor_filter(actor_id: &str) -> Document {
+    any_field(&ACTOR_FIELDS, Bson::String(actor_id.to_string()))
+}
+
+/// `template.*` matches every action starting with `template.`, anything else is exact
+fn action_condition(action: &str) -> Bson {
+    match action.strip_suffix('*') {
+        Some(prefix) => Bson::RegularExpression(Regex {
+            pattern: format!("^{}", regex::escape(prefix)),
+            options: String::new(),
+        }),
+        None => Bson::String(action.to_string()),
+    }
+}
+
+/// `_id` range by entry creation time, both bounds inclusive
+fn id_range(from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Document {
+    let mut range = doc! {};
+    if let Some(from) = from {
+        range.insert("$gte", object_id_at(from));
+    }
+    if let Some(to) = to {
+        range.insert("$lt", object_id_at(to + Duration::seconds(1)));
+    }
+    range
+}
+
+fn build_filter(query: &AuditLogQuery) -> Document {
     let mut filter = doc! {};
+    let mut any_of = Vec::new();
 
     if let Some(event_type) = &query.event_type {
         filter.insert("event_type", event_type.as_str());
@@ -503,19 +1117,31 @@ fn build_filter(query: &AuditLogQuery) -> mongodb::bson::Document {
         filter.insert("user_id", user_id);
     }
 
+    if let Some(actor_id) = &query.actor_id {
+        any_of.push(actor_filter(actor_id));
+    }
+
+    if let Some(action) = &query.action {
+        any_of.push(any_field(&ACTION_FIELDS, action_condition(action)));
+    }
+
+    if let Some(target) = &query.target {
+        any_of.push(any_field(&TARGET_FIELDS, Bson::String(target.clone())));
+    }
+
+    if let Some(target_id) = &query.target_id {
+        any_of.push(any_field(
+            &TARGET_ID_FIELDS,
+            Bson::String(target_id.clone()),
+        ));
+    }
+
     if let Some(success) = query.success {
         filter.insert("success", success);
     }
 
     if query.from.is_some() || query.to.is_some() {
-        let mut range = doc! {};
-        if let Some(from) = query.from {
-            range.insert("$gte", BsonDateTime::from_millis(from.timestamp_millis()));
-        }
-        if let Some(to) = query.to {
-            range.insert("$lte", BsonDateTime::from_millis(to.timestamp_millis()));
-        }
-        filter.insert("createdAt", range);
+        filter.insert("_id", id_range(query.from, query.to));
     }
 
     if let Some(search) = &query.search {
@@ -523,15 +1149,60 @@ fn build_filter(query: &AuditLogQuery) -> mongodb::bson::Document {
             pattern: search.to_string(),
             options: "i".into(),
         };
-        filter.insert(
-            "$or",
-            vec![
-                doc! { "email": &regex },
-                doc! { "details": &regex },
-                doc! { "error_message": &regex },
-            ],
-        );
+        any_of.push(doc! {
+            "$or": [
+                { "email": &regex },
+                { "details": &regex },
+                { "error_message": &regex },
+                { "reason": &regex },
+                { "target_id": &regex },
+            ]
+        });
+    }
+
+    match any_of.len() {
+        0 => {}
+        1 => filter.extend(any_of.remove(0)),
+        _ => {
+            filter.insert("$and", any_of);
+        }
     }
 
     filter
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn action_wildcard_becomes_anchored_prefix() {
+        match action_condition("template.*") {
+            Bson::RegularExpression(regex) => assert_eq!(regex.pattern, "^template\\."),
+            other => panic!("unexpected condition {other:?}"),
+        }
+        assert_eq!(
+            action_condition("template.create"),
+            Bson::String("template.create".into())
+        );
+    }
+
+    #[test]
+    fn filter_combines_field_aliases() {
+        let filter = build_filter(&AuditLogQuery {
+            actor_id: Some("admin-1".into()),
+            target: Some("templates".into()),
+            ..Default::default()
+        });
+        let clauses = filter.get_array("$and").unwrap();
+        assert_eq!(clauses.len(), 2);
+        assert_eq!(
+            clauses[0].as_document().unwrap(),
+            &doc! { "$or": [
+                { "actor_id": "admin-1" },
+                { "user_id": "admin-1" },
+                { "admin_id": "admin-1" },
+            ] }
+        );
+    }
+}
@@ -1,17 +1,30 @@
 use crate::middlewares::auth::JwtService;
-use crate::models::refresh_token::{ActiveSession, RefreshToken};
+use crate::models::login_history::LoginMethod;
+use crate::models::refresh_token::{ActiveSession, RefreshToken, SessionDevice};
 use crate::models::user::{
-    AuthResponse, LoginRequest, RegisterRequest, User, UserProfile, UserRole,
+    AuthResponse, LoginRequest, RegisterRequest, UpdateProfileRequest, User, UserProfile, UserRole,
 };
+use crate::services::block_expiry_worker::release_expired_block;
+use crate::services::login_history_service::LoginHistoryService;
+use crate::utils::mongo_retry::{retry_read, write_once};
 use anyhow::{anyhow, Context, Result};
 use bcrypt::{hash, verify, DEFAULT_COST};
 use chrono::{Duration, Utc};
 use mongodb::bson::{doc, oid::ObjectId};
+use mongodb::options::ReturnDocument;
 use mongodb::Database;
 use redis::aio::ConnectionManager;
 use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
+/// Время жизни access-токена (`JWT_ACCESS_TOKEN_TTL_SECONDS`, по умолчанию час)
+pub fn access_token_ttl_seconds() -> i64 {
+    std::env::var("JWT_ACCESS_TOKEN_TTL_SECONDS")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(3600)
+}
+
 pub struct AuthService {
     mongo: Database,
     redis: ConnectionManager,
@@ -23,10 +36,7 @@ pub struct AuthService {
 impl AuthService {
     pub fn new(mongo: Database, redis: ConnectionManager, jwt_service: JwtService) -> Self {
         // Read TTL from env or use defaults
-        let access_token_ttl_seconds = std::env::var("JWT_ACCESS_TOKEN_TTL_SECONDS")
-            .ok()
-            .and_then(|v| v.parse::<i64>().ok())
-            .unwrap_or(3600); // Default: 1 hour
+        let access_token_ttl_seconds = access_token_ttl_seconds();
 
         let refresh_token_ttl_seconds = std::env::var("JWT_REFRESH_TOKEN_TTL_SECONDS")
             .ok()
@@ -71,12 +81,24 @@ impl AuthService {
 
         // Create user document
         let now = Utc::now();
+        let role = req.role.unwrap_or_default(); // Default to student
+                                                 // Настроенные роли назначает только администратор
+        if !role.is_built_in() {
+            return Err(anyhow!(
+                "Role {} cannot be chosen at registration",
+                role.as_str()
+            ));
+        }
         let user = User {
             id: None, // MongoDB will generate
             email: req.email.clone(),
             password_hash,
             name: req.name,
-            role: req.role.unwrap_or_default(), // Default to student
+            // Год рождения храним только для учеников: нужен для возрастных ограничений
+            birth_year: req.birth_year.filter(|_| role == UserRole::Student),
+            timezone: None,
+            preferred_locale: None,
+            role,
             group_ids: req.group_ids.unwrap_or_default(),
             is_blocked: false,
             created_at: now,
@@ -88,8 +110,7 @@ impl AuthService {
         };
 
         // Insert user
-        let insert_result = users_collection
-            .insert_one(&user)
+        let insert_result = write_once("users.insert", || users_collection.insert_one(&user))
             .await
             .context("Failed to insert user")?;
 
@@ -132,11 +153,13 @@ impl AuthService {
         let users_collection = self.mongo.collection::<User>("users");
 
         // Find user by email
-        let user = users_collection
-            .find_one(doc! { "email": &req.email })
-            .await
-            .context("Failed to query user")?
-            .ok_or_else(|| anyhow!("Invalid email or password"))?;
+        let user = retry_read("users.find_by_email", || {
+            users_collection.find_one(doc! { "email": &req.email })
+        })
+        .await
+        .context("Failed to query user")?
+        .ok_or_else(|| anyhow!("Invalid email or password"))?;
+        let user = self.clear_expired_block(user).await?;
 
         // Check if user is blocked
         if user.is_blocked {
@@ -156,14 +179,29 @@ impl AuthService {
 
         let user_id = user.id.ok_or_else(|| anyhow!("User ID not found"))?;
 
-        // Update last login timestamp
-        users_collection
-            .update_one(
+        // Время входа пишется атомарно, в ответ уходит уже обновлённый профиль
+        let user = users_collection
+            .find_one_and_update(
                 doc! { "_id": user_id },
                 doc! { "$set": { "lastLoginAt": mongodb::bson::DateTime::now() } },
             )
+            .return_document(ReturnDocument::After)
+            .await
+            .context("Failed to update last login timestamp")?
+            .unwrap_or(user);
+
+        // История входов не должна мешать самому входу
+        if let Err(err) = LoginHistoryService::new(self.mongo.clone())
+            .record(
+                user_id,
+                LoginMethod::Password,
+                ip.as_deref(),
+                user_agent.as_deref(),
+            )
             .await
-            .context("Failed to update last login timestamp")?;
+        {
+            tracing::warn!(user_id = %user_id.to_hex(), "Failed to record login history: {:#}", err);
+        }
 
         // Generate access token
         let access_token = self.generate_access_token(&user_id, &user.role, &user.group_ids)?;
@@ -230,6 +268,7 @@ impl AuthService {
         };
         let expires_at = now + Duration::seconds(ttl);
 
+        let device = SessionDevice::new(user_agent.as_deref(), ip.as_deref());
         let refresh_token = RefreshToken {
             id: None,
             user_id: *user_id,
@@ -240,13 +279,15 @@ impl AuthService {
             user_agent,
             ip,
             revoked: false,
+            device: Some(device),
         };
 
         let collection = self.mongo.collection::<RefreshToken>("refresh_tokens");
-        collection
-            .insert_one(&refresh_token)
-            .await
-            .context("Failed to insert refresh token")?;
+        write_once("refresh_tokens.insert", || {
+            collection.insert_one(&refresh_token)
+        })
+        .await
+        .context("Failed to insert refresh token")?;
 
         Ok(token)
     }
@@ -291,6 +332,7 @@ impl AuthService {
             .await
             .context("Failed to query user")?
             .ok_or_else(|| anyhow!("User not found"))?;
+        let user = self.clear_expired_block(user).await?;
 
         if user.is_blocked {
             return Err(anyhow!("User account is blocked"));
@@ -301,6 +343,21 @@ impl AuthService {
         self.generate_access_token(&user_id, &user.role, &user.group_ids)
     }
 
+    /// Истёкшая временная блокировка снимается сразу, не дожидаясь фоновой задачи
+    async fn clear_expired_block(&self, mut user: User) -> Result<User> {
+        let now = Utc::now();
+        if !user.is_blocked || user.block_active(now) {
+            return Ok(user);
+        }
+        if let Some(user_id) = user.id {
+            release_expired_block(&self.mongo, user_id, now).await?;
+        }
+        user.is_blocked = false;
+        user.blocked_until = None;
+        user.block_reason = None;
+        Ok(user)
+    }
+
     /// Logout user by revoking refresh token
     /// Returns user_id for audit logging
     pub async fn logout(&self, refresh_token: &str) -> Result<String> {
@@ -333,24 +390,50 @@ impl AuthService {
         let object_id = ObjectId::parse_str(user_id).context("Invalid user ID format")?;
 
         let collection = self.mongo.collection::<User>("users");
-        collection
-            .find_one(doc! { "_id": object_id })
+        retry_read("users.find_by_id", || {
+            collection.find_one(doc! { "_id": object_id })
+        })
+        .await
+        .context("Failed to query user")?
+        .ok_or_else(|| anyhow!("User not found"))
+    }
+
+    /// Update fields the user may change in their own profile
+    pub async fn update_profile(&self, user_id: &str, req: UpdateProfileRequest) -> Result<User> {
+        let object_id = ObjectId::parse_str(user_id).context("Invalid user ID format")?;
+
+        let mut set = doc! { "updatedAt": mongodb::bson::DateTime::now() };
+        if let Some(locale) = req.preferred_locale {
+            set.insert("preferredLocale", locale);
+        }
+
+        self.mongo
+            .collection::<User>("users")
+            .find_one_and_update(doc! { "_id": object_id }, doc! { "$set": set })
+            .return_document(ReturnDocument::After)
             .await
-            .context("Failed to query user")?
+            .context("Failed to update profile")?
             .ok_or_else(|| anyhow!("User not found"))
     }
 
-    /// Get active sessions for a user
+    /// Get active sessions for a user, most recently used first.
+    /// The session matching `current_token` is flagged `is_current`
     pub async fn get_active_sessions(
         &self,
         user_id: &str,
-        current_token_hash: Option<String>,
+        current_token: Option<&str>,
     ) -> Result<Vec<ActiveSession>> {
         let object_id = ObjectId::parse_str(user_id).context("Invalid user ID format")?;
+        let current_token_hash = current_token.map(|token| self.hash_token(token));
 
         let collection = self.mongo.collection::<RefreshToken>("refresh_tokens");
         let mut cursor = collection
-            .find(doc! { "userId": object_id, "revoked": false })
+            .find(doc! {
+                "userId": object_id,
+                "revoked": false,
+                "expiresAt": { "$gt": mongodb::bson::DateTime::now() },
+            })
+            .sort(doc! { "lastUsedAt": -1 })
             .await
             .context("Failed to query refresh tokens")?;
 
@@ -362,16 +445,32 @@ impl AuthService {
             .await
             .context("Failed to read refresh token")?
         {
-            let mut session = ActiveSession::from(token.clone());
-            if let Some(ref current_hash) = current_token_hash {
-                session.is_current = &token.token_hash == current_hash;
-            }
+            let is_current = current_token_hash.as_deref() == Some(token.token_hash.as_str());
+            let mut session = ActiveSession::from(token);
+            session.is_current = is_current;
             sessions.push(session);
         }
 
         Ok(sessions)
     }
 
+    /// Revoke one session of the user by its id.
+    /// Returns false if there is no such active session for this user
+    pub async fn revoke_session(&self, user_id: &str, session_id: ObjectId) -> Result<bool> {
+        let object_id = ObjectId::parse_str(user_id).context("Invalid user ID format")?;
+
+        let collection = self.mongo.collection::<RefreshToken>("refresh_tokens");
+        let result = collection
+            .update_one(
+                doc! { "_id": session_id, "userId": object_id, "revoked": false },
+                doc! { "$set": { "revoked": true } },
+            )
+            .await
+            .context("Failed to revoke session")?;
+
+        Ok(result.modified_count > 0)
+    }
+
     /// Revoke all sessions except current
     pub async fn revoke_other_sessions(&self, user_id: &str, current_token: &str) -> Result<u64> {
         let object_id = ObjectId::parse_str(user_id).context("Invalid user ID format")?;
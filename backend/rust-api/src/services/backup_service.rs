@@ -1,14 +1,40 @@
-use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+use anyhow::{anyhow, Context, Result};
 use chrono::Utc;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use futures::TryStreamExt;
 use mongodb::{
-    bson::{doc, oid::ObjectId},
+    bson::{doc, oid::ObjectId, to_bson, Bson, DateTime as BsonDateTime, Document},
     Database,
 };
+use redis::aio::ConnectionManager;
+use tracing::warn;
+use uuid::Uuid;
 
 use crate::models::backup::{
-    BackupCreateRequest, BackupCreateResponse, BackupRecord, BackupRestoreResponse, BackupStatus,
+    BackupCollectionProgress, BackupCreateOutcome, BackupCreateRequest, BackupCreateResponse,
+    BackupRecord, BackupRestoreCollectionPlan, BackupRestoreOutcome, BackupRestoreRequest,
+    BackupRestoreResponse, BackupStatus,
+};
+use crate::services::{
+    archive_worker::RELEASE_SCRIPT, audit_service::AuditService,
+    session_archive_service::ArchiveStorage,
 };
 
+const BACKUPS_COLLECTION: &str = "backups";
+
+/// Несжатый размер части NDJSON, после которого она загружается в хранилище.
+/// В памяти держится не больше одной части, а не вся коллекция.
+const PART_MAX_BYTES: usize = 8 * 1024 * 1024;
+
+const RESTORE_LOCK_KEY: &str = "lock:backup_restore";
+/// Восстановление больших коллекций идёт долго; по завершении блокировка снимается явно
+const RESTORE_LOCK_TTL_MS: u64 = 60 * 60 * 1000;
+/// Сколько `_id` сверяется с текущими данными одним запросом
+const RESTORE_LOOKUP_BATCH: usize = 500;
+
 pub struct BackupService {
     mongo: Database,
 }
@@ -19,7 +45,7 @@ impl BackupService {
     }
 
     pub async fn list_backups(&self) -> Result<Vec<BackupRecord>> {
-        let collection = self.mongo.collection::<BackupRecord>("backups");
+        let collection = self.mongo.collection::<BackupRecord>(BACKUPS_COLLECTION);
         let mut cursor = collection
             .find(doc! {})
             .await
@@ -39,61 +65,594 @@ impl BackupService {
         Ok(backups)
     }
 
+    pub async fn get_backup(&self, id: ObjectId) -> Result<Option<BackupRecord>> {
+        self.mongo
+            .collection::<BackupRecord>(BACKUPS_COLLECTION)
+            .find_one(doc! { "_id": id })
+            .await
+            .context("Failed to load backup record")
+    }
+
+    /// Создать задачу резервного копирования в статусе `Pending`.
+    ///
+    /// Сама выгрузка выполняется `run_backup`; здесь фиксируются состав и число документов
+    /// каждой коллекции, от которых считается прогресс.
     pub async fn create_backup(
         &self,
         request: BackupCreateRequest,
-        _created_by: &str,
-    ) -> Result<BackupCreateResponse> {
-        let collection = self.mongo.collection::<BackupRecord>("backups");
+        created_by: &str,
+    ) -> Result<BackupCreateOutcome> {
+        let existing = self
+            .mongo
+            .list_collection_names()
+            .await
+            .context("Failed to list collections")?;
+
+        let names = match request.collections {
+            Some(requested) => {
+                let mut names: Vec<String> = Vec::new();
+                for name in requested {
+                    if !names.contains(&name) {
+                        names.push(name);
+                    }
+                }
+                let unknown: Vec<String> = names
+                    .iter()
+                    .filter(|name| !existing.contains(name))
+                    .cloned()
+                    .collect();
+                if names.is_empty() || !unknown.is_empty() {
+                    return Ok(BackupCreateOutcome::UnknownCollections(unknown));
+                }
+                names
+            }
+            None => {
+                let mut names: Vec<String> = existing
+                    .into_iter()
+                    .filter(|name| name != BACKUPS_COLLECTION && !name.starts_with("system."))
+                    .collect();
+                names.sort();
+                names
+            }
+        };
+
+        let mut collections = Vec::with_capacity(names.len());
+        for name in &names {
+            let total_documents = self
+                .mongo
+                .collection::<Document>(name)
+                .count_documents(doc! {})
+                .await
+                .with_context(|| format!("Failed to count documents in {}", name))?;
+            collections.push(BackupCollectionProgress {
+                name: name.clone(),
+                total_documents,
+                ..Default::default()
+            });
+        }
+
+        let id = ObjectId::new();
         let now = Utc::now();
         let label = request
             .label
             .unwrap_or_else(|| format!("backup-{}", now.format("%Y%m%d-%H%M%S")));
-        let storage_path = format!("mongodb://backup/{label}.bson");
+        let storage_path = format!("backups/{}/", id.to_hex());
 
         let record = BackupRecord {
-            id: None,
+            id: Some(id),
             label,
-            status: BackupStatus::Completed,
+            status: BackupStatus::Pending,
             storage_path: Some(storage_path.clone()),
             error: None,
             created_at: now,
+            created_by: Some(created_by.to_string()),
+            total_collections: collections.len() as u32,
+            total_documents: collections.iter().map(|c| c.total_documents).sum(),
+            collections,
+            documents_written: 0,
+            bytes_written: 0,
+            progress_percent: 0.0,
+            finished_at: None,
         };
-        let insert_result = collection
-            .insert_one(record)
+        self.mongo
+            .collection::<BackupRecord>(BACKUPS_COLLECTION)
+            .insert_one(&record)
             .await
             .context("Failed to insert backup record")?;
-        Ok(BackupCreateResponse {
-            id: insert_result
-                .inserted_id
-                .as_object_id()
-                .map(|oid| oid.to_hex())
-                .unwrap_or_default(),
-            status: BackupStatus::Completed,
+
+        Ok(BackupCreateOutcome::Created(BackupCreateResponse {
+            id: id.to_hex(),
+            status: BackupStatus::Pending,
             storage_path: Some(storage_path),
-        })
+            collections: names,
+        }))
+    }
+
+    /// Выгрузить коллекции задачи в хранилище частями gzip NDJSON.
+    ///
+    /// Документы читаются курсором; после загрузки каждой части запись в "backups"
+    /// обновляется, так что `GET /admin/backups/{id}` показывает прогресс во время работы.
+    pub async fn run_backup(&self, id: ObjectId, storage: &dyn ArchiveStorage) -> Result<()> {
+        let mut record = self
+            .get_backup(id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Backup {} not found", id))?;
+
+        record.status = BackupStatus::Running;
+        self.save_progress(&record).await?;
+
+        let result = self.write_collections(&mut record, storage).await;
+
+        match &result {
+            Ok(()) => {
+                record.status = BackupStatus::Completed;
+                record.progress_percent = 100.0;
+            }
+            Err(err) => {
+                record.status = BackupStatus::Failed;
+                record.error = Some(format!("{:#}", err));
+            }
+        }
+        record.finished_at = Some(Utc::now());
+        self.save_progress(&record).await?;
+
+        result
     }
 
+    async fn write_collections(
+        &self,
+        record: &mut BackupRecord,
+        storage: &dyn ArchiveStorage,
+    ) -> Result<()> {
+        let prefix = record.storage_path.clone().unwrap_or_default();
+
+        for index in 0..record.collections.len() {
+            let name = record.collections[index].name.clone();
+            let mut cursor = self
+                .mongo
+                .collection::<Document>(&name)
+                .find(doc! {})
+                .sort(doc! { "_id": 1 })
+                .await
+                .with_context(|| format!("Failed to read collection {}", name))?;
+
+            let mut part = Vec::new();
+            let mut part_documents = 0u64;
+            while let Some(document) = cursor
+                .try_next()
+                .await
+                .with_context(|| format!("Failed to read document from {}", name))?
+            {
+                append_document(&mut part, document)?;
+                part_documents += 1;
+
+                if part.len() >= PART_MAX_BYTES {
+                    self.upload_part(record, index, &prefix, &part, part_documents, storage)
+                        .await?;
+                    part.clear();
+                    part_documents = 0;
+                }
+            }
+            if part_documents > 0 {
+                self.upload_part(record, index, &prefix, &part, part_documents, storage)
+                    .await?;
+            }
+
+            record.collections[index].completed = true;
+            self.save_progress(record).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn upload_part(
+        &self,
+        record: &mut BackupRecord,
+        index: usize,
+        prefix: &str,
+        part: &[u8],
+        documents: u64,
+        storage: &dyn ArchiveStorage,
+    ) -> Result<()> {
+        let progress = &mut record.collections[index];
+        let key = part_key(prefix, &progress.name, progress.parts.len() + 1);
+        let size = upload_part_bytes(storage, &key, part).await?;
+
+        progress.parts.push(key);
+        progress.documents_written += documents;
+        progress.bytes_written += size;
+        record.documents_written += documents;
+        record.bytes_written += size;
+        record.progress_percent =
+            progress_percent(record.documents_written, record.total_documents);
+
+        self.save_progress(record).await
+    }
+
+    async fn save_progress(&self, record: &BackupRecord) -> Result<()> {
+        let collections = to_bson(&record.collections).context("Failed to encode progress")?;
+        let mut update = doc! {
+            "status": to_bson(&record.status).context("Failed to encode status")?,
+            "collections": collections,
+            "documents_written": record.documents_written as i64,
+            "bytes_written": record.bytes_written as i64,
+            "progress_percent": record.progress_percent,
+        };
+        if let Some(error) = &record.error {
+            update.insert("error", error);
+        }
+        if let Some(finished_at) = record.finished_at {
+            update.insert(
+                "finished_at",
+                BsonDateTime::from_millis(finished_at.timestamp_millis()),
+            );
+        }
+
+        self.mongo
+            .collection::<Document>(BACKUPS_COLLECTION)
+            .update_one(doc! { "_id": record.id }, doc! { "$set": update })
+            .await
+            .context("Failed to update backup progress")?;
+        Ok(())
+    }
+
+    /// Восстановить коллекции из бэкапа или только посчитать изменения (`dry_run`).
+    ///
+    /// Документы бэкапа записываются поверх текущих по `_id`. Перед записью затронутые
+    /// коллекции целиком сохраняются в `rollback/{restore_id}/`, а восстановление
+    /// фиксируется в журнале аудита вместе с этим ключом. Одновременно может идти только
+    /// одно восстановление (блокировка в Redis).
     pub async fn restore_backup(
         &self,
-        backup_id: &str,
-        _requested_by: &str,
-    ) -> Result<BackupRestoreResponse> {
-        let collection = self.mongo.collection::<BackupRecord>("backups");
-        let object_id =
-            ObjectId::parse_str(backup_id).context("Invalid backup id format for restore")?;
-
-        let record = collection
-            .find_one(doc! { "_id": object_id })
+        storage: &dyn ArchiveStorage,
+        redis: &ConnectionManager,
+        backup_id: ObjectId,
+        request: BackupRestoreRequest,
+        requested_by: &str,
+    ) -> Result<BackupRestoreOutcome> {
+        let Some(record) = self.get_backup(backup_id).await? else {
+            return Ok(BackupRestoreOutcome::NotFound);
+        };
+        if record.status != BackupStatus::Completed {
+            return Ok(BackupRestoreOutcome::NotRestorable(record.status));
+        }
+
+        let selected: Vec<&BackupCollectionProgress> = match &request.collections {
+            Some(names) => {
+                let unknown: Vec<String> = names
+                    .iter()
+                    .filter(|name| !record.collections.iter().any(|c| &c.name == *name))
+                    .cloned()
+                    .collect();
+                if names.is_empty() || !unknown.is_empty() {
+                    return Ok(BackupRestoreOutcome::UnknownCollections(unknown));
+                }
+                record
+                    .collections
+                    .iter()
+                    .filter(|c| names.contains(&c.name))
+                    .collect()
+            }
+            None => record.collections.iter().collect(),
+        };
+
+        if request.dry_run {
+            let mut plans = Vec::with_capacity(selected.len());
+            for progress in &selected {
+                plans.push(self.apply_collection(storage, progress, false).await?);
+            }
+            return Ok(BackupRestoreOutcome::Restored(restore_response(
+                &record, plans, true, None,
+            )));
+        }
+
+        let token = Uuid::new_v4().to_string();
+        if !acquire_restore_lock(redis, &token).await? {
+            return Ok(BackupRestoreOutcome::InProgress);
+        }
+        let result = self
+            .restore_locked(storage, &record, &selected, requested_by)
+            .await;
+        if let Err(err) = release_restore_lock(redis, &token).await {
+            warn!("Failed to release backup restore lock: {:#}", err);
+        }
+        result
+    }
+
+    async fn restore_locked(
+        &self,
+        storage: &dyn ArchiveStorage,
+        record: &BackupRecord,
+        selected: &[&BackupCollectionProgress],
+        requested_by: &str,
+    ) -> Result<BackupRestoreOutcome> {
+        let names: Vec<String> = selected.iter().map(|c| c.name.clone()).collect();
+        let rollback_key = format!("rollback/{}/", ObjectId::new().to_hex());
+        for name in &names {
+            self.snapshot_collection(storage, name, &rollback_key)
+                .await
+                .with_context(|| format!("Failed to snapshot {} before restore", name))?;
+        }
+
+        let mut plans = Vec::with_capacity(selected.len());
+        let mut failure = None;
+        for progress in selected {
+            match self.apply_collection(storage, progress, true).await {
+                Ok(plan) => plans.push(plan),
+                Err(err) => {
+                    failure = Some(err);
+                    break;
+                }
+            }
+        }
+
+        let backup_id = record.id.map(|id| id.to_hex()).unwrap_or_default();
+        if let Err(err) = AuditService::new(self.mongo.clone())
+            .log_backup_restore(
+                requested_by,
+                &backup_id,
+                &names,
+                &rollback_key,
+                failure.as_ref().map(|err| format!("{:#}", err)),
+            )
+            .await
+        {
+            warn!("Failed to write backup restore audit entry: {}", err);
+        }
+
+        if let Some(err) = failure {
+            return Err(err.context(format!(
+                "Restore of backup {} failed, rollback snapshot {}",
+                backup_id, rollback_key
+            )));
+        }
+
+        Ok(BackupRestoreOutcome::Restored(restore_response(
+            record,
+            plans,
+            false,
+            Some(rollback_key),
+        )))
+    }
+
+    /// Сравнить документы бэкапа с текущими и, если `write`, записать их поверх
+    async fn apply_collection(
+        &self,
+        storage: &dyn ArchiveStorage,
+        progress: &BackupCollectionProgress,
+        write: bool,
+    ) -> Result<BackupRestoreCollectionPlan> {
+        let collection = self.mongo.collection::<Document>(&progress.name);
+        let mut plan = BackupRestoreCollectionPlan {
+            name: progress.name.clone(),
+            ..Default::default()
+        };
+
+        for key in &progress.parts {
+            let documents = read_part(storage, key).await?;
+            for chunk in documents.chunks(RESTORE_LOOKUP_BATCH) {
+                let ids: Vec<Bson> = chunk
+                    .iter()
+                    .filter_map(|document| document.get("_id").cloned())
+                    .collect();
+                let current: HashMap<String, Document> = collection
+                    .find(doc! { "_id": { "$in": ids } })
+                    .await
+                    .with_context(|| format!("Failed to query {}", progress.name))?
+                    .try_collect::<Vec<Document>>()
+                    .await
+                    .with_context(|| format!("Failed to read {}", progress.name))?
+                    .into_iter()
+                    .filter_map(|document| {
+                        let id = document.get("_id").map(id_key)?;
+                        Some((id, document))
+                    })
+                    .collect();
+
+                for document in chunk {
+                    let id = document
+                        .get("_id")
+                        .cloned()
+                        .ok_or_else(|| anyhow!("Document without _id in {}", key))?;
+                    plan.documents_in_backup += 1;
+                    match current.get(&id_key(&id)) {
+                        None => plan.to_insert += 1,
+                        Some(existing) if existing == document => {
+                            plan.unchanged += 1;
+                            continue;
+                        }
+                        Some(_) => plan.to_overwrite += 1,
+                    }
+
+                    if write {
+                        collection
+                            .replace_one(doc! { "_id": id }, document)
+                            .upsert(true)
+                            .await
+                            .with_context(|| format!("Failed to restore into {}", progress.name))?;
+                    }
+                }
+            }
+        }
+
+        Ok(plan)
+    }
+
+    /// Текущее содержимое коллекции в `{prefix}{collection}/part-NNNNN.ndjson.gz`
+    async fn snapshot_collection(
+        &self,
+        storage: &dyn ArchiveStorage,
+        name: &str,
+        prefix: &str,
+    ) -> Result<()> {
+        let mut cursor = self
+            .mongo
+            .collection::<Document>(name)
+            .find(doc! {})
+            .sort(doc! { "_id": 1 })
             .await
-            .context("Failed to find backup record")?
-            .ok_or_else(|| anyhow::anyhow!("Backup not found"))?;
-
-        Ok(BackupRestoreResponse {
-            id: backup_id.to_string(),
-            status: BackupStatus::Completed,
-            storage_path: record.storage_path.clone(),
-            message: format!("Restore started for backup '{}'", record.label),
+            .with_context(|| format!("Failed to read collection {}", name))?;
+
+        let mut part = Vec::new();
+        let mut parts = 0;
+        while let Some(document) = cursor.try_next().await? {
+            append_document(&mut part, document)?;
+            if part.len() >= PART_MAX_BYTES {
+                parts += 1;
+                upload_part_bytes(storage, &part_key(prefix, name, parts), &part).await?;
+                part.clear();
+            }
+        }
+        if !part.is_empty() || parts == 0 {
+            parts += 1;
+            upload_part_bytes(storage, &part_key(prefix, name, parts), &part).await?;
+        }
+        Ok(())
+    }
+}
+
+fn restore_response(
+    record: &BackupRecord,
+    collections: Vec<BackupRestoreCollectionPlan>,
+    dry_run: bool,
+    rollback_key: Option<String>,
+) -> BackupRestoreResponse {
+    let inserted: u64 = collections.iter().map(|c| c.to_insert).sum();
+    let overwritten: u64 = collections.iter().map(|c| c.to_overwrite).sum();
+    let message = if dry_run {
+        format!(
+            "Dry run for backup '{}': restore would insert {} and overwrite {} documents",
+            record.label, inserted, overwritten
+        )
+    } else {
+        format!(
+            "Backup '{}' restored: inserted {}, overwrote {} documents",
+            record.label, inserted, overwritten
+        )
+    };
+
+    BackupRestoreResponse {
+        id: record.id.map(|id| id.to_hex()).unwrap_or_default(),
+        status: record.status,
+        storage_path: record.storage_path.clone(),
+        message,
+        dry_run,
+        collections,
+        rollback_key,
+    }
+}
+
+async fn acquire_restore_lock(redis: &ConnectionManager, token: &str) -> Result<bool> {
+    let mut conn = redis.clone();
+    let acquired: Option<String> = redis::cmd("SET")
+        .arg(RESTORE_LOCK_KEY)
+        .arg(token)
+        .arg("NX")
+        .arg("PX")
+        .arg(RESTORE_LOCK_TTL_MS)
+        .query_async(&mut conn)
+        .await
+        .context("Failed to acquire backup restore lock")?;
+    Ok(acquired.is_some())
+}
+
+async fn release_restore_lock(redis: &ConnectionManager, token: &str) -> Result<()> {
+    let mut conn = redis.clone();
+    let _: i32 = redis::Script::new(RELEASE_SCRIPT)
+        .key(RESTORE_LOCK_KEY)
+        .arg(token)
+        .invoke_async(&mut conn)
+        .await
+        .context("Failed to release backup restore lock")?;
+    Ok(())
+}
+
+/// Строка NDJSON в canonical Extended JSON: типы BSON (Int64, Date, ObjectId)
+/// сохраняются и восстанавливаются без потерь
+fn append_document(part: &mut Vec<u8>, document: Document) -> Result<()> {
+    let json = Bson::Document(document).into_canonical_extjson();
+    serde_json::to_writer(&mut *part, &json).context("Failed to serialize backup document")?;
+    part.push(b'\n');
+    Ok(())
+}
+
+async fn upload_part_bytes(storage: &dyn ArchiveStorage, key: &str, part: &[u8]) -> Result<u64> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(part)?;
+    let bytes = encoder.finish().context("Failed to compress backup part")?;
+    let size = bytes.len() as u64;
+    storage
+        .put_bundle(key, bytes)
+        .await
+        .with_context(|| format!("Failed to upload backup part {}", key))?;
+    Ok(size)
+}
+
+async fn read_part(storage: &dyn ArchiveStorage, key: &str) -> Result<Vec<Document>> {
+    let bytes = storage
+        .read_object(key)
+        .await
+        .with_context(|| format!("Failed to download backup part {}", key))?;
+    let mut ndjson = String::new();
+    GzDecoder::new(bytes.as_slice())
+        .read_to_string(&mut ndjson)
+        .with_context(|| format!("Failed to decompress backup part {}", key))?;
+
+    ndjson
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let json: serde_json::Value =
+                serde_json::from_str(line).context("Invalid JSON in backup part")?;
+            match Bson::try_from(json).context("Invalid Extended JSON in backup part")? {
+                Bson::Document(document) => Ok(document),
+                other => Err(anyhow!(
+                    "Expected a document in backup part, got {:?}",
+                    other
+                )),
+            }
         })
+        .collect()
+}
+
+/// Ключ для сравнения `_id` любого типа
+fn id_key(id: &Bson) -> String {
+    id.clone().into_canonical_extjson().to_string()
+}
+
+/// `backups/{id}/{collection}/part-00001.ndjson.gz`
+fn part_key(prefix: &str, collection: &str, part: usize) -> String {
+    format!("{}{}/part-{:05}.ndjson.gz", prefix, collection, part)
+}
+
+/// Документы, добавленные во время выгрузки, не дают прогрессу уйти за 100%
+fn progress_percent(written: u64, total: u64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    ((written as f64 / total as f64) * 100.0).min(100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn part_keys_are_ordered_per_collection() {
+        assert_eq!(
+            part_key("backups/abc/", "users", 1),
+            "backups/abc/users/part-00001.ndjson.gz"
+        );
+        assert_eq!(
+            part_key("backups/abc/", "users", 12),
+            "backups/abc/users/part-00012.ndjson.gz"
+        );
+    }
+
+    #[test]
+    fn progress_is_capped_at_hundred_percent() {
+        assert_eq!(progress_percent(0, 0), 0.0);
+        assert_eq!(progress_percent(1, 4), 25.0);
+        assert_eq!(progress_percent(5, 4), 100.0);
     }
 }
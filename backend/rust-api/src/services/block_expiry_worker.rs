@@ -0,0 +1,138 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, oid::ObjectId, DateTime as BsonDateTime, Document},
+    Database,
+};
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+use crate::{
+    config::AccountSettings, metrics::BLOCK_EXPIRY_WORKER_TICKS_TOTAL, models::user::User,
+    services::audit_service::AuditService,
+};
+
+/// Фильтр пользователей, чья временная блокировка истекла к `now`
+fn expired_block_filter(now: DateTime<Utc>) -> Document {
+    doc! {
+        "is_blocked": true,
+        "blockedUntil": { "$lte": BsonDateTime::from_millis(now.timestamp_millis()) },
+    }
+}
+
+/// Снять блокировку пользователя, если её срок истёк к `now`, и записать это в аудит.
+///
+/// Условие на `blockedUntil` проверяется в самом обновлении, поэтому блокировку,
+/// которую админ успел продлить, задача не снимет. Возвращает `true`, если снята.
+pub async fn release_expired_block(
+    mongo: &Database,
+    user_id: ObjectId,
+    now: DateTime<Utc>,
+) -> Result<bool> {
+    let users = mongo.collection::<User>("users");
+    let mut filter = expired_block_filter(now);
+    filter.insert("_id", user_id);
+
+    let released = users
+        .find_one_and_update(
+            filter,
+            doc! {
+                "$set": {
+                    "is_blocked": false,
+                    "updatedAt": BsonDateTime::from_millis(now.timestamp_millis()),
+                },
+                "$unset": {
+                    "blockedUntil": "",
+                    "blockReason": "",
+                }
+            },
+        )
+        .await
+        .context("Failed to release expired block")?;
+
+    let Some(user) = released else {
+        return Ok(false);
+    };
+
+    let audit_service = AuditService::new(mongo.clone());
+    if let Err(err) = audit_service
+        .log_user_auto_unblock(&user_id.to_hex(), user.blocked_until)
+        .await
+    {
+        warn!(error = %err, user = %user_id, "failed to audit auto unblock");
+    }
+    Ok(true)
+}
+
+/// Периодически снимает временные блокировки, у которых прошёл `blocked_until`.
+/// Вход пользователя снимает истёкшую блокировку и сам, не дожидаясь задачи.
+pub struct BlockExpiryWorker {
+    mongo: Database,
+    settings: AccountSettings,
+}
+
+impl BlockExpiryWorker {
+    pub fn new(mongo: Database, settings: AccountSettings) -> Self {
+        Self { mongo, settings }
+    }
+
+    pub async fn run(&self) -> Result<()> {
+        let interval = Duration::from_secs(self.settings.block_sweep_interval_secs);
+        info!(
+            "Starting block expiry worker (interval={}s)",
+            interval.as_secs()
+        );
+
+        loop {
+            match self.sweep().await {
+                Ok(released) => {
+                    BLOCK_EXPIRY_WORKER_TICKS_TOTAL
+                        .with_label_values(&["success"])
+                        .inc();
+                    if released > 0 {
+                        info!(released, "Expired user blocks released");
+                    }
+                }
+                Err(err) => {
+                    BLOCK_EXPIRY_WORKER_TICKS_TOTAL
+                        .with_label_values(&["error"])
+                        .inc();
+                    warn!(error = %err, "block expiry sweep failed");
+                }
+            }
+
+            sleep(interval).await;
+        }
+    }
+
+    /// Один проход: снять все истёкшие блокировки; возвращает число разблокированных
+    pub async fn sweep(&self) -> Result<usize> {
+        let now = Utc::now();
+        let user_ids: Vec<ObjectId> = self
+            .mongo
+            .collection::<Document>("users")
+            .find(expired_block_filter(now))
+            .projection(doc! { "_id": 1 })
+            .await
+            .context("Failed to query expired blocks")?
+            .try_collect::<Vec<_>>()
+            .await
+            .context("Failed to read expired blocks")?
+            .into_iter()
+            .filter_map(|user| user.get_object_id("_id").ok())
+            .collect();
+
+        let mut released = 0;
+        for user_id in user_ids {
+            match release_expired_block(&self.mongo, user_id, now).await {
+                Ok(true) => released += 1,
+                Ok(false) => {}
+                Err(err) => warn!(error = %err, user = %user_id, "failed to release block"),
+            }
+        }
+        Ok(released)
+    }
+}
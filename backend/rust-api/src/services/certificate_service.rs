@@ -0,0 +1,476 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, oid::ObjectId, Document},
+    Database,
+};
+use printpdf::{Color, Greyscale, Mm, Op, PdfDocument, PdfPage, PdfSaveOptions, Point, Pt};
+use tracing::warn;
+
+use crate::models::certificate::Certificate;
+use crate::models::content::{LevelRecord, TopicRecord};
+use crate::models::reporting::ExportLocale;
+use crate::models::ProgressSummary;
+use crate::services::object_storage::ObjectStorageClient;
+use crate::services::pdf::{
+    accent_color, document_warnings, push_pdf_line, push_pdf_rect, push_pdf_text, PdfFonts,
+};
+
+/// Альбомный A4, мм
+const PAGE_WIDTH: f32 = 297.0;
+const PAGE_HEIGHT: f32 = 210.0;
+
+/// Уровень не пройден или не существует (ответ 403 `CERTIFICATE_NOT_EARNED`)
+#[derive(Debug, thiserror::Error)]
+#[error("Certificate for level {level_id} is not earned")]
+pub struct CertificateNotEarnedError {
+    pub level_id: String,
+}
+
+/// Хранилище готовых PDF: повторное скачивание не перерисовывает сертификат
+#[async_trait]
+pub trait CertificateStorage: Send + Sync {
+    /// None - по ключу ещё ничего не сохранено
+    async fn load(&self, key: &str) -> Result<Option<Vec<u8>>>;
+    async fn store(&self, key: &str, bytes: Vec<u8>) -> Result<()>;
+}
+
+#[async_trait]
+impl CertificateStorage for ObjectStorageClient {
+    async fn load(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        // GET отсутствующего объекта не отличить от сбоя, поэтому сначала листинг
+        if !self
+            .list_prefix(key)
+            .await?
+            .iter()
+            .any(|found| found == key)
+        {
+            return Ok(None);
+        }
+        self.get_object(key).await.map(Some)
+    }
+
+    async fn store(&self, key: &str, bytes: Vec<u8>) -> Result<()> {
+        self.upload_bytes(key, bytes, "application/pdf").await
+    }
+}
+
+/// Процент на сертификате: округление вниз, чтобы не завышать результат
+pub fn displayed_percent(percentage: f64) -> u32 {
+    percentage.clamp(0.0, 100.0).floor() as u32
+}
+
+/// Ключ PDF в хранилище. Процент входит в ключ: после пересдачи рисуется новый сертификат
+pub fn certificate_key(user_id: &str, level_id: &str, percentage: f64) -> String {
+    format!(
+        "certificates/{}/{}-{}.pdf",
+        user_id,
+        level_id,
+        displayed_percent(percentage)
+    )
+}
+
+/// Сертификаты по прогрессу ученика: уровень пройден, если процент не ниже
+/// `min_pass_percent`. Прогресс по удалённым уровням и строковым id пропускается
+pub fn earned_certificates(
+    progress: &[ProgressSummary],
+    levels: &HashMap<ObjectId, LevelRecord>,
+    topics: &HashMap<ObjectId, TopicRecord>,
+) -> Vec<Certificate> {
+    let mut certificates: Vec<Certificate> = progress
+        .iter()
+        .filter_map(|summary| {
+            let level_obj = ObjectId::parse_str(&summary.level_id).ok()?;
+            let level = levels.get(&level_obj)?;
+            if summary.percentage < f64::from(level.min_pass_percent) {
+                return None;
+            }
+            Some(Certificate {
+                level_id: summary.level_id.clone(),
+                level_name: level.name.clone(),
+                topic_id: level.topic_id.to_hex(),
+                topic_name: topics
+                    .get(&level.topic_id)
+                    .map(|topic| topic.name.clone())
+                    .unwrap_or_default(),
+                percentage: summary.percentage,
+                min_pass_percent: level.min_pass_percent,
+                earned_at: summary.updated_at,
+            })
+        })
+        .collect();
+    certificates.sort_by_key(|certificate| std::cmp::Reverse(certificate.earned_at));
+    certificates
+}
+
+pub struct CertificateService {
+    mongo: Database,
+}
+
+impl CertificateService {
+    pub fn new(mongo: Database) -> Self {
+        Self { mongo }
+    }
+
+    pub async fn list(&self, user_id: &str) -> Result<Vec<Certificate>> {
+        let progress: Vec<ProgressSummary> = self
+            .mongo
+            .collection::<ProgressSummary>("progress_summary_v2")
+            .find(doc! { "user_id": user_id })
+            .await
+            .context("Failed to query progress for certificates")?
+            .try_collect()
+            .await
+            .context("Failed to read progress for certificates")?;
+        self.certificates_for(&progress).await
+    }
+
+    /// Сертификат за один уровень или `CertificateNotEarnedError`
+    pub async fn certificate(&self, user_id: &str, level_id: &str) -> Result<Certificate> {
+        let progress: Vec<ProgressSummary> = self
+            .mongo
+            .collection::<ProgressSummary>("progress_summary_v2")
+            .find_one(doc! { "_id": format!("{}:{}", user_id, level_id) })
+            .await
+            .context("Failed to load level progress")?
+            .into_iter()
+            .collect();
+        self.certificates_for(&progress)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| {
+                CertificateNotEarnedError {
+                    level_id: level_id.to_string(),
+                }
+                .into()
+            })
+    }
+
+    /// PDF сертификата: из хранилища, а если его там нет - рисуется и сохраняется.
+    /// Сбой хранилища не мешает скачиванию, PDF просто рисуется заново
+    pub async fn pdf(
+        &self,
+        user_id: &str,
+        certificate: &Certificate,
+        storage: Option<&dyn CertificateStorage>,
+    ) -> Result<Vec<u8>> {
+        let key = certificate_key(user_id, &certificate.level_id, certificate.percentage);
+        if let Some(storage) = storage {
+            match storage.load(&key).await {
+                Ok(Some(bytes)) => return Ok(bytes),
+                Ok(None) => {}
+                Err(err) => warn!("Failed to load cached certificate {}: {:#}", key, err),
+            }
+        }
+
+        let student_name = self.student_name(user_id).await?;
+        let bytes = render_certificate(&student_name, certificate)?;
+        if let Some(storage) = storage {
+            if let Err(err) = storage.store(&key, bytes.clone()).await {
+                warn!("Failed to cache certificate {}: {:#}", key, err);
+            }
+        }
+        Ok(bytes)
+    }
+
+    async fn certificates_for(&self, progress: &[ProgressSummary]) -> Result<Vec<Certificate>> {
+        let level_ids: Vec<ObjectId> = progress
+            .iter()
+            .filter_map(|summary| ObjectId::parse_str(&summary.level_id).ok())
+            .collect();
+        if level_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let levels: HashMap<ObjectId, LevelRecord> = self
+            .mongo
+            .collection::<LevelRecord>("levels")
+            .find(doc! { "_id": { "$in": &level_ids } })
+            .await
+            .context("Failed to query certificate levels")?
+            .try_collect::<Vec<_>>()
+            .await
+            .context("Failed to read certificate levels")?
+            .into_iter()
+            .map(|level| (level.id, level))
+            .collect();
+        let topic_ids: Vec<ObjectId> = levels.values().map(|level| level.topic_id).collect();
+        let topics: HashMap<ObjectId, TopicRecord> = self
+            .mongo
+            .collection::<TopicRecord>("topics")
+            .find(doc! { "_id": { "$in": topic_ids } })
+            .await
+            .context("Failed to query certificate topics")?
+            .try_collect::<Vec<_>>()
+            .await
+            .context("Failed to read certificate topics")?
+            .into_iter()
+            .map(|topic| (topic.id, topic))
+            .collect();
+        Ok(earned_certificates(progress, &levels, &topics))
+    }
+
+    /// Имя из `users`; для пользователя без записи - его id
+    async fn student_name(&self, user_id: &str) -> Result<String> {
+        let Ok(user_obj) = ObjectId::parse_str(user_id) else {
+            return Ok(user_id.to_string());
+        };
+        let user = self
+            .mongo
+            .collection::<Document>("users")
+            .find_one(doc! { "_id": user_obj })
+            .await
+            .context("Failed to load certificate owner")?;
+        Ok(user
+            .as_ref()
+            .and_then(|user| user.get_str("name").ok())
+            .unwrap_or(user_id)
+            .to_string())
+    }
+}
+
+/// Одна страница: двойная рамка с уголками, имя ученика, уровень, тема, процент и дата
+pub fn render_certificate(student_name: &str, certificate: &Certificate) -> Result<Vec<u8>> {
+    let mut document = PdfDocument::new("Сертификат");
+    let fonts = PdfFonts::load(&mut document, ExportLocale::Ru)?;
+    let accent = accent_color();
+    let text_color = Color::Greyscale(Greyscale::new(0.08, None));
+    let muted_color = Color::Greyscale(Greyscale::new(0.4, None));
+    let mut ops = Vec::new();
+
+    ops.push(Op::SetOutlineColor {
+        col: accent.clone(),
+    });
+    ops.push(Op::SetOutlineThickness { pt: Pt(2.0) });
+    push_frame(&mut ops, 10.0);
+    ops.push(Op::SetOutlineThickness { pt: Pt(0.6) });
+    push_frame(&mut ops, 14.0);
+    for (left, bottom) in [
+        (10.0, 10.0),
+        (PAGE_WIDTH - 18.0, 10.0),
+        (10.0, PAGE_HEIGHT - 18.0),
+        (PAGE_WIDTH - 18.0, PAGE_HEIGHT - 18.0),
+    ] {
+        push_pdf_rect(&mut ops, left, bottom, 8.0, 8.0, &accent);
+    }
+
+    let left = 40.0;
+    let mut text = |y: f32, font: &_, size: f32, line: String, color: &Color| {
+        push_pdf_text(
+            &mut ops,
+            Point::new(Mm(left), Mm(y)),
+            font,
+            size,
+            size * 1.2,
+            line,
+            color,
+        );
+    };
+    text(168.0, &fonts.bold, 34.0, "СЕРТИФИКАТ".into(), &accent);
+    text(
+        156.0,
+        &fonts.regular,
+        14.0,
+        "о прохождении уровня".into(),
+        &muted_color,
+    );
+    text(
+        134.0,
+        &fonts.regular,
+        13.0,
+        "Настоящим подтверждается, что".into(),
+        &text_color,
+    );
+    text(
+        118.0,
+        &fonts.bold,
+        24.0,
+        student_name.to_string(),
+        &text_color,
+    );
+    text(
+        102.0,
+        &fonts.regular,
+        14.0,
+        format!("успешно прошёл(а) уровень «{}»", certificate.level_name),
+        &text_color,
+    );
+    text(
+        90.0,
+        &fonts.regular,
+        13.0,
+        format!("Тема: {}", certificate.topic_name),
+        &text_color,
+    );
+    text(
+        78.0,
+        &fonts.bold,
+        13.0,
+        format!(
+            "Результат: {}% (проходной балл {}%)",
+            displayed_percent(certificate.percentage),
+            certificate.min_pass_percent
+        ),
+        &text_color,
+    );
+    text(
+        44.0,
+        &fonts.regular,
+        12.0,
+        format!("Дата: {}", certificate.earned_at.format("%d.%m.%Y")),
+        &text_color,
+    );
+    text(
+        32.0,
+        &fonts.oblique,
+        10.0,
+        "TrainingGround".into(),
+        &muted_color,
+    );
+
+    ops.push(Op::SetOutlineColor { col: muted_color });
+    ops.push(Op::SetOutlineThickness { pt: Pt(0.5) });
+    push_pdf_line(&mut ops, (left, 56.0), (left + 110.0, 56.0));
+
+    let page = PdfPage::new(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), ops);
+    let mut warnings = Vec::new();
+    let bytes = document
+        .with_pages(vec![page])
+        .save(&PdfSaveOptions::default(), &mut warnings);
+    let warnings = document_warnings(warnings);
+    if !warnings.is_empty() {
+        warn!(level = %certificate.level_id, warnings = ?warnings, "Certificate rendered with warnings");
+    }
+    Ok(bytes)
+}
+
+/// Рамка с отступом `inset` от краёв страницы
+fn push_frame(ops: &mut Vec<Op>, inset: f32) {
+    let (left, bottom) = (inset, inset);
+    let (right, top) = (PAGE_WIDTH - inset, PAGE_HEIGHT - inset);
+    push_pdf_line(ops, (left, bottom), (right, bottom));
+    push_pdf_line(ops, (right, bottom), (right, top));
+    push_pdf_line(ops, (right, top), (left, top));
+    push_pdf_line(ops, (left, top), (left, bottom));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use mongodb::bson::DateTime as BsonDateTime;
+
+    use crate::models::content::{LevelDifficulty, LevelStatus, TopicStatus};
+
+    fn level(topic_id: ObjectId, name: &str, min_pass_percent: i32) -> LevelRecord {
+        LevelRecord {
+            id: ObjectId::new(),
+            topic_id,
+            order: 1,
+            name: name.into(),
+            difficulty: LevelDifficulty::A1,
+            description: String::new(),
+            min_pass_percent,
+            status: LevelStatus::Active,
+            prerequisite_level_ids: Vec::new(),
+            created_at: BsonDateTime::now(),
+            updated_at: BsonDateTime::now(),
+        }
+    }
+
+    fn topic(name: &str) -> TopicRecord {
+        TopicRecord {
+            id: ObjectId::new(),
+            slug: "topic".into(),
+            name: name.into(),
+            description: String::new(),
+            icon_url: None,
+            sort_order: 0,
+            status: TopicStatus::Active,
+            age_band: None,
+            created_at: BsonDateTime::now(),
+            updated_at: BsonDateTime::now(),
+        }
+    }
+
+    fn progress(level_id: &str, percentage: f64, day: u32) -> ProgressSummary {
+        ProgressSummary {
+            id: format!("u1:{}", level_id),
+            user_id: "u1".into(),
+            level_id: level_id.into(),
+            attempts_total: 10,
+            correct_count: 8,
+            credit: None,
+            percentage,
+            score: 80,
+            updated_at: Utc.with_ymd_and_hms(2026, 3, day, 12, 0, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn earned_certificates_need_min_pass_percent() {
+        let topic = topic("Орфография");
+        let passed = level(topic.id, "Безударные гласные", 80);
+        let exact = level(topic.id, "Приставки", 70);
+        let failed = level(topic.id, "Суффиксы", 80);
+        let orphan_topic = level(ObjectId::new(), "Без темы", 50);
+        let progress = vec![
+            progress(&passed.id.to_hex(), 92.5, 1),
+            progress(&exact.id.to_hex(), 70.0, 3),
+            progress(&failed.id.to_hex(), 79.9, 4),
+            progress(&orphan_topic.id.to_hex(), 60.0, 2),
+            // Удалённый уровень и старый строковый id
+            progress(&ObjectId::new().to_hex(), 100.0, 5),
+            progress("legacy-level", 100.0, 6),
+        ];
+        let topics = HashMap::from([(topic.id, topic)]);
+        let levels = HashMap::from([
+            (passed.id, passed),
+            (exact.id, exact),
+            (failed.id, failed),
+            (orphan_topic.id, orphan_topic),
+        ]);
+
+        let certificates = earned_certificates(&progress, &levels, &topics);
+        let names: Vec<&str> = certificates
+            .iter()
+            .map(|certificate| certificate.level_name.as_str())
+            .collect();
+        // Свежие первыми
+        assert_eq!(names, vec!["Приставки", "Без темы", "Безударные гласные"]);
+        assert_eq!(certificates[0].topic_name, "Орфография");
+        assert_eq!(certificates[0].min_pass_percent, 70);
+        assert_eq!(certificates[1].topic_name, "");
+        assert_eq!(certificates[2].percentage, 92.5);
+    }
+
+    #[test]
+    fn certificate_key_uses_displayed_percent() {
+        assert_eq!(displayed_percent(92.99), 92);
+        assert_eq!(displayed_percent(100.0), 100);
+        assert_eq!(displayed_percent(-1.0), 0);
+        assert_eq!(
+            certificate_key("u1", "level", 92.99),
+            "certificates/u1/level-92.pdf"
+        );
+    }
+
+    #[test]
+    fn render_certificate_produces_pdf() {
+        let topic = topic("Орфография");
+        let level = level(topic.id, "Безударные гласные", 80);
+        let progress = vec![progress(&level.id.to_hex(), 92.5, 1)];
+        let certificate = earned_certificates(
+            &progress,
+            &HashMap::from([(level.id, level)]),
+            &HashMap::from([(topic.id, topic)]),
+        )
+        .remove(0);
+
+        let bytes = render_certificate("Анна Иванова", &certificate).unwrap();
+        assert!(bytes.starts_with(b"%PDF"));
+    }
+}
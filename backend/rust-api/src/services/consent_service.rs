@@ -0,0 +1,442 @@
+use crate::models::consent::{
+    ConsentCoverageReport, ConsentGrantor, ConsentRecord, ConsentTypeCoverage,
+    CreateGuardianLinkRequest, GuardianConsentLink, GuardianConsentRequest, GuardianLinkResponse,
+    RecordConsentRequest,
+};
+use crate::models::group::Group;
+use crate::models::system_settings::ConsentSettings;
+use crate::models::user::{User, UserRole};
+use crate::services::system_settings_service::SystemSettingsService;
+use anyhow::{anyhow, Context, Result};
+use chrono::{Duration, Utc};
+use futures::TryStreamExt;
+use mongodb::bson::{doc, oid::ObjectId, DateTime as BsonDateTime};
+use mongodb::Database;
+use rand::{distr::Alphanumeric, Rng};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use thiserror::Error;
+
+pub const GUARDIAN_CONSENT_LINKS_COLLECTION: &str = "guardian_consent_links";
+const GUARDIAN_TOKEN_LEN: usize = 40;
+
+/// Почему ссылку родителя нельзя использовать
+#[derive(Debug, Error)]
+pub enum GuardianLinkError {
+    #[error("Guardian link not found")]
+    NotFound,
+    #[error("Guardian link has expired")]
+    Expired,
+    #[error("Guardian links are issued only for students")]
+    NotStudent,
+}
+
+pub struct ConsentService {
+    mongo: Database,
+}
+
+impl ConsentService {
+    pub fn new(mongo: Database) -> Self {
+        Self { mongo }
+    }
+
+    /// Записать согласие ученика
+    pub async fn record_consent(
+        &self,
+        user_id: &ObjectId,
+        req: RecordConsentRequest,
+        recorded_by: &str,
+    ) -> Result<ConsentRecord> {
+        self.load_user(user_id).await?;
+
+        let guardian_name = req
+            .guardian_name
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty());
+        if req.granted_by == ConsentGrantor::Guardian && guardian_name.is_none() {
+            return Err(anyhow!("Guardian name is required for guardian consent"));
+        }
+
+        let document_version = match req.document_version {
+            Some(version) if !version.trim().is_empty() => version.trim().to_string(),
+            _ => self.settings().await?.document_version,
+        };
+
+        let mut record = ConsentRecord {
+            id: None,
+            user_id: user_id.to_hex(),
+            consent_type: req.consent_type.trim().to_string(),
+            granted_by: req.granted_by,
+            guardian_name,
+            recorded_by: recorded_by.to_string(),
+            document_version,
+            granted_at: Utc::now(),
+            revoked_at: None,
+            revoked_by: None,
+        };
+
+        let result = self
+            .collection()
+            .insert_one(&record)
+            .await
+            .context("Failed to insert consent record")?;
+        record.id = result.inserted_id.as_object_id();
+
+        Ok(record)
+    }
+
+    /// Отозвать все действующие согласия указанного типа
+    pub async fn revoke_consent(
+        &self,
+        user_id: &ObjectId,
+        consent_type: &str,
+        revoked_by: &str,
+    ) -> Result<()> {
+        let result = self
+            .collection()
+            .update_many(
+                doc! {
+                    "user_id": user_id.to_hex(),
+                    "consent_type": consent_type,
+                    "revokedAt": null,
+                },
+                doc! {
+                    "$set": {
+                        "revokedAt": BsonDateTime::from_millis(Utc::now().timestamp_millis()),
+                        "revoked_by": revoked_by,
+                    }
+                },
+            )
+            .await
+            .context("Failed to revoke consent")?;
+
+        if result.matched_count == 0 {
+            return Err(anyhow!("Active consent not found"));
+        }
+
+        Ok(())
+    }
+
+    /// Создать ссылку, по которой родитель сам даёт и отзывает согласия ученика
+    pub async fn create_guardian_link(
+        &self,
+        user_id: &ObjectId,
+        req: CreateGuardianLinkRequest,
+        created_by: &str,
+    ) -> Result<GuardianLinkResponse> {
+        if self.load_user(user_id).await?.role != UserRole::Student {
+            return Err(GuardianLinkError::NotStudent.into());
+        }
+
+        let token: String = rand::rng()
+            .sample_iter(&Alphanumeric)
+            .take(GUARDIAN_TOKEN_LEN)
+            .map(char::from)
+            .collect();
+        let now = Utc::now();
+        let expires_in = req
+            .expires_in_hours
+            .unwrap_or(CreateGuardianLinkRequest::DEFAULT_EXPIRES_IN_HOURS);
+        let link = GuardianConsentLink {
+            id: None,
+            user_id: user_id.to_hex(),
+            token_hash: hash_guardian_token(&token),
+            created_by: created_by.to_string(),
+            expires_at: now + Duration::hours(i64::from(expires_in)),
+            created_at: now,
+        };
+
+        let inserted = self
+            .links()
+            .insert_one(&link)
+            .await
+            .context("Failed to insert guardian link")?;
+
+        Ok(GuardianLinkResponse {
+            id: inserted
+                .inserted_id
+                .as_object_id()
+                .map(|id| id.to_hex())
+                .unwrap_or_default(),
+            user_id: link.user_id,
+            token,
+            expires_at: link.expires_at,
+        })
+    }
+
+    /// Действующая ссылка родителя по токену
+    pub async fn resolve_guardian_link(&self, token: &str) -> Result<GuardianConsentLink> {
+        let link = self
+            .links()
+            .find_one(doc! { "tokenHash": hash_guardian_token(token) })
+            .await
+            .context("Failed to query guardian link")?
+            .ok_or(GuardianLinkError::NotFound)?;
+        if link.expires_at <= Utc::now() {
+            return Err(GuardianLinkError::Expired.into());
+        }
+        Ok(link)
+    }
+
+    /// Согласие, данное родителем по ссылке
+    pub async fn record_guardian_consent(
+        &self,
+        link: &GuardianConsentLink,
+        req: GuardianConsentRequest,
+    ) -> Result<ConsentRecord> {
+        let user_id = ObjectId::parse_str(&link.user_id).context("Invalid user ID format")?;
+        self.record_consent(
+            &user_id,
+            RecordConsentRequest {
+                consent_type: req.consent_type,
+                granted_by: ConsentGrantor::Guardian,
+                guardian_name: Some(req.guardian_name),
+                document_version: None,
+            },
+            &link.actor(),
+        )
+        .await
+    }
+
+    /// История согласий ученика (включая отозванные)
+    pub async fn list_consents(&self, user_id: &ObjectId) -> Result<Vec<ConsentRecord>> {
+        self.collection()
+            .find(doc! { "user_id": user_id.to_hex() })
+            .sort(doc! { "grantedAt": -1 })
+            .await
+            .context("Failed to query consent records")?
+            .try_collect()
+            .await
+            .context("Failed to collect consent records")
+    }
+
+    /// Недостающие согласия пользователя. Для не-учеников и неизвестных ID всегда пусто.
+    pub async fn missing_for_student(&self, user_id: &str) -> Result<Vec<String>> {
+        let settings = self.settings().await?;
+        if settings.required_consents.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let Ok(object_id) = ObjectId::parse_str(user_id) else {
+            return Ok(Vec::new());
+        };
+        let user = self
+            .mongo
+            .collection::<User>("users")
+            .find_one(doc! { "_id": object_id })
+            .await
+            .context("Failed to load user")?;
+        if !matches!(user, Some(ref user) if user.role == UserRole::Student) {
+            return Ok(Vec::new());
+        }
+
+        let records = self.active_records(&[user_id.to_string()]).await?;
+        Ok(missing_consents(&settings, &records))
+    }
+
+    /// Покрытие согласиями учеников группы
+    pub async fn coverage_report(&self, group_id: &ObjectId) -> Result<ConsentCoverageReport> {
+        let group_exists = self
+            .mongo
+            .collection::<Group>("groups")
+            .find_one(doc! { "_id": group_id })
+            .await
+            .context("Failed to load group")?
+            .is_some();
+        if !group_exists {
+            return Err(anyhow!("Group not found"));
+        }
+
+        let settings = self.settings().await?;
+        let group_id_str = group_id.to_hex();
+        let students: Vec<User> = self
+            .mongo
+            .collection::<User>("users")
+            .find(doc! { "group_ids": &group_id_str, "role": UserRole::Student.as_str() })
+            .await
+            .context("Failed to query group students")?
+            .try_collect()
+            .await
+            .context("Failed to collect group students")?;
+
+        let student_ids: Vec<String> = students
+            .iter()
+            .filter_map(|student| student.id.map(|id| id.to_hex()))
+            .collect();
+        let records = self.active_records(&student_ids).await?;
+
+        let mut by_student: HashMap<&str, Vec<ConsentRecord>> = HashMap::new();
+        for record in &records {
+            by_student
+                .entry(record.user_id.as_str())
+                .or_default()
+                .push(record.clone());
+        }
+
+        let mut granted_counts: HashMap<&str, usize> = HashMap::new();
+        let mut missing_students = Vec::new();
+        for student_id in &student_ids {
+            let student_records = by_student
+                .get(student_id.as_str())
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            let missing = missing_consents(&settings, student_records);
+            for consent_type in &settings.required_consents {
+                if !missing.contains(consent_type) {
+                    *granted_counts.entry(consent_type.as_str()).or_default() += 1;
+                }
+            }
+            if !missing.is_empty() {
+                missing_students.push(student_id.clone());
+            }
+        }
+
+        let total_students = student_ids.len();
+        let fully_covered = total_students - missing_students.len();
+        let coverage_percent = if total_students == 0 {
+            100.0
+        } else {
+            (fully_covered as f64 / total_students as f64 * 1000.0).round() / 10.0
+        };
+        let by_consent = settings
+            .required_consents
+            .iter()
+            .map(|consent_type| ConsentTypeCoverage {
+                consent_type: consent_type.clone(),
+                granted: granted_counts
+                    .get(consent_type.as_str())
+                    .copied()
+                    .unwrap_or(0),
+            })
+            .collect();
+
+        Ok(ConsentCoverageReport {
+            group_id: group_id_str,
+            document_version: settings.document_version,
+            required_consents: settings.required_consents,
+            total_students,
+            fully_covered,
+            coverage_percent,
+            by_consent,
+            missing_students,
+        })
+    }
+
+    pub async fn settings(&self) -> Result<ConsentSettings> {
+        SystemSettingsService::new(self.mongo.clone())
+            .get_consent_settings()
+            .await
+    }
+
+    async fn load_user(&self, user_id: &ObjectId) -> Result<User> {
+        self.mongo
+            .collection::<User>("users")
+            .find_one(doc! { "_id": user_id })
+            .await
+            .context("Failed to load user")?
+            .ok_or_else(|| anyhow!("User not found"))
+    }
+
+    async fn active_records(&self, user_ids: &[String]) -> Result<Vec<ConsentRecord>> {
+        if user_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        self.collection()
+            .find(doc! { "user_id": { "$in": user_ids }, "revokedAt": null })
+            .await
+            .context("Failed to query consent records")?
+            .try_collect()
+            .await
+            .context("Failed to collect consent records")
+    }
+
+    fn collection(&self) -> mongodb::Collection<ConsentRecord> {
+        self.mongo.collection::<ConsentRecord>("consent_records")
+    }
+
+    fn links(&self) -> mongodb::Collection<GuardianConsentLink> {
+        self.mongo
+            .collection::<GuardianConsentLink>(GUARDIAN_CONSENT_LINKS_COLLECTION)
+    }
+}
+
+fn hash_guardian_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Required consent types not covered by the given records.
+///
+/// Revoked records never count. When re-consent is required, only records for the
+/// current document version count.
+pub fn missing_consents(settings: &ConsentSettings, records: &[ConsentRecord]) -> Vec<String> {
+    settings
+        .required_consents
+        .iter()
+        .filter(|consent_type| {
+            !records.iter().any(|record| {
+                &record.consent_type == *consent_type
+                    && record.revoked_at.is_none()
+                    && (!settings.require_reconsent_on_version_bump
+                        || record.document_version == settings.document_version)
+            })
+        })
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(version: &str, reconsent: bool) -> ConsentSettings {
+        ConsentSettings {
+            required_consents: vec!["data_processing".into(), "photo".into()],
+            document_version: version.into(),
+            require_reconsent_on_version_bump: reconsent,
+        }
+    }
+
+    fn record(consent_type: &str, version: &str) -> ConsentRecord {
+        ConsentRecord {
+            id: None,
+            user_id: ObjectId::new().to_hex(),
+            consent_type: consent_type.into(),
+            granted_by: ConsentGrantor::Guardian,
+            guardian_name: Some("Parent".into()),
+            recorded_by: "admin".into(),
+            document_version: version.into(),
+            granted_at: Utc::now(),
+            revoked_at: None,
+            revoked_by: None,
+        }
+    }
+
+    #[test]
+    fn reports_each_missing_consent() {
+        let records = vec![record("data_processing", "1")];
+        assert_eq!(
+            missing_consents(&settings("1", false), &records),
+            vec!["photo".to_string()]
+        );
+        assert!(missing_consents(&ConsentSettings::default(), &[]).is_empty());
+    }
+
+    #[test]
+    fn revoked_consent_does_not_count() {
+        let mut revoked = record("photo", "1");
+        revoked.revoked_at = Some(Utc::now());
+        let records = vec![record("data_processing", "1"), revoked];
+        assert_eq!(
+            missing_consents(&settings("1", false), &records),
+            vec!["photo".to_string()]
+        );
+    }
+
+    #[test]
+    fn version_bump_requires_reconsent_only_when_enabled() {
+        let records = vec![record("data_processing", "1"), record("photo", "1")];
+        assert!(missing_consents(&settings("2", false), &records).is_empty());
+        assert_eq!(missing_consents(&settings("2", true), &records).len(), 2);
+    }
+}
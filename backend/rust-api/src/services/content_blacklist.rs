@@ -0,0 +1,292 @@
+//! Стоп-лист содержимого шаблонов: записи в MongoDB, скомпилированный набор в памяти
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use futures::TryStreamExt;
+use mongodb::bson::{doc, oid::ObjectId, DateTime as BsonDateTime};
+use mongodb::Database;
+use regex::{Regex, RegexBuilder};
+use tokio::sync::RwLock;
+
+use crate::models::content::{BlacklistEntry, BlacklistKind, BlacklistSeverity};
+
+pub const CONTENT_BLACKLIST_COLLECTION: &str = "content_blacklist";
+/// Термины, которые раньше были зашиты в код; создаются при первом запуске
+const DEFAULT_TERMS: [&str; 3] = ["xxx", "запрещенное", "наркотик"];
+/// Отметка в `system_settings`, что стоп-лист уже заполнен: удалённые админом
+/// термины не возвращаются после перезапуска
+const SEED_MARKER_KEY: &str = "content_blacklist_seeded";
+const BLACKLIST_CACHE_TTL: Duration = Duration::from_secs(30);
+const MAX_TERM_LEN: usize = 200;
+/// Тот же предел, что у шаблонов ПДн: `regex` работает за линейное время,
+/// но повторения вида `a{1000}{1000}` раздувают автомат
+const REGEX_SIZE_LIMIT: usize = 256 * 1024;
+
+/// Запись стоп-листа не сохраняется (ответ 400)
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid blacklist entry: {0}")]
+pub struct InvalidBlacklistEntryError(pub String);
+
+/// Совпадение записи стоп-листа в поле шаблона
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlacklistHit {
+    pub path: String,
+    pub term: String,
+    pub severity: BlacklistSeverity,
+}
+
+impl BlacklistHit {
+    /// Запись для `blacklist_warnings` и текста ошибки: `content:xxx`
+    pub fn flag(&self) -> String {
+        format!("{}:{}", self.path, self.term)
+    }
+}
+
+enum Matcher {
+    Term(String),
+    Regex(Regex),
+}
+
+struct CompiledEntry {
+    term: String,
+    severity: BlacklistSeverity,
+    matcher: Matcher,
+}
+
+pub struct CompiledBlacklist {
+    entries: Vec<CompiledEntry>,
+}
+
+impl Default for CompiledBlacklist {
+    /// Встроенные термины: действуют, пока MongoDB недоступна
+    fn default() -> Self {
+        let entries = DEFAULT_TERMS
+            .iter()
+            .map(|term| CompiledEntry {
+                term: term.to_string(),
+                severity: BlacklistSeverity::Block,
+                matcher: Matcher::Term(term.to_string()),
+            })
+            .collect();
+        Self { entries }
+    }
+}
+
+impl CompiledBlacklist {
+    pub fn compile(entries: &[BlacklistEntry]) -> Self {
+        let entries = entries
+            .iter()
+            .filter_map(|entry| match compile_matcher(entry.kind, &entry.term) {
+                Ok(matcher) => Some(CompiledEntry {
+                    term: entry.term.clone(),
+                    severity: entry.severity,
+                    matcher,
+                }),
+                Err(err) => {
+                    tracing::warn!("Skipping invalid blacklist entry '{}': {}", entry.term, err);
+                    None
+                }
+            })
+            .collect();
+        Self { entries }
+    }
+
+    pub fn detect(&self, path: &str, text: &str) -> Vec<BlacklistHit> {
+        let lower = text.to_lowercase();
+        self.entries
+            .iter()
+            .filter(|entry| match &entry.matcher {
+                Matcher::Term(term) => lower.contains(term.as_str()),
+                Matcher::Regex(regex) => regex.is_match(text),
+            })
+            .map(|entry| BlacklistHit {
+                path: path.to_string(),
+                term: entry.term.clone(),
+                severity: entry.severity,
+            })
+            .collect()
+    }
+}
+
+/// Проверка записи перед сохранением
+pub fn validate_entry(kind: BlacklistKind, term: &str) -> Result<(), InvalidBlacklistEntryError> {
+    compile_matcher(kind, term)
+        .map(|_| ())
+        .map_err(InvalidBlacklistEntryError)
+}
+
+/// Термин сравнивается в нижнем регистре; выражение оборачивается в границы слова
+fn compile_matcher(kind: BlacklistKind, term: &str) -> Result<Matcher, String> {
+    let term = term.trim();
+    if term.is_empty() {
+        return Err("term must not be empty".to_string());
+    }
+    if term.chars().count() > MAX_TERM_LEN {
+        return Err(format!("term must be at most {} characters", MAX_TERM_LEN));
+    }
+    match kind {
+        BlacklistKind::Term => Ok(Matcher::Term(term.to_lowercase())),
+        BlacklistKind::Regex => {
+            let build = |pattern: &str| {
+                RegexBuilder::new(pattern)
+                    .case_insensitive(true)
+                    .size_limit(REGEX_SIZE_LIMIT)
+                    .dfa_size_limit(REGEX_SIZE_LIMIT)
+                    .build()
+                    .map_err(|err| err.to_string())
+            };
+            if build(term)?.is_match("") {
+                return Err("regex must not match an empty string".to_string());
+            }
+            Ok(Matcher::Regex(build(&format!(r"\b(?:{})\b", term))?))
+        }
+    }
+}
+
+/// Заполнить стоп-лист встроенными терминами, если это ещё не сделано
+pub async fn seed_defaults(mongo: &Database) -> Result<()> {
+    let marker = mongo
+        .collection::<mongodb::bson::Document>("system_settings")
+        .update_one(
+            doc! { "key": SEED_MARKER_KEY },
+            doc! { "$setOnInsert": {
+                "key": SEED_MARKER_KEY,
+                "category": "content",
+                "value": {},
+                "updatedAt": BsonDateTime::now(),
+            } },
+        )
+        .upsert(true)
+        .await
+        .context("Failed to mark content blacklist seed")?;
+    if marker.upserted_id.is_none() {
+        return Ok(());
+    }
+
+    let collection = mongo.collection::<BlacklistEntry>(CONTENT_BLACKLIST_COLLECTION);
+    let now = BsonDateTime::now();
+    for term in DEFAULT_TERMS {
+        let exists = collection
+            .count_documents(doc! { "term": term, "kind": "term" })
+            .await
+            .context("Failed to check content blacklist")?
+            > 0;
+        if exists {
+            continue;
+        }
+        collection
+            .insert_one(BlacklistEntry {
+                id: ObjectId::new(),
+                term: term.to_string(),
+                kind: BlacklistKind::Term,
+                severity: BlacklistSeverity::Block,
+                created_by: None,
+                created_at: now,
+                updated_at: now,
+            })
+            .await
+            .context("Failed to seed content blacklist")?;
+    }
+    tracing::info!(
+        "Content blacklist seeded with {} terms",
+        DEFAULT_TERMS.len()
+    );
+    Ok(())
+}
+
+/// Скомпилированный стоп-лист. Экземпляр живёт в `AppState`, изменения через
+/// `/admin/content/blacklist` сбрасывают его сразу, другие инстансы - по TTL
+#[derive(Default)]
+pub struct BlacklistCache {
+    compiled: RwLock<Option<(Instant, Arc<CompiledBlacklist>)>>,
+}
+
+impl BlacklistCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn get(&self, mongo: &Database) -> Arc<CompiledBlacklist> {
+        if let Some((loaded_at, compiled)) = self.compiled.read().await.as_ref() {
+            if loaded_at.elapsed() < BLACKLIST_CACHE_TTL {
+                return compiled.clone();
+            }
+        }
+
+        let entries: Result<Vec<BlacklistEntry>> = async {
+            mongo
+                .collection::<BlacklistEntry>(CONTENT_BLACKLIST_COLLECTION)
+                .find(doc! {})
+                .await?
+                .try_collect()
+                .await
+                .map_err(Into::into)
+        }
+        .await;
+        let compiled = match entries {
+            Ok(entries) => Arc::new(CompiledBlacklist::compile(&entries)),
+            Err(err) => {
+                tracing::warn!("Blacklist lookup failed, using built-in terms: {:#}", err);
+                return Arc::new(CompiledBlacklist::default());
+            }
+        };
+        *self.compiled.write().await = Some((Instant::now(), compiled.clone()));
+        compiled
+    }
+
+    pub async fn invalidate(&self) {
+        *self.compiled.write().await = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(term: &str, kind: BlacklistKind, severity: BlacklistSeverity) -> BlacklistEntry {
+        let now = BsonDateTime::now();
+        BlacklistEntry {
+            id: ObjectId::new(),
+            term: term.to_string(),
+            kind,
+            severity,
+            created_by: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn terms_match_substrings_and_regexes_whole_words() {
+        let blacklist = CompiledBlacklist::compile(&[
+            entry("Наркотик", BlacklistKind::Term, BlacklistSeverity::Block),
+            entry("драк[аи]", BlacklistKind::Regex, BlacklistSeverity::Warn),
+        ]);
+
+        let hits = blacklist.detect("content", "НАРКОТИКИ и Драка");
+        assert_eq!(
+            hits.iter().map(BlacklistHit::flag).collect::<Vec<_>>(),
+            vec!["content:Наркотик", "content:драк[аи]"]
+        );
+        assert_eq!(hits[1].severity, BlacklistSeverity::Warn);
+        // Выражение не срабатывает внутри другого слова
+        assert!(blacklist.detect("content", "драках").is_empty());
+    }
+
+    #[test]
+    fn invalid_entries_are_rejected() {
+        assert!(compile_matcher(BlacklistKind::Term, "  ").is_err());
+        assert!(compile_matcher(BlacklistKind::Regex, "[unclosed").is_err());
+        assert!(compile_matcher(BlacklistKind::Regex, r"\w*").is_err());
+        assert!(compile_matcher(BlacklistKind::Regex, r"(\w{100}){100}").is_err());
+        assert!(compile_matcher(BlacklistKind::Regex, "плох(ой|ая)").is_ok());
+    }
+
+    #[test]
+    fn default_blacklist_keeps_legacy_terms() {
+        let hits = CompiledBlacklist::default().detect("content", "Запрещенное слово");
+        assert_eq!(hits[0].flag(), "content:запрещенное");
+    }
+}
@@ -0,0 +1,187 @@
+//! Кэш справочников контента в Redis: темы, уровни и метаданные шаблонов по `_id`.
+//!
+//! Чтение сквозное: отсутствующие в Redis записи догружаются из MongoDB и кладутся
+//! в кэш с TTL. `ContentService` сбрасывает записи при любом изменении темы, уровня
+//! или шаблона, TTL лишь страхует от пропущенной инвалидации. Сбой Redis не ломает
+//! чтение - запрос просто уходит в MongoDB.
+
+use std::collections::HashMap;
+use std::future::Future;
+
+use anyhow::{Context, Result};
+use mongodb::bson::{self, oid::ObjectId};
+use redis::aio::ConnectionManager;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::metrics::record_content_cache_lookups;
+
+/// Сколько запись живёт в кэше без инвалидации
+pub const CONTENT_CACHE_TTL_SECS: u64 = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentCacheKind {
+    Topic,
+    Level,
+    Template,
+}
+
+impl ContentCacheKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentCacheKind::Topic => "topic",
+            ContentCacheKind::Level => "level",
+            ContentCacheKind::Template => "template",
+        }
+    }
+}
+
+pub fn content_cache_key(kind: ContentCacheKind, id: &ObjectId) -> String {
+    format!("content:cache:{}:{}", kind.as_str(), id.to_hex())
+}
+
+/// Метаданные шаблона, которые нужны при создании сессии
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TemplateMeta {
+    pub level_id: Option<ObjectId>,
+}
+
+#[derive(Clone)]
+pub struct ContentCache {
+    redis: ConnectionManager,
+    ttl_secs: u64,
+}
+
+impl ContentCache {
+    pub fn new(redis: ConnectionManager) -> Self {
+        Self {
+            redis,
+            ttl_secs: CONTENT_CACHE_TTL_SECS,
+        }
+    }
+
+    /// Записи по id: из Redis, а промахи - через `load` (ему передаются только
+    /// отсутствующие в кэше id). Несуществующие записи в ответ не попадают и не кэшируются
+    pub async fn get_many<T, F, Fut>(
+        &self,
+        kind: ContentCacheKind,
+        ids: &[ObjectId],
+        load: F,
+    ) -> Result<HashMap<ObjectId, T>>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce(Vec<ObjectId>) -> Fut,
+        Fut: Future<Output = Result<Vec<(ObjectId, T)>>>,
+    {
+        let mut ids = ids.to_vec();
+        ids.sort();
+        ids.dedup();
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let mut found = HashMap::with_capacity(ids.len());
+        let mut missing = Vec::new();
+        match self.read(kind, &ids).await {
+            Ok(cached) => {
+                for (id, raw) in ids.into_iter().zip(cached) {
+                    match raw.map(|raw| bson::from_slice::<T>(&raw)) {
+                        Some(Ok(value)) => {
+                            found.insert(id, value);
+                        }
+                        Some(Err(err)) => {
+                            tracing::warn!(
+                                "Dropping malformed {} cache entry {}: {}",
+                                kind.as_str(),
+                                id,
+                                err
+                            );
+                            missing.push(id);
+                        }
+                        None => missing.push(id),
+                    }
+                }
+            }
+            Err(err) => {
+                tracing::warn!("Content cache read failed: {:#}", err);
+                missing = ids;
+            }
+        }
+        record_content_cache_lookups(kind.as_str(), found.len(), missing.len());
+        if missing.is_empty() {
+            return Ok(found);
+        }
+
+        let loaded = load(missing).await?;
+        if let Err(err) = self.write(kind, &loaded).await {
+            tracing::warn!("Content cache write failed: {:#}", err);
+        }
+        found.extend(loaded);
+        Ok(found)
+    }
+
+    /// Одна запись; см. `get_many`
+    pub async fn get<T, F, Fut>(
+        &self,
+        kind: ContentCacheKind,
+        id: &ObjectId,
+        load: F,
+    ) -> Result<Option<T>>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce(Vec<ObjectId>) -> Fut,
+        Fut: Future<Output = Result<Vec<(ObjectId, T)>>>,
+    {
+        Ok(self
+            .get_many(kind, std::slice::from_ref(id), load)
+            .await?
+            .remove(id))
+    }
+
+    /// Сбросить записи после изменения
+    pub async fn invalidate(&self, kind: ContentCacheKind, ids: &[ObjectId]) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let keys: Vec<String> = ids.iter().map(|id| content_cache_key(kind, id)).collect();
+        let mut conn = self.redis.clone();
+        redis::cmd("DEL")
+            .arg(keys)
+            .query_async::<()>(&mut conn)
+            .await
+            .context("Failed to invalidate content cache")
+    }
+
+    async fn read(&self, kind: ContentCacheKind, ids: &[ObjectId]) -> Result<Vec<Option<Vec<u8>>>> {
+        let keys: Vec<String> = ids.iter().map(|id| content_cache_key(kind, id)).collect();
+        let mut conn = self.redis.clone();
+        redis::cmd("MGET")
+            .arg(keys)
+            .query_async(&mut conn)
+            .await
+            .context("Failed to read content cache")
+    }
+
+    async fn write<T: Serialize>(
+        &self,
+        kind: ContentCacheKind,
+        entries: &[(ObjectId, T)],
+    ) -> Result<()> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let mut pipe = redis::pipe();
+        for (id, value) in entries {
+            let raw = bson::to_vec(value).context("Failed to encode content cache entry")?;
+            pipe.cmd("SET")
+                .arg(content_cache_key(kind, id))
+                .arg(raw)
+                .arg("EX")
+                .arg(self.ttl_secs)
+                .ignore();
+        }
+        let mut conn = self.redis.clone();
+        pipe.query_async::<()>(&mut conn)
+            .await
+            .context("Failed to write content cache")
+    }
+}
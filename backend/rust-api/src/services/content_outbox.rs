@@ -0,0 +1,222 @@
+//! Outbox изменений шаблонов (`content_outbox`).
+//!
+//! `ContentService` записывает изменение шаблона и запись outbox в одной транзакции
+//! MongoDB, а `ContentOutboxDispatcher` в фоне публикует событие в стрим изменений
+//! контента и пишет аудит. Так падение процесса между записью и публикацией не
+//! оставляет опубликованный шаблон без события: запись outbox дождётся диспетчера.
+//!
+//! Диспетчер закрепляет запись за собой на `outbox_lease_secs`; если он упал, запись
+//! забирает следующий. Обе публикации идемпотентны - аудит пишется с `_id` записи
+//! outbox, а XADD выполняется скриптом вместе с меткой отправки, - поэтому повторная
+//! обработка не дублирует ни событие, ни аудит.
+
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use mongodb::{
+    bson::{doc, oid::ObjectId, DateTime as BsonDateTime, Document},
+    options::ReturnDocument,
+    Collection, Database,
+};
+use redis::aio::ConnectionManager;
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+use crate::{
+    config::ContentSettings, metrics::CONTENT_OUTBOX_DISPATCHED_TOTAL,
+    services::audit_service::AUDIT_LOG_COLLECTION, utils::time::chrono_to_bson,
+};
+
+pub const CONTENT_OUTBOX_COLLECTION: &str = "content_outbox";
+
+/// Сколько хранятся отправленные записи outbox и метки отправки событий
+pub const CONTENT_OUTBOX_RETENTION_SECS: u64 = 7 * 24 * 3600;
+
+/// Публикует событие, если метки отправки ещё нет, и возвращает id записи в стриме.
+/// KEYS: стрим, метка. ARGV: template_id, action, timestamp, TTL метки
+const PUBLISH_ONCE_SCRIPT: &str = r#"
+    local sent = redis.call('GET', KEYS[2])
+    if sent then
+        return sent
+    end
+    local id = redis.call('XADD', KEYS[1], '*',
+        'template_id', ARGV[1], 'action', ARGV[2], 'timestamp', ARGV[3])
+    redis.call('SET', KEYS[2], id, 'EX', tonumber(ARGV[4]))
+    return id
+"#;
+
+pub fn outbox_sent_key(entry_id: &ObjectId) -> String {
+    format!("content:outbox:sent:{}", entry_id.to_hex())
+}
+
+/// Запись outbox для вставки в транзакции изменения шаблона: событие стрима
+/// (`None` - только аудит) и готовая запись `audit_log`
+pub fn outbox_entry(template_id: &ObjectId, event: Option<&str>, audit: Document) -> Document {
+    doc! {
+        "_id": ObjectId::new(),
+        "template_id": template_id,
+        "event": event,
+        "audit": audit,
+        "status": "pending",
+        "attempts": 0,
+        "createdAt": BsonDateTime::now(),
+    }
+}
+
+pub struct ContentOutboxDispatcher {
+    mongo: Database,
+    redis: ConnectionManager,
+    stream_name: String,
+    interval: Duration,
+    lease: Duration,
+}
+
+impl ContentOutboxDispatcher {
+    pub fn new(mongo: Database, redis: ConnectionManager, settings: &ContentSettings) -> Self {
+        Self {
+            mongo,
+            redis,
+            stream_name: settings.stream_name.clone(),
+            interval: Duration::from_millis(settings.outbox_interval_ms),
+            lease: Duration::from_secs(settings.outbox_lease_secs),
+        }
+    }
+
+    pub async fn run(&self) -> Result<()> {
+        info!(
+            "Starting content outbox dispatcher (interval={}ms)",
+            self.interval.as_millis()
+        );
+        loop {
+            if let Err(err) = self.run_once().await {
+                warn!(error = %err, "content outbox dispatch failed");
+            }
+            sleep(self.interval).await;
+        }
+    }
+
+    /// Отправить все доступные записи; возвращает число отправленных
+    pub async fn run_once(&self) -> Result<usize> {
+        let mut dispatched = 0;
+        while let Some(entry) = self.claim_next().await? {
+            match self.publish(&entry).await {
+                Ok(stream_id) => {
+                    self.complete(&entry, stream_id).await?;
+                    CONTENT_OUTBOX_DISPATCHED_TOTAL
+                        .with_label_values(&["success"])
+                        .inc();
+                    dispatched += 1;
+                }
+                Err(err) => {
+                    CONTENT_OUTBOX_DISPATCHED_TOTAL
+                        .with_label_values(&["error"])
+                        .inc();
+                    self.release(&entry, &err).await?;
+                    return Err(err);
+                }
+            }
+        }
+        Ok(dispatched)
+    }
+
+    /// Закрепить за собой самую старую неотправленную запись: ожидающую или ту,
+    /// чей диспетчер не уложился в срок (упал)
+    pub async fn claim_next(&self) -> Result<Option<Document>> {
+        let now = Utc::now();
+        let lease_until = now + chrono::Duration::from_std(self.lease).unwrap_or_default();
+        self.outbox()
+            .find_one_and_update(
+                doc! { "$or": [
+                    { "status": "pending" },
+                    {
+                        "status": "dispatching",
+                        "leaseUntil": { "$lte": chrono_to_bson(now) },
+                    },
+                ] },
+                doc! {
+                    "$set": {
+                        "status": "dispatching",
+                        "leaseUntil": chrono_to_bson(lease_until),
+                    },
+                    "$inc": { "attempts": 1 },
+                },
+            )
+            .sort(doc! { "createdAt": 1 })
+            .return_document(ReturnDocument::After)
+            .await
+            .context("Failed to claim content outbox entry")
+    }
+
+    /// Записать аудит и опубликовать событие. Повторный вызов для той же записи
+    /// ничего не дублирует; возвращает id события в стриме
+    pub async fn publish(&self, entry: &Document) -> Result<Option<String>> {
+        let entry_id = entry.get_object_id("_id")?;
+        if let Ok(audit) = entry.get_document("audit") {
+            self.mongo
+                .collection::<Document>(AUDIT_LOG_COLLECTION)
+                .update_one(
+                    doc! { "_id": entry_id },
+                    doc! { "$setOnInsert": audit.clone() },
+                )
+                .upsert(true)
+                .await
+                .context("Failed to write content audit record")?;
+        }
+
+        let Ok(action) = entry.get_str("event") else {
+            return Ok(None);
+        };
+        let template_id = entry.get_object_id("template_id")?;
+        let mut conn = self.redis.clone();
+        let stream_id: String = redis::Script::new(PUBLISH_ONCE_SCRIPT)
+            .key(&self.stream_name)
+            .key(outbox_sent_key(&entry_id))
+            .arg(template_id.to_hex())
+            .arg(action)
+            .arg(Utc::now().timestamp_millis().to_string())
+            .arg(CONTENT_OUTBOX_RETENTION_SECS)
+            .invoke_async(&mut conn)
+            .await
+            .context("Failed to publish template change event")?;
+        Ok(Some(stream_id))
+    }
+
+    /// Закрыть запись после публикации
+    pub async fn complete(&self, entry: &Document, stream_id: Option<String>) -> Result<()> {
+        self.outbox()
+            .update_one(
+                doc! { "_id": entry.get_object_id("_id")? },
+                doc! {
+                    "$set": {
+                        "status": "done",
+                        "streamId": stream_id,
+                        "dispatchedAt": BsonDateTime::now(),
+                    },
+                    "$unset": { "leaseUntil": "", "lastError": "" },
+                },
+            )
+            .await
+            .context("Failed to complete content outbox entry")?;
+        Ok(())
+    }
+
+    /// Вернуть запись в очередь после ошибки публикации
+    async fn release(&self, entry: &Document, err: &anyhow::Error) -> Result<()> {
+        self.outbox()
+            .update_one(
+                doc! { "_id": entry.get_object_id("_id")?, "status": "dispatching" },
+                doc! {
+                    "$set": { "status": "pending", "lastError": format!("{err:#}") },
+                    "$unset": { "leaseUntil": "" },
+                },
+            )
+            .await
+            .context("Failed to release content outbox entry")?;
+        Ok(())
+    }
+
+    fn outbox(&self) -> Collection<Document> {
+        self.mongo.collection(CONTENT_OUTBOX_COLLECTION)
+    }
+}
@@ -0,0 +1,331 @@
+use anyhow::{Context, Result};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, Bson, Document},
+    options::IndexOptions,
+    Database, IndexModel,
+};
+
+use crate::models::content_search::{
+    ContentEntityType, ContentSearchHit, ContentSearchResponse, DEFAULT_CONTENT_SEARCH_LIMIT,
+    MAX_CONTENT_SEARCH_LIMIT,
+};
+
+/// Имя text-индекса; в коллекции MongoDB допускается только один text-индекс
+pub const CONTENT_SEARCH_INDEX: &str = "content_search_text";
+/// Сколько символов контекста показывать вокруг совпадения
+const SNIPPET_RADIUS: usize = 60;
+
+/// Коллекция, участвующая в поиске: поля text-индекса с весами и поля для фрагмента
+struct SearchTarget {
+    collection: &'static str,
+    entity_type: ContentEntityType,
+    weights: &'static [(&'static str, i32)],
+    title_fields: &'static [&'static str],
+}
+
+const TARGETS: [SearchTarget; 3] = [
+    SearchTarget {
+        collection: "templates",
+        entity_type: ContentEntityType::Template,
+        weights: &[
+            ("content", 5),
+            ("metadata.title", 10),
+            ("metadata.description", 3),
+            ("slug", 3),
+        ],
+        title_fields: &["metadata.title", "slug"],
+    },
+    SearchTarget {
+        collection: "rules",
+        entity_type: ContentEntityType::Rule,
+        weights: &[("name", 10), ("description", 5), ("examples", 2)],
+        title_fields: &["name", "slug"],
+    },
+    SearchTarget {
+        collection: "topics",
+        entity_type: ContentEntityType::Topic,
+        weights: &[("name", 10), ("description", 5)],
+        title_fields: &["name", "slug"],
+    },
+];
+
+/// Text-индексы для поиска по контенту (создаются при старте, см. `index_registry`)
+pub fn text_indexes() -> Vec<(&'static str, IndexModel)> {
+    TARGETS
+        .iter()
+        .map(|target| {
+            let mut keys = Document::new();
+            let mut weights = Document::new();
+            for (field, weight) in target.weights {
+                keys.insert(*field, "text");
+                weights.insert(*field, *weight);
+            }
+            let index = IndexModel::builder()
+                .keys(keys)
+                .options(
+                    IndexOptions::builder()
+                        .name(CONTENT_SEARCH_INDEX.to_string())
+                        .weights(weights)
+                        .default_language("russian".to_string())
+                        .build(),
+                )
+                .build();
+            (target.collection, index)
+        })
+        .collect()
+}
+
+pub struct ContentSearchService {
+    mongo: Database,
+}
+
+impl ContentSearchService {
+    pub fn new(mongo: Database) -> Self {
+        Self { mongo }
+    }
+
+    pub async fn search(&self, q: &str, limit: Option<u32>) -> Result<ContentSearchResponse> {
+        let limit = limit
+            .unwrap_or(DEFAULT_CONTENT_SEARCH_LIMIT)
+            .clamp(1, MAX_CONTENT_SEARCH_LIMIT);
+        let terms = search_terms(q);
+
+        let [templates, rules, topics] = &TARGETS;
+        let (templates, rules, topics) = tokio::try_join!(
+            self.search_collection(templates, q, &terms, limit),
+            self.search_collection(rules, q, &terms, limit),
+            self.search_collection(topics, q, &terms, limit),
+        )?;
+
+        Ok(ContentSearchResponse {
+            query: q.to_string(),
+            templates,
+            rules,
+            topics,
+        })
+    }
+
+    async fn search_collection(
+        &self,
+        target: &SearchTarget,
+        q: &str,
+        terms: &[String],
+        limit: u32,
+    ) -> Result<Vec<ContentSearchHit>> {
+        let cursor = self
+            .mongo
+            .collection::<Document>(target.collection)
+            .find(doc! { "$text": { "$search": q } })
+            .projection(doc! { "score": { "$meta": "textScore" } })
+            .sort(doc! { "score": { "$meta": "textScore" } })
+            .limit(limit as i64)
+            .await
+            .with_context(|| format!("Failed to search {}", target.collection))?;
+        let documents: Vec<Document> = cursor
+            .try_collect()
+            .await
+            .with_context(|| format!("Failed to read {} search results", target.collection))?;
+
+        Ok(documents
+            .iter()
+            .map(|document| to_hit(target, document, terms))
+            .collect())
+    }
+}
+
+fn to_hit(target: &SearchTarget, document: &Document, terms: &[String]) -> ContentSearchHit {
+    let fields: Vec<(&str, String)> = target
+        .weights
+        .iter()
+        .filter_map(|(field, _)| field_text(document, field).map(|text| (*field, text)))
+        .collect();
+
+    let (field, snippet) = fields
+        .iter()
+        .find_map(|(field, text)| {
+            build_snippet(text, terms, SNIPPET_RADIUS).map(|snippet| (*field, snippet))
+        })
+        // Совпадение по словоформе (стемминг) - показываем начало первого поля без подсветки
+        .or_else(|| {
+            fields
+                .first()
+                .map(|(field, text)| (*field, leading_excerpt(text, SNIPPET_RADIUS * 2)))
+        })
+        .unwrap_or(("", String::new()));
+
+    let title = target
+        .title_fields
+        .iter()
+        .find_map(|field| field_text(document, field))
+        .unwrap_or_default();
+
+    ContentSearchHit {
+        entity_type: target.entity_type,
+        id: document
+            .get_object_id("_id")
+            .map(|id| id.to_hex())
+            .unwrap_or_default(),
+        title,
+        field: field.to_string(),
+        snippet,
+        score: document.get_f64("score").unwrap_or_default(),
+    }
+}
+
+/// Значение поля по пути через точку; массив строк склеивается построчно
+fn field_text(document: &Document, path: &str) -> Option<String> {
+    let mut current = document;
+    let mut parts = path.split('.').peekable();
+    while let Some(part) = parts.next() {
+        let value = current.get(part)?;
+        if parts.peek().is_none() {
+            return match value {
+                Bson::String(text) if !text.is_empty() => Some(text.clone()),
+                Bson::Array(items) => {
+                    let joined = items
+                        .iter()
+                        .filter_map(|item| item.as_str())
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    (!joined.is_empty()).then_some(joined)
+                }
+                _ => None,
+            };
+        }
+        current = value.as_document()?;
+    }
+    None
+}
+
+/// Слова запроса без кавычек и исключений (`-слово`), в нижнем регистре
+pub fn search_terms(q: &str) -> Vec<String> {
+    q.split_whitespace()
+        .filter(|word| !word.starts_with('-'))
+        .map(|word| word.trim_matches('"').to_lowercase())
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// Фрагмент вокруг самого раннего совпадения с любым из слов (без учёта регистра).
+/// Текст экранируется как HTML, совпадение оборачивается в `<mark>`.
+/// `None`, если ни одно слово не встречается буквально
+pub fn build_snippet(text: &str, terms: &[String], radius: usize) -> Option<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let lowered: Vec<char> = chars.iter().map(|c| lower(*c)).collect();
+
+    let (start, len) = terms
+        .iter()
+        .filter_map(|term| {
+            let needle: Vec<char> = term.chars().map(lower).collect();
+            if needle.is_empty() || needle.len() > lowered.len() {
+                return None;
+            }
+            lowered
+                .windows(needle.len())
+                .position(|window| window == needle.as_slice())
+                .map(|position| (position, needle.len()))
+        })
+        .min()?;
+
+    let from = start.saturating_sub(radius);
+    let to = (start + len + radius).min(chars.len());
+    let collect = |range: std::ops::Range<usize>| chars[range].iter().collect::<String>();
+
+    let mut snippet = String::new();
+    if from > 0 {
+        snippet.push('…');
+    }
+    snippet.push_str(&escape_html(&collect(from..start)));
+    snippet.push_str("<mark>");
+    snippet.push_str(&escape_html(&collect(start..start + len)));
+    snippet.push_str("</mark>");
+    snippet.push_str(&escape_html(&collect(start + len..to)));
+    if to < chars.len() {
+        snippet.push('…');
+    }
+    Some(snippet)
+}
+
+fn leading_excerpt(text: &str, max_chars: usize) -> String {
+    let mut excerpt = escape_html(&text.chars().take(max_chars).collect::<String>());
+    if text.chars().count() > max_chars {
+        excerpt.push('…');
+    }
+    excerpt
+}
+
+fn lower(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_terms_skip_negations_and_quotes() {
+        assert_eq!(
+            search_terms("  \"Безударные\" гласные -исключения "),
+            vec!["безударные".to_string(), "гласные".to_string()]
+        );
+    }
+
+    #[test]
+    fn snippet_highlights_case_insensitive_match() {
+        let terms = search_terms("ГЛАСНЫЕ");
+        let snippet = build_snippet("Проверка гласные <b>корня</b>", &terms, 100).unwrap();
+        assert_eq!(
+            snippet,
+            "Проверка <mark>гласные</mark> &lt;b&gt;корня&lt;/b&gt;"
+        );
+    }
+
+    #[test]
+    fn snippet_trims_around_earliest_match() {
+        let text = format!("{}needle{}", "a".repeat(100), "b".repeat(100));
+        let snippet = build_snippet(&text, &["needle".to_string()], 5).unwrap();
+        assert_eq!(snippet, "…aaaaa<mark>needle</mark>bbbbb…");
+
+        let snippet = build_snippet("x second first", &search_terms("first second"), 1).unwrap();
+        assert!(snippet.contains("<mark>second</mark>"));
+    }
+
+    #[test]
+    fn snippet_is_none_without_literal_match() {
+        assert!(build_snippet("правило", &search_terms("правила"), 10).is_none());
+        assert!(build_snippet("", &search_terms("x"), 10).is_none());
+    }
+
+    #[test]
+    fn field_text_reads_nested_paths_and_arrays() {
+        let document = doc! {
+            "metadata": { "title": "Заголовок" },
+            "examples": ["один", 2, "три"],
+        };
+        assert_eq!(
+            field_text(&document, "metadata.title").as_deref(),
+            Some("Заголовок")
+        );
+        assert_eq!(
+            field_text(&document, "examples").as_deref(),
+            Some("один\nтри")
+        );
+        assert!(field_text(&document, "metadata.missing").is_none());
+    }
+}
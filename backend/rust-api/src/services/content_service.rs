@@ -1,47 +1,204 @@
 use crate::{
+    metrics::MODERATION_QUEUE_DEPTH,
     middlewares::auth::JwtClaims,
+    models::answer::TaskAnswers,
     models::content::{
-        ContentChangeEvent, EmbeddingConsistencyReport, EmbeddingJobSummary,
-        EmbeddingRebuildRequest, FeatureFlagRecord, FeatureFlagUpdateRequest, LevelCreateRequest,
-        LevelRecord, LevelReorderRequest, LevelStatus, LevelUpdateRequest, QueueStatus,
-        RuleCoverage, RuleCreateRequest, RuleRecord, RuleStatus, RuleUpdateRequest,
+        is_valid_locale, BlacklistEntry, BlacklistEntryRequest, BlacklistSeverity, BundleLevel,
+        BundleRule, BundleTemplate, BundleTopic, ContentChangeEvent, ContentTreeQuery,
+        ContentTreeTopic, ContentTreeTopicRow, DeadLetteredEvent, EmbeddingConsistencyReport,
+        EmbeddingJobCancelOutcome, EmbeddingJobListQuery, EmbeddingJobListResponse,
+        EmbeddingJobRetryOutcome, EmbeddingJobSummary, EmbeddingRebuildRequest, FeatureFlagRecord,
+        FeatureFlagUpdateRequest, LevelCreateRequest, LevelRecord, LevelReorderRequest,
+        LevelStatus, LevelUpdateRequest, LintFinding, LintSeverity, ModerationQueueAge,
+        ModerationQueueItem, QueueClaimResult, QueueConsumerGroup, QueueDeadLetter,
+        QueueDeadLetterStream, QueueStatus, ReviewerAssignOutcome, RuleCoverage, RuleCreateRequest,
+        RuleMergeOutcome, RuleMergeResult, RuleRecord, RuleStatus, RuleUpdateRequest,
+        TemplateArchiveOutcome, TemplateBulkStatusError, TemplateBulkStatusResult, TemplateBundle,
         TemplateCreateRequest, TemplateDetail, TemplateDocument, TemplateDuplicate,
-        TemplateListQuery, TemplateRevertRequest, TemplateStatus, TemplateSummary,
-        TemplateUpdateRequest, TemplateValidationIssue, TemplateVersionSummary, TopicCreateRequest,
-        TopicRecord, TopicStatus, TopicUpdateRequest,
+        TemplateFieldChange, TemplateImportItem, TemplateImportOptions, TemplateImportOutcome,
+        TemplateImportReport, TemplateLintReport, TemplateListQuery, TemplatePublishOutcome,
+        TemplateReference, TemplateRevertRequest, TemplateStatus, TemplateSummary,
+        TemplateTranslationRequest, TemplateUpdateRequest, TemplateValidationIssue,
+        TemplateVersionDiff, TemplateVersionSummary, TopicCreateRequest, TopicRecord, TopicStatus,
+        TopicUpdateRequest, DEFAULT_LOCALE, PUBLISHED_SNAPSHOT_FIELDS, TEMPLATE_BUNDLE_FORMAT,
+        TEMPLATE_SNAPSHOT_FIELDS,
     },
-    services::AppState,
+    models::system_settings::PiiSeverity,
+    services::{
+        assignment_service::ASSIGNMENTS_COLLECTION,
+        content_blacklist::{
+            validate_entry, BlacklistCache, BlacklistHit, InvalidBlacklistEntryError,
+            CONTENT_BLACKLIST_COLLECTION,
+        },
+        content_cache::{ContentCache, ContentCacheKind},
+        content_outbox::{outbox_entry, CONTENT_OUTBOX_COLLECTION},
+        pii_scanner::{PiiMatch, PiiPatternCache},
+        template_lint::{has_errors, LintTarget, TemplateLinter},
+        AppState,
+    },
+    utils::{answer_pattern::validate_answer_pattern, diff::unified_diff, mongo_retry::retry_read},
 };
 use anyhow::{anyhow, Context, Result};
 use chrono::{TimeZone, Utc};
 use futures::TryStreamExt;
-use lazy_static::lazy_static;
 use mongodb::{
     bson::{doc, oid::ObjectId, to_bson, Bson, Document},
-    options::FindOptions,
+    error::TRANSIENT_TRANSACTION_ERROR,
+    options::{FindOptions, ReturnDocument},
     Collection, Database,
 };
 use redis::aio::ConnectionManager;
 use regex::Regex;
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{hash_map::Entry, BTreeMap, HashMap, HashSet};
 use std::convert::TryInto;
+use std::hash::Hash;
 use std::str::FromStr;
+use std::sync::Arc;
 use std::time::SystemTime;
+use uuid::Uuid;
 
 const MAX_LIST_LIMIT: i64 = 100;
-const BLACKLISTED_TERMS: &[&str] = &["xxx", "запрещенное", "наркотик"];
+/// Сколько зависших событий на группу показывать в статусе очереди
+const DEAD_LETTER_LIMIT: i64 = 100;
+/// Размер пачки курсора для покрытия правил: весь ответ приходит без getMore
+const RULE_COVERAGE_BATCH_SIZE: u32 = 10_000;
+/// Сколько раз повторять транзакцию изменения шаблона при конфликте записи
+const TEMPLATE_TRANSACTION_ATTEMPTS: u32 = 3;
+/// Снимки опубликованного содержимого шаблонов, по записи на опубликованную версию
+pub const PUBLISHED_VERSIONS_COLLECTION: &str = "published_versions";
+
+/// Запись в `templates` внутри транзакции изменения шаблона
+enum TemplateWrite {
+    Insert(Document),
+    Update {
+        filter: Document,
+        update: Document,
+    },
+    /// Шаблон не меняется, в outbox попадает только аудит
+    None,
+}
+
+/// Изменение шаблона для `ContentService::commit_template_change`
+struct TemplateChange {
+    template_id: ObjectId,
+    write: TemplateWrite,
+    /// Номер новой версии и описание изменений для `template_versions`
+    version: Option<(i32, Document)>,
+    /// Действие для стрима изменений контента
+    event: Option<&'static str>,
+    /// Снимок опубликованного содержимого для `published_versions`
+    publish: Option<PublishRecord>,
+    audit: Document,
+}
+
+/// Запись `published_versions`: опубликованная версия шаблона и, при откате,
+/// версия, содержимое которой вернулось
+struct PublishRecord {
+    version: i32,
+    restored_from: Option<i32>,
+}
+
+/// Ошибки проверки пререквизитов уровня (отдаются клиенту как 400)
+#[derive(Debug, thiserror::Error)]
+pub enum LevelPrerequisiteError {
+    #[error("Invalid prerequisite level id: {0}")]
+    InvalidId(String),
+    #[error("Level cannot be its own prerequisite")]
+    SelfReference,
+    #[error("Prerequisite levels not found: {}", .0.join(", "))]
+    NotFound(Vec<String>),
+    #[error("Prerequisite levels belong to another topic: {}", .0.join(", "))]
+    OtherTopic(Vec<String>),
+    #[error("Prerequisites form a cycle: {}", .0.join(" -> "))]
+    Cycle(Vec<String>),
+}
+
+/// Больше уровней вложенности в `params`/`metadata` шаблона не принимается:
+/// конвертация JSON в BSON рекурсивна и на такой глубине может переполнить стек
+pub const MAX_JSON_DEPTH: usize = 64;
+
+/// Слишком глубокий JSON в шаблоне (отдаётся клиенту как 400)
+#[derive(Debug, thiserror::Error)]
+#[error("{field} is nested deeper than {MAX_JSON_DEPTH} levels")]
+pub struct JsonTooDeepError {
+    pub field: &'static str,
+}
+
+/// `params.answers` шаблона не разбирается или содержит недопустимый regex (ответ 400)
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid params.answers: {reason}")]
+pub struct InvalidAnswersError {
+    pub reason: String,
+}
+
+/// Код языка шаблона или перевода не разбирается (ответ 400)
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid locale: {0}")]
+pub struct InvalidLocaleError(pub String);
+
+/// Поля шаблона с персональными данными по блокирующим шаблонам (ответ 400)
+#[derive(Debug, thiserror::Error)]
+#[error("PII detected: {}", .0.join(", "))]
+pub struct PiiDetectedError(pub Vec<String>);
+
+/// Поля шаблона с терминами стоп-листа уровня `block` (ответ 400)
+#[derive(Debug, thiserror::Error)]
+#[error("Blacklist violation: {}", .0.join(", "))]
+pub struct BlacklistViolationError(pub Vec<String>);
+
+/// Находки линтера уровня `error`: шаблон не отправляется на модерацию (ответ 400)
+#[derive(Debug, thiserror::Error)]
+#[error("Template has lint errors: {}", .0.join("; "))]
+pub struct TemplateLintError(pub Vec<String>);
+
+impl TemplateLintError {
+    fn from_findings(findings: &[LintFinding]) -> Self {
+        Self(
+            findings
+                .iter()
+                .filter(|finding| finding.severity == LintSeverity::Error)
+                .map(|finding| format!("{}: {}", finding.code, finding.message))
+                .collect(),
+        )
+    }
+}
+
+/// Модератор уже одобрил шаблон: два одобрения дают разные модераторы (ответ 409)
+#[derive(Debug, thiserror::Error)]
+#[error("Reviewer {0} has already approved this template")]
+pub struct DuplicateApprovalError(pub String);
+
+/// Некритичные находки проверки полей шаблона; записываются в документ шаблона
+struct FieldWarnings {
+    pii_flags: Vec<String>,
+    blacklist_warnings: Vec<String>,
+}
 
-lazy_static! {
-    static ref EMAIL_REGEX: Regex =
-        Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}").unwrap();
-    static ref PHONE_REGEX: Regex = Regex::new(r"\b\d{10,}\b").unwrap();
+/// Шаблоны из запроса экспорта, которых нет в базе (ответ 404)
+#[derive(Debug, thiserror::Error)]
+#[error("Templates not found: {}", .0.join(", "))]
+pub struct TemplatesNotFoundError(pub Vec<String>);
+
+/// Уже известные при импорте пакета темы, уровни и правила
+#[derive(Default)]
+struct BundleRefs {
+    topics: HashMap<String, ObjectId>,
+    /// Уровни по теме: название → ID, загружаются при первом обращении
+    levels: HashMap<ObjectId, HashMap<String, ObjectId>>,
+    rules: HashMap<String, ObjectId>,
+    report: TemplateImportReport,
 }
 
 pub struct ContentService {
     mongo: Database,
     redis: ConnectionManager,
+    cache: ContentCache,
     stream_name: String,
+    dead_letter_idle_secs: u64,
+    dead_letter_stream: String,
+    pii_patterns: Arc<PiiPatternCache>,
+    blacklist: Arc<BlacklistCache>,
 }
 
 impl ContentService {
@@ -49,7 +206,12 @@ impl ContentService {
         Self {
             mongo: state.mongo.clone(),
             redis: state.redis.clone(),
+            cache: ContentCache::new(state.redis.clone()),
             stream_name: state.config.content.stream_name.clone(),
+            dead_letter_idle_secs: state.config.content.dead_letter_idle_secs,
+            dead_letter_stream: state.config.content.dead_letter_stream.clone(),
+            pii_patterns: state.pii_patterns.clone(),
+            blacklist: state.content_blacklist.clone(),
         }
     }
 
@@ -60,6 +222,10 @@ impl ContentService {
 
         let mut filter = Document::new();
 
+        if !query.include_archived {
+            filter.insert("archived", doc! { "$ne": true });
+        }
+
         if let Some(status) = query.status {
             let parsed = TemplateStatus::from_str(&status)
                 .map_err(|_| anyhow!("Invalid status filter: {}", status))?;
@@ -74,6 +240,10 @@ impl ContentService {
             filter.insert("version", version);
         }
 
+        if let Some(locale) = query.locale {
+            filter.insert("locale", locale_filter(&locale));
+        }
+
         if let Some(q) = query.q {
             let regex = Regex::new(&format!("(?i){}", regex::escape(&q))).unwrap_or_else(|_| {
                 Regex::new(".*") // fallback, should never fail
@@ -153,10 +323,11 @@ impl ContentService {
         }
 
         let collection: Collection<TemplateDocument> = self.mongo.collection("templates");
-        let doc = collection
-            .find_one(doc! { "_id": template_id })
-            .await
-            .context("Failed to fetch template")?;
+        let doc = retry_read("templates.find_by_id", || {
+            collection.find_one(doc! { "_id": template_id })
+        })
+        .await
+        .context("Failed to fetch template")?;
 
         if let Some(template) = doc {
             let levels = self
@@ -187,6 +358,27 @@ impl ContentService {
             payload.level_id
         );
 
+        // До обращений к базе: слишком глубокий JSON отклоняется сразу
+        tracing::info!("Converting params to document");
+        let params = match json_to_document(Some(payload.params), "params") {
+            Ok(p) => p,
+            Err(e) => {
+                tracing::error!("Failed to convert params: {:?}", e);
+                return Err(e);
+            }
+        };
+        tracing::info!("Params converted successfully");
+
+        tracing::info!("Converting metadata to document");
+        let metadata = match json_to_document(Some(payload.metadata), "metadata") {
+            Ok(m) => m,
+            Err(e) => {
+                tracing::error!("Failed to convert metadata: {:?}", e);
+                return Err(e);
+            }
+        };
+        tracing::info!("Metadata converted successfully");
+
         let level_obj = match ObjectId::parse_str(&payload.level_id) {
             Ok(id) => id,
             Err(e) => {
@@ -214,40 +406,47 @@ impl ContentService {
         self.validate_slug(&payload.slug)?;
         tracing::info!("Slug validated");
 
-        self.ensure_unique_slug(&payload.slug, &level_obj, None)
+        let locale = payload.locale.unwrap_or_else(|| DEFAULT_LOCALE.to_string());
+        if !is_valid_locale(&locale) {
+            return Err(InvalidLocaleError(locale).into());
+        }
+
+        self.ensure_unique_slug(&payload.slug, &level_obj, &locale, None)
             .await?;
         tracing::info!("Slug is unique");
 
-        self.validate_content(&payload.content)?;
-        tracing::info!("Content validated");
-
-        tracing::info!("Converting params to document");
-        let params = match json_to_document(Some(payload.params)) {
-            Ok(p) => p,
-            Err(e) => {
-                tracing::error!("Failed to convert params: {:?}", e);
-                return Err(e);
-            }
-        };
-        tracing::info!("Params converted successfully");
-
-        tracing::info!("Converting metadata to document");
-        let metadata = match json_to_document(Some(payload.metadata)) {
-            Ok(m) => m,
-            Err(e) => {
-                tracing::error!("Failed to convert metadata: {:?}", e);
-                return Err(e);
-            }
-        };
-        tracing::info!("Metadata converted successfully");
+        validate_template_answers(&params)?;
+        let translations = self.translations_document(payload.translations, &locale)?;
+        let warnings = self
+            .check_fields(&doc! {
+                "content": &payload.content,
+                "params": &params,
+                "metadata": &metadata,
+                "translations": &translations,
+            })
+            .await?;
+        tracing::info!(
+            "Content validated, PII flags: {:?}, blacklist warnings: {:?}",
+            warnings.pii_flags,
+            warnings.blacklist_warnings
+        );
+        // Черновик сохраняется с любыми находками, ошибки остановят отправку на модерацию
+        let lint_findings = self
+            .lint_fields(
+                &payload.content,
+                &params,
+                payload.difficulty.as_deref(),
+                &level_obj,
+                &rule_ids,
+            )
+            .await?;
 
         let now = now_bson_datetime();
-        tracing::info!("Scanning PII flags");
-        let pii_flags = self.scan_pii(&payload.content);
-        tracing::info!("PII scan complete, flags: {:?}", pii_flags);
 
         tracing::info!("Building template document");
+        let id = ObjectId::new();
         let template_doc = doc! {
+            "_id": id,
             "slug": payload.slug,
             "level_id": level_obj,
             "rule_ids": rule_ids,
@@ -255,44 +454,44 @@ impl ContentService {
             "metadata": metadata,
             "content": payload.content,
             "difficulty": payload.difficulty,
+            "age_band": payload.age_band.map(|band| band.as_str()),
+            "locale": &locale,
+            "translations": translations,
             "status": TemplateStatus::Draft.as_str(),
             "version": 1,
             "source_refs": payload.source_refs,
-            "pii_flags": pii_flags,
+            "pii_flags": warnings.pii_flags,
+            "blacklist_warnings": warnings.blacklist_warnings,
+            "lint_findings": to_bson(&lint_findings)?,
             "reviewers": Vec::<String>::new(),
             "created_by": claims.sub.clone(),
+            "status_changed_at": now,
             "createdAt": now,
             "updatedAt": now,
         };
         tracing::info!("Template document built successfully");
 
         tracing::info!("Inserting template into MongoDB");
-        let collection: Collection<Document> = self.mongo.collection("templates");
-        let result = match collection.insert_one(template_doc).await {
-            Ok(r) => {
-                tracing::info!("Template inserted successfully");
-                r
-            }
-            Err(e) => {
-                tracing::error!("MongoDB insert error: {:?}", e);
-                return Err(anyhow!("Failed to insert template into MongoDB: {}", e));
-            }
-        };
-
-        let id = result
-            .inserted_id
-            .as_object_id()
-            .ok_or_else(|| anyhow!("Template insertion did not return ObjectId"))?;
-
-        self.log_audit(
+        self.commit_template_change(
+            TemplateChange {
+                template_id: id,
+                write: TemplateWrite::Insert(template_doc),
+                version: Some((1, doc! { "action": "create" })),
+                event: None,
+                publish: None,
+                audit: self.audit_record(
+                    claims,
+                    "template.create",
+                    "templates",
+                    &id.to_hex(),
+                    Some(doc! { "status": "draft" }),
+                    None,
+                ),
+            },
             claims,
-            "template.create",
-            "templates",
-            &id.to_hex(),
-            Some(doc! { "status": "draft" }),
-            None,
         )
         .await?;
+        tracing::info!("Template inserted successfully");
 
         self.get_template_summary(&id).await
     }
@@ -325,10 +524,16 @@ impl ContentService {
 
         let mut should_bump_version = false;
 
+        let params = payload
+            .params
+            .map(|params| json_to_document(Some(params), "params"))
+            .transpose()?;
+        if let Some(params) = &params {
+            validate_template_answers(params)?;
+        }
+
         if let Some(content) = payload.content {
-            self.validate_content(&content)?;
-            update.insert("content", content.clone());
-            update.insert("pii_flags", self.scan_pii(&content));
+            update.insert("content", content);
             should_bump_version = true;
         }
 
@@ -337,13 +542,13 @@ impl ContentService {
             should_bump_version = true;
         }
 
-        if let Some(params) = payload.params {
-            update.insert("params", json_to_document(Some(params))?);
+        if let Some(params) = params {
+            update.insert("params", params);
             should_bump_version = true;
         }
 
         if let Some(metadata) = payload.metadata {
-            update.insert("metadata", json_to_document(Some(metadata))?);
+            update.insert("metadata", json_to_document(Some(metadata), "metadata")?);
             should_bump_version = true;
         }
 
@@ -352,6 +557,66 @@ impl ContentService {
             should_bump_version = true;
         }
 
+        // Возрастная категория не меняет содержимое задания, поэтому версия не растёт.
+        // `null` снимает категорию
+        let clear_age_band = match payload.age_band {
+            Some(Some(age_band)) => {
+                update.insert("age_band", age_band.as_str());
+                false
+            }
+            Some(None) => current.age_band.is_some(),
+            None => false,
+        };
+
+        // Язык - классификация, как и возрастная категория: версия не растёт
+        let mut locale = current.locale.clone();
+        if let Some(requested) = payload
+            .locale
+            .filter(|requested| *requested != current.locale)
+        {
+            if !is_valid_locale(&requested) {
+                return Err(InvalidLocaleError(requested).into());
+            }
+            self.ensure_unique_slug(
+                &current.slug,
+                &current.level_id,
+                &requested,
+                Some(template_id),
+            )
+            .await?;
+            update.insert("locale", &requested);
+            locale = requested;
+        }
+
+        if let Some(translations) = payload.translations {
+            update.insert(
+                "translations",
+                self.translations_document(translations, &locale)?,
+            );
+            should_bump_version = true;
+        }
+
+        // Стоп-лист и ПДн проверяются по итоговому шаблону: новое поле вместе с прежними
+        if ["content", "params", "metadata", "translations"]
+            .iter()
+            .any(|field| update.contains_key(field))
+        {
+            let current_fields = doc! {
+                "content": &current.content,
+                "params": &current.params,
+                "metadata": &current.metadata,
+                "translations": to_bson(&current.translations)?,
+            };
+            let mut fields = Document::new();
+            for (field, value) in current_fields {
+                let value = update.get(&field).cloned().unwrap_or(value);
+                fields.insert(field, value);
+            }
+            let warnings = self.check_fields(&fields).await?;
+            update.insert("pii_flags", warnings.pii_flags);
+            update.insert("blacklist_warnings", warnings.blacklist_warnings);
+        }
+
         if should_bump_version {
             let new_version = current.version + 1;
             target_status = TemplateStatus::Draft;
@@ -359,196 +624,1030 @@ impl ContentService {
             update.insert("status", TemplateStatus::Draft.as_str());
         }
 
+        // Линтер - по итоговому шаблону; на модерацию шаблон с ошибками не уходит
+        let entering_review = target_status == TemplateStatus::PendingReview
+            && current.status != TemplateStatus::PendingReview;
+        if entering_review
+            || ["content", "params", "difficulty"]
+                .iter()
+                .any(|field| update.contains_key(field))
+        {
+            let findings = self
+                .lint_fields(
+                    update.get_str("content").unwrap_or(&current.content),
+                    update.get_document("params").unwrap_or(&current.params),
+                    update
+                        .get_str("difficulty")
+                        .ok()
+                        .or(current.difficulty.as_deref()),
+                    &current.level_id,
+                    &current.rule_ids,
+                )
+                .await?;
+            if entering_review && has_errors(&findings) {
+                return Err(TemplateLintError::from_findings(&findings).into());
+            }
+            update.insert("lint_findings", to_bson(&findings)?);
+        }
+
         if target_status != current.status && !update.contains_key("status") {
             update.insert("status", target_status.as_str());
         }
-
-        if !update.is_empty() {
-            let snapshot = update.clone();
-            let mut update_with_meta = update.clone();
-            let now = now_bson_datetime();
-            update_with_meta.insert("updatedAt", now);
-            update_with_meta.insert("createdAt", current.created_at);
-            let mut update_command = doc! { "$set": update_with_meta };
-            update_command.insert(
-                "$unset",
-                doc! {
-                    "updated_at": "",
-                    "created_at": "",
-                },
-            );
-            collection
-                .update_one(doc! { "_id": template_id }, update_command)
-                .await
-                .context("Failed to update template")?;
-
-            if should_bump_version {
-                let new_version = current.version + 1;
-                self.persist_template_version(template_id, new_version, claims, snapshot)
-                    .await?;
-            }
+        if target_status != current.status {
+            update.insert("status_changed_at", now_bson_datetime());
         }
-
-        if current.status != target_status && target_status == TemplateStatus::Published {
-            self.signal_content_change(template_id, "published").await?;
+        // Новый круг ревью: одобрения и назначение прошлого круга не считаются
+        if entering_review {
+            update.insert("reviewers", Vec::<String>::new());
+            update.insert("assigned_reviewer", Bson::Null);
         }
 
-        self.log_audit(
+        let published =
+            current.status != target_status && target_status == TemplateStatus::Published;
+        let audit = self.audit_record(
             claims,
             "template.update",
             "templates",
             &template_id.to_hex(),
             Some(doc! { "status": target_status.as_str() }),
             None,
+        );
+        let write = if update.is_empty() && !clear_age_band {
+            // Изменять нечего, но запрос всё равно попадает в аудит
+            TemplateWrite::None
+        } else {
+            let mut update_with_meta = update.clone();
+            update_with_meta.insert("updatedAt", now_bson_datetime());
+            update_with_meta.insert("createdAt", current.created_at);
+            if published {
+                update_with_meta.insert("published_at", now_bson_datetime());
+            }
+            let mut unset = doc! {
+                "updated_at": "",
+                "created_at": "",
+            };
+            if clear_age_band {
+                unset.insert("age_band", "");
+            }
+            TemplateWrite::Update {
+                filter: doc! { "_id": template_id },
+                update: doc! {
+                    "$set": update_with_meta,
+                    "$unset": unset,
+                },
+            }
+        };
+        let version = should_bump_version.then(|| (current.version + 1, update.clone()));
+        self.commit_template_change(
+            TemplateChange {
+                template_id: *template_id,
+                write,
+                version,
+                event: published.then_some("published"),
+                publish: published.then_some(PublishRecord {
+                    version: current.version,
+                    restored_from: None,
+                }),
+                audit,
+            },
+            claims,
         )
         .await?;
 
         self.get_template_summary(template_id).await
     }
 
-    pub async fn revert_template(
+    /// Переводит шаблоны в `status` по одному; ошибка по одному шаблону
+    /// не откатывает уже выполненные переходы
+    pub async fn bulk_update_template_status(
         &self,
-        template_id: &ObjectId,
-        payload: TemplateRevertRequest,
+        template_ids: Vec<String>,
+        status: TemplateStatus,
+        reason: Option<String>,
         claims: &JwtClaims,
-    ) -> Result<TemplateSummary> {
+    ) -> Result<TemplateBulkStatusResult> {
+        let mut processed = 0usize;
+        let mut failed = Vec::new();
+
+        for id in template_ids {
+            let outcome = match ObjectId::parse_str(&id) {
+                Ok(template_id) => {
+                    self.transition_template_status(&template_id, status, reason.clone(), claims)
+                        .await
+                }
+                Err(_) => Err(anyhow!("Invalid template ID format")),
+            };
+
+            match outcome {
+                Ok(_) => processed += 1,
+                Err(err) => failed.push(TemplateBulkStatusError {
+                    id,
+                    error: err.to_string(),
+                }),
+            }
+        }
+
+        Ok(TemplateBulkStatusResult { processed, failed })
+    }
+
+    /// Пакет шаблонов вместе с темами, уровнями и правилами, на которые они ссылаются
+    pub async fn export_templates(&self, template_ids: &[ObjectId]) -> Result<TemplateBundle> {
         let collection: Collection<TemplateDocument> = self.mongo.collection("templates");
-        let template = collection
-            .find_one(doc! { "_id": template_id })
+        let mut templates: Vec<TemplateDocument> = collection
+            .find(doc! { "_id": { "$in": template_ids } })
             .await
-            .context("Failed to load template for revert")?
-            .ok_or_else(|| anyhow!("Template not found"))?;
-
-        let new_version = template.version + 1;
-        let now = now_bson_datetime();
-        collection
-            .update_one(
-                doc! { "_id": template_id },
-                doc! {
-                    "$set": {
-                        "status": TemplateStatus::Draft.as_str(),
-                        "updatedAt": now,
-                        "createdAt": template.created_at,
-                        "version": new_version
-                    },
-                    "$unset": {
-                        "updated_at": "",
-                        "created_at": "",
-                    }
-                },
-            )
+            .context("Failed to load templates for export")?
+            .try_collect()
             .await
-            .context("Failed to revert template")?;
+            .context("Cursor failed")?;
+        let missing: Vec<String> = template_ids
+            .iter()
+            .filter(|id| !templates.iter().any(|template| template.id == **id))
+            .map(|id| id.to_hex())
+            .collect();
+        if !missing.is_empty() {
+            return Err(TemplatesNotFoundError(missing).into());
+        }
+        // Порядок шаблонов в пакете - как в запросе
+        templates.sort_by_key(|template| template_ids.iter().position(|id| *id == template.id));
 
-        self.persist_template_version(
-            template_id,
-            new_version,
-            claims,
-            doc! { "action": "revert", "reason": payload.reason.clone() },
+        let level_ids: Vec<ObjectId> = templates
+            .iter()
+            .map(|template| template.level_id)
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        let levels = self.fetch_levels(&level_ids).await?;
+        let topic_ids: Vec<ObjectId> = levels
+            .values()
+            .map(|level| level.topic_id)
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        let topics = self.fetch_topics(&topic_ids).await?;
+        let rule_ids: Vec<ObjectId> = templates
+            .iter()
+            .flat_map(|template| template.rule_ids.iter().copied())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        let rules: Vec<RuleRecord> = self
+            .mongo
+            .collection::<RuleRecord>("rules")
+            .find(doc! { "_id": { "$in": rule_ids } })
+            .await
+            .context("Failed to load rules for export")?
+            .try_collect()
+            .await
+            .context("Cursor failed")?;
+        let rules = rules.into_iter().map(|rule| (rule.id, rule)).collect();
+
+        build_template_bundle(
+            &templates,
+            &levels,
+            &topics,
+            &rules,
+            bson_to_iso(&now_bson_datetime()),
         )
-        .await?;
+    }
 
-        self.log_audit(
-            claims,
-            "template.revert",
-            "templates",
-            &template_id.to_hex(),
-            Some(doc! { "version": new_version }),
-            Some(payload.reason),
-        )
-        .await?;
+    /// Импортирует шаблоны пакета по одному в статусе `draft`; ошибка по одному
+    /// шаблону попадает в отчёт и не прерывает импорт остальных
+    pub async fn import_templates(
+        &self,
+        bundle: TemplateBundle,
+        options: TemplateImportOptions,
+        claims: &JwtClaims,
+    ) -> Result<TemplateImportReport> {
+        let mut refs = BundleRefs {
+            topics: self
+                .list_topics()
+                .await?
+                .into_iter()
+                .map(|topic| (topic.slug, topic.id))
+                .collect(),
+            rules: self
+                .list_rules()
+                .await?
+                .into_iter()
+                .map(|rule| (rule.slug, rule.id))
+                .collect(),
+            ..Default::default()
+        };
 
-        self.get_template_summary(template_id).await
+        for template in &bundle.templates {
+            let item = match self
+                .import_bundle_template(&bundle, template, options, &mut refs, claims)
+                .await
+            {
+                Ok(item) => item,
+                Err(err) => TemplateImportItem {
+                    slug: template.slug.clone(),
+                    outcome: TemplateImportOutcome::Failed,
+                    template_id: None,
+                    version: None,
+                    error: Some(err.to_string()),
+                },
+            };
+            refs.report.items.push(item);
+        }
+
+        Ok(refs.report)
     }
 
-    pub async fn list_template_versions(
+    async fn import_bundle_template(
         &self,
-        template_id: &ObjectId,
-    ) -> Result<Vec<TemplateVersionSummary>> {
-        let collection: Collection<Document> = self.mongo.collection("template_versions");
-        let find_options = FindOptions::builder().sort(doc! { "version": -1 }).build();
-        let mut cursor = collection
-            .find(doc! { "template_id": template_id })
-            .with_options(find_options)
-            .await
-            .context("Failed to load template versions")?;
+        bundle: &TemplateBundle,
+        template: &BundleTemplate,
+        options: TemplateImportOptions,
+        refs: &mut BundleRefs,
+        claims: &JwtClaims,
+    ) -> Result<TemplateImportItem> {
+        // Содержимое проверяется до создания недостающих тем, уровней и правил
+        let params = json_to_document(Some(template.params.clone()), "params")?;
+        validate_template_answers(&params)?;
+        self.check_fields(&doc! {
+            "content": &template.content,
+            "params": &params,
+            "metadata": json_to_document(Some(template.metadata.clone()), "metadata")?,
+        })
+        .await?;
 
-        let mut versions = Vec::new();
-        while let Some(record) = cursor.try_next().await.context("Cursor failed")? {
-            versions.push(TemplateVersionSummary::from_record(&record));
+        let level_id = self
+            .resolve_bundle_level(
+                bundle,
+                &template.topic_slug,
+                &template.level_name,
+                options,
+                refs,
+                claims,
+            )
+            .await?;
+        let mut rule_ids = Vec::with_capacity(template.rule_slugs.len());
+        for slug in &template.rule_slugs {
+            rule_ids.push(
+                self.resolve_bundle_rule(bundle, slug, options, refs, claims)
+                    .await?,
+            );
         }
-        Ok(versions)
+
+        let existing = self
+            .mongo
+            .collection::<Document>("templates")
+            .find_one(doc! {
+                "slug": &template.slug,
+                "level_id": level_id,
+                "locale": locale_filter(&template.locale),
+            })
+            .await
+            .context("Failed to check template slug")?
+            .and_then(|existing| existing.get_object_id("_id").ok());
+
+        let (outcome, summary) = match existing {
+            Some(existing_id) if !options.overwrite => {
+                return Ok(TemplateImportItem {
+                    slug: template.slug.clone(),
+                    outcome: TemplateImportOutcome::Skipped,
+                    template_id: Some(existing_id.to_hex()),
+                    version: None,
+                    error: Some("Template slug already exists for this level".to_string()),
+                });
+            }
+            Some(existing_id) => {
+                let payload = TemplateUpdateRequest {
+                    status: None,
+                    params: Some(template.params.clone()),
+                    metadata: Some(template.metadata.clone()),
+                    content: Some(template.content.clone()),
+                    difficulty: template.difficulty.clone(),
+                    source_refs: Some(template.source_refs.clone()),
+                    age_band: template.age_band.map(Some),
+                    locale: None,
+                    translations: Some(template.translations.clone()),
+                };
+                let summary = self.update_template(&existing_id, payload, claims).await?;
+                (TemplateImportOutcome::Updated, summary)
+            }
+            None => {
+                let payload = TemplateCreateRequest {
+                    slug: template.slug.clone(),
+                    level_id: level_id.to_hex(),
+                    rule_ids: rule_ids.iter().map(|id| id.to_hex()).collect(),
+                    params: template.params.clone(),
+                    metadata: template.metadata.clone(),
+                    content: template.content.clone(),
+                    difficulty: template.difficulty.clone(),
+                    source_refs: template.source_refs.clone(),
+                    age_band: template.age_band,
+                    locale: Some(template.locale.clone()),
+                    translations: template.translations.clone(),
+                };
+                let summary = self.create_template(payload, claims).await?;
+                (TemplateImportOutcome::Created, summary)
+            }
+        };
+
+        Ok(TemplateImportItem {
+            slug: template.slug.clone(),
+            outcome,
+            template_id: Some(summary.id),
+            version: Some(summary.version),
+            error: None,
+        })
     }
 
-    pub async fn persist_template_version(
+    async fn resolve_bundle_topic(
         &self,
-        template_id: &ObjectId,
-        version: i32,
+        bundle: &TemplateBundle,
+        slug: &str,
+        options: TemplateImportOptions,
+        refs: &mut BundleRefs,
         claims: &JwtClaims,
-        changes: Document,
-    ) -> Result<()> {
-        let collection: Collection<Document> = self.mongo.collection("template_versions");
-        let record = doc! {
-            "template_id": template_id,
-            "version": version,
-            "changes": changes,
-            "created_by": claims.sub.clone(),
-            "createdAt": now_bson_datetime(),
-        };
-        collection
-            .insert_one(record)
-            .await
-            .context("Failed to persist template version")?;
-        Ok(())
+    ) -> Result<ObjectId> {
+        if let Some(id) = refs.topics.get(slug) {
+            return Ok(*id);
+        }
+        if !options.create_missing {
+            return Err(anyhow!("Unknown topic slug: {}", slug));
+        }
+        let definition = bundle
+            .topics
+            .iter()
+            .find(|topic| topic.slug == slug)
+            .ok_or_else(|| anyhow!("Topic {} is not defined in the bundle", slug))?;
+        let topic = self
+            .create_topic(
+                TopicCreateRequest {
+                    slug: definition.slug.clone(),
+                    name: definition.name.clone(),
+                    description: definition.description.clone(),
+                    icon_url: definition.icon_url.clone(),
+                    status: definition.status,
+                    age_band: definition.age_band,
+                },
+                claims,
+            )
+            .await?;
+        refs.topics.insert(slug.to_string(), topic.id);
+        refs.report.created_topics.push(slug.to_string());
+        Ok(topic.id)
     }
 
-    pub async fn submit_template_for_moderation(
+    async fn resolve_bundle_level(
         &self,
-        template_id: &ObjectId,
+        bundle: &TemplateBundle,
+        topic_slug: &str,
+        name: &str,
+        options: TemplateImportOptions,
+        refs: &mut BundleRefs,
         claims: &JwtClaims,
-    ) -> Result<TemplateSummary> {
+    ) -> Result<ObjectId> {
+        let topic_id = self
+            .resolve_bundle_topic(bundle, topic_slug, options, refs, claims)
+            .await?;
+        if let Entry::Vacant(entry) = refs.levels.entry(topic_id) {
+            let levels = self.list_levels_for_topic(&topic_id).await?;
+            entry.insert(
+                levels
+                    .into_iter()
+                    .map(|level| (level.name, level.id))
+                    .collect(),
+            );
+        }
+        if let Some(id) = refs
+            .levels
+            .get(&topic_id)
+            .and_then(|levels| levels.get(name))
+        {
+            return Ok(*id);
+        }
+        if !options.create_missing {
+            return Err(anyhow!("Unknown level: {}/{}", topic_slug, name));
+        }
+        let definition = bundle
+            .levels
+            .iter()
+            .find(|level| level.topic_slug == topic_slug && level.name == name)
+            .ok_or_else(|| anyhow!("Level {}/{} is not defined in the bundle", topic_slug, name))?;
+        let level = self
+            .create_level(
+                LevelCreateRequest {
+                    topic_id: topic_id.to_hex(),
+                    name: definition.name.clone(),
+                    difficulty: definition.difficulty,
+                    description: definition.description.clone(),
+                    min_pass_percent: definition.min_pass_percent,
+                    order: definition.order,
+                    prerequisite_level_ids: Vec::new(),
+                },
+                claims,
+            )
+            .await?;
+        refs.levels
+            .entry(topic_id)
+            .or_default()
+            .insert(name.to_string(), level.id);
+        refs.report
+            .created_levels
+            .push(format!("{}/{}", topic_slug, name));
+        Ok(level.id)
+    }
+
+    async fn resolve_bundle_rule(
+        &self,
+        bundle: &TemplateBundle,
+        slug: &str,
+        options: TemplateImportOptions,
+        refs: &mut BundleRefs,
+        claims: &JwtClaims,
+    ) -> Result<ObjectId> {
+        if let Some(id) = refs.rules.get(slug) {
+            return Ok(*id);
+        }
+        if !options.create_missing {
+            return Err(anyhow!("Unknown rule slug: {}", slug));
+        }
+        let definition = bundle
+            .rules
+            .iter()
+            .find(|rule| rule.slug == slug)
+            .ok_or_else(|| anyhow!("Rule {} is not defined in the bundle", slug))?;
+        let rule = self
+            .create_rule(
+                RuleCreateRequest {
+                    slug: definition.slug.clone(),
+                    name: definition.name.clone(),
+                    category: definition.category.clone(),
+                    description: definition.description.clone(),
+                    examples: definition.examples.clone(),
+                    exceptions: definition.exceptions.clone(),
+                    sources: definition.sources.clone(),
+                    status: definition.status,
+                },
+                claims,
+            )
+            .await?;
+        refs.rules.insert(slug.to_string(), rule.id);
+        refs.report.created_rules.push(slug.to_string());
+        Ok(rule.id)
+    }
+
+    async fn transition_template_status(
+        &self,
+        template_id: &ObjectId,
+        requested: TemplateStatus,
+        reason: Option<String>,
+        claims: &JwtClaims,
+    ) -> Result<()> {
         let collection: Collection<TemplateDocument> = self.mongo.collection("templates");
-        let template = collection
+        let current = collection
             .find_one(doc! { "_id": template_id })
             .await
-            .context("Failed to load template")?
+            .context("Failed to fetch template")?
             .ok_or_else(|| anyhow!("Template not found"))?;
 
-        if template.status != TemplateStatus::Draft {
+        if current.status == requested {
+            return Ok(());
+        }
+        if !current.status.can_transition_to(requested) {
             return Err(anyhow!(
-                "Template must be in draft to submit for moderation"
+                "Invalid template status transition: {} -> {}",
+                current.status.as_str(),
+                requested.as_str()
             ));
         }
+        if requested == TemplateStatus::PendingReview {
+            let findings = self.lint_document(&current).await?;
+            if has_errors(&findings) {
+                return Err(TemplateLintError::from_findings(&findings).into());
+            }
+        }
 
         let now = now_bson_datetime();
-        collection
+        let mut set = doc! {
+            "status": requested.as_str(),
+            "status_changed_at": now,
+            "updatedAt": now,
+        };
+        if requested == TemplateStatus::PendingReview {
+            set.insert("reviewers", Vec::<String>::new());
+            set.insert("assigned_reviewer", Bson::Null);
+        }
+        if requested == TemplateStatus::Published {
+            set.insert("published_at", now);
+        }
+
+        // Фильтр по текущему статусу: параллельное изменение шаблона не перезаписывается
+        let applied = self
+            .commit_template_change(
+                TemplateChange {
+                    template_id: *template_id,
+                    write: TemplateWrite::Update {
+                        filter: doc! { "_id": template_id, "status": current.status.as_str() },
+                        update: doc! { "$set": set },
+                    },
+                    version: None,
+                    event: (requested == TemplateStatus::Published).then_some("published"),
+                    publish: (requested == TemplateStatus::Published).then_some(PublishRecord {
+                        version: current.version,
+                        restored_from: None,
+                    }),
+                    audit: self.audit_record(
+                        claims,
+                        "template.status",
+                        "templates",
+                        &template_id.to_hex(),
+                        Some(doc! {
+                            "from": current.status.as_str(),
+                            "status": requested.as_str(),
+                            "bulk": true,
+                        }),
+                        reason,
+                    ),
+                },
+                claims,
+            )
+            .await?;
+        if !applied {
+            return Err(anyhow!("Template status changed concurrently"));
+        }
+        Ok(())
+    }
+
+    /// Активные ссылки на шаблон: открытые домашние задания (`assignments.templateIds`,
+    /// срок не прошёл или разрешена сдача после срока) и активные сессии, решающие
+    /// задачи из шаблона (через `tasks.template_id`)
+    pub async fn find_template_references(
+        &self,
+        template_id: &ObjectId,
+    ) -> Result<Vec<TemplateReference>> {
+        let assignments: Vec<Document> = self
+            .mongo
+            .collection::<Document>(ASSIGNMENTS_COLLECTION)
+            .find(doc! {
+                "templateIds": template_id,
+                "$or": [
+                    { "dueAt": { "$gt": now_bson_datetime() } },
+                    { "allowLate": true },
+                ],
+            })
+            .projection(doc! { "_id": 1 })
+            .await
+            .context("Failed to load template assignments")?
+            .try_collect()
+            .await
+            .context("Failed to collect template assignments")?;
+        let mut references: Vec<TemplateReference> = assignments
+            .iter()
+            .filter_map(|assignment| {
+                Some(TemplateReference {
+                    kind: "assignment".to_string(),
+                    id: assignment.get_object_id("_id").ok()?.to_hex(),
+                    user_id: None,
+                })
+            })
+            .collect();
+
+        let task_ids: Vec<String> = self
+            .mongo
+            .collection::<Document>("tasks")
+            .find(doc! { "template_id": template_id })
+            .projection(doc! { "_id": 1 })
+            .await
+            .context("Failed to load template tasks")?
+            .try_collect::<Vec<Document>>()
+            .await
+            .context("Failed to collect template tasks")?
+            .iter()
+            .filter_map(|task| task.get_object_id("_id").ok())
+            .map(|id| id.to_hex())
+            .collect();
+        if task_ids.is_empty() {
+            return Ok(references);
+        }
+
+        let sessions: Vec<Document> = self
+            .mongo
+            .collection::<Document>("sessions")
+            .find(doc! {
+                "task_id": { "$in": task_ids },
+                "status": "active",
+                "expires_at": { "$gt": now_bson_datetime() },
+            })
+            .projection(doc! { "_id": 1, "user_id": 1 })
+            .await
+            .context("Failed to load active sessions")?
+            .try_collect()
+            .await
+            .context("Failed to collect active sessions")?;
+
+        references.extend(sessions.iter().filter_map(|session| {
+            Some(TemplateReference {
+                kind: "session".to_string(),
+                id: session.get_str("_id").ok()?.to_string(),
+                user_id: session.get_str("user_id").ok().map(|id| id.to_string()),
+            })
+        }));
+        Ok(references)
+    }
+
+    pub async fn archive_template(
+        &self,
+        template_id: &ObjectId,
+        claims: &JwtClaims,
+    ) -> Result<TemplateArchiveOutcome> {
+        let references = self.find_template_references(template_id).await?;
+        if !references.is_empty() {
+            return Ok(TemplateArchiveOutcome::Blocked(references));
+        }
+        self.set_template_archived(template_id, true, claims).await
+    }
+
+    pub async fn unarchive_template(
+        &self,
+        template_id: &ObjectId,
+        claims: &JwtClaims,
+    ) -> Result<TemplateArchiveOutcome> {
+        self.set_template_archived(template_id, false, claims).await
+    }
+
+    async fn set_template_archived(
+        &self,
+        template_id: &ObjectId,
+        archived: bool,
+        claims: &JwtClaims,
+    ) -> Result<TemplateArchiveOutcome> {
+        let result = self
+            .mongo
+            .collection::<Document>("templates")
             .update_one(
                 doc! { "_id": template_id },
                 doc! {
                     "$set": {
-                        "status": TemplateStatus::PendingReview.as_str(),
-                        "updatedAt": now,
-                        "createdAt": template.created_at,
-                    },
-                    "$unset": {
-                        "updated_at": "",
-                        "created_at": "",
-                    },
+                        "archived": archived,
+                        "updatedAt": now_bson_datetime(),
+                    }
                 },
             )
             .await
-            .context("Failed to submit template for moderation")?;
+            .context("Failed to update template archive flag")?;
+        if result.matched_count == 0 {
+            return Ok(TemplateArchiveOutcome::NotFound);
+        }
 
+        let action = if archived {
+            "template.archive"
+        } else {
+            "template.unarchive"
+        };
         self.log_audit(
             claims,
-            "template.submit",
+            action,
             "templates",
             &template_id.to_hex(),
-            Some(doc! { "status": TemplateStatus::PendingReview.as_str() }),
+            Some(doc! { "archived": archived }),
             None,
         )
         .await?;
 
+        Ok(TemplateArchiveOutcome::Archived(Box::new(
+            self.get_template_summary(template_id).await?,
+        )))
+    }
+
+    pub async fn revert_template(
+        &self,
+        template_id: &ObjectId,
+        payload: TemplateRevertRequest,
+        claims: &JwtClaims,
+    ) -> Result<TemplateSummary> {
+        let collection: Collection<TemplateDocument> = self.mongo.collection("templates");
+        let template = collection
+            .find_one(doc! { "_id": template_id })
+            .await
+            .context("Failed to load template for revert")?
+            .ok_or_else(|| anyhow!("Template not found"))?;
+
+        let new_version = template.version + 1;
+        self.commit_template_change(
+            TemplateChange {
+                template_id: *template_id,
+                write: TemplateWrite::Update {
+                    filter: doc! { "_id": template_id },
+                    update: doc! {
+                        "$set": {
+                            "status": TemplateStatus::Draft.as_str(),
+                            "status_changed_at": now_bson_datetime(),
+                            "updatedAt": now_bson_datetime(),
+                            "createdAt": template.created_at,
+                            "version": new_version
+                        },
+                        "$unset": {
+                            "updated_at": "",
+                            "created_at": "",
+                        }
+                    },
+                },
+                version: Some((
+                    new_version,
+                    doc! { "action": "revert", "reason": payload.reason.clone() },
+                )),
+                event: None,
+                publish: None,
+                audit: self.audit_record(
+                    claims,
+                    "template.revert",
+                    "templates",
+                    &template_id.to_hex(),
+                    Some(doc! { "version": new_version }),
+                    Some(payload.reason),
+                ),
+            },
+            claims,
+        )
+        .await?;
+
+        self.get_template_summary(template_id).await
+    }
+
+    pub async fn list_template_versions(
+        &self,
+        template_id: &ObjectId,
+    ) -> Result<Vec<TemplateVersionSummary>> {
+        let collection: Collection<Document> = self.mongo.collection("template_versions");
+        let find_options = FindOptions::builder().sort(doc! { "version": -1 }).build();
+        let mut cursor = collection
+            .find(doc! { "template_id": template_id })
+            .with_options(find_options)
+            .await
+            .context("Failed to load template versions")?;
+
+        let mut versions = Vec::new();
+        while let Some(record) = cursor.try_next().await.context("Cursor failed")? {
+            versions.push(TemplateVersionSummary::from_record(&record));
+        }
+        Ok(versions)
+    }
+
+    /// Записывает версию шаблона вместе со снимком полей после изменения,
+    /// чтобы потом можно было построить diff между версиями
+    /// Применяет изменение шаблона, запись версии и запись outbox одной транзакцией.
+    /// Событие стрима и аудит публикует `ContentOutboxDispatcher`. `false`, если фильтр
+    /// обновления ничего не нашёл (изменение не применено)
+    async fn commit_template_change(
+        &self,
+        change: TemplateChange,
+        claims: &JwtClaims,
+    ) -> Result<bool> {
+        let mut attempt = 1;
+        let applied = loop {
+            match self.try_commit_template_change(&change, claims).await {
+                // Конфликт записи с параллельной транзакцией: повторяем целиком
+                Err(err)
+                    if attempt < TEMPLATE_TRANSACTION_ATTEMPTS
+                        && err.contains_label(TRANSIENT_TRANSACTION_ERROR) =>
+                {
+                    attempt += 1;
+                }
+                result => break result.context("Failed to commit template change")?,
+            }
+        };
+        if applied {
+            self.invalidate_cache(ContentCacheKind::Template, &[change.template_id])
+                .await;
+        }
+        Ok(applied)
+    }
+
+    async fn try_commit_template_change(
+        &self,
+        change: &TemplateChange,
+        claims: &JwtClaims,
+    ) -> mongodb::error::Result<bool> {
+        let mut session = self.mongo.client().start_session().await?;
+        session.start_transaction().await?;
+        let templates: Collection<Document> = self.mongo.collection("templates");
+
+        match &change.write {
+            TemplateWrite::Insert(document) => {
+                templates.insert_one(document).session(&mut session).await?;
+            }
+            TemplateWrite::Update { filter, update } => {
+                let result = templates
+                    .update_one(filter.clone(), update.clone())
+                    .session(&mut session)
+                    .await?;
+                if result.matched_count == 0 {
+                    session.abort_transaction().await?;
+                    return Ok(false);
+                }
+            }
+            TemplateWrite::None => {}
+        }
+
+        if let Some((version, changes)) = &change.version {
+            let template = templates
+                .find_one(doc! { "_id": change.template_id })
+                .session(&mut session)
+                .await?
+                .unwrap_or_default();
+            let snapshot = snapshot_fields(&template, &TEMPLATE_SNAPSHOT_FIELDS);
+            self.mongo
+                .collection::<Document>("template_versions")
+                .insert_one(doc! {
+                    "template_id": change.template_id,
+                    "version": version,
+                    "changes": changes.clone(),
+                    "snapshot": snapshot,
+                    "created_by": claims.sub.clone(),
+                    "createdAt": now_bson_datetime(),
+                })
+                .session(&mut session)
+                .await?;
+        }
+
+        if let Some(publish) = &change.publish {
+            let template = templates
+                .find_one(doc! { "_id": change.template_id })
+                .session(&mut session)
+                .await?
+                .unwrap_or_default();
+            // Повторная публикация той же версии (например, после депрекации) обновляет запись
+            self.mongo
+                .collection::<Document>(PUBLISHED_VERSIONS_COLLECTION)
+                .update_one(
+                    doc! { "template_id": change.template_id, "version": publish.version },
+                    doc! {
+                        "$set": {
+                            "snapshot": snapshot_fields(&template, &PUBLISHED_SNAPSHOT_FIELDS),
+                            "restored_from": publish.restored_from,
+                            "published_by": claims.sub.clone(),
+                            "published_at": template
+                                .get_datetime("published_at")
+                                .copied()
+                                .unwrap_or_else(|_| now_bson_datetime()),
+                        }
+                    },
+                )
+                .upsert(true)
+                .session(&mut session)
+                .await?;
+        }
+
+        self.mongo
+            .collection::<Document>(CONTENT_OUTBOX_COLLECTION)
+            .insert_one(outbox_entry(
+                &change.template_id,
+                change.event,
+                change.audit.clone(),
+            ))
+            .session(&mut session)
+            .await?;
+
+        session.commit_transaction().await?;
+        Ok(true)
+    }
+
+    /// Diff версии `version` с предыдущей записанной версией.
+    /// `None`, если такой версии нет
+    pub async fn template_version_diff(
+        &self,
+        template_id: &ObjectId,
+        version: i32,
+    ) -> Result<Option<TemplateVersionDiff>> {
+        let collection: Collection<Document> = self.mongo.collection("template_versions");
+        let Some(current) = collection
+            .find_one(doc! { "template_id": template_id, "version": version })
+            .await
+            .context("Failed to load template version")?
+        else {
+            return Ok(None);
+        };
+        let previous = collection
+            .find_one(doc! { "template_id": template_id, "version": { "$lt": version } })
+            .sort(doc! { "version": -1 })
+            .await
+            .context("Failed to load previous template version")?;
+
+        let previous_version = previous
+            .as_ref()
+            .and_then(|record| record.get_i32("version").ok());
+        let current_snapshot = current.get_document("snapshot").ok();
+        // Для первой версии сравниваем с пустым шаблоном
+        let empty = Document::new();
+        let previous_snapshot = match &previous {
+            Some(record) => record.get_document("snapshot").ok(),
+            None => Some(&empty),
+        };
+
+        let (Some(new), Some(old)) = (current_snapshot, previous_snapshot) else {
+            return Ok(Some(TemplateVersionDiff {
+                template_id: template_id.to_hex(),
+                version,
+                previous_version,
+                available: false,
+                message: Some(
+                    "diff unavailable: version was recorded before content snapshots".to_string(),
+                ),
+                diff: None,
+                changed_fields: Vec::new(),
+            }));
+        };
+
+        let old_label = previous_version
+            .map(|v| format!("v{}", v))
+            .unwrap_or_else(|| "/dev/null".to_string());
+        let diff = unified_diff(
+            old.get_str("content").unwrap_or_default(),
+            new.get_str("content").unwrap_or_default(),
+            &old_label,
+            &format!("v{}", version),
+            3,
+        );
+        let changed_fields = TEMPLATE_SNAPSHOT_FIELDS
+            .iter()
+            .filter(|field| **field != "content")
+            .filter(|field| old.get(**field) != new.get(**field))
+            .map(|field| TemplateFieldChange {
+                field: field.to_string(),
+                old: old.get(*field).cloned(),
+                new: new.get(*field).cloned(),
+            })
+            .collect();
+
+        Ok(Some(TemplateVersionDiff {
+            template_id: template_id.to_hex(),
+            version,
+            previous_version,
+            available: true,
+            message: None,
+            diff: Some(diff),
+            changed_fields,
+        }))
+    }
+
+    pub async fn submit_template_for_moderation(
+        &self,
+        template_id: &ObjectId,
+        claims: &JwtClaims,
+    ) -> Result<TemplateSummary> {
+        let collection: Collection<TemplateDocument> = self.mongo.collection("templates");
+        let template = collection
+            .find_one(doc! { "_id": template_id })
+            .await
+            .context("Failed to load template")?
+            .ok_or_else(|| anyhow!("Template not found"))?;
+
+        if template.status != TemplateStatus::Draft {
+            return Err(anyhow!(
+                "Template must be in draft to submit for moderation"
+            ));
+        }
+        // Правила и уровень могли измениться после сохранения: линтер запускается заново
+        let findings = self.lint_document(&template).await?;
+        if has_errors(&findings) {
+            return Err(TemplateLintError::from_findings(&findings).into());
+        }
+
+        self.commit_template_change(
+            TemplateChange {
+                template_id: *template_id,
+                write: TemplateWrite::Update {
+                    filter: doc! { "_id": template_id },
+                    update: doc! {
+                        "$set": {
+                            "status": TemplateStatus::PendingReview.as_str(),
+                            "lint_findings": to_bson(&findings)?,
+                            // Новый круг ревью: одобрения и назначение прошлого круга сбрасываются
+                            "reviewers": Vec::<String>::new(),
+                            "status_changed_at": now_bson_datetime(),
+                            "updatedAt": now_bson_datetime(),
+                            "createdAt": template.created_at,
+                        },
+                        "$unset": {
+                            "assigned_reviewer": "",
+                            "updated_at": "",
+                            "created_at": "",
+                        },
+                    },
+                },
+                version: None,
+                event: None,
+                publish: None,
+                audit: self.audit_record(
+                    claims,
+                    "template.submit",
+                    "templates",
+                    &template_id.to_hex(),
+                    Some(doc! { "status": TemplateStatus::PendingReview.as_str() }),
+                    None,
+                ),
+            },
+            claims,
+        )
+        .await?;
+        self.refresh_moderation_queue_depth().await;
+
         self.get_template_summary(template_id).await
     }
 
@@ -569,38 +1668,60 @@ impl ContentService {
             TemplateStatus::ReviewedOnce => TemplateStatus::Ready,
             _ => return Err(anyhow!("Template is not awaiting approval")),
         };
+        if template.reviewers.contains(&claims.sub) {
+            return Err(DuplicateApprovalError(claims.sub.clone()).into());
+        }
 
+        let mut unset = doc! { "updated_at": "", "created_at": "" };
+        // Назначенный модератор своё ревью провёл; второе одобрение - за другим
+        if template.assigned_reviewer.as_ref() == Some(&claims.sub) {
+            unset.insert("assigned_reviewer", "");
+        }
         let now = now_bson_datetime();
-        collection
-            .update_one(
-                doc! { "_id": template_id },
-                doc! {
-                    "$set": {
-                        "status": next_status.as_str(),
-                        "updatedAt": now,
-                        "createdAt": template.created_at,
-                    },
-                    "$addToSet": {
-                        "reviewers": claims.sub.clone()
+        // Фильтр по статусу и модераторам: параллельное одобрение тем же модератором
+        // не засчитывается дважды
+        let applied = self
+            .commit_template_change(
+                TemplateChange {
+                    template_id: *template_id,
+                    write: TemplateWrite::Update {
+                        filter: doc! {
+                            "_id": template_id,
+                            "status": template.status.as_str(),
+                            "reviewers": { "$ne": &claims.sub },
+                        },
+                        update: doc! {
+                            "$set": {
+                                "status": next_status.as_str(),
+                                "status_changed_at": now,
+                                "updatedAt": now,
+                                "createdAt": template.created_at,
+                            },
+                            "$addToSet": {
+                                "reviewers": claims.sub.clone()
+                            },
+                            "$unset": unset,
+                        },
                     },
-                    "$unset": {
-                        "updated_at": "",
-                        "created_at": "",
-                    }
+                    version: None,
+                    event: None,
+                    publish: None,
+                    audit: self.audit_record(
+                        claims,
+                        "template.approve",
+                        "templates",
+                        &template_id.to_hex(),
+                        Some(doc! { "status": next_status.as_str() }),
+                        None,
+                    ),
                 },
+                claims,
             )
-            .await
-            .context("Failed to approve template")?;
-
-        self.log_audit(
-            claims,
-            "template.approve",
-            "templates",
-            &template_id.to_hex(),
-            Some(doc! { "status": next_status.as_str() }),
-            None,
-        )
-        .await?;
+            .await?;
+        if !applied {
+            return Err(anyhow!("Template status changed concurrently"));
+        }
+        self.refresh_moderation_queue_depth().await;
 
         self.get_template_summary(template_id).await
     }
@@ -619,73 +1740,454 @@ impl ContentService {
             .ok_or_else(|| anyhow!("Template not found"))?;
 
         let new_version = template.version + 1;
+        self.commit_template_change(
+            TemplateChange {
+                template_id: *template_id,
+                write: TemplateWrite::Update {
+                    filter: doc! { "_id": template_id },
+                    update: doc! {
+                        "$set": {
+                            "status": TemplateStatus::Draft.as_str(),
+                            "status_changed_at": now_bson_datetime(),
+                            "updatedAt": now_bson_datetime(),
+                            "createdAt": template.created_at,
+                            "version": new_version
+                        },
+                        "$unset": {
+                            "updated_at": "",
+                            "created_at": "",
+                        }
+                    },
+                },
+                version: Some((
+                    new_version,
+                    doc! { "action": "reject", "reason": payload.reason.clone() },
+                )),
+                event: None,
+                publish: None,
+                audit: self.audit_record(
+                    claims,
+                    "template.reject",
+                    "templates",
+                    &template_id.to_hex(),
+                    Some(doc! { "status": TemplateStatus::Draft.as_str() }),
+                    Some(payload.reason),
+                ),
+            },
+            claims,
+        )
+        .await?;
+        self.refresh_moderation_queue_depth().await;
+
+        self.get_template_summary(template_id).await
+    }
+
+    /// Назначает модератора на текущий круг ревью. Модератор, уже одобривший шаблон,
+    /// назначен быть не может: второе одобрение от него не примется
+    pub async fn assign_reviewer(
+        &self,
+        template_id: &ObjectId,
+        reviewer_id: &str,
+        claims: &JwtClaims,
+    ) -> Result<ReviewerAssignOutcome> {
+        let collection: Collection<TemplateDocument> = self.mongo.collection("templates");
+        let Some(template) = collection
+            .find_one(doc! { "_id": template_id })
+            .await
+            .context("Failed to load template for reviewer assignment")?
+        else {
+            return Ok(ReviewerAssignOutcome::NotFound);
+        };
+        if !matches!(
+            template.status,
+            TemplateStatus::PendingReview | TemplateStatus::ReviewedOnce
+        ) {
+            return Ok(ReviewerAssignOutcome::NotInReview(template.status));
+        }
+        if template.reviewers.iter().any(|id| id == reviewer_id) {
+            return Ok(ReviewerAssignOutcome::AlreadyApproved);
+        }
+
+        let applied = self
+            .commit_template_change(
+                TemplateChange {
+                    template_id: *template_id,
+                    write: TemplateWrite::Update {
+                        filter: doc! { "_id": template_id, "status": template.status.as_str() },
+                        update: doc! {
+                            "$set": {
+                                "assigned_reviewer": reviewer_id,
+                                "updatedAt": now_bson_datetime(),
+                            }
+                        },
+                    },
+                    version: None,
+                    event: None,
+                    publish: None,
+                    audit: self.audit_record(
+                        claims,
+                        "template.assign_reviewer",
+                        "templates",
+                        &template_id.to_hex(),
+                        Some(doc! {
+                            "reviewer_id": reviewer_id,
+                            "previous": template.assigned_reviewer,
+                        }),
+                        None,
+                    ),
+                },
+                claims,
+            )
+            .await?;
+        if !applied {
+            return Err(anyhow!("Template status changed concurrently"));
+        }
+
+        Ok(ReviewerAssignOutcome::Assigned(Box::new(
+            self.get_template_summary(template_id).await?,
+        )))
+    }
+
+    /// Публикует одобренный шаблон: статус, `published_at`, снимок в `published_versions`
+    /// и событие `published` записываются одной транзакцией
+    pub async fn publish_template(
+        &self,
+        template_id: &ObjectId,
+        claims: &JwtClaims,
+    ) -> Result<TemplatePublishOutcome> {
+        let collection: Collection<TemplateDocument> = self.mongo.collection("templates");
+        let Some(template) = collection
+            .find_one(doc! { "_id": template_id })
+            .await
+            .context("Failed to load template for publish")?
+        else {
+            return Ok(TemplatePublishOutcome::NotFound);
+        };
+        if template.status != TemplateStatus::Ready {
+            return Ok(TemplatePublishOutcome::NotReady(template.status));
+        }
+
         let now = now_bson_datetime();
-        collection
-            .update_one(
-                doc! { "_id": template_id },
-                doc! {
-                    "$set": {
-                        "status": TemplateStatus::Draft.as_str(),
-                        "updatedAt": now,
-                        "createdAt": template.created_at,
-                        "version": new_version
+        let applied = self
+            .commit_template_change(
+                TemplateChange {
+                    template_id: *template_id,
+                    write: TemplateWrite::Update {
+                        filter: doc! { "_id": template_id, "status": TemplateStatus::Ready.as_str() },
+                        update: doc! {
+                            "$set": {
+                                "status": TemplateStatus::Published.as_str(),
+                                "published_at": now,
+                                "status_changed_at": now,
+                                "updatedAt": now,
+                            }
+                        },
                     },
-                    "$unset": {
-                        "updated_at": "",
-                        "created_at": "",
-                    }
+                    version: None,
+                    event: Some("published"),
+                    publish: Some(PublishRecord {
+                        version: template.version,
+                        restored_from: None,
+                    }),
+                    audit: self.audit_record(
+                        claims,
+                        "template.publish",
+                        "templates",
+                        &template_id.to_hex(),
+                        Some(doc! { "version": template.version }),
+                        None,
+                    ),
                 },
+                claims,
             )
+            .await?;
+        if !applied {
+            return Ok(TemplatePublishOutcome::Conflict);
+        }
+
+        Ok(TemplatePublishOutcome::Published(Box::new(
+            self.get_template_summary(template_id).await?,
+        )))
+    }
+
+    /// Возвращает в публикацию содержимое ранее опубликованной версии без нового круга ревью.
+    /// Шаблон получает следующую версию с этим содержимым, событие — `republished`
+    pub async fn rollback_published_version(
+        &self,
+        template_id: &ObjectId,
+        version: i32,
+        claims: &JwtClaims,
+    ) -> Result<TemplatePublishOutcome> {
+        let collection: Collection<TemplateDocument> = self.mongo.collection("templates");
+        let Some(template) = collection
+            .find_one(doc! { "_id": template_id })
             .await
-            .context("Failed to reject template")?;
+            .context("Failed to load template for publish rollback")?
+        else {
+            return Ok(TemplatePublishOutcome::NotFound);
+        };
+        let Some(record) = self
+            .mongo
+            .collection::<Document>(PUBLISHED_VERSIONS_COLLECTION)
+            .find_one(doc! { "template_id": template_id, "version": version })
+            .await
+            .context("Failed to load published version")?
+        else {
+            return Ok(TemplatePublishOutcome::VersionNotFound);
+        };
+        let snapshot = record.get_document("snapshot").cloned().unwrap_or_default();
 
-        self.persist_template_version(
-            template_id,
-            new_version,
-            claims,
-            doc! { "action": "reject", "reason": payload.reason.clone() },
-        )
-        .await?;
+        let now = now_bson_datetime();
+        let new_version = template.version + 1;
+        let mut set = Document::new();
+        let mut unset = Document::new();
+        for field in PUBLISHED_SNAPSHOT_FIELDS {
+            match snapshot.get(field) {
+                Some(value) => {
+                    set.insert(field, value.clone());
+                }
+                None => {
+                    unset.insert(field, "");
+                }
+            }
+        }
+        set.insert("status", TemplateStatus::Published.as_str());
+        set.insert("version", new_version);
+        set.insert("published_at", now);
+        set.insert("status_changed_at", now);
+        set.insert("updatedAt", now);
+        let mut update = doc! { "$set": set };
+        if !unset.is_empty() {
+            update.insert("$unset", unset);
+        }
+
+        // Фильтр по статусу и версии: параллельная правка шаблона не перезаписывается
+        let applied = self
+            .commit_template_change(
+                TemplateChange {
+                    template_id: *template_id,
+                    write: TemplateWrite::Update {
+                        filter: doc! {
+                            "_id": template_id,
+                            "status": template.status.as_str(),
+                            "version": template.version,
+                        },
+                        update,
+                    },
+                    version: Some((
+                        new_version,
+                        doc! { "action": "rollback_publish", "published_version": version },
+                    )),
+                    event: Some("republished"),
+                    publish: Some(PublishRecord {
+                        version: new_version,
+                        restored_from: Some(version),
+                    }),
+                    audit: self.audit_record(
+                        claims,
+                        "template.rollback_publish",
+                        "templates",
+                        &template_id.to_hex(),
+                        Some(doc! {
+                            "from": template.status.as_str(),
+                            "restored_from": version,
+                            "version": new_version,
+                        }),
+                        None,
+                    ),
+                },
+                claims,
+            )
+            .await?;
+        if !applied {
+            return Ok(TemplatePublishOutcome::Conflict);
+        }
+
+        Ok(TemplatePublishOutcome::Published(Box::new(
+            self.get_template_summary(template_id).await?,
+        )))
+    }
+
+    /// Очередь модерации, дольше всех ждущие первыми. Заодно обновляет
+    /// `moderation_queue_depth` по всей очереди, без учёта фильтров
+    pub async fn moderation_queue(
+        &self,
+        topic_id: Option<ObjectId>,
+        age: Option<ModerationQueueAge>,
+        assigned_reviewer: Option<&str>,
+        limit: Option<u32>,
+    ) -> Result<Vec<ModerationQueueItem>> {
+        let now = now_bson_datetime();
+        let templates = self.load_moderation_queue().await?;
+        record_moderation_queue_depth(&templates, &now);
+
+        let level_ids = match topic_id {
+            Some(topic_id) => Some(self.fetch_level_ids_for_topic(&topic_id).await?),
+            None => None,
+        };
+        let mut queued: Vec<(TemplateDocument, i64)> = templates
+            .into_iter()
+            .filter(|template| {
+                level_ids
+                    .as_ref()
+                    .is_none_or(|ids| ids.contains(&template.level_id))
+            })
+            .filter(|template| match assigned_reviewer {
+                Some("none") => template.assigned_reviewer.is_none(),
+                Some(reviewer) => template.assigned_reviewer.as_deref() == Some(reviewer),
+                None => true,
+            })
+            .map(|template| {
+                let secs = time_in_queue_secs(&template, &now);
+                (template, secs)
+            })
+            .filter(|(_, secs)| age.is_none_or(|age| ModerationQueueAge::from_secs(*secs) == age))
+            .collect();
+        queued.sort_by_key(|(_, secs)| std::cmp::Reverse(*secs));
+        queued.truncate(limit.map(i64::from).unwrap_or(25).min(MAX_LIST_LIMIT) as usize);
+
+        let level_ids: Vec<ObjectId> = queued
+            .iter()
+            .map(|(template, _)| template.level_id)
+            .collect();
+        let levels = self.fetch_levels(&level_ids).await?;
+        let topic_ids: Vec<ObjectId> = levels.values().map(|level| level.topic_id).collect();
+        let topics = self.fetch_topics(&topic_ids).await?;
+
+        Ok(queued
+            .into_iter()
+            .map(|(template, secs)| ModerationQueueItem {
+                status_changed_at: bson_to_iso(&queue_entered_at(&template)),
+                time_in_queue_secs: secs,
+                age: ModerationQueueAge::from_secs(secs),
+                template: TemplateSummary::from_doc(&template, &levels, &topics),
+            })
+            .collect())
+    }
+
+    /// Неархивные шаблоны в статусах `pendingreview` и `reviewedonce`
+    async fn load_moderation_queue(&self) -> Result<Vec<TemplateDocument>> {
+        self.mongo
+            .collection::<TemplateDocument>("templates")
+            .find(doc! {
+                "status": {
+                    "$in": [
+                        TemplateStatus::PendingReview.as_str(),
+                        TemplateStatus::ReviewedOnce.as_str(),
+                    ]
+                },
+                "archived": { "$ne": true },
+            })
+            .await
+            .context("Failed to load moderation queue")?
+            .try_collect()
+            .await
+            .context("Cursor failed")
+    }
 
-        self.log_audit(
-            claims,
-            "template.reject",
-            "templates",
-            &template_id.to_hex(),
-            Some(doc! { "status": TemplateStatus::Draft.as_str() }),
-            Some(payload.reason),
-        )
-        .await?;
+    /// Сбой не отменяет переход статуса: метрика обновится при следующем запросе очереди
+    async fn refresh_moderation_queue_depth(&self) {
+        match self.load_moderation_queue().await {
+            Ok(templates) => record_moderation_queue_depth(&templates, &now_bson_datetime()),
+            Err(err) => tracing::warn!("Failed to refresh moderation queue depth: {:#}", err),
+        }
+    }
 
-        self.get_template_summary(template_id).await
+    /// Находки линтера по сохранённому шаблону; `None`, если шаблона нет
+    pub async fn lint_template(
+        &self,
+        template_id: &ObjectId,
+    ) -> Result<Option<TemplateLintReport>> {
+        let collection: Collection<TemplateDocument> = self.mongo.collection("templates");
+        let Some(template) = collection
+            .find_one(doc! { "_id": template_id })
+            .await
+            .context("Failed to load template for lint")?
+        else {
+            return Ok(None);
+        };
+        Ok(Some(TemplateLintReport {
+            template_id: template.id.to_hex(),
+            findings: self.lint_document(&template).await?,
+        }))
     }
 
     pub async fn validate_all_templates(&self) -> Result<Vec<TemplateValidationIssue>> {
         let collection: Collection<TemplateDocument> = self.mongo.collection("templates");
-        let mut cursor = collection
+        let templates: Vec<TemplateDocument> = collection
             .find(Document::new())
             .await
-            .context("Failed to list templates for validation")?;
+            .context("Failed to list templates for validation")?
+            .try_collect()
+            .await
+            .context("Cursor failed")?;
+
+        // Уровни и правила загружаются один раз на все шаблоны
+        let level_ids: Vec<ObjectId> = templates
+            .iter()
+            .map(|template| template.level_id)
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        let levels = self.fetch_levels(&level_ids).await?;
+        let rules: HashMap<ObjectId, RuleRecord> = self
+            .list_rules()
+            .await?
+            .into_iter()
+            .map(|rule| (rule.id, rule))
+            .collect();
+        let linter = TemplateLinter::default();
 
         let mut issues = Vec::new();
-        while let Some(template) = cursor.try_next().await.context("Cursor failed")? {
+        for template in templates {
             let id = template.id.to_hex();
-            if let Err(err) = self.validate_content(&template.content) {
-                issues.push(TemplateValidationIssue {
+            let fields = doc! {
+                "content": &template.content,
+                "params": &template.params,
+                "metadata": &template.metadata,
+                "translations": to_bson(&template.translations)?,
+            };
+            let check = match validate_template_answers(&template.params) {
+                Ok(()) => self.check_fields(&fields).await,
+                Err(err) => Err(err.into()),
+            };
+            match check {
+                Ok(warnings) if !warnings.blacklist_warnings.is_empty() => {
+                    issues.push(TemplateValidationIssue {
+                        template_id: id.clone(),
+                        slug: template.slug.clone(),
+                        reason: format!(
+                            "Blacklist warning: {}",
+                            warnings.blacklist_warnings.join(", ")
+                        ),
+                        severity: "warning".to_string(),
+                    });
+                }
+                Ok(_) => {}
+                Err(err) => issues.push(TemplateValidationIssue {
                     template_id: id.clone(),
                     slug: template.slug.clone(),
                     reason: err.to_string(),
                     severity: "error".to_string(),
-                });
-            }
-            if template.rule_ids.is_empty() {
-                issues.push(TemplateValidationIssue {
-                    template_id: id.clone(),
-                    slug: template.slug.clone(),
-                    reason: "Template has no linked rules".to_string(),
-                    severity: "warning".to_string(),
-                });
+                }),
             }
+            let findings = linter.lint(&LintTarget {
+                content: &template.content,
+                params: &template.params,
+                difficulty: template.difficulty.as_deref(),
+                level: levels.get(&template.level_id),
+                rules: template
+                    .rule_ids
+                    .iter()
+                    .filter_map(|rule_id| rules.get(rule_id))
+                    .collect(),
+            });
+            issues.extend(findings.into_iter().map(|finding| TemplateValidationIssue {
+                template_id: id.clone(),
+                slug: template.slug.clone(),
+                reason: format!("Lint {}: {}", finding.code, finding.message),
+                severity: finding.severity.as_str().to_string(),
+            }));
         }
         Ok(issues)
     }
@@ -756,96 +2258,221 @@ impl ContentService {
                 .context("Failed to count templates for embeddings")?;
             total_u64.try_into().unwrap_or(i64::MAX)
         };
+        self.enqueue_embedding_job(payload.mode, explicit_ids, total, None)
+            .await
+    }
+
+    /// Ставит задание в очередь воркера эмбеддингов
+    async fn enqueue_embedding_job(
+        &self,
+        mode: String,
+        template_ids: Option<Vec<ObjectId>>,
+        total: i64,
+        retry_of: Option<ObjectId>,
+    ) -> Result<EmbeddingJobSummary> {
         let now = now_bson_datetime();
-        let mode = payload.mode.clone();
         let mut record = doc! {
-            "mode": payload.mode,
-            "status": "running",
+            "mode": mode,
+            "status": "queued",
             "total": total,
-            "processed": 0,
+            "processed": 0_i64,
+            "failed_template_ids": [],
             "createdAt": now,
             "updatedAt": now,
         };
-        if let Some(ids) = explicit_ids.as_ref() {
-            let bson_ids = ids.iter().map(|id| Bson::ObjectId(*id)).collect::<Vec<_>>();
+        if let Some(ids) = template_ids {
+            let bson_ids = ids.into_iter().map(Bson::ObjectId).collect::<Vec<_>>();
             record.insert("template_ids", bson_ids);
         }
+        if let Some(retry_of) = retry_of {
+            record.insert("retryOf", retry_of);
+        }
         let collection: Collection<Document> = self.mongo.collection("embedding_jobs");
         let result = collection
-            .insert_one(record)
+            .insert_one(&record)
             .await
             .context("Failed to enqueue embeddings rebuild")?;
         let id = result
             .inserted_id
             .as_object_id()
             .ok_or_else(|| anyhow!("Embedding job missing ObjectId"))?;
-        Ok(EmbeddingJobSummary {
-            id: id.to_hex(),
-            mode,
-            status: "running".to_string(),
-            total,
-            processed: 0,
-            created_at: bson_to_iso(&now),
-        })
+        record.insert("_id", id);
+        Ok(EmbeddingJobSummary::from_document(&record))
     }
 
+    /// Последнее задание; если заданий ещё не было - пустая сводка со статусом `idle`
     pub async fn get_embedding_progress(&self) -> Result<EmbeddingJobSummary> {
         let collection: Collection<Document> = self.mongo.collection("embedding_jobs");
-        let find_options = FindOptions::builder()
+        let latest = collection
+            .find_one(Document::new())
             .sort(doc! { "createdAt": -1 })
-            .limit(1)
-            .build();
-        let mut cursor = collection
-            .find(Document::new())
-            .with_options(find_options)
             .await
             .context("Failed to load embedding jobs")?;
-        if let Some(job_doc) = cursor
-            .try_next()
-            .await
-            .context("Failed to read embedding job")?
-        {
-            let mut summary = self.embedding_job_from_doc(&job_doc)?;
-            let now = Utc::now();
-            let updated_at = job_doc
-                .get_datetime("updatedAt")
-                .cloned()
-                .unwrap_or_else(|_| now_bson_datetime());
-            let elapsed = (now.timestamp_millis() - updated_at.timestamp_millis()) / 1000;
-            let additional = elapsed * 5;
-            let processed = i64::min(summary.total, summary.processed + additional);
-            if processed > summary.processed {
-                collection
-                    .update_one(
-                        doc! { "_id": job_doc.get_object_id("_id")? },
-                        doc! {
-                            "$set": {
-                                "processed": processed,
-                                "updated_at": now_bson_datetime(),
-                                "status": if processed >= summary.total { "completed" } else { "running" },
-                            }
-                        },
-                    )
-                    .await
-                    .context("Failed to update embedding progress")?;
-                summary.processed = processed;
-                summary.status = if processed >= summary.total {
-                    "completed".to_string()
-                } else {
-                    "running".to_string()
-                };
-            }
-            Ok(summary)
-        } else {
-            Ok(EmbeddingJobSummary {
+        Ok(match latest {
+            Some(job_doc) => EmbeddingJobSummary::from_document(&job_doc),
+            None => EmbeddingJobSummary {
                 id: String::new(),
                 mode: "none".to_string(),
                 status: "idle".to_string(),
                 total: 0,
                 processed: 0,
+                failed_count: 0,
                 created_at: Utc::now().to_rfc3339(),
-            })
+                cancel_requested_at: None,
+                cancelled_by: None,
+                finished_at: None,
+                retry_of: None,
+            },
+        })
+    }
+
+    pub async fn list_embedding_jobs(
+        &self,
+        query: EmbeddingJobListQuery,
+    ) -> Result<EmbeddingJobListResponse> {
+        let collection: Collection<Document> = self.mongo.collection("embedding_jobs");
+        let limit = query.limit.unwrap_or(20).clamp(1, MAX_LIST_LIMIT as u32);
+        let offset = query.offset.unwrap_or(0);
+        let total = collection
+            .count_documents(Document::new())
+            .await
+            .context("Failed to count embedding jobs")?;
+        let jobs: Vec<Document> = collection
+            .find(Document::new())
+            .sort(doc! { "createdAt": -1, "_id": -1 })
+            .skip(offset as u64)
+            .limit(limit as i64)
+            .await
+            .context("Failed to list embedding jobs")?
+            .try_collect()
+            .await
+            .context("Failed to read embedding jobs")?;
+        Ok(EmbeddingJobListResponse {
+            total,
+            limit,
+            offset,
+            jobs: jobs
+                .iter()
+                .map(EmbeddingJobSummary::from_document)
+                .collect(),
+        })
+    }
+
+    /// Отмена задания. Задание из очереди отменяется сразу, выполняющееся переходит
+    /// в `cancelling` и останавливается воркером перед следующим батчем
+    pub async fn cancel_embedding_job(
+        &self,
+        job_id: &ObjectId,
+        claims: &JwtClaims,
+    ) -> Result<EmbeddingJobCancelOutcome> {
+        let collection: Collection<Document> = self.mongo.collection("embedding_jobs");
+        let now = now_bson_datetime();
+        let mut cancelled = collection
+            .find_one_and_update(
+                doc! { "_id": job_id, "status": "queued" },
+                doc! {
+                    "$set": {
+                        "status": "cancelled",
+                        "cancelRequestedAt": now,
+                        "cancelledBy": &claims.sub,
+                        "finishedAt": now,
+                        "updatedAt": now,
+                    }
+                },
+            )
+            .return_document(ReturnDocument::After)
+            .await
+            .context("Failed to cancel embedding job")?;
+        if cancelled.is_none() {
+            cancelled = collection
+                .find_one_and_update(
+                    doc! { "_id": job_id, "status": "running" },
+                    doc! {
+                        "$set": {
+                            "status": "cancelling",
+                            "cancelRequestedAt": now,
+                            "cancelledBy": &claims.sub,
+                            "updatedAt": now,
+                        }
+                    },
+                )
+                .return_document(ReturnDocument::After)
+                .await
+                .context("Failed to cancel embedding job")?;
+        }
+
+        let Some(job) = cancelled else {
+            let existing = collection
+                .find_one(doc! { "_id": job_id })
+                .await
+                .context("Failed to load embedding job")?;
+            return Ok(match existing {
+                Some(job) => EmbeddingJobCancelOutcome::NotActive(
+                    job.get_str("status").unwrap_or("unknown").to_string(),
+                ),
+                None => EmbeddingJobCancelOutcome::NotFound,
+            });
+        };
+
+        let summary = EmbeddingJobSummary::from_document(&job);
+        self.log_audit(
+            claims,
+            "embeddings.cancel",
+            "embedding_job",
+            &summary.id,
+            Some(doc! { "status": &summary.status, "processed": summary.processed }),
+            None,
+        )
+        .await?;
+        Ok(EmbeddingJobCancelOutcome::Cancelling(Box::new(summary)))
+    }
+
+    /// Новое задание только по шаблонам, которые не удалось обработать в завершённом задании
+    pub async fn retry_failed_embeddings(
+        &self,
+        job_id: &ObjectId,
+        claims: &JwtClaims,
+    ) -> Result<EmbeddingJobRetryOutcome> {
+        let collection: Collection<Document> = self.mongo.collection("embedding_jobs");
+        let Some(job) = collection
+            .find_one(doc! { "_id": job_id })
+            .await
+            .context("Failed to load embedding job")?
+        else {
+            return Ok(EmbeddingJobRetryOutcome::NotFound);
+        };
+
+        let status = job.get_str("status").unwrap_or("unknown");
+        if !matches!(status, "completed" | "cancelled" | "failed") {
+            return Ok(EmbeddingJobRetryOutcome::NotFinished(status.to_string()));
         }
+        let failed: Vec<ObjectId> = job
+            .get_array("failed_template_ids")
+            .map(|ids| ids.iter().filter_map(Bson::as_object_id).collect())
+            .unwrap_or_default();
+        if failed.is_empty() {
+            return Ok(EmbeddingJobRetryOutcome::NoFailedTemplates);
+        }
+
+        let total = failed.len() as i64;
+        let summary = self
+            .enqueue_embedding_job(
+                "retry_failed".to_string(),
+                Some(failed),
+                total,
+                Some(*job_id),
+            )
+            .await?;
+        self.log_audit(
+            claims,
+            "embeddings.retry_failed",
+            "embedding_job",
+            &job_id.to_hex(),
+            Some(doc! { "retry_job_id": &summary.id, "templates": total }),
+            None,
+        )
+        .await?;
+        Ok(EmbeddingJobRetryOutcome::Enqueued(Box::new(summary)))
     }
 
     pub async fn check_embeddings_consistency(&self) -> Result<EmbeddingConsistencyReport> {
@@ -867,35 +2494,6 @@ impl ContentService {
         })
     }
 
-    fn embedding_job_from_doc(&self, doc: &Document) -> Result<EmbeddingJobSummary> {
-        let mode = doc
-            .get_str("mode")
-            .map(|s| s.to_string())
-            .unwrap_or_else(|_| "unknown".to_string());
-        let status = doc
-            .get_str("status")
-            .map(|s| s.to_string())
-            .unwrap_or_else(|_| "running".to_string());
-        let total = doc.get_i64("total").unwrap_or(0);
-        let processed = doc.get_i64("processed").unwrap_or(0);
-        let created_at = doc
-            .get_datetime("createdAt")
-            .map(bson_to_iso)
-            .unwrap_or_else(|_| Utc::now().to_rfc3339());
-        let id = doc
-            .get_object_id("_id")
-            .map(|oid| oid.to_hex())
-            .unwrap_or_else(|_| String::new());
-        Ok(EmbeddingJobSummary {
-            id,
-            mode,
-            status,
-            total,
-            processed,
-            created_at,
-        })
-    }
-
     pub async fn list_topics(&self) -> Result<Vec<TopicRecord>> {
         let collection: Collection<TopicRecord> = self.mongo.collection("topics");
         let mut cursor = collection
@@ -926,6 +2524,7 @@ impl ContentService {
             "icon_url": payload.icon_url,
             "sort_order": 0,
             "status": payload.status.unwrap_or(TopicStatus::Active).as_str(),
+            "age_band": payload.age_band.map(|band| band.as_str()),
             "createdAt": now,
             "updatedAt": now,
         };
@@ -977,7 +2576,17 @@ impl ContentService {
         if let Some(status) = payload.status {
             update.insert("status", status.as_str());
         }
-        if update.is_empty() {
+        let mut unset = Document::new();
+        match payload.age_band {
+            Some(Some(age_band)) => {
+                update.insert("age_band", age_band.as_str());
+            }
+            Some(None) => {
+                unset.insert("age_band", "");
+            }
+            None => {}
+        }
+        if update.is_empty() && unset.is_empty() {
             return collection
                 .find_one(doc! { "_id": topic_id })
                 .await
@@ -986,10 +2595,16 @@ impl ContentService {
         }
 
         update.insert("updated_at", now_bson_datetime());
+        let mut changes = doc! { "$set": update };
+        if !unset.is_empty() {
+            changes.insert("$unset", unset);
+        }
         collection
-            .update_one(doc! { "_id": topic_id }, doc! { "$set": update })
+            .update_one(doc! { "_id": topic_id }, changes)
             .await
             .context("Failed to update topic")?;
+        self.invalidate_cache(ContentCacheKind::Topic, &[*topic_id])
+            .await;
 
         self.log_audit(
             claims,
@@ -1009,6 +2624,8 @@ impl ContentService {
     }
 
     pub async fn delete_topic(&self, topic_id: &ObjectId, claims: &JwtClaims) -> Result<()> {
+        let level_ids = self.fetch_level_ids_for_topic(topic_id).await?;
+
         // Удалить все уровни темы
         let levels_collection: Collection<LevelRecord> = self.mongo.collection("levels");
         levels_collection
@@ -1022,6 +2639,10 @@ impl ContentService {
             .delete_one(doc! { "_id": topic_id })
             .await
             .context("Failed to delete topic")?;
+        self.invalidate_cache(ContentCacheKind::Level, &level_ids)
+            .await;
+        self.invalidate_cache(ContentCacheKind::Topic, &[*topic_id])
+            .await;
 
         self.log_audit(
             claims,
@@ -1035,6 +2656,84 @@ impl ContentService {
         Ok(())
     }
 
+    /// Темы с вложенными уровнями и счётчиками шаблонов по статусам
+    /// одним aggregation-запросом; архивные шаблоны не считаются
+    pub async fn content_tree(&self, query: ContentTreeQuery) -> Result<Vec<ContentTreeTopic>> {
+        let status_match = if query.include_deprecated {
+            doc! {}
+        } else {
+            doc! { "status": TopicStatus::Active.as_str() }
+        };
+        let level_match = if query.include_deprecated {
+            doc! { "$expr": { "$eq": ["$topic_id", "$$topic_id"] } }
+        } else {
+            doc! {
+                "$expr": { "$eq": ["$topic_id", "$$topic_id"] },
+                "status": LevelStatus::Active.as_str(),
+            }
+        };
+
+        let pipeline = vec![
+            doc! { "$match": status_match },
+            doc! { "$sort": { "sort_order": 1, "name": 1 } },
+            doc! {
+                "$lookup": {
+                    "from": "levels",
+                    "let": { "topic_id": "$_id" },
+                    "pipeline": [
+                        { "$match": level_match },
+                        { "$sort": { "order": 1 } },
+                        {
+                            "$lookup": {
+                                "from": "templates",
+                                "let": { "level_id": "$_id" },
+                                "pipeline": [
+                                    {
+                                        "$match": {
+                                            "$expr": { "$eq": ["$level_id", "$$level_id"] },
+                                            "archived": { "$ne": true },
+                                        }
+                                    },
+                                    { "$group": { "_id": "$status", "count": { "$sum": 1 } } },
+                                ],
+                                "as": "template_counts",
+                            }
+                        },
+                        {
+                            "$project": {
+                                "name": 1,
+                                "order": 1,
+                                "difficulty": 1,
+                                "status": 1,
+                                "template_counts": 1,
+                            }
+                        },
+                    ],
+                    "as": "levels",
+                }
+            },
+            doc! { "$project": { "slug": 1, "name": 1, "status": 1, "levels": 1 } },
+        ];
+
+        let rows: Vec<Document> = self
+            .mongo
+            .collection::<Document>("topics")
+            .aggregate(pipeline)
+            .await
+            .context("Failed to aggregate content tree")?
+            .try_collect()
+            .await
+            .context("Failed to collect content tree")?;
+
+        rows.into_iter()
+            .map(|row| {
+                let row: ContentTreeTopicRow = mongodb::bson::from_document(row)
+                    .context("Failed to decode content tree row")?;
+                Ok(ContentTreeTopic::from_row(row))
+            })
+            .collect()
+    }
+
     pub async fn list_levels_for_topic(&self, topic_id: &ObjectId) -> Result<Vec<LevelRecord>> {
         let collection: Collection<LevelRecord> = self.mongo.collection("levels");
         let mut cursor = collection
@@ -1058,6 +2757,10 @@ impl ContentService {
         let topic_obj =
             ObjectId::parse_str(&payload.topic_id).with_context(|| "Invalid topic_id")?;
         self.ensure_topic_exists(&topic_obj).await?;
+        // Новый уровень ещё ни у кого не в пререквизитах, поэтому цикл невозможен
+        let prerequisites = self
+            .resolve_level_prerequisites(None, &topic_obj, &payload.prerequisite_level_ids)
+            .await?;
 
         let collection: Collection<LevelRecord> = self.mongo.collection("levels");
         let now = now_bson_datetime();
@@ -1069,6 +2772,7 @@ impl ContentService {
             "description": payload.description,
             "min_pass_percent": payload.min_pass_percent.unwrap_or(80),
             "status": LevelStatus::Active.as_str(),
+            "prerequisite_level_ids": prerequisites,
             "created_at": now,
             "updated_at": now,
         };
@@ -1123,6 +2827,17 @@ impl ContentService {
         if let Some(status) = payload.status {
             update.insert("status", status.as_str());
         }
+        if let Some(prerequisite_ids) = payload.prerequisite_level_ids {
+            let level = collection
+                .find_one(doc! { "_id": level_id })
+                .await
+                .context("Failed to load level")?
+                .ok_or_else(|| anyhow!("Level not found"))?;
+            let prerequisites = self
+                .resolve_level_prerequisites(Some(level_id), &level.topic_id, &prerequisite_ids)
+                .await?;
+            update.insert("prerequisite_level_ids", prerequisites);
+        }
         if update.is_empty() {
             return collection
                 .find_one(doc! { "_id": level_id })
@@ -1136,6 +2851,8 @@ impl ContentService {
             .update_one(doc! { "_id": level_id }, doc! { "$set": update })
             .await
             .context("Failed to update level")?;
+        self.invalidate_cache(ContentCacheKind::Level, &[*level_id])
+            .await;
 
         self.log_audit(
             claims,
@@ -1160,6 +2877,8 @@ impl ContentService {
             .delete_one(doc! { "_id": level_id })
             .await
             .context("Failed to delete level")?;
+        self.invalidate_cache(ContentCacheKind::Level, &[*level_id])
+            .await;
 
         self.log_audit(
             claims,
@@ -1175,6 +2894,7 @@ impl ContentService {
 
     pub async fn reorder_levels(&self, payload: LevelReorderRequest) -> Result<()> {
         let collection: Collection<LevelRecord> = self.mongo.collection("levels");
+        let mut reordered = Vec::with_capacity(payload.ordering.len());
         for (order, level_id) in payload.ordering.iter().enumerate() {
             let level_obj = ObjectId::parse_str(level_id)
                 .with_context(|| format!("Invalid level_id {}", level_id))?;
@@ -1185,7 +2905,10 @@ impl ContentService {
                 )
                 .await
                 .context("Failed to reorder level")?;
+            reordered.push(level_obj);
         }
+        self.invalidate_cache(ContentCacheKind::Level, &reordered)
+            .await;
         Ok(())
     }
 
@@ -1353,26 +3076,313 @@ impl ContentService {
         Ok(())
     }
 
-    pub async fn rule_coverage(&self) -> Result<Vec<RuleCoverage>> {
-        let collection: Collection<RuleRecord> = self.mongo.collection("rules");
-        let mut cursor = collection
+    pub async fn list_blacklist(&self) -> Result<Vec<BlacklistEntry>> {
+        self.mongo
+            .collection::<BlacklistEntry>(CONTENT_BLACKLIST_COLLECTION)
+            .find(Document::new())
+            .sort(doc! { "term": 1, "kind": 1 })
+            .await
+            .context("Failed to list blacklist")?
+            .try_collect()
+            .await
+            .context("Failed to read blacklist")
+    }
+
+    pub async fn create_blacklist_entry(
+        &self,
+        payload: BlacklistEntryRequest,
+        claims: &JwtClaims,
+    ) -> Result<BlacklistEntry> {
+        let term = self.validate_blacklist_entry(&payload, None).await?;
+        let now = now_bson_datetime();
+        let entry = BlacklistEntry {
+            id: ObjectId::new(),
+            term,
+            kind: payload.kind,
+            severity: payload.severity,
+            created_by: Some(claims.sub.clone()),
+            created_at: now,
+            updated_at: now,
+        };
+        self.mongo
+            .collection::<BlacklistEntry>(CONTENT_BLACKLIST_COLLECTION)
+            .insert_one(&entry)
+            .await
+            .context("Failed to create blacklist entry")?;
+        self.blacklist.invalidate().await;
+
+        self.log_audit(
+            claims,
+            "blacklist.create",
+            CONTENT_BLACKLIST_COLLECTION,
+            &entry.id.to_hex(),
+            Some(blacklist_audit_details(&entry)),
+            None,
+        )
+        .await?;
+        Ok(entry)
+    }
+
+    /// `None`, если записи нет
+    pub async fn update_blacklist_entry(
+        &self,
+        entry_id: &ObjectId,
+        payload: BlacklistEntryRequest,
+        claims: &JwtClaims,
+    ) -> Result<Option<BlacklistEntry>> {
+        let term = self
+            .validate_blacklist_entry(&payload, Some(entry_id))
+            .await?;
+        let updated = self
+            .mongo
+            .collection::<BlacklistEntry>(CONTENT_BLACKLIST_COLLECTION)
+            .find_one_and_update(
+                doc! { "_id": entry_id },
+                doc! { "$set": {
+                    "term": term,
+                    "kind": to_bson(&payload.kind)?,
+                    "severity": to_bson(&payload.severity)?,
+                    "updatedAt": now_bson_datetime(),
+                } },
+            )
+            .return_document(ReturnDocument::After)
+            .await
+            .context("Failed to update blacklist entry")?;
+        let Some(entry) = updated else {
+            return Ok(None);
+        };
+        self.blacklist.invalidate().await;
+
+        self.log_audit(
+            claims,
+            "blacklist.update",
+            CONTENT_BLACKLIST_COLLECTION,
+            &entry.id.to_hex(),
+            Some(blacklist_audit_details(&entry)),
+            None,
+        )
+        .await?;
+        Ok(Some(entry))
+    }
+
+    /// `false`, если записи нет
+    pub async fn delete_blacklist_entry(
+        &self,
+        entry_id: &ObjectId,
+        claims: &JwtClaims,
+    ) -> Result<bool> {
+        let deleted = self
+            .mongo
+            .collection::<BlacklistEntry>(CONTENT_BLACKLIST_COLLECTION)
+            .find_one_and_delete(doc! { "_id": entry_id })
+            .await
+            .context("Failed to delete blacklist entry")?;
+        let Some(entry) = deleted else {
+            return Ok(false);
+        };
+        self.blacklist.invalidate().await;
+
+        self.log_audit(
+            claims,
+            "blacklist.delete",
+            CONTENT_BLACKLIST_COLLECTION,
+            &entry.id.to_hex(),
+            Some(blacklist_audit_details(&entry)),
+            None,
+        )
+        .await?;
+        Ok(true)
+    }
+
+    /// Термин без пробелов по краям; выражение должно компилироваться, пара
+    /// (термин, вид) - быть уникальной
+    async fn validate_blacklist_entry(
+        &self,
+        payload: &BlacklistEntryRequest,
+        entry_id: Option<&ObjectId>,
+    ) -> Result<String> {
+        validate_entry(payload.kind, &payload.term)?;
+        let term = payload.term.trim().to_string();
+
+        let mut filter = doc! { "term": &term, "kind": to_bson(&payload.kind)? };
+        if let Some(entry_id) = entry_id {
+            filter.insert("_id", doc! { "$ne": entry_id });
+        }
+        let duplicates = self
+            .mongo
+            .collection::<BlacklistEntry>(CONTENT_BLACKLIST_COLLECTION)
+            .count_documents(filter)
+            .await
+            .context("Failed to check blacklist duplicates")?;
+        if duplicates > 0 {
+            return Err(InvalidBlacklistEntryError(format!("'{}' is already listed", term)).into());
+        }
+        Ok(term)
+    }
+
+    /// Вливает правило-дубликат в `target_id`: шаблоны переводятся на целевое
+    /// правило, исходное помечается `deprecated` с `merged_into`
+    pub async fn merge_rule(
+        &self,
+        source_id: &ObjectId,
+        target_id: &ObjectId,
+        claims: &JwtClaims,
+    ) -> Result<RuleMergeOutcome> {
+        if source_id == target_id {
+            return Ok(RuleMergeOutcome::SameRule);
+        }
+
+        let rules: Collection<RuleRecord> = self.mongo.collection("rules");
+        let source = rules
+            .find_one(doc! { "_id": source_id })
+            .await
+            .context("Failed to load source rule")?;
+        let target = rules
+            .find_one(doc! { "_id": target_id })
+            .await
+            .context("Failed to load target rule")?;
+        let target = match (source, target) {
+            (Some(_), Some(target)) => target,
+            _ => return Ok(RuleMergeOutcome::NotFound),
+        };
+        if target.status == RuleStatus::Deprecated {
+            return Ok(RuleMergeOutcome::TargetDeprecated);
+        }
+
+        let templates: Collection<Document> = self.mongo.collection("templates");
+        let template_ids: Vec<ObjectId> = templates
+            .find(doc! { "rule_ids": source_id })
+            .projection(doc! { "_id": 1 })
+            .await
+            .context("Failed to load templates for rule merge")?
+            .try_collect::<Vec<Document>>()
+            .await
+            .context("Failed to collect templates for rule merge")?
+            .iter()
+            .filter_map(|template| template.get_object_id("_id").ok())
+            .collect();
+
+        if !template_ids.is_empty() {
+            // $addToSet и $pull по одному полю нельзя совместить в одном обновлении;
+            // сначала добавляем цель, чтобы шаблон не остался без правила
+            let filter = doc! { "_id": { "$in": &template_ids } };
+            templates
+                .update_many(
+                    filter.clone(),
+                    doc! {
+                        "$addToSet": { "rule_ids": target_id },
+                        "$set": { "updatedAt": now_bson_datetime() },
+                    },
+                )
+                .await
+                .context("Failed to add target rule to templates")?;
+            templates
+                .update_many(filter, doc! { "$pull": { "rule_ids": source_id } })
+                .await
+                .context("Failed to remove source rule from templates")?;
+        }
+
+        rules
+            .update_one(
+                doc! { "_id": source_id },
+                doc! {
+                    "$set": {
+                        "status": RuleStatus::Deprecated.as_str(),
+                        "merged_into": target_id,
+                        "updatedAt": now_bson_datetime(),
+                    }
+                },
+            )
+            .await
+            .context("Failed to deprecate merged rule")?;
+
+        let affected: Vec<String> = template_ids.iter().map(|id| id.to_hex()).collect();
+        self.log_audit(
+            claims,
+            "rule.merge",
+            "rules",
+            &source_id.to_hex(),
+            Some(doc! {
+                "merged_into": target_id.to_hex(),
+                "template_ids": &affected,
+            }),
+            None,
+        )
+        .await?;
+
+        Ok(RuleMergeOutcome::Merged(RuleMergeResult {
+            source_id: source_id.to_hex(),
+            target_id: target_id.to_hex(),
+            templates_updated: affected.len(),
+        }))
+    }
+
+    /// Число шаблонов на каждое правило: одна агрегация по шаблонам и одно чтение
+    /// правил, правила без шаблонов попадают в ответ с нулём
+    pub async fn rule_coverage(
+        &self,
+        status: Option<TemplateStatus>,
+        topic_id: Option<ObjectId>,
+    ) -> Result<Vec<RuleCoverage>> {
+        let mut template_filter = doc! { "rule_ids.0": { "$exists": true } };
+        if let Some(status) = status {
+            template_filter.insert("status", status.as_str());
+        }
+        let mut pipeline = vec![doc! { "$match": template_filter }];
+        if let Some(topic_id) = topic_id {
+            pipeline.push(doc! {
+                "$lookup": {
+                    "from": "levels",
+                    "localField": "level_id",
+                    "foreignField": "_id",
+                    "as": "level",
+                }
+            });
+            pipeline.push(doc! { "$match": { "level.topic_id": topic_id } });
+        }
+        pipeline.push(doc! { "$unwind": "$rule_ids" });
+        pipeline.push(doc! { "$group": { "_id": "$rule_ids", "count": { "$sum": 1 } } });
+
+        let templates: Collection<Document> = self.mongo.collection("templates");
+        let counts: HashMap<ObjectId, i64> = templates
+            .aggregate(pipeline)
+            .batch_size(RULE_COVERAGE_BATCH_SIZE)
+            .await
+            .context("Failed to aggregate rule coverage")?
+            .try_collect::<Vec<Document>>()
+            .await
+            .context("Failed to read rule coverage")?
+            .into_iter()
+            .filter_map(|row| {
+                let rule_id = row.get_object_id("_id").ok()?;
+                let count = match row.get("count")? {
+                    Bson::Int32(value) => *value as i64,
+                    Bson::Int64(value) => *value,
+                    _ => return None,
+                };
+                Some((rule_id, count))
+            })
+            .collect();
+
+        let rules: Collection<RuleRecord> = self.mongo.collection("rules");
+        let rules: Vec<RuleRecord> = rules
             .find(Document::new())
+            .batch_size(RULE_COVERAGE_BATCH_SIZE)
             .await
-            .context("Failed to list rules for coverage")?;
-        let mut coverage = Vec::new();
-        while let Some(rule) = cursor.try_next().await.context("Cursor failed")? {
-            let template_collection: Collection<TemplateDocument> =
-                self.mongo.collection("templates");
-            let count = template_collection
-                .count_documents(doc! { "rule_ids": { "$in": [&rule.id] } })
-                .await
-                .context("Failed to count templates")?;
-            coverage.push(RuleCoverage {
+            .context("Failed to list rules for coverage")?
+            .try_collect()
+            .await
+            .context("Cursor failed")?;
+
+        Ok(rules
+            .into_iter()
+            .map(|rule| RuleCoverage {
                 rule_id: rule.id.to_hex(),
-                linked_templates: count as i64,
-            });
-        }
-        Ok(coverage)
+                linked_templates: counts.get(&rule.id).copied().unwrap_or(0),
+                slug: Some(rule.slug),
+                name: Some(rule.name),
+            })
+            .collect())
     }
 
     pub async fn list_feature_flags(&self) -> Result<Vec<FeatureFlagRecord>> {
@@ -1426,7 +3436,7 @@ impl ContentService {
             .and_then(|opt| opt.ok_or_else(|| anyhow!("Feature flag not found")))
     }
 
-    pub async fn queue_status(&self) -> Result<QueueStatus> {
+    pub async fn queue_status(&self, idle_secs: Option<u64>) -> Result<QueueStatus> {
         let mut conn = self.redis.clone();
         let length: i64 = redis::cmd("XLEN")
             .arg(&self.stream_name)
@@ -1447,32 +3457,189 @@ impl ContentService {
         let last_event = events
             .into_iter()
             .next()
-            .map(|(id, fields)| ContentChangeEvent {
-                id,
-                template_id: fields.get("template_id").cloned().unwrap_or_default(),
-                action: fields.get("action").cloned().unwrap_or_default(),
-                version: fields.get("version").cloned(),
-                timestamp: fields.get("timestamp").cloned(),
+            .map(|(id, fields)| ContentChangeEvent::from_fields(id, &fields));
+
+        let dead_letter_idle_secs = idle_secs.unwrap_or(self.dead_letter_idle_secs);
+        let idle_ms = dead_letter_idle_secs.saturating_mul(1000);
+        let mut consumer_groups = Vec::new();
+        let mut dead_letters = Vec::new();
+        for group in self.stream_groups().await? {
+            let name: String = info_field(&group, "name").unwrap_or_default();
+
+            let consumers: Vec<HashMap<String, redis::Value>> = redis::cmd("XINFO")
+                .arg("CONSUMERS")
+                .arg(&self.stream_name)
+                .arg(&name)
+                .query_async(&mut conn)
+                .await
+                .context("Failed to read stream consumers")?;
+            let idle_consumers = consumers
+                .iter()
+                .filter(|consumer| {
+                    info_field::<u64>(consumer, "idle").is_some_and(|idle| idle >= idle_ms)
+                })
+                .count() as i64;
+
+            dead_letters.extend(self.stuck_entries(&name, idle_ms).await?);
+            consumer_groups.push(QueueConsumerGroup {
+                consumers: consumers.len() as i64,
+                idle_consumers,
+                pending: info_field(&group, "pending").unwrap_or_default(),
+                last_delivered_id: info_field(&group, "last-delivered-id").unwrap_or_default(),
+                lag: info_field(&group, "lag"),
+                name,
             });
+        }
+
+        Ok(QueueStatus {
+            length,
+            last_event,
+            consumer_groups,
+            dead_letter_idle_secs,
+            dead_letters,
+            dead_letter_stream: self.dead_letter_stream_status().await?,
+        })
+    }
+
+    async fn dead_letter_stream_status(&self) -> Result<QueueDeadLetterStream> {
+        let mut conn = self.redis.clone();
+        let length: i64 = redis::cmd("XLEN")
+            .arg(&self.dead_letter_stream)
+            .query_async(&mut conn)
+            .await
+            .context("Failed to query dead letter stream length")?;
+        let entries: Vec<(String, HashMap<String, String>)> = redis::cmd("XREVRANGE")
+            .arg(&self.dead_letter_stream)
+            .arg("+")
+            .arg("-")
+            .arg("COUNT")
+            .arg(DEAD_LETTER_LIMIT)
+            .query_async(&mut conn)
+            .await
+            .context("Failed to read dead letter stream")?;
+        Ok(QueueDeadLetterStream {
+            name: self.dead_letter_stream.clone(),
+            length,
+            entries: entries
+                .into_iter()
+                .map(|(id, fields)| DeadLetteredEvent::from_fields(id, &fields))
+                .collect(),
+        })
+    }
+
+    /// Переназначает зависшее событие новому консьюмеру (XCLAIM), чтобы
+    /// его подобрал живой обработчик. `None`, если событие ни в одной группе не ждёт XACK
+    pub async fn claim_queue_entry(
+        &self,
+        entry_id: &str,
+        claims: &JwtClaims,
+    ) -> Result<Option<QueueClaimResult>> {
+        let mut conn = self.redis.clone();
+        for group in self.stream_groups().await? {
+            let name: String = info_field(&group, "name").unwrap_or_default();
+            let pending: Vec<(String, String, i64, i64)> = redis::cmd("XPENDING")
+                .arg(&self.stream_name)
+                .arg(&name)
+                .arg(entry_id)
+                .arg(entry_id)
+                .arg(1)
+                .query_async(&mut conn)
+                .await
+                .context("Failed to read pending entry")?;
+            if pending.is_empty() {
+                continue;
+            }
+
+            let consumer = format!("admin-claim-{}", &Uuid::new_v4().simple().to_string()[..8]);
+            let claimed: Vec<redis::Value> = redis::cmd("XCLAIM")
+                .arg(&self.stream_name)
+                .arg(&name)
+                .arg(&consumer)
+                .arg(0)
+                .arg(entry_id)
+                .query_async(&mut conn)
+                .await
+                .context("Failed to claim pending entry")?;
+            if claimed.is_empty() {
+                continue;
+            }
+
+            self.log_audit(
+                claims,
+                "queue.claim",
+                "content_stream",
+                entry_id,
+                Some(doc! {
+                    "group": &name,
+                    "from_consumer": &pending[0].1,
+                    "to_consumer": &consumer,
+                }),
+                None,
+            )
+            .await?;
 
-        Ok(QueueStatus { length, last_event })
+            return Ok(Some(QueueClaimResult {
+                entry_id: entry_id.to_string(),
+                group: name,
+                consumer,
+            }));
+        }
+        Ok(None)
     }
 
-    async fn signal_content_change(&self, template_id: &ObjectId, action: &str) -> Result<()> {
+    async fn stream_groups(&self) -> Result<Vec<HashMap<String, redis::Value>>> {
         let mut conn = self.redis.clone();
-        redis::cmd("XADD")
+        // XINFO GROUPS на несуществующем ключе возвращает ошибку
+        let exists: bool = redis::cmd("EXISTS")
             .arg(&self.stream_name)
-            .arg("*")
-            .arg("template_id")
-            .arg(template_id.to_hex())
-            .arg("action")
-            .arg(action)
-            .arg("timestamp")
-            .arg(Utc::now().timestamp_millis().to_string())
-            .query_async::<String>(&mut conn)
-            .await
-            .context("Failed to publish template change event")?;
-        Ok(())
+            .query_async(&mut conn)
+            .await
+            .context("Failed to check Redis stream")?;
+        if !exists {
+            return Ok(Vec::new());
+        }
+        redis::cmd("XINFO")
+            .arg("GROUPS")
+            .arg(&self.stream_name)
+            .query_async(&mut conn)
+            .await
+            .context("Failed to read stream consumer groups")
+    }
+
+    /// Неподтверждённые события группы, которые ждут дольше `idle_ms`, с их полями
+    async fn stuck_entries(&self, group: &str, idle_ms: u64) -> Result<Vec<QueueDeadLetter>> {
+        let mut conn = self.redis.clone();
+        let pending: Vec<(String, String, i64, i64)> = redis::cmd("XPENDING")
+            .arg(&self.stream_name)
+            .arg(group)
+            .arg("IDLE")
+            .arg(idle_ms)
+            .arg("-")
+            .arg("+")
+            .arg(DEAD_LETTER_LIMIT)
+            .query_async(&mut conn)
+            .await
+            .context("Failed to read pending entries")?;
+
+        let mut entries = Vec::with_capacity(pending.len());
+        for (id, consumer, idle_ms, delivery_count) in pending {
+            let payload: Vec<(String, BTreeMap<String, String>)> = redis::cmd("XRANGE")
+                .arg(&self.stream_name)
+                .arg(&id)
+                .arg(&id)
+                .query_async(&mut conn)
+                .await
+                .context("Failed to read pending entry payload")?;
+            entries.push(QueueDeadLetter {
+                group: group.to_string(),
+                id,
+                consumer,
+                idle_ms,
+                delivery_count,
+                payload: payload.into_iter().next().map(|(_, fields)| fields),
+            });
+        }
+        Ok(entries)
     }
 
     async fn get_template_summary(&self, id: &ObjectId) -> Result<TemplateSummary> {
@@ -1492,40 +3659,43 @@ impl ContentService {
         Ok(TemplateSummary::from_doc(&template, &level_map, &topic_map))
     }
 
-    async fn fetch_levels(&self, ids: &[ObjectId]) -> Result<HashMap<ObjectId, LevelRecord>> {
-        if ids.is_empty() {
-            return Ok(HashMap::new());
+    /// Сбой инвалидации не отменяет изменение: запись доживёт до конца TTL
+    async fn invalidate_cache(&self, kind: ContentCacheKind, ids: &[ObjectId]) {
+        if let Err(err) = self.cache.invalidate(kind, ids).await {
+            tracing::warn!("Failed to invalidate {} cache: {:#}", kind.as_str(), err);
         }
+    }
 
+    async fn fetch_levels(&self, ids: &[ObjectId]) -> Result<HashMap<ObjectId, LevelRecord>> {
         let collection: Collection<LevelRecord> = self.mongo.collection("levels");
-        let mut cursor = collection
-            .find(doc! { "_id": { "$in": ids } })
+        self.cache
+            .get_many(ContentCacheKind::Level, ids, |missing| async move {
+                let levels: Vec<LevelRecord> = collection
+                    .find(doc! { "_id": { "$in": missing } })
+                    .await
+                    .context("Failed to load levels")?
+                    .try_collect()
+                    .await
+                    .context("Cursor failed")?;
+                Ok(levels.into_iter().map(|level| (level.id, level)).collect())
+            })
             .await
-            .context("Failed to load levels")?;
-
-        let mut map = HashMap::new();
-        while let Some(level) = cursor.try_next().await.context("Cursor failed")? {
-            map.insert(level.id, level);
-        }
-        Ok(map)
     }
 
     async fn fetch_topics(&self, ids: &[ObjectId]) -> Result<HashMap<ObjectId, TopicRecord>> {
-        if ids.is_empty() {
-            return Ok(HashMap::new());
-        }
-
         let collection: Collection<TopicRecord> = self.mongo.collection("topics");
-        let mut cursor = collection
-            .find(doc! { "_id": { "$in": ids } })
+        self.cache
+            .get_many(ContentCacheKind::Topic, ids, |missing| async move {
+                let topics: Vec<TopicRecord> = collection
+                    .find(doc! { "_id": { "$in": missing } })
+                    .await
+                    .context("Failed to load topics")?
+                    .try_collect()
+                    .await
+                    .context("Cursor failed")?;
+                Ok(topics.into_iter().map(|topic| (topic.id, topic)).collect())
+            })
             .await
-            .context("Failed to load topics")?;
-
-        let mut map = HashMap::new();
-        while let Some(topic) = cursor.try_next().await.context("Cursor failed")? {
-            map.insert(topic.id, topic);
-        }
-        Ok(map)
     }
 
     async fn fetch_level_ids_for_topic(&self, topic_id: &ObjectId) -> Result<Vec<ObjectId>> {
@@ -1557,6 +3727,77 @@ impl ContentService {
         }
     }
 
+    /// Проверяет пререквизиты уровня: существуют, из той же темы и без циклов.
+    /// `level_id` - редактируемый уровень (`None` при создании)
+    async fn resolve_level_prerequisites(
+        &self,
+        level_id: Option<&ObjectId>,
+        topic_id: &ObjectId,
+        raw_ids: &[String],
+    ) -> Result<Vec<ObjectId>> {
+        let mut prerequisites: Vec<ObjectId> = Vec::new();
+        for raw in raw_ids {
+            let id = ObjectId::parse_str(raw)
+                .map_err(|_| LevelPrerequisiteError::InvalidId(raw.clone()))?;
+            if Some(&id) == level_id {
+                return Err(LevelPrerequisiteError::SelfReference.into());
+            }
+            if !prerequisites.contains(&id) {
+                prerequisites.push(id);
+            }
+        }
+        if prerequisites.is_empty() {
+            return Ok(prerequisites);
+        }
+
+        let collection: Collection<LevelRecord> = self.mongo.collection("levels");
+        let levels: Vec<LevelRecord> = collection
+            .find(doc! { "$or": [{ "topic_id": topic_id }, { "_id": { "$in": &prerequisites } }] })
+            .await
+            .context("Failed to load levels for prerequisite check")?
+            .try_collect()
+            .await
+            .context("Failed to collect levels for prerequisite check")?;
+
+        let topics: HashMap<ObjectId, ObjectId> = levels
+            .iter()
+            .map(|level| (level.id, level.topic_id))
+            .collect();
+        let missing: Vec<String> = prerequisites
+            .iter()
+            .filter(|id| !topics.contains_key(id))
+            .map(|id| id.to_hex())
+            .collect();
+        if !missing.is_empty() {
+            return Err(LevelPrerequisiteError::NotFound(missing).into());
+        }
+        let foreign: Vec<String> = prerequisites
+            .iter()
+            .filter(|id| topics.get(id) != Some(topic_id))
+            .map(|id| id.to_hex())
+            .collect();
+        if !foreign.is_empty() {
+            return Err(LevelPrerequisiteError::OtherTopic(foreign).into());
+        }
+
+        if let Some(level_id) = level_id {
+            let mut graph: HashMap<ObjectId, Vec<ObjectId>> = levels
+                .into_iter()
+                .filter(|level| &level.topic_id == topic_id)
+                .map(|level| (level.id, level.prerequisite_level_ids))
+                .collect();
+            graph.insert(*level_id, prerequisites.clone());
+            if let Some(cycle) = find_prerequisite_cycle(&graph) {
+                return Err(LevelPrerequisiteError::Cycle(
+                    cycle.iter().map(|id| id.to_hex()).collect(),
+                )
+                .into());
+            }
+        }
+
+        Ok(prerequisites)
+    }
+
     async fn ensure_topic_exists(&self, topic_id: &ObjectId) -> Result<()> {
         let collection: Collection<TopicRecord> = self.mongo.collection("topics");
         let count = collection
@@ -1603,12 +3844,14 @@ impl ContentService {
         &self,
         slug: &str,
         level_id: &ObjectId,
+        locale: &str,
         existing: Option<&ObjectId>,
     ) -> Result<()> {
         let collection: Collection<Document> = self.mongo.collection("templates");
         let mut filter = doc! {
             "slug": slug,
-            "level_id": level_id
+            "level_id": level_id,
+            "locale": locale_filter(locale),
         };
 
         if let Some(exclude_id) = existing {
@@ -1621,22 +3864,38 @@ impl ContentService {
             .context("Failed to check unique slug")?;
 
         if count > 0 {
-            Err(anyhow!("Template slug already exists for this level"))
+            Err(anyhow!(
+                "Template slug already exists for this level and locale"
+            ))
         } else {
             Ok(())
         }
     }
 
-    fn validate_content(&self, content: &str) -> Result<()> {
-        let problems = detect_blacklist(content);
-        if !problems.is_empty() {
-            return Err(anyhow!("Blacklist violation: {:?}", problems));
-        }
-        let pii = self.scan_pii(content);
-        if !pii.is_empty() {
-            return Err(anyhow!("PII detected: {:?}", pii));
+    /// Переводы для записи в шаблон; стоп-лист и ПДн в них проверяет `check_fields`
+    fn translations_document(
+        &self,
+        translations: BTreeMap<String, TemplateTranslationRequest>,
+        base_locale: &str,
+    ) -> Result<Document> {
+        let mut document = Document::new();
+        for (locale, translation) in translations {
+            if !is_valid_locale(&locale) || locale == base_locale {
+                return Err(InvalidLocaleError(locale).into());
+            }
+            let mut entry = Document::new();
+            if let Some(content) = translation.content {
+                entry.insert("content", content);
+            }
+            if let Some(metadata) = translation.metadata {
+                entry.insert(
+                    "metadata",
+                    json_to_document(Some(metadata), "translations")?,
+                );
+            }
+            document.insert(locale, entry);
         }
-        Ok(())
+        Ok(document)
     }
 
     fn validate_slug(&self, slug: &str) -> Result<()> {
@@ -1657,15 +3916,89 @@ impl ContentService {
         Ok(())
     }
 
-    fn scan_pii(&self, content: &str) -> Vec<String> {
-        let mut matches = Vec::new();
-        if EMAIL_REGEX.is_match(content) {
-            matches.push("email".to_string());
+    /// Проверка полей шаблона (`content`, `params`, `metadata`, `translations`): стоп-лист
+    /// по текстам задания и переводов, ПДн по всем строкам. Блокирующие совпадения - ошибка
+    async fn check_fields(&self, fields: &Document) -> Result<FieldWarnings> {
+        let blacklist = self.blacklist.get(&self.mongo).await;
+        let mut hits = Vec::new();
+        if let Ok(content) = fields.get_str("content") {
+            hits.extend(blacklist.detect("content", content));
+        }
+        if let Ok(translations) = fields.get_document("translations") {
+            for (locale, translation) in translations {
+                let content = translation
+                    .as_document()
+                    .and_then(|translation| translation.get_str("content").ok());
+                if let Some(content) = content {
+                    let path = format!("translations.{}.content", locale);
+                    hits.extend(blacklist.detect(&path, content));
+                }
+            }
         }
-        if PHONE_REGEX.is_match(content) {
-            matches.push("phone".to_string());
+        let (blocked, warned): (Vec<_>, Vec<_>) = hits
+            .into_iter()
+            .partition(|hit| hit.severity == BlacklistSeverity::Block);
+        if !blocked.is_empty() {
+            return Err(
+                BlacklistViolationError(blocked.iter().map(BlacklistHit::flag).collect()).into(),
+            );
+        }
+
+        let scanner = self.pii_patterns.get(&self.mongo).await;
+        let (blocked, flagged): (Vec<_>, Vec<_>) = scanner
+            .scan_document("", fields)
+            .into_iter()
+            .partition(|found| found.severity == PiiSeverity::Block);
+        if !blocked.is_empty() {
+            return Err(PiiDetectedError(blocked.iter().map(PiiMatch::flag).collect()).into());
         }
-        matches
+
+        Ok(FieldWarnings {
+            pii_flags: flagged.iter().map(PiiMatch::flag).collect(),
+            blacklist_warnings: warned.iter().map(BlacklistHit::flag).collect(),
+        })
+    }
+
+    /// Линтер по полям шаблона; уровень и связанные правила берутся из базы
+    async fn lint_fields(
+        &self,
+        content: &str,
+        params: &Document,
+        difficulty: Option<&str>,
+        level_id: &ObjectId,
+        rule_ids: &[ObjectId],
+    ) -> Result<Vec<LintFinding>> {
+        let levels = self.fetch_levels(std::slice::from_ref(level_id)).await?;
+        let rules: Vec<RuleRecord> = if rule_ids.is_empty() {
+            Vec::new()
+        } else {
+            self.mongo
+                .collection::<RuleRecord>("rules")
+                .find(doc! { "_id": { "$in": rule_ids } })
+                .await
+                .context("Failed to load rules for lint")?
+                .try_collect()
+                .await
+                .context("Cursor failed")?
+        };
+        Ok(TemplateLinter::default().lint(&LintTarget {
+            content,
+            params,
+            difficulty,
+            level: levels.get(level_id),
+            rules: rules.iter().collect(),
+        }))
+    }
+
+    async fn lint_document(&self, template: &TemplateDocument) -> Result<Vec<LintFinding>> {
+        self.lint_fields(
+            &template.content,
+            &template.params,
+            template.difficulty.as_deref(),
+            &template.level_id,
+            &template.rule_ids,
+        )
+        .await
     }
 
     async fn log_audit(
@@ -1678,7 +4011,23 @@ impl ContentService {
         reason: Option<String>,
     ) -> Result<()> {
         let collection: Collection<Document> = self.mongo.collection("audit_log");
-        let record = doc! {
+        collection
+            .insert_one(self.audit_record(claims, action, target, target_id, details, reason))
+            .await
+            .context("Failed to write audit log")?;
+        Ok(())
+    }
+
+    fn audit_record(
+        &self,
+        claims: &JwtClaims,
+        action: &str,
+        target: &str,
+        target_id: &str,
+        details: Option<Document>,
+        reason: Option<String>,
+    ) -> Document {
+        doc! {
             "actor_id": claims.sub.clone(),
             "actor_role": claims.role.clone(),
             "action": action,
@@ -1687,12 +4036,7 @@ impl ContentService {
             "details": details.unwrap_or_default(),
             "reason": reason,
             "created_at": now_bson_datetime(),
-        };
-        collection
-            .insert_one(record)
-            .await
-            .context("Failed to write audit log")?;
-        Ok(())
+        }
     }
     async fn normalize_template_timestamp_fields(&self) -> Result<()> {
         let collection: Collection<Document> = self.mongo.collection("templates");
@@ -1745,8 +4089,73 @@ impl ContentService {
     }
 }
 
-fn json_to_document(value: Option<Value>) -> Result<Document> {
+/// Проверяет `params.answers` (`{ accepted: [...], pattern: "...", parts: [...] }`), которые
+/// копируются в сгенерированные задания, и режим начисления `scoring`/`points_per_part`
+fn validate_template_answers(params: &Document) -> Result<(), InvalidAnswersError> {
+    let invalid = |reason: &str| InvalidAnswersError {
+        reason: reason.to_string(),
+    };
+
+    let answers: TaskAnswers = match params.get("answers") {
+        None => TaskAnswers::default(),
+        Some(Bson::Document(answers)) => {
+            mongodb::bson::from_document(answers.clone()).map_err(|error| InvalidAnswersError {
+                reason: error.to_string(),
+            })?
+        }
+        Some(_) => return Err(invalid("expected an object")),
+    };
+    validate_answer_set(&answers)?;
+    for part in &answers.parts {
+        if part.accepted.is_empty() && part.pattern.is_none() {
+            return Err(invalid("each part needs accepted answers or a pattern"));
+        }
+        if !part.parts.is_empty() {
+            return Err(invalid("parts cannot be nested"));
+        }
+        validate_answer_set(part)?;
+    }
+
+    match params.get("scoring") {
+        None => {}
+        Some(Bson::String(mode)) if mode == "per_part" && answers.parts.is_empty() => {
+            return Err(invalid("per_part scoring requires answers.parts"));
+        }
+        Some(Bson::String(mode)) if mode == "exact" || mode == "per_part" => {}
+        Some(_) => return Err(invalid("scoring must be \"exact\" or \"per_part\"")),
+    }
+    match params.get("points_per_part") {
+        None => {}
+        Some(Bson::Int32(points)) if *points > 0 => {}
+        Some(Bson::Int64(points)) if *points > 0 && *points <= i64::from(i32::MAX) => {}
+        Some(_) => return Err(invalid("points_per_part must be a positive integer")),
+    }
+    Ok(())
+}
+
+fn validate_answer_set(answers: &TaskAnswers) -> Result<(), InvalidAnswersError> {
+    if answers
+        .accepted
+        .iter()
+        .any(|answer| answer.trim().is_empty())
+    {
+        return Err(InvalidAnswersError {
+            reason: "accepted answers cannot be empty".to_string(),
+        });
+    }
+    if let Some(pattern) = answers.pattern.as_deref() {
+        validate_answer_pattern(pattern).map_err(|error| InvalidAnswersError {
+            reason: error.to_string(),
+        })?;
+    }
+    Ok(())
+}
+
+fn json_to_document(value: Option<Value>, field: &'static str) -> Result<Document> {
     if let Some(json) = value {
+        if exceeds_depth(&json, MAX_JSON_DEPTH) {
+            return Err(JsonTooDeepError { field }.into());
+        }
         let bson = to_bson(&json).context("Failed to convert JSON to BSON")?;
         match bson {
             Bson::Document(doc) => Ok(doc),
@@ -1757,6 +4166,127 @@ fn json_to_document(value: Option<Value>) -> Result<Document> {
     }
 }
 
+/// Пакет экспорта: ObjectId заменяются slug'ами, связанные сущности - без повторов
+fn build_template_bundle(
+    templates: &[TemplateDocument],
+    levels: &HashMap<ObjectId, LevelRecord>,
+    topics: &HashMap<ObjectId, TopicRecord>,
+    rules: &HashMap<ObjectId, RuleRecord>,
+    exported_at: String,
+) -> Result<TemplateBundle> {
+    let mut bundle_topics = BTreeMap::new();
+    let mut bundle_levels = BTreeMap::new();
+    let mut bundle_rules = BTreeMap::new();
+    let mut bundle_templates = Vec::with_capacity(templates.len());
+
+    for template in templates {
+        let level = levels
+            .get(&template.level_id)
+            .ok_or_else(|| anyhow!("Template {} references a missing level", template.slug))?;
+        let topic = topics
+            .get(&level.topic_id)
+            .ok_or_else(|| anyhow!("Template {} references a missing topic", template.slug))?;
+        bundle_topics
+            .entry(topic.slug.clone())
+            .or_insert_with(|| BundleTopic {
+                slug: topic.slug.clone(),
+                name: topic.name.clone(),
+                description: topic.description.clone(),
+                icon_url: topic.icon_url.clone(),
+                status: Some(topic.status),
+                age_band: topic.age_band,
+            });
+        bundle_levels
+            .entry((topic.slug.clone(), level.order, level.name.clone()))
+            .or_insert_with(|| BundleLevel {
+                topic_slug: topic.slug.clone(),
+                name: level.name.clone(),
+                difficulty: level.difficulty,
+                description: level.description.clone(),
+                min_pass_percent: Some(level.min_pass_percent),
+                order: Some(level.order),
+            });
+
+        let mut rule_slugs = Vec::with_capacity(template.rule_ids.len());
+        for rule_id in &template.rule_ids {
+            let rule = rules
+                .get(rule_id)
+                .ok_or_else(|| anyhow!("Template {} references a missing rule", template.slug))?;
+            bundle_rules
+                .entry(rule.slug.clone())
+                .or_insert_with(|| BundleRule {
+                    slug: rule.slug.clone(),
+                    name: rule.name.clone(),
+                    category: rule.category.clone(),
+                    description: rule.description.clone(),
+                    examples: rule.examples.clone(),
+                    exceptions: rule.exceptions.clone(),
+                    sources: rule.sources.clone(),
+                    status: Some(rule.status),
+                });
+            rule_slugs.push(rule.slug.clone());
+        }
+
+        bundle_templates.push(BundleTemplate {
+            slug: template.slug.clone(),
+            topic_slug: topic.slug.clone(),
+            level_name: level.name.clone(),
+            rule_slugs,
+            params: Bson::Document(template.params.clone()).into_relaxed_extjson(),
+            metadata: Bson::Document(template.metadata.clone()).into_relaxed_extjson(),
+            content: template.content.clone(),
+            difficulty: template.difficulty.clone(),
+            source_refs: template.source_refs.clone(),
+            age_band: template.age_band,
+            locale: template.locale.clone(),
+            translations: template
+                .translations
+                .iter()
+                .map(|(locale, translation)| {
+                    let translation = TemplateTranslationRequest {
+                        content: translation.content.clone(),
+                        metadata: translation
+                            .metadata
+                            .clone()
+                            .map(|metadata| Bson::Document(metadata).into_relaxed_extjson()),
+                    };
+                    (locale.clone(), translation)
+                })
+                .collect(),
+        });
+    }
+
+    Ok(TemplateBundle {
+        format: TEMPLATE_BUNDLE_FORMAT,
+        exported_at,
+        topics: bundle_topics.into_values().collect(),
+        levels: bundle_levels.into_values().collect(),
+        rules: bundle_rules.into_values().collect(),
+        templates: bundle_templates,
+    })
+}
+
+/// Вложенность JSON глубже `max` (`{}` и `[]` - один уровень). Обход без рекурсии,
+/// чтобы сама проверка не упиралась в стек
+fn exceeds_depth(value: &Value, max: usize) -> bool {
+    let mut stack = vec![(value, 0)];
+    while let Some((value, depth)) = stack.pop() {
+        let depth = match value {
+            Value::Object(_) | Value::Array(_) => depth + 1,
+            _ => continue,
+        };
+        if depth > max {
+            return true;
+        }
+        match value {
+            Value::Object(map) => stack.extend(map.values().map(|child| (child, depth))),
+            Value::Array(items) => stack.extend(items.iter().map(|child| (child, depth))),
+            _ => {}
+        }
+    }
+    false
+}
+
 fn parse_object_id_list(values: &[String]) -> Result<Vec<ObjectId>> {
     values
         .iter()
@@ -1771,19 +4301,108 @@ fn parse_object_id_list(values: &[String]) -> Result<Vec<ObjectId>> {
         .collect()
 }
 
-fn detect_blacklist(content: &str) -> Vec<String> {
-    BLACKLISTED_TERMS
-        .iter()
-        .filter_map(|word| {
-            if content.to_lowercase().contains(word) {
-                Some(word.to_string())
-            } else {
-                None
+fn blacklist_audit_details(entry: &BlacklistEntry) -> Document {
+    doc! {
+        "term": &entry.term,
+        "kind": to_bson(&entry.kind).unwrap_or(Bson::Null),
+        "severity": to_bson(&entry.severity).unwrap_or(Bson::Null),
+    }
+}
+
+/// Поиск цикла в графе «уровень -> его пререквизиты» обходом в глубину.
+/// Возвращает путь цикла, где первая вершина повторяется в конце
+pub fn find_prerequisite_cycle<K>(graph: &HashMap<K, Vec<K>>) -> Option<Vec<K>>
+where
+    K: Copy + Eq + Hash + Ord,
+{
+    fn visit<K: Copy + Eq + Hash>(
+        node: K,
+        graph: &HashMap<K, Vec<K>>,
+        done: &mut HashSet<K>,
+        path: &mut Vec<K>,
+    ) -> Option<Vec<K>> {
+        if let Some(start) = path.iter().position(|visited| *visited == node) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(node);
+            return Some(cycle);
+        }
+        if done.contains(&node) {
+            return None;
+        }
+
+        path.push(node);
+        for next in graph.get(&node).into_iter().flatten() {
+            if let Some(cycle) = visit(*next, graph, done, path) {
+                return Some(cycle);
             }
+        }
+        path.pop();
+        done.insert(node);
+        None
+    }
+
+    // Сортировка делает найденный цикл детерминированным
+    let mut nodes: Vec<K> = graph.keys().copied().collect();
+    nodes.sort();
+    let mut done = HashSet::new();
+    nodes
+        .into_iter()
+        .find_map(|node| visit(node, graph, &mut done, &mut Vec::new()))
+}
+
+fn info_field<T: redis::FromRedisValue>(
+    info: &HashMap<String, redis::Value>,
+    key: &str,
+) -> Option<T> {
+    info.get(key)
+        .and_then(|value| redis::from_redis_value_ref(value).ok())
+}
+
+/// Фильтр по языку: шаблоны без поля `locale` (созданные до его появления) - на языке по умолчанию
+/// Вход в текущий статус; у шаблонов без `status_changed_at` - последнее изменение
+fn queue_entered_at(template: &TemplateDocument) -> mongodb::bson::DateTime {
+    template.status_changed_at.unwrap_or(template.updated_at)
+}
+
+fn time_in_queue_secs(template: &TemplateDocument, now: &mongodb::bson::DateTime) -> i64 {
+    ((now.timestamp_millis() - queue_entered_at(template).timestamp_millis()) / 1000).max(0)
+}
+
+fn record_moderation_queue_depth(templates: &[TemplateDocument], now: &mongodb::bson::DateTime) {
+    let mut depth: HashMap<ModerationQueueAge, i64> = HashMap::new();
+    for template in templates {
+        *depth
+            .entry(ModerationQueueAge::from_secs(time_in_queue_secs(
+                template, now,
+            )))
+            .or_default() += 1;
+    }
+    for age in ModerationQueueAge::ALL {
+        MODERATION_QUEUE_DEPTH
+            .with_label_values(&[age.as_str()])
+            .set(depth.get(&age).copied().unwrap_or(0));
+    }
+}
+
+fn snapshot_fields(template: &Document, fields: &[&str]) -> Document {
+    fields
+        .iter()
+        .filter_map(|field| {
+            template
+                .get(*field)
+                .map(|value| (field.to_string(), value.clone()))
         })
         .collect()
 }
 
+fn locale_filter(locale: &str) -> Bson {
+    if locale == DEFAULT_LOCALE {
+        Bson::Document(doc! { "$in": [DEFAULT_LOCALE, Bson::Null] })
+    } else {
+        Bson::String(locale.to_string())
+    }
+}
+
 fn now_bson_datetime() -> mongodb::bson::DateTime {
     mongodb::bson::DateTime::from_system_time(SystemTime::now())
 }
@@ -1800,6 +4419,7 @@ fn bson_to_iso(dt: &mongodb::bson::DateTime) -> String {
 mod tests {
     use super::*;
     use crate::models::content::{TemplateDocument, TemplateStatus};
+    use serde_json::json;
 
     fn make_template(slug: &str, level_id: ObjectId, rule_ids: Vec<ObjectId>) -> TemplateDocument {
         TemplateDocument {
@@ -1811,18 +4431,73 @@ mod tests {
             metadata: Document::new(),
             content: "test".to_string(),
             difficulty: Some("a1".to_string()),
+            age_band: None,
+            locale: DEFAULT_LOCALE.to_string(),
+            translations: BTreeMap::new(),
             status: TemplateStatus::Draft,
             version: 1,
             source_refs: Vec::new(),
             pii_flags: Vec::new(),
+            blacklist_warnings: Vec::new(),
+            lint_findings: Vec::new(),
             reviewers: Vec::new(),
+            assigned_reviewer: None,
             created_by: None,
             published_at: None,
+            status_changed_at: None,
+            archived: false,
             created_at: now_bson_datetime(),
             updated_at: now_bson_datetime(),
         }
     }
 
+    #[test]
+    fn template_answers_are_validated() {
+        assert!(validate_template_answers(&doc! { "question_count": 5 }).is_ok());
+        assert!(validate_template_answers(&doc! {
+            "answers": { "accepted": ["не знаю", "не знаю."], "pattern": r"не\s?знаю\.?" }
+        })
+        .is_ok());
+        assert!(validate_template_answers(&doc! {
+            "scoring": "per_part",
+            "points_per_part": 4,
+            "answers": { "parts": [{ "accepted": ["о"] }, { "pattern": "[ао]" }] },
+        })
+        .is_ok());
+
+        for params in [
+            doc! { "answers": { "pattern": "(a{1000}){1000}" } },
+            doc! { "answers": { "pattern": "(unclosed" } },
+            doc! { "answers": { "accepted": "не знаю" } },
+            doc! { "answers": { "accepted": [" "] } },
+            doc! { "answers": "не знаю" },
+            doc! { "answers": { "parts": [{ "accepted": [] }] } },
+            doc! { "answers": { "parts": [{ "pattern": "(a{1000}){1000}" }] } },
+            doc! { "scoring": "per_part" },
+            doc! { "scoring": "partial", "answers": { "parts": [{ "accepted": ["а"] }] } },
+            doc! { "answers": { "parts": [{ "accepted": ["а"] }] }, "points_per_part": 0 },
+            doc! { "answers": { "parts": [{ "accepted": ["а"] }] }, "points_per_part": 2.5 },
+        ] {
+            assert!(
+                validate_template_answers(&params).is_err(),
+                "params {params:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_json_nested_deeper_than_limit() {
+        let nested = |levels: usize| (1..levels).fold(json!([]), |inner, _| json!({ "a": inner }));
+
+        assert!(!exceeds_depth(&nested(MAX_JSON_DEPTH), MAX_JSON_DEPTH));
+        assert!(exceeds_depth(&nested(MAX_JSON_DEPTH + 1), MAX_JSON_DEPTH));
+        assert!(json_to_document(Some(nested(MAX_JSON_DEPTH)), "params").is_ok());
+
+        let error = json_to_document(Some(nested(MAX_JSON_DEPTH + 1)), "params").unwrap_err();
+        let too_deep = error.downcast_ref::<JsonTooDeepError>().unwrap();
+        assert_eq!(too_deep.field, "params");
+    }
+
     #[test]
     fn duplicates_detect_same_slug() {
         let level = ObjectId::new();
@@ -1847,4 +4522,119 @@ mod tests {
         assert_eq!(duplicates.len(), 1);
         assert!(duplicates[0].reason.contains("Matching rule"));
     }
+
+    fn graph(edges: &[(u8, &[u8])]) -> HashMap<u8, Vec<u8>> {
+        edges
+            .iter()
+            .map(|(node, prerequisites)| (*node, prerequisites.to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn prerequisite_chain_and_dag_have_no_cycle() {
+        assert!(find_prerequisite_cycle(&graph(&[(1, &[]), (2, &[1]), (3, &[2])])).is_none());
+        // Ромб: 4 зависит от 2 и 3, оба - от 1
+        let diamond = graph(&[(1, &[]), (2, &[1]), (3, &[1]), (4, &[2, 3])]);
+        assert!(find_prerequisite_cycle(&diamond).is_none());
+        assert!(find_prerequisite_cycle::<u8>(&HashMap::new()).is_none());
+    }
+
+    #[test]
+    fn prerequisite_cycle_is_reported_as_path() {
+        let cyclic = graph(&[(1, &[3]), (2, &[1]), (3, &[2])]);
+        assert_eq!(find_prerequisite_cycle(&cyclic), Some(vec![1, 3, 2, 1]));
+
+        let self_loop = graph(&[(1, &[]), (2, &[2])]);
+        assert_eq!(find_prerequisite_cycle(&self_loop), Some(vec![2, 2]));
+    }
+
+    #[test]
+    fn prerequisite_cycle_ignores_edges_to_unknown_levels() {
+        let dangling = graph(&[(1, &[9]), (2, &[1, 9])]);
+        assert!(find_prerequisite_cycle(&dangling).is_none());
+    }
+
+    #[test]
+    fn bundle_replaces_ids_with_slugs_and_dedupes_references() {
+        let now = now_bson_datetime();
+        let topic = TopicRecord {
+            id: ObjectId::new(),
+            slug: "orthography".to_string(),
+            name: "Орфография".to_string(),
+            description: String::new(),
+            icon_url: None,
+            sort_order: 0,
+            status: TopicStatus::Active,
+            age_band: None,
+            created_at: now,
+            updated_at: now,
+        };
+        let level = LevelRecord {
+            id: ObjectId::new(),
+            topic_id: topic.id,
+            order: 1,
+            name: "Безударные гласные".to_string(),
+            difficulty: crate::models::content::LevelDifficulty::A1,
+            description: String::new(),
+            min_pass_percent: 80,
+            status: LevelStatus::Active,
+            prerequisite_level_ids: Vec::new(),
+            created_at: now,
+            updated_at: now,
+        };
+        let rule = |slug: &str| RuleRecord {
+            id: ObjectId::new(),
+            slug: slug.to_string(),
+            name: slug.to_string(),
+            category: "orthography".to_string(),
+            description: String::new(),
+            examples: Vec::new(),
+            exceptions: Vec::new(),
+            sources: Vec::new(),
+            status: RuleStatus::Active,
+            merged_into: None,
+            created_at: now,
+            updated_at: now,
+        };
+        let (first_rule, second_rule) = (rule("rule-b"), rule("rule-a"));
+
+        let mut first = make_template("first", level.id, vec![first_rule.id, second_rule.id]);
+        first.params = doc! { "answers": { "accepted": ["о"] }, "points": 2 };
+        let second = make_template("second", level.id, vec![first_rule.id]);
+
+        let bundle = build_template_bundle(
+            &[first, second],
+            &HashMap::from([(level.id, level)]),
+            &HashMap::from([(topic.id, topic)]),
+            &HashMap::from([(first_rule.id, first_rule), (second_rule.id, second_rule)]),
+            "2026-10-16T00:00:00+00:00".to_string(),
+        )
+        .unwrap();
+
+        assert_eq!(bundle.format, TEMPLATE_BUNDLE_FORMAT);
+        assert_eq!(bundle.topics.len(), 1);
+        assert_eq!(bundle.levels.len(), 1);
+        assert_eq!(bundle.levels[0].topic_slug, "orthography");
+        let rule_slugs: Vec<&str> = bundle.rules.iter().map(|rule| rule.slug.as_str()).collect();
+        assert_eq!(rule_slugs, vec!["rule-a", "rule-b"]);
+        assert_eq!(bundle.templates[0].slug, "first");
+        assert_eq!(bundle.templates[0].level_name, "Безударные гласные");
+        assert_eq!(bundle.templates[0].rule_slugs, vec!["rule-b", "rule-a"]);
+        assert_eq!(
+            bundle.templates[0].params,
+            json!({ "answers": { "accepted": ["о"] }, "points": 2 })
+        );
+
+        // Шаблон со ссылкой на удалённый уровень не экспортируется молча
+        let orphan = make_template("orphan", ObjectId::new(), Vec::new());
+        let err = build_template_bundle(
+            &[orphan],
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashMap::new(),
+            String::new(),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("missing level"));
+    }
 }
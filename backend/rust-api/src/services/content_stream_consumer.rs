@@ -0,0 +1,401 @@
+//! Консьюмер стрима изменений контента (`content.stream_name`) внутри API.
+//!
+//! Стрим читается в consumer group (XREADGROUP), событие передаётся всем обработчикам
+//! по очереди и подтверждается (XACK), только когда все они отработали. Доставка -
+//! "хотя бы один раз": после перезапуска группа продолжает с последнего выданного
+//! события, а неподтверждённые события (в том числе упавших консьюмеров) через
+//! `consumer_retry_idle_secs` забираются повторно (XCLAIM). Поэтому обработчики обязаны
+//! быть идемпотентными. Событие, которое не удалось обработать `consumer_max_attempts`
+//! раз, переносится в `dead_letter_stream` и подтверждается, чтобы не блокировать очередь.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use mongodb::{
+    bson::{doc, oid::ObjectId, DateTime as BsonDateTime},
+    Collection, Database,
+};
+use redis::aio::{ConnectionManager, ConnectionManagerConfig};
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+use crate::{
+    config::ContentSettings,
+    metrics::CONTENT_STREAM_EVENTS_TOTAL,
+    models::content::{ContentChangeEvent, DeadLetteredEvent},
+    services::{
+        content_cache::{ContentCache, ContentCacheKind},
+        AppState,
+    },
+};
+
+/// Сколько событий забирается из стрима за один запрос
+const READ_BATCH: usize = 50;
+/// Запас к таймауту ответа Redis сверх времени блокировки XREADGROUP
+const RESPONSE_TIMEOUT_MARGIN: Duration = Duration::from_secs(5);
+const ERROR_BACKOFF: Duration = Duration::from_secs(1);
+
+type StreamEntry = (String, HashMap<String, String>);
+
+/// Обработчик событий стрима. Одно событие может прийти несколько раз
+#[async_trait]
+pub trait ContentEventHandler: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    async fn handle(&self, event: &ContentChangeEvent) -> Result<()>;
+}
+
+/// Сбрасывает закэшированные метаданные шаблона; нужен для изменений, пришедших
+/// не через этот экземпляр API
+pub struct CacheInvalidationHandler {
+    cache: ContentCache,
+}
+
+impl CacheInvalidationHandler {
+    pub fn new(cache: ContentCache) -> Self {
+        Self { cache }
+    }
+}
+
+#[async_trait]
+impl ContentEventHandler for CacheInvalidationHandler {
+    fn name(&self) -> &'static str {
+        "cache_invalidation"
+    }
+
+    async fn handle(&self, event: &ContentChangeEvent) -> Result<()> {
+        let Ok(template_id) = ObjectId::parse_str(&event.template_id) else {
+            return Ok(());
+        };
+        self.cache
+            .invalidate(ContentCacheKind::Template, &[template_id])
+            .await
+    }
+}
+
+/// Ставит пересчёт эмбеддингов для опубликованного шаблона в `embedding_jobs`.
+/// Задание привязано к id события, так что повторная доставка его не дублирует
+pub struct EmbeddingEnqueueHandler {
+    mongo: Database,
+}
+
+impl EmbeddingEnqueueHandler {
+    pub fn new(mongo: Database) -> Self {
+        Self { mongo }
+    }
+}
+
+#[async_trait]
+impl ContentEventHandler for EmbeddingEnqueueHandler {
+    fn name(&self) -> &'static str {
+        "embedding_enqueue"
+    }
+
+    async fn handle(&self, event: &ContentChangeEvent) -> Result<()> {
+        // `reindex` публикует сам воркер эмбеддингов - на него задание не ставим.
+        // `republished` - откат публикации, содержимое шаблона сменилось
+        if event.action != "published" && event.action != "republished" {
+            return Ok(());
+        }
+        let Ok(template_id) = ObjectId::parse_str(&event.template_id) else {
+            return Ok(());
+        };
+        let now = BsonDateTime::now();
+        let jobs: Collection<mongodb::bson::Document> = self.mongo.collection("embedding_jobs");
+        jobs.update_one(
+            doc! { "sourceEvent": &event.id },
+            doc! { "$setOnInsert": {
+                "mode": "selected",
+                "status": "queued",
+                "template_ids": [template_id],
+                "total": 1_i64,
+                "processed": 0_i64,
+                "failed_template_ids": [],
+                "createdAt": now,
+                "updatedAt": now,
+            } },
+        )
+        .upsert(true)
+        .await
+        .context("Failed to enqueue embedding job for published template")?;
+        Ok(())
+    }
+}
+
+/// Итог одного прохода консьюмера
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ConsumerTick {
+    /// Обработано и подтверждено
+    pub processed: usize,
+    /// Обработчик вернул ошибку, событие будет выдано повторно
+    pub failed: usize,
+    /// Перенесено в стрим недоставленных событий
+    pub dead_lettered: usize,
+}
+
+pub struct ContentStreamConsumer {
+    redis: ConnectionManager,
+    settings: ContentSettings,
+    handlers: Vec<Arc<dyn ContentEventHandler>>,
+}
+
+impl ContentStreamConsumer {
+    /// Консьюмер без обработчиков; они добавляются через `with_handler`
+    pub fn new(redis: ConnectionManager, settings: ContentSettings) -> Self {
+        Self {
+            redis,
+            settings,
+            handlers: Vec::new(),
+        }
+    }
+
+    /// Консьюмер API со стандартными обработчиками: инвалидация кэша и эмбеддинги
+    pub fn from_state(state: &AppState, redis: ConnectionManager) -> Self {
+        Self::new(redis, state.config.content.clone())
+            .with_handler(Arc::new(CacheInvalidationHandler::new(ContentCache::new(
+                state.redis.clone(),
+            ))))
+            .with_handler(Arc::new(EmbeddingEnqueueHandler::new(state.mongo.clone())))
+    }
+
+    pub fn with_handler(mut self, handler: Arc<dyn ContentEventHandler>) -> Self {
+        self.handlers.push(handler);
+        self
+    }
+
+    /// Отдельное соединение: XREADGROUP BLOCK занимает его целиком и дольше
+    /// стандартного таймаута ответа
+    pub async fn connect(
+        client: redis::Client,
+        settings: &ContentSettings,
+    ) -> Result<ConnectionManager> {
+        let config = ConnectionManagerConfig::new().set_response_timeout(Some(
+            Duration::from_millis(settings.consumer_block_ms) + RESPONSE_TIMEOUT_MARGIN,
+        ));
+        ConnectionManager::new_with_config(client, config)
+            .await
+            .context("Failed to connect content stream consumer to Redis")
+    }
+
+    pub async fn run(&self) -> Result<()> {
+        self.ensure_group().await?;
+        info!(
+            stream = %self.settings.stream_name,
+            group = %self.settings.consumer_group,
+            consumer = %self.settings.consumer_name,
+            "Starting content stream consumer"
+        );
+
+        loop {
+            // Ошибки отдельных событий уже залогированы в `process`
+            if let Err(err) = self.run_once().await {
+                warn!(error = %err, "content stream consumer tick failed");
+                sleep(ERROR_BACKOFF).await;
+                // Стрим могли удалить вместе с группой (NOGROUP)
+                if let Err(err) = self.ensure_group().await {
+                    warn!(error = %err, "failed to recreate content consumer group");
+                }
+            }
+        }
+    }
+
+    /// Создаёт группу (и стрим), если их ещё нет. Новая группа читает только
+    /// события, появившиеся после её создания
+    pub async fn ensure_group(&self) -> Result<()> {
+        let mut conn = self.redis.clone();
+        let created = redis::cmd("XGROUP")
+            .arg("CREATE")
+            .arg(&self.settings.stream_name)
+            .arg(&self.settings.consumer_group)
+            .arg("$")
+            .arg("MKSTREAM")
+            .query_async::<()>(&mut conn)
+            .await;
+        match created {
+            Err(err) if err.code() == Some("BUSYGROUP") => Ok(()),
+            result => result.context("Failed to create content stream consumer group"),
+        }
+    }
+
+    /// Один проход: повтор зависших событий, затем новые (с ожиданием до
+    /// `consumer_block_ms`)
+    pub async fn run_once(&self) -> Result<ConsumerTick> {
+        let mut tick = ConsumerTick::default();
+        self.retry_pending(&mut tick).await?;
+
+        let mut conn = self.redis.clone();
+        let reply: Option<Vec<(String, Vec<StreamEntry>)>> = redis::cmd("XREADGROUP")
+            .arg("GROUP")
+            .arg(&self.settings.consumer_group)
+            .arg(&self.settings.consumer_name)
+            .arg("COUNT")
+            .arg(READ_BATCH)
+            .arg("BLOCK")
+            .arg(self.settings.consumer_block_ms)
+            .arg("STREAMS")
+            .arg(&self.settings.stream_name)
+            .arg(">")
+            .query_async(&mut conn)
+            .await
+            .context("Failed to read content stream")?;
+        for (id, fields) in reply.into_iter().flatten().flat_map(|(_, entries)| entries) {
+            self.process(id, fields, 1, &mut tick).await?;
+        }
+        Ok(tick)
+    }
+
+    /// События группы без XACK дольше `consumer_retry_idle_secs`: исчерпавшие попытки
+    /// уходят в стрим недоставленных, остальные забираются этим консьюмером
+    async fn retry_pending(&self, tick: &mut ConsumerTick) -> Result<()> {
+        let mut conn = self.redis.clone();
+        let idle_ms = self.settings.consumer_retry_idle_secs.saturating_mul(1000);
+        let pending: Vec<(String, String, i64, i64)> = redis::cmd("XPENDING")
+            .arg(&self.settings.stream_name)
+            .arg(&self.settings.consumer_group)
+            .arg("IDLE")
+            .arg(idle_ms)
+            .arg("-")
+            .arg("+")
+            .arg(READ_BATCH)
+            .query_async(&mut conn)
+            .await
+            .context("Failed to read pending content events")?;
+
+        for (id, _, _, deliveries) in pending {
+            if deliveries >= i64::from(self.settings.consumer_max_attempts) {
+                // Последняя попытка не завершилась ни XACK, ни ошибкой: консьюмер упал
+                let entries: Vec<StreamEntry> = redis::cmd("XRANGE")
+                    .arg(&self.settings.stream_name)
+                    .arg(&id)
+                    .arg(&id)
+                    .query_async(&mut conn)
+                    .await
+                    .context("Failed to read pending content event")?;
+                let fields = entries
+                    .into_iter()
+                    .next()
+                    .map(|(_, fields)| fields)
+                    .unwrap_or_default();
+                self.dead_letter(&id, fields, deliveries, None).await?;
+                tick.dead_lettered += 1;
+                continue;
+            }
+
+            // XCLAIM увеличивает счётчик доставок; пусто, если событие уже забрал другой
+            let claimed: Vec<StreamEntry> = redis::cmd("XCLAIM")
+                .arg(&self.settings.stream_name)
+                .arg(&self.settings.consumer_group)
+                .arg(&self.settings.consumer_name)
+                .arg(idle_ms)
+                .arg(&id)
+                .query_async(&mut conn)
+                .await
+                .context("Failed to claim pending content event")?;
+            for (id, fields) in claimed {
+                self.process(id, fields, deliveries + 1, tick).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn process(
+        &self,
+        id: String,
+        fields: HashMap<String, String>,
+        attempt: i64,
+        tick: &mut ConsumerTick,
+    ) -> Result<()> {
+        let event = ContentChangeEvent::from_fields(id, &fields);
+        match self.dispatch(&event).await {
+            Ok(()) => {
+                let mut conn = self.redis.clone();
+                redis::cmd("XACK")
+                    .arg(&self.settings.stream_name)
+                    .arg(&self.settings.consumer_group)
+                    .arg(&event.id)
+                    .query_async::<i64>(&mut conn)
+                    .await
+                    .context("Failed to acknowledge content event")?;
+                CONTENT_STREAM_EVENTS_TOTAL
+                    .with_label_values(&["processed"])
+                    .inc();
+                tick.processed += 1;
+            }
+            Err(err) if attempt >= i64::from(self.settings.consumer_max_attempts) => {
+                warn!(event_id = %event.id, attempt, error = %err, "content event dead-lettered");
+                self.dead_letter(&event.id, fields, attempt, Some(format!("{err:#}")))
+                    .await?;
+                tick.dead_lettered += 1;
+            }
+            Err(err) => {
+                warn!(event_id = %event.id, attempt, error = %err, "content event failed");
+                CONTENT_STREAM_EVENTS_TOTAL
+                    .with_label_values(&["failed"])
+                    .inc();
+                tick.failed += 1;
+            }
+        }
+        Ok(())
+    }
+
+    async fn dispatch(&self, event: &ContentChangeEvent) -> Result<()> {
+        for handler in &self.handlers {
+            handler
+                .handle(event)
+                .await
+                .with_context(|| format!("{} handler failed", handler.name()))?;
+        }
+        Ok(())
+    }
+
+    /// Копия события с причиной переноса; XADD и XACK выполняются атомарно
+    async fn dead_letter(
+        &self,
+        id: &str,
+        mut fields: HashMap<String, String>,
+        attempts: i64,
+        error: Option<String>,
+    ) -> Result<()> {
+        fields.insert(DeadLetteredEvent::SOURCE_ID.to_string(), id.to_string());
+        fields.insert(
+            DeadLetteredEvent::GROUP.to_string(),
+            self.settings.consumer_group.clone(),
+        );
+        fields.insert(
+            DeadLetteredEvent::ATTEMPTS.to_string(),
+            attempts.to_string(),
+        );
+        fields.insert(
+            DeadLetteredEvent::FAILED_AT.to_string(),
+            Utc::now().to_rfc3339(),
+        );
+        if let Some(error) = error {
+            fields.insert(DeadLetteredEvent::ERROR.to_string(), error);
+        }
+
+        let mut conn = self.redis.clone();
+        redis::pipe()
+            .atomic()
+            .cmd("XADD")
+            .arg(&self.settings.dead_letter_stream)
+            .arg("*")
+            .arg(&fields)
+            .ignore()
+            .cmd("XACK")
+            .arg(&self.settings.stream_name)
+            .arg(&self.settings.consumer_group)
+            .arg(id)
+            .ignore()
+            .query_async::<()>(&mut conn)
+            .await
+            .context("Failed to move content event to dead letter stream")?;
+        CONTENT_STREAM_EVENTS_TOTAL
+            .with_label_values(&["dead_lettered"])
+            .inc();
+        Ok(())
+    }
+}
@@ -0,0 +1,222 @@
+//! Сводка для главной страницы админки: счётчики пользователей, групп, шаблонов,
+//! инцидентов, выгрузок, очереди и сессий одним запросом.
+//!
+//! Подсчёты идут параллельно, результат лежит в Redis `DASHBOARD_CACHE_TTL_SECS`
+//! секунд. Недоступный кэш не мешает ответу - сводка просто считается заново.
+
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+use chrono::{Duration, Utc};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, DateTime as BsonDateTime, Document},
+    Database,
+};
+use redis::{aio::ConnectionManager, AsyncCommands};
+
+use crate::models::{
+    content::TemplateStatus,
+    dashboard::{DashboardSummary, DashboardUserCounts, TemplateStatusCounts},
+};
+
+pub const DASHBOARD_CACHE_KEY: &str = "admin:dashboard:summary";
+pub const DASHBOARD_CACHE_TTL_SECS: u64 = 30;
+
+/// Пользователь считается активным, если входил за это число дней
+const ACTIVE_USER_WINDOW_DAYS: i64 = 30;
+
+pub struct DashboardService {
+    mongo: Database,
+    redis: ConnectionManager,
+    stream_name: String,
+}
+
+impl DashboardService {
+    pub fn new(mongo: Database, redis: ConnectionManager, stream_name: String) -> Self {
+        Self {
+            mongo,
+            redis,
+            stream_name,
+        }
+    }
+
+    pub async fn summary(&self) -> Result<DashboardSummary> {
+        if let Some(cached) = self.cached().await {
+            return Ok(cached);
+        }
+
+        let summary = self.collect().await?;
+        self.store(&summary).await;
+        Ok(summary)
+    }
+
+    async fn cached(&self) -> Option<DashboardSummary> {
+        let mut conn = self.redis.clone();
+        let raw: Option<String> = match conn.get(DASHBOARD_CACHE_KEY).await {
+            Ok(raw) => raw,
+            Err(err) => {
+                tracing::warn!("Dashboard cache unavailable: {}", err);
+                return None;
+            }
+        };
+        raw.and_then(|raw| serde_json::from_str(&raw).ok())
+    }
+
+    async fn store(&self, summary: &DashboardSummary) {
+        let Ok(raw) = serde_json::to_string(summary) else {
+            return;
+        };
+        let mut conn = self.redis.clone();
+        let stored: redis::RedisResult<()> = conn
+            .set_ex(DASHBOARD_CACHE_KEY, raw, DASHBOARD_CACHE_TTL_SECS)
+            .await;
+        if let Err(err) = stored {
+            tracing::warn!("Failed to cache dashboard summary: {}", err);
+        }
+    }
+
+    async fn collect(&self) -> Result<DashboardSummary> {
+        let users = self.mongo.collection::<Document>("users");
+        let active_since = bson_time(Utc::now() - Duration::days(ACTIVE_USER_WINDOW_DAYS));
+        let today = Utc::now()
+            .date_naive()
+            .and_hms_opt(0, 0, 0)
+            .map(|midnight| midnight.and_utc())
+            .unwrap_or_else(Utc::now);
+
+        let (
+            total_users,
+            active_users,
+            blocked_users,
+            groups,
+            templates,
+            open_incidents,
+            pending_exports,
+            queue_length,
+            sessions_started_today,
+        ) = tokio::try_join!(
+            async {
+                users
+                    .estimated_document_count()
+                    .await
+                    .context("Failed to count users")
+            },
+            async {
+                users
+                    .count_documents(doc! { "lastLoginAt": { "$gte": active_since } })
+                    .await
+                    .context("Failed to count active users")
+            },
+            async {
+                users
+                    .count_documents(doc! { "is_blocked": true })
+                    .await
+                    .context("Failed to count blocked users")
+            },
+            async {
+                self.mongo
+                    .collection::<Document>("groups")
+                    .estimated_document_count()
+                    .await
+                    .context("Failed to count groups")
+            },
+            self.template_counts(),
+            async {
+                self.mongo
+                    .collection::<Document>("incidents")
+                    .count_documents(doc! { "status": "open" })
+                    .await
+                    .context("Failed to count open incidents")
+            },
+            async {
+                self.mongo
+                    .collection::<Document>("report_exports")
+                    .count_documents(doc! { "status": { "$in": ["pending", "processing"] } })
+                    .await
+                    .context("Failed to count pending exports")
+            },
+            self.queue_length(),
+            async {
+                self.mongo
+                    .collection::<Document>("sessions")
+                    .count_documents(doc! { "started_at": { "$gte": bson_time(today) } })
+                    .await
+                    .context("Failed to count today's sessions")
+            },
+        )?;
+
+        Ok(DashboardSummary {
+            users: DashboardUserCounts {
+                total: total_users,
+                active: active_users,
+                blocked: blocked_users,
+            },
+            groups,
+            templates,
+            open_incidents,
+            pending_exports,
+            queue_length,
+            sessions_started_today,
+            generated_at: Utc::now(),
+        })
+    }
+
+    async fn template_counts(&self) -> Result<TemplateStatusCounts> {
+        let rows: Vec<Document> = self
+            .mongo
+            .collection::<Document>("templates")
+            .aggregate(vec![
+                doc! { "$group": { "_id": "$status", "count": { "$sum": 1 } } },
+            ])
+            .await
+            .context("Failed to count templates by status")?
+            .try_collect()
+            .await
+            .context("Failed to read template counts")?;
+
+        let mut counts = TemplateStatusCounts::default();
+        for row in rows {
+            let Some(status) = row
+                .get_str("_id")
+                .ok()
+                .and_then(|status| TemplateStatus::from_str(status).ok())
+            else {
+                continue;
+            };
+            let count = row
+                .get_i32("count")
+                .map(i64::from)
+                .or_else(|_| row.get_i64("count"))
+                .unwrap_or_default()
+                .max(0) as u64;
+            *status_count(&mut counts, status) += count;
+        }
+        Ok(counts)
+    }
+
+    async fn queue_length(&self) -> Result<u64> {
+        let mut conn = self.redis.clone();
+        let length: u64 = redis::cmd("XLEN")
+            .arg(&self.stream_name)
+            .query_async(&mut conn)
+            .await
+            .context("Failed to query content stream length")?;
+        Ok(length)
+    }
+}
+
+fn status_count(counts: &mut TemplateStatusCounts, status: TemplateStatus) -> &mut u64 {
+    match status {
+        TemplateStatus::Draft => &mut counts.draft,
+        TemplateStatus::PendingReview => &mut counts.pending_review,
+        TemplateStatus::ReviewedOnce => &mut counts.reviewed_once,
+        TemplateStatus::Ready => &mut counts.ready,
+        TemplateStatus::Published => &mut counts.published,
+        TemplateStatus::Deprecated => &mut counts.deprecated,
+    }
+}
+
+fn bson_time(at: chrono::DateTime<Utc>) -> BsonDateTime {
+    BsonDateTime::from_millis(at.timestamp_millis())
+}
@@ -0,0 +1,271 @@
+//! Очередь писем `email_outbox`.
+//!
+//! Обработчик кладёт по документу на получателя и сразу отвечает; `EmailWorker`
+//! забирает письма пачками, повторяет неудачные с задержкой и записывает итог
+//! каждого письма. Так сбой SMTP посреди рассылки не теряет, кому письмо уже ушло.
+
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, oid::ObjectId, to_bson},
+    options::ReturnDocument,
+    Database,
+};
+
+use crate::{
+    models::notification::{EmailDeliveryStatus, EmailOutboxMessage},
+    utils::time::chrono_to_bson,
+};
+
+pub const EMAIL_OUTBOX_COLLECTION: &str = "email_outbox";
+
+/// Письмо для постановки в очередь
+#[derive(Debug, Clone)]
+pub struct OutgoingEmail {
+    pub recipient_id: ObjectId,
+    pub recipient_email: String,
+    pub recipient_name: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Итоговый статус рассылки по статусам её писем
+pub fn aggregate_status(statuses: &[EmailDeliveryStatus]) -> &'static str {
+    let count = |status| statuses.iter().filter(|s| **s == status).count();
+    let pending = count(EmailDeliveryStatus::Queued) + count(EmailDeliveryStatus::Sending);
+    let failed = count(EmailDeliveryStatus::Failed);
+    let skipped = count(EmailDeliveryStatus::Skipped);
+
+    if pending > 0 {
+        "queued"
+    } else if failed == statuses.len() && failed > 0 {
+        "failed"
+    } else if failed > 0 {
+        "partial"
+    } else if skipped == statuses.len() && skipped > 0 {
+        "skipped"
+    } else {
+        "sent"
+    }
+}
+
+pub struct EmailOutboxService {
+    mongo: Database,
+}
+
+impl EmailOutboxService {
+    pub fn new(mongo: Database) -> Self {
+        Self { mongo }
+    }
+
+    fn collection(&self) -> mongodb::Collection<EmailOutboxMessage> {
+        self.mongo
+            .collection::<EmailOutboxMessage>(EMAIL_OUTBOX_COLLECTION)
+    }
+
+    /// Поставить письма рассылки в очередь. При `skipped` (отправка отключена)
+    /// письма сразу получают итоговый статус и воркер их не берёт
+    pub async fn enqueue(
+        &self,
+        notification_id: ObjectId,
+        emails: Vec<OutgoingEmail>,
+        skipped: bool,
+    ) -> Result<usize> {
+        if emails.is_empty() {
+            return Ok(0);
+        }
+        let now = Utc::now();
+        let status = if skipped {
+            EmailDeliveryStatus::Skipped
+        } else {
+            EmailDeliveryStatus::Queued
+        };
+        let messages = emails
+            .into_iter()
+            .map(|email| EmailOutboxMessage {
+                id: ObjectId::new(),
+                notification_id,
+                recipient_id: email.recipient_id,
+                recipient_email: email.recipient_email,
+                recipient_name: email.recipient_name,
+                subject: email.subject,
+                body: email.body,
+                status,
+                attempts: 0,
+                last_error: None,
+                next_attempt_at: None,
+                lease_until: None,
+                created_at: now,
+                sent_at: None,
+            })
+            .collect::<Vec<_>>();
+        let inserted = self
+            .collection()
+            .insert_many(messages)
+            .await
+            .context("Failed to enqueue notification emails")?;
+        Ok(inserted.inserted_ids.len())
+    }
+
+    /// Взять следующее письмо, чья попытка уже наступила, и закрепить его за собой
+    /// на `lease`. Письмо в `sending` с истёкшей арендой (воркер упал посреди
+    /// отправки) берётся снова
+    pub async fn claim_next(
+        &self,
+        now: DateTime<Utc>,
+        lease: Duration,
+    ) -> Result<Option<EmailOutboxMessage>> {
+        self.collection()
+            .find_one_and_update(
+                doc! {
+                    "$or": [
+                        {
+                            "status": to_bson(&EmailDeliveryStatus::Queued)?,
+                            "$or": [
+                                { "nextAttemptAt": { "$exists": false } },
+                                { "nextAttemptAt": { "$lte": chrono_to_bson(now) } },
+                            ],
+                        },
+                        {
+                            "status": to_bson(&EmailDeliveryStatus::Sending)?,
+                            "leaseUntil": { "$lte": chrono_to_bson(now) },
+                        },
+                    ],
+                },
+                doc! {
+                    "$set": {
+                        "status": to_bson(&EmailDeliveryStatus::Sending)?,
+                        "leaseUntil": chrono_to_bson(now + lease),
+                    },
+                    "$inc": { "attempts": 1 },
+                    "$unset": { "nextAttemptAt": "" },
+                },
+            )
+            .sort(doc! { "createdAt": 1 })
+            .return_document(ReturnDocument::After)
+            .await
+            .context("Failed to claim outbox email")
+    }
+
+    pub async fn mark_sent(&self, message_id: &ObjectId) -> Result<()> {
+        self.collection()
+            .update_one(
+                doc! { "_id": message_id },
+                doc! {
+                    "$set": {
+                        "status": to_bson(&EmailDeliveryStatus::Sent)?,
+                        "sentAt": chrono_to_bson(Utc::now()),
+                    },
+                    "$unset": { "leaseUntil": "" },
+                },
+            )
+            .await
+            .context("Failed to mark outbox email as sent")?;
+        Ok(())
+    }
+
+    pub async fn mark_skipped(&self, message_id: &ObjectId) -> Result<()> {
+        self.collection()
+            .update_one(
+                doc! { "_id": message_id },
+                doc! {
+                    "$set": { "status": to_bson(&EmailDeliveryStatus::Skipped)? },
+                    "$unset": { "leaseUntil": "" },
+                },
+            )
+            .await
+            .context("Failed to mark outbox email as skipped")?;
+        Ok(())
+    }
+
+    /// Записать ошибку попытки: с `retry_at` письмо вернётся в очередь,
+    /// без него - станет `failed` с этой ошибкой
+    pub async fn record_failure(
+        &self,
+        message_id: &ObjectId,
+        error: &str,
+        retry_at: Option<DateTime<Utc>>,
+    ) -> Result<()> {
+        let set_doc = match retry_at {
+            Some(retry_at) => doc! {
+                "status": to_bson(&EmailDeliveryStatus::Queued)?,
+                "last_error": error,
+                "nextAttemptAt": chrono_to_bson(retry_at),
+            },
+            None => doc! {
+                "status": to_bson(&EmailDeliveryStatus::Failed)?,
+                "last_error": error,
+            },
+        };
+        self.collection()
+            .update_one(
+                doc! { "_id": message_id },
+                doc! { "$set": set_doc, "$unset": { "leaseUntil": "" } },
+            )
+            .await
+            .context("Failed to record outbox email failure")?;
+        Ok(())
+    }
+
+    pub async fn list_for_notification(
+        &self,
+        notification_id: &ObjectId,
+    ) -> Result<Vec<EmailOutboxMessage>> {
+        self.collection()
+            .find(doc! { "notification_id": notification_id })
+            .sort(doc! { "recipient_name": 1 })
+            .await
+            .context("Failed to load notification emails")?
+            .try_collect()
+            .await
+            .context("Failed to read notification emails")
+    }
+
+    /// Письма нескольких рассылок, сгруппированные по рассылке
+    pub async fn list_for_notifications(
+        &self,
+        notification_ids: &[ObjectId],
+    ) -> Result<HashMap<ObjectId, Vec<EmailOutboxMessage>>> {
+        if notification_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let messages: Vec<EmailOutboxMessage> = self
+            .collection()
+            .find(doc! { "notification_id": { "$in": notification_ids } })
+            .sort(doc! { "recipient_name": 1 })
+            .await
+            .context("Failed to load notification emails")?
+            .try_collect()
+            .await
+            .context("Failed to read notification emails")?;
+
+        let mut grouped: HashMap<ObjectId, Vec<EmailOutboxMessage>> = HashMap::new();
+        for message in messages {
+            grouped
+                .entry(message.notification_id)
+                .or_default()
+                .push(message);
+        }
+        Ok(grouped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use EmailDeliveryStatus::*;
+
+    #[test]
+    fn aggregate_status_reflects_deliveries() {
+        assert_eq!(aggregate_status(&[Sent, Queued]), "queued");
+        assert_eq!(aggregate_status(&[Sent, Sending]), "queued");
+        assert_eq!(aggregate_status(&[Sent, Sent]), "sent");
+        assert_eq!(aggregate_status(&[Sent, Failed]), "partial");
+        assert_eq!(aggregate_status(&[Failed, Failed]), "failed");
+        assert_eq!(aggregate_status(&[Skipped, Skipped]), "skipped");
+        assert_eq!(aggregate_status(&[]), "sent");
+    }
+}
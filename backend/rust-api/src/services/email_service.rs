@@ -1,15 +1,27 @@
+use std::time::Duration;
+
 use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
 use lettre::{
     message::Mailbox, transport::smtp::authentication::Credentials, AsyncSmtpTransport,
     AsyncTransport, Message, Tokio1Executor,
 };
 use mongodb::Database;
+use tokio::time::{timeout_at, Instant};
 
 use crate::{
-    models::system_settings::EmailSettings,
+    models::system_settings::{EmailSettings, EmailTestResponse, EmailTestStatus},
     services::system_settings_service::SystemSettingsService,
 };
 
+/// Сколько ждать SMTP-сервер при проверке настроек из админки
+const EMAIL_TEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Коды ответа, которыми сервер отклоняет авторизацию
+const AUTH_REJECTED_CODES: [u16; 5] = [454, 530, 534, 535, 538];
+/// Коды ответа на RCPT TO для несуществующего или запрещённого адресата
+const RECIPIENT_REFUSED_CODES: [u16; 4] = [550, 551, 553, 501];
+
 pub struct EmailService {
     mongo: Database,
 }
@@ -56,7 +68,7 @@ impl EmailService {
             .body(body)
             .context("Failed to build email message")?;
 
-        let mailer = self.build_mailer(&settings)?;
+        let mailer = build_mailer(&settings, None)?;
         mailer
             .send(email)
             .await
@@ -91,7 +103,7 @@ impl EmailService {
             .body(body.to_string())
             .context("Failed to build notification email")?;
 
-        let mailer = self.build_mailer(&settings)?;
+        let mailer = build_mailer(&settings, None)?;
         mailer
             .send(email)
             .await
@@ -105,18 +117,404 @@ impl EmailService {
         settings_service.get_email_settings().await
     }
 
-    fn build_mailer(&self, settings: &EmailSettings) -> Result<AsyncSmtpTransport<Tokio1Executor>> {
-        let creds = Credentials::new(settings.login.clone(), settings.password.clone());
+    /// Проверить SMTP-настройки: DNS, подключение и авторизация, затем отправка тестового
+    /// письма на `recipient`. При `EMAIL_SEND_DISABLED` проверка заканчивается после
+    /// авторизации. Ошибки не пробрасываются, а описываются в ответе.
+    pub async fn send_test_email(settings: &EmailSettings, recipient: &str) -> EmailTestResponse {
+        let message = match test_message(settings, recipient) {
+            Ok(message) => message,
+            Err(err) => {
+                return EmailTestResponse {
+                    success: false,
+                    status: EmailTestStatus::InvalidAddress,
+                    message: Some(format!("{:#}", err)),
+                    smtp_code: None,
+                }
+            }
+        };
+        let probe = match LettreProbe::new(settings) {
+            Ok(probe) => probe,
+            Err(err) => {
+                return EmailTestResponse {
+                    success: false,
+                    status: EmailTestStatus::ConnectionFailed,
+                    message: Some(format!("{:#}", err)),
+                    smtp_code: None,
+                }
+            }
+        };
+
+        run_smtp_test(
+            &probe,
+            message,
+            Self::sending_disabled(),
+            EMAIL_TEST_TIMEOUT,
+        )
+        .await
+    }
+}
+
+fn build_mailer(
+    settings: &EmailSettings,
+    timeout: Option<Duration>,
+) -> Result<AsyncSmtpTransport<Tokio1Executor>> {
+    let creds = Credentials::new(settings.login.clone(), settings.password.clone());
+
+    let builder = if settings.use_tls {
+        AsyncSmtpTransport::<Tokio1Executor>::relay(&settings.server)
+            .context("Invalid SMTP server for TLS")?
+    } else {
+        AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&settings.server)
+    }
+    .port(settings.port)
+    .credentials(creds);
+
+    let builder = match timeout {
+        Some(timeout) => builder.timeout(Some(timeout)),
+        None => builder,
+    };
+
+    Ok(builder.build())
+}
 
-        let builder = if settings.use_tls {
-            AsyncSmtpTransport::<Tokio1Executor>::relay(&settings.server)
-                .context("Invalid SMTP server for TLS")?
-        } else {
-            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&settings.server)
+fn test_message(settings: &EmailSettings, recipient: &str) -> Result<Message> {
+    let from_address: Mailbox = format!("{} <{}>", settings.from_name, settings.from_email)
+        .parse()
+        .context("Invalid from email address")?;
+    let to_address: Mailbox = recipient
+        .parse()
+        .context("Invalid recipient email address")?;
+
+    Message::builder()
+        .from(from_address)
+        .to(to_address)
+        .subject("Тестовое письмо TrainingGround")
+        .body("Это тестовое письмо: настройки почты TrainingGround работают.\n".to_string())
+        .context("Failed to build test email")
+}
+
+/// Шаг проверки SMTP, на котором произошла ошибка
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SmtpStage {
+    Resolve,
+    Handshake,
+    Send,
+}
+
+/// Ошибка SMTP без привязки к lettre, чтобы классификацию можно было проверить без сервера
+#[derive(Debug, Clone, Default)]
+pub struct SmtpFailure {
+    pub timeout: bool,
+    pub tls: bool,
+    pub code: Option<u16>,
+    pub message: String,
+}
+
+impl From<lettre::transport::smtp::Error> for SmtpFailure {
+    fn from(err: lettre::transport::smtp::Error) -> Self {
+        Self {
+            timeout: err.is_timeout(),
+            tls: err.is_tls(),
+            code: err.status().and_then(|code| code.to_string().parse().ok()),
+            message: err.to_string(),
         }
-        .port(settings.port)
-        .credentials(creds);
+    }
+}
+
+/// Шаги проверки SMTP; в тестах подменяется фейковым транспортом
+#[async_trait]
+pub trait SmtpProbe: Send + Sync {
+    async fn resolve(&self) -> Result<(), SmtpFailure>;
+    /// Подключение, TLS и авторизация
+    async fn handshake(&self) -> Result<(), SmtpFailure>;
+    async fn send(&self, message: Message) -> Result<(), SmtpFailure>;
+}
+
+struct LettreProbe {
+    server: String,
+    port: u16,
+    mailer: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl LettreProbe {
+    fn new(settings: &EmailSettings) -> Result<Self> {
+        Ok(Self {
+            server: settings.server.clone(),
+            port: settings.port,
+            mailer: build_mailer(settings, Some(EMAIL_TEST_TIMEOUT))?,
+        })
+    }
+}
+
+#[async_trait]
+impl SmtpProbe for LettreProbe {
+    async fn resolve(&self) -> Result<(), SmtpFailure> {
+        let mut addresses = tokio::net::lookup_host((self.server.as_str(), self.port))
+            .await
+            .map_err(|err| SmtpFailure {
+                message: err.to_string(),
+                ..SmtpFailure::default()
+            })?;
+        match addresses.next() {
+            Some(_) => Ok(()),
+            None => Err(SmtpFailure {
+                message: format!("{} has no addresses", self.server),
+                ..SmtpFailure::default()
+            }),
+        }
+    }
+
+    async fn handshake(&self) -> Result<(), SmtpFailure> {
+        match self.mailer.test_connection().await? {
+            true => Ok(()),
+            false => Err(SmtpFailure {
+                message: "SMTP server closed the connection".into(),
+                ..SmtpFailure::default()
+            }),
+        }
+    }
+
+    async fn send(&self, message: Message) -> Result<(), SmtpFailure> {
+        self.mailer.send(message).await?;
+        Ok(())
+    }
+}
+
+/// Пройти шаги проверки по порядку, уложившись в общий `limit`
+pub async fn run_smtp_test(
+    probe: &dyn SmtpProbe,
+    message: Message,
+    send_disabled: bool,
+    limit: Duration,
+) -> EmailTestResponse {
+    let deadline = Instant::now() + limit;
+    let timed_out = || SmtpFailure {
+        timeout: true,
+        message: format!("SMTP server did not respond within {}s", limit.as_secs()),
+        ..SmtpFailure::default()
+    };
+
+    let steps = async {
+        timeout_at(deadline, probe.resolve())
+            .await
+            .unwrap_or_else(|_| Err(timed_out()))
+            .map_err(|failure| (SmtpStage::Resolve, failure))?;
+        timeout_at(deadline, probe.handshake())
+            .await
+            .unwrap_or_else(|_| Err(timed_out()))
+            .map_err(|failure| (SmtpStage::Handshake, failure))?;
+        if send_disabled {
+            return Ok(EmailTestStatus::HandshakeOk);
+        }
+        timeout_at(deadline, probe.send(message))
+            .await
+            .unwrap_or_else(|_| Err(timed_out()))
+            .map_err(|failure| (SmtpStage::Send, failure))?;
+        Ok(EmailTestStatus::Sent)
+    };
+
+    match steps.await {
+        Ok(status) => EmailTestResponse {
+            success: true,
+            status,
+            message: Some(match status {
+                EmailTestStatus::HandshakeOk => {
+                    "SMTP handshake succeeded; sending is disabled by EMAIL_SEND_DISABLED".into()
+                }
+                _ => "Test email sent".into(),
+            }),
+            smtp_code: None,
+        },
+        Err((stage, failure)) => EmailTestResponse {
+            success: false,
+            status: classify_failure(stage, &failure),
+            message: Some(failure.message),
+            smtp_code: failure.code,
+        },
+    }
+}
+
+fn classify_failure(stage: SmtpStage, failure: &SmtpFailure) -> EmailTestStatus {
+    if failure.timeout {
+        return EmailTestStatus::Timeout;
+    }
+    if failure.tls {
+        return EmailTestStatus::TlsError;
+    }
+    let auth_rejected = failure
+        .code
+        .is_some_and(|code| AUTH_REJECTED_CODES.contains(&code));
+
+    match stage {
+        SmtpStage::Resolve => EmailTestStatus::DnsResolutionFailed,
+        SmtpStage::Handshake
+            if auth_rejected || failure.message.contains("authentication mechanism") =>
+        {
+            EmailTestStatus::AuthRejected
+        }
+        SmtpStage::Handshake => EmailTestStatus::ConnectionFailed,
+        SmtpStage::Send if auth_rejected => EmailTestStatus::AuthRejected,
+        SmtpStage::Send
+            if failure
+                .code
+                .is_some_and(|code| RECIPIENT_REFUSED_CODES.contains(&code)) =>
+        {
+            EmailTestStatus::RecipientRefused
+        }
+        SmtpStage::Send => EmailTestStatus::SendFailed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Фейковый транспорт: ошибка на заданном шаге и журнал вызванных шагов
+    #[derive(Default)]
+    struct FakeProbe {
+        fail_at: Option<(SmtpStage, SmtpFailure)>,
+        hang_at: Option<SmtpStage>,
+        calls: Mutex<Vec<SmtpStage>>,
+    }
+
+    impl FakeProbe {
+        fn failing(stage: SmtpStage, failure: SmtpFailure) -> Self {
+            Self {
+                fail_at: Some((stage, failure)),
+                ..Self::default()
+            }
+        }
+
+        async fn step(&self, stage: SmtpStage) -> Result<(), SmtpFailure> {
+            self.calls.lock().unwrap().push(stage);
+            if self.hang_at == Some(stage) {
+                std::future::pending::<()>().await;
+            }
+            match &self.fail_at {
+                Some((failing, failure)) if *failing == stage => Err(failure.clone()),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl SmtpProbe for FakeProbe {
+        async fn resolve(&self) -> Result<(), SmtpFailure> {
+            self.step(SmtpStage::Resolve).await
+        }
+
+        async fn handshake(&self) -> Result<(), SmtpFailure> {
+            self.step(SmtpStage::Handshake).await
+        }
+
+        async fn send(&self, _message: Message) -> Result<(), SmtpFailure> {
+            self.step(SmtpStage::Send).await
+        }
+    }
+
+    fn message() -> Message {
+        let settings = EmailSettings {
+            server: "smtp.example.com".into(),
+            port: 587,
+            login: "mailer".into(),
+            password: "secret".into(),
+            from_email: "noreply@example.com".into(),
+            from_name: "TrainingGround".into(),
+            use_tls: true,
+        };
+        test_message(&settings, "admin@example.com").unwrap()
+    }
+
+    fn smtp_error(code: u16) -> SmtpFailure {
+        SmtpFailure {
+            code: Some(code),
+            message: format!("permanent error ({})", code),
+            ..SmtpFailure::default()
+        }
+    }
+
+    async fn run(probe: &FakeProbe) -> EmailTestResponse {
+        run_smtp_test(probe, message(), false, Duration::from_secs(5)).await
+    }
+
+    #[tokio::test]
+    async fn test_failures_are_classified_by_stage_and_code() {
+        let cases = [
+            (
+                FakeProbe::failing(SmtpStage::Resolve, SmtpFailure::default()),
+                EmailTestStatus::DnsResolutionFailed,
+            ),
+            (
+                FakeProbe::failing(
+                    SmtpStage::Handshake,
+                    SmtpFailure {
+                        tls: true,
+                        ..SmtpFailure::default()
+                    },
+                ),
+                EmailTestStatus::TlsError,
+            ),
+            (
+                FakeProbe::failing(SmtpStage::Handshake, smtp_error(535)),
+                EmailTestStatus::AuthRejected,
+            ),
+            (
+                FakeProbe::failing(SmtpStage::Handshake, SmtpFailure::default()),
+                EmailTestStatus::ConnectionFailed,
+            ),
+            (
+                FakeProbe::failing(SmtpStage::Send, smtp_error(550)),
+                EmailTestStatus::RecipientRefused,
+            ),
+            (
+                FakeProbe::failing(SmtpStage::Send, smtp_error(552)),
+                EmailTestStatus::SendFailed,
+            ),
+        ];
+
+        for (probe, expected) in cases {
+            let result = run(&probe).await;
+            assert!(!result.success);
+            assert_eq!(result.status, expected);
+        }
+
+        let result = run(&FakeProbe::failing(SmtpStage::Send, smtp_error(550))).await;
+        assert_eq!(result.smtp_code, Some(550));
+    }
+
+    #[tokio::test]
+    async fn test_successful_run_sends_message() {
+        let probe = FakeProbe::default();
+        let result = run(&probe).await;
+        assert!(result.success);
+        assert_eq!(result.status, EmailTestStatus::Sent);
+        assert_eq!(
+            *probe.calls.lock().unwrap(),
+            [SmtpStage::Resolve, SmtpStage::Handshake, SmtpStage::Send]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_disabled_sending_stops_after_handshake() {
+        let probe = FakeProbe::default();
+        let result = run_smtp_test(&probe, message(), true, Duration::from_secs(5)).await;
+        assert!(result.success);
+        assert_eq!(result.status, EmailTestStatus::HandshakeOk);
+        assert_eq!(
+            *probe.calls.lock().unwrap(),
+            [SmtpStage::Resolve, SmtpStage::Handshake]
+        );
+    }
 
-        Ok(builder.build())
+    #[tokio::test]
+    async fn test_black_holed_server_times_out() {
+        let probe = FakeProbe {
+            hang_at: Some(SmtpStage::Handshake),
+            ..FakeProbe::default()
+        };
+        let result = run_smtp_test(&probe, message(), false, Duration::from_millis(50)).await;
+        assert!(!result.success);
+        assert_eq!(result.status, EmailTestStatus::Timeout);
     }
 }
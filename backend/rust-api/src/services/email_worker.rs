@@ -0,0 +1,147 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{Duration as ChronoDuration, Utc};
+use mongodb::Database;
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+use crate::{
+    config::EmailOutboxSettings,
+    metrics::EMAIL_OUTBOX_MESSAGES_TOTAL,
+    models::notification::EmailOutboxMessage,
+    services::{
+        email_outbox_service::EmailOutboxService, email_service::EmailService,
+        export_worker::retry_delay,
+    },
+};
+
+/// Чем воркер отправляет письма (по умолчанию - SMTP из системных настроек)
+#[async_trait]
+pub trait EmailTransport: Send + Sync {
+    async fn send(&self, message: &EmailOutboxMessage) -> Result<()>;
+}
+
+#[async_trait]
+impl EmailTransport for EmailService {
+    async fn send(&self, message: &EmailOutboxMessage) -> Result<()> {
+        self.send_notification_email(
+            &message.recipient_email,
+            &message.recipient_name,
+            &message.subject,
+            &message.body,
+        )
+        .await
+    }
+}
+
+/// Итог одного тика воркера
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct EmailTickSummary {
+    pub sent: usize,
+    /// Попытка не удалась, письмо вернулось в очередь
+    pub retried: usize,
+    /// Попытки исчерпаны, письмо помечено failed
+    pub failed: usize,
+    /// Отправка отключена `EMAIL_SEND_DISABLED`
+    pub skipped: usize,
+}
+
+pub struct EmailWorker {
+    outbox: EmailOutboxService,
+    transport: Arc<dyn EmailTransport>,
+    settings: EmailOutboxSettings,
+}
+
+impl EmailWorker {
+    pub fn new(
+        mongo: Database,
+        transport: Arc<dyn EmailTransport>,
+        settings: EmailOutboxSettings,
+    ) -> Self {
+        Self {
+            outbox: EmailOutboxService::new(mongo),
+            transport,
+            settings,
+        }
+    }
+
+    pub async fn run(&self) -> Result<()> {
+        let interval = Duration::from_secs(self.settings.worker_interval_secs);
+        info!(
+            "Starting email worker (interval={}s, batch={})",
+            interval.as_secs(),
+            self.settings.batch_size
+        );
+
+        loop {
+            match self.process_pending().await {
+                Ok(summary) => {
+                    if summary != EmailTickSummary::default() {
+                        info!(
+                            sent = summary.sent,
+                            retried = summary.retried,
+                            failed = summary.failed,
+                            skipped = summary.skipped,
+                            "Email worker tick completed"
+                        );
+                    }
+                }
+                Err(err) => warn!(error = %err, "email worker tick failed"),
+            }
+            sleep(interval).await;
+        }
+    }
+
+    /// Один тик: отправить до `batch_size` писем, чья попытка уже наступила.
+    /// Письма уходят по одному - SMTP-сервер не любит параллельных сессий
+    pub async fn process_pending(&self) -> Result<EmailTickSummary> {
+        let mut summary = EmailTickSummary::default();
+        let lease = ChronoDuration::seconds(self.settings.lease_secs as i64);
+        for _ in 0..self.settings.batch_size.max(1) {
+            let Some(message) = self.outbox.claim_next(Utc::now(), lease).await? else {
+                break;
+            };
+            // Отправку могли отключить уже после постановки в очередь
+            if EmailService::sending_disabled() {
+                self.outbox.mark_skipped(&message.id).await?;
+                summary.skipped += 1;
+                continue;
+            }
+            let Err(err) = self.transport.send(&message).await else {
+                self.outbox.mark_sent(&message.id).await?;
+                EMAIL_OUTBOX_MESSAGES_TOTAL
+                    .with_label_values(&["sent"])
+                    .inc();
+                summary.sent += 1;
+                continue;
+            };
+
+            let error = format!("{:#}", err);
+            if message.attempts < self.settings.max_attempts {
+                let delay = retry_delay(message.attempts, self.settings.retry_base_secs);
+                let retry_at = Utc::now()
+                    + ChronoDuration::from_std(delay).unwrap_or_else(|_| ChronoDuration::hours(1));
+                warn!(error = %error, email = %message.id, attempt = message.attempts, "email send failed, will retry");
+                self.outbox
+                    .record_failure(&message.id, &error, Some(retry_at))
+                    .await?;
+                EMAIL_OUTBOX_MESSAGES_TOTAL
+                    .with_label_values(&["retried"])
+                    .inc();
+                summary.retried += 1;
+            } else {
+                warn!(error = %error, email = %message.id, attempt = message.attempts, "email send failed, attempts exhausted");
+                self.outbox
+                    .record_failure(&message.id, &error, None)
+                    .await?;
+                EMAIL_OUTBOX_MESSAGES_TOTAL
+                    .with_label_values(&["failed"])
+                    .inc();
+                summary.failed += 1;
+            }
+        }
+        Ok(summary)
+    }
+}
@@ -0,0 +1,280 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, oid::ObjectId, Bson, DateTime as BsonDateTime, Document},
+    options::ReturnDocument,
+    Collection, Database,
+};
+use redis::aio::ConnectionManager;
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+use crate::{
+    config::ContentSettings, metrics::EMBEDDING_WORKER_TICKS_TOTAL,
+    models::content::EmbeddingJobSummary,
+};
+
+const JOBS: &str = "embedding_jobs";
+const TEMPLATES: &str = "templates";
+
+/// Пересчёт эмбеддингов для батча шаблонов
+#[async_trait]
+pub trait EmbeddingBackend: Send + Sync {
+    /// Возвращает id шаблонов, которые не удалось обработать
+    async fn embed_templates(&self, templates: &[Document]) -> Result<Vec<ObjectId>>;
+}
+
+/// Публикует `reindex` в стрим изменений контента; сами векторы пересчитывают его консьюмеры
+pub struct StreamEmbeddingBackend {
+    redis: ConnectionManager,
+    stream_name: String,
+}
+
+impl StreamEmbeddingBackend {
+    pub fn new(redis: ConnectionManager, stream_name: String) -> Self {
+        Self { redis, stream_name }
+    }
+}
+
+#[async_trait]
+impl EmbeddingBackend for StreamEmbeddingBackend {
+    async fn embed_templates(&self, templates: &[Document]) -> Result<Vec<ObjectId>> {
+        let mut conn = self.redis.clone();
+        let mut failed = Vec::new();
+        for template in templates {
+            let id = template.get_object_id("_id")?;
+            let published = redis::cmd("XADD")
+                .arg(&self.stream_name)
+                .arg("*")
+                .arg("template_id")
+                .arg(id.to_hex())
+                .arg("action")
+                .arg("reindex")
+                .arg("timestamp")
+                .arg(Utc::now().timestamp_millis().to_string())
+                .query_async::<String>(&mut conn)
+                .await;
+            if let Err(err) = published {
+                warn!(template_id = %id, error = %err, "failed to publish reindex event");
+                failed.push(id);
+            }
+        }
+        Ok(failed)
+    }
+}
+
+/// Выполняет задания из `embedding_jobs` по одному.
+///
+/// Перед каждым батчем задание перечитывается: если администратор запросил отмену
+/// (`cancelling`), оно завершается как `cancelled`, обработанные шаблоны сохраняются в `processed`.
+pub struct EmbeddingJobWorker {
+    mongo: Database,
+    backend: Arc<dyn EmbeddingBackend>,
+    settings: ContentSettings,
+}
+
+impl EmbeddingJobWorker {
+    pub fn new(
+        mongo: Database,
+        backend: Arc<dyn EmbeddingBackend>,
+        settings: ContentSettings,
+    ) -> Self {
+        Self {
+            mongo,
+            backend,
+            settings,
+        }
+    }
+
+    pub async fn run(&self) -> Result<()> {
+        let interval = Duration::from_secs(self.settings.embedding_worker_interval_secs);
+        info!(
+            "Starting embedding job worker (interval={}s)",
+            interval.as_secs()
+        );
+
+        loop {
+            match self.run_once().await {
+                Ok(Some(job)) => {
+                    EMBEDDING_WORKER_TICKS_TOTAL
+                        .with_label_values(&["success"])
+                        .inc();
+                    info!(
+                        job_id = %job.id,
+                        status = %job.status,
+                        processed = job.processed,
+                        failed = job.failed_count,
+                        "Embedding job finished"
+                    );
+                    // Следующее задание берём сразу, без ожидания
+                    continue;
+                }
+                Ok(None) => {
+                    EMBEDDING_WORKER_TICKS_TOTAL
+                        .with_label_values(&["idle"])
+                        .inc();
+                }
+                Err(err) => {
+                    EMBEDDING_WORKER_TICKS_TOTAL
+                        .with_label_values(&["error"])
+                        .inc();
+                    warn!(error = %err, "embedding worker tick failed");
+                }
+            }
+
+            sleep(interval).await;
+        }
+    }
+
+    /// Выполнить самое старое задание из очереди. `None`, если очередь пуста
+    pub async fn run_once(&self) -> Result<Option<EmbeddingJobSummary>> {
+        self.claim_and_run(doc! { "status": "queued" }).await
+    }
+
+    /// Выполнить конкретное задание, если оно ещё в очереди
+    pub async fn run_job(&self, job_id: &ObjectId) -> Result<Option<EmbeddingJobSummary>> {
+        self.claim_and_run(doc! { "_id": job_id, "status": "queued" })
+            .await
+    }
+
+    async fn claim_and_run(&self, filter: Document) -> Result<Option<EmbeddingJobSummary>> {
+        let now = BsonDateTime::now();
+        let Some(job) = self
+            .jobs()
+            .find_one_and_update(
+                filter,
+                doc! { "$set": { "status": "running", "startedAt": now, "updatedAt": now } },
+            )
+            .sort(doc! { "createdAt": 1 })
+            .return_document(ReturnDocument::After)
+            .await
+            .context("Failed to claim embedding job")?
+        else {
+            return Ok(None);
+        };
+        let job_id = job.get_object_id("_id")?;
+
+        let status = match self.process(&job_id, &job).await {
+            Ok(status) => status,
+            Err(err) => {
+                self.finish(&job_id, "failed", Some(err.to_string()))
+                    .await?;
+                return Err(err);
+            }
+        };
+        self.finish(&job_id, status, None).await?;
+
+        let job = self
+            .jobs()
+            .find_one(doc! { "_id": job_id })
+            .await
+            .context("Failed to reload embedding job")?
+            .context("Embedding job disappeared while running")?;
+        Ok(Some(EmbeddingJobSummary::from_document(&job)))
+    }
+
+    /// Обработка батчами; возвращает итоговый статус задания
+    async fn process(&self, job_id: &ObjectId, job: &Document) -> Result<&'static str> {
+        let template_ids = match job.get_array("template_ids") {
+            Ok(ids) => ids.iter().filter_map(Bson::as_object_id).collect(),
+            Err(_) => self.all_template_ids().await?,
+        };
+        self.jobs()
+            .update_one(
+                doc! { "_id": job_id },
+                doc! { "$set": { "total": template_ids.len() as i64 } },
+            )
+            .await
+            .context("Failed to update embedding job total")?;
+
+        let templates: Collection<Document> = self.mongo.collection(TEMPLATES);
+        for batch in template_ids.chunks(self.settings.embedding_batch_size.max(1)) {
+            if self.cancel_requested(job_id).await? {
+                return Ok("cancelled");
+            }
+
+            // Шаблоны, удалённые после постановки задания, просто пропускаются
+            let documents: Vec<Document> = templates
+                .find(doc! { "_id": { "$in": batch } })
+                .await
+                .context("Failed to load templates for embeddings")?
+                .try_collect()
+                .await
+                .context("Failed to read templates for embeddings")?;
+            let failed = match self.backend.embed_templates(&documents).await {
+                Ok(failed) => failed,
+                Err(err) => {
+                    warn!(job_id = %job_id, error = %err, "embedding batch failed");
+                    batch.to_vec()
+                }
+            };
+
+            self.jobs()
+                .update_one(
+                    doc! { "_id": job_id },
+                    doc! {
+                        "$inc": { "processed": batch.len() as i64 },
+                        "$addToSet": { "failed_template_ids": { "$each": failed } },
+                        "$set": { "updatedAt": BsonDateTime::now() },
+                    },
+                )
+                .await
+                .context("Failed to update embedding progress")?;
+        }
+
+        // Отмена, пришедшая во время последнего батча, уже ничего не останавливает
+        Ok("completed")
+    }
+
+    async fn all_template_ids(&self) -> Result<Vec<ObjectId>> {
+        let templates: Collection<Document> = self.mongo.collection(TEMPLATES);
+        let documents: Vec<Document> = templates
+            .find(Document::new())
+            .projection(doc! { "_id": 1 })
+            .sort(doc! { "_id": 1 })
+            .await
+            .context("Failed to list templates for embeddings")?
+            .try_collect()
+            .await
+            .context("Failed to read templates for embeddings")?;
+        Ok(documents
+            .iter()
+            .filter_map(|document| document.get_object_id("_id").ok())
+            .collect())
+    }
+
+    async fn cancel_requested(&self, job_id: &ObjectId) -> Result<bool> {
+        let job = self
+            .jobs()
+            .find_one(doc! { "_id": job_id })
+            .projection(doc! { "status": 1 })
+            .await
+            .context("Failed to check embedding job status")?;
+        Ok(job.is_some_and(|job| {
+            job.get_str("status")
+                .is_ok_and(|status| status == "cancelling")
+        }))
+    }
+
+    async fn finish(&self, job_id: &ObjectId, status: &str, error: Option<String>) -> Result<()> {
+        let now = BsonDateTime::now();
+        let mut set = doc! { "status": status, "finishedAt": now, "updatedAt": now };
+        if let Some(error) = error {
+            set.insert("error", error);
+        }
+        self.jobs()
+            .update_one(doc! { "_id": job_id }, doc! { "$set": set })
+            .await
+            .context("Failed to finish embedding job")?;
+        Ok(())
+    }
+
+    fn jobs(&self) -> Collection<Document> {
+        self.mongo.collection(JOBS)
+    }
+}
@@ -0,0 +1,212 @@
+//! Подписи и форматы чисел и дат в выгрузках отчётов на языке запроса.
+
+use chrono::{DateTime, Utc};
+
+use crate::models::reporting::{ExportLocale, TimeRange};
+
+/// Ключ подписи в файле выгрузки
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Label {
+    GroupReport,
+    GroupReportFor,
+    StudentReport,
+    StudentReportFor,
+    ReportId,
+    GroupId,
+    UserId,
+    Group,
+    Student,
+    Format,
+    Period,
+    CreatedAt,
+    Metric,
+    Value,
+    SummaryMetrics,
+    NoData,
+    NoChartData,
+    Unavailable,
+    AvgAccuracy,
+    AvgScore,
+    TotalAttempts,
+    TotalUsers,
+    Levels,
+    Accuracy,
+    AccuracyPercent,
+    TotalScore,
+    HintsUsed,
+    HintPenalty,
+    HintsPerAttempt,
+    Leaderboard,
+    ScoreDistribution,
+    Rank,
+    Score,
+    ScoreOverTime,
+    Topics,
+    TopicAccuracy,
+    Topic,
+    Attempts,
+    Progress,
+    UpdatedAt,
+    Level,
+    /// `{n}` - число показанных строк
+    FirstEntries,
+    /// `{n}` - число показанных тем
+    FirstTopics,
+    SummarySheet,
+    GroupStudents,
+    /// `{n}` - число листов учеников
+    FirstStudentSheets,
+}
+
+impl Label {
+    pub fn text(self, locale: ExportLocale) -> &'static str {
+        let (ru, en) = match self {
+            Label::GroupReport => ("Групповой отчёт", "Group report"),
+            Label::GroupReportFor => ("Отчёт по группе", "Report for group"),
+            Label::StudentReport => ("Отчёт ученика", "Student report"),
+            Label::StudentReportFor => ("Отчёт по ученику", "Report for student"),
+            Label::ReportId => ("Отчёт ID", "Report ID"),
+            Label::GroupId => ("ID группы", "Group ID"),
+            Label::UserId => ("ID ученика", "User ID"),
+            Label::Group => ("Группа", "Group"),
+            Label::Student => ("Ученик", "Student"),
+            Label::Format => ("Формат", "Format"),
+            Label::Period => ("Период", "Period"),
+            Label::CreatedAt => ("Сформирован", "Created at"),
+            Label::Metric => ("Метрика", "Metric"),
+            Label::Value => ("Значение", "Value"),
+            Label::SummaryMetrics => ("Сводные метрики", "Summary"),
+            Label::NoData => ("Нет данных", "No data"),
+            Label::NoChartData => ("Нет данных для графика", "No data for the chart"),
+            Label::Unavailable => ("Недоступно", "Unavailable"),
+            Label::AvgAccuracy => ("Средняя точность", "Average accuracy"),
+            Label::AvgScore => ("Средний балл", "Average score"),
+            Label::TotalAttempts => ("Всего попыток", "Total attempts"),
+            Label::TotalUsers => ("Ученики", "Students"),
+            Label::Levels => ("Уровней с попытками", "Levels attempted"),
+            Label::Accuracy => ("Точность", "Accuracy"),
+            Label::AccuracyPercent => ("Точность, %", "Accuracy, %"),
+            Label::TotalScore => ("Сумма баллов", "Total score"),
+            Label::HintsUsed => ("Подсказок взято", "Hints used"),
+            Label::HintPenalty => ("Штраф за подсказки", "Hint penalty"),
+            Label::HintsPerAttempt => ("Подсказок на попытку", "Hints per attempt"),
+            Label::Leaderboard => ("Таблица лидеров", "Leaderboard"),
+            Label::ScoreDistribution => ("Распределение баллов", "Score distribution"),
+            Label::Rank => ("Место", "Rank"),
+            Label::Score => ("Баллы", "Score"),
+            Label::ScoreOverTime => ("Баллы по времени", "Score over time"),
+            Label::Topics => ("Темы", "Topics"),
+            Label::TopicAccuracy => ("Точность по темам", "Accuracy by topic"),
+            Label::Topic => ("Тема", "Topic"),
+            Label::Attempts => ("Попытки", "Attempts"),
+            Label::Progress => ("Прогресс", "Progress"),
+            Label::UpdatedAt => ("Обновлено", "Updated at"),
+            Label::Level => ("Уровень", "Level"),
+            Label::FirstEntries => ("Показаны первые {n} записей", "Showing first {n} entries"),
+            Label::FirstTopics => ("Показаны первые {n} тем", "Showing first {n} topics"),
+            Label::SummarySheet => ("Сводка", "Summary"),
+            Label::GroupStudents => ("Учеников в группе", "Students in group"),
+            Label::FirstStudentSheets => (
+                "Листы созданы для первых {n} учеников по алфавиту",
+                "Sheets cover the first {n} students alphabetically",
+            ),
+        };
+        match locale {
+            ExportLocale::Ru => ru,
+            ExportLocale::En => en,
+        }
+    }
+
+    /// Подпись с подставленным числом вместо `{n}`
+    pub fn with_count(self, locale: ExportLocale, count: usize) -> String {
+        self.text(locale).replace("{n}", &count.to_string())
+    }
+}
+
+/// Дробное число с десятичным разделителем языка: `85,5` или `85.5`
+pub fn format_decimal(locale: ExportLocale, value: f64, precision: usize) -> String {
+    let formatted = format!("{value:.precision$}");
+    match locale {
+        ExportLocale::Ru => formatted.replace('.', ","),
+        ExportLocale::En => formatted,
+    }
+}
+
+pub fn format_percent(locale: ExportLocale, value: f64, precision: usize) -> String {
+    format!("{}%", format_decimal(locale, value, precision))
+}
+
+pub fn format_timestamp(locale: ExportLocale, value: &DateTime<Utc>) -> String {
+    match locale {
+        ExportLocale::Ru => value.format("%d.%m.%Y %H:%M:%S UTC").to_string(),
+        ExportLocale::En => value.format("%d %b %Y %H:%M:%S UTC").to_string(),
+    }
+}
+
+pub fn format_period(locale: ExportLocale, range: &TimeRange) -> String {
+    let pattern = match locale {
+        ExportLocale::Ru => "%d.%m.%Y %H:%M",
+        ExportLocale::En => "%d %b %Y %H:%M",
+    };
+    format!(
+        "{} — {}",
+        range.from.format(pattern),
+        range.to.format(pattern)
+    )
+}
+
+/// Короткая дата под столбцом графика
+pub fn format_day(locale: ExportLocale, value: &DateTime<Utc>) -> String {
+    match locale {
+        ExportLocale::Ru => value.format("%d.%m").to_string(),
+        ExportLocale::En => value.format("%d %b").to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn decimals_use_locale_separator() {
+        assert_eq!(format_decimal(ExportLocale::Ru, 85.26, 1), "85,3");
+        assert_eq!(format_decimal(ExportLocale::En, 85.26, 1), "85.3");
+        assert_eq!(format_percent(ExportLocale::Ru, 0.5, 2), "0,50%");
+        assert_eq!(format_decimal(ExportLocale::Ru, 12.0, 0), "12");
+    }
+
+    #[test]
+    fn dates_follow_locale_patterns() {
+        let value = Utc.with_ymd_and_hms(2026, 3, 5, 14, 7, 9).unwrap();
+        assert_eq!(
+            format_timestamp(ExportLocale::Ru, &value),
+            "05.03.2026 14:07:09 UTC"
+        );
+        assert_eq!(
+            format_timestamp(ExportLocale::En, &value),
+            "05 Mar 2026 14:07:09 UTC"
+        );
+        assert_eq!(format_day(ExportLocale::En, &value), "05 Mar");
+        let range = TimeRange {
+            from: value,
+            to: value + chrono::Duration::days(1),
+        };
+        assert_eq!(
+            format_period(ExportLocale::Ru, &range),
+            "05.03.2026 14:07 — 06.03.2026 14:07"
+        );
+    }
+
+    #[test]
+    fn counts_are_substituted() {
+        assert_eq!(
+            Label::FirstEntries.with_count(ExportLocale::En, 10),
+            "Showing first 10 entries"
+        );
+        assert_eq!(
+            Label::FirstTopics.with_count(ExportLocale::Ru, 9),
+            "Показаны первые 9 тем"
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+use crate::{
+    config::Config,
+    metrics::EXPORT_SCHEDULE_WORKER_TICKS_TOTAL,
+    models::reporting::{
+        ExportLocale, ExportSchedule, ExportScope, ExportStatus, NewReportExport, ReportExport,
+        ReportFilters, TimeRange,
+    },
+    services::{
+        email_service::EmailService,
+        group_service::GroupService,
+        reporting_service::{ExportLinkSigner, ReportingService},
+    },
+};
+
+/// Ставит в очередь выгрузки по расписаниям и рассылает ссылки на готовые отчёты.
+///
+/// Сами файлы собирает `ExportWorker`: этот воркер только создаёт `ReportExport`
+/// раз в период и ждёт, пока выгрузка дойдёт до `Ready`.
+pub struct ExportScheduleWorker {
+    reporting_service: ReportingService,
+    email_service: EmailService,
+    /// Тот же подписчик ссылок, что у API (`AppState::export_links`)
+    export_links: Option<Arc<dyn ExportLinkSigner>>,
+    config: Config,
+}
+
+impl ExportScheduleWorker {
+    pub fn new(
+        reporting_service: ReportingService,
+        export_links: Option<Arc<dyn ExportLinkSigner>>,
+        config: Config,
+    ) -> Self {
+        let email_service = EmailService::new(reporting_service.mongo());
+        Self {
+            reporting_service,
+            email_service,
+            export_links,
+            config,
+        }
+    }
+
+    pub async fn run(&self) -> Result<()> {
+        let interval = Duration::from_secs(self.config.reporting.export_worker_interval_secs);
+        info!(
+            "Starting export schedule worker (interval={}s)",
+            interval.as_secs()
+        );
+
+        loop {
+            match self.tick(Utc::now()).await {
+                Ok(enqueued) => {
+                    EXPORT_SCHEDULE_WORKER_TICKS_TOTAL
+                        .with_label_values(&["success"])
+                        .inc();
+                    if enqueued > 0 {
+                        info!(enqueued, "Scheduled exports enqueued");
+                    }
+                }
+                Err(err) => {
+                    EXPORT_SCHEDULE_WORKER_TICKS_TOTAL
+                        .with_label_values(&["error"])
+                        .inc();
+                    warn!(error = %err, "export schedule worker tick failed");
+                }
+            }
+
+            sleep(interval).await;
+        }
+    }
+
+    /// Один проход: поставить наступившие выгрузки и разослать готовые.
+    /// Возвращает число поставленных в очередь выгрузок.
+    pub async fn tick(&self, now: DateTime<Utc>) -> Result<usize> {
+        let enqueued = self.enqueue_due(now).await?;
+        self.notify_ready().await?;
+        Ok(enqueued)
+    }
+
+    async fn enqueue_due(&self, now: DateTime<Utc>) -> Result<usize> {
+        let schedules = self.reporting_service.fetch_export_schedules().await?;
+
+        let mut enqueued = 0;
+        for schedule in schedules.into_iter().filter(|item| item.is_due(now)) {
+            match self.enqueue_schedule(&schedule, now).await {
+                Ok(true) => enqueued += 1,
+                Ok(false) => {}
+                Err(err) => {
+                    warn!(error = %err, schedule = %schedule.id, "failed to enqueue scheduled export")
+                }
+            }
+        }
+        Ok(enqueued)
+    }
+
+    async fn enqueue_schedule(
+        &self,
+        schedule: &ExportSchedule,
+        now: DateTime<Utc>,
+    ) -> Result<bool> {
+        let slot = schedule.latest_run_slot(now);
+        let claimed = self
+            .reporting_service
+            .claim_schedule_run(&schedule.id, slot, now)
+            .await?;
+        if !claimed {
+            return Ok(false);
+        }
+
+        let expires_at = now
+            + ChronoDuration::from_std(self.config.reporting.export_expiration())
+                .map_err(|_| anyhow!("Invalid export expiration configured"))?;
+        let request = NewReportExport {
+            scope: ExportScope::Group,
+            subject_id: schedule.group_id,
+            teacher_id: schedule.teacher_id,
+            format: schedule.format.clone(),
+            filters: ReportFilters {
+                topic_ids: Vec::new(),
+                period: TimeRange {
+                    from: schedule.cadence.period_start(slot),
+                    to: now,
+                },
+                fields: Vec::new(),
+                reveal_user_ids: false,
+                locale: ExportLocale::default(),
+            },
+            expires_at,
+        };
+
+        let export = match self.reporting_service.create_export_request(request).await {
+            Ok(export) => export,
+            Err(err) => {
+                self.reporting_service
+                    .release_schedule_run(&schedule.id, schedule.last_run_at)
+                    .await?;
+                return Err(err);
+            }
+        };
+
+        self.reporting_service
+            .record_schedule_export(&schedule.id, &export.id)
+            .await?;
+        info!(schedule = %schedule.id, export = %export.id, "scheduled export enqueued");
+        Ok(true)
+    }
+
+    async fn notify_ready(&self) -> Result<()> {
+        let schedules = self
+            .reporting_service
+            .fetch_schedules_awaiting_notification()
+            .await?;
+
+        for schedule in schedules {
+            let Some(export_id) = schedule.last_export_id else {
+                continue;
+            };
+            let export = self.reporting_service.get_export_by_id(&export_id).await?;
+
+            match export {
+                Some(export) if export.status == ExportStatus::Ready => {
+                    if let Err(err) = self.send_download_link(&schedule, &export).await {
+                        // Отметку не ставим: письмо повторится на следующем проходе
+                        warn!(error = %err, schedule = %schedule.id, "failed to email scheduled export");
+                        continue;
+                    }
+                }
+                Some(export) if !export.status.is_terminal() => continue,
+                _ => {
+                    warn!(schedule = %schedule.id, export = %export_id, "scheduled export failed or missing");
+                }
+            }
+
+            self.reporting_service
+                .mark_schedule_notified(&schedule.id, &export_id)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn send_download_link(
+        &self,
+        schedule: &ExportSchedule,
+        export: &ReportExport,
+    ) -> Result<()> {
+        if EmailService::sending_disabled() {
+            info!(schedule = %schedule.id, "Email sending disabled, scheduled export link not sent");
+            return Ok(());
+        }
+
+        let signer = self
+            .export_links
+            .as_ref()
+            .ok_or_else(|| anyhow!("Object storage is not configured"))?;
+        let key = export
+            .storage_key
+            .as_deref()
+            .ok_or_else(|| anyhow!("Ready export has no storage key"))?;
+        let ttl = self.config.presign_ttl();
+        let download_url = signer.presigned_download_url(key, ttl)?;
+        let link_expires_at = export
+            .expires_at
+            .min(Utc::now() + ChronoDuration::from_std(ttl).unwrap_or_default());
+
+        let group_id = schedule.group_id.to_hex();
+        let group_name = GroupService::new(self.reporting_service.mongo())
+            .get_group(&group_id)
+            .await
+            .map(|group| group.name)
+            .unwrap_or(group_id);
+
+        let subject = format!("Отчёт по группе {}", group_name);
+        let body = format!(
+            "Здравствуйте!\n\nРегулярный отчёт ({}) по группе {} готов.\nСкачать: {}\n\nСсылка действительна до {}.\n",
+            export.format.as_label(),
+            group_name,
+            download_url,
+            link_expires_at.format("%d.%m.%Y %H:%M UTC")
+        );
+
+        for recipient in &schedule.recipients {
+            let name = recipient.split('@').next().unwrap_or(recipient);
+            self.email_service
+                .send_notification_email(recipient, name, &subject, &body)
+                .await?;
+        }
+        Ok(())
+    }
+}
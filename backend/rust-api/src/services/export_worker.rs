@@ -1,75 +1,508 @@
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 
-use anyhow::Result;
-use chrono::{DateTime, Utc};
-use mongodb::bson::Bson;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{Duration as ChronoDuration, Utc};
+use futures::{future::join_all, TryStreamExt};
+use hmac::{Hmac, Mac};
+use mongodb::bson::{oid::ObjectId, Bson};
 use printpdf::{
-    BuiltinFont, Color, Greyscale, Line, LinePoint, Mm, Op, PaintMode, PdfDocument, PdfPage,
-    PdfSaveOptions, Point, Polygon, PolygonRing, Pt, Rgb, TextItem, WindingOrder,
+    Color, Greyscale, Mm, Op, PdfDocument, PdfPage, PdfSaveOptions, PdfWarnMsg, Point, Pt, Rgb,
 };
-use rust_xlsxwriter::{Format, Workbook};
+use rust_xlsxwriter::{Chart, ChartType, Format, Workbook, Worksheet};
+use sha2::Sha256;
 use tokio::time::sleep;
 use tracing::{info, warn};
 
 use crate::{
     config::Config,
     metrics::{EXPORTS_GENERATED_TOTAL, EXPORT_WORKER_TICKS_TOTAL},
-    models::reporting::{
-        ExportFormat, ExportStatus, LeaderboardDocument, LeaderboardScope, MaterializedStat,
-        ReportExport, TimeRange,
+    models::{
+        answer::AttemptRecord,
+        reporting::{
+            ExportFormat, ExportLocale, ExportScope, ExportStatus, LeaderboardDocument,
+            LeaderboardScope, MaterializedStat, ReportExport,
+        },
+        ProgressSummary,
     },
-    services::{object_storage::ObjectStorageClient, reporting_service::ReportingService},
+    services::{
+        export_i18n::{self, Label},
+        object_storage::ObjectStorageClient,
+        pdf::{
+            accent_color, document_warnings, push_pdf_line, push_pdf_rect, push_pdf_text, PdfFonts,
+        },
+        reporting_service::{
+            GroupStudentProgress, HintTotals, ReportingService, TopicAnalyticsRow,
+        },
+    },
+    utils::csv::escape_csv_field,
 };
 
-/// Escapes CSV field to prevent formula injection attacks.
-/// Prefixes dangerous characters (=, +, @, -, tab, newline) with a tab to neutralize them.
-/// Also wraps fields containing special characters in quotes.
-fn escape_csv_field(value: &str) -> String {
-    // Prevent formula injection by prefixing dangerous characters with tab
-    let sanitized = if value.starts_with(['=', '+', '@', '-', '\t', '\r', '\n']) {
-        format!("\t{}", value)
-    } else {
-        value.to_string()
-    };
-
-    // Escape quotes and wrap in quotes if contains special characters
-    if sanitized.contains([',', '"', '\n', '\r']) {
-        format!("\"{}\"", sanitized.replace('"', "\"\""))
-    } else {
-        sanitized
+/// Максимальная пауза между повторами сборки выгрузки
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(3600);
+
+/// Пауза перед следующей попыткой: `base * 2^(attempt - 1)`, не больше часа.
+/// `attempt` — номер только что неудавшейся попытки, начиная с 1.
+pub fn retry_delay(attempt: u32, base_secs: u64) -> Duration {
+    let factor = 1u64 << attempt.saturating_sub(1).min(16);
+    Duration::from_secs(base_secs.saturating_mul(factor)).min(MAX_RETRY_DELAY)
+}
+
+/// Куда воркер складывает готовые файлы (по умолчанию - объектное хранилище)
+#[async_trait]
+pub trait ExportStorage: Send + Sync {
+    /// Ключ файла; префикс зависит от `scope` (`groups/…` или `users/…`)
+    fn export_key(
+        &self,
+        scope: ExportScope,
+        owner_id: &str,
+        export_id: &str,
+        extension: &str,
+    ) -> String;
+    async fn upload_export(&self, key: &str, bytes: Vec<u8>, content_type: &str) -> Result<()>;
+
+    /// Составная загрузка для потоковых выгрузок (NDJSON): возвращает id загрузки.
+    /// Хранилища без её поддержки отдают только файлы целиком.
+    async fn begin_multipart(&self, key: &str, _content_type: &str) -> Result<String> {
+        Err(anyhow!(
+            "Storage does not support multipart upload of {}",
+            key
+        ))
+    }
+
+    /// Загрузить часть `part_number` (с 1); возвращает ETag
+    async fn upload_part(
+        &self,
+        key: &str,
+        _upload_id: &str,
+        _part_number: u32,
+        _bytes: Vec<u8>,
+    ) -> Result<String> {
+        Err(anyhow!(
+            "Storage does not support multipart upload of {}",
+            key
+        ))
+    }
+
+    async fn complete_multipart(
+        &self,
+        key: &str,
+        _upload_id: &str,
+        _etags: &[String],
+    ) -> Result<()> {
+        Err(anyhow!(
+            "Storage does not support multipart upload of {}",
+            key
+        ))
+    }
+
+    async fn abort_multipart(&self, _key: &str, _upload_id: &str) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ExportStorage for ObjectStorageClient {
+    fn export_key(
+        &self,
+        scope: ExportScope,
+        owner_id: &str,
+        export_id: &str,
+        extension: &str,
+    ) -> String {
+        self.build_export_key(scope, owner_id, export_id, extension)
+    }
+
+    async fn upload_export(&self, key: &str, bytes: Vec<u8>, content_type: &str) -> Result<()> {
+        self.upload_bytes(key, bytes, content_type).await
+    }
+
+    async fn begin_multipart(&self, key: &str, content_type: &str) -> Result<String> {
+        self.create_multipart_upload(key, content_type).await
+    }
+
+    async fn upload_part(
+        &self,
+        key: &str,
+        upload_id: &str,
+        part_number: u32,
+        bytes: Vec<u8>,
+    ) -> Result<String> {
+        ObjectStorageClient::upload_part(self, key, upload_id, part_number, bytes).await
+    }
+
+    async fn complete_multipart(&self, key: &str, upload_id: &str, etags: &[String]) -> Result<()> {
+        self.complete_multipart_upload(key, upload_id, etags).await
+    }
+
+    async fn abort_multipart(&self, key: &str, upload_id: &str) -> Result<()> {
+        self.abort_multipart_upload(key, upload_id).await
+    }
+}
+
+/// Запись NDJSON частями: в памяти лежит не больше одной части. Если вся выгрузка
+/// уместилась в первую часть, она уходит одним `upload_export` без составной загрузки.
+struct PartWriter<'a> {
+    storage: &'a dyn ExportStorage,
+    key: &'a str,
+    content_type: &'a str,
+    part_size: usize,
+    buffer: Vec<u8>,
+    upload_id: Option<String>,
+    etags: Vec<String>,
+}
+
+impl<'a> PartWriter<'a> {
+    fn new(
+        storage: &'a dyn ExportStorage,
+        key: &'a str,
+        content_type: &'a str,
+        part_size: usize,
+    ) -> Self {
+        let part_size = part_size.max(1);
+        Self {
+            storage,
+            key,
+            content_type,
+            part_size,
+            buffer: Vec::with_capacity(part_size.min(1024 * 1024)),
+            upload_id: None,
+            etags: Vec::new(),
+        }
+    }
+
+    async fn write(&mut self, bytes: &[u8]) -> Result<()> {
+        self.buffer.extend_from_slice(bytes);
+        if self.buffer.len() >= self.part_size {
+            self.flush_part().await?;
+        }
+        Ok(())
+    }
+
+    async fn flush_part(&mut self) -> Result<()> {
+        let upload_id = match &self.upload_id {
+            Some(upload_id) => upload_id.clone(),
+            None => {
+                let upload_id = self
+                    .storage
+                    .begin_multipart(self.key, self.content_type)
+                    .await?;
+                self.upload_id = Some(upload_id.clone());
+                upload_id
+            }
+        };
+        let part = std::mem::take(&mut self.buffer);
+        let etag = self
+            .storage
+            .upload_part(self.key, &upload_id, self.etags.len() as u32 + 1, part)
+            .await?;
+        self.etags.push(etag);
+        Ok(())
+    }
+
+    async fn finish(mut self) -> Result<()> {
+        if self.upload_id.is_none() {
+            return self
+                .storage
+                .upload_export(self.key, self.buffer, self.content_type)
+                .await;
+        }
+        if !self.buffer.is_empty() {
+            self.flush_part().await?;
+        }
+        let upload_id = self.upload_id.take().unwrap_or_default();
+        self.storage
+            .complete_multipart(self.key, &upload_id, &self.etags)
+            .await
+    }
+
+    /// Отменить начатую составную загрузку после ошибки
+    async fn abort(self) {
+        if let Some(upload_id) = &self.upload_id {
+            if let Err(err) = self.storage.abort_multipart(self.key, upload_id).await {
+                warn!(error = %err, key = self.key, "failed to abort multipart upload");
+            }
+        }
+    }
+}
+
+/// Стабильный псевдоним ученика в выгрузках группы: HMAC от id группы и ученика.
+/// Без секрета его не сопоставить с настоящим id, а в разных выгрузках одной
+/// группы ученик получает один и тот же псевдоним.
+pub fn pseudonymize_user_id(secret: &str, group_id: &ObjectId, user_id: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(group_id.to_hex().as_bytes());
+    mac.update(b":");
+    mac.update(user_id.as_bytes());
+    let digest = mac.finalize().into_bytes();
+    format!("anon-{}", hex::encode(&digest[..8]))
+}
+
+/// Строка NDJSON для одного ответа; `fields` - белый список, пустой - все поля.
+/// `user_name` - только когда id не псевдонимизированы.
+fn answer_line(
+    attempt: &AttemptRecord,
+    user_id: String,
+    user_name: Option<&str>,
+    fields: &[String],
+) -> Result<Vec<u8>> {
+    let wanted = |field: &str| fields.is_empty() || fields.iter().any(|f| f == field);
+    let mut row = serde_json::Map::new();
+    if wanted("user_id") {
+        row.insert("user_id".into(), user_id.into());
+    }
+    if let Some(name) = user_name.filter(|_| wanted("user_name")) {
+        row.insert("user_name".into(), name.into());
+    }
+    if wanted("session_id") {
+        row.insert("session_id".into(), attempt.session_id.clone().into());
+    }
+    if wanted("task_id") {
+        row.insert("task_id".into(), attempt.task_id.clone().into());
+    }
+    if wanted("answer") {
+        row.insert("answer".into(), attempt.answer.clone().into());
+    }
+    if wanted("correct") {
+        row.insert("correct".into(), attempt.correct.into());
+    }
+    if wanted("score") {
+        row.insert("score".into(), attempt.score.into());
+    }
+    if wanted("timestamp") {
+        row.insert("timestamp".into(), attempt.timestamp.to_rfc3339().into());
+    }
+    if wanted("reason") {
+        row.insert("reason".into(), serde_json::to_value(&attempt.reason)?);
+    }
+    let mut line = serde_json::to_vec(&row)?;
+    line.push(b'\n');
+    Ok(line)
+}
+
+/// Итог одного тика воркера
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ExportTickSummary {
+    pub completed: usize,
+    /// Попытка не удалась, выгрузка вернулась в очередь
+    pub retried: usize,
+    /// Попытки исчерпаны, выгрузка помечена failed
+    pub failed: usize,
+}
+
+/// Сколько учеников попало в каждый диапазон точности 0-10 … 90-100.
+/// 100% относится к последнему диапазону
+fn score_distribution(accuracy: &[f64]) -> [u64; SCORE_BUCKETS] {
+    let mut buckets = [0u64; SCORE_BUCKETS];
+    for value in accuracy {
+        let bucket = (value.clamp(0.0, 100.0) / 10.0) as usize;
+        buckets[bucket.min(SCORE_BUCKETS - 1)] += 1;
+    }
+    buckets
+}
+
+enum ExportOutcome {
+    Completed,
+    Retried,
+    Failed,
+}
+
+/// Сколько последних записей прогресса попадает на график баллов в личном отчёте
+const SCORE_CHART_BARS: usize = 10;
+
+/// Больше листов учеников в XLSX-выгрузке группы не создаётся
+const XLSX_STUDENT_SHEETS_LIMIT: usize = 500;
+/// Диапазонов точности в распределении баллов группы: 0-10 … 90-100
+const SCORE_BUCKETS: usize = 10;
+
+/// Значение сводной метрики группы
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MetricValue {
+    /// Проценты по шкале 0-100
+    Percent(f64),
+    Decimal(f64),
+    Count(i64),
+}
+
+/// Область диаграммы на странице PDF, в миллиметрах
+#[derive(Debug, Clone, Copy)]
+struct ChartArea {
+    left: f32,
+    bottom: f32,
+    width: f32,
+    height: f32,
+}
+
+/// Собранный PDF и предупреждения printpdf об операциях страницы
+struct RenderedPdf {
+    bytes: Vec<u8>,
+    warnings: Vec<PdfWarnMsg>,
+}
+
+impl RenderedPdf {
+    fn new(bytes: Vec<u8>, warnings: Vec<PdfWarnMsg>) -> Self {
+        Self {
+            bytes,
+            warnings: document_warnings(warnings),
+        }
+    }
+
+    fn into_bytes(self, export: &ReportExport) -> Vec<u8> {
+        if !self.warnings.is_empty() {
+            warn!(export = %export.id, warnings = ?self.warnings, "PDF export rendered with warnings");
+        }
+        self.bytes
+    }
+}
+
+/// Данные личного отчёта ученика
+struct UserReport {
+    user_id: ObjectId,
+    /// Прогресс по уровням, от давнего к свежему
+    progress: Vec<ProgressSummary>,
+    topics: Vec<TopicAnalyticsRow>,
+    hints: HintTotals,
+}
+
+impl UserReport {
+    fn total_attempts(&self) -> u64 {
+        self.progress
+            .iter()
+            .map(|entry| u64::from(entry.attempts_total))
+            .sum()
+    }
+
+    fn total_correct(&self) -> u64 {
+        self.progress
+            .iter()
+            .map(|entry| u64::from(entry.correct_count))
+            .sum()
+    }
+
+    fn total_score(&self) -> i64 {
+        self.progress
+            .iter()
+            .map(|entry| i64::from(entry.score))
+            .sum()
+    }
+
+    /// Доля верных ответов по всем уровням, в процентах
+    fn accuracy(&self) -> Option<f64> {
+        let attempts = self.total_attempts();
+        (attempts > 0).then(|| self.total_correct() as f64 / attempts as f64 * 100.0)
+    }
+
+    fn hints_per_attempt(&self) -> Option<f64> {
+        let attempts = self.total_attempts();
+        (attempts > 0).then(|| self.hints.hints as f64 / attempts as f64)
+    }
+
+    fn summary_rows(&self, locale: ExportLocale) -> Vec<(String, String)> {
+        let label = |label: Label| label.text(locale).to_string();
+        let mut rows = vec![
+            (label(Label::Levels), self.progress.len().to_string()),
+            (
+                label(Label::TotalAttempts),
+                self.total_attempts().to_string(),
+            ),
+        ];
+        if let Some(accuracy) = self.accuracy() {
+            rows.push((
+                label(Label::Accuracy),
+                export_i18n::format_percent(locale, accuracy, 1),
+            ));
+        }
+        rows.push((label(Label::TotalScore), self.total_score().to_string()));
+        rows.push((label(Label::HintsUsed), self.hints.hints.to_string()));
+        rows.push((label(Label::HintPenalty), self.hints.cost.to_string()));
+        if let Some(ratio) = self.hints_per_attempt() {
+            rows.push((
+                label(Label::HintsPerAttempt),
+                export_i18n::format_decimal(locale, ratio, 2),
+            ));
+        }
+        rows
+    }
+
+    /// Тема, средняя точность, попытки, баллы
+    fn topic_rows(&self) -> Vec<(String, f64, i64, i64)> {
+        self.topics
+            .iter()
+            .map(|row| {
+                (
+                    row.topic_name
+                        .clone()
+                        .unwrap_or_else(|| row.topic_id.to_hex()),
+                    row.avg_percentage.unwrap_or(0.0),
+                    row.total_attempts.unwrap_or(0),
+                    row.total_score.unwrap_or(0),
+                )
+            })
+            .collect()
+    }
+
+    /// Баллы по последним обновлениям прогресса — столбцы графика
+    fn score_timeline(&self, locale: ExportLocale) -> Vec<(String, i64)> {
+        let skip = self.progress.len().saturating_sub(SCORE_CHART_BARS);
+        self.progress
+            .iter()
+            .skip(skip)
+            .map(|entry| {
+                (
+                    export_i18n::format_day(locale, &entry.updated_at),
+                    i64::from(entry.score),
+                )
+            })
+            .collect()
     }
 }
 
 pub struct ExportWorker {
     reporting_service: ReportingService,
-    object_storage: ObjectStorageClient,
+    storage: Arc<dyn ExportStorage>,
     config: Config,
 }
 
 impl ExportWorker {
     pub fn new(
         reporting_service: ReportingService,
-        object_storage: ObjectStorageClient,
+        storage: Arc<dyn ExportStorage>,
         config: Config,
     ) -> Self {
         Self {
             reporting_service,
-            object_storage,
+            storage,
             config,
         }
     }
 
     pub async fn run(&self) -> Result<()> {
         let interval = Duration::from_secs(self.config.reporting.export_worker_interval_secs);
-        info!("Starting export worker (interval={}s)", interval.as_secs());
+        info!(
+            "Starting export worker (interval={}s, concurrency={})",
+            interval.as_secs(),
+            self.config.reporting.export_concurrency
+        );
 
         loop {
             match self.process_pending().await {
-                Ok(_) => {
+                Ok(summary) => {
                     EXPORT_WORKER_TICKS_TOTAL
                         .with_label_values(&["success"])
                         .inc();
-                    info!("Export worker tick completed");
+                    EXPORT_WORKER_TICKS_TOTAL
+                        .with_label_values(&["retried"])
+                        .inc_by(summary.retried as u64);
+                    EXPORT_WORKER_TICKS_TOTAL
+                        .with_label_values(&["failed"])
+                        .inc_by(summary.failed as u64);
+                    info!(
+                        completed = summary.completed,
+                        retried = summary.retried,
+                        failed = summary.failed,
+                        "Export worker tick completed"
+                    );
                 }
                 Err(err) => {
                     EXPORT_WORKER_TICKS_TOTAL
@@ -79,64 +512,113 @@ impl ExportWorker {
                 }
             }
 
+            // Живость воркера, а не успех тика: упавший тик всё равно означает,
+            // что цикл крутится
+            if let Err(err) = self
+                .reporting_service
+                .record_export_worker_heartbeat(Utc::now())
+                .await
+            {
+                warn!(error = %err, "failed to write export worker heartbeat");
+            }
+
             sleep(interval).await;
         }
     }
 
-    async fn process_pending(&self) -> Result<()> {
-        let pending = self.reporting_service.fetch_pending_exports(10).await?;
-        if pending.is_empty() {
-            return Ok(());
+    /// Один тик: взять до `export_concurrency` выгрузок и собрать их параллельно
+    pub async fn process_pending(&self) -> Result<ExportTickSummary> {
+        let now = Utc::now();
+        let mut claimed = Vec::new();
+        while claimed.len() < self.config.reporting.export_concurrency.max(1) {
+            match self.reporting_service.claim_pending_export(now).await? {
+                Some(export) => claimed.push(export),
+                None => break,
+            }
         }
 
-        for export in pending {
-            let export_id = export.id;
-            if let Err(err) = self.process_export(export).await {
-                warn!(error = %err, export = %export_id, "failed to process export");
+        let outcomes = join_all(
+            claimed
+                .into_iter()
+                .map(|export| self.attempt_export(export)),
+        )
+        .await;
+
+        let mut summary = ExportTickSummary::default();
+        for outcome in outcomes {
+            match outcome? {
+                ExportOutcome::Completed => summary.completed += 1,
+                ExportOutcome::Retried => summary.retried += 1,
+                ExportOutcome::Failed => summary.failed += 1,
             }
         }
-
-        Ok(())
+        Ok(summary)
     }
 
-    async fn process_export(&self, export: ReportExport) -> Result<()> {
-        self.reporting_service
-            .update_export_status(&export.id, ExportStatus::Processing, None, None)
-            .await?;
+    /// Собрать выгрузку; при ошибке записать её и решить, повторять ли
+    async fn attempt_export(&self, export: ReportExport) -> Result<ExportOutcome> {
+        let export_id = export.id;
+        let attempt = export.attempts;
+        let Err(err) = self.process_export(export).await else {
+            return Ok(ExportOutcome::Completed);
+        };
 
-        let (stats, leaderboard) = tokio::try_join!(
-            self.reporting_service.load_group_snapshot(&export.group_id),
+        let message = format!("{:#}", err);
+        let settings = &self.config.reporting;
+        if attempt < settings.export_max_attempts {
+            let delay = retry_delay(attempt, settings.export_retry_base_secs);
+            let retry_at = Utc::now()
+                + ChronoDuration::from_std(delay).unwrap_or_else(|_| ChronoDuration::hours(1));
+            warn!(error = %message, export = %export_id, attempt, "export attempt failed, will retry");
             self.reporting_service
-                .load_leaderboard(LeaderboardScope::Group, Some(&export.group_id))
-        )?;
+                .record_export_failure(&export_id, &message, Some(retry_at))
+                .await?;
+            Ok(ExportOutcome::Retried)
+        } else {
+            warn!(error = %message, export = %export_id, attempt, "export failed, attempts exhausted");
+            self.reporting_service
+                .record_export_failure(&export_id, &message, None)
+                .await?;
+            Ok(ExportOutcome::Failed)
+        }
+    }
 
-        let (payload, extension, content_type) = match export.format {
-            ExportFormat::Csv => (
-                self.build_csv(&export, stats.as_ref(), leaderboard.as_ref()),
-                "csv",
-                "text/csv",
-            ),
-            ExportFormat::Pdf => (
-                self.build_pdf(&export, stats.as_ref(), leaderboard.as_ref())?,
-                "pdf",
-                "application/pdf",
-            ),
-            ExportFormat::Xlsx => (
-                self.build_xlsx(&export, stats.as_ref(), leaderboard.as_ref())?,
-                "xlsx",
-                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
-            ),
+    async fn process_export(&self, export: ReportExport) -> Result<()> {
+        let subject_id = export
+            .subject_id()
+            .ok_or_else(|| anyhow!("Export {} has no group or user", export.id))?;
+        let extension = match export.format {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Pdf => "pdf",
+            ExportFormat::Xlsx => "xlsx",
+            ExportFormat::Ndjson => "ndjson",
         };
+        let content_type = export.format.as_mime();
 
-        let key = self.object_storage.build_export_key(
-            &export.group_id.to_string(),
-            &export.id.to_string(),
+        let key = self.storage.export_key(
+            export.scope,
+            &subject_id.to_hex(),
+            &export.id.to_hex(),
             extension,
         );
 
-        self.object_storage
-            .upload_bytes(&key, payload, content_type)
-            .await?;
+        if export.format == ExportFormat::Ndjson {
+            if export.scope != ExportScope::Group {
+                return Err(anyhow!("NDJSON exports are only built for groups"));
+            }
+            let rows = self
+                .stream_group_answers(&export, &subject_id, &key)
+                .await?;
+            info!(export = %export.id, rows, "ndjson export streamed");
+        } else {
+            let payload = match export.scope {
+                ExportScope::Group => self.render_group_export(&export, &subject_id).await?,
+                ExportScope::User => self.render_user_export(&export, &subject_id).await?,
+            };
+            self.storage
+                .upload_export(&key, payload, content_type)
+                .await?;
+        }
 
         self.reporting_service
             .update_export_status(&export.id, ExportStatus::Ready, Some(&key), None)
@@ -150,134 +632,225 @@ impl ExportWorker {
         Ok(())
     }
 
-    fn build_csv(
+    /// Сырые ответы учеников группы: курсор MongoDB пишется в хранилище частями,
+    /// так что в памяти держатся только состав группы и одна часть файла
+    async fn stream_group_answers(
         &self,
         export: &ReportExport,
-        stats: Option<&crate::models::reporting::MaterializedStat>,
-        leaderboard: Option<&crate::models::reporting::LeaderboardDocument>,
-    ) -> Vec<u8> {
-        let mut lines = vec![
-            "Metric,Value".to_string(),
-            format!("Group ID,{}", export.group_id),
-            format!(
-                "Format,{}",
-                match export.format {
-                    ExportFormat::Csv => "CSV",
-                    ExportFormat::Pdf => "PDF",
-                    ExportFormat::Xlsx => "XLSX",
-                }
-            ),
-            format!("Created At,{}", export.created_at),
-        ];
+        group_id: &ObjectId,
+        key: &str,
+    ) -> Result<u64> {
+        let students = self.reporting_service.group_student_names(group_id).await?;
+        let mut answers = self
+            .reporting_service
+            .answers_cursor(students.keys().cloned().collect(), &export.filters.period)
+            .await?;
 
-        if let Some(stats) = stats {
-            let metrics = &stats.metrics;
-            if let Some(avg_accuracy) = metrics.get("avg_accuracy") {
-                lines.push(format!("Avg Accuracy,{}", avg_accuracy));
-            }
-            if let Some(avg_score) = metrics.get("avg_score") {
-                lines.push(format!("Avg Score,{}", avg_score));
+        let reveal = export.filters.reveal_user_ids;
+        let mut writer = PartWriter::new(
+            self.storage.as_ref(),
+            key,
+            export.format.as_mime(),
+            self.config.reporting.export_part_size_bytes,
+        );
+        let mut rows = 0u64;
+        let streamed: Result<()> = async {
+            while let Some(attempt) = answers.try_next().await? {
+                let (user_id, user_name) = if reveal {
+                    let name = students.get(&attempt.user_id).map(String::as_str);
+                    (attempt.user_id.clone(), name)
+                } else {
+                    let pseudonym = pseudonymize_user_id(
+                        &self.config.reporting.export_pseudonym_secret,
+                        group_id,
+                        &attempt.user_id,
+                    );
+                    (pseudonym, None)
+                };
+                let line = answer_line(&attempt, user_id, user_name, &export.filters.fields)?;
+                writer.write(&line).await?;
+                rows += 1;
             }
-            if let Some(total_attempts) = metrics.get("total_attempts") {
-                lines.push(format!("Total Attempts,{}", total_attempts));
+            Ok(())
+        }
+        .await;
+
+        match streamed {
+            Ok(()) => writer.finish().await?,
+            Err(err) => {
+                writer.abort().await;
+                return Err(err);
             }
-            if let Some(total_users) = metrics.get("total_users") {
-                lines.push(format!("Total Users,{}", total_users));
+        }
+        Ok(rows)
+    }
+
+    async fn render_group_export(
+        &self,
+        export: &ReportExport,
+        group_id: &ObjectId,
+    ) -> Result<Vec<u8>> {
+        let (stats, leaderboard, accuracy) = tokio::try_join!(
+            self.reporting_service.load_group_snapshot(group_id),
+            self.reporting_service
+                .load_leaderboard(LeaderboardScope::Group, Some(group_id)),
+            self.reporting_service.group_student_accuracy(group_id)
+        )?;
+        let distribution = score_distribution(&accuracy);
+        // Листы учеников есть только в XLSX
+        let students = if export.format == ExportFormat::Xlsx {
+            self.reporting_service
+                .load_group_student_progress(group_id, XLSX_STUDENT_SHEETS_LIMIT)
+                .await?
+        } else {
+            GroupStudentProgress::default()
+        };
+
+        match export.format {
+            ExportFormat::Csv => Ok(Self::build_csv(
+                export,
+                group_id,
+                stats.as_ref(),
+                leaderboard.as_ref(),
+            )),
+            ExportFormat::Pdf => Self::build_pdf(
+                export,
+                group_id,
+                stats.as_ref(),
+                leaderboard.as_ref(),
+                &distribution,
+            )
+            .map(|pdf| pdf.into_bytes(export)),
+            ExportFormat::Xlsx => Self::build_xlsx(
+                export,
+                group_id,
+                stats.as_ref(),
+                leaderboard.as_ref(),
+                &distribution,
+                &students,
+            ),
+            ExportFormat::Ndjson => Err(anyhow!("NDJSON exports are streamed, not rendered")),
+        }
+    }
+
+    async fn render_user_export(
+        &self,
+        export: &ReportExport,
+        user_id: &ObjectId,
+    ) -> Result<Vec<u8>> {
+        let student_ids = [user_id.to_hex()];
+        let (mut progress, topics, hints) = tokio::try_join!(
+            self.reporting_service.load_user_progress(user_id),
+            self.reporting_service.aggregate_topic_stats(&student_ids),
+            self.reporting_service.user_hint_totals(user_id)
+        )?;
+        progress.sort_by_key(|entry| entry.updated_at);
+        let report = UserReport {
+            user_id: *user_id,
+            progress,
+            topics,
+            hints,
+        };
+
+        match export.format {
+            ExportFormat::Csv => Ok(Self::build_user_csv(export, &report)),
+            ExportFormat::Pdf => {
+                Self::build_user_pdf(export, &report).map(|pdf| pdf.into_bytes(export))
             }
+            ExportFormat::Xlsx => Self::build_user_xlsx(export, &report),
+            ExportFormat::Ndjson => Err(anyhow!("NDJSON exports are only built for groups")),
+        }
+    }
+
+    fn build_csv(
+        export: &ReportExport,
+        group_id: &ObjectId,
+        stats: Option<&MaterializedStat>,
+        leaderboard: Option<&LeaderboardDocument>,
+    ) -> Vec<u8> {
+        let locale = export.filters.locale;
+        let text = |label: Label| label.text(locale);
+        let mut lines = vec![
+            Self::csv_row(&[text(Label::Metric), text(Label::Value)]),
+            Self::csv_row(&[text(Label::GroupId), &group_id.to_hex()]),
+            Self::csv_row(&[text(Label::Format), export.format.as_label()]),
+            Self::csv_row(&[
+                text(Label::CreatedAt),
+                &export_i18n::format_timestamp(locale, &export.created_at),
+            ]),
+        ];
+        for (label, value) in Self::summary_metrics(locale, stats) {
+            lines.push(Self::csv_row(&[&label, &value]));
         }
 
         lines.push("".into());
-        lines.push("Leaderboard".into());
-        lines.push("Rank,User,Score".into());
-
-        if let Some(lb) = leaderboard {
-            for entry in &lb.rankings {
-                lines.push(format!(
-                    "{},{},{}",
-                    entry.rank,
-                    escape_csv_field(&entry.name),
-                    entry.score
-                ));
-            }
+        lines.push(text(Label::Leaderboard).into());
+        lines.push(Self::csv_row(&[
+            text(Label::Rank),
+            text(Label::Student),
+            text(Label::Score),
+        ]));
+        for (rank, name, score) in Self::leaderboard_rows(leaderboard) {
+            lines.push(Self::csv_row(&[
+                &rank.to_string(),
+                &name,
+                &score.to_string(),
+            ]));
         }
 
         lines.join("\n").into_bytes()
     }
 
     fn build_pdf(
-        &self,
         export: &ReportExport,
+        group_id: &ObjectId,
         stats: Option<&MaterializedStat>,
         leaderboard: Option<&LeaderboardDocument>,
-    ) -> Result<Vec<u8>> {
-        let mut document = PdfDocument::new("Групповой отчёт");
-        let summary_rows = Self::summary_metrics(stats);
+        distribution: &[u64; SCORE_BUCKETS],
+    ) -> Result<RenderedPdf> {
+        let locale = export.filters.locale;
+        let mut document = PdfDocument::new(Label::GroupReport.text(locale));
+        let fonts = PdfFonts::load(&mut document, locale)?;
+        let summary_rows = Self::summary_metrics(locale, stats);
         let leaderboard_rows = Self::leaderboard_rows(leaderboard);
         let mut ops = Vec::new();
 
-        let accent_color = Color::Rgb(Rgb {
-            r: 0.16,
-            g: 0.4,
-            b: 0.69,
-            icc_profile: None,
-        });
-        let bar_palette = [
-            Color::Rgb(Rgb {
-                r: 0.23,
-                g: 0.52,
-                b: 0.87,
-                icc_profile: None,
-            }),
-            Color::Rgb(Rgb {
-                r: 0.33,
-                g: 0.66,
-                b: 0.53,
-                icc_profile: None,
-            }),
-            Color::Rgb(Rgb {
-                r: 0.89,
-                g: 0.57,
-                b: 0.28,
-                icc_profile: None,
-            }),
-        ];
+        let accent_color = accent_color();
         let text_color = Color::Greyscale(Greyscale::new(0.08, None));
         let border_color = Color::Greyscale(Greyscale::new(0.65, None));
 
-        let title = format!("Отчёт по группе {}", export.group_id);
-        Self::push_pdf_text(
+        let title = format!("{} {}", Label::GroupReportFor.text(locale), group_id);
+        push_pdf_text(
             &mut ops,
             Point::new(Mm(20.0), Mm(275.0)),
-            BuiltinFont::HelveticaBold,
+            &fonts.bold,
             18.0,
             22.0,
             title,
             &accent_color,
         );
-        let period_label = Self::format_period(&export.filters.period);
-        let format_label = match export.format {
-            ExportFormat::Csv => "CSV",
-            ExportFormat::Pdf => "PDF",
-            ExportFormat::Xlsx => "XLSX",
-        };
-        Self::push_pdf_text(
+        let period_label = export_i18n::format_period(locale, &export.filters.period);
+        let format_label = export.format.as_label();
+        push_pdf_text(
             &mut ops,
             Point::new(Mm(20.0), Mm(263.0)),
-            BuiltinFont::Helvetica,
+            &fonts.regular,
             11.0,
             14.0,
-            format!("Период: {period_label}"),
+            format!("{}: {period_label}", Label::Period.text(locale)),
             &text_color,
         );
-        Self::push_pdf_text(
+        push_pdf_text(
             &mut ops,
             Point::new(Mm(20.0), Mm(255.0)),
-            BuiltinFont::Helvetica,
+            &fonts.regular,
             11.0,
             14.0,
             format!(
-                "Формат: {format_label} • Сформирован: {}",
-                Self::format_timestamp(&export.created_at)
+                "{}: {format_label} • {}: {}",
+                Label::Format.text(locale),
+                Label::CreatedAt.text(locale),
+                export_i18n::format_timestamp(locale, &export.created_at)
             ),
             &text_color,
         );
@@ -288,13 +861,13 @@ impl ExportWorker {
         let summary_row_height = 10.0_f32;
         let summary_columns = [75.0_f32, 55.0_f32];
         let summary_row_count = summary_rows.len().max(1) + 1;
-        Self::push_pdf_text(
+        push_pdf_text(
             &mut ops,
             Point::new(Mm(summary_left), Mm(summary_top + 8.0)),
-            BuiltinFont::HelveticaBold,
+            &fonts.bold,
             12.0,
             15.0,
-            "Сводные метрики".into(),
+            Label::SummaryMetrics.text(locale).into(),
             &accent_color,
         );
         ops.push(Op::SetOutlineColor {
@@ -310,50 +883,50 @@ impl ExportWorker {
             summary_row_count,
         );
         let mut summary_y = summary_top - 7.0;
-        Self::push_pdf_text(
+        push_pdf_text(
             &mut ops,
             Point::new(Mm(summary_left + 3.0), Mm(summary_y)),
-            BuiltinFont::HelveticaBold,
+            &fonts.bold,
             10.0,
             12.0,
-            "Метрика".into(),
+            Label::Metric.text(locale).into(),
             &text_color,
         );
-        Self::push_pdf_text(
+        push_pdf_text(
             &mut ops,
             Point::new(Mm(summary_left + summary_columns[0] + 3.0), Mm(summary_y)),
-            BuiltinFont::HelveticaBold,
+            &fonts.bold,
             10.0,
             12.0,
-            "Значение".into(),
+            Label::Value.text(locale).into(),
             &text_color,
         );
         summary_y -= summary_row_height;
         if summary_rows.is_empty() {
-            Self::push_pdf_text(
+            push_pdf_text(
                 &mut ops,
                 Point::new(Mm(summary_left + 3.0), Mm(summary_y)),
-                BuiltinFont::Helvetica,
+                &fonts.regular,
                 10.0,
                 12.0,
-                "Нет данных".into(),
+                Label::NoData.text(locale).into(),
                 &text_color,
             );
         } else {
             for (metric, value) in summary_rows {
-                Self::push_pdf_text(
+                push_pdf_text(
                     &mut ops,
                     Point::new(Mm(summary_left + 3.0), Mm(summary_y)),
-                    BuiltinFont::Helvetica,
+                    &fonts.regular,
                     10.0,
                     12.0,
                     metric,
                     &text_color,
                 );
-                Self::push_pdf_text(
+                push_pdf_text(
                     &mut ops,
                     Point::new(Mm(summary_left + summary_columns[0] + 3.0), Mm(summary_y)),
-                    BuiltinFont::Helvetica,
+                    &fonts.regular,
                     10.0,
                     12.0,
                     value,
@@ -363,101 +936,44 @@ impl ExportWorker {
             }
         }
 
-        // Leaderboard chart (right column).
+        // Score distribution chart (right column).
         let chart_left = 125.0_f32;
         let chart_bottom = 110.0_f32;
         let chart_height = 115.0_f32;
         let chart_width = 70.0_f32;
-        Self::push_pdf_text(
+        push_pdf_text(
             &mut ops,
             Point::new(Mm(chart_left), Mm(chart_bottom + chart_height + 12.0)),
-            BuiltinFont::HelveticaBold,
+            &fonts.bold,
             12.0,
             15.0,
-            "Распределение баллов".into(),
+            Label::ScoreDistribution.text(locale).into(),
             &accent_color,
         );
-        {
-            let chart_entries = leaderboard_rows.iter().take(5).cloned().collect::<Vec<_>>();
-            if chart_entries.is_empty() {
-                Self::push_pdf_text(
-                    &mut ops,
-                    Point::new(Mm(chart_left), Mm(chart_bottom + chart_height / 2.0)),
-                    BuiltinFont::Helvetica,
-                    10.0,
-                    12.0,
-                    "Нет данных для графика".into(),
-                    &text_color,
-                );
-            } else {
-                let mut max_score = leaderboard_rows
-                    .iter()
-                    .map(|(_, _, score)| *score)
-                    .max()
-                    .unwrap_or(1);
-                if max_score <= 0 {
-                    max_score = 1;
-                }
-
-                ops.push(Op::SetOutlineColor {
-                    col: accent_color.clone(),
-                });
-                ops.push(Op::SetOutlineThickness { pt: Pt(0.8) });
-                Self::push_pdf_line(
-                    &mut ops,
-                    (chart_left, chart_bottom),
-                    (chart_left, chart_bottom + chart_height),
-                );
-                Self::push_pdf_line(
-                    &mut ops,
-                    (chart_left, chart_bottom),
-                    (chart_left + chart_width, chart_bottom),
-                );
-
-                let bar_count = chart_entries.len();
-                let spacing = 4.0_f32;
-                let total_spacing = spacing * (bar_count as f32 + 1.0);
-                let mut bar_width = ((chart_width - total_spacing) / bar_count as f32).max(9.0_f32);
-                if bar_width.is_nan() {
-                    bar_width = 9.0;
-                }
-                let mut current_x = chart_left + spacing;
-                for (idx, (rank, name, score)) in chart_entries.into_iter().enumerate() {
-                    let score_value = score;
-                    let ratio = (score_value as f32 / max_score as f32).clamp(0.0, 1.0);
-                    let bar_height = ratio * chart_height;
-                    let color = bar_palette[idx % bar_palette.len()].clone();
-                    Self::push_pdf_rect(
-                        &mut ops,
-                        current_x,
-                        chart_bottom,
-                        bar_width,
-                        bar_height,
-                        &color,
-                    );
-                    let score_label_y = chart_bottom + bar_height + 3.0;
-                    Self::push_pdf_text(
-                        &mut ops,
-                        Point::new(Mm(current_x), Mm(score_label_y)),
-                        BuiltinFont::Helvetica,
-                        9.0,
-                        11.0,
-                        format!("{score_value}"),
-                        &text_color,
-                    );
-                    Self::push_pdf_text(
-                        &mut ops,
-                        Point::new(Mm(current_x), Mm(chart_bottom - 6.0)),
-                        BuiltinFont::Helvetica,
-                        8.0,
-                        10.0,
-                        format!("#{rank} {}", Self::shorten_label(&name, 11)),
-                        &text_color,
-                    );
-                    current_x += bar_width + spacing;
-                }
-            }
-        }
+        // Под узкими столбцами помещается только нижняя граница диапазона
+        let chart_entries = if distribution.iter().all(|count| *count == 0) {
+            Vec::new()
+        } else {
+            distribution
+                .iter()
+                .enumerate()
+                .map(|(bucket, count)| ((bucket * 10).to_string(), *count as i64))
+                .collect::<Vec<_>>()
+        };
+        Self::draw_bar_chart(
+            &mut ops,
+            &fonts,
+            ChartArea {
+                left: chart_left,
+                bottom: chart_bottom,
+                width: chart_width,
+                height: chart_height,
+            },
+            &chart_entries,
+            Label::NoChartData.text(locale),
+            &accent_color,
+            &text_color,
+        );
 
         // Leaderboard table (full width at the bottom).
         let leaderboard_top = 105.0_f32;
@@ -471,13 +987,13 @@ impl ExportWorker {
             .cloned()
             .collect();
         let leaderboard_row_count = leaderboard_visible.len().max(1) + 1;
-        Self::push_pdf_text(
+        push_pdf_text(
             &mut ops,
             Point::new(Mm(leaderboard_left), Mm(leaderboard_top + 8.0)),
-            BuiltinFont::HelveticaBold,
+            &fonts.bold,
             12.0,
             15.0,
-            "Таблица лидеров".into(),
+            Label::Leaderboard.text(locale).into(),
             &accent_color,
         );
         ops.push(Op::SetOutlineColor {
@@ -493,74 +1009,74 @@ impl ExportWorker {
             leaderboard_row_count,
         );
         let mut leaderboard_y = leaderboard_top - 6.5;
-        Self::push_pdf_text(
+        push_pdf_text(
             &mut ops,
             Point::new(Mm(leaderboard_left + 2.0), Mm(leaderboard_y)),
-            BuiltinFont::HelveticaBold,
+            &fonts.bold,
             9.5,
             10.0,
-            "Место".into(),
+            Label::Rank.text(locale).into(),
             &text_color,
         );
-        Self::push_pdf_text(
+        push_pdf_text(
             &mut ops,
             Point::new(
                 Mm(leaderboard_left + leaderboard_columns[0] + 2.0),
                 Mm(leaderboard_y),
             ),
-            BuiltinFont::HelveticaBold,
+            &fonts.bold,
             9.5,
             10.0,
-            "Ученик".into(),
+            Label::Student.text(locale).into(),
             &text_color,
         );
-        Self::push_pdf_text(
+        push_pdf_text(
             &mut ops,
             Point::new(
                 Mm(leaderboard_left + leaderboard_columns[0] + leaderboard_columns[1] + 2.0),
                 Mm(leaderboard_y),
             ),
-            BuiltinFont::HelveticaBold,
+            &fonts.bold,
             9.5,
             10.0,
-            "Баллы".into(),
+            Label::Score.text(locale).into(),
             &text_color,
         );
         leaderboard_y -= leaderboard_row_height;
         if leaderboard_visible.is_empty() {
-            Self::push_pdf_text(
+            push_pdf_text(
                 &mut ops,
                 Point::new(Mm(leaderboard_left + 2.0), Mm(leaderboard_y)),
-                BuiltinFont::Helvetica,
+                &fonts.regular,
                 9.5,
                 11.0,
-                "Нет данных".into(),
+                Label::NoData.text(locale).into(),
                 &text_color,
             );
         } else {
             for (rank, name, score) in leaderboard_visible {
-                Self::push_pdf_text(
+                push_pdf_text(
                     &mut ops,
                     Point::new(Mm(leaderboard_left + 2.0), Mm(leaderboard_y)),
-                    BuiltinFont::Helvetica,
+                    &fonts.regular,
                     9.5,
                     11.0,
                     format!("{rank}"),
                     &text_color,
                 );
-                Self::push_pdf_text(
+                push_pdf_text(
                     &mut ops,
                     Point::new(
                         Mm(leaderboard_left + leaderboard_columns[0] + 2.0),
                         Mm(leaderboard_y),
                     ),
-                    BuiltinFont::Helvetica,
+                    &fonts.regular,
                     9.5,
                     11.0,
                     name,
                     &text_color,
                 );
-                Self::push_pdf_text(
+                push_pdf_text(
                     &mut ops,
                     Point::new(
                         Mm(leaderboard_left
@@ -569,7 +1085,7 @@ impl ExportWorker {
                             + 2.0),
                         Mm(leaderboard_y),
                     ),
-                    BuiltinFont::Helvetica,
+                    &fonts.regular,
                     9.5,
                     11.0,
                     format!("{score}"),
@@ -585,13 +1101,13 @@ impl ExportWorker {
             let note_y =
                 leaderboard_top - leaderboard_row_height * leaderboard_row_count as f32 - 4.0;
             if note_y > 5.0 {
-                Self::push_pdf_text(
+                push_pdf_text(
                     &mut ops,
                     Point::new(Mm(leaderboard_left), Mm(note_y)),
-                    BuiltinFont::HelveticaOblique,
+                    &fonts.oblique,
                     8.0,
                     9.0,
-                    format!("Показаны первые {leaderboard_limit} записей"),
+                    Label::FirstEntries.with_count(locale, leaderboard_limit),
                     &text_color,
                 );
             }
@@ -602,72 +1118,598 @@ impl ExportWorker {
         let bytes = document
             .with_pages(vec![page])
             .save(&PdfSaveOptions::default(), &mut warnings);
-        Ok(bytes)
+        Ok(RenderedPdf::new(bytes, warnings))
     }
 
     fn build_xlsx(
-        &self,
         export: &ReportExport,
+        group_id: &ObjectId,
         stats: Option<&MaterializedStat>,
         leaderboard: Option<&LeaderboardDocument>,
+        distribution: &[u64; SCORE_BUCKETS],
+        students: &GroupStudentProgress,
     ) -> Result<Vec<u8>> {
-        let mut workbook = Workbook::new();
-        let worksheet = workbook.add_worksheet();
-        worksheet.set_column_width(0, 28.0)?;
-        worksheet.set_column_width(1, 24.0)?;
-
-        let header_format = Format::new().set_bold();
+        let mut workbook =
+            Self::group_workbook(export, group_id, stats, leaderboard, distribution, students)?;
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        workbook.save_to_writer(&mut cursor)?;
+        Ok(cursor.into_inner())
+    }
+
+    /// Книга выгрузки группы: сводка, лидеры с диаграммой баллов и по листу
+    /// на ученика с точностью по темам
+    fn group_workbook(
+        export: &ReportExport,
+        group_id: &ObjectId,
+        stats: Option<&MaterializedStat>,
+        leaderboard: Option<&LeaderboardDocument>,
+        distribution: &[u64; SCORE_BUCKETS],
+        students: &GroupStudentProgress,
+    ) -> Result<Workbook> {
+        let locale = export.filters.locale;
+        let text = |label: Label| label.text(locale);
+        let header_format = Format::new().set_bold();
+        let percent_format = Format::new().set_num_format("0.0%");
+        let decimal_format = Format::new().set_num_format("0.0");
+        let mut workbook = Workbook::new();
+
+        let summary = workbook.add_worksheet();
+        summary.set_name(text(Label::SummarySheet))?;
+        summary.set_column_width(0, 32.0)?;
+        summary.set_column_width(1, 28.0)?;
+        Self::write_xlsx_header(
+            summary,
+            &[text(Label::Metric), text(Label::Value)],
+            &header_format,
+        )?;
+        let mut row = 1;
+        let details = [
+            (Label::ReportId, export.id.to_hex()),
+            (Label::Group, group_id.to_hex()),
+            (Label::Format, export.format.as_label().to_string()),
+            (
+                Label::Period,
+                export_i18n::format_period(locale, &export.filters.period),
+            ),
+        ];
+        for (label, value) in details {
+            summary.write_string(row, 0, text(label))?;
+            summary.write_string(row, 1, value)?;
+            row += 1;
+        }
+        summary.write_string(row, 0, text(Label::GroupStudents))?;
+        summary.write_number(row, 1, students.total_students as f64)?;
+        row += 1;
+        if students.total_students > students.students.len() {
+            summary.write_string(
+                row,
+                0,
+                Label::FirstStudentSheets.with_count(locale, students.students.len()),
+            )?;
+            row += 1;
+        }
+        let metrics = Self::summary_values(stats);
+        if metrics.is_empty() {
+            summary.write_string(row, 0, text(Label::Unavailable))?;
+            summary.write_string(row, 1, text(Label::NoData))?;
+        }
+        for (label, value) in metrics {
+            summary.write_string(row, 0, text(label))?;
+            match value {
+                MetricValue::Percent(value) => {
+                    summary.write_number_with_format(row, 1, value / 100.0, &percent_format)?
+                }
+                MetricValue::Decimal(value) => {
+                    summary.write_number_with_format(row, 1, value, &decimal_format)?
+                }
+                MetricValue::Count(value) => summary.write_number(row, 1, value as f64)?,
+            };
+            row += 1;
+        }
+
+        let leaderboard_name = text(Label::Leaderboard);
+        let leaders = workbook.add_worksheet();
+        leaders.set_name(leaderboard_name)?;
+        leaders.set_column_width(0, 8.0)?;
+        leaders.set_column_width(1, 32.0)?;
+        leaders.set_column_width(2, 12.0)?;
+        Self::write_xlsx_header(
+            leaders,
+            &[text(Label::Rank), text(Label::Student), text(Label::Score)],
+            &header_format,
+        )?;
+        let leaderboard_rows = Self::leaderboard_rows(leaderboard);
+        if leaderboard_rows.is_empty() {
+            leaders.write_string(1, 0, "—")?;
+            leaders.write_string(1, 1, text(Label::NoData))?;
+        }
+        for (offset, (rank, name, score)) in leaderboard_rows.iter().enumerate() {
+            let row = offset as u32 + 1;
+            leaders.write_number(row, 0, f64::from(*rank))?;
+            leaders.write_string(row, 1, name)?;
+            leaders.write_number(row, 2, *score as f64)?;
+        }
+
+        // Распределение учеников по диапазонам точности - рядом с таблицей лидеров
+        leaders.set_column_width(4, 14.0)?;
+        leaders.set_column_width(5, 12.0)?;
+        leaders.write_string_with_format(0, 4, text(Label::AccuracyPercent), &header_format)?;
+        leaders.write_string_with_format(0, 5, text(Label::TotalUsers), &header_format)?;
+        for (bucket, count) in distribution.iter().enumerate() {
+            let row = bucket as u32 + 1;
+            let from = bucket * 10;
+            leaders.write_string(row, 4, format!("{from}-{}", from + 10))?;
+            leaders.write_number(row, 5, *count as f64)?;
+        }
+        if distribution.iter().any(|count| *count > 0) {
+            let last_row = SCORE_BUCKETS as u32;
+            let mut chart = Chart::new(ChartType::Column);
+            chart.title().set_name(text(Label::ScoreDistribution));
+            chart.legend().set_hidden();
+            chart
+                .add_series()
+                .set_name(text(Label::TotalUsers))
+                .set_categories((leaderboard_name, 1, 4, last_row, 4))
+                .set_values((leaderboard_name, 1, 5, last_row, 5));
+            leaders.insert_chart(1, 7, &chart)?;
+        }
+
+        for (index, student) in students.students.iter().enumerate() {
+            let label = if student.name.trim().is_empty() {
+                &student.user_id
+            } else {
+                &student.name
+            };
+            let sheet = workbook.add_worksheet();
+            sheet.set_name(Self::student_sheet_name(index, label))?;
+            sheet.set_column_width(0, 40.0)?;
+            sheet.set_column_width(1, 14.0)?;
+            sheet.set_column_width(2, 14.0)?;
+            sheet.set_column_width(3, 14.0)?;
+            Self::write_xlsx_header(
+                sheet,
+                &[
+                    text(Label::Topic),
+                    text(Label::Accuracy),
+                    text(Label::Attempts),
+                    text(Label::Score),
+                ],
+                &header_format,
+            )?;
+            if student.topics.is_empty() {
+                sheet.write_string(1, 0, text(Label::NoData))?;
+            }
+            for (offset, topic) in student.topics.iter().enumerate() {
+                let row = offset as u32 + 1;
+                let name = topic
+                    .topic_name
+                    .clone()
+                    .unwrap_or_else(|| topic.topic_id.to_hex());
+                sheet.write_string(row, 0, name)?;
+                sheet.write_number_with_format(
+                    row,
+                    1,
+                    topic.avg_percentage.unwrap_or(0.0) / 100.0,
+                    &percent_format,
+                )?;
+                sheet.write_number(row, 2, topic.total_attempts.unwrap_or(0) as f64)?;
+                sheet.write_number(row, 3, topic.total_score.unwrap_or(0) as f64)?;
+            }
+        }
+
+        Ok(workbook)
+    }
+
+    /// Строка заголовков жирным, закреплённая при прокрутке
+    fn write_xlsx_header(
+        worksheet: &mut Worksheet,
+        headers: &[&str],
+        format: &Format,
+    ) -> Result<()> {
+        for (col, header) in headers.iter().enumerate() {
+            worksheet.write_string_with_format(0, col as u16, *header, format)?;
+        }
+        worksheet.set_freeze_panes(1, 0)?;
+        Ok(())
+    }
+
+    /// Имя листа ученика: порядковый номер делает имена уникальными, символы
+    /// `[]:*?/\` Excel не допускает, длина ограничена 31 символом
+    fn student_sheet_name(index: usize, name: &str) -> String {
+        let cleaned: String = name
+            .chars()
+            .map(|ch| if "[]:*?/\\".contains(ch) { ' ' } else { ch })
+            .collect();
+        let full = format!("{:03} {}", index + 1, cleaned.trim());
+        full.chars()
+            .take(31)
+            .collect::<String>()
+            .trim_end_matches(|ch: char| ch.is_whitespace() || ch == '\'')
+            .to_string()
+    }
+
+    fn build_user_csv(export: &ReportExport, report: &UserReport) -> Vec<u8> {
+        let locale = export.filters.locale;
+        let text = |label: Label| label.text(locale);
+        let mut lines = vec![
+            Self::csv_row(&[text(Label::Metric), text(Label::Value)]),
+            Self::csv_row(&[text(Label::UserId), &report.user_id.to_hex()]),
+            Self::csv_row(&[text(Label::Format), export.format.as_label()]),
+            Self::csv_row(&[
+                text(Label::CreatedAt),
+                &export_i18n::format_timestamp(locale, &export.created_at),
+            ]),
+        ];
+        for (label, value) in report.summary_rows(locale) {
+            lines.push(Self::csv_row(&[&label, &value]));
+        }
+
+        lines.push("".into());
+        lines.push(text(Label::Topics).into());
+        lines.push(Self::csv_row(&[
+            text(Label::Topic),
+            text(Label::Accuracy),
+            text(Label::Attempts),
+            text(Label::Score),
+        ]));
+        for (topic, accuracy, attempts, score) in report.topic_rows() {
+            lines.push(Self::csv_row(&[
+                &topic,
+                &export_i18n::format_decimal(locale, accuracy, 1),
+                &attempts.to_string(),
+                &score.to_string(),
+            ]));
+        }
+
+        lines.push("".into());
+        lines.push(text(Label::Progress).into());
+        lines.push(Self::csv_row(&[
+            text(Label::UpdatedAt),
+            text(Label::Level),
+            text(Label::Accuracy),
+            text(Label::Attempts),
+            text(Label::Score),
+        ]));
+        for entry in &report.progress {
+            lines.push(Self::csv_row(&[
+                &export_i18n::format_timestamp(locale, &entry.updated_at),
+                &entry.level_id,
+                &export_i18n::format_decimal(locale, entry.percentage, 1),
+                &entry.attempts_total.to_string(),
+                &entry.score.to_string(),
+            ]));
+        }
+
+        lines.join("\n").into_bytes()
+    }
+
+    fn build_user_pdf(export: &ReportExport, report: &UserReport) -> Result<RenderedPdf> {
+        let locale = export.filters.locale;
+        let mut document = PdfDocument::new(Label::StudentReport.text(locale));
+        let fonts = PdfFonts::load(&mut document, locale)?;
+        let mut ops = Vec::new();
+
+        let accent_color = accent_color();
+        let text_color = Color::Greyscale(Greyscale::new(0.08, None));
+        let border_color = Color::Greyscale(Greyscale::new(0.65, None));
+
+        push_pdf_text(
+            &mut ops,
+            Point::new(Mm(20.0), Mm(275.0)),
+            &fonts.bold,
+            18.0,
+            22.0,
+            format!(
+                "{} {}",
+                Label::StudentReportFor.text(locale),
+                report.user_id
+            ),
+            &accent_color,
+        );
+        push_pdf_text(
+            &mut ops,
+            Point::new(Mm(20.0), Mm(263.0)),
+            &fonts.regular,
+            11.0,
+            14.0,
+            format!(
+                "{}: {}",
+                Label::Period.text(locale),
+                export_i18n::format_period(locale, &export.filters.period)
+            ),
+            &text_color,
+        );
+        push_pdf_text(
+            &mut ops,
+            Point::new(Mm(20.0), Mm(255.0)),
+            &fonts.regular,
+            11.0,
+            14.0,
+            format!(
+                "{}: {} • {}: {}",
+                Label::Format.text(locale),
+                export.format.as_label(),
+                Label::CreatedAt.text(locale),
+                export_i18n::format_timestamp(locale, &export.created_at)
+            ),
+            &text_color,
+        );
+
+        // Сводка с подсказками (верх страницы).
+        let summary_rows = report.summary_rows(locale);
+        let summary_left = 20.0_f32;
+        let summary_top = 245.0_f32;
+        let summary_row_height = 9.0_f32;
+        let summary_columns = [75.0_f32, 55.0_f32];
+        push_pdf_text(
+            &mut ops,
+            Point::new(Mm(summary_left), Mm(summary_top + 8.0)),
+            &fonts.bold,
+            12.0,
+            15.0,
+            Label::SummaryMetrics.text(locale).into(),
+            &accent_color,
+        );
+        ops.push(Op::SetOutlineColor {
+            col: border_color.clone(),
+        });
+        ops.push(Op::SetOutlineThickness { pt: Pt(0.6) });
+        Self::draw_table_grid(
+            &mut ops,
+            summary_left,
+            summary_top,
+            summary_row_height,
+            &summary_columns,
+            summary_rows.len() + 1,
+        );
+        let mut summary_y = summary_top - 6.5;
+        for (idx, (metric, value)) in [(
+            Label::Metric.text(locale).to_string(),
+            Label::Value.text(locale).to_string(),
+        )]
+        .into_iter()
+        .chain(summary_rows)
+        .enumerate()
+        {
+            let font = if idx == 0 {
+                &fonts.bold
+            } else {
+                &fonts.regular
+            };
+            push_pdf_text(
+                &mut ops,
+                Point::new(Mm(summary_left + 3.0), Mm(summary_y)),
+                font,
+                10.0,
+                12.0,
+                metric,
+                &text_color,
+            );
+            push_pdf_text(
+                &mut ops,
+                Point::new(Mm(summary_left + summary_columns[0] + 3.0), Mm(summary_y)),
+                font,
+                10.0,
+                12.0,
+                value,
+                &text_color,
+            );
+            summary_y -= summary_row_height;
+        }
+
+        // Баллы по времени (на всю ширину).
+        let chart = ChartArea {
+            left: 20.0,
+            bottom: 118.0,
+            width: 170.0,
+            height: 38.0,
+        };
+        push_pdf_text(
+            &mut ops,
+            Point::new(Mm(chart.left), Mm(chart.bottom + chart.height + 12.0)),
+            &fonts.bold,
+            12.0,
+            15.0,
+            Label::ScoreOverTime.text(locale).into(),
+            &accent_color,
+        );
+        Self::draw_bar_chart(
+            &mut ops,
+            &fonts,
+            chart,
+            &report.score_timeline(locale),
+            Label::NoChartData.text(locale),
+            &accent_color,
+            &text_color,
+        );
+
+        // Точность по темам (низ страницы).
+        let topics_top = 100.0_f32;
+        let topics_left = 20.0_f32;
+        let topics_row_height = 9.0_f32;
+        let topics_columns = [80.0_f32, 30.0_f32, 30.0_f32, 30.0_f32];
+        let topics_limit = 9;
+        let topic_rows = report.topic_rows();
+        let topics_visible = topic_rows.iter().take(topics_limit).collect::<Vec<_>>();
+        let topics_row_count = topics_visible.len().max(1) + 1;
+        push_pdf_text(
+            &mut ops,
+            Point::new(Mm(topics_left), Mm(topics_top + 8.0)),
+            &fonts.bold,
+            12.0,
+            15.0,
+            Label::TopicAccuracy.text(locale).into(),
+            &accent_color,
+        );
+        ops.push(Op::SetOutlineColor {
+            col: border_color.clone(),
+        });
+        ops.push(Op::SetOutlineThickness { pt: Pt(0.5) });
+        Self::draw_table_grid(
+            &mut ops,
+            topics_left,
+            topics_top,
+            topics_row_height,
+            &topics_columns,
+            topics_row_count,
+        );
+        let mut topics_y = topics_top - 6.5;
+        let mut table_rows = vec![(
+            &fonts.bold,
+            [
+                Label::Topic.text(locale).to_string(),
+                Label::Accuracy.text(locale).to_string(),
+                Label::Attempts.text(locale).to_string(),
+                Label::Score.text(locale).to_string(),
+            ],
+        )];
+        if topics_visible.is_empty() {
+            table_rows.push((
+                &fonts.regular,
+                [
+                    Label::NoData.text(locale).to_string(),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                ],
+            ));
+        }
+        for (topic, accuracy, attempts, score) in topics_visible {
+            table_rows.push((
+                &fonts.regular,
+                [
+                    Self::shorten_label(topic, 40),
+                    export_i18n::format_percent(locale, *accuracy, 1),
+                    attempts.to_string(),
+                    score.to_string(),
+                ],
+            ));
+        }
+        for (font, cells) in table_rows {
+            let mut cell_left = topics_left;
+            for (cell, width) in cells.into_iter().zip(topics_columns) {
+                push_pdf_text(
+                    &mut ops,
+                    Point::new(Mm(cell_left + 2.0), Mm(topics_y)),
+                    font,
+                    9.5,
+                    11.0,
+                    cell,
+                    &text_color,
+                );
+                cell_left += width;
+            }
+            topics_y -= topics_row_height;
+        }
+        if topic_rows.len() > topics_limit {
+            push_pdf_text(
+                &mut ops,
+                Point::new(
+                    Mm(topics_left),
+                    Mm(topics_top - topics_row_height * topics_row_count as f32 - 4.0),
+                ),
+                &fonts.oblique,
+                8.0,
+                9.0,
+                Label::FirstTopics.with_count(locale, topics_limit),
+                &text_color,
+            );
+        }
+
+        let page = PdfPage::new(Mm(210.0), Mm(297.0), ops);
+        let mut warnings = Vec::new();
+        let bytes = document
+            .with_pages(vec![page])
+            .save(&PdfSaveOptions::default(), &mut warnings);
+        Ok(RenderedPdf::new(bytes, warnings))
+    }
+
+    fn build_user_xlsx(export: &ReportExport, report: &UserReport) -> Result<Vec<u8>> {
+        let locale = export.filters.locale;
+        let mut workbook = Workbook::new();
+        let worksheet = workbook.add_worksheet();
+        worksheet.set_column_width(0, 28.0)?;
+        worksheet.set_column_width(1, 24.0)?;
+
+        let header_format = Format::new().set_bold();
 
         let mut row = 0;
-        worksheet.write_string(row, 0, "Отчёт ID")?;
+        worksheet.write_string(row, 0, Label::ReportId.text(locale))?;
         worksheet.write_string(row, 1, export.id.to_hex())?;
         row += 1;
-        worksheet.write_string(row, 0, "Группа")?;
-        worksheet.write_string(row, 1, export.group_id.to_hex())?;
+        worksheet.write_string(row, 0, Label::Student.text(locale))?;
+        worksheet.write_string(row, 1, report.user_id.to_hex())?;
         row += 1;
-        worksheet.write_string(row, 0, "Формат")?;
+        worksheet.write_string(row, 0, Label::Format.text(locale))?;
         worksheet.write_string(row, 1, export.format.as_label())?;
         row += 1;
-        worksheet.write_string(row, 0, "Период")?;
-        worksheet.write_string(row, 1, Self::format_period(&export.filters.period))?;
+        worksheet.write_string(row, 0, Label::Period.text(locale))?;
+        worksheet.write_string(
+            row,
+            1,
+            export_i18n::format_period(locale, &export.filters.period),
+        )?;
         row += 2;
 
-        worksheet.write_string_with_format(row, 0, "Метрика", &header_format)?;
-        worksheet.write_string_with_format(row, 1, "Значение", &header_format)?;
+        worksheet.write_string_with_format(row, 0, Label::Metric.text(locale), &header_format)?;
+        worksheet.write_string_with_format(row, 1, Label::Value.text(locale), &header_format)?;
         row += 1;
-
-        let metrics = Self::summary_metrics(stats);
-        if metrics.is_empty() {
-            worksheet.write_string(row, 0, "Недоступно")?;
-            worksheet.write_string(row, 1, "Нет данных")?;
-            row += 2;
-        } else {
-            for (label, value) in metrics {
-                worksheet.write_string(row, 0, &label)?;
-                worksheet.write_string(row, 1, &value)?;
-                row += 1;
-            }
+        for (label, value) in report.summary_rows(locale) {
+            worksheet.write_string(row, 0, &label)?;
+            worksheet.write_string(row, 1, &value)?;
             row += 1;
         }
+        row += 1;
 
-        worksheet.write_string_with_format(row, 0, "Лидерборд", &header_format)?;
+        worksheet.write_string_with_format(row, 0, Label::Topic.text(locale), &header_format)?;
+        worksheet.write_string_with_format(
+            row,
+            1,
+            Label::AccuracyPercent.text(locale),
+            &header_format,
+        )?;
+        worksheet.write_string_with_format(row, 2, Label::Attempts.text(locale), &header_format)?;
+        worksheet.write_string_with_format(row, 3, Label::Score.text(locale), &header_format)?;
         row += 1;
-        worksheet.write_string_with_format(row, 0, "Место", &header_format)?;
-        worksheet.write_string_with_format(row, 1, "Ученик", &header_format)?;
-        worksheet.write_string_with_format(row, 2, "Баллы", &header_format)?;
+        let topic_rows = report.topic_rows();
+        if topic_rows.is_empty() {
+            worksheet.write_string(row, 0, Label::NoData.text(locale))?;
+            row += 1;
+        }
+        for (topic, accuracy, attempts, score) in topic_rows {
+            worksheet.write_string(row, 0, &topic)?;
+            worksheet.write_number(row, 1, accuracy)?;
+            worksheet.write_number(row, 2, attempts as f64)?;
+            worksheet.write_number(row, 3, score as f64)?;
+            row += 1;
+        }
         row += 1;
 
-        let leaderboard_rows = Self::leaderboard_rows(leaderboard);
-        if leaderboard_rows.is_empty() {
-            worksheet.write_string(row, 0, "—")?;
-            worksheet.write_string(row, 1, "Нет данных")?;
-        } else {
-            for (rank, name, score) in leaderboard_rows {
-                worksheet.write_number(row, 0, rank as f64)?;
-                worksheet.write_string(row, 1, &name)?;
-                worksheet.write_number(row, 2, score as f64)?;
-                row += 1;
-            }
+        worksheet.write_string_with_format(
+            row,
+            0,
+            Label::UpdatedAt.text(locale),
+            &header_format,
+        )?;
+        worksheet.write_string_with_format(row, 1, Label::Level.text(locale), &header_format)?;
+        worksheet.write_string_with_format(
+            row,
+            2,
+            Label::AccuracyPercent.text(locale),
+            &header_format,
+        )?;
+        worksheet.write_string_with_format(row, 3, Label::Score.text(locale), &header_format)?;
+        row += 1;
+        for entry in &report.progress {
+            worksheet.write_string(
+                row,
+                0,
+                export_i18n::format_timestamp(locale, &entry.updated_at),
+            )?;
+            worksheet.write_string(row, 1, &entry.level_id)?;
+            worksheet.write_number(row, 2, entry.percentage)?;
+            worksheet.write_number(row, 3, f64::from(entry.score))?;
+            row += 1;
         }
 
         let mut cursor = std::io::Cursor::new(Vec::new());
@@ -675,27 +1717,44 @@ impl ExportWorker {
         Ok(cursor.into_inner())
     }
 
-    fn summary_metrics(stats: Option<&MaterializedStat>) -> Vec<(String, String)> {
+    fn summary_values(stats: Option<&MaterializedStat>) -> Vec<(Label, MetricValue)> {
         let mut rows = Vec::new();
         let Some(stats) = stats else {
             return rows;
         };
         let metrics = &stats.metrics;
         if let Some(value) = metrics.get("avg_accuracy").and_then(Self::bson_to_f64) {
-            rows.push(("Средняя точность".into(), format!("{value:.1}%")));
+            rows.push((Label::AvgAccuracy, MetricValue::Percent(value)));
         }
         if let Some(value) = metrics.get("avg_score").and_then(Self::bson_to_f64) {
-            rows.push(("Средний балл".into(), format!("{value:.1}")));
+            rows.push((Label::AvgScore, MetricValue::Decimal(value)));
         }
         if let Some(value) = metrics.get("total_attempts").and_then(Self::bson_to_i64) {
-            rows.push(("Всего попыток".into(), value.to_string()));
+            rows.push((Label::TotalAttempts, MetricValue::Count(value)));
         }
         if let Some(value) = metrics.get("total_users").and_then(Self::bson_to_i64) {
-            rows.push(("Ученики".into(), value.to_string()));
+            rows.push((Label::TotalUsers, MetricValue::Count(value)));
         }
         rows
     }
 
+    fn summary_metrics(
+        locale: ExportLocale,
+        stats: Option<&MaterializedStat>,
+    ) -> Vec<(String, String)> {
+        Self::summary_values(stats)
+            .into_iter()
+            .map(|(label, value)| {
+                let value = match value {
+                    MetricValue::Percent(value) => export_i18n::format_percent(locale, value, 1),
+                    MetricValue::Decimal(value) => export_i18n::format_decimal(locale, value, 1),
+                    MetricValue::Count(value) => value.to_string(),
+                };
+                (label.text(locale).to_string(), value)
+            })
+            .collect()
+    }
+
     fn leaderboard_rows(leaderboard: Option<&LeaderboardDocument>) -> Vec<(u32, String, i64)> {
         leaderboard
             .map(|lb| {
@@ -724,102 +1783,113 @@ impl ExportWorker {
         }
     }
 
-    fn format_timestamp(value: &DateTime<Utc>) -> String {
-        value.format("%Y-%m-%d %H:%M:%S UTC").to_string()
+    fn csv_row(cells: &[&str]) -> String {
+        cells
+            .iter()
+            .map(|cell| escape_csv_field(cell))
+            .collect::<Vec<_>>()
+            .join(",")
     }
 
-    fn format_period(range: &TimeRange) -> String {
-        format!(
-            "{} — {}",
-            range.from.format("%Y-%m-%d %H:%M"),
-            range.to.format("%Y-%m-%d %H:%M")
-        )
+    fn bar_palette() -> [Color; 3] {
+        [
+            Color::Rgb(Rgb {
+                r: 0.23,
+                g: 0.52,
+                b: 0.87,
+                icc_profile: None,
+            }),
+            Color::Rgb(Rgb {
+                r: 0.33,
+                g: 0.66,
+                b: 0.53,
+                icc_profile: None,
+            }),
+            Color::Rgb(Rgb {
+                r: 0.89,
+                g: 0.57,
+                b: 0.28,
+                icc_profile: None,
+            }),
+        ]
     }
 
-    fn push_pdf_text(
+    /// Столбчатая диаграмма: оси, столбцы с подписью значения сверху и подписью
+    /// категории под осью. Высота столбцов нормируется по максимуму.
+    fn draw_bar_chart(
         ops: &mut Vec<Op>,
-        pos: Point,
-        font: BuiltinFont,
-        font_size: f32,
-        line_height: f32,
-        text: String,
-        color: &Color,
+        fonts: &PdfFonts,
+        area: ChartArea,
+        entries: &[(String, i64)],
+        empty_label: &str,
+        axis_color: &Color,
+        text_color: &Color,
     ) {
-        ops.extend([
-            Op::StartTextSection,
-            Op::SetTextCursor { pos },
-            Op::SetFontSizeBuiltinFont {
-                size: Pt(font_size),
-                font,
-            },
-            Op::SetLineHeight {
-                lh: Pt(line_height),
-            },
-            Op::SetFillColor { col: color.clone() },
-            Op::WriteTextBuiltinFont {
-                items: vec![TextItem::Text(text)],
-                font,
-            },
-            Op::EndTextSection,
-        ]);
-    }
-
-    fn push_pdf_line(ops: &mut Vec<Op>, from: (f32, f32), to: (f32, f32)) {
-        ops.push(Op::DrawLine {
-            line: Line {
-                points: vec![
-                    LinePoint {
-                        p: Point::new(Mm(from.0), Mm(from.1)),
-                        bezier: false,
-                    },
-                    LinePoint {
-                        p: Point::new(Mm(to.0), Mm(to.1)),
-                        bezier: false,
-                    },
-                ],
-                is_closed: false,
-            },
+        if entries.is_empty() {
+            push_pdf_text(
+                ops,
+                Point::new(Mm(area.left), Mm(area.bottom + area.height / 2.0)),
+                &fonts.regular,
+                10.0,
+                12.0,
+                empty_label.into(),
+                text_color,
+            );
+            return;
+        }
+
+        let max_value = entries
+            .iter()
+            .map(|(_, value)| *value)
+            .max()
+            .unwrap_or(1)
+            .max(1);
+
+        ops.push(Op::SetOutlineColor {
+            col: axis_color.clone(),
         });
-    }
+        ops.push(Op::SetOutlineThickness { pt: Pt(0.8) });
+        push_pdf_line(
+            ops,
+            (area.left, area.bottom),
+            (area.left, area.bottom + area.height),
+        );
+        push_pdf_line(
+            ops,
+            (area.left, area.bottom),
+            (area.left + area.width, area.bottom),
+        );
 
-    fn push_pdf_rect(
-        ops: &mut Vec<Op>,
-        left: f32,
-        bottom: f32,
-        width: f32,
-        height: f32,
-        color: &Color,
-    ) {
-        if width <= 0.0 || height <= 0.0 {
-            return;
+        let bar_count = entries.len() as f32;
+        let spacing = (area.width / (bar_count * 4.0)).min(4.0_f32);
+        let bar_width = (area.width - spacing * (bar_count + 1.0)) / bar_count;
+        let palette = Self::bar_palette();
+        let mut current_x = area.left + spacing;
+        for (idx, (label, value)) in entries.iter().enumerate() {
+            let ratio = (*value as f32 / max_value as f32).clamp(0.0, 1.0);
+            let bar_height = ratio * area.height;
+            let color = &palette[idx % palette.len()];
+            push_pdf_rect(ops, current_x, area.bottom, bar_width, bar_height, color);
+            push_pdf_text(
+                ops,
+                Point::new(Mm(current_x), Mm(area.bottom + bar_height + 3.0)),
+                &fonts.regular,
+                9.0,
+                11.0,
+                format!("{value}"),
+                text_color,
+            );
+            push_pdf_text(
+                ops,
+                Point::new(Mm(current_x), Mm(area.bottom - 6.0)),
+                &fonts.regular,
+                8.0,
+                10.0,
+                label.clone(),
+                text_color,
+            );
+            current_x += bar_width + spacing;
         }
-        let ring = PolygonRing {
-            points: vec![
-                LinePoint {
-                    p: Point::new(Mm(left), Mm(bottom)),
-                    bezier: false,
-                },
-                LinePoint {
-                    p: Point::new(Mm(left + width), Mm(bottom)),
-                    bezier: false,
-                },
-                LinePoint {
-                    p: Point::new(Mm(left + width), Mm(bottom + height)),
-                    bezier: false,
-                },
-                LinePoint {
-                    p: Point::new(Mm(left), Mm(bottom + height)),
-                    bezier: false,
-                },
-            ],
-        };
-        let polygon = Polygon {
-            rings: vec![ring],
-            mode: PaintMode::Fill,
-            winding_order: WindingOrder::NonZero,
-        };
-        ops.push(Op::SetFillColor { col: color.clone() });
-        ops.push(Op::DrawPolygon { polygon });
     }
 
     fn draw_table_grid(
@@ -837,14 +1907,14 @@ impl ExportWorker {
         let table_height = row_height * row_count as f32;
         for idx in 0..=row_count {
             let y = top - row_height * idx as f32;
-            Self::push_pdf_line(ops, (left, y), (left + total_width, y));
+            push_pdf_line(ops, (left, y), (left + total_width, y));
         }
         let mut x = left;
         for width in columns {
-            Self::push_pdf_line(ops, (x, top), (x, top - table_height));
+            push_pdf_line(ops, (x, top), (x, top - table_height));
             x += *width;
         }
-        Self::push_pdf_line(ops, (x, top), (x, top - table_height));
+        push_pdf_line(ops, (x, top), (x, top - table_height));
     }
 
     fn shorten_label(label: &str, max_chars: usize) -> String {
@@ -866,24 +1936,65 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_csv_escape_formula_injection() {
-        // Test formula injection prevention
-        assert_eq!(escape_csv_field("=1+1"), "\t=1+1");
-        assert_eq!(escape_csv_field("+cmd"), "\t+cmd");
-        assert_eq!(escape_csv_field("@SUM(A1)"), "\t@SUM(A1)");
-        assert_eq!(escape_csv_field("-2+3"), "\t-2+3");
+    fn test_retry_delay_doubles_up_to_cap() {
+        assert_eq!(retry_delay(1, 30), Duration::from_secs(30));
+        assert_eq!(retry_delay(2, 30), Duration::from_secs(60));
+        assert_eq!(retry_delay(3, 30), Duration::from_secs(120));
+        assert_eq!(retry_delay(5, 30), Duration::from_secs(480));
+        assert_eq!(retry_delay(8, 30), MAX_RETRY_DELAY);
+        assert_eq!(retry_delay(u32::MAX, u64::MAX), MAX_RETRY_DELAY);
+        // Нулевая база - повтор на следующем тике
+        assert_eq!(retry_delay(3, 0), Duration::ZERO);
+        assert_eq!(retry_delay(0, 30), Duration::from_secs(30));
+    }
 
-        // Test normal names
-        assert_eq!(escape_csv_field("Normal Name"), "Normal Name");
-        assert_eq!(escape_csv_field("John Doe"), "John Doe");
-        assert_eq!(escape_csv_field("Иван Иванов"), "Иван Иванов");
+    fn attempt(user_id: &str) -> AttemptRecord {
+        AttemptRecord {
+            id: "a1".into(),
+            session_id: "s1".into(),
+            user_id: user_id.into(),
+            task_id: "t1".into(),
+            answer: "ответ".into(),
+            correct: true,
+            score: 10,
+            timestamp: Utc::now(),
+            reason: None,
+            credit: None,
+            timing: None,
+        }
+    }
+
+    #[test]
+    fn test_pseudonyms_are_stable_per_group() {
+        let group = ObjectId::new();
+        let first = pseudonymize_user_id("secret", &group, "u1");
+        assert_eq!(first, pseudonymize_user_id("secret", &group, "u1"));
+        assert!(first.starts_with("anon-"));
+        assert!(!first.contains("u1"));
+        assert_ne!(first, pseudonymize_user_id("secret", &group, "u2"));
+        assert_ne!(
+            first,
+            pseudonymize_user_id("secret", &ObjectId::new(), "u1")
+        );
+        assert_ne!(first, pseudonymize_user_id("other", &group, "u1"));
+    }
 
-        // Test special characters that need quoting
-        assert_eq!(escape_csv_field("Name, Jr."), "\"Name, Jr.\"");
-        assert_eq!(escape_csv_field("O\"Brien"), "\"O\"\"Brien\"");
+    #[test]
+    fn test_answer_line_respects_field_whitelist() {
+        let line = answer_line(&attempt("u1"), "u1".into(), Some("Маша"), &[]).unwrap();
+        assert_eq!(line.last(), Some(&b'\n'));
+        let row: serde_json::Value = serde_json::from_slice(&line).unwrap();
+        assert_eq!(row["user_name"], "Маша");
+        assert_eq!(row["score"], 10);
+        assert!(row["reason"].is_null());
 
-        // Test combination: formula + special chars
-        assert_eq!(escape_csv_field("=1+1, test"), "\"\t=1+1, test\"");
+        let fields = vec!["user_id".to_string(), "correct".to_string()];
+        let line = answer_line(&attempt("u1"), "anon-1".into(), None, &fields).unwrap();
+        let row: serde_json::Value = serde_json::from_slice(&line).unwrap();
+        assert_eq!(
+            row,
+            serde_json::json!({ "user_id": "anon-1", "correct": true })
+        );
     }
 
     #[test]
@@ -902,4 +2013,253 @@ mod tests {
         // Tab character (starts with tab, so gets prefixed with another tab)
         assert_eq!(escape_csv_field("\t"), "\t\t");
     }
+
+    fn localized_export(format: ExportFormat, locale: ExportLocale) -> ReportExport {
+        use crate::models::reporting::{NewReportExport, ReportFilters, TimeRange};
+        use chrono::TimeZone;
+
+        let created_at = Utc.with_ymd_and_hms(2026, 3, 5, 9, 30, 0).unwrap();
+        let mut export = NewReportExport {
+            scope: ExportScope::Group,
+            subject_id: ObjectId::new(),
+            teacher_id: ObjectId::new(),
+            format,
+            filters: ReportFilters {
+                topic_ids: vec![],
+                period: TimeRange {
+                    from: created_at - ChronoDuration::days(7),
+                    to: created_at,
+                },
+                fields: vec![],
+                reveal_user_ids: false,
+                locale,
+            },
+            expires_at: created_at + ChronoDuration::days(7),
+        }
+        .into_record();
+        export.created_at = created_at;
+        export
+    }
+
+    fn group_snapshot() -> (MaterializedStat, LeaderboardDocument) {
+        use crate::models::reporting::{LeaderboardEntry, StatType};
+        use mongodb::bson::doc;
+
+        let group_id = ObjectId::new();
+        let stats = MaterializedStat {
+            id: ObjectId::new(),
+            stat_type: StatType::Group,
+            entity_id: group_id,
+            metrics: doc! {
+                "avg_accuracy": 85.26,
+                "avg_score": 12.5,
+                "total_attempts": 40_i64,
+                "total_users": 3_i32,
+            },
+            calculated_at: Utc::now(),
+        };
+        let leaderboard = LeaderboardDocument {
+            id: ObjectId::new(),
+            scope: LeaderboardScope::Group,
+            scope_id: Some(group_id),
+            rankings: vec![LeaderboardEntry {
+                user_id: ObjectId::new(),
+                score: 120,
+                rank: 1,
+                name: "Иванова, Маша".into(),
+            }],
+            generated_at: Utc::now(),
+        };
+        (stats, leaderboard)
+    }
+
+    #[test]
+    fn test_group_csv_uses_locale_labels_and_numbers() {
+        let (stats, leaderboard) = group_snapshot();
+        let group_id = stats.entity_id;
+        let render = |locale| {
+            let export = localized_export(ExportFormat::Csv, locale);
+            let bytes =
+                ExportWorker::build_csv(&export, &group_id, Some(&stats), Some(&leaderboard));
+            String::from_utf8(bytes).unwrap()
+        };
+
+        assert_eq!(
+            render(ExportLocale::Ru),
+            format!(
+                "Метрика,Значение\n\
+                 ID группы,{group_id}\n\
+                 Формат,CSV\n\
+                 Сформирован,05.03.2026 09:30:00 UTC\n\
+                 Средняя точность,\"85,3%\"\n\
+                 Средний балл,\"12,5\"\n\
+                 Всего попыток,40\n\
+                 Ученики,3\n\
+                 \n\
+                 Таблица лидеров\n\
+                 Место,Ученик,Баллы\n\
+                 1,\"Иванова, Маша\",120"
+            )
+        );
+        assert_eq!(
+            render(ExportLocale::En),
+            format!(
+                "Metric,Value\n\
+                 Group ID,{group_id}\n\
+                 Format,CSV\n\
+                 Created at,05 Mar 2026 09:30:00 UTC\n\
+                 Average accuracy,85.3%\n\
+                 Average score,12.5\n\
+                 Total attempts,40\n\
+                 Students,3\n\
+                 \n\
+                 Leaderboard\n\
+                 Rank,Student,Score\n\
+                 1,\"Иванова, Маша\",120"
+            )
+        );
+    }
+
+    #[test]
+    fn test_user_csv_uses_locale_labels() {
+        let report = UserReport {
+            user_id: ObjectId::new(),
+            progress: vec![ProgressSummary {
+                id: "p1".into(),
+                user_id: "u1".into(),
+                level_id: "level-1".into(),
+                attempts_total: 4,
+                correct_count: 3,
+                credit: None,
+                percentage: 75.0,
+                score: 30,
+                updated_at: Utc::now(),
+            }],
+            topics: vec![],
+            hints: HintTotals { hints: 2, cost: 4 },
+        };
+        let ru = String::from_utf8(ExportWorker::build_user_csv(
+            &localized_export(ExportFormat::Csv, ExportLocale::Ru),
+            &report,
+        ))
+        .unwrap();
+        assert!(ru.contains("\nТочность,\"75,0%\"\n"), "{ru}");
+        assert!(ru.contains("\nПодсказок на попытку,\"0,50\"\n"), "{ru}");
+        assert!(ru.contains("\nОбновлено,Уровень,Точность,Попытки,Баллы\n"));
+
+        let en = String::from_utf8(ExportWorker::build_user_csv(
+            &localized_export(ExportFormat::Csv, ExportLocale::En),
+            &report,
+        ))
+        .unwrap();
+        assert!(en.contains("\nAccuracy,75.0%\n"), "{en}");
+        assert!(en.contains("\nHints per attempt,0.50\n"), "{en}");
+        assert!(en.contains("\nUpdated at,Level,Accuracy,Attempts,Score\n"));
+    }
+
+    #[test]
+    fn test_pdf_builds_without_warnings_in_both_locales() {
+        let (stats, leaderboard) = group_snapshot();
+        for locale in [ExportLocale::Ru, ExportLocale::En] {
+            let export = localized_export(ExportFormat::Pdf, locale);
+            let pdf = ExportWorker::build_pdf(
+                &export,
+                &stats.entity_id,
+                Some(&stats),
+                Some(&leaderboard),
+                &score_distribution(&[35.0, 72.5, 98.0]),
+            )
+            .unwrap();
+            assert!(pdf.warnings.is_empty(), "{locale:?}: {:?}", pdf.warnings);
+            assert!(pdf.bytes.starts_with(b"%PDF"));
+        }
+    }
+
+    #[test]
+    fn test_group_workbook_has_summary_leaderboard_and_student_sheets() {
+        use crate::services::reporting_service::StudentProgress;
+
+        let (stats, leaderboard) = group_snapshot();
+        let students = GroupStudentProgress {
+            students: vec![
+                StudentProgress {
+                    user_id: ObjectId::new().to_hex(),
+                    name: "Иванова Маша".into(),
+                    topics: vec![TopicAnalyticsRow {
+                        topic_id: ObjectId::new(),
+                        topic_name: Some("Орфография".into()),
+                        avg_percentage: Some(72.5),
+                        total_attempts: Some(8),
+                        total_score: Some(40),
+                    }],
+                },
+                StudentProgress {
+                    user_id: "65f0c0ffee".into(),
+                    name: " ".into(),
+                    topics: vec![],
+                },
+            ],
+            total_students: 600,
+        };
+        let export = localized_export(ExportFormat::Xlsx, ExportLocale::En);
+        let distribution = score_distribution(&[72.5, 40.0]);
+
+        let mut workbook = ExportWorker::group_workbook(
+            &export,
+            &stats.entity_id,
+            Some(&stats),
+            Some(&leaderboard),
+            &distribution,
+            &students,
+        )
+        .unwrap();
+        let names: Vec<String> = workbook
+            .worksheets()
+            .iter()
+            .map(|sheet| sheet.name())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "Summary",
+                "Leaderboard",
+                "001 Иванова Маша",
+                "002 65f0c0ffee"
+            ]
+        );
+
+        let bytes = ExportWorker::build_xlsx(
+            &export,
+            &stats.entity_id,
+            Some(&stats),
+            Some(&leaderboard),
+            &distribution,
+            &students,
+        )
+        .unwrap();
+        // Имена файлов в zip не сжимаются: диаграмма попала в книгу
+        let has_chart = bytes
+            .windows(b"xl/charts/chart1.xml".len())
+            .any(|window| window == b"xl/charts/chart1.xml");
+        assert!(has_chart);
+    }
+
+    #[test]
+    fn test_score_distribution_buckets_by_tens() {
+        let buckets = score_distribution(&[0.0, 9.9, 10.0, 55.0, 99.9, 100.0, -5.0, 120.0]);
+        assert_eq!(buckets, [3, 1, 0, 0, 0, 1, 0, 0, 0, 3]);
+        assert_eq!(score_distribution(&[]), [0; SCORE_BUCKETS]);
+    }
+
+    #[test]
+    fn test_student_sheet_names_are_valid_for_excel() {
+        assert_eq!(
+            ExportWorker::student_sheet_name(0, "Петров [тест]: a/b"),
+            "001 Петров  тест   a b"
+        );
+        let long = ExportWorker::student_sheet_name(499, &"Очень длинное имя ".repeat(4));
+        assert_eq!(long.chars().count(), 31);
+        assert!(long.starts_with("500 "));
+        assert_eq!(ExportWorker::student_sheet_name(1, "O'"), "002 O");
+    }
 }
@@ -1,18 +1,30 @@
+use crate::middlewares::auth::JwtClaims;
 use crate::models::feature_flag::{FeatureFlag, FeatureFlagCreateRequest};
 use chrono::Utc;
-use mongodb::{bson::doc, Database};
+use mongodb::{
+    bson::{doc, oid::ObjectId, Document},
+    Database,
+};
 use redis::{aio::ConnectionManager, AsyncCommands};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 use tracing::{debug, error, info, warn};
 
 const FEATURE_FLAGS_COLLECTION: &str = "feature_flags";
 const CACHE_TTL_SECONDS: usize = 60;
 const CACHE_KEY_PREFIX: &str = "ff:";
 
+/// Подсказки в сессиях; без флага в базе включены
+pub const HINTS_ENABLED_FLAG: &str = "hints_enabled";
+
 /// Parameters for updating a feature flag
 pub struct UpdateFlagRequest {
     pub enabled: Option<bool>,
     pub scope: Option<String>,
     pub target_ids: Option<Vec<String>>,
+    pub enabled_for_roles: Option<Vec<String>>,
+    pub enabled_for_user_ids: Option<Vec<String>>,
+    pub rollout_percentage: Option<Option<i32>>,
     pub description: Option<String>,
     pub change_reason: String,
 }
@@ -37,62 +49,114 @@ impl FeatureFlagService {
         Self { db, redis_conn }
     }
 
-    /// Check if flag is enabled for given context
-    /// Hierarchy: user-level > group-level > global
-    pub async fn is_enabled(
-        &mut self,
-        flag_key: &str,
-        user_id: Option<&str>,
-        group_id: Option<&str>,
-    ) -> bool {
-        // Try to get from cache first
-        if let Some(cached) = self.get_from_cache(flag_key, user_id, group_id).await {
-            debug!(
-                "Cache hit for flag: {}, user: {:?}, group: {:?}",
-                flag_key, user_id, group_id
-            );
-            return cached;
+    /// Check if flag is enabled for the authenticated user
+    /// Targeting: user IDs > roles > percentage rollout; flags without targeting use scope.
+    /// `default` is returned when the flag does not exist or MongoDB is unavailable
+    pub async fn is_enabled(&self, flag_key: &str, claims: &JwtClaims, default: bool) -> bool {
+        // The definition is cached, not the per-user result: role and groups differ per token
+        if let Some(flag) = self.get_from_cache(flag_key).await {
+            debug!("Cache hit for flag: {}", flag_key);
+            return Self::resolve(&flag, claims);
         }
 
-        // Get from MongoDB
-        match self.get_flag_from_db(flag_key).await {
+        // Box<dyn Error> is not Send and must not be held across the cache write
+        let loaded = self
+            .get_flag_from_db(flag_key)
+            .await
+            .map_err(|e| e.to_string());
+        match loaded {
             Ok(Some(flag)) => {
-                let enabled = self.check_flag_enabled(&flag, user_id, group_id);
-                // Try to cache the result
-                let _ = self.set_cache(flag_key, user_id, group_id, enabled).await;
-                enabled
+                let _ = self.set_cache(&flag).await;
+                Self::resolve(&flag, claims)
             }
             Ok(None) => {
-                warn!("Flag not found: {}", flag_key);
-                false
+                debug!("Flag not found: {}, using default {}", flag_key, default);
+                default
             }
             Err(e) => {
                 error!(
                     "Failed to get flag from DB: {}, using graceful degradation",
                     e
                 );
-                // Graceful degradation: return false on database error
-                false
+                default
             }
         }
     }
 
-    /// Get all enabled flags for a user
-    pub async fn get_all_enabled_flags(
-        &mut self,
-        user_id: Option<&str>,
-        group_id: Option<&str>,
-    ) -> Vec<String> {
-        match self.get_all_flags_from_db().await {
-            Ok(flags) => flags
-                .into_iter()
-                .filter(|flag| self.check_flag_enabled(flag, user_id, group_id))
-                .map(|flag| flag.flag_key)
-                .collect(),
-            Err(e) => {
-                error!("Failed to get all flags: {}", e);
-                Vec::new()
+    /// Check a flag for a user when the request carries no token (e.g. session hints):
+    /// role and groups are read from `users`
+    pub async fn is_enabled_for_user(&self, flag_key: &str, user_id: &str, default: bool) -> bool {
+        let user = match ObjectId::parse_str(user_id) {
+            Ok(object_id) => self
+                .db
+                .collection::<Document>("users")
+                .find_one(doc! { "_id": object_id })
+                .await
+                .unwrap_or_else(|e| {
+                    warn!("Failed to load user {} for flag check: {}", user_id, e);
+                    None
+                }),
+            Err(_) => None,
+        };
+        let claims = JwtClaims {
+            sub: user_id.to_string(),
+            role: user
+                .as_ref()
+                .and_then(|user| user.get_str("role").ok())
+                .unwrap_or_default()
+                .to_string(),
+            group_ids: user
+                .as_ref()
+                .and_then(|user| user.get_array("group_ids").ok())
+                .map(|ids| {
+                    ids.iter()
+                        .filter_map(|id| id.as_str().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            exp: 0,
+            iat: 0,
+        };
+        self.is_enabled(flag_key, &claims, default).await
+    }
+
+    /// Resolve every flag for the authenticated user into a flag_key -> enabled map
+    pub async fn resolve_all(
+        &self,
+        claims: &JwtClaims,
+    ) -> Result<BTreeMap<String, bool>, Box<dyn std::error::Error>> {
+        let flags = self.get_all_flags_from_db().await?;
+        Ok(flags
+            .iter()
+            .map(|flag| (flag.flag_key.clone(), Self::resolve(flag, claims)))
+            .collect())
+    }
+
+    /// Resolve a single flag for the user from JWT claims
+    pub fn resolve(flag: &FeatureFlag, claims: &JwtClaims) -> bool {
+        if !flag.enabled {
+            return false;
+        }
+
+        if !flag.has_targeting() {
+            if claims.group_ids.is_empty() {
+                return Self::check_flag_enabled_static(flag, Some(&claims.sub), None);
             }
+            return claims.group_ids.iter().any(|group_id| {
+                Self::check_flag_enabled_static(flag, Some(&claims.sub), Some(group_id))
+            });
+        }
+
+        if flag.enabled_for_user_ids.contains(&claims.sub) {
+            return true;
+        }
+        if flag.enabled_for_roles.contains(&claims.role) {
+            return true;
+        }
+
+        match flag.rollout_percentage {
+            Some(percentage) => i32::from(rollout_bucket(&flag.flag_key, &claims.sub)) < percentage,
+            None => false,
         }
     }
 
@@ -123,6 +187,9 @@ impl FeatureFlagService {
             enabled: req.enabled,
             scope: req.scope,
             target_ids: req.target_ids,
+            enabled_for_roles: req.enabled_for_roles,
+            enabled_for_user_ids: req.enabled_for_user_ids,
+            rollout_percentage: req.rollout_percentage,
             config: req.config,
             version: 1,
             updated_at: now,
@@ -161,9 +228,8 @@ impl FeatureFlagService {
 
         // Build update document
         let now = Utc::now();
-        let now_rfc3339 = now.to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
         let mut update_doc = doc! {
-            "updated_at": now_rfc3339,
+            "updated_at": mongodb::bson::DateTime::from_millis(now.timestamp_millis()),
             "updated_by": admin_user_id,
             "change_reason": req.change_reason.clone(),
             "version": current_flag.version + 1,
@@ -178,6 +244,15 @@ impl FeatureFlagService {
         if let Some(t) = req.target_ids {
             update_doc.insert("target_ids", t);
         }
+        if let Some(roles) = req.enabled_for_roles {
+            update_doc.insert("enabled_for_roles", roles);
+        }
+        if let Some(user_ids) = req.enabled_for_user_ids {
+            update_doc.insert("enabled_for_user_ids", user_ids);
+        }
+        if let Some(percentage) = req.rollout_percentage {
+            update_doc.insert("rollout_percentage", percentage);
+        }
         if let Some(d) = req.description {
             update_doc.insert("description", d);
         }
@@ -215,17 +290,7 @@ impl FeatureFlagService {
 
     // ============ Private Helper Methods ============
 
-    /// Check if flag is enabled for specific context
-    fn check_flag_enabled(
-        &self,
-        flag: &FeatureFlag,
-        user_id: Option<&str>,
-        group_id: Option<&str>,
-    ) -> bool {
-        Self::check_flag_enabled_static(flag, user_id, group_id)
-    }
-
-    /// Static version for testing
+    /// Check legacy scope (global/group/user) for specific context
     fn check_flag_enabled_static(
         flag: &FeatureFlag,
         user_id: Option<&str>,
@@ -269,100 +334,92 @@ impl FeatureFlagService {
         Ok(flags)
     }
 
-    /// Get flag value from Redis cache
-    async fn get_from_cache(
-        &mut self,
-        flag_key: &str,
-        user_id: Option<&str>,
-        group_id: Option<&str>,
-    ) -> Option<bool> {
-        self.redis_conn.as_ref()?;
-
-        let cache_key = self.build_cache_key(flag_key, user_id, group_id);
-
-        if let Some(conn) = &self.redis_conn {
-            match conn.clone().get::<&str, String>(&cache_key).await {
-                Ok(value) => {
-                    if let Ok(enabled) = value.parse::<bool>() {
-                        return Some(enabled);
-                    }
-                }
-                Err(e) => {
-                    debug!("Cache read failed: {}", e);
-                }
+    /// Get flag definition from Redis cache
+    async fn get_from_cache(&self, flag_key: &str) -> Option<FeatureFlag> {
+        let conn = self.redis_conn.as_ref()?;
+        match conn
+            .clone()
+            .get::<_, Option<Vec<u8>>>(Self::build_cache_key(flag_key))
+            .await
+        {
+            Ok(bytes) => bytes.and_then(|bytes| mongodb::bson::from_slice(&bytes).ok()),
+            Err(e) => {
+                debug!("Cache read failed: {}", e);
+                None
             }
         }
-
-        None
     }
 
-    /// Set flag value in Redis cache
-    async fn set_cache(
-        &mut self,
-        flag_key: &str,
-        user_id: Option<&str>,
-        group_id: Option<&str>,
-        enabled: bool,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        if self.redis_conn.is_none() {
+    /// Store flag definition in Redis cache
+    async fn set_cache(&self, flag: &FeatureFlag) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(conn) = &self.redis_conn else {
             return Ok(());
-        }
-
-        let cache_key = self.build_cache_key(flag_key, user_id, group_id);
-
-        if let Some(conn) = &self.redis_conn {
-            let _: () = conn
-                .clone()
-                .set_ex(&cache_key, enabled.to_string(), CACHE_TTL_SECONDS as u64)
-                .await?;
-        }
-
+        };
+        let bytes = mongodb::bson::to_vec(flag)?;
+        let _: () = conn
+            .clone()
+            .set_ex(
+                Self::build_cache_key(&flag.flag_key),
+                bytes,
+                CACHE_TTL_SECONDS as u64,
+            )
+            .await?;
         Ok(())
     }
 
     /// Invalidate all cache entries for a flag
     async fn invalidate_flag_cache(&self, flag_key: &str) {
-        if self.redis_conn.is_none() {
-            return;
-        }
-
-        // Invalidate global cache
-        let global_key = format!("{}{}:global", CACHE_KEY_PREFIX, flag_key);
         if let Some(conn) = &self.redis_conn {
-            let _: Result<(), _> = conn.clone().del(&global_key).await;
+            invalidate_flag_cache(conn, flag_key).await;
         }
-
-        // In real implementation, would need to invalidate all user/group specific entries
-        // This is a limitation of Redis key-value store, would benefit from Redis Streams
-        debug!("Invalidated cache for flag: {}", flag_key);
     }
 
-    /// Build cache key
-    fn build_cache_key(
-        &self,
-        flag_key: &str,
-        user_id: Option<&str>,
-        group_id: Option<&str>,
-    ) -> String {
-        Self::build_cache_key_static(flag_key, user_id, group_id)
+    /// Cache key of a flag definition
+    fn build_cache_key(flag_key: &str) -> String {
+        format!("{}{}:definition", CACHE_KEY_PREFIX, flag_key)
     }
+}
 
-    /// Static version for testing
-    fn build_cache_key_static(
-        flag_key: &str,
-        user_id: Option<&str>,
-        group_id: Option<&str>,
-    ) -> String {
-        let scope = if let Some(uid) = user_id {
-            format!("user:{}", uid)
-        } else if let Some(gid) = group_id {
-            format!("group:{}", gid)
-        } else {
-            "global".to_string()
+/// Drop every cached entry of a flag (`ff:{flag_key}:*`). SCAN instead of KEYS
+/// so a large keyspace does not block Redis
+pub async fn invalidate_flag_cache(redis: &ConnectionManager, flag_key: &str) {
+    let mut conn = redis.clone();
+    let pattern = format!("{}{}:*", CACHE_KEY_PREFIX, flag_key);
+    let mut cursor: u64 = 0;
+    loop {
+        let scanned: Result<(u64, Vec<String>), _> = redis::cmd("SCAN")
+            .arg(cursor)
+            .arg("MATCH")
+            .arg(&pattern)
+            .arg("COUNT")
+            .arg(100)
+            .query_async(&mut conn)
+            .await;
+        let (next, keys) = match scanned {
+            Ok(page) => page,
+            Err(e) => {
+                warn!("Failed to invalidate cache for flag {}: {}", flag_key, e);
+                return;
+            }
         };
-
-        format!("{}{flag_key}:{scope}", CACHE_KEY_PREFIX)
+        if !keys.is_empty() {
+            let _: Result<(), _> = conn.del(&keys).await;
+        }
+        if next == 0 {
+            break;
+        }
+        cursor = next;
     }
+    debug!("Invalidated cache for flag: {}", flag_key);
+}
+
+/// Stable rollout bucket (0-99) for a user; salted with the flag key so
+/// different flags roll out to different subsets of users
+pub fn rollout_bucket(flag_key: &str, user_id: &str) -> u8 {
+    let digest = Sha256::digest(format!("{}:{}", flag_key, user_id).as_bytes());
+    let mut prefix = [0u8; 8];
+    prefix.copy_from_slice(&digest[..8]);
+    (u64::from_be_bytes(prefix) % 100) as u8
 }
 
 #[cfg(test)]
@@ -370,6 +427,43 @@ mod tests {
     use super::*;
     use mongodb::bson::Document;
 
+    fn targeted_flag(roles: &[&str], user_ids: &[&str], rollout: Option<i32>) -> FeatureFlag {
+        FeatureFlag {
+            id: None,
+            flag_key: "scoring_bonus_v2".to_string(),
+            description: String::new(),
+            enabled: true,
+            scope: "global".to_string(),
+            target_ids: vec![],
+            enabled_for_roles: roles.iter().map(|r| r.to_string()).collect(),
+            enabled_for_user_ids: user_ids.iter().map(|u| u.to_string()).collect(),
+            rollout_percentage: rollout,
+            config: Document::default(),
+            version: 1,
+            updated_at: Utc::now(),
+            updated_by: String::new(),
+            change_reason: String::new(),
+        }
+    }
+
+    fn claims(user_id: &str, role: &str) -> JwtClaims {
+        JwtClaims {
+            sub: user_id.to_string(),
+            role: role.to_string(),
+            group_ids: vec![],
+            exp: 0,
+            iat: 0,
+        }
+    }
+
+    /// Первый пользователь вида user_N, попадающий (или не попадающий) в rollout
+    fn find_user(flag_key: &str, percentage: i32, inside: bool) -> String {
+        (0..1000)
+            .map(|n| format!("user_{}", n))
+            .find(|id| (i32::from(rollout_bucket(flag_key, id)) < percentage) == inside)
+            .expect("no user found for bucket")
+    }
+
     #[test]
     fn test_check_flag_enabled_global() {
         let flag = FeatureFlag {
@@ -379,6 +473,9 @@ mod tests {
             enabled: true,
             scope: "global".to_string(),
             target_ids: vec![],
+            enabled_for_roles: vec![],
+            enabled_for_user_ids: vec![],
+            rollout_percentage: None,
             config: Document::default(),
             version: 1,
             updated_at: Utc::now(),
@@ -410,6 +507,9 @@ mod tests {
             enabled: true,
             scope: "user".to_string(),
             target_ids: vec!["user1".to_string()],
+            enabled_for_roles: vec![],
+            enabled_for_user_ids: vec![],
+            rollout_percentage: None,
             config: Document::default(),
             version: 1,
             updated_at: Utc::now(),
@@ -435,16 +535,105 @@ mod tests {
     #[test]
     fn test_build_cache_key() {
         assert_eq!(
-            FeatureFlagService::build_cache_key_static("hints_enabled", None, None),
-            "ff:hints_enabled:global"
-        );
-        assert_eq!(
-            FeatureFlagService::build_cache_key_static("hints_enabled", Some("user1"), None),
-            "ff:hints_enabled:user:user1"
-        );
-        assert_eq!(
-            FeatureFlagService::build_cache_key_static("hints_enabled", None, Some("group1")),
-            "ff:hints_enabled:group:group1"
+            FeatureFlagService::build_cache_key("hints_enabled"),
+            "ff:hints_enabled:definition"
         );
     }
+
+    #[test]
+    fn test_rollout_bucket_is_stable() {
+        for user_id in ["user1", "507f1f77bcf86cd799439011", ""] {
+            let bucket = rollout_bucket("scoring_bonus_v2", user_id);
+            assert!(bucket < 100);
+            for _ in 0..10 {
+                assert_eq!(rollout_bucket("scoring_bonus_v2", user_id), bucket);
+            }
+        }
+
+        let flag = targeted_flag(&[], &[], Some(30));
+        let user = find_user(&flag.flag_key, 30, true);
+        for _ in 0..10 {
+            assert!(FeatureFlagService::resolve(
+                &flag,
+                &claims(&user, "student")
+            ));
+        }
+    }
+
+    #[test]
+    fn test_rollout_percentage_bounds_and_spread() {
+        let none = targeted_flag(&[], &[], Some(0));
+        let all = targeted_flag(&[], &[], Some(100));
+        let half = targeted_flag(&[], &[], Some(50));
+
+        let mut enabled = 0;
+        for n in 0..1000 {
+            let user = claims(&format!("user_{}", n), "student");
+            assert!(!FeatureFlagService::resolve(&none, &user));
+            assert!(FeatureFlagService::resolve(&all, &user));
+            if FeatureFlagService::resolve(&half, &user) {
+                enabled += 1;
+            }
+        }
+        assert!((400..600).contains(&enabled), "enabled = {}", enabled);
+    }
+
+    #[test]
+    fn test_role_targeting_takes_precedence_over_rollout() {
+        let flag = targeted_flag(&["teacher"], &[], Some(10));
+        let outside = find_user(&flag.flag_key, 10, false);
+
+        assert!(FeatureFlagService::resolve(
+            &flag,
+            &claims(&outside, "teacher")
+        ));
+        assert!(!FeatureFlagService::resolve(
+            &flag,
+            &claims(&outside, "student")
+        ));
+
+        let inside = find_user(&flag.flag_key, 10, true);
+        assert!(FeatureFlagService::resolve(
+            &flag,
+            &claims(&inside, "student")
+        ));
+    }
+
+    #[test]
+    fn test_user_targeting_and_kill_switch() {
+        let mut flag = targeted_flag(&["admin"], &["user_42"], None);
+
+        assert!(FeatureFlagService::resolve(
+            &flag,
+            &claims("user_42", "student")
+        ));
+        assert!(FeatureFlagService::resolve(
+            &flag,
+            &claims("user_7", "admin")
+        ));
+        // Targeting задан, но пользователь ни под одно правило не подходит
+        assert!(!FeatureFlagService::resolve(
+            &flag,
+            &claims("user_7", "student")
+        ));
+
+        flag.enabled = false;
+        assert!(!FeatureFlagService::resolve(
+            &flag,
+            &claims("user_42", "admin")
+        ));
+    }
+
+    #[test]
+    fn test_resolve_without_targeting_uses_scope() {
+        let mut flag = targeted_flag(&[], &[], None);
+        flag.scope = "group".to_string();
+        flag.target_ids = vec!["group_2".to_string()];
+
+        let mut user = claims("user_1", "student");
+        assert!(!FeatureFlagService::resolve(&flag, &user));
+
+        user.group_ids = vec!["group_1".to_string(), "group_2".to_string()];
+        assert!(FeatureFlagService::resolve(&flag, &user));
+    }
 }
@@ -0,0 +1,464 @@
+//! Журнал оценок группы: строка на ученика, по два столбца (лучший балл и дата)
+//! на каждый шаблон домашних заданий и на шаблоны, пройденные вне заданий
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use mongodb::bson::{doc, oid::ObjectId, Bson, DateTime as BsonDateTime, Document};
+use mongodb::Database;
+
+use crate::models::assignment::{Assignment, AssignmentCompletion};
+use crate::models::session_archive::SessionRecord;
+use crate::services::assignment_service::{
+    ASSIGNMENTS_COLLECTION, ASSIGNMENT_COMPLETIONS_COLLECTION,
+};
+use crate::utils::collation::russian_sort_key;
+use crate::utils::csv::escape_csv_field;
+
+/// Столбец журнала: шаблон в составе задания или шаблон вне заданий
+#[derive(Debug, Clone, PartialEq)]
+pub struct GradebookColumn {
+    pub label: String,
+    pub template_id: ObjectId,
+    pub assignment_id: Option<ObjectId>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GradebookCell {
+    pub best_score: i32,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GradebookRow {
+    pub student_id: String,
+    pub name: String,
+    pub cells: Vec<Option<GradebookCell>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Gradebook {
+    pub columns: Vec<GradebookColumn>,
+    pub rows: Vec<GradebookRow>,
+}
+
+/// Завершённая сессия ученика по шаблону
+#[derive(Debug, Clone)]
+pub struct GradedSession {
+    pub user_id: String,
+    pub template_id: ObjectId,
+    pub score: i32,
+    pub completed_at: DateTime<Utc>,
+}
+
+/// Порядок столбцов: задания по сроку сдачи, внутри задания - по slug шаблона,
+/// затем шаблоны вне заданий по slug
+pub fn gradebook_columns(
+    assignments: &[Assignment],
+    slugs: &HashMap<ObjectId, String>,
+    session_templates: &HashSet<ObjectId>,
+) -> Vec<GradebookColumn> {
+    let slug = |id: &ObjectId| slugs.get(id).cloned().unwrap_or_else(|| id.to_hex());
+    let by_slug = |ids: &mut Vec<ObjectId>| ids.sort_by_key(|id| (slug(id), *id));
+
+    let mut assignments: Vec<&Assignment> = assignments.iter().collect();
+    assignments.sort_by_key(|assignment| (assignment.due_at, assignment.id));
+
+    let mut columns = Vec::new();
+    let mut assigned = HashSet::new();
+    for assignment in assignments {
+        let mut templates = assignment.template_ids.clone();
+        by_slug(&mut templates);
+        for template_id in templates {
+            assigned.insert(template_id);
+            columns.push(GradebookColumn {
+                label: format!(
+                    "{} ({})",
+                    slug(&template_id),
+                    assignment.due_at.format("%Y-%m-%d")
+                ),
+                template_id,
+                assignment_id: Some(assignment.id),
+            });
+        }
+    }
+
+    let mut free: Vec<ObjectId> = session_templates
+        .iter()
+        .filter(|id| !assigned.contains(id))
+        .copied()
+        .collect();
+    by_slug(&mut free);
+    columns.extend(free.into_iter().map(|template_id| GradebookColumn {
+        label: slug(&template_id),
+        template_id,
+        assignment_id: None,
+    }));
+    columns
+}
+
+/// Ячейка ученика по столбцу: лучший балл за период и дата выполнения. Для шаблона
+/// задания дата - когда пункт засчитан, для остальных - первая сессия с лучшим баллом
+pub fn gradebook_cell(
+    column: &GradebookColumn,
+    sessions: &[&GradedSession],
+    completion: Option<&AssignmentCompletion>,
+) -> Option<GradebookCell> {
+    let best = sessions
+        .iter()
+        .filter(|session| session.template_id == column.template_id)
+        .min_by_key(|session| (-session.score, session.completed_at))?;
+    let completed_at = match column.assignment_id {
+        Some(_) => completion.map(|completion| completion.completed_at),
+        None => Some(best.completed_at),
+    };
+    Some(GradebookCell {
+        best_score: best.score,
+        completed_at,
+    })
+}
+
+pub fn gradebook_csv_header(columns: &[GradebookColumn]) -> String {
+    let mut cells = vec!["student_id".to_string(), "student_name".to_string()];
+    for column in columns {
+        cells.push(escape_csv_field(&format!("{} best_score", column.label)));
+        cells.push(escape_csv_field(&format!("{} completed_at", column.label)));
+    }
+    format!("{}\n", cells.join(","))
+}
+
+pub fn gradebook_csv_row(row: &GradebookRow) -> String {
+    let mut cells = vec![
+        escape_csv_field(&row.student_id),
+        escape_csv_field(&row.name),
+    ];
+    for cell in &row.cells {
+        let (score, date) = match cell {
+            Some(cell) => (
+                cell.best_score.to_string(),
+                cell.completed_at
+                    .map(|at| at.format("%Y-%m-%d").to_string())
+                    .unwrap_or_default(),
+            ),
+            None => (String::new(), String::new()),
+        };
+        cells.push(score);
+        cells.push(date);
+    }
+    format!("{}\n", cells.join(","))
+}
+
+pub struct GradebookService {
+    mongo: Database,
+}
+
+impl GradebookService {
+    pub fn new(mongo: Database) -> Self {
+        Self { mongo }
+    }
+
+    /// Журнал группы за период (по дате завершения сессии)
+    pub async fn build(
+        &self,
+        group_id: &ObjectId,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Result<Gradebook> {
+        let mut students: Vec<(String, String)> = self
+            .mongo
+            .collection::<Document>("users")
+            .find(doc! { "group_ids": group_id.to_hex(), "role": "student" })
+            .projection(doc! { "_id": 1, "name": 1 })
+            .await
+            .context("Failed to query group students")?
+            .try_collect::<Vec<_>>()
+            .await
+            .context("Failed to collect group students")?
+            .iter()
+            .filter_map(|student| {
+                let id = student.get_object_id("_id").ok()?.to_hex();
+                let name = student.get_str("name").unwrap_or_default().to_string();
+                Some((id, name))
+            })
+            .collect();
+        students.sort_by_cached_key(|(id, name)| (russian_sort_key(name), id.clone()));
+        let student_ids: Vec<String> = students.iter().map(|(id, _)| id.clone()).collect();
+
+        let assignments: Vec<Assignment> = self
+            .mongo
+            .collection::<Assignment>(ASSIGNMENTS_COLLECTION)
+            .find(doc! { "groupId": group_id })
+            .await
+            .context("Failed to query group assignments")?
+            .try_collect()
+            .await
+            .context("Failed to collect group assignments")?;
+        let assignment_ids: Vec<ObjectId> = assignments.iter().map(|a| a.id).collect();
+        let completions: Vec<AssignmentCompletion> = self
+            .mongo
+            .collection::<AssignmentCompletion>(ASSIGNMENT_COMPLETIONS_COLLECTION)
+            .find(doc! {
+                "assignmentId": { "$in": &assignment_ids },
+                "userId": { "$in": &student_ids },
+            })
+            .await
+            .context("Failed to query assignment completions")?
+            .try_collect()
+            .await
+            .context("Failed to collect assignment completions")?;
+
+        let sessions = self.graded_sessions(&student_ids, from, to).await?;
+        let session_templates: HashSet<ObjectId> =
+            sessions.iter().map(|session| session.template_id).collect();
+        let mut template_ids: Vec<ObjectId> = session_templates.iter().copied().collect();
+        template_ids.extend(assignments.iter().flat_map(|a| a.template_ids.clone()));
+        let slugs = self.template_slugs(template_ids).await?;
+        let columns = gradebook_columns(&assignments, &slugs, &session_templates);
+
+        let completions: HashMap<(ObjectId, &str, ObjectId), &AssignmentCompletion> = completions
+            .iter()
+            .map(|c| ((c.assignment_id, c.user_id.as_str(), c.template_id), c))
+            .collect();
+        let rows = students
+            .into_iter()
+            .map(|(student_id, name)| {
+                let own: Vec<&GradedSession> = sessions
+                    .iter()
+                    .filter(|session| session.user_id == student_id)
+                    .collect();
+                let cells = columns
+                    .iter()
+                    .map(|column| {
+                        let completion = column.assignment_id.and_then(|assignment_id| {
+                            completions
+                                .get(&(assignment_id, student_id.as_str(), column.template_id))
+                                .copied()
+                        });
+                        gradebook_cell(column, &own, completion)
+                    })
+                    .collect();
+                GradebookRow {
+                    student_id,
+                    name,
+                    cells,
+                }
+            })
+            .collect();
+
+        Ok(Gradebook { columns, rows })
+    }
+
+    /// Завершённые сессии учеников за период с шаблоном их задания
+    async fn graded_sessions(
+        &self,
+        student_ids: &[String],
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Result<Vec<GradedSession>> {
+        if student_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut completed_at = doc! { "$exists": true };
+        if let Some(from) = from {
+            completed_at.insert("$gte", BsonDateTime::from_millis(from.timestamp_millis()));
+        }
+        if let Some(to) = to {
+            completed_at.insert("$lte", BsonDateTime::from_millis(to.timestamp_millis()));
+        }
+        let sessions: Vec<SessionRecord> = self
+            .mongo
+            .collection::<SessionRecord>("sessions")
+            .find(doc! {
+                "user_id": { "$in": student_ids },
+                "status": "completed",
+                "completed_at": completed_at,
+            })
+            .await
+            .context("Failed to query group sessions")?
+            .try_collect()
+            .await
+            .context("Failed to collect group sessions")?;
+
+        let task_ids: Vec<Bson> = sessions
+            .iter()
+            .map(|session| match ObjectId::parse_str(&session.task_id) {
+                Ok(object_id) => Bson::ObjectId(object_id),
+                Err(_) => Bson::String(session.task_id.clone()),
+            })
+            .collect();
+        let task_templates: HashMap<String, ObjectId> = if task_ids.is_empty() {
+            HashMap::new()
+        } else {
+            self.mongo
+                .collection::<Document>("tasks")
+                .find(doc! { "_id": { "$in": task_ids } })
+                .projection(doc! { "template_id": 1 })
+                .await
+                .context("Failed to query session tasks")?
+                .try_collect::<Vec<_>>()
+                .await
+                .context("Failed to collect session tasks")?
+                .iter()
+                .filter_map(|task| {
+                    let id = match task.get("_id")? {
+                        Bson::ObjectId(id) => id.to_hex(),
+                        Bson::String(id) => id.clone(),
+                        _ => return None,
+                    };
+                    Some((id, task.get_object_id("template_id").ok()?))
+                })
+                .collect()
+        };
+
+        Ok(sessions
+            .into_iter()
+            .filter_map(|session| {
+                Some(GradedSession {
+                    template_id: *task_templates.get(&session.task_id)?,
+                    completed_at: session.completed_at?,
+                    user_id: session.user_id,
+                    score: session.score,
+                })
+            })
+            .collect())
+    }
+
+    async fn template_slugs(&self, ids: Vec<ObjectId>) -> Result<HashMap<ObjectId, String>> {
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let templates: Vec<Document> = self
+            .mongo
+            .collection::<Document>("templates")
+            .find(doc! { "_id": { "$in": ids } })
+            .projection(doc! { "slug": 1 })
+            .await
+            .context("Failed to query gradebook templates")?
+            .try_collect()
+            .await
+            .context("Failed to collect gradebook templates")?;
+        Ok(templates
+            .iter()
+            .filter_map(|template| {
+                Some((
+                    template.get_object_id("_id").ok()?,
+                    template.get_str("slug").ok()?.to_string(),
+                ))
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn assignment(templates: &[ObjectId], due_at: DateTime<Utc>) -> Assignment {
+        Assignment {
+            id: ObjectId::new(),
+            group_id: ObjectId::new(),
+            template_ids: templates.to_vec(),
+            due_at,
+            instructions: String::new(),
+            allow_late: false,
+            min_score: 1,
+            created_by: "teacher".to_string(),
+            created_at: due_at,
+            updated_at: due_at,
+        }
+    }
+
+    #[test]
+    fn columns_follow_due_date_then_slug() {
+        let (a, b, c, d) = (
+            ObjectId::new(),
+            ObjectId::new(),
+            ObjectId::new(),
+            ObjectId::new(),
+        );
+        let slugs: HashMap<ObjectId, String> =
+            [(a, "zeta"), (b, "alpha"), (c, "beta"), (d, "gamma")]
+                .into_iter()
+                .map(|(id, slug)| (id, slug.to_string()))
+                .collect();
+        let due = Utc.with_ymd_and_hms(2026, 3, 10, 12, 0, 0).unwrap();
+        let later = assignment(&[c], due + Duration::days(7));
+        let sooner = assignment(&[a, b], due);
+        let sessions: HashSet<ObjectId> = [a, d].into_iter().collect();
+
+        let labels: Vec<String> = gradebook_columns(&[later, sooner], &slugs, &sessions)
+            .into_iter()
+            .map(|column| column.label)
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                "alpha (2026-03-10)",
+                "zeta (2026-03-10)",
+                "beta (2026-03-17)",
+                "gamma"
+            ]
+        );
+    }
+
+    #[test]
+    fn cell_keeps_best_score_and_first_time_it_was_reached() {
+        let template_id = ObjectId::new();
+        let at = Utc.with_ymd_and_hms(2026, 3, 10, 12, 0, 0).unwrap();
+        let session = |score, days| GradedSession {
+            user_id: "student".to_string(),
+            template_id,
+            score,
+            completed_at: at + Duration::days(days),
+        };
+        let sessions = [
+            session(40, 0),
+            session(90, 2),
+            session(90, 5),
+            session(70, 1),
+        ];
+        let refs: Vec<&GradedSession> = sessions.iter().collect();
+        let column = GradebookColumn {
+            label: "slug".to_string(),
+            template_id,
+            assignment_id: None,
+        };
+
+        let cell = gradebook_cell(&column, &refs, None).unwrap();
+        assert_eq!(cell.best_score, 90);
+        assert_eq!(cell.completed_at, Some(at + Duration::days(2)));
+
+        // В столбце задания дата - только если пункт засчитан
+        let column = GradebookColumn {
+            assignment_id: Some(ObjectId::new()),
+            ..column
+        };
+        assert_eq!(
+            gradebook_cell(&column, &refs, None).unwrap().completed_at,
+            None
+        );
+        assert!(gradebook_cell(&column, &[], None).is_none());
+    }
+
+    #[test]
+    fn csv_escapes_names_and_leaves_missing_cells_empty() {
+        let row = GradebookRow {
+            student_id: "id1".to_string(),
+            name: "=Иванов, Иван".to_string(),
+            cells: vec![
+                Some(GradebookCell {
+                    best_score: 80,
+                    completed_at: Some(Utc.with_ymd_and_hms(2026, 3, 10, 23, 0, 0).unwrap()),
+                }),
+                None,
+            ],
+        };
+        assert_eq!(
+            gradebook_csv_row(&row),
+            "id1,\"\t=Иванов, Иван\",80,2026-03-10,,\n"
+        );
+    }
+}
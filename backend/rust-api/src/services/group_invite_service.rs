@@ -0,0 +1,259 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use futures::TryStreamExt;
+use mongodb::bson::{doc, oid::ObjectId, DateTime as BsonDateTime, Document};
+use mongodb::Database;
+use rand::Rng;
+use thiserror::Error;
+
+use crate::models::group::{
+    CreateInviteCodeRequest, GroupInviteCode, GroupInviteCodeResponse, JoinGroupResponse,
+};
+use crate::models::user::User;
+use crate::services::group_service::GroupService;
+
+/// Без 0/O и 1/I, чтобы код было легко продиктовать
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const CODE_LENGTH: usize = 8;
+
+/// Почему код приглашения нельзя использовать
+#[derive(Debug, Error)]
+pub enum InviteCodeError {
+    #[error("Invite code not found")]
+    NotFound,
+    #[error("Invite code has been revoked")]
+    Revoked,
+    #[error("Invite code has expired")]
+    Expired,
+    #[error("Invite code has no uses left")]
+    Exhausted,
+    #[error("Group is archived")]
+    GroupArchived,
+}
+
+pub struct GroupInviteService {
+    mongo: Database,
+}
+
+impl GroupInviteService {
+    pub fn new(mongo: Database) -> Self {
+        Self { mongo }
+    }
+
+    fn collection(&self) -> mongodb::Collection<GroupInviteCode> {
+        self.mongo
+            .collection::<GroupInviteCode>("group_invite_codes")
+    }
+
+    /// Создать код приглашения для группы
+    pub async fn create_code(
+        &self,
+        group_id: &str,
+        req: CreateInviteCodeRequest,
+        created_by: &str,
+    ) -> Result<GroupInviteCodeResponse> {
+        let group_service = GroupService::new(self.mongo.clone());
+        let group = group_service.get_group(group_id).await?;
+        if group.archived_at.is_some() {
+            return Err(InviteCodeError::GroupArchived.into());
+        }
+
+        let now = Utc::now();
+        let expires_in = req
+            .expires_in_hours
+            .unwrap_or(CreateInviteCodeRequest::DEFAULT_EXPIRES_IN_HOURS);
+        let mut invite = GroupInviteCode {
+            id: None,
+            group_id: ObjectId::parse_str(&group.id).context("Invalid group ID format")?,
+            code: self.unused_code().await?,
+            max_uses: req
+                .max_uses
+                .unwrap_or(CreateInviteCodeRequest::DEFAULT_MAX_USES),
+            uses: 0,
+            used_by: Vec::new(),
+            created_by: created_by.to_string(),
+            expires_at: now + Duration::hours(i64::from(expires_in)),
+            revoked_at: None,
+            created_at: now,
+        };
+
+        let inserted = self
+            .collection()
+            .insert_one(&invite)
+            .await
+            .context("Failed to insert invite code")?;
+        invite.id = inserted.inserted_id.as_object_id();
+
+        Ok(invite.into())
+    }
+
+    /// Действующие коды группы: не отозваны, не истекли и ещё не исчерпаны
+    pub async fn list_active_codes(&self, group_id: &str) -> Result<Vec<GroupInviteCodeResponse>> {
+        let group_oid = ObjectId::parse_str(group_id).context("Invalid group ID format")?;
+
+        let mut filter = usable_filter(Utc::now());
+        filter.insert("groupId", group_oid);
+
+        let codes: Vec<GroupInviteCode> = self
+            .collection()
+            .find(filter)
+            .sort(doc! { "createdAt": -1 })
+            .await
+            .context("Failed to query invite codes")?
+            .try_collect()
+            .await
+            .context("Failed to read invite codes")?;
+
+        Ok(codes.into_iter().map(Into::into).collect())
+    }
+
+    /// Отозвать код; возвращает сам код для аудита
+    pub async fn revoke_code(&self, group_id: &str, code_id: &str) -> Result<String> {
+        let group_oid = ObjectId::parse_str(group_id).context("Invalid group ID format")?;
+        let code_oid = ObjectId::parse_str(code_id).context("Invalid invite code ID format")?;
+
+        let revoked = self
+            .collection()
+            .find_one_and_update(
+                doc! {
+                    "_id": code_oid,
+                    "groupId": group_oid,
+                    "revokedAt": { "$exists": false },
+                },
+                doc! { "$set": { "revokedAt": BsonDateTime::from_millis(Utc::now().timestamp_millis()) } },
+            )
+            .await
+            .context("Failed to revoke invite code")?
+            .ok_or(InviteCodeError::NotFound)?;
+
+        Ok(revoked.code)
+    }
+
+    /// Вступить в группу по коду.
+    ///
+    /// Ученик, который уже состоит в группе, получает `joined = false`, и
+    /// использование кода не расходуется. Использование списывается условным
+    /// `$inc` уже после добавления в группу; если код за это время исчерпали,
+    /// вступление откатывается.
+    pub async fn join(&self, code: &str, user_id: &str) -> Result<JoinGroupResponse> {
+        let code = code.trim().to_uppercase();
+        let invite = self
+            .collection()
+            .find_one(doc! { "code": &code })
+            .await
+            .context("Failed to query invite code")?
+            .ok_or(InviteCodeError::NotFound)?;
+        let invite_id = invite.id.ok_or(InviteCodeError::NotFound)?;
+
+        if invite.revoked_at.is_some() {
+            return Err(InviteCodeError::Revoked.into());
+        }
+
+        let group_service = GroupService::new(self.mongo.clone());
+        let group_id = invite.group_id.to_hex();
+
+        let user_oid = ObjectId::parse_str(user_id).context("Invalid user ID format")?;
+        let user = self
+            .mongo
+            .collection::<User>("users")
+            .find_one(doc! { "_id": user_oid })
+            .await
+            .context("Failed to query user")?
+            .ok_or_else(|| anyhow!("User not found"))?;
+        if user.group_ids.contains(&group_id) {
+            return Ok(JoinGroupResponse {
+                group: group_service.get_group(&group_id).await?,
+                joined: false,
+            });
+        }
+
+        let now = Utc::now();
+        if invite.expires_at <= now {
+            return Err(InviteCodeError::Expired.into());
+        }
+        if invite.uses >= invite.max_uses {
+            return Err(InviteCodeError::Exhausted.into());
+        }
+        if group_service.is_archived(&group_id).await? {
+            return Err(InviteCodeError::GroupArchived.into());
+        }
+
+        if !group_service.add_member(&group_id, user_id).await? {
+            return Ok(JoinGroupResponse {
+                group: group_service.get_group(&group_id).await?,
+                joined: false,
+            });
+        }
+
+        let mut consume_filter = usable_filter(now);
+        consume_filter.insert("_id", invite_id);
+        let consumed = self
+            .collection()
+            .update_one(
+                consume_filter,
+                doc! {
+                    "$inc": { "uses": 1 },
+                    "$addToSet": { "usedBy": user_id },
+                },
+            )
+            .await
+            .context("Failed to consume invite code")?;
+
+        if consumed.matched_count == 0 {
+            group_service.remove_member(&group_id, user_id).await?;
+            return Err(InviteCodeError::Exhausted.into());
+        }
+
+        Ok(JoinGroupResponse {
+            group: group_service.get_group(&group_id).await?,
+            joined: true,
+        })
+    }
+
+    /// Случайный код, которого ещё нет в коллекции
+    async fn unused_code(&self) -> Result<String> {
+        for _ in 0..5 {
+            let code = generate_code();
+            let exists = self
+                .collection()
+                .count_documents(doc! { "code": &code })
+                .await
+                .context("Failed to check invite code uniqueness")?;
+            if exists == 0 {
+                return Ok(code);
+            }
+        }
+        Err(anyhow!("Failed to generate a unique invite code"))
+    }
+}
+
+/// Код ещё можно использовать: не отозван, не истёк и не исчерпан
+fn usable_filter(now: DateTime<Utc>) -> Document {
+    doc! {
+        "revokedAt": { "$exists": false },
+        "expiresAt": { "$gt": BsonDateTime::from_millis(now.timestamp_millis()) },
+        "$expr": { "$lt": ["$uses", "$maxUses"] },
+    }
+}
+
+fn generate_code() -> String {
+    let mut rng = rand::rng();
+    (0..CODE_LENGTH)
+        .map(|_| CODE_ALPHABET[rng.random_range(0..CODE_ALPHABET.len())] as char)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_codes_use_unambiguous_alphabet() {
+        for _ in 0..100 {
+            let code = generate_code();
+            assert_eq!(code.len(), CODE_LENGTH);
+            assert!(code.bytes().all(|byte| CODE_ALPHABET.contains(&byte)));
+            assert!(!code.contains(['0', 'O', '1', 'I']));
+        }
+    }
+}
@@ -6,7 +6,27 @@ use anyhow::{anyhow, Context, Result};
 use chrono::Utc;
 use mongodb::bson::{doc, oid::ObjectId, DateTime as BsonDateTime, Regex};
 use mongodb::Database;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use thiserror::Error;
+
+/// Ошибки изменения состава группы, которые handler отдаёт клиенту как есть
+#[derive(Debug, Error)]
+pub enum GroupMembershipError {
+    #[error("Group is full ({max_students} students max)")]
+    Full { max_students: u32 },
+    #[error("Only students can be group members")]
+    NotStudent,
+    #[error("Member not found in group")]
+    NotMember,
+}
+
+/// Результат смены куратора: id прежнего и нового куратора для аудита
+#[derive(Debug)]
+pub struct CuratorChange {
+    pub group: GroupResponse,
+    pub previous: Option<String>,
+    pub current: Option<String>,
+}
 
 pub struct GroupService {
     mongo: Database,
@@ -52,6 +72,9 @@ impl GroupService {
             school: req.school,
             curator_id,
             description: req.description,
+            student_count: Some(0),
+            max_students: req.max_students,
+            archived_at: None,
             created_at: now,
             updated_at: now,
         };
@@ -85,6 +108,10 @@ impl GroupService {
         // Построение фильтра
         let mut filter = doc! {};
 
+        if !query.include_archived {
+            filter.insert("archivedAt", doc! { "$exists": false });
+        }
+
         if let Some(school) = query.school {
             filter.insert("school", school);
         }
@@ -123,7 +150,11 @@ impl GroupService {
     }
 
     /// Получить несколько групп по списку id, сохраняя порядок
-    pub async fn fetch_groups_by_ids(&self, group_ids: &[String]) -> Result<Vec<GroupResponse>> {
+    pub async fn fetch_groups_by_ids(
+        &self,
+        group_ids: &[String],
+        include_archived: bool,
+    ) -> Result<Vec<GroupResponse>> {
         if group_ids.is_empty() {
             return Ok(Vec::new());
         }
@@ -137,9 +168,14 @@ impl GroupService {
             return Ok(Vec::new());
         }
 
+        let mut filter = doc! { "_id": { "$in": &object_ids } };
+        if !include_archived {
+            filter.insert("archivedAt", doc! { "$exists": false });
+        }
+
         let groups_collection = self.mongo.collection::<Group>("groups");
         let mut cursor = groups_collection
-            .find(doc! { "_id": { "$in": &object_ids } })
+            .find(filter)
             .await
             .context("Failed to query groups")?;
 
@@ -168,6 +204,53 @@ impl GroupService {
         Ok(ordered)
     }
 
+    /// Ученики групп одним запросом: group_id -> id учеников
+    pub async fn students_by_group(
+        &self,
+        group_ids: &[String],
+    ) -> Result<HashMap<String, Vec<String>>> {
+        let mut students: HashMap<String, Vec<String>> = group_ids
+            .iter()
+            .map(|group_id| (group_id.clone(), Vec::new()))
+            .collect();
+        if group_ids.is_empty() {
+            return Ok(students);
+        }
+
+        let mut cursor = self
+            .mongo
+            .collection::<mongodb::bson::Document>("users")
+            .find(doc! { "group_ids": { "$in": group_ids }, "role": "student" })
+            .projection(doc! { "_id": 1, "group_ids": 1 })
+            .await
+            .context("Failed to query group students")?;
+
+        while cursor
+            .advance()
+            .await
+            .context("Failed to advance students cursor")?
+        {
+            let user = cursor
+                .deserialize_current()
+                .context("Failed to deserialize student")?;
+            let Ok(user_id) = user.get_object_id("_id") else {
+                continue;
+            };
+            let user_groups = user.get_array("group_ids").map(|ids| ids.iter());
+            for group_id in user_groups
+                .into_iter()
+                .flatten()
+                .filter_map(|id| id.as_str())
+            {
+                if let Some(members) = students.get_mut(group_id) {
+                    members.push(user_id.to_hex());
+                }
+            }
+        }
+
+        Ok(students)
+    }
+
     /// Экспорт всех групп без пагинации
     pub async fn export_groups(&self) -> Result<Vec<GroupResponse>> {
         let groups_collection = self.mongo.collection::<Group>("groups");
@@ -265,6 +348,12 @@ impl GroupService {
                 .insert("description", description);
         }
 
+        if let Some(max_students) = req.max_students {
+            update_doc
+                .get_document_mut("$set")?
+                .insert("maxStudents", max_students);
+        }
+
         // Обновление в MongoDB
         let result = groups_collection
             .update_one(doc! { "_id": object_id }, update_doc)
@@ -285,6 +374,117 @@ impl GroupService {
         self.populate_group_response(updated_group).await
     }
 
+    /// Архивировать группу. Повторный вызов ничего не меняет
+    pub async fn archive_group(&self, group_id: &str) -> Result<GroupResponse> {
+        let groups_collection = self.mongo.collection::<Group>("groups");
+
+        let object_id = ObjectId::parse_str(group_id).context("Invalid group ID format")?;
+        let now = BsonDateTime::from_millis(Utc::now().timestamp_millis());
+
+        groups_collection
+            .update_one(
+                doc! { "_id": object_id, "archivedAt": { "$exists": false } },
+                doc! { "$set": { "archivedAt": now, "updatedAt": now } },
+            )
+            .await
+            .context("Failed to archive group")?;
+
+        self.get_group(group_id).await
+    }
+
+    /// Архивирована ли группа; несуществующая группа архивной не считается
+    pub async fn is_archived(&self, group_id: &str) -> Result<bool> {
+        let Ok(object_id) = ObjectId::parse_str(group_id) else {
+            return Ok(false);
+        };
+
+        let archived = self
+            .mongo
+            .collection::<Group>("groups")
+            .count_documents(doc! { "_id": object_id, "archivedAt": { "$exists": true } })
+            .await
+            .context("Failed to check group archive state")?;
+
+        Ok(archived > 0)
+    }
+
+    /// Сменить куратора группы и перенести группу в group_ids учителей
+    pub async fn reassign_curator(
+        &self,
+        group_id: &str,
+        curator_id: Option<&str>,
+    ) -> Result<CuratorChange> {
+        let groups_collection = self.mongo.collection::<Group>("groups");
+        let users_collection = self.mongo.collection::<User>("users");
+
+        let object_id = ObjectId::parse_str(group_id).context("Invalid group ID format")?;
+        let group_id_str = object_id.to_hex();
+
+        let curator_oid = match curator_id {
+            Some(curator_id) => {
+                let curator_oid =
+                    ObjectId::parse_str(curator_id).context("Invalid curator ID format")?;
+                let curator = users_collection
+                    .find_one(doc! { "_id": curator_oid })
+                    .await
+                    .context("Failed to query curator")?
+                    .ok_or_else(|| anyhow!("Curator not found"))?;
+                if curator.role != UserRole::Teacher {
+                    return Err(anyhow!("Curator must have teacher role"));
+                }
+                Some(curator_oid)
+            }
+            None => None,
+        };
+
+        let now = BsonDateTime::from_millis(Utc::now().timestamp_millis());
+        let update_doc = match curator_oid {
+            Some(oid) => doc! { "$set": { "curatorId": oid, "updatedAt": now } },
+            None => doc! { "$set": { "updatedAt": now }, "$unset": { "curatorId": "" } },
+        };
+
+        // Возвращает документ до изменения - из него берём прежнего куратора
+        let previous_group = groups_collection
+            .find_one_and_update(doc! { "_id": object_id }, update_doc)
+            .await
+            .context("Failed to update group curator")?
+            .ok_or_else(|| anyhow!("Group not found"))?;
+        let previous = previous_group.curator_id;
+
+        if previous != curator_oid {
+            if let Some(previous) = previous {
+                users_collection
+                    .update_one(
+                        doc! { "_id": previous },
+                        doc! {
+                            "$pull": { "group_ids": &group_id_str },
+                            "$set": { "updatedAt": now },
+                        },
+                    )
+                    .await
+                    .context("Failed to detach group from previous curator")?;
+            }
+            if let Some(current) = curator_oid {
+                users_collection
+                    .update_one(
+                        doc! { "_id": current },
+                        doc! {
+                            "$addToSet": { "group_ids": &group_id_str },
+                            "$set": { "updatedAt": now },
+                        },
+                    )
+                    .await
+                    .context("Failed to attach group to new curator")?;
+            }
+        }
+
+        Ok(CuratorChange {
+            group: self.get_group(group_id).await?,
+            previous: previous.map(|id| id.to_hex()),
+            current: curator_oid.map(|id| id.to_hex()),
+        })
+    }
+
     /// Удалить группу
     pub async fn delete_group(&self, group_id: &str) -> Result<()> {
         let groups_collection = self.mongo.collection::<Group>("groups");
@@ -315,6 +515,167 @@ impl GroupService {
         Ok(())
     }
 
+    /// Добавить ученика в группу. Возвращает `false`, если он уже состоит в ней.
+    ///
+    /// Место в группе сначала резервируется условным `$inc` по `studentCount`,
+    /// поэтому параллельные добавления не превысят `maxStudents`.
+    pub async fn add_member(&self, group_id: &str, user_id: &str) -> Result<bool> {
+        let groups_collection = self.mongo.collection::<Group>("groups");
+        let users_collection = self.mongo.collection::<User>("users");
+
+        let group_oid = ObjectId::parse_str(group_id).context("Invalid group ID format")?;
+        let user_oid = ObjectId::parse_str(user_id).context("Invalid user ID format")?;
+        let group_id_str = group_oid.to_hex();
+
+        let group = groups_collection
+            .find_one(doc! { "_id": group_oid })
+            .await
+            .context("Failed to query group")?
+            .ok_or_else(|| anyhow!("Group not found"))?;
+
+        let user = users_collection
+            .find_one(doc! { "_id": user_oid })
+            .await
+            .context("Failed to query user")?
+            .ok_or_else(|| anyhow!("User not found"))?;
+
+        if user.role != UserRole::Student {
+            return Err(GroupMembershipError::NotStudent.into());
+        }
+        if user.group_ids.contains(&group_id_str) {
+            return Ok(false);
+        }
+
+        // Группы, созданные до денормализации, получают счётчик при первом изменении
+        if group.student_count.is_none() {
+            self.sync_student_counts(std::slice::from_ref(&group_id_str))
+                .await?;
+        }
+
+        let mut reserve_filter = doc! { "_id": group_oid };
+        if let Some(max_students) = group.max_students {
+            reserve_filter.insert("studentCount", doc! { "$lt": max_students });
+        }
+        let reserved = groups_collection
+            .update_one(
+                reserve_filter,
+                doc! {
+                    "$inc": { "studentCount": 1 },
+                    "$set": { "updatedAt": BsonDateTime::from_millis(Utc::now().timestamp_millis()) },
+                },
+            )
+            .await
+            .context("Failed to reserve group slot")?;
+
+        if reserved.matched_count == 0 {
+            return Err(GroupMembershipError::Full {
+                max_students: group.max_students.unwrap_or_default(),
+            }
+            .into());
+        }
+
+        let added = users_collection
+            .update_one(
+                doc! { "_id": user_oid, "group_ids": { "$ne": &group_id_str } },
+                doc! {
+                    "$addToSet": { "group_ids": &group_id_str },
+                    "$set": { "updatedAt": BsonDateTime::from_millis(Utc::now().timestamp_millis()) },
+                },
+            )
+            .await
+            .context("Failed to add user to group")?;
+
+        if added.modified_count == 0 {
+            // Ученика успели добавить параллельно - возвращаем зарезервированное место
+            groups_collection
+                .update_one(
+                    doc! { "_id": group_oid },
+                    doc! { "$inc": { "studentCount": -1 } },
+                )
+                .await
+                .context("Failed to release group slot")?;
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+
+    /// Исключить ученика из группы
+    pub async fn remove_member(&self, group_id: &str, user_id: &str) -> Result<()> {
+        let groups_collection = self.mongo.collection::<Group>("groups");
+        let users_collection = self.mongo.collection::<User>("users");
+
+        let group_oid = ObjectId::parse_str(group_id).context("Invalid group ID format")?;
+        let user_oid = ObjectId::parse_str(user_id).context("Invalid user ID format")?;
+        let group_id_str = group_oid.to_hex();
+
+        let group = groups_collection
+            .find_one(doc! { "_id": group_oid })
+            .await
+            .context("Failed to query group")?
+            .ok_or_else(|| anyhow!("Group not found"))?;
+
+        let removed = users_collection
+            .update_one(
+                doc! { "_id": user_oid, "group_ids": &group_id_str },
+                doc! {
+                    "$pull": { "group_ids": &group_id_str },
+                    "$set": { "updatedAt": BsonDateTime::from_millis(Utc::now().timestamp_millis()) },
+                },
+            )
+            .await
+            .context("Failed to remove user from group")?;
+
+        if removed.modified_count == 0 {
+            return Err(GroupMembershipError::NotMember.into());
+        }
+
+        if group.student_count.is_some() {
+            groups_collection
+                .update_one(
+                    doc! { "_id": group_oid },
+                    doc! {
+                        "$inc": { "studentCount": -1 },
+                        "$set": { "updatedAt": BsonDateTime::from_millis(Utc::now().timestamp_millis()) },
+                    },
+                )
+                .await
+                .context("Failed to update group student count")?;
+        } else {
+            self.sync_student_counts(std::slice::from_ref(&group_id_str))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Пересчитать `studentCount` у групп после изменения users.group_ids в обход
+    /// add_member/remove_member (создание, редактирование, удаление пользователя)
+    pub async fn sync_student_counts(&self, group_ids: &[String]) -> Result<()> {
+        let groups_collection = self.mongo.collection::<Group>("groups");
+        let users_collection = self.mongo.collection::<User>("users");
+
+        let unique: BTreeSet<&String> = group_ids.iter().collect();
+        for group_id in unique {
+            let Ok(group_oid) = ObjectId::parse_str(group_id) else {
+                continue;
+            };
+            let count = users_collection
+                .count_documents(doc! { "group_ids": group_id, "role": "student" })
+                .await
+                .context("Failed to count group students")?;
+            groups_collection
+                .update_one(
+                    doc! { "_id": group_oid },
+                    doc! { "$set": { "studentCount": count as i64 } },
+                )
+                .await
+                .context("Failed to update group student count")?;
+        }
+
+        Ok(())
+    }
+
     /// Populate GroupResponse с curator_name и student_count
     async fn populate_group_response(&self, group: Group) -> Result<GroupResponse> {
         let mut response = GroupResponse::from(group.clone());
@@ -327,15 +688,18 @@ impl GroupService {
             }
         }
 
-        // Подсчет student_count
-        let group_id_str = group.id.map(|id| id.to_hex()).unwrap_or_default();
-        let users_collection = self.mongo.collection::<User>("users");
-        let student_count = users_collection
-            .count_documents(doc! { "group_ids": &group_id_str })
-            .await
-            .unwrap_or(0);
-
-        response.student_count = student_count as usize;
+        // student_count хранится в группе; у старых групп считаем на лету
+        response.student_count = match group.student_count {
+            Some(count) => count as usize,
+            None => {
+                let group_id_str = group.id.map(|id| id.to_hex()).unwrap_or_default();
+                let users_collection = self.mongo.collection::<User>("users");
+                users_collection
+                    .count_documents(doc! { "group_ids": &group_id_str, "role": "student" })
+                    .await
+                    .unwrap_or(0) as usize
+            }
+        };
 
         Ok(response)
     }
@@ -424,6 +788,7 @@ mod tests {
             school: "School 1".into(),
             curator_id: Some(curator_id.to_hex()),
             description: None,
+            max_students: None,
         };
 
         let err = service
@@ -472,6 +837,7 @@ mod tests {
         let mut query = ListGroupsQuery {
             search: None,
             school: Some("School X".into()),
+            include_archived: false,
             limit: None,
             offset: None,
         };
@@ -1,29 +1,116 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
 use anyhow::{Context, Result};
+use async_trait::async_trait;
 use chrono::Utc;
-use mongodb::Database;
+use mongodb::{
+    bson::{doc, oid::ObjectId, Bson, Document},
+    Database,
+};
 use redis::aio::ConnectionManager;
 use uuid::Uuid;
 
-use crate::models::hint::{HintRecord, HintSource, RequestHintRequest, RequestHintResponse};
+use crate::config::HintSettings;
+use crate::metrics::{HINTS_REQUESTED_TOTAL, HINT_PROVIDER_DURATION_SECONDS};
+use crate::models::hint::{
+    HintPolicy, HintRecord, HintSource, RequestHintRequest, RequestHintResponse,
+};
+use crate::services::llm_provider::{LlmParams, LlmProvider};
 
 const HINT_COST: i32 = 5;
 const CACHE_TTL: u64 = 300; // 5 minutes
+/// Сколько правил и примеров каждого правила попадает в запрос к LLM
+const GROUNDING_RULES_LIMIT: usize = 3;
+const GROUNDING_EXAMPLES_LIMIT: usize = 3;
+/// Последнее звено цепочки: отдаётся, когда ни один провайдер не сработал
+pub const GENERIC_HINT: &str = "Перечитайте правило к этому заданию и сверьте с ним свой ответ.";
+
+/// Атомарно резервирует подсказку и добавляет её штраф к сумме сессии.
+/// ARGV[1] - лимит (-1 без ограничения), ARGV[2..] - шкала штрафов.
+/// Возвращает {подсказок использовано, суммарный штраф, 1 если подсказка выдана}
+const RESERVE_HINT_SCRIPT: &str = r#"
+    local current = tonumber(redis.call('GET', KEYS[1]) or '0')
+    local max_hints = tonumber(ARGV[1])
+    if max_hints >= 0 and current >= max_hints then
+        return {current, tonumber(redis.call('GET', KEYS[2]) or '0'), 0}
+    end
+
+    local index = current + 1
+    local penalty = 0
+    local steps = #ARGV - 1
+    if steps > 0 then
+        penalty = tonumber(ARGV[math.min(index, steps) + 1])
+    end
+
+    redis.call('INCR', KEYS[1])
+    local total = redis.call('INCRBY', KEYS[2], penalty)
+    redis.call('EXPIRE', KEYS[1], 3600)
+    redis.call('EXPIRE', KEYS[2], 3600)
+    return {index, total, 1}
+"#;
+
+/// Лимит подсказок сессии исчерпан (ответ 409 `HINT_LIMIT_REACHED`)
+#[derive(Debug, thiserror::Error)]
+#[error("Maximum hints limit reached ({max_hints})")]
+pub struct HintBudgetExhausted {
+    pub max_hints: u32,
+    pub hints_used: u32,
+}
+
+pub fn hints_used_key(session_id: &str) -> String {
+    format!("hints_used:{}", session_id)
+}
+
+/// Накопленный штраф сессии за подсказки, в процентах
+pub fn hint_penalty_key(session_id: &str) -> String {
+    format!("hints_penalty:{}", session_id)
+}
+
+/// Ответ на запрос подсказки с `idempotency_key`: повтор запроса получает тот же текст
+pub fn hint_request_cache_key(request_id: &str) -> String {
+    format!("hint:request:{}", request_id)
+}
 
 pub struct HintService {
     mongo: Database,
     redis: ConnectionManager,
-    python_api_url: String,
+    settings: HintSettings,
+    chain: HintChain,
 }
 
 impl HintService {
-    pub fn new(mongo: Database, redis: ConnectionManager, python_api_url: String) -> Self {
+    /// `llm_timeout` - таймаут запроса к LLM (`config.yandexgpt.timeout_ms`)
+    pub fn new(
+        mongo: Database,
+        redis: ConnectionManager,
+        llm: Arc<dyn LlmProvider>,
+        settings: HintSettings,
+        llm_timeout: Duration,
+    ) -> Self {
+        let chain = HintChain::new(vec![
+            Arc::new(LlmHintProvider::new(
+                llm,
+                llm_timeout,
+                LlmHintProvider::enabled_from_env(),
+            )),
+            Arc::new(RuleHintProvider::new(mongo.clone())),
+            Arc::new(GenericHintProvider),
+        ]);
         Self {
             mongo,
             redis,
-            python_api_url,
+            settings,
+            chain,
         }
     }
 
+    /// Заменить цепочку провайдеров (тесты, альтернативные LLM)
+    pub fn with_chain(mut self, chain: HintChain) -> Self {
+        self.chain = chain;
+        self
+    }
+
     pub async fn request_hint(
         &self,
         session_id: &str,
@@ -38,24 +125,14 @@ impl HintService {
             task_id
         );
 
-        let max_hints = Self::max_hints_per_session();
-        // Check hints limit using Lua script for atomicity (unless limit disabled)
-        let hints_used = if let Some(limit) = max_hints {
-            let used = self.check_and_increment_hints(session_id, limit).await?;
-
-            if used > limit {
-                anyhow::bail!("Maximum hints limit reached ({})", limit);
-            }
-
-            used
-        } else {
-            self.increment_hints_counter(session_id).await?
-        };
+        let policy = self.resolve_policy(task_id).await?;
+        let (hints_used, total_penalty) = self.reserve_hint(session_id, &policy).await?;
+        let penalty_percent = policy.penalty_for(hints_used);
+        let remaining_hints = policy.remaining(hints_used);
 
         // Deduct points BEFORE providing hint (Rule S3)
         let new_score = self.deduct_hint_cost(user_id).await?;
 
-        // Get hint text (cache -> Python API -> fallback)
         let (hint_text, source) = self.get_hint_text(task_id, req).await?;
 
         // Save hint record to MongoDB
@@ -71,11 +148,15 @@ impl HintService {
         };
 
         self.save_hint_record(&record).await?;
+        HINTS_REQUESTED_TOTAL
+            .with_label_values(&[source.as_str()])
+            .inc();
 
         tracing::info!(
-            "Hint provided: session={}, hints_used={}, new_score={}",
+            "Hint provided: session={}, hints_used={}, penalty={}%, new_score={}",
             session_id,
             hints_used,
+            total_penalty,
             new_score
         );
 
@@ -83,68 +164,78 @@ impl HintService {
             hint: hint_text.clone(),
             hint_text,
             hints_used,
-            hints_remaining: max_hints
-                .map(|limit| limit.saturating_sub(hints_used))
-                .unwrap_or(u32::MAX),
+            hints_remaining: remaining_hints.unwrap_or(u32::MAX),
+            remaining_hints,
             cost: HINT_COST,
             new_score,
+            penalty_percent,
+            total_penalty_percent: total_penalty.min(100),
+            next_penalty_percent: (remaining_hints != Some(0))
+                .then(|| policy.penalty_for(hints_used + 1)),
+            provider: source,
         })
     }
 
-    // Lua script ensures atomic check + increment
-    async fn check_and_increment_hints(&self, session_id: &str, max_hints: u32) -> Result<u32> {
-        let mut conn = self.redis.clone();
-        let key = format!("hints_used:{}", session_id);
-
-        let lua_script = r#"
-            local key = KEYS[1]
-            local max_hints = tonumber(ARGV[1])
-            local current = redis.call('GET', key)
-            
-            if current == false then
-                current = 0
-            else
-                current = tonumber(current)
-            end
-            
-            if current >= max_hints then
-                return current + 1
-            end
-            
-            redis.call('INCR', key)
-            redis.call('EXPIRE', key, 3600)
-            
-            return current + 1
-        "#;
-
-        let hints_used: u32 = redis::Script::new(lua_script)
-            .key(&key)
-            .arg(max_hints)
-            .invoke_async(&mut conn)
+    /// Политика подсказок задания: поля задания, затем `params` шаблона, затем настройки
+    pub async fn resolve_policy(&self, task_id: &str) -> Result<HintPolicy> {
+        let filter = match ObjectId::parse_str(task_id) {
+            Ok(oid) => doc! { "_id": oid },
+            Err(_) => doc! { "_id": task_id },
+        };
+        let task = self
+            .mongo
+            .collection::<Document>("tasks")
+            .find_one(filter)
             .await
-            .context("Failed to execute hints limit Lua script")?;
+            .context("Failed to load task for hint policy")?;
+
+        let template_id = task
+            .as_ref()
+            .and_then(|task| task.get_object_id("template_id").ok());
+        let template = match template_id {
+            Some(template_id) => self
+                .mongo
+                .collection::<Document>("templates")
+                .find_one(doc! { "_id": template_id })
+                .projection(doc! { "params": 1 })
+                .await
+                .context("Failed to load template for hint policy")?,
+            None => None,
+        };
+        let params = template
+            .as_ref()
+            .and_then(|template| template.get_document("params").ok());
 
-        Ok(hints_used)
+        Ok(HintPolicy::resolve(task.as_ref(), params, &self.settings))
     }
 
-    /// Increment counter without enforcing limit (used when limit disabled)
-    async fn increment_hints_counter(&self, session_id: &str) -> Result<u32> {
+    /// Резервирует подсказку в бюджете сессии; возвращает (использовано, суммарный штраф %)
+    async fn reserve_hint(&self, session_id: &str, policy: &HintPolicy) -> Result<(u32, u32)> {
         let mut conn = self.redis.clone();
-        let key = format!("hints_used:{}", session_id);
-
-        let hints_used: u32 = redis::cmd("INCR")
-            .arg(&key)
-            .query_async(&mut conn)
+        let limit = policy.max_hints.map(i64::from).unwrap_or(-1);
+
+        let script = redis::Script::new(RESERVE_HINT_SCRIPT);
+        let mut invocation = script.prepare_invoke();
+        invocation
+            .key(hints_used_key(session_id))
+            .key(hint_penalty_key(session_id))
+            .arg(limit);
+        for penalty in &policy.penalty_schedule {
+            invocation.arg(*penalty);
+        }
+        let (hints_used, total_penalty, granted): (u32, u32, u8) = invocation
+            .invoke_async(&mut conn)
             .await
-            .context("Failed to increment hints counter")?;
-
-        redis::cmd("EXPIRE")
-            .arg(&key)
-            .arg(3600)
-            .query_async::<()>(&mut conn)
-            .await?;
+            .context("Failed to execute hints limit Lua script")?;
 
-        Ok(hints_used)
+        if granted == 0 {
+            return Err(HintBudgetExhausted {
+                max_hints: policy.max_hints.unwrap_or_default(),
+                hints_used,
+            }
+            .into());
+        }
+        Ok((hints_used, total_penalty))
     }
 
     // Rule S3: Deduct -5 points for hint
@@ -162,52 +253,118 @@ impl HintService {
         Ok(new_score)
     }
 
+    /// Текст подсказки: кэш, затем цепочка провайдеров (LLM -> правило -> общая подсказка)
     async fn get_hint_text(
         &self,
         task_id: &str,
         req: &RequestHintRequest,
     ) -> Result<(String, HintSource)> {
-        // 1. Try cache first
+        let request_key = req.idempotency_key.as_deref().map(hint_request_cache_key);
+        if let Some(key) = &request_key {
+            if let Ok(cached) = self.get_cached(key).await {
+                tracing::debug!("Hint found in cache for request={}", key);
+                return Ok((cached, HintSource::Cache));
+            }
+        }
         if let Ok(cached) = self.get_cached_hint(task_id).await {
             tracing::debug!("Hint found in cache for task={}", task_id);
             return Ok((cached, HintSource::Cache));
         }
 
-        // 2. Try Python Explanation API with 2s timeout (if enabled)
-        if Self::python_api_enabled() {
-            match self.fetch_from_python_api(task_id, req).await {
-                Ok(hint) => {
-                    // Cache the result
-                    self.cache_hint(task_id, &hint).await.ok();
-                    tracing::debug!("Hint fetched from Python API for task={}", task_id);
-                    return Ok((hint, HintSource::PythonApi));
-                }
-                Err(e) => {
-                    tracing::warn!("Python API failed for task={}: {}", task_id, e);
-                }
-            }
-        } else {
-            tracing::debug!(
-                "Python API disabled via env; skipping call for task={}",
-                task_id
+        // Без текста задания и правил подсказка LLM не опирается на контент; ошибка
+        // загрузки не мешает цепочке - дальше есть подсказка по правилу
+        let grounding = self.load_grounding(task_id).await.unwrap_or_else(|e| {
+            tracing::warn!(
+                "Failed to load hint grounding for task {}: {:#}",
+                task_id,
+                e
             );
+            HintGrounding::default()
+        });
+        let (hint, source) = self
+            .chain
+            .generate(&HintContext {
+                task_id,
+                request: req,
+                grounding: &grounding,
+            })
+            .await;
+        // Кэшируем только ответ LLM: подсказки по правилу дешёвые и могут поменяться вместе с контентом
+        if source == HintSource::Llm {
+            self.cache_hint(&explanation_cache_key(task_id), &hint)
+                .await
+                .ok();
+            if let Some(key) = &request_key {
+                self.cache_hint(key, &hint).await.ok();
+            }
         }
+        Ok((hint, source))
+    }
+
+    /// Текст задания (или шаблона) и привязанные правила с примерами
+    async fn load_grounding(&self, task_id: &str) -> Result<HintGrounding> {
+        let filter = match ObjectId::parse_str(task_id) {
+            Ok(oid) => doc! { "_id": oid },
+            Err(_) => doc! { "_id": task_id },
+        };
+        let Some(task) = self
+            .mongo
+            .collection::<Document>("tasks")
+            .find_one(filter)
+            .await
+            .context("Failed to load task for hint grounding")?
+        else {
+            return Ok(HintGrounding::default());
+        };
 
-        // 3. Fallback to static hint from MongoDB
-        let fallback = self.get_fallback_hint(task_id).await?;
-        tracing::debug!("Using fallback hint for task={}", task_id);
-        Ok((fallback, HintSource::Fallback))
+        let mut task_text = ["description", "title"]
+            .iter()
+            .find_map(|field| {
+                task.get_str(field)
+                    .ok()
+                    .filter(|text| !text.trim().is_empty())
+            })
+            .map(str::to_string);
+        if task_text.is_none() {
+            if let Ok(template_id) = task.get_object_id("template_id") {
+                task_text = self
+                    .mongo
+                    .collection::<Document>("templates")
+                    .find_one(doc! { "_id": template_id })
+                    .projection(doc! { "content": 1 })
+                    .await
+                    .context("Failed to load template for hint grounding")?
+                    .and_then(|template| template.get_str("content").ok().map(str::to_string));
+            }
+        }
+
+        let rule_ids = linked_rule_ids(&self.mongo, &task).await?;
+        let mut rules = Vec::new();
+        for rule_id in rule_ids.iter().take(GROUNDING_RULES_LIMIT) {
+            let rule = self
+                .mongo
+                .collection::<Document>("rules")
+                .find_one(doc! { "_id": rule_id })
+                .await
+                .context("Failed to load rule for hint grounding")?;
+            if let Some(rule) = rule {
+                rules.push(GroundingRule::from_document(&rule));
+            }
+        }
+        Ok(HintGrounding { task_text, rules })
     }
 
-    async fn get_cached_hint(&self, task_id: &str) -> Result<String> {
+    async fn get_cached(&self, cache_key: &str) -> Result<String> {
         let mut conn = self.redis.clone();
-        let cache_key = format!("explanation:cache:{}", task_id);
-
-        let raw: String = redis::cmd("GET")
-            .arg(&cache_key)
+        redis::cmd("GET")
+            .arg(cache_key)
             .query_async(&mut conn)
             .await
-            .context("Hint not in cache")?;
+            .context("Hint not in cache")
+    }
+
+    async fn get_cached_hint(&self, task_id: &str) -> Result<String> {
+        let raw = self.get_cached(&explanation_cache_key(task_id)).await?;
 
         if let Ok(json_value) = serde_json::from_str::<serde_json::Value>(&raw) {
             if let Some(explanation) = json_value
@@ -225,55 +382,10 @@ impl HintService {
         Ok(raw)
     }
 
-    async fn fetch_from_python_api(
-        &self,
-        task_id: &str,
-        req: &RequestHintRequest,
-    ) -> Result<String> {
-        let url = format!("{}/v1/explanations", self.python_api_url);
-
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(2))
-            .build()?;
-
-        let language = req.language.clone().unwrap_or_else(|| "ru".to_string());
-
-        let body = serde_json::json!({
-            "task_id": task_id,
-            "topic_id": req.topic_id.clone(),
-            "task_type": req.task_type.clone(),
-            "user_errors": req.user_errors.clone(),
-            "language_level": req.language_level.clone(),
-            "language": language,
-            "request_id": req.idempotency_key.clone(),
-        });
-
-        let response = client
-            .post(&url)
-            .json(&body)
-            .send()
-            .await
-            .context("Failed to call Python API")?;
-
-        if !response.status().is_success() {
-            anyhow::bail!("Python API returned status: {}", response.status());
-        }
-
-        let body: serde_json::Value = response.json().await?;
-        let hint_text = body["explanation"]
-            .as_str()
-            .ok_or_else(|| anyhow::anyhow!("Invalid response format"))?
-            .to_string();
-
-        Ok(hint_text)
-    }
-
-    async fn cache_hint(&self, task_id: &str, hint: &str) -> Result<()> {
+    async fn cache_hint(&self, cache_key: &str, hint: &str) -> Result<()> {
         let mut conn = self.redis.clone();
-        let cache_key = format!("explanation:cache:{}", task_id);
-
         let _: () = redis::cmd("SETEX")
-            .arg(&cache_key)
+            .arg(cache_key)
             .arg(CACHE_TTL)
             .arg(hint)
             .query_async(&mut conn)
@@ -283,32 +395,6 @@ impl HintService {
         Ok(())
     }
 
-    async fn get_fallback_hint(&self, task_id: &str) -> Result<String> {
-        let collection: mongodb::Collection<mongodb::bson::Document> =
-            self.mongo.collection("tasks");
-
-        let filter = mongodb::bson::doc! { "_id": task_id };
-
-        match collection.find_one(filter).await {
-            Ok(Some(task)) => {
-                let hint = task
-                    .get_str("hint")
-                    .or_else(|_| task.get_str("static_hint"))
-                    .unwrap_or("Try to think about the problem from a different angle.")
-                    .to_string();
-                Ok(hint)
-            }
-            Ok(None) => {
-                tracing::warn!("Task {} not found in MongoDB, using default hint", task_id);
-                Ok("Try to think about the problem from a different angle.".to_string())
-            }
-            Err(e) => {
-                tracing::error!("Failed to query tasks collection: {}", e);
-                Ok("Try to think about the problem from a different angle.".to_string())
-            }
-        }
-    }
-
     async fn save_hint_record(&self, record: &HintRecord) -> Result<()> {
         tracing::info!(
             "Saving hint record to MongoDB: user={}, task={}, source={:?}",
@@ -327,19 +413,516 @@ impl HintService {
         tracing::info!("Hint record saved successfully with id={}", record.id);
         Ok(())
     }
+}
+
+fn explanation_cache_key(task_id: &str) -> String {
+    format!("explanation:cache:{}", task_id)
+}
+
+/// Данные запроса, доступные провайдерам подсказок
+pub struct HintContext<'a> {
+    pub task_id: &'a str,
+    pub request: &'a RequestHintRequest,
+    pub grounding: &'a HintGrounding,
+}
+
+/// Контент, на который опирается подсказка LLM: текст задания и его правила
+#[derive(Debug, Clone, Default)]
+pub struct HintGrounding {
+    pub task_text: Option<String>,
+    pub rules: Vec<GroundingRule>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GroundingRule {
+    pub name: String,
+    pub description: String,
+    pub examples: Vec<String>,
+}
+
+impl GroundingRule {
+    fn from_document(rule: &Document) -> Self {
+        Self {
+            name: rule.get_str("name").unwrap_or_default().to_string(),
+            description: rule.get_str("description").unwrap_or_default().to_string(),
+            examples: rule
+                .get_array("examples")
+                .map(|examples| {
+                    examples
+                        .iter()
+                        .filter_map(Bson::as_str)
+                        .map(str::trim)
+                        .filter(|example| !example.is_empty())
+                        .take(GROUNDING_EXAMPLES_LIMIT)
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Источник текста подсказки в цепочке фолбэков
+#[async_trait]
+pub trait HintProvider: Send + Sync {
+    fn source(&self) -> HintSource;
+
+    async fn generate(&self, ctx: &HintContext<'_>) -> Result<String>;
+}
+
+/// Провайдеры по порядку: первый непустой ответ становится подсказкой.
+///
+/// Каждая попытка логируется с именем провайдера, её длительность пишется
+/// в `hint_provider_duration_seconds{provider, outcome}`.
+pub struct HintChain {
+    providers: Vec<Arc<dyn HintProvider>>,
+}
 
-    fn max_hints_per_session() -> Option<u32> {
-        match std::env::var("HINTS_MAX_PER_SESSION") {
-            Ok(value) => match value.parse::<i64>() {
-                Ok(parsed) if parsed <= 0 => None,
-                Ok(parsed) => Some(parsed as u32),
-                Err(_) => Some(2),
-            },
-            Err(_) => Some(2),
+impl HintChain {
+    pub fn new(providers: Vec<Arc<dyn HintProvider>>) -> Self {
+        Self { providers }
+    }
+
+    pub async fn generate(&self, ctx: &HintContext<'_>) -> (String, HintSource) {
+        for provider in &self.providers {
+            let source = provider.source();
+            let started = Instant::now();
+            let result = provider.generate(ctx).await;
+            let elapsed = started.elapsed();
+
+            let outcome = match &result {
+                Ok(text) if !text.trim().is_empty() => "success",
+                Ok(_) => "empty",
+                Err(_) => "error",
+            };
+            HINT_PROVIDER_DURATION_SECONDS
+                .with_label_values(&[source.as_str(), outcome])
+                .observe(elapsed.as_secs_f64());
+
+            match result {
+                Ok(text) if outcome == "success" => {
+                    tracing::info!(
+                        task_id = ctx.task_id,
+                        provider = source.as_str(),
+                        latency_ms = elapsed.as_millis() as u64,
+                        "Hint generated"
+                    );
+                    return (text, source);
+                }
+                Ok(_) => tracing::warn!(
+                    task_id = ctx.task_id,
+                    provider = source.as_str(),
+                    latency_ms = elapsed.as_millis() as u64,
+                    "Hint provider returned empty text"
+                ),
+                Err(err) => tracing::warn!(
+                    task_id = ctx.task_id,
+                    provider = source.as_str(),
+                    latency_ms = elapsed.as_millis() as u64,
+                    error = %err,
+                    "Hint provider failed"
+                ),
+            }
         }
+
+        (GENERIC_HINT.to_string(), HintSource::Generic)
     }
+}
 
-    fn python_api_enabled() -> bool {
-        std::env::var("HINTS_PYTHON_API_ENABLED").unwrap_or_else(|_| "0".to_string()) == "1"
+const HINT_SYSTEM_PROMPT: &str = "Ты помогаешь ученику с заданием по русскому языку. \
+Дай короткую подсказку (1-2 предложения), которая направит к правилу, но не называй правильный ответ.";
+
+/// Объяснение от LLM-провайдера, выбранного в конфигурации
+pub struct LlmHintProvider {
+    llm: Arc<dyn LlmProvider>,
+    timeout: Duration,
+    enabled: bool,
+}
+
+impl LlmHintProvider {
+    pub fn new(llm: Arc<dyn LlmProvider>, timeout: Duration, enabled: bool) -> Self {
+        Self {
+            llm,
+            timeout,
+            enabled,
+        }
+    }
+
+    /// Флаг `HINTS_LLM_ENABLED=1` (прежнее имя `HINTS_PYTHON_API_ENABLED` тоже учитывается);
+    /// выключенный провайдер сразу уступает следующему
+    pub fn enabled_from_env() -> bool {
+        ["HINTS_LLM_ENABLED", "HINTS_PYTHON_API_ENABLED"]
+            .iter()
+            .any(|key| std::env::var(key).is_ok_and(|value| value == "1"))
+    }
+}
+
+fn hint_prompt(ctx: &HintContext<'_>) -> String {
+    let req = ctx.request;
+    let mut prompt = format!("Задание: {}.", ctx.task_id);
+    if let Some(text) = &ctx.grounding.task_text {
+        prompt.push_str(&format!("\nТекст задания: {}", text.trim()));
+    }
+    if !ctx.grounding.rules.is_empty() {
+        prompt.push_str("\nПравила к заданию:");
+        for rule in &ctx.grounding.rules {
+            prompt.push_str(&format!("\n- {}", rule.name));
+            if !rule.description.trim().is_empty() {
+                prompt.push_str(&format!(": {}", rule.description.trim()));
+            }
+            if !rule.examples.is_empty() {
+                prompt.push_str(&format!(" Примеры: {}.", rule.examples.join("; ")));
+            }
+        }
+        prompt.push('\n');
+    }
+    if let Some(topic) = &req.topic_id {
+        prompt.push_str(&format!(" Тема: {}.", topic));
+    }
+    if let Some(task_type) = &req.task_type {
+        prompt.push_str(&format!(" Тип задания: {}.", task_type));
+    }
+    if let Some(level) = &req.language_level {
+        prompt.push_str(&format!(" Уровень ученика: {}.", level));
+    }
+    if !req.user_errors.is_empty() {
+        prompt.push_str(&format!(" Ошибки ученика: {}.", req.user_errors.join("; ")));
+    }
+    let language = req.language.as_deref().unwrap_or("ru");
+    prompt.push_str(&format!(" Язык ответа: {}.", language));
+    prompt
+}
+
+#[async_trait]
+impl HintProvider for LlmHintProvider {
+    fn source(&self) -> HintSource {
+        HintSource::Llm
+    }
+
+    async fn generate(&self, ctx: &HintContext<'_>) -> Result<String> {
+        if !self.enabled {
+            anyhow::bail!("LLM hints are disabled");
+        }
+
+        let params = LlmParams {
+            system_prompt: Some(HINT_SYSTEM_PROMPT.to_string()),
+            ..LlmParams::default()
+        };
+        let response =
+            tokio::time::timeout(self.timeout, self.llm.generate(&hint_prompt(ctx), &params))
+                .await
+                .map_err(|_| anyhow::anyhow!("LLM hint timed out after {:?}", self.timeout))??;
+
+        Ok(response.text)
+    }
+}
+
+/// Подсказка из контента: подсказка автора задания, иначе первый пример
+/// или исключение из правил, к которым привязан шаблон задания
+pub struct RuleHintProvider {
+    mongo: Database,
+}
+
+impl RuleHintProvider {
+    pub fn new(mongo: Database) -> Self {
+        Self { mongo }
+    }
+}
+
+/// Правила задания: свои `rule_ids` задания, иначе правила его шаблона
+async fn linked_rule_ids(mongo: &Database, task: &Document) -> Result<Vec<ObjectId>> {
+    if let Ok(ids) = task.get_array("rule_ids") {
+        return Ok(ids.iter().filter_map(Bson::as_object_id).collect());
+    }
+    let Ok(template_id) = task.get_object_id("template_id") else {
+        return Ok(Vec::new());
+    };
+    let template = mongo
+        .collection::<Document>("templates")
+        .find_one(doc! { "_id": template_id })
+        .projection(doc! { "rule_ids": 1 })
+        .await
+        .context("Failed to load template for rule hint")?;
+    Ok(template
+        .as_ref()
+        .and_then(|template| template.get_array("rule_ids").ok())
+        .map(|ids| ids.iter().filter_map(Bson::as_object_id).collect())
+        .unwrap_or_default())
+}
+
+#[async_trait]
+impl HintProvider for RuleHintProvider {
+    fn source(&self) -> HintSource {
+        HintSource::Rule
+    }
+
+    async fn generate(&self, ctx: &HintContext<'_>) -> Result<String> {
+        let filter = match ObjectId::parse_str(ctx.task_id) {
+            Ok(oid) => doc! { "_id": oid },
+            Err(_) => doc! { "_id": ctx.task_id },
+        };
+        let task = self
+            .mongo
+            .collection::<Document>("tasks")
+            .find_one(filter)
+            .await
+            .context("Failed to load task for rule hint")?
+            .with_context(|| format!("Task {} not found", ctx.task_id))?;
+
+        if let Some(hint) = ["hint", "static_hint"]
+            .iter()
+            .find_map(|field| task.get_str(field).ok().filter(|hint| !hint.is_empty()))
+        {
+            return Ok(hint.to_string());
+        }
+
+        let rule_ids = linked_rule_ids(&self.mongo, &task).await?;
+        for rule_id in &rule_ids {
+            let rule = self
+                .mongo
+                .collection::<Document>("rules")
+                .find_one(doc! { "_id": rule_id })
+                .await
+                .context("Failed to load rule for hint")?;
+            if let Some(hint) = rule.as_ref().and_then(rule_hint_text) {
+                return Ok(hint);
+            }
+        }
+        anyhow::bail!("No linked rule has examples or exceptions")
+    }
+}
+
+/// Подсказка по правилу: его первый пример, иначе первое исключение
+pub fn rule_hint_text(rule: &Document) -> Option<String> {
+    let first = |field: &str| {
+        rule.get_array(field).ok()?.iter().find_map(|value| {
+            value
+                .as_str()
+                .map(str::trim)
+                .filter(|text| !text.is_empty())
+        })
+    };
+    let name = rule
+        .get_str("name")
+        .map(|name| format!("«{}»", name))
+        .unwrap_or_else(|_| "к заданию".to_string());
+
+    if let Some(example) = first("examples") {
+        return Some(format!("Вспомните правило {}. Пример: {}", name, example));
+    }
+    first("exceptions").map(|exception| {
+        format!(
+            "Вспомните правило {}. Обратите внимание на исключение: {}",
+            name, exception
+        )
+    })
+}
+
+/// Общая подсказка «перечитайте правило»; срабатывает всегда
+pub struct GenericHintProvider;
+
+#[async_trait]
+impl HintProvider for GenericHintProvider {
+    fn source(&self) -> HintSource {
+        HintSource::Generic
+    }
+
+    async fn generate(&self, _ctx: &HintContext<'_>) -> Result<String> {
+        Ok(GENERIC_HINT.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::llm_provider::LlmResponse;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Провайдер с заданным ответом; `None` - ошибка
+    struct StubProvider {
+        source: HintSource,
+        reply: Option<&'static str>,
+        calls: AtomicUsize,
+    }
+
+    impl StubProvider {
+        fn new(source: HintSource, reply: Option<&'static str>) -> Arc<Self> {
+            Arc::new(Self {
+                source,
+                reply,
+                calls: AtomicUsize::new(0),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl HintProvider for StubProvider {
+        fn source(&self) -> HintSource {
+            self.source
+        }
+
+        async fn generate(&self, _ctx: &HintContext<'_>) -> Result<String> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            self.reply
+                .map(str::to_string)
+                .ok_or_else(|| anyhow::anyhow!("provider is down"))
+        }
+    }
+
+    fn request() -> RequestHintRequest {
+        RequestHintRequest {
+            idempotency_key: None,
+            topic_id: None,
+            task_type: None,
+            user_errors: vec![],
+            language_level: None,
+            language: None,
+        }
+    }
+
+    async fn run(chain: &HintChain) -> (String, HintSource) {
+        let req = request();
+        chain
+            .generate(&HintContext {
+                task_id: "task-1",
+                request: &req,
+                grounding: &HintGrounding::default(),
+            })
+            .await
+    }
+
+    #[tokio::test]
+    async fn chain_falls_back_when_llm_fails() {
+        let llm = StubProvider::new(HintSource::Llm, None);
+        let rule = StubProvider::new(HintSource::Rule, Some("Пример: ёж"));
+        let generic = StubProvider::new(HintSource::Generic, Some("generic"));
+        let chain = HintChain::new(vec![llm.clone(), rule.clone(), generic.clone()]);
+
+        assert_eq!(
+            run(&chain).await,
+            ("Пример: ёж".to_string(), HintSource::Rule)
+        );
+        assert_eq!(llm.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(generic.calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn chain_skips_empty_replies_and_ends_with_generic() {
+        let chain = HintChain::new(vec![
+            StubProvider::new(HintSource::Llm, Some("  ")),
+            StubProvider::new(HintSource::Rule, None),
+        ]);
+        assert_eq!(
+            run(&chain).await,
+            (GENERIC_HINT.to_string(), HintSource::Generic)
+        );
+
+        let llm = StubProvider::new(HintSource::Llm, Some("LLM hint"));
+        let chain = HintChain::new(vec![llm, Arc::new(GenericHintProvider)]);
+        assert_eq!(run(&chain).await, ("LLM hint".to_string(), HintSource::Llm));
+    }
+
+    /// LLM, который отвечает только через `delay`
+    struct SlowLlm {
+        delay: Duration,
+    }
+
+    #[async_trait]
+    impl LlmProvider for SlowLlm {
+        fn name(&self) -> &str {
+            "slow"
+        }
+
+        async fn generate(&self, prompt: &str, _params: &LlmParams) -> Result<LlmResponse> {
+            tokio::time::sleep(self.delay).await;
+            Ok(LlmResponse {
+                text: format!("hint for {}", prompt),
+                prompt_tokens: 0,
+                completion_tokens: 0,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn llm_provider_respects_timeout_and_flag() {
+        let slow: Arc<dyn LlmProvider> = Arc::new(SlowLlm {
+            delay: Duration::from_secs(30),
+        });
+        let llm = LlmHintProvider::new(slow.clone(), Duration::from_millis(50), true);
+        let req = request();
+        let grounding = HintGrounding::default();
+        let ctx = HintContext {
+            task_id: "task-1",
+            request: &req,
+            grounding: &grounding,
+        };
+        let started = Instant::now();
+        assert!(llm.generate(&ctx).await.is_err());
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        let chain = HintChain::new(vec![
+            Arc::new(llm),
+            StubProvider::new(HintSource::Rule, Some("rule hint")),
+        ]);
+        assert_eq!(run(&chain).await.1, HintSource::Rule);
+
+        let disabled = LlmHintProvider::new(slow, Duration::from_secs(5), false);
+        assert!(disabled.generate(&ctx).await.is_err());
+
+        let fast = LlmHintProvider::new(
+            Arc::new(SlowLlm {
+                delay: Duration::ZERO,
+            }),
+            Duration::from_secs(5),
+            true,
+        );
+        let hint = fast.generate(&ctx).await.unwrap();
+        assert!(hint.starts_with("hint for Задание: task-1."), "{hint}");
+    }
+
+    #[test]
+    fn hint_prompt_is_grounded_in_task_text_and_rules() {
+        let mut req = request();
+        req.user_errors = vec!["жырафф".to_string()];
+        let grounding = HintGrounding {
+            task_text: Some("Вставьте пропущенную букву: ж_раф".to_string()),
+            rules: vec![GroundingRule::from_document(&doc! {
+                "name": "Жи-ши",
+                "description": "Жи и ши пишутся с буквой и.",
+                "examples": ["жираф", "", "шило", "жизнь", "машина"],
+            })],
+        };
+        let prompt = hint_prompt(&HintContext {
+            task_id: "task-1",
+            request: &req,
+            grounding: &grounding,
+        });
+
+        assert!(prompt.contains("Текст задания: Вставьте пропущенную букву: ж_раф"));
+        assert!(
+            prompt.contains("- Жи-ши: Жи и ши пишутся с буквой и. Примеры: жираф; шило; жизнь.")
+        );
+        assert!(!prompt.contains("машина"));
+        assert!(prompt.contains("Ошибки ученика: жырафф."));
+    }
+
+    #[test]
+    fn rule_hint_uses_first_example_then_exception() {
+        let rule = doc! {
+            "name": "Жи-ши",
+            "examples": ["", "жираф, шило"],
+            "exceptions": ["брошюра"],
+        };
+        assert_eq!(
+            rule_hint_text(&rule).as_deref(),
+            Some("Вспомните правило «Жи-ши». Пример: жираф, шило")
+        );
+
+        let rule = doc! { "name": "Жи-ши", "examples": [], "exceptions": ["брошюра"] };
+        assert_eq!(
+            rule_hint_text(&rule).as_deref(),
+            Some("Вспомните правило «Жи-ши». Обратите внимание на исключение: брошюра")
+        );
+        assert!(rule_hint_text(&doc! { "name": "Пустое" }).is_none());
     }
 }
@@ -4,15 +4,64 @@ use anyhow::{anyhow, Context, Result};
 use chrono::Utc;
 use mongodb::bson::{doc, oid::ObjectId, to_bson};
 use mongodb::Database;
+use uuid::Uuid;
 
 use crate::models::{
     anticheat::{
-        IncidentRecord, IncidentResolutionAction, IncidentStatus, IncidentUserInfo,
-        IncidentWithUser, ListIncidentsQuery,
+        CreateIncidentCommentRequest, IncidentComment, IncidentRecord, IncidentSort,
+        IncidentStatus, IncidentUserInfo, IncidentWithUser, ListIncidentsQuery,
     },
-    user::User,
+    user::{User, UserRole},
 };
 
+pub const MAX_COMMENT_LENGTH: usize = 2000;
+
+/// Нарушение правил разбора инцидента (ответы 400/409 в админке)
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum IncidentWorkflowError {
+    #[error("Incident cannot move from {from} to {to}")]
+    InvalidTransition {
+        from: IncidentStatus,
+        to: IncidentStatus,
+    },
+    #[error("Incident has no assignee; assign it or pass force=true to close it")]
+    Unassigned,
+    #[error("Closed incident cannot be reassigned")]
+    Closed,
+    #[error("Assignee must be an existing admin")]
+    InvalidAssignee,
+    #[error("Comment must be between 1 and {MAX_COMMENT_LENGTH} characters")]
+    InvalidComment,
+    #[error("Parent comment does not belong to this incident")]
+    InvalidParentComment,
+}
+
+/// Переходы: open → investigating → resolved / dismissed. Открытый инцидент можно
+/// закрыть сразу, расследование - вернуть в очередь, закрытый - только переоткрыть.
+/// Закрыть инцидент без ответственного можно только с `force`.
+pub fn validate_transition(
+    from: IncidentStatus,
+    to: IncidentStatus,
+    assigned: bool,
+    force: bool,
+) -> Result<(), IncidentWorkflowError> {
+    use IncidentStatus::*;
+
+    let allowed = matches!(
+        (from, to),
+        (Open, Investigating | Resolved | Dismissed)
+            | (Investigating, Open | Resolved | Dismissed)
+            | (Resolved | Dismissed, Open)
+    );
+    if !allowed {
+        return Err(IncidentWorkflowError::InvalidTransition { from, to });
+    }
+    if to.is_closed() && !assigned && !force {
+        return Err(IncidentWorkflowError::Unassigned);
+    }
+    Ok(())
+}
+
 pub struct IncidentsService {
     mongo: Database,
 }
@@ -35,19 +84,32 @@ impl IncidentsService {
         }
 
         if let Some(status) = query.status {
-            filter.insert("status", to_bson(&status)?);
+            filter.insert("status", doc! { "$in": status.stored_values() });
         }
 
         if let Some(user_id) = query.user_id {
             filter.insert("user_id", user_id);
         }
 
+        if let Some(session_id) = query.session_id {
+            filter.insert("session_id", session_id);
+        }
+
+        if let Some(assigned_to) = query.assigned_to {
+            filter.insert("assigned_to", assigned_to);
+        }
+
+        let sort = match query.sort.unwrap_or_default() {
+            IncidentSort::Timestamp => doc! { "timestamp": -1 },
+            IncidentSort::UpdatedAt => doc! { "updated_at": -1, "timestamp": -1 },
+        };
+
         let limit = query.limit.unwrap_or(50).min(100) as i64;
         let skip = query.offset.unwrap_or(0) as u64;
 
         let mut cursor = collection
             .find(filter)
-            .sort(doc! { "timestamp": -1 })
+            .sort(sort)
             .skip(skip)
             .limit(limit)
             .await
@@ -68,50 +130,205 @@ impl IncidentsService {
         self.attach_user_info(incidents).await
     }
 
+    /// Карточка инцидента вместе с обсуждением
     pub async fn get_incident(&self, incident_id: &str) -> Result<IncidentWithUser> {
-        let collection = self.mongo.collection::<IncidentRecord>("incidents");
-        let incident = collection
-            .find_one(doc! { "id": incident_id })
-            .await
-            .context("Failed to fetch incident")?
-            .ok_or_else(|| anyhow!("Incident not found"))?;
+        let incident = self.find_incident(incident_id).await?;
+        let comments = self.list_comments(incident_id).await?;
 
         let mut results = self.attach_user_info(vec![incident]).await?;
-        results
+        let mut result = results
             .pop()
-            .ok_or_else(|| anyhow!("Incident enrichment failed"))
+            .ok_or_else(|| anyhow!("Incident enrichment failed"))?;
+        result.comments = Some(comments);
+        Ok(result)
     }
 
-    pub async fn update_incident_status(
+    /// Перевести инцидент в новый статус; возвращает карточку и прежний статус.
+    /// Взявший инцидент в работу становится ответственным, если его ещё нет.
+    pub async fn transition_incident(
         &self,
         incident_id: &str,
-        action: IncidentResolutionAction,
+        to: IncidentStatus,
         note: Option<String>,
+        force: bool,
         admin_user_id: &str,
-    ) -> Result<IncidentWithUser> {
-        let collection = self.mongo.collection::<IncidentRecord>("incidents");
-        let mut incident = collection
-            .find_one(doc! { "id": incident_id })
-            .await
-            .context("Failed to fetch incident")?
-            .ok_or_else(|| anyhow!("Incident not found"))?;
+    ) -> Result<(IncidentWithUser, IncidentStatus)> {
+        let mut incident = self.find_incident(incident_id).await?;
+        let from = incident.status;
+        validate_transition(from, to, incident.assigned_to.is_some(), force)?;
 
-        incident.status = match action {
-            IncidentResolutionAction::Resolve => IncidentStatus::Resolved,
-            IncidentResolutionAction::FalsePositive => IncidentStatus::FalsePositive,
-        };
-        incident.resolution_note = note;
-        incident.resolved_by = Some(admin_user_id.to_string());
-        incident.resolved_at = Some(Utc::now());
+        let now = Utc::now();
+        incident.status = to;
+        incident.updated_at = Some(now);
+        if to == IncidentStatus::Investigating && incident.assigned_to.is_none() {
+            incident.assigned_to = Some(admin_user_id.to_string());
+        }
+        if to.is_closed() {
+            incident.resolution_note = note;
+            incident.resolved_by = Some(admin_user_id.to_string());
+            incident.resolved_at = Some(now);
+        } else {
+            incident.resolved_by = None;
+            incident.resolved_at = None;
+        }
 
-        collection
+        self.mongo
+            .collection::<IncidentRecord>("incidents")
             .replace_one(doc! { "id": &incident.id }, &incident)
             .await
             .context("Failed to update incident status")?;
 
+        Ok((self.get_incident(incident_id).await?, from))
+    }
+
+    /// Назначить ответственного администратора (`None` снимает назначение)
+    pub async fn assign_incident(
+        &self,
+        incident_id: &str,
+        assignee: Option<String>,
+    ) -> Result<IncidentWithUser> {
+        let incident = self.find_incident(incident_id).await?;
+        if incident.status.is_closed() {
+            return Err(IncidentWorkflowError::Closed.into());
+        }
+        if let Some(assignee) = &assignee {
+            self.ensure_admin(assignee).await?;
+        }
+
+        self.mongo
+            .collection::<IncidentRecord>("incidents")
+            .update_one(
+                doc! { "id": incident_id },
+                doc! { "$set": {
+                    "assigned_to": to_bson(&assignee)?,
+                    "updated_at": to_bson(&Utc::now())?,
+                } },
+            )
+            .await
+            .context("Failed to assign incident")?;
+
         self.get_incident(incident_id).await
     }
 
+    /// Отметить, что администратор снял блокировку пользователя по инциденту
+    pub async fn mark_unblocked(&self, incident_id: &str, admin_user_id: &str) -> Result<()> {
+        let now = to_bson(&Utc::now())?;
+        self.mongo
+            .collection::<IncidentRecord>("incidents")
+            .update_one(
+                doc! { "id": incident_id },
+                doc! { "$set": {
+                    "unblocked_by": admin_user_id,
+                    "unblocked_at": &now,
+                    "updated_at": &now,
+                } },
+            )
+            .await
+            .context("Failed to mark incident as unblocked")?;
+        Ok(())
+    }
+
+    /// Добавить комментарий; `parent_id` должен указывать на комментарий того же инцидента
+    pub async fn add_comment(
+        &self,
+        incident_id: &str,
+        request: CreateIncidentCommentRequest,
+        author_id: &str,
+    ) -> Result<IncidentComment> {
+        let body = request.body.trim();
+        if body.is_empty() || body.chars().count() > MAX_COMMENT_LENGTH {
+            return Err(IncidentWorkflowError::InvalidComment.into());
+        }
+        self.find_incident(incident_id).await?;
+
+        let comments = self
+            .mongo
+            .collection::<IncidentComment>("incident_comments");
+        if let Some(parent_id) = &request.parent_id {
+            let parent = comments
+                .find_one(doc! { "id": parent_id, "incident_id": incident_id })
+                .await
+                .context("Failed to fetch parent comment")?;
+            if parent.is_none() {
+                return Err(IncidentWorkflowError::InvalidParentComment.into());
+            }
+        }
+
+        let comment = IncidentComment {
+            id: Uuid::new_v4().to_string(),
+            incident_id: incident_id.to_string(),
+            author_id: author_id.to_string(),
+            body: body.to_string(),
+            parent_id: request.parent_id,
+            created_at: Utc::now(),
+        };
+        comments
+            .insert_one(&comment)
+            .await
+            .context("Failed to save incident comment")?;
+
+        self.mongo
+            .collection::<IncidentRecord>("incidents")
+            .update_one(
+                doc! { "id": incident_id },
+                doc! { "$set": { "updated_at": to_bson(&comment.created_at)? } },
+            )
+            .await
+            .context("Failed to touch incident")?;
+
+        Ok(comment)
+    }
+
+    /// Комментарии инцидента в порядке добавления
+    pub async fn list_comments(&self, incident_id: &str) -> Result<Vec<IncidentComment>> {
+        let mut cursor = self
+            .mongo
+            .collection::<IncidentComment>("incident_comments")
+            .find(doc! { "incident_id": incident_id })
+            .sort(doc! { "created_at": 1 })
+            .await
+            .context("Failed to query incident comments")?;
+
+        let mut comments = Vec::new();
+        while cursor
+            .advance()
+            .await
+            .context("Failed to advance incident comments cursor")?
+        {
+            comments.push(
+                cursor
+                    .deserialize_current()
+                    .context("Failed to deserialize incident comment")?,
+            );
+        }
+        Ok(comments)
+    }
+
+    async fn find_incident(&self, incident_id: &str) -> Result<IncidentRecord> {
+        self.mongo
+            .collection::<IncidentRecord>("incidents")
+            .find_one(doc! { "id": incident_id })
+            .await
+            .context("Failed to fetch incident")?
+            .ok_or_else(|| anyhow!("Incident not found"))
+    }
+
+    async fn ensure_admin(&self, user_id: &str) -> Result<()> {
+        let Ok(object_id) = ObjectId::parse_str(user_id) else {
+            return Err(IncidentWorkflowError::InvalidAssignee.into());
+        };
+        let user = self
+            .mongo
+            .collection::<User>("users")
+            .find_one(doc! { "_id": object_id })
+            .await
+            .context("Failed to fetch assignee")?;
+        match user {
+            Some(user) if user.role == UserRole::Admin => Ok(()),
+            _ => Err(IncidentWorkflowError::InvalidAssignee.into()),
+        }
+    }
+
     async fn attach_user_info(
         &self,
         incidents: Vec<IncidentRecord>,
@@ -126,6 +343,7 @@ impl IncidentsService {
                 IncidentWithUser {
                     incident,
                     user: user_info,
+                    comments: None,
                 }
             })
             .collect())
@@ -176,3 +394,65 @@ impl IncidentsService {
         Ok(map)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transition_matrix() {
+        use IncidentStatus::*;
+
+        let allowed = [
+            (Open, Investigating),
+            (Open, Resolved),
+            (Open, Dismissed),
+            (Investigating, Open),
+            (Investigating, Resolved),
+            (Investigating, Dismissed),
+            (Resolved, Open),
+            (Dismissed, Open),
+        ];
+        for from in IncidentStatus::ALL {
+            for to in IncidentStatus::ALL {
+                let result = validate_transition(from, to, true, false);
+                if allowed.contains(&(from, to)) {
+                    assert_eq!(result, Ok(()), "{} -> {}", from, to);
+                } else {
+                    assert_eq!(
+                        result,
+                        Err(IncidentWorkflowError::InvalidTransition { from, to }),
+                        "{} -> {}",
+                        from,
+                        to
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn closing_unassigned_incident_requires_force() {
+        use IncidentStatus::*;
+
+        for to in [Resolved, Dismissed] {
+            assert_eq!(
+                validate_transition(Open, to, false, false),
+                Err(IncidentWorkflowError::Unassigned)
+            );
+            assert_eq!(validate_transition(Open, to, false, true), Ok(()));
+        }
+        assert_eq!(
+            validate_transition(Open, Investigating, false, false),
+            Ok(())
+        );
+        assert_eq!(validate_transition(Resolved, Open, false, false), Ok(()));
+    }
+
+    #[test]
+    fn legacy_false_positive_reads_as_dismissed() {
+        let status: IncidentStatus = serde_json::from_str("\"false_positive\"").unwrap();
+        assert_eq!(status, IncidentStatus::Dismissed);
+        assert_eq!(serde_json::to_string(&status).unwrap(), "\"dismissed\"");
+    }
+}
@@ -0,0 +1,506 @@
+//! Индексы MongoDB, на которые опираются запросы API, объявлены здесь в одном месте.
+//!
+//! При старте `ensure_indexes` создаёт недостающие (повторный запуск ничего не меняет)
+//! и пишет в лог индексы, которые есть в базе, но не объявлены (дрейф). Ошибка создания
+//! одного индекса не мешает остальным и не роняет API - она попадает в отчёт
+//! `GET /admin/system/indexes`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, Bson, Document},
+    error::{Error as MongoError, ErrorKind},
+    options::IndexOptions,
+    Database, IndexModel,
+};
+
+use crate::models::db_index::{DeclaredIndexStatus, IndexReport, IndexState, UndeclaredIndex};
+use crate::services::{
+    assignment_service::{ASSIGNMENTS_COLLECTION, ASSIGNMENT_COMPLETIONS_COLLECTION},
+    audit_service::AUDIT_LOG_COLLECTION,
+    consent_service::GUARDIAN_CONSENT_LINKS_COLLECTION,
+    content_blacklist::CONTENT_BLACKLIST_COLLECTION,
+    content_outbox::{CONTENT_OUTBOX_COLLECTION, CONTENT_OUTBOX_RETENTION_SECS},
+    content_search_service,
+    content_service::PUBLISHED_VERSIONS_COLLECTION,
+    email_outbox_service::EMAIL_OUTBOX_COLLECTION,
+    login_history_service::{LOGIN_HISTORY_COLLECTION, LOGIN_HISTORY_RETENTION_SECS},
+    notification_center_service::NOTIFICATIONS_COLLECTION,
+    review_service::REVIEW_QUEUE_COLLECTION,
+};
+
+const DUPLICATE_KEY: i32 = 11000;
+const NAMESPACE_NOT_FOUND: i32 = 26;
+
+/// Объявленный индекс коллекции
+#[derive(Debug, Clone)]
+pub struct IndexSpec {
+    pub collection: &'static str,
+    pub model: IndexModel,
+}
+
+impl IndexSpec {
+    fn new(collection: &'static str, keys: Document) -> Self {
+        Self {
+            collection,
+            model: IndexModel::builder().keys(keys).build(),
+        }
+    }
+
+    fn with_options(collection: &'static str, keys: Document, options: IndexOptions) -> Self {
+        Self {
+            collection,
+            model: IndexModel::builder().keys(keys).options(options).build(),
+        }
+    }
+
+    fn unique(collection: &'static str, keys: Document) -> Self {
+        Self::with_options(
+            collection,
+            keys,
+            IndexOptions::builder().unique(true).build(),
+        )
+    }
+
+    /// Имя индекса: заданное в опциях или то, которое MongoDB даст по умолчанию
+    pub fn name(&self) -> String {
+        self.model
+            .options
+            .as_ref()
+            .and_then(|options| options.name.clone())
+            .unwrap_or_else(|| default_index_name(&self.model.keys))
+    }
+
+    fn is_unique(&self) -> bool {
+        is_unique(&self.model)
+    }
+
+    fn ttl(&self) -> Option<Duration> {
+        ttl(&self.model)
+    }
+}
+
+/// Все индексы, которые API ожидает увидеть в базе
+pub fn declared_indexes() -> Vec<IndexSpec> {
+    let mut indexes = vec![
+        IndexSpec::unique("users", doc! { "email": 1 }),
+        IndexSpec::new("users", doc! { "role": 1 }),
+        IndexSpec::unique("topics", doc! { "slug": 1 }),
+        IndexSpec::unique("rules", doc! { "slug": 1 }),
+        // Slug шаблона уникален в пределах уровня и языка (`ContentService::ensure_unique_slug`)
+        IndexSpec::unique("templates", doc! { "slug": 1, "level_id": 1, "locale": 1 }),
+        IndexSpec::new("templates", doc! { "rule_ids": 1 }),
+        // Одна запись на опубликованную версию шаблона
+        IndexSpec::unique(
+            PUBLISHED_VERSIONS_COLLECTION,
+            doc! { "template_id": 1, "version": 1 },
+        ),
+        IndexSpec::unique(CONTENT_BLACKLIST_COLLECTION, doc! { "term": 1, "kind": 1 }),
+        IndexSpec::unique("progress_summary", doc! { "user_id": 1, "level_id": 1 }),
+        IndexSpec::new(AUDIT_LOG_COLLECTION, doc! { "createdAt": -1 }),
+    ];
+
+    // Фильтры журнала по полям-синонимам превращаются в `$or`, поэтому у каждого
+    // синонима свой составной индекс с `_id` - он же обслуживает сортировку и диапазон дат
+    indexes.extend(
+        [
+            doc! { "actor_id": 1, "_id": -1 },
+            doc! { "user_id": 1, "_id": -1 },
+            doc! { "admin_id": 1, "_id": -1 },
+            doc! { "action": 1, "_id": -1 },
+            doc! { "event_type": 1, "_id": -1 },
+            doc! { "target": 1, "target_id": 1, "_id": -1 },
+            doc! { "entity_type": 1, "entity_id": 1, "_id": -1 },
+        ]
+        .into_iter()
+        .map(|keys| IndexSpec::new(AUDIT_LOG_COLLECTION, keys)),
+    );
+
+    indexes.extend([
+        // Очередь повторений: одна запись на пару (ученик, шаблон) и выборка по сроку
+        IndexSpec::unique(
+            REVIEW_QUEUE_COLLECTION,
+            doc! { "user_id": 1, "template_id": 1 },
+        ),
+        IndexSpec::new(REVIEW_QUEUE_COLLECTION, doc! { "user_id": 1, "due_at": 1 }),
+        // Очередь писем: выборка воркером и письма одной рассылки
+        IndexSpec::new(
+            EMAIL_OUTBOX_COLLECTION,
+            doc! { "status": 1, "nextAttemptAt": 1, "createdAt": 1 },
+        ),
+        IndexSpec::new(EMAIL_OUTBOX_COLLECTION, doc! { "notification_id": 1 }),
+        // Ссылка родителя ищется по хешу токена
+        IndexSpec::unique(GUARDIAN_CONSENT_LINKS_COLLECTION, doc! { "tokenHash": 1 }),
+        // Outbox изменений шаблонов: выборка диспетчера и удаление отправленных записей
+        IndexSpec::new(
+            CONTENT_OUTBOX_COLLECTION,
+            doc! { "status": 1, "createdAt": 1 },
+        ),
+        IndexSpec::with_options(
+            CONTENT_OUTBOX_COLLECTION,
+            doc! { "dispatchedAt": 1 },
+            IndexOptions::builder()
+                .expire_after(Duration::from_secs(CONTENT_OUTBOX_RETENTION_SECS))
+                .build(),
+        ),
+        // Уведомления: лента получателя и уникальность `dedupKey`
+        IndexSpec::new(
+            NOTIFICATIONS_COLLECTION,
+            doc! { "recipient_id": 1, "createdAt": -1 },
+        ),
+        IndexSpec::new(
+            NOTIFICATIONS_COLLECTION,
+            doc! { "recipient_id": 1, "read": 1 },
+        ),
+        IndexSpec::with_options(
+            NOTIFICATIONS_COLLECTION,
+            doc! { "recipient_id": 1, "dedupKey": 1 },
+            IndexOptions::builder()
+                .unique(true)
+                .partial_filter_expression(doc! { "dedupKey": { "$exists": true } })
+                .build(),
+        ),
+        // История входов: лента пользователя, старые записи удаляются по `at`
+        IndexSpec::new(
+            LOGIN_HISTORY_COLLECTION,
+            doc! { "userId": 1, "at": -1, "_id": -1 },
+        ),
+        IndexSpec::with_options(
+            LOGIN_HISTORY_COLLECTION,
+            doc! { "at": 1 },
+            IndexOptions::builder()
+                .expire_after(Duration::from_secs(LOGIN_HISTORY_RETENTION_SECS))
+                .build(),
+        ),
+        // Пересчёт сводки прогресса читает ответы и архивные сессии батчами учеников
+        IndexSpec::new("attempt_records", doc! { "user_id": 1 }),
+        IndexSpec::new("session_archive_stubs", doc! { "user_id": 1 }),
+        // Восстановленные из архива сессии удаляются по `expires_at`
+        IndexSpec::with_options(
+            "sessions_rehydrated",
+            doc! { "expires_at": 1 },
+            IndexOptions::builder()
+                .expire_after(Duration::from_secs(0))
+                .build(),
+        ),
+        // Домашние задания: список группы по сроку и выполнения ученика
+        IndexSpec::new(ASSIGNMENTS_COLLECTION, doc! { "groupId": 1, "dueAt": 1 }),
+        IndexSpec::new(
+            ASSIGNMENT_COMPLETIONS_COLLECTION,
+            doc! { "assignmentId": 1, "userId": 1 },
+        ),
+    ]);
+
+    indexes.extend(
+        content_search_service::text_indexes()
+            .into_iter()
+            .map(|(collection, model)| IndexSpec { collection, model }),
+    );
+    indexes
+}
+
+/// Создать недостающие индексы и сообщить о дрейфе (вызывается при старте)
+pub async fn ensure_indexes(mongo: &Database) -> IndexReport {
+    let report = reconcile(mongo, &declared_indexes(), true).await;
+    for index in &report.indexes {
+        match index.state {
+            IndexState::Created => {
+                tracing::info!("Created index {}.{}", index.collection, index.name)
+            }
+            IndexState::Conflict | IndexState::Failed | IndexState::Missing => tracing::error!(
+                "Index {}.{} is not usable: {}",
+                index.collection,
+                index.name,
+                index.error.as_deref().unwrap_or("unknown error")
+            ),
+            IndexState::Present => {}
+        }
+    }
+    for index in &report.drift {
+        tracing::warn!(
+            "Index {}.{} {} exists in MongoDB but is not declared in index_registry",
+            index.collection,
+            index.name,
+            index.keys
+        );
+    }
+    report
+}
+
+/// Текущее состояние индексов без изменений в базе
+pub async fn inspect_indexes(mongo: &Database) -> IndexReport {
+    reconcile(mongo, &declared_indexes(), false).await
+}
+
+async fn reconcile(mongo: &Database, declared: &[IndexSpec], create: bool) -> IndexReport {
+    let mut by_collection: BTreeMap<&str, Vec<&IndexSpec>> = BTreeMap::new();
+    for spec in declared {
+        by_collection.entry(spec.collection).or_default().push(spec);
+    }
+
+    let mut indexes = Vec::with_capacity(declared.len());
+    let mut drift = Vec::new();
+    for (collection, specs) in by_collection {
+        let existing = match list_indexes(mongo, collection).await {
+            Ok(existing) => existing,
+            Err(err) => {
+                let error = format!("{:#}", err);
+                indexes.extend(
+                    specs
+                        .into_iter()
+                        .map(|spec| status(spec, IndexState::Failed, Some(error.clone()))),
+                );
+                continue;
+            }
+        };
+
+        let mut matched = vec![false; existing.len()];
+        for spec in specs {
+            let found = existing.iter().position(|index| same_index(spec, index));
+            let (state, error) = match found {
+                Some(position) => {
+                    matched[position] = true;
+                    match options_mismatch(spec, &existing[position]) {
+                        Some(reason) => (IndexState::Conflict, Some(reason)),
+                        None => (IndexState::Present, None),
+                    }
+                }
+                None if create => match create_index(mongo, spec).await {
+                    Ok(()) => (IndexState::Created, None),
+                    Err(err) => (IndexState::Failed, Some(err)),
+                },
+                None => (IndexState::Missing, None),
+            };
+            indexes.push(status(spec, state, error));
+        }
+
+        drift.extend(
+            existing
+                .into_iter()
+                .zip(matched)
+                .filter(|(index, matched)| !matched && index_name(index) != "_id_")
+                .map(|(index, _)| UndeclaredIndex {
+                    collection: collection.to_string(),
+                    name: index_name(&index),
+                    keys: index.keys,
+                }),
+        );
+    }
+
+    IndexReport {
+        checked_at: Utc::now(),
+        indexes,
+        drift,
+    }
+}
+
+async fn list_indexes(mongo: &Database, collection: &str) -> Result<Vec<IndexModel>> {
+    let cursor = match mongo
+        .collection::<Document>(collection)
+        .list_indexes()
+        .await
+    {
+        Ok(cursor) => cursor,
+        Err(err) if command_code(&err) == Some(NAMESPACE_NOT_FOUND) => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(err).with_context(|| format!("Failed to list indexes of {}", collection))
+        }
+    };
+    cursor
+        .try_collect()
+        .await
+        .with_context(|| format!("Failed to list indexes of {}", collection))
+}
+
+/// Создать индекс; текст ошибки - для оператора
+async fn create_index(mongo: &Database, spec: &IndexSpec) -> std::result::Result<(), String> {
+    let result = mongo
+        .collection::<Document>(spec.collection)
+        .create_index(spec.model.clone())
+        .await;
+    match result {
+        Ok(_) => Ok(()),
+        Err(err) if command_code(&err) == Some(DUPLICATE_KEY) => Err(format!(
+            "Collection {} has documents with duplicate {} values, so the unique index cannot \
+             be built. Remove or merge the duplicates and restart the API. MongoDB: {}",
+            spec.collection,
+            key_fields(&spec.model.keys),
+            err
+        )),
+        Err(err) => Err(format!("Failed to create index: {}", err)),
+    }
+}
+
+fn command_code(err: &MongoError) -> Option<i32> {
+    match err.kind.as_ref() {
+        ErrorKind::Command(command_error) => Some(command_error.code),
+        _ => None,
+    }
+}
+
+/// Индекс в базе совпадает с объявленным по имени или по набору полей
+fn same_index(spec: &IndexSpec, index: &IndexModel) -> bool {
+    index_name(index) == spec.name()
+        || key_signature(&index.keys) == key_signature(&spec.model.keys)
+}
+
+fn options_mismatch(spec: &IndexSpec, index: &IndexModel) -> Option<String> {
+    if spec.is_unique() != is_unique(index) {
+        return Some(format!(
+            "index {} exists with unique={}, expected unique={}; drop it to let the API recreate it",
+            index_name(index),
+            is_unique(index),
+            spec.is_unique()
+        ));
+    }
+    if spec.ttl() != ttl(index) {
+        return Some(format!(
+            "index {} exists with TTL {:?}, expected {:?}; drop it to let the API recreate it",
+            index_name(index),
+            ttl(index),
+            spec.ttl()
+        ));
+    }
+    None
+}
+
+fn status(spec: &IndexSpec, state: IndexState, error: Option<String>) -> DeclaredIndexStatus {
+    DeclaredIndexStatus {
+        collection: spec.collection.to_string(),
+        name: spec.name(),
+        keys: spec.model.keys.clone(),
+        unique: spec.is_unique(),
+        ttl_seconds: spec.ttl().map(|ttl| ttl.as_secs()),
+        state,
+        error,
+    }
+}
+
+fn index_name(index: &IndexModel) -> String {
+    index
+        .options
+        .as_ref()
+        .and_then(|options| options.name.clone())
+        .unwrap_or_else(|| default_index_name(&index.keys))
+}
+
+fn is_unique(index: &IndexModel) -> bool {
+    index
+        .options
+        .as_ref()
+        .and_then(|options| options.unique)
+        .unwrap_or(false)
+}
+
+fn ttl(index: &IndexModel) -> Option<Duration> {
+    index
+        .options
+        .as_ref()
+        .and_then(|options| options.expire_after)
+}
+
+/// Имя по правилам MongoDB: `поле_направление`, соединённые через `_`
+fn default_index_name(keys: &Document) -> String {
+    keys.iter()
+        .map(|(field, value)| format!("{}_{}", field, key_value(value)))
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// Поля и направления без учёта числового типа (`1`, `1.0` и `1i64` - одно и то же)
+fn key_signature(keys: &Document) -> Vec<(String, String)> {
+    keys.iter()
+        .map(|(field, value)| (field.clone(), key_value(value)))
+        .collect()
+}
+
+fn key_value(value: &Bson) -> String {
+    match value {
+        Bson::Int32(value) => value.to_string(),
+        Bson::Int64(value) => value.to_string(),
+        Bson::Double(value) => (*value as i64).to_string(),
+        Bson::String(value) => value.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn key_fields(keys: &Document) -> String {
+    keys.keys().cloned().collect::<Vec<_>>().join(" + ")
+}
+
+/// Подставить в свежий отчёт ошибки создания из стартового: если индекс так и не
+/// появился, оператору нужна причина, а не только статус `missing`
+pub fn with_startup_errors(mut report: IndexReport, startup: &IndexReport) -> IndexReport {
+    let errors: HashMap<(&str, &str), &str> = startup
+        .indexes
+        .iter()
+        .filter_map(|index| {
+            index
+                .error
+                .as_deref()
+                .map(|error| ((index.collection.as_str(), index.name.as_str()), error))
+        })
+        .collect();
+    for index in &mut report.indexes {
+        if index.error.is_none() {
+            if let Some(error) = errors.get(&(index.collection.as_str(), index.name.as_str())) {
+                index.error = Some(error.to_string());
+            }
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_name_matches_mongo_convention() {
+        assert_eq!(
+            default_index_name(&doc! { "slug": 1, "level_id": 1 }),
+            "slug_1_level_id_1"
+        );
+        assert_eq!(
+            default_index_name(&doc! { "createdAt": -1 }),
+            "createdAt_-1"
+        );
+    }
+
+    #[test]
+    fn key_signature_ignores_numeric_type() {
+        assert_eq!(
+            key_signature(&doc! { "email": 1.0 }),
+            key_signature(&doc! { "email": 1 })
+        );
+        assert_ne!(
+            key_signature(&doc! { "email": -1 }),
+            key_signature(&doc! { "email": 1 })
+        );
+    }
+
+    #[test]
+    fn declared_indexes_have_unique_names_per_collection() {
+        let mut seen = std::collections::HashSet::new();
+        for spec in declared_indexes() {
+            assert!(
+                seen.insert((spec.collection, spec.name())),
+                "duplicate index {}.{}",
+                spec.collection,
+                spec.name()
+            );
+        }
+    }
+
+    #[test]
+    fn unique_option_mismatch_is_a_conflict() {
+        let spec = IndexSpec::unique("users", doc! { "email": 1 });
+        let existing = IndexModel::builder().keys(doc! { "email": 1 }).build();
+        assert!(same_index(&spec, &existing));
+        assert!(options_mismatch(&spec, &existing).is_some());
+    }
+}
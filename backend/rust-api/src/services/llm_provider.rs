@@ -0,0 +1,500 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use mongodb::Database;
+use rand::Rng;
+use reqwest::{header::RETRY_AFTER, RequestBuilder, StatusCode};
+use serde_json::{json, Value};
+
+use crate::config::{LlmConfig, LlmProviderKind};
+use crate::metrics::{LLM_REQUESTS_TOTAL, LLM_TOKENS_TOTAL};
+use crate::models::system_settings::{OpenAiSettings, YandexGptSettings};
+use crate::services::system_settings_service::SystemSettingsService;
+
+/// Параметры генерации; незаданные берутся из настроек провайдера
+#[derive(Debug, Clone, Default)]
+pub struct LlmParams {
+    pub system_prompt: Option<String>,
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LlmResponse {
+    pub text: String,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+/// Провайдер ответил 429; `retry_after` - из заголовка `Retry-After`, если он был
+#[derive(Debug, thiserror::Error)]
+#[error("LLM provider rate limited the request")]
+pub struct LlmRateLimited {
+    pub retry_after: Option<Duration>,
+}
+
+/// Генерация текста языковой моделью. Подсказки и проверка настроек работают
+/// только через этот трейт и не знают, какой провайдер за ним стоит
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    /// Имя провайдера для метрик и логов
+    fn name(&self) -> &str;
+
+    async fn generate(&self, prompt: &str, params: &LlmParams) -> Result<LlmResponse>;
+}
+
+/// YandexGPT Foundation Models API (`completion`)
+pub struct YandexGptProvider {
+    client: reqwest::Client,
+    url: String,
+    settings: YandexGptSettings,
+}
+
+impl YandexGptProvider {
+    pub fn new(settings: YandexGptSettings, config: &LlmConfig) -> Result<Self> {
+        Ok(Self {
+            client: http_client(config)?,
+            url: config.yandexgpt_url.clone(),
+            settings,
+        })
+    }
+
+    fn model_uri(&self) -> String {
+        if self.settings.model.starts_with("gpt://") {
+            self.settings.model.clone()
+        } else {
+            format!(
+                "gpt://{}/{}/latest",
+                self.settings.folder_id, self.settings.model
+            )
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for YandexGptProvider {
+    fn name(&self) -> &str {
+        LlmProviderKind::YandexGpt.as_str()
+    }
+
+    async fn generate(&self, prompt: &str, params: &LlmParams) -> Result<LlmResponse> {
+        let mut messages = Vec::new();
+        if let Some(system) = &params.system_prompt {
+            messages.push(json!({ "role": "system", "text": system }));
+        }
+        messages.push(json!({ "role": "user", "text": prompt }));
+
+        let body = json!({
+            "modelUri": self.model_uri(),
+            "completionOptions": {
+                "stream": false,
+                "temperature": params.temperature.unwrap_or(self.settings.temperature),
+                // API принимает maxTokens строкой
+                "maxTokens": params.max_tokens.unwrap_or(self.settings.max_tokens).to_string(),
+            },
+            "messages": messages,
+        });
+
+        let request = self
+            .client
+            .post(&self.url)
+            .header(
+                "Authorization",
+                format!("Api-Key {}", self.settings.api_key),
+            )
+            .header("x-folder-id", &self.settings.folder_id)
+            .json(&body);
+        parse_yandexgpt_response(&send_json(request, self.name()).await?)
+    }
+}
+
+/// Сервер с OpenAI-совместимым `POST {base_url}/chat/completions`
+pub struct OpenAiCompatibleProvider {
+    client: reqwest::Client,
+    settings: OpenAiSettings,
+}
+
+impl OpenAiCompatibleProvider {
+    pub fn new(settings: OpenAiSettings, config: &LlmConfig) -> Result<Self> {
+        Ok(Self {
+            client: http_client(config)?,
+            settings,
+        })
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiCompatibleProvider {
+    fn name(&self) -> &str {
+        LlmProviderKind::OpenAi.as_str()
+    }
+
+    async fn generate(&self, prompt: &str, params: &LlmParams) -> Result<LlmResponse> {
+        let mut messages = Vec::new();
+        if let Some(system) = &params.system_prompt {
+            messages.push(json!({ "role": "system", "content": system }));
+        }
+        messages.push(json!({ "role": "user", "content": prompt }));
+
+        let body = json!({
+            "model": self.settings.model,
+            "messages": messages,
+            "temperature": params.temperature.unwrap_or(self.settings.temperature),
+            "max_tokens": params.max_tokens.unwrap_or(self.settings.max_tokens),
+        });
+
+        let url = format!(
+            "{}/chat/completions",
+            self.settings.base_url.trim_end_matches('/')
+        );
+        let mut request = self.client.post(url).json(&body);
+        if !self.settings.api_key.is_empty() {
+            request = request.bearer_auth(&self.settings.api_key);
+        }
+        parse_openai_response(&send_json(request, self.name()).await?)
+    }
+}
+
+/// Повтор запросов, отклонённых с 429, и учёт запросов и токенов в метриках
+pub struct RetryingProvider {
+    inner: Arc<dyn LlmProvider>,
+    max_retries: u32,
+    base_delay: Duration,
+}
+
+impl RetryingProvider {
+    pub fn new(inner: Arc<dyn LlmProvider>, max_retries: u32, base_delay: Duration) -> Self {
+        Self {
+            inner,
+            max_retries,
+            base_delay,
+        }
+    }
+
+    pub fn from_config(inner: Arc<dyn LlmProvider>, config: &LlmConfig) -> Self {
+        Self::new(inner, config.max_retries, config.retry_base_delay())
+    }
+}
+
+#[async_trait]
+impl LlmProvider for RetryingProvider {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn generate(&self, prompt: &str, params: &LlmParams) -> Result<LlmResponse> {
+        let provider = self.inner.name().to_string();
+        let mut attempt = 0;
+        loop {
+            match self.inner.generate(prompt, params).await {
+                Ok(response) => {
+                    record_request(&provider, "success");
+                    LLM_TOKENS_TOTAL
+                        .with_label_values(&[provider.as_str(), "prompt"])
+                        .inc_by(response.prompt_tokens);
+                    LLM_TOKENS_TOTAL
+                        .with_label_values(&[provider.as_str(), "completion"])
+                        .inc_by(response.completion_tokens);
+                    return Ok(response);
+                }
+                Err(err) => {
+                    let Some(limited) = err.downcast_ref::<LlmRateLimited>() else {
+                        record_request(&provider, "error");
+                        return Err(err);
+                    };
+                    record_request(&provider, "rate_limited");
+                    if attempt >= self.max_retries {
+                        return Err(err);
+                    }
+
+                    let delay = retry_delay(self.base_delay, attempt, limited.retry_after);
+                    tracing::warn!(
+                        provider = provider.as_str(),
+                        attempt = attempt + 1,
+                        delay_ms = delay.as_millis() as u64,
+                        "LLM provider rate limited, retrying"
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Провайдер, выбранный `config.llm.provider`, с настройками из `/admin/settings`.
+/// Настройки читаются при каждом запросе, поэтому изменения применяются без рестарта
+pub struct ConfiguredLlmProvider {
+    settings: SystemSettingsService,
+    config: LlmConfig,
+}
+
+impl ConfiguredLlmProvider {
+    pub fn new(mongo: Database, config: LlmConfig) -> Self {
+        Self {
+            settings: SystemSettingsService::new(mongo),
+            config,
+        }
+    }
+
+    async fn resolve(&self) -> Result<RetryingProvider> {
+        let inner: Arc<dyn LlmProvider> = match self.config.provider {
+            LlmProviderKind::YandexGpt => {
+                let settings = self
+                    .settings
+                    .get_yandexgpt_settings()
+                    .await?
+                    .context("YandexGPT settings are not configured")?;
+                Arc::new(YandexGptProvider::new(settings, &self.config)?)
+            }
+            LlmProviderKind::OpenAi => {
+                let settings = self
+                    .settings
+                    .get_openai_settings()
+                    .await?
+                    .context("OpenAI settings are not configured")?;
+                Arc::new(OpenAiCompatibleProvider::new(settings, &self.config)?)
+            }
+        };
+        Ok(RetryingProvider::from_config(inner, &self.config))
+    }
+}
+
+#[async_trait]
+impl LlmProvider for ConfiguredLlmProvider {
+    fn name(&self) -> &str {
+        self.config.provider.as_str()
+    }
+
+    async fn generate(&self, prompt: &str, params: &LlmParams) -> Result<LlmResponse> {
+        self.resolve().await?.generate(prompt, params).await
+    }
+}
+
+fn http_client(config: &LlmConfig) -> Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(config.request_timeout())
+        .build()
+        .context("Failed to build LLM HTTP client")
+}
+
+fn record_request(provider: &str, outcome: &str) {
+    LLM_REQUESTS_TOTAL
+        .with_label_values(&[provider, outcome])
+        .inc();
+}
+
+/// Экспоненциальная задержка (или `Retry-After` сервера) плюс случайная добавка
+/// до `base`, чтобы параллельные запросы не повторялись одновременно
+fn retry_delay(base: Duration, attempt: u32, retry_after: Option<Duration>) -> Duration {
+    let backoff = retry_after.unwrap_or_else(|| base.saturating_mul(1 << attempt.min(10)));
+    let jitter_ms = rand::rng().random_range(0..=base.as_millis() as u64);
+    backoff + Duration::from_millis(jitter_ms)
+}
+
+async fn send_json(request: RequestBuilder, provider: &str) -> Result<Value> {
+    let response = request
+        .send()
+        .await
+        .with_context(|| format!("Failed to call {} API", provider))?;
+
+    let status = response.status();
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        let retry_after = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .map(Duration::from_secs);
+        return Err(LlmRateLimited { retry_after }.into());
+    }
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        let snippet: String = body.chars().take(200).collect();
+        anyhow::bail!("{} API returned status {}: {}", provider, status, snippet);
+    }
+
+    response
+        .json()
+        .await
+        .with_context(|| format!("Invalid {} API response", provider))
+}
+
+/// Счётчики токенов YandexGPT приходят строками (int64 в JSON)
+fn token_count(value: &Value) -> u64 {
+    value
+        .as_u64()
+        .or_else(|| value.as_str().and_then(|text| text.parse().ok()))
+        .unwrap_or(0)
+}
+
+fn parse_yandexgpt_response(body: &Value) -> Result<LlmResponse> {
+    let result = &body["result"];
+    let text = result["alternatives"][0]["message"]["text"]
+        .as_str()
+        .context("YandexGPT response has no alternatives")?;
+    Ok(LlmResponse {
+        text: text.to_string(),
+        prompt_tokens: token_count(&result["usage"]["inputTextTokens"]),
+        completion_tokens: token_count(&result["usage"]["completionTokens"]),
+    })
+}
+
+fn parse_openai_response(body: &Value) -> Result<LlmResponse> {
+    let text = body["choices"][0]["message"]["content"]
+        .as_str()
+        .context("OpenAI response has no choices")?;
+    Ok(LlmResponse {
+        text: text.to_string(),
+        prompt_tokens: token_count(&body["usage"]["prompt_tokens"]),
+        completion_tokens: token_count(&body["usage"]["completion_tokens"]),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Отвечает 429 первые `rate_limited` раз, затем возвращает ответ или ошибку
+    struct MockProvider {
+        name: &'static str,
+        rate_limited: u32,
+        fail: bool,
+        calls: AtomicU32,
+    }
+
+    impl MockProvider {
+        fn new(name: &'static str, rate_limited: u32, fail: bool) -> Arc<Self> {
+            Arc::new(Self {
+                name,
+                rate_limited,
+                fail,
+                calls: AtomicU32::new(0),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl LlmProvider for MockProvider {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        async fn generate(&self, prompt: &str, _params: &LlmParams) -> Result<LlmResponse> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            if call < self.rate_limited {
+                return Err(LlmRateLimited { retry_after: None }.into());
+            }
+            if self.fail {
+                anyhow::bail!("provider is down");
+            }
+            Ok(LlmResponse {
+                text: format!("echo: {}", prompt),
+                prompt_tokens: 12,
+                completion_tokens: 5,
+            })
+        }
+    }
+
+    fn requests(provider: &str, outcome: &str) -> u64 {
+        LLM_REQUESTS_TOTAL
+            .with_label_values(&[provider, outcome])
+            .get()
+    }
+
+    fn tokens(provider: &str, kind: &str) -> u64 {
+        LLM_TOKENS_TOTAL.with_label_values(&[provider, kind]).get()
+    }
+
+    fn retrying(inner: Arc<MockProvider>, max_retries: u32) -> RetryingProvider {
+        RetryingProvider::new(inner, max_retries, Duration::from_millis(1))
+    }
+
+    #[tokio::test]
+    async fn retries_rate_limited_requests_and_counts_tokens() {
+        let mock = MockProvider::new("mock-retry", 2, false);
+        let provider = retrying(mock.clone(), 3);
+
+        let response = provider
+            .generate("привет", &LlmParams::default())
+            .await
+            .unwrap();
+
+        assert_eq!(response.text, "echo: привет");
+        assert_eq!(mock.calls.load(Ordering::SeqCst), 3);
+        assert_eq!(requests("mock-retry", "rate_limited"), 2);
+        assert_eq!(requests("mock-retry", "success"), 1);
+        assert_eq!(tokens("mock-retry", "prompt"), 12);
+        assert_eq!(tokens("mock-retry", "completion"), 5);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_retries() {
+        let mock = MockProvider::new("mock-exhausted", 10, false);
+        let provider = retrying(mock.clone(), 2);
+
+        let err = provider
+            .generate("prompt", &LlmParams::default())
+            .await
+            .unwrap_err();
+
+        assert!(err.downcast_ref::<LlmRateLimited>().is_some());
+        assert_eq!(mock.calls.load(Ordering::SeqCst), 3);
+        assert_eq!(requests("mock-exhausted", "rate_limited"), 3);
+        assert_eq!(tokens("mock-exhausted", "prompt"), 0);
+    }
+
+    #[tokio::test]
+    async fn other_errors_are_not_retried() {
+        let mock = MockProvider::new("mock-error", 0, true);
+        let provider = retrying(mock.clone(), 3);
+
+        assert!(provider
+            .generate("prompt", &LlmParams::default())
+            .await
+            .is_err());
+        assert_eq!(mock.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(requests("mock-error", "error"), 1);
+        assert_eq!(requests("mock-error", "success"), 0);
+    }
+
+    #[test]
+    fn retry_delay_grows_and_honours_retry_after() {
+        let base = Duration::from_millis(100);
+        let first = retry_delay(base, 0, None);
+        assert!(first >= base && first <= base * 2);
+        let third = retry_delay(base, 2, None);
+        assert!(third >= base * 4 && third <= base * 5);
+
+        let server = retry_delay(base, 0, Some(Duration::from_secs(3)));
+        assert!(server >= Duration::from_secs(3));
+    }
+
+    #[test]
+    fn provider_responses_are_parsed_with_usage() {
+        let yandex = json!({
+            "result": {
+                "alternatives": [{ "message": { "role": "assistant", "text": "Ответ" } }],
+                "usage": { "inputTextTokens": "19", "completionTokens": "6", "totalTokens": "25" }
+            }
+        });
+        assert_eq!(
+            parse_yandexgpt_response(&yandex).unwrap(),
+            LlmResponse {
+                text: "Ответ".into(),
+                prompt_tokens: 19,
+                completion_tokens: 6,
+            }
+        );
+
+        let openai = json!({
+            "choices": [{ "message": { "role": "assistant", "content": "Answer" } }],
+            "usage": { "prompt_tokens": 7, "completion_tokens": 3 }
+        });
+        assert_eq!(parse_openai_response(&openai).unwrap().completion_tokens, 3);
+        assert!(parse_openai_response(&json!({ "choices": [] })).is_err());
+    }
+}
@@ -0,0 +1,121 @@
+//! История входов (коллекция `login_history`).
+//!
+//! Каждый успешный вход добавляет запись: время, усечённый IP, User-Agent и способ
+//! входа. Записи старше `LOGIN_HISTORY_RETENTION_SECS` удаляет TTL-индекс по `at`.
+//! Админка читает историю через `GET /admin/users/{id}/logins`.
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{self, doc, oid::ObjectId, Document},
+    Database,
+};
+
+use crate::{
+    models::login_history::{LoginHistoryEntry, LoginHistoryRecord, LoginMethod},
+    utils::{
+        pagination::{Page, PageCursor, SortKey},
+        user_agent::truncate_ip,
+    },
+};
+
+pub const LOGIN_HISTORY_COLLECTION: &str = "login_history";
+pub const LOGIN_HISTORY_RETENTION_SECS: u64 = 180 * 24 * 3600;
+
+/// Длинный User-Agent обрезается, чтобы запись оставалась небольшой
+const MAX_USER_AGENT_LEN: usize = 256;
+const DEFAULT_PAGE_SIZE: u32 = 20;
+const MAX_PAGE_SIZE: u32 = 100;
+
+/// Новые входы первыми
+pub const NEWEST_FIRST: SortKey = SortKey::new("-at", "at", -1);
+
+pub struct LoginHistoryService {
+    mongo: Database,
+}
+
+impl LoginHistoryService {
+    pub fn new(mongo: Database) -> Self {
+        Self { mongo }
+    }
+
+    fn collection(&self) -> mongodb::Collection<LoginHistoryRecord> {
+        self.mongo
+            .collection::<LoginHistoryRecord>(LOGIN_HISTORY_COLLECTION)
+    }
+
+    pub async fn record(
+        &self,
+        user_id: ObjectId,
+        method: LoginMethod,
+        ip: Option<&str>,
+        user_agent: Option<&str>,
+    ) -> Result<()> {
+        let record = LoginHistoryRecord {
+            id: None,
+            user_id,
+            at: Utc::now(),
+            ip: ip.and_then(truncate_ip),
+            user_agent: user_agent.map(cap_user_agent),
+            method,
+        };
+        self.collection()
+            .insert_one(&record)
+            .await
+            .context("Failed to record login")?;
+        Ok(())
+    }
+
+    /// Страница истории входов пользователя
+    pub async fn list(
+        &self,
+        user_id: ObjectId,
+        limit: Option<u32>,
+        cursor: Option<&PageCursor>,
+    ) -> Result<Page<LoginHistoryEntry>> {
+        let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE) as usize;
+        let documents: Vec<Document> = self
+            .mongo
+            .collection::<Document>(LOGIN_HISTORY_COLLECTION)
+            .find(NEWEST_FIRST.apply(doc! { "userId": user_id }, cursor))
+            .sort(NEWEST_FIRST.sort_document())
+            .limit(limit as i64 + 1)
+            .await
+            .context("Failed to query login history")?
+            .try_collect()
+            .await
+            .context("Failed to read login history")?;
+        let (documents, next_cursor) = NEWEST_FIRST.split_page(documents, limit);
+
+        let items = documents
+            .into_iter()
+            .map(|document| {
+                bson::from_document::<LoginHistoryRecord>(document)
+                    .map(LoginHistoryEntry::from)
+                    .context("Failed to deserialize login history entry")
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Page { items, next_cursor })
+    }
+}
+
+fn cap_user_agent(user_agent: &str) -> String {
+    user_agent.chars().take(MAX_USER_AGENT_LEN).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_user_agent_is_capped() {
+        let user_agent = "Mozilla/5.0 ".repeat(100);
+        assert_eq!(
+            cap_user_agent(&user_agent).chars().count(),
+            MAX_USER_AGENT_LEN
+        );
+        assert_eq!(cap_user_agent("curl/8.0"), "curl/8.0");
+    }
+}
@@ -0,0 +1,110 @@
+//! Режим обслуживания на время миграций. Состояние лежит в Redis, поэтому включение
+//! сразу видят все реплики; `middlewares::maintenance` читает его на каждом запросе.
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use redis::{aio::ConnectionManager, AsyncCommands};
+
+use crate::models::maintenance::{MaintenanceMode, MaintenanceModeRequest};
+
+pub const MAINTENANCE_MODE_KEY: &str = "maintenance:mode";
+pub const DEFAULT_MAINTENANCE_MESSAGE: &str =
+    "The service is under maintenance, please try again later";
+pub const DEFAULT_RETRY_AFTER_SECONDS: u64 = 300;
+/// Потолок `Retry-After`: клиенты не должны откладывать повтор на сутки
+pub const MAX_RETRY_AFTER_SECONDS: u64 = 3600;
+const MAX_MESSAGE_CHARS: usize = 500;
+
+/// Некорректный запрос на включение (ответ 400)
+#[derive(Debug, thiserror::Error)]
+pub enum MaintenanceModeError {
+    #[error("Message must be at most 500 characters")]
+    MessageTooLong,
+    #[error("retry_after_seconds must be between 1 and 3600")]
+    InvalidRetryAfter,
+    /// Иначе включивший режим сам потеряет доступ к API и не сможет его выключить
+    #[error("allow_roles must include your own role {0}")]
+    SelfLockout(String),
+}
+
+/// Выключенный режим с настройками по умолчанию
+pub fn disabled() -> MaintenanceMode {
+    MaintenanceMode {
+        enabled: false,
+        message: DEFAULT_MAINTENANCE_MESSAGE.to_string(),
+        allow_roles: vec!["admin".to_string()],
+        retry_after_seconds: DEFAULT_RETRY_AFTER_SECONDS,
+        updated_by: None,
+        updated_at: None,
+    }
+}
+
+/// Текущее состояние; `None`, если режим ни разу не включали или он выключен
+pub async fn load(redis: &ConnectionManager) -> Result<Option<MaintenanceMode>> {
+    let mut conn = redis.clone();
+    let raw: Option<String> = conn
+        .get(MAINTENANCE_MODE_KEY)
+        .await
+        .context("Failed to read maintenance mode")?;
+    let Some(raw) = raw else {
+        return Ok(None);
+    };
+    let mode: MaintenanceMode =
+        serde_json::from_str(&raw).context("Failed to parse maintenance mode")?;
+    Ok(mode.enabled.then_some(mode))
+}
+
+/// Включить или выключить режим. Выключение удаляет ключ
+pub async fn update(
+    redis: &ConnectionManager,
+    request: MaintenanceModeRequest,
+    actor_id: &str,
+    actor_role: &str,
+) -> Result<MaintenanceMode> {
+    let message = request
+        .message
+        .map(|message| message.trim().to_string())
+        .filter(|message| !message.is_empty())
+        .unwrap_or_else(|| DEFAULT_MAINTENANCE_MESSAGE.to_string());
+    if message.chars().count() > MAX_MESSAGE_CHARS {
+        return Err(MaintenanceModeError::MessageTooLong.into());
+    }
+    let retry_after_seconds = request
+        .retry_after_seconds
+        .unwrap_or(DEFAULT_RETRY_AFTER_SECONDS);
+    if !(1..=MAX_RETRY_AFTER_SECONDS).contains(&retry_after_seconds) {
+        return Err(MaintenanceModeError::InvalidRetryAfter.into());
+    }
+    let mut allow_roles = request
+        .allow_roles
+        .unwrap_or_else(|| vec!["admin".to_string()]);
+    allow_roles.sort();
+    allow_roles.dedup();
+    if request.enabled && !allow_roles.iter().any(|role| role == actor_role) {
+        return Err(MaintenanceModeError::SelfLockout(actor_role.to_string()).into());
+    }
+
+    let mode = MaintenanceMode {
+        enabled: request.enabled,
+        message,
+        allow_roles,
+        retry_after_seconds,
+        updated_by: Some(actor_id.to_string()),
+        updated_at: Some(Utc::now()),
+    };
+
+    let mut conn = redis.clone();
+    if mode.enabled {
+        let raw = serde_json::to_string(&mode).context("Failed to serialize maintenance mode")?;
+        let _: () = conn
+            .set(MAINTENANCE_MODE_KEY, raw)
+            .await
+            .context("Failed to store maintenance mode")?;
+    } else {
+        let _: () = conn
+            .del(MAINTENANCE_MODE_KEY)
+            .await
+            .context("Failed to clear maintenance mode")?;
+    }
+    Ok(mode)
+}
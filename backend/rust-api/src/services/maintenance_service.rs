@@ -0,0 +1,532 @@
+//! Служебные задачи админки (коллекция `maintenance_jobs`).
+//!
+//! Пока задача одна - пересчёт `progress_summary_v2` по `attempt_records`. Сводка
+//! обновляется инкрементально на каждом ответе, и после исправления ошибок подсчёта
+//! старые строки расходятся с ответами. Задача считает пары ученик/уровень заново
+//! батчами учеников, перезаписывает разошедшиеся строки и копит отчёт о расхождениях,
+//! который виден в `GET /admin/maintenance/jobs/{id}` уже во время работы.
+//!
+//! Уровень ответа берётся, как при ответе: `level_id` сессии, а без него - id задания.
+//! Ученики, у которых часть сессий в архиве или сессию не удаётся найти ни в MongoDB,
+//! ни в Redis, пропускаются: пересчёт по неполным ответам только испортил бы строки.
+//! Одновременно идёт только один пересчёт (блокировка в Redis).
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, oid::ObjectId, to_bson, Bson, Document},
+    Collection, Database,
+};
+use redis::aio::ConnectionManager;
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        answer::{AttemptFailureReason, AttemptRecord},
+        maintenance::{
+            MaintenanceJob, MaintenanceJobKind, MaintenanceJobStatus, ProgressDriftReport,
+            RecomputeProgressRequest,
+        },
+        ProgressSummary,
+    },
+    services::{
+        answer_service::progress_percentage,
+        archive_worker::{HEARTBEAT_SCRIPT, RELEASE_SCRIPT},
+    },
+};
+
+pub const MAINTENANCE_JOBS_COLLECTION: &str = "maintenance_jobs";
+
+const PROGRESS_COLLECTION: &str = "progress_summary_v2";
+pub const RECOMPUTE_LOCK_KEY: &str = "lock:progress_recompute";
+/// Блокировка продлевается после каждого батча учеников
+const RECOMPUTE_LOCK_TTL_MS: u64 = 10 * 60 * 1000;
+/// Сколько учеников пересчитывается за раз
+const USER_BATCH_SIZE: usize = 200;
+
+pub enum RecomputeOutcome {
+    Started(Box<MaintenanceJob>),
+    /// Другой пересчёт ещё идёт
+    AlreadyRunning,
+    GroupNotFound,
+}
+
+/// Пересчитанные по ответам значения одной строки сводки
+#[derive(Debug, Default)]
+struct RecomputedRow {
+    attempts_total: u32,
+    correct_count: u32,
+    credit: f64,
+    score: i32,
+}
+
+pub struct MaintenanceService {
+    mongo: Database,
+    redis: ConnectionManager,
+}
+
+impl MaintenanceService {
+    pub fn new(mongo: Database, redis: ConnectionManager) -> Self {
+        Self { mongo, redis }
+    }
+
+    fn jobs(&self) -> Collection<MaintenanceJob> {
+        self.mongo
+            .collection::<MaintenanceJob>(MAINTENANCE_JOBS_COLLECTION)
+    }
+
+    pub async fn get_job(&self, id: ObjectId) -> Result<Option<MaintenanceJob>> {
+        self.jobs()
+            .find_one(doc! { "_id": id })
+            .await
+            .context("Failed to load maintenance job")
+    }
+
+    /// Поставить пересчёт в очередь и запустить его в фоне
+    pub async fn start_progress_recompute(
+        &self,
+        scope: RecomputeProgressRequest,
+        created_by: &str,
+    ) -> Result<RecomputeOutcome> {
+        if let Some(group_id) = &scope.group_id {
+            let group_id = ObjectId::parse_str(group_id).context("Invalid group id")?;
+            let exists = self
+                .mongo
+                .collection::<Document>("groups")
+                .count_documents(doc! { "_id": group_id })
+                .await
+                .context("Failed to look up group")?;
+            if exists == 0 {
+                return Ok(RecomputeOutcome::GroupNotFound);
+            }
+        }
+
+        let token = Uuid::new_v4().to_string();
+        if !self.acquire_lock(&token).await? {
+            return Ok(RecomputeOutcome::AlreadyRunning);
+        }
+
+        let job = MaintenanceJob {
+            id: ObjectId::new(),
+            kind: MaintenanceJobKind::RecomputeProgress,
+            status: MaintenanceJobStatus::Pending,
+            scope,
+            created_by: created_by.to_string(),
+            created_at: Utc::now(),
+            finished_at: None,
+            error: None,
+            drift: None,
+        };
+        if let Err(err) = self.jobs().insert_one(&job).await {
+            self.release_lock(&token).await.ok();
+            return Err(err).context("Failed to insert maintenance job");
+        }
+
+        let worker = MaintenanceService::new(self.mongo.clone(), self.redis.clone());
+        let pending = job.clone();
+        tokio::spawn(async move {
+            let job_id = pending.id;
+            if let Err(err) = worker.run_progress_recompute(pending, &token).await {
+                tracing::error!("Progress recompute {} failed: {:#}", job_id, err);
+            }
+        });
+
+        Ok(RecomputeOutcome::Started(Box::new(job)))
+    }
+
+    async fn run_progress_recompute(&self, mut job: MaintenanceJob, token: &str) -> Result<()> {
+        job.status = MaintenanceJobStatus::Running;
+        job.drift = Some(ProgressDriftReport::default());
+        self.save_job(&job).await?;
+
+        let result = self.recompute(&mut job, token).await;
+        match &result {
+            Ok(()) => job.status = MaintenanceJobStatus::Completed,
+            Err(err) => {
+                job.status = MaintenanceJobStatus::Failed;
+                job.error = Some(format!("{:#}", err));
+            }
+        }
+        job.finished_at = Some(Utc::now());
+
+        // Блокировка снимается до финального статуса: увидев `completed`,
+        // можно сразу запускать следующий пересчёт
+        if let Err(err) = self.release_lock(token).await {
+            tracing::warn!("Failed to release progress recompute lock: {:#}", err);
+        }
+        let saved = self.save_job(&job).await;
+        result.and(saved)
+    }
+
+    async fn recompute(&self, job: &mut MaintenanceJob, token: &str) -> Result<()> {
+        let users = self.scope_users(&job.scope).await?;
+        let mut report = ProgressDriftReport::default();
+
+        for batch in users.chunks(USER_BATCH_SIZE) {
+            self.recompute_batch(batch, &mut report).await?;
+            job.drift = Some(report.clone());
+            self.save_job(job).await?;
+            if !self.heartbeat(token).await? {
+                bail!("Progress recompute lock was lost");
+            }
+        }
+
+        tracing::info!(
+            job_id = %job.id,
+            users = report.users_scanned,
+            drifted = report.rows_drifted,
+            "Progress recompute finished"
+        );
+        Ok(())
+    }
+
+    /// Ученики, чьи строки пересчитываются: один ученик, ученики группы или все,
+    /// у кого есть ответы
+    async fn scope_users(&self, scope: &RecomputeProgressRequest) -> Result<Vec<String>> {
+        if let Some(user_id) = &scope.user_id {
+            return Ok(vec![user_id.clone()]);
+        }
+
+        let mut users: Vec<String> = if let Some(group_id) = &scope.group_id {
+            self.mongo
+                .collection::<Document>("users")
+                .find(doc! { "group_ids": group_id, "role": "student" })
+                .projection(doc! { "_id": 1 })
+                .await
+                .context("Failed to query group students")?
+                .try_collect::<Vec<Document>>()
+                .await
+                .context("Failed to read group students")?
+                .iter()
+                .filter_map(|user| user.get_object_id("_id").ok())
+                .map(|id| id.to_hex())
+                .collect()
+        } else {
+            self.mongo
+                .collection::<Document>("attempt_records")
+                .distinct("user_id", doc! {})
+                .await
+                .context("Failed to list users with answers")?
+                .into_iter()
+                .filter_map(|value| match value {
+                    Bson::String(user_id) => Some(user_id),
+                    _ => None,
+                })
+                .collect()
+        };
+        users.sort();
+        users.dedup();
+        Ok(users)
+    }
+
+    async fn recompute_batch(
+        &self,
+        users: &[String],
+        report: &mut ProgressDriftReport,
+    ) -> Result<()> {
+        report.users_scanned += users.len() as u64;
+
+        let archived: HashSet<String> = self
+            .mongo
+            .collection::<Document>("session_archive_stubs")
+            .distinct("user_id", doc! { "user_id": { "$in": users } })
+            .await
+            .context("Failed to check archived sessions")?
+            .into_iter()
+            .filter_map(|value| value.as_str().map(str::to_string))
+            .collect();
+        let users: Vec<&String> = users
+            .iter()
+            .filter(|user| !archived.contains(*user))
+            .collect();
+
+        // Таймауты сводку не меняют - их нет и в пересчёте
+        let attempts: Vec<AttemptRecord> = self
+            .mongo
+            .collection::<AttemptRecord>("attempt_records")
+            .find(doc! {
+                "user_id": { "$in": &users },
+                "reason": { "$ne": to_bson(&AttemptFailureReason::Timeout)? },
+            })
+            .await
+            .context("Failed to query answers")?
+            .try_collect()
+            .await
+            .context("Failed to read answers")?;
+
+        let session_ids: HashSet<&str> = attempts
+            .iter()
+            .map(|attempt| attempt.session_id.as_str())
+            .collect();
+        let levels = self.session_levels(session_ids).await?;
+
+        let mut skipped: HashSet<&str> = archived.iter().map(String::as_str).collect();
+        let mut recomputed: BTreeMap<(String, String), RecomputedRow> = BTreeMap::new();
+        for attempt in &attempts {
+            let Some(level_id) = levels.get(attempt.session_id.as_str()) else {
+                skipped.insert(attempt.user_id.as_str());
+                continue;
+            };
+            let level_key = level_id
+                .as_deref()
+                .filter(|value| !value.is_empty())
+                .unwrap_or(&attempt.task_id)
+                .to_string();
+            let row = recomputed
+                .entry((attempt.user_id.clone(), level_key))
+                .or_default();
+            row.attempts_total += 1;
+            if attempt.correct {
+                row.correct_count += 1;
+            }
+            row.credit += attempt
+                .credit
+                .unwrap_or(if attempt.correct { 1.0 } else { 0.0 });
+            row.score = row.score.saturating_add(attempt.score);
+        }
+        recomputed.retain(|(user_id, _), _| !skipped.contains(user_id.as_str()));
+        report.users_skipped += skipped.len() as u64;
+
+        let checked: Vec<&String> = users
+            .into_iter()
+            .filter(|user| !skipped.contains(user.as_str()))
+            .collect();
+        let progress = self
+            .mongo
+            .collection::<ProgressSummary>(PROGRESS_COLLECTION);
+        let mut existing: HashMap<(String, String), ProgressSummary> = progress
+            .find(doc! { "user_id": { "$in": &checked } })
+            .await
+            .context("Failed to query progress summary")?
+            .try_collect::<Vec<_>>()
+            .await
+            .context("Failed to read progress summary")?
+            .into_iter()
+            .map(|row| ((row.user_id.clone(), row.level_id.clone()), row))
+            .collect();
+
+        for ((user_id, level_id), row) in recomputed {
+            report.rows_checked += 1;
+            let percentage = progress_percentage(row.credit, row.attempts_total);
+            let current = existing.remove(&(user_id.clone(), level_id.clone()));
+            let drifted = match &current {
+                Some(current) => record_drift(report, current, &row, percentage),
+                None => {
+                    report.rows_created += 1;
+                    let empty = ProgressSummary {
+                        id: String::new(),
+                        user_id: user_id.clone(),
+                        level_id: level_id.clone(),
+                        attempts_total: 0,
+                        correct_count: 0,
+                        credit: None,
+                        percentage: 0.0,
+                        score: 0,
+                        updated_at: Utc::now(),
+                    };
+                    record_drift(report, &empty, &row, percentage);
+                    true
+                }
+            };
+            if !drifted {
+                continue;
+            }
+            report.rows_drifted += 1;
+
+            let summary_id = current
+                .map(|current| current.id)
+                .unwrap_or_else(|| format!("{}:{}", user_id, level_id));
+            let corrected = ProgressSummary {
+                id: summary_id.clone(),
+                user_id,
+                level_id,
+                attempts_total: row.attempts_total,
+                correct_count: row.correct_count,
+                credit: Some(row.credit),
+                percentage,
+                score: row.score,
+                updated_at: Utc::now(),
+            };
+            progress
+                .replace_one(doc! { "_id": &summary_id }, &corrected)
+                .upsert(true)
+                .await
+                .context("Failed to write corrected progress summary")?;
+        }
+        report.rows_orphaned += existing.len() as u64;
+
+        Ok(())
+    }
+
+    /// `level_id` сессий: завершённые лежат в MongoDB, идущие - в Redis.
+    /// Ненайденной сессии в ответе нет вовсе
+    async fn session_levels(
+        &self,
+        session_ids: HashSet<&str>,
+    ) -> Result<HashMap<String, Option<String>>> {
+        let mut levels: HashMap<String, Option<String>> = HashMap::new();
+        let ids: Vec<&str> = session_ids.iter().copied().collect();
+        let mut cursor = self
+            .mongo
+            .collection::<Document>("sessions")
+            .find(doc! { "_id": { "$in": &ids } })
+            .projection(doc! { "_id": 1, "level_id": 1 })
+            .await
+            .context("Failed to query sessions")?;
+        while let Some(session) = cursor.try_next().await.context("Failed to read session")? {
+            if let Ok(id) = session.get_str("_id") {
+                let level_id = session.get_str("level_id").ok().map(str::to_string);
+                levels.insert(id.to_string(), level_id);
+            }
+        }
+
+        let mut conn = self.redis.clone();
+        for session_id in ids {
+            if levels.contains_key(session_id) {
+                continue;
+            }
+            let raw: Option<String> = redis::cmd("GET")
+                .arg(format!("session:{}", session_id))
+                .query_async(&mut conn)
+                .await
+                .context("Failed to read session from Redis")?;
+            if let Some(session) =
+                raw.and_then(|raw| serde_json::from_str::<crate::models::Session>(&raw).ok())
+            {
+                levels.insert(session_id.to_string(), session.level_id);
+            }
+        }
+        Ok(levels)
+    }
+
+    async fn save_job(&self, job: &MaintenanceJob) -> Result<()> {
+        self.jobs()
+            .replace_one(doc! { "_id": job.id }, job)
+            .await
+            .context("Failed to update maintenance job")?;
+        Ok(())
+    }
+
+    async fn acquire_lock(&self, token: &str) -> Result<bool> {
+        let mut conn = self.redis.clone();
+        let acquired: Option<String> = redis::cmd("SET")
+            .arg(RECOMPUTE_LOCK_KEY)
+            .arg(token)
+            .arg("NX")
+            .arg("PX")
+            .arg(RECOMPUTE_LOCK_TTL_MS)
+            .query_async(&mut conn)
+            .await
+            .context("Failed to acquire progress recompute lock")?;
+        Ok(acquired.is_some())
+    }
+
+    async fn heartbeat(&self, token: &str) -> Result<bool> {
+        let mut conn = self.redis.clone();
+        let extended: i32 = redis::Script::new(HEARTBEAT_SCRIPT)
+            .key(RECOMPUTE_LOCK_KEY)
+            .arg(token)
+            .arg(RECOMPUTE_LOCK_TTL_MS)
+            .invoke_async(&mut conn)
+            .await
+            .context("Failed to extend progress recompute lock")?;
+        Ok(extended == 1)
+    }
+
+    async fn release_lock(&self, token: &str) -> Result<()> {
+        let mut conn = self.redis.clone();
+        let _: i32 = redis::Script::new(RELEASE_SCRIPT)
+            .key(RECOMPUTE_LOCK_KEY)
+            .arg(token)
+            .invoke_async(&mut conn)
+            .await
+            .context("Failed to release progress recompute lock")?;
+        Ok(())
+    }
+}
+
+/// Учесть расхождения строки в отчёте; `true`, если строку надо перезаписать
+fn record_drift(
+    report: &mut ProgressDriftReport,
+    current: &ProgressSummary,
+    row: &RecomputedRow,
+    percentage: f64,
+) -> bool {
+    let before = report.clone();
+    report.attempts_total.record(
+        f64::from(current.attempts_total),
+        f64::from(row.attempts_total),
+    );
+    report.correct_count.record(
+        f64::from(current.correct_count),
+        f64::from(row.correct_count),
+    );
+    report.percentage.record(current.percentage, percentage);
+    report
+        .score
+        .record(f64::from(current.score), f64::from(row.score));
+    *report != before
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(
+        attempts_total: u32,
+        correct_count: u32,
+        percentage: f64,
+        score: i32,
+    ) -> ProgressSummary {
+        ProgressSummary {
+            id: "user:level".into(),
+            user_id: "user".into(),
+            level_id: "level".into(),
+            attempts_total,
+            correct_count,
+            credit: None,
+            percentage,
+            score,
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn matching_row_is_not_drift() {
+        let mut report = ProgressDriftReport::default();
+        let row = RecomputedRow {
+            attempts_total: 4,
+            correct_count: 3,
+            credit: 3.0,
+            score: 30,
+        };
+        let current = summary(4, 3, progress_percentage(3.0, 4), 30);
+        assert!(!record_drift(&mut report, &current, &row, 75.0));
+        assert_eq!(report, ProgressDriftReport::default());
+    }
+
+    #[test]
+    fn drift_is_counted_per_field() {
+        let mut report = ProgressDriftReport::default();
+        let row = RecomputedRow {
+            attempts_total: 4,
+            correct_count: 3,
+            credit: 3.0,
+            score: 30,
+        };
+        assert!(record_drift(
+            &mut report,
+            &summary(6, 3, 50.0, 45),
+            &row,
+            75.0
+        ));
+        assert_eq!(report.attempts_total.rows, 1);
+        assert_eq!(report.attempts_total.total_abs_delta, 2.0);
+        assert_eq!(report.correct_count.rows, 0);
+        assert_eq!(report.percentage.max_abs_delta, 25.0);
+        assert_eq!(report.score.total_abs_delta, 15.0);
+    }
+}
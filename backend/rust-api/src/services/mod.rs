@@ -1,16 +1,50 @@
 use crate::config::Config;
+use crate::models::db_index::IndexReport;
 use mongodb::{Client as MongoClient, Database};
 use redis::aio::ConnectionManager;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::Instant;
 
+use self::certificate_service::CertificateStorage;
+use self::content_blacklist::BlacklistCache;
 use self::object_storage::ObjectStorageClient;
+use self::permission_service::RolePermissionCache;
+use self::pii_scanner::PiiPatternCache;
+use self::reporting_service::ExportLinkSigner;
+use self::runtime_settings::{RuntimeSettings, RuntimeSettingsHandle};
+use self::session_archive_service::ArchiveStorage;
+use self::system_metrics_service::SystemMetricsService;
+use self::system_settings_service::AnticheatSettingsCache;
 
 pub struct AppState {
     pub config: Config,
     pub mongo: Database,
     pub redis: ConnectionManager,
     pub object_storage: Option<ObjectStorageClient>,
+    /// Хранилище архивов сессий (по умолчанию - то же объектное хранилище)
+    pub archive_storage: Option<Arc<dyn ArchiveStorage>>,
+    /// Подпись ссылок на скачивание отчётов (по умолчанию - объектное хранилище)
+    pub export_links: Option<Arc<dyn ExportLinkSigner>>,
+    /// Кэш PDF сертификатов (по умолчанию - объектное хранилище)
+    pub certificate_storage: Option<Arc<dyn CertificateStorage>>,
     pub start_time: Instant,
+    /// Кэш метрик MongoDB/Redis для `/admin/system/metrics`
+    pub system_metrics: SystemMetricsService,
+    /// Кэш разрешений ролей для `require_permission`
+    pub role_permissions: RolePermissionCache,
+    /// Кэш настроек античита из `system_settings`
+    pub anticheat_settings: AnticheatSettingsCache,
+    /// Скомпилированные шаблоны ПДн для проверки шаблонов заданий
+    pub pii_patterns: Arc<PiiPatternCache>,
+    /// Скомпилированный стоп-лист содержимого шаблонов
+    pub content_blacklist: Arc<BlacklistCache>,
+    /// Лимиты, пороги и origin-ы, которые перезагружаются без рестарта
+    pub runtime: RuntimeSettingsHandle,
+    /// Результат проверки индексов при старте (`index_registry::ensure_indexes`)
+    pub index_report: IndexReport,
+    /// Стартовые задачи (сид суперпользователя, индексы) завершены
+    ready: AtomicBool,
 }
 
 impl AppState {
@@ -64,36 +98,130 @@ impl AppState {
             None
         };
 
-        superuser_seed::bootstrap(&config, &mongo).await?;
+        let archive_storage = object_storage
+            .clone()
+            .map(|client| Arc::new(client) as Arc<dyn ArchiveStorage>);
+        let export_links = object_storage
+            .clone()
+            .map(|client| Arc::new(client) as Arc<dyn ExportLinkSigner>);
+        let certificate_storage = object_storage
+            .clone()
+            .map(|client| Arc::new(client) as Arc<dyn CertificateStorage>);
+
+        superuser_seed::bootstrap(&config, &mongo, &redis).await?;
+
+        // Недостающий индекс не мешает старту: запросы работают медленнее, а причина
+        // видна в логе и в `GET /admin/system/indexes`
+        let index_report = index_registry::ensure_indexes(&mongo).await;
+
+        // Без записей в базе проверка шаблонов работает по встроенным терминам
+        if let Err(err) = content_blacklist::seed_defaults(&mongo).await {
+            tracing::warn!("Content blacklist seed failed: {:#}", err);
+        }
+
+        // Переопределения из админки переживают перезапуск
+        let runtime = RuntimeSettings::load(&config, &mongo)
+            .await
+            .unwrap_or_else(|err| {
+                tracing::warn!(
+                    "Runtime setting overrides unavailable, using configuration: {:#}",
+                    err
+                );
+                RuntimeSettings::from_config(&config)
+            });
 
         Ok(Self {
             config,
             mongo,
             redis,
             object_storage,
+            archive_storage,
+            export_links,
+            certificate_storage,
             start_time: Instant::now(),
+            system_metrics: SystemMetricsService::new(),
+            role_permissions: RolePermissionCache::new(),
+            anticheat_settings: AnticheatSettingsCache::new(),
+            pii_patterns: Arc::new(PiiPatternCache::new()),
+            content_blacklist: Arc::new(BlacklistCache::new()),
+            runtime: RuntimeSettingsHandle::new(runtime),
+            index_report,
+            ready: AtomicBool::new(false),
         })
     }
+
+    /// Вызывается из `main`, когда слушатель поднят и фоновые задачи запущены
+    pub fn mark_ready(&self) {
+        self.ready.store(true, Ordering::Release);
+    }
+
+    /// Готовность к трафику по стартовым задачам; зависимости проверяет `/health/ready`
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::Acquire)
+    }
 }
 
+pub mod achievement_service;
 pub mod analytics_worker;
 pub mod answer_service;
+pub mod anticheat_preview_service;
 pub mod anticheat_service;
+pub mod archive_worker;
+pub mod assignment_service;
+pub mod audit_archive_service;
+pub mod audit_retention_worker;
 pub mod audit_service;
 pub mod auth_service;
 pub mod backup_service;
+pub mod block_expiry_worker;
+pub mod certificate_service;
+pub mod consent_service;
+pub mod content_blacklist;
+pub mod content_cache;
+pub mod content_outbox;
+pub mod content_search_service;
 pub mod content_service;
+pub mod content_stream_consumer;
+pub mod dashboard_service;
+pub mod email_outbox_service;
 pub mod email_service;
+pub mod email_worker;
+pub mod embedding_worker;
+pub mod export_i18n;
+pub mod export_schedule_worker;
 pub mod export_worker;
 pub mod feature_flag_service;
+pub mod gradebook_service;
+pub mod group_invite_service;
 pub mod group_service;
 pub mod hint_service;
 pub mod incidents_service;
+pub mod index_registry;
+pub mod llm_provider;
+pub mod login_history_service;
+pub mod maintenance_mode;
+pub mod maintenance_service;
+pub mod notification_center_service;
+pub mod notification_template_service;
 pub mod object_storage;
+pub mod pdf;
+pub mod permission_service;
+pub mod pii_scanner;
+pub mod prefetch_service;
+pub mod rate_limit_service;
 pub mod reporting_service;
+pub mod review_service;
+pub mod runtime_settings;
+pub mod session_archive_service;
+pub mod session_events;
 pub mod session_service;
 pub mod superuser_seed;
+pub mod system_metrics_service;
 pub mod system_settings_service;
+pub mod task_selection_service;
 pub mod template_enrichment_service;
 pub mod template_generator;
+pub mod template_lint;
+pub mod token_revocation;
 pub mod user_management_service;
+pub mod user_purge_service;
@@ -0,0 +1,224 @@
+//! Центр уведомлений внутри приложения (коллекция `notifications`).
+//!
+//! Уведомления пишутся при рассылке учителя и системных событиях (новое достижение,
+//! скорое окончание сессии). Каждое новое уведомление дополнительно публикуется в Redis,
+//! откуда его забирает `GET /api/v1/notifications/stream` получателя.
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, oid::ObjectId, to_document},
+    options::ReturnDocument,
+    Database,
+};
+use redis::aio::ConnectionManager;
+
+use crate::models::notification::{
+    InAppNotification, InAppNotificationKind, InAppNotificationResponse,
+};
+
+pub const NOTIFICATIONS_COLLECTION: &str = "notifications";
+
+/// Канал Redis Pub/Sub с новыми уведомлениями пользователя
+pub fn user_notifications_channel(user_id: &str) -> String {
+    format!("notifications:user:{}", user_id)
+}
+
+/// Уведомление для записи
+#[derive(Debug, Clone)]
+pub struct NewNotification {
+    pub recipient_id: String,
+    pub kind: InAppNotificationKind,
+    pub title: String,
+    pub body: String,
+    /// Повторное уведомление с тем же ключом для того же получателя не создаётся
+    pub dedup_key: Option<String>,
+}
+
+impl NewNotification {
+    fn into_document(self) -> InAppNotification {
+        InAppNotification {
+            id: ObjectId::new(),
+            recipient_id: self.recipient_id,
+            kind: self.kind,
+            title: self.title,
+            body: self.body,
+            read: false,
+            dedup_key: self.dedup_key,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// Страница уведомлений пользователя
+#[derive(Debug, Clone)]
+pub struct NotificationPage {
+    pub items: Vec<InAppNotification>,
+    pub total: u64,
+    pub unread_count: u64,
+}
+
+pub struct NotificationCenterService {
+    mongo: Database,
+    redis: ConnectionManager,
+}
+
+impl NotificationCenterService {
+    pub fn new(mongo: Database, redis: ConnectionManager) -> Self {
+        Self { mongo, redis }
+    }
+
+    fn collection(&self) -> mongodb::Collection<InAppNotification> {
+        self.mongo
+            .collection::<InAppNotification>(NOTIFICATIONS_COLLECTION)
+    }
+
+    /// Записать уведомление и сообщить о нём открытым стримам получателя.
+    /// `None`, если уведомление с таким `dedup_key` уже есть
+    pub async fn create(&self, notification: NewNotification) -> Result<Option<InAppNotification>> {
+        let notification = notification.into_document();
+        let inserted = match &notification.dedup_key {
+            Some(dedup_key) => self
+                .collection()
+                .update_one(
+                    doc! { "recipient_id": &notification.recipient_id, "dedupKey": dedup_key },
+                    doc! { "$setOnInsert": to_document(&notification)? },
+                )
+                .upsert(true)
+                .await
+                .context("Failed to create notification")?
+                .upserted_id
+                .is_some(),
+            None => {
+                self.collection()
+                    .insert_one(&notification)
+                    .await
+                    .context("Failed to create notification")?;
+                true
+            }
+        };
+        if !inserted {
+            return Ok(None);
+        }
+        self.publish(&notification).await;
+        Ok(Some(notification))
+    }
+
+    /// Записать уведомления нескольким получателям (рассылка учителя)
+    pub async fn create_many(&self, notifications: Vec<NewNotification>) -> Result<usize> {
+        if notifications.is_empty() {
+            return Ok(0);
+        }
+        let documents = notifications
+            .into_iter()
+            .map(NewNotification::into_document)
+            .collect::<Vec<_>>();
+        self.collection()
+            .insert_many(&documents)
+            .await
+            .context("Failed to create notifications")?;
+        for notification in &documents {
+            self.publish(notification).await;
+        }
+        Ok(documents.len())
+    }
+
+    /// Уведомления пользователя, новые первыми
+    pub async fn list(
+        &self,
+        recipient_id: &str,
+        unread_only: bool,
+        limit: u32,
+        offset: u32,
+    ) -> Result<NotificationPage> {
+        let mut filter = doc! { "recipient_id": recipient_id };
+        if unread_only {
+            filter.insert("read", false);
+        }
+        let total = self
+            .collection()
+            .count_documents(filter.clone())
+            .await
+            .context("Failed to count notifications")?;
+        let items = self
+            .collection()
+            .find(filter)
+            .sort(doc! { "createdAt": -1, "_id": -1 })
+            .skip(u64::from(offset))
+            .limit(i64::from(limit))
+            .await
+            .context("Failed to load notifications")?
+            .try_collect()
+            .await
+            .context("Failed to read notifications")?;
+        let unread_count = self.unread_count(recipient_id).await?;
+        Ok(NotificationPage {
+            items,
+            total,
+            unread_count,
+        })
+    }
+
+    pub async fn unread_count(&self, recipient_id: &str) -> Result<u64> {
+        self.collection()
+            .count_documents(doc! { "recipient_id": recipient_id, "read": false })
+            .await
+            .context("Failed to count unread notifications")
+    }
+
+    /// Отметить уведомление прочитанным; `None`, если у пользователя такого нет
+    pub async fn mark_read(
+        &self,
+        recipient_id: &str,
+        notification_id: &ObjectId,
+    ) -> Result<Option<InAppNotification>> {
+        self.collection()
+            .find_one_and_update(
+                doc! { "_id": notification_id, "recipient_id": recipient_id },
+                doc! { "$set": { "read": true } },
+            )
+            .return_document(ReturnDocument::After)
+            .await
+            .context("Failed to mark notification as read")
+    }
+
+    /// Отметить прочитанными все уведомления пользователя; возвращает число изменённых
+    pub async fn mark_all_read(&self, recipient_id: &str) -> Result<u64> {
+        let result = self
+            .collection()
+            .update_many(
+                doc! { "recipient_id": recipient_id, "read": false },
+                doc! { "$set": { "read": true } },
+            )
+            .await
+            .context("Failed to mark notifications as read")?;
+        Ok(result.modified_count)
+    }
+
+    /// Сбой публикации только логируется: уведомление уже сохранено и придёт со списком
+    async fn publish(&self, notification: &InAppNotification) {
+        let payload =
+            match serde_json::to_string(&InAppNotificationResponse::from(notification.clone())) {
+                Ok(payload) => payload,
+                Err(err) => {
+                    tracing::warn!("Failed to encode notification {}: {}", notification.id, err);
+                    return;
+                }
+            };
+        let mut conn = self.redis.clone();
+        let published: redis::RedisResult<()> = redis::cmd("PUBLISH")
+            .arg(user_notifications_channel(&notification.recipient_id))
+            .arg(payload)
+            .query_async(&mut conn)
+            .await;
+        if let Err(err) = published {
+            tracing::warn!(
+                "Failed to publish notification {} to user {}: {}",
+                notification.id,
+                notification.recipient_id,
+                err
+            );
+        }
+    }
+}
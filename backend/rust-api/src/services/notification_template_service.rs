@@ -0,0 +1,237 @@
+//! Переменные шаблонов уведомлений.
+//!
+//! Шаблон может ссылаться только на переменные из `TEMPLATE_VARIABLES`; неизвестные
+//! плейсхолдеры отклоняются при сохранении, иначе `{teacher_nme}` ушёл бы ученику как есть.
+//! `last_score` и `last_activity_date` берутся из последней записи `progress_summary`.
+
+use std::collections::{BTreeSet, HashMap};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, Bson, Document},
+    Database,
+};
+
+pub const TEMPLATE_VARIABLES: [&str; 5] = [
+    "student_name",
+    "group_name",
+    "teacher_name",
+    "last_score",
+    "last_activity_date",
+];
+
+/// Чем заменяется переменная прогресса, если у ученика ещё нет попыток
+const MISSING_VALUE: &str = "—";
+
+/// Значения переменных для одного получателя
+#[derive(Debug, Clone, Default)]
+pub struct TemplateContext {
+    pub student_name: String,
+    pub group_name: String,
+    pub teacher_name: String,
+    pub last_score: Option<i32>,
+    pub last_activity: Option<DateTime<Utc>>,
+}
+
+impl TemplateContext {
+    pub fn with_progress(mut self, progress: Option<&ProgressSnapshot>) -> Self {
+        self.last_score = progress.map(|snapshot| snapshot.score);
+        self.last_activity = progress.and_then(|snapshot| snapshot.updated_at);
+        self
+    }
+
+    /// Вымышленный ученик для предпросмотра без выбора получателя
+    pub fn sample(group_name: &str, teacher_name: &str) -> Self {
+        Self {
+            student_name: "Иван Петров".to_string(),
+            group_name: group_name.to_string(),
+            teacher_name: teacher_name.to_string(),
+            last_score: Some(85),
+            last_activity: Some(Utc::now()),
+        }
+    }
+
+    fn value(&self, variable: &str) -> Option<String> {
+        let value = match variable {
+            "student_name" => self.student_name.clone(),
+            "group_name" => self.group_name.clone(),
+            "teacher_name" => self.teacher_name.clone(),
+            "last_score" => self
+                .last_score
+                .map(|score| score.to_string())
+                .unwrap_or_else(|| MISSING_VALUE.to_string()),
+            "last_activity_date" => self
+                .last_activity
+                .map(|at| at.format("%d.%m.%Y").to_string())
+                .unwrap_or_else(|| MISSING_VALUE.to_string()),
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+/// Плейсхолдеры вида `{name}` (латиница в нижнем регистре, цифры и `_`).
+/// Фигурные скобки с другим содержимым считаются обычным текстом
+fn placeholders(text: &str) -> impl Iterator<Item = (usize, usize, &str)> {
+    text.match_indices('{').filter_map(move |(start, _)| {
+        let rest = &text[start + 1..];
+        let end = rest.find('}')?;
+        let name = &rest[..end];
+        let is_identifier = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+        is_identifier.then_some((start, start + end + 2, name))
+    })
+}
+
+/// Неизвестные переменные во всех текстах шаблона, по алфавиту
+pub fn unknown_variables(texts: &[&str]) -> Vec<String> {
+    texts
+        .iter()
+        .flat_map(|text| placeholders(text))
+        .map(|(_, _, name)| name)
+        .filter(|name| !TEMPLATE_VARIABLES.contains(name))
+        .map(str::to_string)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// Подставить значения переменных; неизвестные плейсхолдеры остаются как есть
+pub fn render(text: &str, context: &TemplateContext) -> String {
+    let mut rendered = String::with_capacity(text.len());
+    let mut copied = 0;
+    for (start, end, name) in placeholders(text) {
+        if start < copied {
+            continue;
+        }
+        if let Some(value) = context.value(name) {
+            rendered.push_str(&text[copied..start]);
+            rendered.push_str(&value);
+            copied = end;
+        }
+    }
+    rendered.push_str(&text[copied..]);
+    rendered
+}
+
+/// Последняя запись прогресса ученика
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgressSnapshot {
+    pub score: i32,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// `updated_at` пишется и как BSON-дата, и как RFC 3339 строка (`ProgressSummary`)
+fn bson_to_chrono(value: Option<&Bson>) -> Option<DateTime<Utc>> {
+    match value? {
+        Bson::DateTime(at) => DateTime::from_timestamp_millis(at.timestamp_millis()),
+        Bson::String(raw) => DateTime::parse_from_rfc3339(raw)
+            .ok()
+            .map(|at| at.with_timezone(&Utc)),
+        _ => None,
+    }
+}
+
+pub struct NotificationTemplateService {
+    mongo: Database,
+}
+
+impl NotificationTemplateService {
+    pub fn new(mongo: Database) -> Self {
+        Self { mongo }
+    }
+
+    /// Последний прогресс учеников по `user_id` (ученики без попыток не попадают в ответ)
+    pub async fn latest_progress(
+        &self,
+        user_ids: &[String],
+    ) -> Result<HashMap<String, ProgressSnapshot>> {
+        if user_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let pipeline = vec![
+            doc! { "$match": { "user_id": { "$in": user_ids } } },
+            doc! { "$sort": { "updated_at": -1 } },
+            doc! { "$group": {
+                "_id": "$user_id",
+                "score": { "$first": "$score" },
+                "updated_at": { "$first": "$updated_at" },
+            } },
+        ];
+        let rows: Vec<Document> = self
+            .mongo
+            .collection::<Document>("progress_summary")
+            .aggregate(pipeline)
+            .await
+            .context("Failed to load latest progress")?
+            .try_collect()
+            .await
+            .context("Failed to read latest progress")?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                let user_id = row.get_str("_id").ok()?.to_string();
+                let score = match row.get("score") {
+                    Some(Bson::Int32(score)) => *score,
+                    Some(Bson::Int64(score)) => i32::try_from(*score).unwrap_or(i32::MAX),
+                    _ => 0,
+                };
+                let updated_at = bson_to_chrono(row.get("updated_at"));
+                Some((user_id, ProgressSnapshot { score, updated_at }))
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn context() -> TemplateContext {
+        TemplateContext {
+            student_name: "Анна".to_string(),
+            group_name: "7Б".to_string(),
+            teacher_name: "Мария Ивановна".to_string(),
+            last_score: Some(42),
+            last_activity: Some(Utc.with_ymd_and_hms(2026, 3, 9, 10, 0, 0).unwrap()),
+        }
+    }
+
+    #[test]
+    fn render_resolves_all_variables() {
+        let text = "{student_name} из {group_name}: {last_score} баллов на {last_activity_date}. {teacher_name}";
+        assert_eq!(
+            render(text, &context()),
+            "Анна из 7Б: 42 баллов на 09.03.2026. Мария Ивановна"
+        );
+    }
+
+    #[test]
+    fn render_marks_missing_progress_and_keeps_other_braces() {
+        let context = TemplateContext {
+            last_score: None,
+            last_activity: None,
+            ..context()
+        };
+        assert_eq!(
+            render("{last_score} / {last_activity_date} {x} { json }", &context),
+            "— / — {x} { json }"
+        );
+        assert_eq!(render("{{student_name}}", &context), "{Анна}");
+    }
+
+    #[test]
+    fn unknown_variables_are_listed_once() {
+        assert!(unknown_variables(&["Привет, {student_name}!", "{group_name}"]).is_empty());
+        assert_eq!(
+            unknown_variables(&["{teacher_nme} {score}", "{score} {Student_Name} {}"]),
+            vec!["score".to_string(), "teacher_nme".to_string()]
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::BTreeMap;
+use std::future::Future;
 use std::time::Duration;
 
 use anyhow::{anyhow, bail, Context, Result};
@@ -6,11 +7,13 @@ use chrono::Utc;
 use hex;
 use hmac::{Hmac, Mac};
 use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
-use reqwest::Client;
+use reqwest::{Client, Method, StatusCode};
 use sha2::{Digest, Sha256};
+use tracing::warn;
 use url::Url;
 
-use crate::config::ObjectStorageSettings;
+use crate::config::{ObjectStorageSettings, MIN_MULTIPART_PART_SIZE_BYTES};
+use crate::models::reporting::ExportScope;
 
 type HmacSha256 = Hmac<Sha256>;
 
@@ -28,6 +31,108 @@ pub struct ObjectStorageClient {
     access_key: String,
     secret_key: String,
     prefix: String,
+    http: Client,
+    retry: RetryPolicy,
+    /// Заголовки шифрования на стороне хранилища для загрузок
+    sse_headers: Vec<(&'static str, String)>,
+    multipart_threshold: usize,
+    part_size: usize,
+}
+
+/// Повторы запросов к хранилищу с экспоненциальной паузой
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Всего попыток, включая первую
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Пауза перед попыткой `attempt + 1` или `None`, если после неудачной попытки
+    /// `attempt` (с 1) повторять не нужно
+    pub fn next_delay(&self, attempt: u32, failure: &RequestFailure) -> Option<Duration> {
+        if attempt >= self.max_attempts || !failure.is_transient() {
+            return None;
+        }
+        let factor = 1u32 << attempt.saturating_sub(1).min(10);
+        Some(self.base_delay.saturating_mul(factor))
+    }
+
+    /// Выполнить запрос `send`, повторяя его после временных сбоев
+    pub async fn run<T, F, Fut>(&self, operation: &str, mut send: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, RequestFailure>>,
+    {
+        let mut attempt = 1;
+        loop {
+            let failure = match send().await {
+                Ok(value) => return Ok(value),
+                Err(failure) => failure,
+            };
+            let Some(delay) = self.next_delay(attempt, &failure) else {
+                return Err(anyhow!(
+                    "{} failed after {} attempt(s): {}",
+                    operation,
+                    attempt,
+                    failure
+                ));
+            };
+            warn!(operation, attempt, error = %failure, "object storage request failed, retrying");
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+/// Неудачная попытка запроса к хранилищу
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum RequestFailure {
+    #[error("object storage responded with {status}: {message}")]
+    Status { status: u16, message: String },
+    #[error("object storage request timed out")]
+    Timeout,
+    #[error("failed to connect to object storage: {0}")]
+    Connect(String),
+    #[error("object storage request failed: {0}")]
+    Other(String),
+}
+
+impl RequestFailure {
+    /// 5xx, 408, 429, таймауты и обрывы соединения стоит повторить;
+    /// остальные 4xx от повтора не исправятся
+    pub fn is_transient(&self) -> bool {
+        match self {
+            RequestFailure::Status { status, .. } => {
+                *status >= 500 || *status == 408 || *status == 429
+            }
+            RequestFailure::Timeout | RequestFailure::Connect(_) => true,
+            RequestFailure::Other(_) => false,
+        }
+    }
+
+    fn from_reqwest(err: reqwest::Error) -> Self {
+        if err.is_timeout() {
+            RequestFailure::Timeout
+        } else if err.is_connect() {
+            RequestFailure::Connect(err.to_string())
+        } else {
+            RequestFailure::Other(err.to_string())
+        }
+    }
+}
+
+/// Запрос к хранилищу до подписи
+struct SignedRequest<'a> {
+    method: Method,
+    /// Путь от корня endpoint: `/{bucket}` или `/{bucket}/{key}`
+    path: String,
+    query: BTreeMap<String, String>,
+    body: &'a [u8],
+    content_type: Option<&'a str>,
+    /// Дополнительные заголовки (`x-amz-*`, `range`), подписываются вместе с
+    /// остальными; имена в нижнем регистре, как в каноническом запросе
+    amz_headers: &'a [(&'static str, String)],
 }
 
 impl ObjectStorageClient {
@@ -58,6 +163,19 @@ impl ObjectStorageClient {
             );
         }
 
+        let mut sse_headers = Vec::new();
+        if let Some(sse) = settings.sse.filter(|sse| !sse.is_empty()) {
+            sse_headers.push(("x-amz-server-side-encryption", sse));
+            if let Some(key_id) = settings.sse_kms_key_id.filter(|key| !key.is_empty()) {
+                sse_headers.push(("x-amz-server-side-encryption-aws-kms-key-id", key_id));
+            }
+        }
+
+        let http = Client::builder()
+            .timeout(Duration::from_secs(settings.request_timeout_secs.max(1)))
+            .build()
+            .context("Failed to build object storage HTTP client")?;
+
         Ok(Self {
             bucket: settings.bucket,
             region: settings.region,
@@ -65,82 +183,344 @@ impl ObjectStorageClient {
             secret_key: settings.secret_key,
             endpoint,
             prefix: sanitize_prefix(&settings.reports_prefix),
+            http,
+            retry: RetryPolicy {
+                max_attempts: settings.max_attempts.max(1),
+                base_delay: Duration::from_millis(settings.retry_base_delay_ms),
+            },
+            sse_headers,
+            multipart_threshold: settings.multipart_threshold_bytes,
+            part_size: settings
+                .multipart_part_size_bytes
+                .max(MIN_MULTIPART_PART_SIZE_BYTES),
         })
     }
 
+    /// Загрузить объект; больше `multipart_threshold_bytes` - по частям
     pub async fn upload_bytes(&self, key: &str, bytes: Vec<u8>, content_type: &str) -> Result<()> {
+        if bytes.len() > self.multipart_threshold {
+            return self.upload_multipart(key, &bytes, content_type).await;
+        }
+
         let object_key = self.full_key(key);
-        let canonical_uri = self.canonical_uri(&object_key);
+        self.send_signed(
+            "PutObject",
+            SignedRequest {
+                method: Method::PUT,
+                path: self.canonical_uri(&object_key),
+                query: BTreeMap::new(),
+                body: &bytes,
+                content_type: Some(content_type),
+                amz_headers: &self.sse_headers,
+            },
+        )
+        .await
+        .with_context(|| format!("Failed to upload object {}", object_key))?;
+        Ok(())
+    }
 
-        let payload_hash = hex::encode(Sha256::digest(&bytes));
-        let now = Utc::now();
-        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
-        let date_stamp = now.format("%Y%m%d").to_string();
-        let scope = format!("{}/{}/s3/aws4_request", date_stamp, self.region);
+    /// Составная загрузка частями по `multipart_part_size_bytes`; при ошибке
+    /// загрузка отменяется, чтобы хранилище не хранило брошенные части
+    async fn upload_multipart(&self, key: &str, bytes: &[u8], content_type: &str) -> Result<()> {
+        let upload_id = self.create_multipart_upload(key, content_type).await?;
+        let uploaded = async {
+            let mut etags = Vec::new();
+            for (index, part) in bytes.chunks(self.part_size).enumerate() {
+                let etag = self
+                    .upload_part(key, &upload_id, index as u32 + 1, part.to_vec())
+                    .await?;
+                etags.push(etag);
+            }
+            self.complete_multipart_upload(key, &upload_id, &etags)
+                .await
+        }
+        .await;
 
-        let host = self
-            .endpoint
-            .host_str()
-            .ok_or_else(|| anyhow!("Object storage endpoint missing host"))?
-            .to_lowercase();
+        if let Err(err) = uploaded {
+            if let Err(abort_err) = self.abort_multipart_upload(key, &upload_id).await {
+                warn!(error = %abort_err, key, "failed to abort multipart upload");
+            }
+            return Err(err);
+        }
+        Ok(())
+    }
 
-        let canonical_headers = format!(
-            "host:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n",
-            host, payload_hash, amz_date
-        );
-        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+    pub async fn delete_object(&self, key: &str) -> Result<()> {
+        let object_key = self.full_key(key);
+        self.send_signed(
+            "DeleteObject",
+            SignedRequest {
+                method: Method::DELETE,
+                path: self.canonical_uri(&object_key),
+                query: BTreeMap::new(),
+                body: &[],
+                content_type: None,
+                amz_headers: &[],
+            },
+        )
+        .await
+        .with_context(|| format!("Failed to delete object {}", object_key))?;
+        Ok(())
+    }
 
-        let canonical_request = format!(
-            "PUT\n{}\n\n{}\n{}\n{}",
-            canonical_uri, canonical_headers, signed_headers, payload_hash
-        );
+    /// Ключи объектов с префиксом `prefix` (ListObjectsV2, все страницы).
+    /// Ключи возвращаются без префикса клиента, как их принимают остальные методы
+    pub async fn list_prefix(&self, prefix: &str) -> Result<Vec<String>> {
+        let mut full_prefix = self.full_key(prefix);
+        if prefix.ends_with('/') && !full_prefix.is_empty() {
+            full_prefix.push('/');
+        }
 
-        let hashed_canonical_request = hex::encode(Sha256::digest(canonical_request.as_bytes()));
-        let string_to_sign = format!(
-            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
-            amz_date, scope, hashed_canonical_request
-        );
+        let mut keys = Vec::new();
+        let mut continuation: Option<String> = None;
+        loop {
+            let mut query = BTreeMap::new();
+            query.insert("list-type".to_string(), "2".to_string());
+            query.insert("prefix".to_string(), full_prefix.clone());
+            if let Some(token) = continuation.take() {
+                query.insert("continuation-token".to_string(), token);
+            }
+            let body = self
+                .send_signed(
+                    "ListObjectsV2",
+                    SignedRequest {
+                        method: Method::GET,
+                        path: format!("/{}", self.bucket),
+                        query,
+                        body: &[],
+                        content_type: None,
+                        amz_headers: &[],
+                    },
+                )
+                .await
+                .with_context(|| format!("Failed to list objects under {}", full_prefix))?
+                .text()
+                .await
+                .context("Failed to read object listing")?;
 
-        let signing_key = derive_signing_key(&self.secret_key, &date_stamp, &self.region, "s3");
-        let signature = hex::encode(hmac_sign(&signing_key, string_to_sign.as_bytes()));
+            keys.extend(
+                xml_elements(&body, "Key")
+                    .into_iter()
+                    .map(|key| self.relative_key(&xml_unescape(key))),
+            );
+            match xml_element(&body, "NextContinuationToken") {
+                Some(token) if xml_element(&body, "IsTruncated") == Some("true") => {
+                    continuation = Some(xml_unescape(token));
+                }
+                _ => break,
+            }
+        }
+        Ok(keys)
+    }
 
-        let authorization = format!(
-            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
-            self.access_key, scope, signed_headers, signature
-        );
+    /// Проверка доступности бакета (HEAD bucket) для health-check
+    pub async fn head_bucket(&self) -> Result<()> {
+        self.send_signed(
+            "HeadBucket",
+            SignedRequest {
+                method: Method::HEAD,
+                path: format!("/{}", self.bucket),
+                query: BTreeMap::new(),
+                body: &[],
+                content_type: None,
+                amz_headers: &[],
+            },
+        )
+        .await
+        .with_context(|| format!("Failed to reach bucket {}", self.bucket))?;
+        Ok(())
+    }
 
-        let mut upload_url = self.endpoint.clone();
-        upload_url.set_path(&format!(
-            "{}/{}",
-            self.bucket,
-            object_key
-                .split('/')
-                .map(|segment| utf8_percent_encode(segment, AWS_URI_ENCODE_SET).to_string())
-                .collect::<Vec<_>>()
-                .join("/")
-        ));
+    /// Скачать диапазон байт `[offset, offset + length)` объекта (HTTP Range)
+    pub async fn get_object_range(&self, key: &str, offset: u64, length: u64) -> Result<Vec<u8>> {
+        if length == 0 {
+            return Ok(Vec::new());
+        }
+        self.get_object_bytes(
+            key,
+            Some(format!("bytes={}-{}", offset, offset + length - 1)),
+        )
+        .await
+    }
 
-        Client::new()
-            .put(upload_url)
-            .header("Authorization", authorization)
-            .header("x-amz-date", amz_date)
-            .header("x-amz-content-sha256", payload_hash)
-            .header("content-type", content_type)
-            .body(bytes)
-            .send()
+    /// Скачать объект целиком
+    pub async fn get_object(&self, key: &str) -> Result<Vec<u8>> {
+        self.get_object_bytes(key, None).await
+    }
+
+    async fn get_object_bytes(&self, key: &str, range: Option<String>) -> Result<Vec<u8>> {
+        let object_key = self.full_key(key);
+        let headers: Vec<(&'static str, String)> =
+            range.into_iter().map(|range| ("range", range)).collect();
+        let bytes = self
+            .send_signed(
+                "GetObject",
+                SignedRequest {
+                    method: Method::GET,
+                    path: self.canonical_uri(&object_key),
+                    query: BTreeMap::new(),
+                    body: &[],
+                    content_type: None,
+                    amz_headers: &headers,
+                },
+            )
+            .await
+            .with_context(|| format!("Failed to download object {}", object_key))?
+            .bytes()
+            .await
+            .context("Failed to read object storage response body")?;
+
+        Ok(bytes.to_vec())
+    }
+
+    /// Начать составную загрузку (S3 CreateMultipartUpload); возвращает `UploadId`
+    pub async fn create_multipart_upload(&self, key: &str, content_type: &str) -> Result<String> {
+        let mut query = BTreeMap::new();
+        query.insert("uploads".to_string(), String::new());
+        let body = self
+            .send_signed(
+                "CreateMultipartUpload",
+                SignedRequest {
+                    method: Method::POST,
+                    path: self.canonical_uri(&self.full_key(key)),
+                    query,
+                    body: &[],
+                    content_type: Some(content_type),
+                    amz_headers: &self.sse_headers,
+                },
+            )
+            .await
+            .with_context(|| format!("Failed to start multipart upload of {}", key))?
+            .text()
+            .await
+            .context("Failed to read multipart upload response")?;
+        xml_element(&body, "UploadId")
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("Multipart upload response has no UploadId"))
+    }
+
+    /// Загрузить часть `part_number` (с 1); возвращает ETag части
+    pub async fn upload_part(
+        &self,
+        key: &str,
+        upload_id: &str,
+        part_number: u32,
+        bytes: Vec<u8>,
+    ) -> Result<String> {
+        let mut query = BTreeMap::new();
+        query.insert("partNumber".to_string(), part_number.to_string());
+        query.insert("uploadId".to_string(), upload_id.to_string());
+        let response = self
+            .send_signed(
+                "UploadPart",
+                SignedRequest {
+                    method: Method::PUT,
+                    path: self.canonical_uri(&self.full_key(key)),
+                    query,
+                    body: &bytes,
+                    content_type: None,
+                    amz_headers: &[],
+                },
+            )
+            .await
+            .with_context(|| format!("Failed to upload part {} of {}", part_number, key))?;
+        response
+            .headers()
+            .get("etag")
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("Object storage returned no ETag for part {}", part_number))
+    }
+
+    /// Собрать объект из частей; `etags` - по порядку номеров частей
+    pub async fn complete_multipart_upload(
+        &self,
+        key: &str,
+        upload_id: &str,
+        etags: &[String],
+    ) -> Result<()> {
+        let parts: String = etags
+            .iter()
+            .enumerate()
+            .map(|(index, etag)| {
+                format!(
+                    "<Part><PartNumber>{}</PartNumber><ETag>{}</ETag></Part>",
+                    index + 1,
+                    etag
+                )
+            })
+            .collect();
+        let body = format!(
+            "<CompleteMultipartUpload>{}</CompleteMultipartUpload>",
+            parts
+        );
+
+        let mut query = BTreeMap::new();
+        query.insert("uploadId".to_string(), upload_id.to_string());
+        let response = self
+            .send_signed(
+                "CompleteMultipartUpload",
+                SignedRequest {
+                    method: Method::POST,
+                    path: self.canonical_uri(&self.full_key(key)),
+                    query,
+                    body: body.as_bytes(),
+                    content_type: Some("application/xml"),
+                    amz_headers: &[],
+                },
+            )
+            .await
+            .with_context(|| format!("Failed to complete multipart upload of {}", key))?
+            .text()
             .await
-            .with_context(|| format!("Failed to upload object {}", object_key))?
-            .error_for_status()
-            .context("Object storage upload returned error status")?;
+            .context("Failed to read multipart completion response")?;
+        // S3 может ответить 200 с ошибкой в теле
+        if let Some(message) = xml_element(&response, "Error")
+            .map(|error| xml_element(error, "Message").unwrap_or(error).to_string())
+        {
+            bail!("Multipart upload of {} failed: {}", key, message);
+        }
+        Ok(())
+    }
 
+    /// Отменить составную загрузку, чтобы хранилище освободило загруженные части
+    pub async fn abort_multipart_upload(&self, key: &str, upload_id: &str) -> Result<()> {
+        let mut query = BTreeMap::new();
+        query.insert("uploadId".to_string(), upload_id.to_string());
+        self.send_signed(
+            "AbortMultipartUpload",
+            SignedRequest {
+                method: Method::DELETE,
+                path: self.canonical_uri(&self.full_key(key)),
+                query,
+                body: &[],
+                content_type: None,
+                amz_headers: &[],
+            },
+        )
+        .await
+        .with_context(|| format!("Failed to abort multipart upload of {}", key))?;
         Ok(())
     }
 
-    pub async fn delete_object(&self, key: &str) -> Result<()> {
-        let object_key = self.full_key(key);
-        let canonical_uri = self.canonical_uri(&object_key);
+    /// Подписанный (SigV4) запрос с повторами по `RetryPolicy`
+    async fn send_signed(
+        &self,
+        operation: &str,
+        request: SignedRequest<'_>,
+    ) -> Result<reqwest::Response> {
+        self.retry
+            .run(operation, || self.send_signed_once(&request))
+            .await
+    }
 
-        let payload_hash = hex::encode(Sha256::digest([]));
+    async fn send_signed_once(
+        &self,
+        request: &SignedRequest<'_>,
+    ) -> Result<reqwest::Response, RequestFailure> {
+        let canonical_query = Self::canonical_query_string(&request.query);
+
+        let payload_hash = hex::encode(Sha256::digest(request.body));
         let now = Utc::now();
         let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
         let date_stamp = now.format("%Y%m%d").to_string();
@@ -149,18 +529,31 @@ impl ObjectStorageClient {
         let host = self
             .endpoint
             .host_str()
-            .ok_or_else(|| anyhow!("Object storage endpoint missing host"))?
+            .ok_or_else(|| RequestFailure::Other("endpoint missing host".into()))?
             .to_lowercase();
 
-        let canonical_headers = format!(
-            "host:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n",
-            host, payload_hash, amz_date
-        );
-        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        // Канонические заголовки идут по алфавиту
+        let mut headers = BTreeMap::new();
+        headers.insert("host", host);
+        headers.insert("x-amz-content-sha256", payload_hash.clone());
+        headers.insert("x-amz-date", amz_date.clone());
+        for (name, value) in request.amz_headers {
+            headers.insert(name, value.clone());
+        }
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+            .collect();
+        let signed_headers = headers.keys().copied().collect::<Vec<_>>().join(";");
 
         let canonical_request = format!(
-            "DELETE\n{}\n\n{}\n{}\n{}",
-            canonical_uri, canonical_headers, signed_headers, payload_hash
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            request.method.as_str(),
+            request.path,
+            canonical_query,
+            canonical_headers,
+            signed_headers,
+            payload_hash
         );
 
         let hashed_canonical_request = hex::encode(Sha256::digest(canonical_request.as_bytes()));
@@ -177,37 +570,56 @@ impl ObjectStorageClient {
             self.access_key, scope, signed_headers, signature
         );
 
-        let mut delete_url = self.endpoint.clone();
-        delete_url.set_path(&format!(
-            "{}/{}",
-            self.bucket,
-            object_key
-                .split('/')
-                .map(|segment| utf8_percent_encode(segment, AWS_URI_ENCODE_SET).to_string())
-                .collect::<Vec<_>>()
-                .join("/")
-        ));
+        let mut url = self.endpoint.clone();
+        url.set_path(&request.path);
+        if !canonical_query.is_empty() {
+            url.set_query(Some(&canonical_query));
+        }
 
-        Client::new()
-            .delete(delete_url)
-            .header("Authorization", authorization)
-            .header("x-amz-date", amz_date)
-            .header("x-amz-content-sha256", payload_hash)
+        let mut builder = self
+            .http
+            .request(request.method.clone(), url)
+            .header("Authorization", authorization);
+        for (name, value) in headers.iter().filter(|(name, _)| **name != "host") {
+            builder = builder.header(*name, value);
+        }
+        if let Some(content_type) = request.content_type {
+            builder = builder.header("content-type", content_type);
+        }
+        let response = builder
+            .body(request.body.to_vec())
             .send()
             .await
-            .with_context(|| format!("Failed to delete object {}", object_key))?
-            .error_for_status()
-            .context("Object storage delete returned error status")?;
+            .map_err(RequestFailure::from_reqwest)?;
 
-        Ok(())
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let message = response.text().await.unwrap_or_default();
+        let message = xml_element(&message, "Message")
+            .map(str::to_string)
+            .unwrap_or_else(|| status_text(status));
+        Err(RequestFailure::Status {
+            status: status.as_u16(),
+            message,
+        })
     }
 
-    pub fn build_export_key(&self, group_id: &str, export_id: &str, extension: &str) -> String {
+    /// Ключ выгрузки: `groups/{id}/…` для отчётов по группе, `users/{id}/…` — по ученику
+    pub fn build_export_key(
+        &self,
+        scope: ExportScope,
+        owner_id: &str,
+        export_id: &str,
+        extension: &str,
+    ) -> String {
         let timestamp = Utc::now().format("%Y%m%dT%H%M%S");
         let ext = extension.trim_start_matches('.');
         format!(
-            "groups/{group_id}/export-{export_id}-{timestamp}.{ext}",
-            group_id = group_id,
+            "{prefix}/{owner_id}/export-{export_id}-{timestamp}.{ext}",
+            prefix = scope.storage_prefix(),
+            owner_id = owner_id,
             export_id = export_id,
             timestamp = timestamp,
             ext = ext
@@ -225,6 +637,17 @@ impl ObjectStorageClient {
         }
     }
 
+    /// Ключ без префикса клиента (обратное к `full_key`)
+    fn relative_key(&self, full_key: &str) -> String {
+        if self.prefix.is_empty() {
+            return full_key.to_string();
+        }
+        full_key
+            .strip_prefix(&self.prefix)
+            .map(|rest| rest.trim_start_matches('/').to_string())
+            .unwrap_or_else(|| full_key.to_string())
+    }
+
     fn canonical_uri(&self, key: &str) -> String {
         let encoded_key = key
             .split('/')
@@ -324,6 +747,48 @@ fn sanitize_prefix(prefix: &str) -> String {
         .join("/")
 }
 
+/// Содержимое первого элемента `<tag>…</tag>` в XML-ответе S3
+fn xml_element<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let start = xml.find(&open)? + open.len();
+    let end = start + xml[start..].find(&close)?;
+    Some(&xml[start..end])
+}
+
+/// Содержимое всех элементов `<tag>…</tag>` по порядку
+fn xml_elements<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let mut found = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        let after = &rest[start + open.len()..];
+        let Some(end) = after.find(&close) else {
+            break;
+        };
+        found.push(&after[..end]);
+        rest = &after[end + close.len()..];
+    }
+    found
+}
+
+fn xml_unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn status_text(status: StatusCode) -> String {
+    status
+        .canonical_reason()
+        .unwrap_or("unexpected status")
+        .to_string()
+}
+
 fn derive_signing_key(secret: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
     let mut key = format!("AWS4{}", secret).into_bytes();
     key = hmac_sign(&key, date);
@@ -355,6 +820,14 @@ mod tests {
             access_key: "key".into(),
             secret_key: "secret".into(),
             reports_prefix: "reports".into(),
+            presign_ttl_secs: ObjectStorageSettings::DEFAULT_PRESIGN_TTL_SECS,
+            sse: None,
+            sse_kms_key_id: None,
+            max_attempts: 4,
+            retry_base_delay_ms: 200,
+            request_timeout_secs: 120,
+            multipart_threshold_bytes: 32 * 1024 * 1024,
+            multipart_part_size_bytes: 8 * 1024 * 1024,
         };
 
         let result = ObjectStorageClient::new(settings);
@@ -380,6 +853,14 @@ mod tests {
             access_key: "minioadmin".into(),
             secret_key: "minioadmin".into(),
             reports_prefix: "reports/dev".into(),
+            presign_ttl_secs: ObjectStorageSettings::DEFAULT_PRESIGN_TTL_SECS,
+            sse: None,
+            sse_kms_key_id: None,
+            max_attempts: 4,
+            retry_base_delay_ms: 200,
+            request_timeout_secs: 120,
+            multipart_threshold_bytes: 32 * 1024 * 1024,
+            multipart_part_size_bytes: 8 * 1024 * 1024,
         };
 
         let result = ObjectStorageClient::new(settings);
@@ -398,6 +879,14 @@ mod tests {
             access_key: "key".into(),
             secret_key: "secret".into(),
             reports_prefix: "reports".into(),
+            presign_ttl_secs: ObjectStorageSettings::DEFAULT_PRESIGN_TTL_SECS,
+            sse: None,
+            sse_kms_key_id: None,
+            max_attempts: 4,
+            retry_base_delay_ms: 200,
+            request_timeout_secs: 120,
+            multipart_threshold_bytes: 32 * 1024 * 1024,
+            multipart_part_size_bytes: 8 * 1024 * 1024,
         };
 
         let result = ObjectStorageClient::new(settings);
@@ -413,9 +902,158 @@ mod tests {
             access_key: "key".into(),
             secret_key: "secret".into(),
             reports_prefix: "reports".into(),
+            presign_ttl_secs: ObjectStorageSettings::DEFAULT_PRESIGN_TTL_SECS,
+            sse: None,
+            sse_kms_key_id: None,
+            max_attempts: 4,
+            retry_base_delay_ms: 200,
+            request_timeout_secs: 120,
+            multipart_threshold_bytes: 32 * 1024 * 1024,
+            multipart_part_size_bytes: 8 * 1024 * 1024,
         };
 
         let result = ObjectStorageClient::new(settings);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn xml_elements_are_extracted_from_s3_responses() {
+        let body = "<InitiateMultipartUploadResult><Bucket>b</Bucket>\
+            <UploadId>abc-123</UploadId></InitiateMultipartUploadResult>";
+        assert_eq!(xml_element(body, "UploadId"), Some("abc-123"));
+        assert_eq!(xml_element(body, "Error"), None);
+    }
+
+    #[test]
+    fn test_export_keys_are_prefixed_by_scope() {
+        let settings = ObjectStorageSettings {
+            bucket: "test".into(),
+            region: "ru-central1".into(),
+            endpoint: Some("https://storage.yandexcloud.net".into()),
+            access_key: "key".into(),
+            secret_key: "secret".into(),
+            reports_prefix: "reports".into(),
+            presign_ttl_secs: ObjectStorageSettings::DEFAULT_PRESIGN_TTL_SECS,
+            sse: None,
+            sse_kms_key_id: None,
+            max_attempts: 4,
+            retry_base_delay_ms: 200,
+            request_timeout_secs: 120,
+            multipart_threshold_bytes: 32 * 1024 * 1024,
+            multipart_part_size_bytes: 8 * 1024 * 1024,
+        };
+        let client = ObjectStorageClient::new(settings).unwrap();
+
+        let group_key = client.build_export_key(ExportScope::Group, "g1", "e1", "pdf");
+        assert!(group_key.starts_with("groups/g1/export-e1-"));
+        let user_key = client.build_export_key(ExportScope::User, "u1", "e2", ".csv");
+        assert!(user_key.starts_with("users/u1/export-e2-"));
+        assert!(user_key.ends_with(".csv"));
+    }
+
+    fn status(status: u16) -> RequestFailure {
+        RequestFailure::Status {
+            status,
+            message: "test".into(),
+        }
+    }
+
+    #[test]
+    fn retry_policy_backs_off_only_on_transient_failures() {
+        let policy = RetryPolicy {
+            max_attempts: 4,
+            base_delay: Duration::from_millis(100),
+        };
+        assert_eq!(
+            policy.next_delay(1, &status(503)),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(
+            policy.next_delay(2, &RequestFailure::Timeout),
+            Some(Duration::from_millis(200))
+        );
+        assert_eq!(
+            policy.next_delay(3, &status(429)),
+            Some(Duration::from_millis(400))
+        );
+        // Бюджет попыток исчерпан
+        assert_eq!(policy.next_delay(4, &status(500)), None);
+        // Ошибки клиента повтором не исправить
+        assert_eq!(policy.next_delay(1, &status(403)), None);
+        assert_eq!(
+            policy.next_delay(1, &RequestFailure::Other("bad".into())),
+            None
+        );
+    }
+
+    /// Поддельный HTTP-слой: отдаёт заранее заданные исходы и считает вызовы
+    async fn run_fake(outcomes: Vec<Result<u32, RequestFailure>>) -> (Result<u32>, usize) {
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::ZERO,
+        };
+        let outcomes = std::sync::Mutex::new(outcomes.into_iter());
+        let calls = std::sync::atomic::AtomicUsize::new(0);
+        let result = policy
+            .run("PutObject", || {
+                calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                let outcome = outcomes.lock().unwrap().next().unwrap();
+                async move { outcome }
+            })
+            .await;
+        (result, calls.into_inner())
+    }
+
+    #[tokio::test]
+    async fn retry_policy_runs_until_success_or_budget() {
+        let (result, calls) =
+            run_fake(vec![Err(status(502)), Err(RequestFailure::Timeout), Ok(7)]).await;
+        assert_eq!(result.unwrap(), 7);
+        assert_eq!(calls, 3);
+
+        let (result, calls) = run_fake(vec![Err(status(404)), Ok(1)]).await;
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+
+        let (result, calls) = run_fake(vec![
+            Err(status(500)),
+            Err(status(500)),
+            Err(status(500)),
+            Ok(1),
+        ])
+        .await;
+        let message = result.unwrap_err().to_string();
+        assert!(
+            message.contains("PutObject failed after 3 attempt(s)"),
+            "{message}"
+        );
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn listing_keys_are_unescaped_and_relative() {
+        let body = "<ListBucketResult><Contents><Key>tg/a&amp;b.gz</Key></Contents>\
+            <Contents><Key>tg/c.gz</Key></Contents></ListBucketResult>";
+        assert_eq!(xml_elements(body, "Key"), vec!["tg/a&amp;b.gz", "tg/c.gz"]);
+        assert_eq!(xml_unescape("tg/a&amp;b.gz"), "tg/a&b.gz");
+
+        let settings = ObjectStorageSettings {
+            bucket: "test".into(),
+            region: "ru-central1".into(),
+            endpoint: Some("https://storage.yandexcloud.net".into()),
+            access_key: "key".into(),
+            secret_key: "secret".into(),
+            reports_prefix: "tg".into(),
+            presign_ttl_secs: ObjectStorageSettings::DEFAULT_PRESIGN_TTL_SECS,
+            sse: None,
+            sse_kms_key_id: None,
+            max_attempts: 4,
+            retry_base_delay_ms: 200,
+            request_timeout_secs: 120,
+            multipart_threshold_bytes: 32 * 1024 * 1024,
+            multipart_part_size_bytes: 8 * 1024 * 1024,
+        };
+        let client = ObjectStorageClient::new(settings).unwrap();
+        assert_eq!(client.relative_key("tg/a&b.gz"), "a&b.gz");
+    }
 }
@@ -0,0 +1,232 @@
+//! Общие помощники printpdf: шрифты, текст, линии и залитые прямоугольники.
+//! Координаты - в миллиметрах от левого нижнего угла страницы.
+
+use anyhow::{anyhow, Result};
+use printpdf::{
+    BuiltinFont, Color, FontId, Line, LinePoint, Mm, Op, PaintMode, ParsedFont, PdfDocument,
+    PdfParseErrorSeverity, PdfWarnMsg, Point, Polygon, PolygonRing, Pt, Rgb, TextItem,
+    WindingOrder,
+};
+use tracing::debug;
+
+use crate::models::reporting::ExportLocale;
+
+/// Шрифт текста в PDF: встроенный или вшитый в документ TTF
+#[derive(Debug, Clone)]
+pub(crate) enum PdfFont {
+    Builtin(BuiltinFont),
+    Embedded(FontId),
+}
+
+/// DejaVu Sans из `assets/fonts` — у встроенной Helvetica нет кириллицы
+const DEJAVU_SANS: &[u8] = include_bytes!("../../assets/fonts/DejaVuSans.ttf");
+const DEJAVU_SANS_BOLD: &[u8] = include_bytes!("../../assets/fonts/DejaVuSans-Bold.ttf");
+
+/// Начертания текста одного PDF
+pub(crate) struct PdfFonts {
+    pub regular: PdfFont,
+    pub bold: PdfFont,
+    pub oblique: PdfFont,
+}
+
+impl PdfFonts {
+    /// Для русской выгрузки вшивает DejaVu Sans (курсив заменяется обычным
+    /// начертанием), для английской хватает встроенной Helvetica. Сообщения разбора
+    /// шрифта к документу не относятся и пишутся только в debug
+    pub fn load(document: &mut PdfDocument, locale: ExportLocale) -> Result<Self> {
+        match locale {
+            ExportLocale::En => Ok(Self {
+                regular: PdfFont::Builtin(BuiltinFont::Helvetica),
+                bold: PdfFont::Builtin(BuiltinFont::HelveticaBold),
+                oblique: PdfFont::Builtin(BuiltinFont::HelveticaOblique),
+            }),
+            ExportLocale::Ru => {
+                let mut embed = |bytes: &[u8], name: &str| -> Result<PdfFont> {
+                    let mut messages = Vec::new();
+                    let font = ParsedFont::from_bytes(bytes, 0, &mut messages)
+                        .ok_or_else(|| anyhow!("failed to parse bundled font {name}"))?;
+                    debug!(
+                        font = name,
+                        messages = messages.len(),
+                        "Bundled font parsed"
+                    );
+                    Ok(PdfFont::Embedded(document.add_font(&font)))
+                };
+                let regular = embed(DEJAVU_SANS, "DejaVuSans")?;
+                let bold = embed(DEJAVU_SANS_BOLD, "DejaVuSans-Bold")?;
+                Ok(Self {
+                    oblique: regular.clone(),
+                    regular,
+                    bold,
+                })
+            }
+        }
+    }
+}
+
+/// Предупреждения сохранения, которые влияют на документ. Info-сообщения и пропуск
+/// глифов без контура (пробел, .notdef) printpdf выдаёт при повторном разборе
+/// подмножества шрифта на каждом сохранении
+pub(crate) fn document_warnings(mut warnings: Vec<PdfWarnMsg>) -> Vec<PdfWarnMsg> {
+    warnings.retain(|w| {
+        w.severity != PdfParseErrorSeverity::Info && !w.msg.starts_with("Failed to convert glyph")
+    });
+    warnings
+}
+
+pub(crate) fn accent_color() -> Color {
+    Color::Rgb(Rgb {
+        r: 0.16,
+        g: 0.4,
+        b: 0.69,
+        icc_profile: None,
+    })
+}
+
+pub(crate) fn push_pdf_text(
+    ops: &mut Vec<Op>,
+    pos: Point,
+    font: &PdfFont,
+    font_size: f32,
+    line_height: f32,
+    text: String,
+    color: &Color,
+) {
+    let size = Pt(font_size);
+    let items = vec![TextItem::Text(text)];
+    let (set_font, write_text) = match font {
+        PdfFont::Builtin(font) => (
+            Op::SetFontSizeBuiltinFont { size, font: *font },
+            Op::WriteTextBuiltinFont { items, font: *font },
+        ),
+        PdfFont::Embedded(font) => (
+            Op::SetFontSize {
+                size,
+                font: font.clone(),
+            },
+            Op::WriteText {
+                items,
+                font: font.clone(),
+            },
+        ),
+    };
+    ops.extend([
+        Op::StartTextSection,
+        Op::SetTextCursor { pos },
+        set_font,
+        Op::SetLineHeight {
+            lh: Pt(line_height),
+        },
+        Op::SetFillColor { col: color.clone() },
+        write_text,
+        Op::EndTextSection,
+    ]);
+}
+
+pub(crate) fn push_pdf_line(ops: &mut Vec<Op>, from: (f32, f32), to: (f32, f32)) {
+    ops.push(Op::DrawLine {
+        line: Line {
+            points: vec![
+                LinePoint {
+                    p: Point::new(Mm(from.0), Mm(from.1)),
+                    bezier: false,
+                },
+                LinePoint {
+                    p: Point::new(Mm(to.0), Mm(to.1)),
+                    bezier: false,
+                },
+            ],
+            is_closed: false,
+        },
+    });
+}
+
+pub(crate) fn push_pdf_rect(
+    ops: &mut Vec<Op>,
+    left: f32,
+    bottom: f32,
+    width: f32,
+    height: f32,
+    color: &Color,
+) {
+    if width <= 0.0 || height <= 0.0 {
+        return;
+    }
+    let ring = PolygonRing {
+        points: vec![
+            LinePoint {
+                p: Point::new(Mm(left), Mm(bottom)),
+                bezier: false,
+            },
+            LinePoint {
+                p: Point::new(Mm(left + width), Mm(bottom)),
+                bezier: false,
+            },
+            LinePoint {
+                p: Point::new(Mm(left + width), Mm(bottom + height)),
+                bezier: false,
+            },
+            LinePoint {
+                p: Point::new(Mm(left), Mm(bottom + height)),
+                bezier: false,
+            },
+        ],
+    };
+    let polygon = Polygon {
+        rings: vec![ring],
+        mode: PaintMode::Fill,
+        winding_order: WindingOrder::NonZero,
+    };
+    ops.push(Op::SetFillColor { col: color.clone() });
+    ops.push(Op::DrawPolygon { polygon });
+}
+
+#[cfg(test)]
+mod tests {
+    use printpdf::{PdfPage, PdfSaveOptions};
+
+    use super::*;
+
+    #[test]
+    fn test_ru_pdf_embeds_bundled_font() {
+        let mut document = PdfDocument::new("test");
+        let fonts = PdfFonts::load(&mut document, ExportLocale::Ru).unwrap();
+        assert!(matches!(fonts.regular, PdfFont::Embedded(_)));
+        assert!(matches!(fonts.bold, PdfFont::Embedded(_)));
+
+        let mut ops = Vec::new();
+        push_pdf_text(
+            &mut ops,
+            Point::new(Mm(20.0), Mm(270.0)),
+            &fonts.bold,
+            14.0,
+            16.0,
+            "Отчёт по группе 7А".to_string(),
+            &accent_color(),
+        );
+        let mut warnings = Vec::new();
+        let bytes = document
+            .with_pages(vec![PdfPage::new(Mm(210.0), Mm(297.0), ops)])
+            .save(&PdfSaveOptions::default(), &mut warnings);
+        assert!(bytes.starts_with(b"%PDF"));
+        let warnings = document_warnings(warnings);
+        assert!(warnings.is_empty(), "{warnings:?}");
+
+        let fonts = PdfFonts::load(&mut document, ExportLocale::En).unwrap();
+        assert!(matches!(
+            fonts.regular,
+            PdfFont::Builtin(BuiltinFont::Helvetica)
+        ));
+    }
+
+    #[test]
+    fn test_document_warnings_drop_font_parse_noise() {
+        let warnings = document_warnings(vec![
+            PdfWarnMsg::info(0, 0, "Parsed font tables".to_string()),
+            PdfWarnMsg::warning(0, 0, "Failed to convert glyph 3 to OwnedGlyph".to_string()),
+            PdfWarnMsg::error(1, 4, "Unknown font id".to_string()),
+        ]);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].msg, "Unknown font id");
+    }
+}
@@ -0,0 +1,267 @@
+//! Разрешения ролей.
+//!
+//! Встроенные роли получают разрешения по умолчанию (`default_permissions`), документ
+//! в `role_permissions` их переопределяет; роль без документа и не из `UserRole` не имеет
+//! разрешений и не назначается пользователям. Все переопределения держатся в памяти (`RolePermissionCache` в `AppState`)
+//! не дольше `CACHE_TTL`, запись через `PermissionService` сбрасывает кэш сразу.
+
+use std::collections::{BTreeSet, HashMap};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use futures::TryStreamExt;
+use mongodb::{bson::doc, Database};
+use tokio::sync::RwLock;
+
+use crate::models::{
+    permission::{Permission, RolePermissionsRecord, RolePermissionsResponse},
+    user::UserRole,
+};
+
+pub const ROLE_PERMISSIONS_COLLECTION: &str = "role_permissions";
+/// Сколько держать переопределения в памяти; другие инстансы увидят правку не позже
+const CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Некорректная правка роли (ответ 400)
+#[derive(Debug, thiserror::Error)]
+pub enum RolePermissionsError {
+    #[error("Role name must be 2-32 lowercase letters, digits or underscores")]
+    InvalidRoleName,
+    /// Без `users.manage` у админа ролями больше некому управлять
+    #[error("Role admin must keep users.manage")]
+    AdminLockout,
+}
+
+/// Разрешения встроенных ролей по таблице прав из `docs/rbac.md`
+pub fn default_permissions(role: &str) -> &'static [Permission] {
+    match role {
+        "admin" => &Permission::ALL,
+        "content_admin" | "content_moderator" => &[Permission::ContentModerate],
+        "teacher" => &[Permission::ReportsView],
+        _ => &[],
+    }
+}
+
+fn is_built_in(role: &str) -> bool {
+    UserRole::ALL.iter().any(|known| known.as_str() == role)
+}
+
+/// Роли нет среди встроенных и в `role_permissions` (ответ 400)
+#[derive(Debug, thiserror::Error)]
+#[error("Unknown role: {role}")]
+pub struct UnknownRoleError {
+    pub role: String,
+}
+
+/// Можно ли назначить роль пользователю: встроенная или настроенная через `/admin/roles`
+pub async fn ensure_assignable_role(mongo: &Database, role: &UserRole) -> Result<()> {
+    let UserRole::Custom(name) = role else {
+        return Ok(());
+    };
+    let configured = is_valid_role_name(name)
+        && mongo
+            .collection::<RolePermissionsRecord>(ROLE_PERMISSIONS_COLLECTION)
+            .count_documents(doc! { "_id": name })
+            .await
+            .context("Failed to check role")?
+            > 0;
+    if !configured {
+        return Err(UnknownRoleError { role: name.clone() }.into());
+    }
+    Ok(())
+}
+
+fn is_valid_role_name(role: &str) -> bool {
+    (2..=32).contains(&role.len())
+        && role.starts_with(|c: char| c.is_ascii_lowercase())
+        && role
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+type Overrides = HashMap<String, Vec<Permission>>;
+
+/// Кэш переопределений из `role_permissions`. Экземпляр живёт в `AppState`
+#[derive(Default)]
+pub struct RolePermissionCache {
+    overrides: RwLock<Option<(Instant, Overrides)>>,
+}
+
+impl RolePermissionCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn permissions(&self, mongo: &Database, role: &str) -> Result<Vec<Permission>> {
+        let overrides = self.overrides(mongo).await?;
+        Ok(overrides
+            .get(role)
+            .cloned()
+            .unwrap_or_else(|| default_permissions(role).to_vec()))
+    }
+
+    /// Есть ли у роли разрешение. Если MongoDB недоступна, действуют разрешения
+    /// по умолчанию - так же, как до появления настраиваемых ролей
+    pub async fn has_permission(
+        &self,
+        mongo: &Database,
+        role: &str,
+        permission: Permission,
+    ) -> bool {
+        match self.permissions(mongo, role).await {
+            Ok(permissions) => permissions.contains(&permission),
+            Err(err) => {
+                tracing::warn!("Role permissions lookup failed, using defaults: {:#}", err);
+                default_permissions(role).contains(&permission)
+            }
+        }
+    }
+
+    pub async fn invalidate(&self) {
+        *self.overrides.write().await = None;
+    }
+
+    async fn overrides(&self, mongo: &Database) -> Result<Overrides> {
+        if let Some((loaded_at, overrides)) = self.overrides.read().await.as_ref() {
+            if loaded_at.elapsed() < CACHE_TTL {
+                return Ok(overrides.clone());
+            }
+        }
+
+        let records: Vec<RolePermissionsRecord> = mongo
+            .collection::<RolePermissionsRecord>(ROLE_PERMISSIONS_COLLECTION)
+            .find(doc! {})
+            .await
+            .context("Failed to load role permissions")?
+            .try_collect()
+            .await
+            .context("Failed to read role permissions")?;
+        let overrides: Overrides = records
+            .into_iter()
+            .map(|record| (record.role, record.permissions))
+            .collect();
+        *self.overrides.write().await = Some((Instant::now(), overrides.clone()));
+        Ok(overrides)
+    }
+}
+
+pub struct PermissionService<'a> {
+    mongo: Database,
+    cache: &'a RolePermissionCache,
+}
+
+impl<'a> PermissionService<'a> {
+    pub fn new(mongo: Database, cache: &'a RolePermissionCache) -> Self {
+        Self { mongo, cache }
+    }
+
+    /// Встроенные роли и роли с переопределениями, по имени
+    pub async fn list_roles(&self) -> Result<Vec<RolePermissionsResponse>> {
+        let overrides = self.cache.overrides(&self.mongo).await?;
+        let roles: BTreeSet<&str> = UserRole::ALL
+            .iter()
+            .map(UserRole::as_str)
+            .chain(overrides.keys().map(String::as_str))
+            .collect();
+        Ok(roles
+            .into_iter()
+            .map(|role| Self::response(role, overrides.get(role)))
+            .collect())
+    }
+
+    pub async fn set_role_permissions(
+        &self,
+        role: &str,
+        permissions: &[Permission],
+        actor_id: &str,
+    ) -> Result<RolePermissionsResponse> {
+        if !is_valid_role_name(role) {
+            return Err(RolePermissionsError::InvalidRoleName.into());
+        }
+        let permissions: Vec<Permission> = permissions
+            .iter()
+            .copied()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        if role == UserRole::Admin.as_str() && !permissions.contains(&Permission::UsersManage) {
+            return Err(RolePermissionsError::AdminLockout.into());
+        }
+
+        let record = RolePermissionsRecord {
+            role: role.to_string(),
+            permissions,
+            updated_at: Utc::now(),
+            updated_by: Some(actor_id.to_string()),
+        };
+        self.mongo
+            .collection::<RolePermissionsRecord>(ROLE_PERMISSIONS_COLLECTION)
+            .replace_one(doc! { "_id": role }, &record)
+            .upsert(true)
+            .await
+            .context("Failed to save role permissions")?;
+        self.cache.invalidate().await;
+
+        Ok(Self::response(role, Some(&record.permissions)))
+    }
+
+    fn response(role: &str, overridden: Option<&Vec<Permission>>) -> RolePermissionsResponse {
+        RolePermissionsResponse {
+            role: role.to_string(),
+            permissions: overridden
+                .cloned()
+                .unwrap_or_else(|| default_permissions(role).to_vec()),
+            built_in: is_built_in(role),
+            customized: overridden.is_some(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn built_in_roles_keep_previous_access() {
+        // Права ролей из docs/rbac.md: контент-админ - только контент, учитель - свой кабинет
+        assert_eq!(default_permissions("admin"), &Permission::ALL);
+        assert!(default_permissions("content_admin").contains(&Permission::ContentModerate));
+        assert!(!default_permissions("content_admin").contains(&Permission::UsersManage));
+        assert_eq!(default_permissions("teacher"), &[Permission::ReportsView]);
+        assert!(default_permissions("student").is_empty());
+        assert!(default_permissions("unknown_role").is_empty());
+        assert!(is_built_in("content_moderator"));
+        assert!(!is_built_in("template_editor"));
+    }
+
+    #[test]
+    fn role_names_are_validated() {
+        assert!(is_valid_role_name("template_editor"));
+        assert!(is_valid_role_name("qa2"));
+        assert!(!is_valid_role_name("a"));
+        assert!(!is_valid_role_name("2fast"));
+        assert!(!is_valid_role_name("Editor"));
+        assert!(!is_valid_role_name("content.moderator"));
+        assert!(!is_valid_role_name(&"x".repeat(33)));
+    }
+
+    #[test]
+    fn custom_roles_round_trip_as_plain_strings() {
+        let role: UserRole = serde_json::from_value(serde_json::json!("content_admin")).unwrap();
+        assert_eq!(role, UserRole::ContentAdmin);
+        let role: UserRole = serde_json::from_value(serde_json::json!("template_editor")).unwrap();
+        assert_eq!(role, UserRole::Custom("template_editor".to_string()));
+        assert!(!role.is_built_in());
+        assert_eq!(role.as_str(), "template_editor");
+        assert_eq!(serde_json::to_value(&role).unwrap(), "template_editor");
+    }
+
+    #[test]
+    fn permissions_use_dotted_names() {
+        for permission in Permission::ALL {
+            let json = serde_json::to_value(permission).unwrap();
+            assert_eq!(json, permission.as_str());
+        }
+    }
+}
@@ -0,0 +1,225 @@
+//! Поиск персональных данных в шаблонах заданий по шаблонам из админки
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use mongodb::bson::{Bson, Document};
+use mongodb::Database;
+use regex::Regex;
+use tokio::sync::RwLock;
+
+use crate::models::system_settings::{PiiSettings, PiiSeverity};
+use crate::services::system_settings_service::SystemSettingsService;
+
+/// Сколько держать скомпилированные шаблоны в памяти; другие инстансы увидят правку не позже
+const PII_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Совпадение шаблона ПДн в поле шаблона задания
+#[derive(Debug, Clone, PartialEq)]
+pub struct PiiMatch {
+    /// Путь к полю: `content`, `metadata.title`, `params.answers.0`
+    pub path: String,
+    pub pattern: String,
+    pub severity: PiiSeverity,
+}
+
+impl PiiMatch {
+    /// Запись для `pii_flags` и текста ошибки: `metadata.title:email`
+    pub fn flag(&self) -> String {
+        format!("{}:{}", self.path, self.pattern)
+    }
+}
+
+struct CompiledPattern {
+    name: String,
+    severity: PiiSeverity,
+    regex: Regex,
+}
+
+pub struct PiiScanner {
+    patterns: Vec<CompiledPattern>,
+}
+
+impl Default for PiiScanner {
+    fn default() -> Self {
+        Self::compile(&PiiSettings::default())
+    }
+}
+
+impl PiiScanner {
+    /// Шаблоны проверяются при сохранении; правленные в базе вручную и некорректные
+    /// пропускаются с предупреждением, чтобы один шаблон не выключал остальные
+    pub fn compile(settings: &PiiSettings) -> Self {
+        let patterns = settings
+            .patterns
+            .iter()
+            .filter_map(|pattern| match pattern.compile() {
+                Ok(regex) => Some(CompiledPattern {
+                    name: pattern.name.clone(),
+                    severity: pattern.severity,
+                    regex,
+                }),
+                Err(err) => {
+                    tracing::warn!("Skipping invalid PII pattern '{}': {}", pattern.name, err);
+                    None
+                }
+            })
+            .collect();
+        Self { patterns }
+    }
+
+    pub fn scan_text(&self, path: &str, text: &str) -> Vec<PiiMatch> {
+        let mut matches = Vec::new();
+        self.scan_into(path, text, &mut matches);
+        matches
+    }
+
+    /// Все строки документа, включая вложенные документы и массивы
+    pub fn scan_document(&self, path: &str, document: &Document) -> Vec<PiiMatch> {
+        let mut matches = Vec::new();
+        for (key, value) in document {
+            self.scan_bson(&join_path(path, key), value, &mut matches);
+        }
+        matches
+    }
+
+    fn scan_bson(&self, path: &str, value: &Bson, matches: &mut Vec<PiiMatch>) {
+        match value {
+            Bson::String(text) => self.scan_into(path, text, matches),
+            Bson::Document(document) => {
+                for (key, value) in document {
+                    self.scan_bson(&join_path(path, key), value, matches);
+                }
+            }
+            Bson::Array(items) => {
+                for (index, value) in items.iter().enumerate() {
+                    self.scan_bson(&join_path(path, &index.to_string()), value, matches);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn scan_into(&self, path: &str, text: &str, matches: &mut Vec<PiiMatch>) {
+        for pattern in &self.patterns {
+            if pattern.regex.is_match(text) {
+                matches.push(PiiMatch {
+                    path: path.to_string(),
+                    pattern: pattern.name.clone(),
+                    severity: pattern.severity,
+                });
+            }
+        }
+    }
+}
+
+fn join_path(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", prefix, key)
+    }
+}
+
+/// Скомпилированные шаблоны ПДн. Экземпляр живёт в `AppState`,
+/// `PUT /admin/settings/pii-patterns` сбрасывает его сразу
+#[derive(Default)]
+pub struct PiiPatternCache {
+    scanner: RwLock<Option<(Instant, Arc<PiiScanner>)>>,
+}
+
+impl PiiPatternCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Текущие шаблоны. Если MongoDB недоступна или шаблоны не сохранены,
+    /// действуют встроенные (email и телефон)
+    pub async fn get(&self, mongo: &Database) -> Arc<PiiScanner> {
+        if let Some((loaded_at, scanner)) = self.scanner.read().await.as_ref() {
+            if loaded_at.elapsed() < PII_CACHE_TTL {
+                return scanner.clone();
+            }
+        }
+
+        let settings = match SystemSettingsService::new(mongo.clone())
+            .get_pii_settings()
+            .await
+        {
+            Ok(settings) => settings.unwrap_or_default(),
+            Err(err) => {
+                tracing::warn!("PII pattern lookup failed, using defaults: {:#}", err);
+                return Arc::new(PiiScanner::default());
+            }
+        };
+        let scanner = Arc::new(PiiScanner::compile(&settings));
+        *self.scanner.write().await = Some((Instant::now(), scanner.clone()));
+        scanner
+    }
+
+    pub async fn invalidate(&self) {
+        *self.scanner.write().await = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::system_settings::PiiPattern;
+    use mongodb::bson::doc;
+
+    fn flags(matches: &[PiiMatch]) -> Vec<String> {
+        matches.iter().map(PiiMatch::flag).collect()
+    }
+
+    #[test]
+    fn scans_nested_documents_and_arrays() {
+        let scanner = PiiScanner::default();
+        let template = doc! {
+            "metadata": {
+                "title": "Пишите на teacher@school.ru",
+                "tags": ["орфография", "звоните 89991234567"],
+                "order": 1,
+            },
+            "params": { "answers": [{ "text": "чисто" }, { "text": "a@b.cd" }] },
+        };
+
+        assert_eq!(
+            flags(&scanner.scan_document("", &template)),
+            vec![
+                "metadata.title:email",
+                "metadata.tags.1:phone",
+                "params.answers.1.text:email",
+            ]
+        );
+        assert!(scanner.scan_text("content", "Пол..гать").is_empty());
+    }
+
+    #[test]
+    fn custom_patterns_keep_their_severity() {
+        let scanner = PiiScanner::compile(&PiiSettings {
+            patterns: vec![
+                PiiPattern {
+                    name: "student_id".to_string(),
+                    regex: r"\bST-\d{6}\b".to_string(),
+                    severity: PiiSeverity::Warn,
+                },
+                PiiPattern {
+                    name: "broken".to_string(),
+                    regex: "[unclosed".to_string(),
+                    severity: PiiSeverity::Block,
+                },
+            ],
+        });
+
+        let matches = scanner.scan_text("content", "Ученик ST-123456, почта a@b.cd");
+        assert_eq!(
+            matches,
+            vec![PiiMatch {
+                path: "content".to_string(),
+                pattern: "student_id".to_string(),
+                severity: PiiSeverity::Warn,
+            }]
+        );
+    }
+}
@@ -0,0 +1,382 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use mongodb::{
+    bson::{doc, Document},
+    Database,
+};
+use redis::aio::ConnectionManager;
+
+use crate::{
+    middlewares::auth::JwtClaims,
+    models::prefetch::{PrefetchManifest, ResourceDescriptor, PREFETCH_MANIFEST_VERSION},
+};
+
+/// Максимум дескрипторов в одном манифесте
+pub const MAX_PREFETCH_RESOURCES: usize = 8;
+/// Сколько секунд клиент и прокси могут кэшировать сам манифест
+pub const MANIFEST_MAX_AGE_SECS: u64 = 60;
+
+/// Указатель на активную сессию ученика (ставится при создании сессии)
+pub fn active_session_key(user_id: &str) -> String {
+    format!("active_session:{}", user_id)
+}
+
+/// Дешёвые признаки, по которым ранжируются ресурсы
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PrefetchSignals {
+    pub active_session_id: Option<String>,
+    pub has_course_in_progress: bool,
+    pub last_completed_session_id: Option<String>,
+    pub group_ids: Vec<String>,
+}
+
+/// Источник признаков. Реализация обязана обходиться проверками существования
+/// по индексированным полям или готовыми счётчиками - без агрегаций.
+#[async_trait]
+pub trait PrefetchSignalSource: Send + Sync {
+    async fn active_session_id(&self, user_id: &str) -> Result<Option<String>>;
+    async fn has_course_in_progress(&self, user_id: &str) -> Result<bool>;
+    async fn last_completed_session_id(&self, user_id: &str) -> Result<Option<String>>;
+}
+
+/// Признаки из Redis и MongoDB
+pub struct StoreSignalSource {
+    mongo: Database,
+    redis: ConnectionManager,
+}
+
+impl StoreSignalSource {
+    pub fn new(mongo: Database, redis: ConnectionManager) -> Self {
+        Self { mongo, redis }
+    }
+}
+
+#[async_trait]
+impl PrefetchSignalSource for StoreSignalSource {
+    async fn active_session_id(&self, user_id: &str) -> Result<Option<String>> {
+        let mut conn = self.redis.clone();
+        redis::cmd("GET")
+            .arg(active_session_key(user_id))
+            .query_async(&mut conn)
+            .await
+            .context("Failed to read active session pointer")
+    }
+
+    async fn has_course_in_progress(&self, user_id: &str) -> Result<bool> {
+        let row = self
+            .mongo
+            .collection::<Document>("progress_summary_v2")
+            .find_one(doc! { "user_id": user_id, "percentage": { "$lt": 100.0 } })
+            .projection(doc! { "_id": 1 })
+            .await
+            .context("Failed to check course progress")?;
+        Ok(row.is_some())
+    }
+
+    async fn last_completed_session_id(&self, user_id: &str) -> Result<Option<String>> {
+        let row = self
+            .mongo
+            .collection::<Document>("sessions")
+            .find_one(doc! { "user_id": user_id, "completed_at": { "$exists": true } })
+            .sort(doc! { "completed_at": -1 })
+            .projection(doc! { "_id": 1 })
+            .await
+            .context("Failed to look up last completed session")?;
+        Ok(row.and_then(|doc| doc.get_str("_id").ok().map(str::to_string)))
+    }
+}
+
+/// Собрать признаки для пользователя. Манифест - только подсказка, поэтому
+/// ошибка отдельной проверки не валит запрос, а считается отсутствием признака.
+pub async fn collect_signals(
+    source: &dyn PrefetchSignalSource,
+    claims: &JwtClaims,
+) -> PrefetchSignals {
+    let mut signals = PrefetchSignals {
+        group_ids: claims.group_ids.clone(),
+        ..PrefetchSignals::default()
+    };
+    if claims.role != "student" {
+        return signals;
+    }
+
+    let user_id = claims.sub.as_str();
+    let (active, in_progress, last_completed) = tokio::join!(
+        source.active_session_id(user_id),
+        source.has_course_in_progress(user_id),
+        source.last_completed_session_id(user_id),
+    );
+
+    signals.active_session_id = active.unwrap_or_else(|err| {
+        tracing::warn!(error = %err, "prefetch signal lookup failed");
+        None
+    });
+    signals.has_course_in_progress = in_progress.unwrap_or_else(|err| {
+        tracing::warn!(error = %err, "prefetch signal lookup failed");
+        false
+    });
+    signals.last_completed_session_id = last_completed.unwrap_or_else(|err| {
+        tracing::warn!(error = %err, "prefetch signal lookup failed");
+        None
+    });
+    signals
+}
+
+pub async fn build_manifest(
+    source: &dyn PrefetchSignalSource,
+    claims: &JwtClaims,
+) -> PrefetchManifest {
+    let signals = collect_signals(source, claims).await;
+    PrefetchManifest {
+        version: PREFETCH_MANIFEST_VERSION,
+        role: claims.role.clone(),
+        max_age_secs: MANIFEST_MAX_AGE_SECS,
+        resources: rank_resources(&claims.role, &signals, MAX_PREFETCH_RESOURCES),
+    }
+}
+
+/// Ранжировать ресурсы для роли. Чистая функция: результат зависит только от аргументов.
+pub fn rank_resources(
+    role: &str,
+    signals: &PrefetchSignals,
+    limit: usize,
+) -> Vec<ResourceDescriptor> {
+    let mut candidates: Vec<(u32, ResourceDescriptor)> = Vec::new();
+
+    match role {
+        "student" => {
+            if let Some(session_id) = &signals.active_session_id {
+                candidates.push((
+                    100,
+                    ResourceDescriptor::api("/api/v1/sessions/{id}", 15)
+                        .with_param("id", session_id.clone()),
+                ));
+            }
+            let catalog_score = if signals.has_course_in_progress {
+                80
+            } else {
+                50
+            };
+            candidates.push((
+                catalog_score,
+                ResourceDescriptor::api("/api/v1/student/courses", 300),
+            ));
+            if let Some(session_id) = &signals.last_completed_session_id {
+                candidates.push((
+                    60,
+                    ResourceDescriptor::api("/api/v1/sessions/{id}", 600)
+                        .with_param("id", session_id.clone()),
+                ));
+            }
+            candidates.push((40, ResourceDescriptor::api("/api/v1/student/stats", 60)));
+        }
+        "teacher" => {
+            candidates.push((90, ResourceDescriptor::api("/api/v1/teacher/groups", 300)));
+            // Группы в порядке из токена; каждая следующая чуть ниже предыдущей
+            for (index, group_id) in signals.group_ids.iter().enumerate() {
+                let step = (index as u32).min(20);
+                candidates.push((
+                    70 - step,
+                    ResourceDescriptor::api("/api/v1/teacher/groups/{group_id}/students", 120)
+                        .with_param("group_id", group_id.clone()),
+                ));
+                candidates.push((
+                    45 - step,
+                    ResourceDescriptor::api("/stats/groups/{id}", 300)
+                        .with_param("id", group_id.clone()),
+                ));
+            }
+        }
+        "admin" => {
+            candidates.push((60, ResourceDescriptor::api("/admin/system/metrics", 30)));
+        }
+        _ => {}
+    }
+
+    candidates.push((10, ResourceDescriptor::api("/api/feature-flags", 300)));
+
+    // Сортировка стабильна: при равном приоритете сохраняется порядок добавления
+    candidates.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    candidates
+        .into_iter()
+        .take(limit)
+        .map(|(_, descriptor)| descriptor)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn claims(role: &str, group_ids: &[&str]) -> JwtClaims {
+        JwtClaims {
+            sub: "65a1b2c3d4e5f60718293a4b".to_string(),
+            role: role.to_string(),
+            group_ids: group_ids.iter().map(|id| id.to_string()).collect(),
+            exp: 0,
+            iat: 0,
+        }
+    }
+
+    fn routes(resources: &[ResourceDescriptor]) -> Vec<&str> {
+        resources.iter().map(|r| r.route.as_str()).collect()
+    }
+
+    #[derive(Default)]
+    struct CountingSource {
+        active: Option<String>,
+        lookups: AtomicUsize,
+        fail: bool,
+    }
+
+    impl CountingSource {
+        fn lookup(&self) -> Result<()> {
+            self.lookups.fetch_add(1, Ordering::SeqCst);
+            if self.fail {
+                Err(anyhow!("store unavailable"))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    #[async_trait]
+    impl PrefetchSignalSource for CountingSource {
+        async fn active_session_id(&self, _user_id: &str) -> Result<Option<String>> {
+            self.lookup()?;
+            Ok(self.active.clone())
+        }
+
+        async fn has_course_in_progress(&self, _user_id: &str) -> Result<bool> {
+            self.lookup()?;
+            Ok(true)
+        }
+
+        async fn last_completed_session_id(&self, _user_id: &str) -> Result<Option<String>> {
+            self.lookup()?;
+            Ok(Some("finished-session".to_string()))
+        }
+    }
+
+    #[test]
+    fn active_session_is_ranked_first_for_student() {
+        let signals = PrefetchSignals {
+            active_session_id: Some("live-session".to_string()),
+            has_course_in_progress: true,
+            last_completed_session_id: Some("finished-session".to_string()),
+            ..PrefetchSignals::default()
+        };
+
+        let resources = rank_resources("student", &signals, MAX_PREFETCH_RESOURCES);
+
+        assert_eq!(resources[0].route, "/api/v1/sessions/{id}");
+        assert_eq!(resources[0].params["id"], "live-session");
+        assert_eq!(resources[1].route, "/api/v1/student/courses");
+        assert_eq!(resources[2].params["id"], "finished-session");
+    }
+
+    #[test]
+    fn student_without_activity_gets_catalog_first() {
+        let resources = rank_resources(
+            "student",
+            &PrefetchSignals::default(),
+            MAX_PREFETCH_RESOURCES,
+        );
+
+        assert_eq!(
+            routes(&resources),
+            vec![
+                "/api/v1/student/courses",
+                "/api/v1/student/stats",
+                "/api/feature-flags"
+            ]
+        );
+    }
+
+    #[test]
+    fn teacher_gets_group_dashboards() {
+        let signals = PrefetchSignals {
+            group_ids: vec!["g1".to_string(), "g2".to_string()],
+            ..PrefetchSignals::default()
+        };
+
+        let resources = rank_resources("teacher", &signals, MAX_PREFETCH_RESOURCES);
+
+        assert_eq!(resources[0].route, "/api/v1/teacher/groups");
+        assert_eq!(resources[1].params["group_id"], "g1");
+        assert_eq!(resources[2].params["group_id"], "g2");
+        assert!(resources
+            .iter()
+            .any(|r| r.route == "/stats/groups/{id}" && r.params["id"] == "g1"));
+    }
+
+    #[test]
+    fn manifest_is_capped() {
+        let signals = PrefetchSignals {
+            group_ids: (0..30).map(|i| format!("group-{}", i)).collect(),
+            ..PrefetchSignals::default()
+        };
+
+        let resources = rank_resources("teacher", &signals, MAX_PREFETCH_RESOURCES);
+        assert_eq!(resources.len(), MAX_PREFETCH_RESOURCES);
+        assert_eq!(resources[0].route, "/api/v1/teacher/groups");
+
+        assert_eq!(rank_resources("teacher", &signals, 2).len(), 2);
+    }
+
+    #[test]
+    fn ranking_is_pure() {
+        let signals = PrefetchSignals {
+            active_session_id: Some("live-session".to_string()),
+            has_course_in_progress: false,
+            last_completed_session_id: None,
+            group_ids: vec!["g1".to_string()],
+        };
+        let snapshot = signals.clone();
+
+        let first = rank_resources("student", &signals, MAX_PREFETCH_RESOURCES);
+        let second = rank_resources("student", &signals, MAX_PREFETCH_RESOURCES);
+
+        assert_eq!(first, second);
+        assert_eq!(signals, snapshot);
+    }
+
+    #[tokio::test]
+    async fn student_manifest_performs_only_cheap_lookups() {
+        let source = CountingSource {
+            active: Some("live-session".to_string()),
+            ..CountingSource::default()
+        };
+
+        let manifest = build_manifest(&source, &claims("student", &[])).await;
+
+        assert_eq!(source.lookups.load(Ordering::SeqCst), 3);
+        assert_eq!(manifest.version, PREFETCH_MANIFEST_VERSION);
+        assert_eq!(manifest.resources[0].params["id"], "live-session");
+    }
+
+    #[tokio::test]
+    async fn teacher_manifest_uses_token_claims_only() {
+        let source = CountingSource::default();
+
+        let manifest = build_manifest(&source, &claims("teacher", &["g1"])).await;
+
+        assert_eq!(source.lookups.load(Ordering::SeqCst), 0);
+        assert_eq!(manifest.role, "teacher");
+        assert_eq!(manifest.resources[1].params["group_id"], "g1");
+    }
+
+    #[tokio::test]
+    async fn failed_lookups_degrade_to_defaults() {
+        let source = CountingSource {
+            fail: true,
+            ..CountingSource::default()
+        };
+
+        let manifest = build_manifest(&source, &claims("student", &[])).await;
+
+        assert_eq!(manifest.resources[0].route, "/api/v1/student/courses");
+    }
+}
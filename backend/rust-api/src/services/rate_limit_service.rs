@@ -0,0 +1,123 @@
+use anyhow::{Context, Result};
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+
+use crate::config::{RateLimitAlgorithm, RateLimitSettings};
+use crate::middlewares::rate_limit::window_cutoff;
+use crate::models::rate_limit::{
+    storage_key, RateLimitCounter, RateLimitNamespace, RateLimitSubject,
+};
+
+/// Просмотр и сброс счётчиков лимитеров для поддержки
+pub struct RateLimitService {
+    redis: ConnectionManager,
+    settings: RateLimitSettings,
+}
+
+impl RateLimitService {
+    pub fn new(redis: ConnectionManager, settings: RateLimitSettings) -> Self {
+        Self { redis, settings }
+    }
+
+    /// Все известные счётчики для ключа: он может быть и id пользователя, и IP
+    fn candidates(key: &str) -> Vec<(RateLimitNamespace, RateLimitSubject)> {
+        let subjects = [
+            RateLimitSubject::User(key.to_string()),
+            RateLimitSubject::Ip(key.to_string()),
+        ];
+        RateLimitNamespace::ALL
+            .iter()
+            .flat_map(|ns| subjects.iter().map(move |subject| (*ns, subject.clone())))
+            .collect()
+    }
+
+    /// Активные счётчики по текущему алгоритму
+    pub async fn inspect(&self, key: &str) -> Result<Vec<RateLimitCounter>> {
+        let mut conn = self.redis.clone();
+        let algorithm = self.settings.algorithm;
+        let now_ms = chrono::Utc::now().timestamp_millis().max(0) as u64;
+        let mut counters = Vec::new();
+
+        for (namespace, subject) in Self::candidates(key) {
+            let window = namespace.window(&self.settings, &subject);
+            let redis_key = storage_key(&namespace.key(&subject), algorithm);
+
+            let count: u32 = match algorithm {
+                RateLimitAlgorithm::Fixed => conn
+                    .get::<_, Option<u32>>(&redis_key)
+                    .await
+                    .with_context(|| format!("Failed to read {}", redis_key))?
+                    .unwrap_or(0),
+                RateLimitAlgorithm::Sliding => {
+                    let cutoff = window_cutoff(now_ms, window.window_secs * 1000);
+                    conn.zcount(&redis_key, format!("({}", cutoff), "+inf")
+                        .await
+                        .with_context(|| format!("Failed to read {}", redis_key))?
+                }
+            };
+            if count == 0 {
+                continue;
+            }
+
+            let ttl_secs: i64 = conn
+                .ttl(&redis_key)
+                .await
+                .with_context(|| format!("Failed to read TTL of {}", redis_key))?;
+
+            counters.push(RateLimitCounter {
+                namespace,
+                scope: subject.scope(),
+                redis_key,
+                count,
+                limit: window.limit,
+                window_secs: window.window_secs,
+                ttl_secs,
+            });
+        }
+
+        Ok(counters)
+    }
+
+    /// Сбросить счётчики ключа во всех пространствах имён. Удаляются ключи
+    /// обоих алгоритмов, чтобы после отката не остались старые блокировки.
+    pub async fn reset(&self, key: &str) -> Result<Vec<RateLimitCounter>> {
+        let cleared = self.inspect(key).await?;
+
+        let keys: Vec<String> = Self::candidates(key)
+            .iter()
+            .flat_map(|(namespace, subject)| {
+                let base = namespace.key(subject);
+                [
+                    storage_key(&base, RateLimitAlgorithm::Fixed),
+                    storage_key(&base, RateLimitAlgorithm::Sliding),
+                ]
+            })
+            .collect();
+
+        let mut conn = self.redis.clone();
+        let _: u64 = conn
+            .del(&keys)
+            .await
+            .context("Failed to delete rate limit keys")?;
+
+        Ok(cleared)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidates_cover_every_namespace_and_scope() {
+        let keys: Vec<String> = RateLimitService::candidates("10.0.0.1")
+            .iter()
+            .map(|(ns, subject)| ns.key(subject))
+            .collect();
+
+        assert_eq!(keys.len(), RateLimitNamespace::ALL.len() * 2);
+        assert!(keys.contains(&"ratelimit:login:ip:10.0.0.1".to_string()));
+        assert!(keys.contains(&"ratelimit:sessions:user:10.0.0.1".to_string()));
+        assert!(keys.contains(&"ratelimit:admin:ip:10.0.0.1".to_string()));
+    }
+}
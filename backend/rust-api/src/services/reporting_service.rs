@@ -1,28 +1,52 @@
+use std::collections::{HashMap, HashSet};
 use std::time::Duration;
 
 use anyhow::{anyhow, Context, Result};
-use chrono::{Duration as ChronoDuration, Utc};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
 use futures::TryStreamExt;
 use mongodb::{
     bson::{doc, from_document, oid::ObjectId, to_bson, Bson, Document},
-    Collection, Database,
+    options::ReturnDocument,
+    Collection, Cursor, Database,
 };
 use redis::aio::ConnectionManager;
 
+use crate::utils::mongo_retry::retry_idempotent_write;
 use crate::utils::time::chrono_to_bson;
 
 use crate::{
     middlewares::auth::JwtClaims,
     models::{
+        answer::AttemptRecord,
+        group::GroupHealthStats,
+        permission::Permission,
         reporting::{
-            ExportStatus, LeaderboardDocument, LeaderboardEntry, LeaderboardScope,
-            MaterializedStat, NewReportExport, ReportExport, StatType,
+            ExportSchedule, ExportScope, ExportStatus, LeaderboardDocument, LeaderboardEntry,
+            LeaderboardScope, MaterializedStat, NewReportExport, ReportExport, StatType, TimeRange,
+            TopicGroupStats,
         },
         ProgressSummary,
     },
+    services::{object_storage::ObjectStorageClient, permission_service::RolePermissionCache},
 };
 use serde::Deserialize;
 
+/// Redis-ключ, куда export worker пишет время последнего тика
+pub const EXPORT_WORKER_HEARTBEAT_KEY: &str = "export_worker:heartbeat";
+
+/// Подписывает ссылки на скачивание готовых выгрузок.
+///
+/// По умолчанию это объектное хранилище; в тестах подменяется заглушкой.
+pub trait ExportLinkSigner: Send + Sync {
+    fn presigned_download_url(&self, key: &str, ttl: Duration) -> Result<String>;
+}
+
+impl ExportLinkSigner for ObjectStorageClient {
+    fn presigned_download_url(&self, key: &str, ttl: Duration) -> Result<String> {
+        self.generate_presigned_download_url(key, ttl)
+    }
+}
+
 pub struct ReportingService {
     mongo: Database,
     redis: ConnectionManager,
@@ -47,20 +71,46 @@ impl ReportingService {
         self.redis.clone()
     }
 
-    pub fn guard_group_access(&self, claims: &JwtClaims, group_id: &ObjectId) -> Result<()> {
-        if claims.role == "admin" {
+    /// Доступ к группе: роль с `users.manage` - к любой, с `reports.view` - к группам
+    /// из токена и к тем, где пользователь куратор (назначение куратором не перевыпускает токен)
+    pub async fn guard_group_access(
+        &self,
+        permissions: &RolePermissionCache,
+        claims: &JwtClaims,
+        group_id: &ObjectId,
+    ) -> Result<()> {
+        if permissions
+            .has_permission(&self.mongo, &claims.role, Permission::UsersManage)
+            .await
+        {
             return Ok(());
         }
 
-        if claims.role != "teacher" {
+        if !permissions
+            .has_permission(&self.mongo, &claims.role, Permission::ReportsView)
+            .await
+        {
             return Err(anyhow!("Forbidden"));
         }
 
-        let allowed = claims
+        let in_token = claims
             .group_ids
             .iter()
             .any(|gid| gid == &group_id.to_string());
-        if allowed {
+        if in_token {
+            return Ok(());
+        }
+
+        let Ok(teacher_id) = ObjectId::parse_str(&claims.sub) else {
+            return Err(anyhow!("Forbidden"));
+        };
+        let curated = self
+            .mongo
+            .collection::<Document>("groups")
+            .count_documents(doc! { "_id": group_id, "curatorId": teacher_id })
+            .await
+            .context("Failed to check group curator")?;
+        if curated > 0 {
             Ok(())
         } else {
             Err(anyhow!("Forbidden"))
@@ -94,6 +144,127 @@ impl ReportingService {
             .map_err(|e| anyhow!("Progress summary query failed: {}", e))
     }
 
+    /// Сколько подсказок ученик взял за всё время и сколько баллов они стоили
+    pub async fn user_hint_totals(&self, user_id: &ObjectId) -> Result<HintTotals> {
+        let collection = self.mongo.collection::<Document>("hint_records");
+        let pipeline = vec![
+            doc! { "$match": { "user_id": user_id.to_hex() } },
+            doc! {
+                "$group": {
+                    "_id": Bson::Null,
+                    "hints": { "$sum": 1 },
+                    "cost": { "$sum": "$cost" }
+                }
+            },
+        ];
+
+        let mut cursor = collection
+            .aggregate(pipeline)
+            .await
+            .context("Failed to aggregate hint records")?;
+        let Some(row) = cursor
+            .try_next()
+            .await
+            .map_err(|e| anyhow!("Hint records cursor failure: {}", e))?
+        else {
+            return Ok(HintTotals::default());
+        };
+
+        Ok(HintTotals {
+            hints: bson_number(row.get("hints")) as u64,
+            cost: bson_number(row.get("cost")) as i64,
+        })
+    }
+
+    /// Ученики группы для сырой выгрузки: hex id → имя
+    pub async fn group_student_names(
+        &self,
+        group_id: &ObjectId,
+    ) -> Result<HashMap<String, String>> {
+        let mut cursor = self
+            .mongo
+            .collection::<Document>("users")
+            .find(doc! { "group_ids": group_id.to_hex(), "role": "student" })
+            .projection(doc! { "_id": 1, "name": 1 })
+            .await
+            .context("Failed to query group students")?;
+
+        let mut students = HashMap::new();
+        while let Some(user) = cursor
+            .try_next()
+            .await
+            .context("Failed to read group student")?
+        {
+            if let Ok(user_id) = user.get_object_id("_id") {
+                let name = user.get_str("name").unwrap_or_default().to_string();
+                students.insert(user_id.to_hex(), name);
+            }
+        }
+        Ok(students)
+    }
+
+    /// Средняя точность (0-100) каждого ученика группы по всем уровням.
+    /// Ученики без прогресса в результат не попадают
+    pub async fn group_student_accuracy(&self, group_id: &ObjectId) -> Result<Vec<f64>> {
+        let student_ids: Vec<String> = self
+            .group_student_names(group_id)
+            .await?
+            .into_keys()
+            .collect();
+        if student_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let pipeline = vec![
+            doc! { "$match": { "user_id": { "$in": student_ids } } },
+            doc! {
+                "$group": {
+                    "_id": "$user_id",
+                    "avg_percentage": { "$avg": "$percentage" }
+                }
+            },
+        ];
+        let mut cursor = self
+            .mongo
+            .collection::<Document>("progress_summary")
+            .aggregate(pipeline)
+            .await
+            .context("Failed to aggregate student accuracy")?;
+
+        let mut accuracy = Vec::new();
+        while let Some(row) = cursor
+            .try_next()
+            .await
+            .map_err(|e| anyhow!("Student accuracy cursor failure: {}", e))?
+        {
+            accuracy.push(bson_number(row.get("avg_percentage")));
+        }
+        Ok(accuracy)
+    }
+
+    /// Ответы учеников за период в порядке времени - курсором, без загрузки в память.
+    /// `timestamp` попыток хранится строкой RFC 3339, поэтому границы сравниваются
+    /// как строки с точностью до секунды.
+    pub async fn answers_cursor(
+        &self,
+        student_ids: Vec<String>,
+        period: &TimeRange,
+    ) -> Result<Cursor<AttemptRecord>> {
+        let from = period.from.format("%Y-%m-%dT%H:%M:%S").to_string();
+        let until = (period.to + ChronoDuration::seconds(1))
+            .format("%Y-%m-%dT%H:%M:%S")
+            .to_string();
+        self.mongo
+            .collection::<AttemptRecord>("attempt_records")
+            .find(doc! {
+                "user_id": { "$in": student_ids },
+                "timestamp": { "$gte": from, "$lt": until },
+            })
+            .sort(doc! { "timestamp": 1, "_id": 1 })
+            .await
+            .context("Failed to query group answers")
+    }
+
     pub async fn user_belongs_to_groups(
         &self,
         user_id: &ObjectId,
@@ -193,17 +364,19 @@ impl ReportingService {
             }
         };
 
-        collection
-            .update_one(
-                doc! {
-                    "type": stat_type.as_str(),
-                    "entity_id": entity_id
-                },
-                update_doc,
-            )
-            .upsert(true)
-            .await
-            .context("Failed to update materialized stat")?;
+        retry_idempotent_write("materialized_stats.upsert", || {
+            collection
+                .update_one(
+                    doc! {
+                        "type": stat_type.as_str(),
+                        "entity_id": entity_id
+                    },
+                    update_doc.clone(),
+                )
+                .upsert(true)
+        })
+        .await
+        .context("Failed to update materialized stat")?;
 
         Ok(())
     }
@@ -234,11 +407,13 @@ impl ReportingService {
             }
         };
 
-        collection
-            .update_one(filter, update_doc)
-            .upsert(true)
-            .await
-            .context("Failed to persist leaderboard")?;
+        retry_idempotent_write("leaderboards.upsert", || {
+            collection
+                .update_one(filter.clone(), update_doc.clone())
+                .upsert(true)
+        })
+        .await
+        .context("Failed to persist leaderboard")?;
 
         Ok(())
     }
@@ -253,26 +428,115 @@ impl ReportingService {
         Ok(record)
     }
 
-    pub async fn fetch_pending_exports(&self, limit: i64) -> Result<Vec<ReportExport>> {
+    /// Ещё не собранная выгрузка с тем же объектом, форматом и фильтрами
+    /// (период, поля, язык) - повторный запрос её не дублирует
+    pub async fn find_pending_duplicate(
+        &self,
+        payload: &NewReportExport,
+    ) -> Result<Option<ReportExport>> {
+        let subject_field = match payload.scope {
+            ExportScope::Group => "group_id",
+            ExportScope::User => "user_id",
+        };
         let collection = self.mongo.collection::<ReportExport>("report_exports");
+        collection
+            .find_one(doc! {
+                subject_field: payload.subject_id,
+                "format": to_bson(&payload.format)?,
+                "filters": to_bson(&payload.filters)?,
+                "status": {
+                    "$in": [to_bson(&ExportStatus::Pending)?, to_bson(&ExportStatus::Processing)?]
+                },
+            })
+            .sort(doc! { "createdAt": -1 })
+            .await
+            .context("Failed to look up pending exports")
+    }
 
-        let mut cursor = collection
-            .find(doc! { "status": to_bson(&ExportStatus::Pending)? })
+    /// Атомарно взять в работу самую старую ожидающую выгрузку.
+    ///
+    /// Переход `pending → processing` делается одним `findOneAndUpdate`, поэтому
+    /// несколько реплик воркера не соберут одну выгрузку дважды. Выгрузки, ждущие
+    /// повтора, берутся только после `nextAttemptAt`.
+    pub async fn claim_pending_export(&self, now: DateTime<Utc>) -> Result<Option<ReportExport>> {
+        let collection = self.mongo.collection::<ReportExport>("report_exports");
+        collection
+            .find_one_and_update(
+                doc! {
+                    "status": to_bson(&ExportStatus::Pending)?,
+                    "$or": [
+                        { "nextAttemptAt": { "$exists": false } },
+                        { "nextAttemptAt": { "$lte": chrono_to_bson(now) } },
+                    ],
+                },
+                doc! {
+                    "$set": { "status": to_bson(&ExportStatus::Processing)? },
+                    "$inc": { "attempts": 1 },
+                    "$unset": { "nextAttemptAt": "" },
+                },
+            )
             .sort(doc! { "createdAt": 1 })
-            .limit(limit)
+            .return_document(ReturnDocument::After)
             .await
-            .context("Failed to fetch pending exports")?;
+            .context("Failed to claim pending export")
+    }
 
-        let mut exports = Vec::new();
-        while let Some(export) = cursor
-            .try_next()
+    /// Записать ошибку попытки: с `retry_at` выгрузка вернётся в очередь,
+    /// без него — станет `failed` с этой ошибкой
+    pub async fn record_export_failure(
+        &self,
+        export_id: &ObjectId,
+        error: &str,
+        retry_at: Option<DateTime<Utc>>,
+    ) -> Result<()> {
+        let set_doc = match retry_at {
+            Some(retry_at) => doc! {
+                "status": to_bson(&ExportStatus::Pending)?,
+                "last_error": error,
+                "nextAttemptAt": chrono_to_bson(retry_at),
+            },
+            None => doc! {
+                "status": to_bson(&ExportStatus::Failed)?,
+                "last_error": error,
+                "error": error,
+                "completedAt": chrono_to_bson(Utc::now()),
+            },
+        };
+
+        self.mongo
+            .collection::<ReportExport>("report_exports")
+            .update_one(doc! { "_id": export_id }, doc! { "$set": set_doc })
             .await
-            .map_err(|e| anyhow!("Pending exports cursor failure: {}", e))?
-        {
-            exports.push(export);
-        }
+            .context("Failed to record export failure")?;
+        Ok(())
+    }
 
-        Ok(exports)
+    /// Выгрузки группы, новые первыми; возвращает общее число и страницу
+    pub async fn list_group_exports(
+        &self,
+        group_id: &ObjectId,
+        limit: u32,
+        offset: u32,
+    ) -> Result<(u64, Vec<ReportExport>)> {
+        let collection = self.mongo.collection::<ReportExport>("report_exports");
+        let filter = doc! { "group_id": group_id };
+
+        let total = collection
+            .count_documents(filter.clone())
+            .await
+            .context("Failed to count group exports")?;
+        let exports = collection
+            .find(filter)
+            .sort(doc! { "createdAt": -1, "_id": -1 })
+            .skip(offset as u64)
+            .limit(limit as i64)
+            .await
+            .context("Failed to list group exports")?
+            .try_collect()
+            .await
+            .context("Group exports cursor failure")?;
+
+        Ok((total, exports))
     }
 
     pub async fn get_export_by_id(&self, export_id: &ObjectId) -> Result<Option<ReportExport>> {
@@ -339,6 +603,30 @@ impl ReportingService {
         Ok(count)
     }
 
+    /// Отметка живости export worker; читается health-check'ом
+    pub async fn record_export_worker_heartbeat(&self, at: DateTime<Utc>) -> Result<()> {
+        let mut conn = self.redis.clone();
+        let _: () = redis::cmd("SET")
+            .arg(EXPORT_WORKER_HEARTBEAT_KEY)
+            .arg(at.to_rfc3339())
+            .query_async(&mut conn)
+            .await
+            .context("Failed to write export worker heartbeat")?;
+        Ok(())
+    }
+
+    pub async fn export_worker_heartbeat(&self) -> Result<Option<DateTime<Utc>>> {
+        let mut conn = self.redis.clone();
+        let value: Option<String> = redis::cmd("GET")
+            .arg(EXPORT_WORKER_HEARTBEAT_KEY)
+            .query_async(&mut conn)
+            .await
+            .context("Failed to read export worker heartbeat")?;
+        Ok(value
+            .and_then(|raw| DateTime::parse_from_rfc3339(&raw).ok())
+            .map(|at| at.with_timezone(&Utc)))
+    }
+
     pub async fn reset_expired_exports(&self) -> Result<u64> {
         let collection = self.mongo.collection::<ReportExport>("report_exports");
         let now = Utc::now();
@@ -412,6 +700,164 @@ impl ReportingService {
         Ok(expired)
     }
 
+    pub async fn create_export_schedule(&self, schedule: &ExportSchedule) -> Result<()> {
+        self.mongo
+            .collection::<ExportSchedule>("report_export_schedules")
+            .insert_one(schedule)
+            .await
+            .context("Failed to insert export schedule")?;
+        Ok(())
+    }
+
+    /// Расписания группы; для учителя — только его собственные
+    pub async fn list_export_schedules(
+        &self,
+        group_id: &ObjectId,
+        teacher_id: Option<&ObjectId>,
+    ) -> Result<Vec<ExportSchedule>> {
+        let mut filter = doc! { "group_id": group_id };
+        if let Some(teacher_id) = teacher_id {
+            filter.insert("teacher_id", teacher_id);
+        }
+
+        self.mongo
+            .collection::<ExportSchedule>("report_export_schedules")
+            .find(filter)
+            .sort(doc! { "createdAt": 1 })
+            .await
+            .context("Failed to query export schedules")?
+            .try_collect()
+            .await
+            .context("Export schedules cursor failure")
+    }
+
+    pub async fn delete_export_schedule(
+        &self,
+        group_id: &ObjectId,
+        schedule_id: &ObjectId,
+        teacher_id: Option<&ObjectId>,
+    ) -> Result<bool> {
+        let mut filter = doc! { "_id": schedule_id, "group_id": group_id };
+        if let Some(teacher_id) = teacher_id {
+            filter.insert("teacher_id", teacher_id);
+        }
+
+        let result = self
+            .mongo
+            .collection::<ExportSchedule>("report_export_schedules")
+            .delete_one(filter)
+            .await
+            .context("Failed to delete export schedule")?;
+        Ok(result.deleted_count > 0)
+    }
+
+    pub async fn fetch_export_schedules(&self) -> Result<Vec<ExportSchedule>> {
+        self.mongo
+            .collection::<ExportSchedule>("report_export_schedules")
+            .find(doc! {})
+            .await
+            .context("Failed to query export schedules")?
+            .try_collect()
+            .await
+            .context("Export schedules cursor failure")
+    }
+
+    /// Занять запуск периода, начавшегося в `slot`.
+    ///
+    /// `lastRunAt` сравнивается в самом обновлении, поэтому из нескольких воркеров
+    /// (или после перезапуска) период достаётся ровно одному. Возвращает `true`,
+    /// если запуск занят этим вызовом.
+    pub async fn claim_schedule_run(
+        &self,
+        schedule_id: &ObjectId,
+        slot: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<bool> {
+        let result = self
+            .mongo
+            .collection::<ExportSchedule>("report_export_schedules")
+            .update_one(
+                doc! {
+                    "_id": schedule_id,
+                    "$or": [
+                        { "lastRunAt": { "$exists": false } },
+                        { "lastRunAt": { "$lt": chrono_to_bson(slot) } },
+                    ],
+                },
+                doc! { "$set": { "lastRunAt": chrono_to_bson(now) } },
+            )
+            .await
+            .context("Failed to claim export schedule run")?;
+        Ok(result.modified_count > 0)
+    }
+
+    /// Вернуть `lastRunAt` к прежнему значению, если выгрузку поставить не удалось
+    pub async fn release_schedule_run(
+        &self,
+        schedule_id: &ObjectId,
+        previous: Option<DateTime<Utc>>,
+    ) -> Result<()> {
+        let update = match previous {
+            Some(value) => doc! { "$set": { "lastRunAt": chrono_to_bson(value) } },
+            None => doc! { "$unset": { "lastRunAt": "" } },
+        };
+        self.mongo
+            .collection::<ExportSchedule>("report_export_schedules")
+            .update_one(doc! { "_id": schedule_id }, update)
+            .await
+            .context("Failed to release export schedule run")?;
+        Ok(())
+    }
+
+    pub async fn record_schedule_export(
+        &self,
+        schedule_id: &ObjectId,
+        export_id: &ObjectId,
+    ) -> Result<()> {
+        self.mongo
+            .collection::<ExportSchedule>("report_export_schedules")
+            .update_one(
+                doc! { "_id": schedule_id },
+                doc! { "$set": { "lastExportId": export_id } },
+            )
+            .await
+            .context("Failed to record scheduled export")?;
+        Ok(())
+    }
+
+    /// Расписания, о последней выгрузке которых получатели ещё не уведомлены
+    pub async fn fetch_schedules_awaiting_notification(&self) -> Result<Vec<ExportSchedule>> {
+        self.mongo
+            .collection::<ExportSchedule>("report_export_schedules")
+            .find(doc! {
+                "lastExportId": { "$exists": true },
+                "$expr": { "$ne": ["$lastExportId", "$notifiedExportId"] },
+            })
+            .await
+            .context("Failed to query schedules awaiting notification")?
+            .try_collect()
+            .await
+            .context("Export schedules cursor failure")
+    }
+
+    /// Отметить уведомление; условие на `lastExportId` не даёт затереть более новый запуск
+    pub async fn mark_schedule_notified(
+        &self,
+        schedule_id: &ObjectId,
+        export_id: &ObjectId,
+    ) -> Result<bool> {
+        let result = self
+            .mongo
+            .collection::<ExportSchedule>("report_export_schedules")
+            .update_one(
+                doc! { "_id": schedule_id, "lastExportId": export_id },
+                doc! { "$set": { "notifiedExportId": export_id } },
+            )
+            .await
+            .context("Failed to mark export schedule notified")?;
+        Ok(result.modified_count > 0)
+    }
+
     pub async fn aggregate_topic_stats(
         &self,
         student_ids: &[String],
@@ -421,67 +867,8 @@ impl ReportingService {
         }
 
         let collection = self.mongo.collection::<Document>("progress_summary");
-        let pipeline = vec![
-            doc! {
-                "$match": {
-                    "user_id": { "$in": student_ids },
-                }
-            },
-            doc! {
-                "$lookup": {
-                    "from": "levels",
-                    "localField": "level_id",
-                    "foreignField": "_id",
-                    "as": "level"
-                }
-            },
-            doc! {
-                "$unwind": "$level"
-            },
-            doc! {
-                "$group": {
-                    "_id": "$level.topic_id",
-                    "topic_name": { "$first": "$level.topic" },
-                    "avg_percentage": { "$avg": "$percentage" },
-                    "total_attempts": { "$sum": "$attempts_total" },
-                    "total_score": { "$sum": "$score" }
-                }
-            },
-            doc! {
-                "$lookup": {
-                    "from": "topics",
-                    "localField": "_id",
-                    "foreignField": "_id",
-                    "as": "topic"
-                }
-            },
-            doc! {
-                "$unwind": {
-                    "path": "$topic",
-                    "preserveNullAndEmptyArrays": true
-                }
-            },
-            doc! {
-                "$addFields": {
-                    "topic_name": { "$ifNull": [ "$topic.name", "$topic_name" ] }
-                }
-            },
-            doc! {
-                "$project": {
-                    "topic_id": "$_id",
-                    "topic_name": 1,
-                    "avg_percentage": 1,
-                    "total_attempts": 1,
-                    "total_score": 1
-                }
-            },
-            doc! {
-                "$sort": { "avg_percentage": 1 }
-            },
-        ];
-
         let mut cursor = collection
-            .aggregate(pipeline)
+            .aggregate(topic_stats_pipeline(student_ids, false))
             .await
             .context("Failed to aggregate topic stats")?;
 
@@ -499,6 +886,211 @@ impl ReportingService {
         Ok(results)
     }
 
+    /// Разбивка по темам для каждого ученика группы (листы XLSX-выгрузки).
+    /// Берутся первые `limit` учеников по имени; `total_students` - весь состав
+    pub async fn load_group_student_progress(
+        &self,
+        group_id: &ObjectId,
+        limit: usize,
+    ) -> Result<GroupStudentProgress> {
+        let mut roster: Vec<(String, String)> = self
+            .group_student_names(group_id)
+            .await?
+            .into_iter()
+            .collect();
+        roster.sort_by(|(a_id, a_name), (b_id, b_name)| {
+            a_name.cmp(b_name).then_with(|| a_id.cmp(b_id))
+        });
+        let total_students = roster.len();
+        roster.truncate(limit);
+        if roster.is_empty() {
+            return Ok(GroupStudentProgress {
+                students: Vec::new(),
+                total_students,
+            });
+        }
+
+        let student_ids: Vec<String> = roster.iter().map(|(id, _)| id.clone()).collect();
+        let mut cursor = self
+            .mongo
+            .collection::<Document>("progress_summary")
+            .aggregate(topic_stats_pipeline(&student_ids, true))
+            .await
+            .context("Failed to aggregate student topic stats")?;
+
+        let mut topics: HashMap<String, Vec<TopicAnalyticsRow>> = HashMap::new();
+        while let Some(doc) = cursor
+            .try_next()
+            .await
+            .map_err(|e| anyhow!("Student topic stats cursor failure: {}", e))?
+        {
+            let user_id = doc.get_str("user_id").unwrap_or_default().to_string();
+            let row: TopicAnalyticsRow =
+                from_document(doc).context("Failed to parse student topic row")?;
+            topics.entry(user_id).or_default().push(row);
+        }
+
+        let students = roster
+            .into_iter()
+            .map(|(user_id, name)| StudentProgress {
+                topics: topics.remove(&user_id).unwrap_or_default(),
+                user_id,
+                name,
+            })
+            .collect();
+        Ok(GroupStudentProgress {
+            students,
+            total_students,
+        })
+    }
+
+    /// Показатели активности нескольких групп одной агрегацией по progress_summary.
+    /// `students_by_group` - состав групп из `GroupService::students_by_group`
+    pub async fn group_health_stats(
+        &self,
+        students_by_group: &HashMap<String, Vec<String>>,
+    ) -> Result<HashMap<String, GroupHealthStats>> {
+        let student_ids = students_by_group
+            .values()
+            .flatten()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        let mut activity = HashMap::new();
+        if !student_ids.is_empty() {
+            let pipeline = vec![
+                doc! {
+                    "$match": {
+                        "user_id": { "$in": student_ids },
+                    }
+                },
+                doc! {
+                    "$group": {
+                        "_id": "$user_id",
+                        "percentage_sum": { "$sum": "$percentage" },
+                        "rows": { "$sum": 1 },
+                        "last_updated": { "$max": "$updated_at" },
+                    }
+                },
+            ];
+
+            let mut cursor = self
+                .mongo
+                .collection::<Document>("progress_summary")
+                .aggregate(pipeline)
+                .await
+                .context("Failed to aggregate group health")?;
+
+            while let Some(row) = cursor
+                .try_next()
+                .await
+                .context("Failed to read group health row")?
+            {
+                let Ok(user_id) = row.get_str("_id") else {
+                    continue;
+                };
+                activity.insert(
+                    user_id.to_string(),
+                    StudentActivity {
+                        percentage_sum: bson_number(row.get("percentage_sum")),
+                        rows: bson_number(row.get("rows")) as u32,
+                        last_updated: row.get_datetime("last_updated").ok().and_then(|value| {
+                            DateTime::from_timestamp_millis(value.timestamp_millis())
+                        }),
+                    },
+                );
+            }
+        }
+
+        Ok(combine_group_health(
+            students_by_group,
+            &activity,
+            Utc::now(),
+        ))
+    }
+
+    /// Точность по теме в разрезе групп: строки progress_summary на уровнях темы
+    /// сворачиваются по ученикам, затем по их группам.
+    /// `students_by_group` - состав групп из `GroupService::students_by_group`
+    pub async fn topic_stats_by_group(
+        &self,
+        topic_id: &ObjectId,
+        students_by_group: &HashMap<String, Vec<String>>,
+    ) -> Result<HashMap<String, TopicGroupStats>> {
+        let student_ids = students_by_group
+            .values()
+            .flatten()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        let mut progress = HashMap::new();
+        if !student_ids.is_empty() {
+            // progress_summary хранит level_id строкой, поэтому уровни темы
+            // берём отдельным запросом, а не через $lookup
+            let level_ids = self
+                .mongo
+                .collection::<Document>("levels")
+                .find(doc! { "topic_id": topic_id })
+                .projection(doc! { "_id": 1 })
+                .await
+                .context("Failed to query topic levels")?
+                .try_collect::<Vec<_>>()
+                .await
+                .context("Topic levels cursor failure")?
+                .into_iter()
+                .filter_map(|level| level.get_object_id("_id").ok().map(|id| id.to_hex()))
+                .collect::<Vec<_>>();
+
+            if !level_ids.is_empty() {
+                let pipeline = vec![
+                    doc! {
+                        "$match": {
+                            "user_id": { "$in": student_ids },
+                            "level_id": { "$in": level_ids },
+                        }
+                    },
+                    doc! {
+                        "$group": {
+                            "_id": "$user_id",
+                            "percentage_sum": { "$sum": "$percentage" },
+                            "rows": { "$sum": 1 },
+                            "attempts": { "$sum": "$attempts_total" },
+                        }
+                    },
+                ];
+
+                let mut cursor = self
+                    .mongo
+                    .collection::<Document>("progress_summary")
+                    .aggregate(pipeline)
+                    .await
+                    .context("Failed to aggregate topic stats by group")?;
+
+                while let Some(row) = cursor
+                    .try_next()
+                    .await
+                    .context("Failed to read topic stats row")?
+                {
+                    let Ok(user_id) = row.get_str("_id") else {
+                        continue;
+                    };
+                    progress.insert(
+                        user_id.to_string(),
+                        StudentTopicProgress {
+                            percentage_sum: bson_number(row.get("percentage_sum")),
+                            rows: bson_number(row.get("rows")) as u32,
+                            attempts: bson_number(row.get("attempts")) as i64,
+                        },
+                    );
+                }
+            }
+        }
+
+        Ok(combine_topic_by_group(students_by_group, &progress))
+    }
+
     pub async fn aggregate_activity(&self, student_ids: &[String]) -> Result<Vec<ActivityRow>> {
         if student_ids.is_empty() {
             return Ok(Vec::new());
@@ -579,6 +1171,80 @@ impl ReportingService {
     }
 }
 
+/// Точность по темам из `progress_summary`; с `per_student` строки считаются
+/// отдельно для каждого ученика и несут его `user_id`
+fn topic_stats_pipeline(student_ids: &[String], per_student: bool) -> Vec<Document> {
+    let (group_key, topic_field) = if per_student {
+        (
+            Bson::Document(doc! { "user_id": "$user_id", "topic_id": "$level.topic_id" }),
+            "_id.topic_id",
+        )
+    } else {
+        (Bson::String("$level.topic_id".into()), "_id")
+    };
+    let mut projection = doc! {
+        "topic_id": format!("${topic_field}"),
+        "topic_name": 1,
+        "avg_percentage": 1,
+        "total_attempts": 1,
+        "total_score": 1
+    };
+    let mut sort = doc! { "avg_percentage": 1 };
+    if per_student {
+        projection.insert("user_id", "$_id.user_id");
+        sort = doc! { "_id.user_id": 1, "avg_percentage": 1 };
+    }
+
+    vec![
+        doc! {
+            "$match": {
+                "user_id": { "$in": student_ids },
+            }
+        },
+        doc! {
+            "$lookup": {
+                "from": "levels",
+                "localField": "level_id",
+                "foreignField": "_id",
+                "as": "level"
+            }
+        },
+        doc! {
+            "$unwind": "$level"
+        },
+        doc! {
+            "$group": {
+                "_id": group_key,
+                "topic_name": { "$first": "$level.topic" },
+                "avg_percentage": { "$avg": "$percentage" },
+                "total_attempts": { "$sum": "$attempts_total" },
+                "total_score": { "$sum": "$score" }
+            }
+        },
+        doc! {
+            "$lookup": {
+                "from": "topics",
+                "localField": topic_field,
+                "foreignField": "_id",
+                "as": "topic"
+            }
+        },
+        doc! {
+            "$unwind": {
+                "path": "$topic",
+                "preserveNullAndEmptyArrays": true
+            }
+        },
+        doc! {
+            "$addFields": {
+                "topic_name": { "$ifNull": [ "$topic.name", "$topic_name" ] }
+            }
+        },
+        doc! { "$project": projection },
+        doc! { "$sort": sort },
+    ]
+}
+
 #[derive(Debug, Deserialize)]
 pub struct TopicAnalyticsRow {
     #[serde(rename = "topic_id")]
@@ -589,6 +1255,29 @@ pub struct TopicAnalyticsRow {
     pub total_score: Option<i64>,
 }
 
+/// Ученик группы и его точность по темам
+#[derive(Debug)]
+pub struct StudentProgress {
+    pub user_id: String,
+    pub name: String,
+    pub topics: Vec<TopicAnalyticsRow>,
+}
+
+/// Ученики группы для XLSX-выгрузки, не больше запрошенного лимита
+#[derive(Debug, Default)]
+pub struct GroupStudentProgress {
+    pub students: Vec<StudentProgress>,
+    /// Сколько учеников в группе всего; больше `students.len()`, если список обрезан
+    pub total_students: usize,
+}
+
+/// Итог по подсказкам ученика (коллекция `hint_records`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HintTotals {
+    pub hints: u64,
+    pub cost: i64,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ActivityRow {
     #[serde(rename = "date")]
@@ -597,3 +1286,168 @@ pub struct ActivityRow {
     pub total_attempts: Option<i64>,
     pub total_score: Option<i64>,
 }
+
+/// Прогресс одного ученика, свёрнутый по всем уровням
+#[derive(Debug, Clone, Default)]
+struct StudentActivity {
+    percentage_sum: f64,
+    rows: u32,
+    last_updated: Option<DateTime<Utc>>,
+}
+
+/// Прогресс одного ученика на уровнях одной темы
+#[derive(Debug, Clone, Default)]
+struct StudentTopicProgress {
+    percentage_sum: f64,
+    rows: u32,
+    attempts: i64,
+}
+
+fn bson_number(value: Option<&Bson>) -> f64 {
+    match value {
+        Some(Bson::Double(value)) => *value,
+        Some(Bson::Int32(value)) => f64::from(*value),
+        Some(Bson::Int64(value)) => *value as f64,
+        _ => 0.0,
+    }
+}
+
+/// Сводит прогресс учеников в показатели их групп.
+/// Точность взвешена по строкам прогресса, как и у `avg_percentage` в статистике учеников
+fn combine_group_health(
+    students_by_group: &HashMap<String, Vec<String>>,
+    activity: &HashMap<String, StudentActivity>,
+    now: DateTime<Utc>,
+) -> HashMap<String, GroupHealthStats> {
+    let active_since = now - ChronoDuration::days(7);
+
+    students_by_group
+        .iter()
+        .map(|(group_id, students)| {
+            let mut percentage_sum = 0.0;
+            let mut rows = 0;
+            let mut stats = GroupHealthStats::default();
+            for student in students.iter().filter_map(|id| activity.get(id)) {
+                percentage_sum += student.percentage_sum;
+                rows += student.rows;
+                if student.last_updated.is_some_and(|at| at >= active_since) {
+                    stats.active_students_last_7d += 1;
+                }
+                stats.last_activity_at = stats.last_activity_at.max(student.last_updated);
+            }
+            stats.avg_accuracy = (rows > 0).then(|| percentage_sum / f64::from(rows));
+            (group_id.clone(), stats)
+        })
+        .collect()
+}
+
+/// Сводит прогресс учеников по теме в показатели их групп (точность взвешена по строкам)
+fn combine_topic_by_group(
+    students_by_group: &HashMap<String, Vec<String>>,
+    progress: &HashMap<String, StudentTopicProgress>,
+) -> HashMap<String, TopicGroupStats> {
+    students_by_group
+        .iter()
+        .map(|(group_id, students)| {
+            let mut percentage_sum = 0.0;
+            let mut rows = 0;
+            let mut stats = TopicGroupStats::default();
+            for student in students.iter().filter_map(|id| progress.get(id)) {
+                percentage_sum += student.percentage_sum;
+                rows += student.rows;
+                stats.total_attempts += student.attempts;
+            }
+            stats.avg_percentage = (rows > 0).then(|| percentage_sum / f64::from(rows));
+            (group_id.clone(), stats)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn group_health_combines_students_of_each_group() {
+        let now = Utc::now();
+        let students_by_group = HashMap::from([
+            ("g1".to_string(), vec!["a".to_string(), "b".to_string()]),
+            ("g2".to_string(), vec!["b".to_string(), "c".to_string()]),
+            ("empty".to_string(), Vec::new()),
+        ]);
+        let activity = HashMap::from([
+            (
+                "a".to_string(),
+                StudentActivity {
+                    percentage_sum: 160.0,
+                    rows: 2,
+                    last_updated: Some(now - ChronoDuration::days(1)),
+                },
+            ),
+            (
+                "b".to_string(),
+                StudentActivity {
+                    percentage_sum: 50.0,
+                    rows: 1,
+                    last_updated: Some(now - ChronoDuration::days(10)),
+                },
+            ),
+        ]);
+
+        let health = combine_group_health(&students_by_group, &activity, now);
+
+        let g1 = &health["g1"];
+        assert_eq!(g1.avg_accuracy, Some(70.0));
+        assert_eq!(g1.active_students_last_7d, 1);
+        assert_eq!(g1.last_activity_at, Some(now - ChronoDuration::days(1)));
+
+        let g2 = &health["g2"];
+        assert_eq!(g2.avg_accuracy, Some(50.0));
+        assert_eq!(g2.active_students_last_7d, 0);
+
+        assert_eq!(health["empty"], GroupHealthStats::default());
+    }
+
+    #[test]
+    fn topic_stats_are_split_by_group_membership() {
+        let students_by_group = HashMap::from([
+            ("g1".to_string(), vec!["a".to_string(), "b".to_string()]),
+            ("g2".to_string(), vec!["b".to_string(), "c".to_string()]),
+            ("idle".to_string(), vec!["d".to_string()]),
+        ]);
+        let progress = HashMap::from([
+            (
+                "a".to_string(),
+                StudentTopicProgress {
+                    percentage_sum: 180.0,
+                    rows: 2,
+                    attempts: 12,
+                },
+            ),
+            (
+                "b".to_string(),
+                StudentTopicProgress {
+                    percentage_sum: 60.0,
+                    rows: 1,
+                    attempts: 5,
+                },
+            ),
+            (
+                "c".to_string(),
+                StudentTopicProgress {
+                    percentage_sum: 20.0,
+                    rows: 1,
+                    attempts: 3,
+                },
+            ),
+        ]);
+
+        let stats = combine_topic_by_group(&students_by_group, &progress);
+
+        assert_eq!(stats["g1"].avg_percentage, Some(80.0));
+        assert_eq!(stats["g1"].total_attempts, 17);
+        assert_eq!(stats["g2"].avg_percentage, Some(40.0));
+        assert_eq!(stats["g2"].total_attempts, 8);
+        assert_eq!(stats["idle"], TopicGroupStats::default());
+    }
+}
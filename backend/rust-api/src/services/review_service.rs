@@ -0,0 +1,311 @@
+//! Очередь интервального повторения шаблонов, в заданиях по которым ученик ошибся.
+//!
+//! Расписание - упрощённый SM-2: верное повторение увеличивает интервал (1 день, 6 дней,
+//! дальше интервал умножается на коэффициент лёгкости), ошибка сбрасывает серию и снижает
+//! коэффициент. После `GRADUATION_REPETITIONS` верных повторений подряд шаблон уходит
+//! из очереди.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, oid::ObjectId},
+    Database,
+};
+
+use crate::models::review::ReviewItem;
+
+pub const REVIEW_QUEUE_COLLECTION: &str = "review_queue";
+pub const INITIAL_EASE_FACTOR: f64 = 2.5;
+pub const MIN_EASE_FACTOR: f64 = 1.3;
+const EASE_BONUS: f64 = 0.1;
+const EASE_PENALTY: f64 = 0.2;
+/// Верных повторений подряд, после которых шаблон считается выученным
+pub const GRADUATION_REPETITIONS: u32 = 4;
+pub const MAX_REVIEW_BATCH: u32 = 50;
+
+/// Шаблона нет в очереди пользователя
+#[derive(Debug, thiserror::Error)]
+#[error("Template {template_id} is not in the review queue")]
+pub struct ReviewItemNotFoundError {
+    pub template_id: String,
+}
+
+/// Сессия повторения запрошена, но повторять нечего (ответ 404)
+#[derive(Debug, thiserror::Error)]
+#[error("Nothing is due for review")]
+pub struct ReviewQueueEmptyError;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReviewSchedule {
+    pub interval_days: u32,
+    pub ease_factor: f64,
+    pub repetitions: u32,
+}
+
+/// Итог повторения: новое расписание или выход шаблона из очереди
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReviewStep {
+    Scheduled(ReviewSchedule),
+    Graduated,
+}
+
+impl Default for ReviewSchedule {
+    fn default() -> Self {
+        Self {
+            interval_days: 0,
+            ease_factor: INITIAL_EASE_FACTOR,
+            repetitions: 0,
+        }
+    }
+}
+
+impl ReviewSchedule {
+    fn from_item(item: &ReviewItem) -> Self {
+        Self {
+            interval_days: item.interval_days,
+            ease_factor: item.ease_factor,
+            repetitions: item.repetitions,
+        }
+    }
+
+    /// Повторная ошибка в обычной сессии: шаблон снова доступен к повторению сразу
+    pub fn after_mistake(self) -> Self {
+        Self {
+            interval_days: 0,
+            ease_factor: lower_ease(self.ease_factor),
+            repetitions: 0,
+        }
+    }
+
+    pub fn after_review(self, correct: bool) -> ReviewStep {
+        if !correct {
+            return ReviewStep::Scheduled(Self {
+                interval_days: 1,
+                ease_factor: lower_ease(self.ease_factor),
+                repetitions: 0,
+            });
+        }
+
+        let repetitions = self.repetitions + 1;
+        if repetitions >= GRADUATION_REPETITIONS {
+            return ReviewStep::Graduated;
+        }
+        let ease_factor = self.ease_factor + EASE_BONUS;
+        let interval_days = match repetitions {
+            1 => 1,
+            2 => 6,
+            _ => ((f64::from(self.interval_days) * ease_factor).round() as u32)
+                .max(self.interval_days + 1),
+        };
+        ReviewStep::Scheduled(Self {
+            interval_days,
+            ease_factor,
+            repetitions,
+        })
+    }
+
+    pub fn due_at(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now + Duration::days(i64::from(self.interval_days))
+    }
+}
+
+fn lower_ease(ease_factor: f64) -> f64 {
+    (ease_factor - EASE_PENALTY).max(MIN_EASE_FACTOR)
+}
+
+pub struct ReviewService {
+    mongo: Database,
+}
+
+impl ReviewService {
+    pub fn new(mongo: Database) -> Self {
+        Self { mongo }
+    }
+
+    fn collection(&self) -> mongodb::Collection<ReviewItem> {
+        self.mongo.collection(REVIEW_QUEUE_COLLECTION)
+    }
+
+    /// Ошибка в задании по шаблону ставит шаблон в очередь (или возвращает его в начало)
+    pub async fn record_mistake(
+        &self,
+        user_id: &str,
+        template_id: ObjectId,
+        task_id: &str,
+    ) -> Result<()> {
+        let filter = doc! { "user_id": user_id, "template_id": template_id };
+        let existing = self
+            .collection()
+            .find_one(filter.clone())
+            .await
+            .context("Failed to load review item")?;
+
+        let (schedule, lapses) = match &existing {
+            Some(item) => (
+                ReviewSchedule::from_item(item).after_mistake(),
+                item.lapses + 1,
+            ),
+            None => (ReviewSchedule::default(), 1),
+        };
+        let now = Utc::now();
+        let now_bson = mongodb::bson::DateTime::from_millis(now.timestamp_millis());
+        let update = doc! {
+            "$set": {
+                "task_id": task_id,
+                "interval_days": schedule.interval_days,
+                "ease_factor": schedule.ease_factor,
+                "repetitions": schedule.repetitions,
+                "lapses": lapses,
+                "last_seen": now_bson,
+                "due_at": now_bson,
+            },
+            "$setOnInsert": { "createdAt": now_bson },
+        };
+        self.collection()
+            .update_one(filter, update)
+            .upsert(true)
+            .await
+            .context("Failed to record review mistake")?;
+        Ok(())
+    }
+
+    /// Шаблоны, срок повторения которых наступил, и их общее число
+    pub async fn next_due(&self, user_id: &str, limit: u32) -> Result<(Vec<ReviewItem>, u64)> {
+        let now = mongodb::bson::DateTime::from_millis(Utc::now().timestamp_millis());
+        let filter = doc! { "user_id": user_id, "due_at": { "$lte": now } };
+        let items: Vec<ReviewItem> = self
+            .collection()
+            .find(filter.clone())
+            .sort(doc! { "due_at": 1, "_id": 1 })
+            .limit(i64::from(limit.clamp(1, MAX_REVIEW_BATCH)))
+            .await
+            .context("Failed to query review queue")?
+            .try_collect()
+            .await
+            .context("Failed to read review queue")?;
+        let total_due = self
+            .collection()
+            .count_documents(filter)
+            .await
+            .context("Failed to count due review items")?;
+        Ok((items, total_due))
+    }
+
+    /// Результат повторения; `None` - шаблон выучен и удалён из очереди
+    pub async fn record_result(
+        &self,
+        user_id: &str,
+        template_id: ObjectId,
+        correct: bool,
+    ) -> Result<Option<ReviewItem>> {
+        let filter = doc! { "user_id": user_id, "template_id": template_id };
+        let mut item = self
+            .collection()
+            .find_one(filter.clone())
+            .await
+            .context("Failed to load review item")?
+            .ok_or_else(|| ReviewItemNotFoundError {
+                template_id: template_id.to_hex(),
+            })?;
+
+        let schedule = match ReviewSchedule::from_item(&item).after_review(correct) {
+            ReviewStep::Graduated => {
+                self.collection()
+                    .delete_one(filter)
+                    .await
+                    .context("Failed to remove review item")?;
+                return Ok(None);
+            }
+            ReviewStep::Scheduled(schedule) => schedule,
+        };
+
+        let now = Utc::now();
+        item.interval_days = schedule.interval_days;
+        item.ease_factor = schedule.ease_factor;
+        item.repetitions = schedule.repetitions;
+        if !correct {
+            item.lapses += 1;
+        }
+        item.last_seen = now;
+        item.due_at = schedule.due_at(now);
+
+        self.collection()
+            .replace_one(doc! { "_id": item.id }, &item)
+            .await
+            .context("Failed to update review item")?;
+        Ok(Some(item))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scheduled(step: ReviewStep) -> ReviewSchedule {
+        match step {
+            ReviewStep::Scheduled(schedule) => schedule,
+            ReviewStep::Graduated => panic!("unexpected graduation"),
+        }
+    }
+
+    #[test]
+    fn correct_reviews_grow_interval_and_graduate() {
+        let first = scheduled(ReviewSchedule::default().after_review(true));
+        assert_eq!(first.interval_days, 1);
+        assert_eq!(first.repetitions, 1);
+
+        let second = scheduled(first.after_review(true));
+        assert_eq!(second.interval_days, 6);
+
+        // 6 дней * 2.8
+        let third = scheduled(second.after_review(true));
+        assert_eq!(third.interval_days, 17);
+        assert!((third.ease_factor - 2.8).abs() < 1e-9);
+
+        assert_eq!(third.after_review(true), ReviewStep::Graduated);
+    }
+
+    #[test]
+    fn failed_review_resets_streak_and_lowers_ease() {
+        let learned =
+            scheduled(scheduled(ReviewSchedule::default().after_review(true)).after_review(true));
+
+        let failed = scheduled(learned.after_review(false));
+        assert_eq!(failed.interval_days, 1);
+        assert_eq!(failed.repetitions, 0);
+        assert!((failed.ease_factor - 2.5).abs() < 1e-9);
+
+        // После ошибки серия начинается заново
+        assert_eq!(scheduled(failed.after_review(true)).interval_days, 1);
+    }
+
+    #[test]
+    fn ease_factor_never_drops_below_minimum() {
+        let mut schedule = ReviewSchedule::default();
+        for _ in 0..10 {
+            schedule = scheduled(schedule.after_review(false));
+        }
+        assert_eq!(schedule.ease_factor, MIN_EASE_FACTOR);
+
+        let mistake = ReviewSchedule {
+            interval_days: 17,
+            ease_factor: 1.4,
+            repetitions: 3,
+        }
+        .after_mistake();
+        assert_eq!(mistake.interval_days, 0);
+        assert_eq!(mistake.repetitions, 0);
+        assert_eq!(mistake.ease_factor, MIN_EASE_FACTOR);
+    }
+
+    #[test]
+    fn interval_always_grows_with_low_ease() {
+        let schedule = ReviewSchedule {
+            interval_days: 1,
+            ease_factor: MIN_EASE_FACTOR,
+            repetitions: 2,
+        };
+        assert_eq!(scheduled(schedule.after_review(true)).interval_days, 2);
+    }
+}
@@ -0,0 +1,191 @@
+//! Настройки, которые меняются без перезапуска процесса.
+//!
+//! `AppState` держит снимок `RuntimeSettings`; middleware и обработчики берут его на
+//! каждый запрос. `POST /admin/settings/reload` заново читает конфигурацию
+//! (config/*.toml, CONFIG_FILE, переменные окружения) и переопределения из
+//! `system_settings`, после чего атомарно подменяет снимок. Запросы, уже получившие
+//! старый снимок, дорабатывают с ним.
+
+use std::sync::{Arc, RwLock};
+
+use anyhow::{Context, Result};
+use axum::http::HeaderValue;
+use chrono::{DateTime, Utc};
+use mongodb::Database;
+
+use crate::{
+    config::{AnticheatSignalSettings, Config, CorsSettings, HintSettings, RateLimitSettings},
+    models::system_settings::{RateLimitOverrides, ReloadableSetting, RuntimeSettingsView},
+    services::{system_settings_service::SystemSettingsService, AppState},
+};
+
+/// Что перечитывается при перезагрузке и откуда
+pub const RELOADABLE_SETTINGS: &[ReloadableSetting] = &[
+    ReloadableSetting {
+        key: "rate_limit",
+        source: "RATE_LIMIT_*, then PUT /admin/settings/rate-limits",
+    },
+    ReloadableSetting {
+        key: "anticheat_signals",
+        source: "ANTICHEAT_SIGNAL_*, ANTICHEAT_*_THRESHOLD or [anticheat.signals]",
+    },
+    ReloadableSetting {
+        key: "anticheat",
+        source: "PUT /admin/settings/anticheat (also refreshed every 30s)",
+    },
+    ReloadableSetting {
+        key: "hints",
+        source: "HINTS_MAX_PER_SESSION, HINTS_PENALTY_SCHEDULE or [hints]",
+    },
+    ReloadableSetting {
+        key: "cors_allowed_origins",
+        source: "CORS_ALLOWED_ORIGINS or [cors]",
+    },
+];
+
+#[derive(Debug, Clone)]
+pub struct RuntimeSettings {
+    pub rate_limit: RateLimitSettings,
+    pub anticheat_signals: AnticheatSignalSettings,
+    pub hints: HintSettings,
+    pub cors: CorsSettings,
+    pub loaded_at: DateTime<Utc>,
+}
+
+impl RuntimeSettings {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            rate_limit: config.rate_limit.clone(),
+            anticheat_signals: config.anticheat_signals.clone(),
+            hints: config.hints.clone(),
+            cors: config.cors.clone(),
+            loaded_at: Utc::now(),
+        }
+    }
+
+    /// Значения из конфигурации с переопределениями из `system_settings`
+    pub async fn load(config: &Config, mongo: &Database) -> Result<Self> {
+        let mut settings = Self::from_config(config);
+        if let Some(overrides) = SystemSettingsService::new(mongo.clone())
+            .get_rate_limit_overrides()
+            .await?
+        {
+            settings.apply_rate_limit_overrides(&overrides);
+        }
+        Ok(settings)
+    }
+
+    pub fn apply_rate_limit_overrides(&mut self, overrides: &RateLimitOverrides) {
+        overrides.apply(&mut self.rate_limit);
+    }
+
+    /// Пустой список или `*` - любой origin
+    pub fn allows_origin(&self, origin: &HeaderValue) -> bool {
+        self.cors.allows_any()
+            || self
+                .cors
+                .allowed_origins
+                .iter()
+                .any(|allowed| allowed.as_bytes() == origin.as_bytes())
+    }
+
+    pub fn view(&self) -> RuntimeSettingsView {
+        RuntimeSettingsView {
+            loaded_at: self.loaded_at,
+            reloadable: RELOADABLE_SETTINGS.to_vec(),
+            rate_limit: self.rate_limit.clone(),
+            anticheat_signals: self.anticheat_signals.clone(),
+            hints: self.hints.clone(),
+            cors_allowed_origins: self.cors.allowed_origins.clone(),
+        }
+    }
+}
+
+/// Текущий снимок; чтение не ждёт перезагрузку дольше подмены указателя
+pub struct RuntimeSettingsHandle {
+    current: RwLock<Arc<RuntimeSettings>>,
+}
+
+impl RuntimeSettingsHandle {
+    pub fn new(settings: RuntimeSettings) -> Self {
+        Self {
+            current: RwLock::new(Arc::new(settings)),
+        }
+    }
+
+    pub fn snapshot(&self) -> Arc<RuntimeSettings> {
+        self.current
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    pub fn replace(&self, settings: RuntimeSettings) -> Arc<RuntimeSettings> {
+        let settings = Arc::new(settings);
+        *self
+            .current
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = settings.clone();
+        settings
+    }
+}
+
+/// Перечитать конфигурацию и `system_settings` и подменить снимок. Если новая
+/// конфигурация не проходит проверку, действует прежний снимок
+pub async fn reload(state: &AppState) -> Result<Arc<RuntimeSettings>> {
+    let config = Config::load().context("Failed to reload configuration")?;
+    let settings = RuntimeSettings::load(&config, &state.mongo)
+        .await
+        .context("Failed to load runtime setting overrides")?;
+    state.anticheat_settings.invalidate().await;
+    Ok(state.runtime.replace(settings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RateLimitWindow;
+
+    fn settings(origins: &[&str]) -> RuntimeSettings {
+        RuntimeSettings {
+            rate_limit: RateLimitSettings::default(),
+            anticheat_signals: AnticheatSignalSettings::default(),
+            hints: HintSettings::default(),
+            cors: CorsSettings {
+                allowed_origins: origins.iter().map(|origin| origin.to_string()).collect(),
+            },
+            loaded_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn overrides_replace_only_configured_windows() {
+        let mut runtime = settings(&[]);
+        let defaults = runtime.rate_limit.clone();
+        runtime.apply_rate_limit_overrides(&RateLimitOverrides {
+            login: Some(RateLimitWindow {
+                limit: 3,
+                window_secs: 60,
+            }),
+            ..RateLimitOverrides::default()
+        });
+
+        assert_eq!(runtime.rate_limit.login.limit, 3);
+        assert_eq!(runtime.rate_limit.login.window_secs, 60);
+        assert_eq!(runtime.rate_limit.register, defaults.register);
+        assert_eq!(runtime.rate_limit.per_user, defaults.per_user);
+    }
+
+    #[test]
+    fn handle_swaps_snapshot_without_touching_readers() {
+        let handle = RuntimeSettingsHandle::new(settings(&["https://old.example"]));
+        let before = handle.snapshot();
+
+        handle.replace(settings(&["https://new.example"]));
+
+        let origin = HeaderValue::from_static("https://new.example");
+        assert!(!before.allows_origin(&origin));
+        assert!(handle.snapshot().allows_origin(&origin));
+        assert!(settings(&[]).allows_origin(&origin));
+    }
+}
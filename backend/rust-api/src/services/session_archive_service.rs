@@ -0,0 +1,451 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Read, Write};
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use futures::TryStreamExt;
+use mongodb::bson::{doc, DateTime as BsonDateTime};
+use mongodb::{Collection, Database};
+use uuid::Uuid;
+
+use crate::metrics::SESSIONS_ARCHIVED_TOTAL;
+use crate::models::answer::AttemptRecord;
+use crate::models::session_archive::{
+    ArchiveRunSummary, ArchivedSession, RehydratedSession, SessionArchiveStub, SessionRecord,
+};
+use crate::models::Session;
+use crate::services::object_storage::ObjectStorageClient;
+
+const SESSIONS: &str = "sessions";
+const ATTEMPTS: &str = "attempt_records";
+const STUBS: &str = "session_archive_stubs";
+const REHYDRATED: &str = "sessions_rehydrated";
+
+/// `(offset, length)` gzip member'а сессии внутри бандла
+pub type ByteRange = (u64, u64);
+
+/// Хранилище архивов сессий.
+///
+/// Архив читается диапазонами байт, чтобы восстановление одной сессии не скачивало бандл целиком.
+#[async_trait]
+pub trait ArchiveStorage: Send + Sync {
+    async fn put_bundle(&self, key: &str, bytes: Vec<u8>) -> Result<()>;
+    async fn read_range(&self, key: &str, offset: u64, length: u64) -> Result<Vec<u8>>;
+    /// Объект целиком; для небольших объектов, например частей бэкапа
+    async fn read_object(&self, key: &str) -> Result<Vec<u8>>;
+}
+
+#[async_trait]
+impl ArchiveStorage for ObjectStorageClient {
+    async fn put_bundle(&self, key: &str, bytes: Vec<u8>) -> Result<()> {
+        self.upload_bytes(key, bytes, "application/gzip").await
+    }
+
+    async fn read_range(&self, key: &str, offset: u64, length: u64) -> Result<Vec<u8>> {
+        self.get_object_range(key, offset, length).await
+    }
+
+    async fn read_object(&self, key: &str) -> Result<Vec<u8>> {
+        self.get_object(key).await
+    }
+}
+
+/// Где сейчас находится сессия
+#[derive(Debug)]
+pub enum SessionLookup {
+    /// В основной коллекции или во временной восстановленной копии
+    Found(Box<Session>),
+    /// Перенесена в архив и ещё не восстановлена
+    Archived(Box<SessionArchiveStub>),
+    Missing,
+}
+
+pub struct SessionArchiveService {
+    mongo: Database,
+}
+
+impl SessionArchiveService {
+    pub fn new(mongo: Database) -> Self {
+        Self { mongo }
+    }
+
+    /// Перенести в архив один батч сессий, начатых раньше `cutoff`.
+    ///
+    /// Сессии группируются в бандлы по месяцу и группе. Порядок шагов (загрузка бандла,
+    /// заглушки, удаление) позволяет безопасно повторить батч после сбоя. Агрегаты
+    /// (progress_summary, materialized_stats, leaderboards) не затрагиваются.
+    pub async fn archive_batch(
+        &self,
+        storage: &dyn ArchiveStorage,
+        cutoff: DateTime<Utc>,
+        batch_size: i64,
+    ) -> Result<ArchiveRunSummary> {
+        let cutoff = BsonDateTime::from_millis(cutoff.timestamp_millis());
+        let sessions: Vec<SessionRecord> = self
+            .sessions()
+            .find(doc! { "started_at": { "$lt": cutoff } })
+            .sort(doc! { "started_at": 1 })
+            .limit(batch_size)
+            .await
+            .context("Failed to query sessions for archival")?
+            .try_collect()
+            .await
+            .context("Failed to collect sessions for archival")?;
+
+        if sessions.is_empty() {
+            return Ok(ArchiveRunSummary::default());
+        }
+
+        let session_ids: Vec<String> = sessions.iter().map(|s| s.id.clone()).collect();
+        let mut attempts_by_session: HashMap<String, Vec<AttemptRecord>> = HashMap::new();
+        let mut cursor = self
+            .attempts()
+            .find(doc! { "session_id": { "$in": &session_ids } })
+            .sort(doc! { "timestamp": 1 })
+            .await
+            .context("Failed to query attempts for archival")?;
+        while let Some(attempt) = cursor
+            .try_next()
+            .await
+            .context("Failed to read attempts for archival")?
+        {
+            attempts_by_session
+                .entry(attempt.session_id.clone())
+                .or_default()
+                .push(attempt);
+        }
+
+        let mut bundles: BTreeMap<String, Vec<ArchivedSession>> = BTreeMap::new();
+        for record in sessions {
+            let prefix = bundle_prefix(record.started_at, record.group_id.as_deref());
+            let attempts = attempts_by_session.remove(&record.id).unwrap_or_default();
+            let completed_at = record.completed_at;
+            bundles.entry(prefix).or_default().push(ArchivedSession {
+                session: record.into(),
+                completed_at,
+                attempts,
+            });
+        }
+
+        let mut summary = ArchiveRunSummary {
+            batches: 1,
+            ..Default::default()
+        };
+        let archived_at = Utc::now();
+
+        for (prefix, entries) in bundles {
+            let key = format!("{}/{}.ndjson.gz", prefix, Uuid::new_v4());
+            let (bytes, ranges) = encode_bundle(&entries)?;
+            storage
+                .put_bundle(&key, bytes)
+                .await
+                .with_context(|| format!("Failed to upload archive bundle {}", key))?;
+
+            for (entry, (offset, length)) in entries.iter().zip(ranges) {
+                let stub = SessionArchiveStub {
+                    session_id: entry.session.id.clone(),
+                    user_id: entry.session.user_id.clone(),
+                    group_id: entry.session.group_id.clone(),
+                    started_at: entry.session.started_at,
+                    archive_key: key.clone(),
+                    offset,
+                    length,
+                    attempt_count: entry.attempts.len() as u64,
+                    archived_at,
+                };
+                self.stubs()
+                    .replace_one(doc! { "_id": &stub.session_id }, &stub)
+                    .upsert(true)
+                    .await
+                    .context("Failed to store archive stub")?;
+
+                summary.sessions_archived += 1;
+                summary.attempts_archived += entry.attempts.len() as u64;
+            }
+            summary.bundles_written += 1;
+        }
+
+        self.attempts()
+            .delete_many(doc! { "session_id": { "$in": &session_ids } })
+            .await
+            .context("Failed to delete archived attempts")?;
+        self.sessions()
+            .delete_many(doc! { "_id": { "$in": &session_ids } })
+            .await
+            .context("Failed to delete archived sessions")?;
+
+        SESSIONS_ARCHIVED_TOTAL
+            .with_label_values(&["archived"])
+            .inc_by(summary.sessions_archived);
+
+        Ok(summary)
+    }
+
+    /// Восстановить архивированную сессию во временную коллекцию на `ttl`.
+    ///
+    /// Из хранилища читается только диапазон байт этой сессии (по индексу в заглушке).
+    pub async fn rehydrate(
+        &self,
+        storage: &dyn ArchiveStorage,
+        session_id: &str,
+        ttl: Duration,
+    ) -> Result<RehydratedSession> {
+        let now = Utc::now();
+        let expires_at = now + ttl;
+
+        if let Some(mut existing) = self.find_rehydrated(session_id).await? {
+            let expires_at_bson = BsonDateTime::from_millis(expires_at.timestamp_millis());
+            self.rehydrated()
+                .update_one(
+                    doc! { "_id": session_id },
+                    doc! { "$set": { "expires_at": expires_at_bson } },
+                )
+                .await
+                .context("Failed to extend rehydrated session TTL")?;
+            existing.expires_at = expires_at;
+            return Ok(existing);
+        }
+
+        let stub = self
+            .stubs()
+            .find_one(doc! { "_id": session_id })
+            .await
+            .context("Failed to load archive stub")?
+            .ok_or_else(|| anyhow!("Archived session not found"))?;
+
+        let bytes = storage
+            .read_range(&stub.archive_key, stub.offset, stub.length)
+            .await
+            .with_context(|| format!("Failed to read archive {}", stub.archive_key))?;
+        let archived = decode_entry(&bytes)?;
+        if archived.session.id != session_id {
+            bail!(
+                "Archive index mismatch: expected session {}, found {}",
+                session_id,
+                archived.session.id
+            );
+        }
+
+        let copy = RehydratedSession {
+            session_id: session_id.to_string(),
+            session: SessionRecord::from_session(archived.session, archived.completed_at),
+            attempts: archived.attempts,
+            archive_key: stub.archive_key,
+            rehydrated_at: now,
+            expires_at,
+        };
+
+        // TTL-индекс по `expires_at` объявлен в index_registry
+        self.rehydrated()
+            .replace_one(doc! { "_id": session_id }, &copy)
+            .upsert(true)
+            .await
+            .context("Failed to store rehydrated session")?;
+
+        SESSIONS_ARCHIVED_TOTAL
+            .with_label_values(&["rehydrated"])
+            .inc();
+
+        Ok(copy)
+    }
+
+    /// Найти завершённую сессию: основная коллекция, затем восстановленная копия, затем архив.
+    pub async fn find_session(&self, session_id: &str) -> Result<SessionLookup> {
+        if let Some(record) = self
+            .sessions()
+            .find_one(doc! { "_id": session_id })
+            .await
+            .context("Failed to load session")?
+        {
+            return Ok(SessionLookup::Found(Box::new(record.into())));
+        }
+
+        if let Some(copy) = self.find_rehydrated(session_id).await? {
+            return Ok(SessionLookup::Found(Box::new(copy.session.into())));
+        }
+
+        let stub = self
+            .stubs()
+            .find_one(doc! { "_id": session_id })
+            .await
+            .context("Failed to load archive stub")?;
+
+        Ok(match stub {
+            Some(stub) => SessionLookup::Archived(Box::new(stub)),
+            None => SessionLookup::Missing,
+        })
+    }
+
+    /// Удалить просроченные восстановленные копии.
+    ///
+    /// TTL-индекс MongoDB делает то же самое, но с задержкой до минуты; воркер
+    /// вызывает это явно, а чтение в любом случае игнорирует просроченные копии.
+    pub async fn purge_expired_rehydrated(&self) -> Result<u64> {
+        let result = self
+            .rehydrated()
+            .delete_many(doc! { "expires_at": { "$lte": BsonDateTime::now() } })
+            .await
+            .context("Failed to purge expired rehydrated sessions")?;
+        Ok(result.deleted_count)
+    }
+
+    async fn find_rehydrated(&self, session_id: &str) -> Result<Option<RehydratedSession>> {
+        self.rehydrated()
+            .find_one(doc! {
+                "_id": session_id,
+                "expires_at": { "$gt": BsonDateTime::now() },
+            })
+            .await
+            .context("Failed to load rehydrated session")
+    }
+
+    fn sessions(&self) -> Collection<SessionRecord> {
+        self.mongo.collection(SESSIONS)
+    }
+
+    fn attempts(&self) -> Collection<AttemptRecord> {
+        self.mongo.collection(ATTEMPTS)
+    }
+
+    fn stubs(&self) -> Collection<SessionArchiveStub> {
+        self.mongo.collection(STUBS)
+    }
+
+    fn rehydrated(&self) -> Collection<RehydratedSession> {
+        self.mongo.collection(REHYDRATED)
+    }
+}
+
+/// `archives/sessions/{YYYY-MM}/{group_id}` - бандлы раскладываются по месяцу и группе
+pub fn bundle_prefix(started_at: DateTime<Utc>, group_id: Option<&str>) -> String {
+    format!(
+        "archives/sessions/{}/{}",
+        started_at.format("%Y-%m"),
+        group_id.filter(|id| !id.is_empty()).unwrap_or("ungrouped")
+    )
+}
+
+/// Закодировать бандл: каждая сессия - отдельный gzip member с одной строкой NDJSON.
+///
+/// Склейка gzip members остаётся валидным `.ndjson.gz`, а возвращаемые диапазоны
+/// `(offset, length)` позволяют распаковать любую сессию независимо.
+pub fn encode_bundle(entries: &[ArchivedSession]) -> Result<(Vec<u8>, Vec<ByteRange>)> {
+    let mut bundle = Vec::new();
+    let mut ranges = Vec::with_capacity(entries.len());
+
+    for entry in entries {
+        let mut line = serde_json::to_vec(entry).context("Failed to serialize archived session")?;
+        line.push(b'\n');
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&line)?;
+        let member = encoder
+            .finish()
+            .context("Failed to compress archived session")?;
+
+        ranges.push((bundle.len() as u64, member.len() as u64));
+        bundle.extend_from_slice(&member);
+    }
+
+    Ok((bundle, ranges))
+}
+
+/// Распаковать одну сессию из диапазона, указанного в заглушке
+pub fn decode_entry(member: &[u8]) -> Result<ArchivedSession> {
+    let mut line = String::new();
+    GzDecoder::new(member)
+        .read_to_string(&mut line)
+        .context("Failed to decompress archived session")?;
+    serde_json::from_str(line.trim_end()).context("Failed to parse archived session")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{SessionMode, SessionStatus};
+    use chrono::TimeZone;
+    use flate2::read::MultiGzDecoder;
+
+    fn entry(id: &str, attempts: usize) -> ArchivedSession {
+        let started_at = Utc.with_ymd_and_hms(2024, 3, 5, 10, 0, 0).unwrap();
+        ArchivedSession {
+            session: Session {
+                id: id.into(),
+                user_id: "user-1".into(),
+                task_id: "task-1".into(),
+                group_id: Some("group-1".into()),
+                started_at,
+                expires_at: started_at + Duration::hours(1),
+                status: SessionStatus::Completed,
+                hints_used: 1,
+                score: 10,
+                level_id: None,
+                mode: SessionMode::Normal,
+            },
+            completed_at: Some(started_at + Duration::minutes(20)),
+            attempts: (0..attempts)
+                .map(|n| AttemptRecord {
+                    id: format!("{}-attempt-{}", id, n),
+                    session_id: id.into(),
+                    user_id: "user-1".into(),
+                    task_id: "task-1".into(),
+                    answer: "42".into(),
+                    correct: true,
+                    score: 10,
+                    timestamp: started_at,
+                    reason: None,
+                    credit: None,
+                    timing: None,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn each_session_decodes_from_its_own_range() {
+        let entries = vec![entry("s-1", 2), entry("s-2", 0), entry("s-3", 5)];
+        let (bundle, ranges) = encode_bundle(&entries).unwrap();
+
+        assert_eq!(ranges.len(), 3);
+        assert_eq!(ranges[0].0, 0);
+        let (last_offset, last_length) = ranges[2];
+        assert_eq!((last_offset + last_length) as usize, bundle.len());
+
+        for (expected, (offset, length)) in entries.iter().zip(ranges) {
+            let member = &bundle[offset as usize..(offset + length) as usize];
+            let decoded = decode_entry(member).unwrap();
+            assert_eq!(decoded.session.id, expected.session.id);
+            assert_eq!(decoded.attempts.len(), expected.attempts.len());
+        }
+    }
+
+    #[test]
+    fn bundle_is_plain_ndjson_gzip() {
+        let entries = vec![entry("s-1", 1), entry("s-2", 1)];
+        let (bundle, _) = encode_bundle(&entries).unwrap();
+
+        let mut text = String::new();
+        MultiGzDecoder::new(bundle.as_slice())
+            .read_to_string(&mut text)
+            .unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let first: ArchivedSession = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(first.session.id, "s-1");
+    }
+
+    #[test]
+    fn bundle_prefix_uses_month_and_group() {
+        let started_at = Utc.with_ymd_and_hms(2024, 11, 30, 23, 59, 0).unwrap();
+        assert_eq!(
+            bundle_prefix(started_at, Some("abc")),
+            "archives/sessions/2024-11/abc"
+        );
+        assert_eq!(
+            bundle_prefix(started_at, None),
+            "archives/sessions/2024-11/ungrouped"
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use anyhow::{Context, Result};
+use redis::aio::ConnectionManager;
+use serde::{Deserialize, Serialize};
+
+use crate::models::timer::TimerEvent;
+
+/// Присваивает событию следующий id сессии и кладёт его в ограниченный буфер.
+/// KEYS: счётчик, буфер, ключ сессии (его TTL переносится на буфер).
+/// ARGV[1] - событие в JSON, ARGV[2] - размер буфера, ARGV[3] - TTL, если у сессии его нет
+const APPEND_EVENT_SCRIPT: &str = r#"
+    local id = redis.call('INCR', KEYS[1])
+    local entry = '{"id":' .. id .. ',"event":' .. ARGV[1] .. '}'
+    redis.call('RPUSH', KEYS[2], entry)
+    redis.call('LTRIM', KEYS[2], -tonumber(ARGV[2]), -1)
+
+    local ttl = redis.call('TTL', KEYS[3])
+    if ttl < 1 then
+        ttl = tonumber(ARGV[3])
+    end
+    redis.call('EXPIRE', KEYS[1], ttl)
+    redis.call('EXPIRE', KEYS[2], ttl)
+    return entry
+"#;
+
+/// TTL буфера, если ключ сессии уже исчез
+const FALLBACK_TTL_SECS: u64 = 3600;
+
+/// Канал Redis Pub/Sub с серверными событиями сессии; их пересылает SSE-стрим сессии
+pub fn session_events_channel(session_id: &str) -> String {
+    format!("session:events:{}", session_id)
+}
+
+pub fn session_event_seq_key(session_id: &str) -> String {
+    format!("session:events:seq:{}", session_id)
+}
+
+pub fn session_event_log_key(session_id: &str) -> String {
+    format!("session:events:log:{}", session_id)
+}
+
+/// Событие SSE-стрима с порядковым номером внутри сессии (уходит клиенту как `id:`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggedEvent {
+    pub id: u64,
+    pub event: TimerEvent,
+}
+
+/// Последние события SSE-стрима сессии: по `Last-Event-ID` переподключившийся клиент
+/// получает пропущенные события
+pub struct SessionEventLog {
+    redis: ConnectionManager,
+    capacity: usize,
+}
+
+impl SessionEventLog {
+    pub fn new(redis: ConnectionManager, capacity: usize) -> Self {
+        Self {
+            redis,
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Записать событие в буфер и получить его id
+    pub async fn append(&self, session_id: &str, event: &TimerEvent) -> Result<LoggedEvent> {
+        let mut conn = self.redis.clone();
+        let script = redis::Script::new(APPEND_EVENT_SCRIPT);
+        let entry: String = script
+            .key(session_event_seq_key(session_id))
+            .key(session_event_log_key(session_id))
+            .key(format!("session:{}", session_id))
+            .arg(event.to_sse_data())
+            .arg(self.capacity)
+            .arg(FALLBACK_TTL_SECS)
+            .invoke_async(&mut conn)
+            .await
+            .context("Failed to append session event")?;
+        serde_json::from_str(&entry).context("Failed to decode session event")
+    }
+
+    /// Записать событие и разослать его открытым SSE-стримам сессии
+    pub async fn publish(&self, session_id: &str, event: &TimerEvent) -> Result<LoggedEvent> {
+        let logged = self.append(session_id, event).await?;
+        let mut conn = self.redis.clone();
+        let _: () = redis::cmd("PUBLISH")
+            .arg(session_events_channel(session_id))
+            .arg(serde_json::to_string(&logged)?)
+            .query_async(&mut conn)
+            .await
+            .context("Failed to publish session event")?;
+        Ok(logged)
+    }
+
+    /// События буфера с id больше `last_id`, по возрастанию
+    pub async fn since(&self, session_id: &str, last_id: u64) -> Result<Vec<LoggedEvent>> {
+        let mut conn = self.redis.clone();
+        let entries: Vec<String> = redis::cmd("LRANGE")
+            .arg(session_event_log_key(session_id))
+            .arg(0)
+            .arg(-1)
+            .query_async(&mut conn)
+            .await
+            .context("Failed to read session events")?;
+        Ok(entries
+            .iter()
+            .filter_map(|entry| serde_json::from_str::<LoggedEvent>(entry).ok())
+            .filter(|logged| logged.id > last_id)
+            .collect())
+    }
+}
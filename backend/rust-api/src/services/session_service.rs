@@ -1,22 +1,110 @@
-use crate::metrics::{track_cache_operation, SESSIONS_ACTIVE, SESSIONS_TOTAL};
+use crate::config::SessionSettings;
+use crate::metrics::{track_cache_operation, ACTIVE_SESSIONS, SESSIONS_TOTAL};
+use crate::models::achievement::UnlockedAchievement;
+use crate::models::timer::{is_past_deadline, AttemptsExhausted, TimerEvent};
 use crate::models::{
-    content::LevelRecord, CreateSessionRequest, CreateSessionResponse, Session, SessionStatus,
-    TaskInfo,
+    content::{AgeBand, LevelRecord},
+    review::ReviewItem,
+    session_archive::SessionRecord,
+    AutoSessionRequest, CreateSessionRequest, CreateSessionResponse, MissingPrerequisite,
+    ProgressSummary, Session, SessionMode, SessionStatus, TaskInfo,
 };
 use anyhow::{anyhow, Context, Result};
 use chrono::Utc;
+use chrono_tz::Tz;
 use futures::TryStreamExt;
 use mongodb::bson::{doc, oid::ObjectId, Bson, Document};
+use mongodb::error::TRANSIENT_TRANSACTION_ERROR;
 use mongodb::options::FindOptions;
 use mongodb::Database;
+use rand::Rng;
 use redis::aio::ConnectionManager;
 use reqwest::Client;
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
+use crate::utils::mongo_retry::retry_read;
+
+use crate::services::achievement_service::AchievementService;
+use crate::services::answer_service::{
+    publish_achievement, session_answer_offsets_key, session_score_key, AnswerService,
+    FinalSessionScore,
+};
+use crate::services::assignment_service::{
+    parse_session_assignment, session_assignment_key, session_assignment_value, AssignmentService,
+};
+use crate::services::content_cache::{ContentCache, ContentCacheKind, TemplateMeta};
+use crate::services::group_service::GroupService;
+use crate::services::hint_service::{hint_penalty_key, hints_used_key};
+use crate::services::notification_center_service::NotificationCenterService;
+use crate::services::prefetch_service::active_session_key;
+use crate::services::review_service::{ReviewQueueEmptyError, ReviewService};
+use crate::services::session_events::{
+    session_event_log_key, session_event_seq_key, SessionEventLog,
+};
+use crate::services::task_selection_service::{
+    select_candidate, NoTemplatesAvailableError, TaskSelectionService,
+};
 use crate::services::template_generator::{
     request_instances, GenerateInstancesRequest, TaskInstance,
 };
 
+/// Сколько раз повторяется транзакция завершения сессии при конфликте записи
+const FINISH_TRANSACTION_ATTEMPTS: u32 = 3;
+
+const CLEAR_ACTIVE_SESSION_SCRIPT: &str = r#"
+    if redis.call('GET', KEYS[1]) == ARGV[1] then
+        return redis.call('DEL', KEYS[1])
+    end
+    return 0
+"#;
+
+/// Бизнес-метрики жизненного цикла сессии. Считаются в сервисах, а не в хендлерах,
+/// чтобы учитывалось и истечение, зафиксированное при ответе после дедлайна
+fn record_session_started() {
+    SESSIONS_TOTAL.with_label_values(&["started"]).inc();
+    ACTIVE_SESSIONS.inc();
+}
+
+/// Сессия вышла из активных: завершена или истекла
+pub(crate) fn record_session_finished(status: SessionStatus) {
+    let label = match status {
+        SessionStatus::Active => return,
+        SessionStatus::Completed => "completed",
+        SessionStatus::Expired => "expired",
+        SessionStatus::Abandoned => "abandoned",
+    };
+    SESSIONS_TOTAL.with_label_values(&[label]).inc();
+    ACTIVE_SESSIONS.dec();
+}
+
+/// Уровень задания закрыт: не пройдены его пререквизиты
+#[derive(Debug, thiserror::Error)]
+#[error("Level {level_id} is locked by unmet prerequisites")]
+pub struct LevelLockedError {
+    pub level_id: String,
+    pub missing: Vec<MissingPrerequisite>,
+}
+
+/// Задание сессии выше возрастной категории ученика
+#[derive(Debug, thiserror::Error)]
+#[error("Content is not available for the student's age band")]
+pub struct AgeBandRestrictedError;
+
+#[derive(Debug, thiserror::Error)]
+#[error("Group {group_id} is archived")]
+pub struct GroupArchivedError {
+    pub group_id: String,
+}
+
+/// Итог `complete_session`: истёкшая сессия не завершается, а фиксируется как `expired`
+/// со счётом, набранным до дедлайна (ответы после него отклоняются)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionCompletion {
+    Completed(FinalSessionScore),
+    Expired(FinalSessionScore),
+}
+
 pub struct SessionService {
     mongo: Database,
     redis: ConnectionManager,
@@ -34,13 +122,78 @@ impl SessionService {
         }
     }
 
-    pub async fn create_session(&self, req: CreateSessionRequest) -> Result<CreateSessionResponse> {
-        let session_id = Uuid::new_v4().to_string();
+    /// Сессия всегда открывается для `user_id` из токена: пререквизиты, очередь
+    /// повторения и задание проверяются для него, а не для `user_id` из тела запроса.
+    /// С `age_band` шаблон задания не может быть выше этой возрастной категории
+    pub async fn create_session(
+        &self,
+        user_id: &str,
+        mut req: CreateSessionRequest,
+        age_band: Option<AgeBand>,
+    ) -> Result<CreateSessionResponse> {
+        req.user_id = user_id.to_string();
+        if let Some(assignment_id) = req.assignment_id.clone() {
+            return self
+                .create_assignment_session(req, &assignment_id, age_band)
+                .await;
+        }
+        self.ensure_group_active(req.group_id.as_deref()).await?;
+
+        let review_item = match req.mode {
+            SessionMode::Review => Some(
+                ReviewService::new(self.mongo.clone())
+                    .next_due(&req.user_id, 1)
+                    .await?
+                    .0
+                    .into_iter()
+                    .next()
+                    .ok_or(ReviewQueueEmptyError)?,
+            ),
+            SessionMode::Normal => None,
+        };
+
+        let level_id = match (&review_item, &req.level_id) {
+            (Some(item), _) => match self.template_level_id(&item.template_id).await? {
+                Some(level_id) => Some(level_id),
+                None => self.task_level_id(&item.task_id).await?,
+            },
+            (None, Some(level_id)) => Some(level_id.clone()),
+            (None, None) => self.task_level_id(&req.task_id).await?,
+        };
+        if let Some(level_id) = &level_id {
+            let missing = self.missing_prerequisites(&req.user_id, level_id).await?;
+            if !missing.is_empty() {
+                return Err(LevelLockedError {
+                    level_id: level_id.clone(),
+                    missing,
+                }
+                .into());
+            }
+        }
 
-        // Попытка генерации через Template Generator если указан level_id
-        let task = if let Some(ref level_id) = req.level_id {
+        if let (Some(band), Some(item)) = (age_band, &review_item) {
+            self.ensure_age_band(band, &item.template_id).await?;
+        }
+        // Если на уровне есть шаблоны выше категории, шаблон выбирается здесь, а не генератором
+        let mut band_template = None;
+        let level_obj = req.level_id.as_deref().map(ObjectId::parse_str);
+        if let (Some(band), None, Some(Ok(level_obj))) = (age_band, &review_item, level_obj) {
+            if let Some(allowed) = TaskSelectionService::new(self.mongo.clone())
+                .level_templates_for_band(&level_obj, band)
+                .await?
+            {
+                let template_id = allowed.first().ok_or(AgeBandRestrictedError)?;
+                band_template = Some(template_id.to_hex());
+            }
+        }
+
+        let task = if let Some(item) = &review_item {
+            self.fetch_review_task(item, level_id.as_deref(), &req.user_id)
+                .await?
+        } else if let Some(ref level_id) = req.level_id {
+            // Попытка генерации через Template Generator если указан level_id
             match self
-                .generate_and_store_task(level_id, &req.user_id, None, false)
+                .generate_and_store_task(level_id, &req.user_id, band_template.as_deref(), false)
                 .await
             {
                 Ok(generated_task) => {
@@ -74,7 +227,179 @@ impl SessionService {
             // Fallback на готовое задание из MongoDB
             self.fetch_task(&req.task_id).await?
         };
+        // Сохранённое задание (когда генератор недоступен) может оказаться из любого шаблона
+        if let (Some(band), Some(template_id)) = (age_band, task.template_id) {
+            self.ensure_age_band(band, &template_id).await?;
+        }
 
+        // В режиме повторения уровень берётся из шаблона, чтобы ответы шли в его прогресс
+        let session_level_id = match req.mode {
+            SessionMode::Review => level_id,
+            SessionMode::Normal => req.level_id.clone(),
+        };
+        self.open_session(&req, task, session_level_id).await
+    }
+
+    /// Сессия с заданием, подобранным по слабым темам ученика
+    /// (см. `task_selection_service`). `rng` передаётся снаружи, чтобы выбор был воспроизводим
+    pub async fn create_auto_session<R: Rng + ?Sized>(
+        &self,
+        user_id: &str,
+        req: AutoSessionRequest,
+        age_band: Option<AgeBand>,
+        rng: &mut R,
+    ) -> Result<CreateSessionResponse> {
+        self.ensure_group_active(req.group_id.as_deref()).await?;
+
+        let selection = TaskSelectionService::new(self.mongo.clone());
+        let mut candidates = selection.load_candidates(age_band).await?;
+        let profile = selection.load_profile(user_id).await?;
+
+        // Уровни с непройденными пререквизитами не предлагаются
+        let mut locked = HashSet::new();
+        let gated: HashSet<ObjectId> = candidates
+            .iter()
+            .filter(|candidate| candidate.has_prerequisites)
+            .map(|candidate| candidate.level_id)
+            .collect();
+        for level_id in gated {
+            if !self
+                .missing_prerequisites(user_id, &level_id.to_hex())
+                .await?
+                .is_empty()
+            {
+                locked.insert(level_id);
+            }
+        }
+        candidates.retain(|candidate| !locked.contains(&candidate.level_id));
+
+        let candidate = select_candidate(&candidates, &profile, rng)
+            .ok_or(NoTemplatesAvailableError)?
+            .clone();
+        let level_id = candidate.level_id.to_hex();
+        let template_id = candidate.template_id.to_hex();
+        tracing::info!(
+            "Auto-selected template {} (level {}) for user {}",
+            template_id,
+            level_id,
+            user_id
+        );
+
+        let task = match self
+            .generate_and_store_task(&level_id, user_id, Some(&template_id), true)
+            .await
+        {
+            Ok(task) => task,
+            Err(e) => {
+                tracing::warn!(
+                    "Template Generator failed for auto-selected template {} ({}), using a stored task",
+                    template_id,
+                    e
+                );
+                self.fetch_recent_task_for_template(&candidate.template_id)
+                    .await?
+            }
+        };
+
+        let request = CreateSessionRequest {
+            user_id: user_id.to_string(),
+            task_id: task.id.clone(),
+            group_id: req.group_id,
+            level_id: Some(level_id.clone()),
+            session_duration_seconds: req.session_duration_seconds,
+            mode: SessionMode::Normal,
+            assignment_id: None,
+        };
+        self.open_session(&request, task, Some(level_id)).await
+    }
+
+    /// Сессия по домашнему заданию: следующий невыполненный шаблон задания.
+    /// Связь сессии с заданием хранится рядом с сессией и читается при завершении
+    async fn create_assignment_session(
+        &self,
+        req: CreateSessionRequest,
+        assignment_id: &str,
+        age_band: Option<AgeBand>,
+    ) -> Result<CreateSessionResponse> {
+        let (assignment, template_id) = AssignmentService::new(self.mongo.clone())
+            .start(assignment_id, &req.user_id, Utc::now())
+            .await?;
+        if let Some(band) = age_band {
+            self.ensure_age_band(band, &template_id).await?;
+        }
+        let group_id = assignment.group_id.to_hex();
+        self.ensure_group_active(Some(&group_id)).await?;
+
+        let level_id = self.template_level_id(&template_id).await?;
+        let generated = match &level_id {
+            Some(level_id) => self
+                .generate_and_store_task(
+                    level_id,
+                    &req.user_id,
+                    Some(&template_id.to_hex()),
+                    true,
+                )
+                .await
+                .inspect_err(|e| {
+                    tracing::warn!(
+                        "Template Generator failed for assignment template {} ({}), using a stored task",
+                        template_id,
+                        e
+                    )
+                })
+                .ok(),
+            None => None,
+        };
+        let task = match generated {
+            Some(task) => task,
+            None => self.fetch_recent_task_for_template(&template_id).await?,
+        };
+
+        let request = CreateSessionRequest {
+            user_id: req.user_id,
+            task_id: task.id.clone(),
+            group_id: Some(group_id),
+            level_id: level_id.clone(),
+            session_duration_seconds: req.session_duration_seconds,
+            mode: SessionMode::Normal,
+            assignment_id: Some(assignment.id.to_hex()),
+        };
+        let response = self.open_session(&request, task, level_id).await?;
+
+        let mut conn = self.redis.clone();
+        redis::cmd("SETEX")
+            .arg(session_assignment_key(&response.session_id))
+            .arg(3600)
+            .arg(session_assignment_value(&assignment.id, &template_id))
+            .query_async::<()>(&mut conn)
+            .await
+            .context("Failed to link session to assignment")?;
+        Ok(response)
+    }
+
+    async fn ensure_group_active(&self, group_id: Option<&str>) -> Result<()> {
+        if let Some(group_id) = group_id {
+            if GroupService::new(self.mongo.clone())
+                .is_archived(group_id)
+                .await?
+            {
+                return Err(GroupArchivedError {
+                    group_id: group_id.to_string(),
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Сохранить новую сессию с выбранным заданием в Redis
+    async fn open_session(
+        &self,
+        req: &CreateSessionRequest,
+        task: FetchedTask,
+        level_id: Option<String>,
+    ) -> Result<CreateSessionResponse> {
+        let session_id = Uuid::new_v4().to_string();
         let now = Utc::now();
         let default_ttl = std::env::var("SESSION_DURATION_SECONDS")
             .ok()
@@ -94,7 +419,8 @@ impl SessionService {
             status: SessionStatus::Active,
             hints_used: 0,
             score: 0,
-            level_id: req.level_id.clone(),
+            level_id,
+            mode: req.mode,
         };
 
         // Save to Redis with TTL - clone connection for this operation
@@ -104,20 +430,27 @@ impl SessionService {
         let session_json = serde_json::to_string(&session)?;
 
         // Track cache operation with metrics
+        // Вместе с сессией ставим указатель на неё (нужен prefetch-манифесту)
         track_cache_operation("setex", async {
-            redis::cmd("SETEX")
+            redis::pipe()
+                .atomic()
+                .cmd("SETEX")
                 .arg(&session_key)
                 .arg(3600) // TTL 1 hour
                 .arg(session_json)
+                .ignore()
+                .cmd("SETEX")
+                .arg(active_session_key(&req.user_id))
+                .arg(3600)
+                .arg(&session_id)
+                .ignore()
                 .query_async::<()>(&mut conn)
                 .await
                 .context("Failed to save session to Redis")
         })
         .await?;
 
-        // Record business metrics
-        SESSIONS_TOTAL.with_label_values(&["created"]).inc();
-        SESSIONS_ACTIVE.inc();
+        record_session_started();
 
         tracing::info!("Session created: {} for user: {}", session_id, req.user_id);
 
@@ -154,11 +487,73 @@ impl SessionService {
         Ok(session)
     }
 
-    pub async fn complete_session(&self, session_id: &str) -> Result<()> {
+    pub async fn complete_session(
+        &self,
+        session_id: &str,
+        settings: &SessionSettings,
+    ) -> Result<SessionCompletion> {
+        self.finish_session(session_id, settings, None).await
+    }
+
+    /// Принят последний ответ задания с `max_attempts`: сессия завершается без запроса
+    /// клиента, а стрим получает итог событием `attempts-exhausted`
+    pub async fn complete_out_of_attempts(
+        &self,
+        session_id: &str,
+        max_attempts: u32,
+        settings: &SessionSettings,
+    ) -> Result<SessionCompletion> {
+        self.finish_session(session_id, settings, Some(max_attempts))
+            .await
+    }
+
+    /// `out_of_attempts` - лимит ответов задания, если сессию завершил последний из них
+    async fn finish_session(
+        &self,
+        session_id: &str,
+        settings: &SessionSettings,
+        out_of_attempts: Option<u32>,
+    ) -> Result<SessionCompletion> {
         // Get session from Redis
-        let _session = self.get_session(session_id).await?;
+        let mut session = self.get_session(session_id).await?;
+
+        let user_id = session.user_id.clone();
+        // Переход в expired уже учтён в метриках, если его зафиксировал ответ после дедлайна
+        let already_expired = matches!(session.status, SessionStatus::Expired);
+        let expired = already_expired
+            || is_past_deadline(session.expires_at, settings.grace_seconds, Utc::now());
+
+        // Итоговый счёт с учётом штрафов за подсказки
+        let final_score =
+            AnswerService::new(self.mongo.clone(), self.redis.clone(), settings.clone())
+                .final_session_score(session_id)
+                .await?;
+        session.score = final_score.score;
+        session.hints_used = final_score.hints_used;
+
+        // Persist final results to MongoDB (source for history and archival)
+        session.status = if expired {
+            SessionStatus::Expired
+        } else {
+            SessionStatus::Completed
+        };
+        let record = SessionRecord::from_session(session, Some(Utc::now()));
+        let unlocked = self
+            .persist_finished_session(&record, settings, !expired)
+            .await?;
 
-        // TODO: Update MongoDB with final results
+        // Достижения за сессию отправляются в её стрим, пока журнал событий ещё не удалён
+        if !expired {
+            self.publish_session_achievements(&user_id, session_id, unlocked, settings)
+                .await;
+            self.record_assignment_completion(&user_id, session_id, final_score.score)
+                .await;
+        }
+        // Завершающее событие закрывает стрим, поэтому идёт после достижений
+        if let Some(max_attempts) = out_of_attempts {
+            self.publish_attempts_exhausted(session_id, max_attempts, final_score, settings)
+                .await;
+        }
 
         // Delete from Redis - clone connection for this operation
         let mut conn = self.redis.clone();
@@ -167,19 +562,179 @@ impl SessionService {
         track_cache_operation("del", async {
             redis::cmd("DEL")
                 .arg(&session_key)
+                .arg(session_score_key(session_id))
+                .arg(session_answer_offsets_key(session_id))
+                .arg(hints_used_key(session_id))
+                .arg(hint_penalty_key(session_id))
+                .arg(session_event_seq_key(session_id))
+                .arg(session_event_log_key(session_id))
+                .arg(session_assignment_key(session_id))
                 .query_async::<()>(&mut conn)
                 .await
                 .context("Failed to delete session from Redis")
         })
         .await?;
 
-        // Record business metrics
-        SESSIONS_TOTAL.with_label_values(&["completed"]).inc();
-        SESSIONS_ACTIVE.dec();
+        // Указатель снимаем, только если он не успел смениться на более новую сессию
+        let _: i32 = redis::Script::new(CLEAR_ACTIVE_SESSION_SCRIPT)
+            .key(active_session_key(&user_id))
+            .arg(session_id)
+            .invoke_async(&mut conn)
+            .await
+            .context("Failed to clear active session pointer")?;
+
+        if expired {
+            if !already_expired {
+                record_session_finished(SessionStatus::Expired);
+            }
+            tracing::info!("Session expired before completion: {}", session_id);
+            return Ok(SessionCompletion::Expired(final_score));
+        }
+        record_session_finished(SessionStatus::Completed);
 
         tracing::info!("Session completed: {}", session_id);
 
-        Ok(())
+        Ok(SessionCompletion::Completed(final_score))
+    }
+
+    async fn publish_attempts_exhausted(
+        &self,
+        session_id: &str,
+        max_attempts: u32,
+        final_score: FinalSessionScore,
+        settings: &SessionSettings,
+    ) {
+        let event = TimerEvent::AttemptsExhausted(AttemptsExhausted {
+            session_id: session_id.to_string(),
+            max_attempts,
+            score: final_score.score,
+            timestamp: Utc::now(),
+        });
+        if let Err(e) = SessionEventLog::new(self.redis.clone(), settings.event_buffer_size)
+            .publish(session_id, &event)
+            .await
+        {
+            tracing::warn!(
+                "Failed to publish attempts-exhausted for session {}: {:#}",
+                session_id,
+                e
+            );
+        }
+    }
+
+    /// Засчитать шаблон домашнего задания, если сессия была начата по нему
+    async fn record_assignment_completion(&self, user_id: &str, session_id: &str, score: i32) {
+        let mut conn = self.redis.clone();
+        let link: Option<String> = redis::cmd("GET")
+            .arg(session_assignment_key(session_id))
+            .query_async(&mut conn)
+            .await
+            .unwrap_or_default();
+        let Some((assignment_id, template_id)) = link.as_deref().and_then(parse_session_assignment)
+        else {
+            return;
+        };
+        if let Err(e) = AssignmentService::new(self.mongo.clone())
+            .record_completion(
+                &assignment_id,
+                &template_id,
+                user_id,
+                session_id,
+                score,
+                Utc::now(),
+            )
+            .await
+        {
+            tracing::warn!(
+                "Failed to record assignment {} completion for session {}: {:#}",
+                assignment_id,
+                session_id,
+                e
+            );
+        }
+    }
+
+    /// Итог сессии и серия с достижениями (для завершённой, не истёкшей сессии) пишутся
+    /// одной транзакцией: сессия не может завершиться без учёта в достижениях
+    async fn persist_finished_session(
+        &self,
+        record: &SessionRecord,
+        settings: &SessionSettings,
+        completed: bool,
+    ) -> Result<Vec<UnlockedAchievement>> {
+        let achievements = AchievementService::new(self.mongo.clone(), settings);
+        let timezone = if completed {
+            Some(achievements.user_timezone(&record.user_id).await?)
+        } else {
+            None
+        };
+
+        let mut attempt = 1;
+        loop {
+            match self
+                .try_persist_finished_session(record, &achievements, timezone)
+                .await
+            {
+                // Конфликт записи с параллельной транзакцией: повторяем целиком
+                Err(err)
+                    if attempt < FINISH_TRANSACTION_ATTEMPTS
+                        && err.contains_label(TRANSIENT_TRANSACTION_ERROR) =>
+                {
+                    attempt += 1;
+                }
+                result => return result.context("Failed to persist completed session"),
+            }
+        }
+    }
+
+    async fn try_persist_finished_session(
+        &self,
+        record: &SessionRecord,
+        achievements: &AchievementService,
+        timezone: Option<Tz>,
+    ) -> mongodb::error::Result<Vec<UnlockedAchievement>> {
+        let mut session = self.mongo.client().start_session().await?;
+        session.start_transaction().await?;
+
+        // `$set` вместо замены: счётчик `attempts_used` идущей сессии сохраняется
+        let mut fields = mongodb::bson::to_document(record)?;
+        fields.remove("_id");
+        self.mongo
+            .collection::<SessionRecord>("sessions")
+            .update_one(doc! { "_id": &record.id }, doc! { "$set": fields })
+            .upsert(true)
+            .session(&mut session)
+            .await?;
+        let unlocked = match timezone {
+            Some(timezone) => {
+                achievements
+                    .record_completed_session(
+                        &mut session,
+                        &record.user_id,
+                        record.completed_at.unwrap_or_else(Utc::now),
+                        timezone,
+                    )
+                    .await?
+            }
+            None => Vec::new(),
+        };
+
+        session.commit_transaction().await?;
+        Ok(unlocked)
+    }
+
+    async fn publish_session_achievements(
+        &self,
+        user_id: &str,
+        session_id: &str,
+        unlocked: Vec<UnlockedAchievement>,
+        settings: &SessionSettings,
+    ) {
+        let log = SessionEventLog::new(self.redis.clone(), settings.event_buffer_size);
+        let notifications = NotificationCenterService::new(self.mongo.clone(), self.redis.clone());
+        for achievement in unlocked {
+            publish_achievement(&log, &notifications, user_id, session_id, achievement).await;
+        }
     }
 
     async fn fetch_task(&self, task_id: &str) -> Result<FetchedTask> {
@@ -190,11 +745,12 @@ impl SessionService {
             doc! { "_id": task_id }
         };
 
-        let task = tasks_collection
-            .find_one(filter.clone())
-            .await
-            .context("Failed to query task")?
-            .ok_or_else(|| anyhow!("Task not found"))?;
+        let task = retry_read("tasks.find_by_id", || {
+            tasks_collection.find_one(filter.clone())
+        })
+        .await
+        .context("Failed to query task")?
+        .ok_or_else(|| anyhow!("Task not found"))?;
 
         let mut fetched = Self::task_from_document(&task)?;
         if fetched.title.starts_with("Generated task from level") {
@@ -223,6 +779,32 @@ impl SessionService {
         Ok(fetched)
     }
 
+    /// Задание для сессии повторения: новое по шаблону из очереди, а если генератор
+    /// недоступен - то задание, в котором была ошибка
+    async fn fetch_review_task(
+        &self,
+        item: &ReviewItem,
+        level_id: Option<&str>,
+        user_id: &str,
+    ) -> Result<FetchedTask> {
+        let template_id = item.template_id.to_hex();
+        if let Some(level_id) = level_id {
+            match self
+                .generate_and_store_task(level_id, user_id, Some(&template_id), true)
+                .await
+            {
+                Ok(task) => return Ok(task),
+                Err(e) => tracing::warn!(
+                    "Template Generator failed for review template {} ({}), reusing task {}",
+                    template_id,
+                    e,
+                    item.task_id
+                ),
+            }
+        }
+        self.fetch_task(&item.task_id).await
+    }
+
     async fn fetch_recent_task_for_level(&self, level_id: &str) -> Result<FetchedTask> {
         let tasks_collection = self.mongo.collection::<Document>("tasks");
         let options = FindOptions::builder()
@@ -276,6 +858,19 @@ impl SessionService {
         Ok(fetched)
     }
 
+    /// Последнее сохранённое задание по шаблону (когда генератор недоступен)
+    async fn fetch_recent_task_for_template(&self, template_id: &ObjectId) -> Result<FetchedTask> {
+        let task = self
+            .mongo
+            .collection::<Document>("tasks")
+            .find_one(doc! { "template_id": template_id })
+            .sort(doc! { "createdAt": -1, "_id": -1 })
+            .await
+            .context("Failed to query stored task by template")?
+            .ok_or_else(|| anyhow!("Task not found for template {}", template_id))?;
+        Self::task_from_document(&task)
+    }
+
     /// Генерация задания через Template Generator и сохранение в MongoDB
     async fn generate_and_store_task(
         &self,
@@ -334,7 +929,7 @@ impl SessionService {
             .map(Bson::ObjectId)
             .unwrap_or_else(|_| Bson::String(level_id.to_string()));
 
-        let task_doc = doc! {
+        let mut task_doc = doc! {
             "template_id": template_object_id,
             "session_id": Uuid::new_v4().to_string(),
             "title": &title,
@@ -354,6 +949,9 @@ impl SessionService {
             "createdAt": now_bson,
             "metadata": metadata_bson,
         };
+        if let Some(answers) = self.load_template_answers(&template_object_id).await {
+            task_doc.insert("answers", answers);
+        }
 
         let insert_result = tasks_collection
             .insert_one(task_doc)
@@ -369,6 +967,7 @@ impl SessionService {
 
         Ok(FetchedTask {
             id: inserted_id,
+            template_id: Some(template_object_id),
             title,
             description,
             time_limit_seconds: 300, // 5 минут по умолчанию
@@ -407,18 +1006,153 @@ impl SessionService {
 
 struct FetchedTask {
     id: String,
+    template_id: Option<ObjectId>,
     title: String,
     description: String,
     time_limit_seconds: u32,
 }
 
 impl SessionService {
+    /// Пререквизиты уровня, которые пользователь ещё не прошёл: процент
+    /// в `progress_summary_v2` ниже `min_pass_percent` пререквизита
+    pub async fn missing_prerequisites(
+        &self,
+        user_id: &str,
+        level_id: &str,
+    ) -> Result<Vec<MissingPrerequisite>> {
+        // Старые задания ссылаются на уровни строковыми id - для них ограничений нет
+        let Ok(level_obj) = ObjectId::parse_str(level_id) else {
+            return Ok(Vec::new());
+        };
+        let Some(level) = self
+            .load_levels(std::slice::from_ref(&level_obj))
+            .await?
+            .remove(&level_obj)
+        else {
+            return Ok(Vec::new());
+        };
+        if level.prerequisite_level_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let prerequisites = self.load_levels(&level.prerequisite_level_ids).await?;
+
+        let summary_ids: Vec<String> = prerequisites
+            .values()
+            .map(|prerequisite| format!("{}:{}", user_id, prerequisite.id.to_hex()))
+            .collect();
+        let progress: Vec<ProgressSummary> = self
+            .mongo
+            .collection::<ProgressSummary>("progress_summary_v2")
+            .find(doc! { "_id": { "$in": summary_ids } })
+            .await
+            .context("Failed to load prerequisite progress")?
+            .try_collect()
+            .await
+            .context("Failed to collect prerequisite progress")?;
+
+        let mut missing = Vec::new();
+        for prerequisite_id in &level.prerequisite_level_ids {
+            // Удалённый пререквизит не блокирует уровень
+            let Some(prerequisite) = prerequisites.get(prerequisite_id) else {
+                continue;
+            };
+            let level_key = prerequisite.id.to_hex();
+            let percentage = progress
+                .iter()
+                .find(|summary| summary.level_id == level_key)
+                .map(|summary| summary.percentage)
+                .unwrap_or(0.0);
+            if percentage < f64::from(prerequisite.min_pass_percent) {
+                missing.push(MissingPrerequisite {
+                    level_id: level_key,
+                    name: prerequisite.name.clone(),
+                    min_pass_percent: prerequisite.min_pass_percent,
+                    percentage,
+                });
+            }
+        }
+        Ok(missing)
+    }
+
+    /// Уровни по id через кэш контента
+    async fn load_levels(&self, ids: &[ObjectId]) -> Result<HashMap<ObjectId, LevelRecord>> {
+        let levels = self.mongo.collection::<LevelRecord>("levels");
+        ContentCache::new(self.redis.clone())
+            .get_many(ContentCacheKind::Level, ids, |missing| async move {
+                let levels: Vec<LevelRecord> = levels
+                    .find(doc! { "_id": { "$in": missing } })
+                    .await
+                    .context("Failed to load levels")?
+                    .try_collect()
+                    .await
+                    .context("Failed to collect levels")?;
+                Ok(levels.into_iter().map(|level| (level.id, level)).collect())
+            })
+            .await
+    }
+
+    async fn ensure_age_band(&self, band: AgeBand, template_id: &ObjectId) -> Result<()> {
+        let content_band = TaskSelectionService::new(self.mongo.clone())
+            .template_age_band(template_id)
+            .await?;
+        if !band.allows(content_band) {
+            return Err(AgeBandRestrictedError.into());
+        }
+        Ok(())
+    }
+
+    /// Уровень шаблона (если шаблон не найден - `None`)
+    async fn template_level_id(&self, template_id: &ObjectId) -> Result<Option<String>> {
+        let templates = self.mongo.collection::<Document>("templates");
+        let meta = ContentCache::new(self.redis.clone())
+            .get(
+                ContentCacheKind::Template,
+                template_id,
+                |missing| async move {
+                    let found: Vec<Document> = templates
+                        .find(doc! { "_id": { "$in": missing } })
+                        .projection(doc! { "level_id": 1 })
+                        .await
+                        .context("Failed to query template level")?
+                        .try_collect()
+                        .await
+                        .context("Failed to collect template levels")?;
+                    Ok(found
+                        .iter()
+                        .filter_map(|template| {
+                            let id = template.get_object_id("_id").ok()?;
+                            let level_id = template.get_object_id("level_id").ok();
+                            Some((id, TemplateMeta { level_id }))
+                        })
+                        .collect())
+                },
+            )
+            .await?;
+        Ok(meta
+            .and_then(|meta| meta.level_id)
+            .map(|level_id| level_id.to_hex()))
+    }
+
+    async fn task_level_id(&self, task_id: &str) -> Result<Option<String>> {
+        let filter = match ObjectId::parse_str(task_id) {
+            Ok(object_id) => doc! { "_id": object_id },
+            Err(_) => doc! { "_id": task_id },
+        };
+        let task = self
+            .mongo
+            .collection::<Document>("tasks")
+            .find_one(filter)
+            .projection(doc! { "level_id": 1, "metadata.level_id": 1 })
+            .await
+            .context("Failed to query task level")?;
+        Ok(task.as_ref().and_then(Self::extract_level_id))
+    }
+
     async fn load_level_label(&self, level_id: &str) -> Option<String> {
         let object_id = ObjectId::parse_str(level_id).ok()?;
-        let collection = self.mongo.collection::<LevelRecord>("levels");
-        match collection.find_one(doc! { "_id": object_id }).await {
-            Ok(Some(level)) => Some(level.name),
-            Ok(None) => None,
+        match self.load_levels(std::slice::from_ref(&object_id)).await {
+            Ok(mut levels) => levels.remove(&object_id).map(|level| level.name),
             Err(err) => {
                 tracing::warn!("Failed to load level {}: {}", level_id, err);
                 None
@@ -426,6 +1160,33 @@ impl SessionService {
         }
     }
 
+    /// `params.answers` шаблона (варианты и regex ответа) копируются в задание,
+    /// проверка ответа читает только документ задания
+    async fn load_template_answers(&self, template_id: &ObjectId) -> Option<Document> {
+        let template = self
+            .mongo
+            .collection::<Document>("templates")
+            .find_one(doc! { "_id": template_id })
+            .projection(doc! { "params.answers": 1 })
+            .await;
+        match template {
+            Ok(template) => template?
+                .get_document("params")
+                .ok()?
+                .get_document("answers")
+                .ok()
+                .cloned(),
+            Err(err) => {
+                tracing::warn!(
+                    "Failed to load answers of template {}: {}",
+                    template_id,
+                    err
+                );
+                None
+            }
+        }
+    }
+
     fn extract_level_label(task: &Document) -> Option<String> {
         task.get_str("level_label")
             .ok()
@@ -480,6 +1241,7 @@ impl SessionService {
 
         Ok(FetchedTask {
             id,
+            template_id: task.get_object_id("template_id").ok(),
             title,
             description,
             time_limit_seconds: time_limit_seconds as u32,
@@ -1,14 +1,36 @@
-use crate::config::Config;
+//! Суперпользователь из seed-файла.
+//!
+//! Пароль из seed-файла известен всем, у кого был доступ к секрету, поэтому пока его
+//! не сменили через `POST /auth/change-password`, у пользователя стоит
+//! `seed_password_in_use` (видно в `GET /admin/system/superuser-status`).
+//! `SUPERUSER_ROTATE=1` при старте выдаёт новый случайный пароль и отзывает все сессии.
+
+use crate::{
+    config::Config,
+    models::superuser::{SuperuserStatus, SuperuserStatusResponse},
+    services::token_revocation,
+};
 use anyhow::{Context, Result};
-use bcrypt::{hash, DEFAULT_COST};
+use bcrypt::{hash, verify, DEFAULT_COST};
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
 use mongodb::{
-    bson::{doc, Document},
+    bson::{doc, oid::ObjectId, Document},
     Database,
 };
+use rand::{distr::Alphanumeric, Rng};
+use redis::aio::ConnectionManager;
 use serde::Deserialize;
 use std::path::Path;
 use tokio::fs;
 
+/// Пользователь создан из seed-файла
+pub const SEED_SUPERUSER_FIELD: &str = "seed_superuser";
+/// Пароль не выбран самим пользователем (seed или ротация)
+pub const SEED_PASSWORD_FIELD: &str = "seed_password_in_use";
+
+const ROTATED_PASSWORD_LEN: usize = 24;
+
 #[derive(Debug, Deserialize)]
 pub struct SuperuserSeed {
     pub email: String,
@@ -40,6 +62,7 @@ impl SuperuserSeed {
             "role": self.role,
             "group_ids": self.group_ids,
             "metadata": self.metadata,
+            SEED_SUPERUSER_FIELD: true,
             "createdAt": bson_now(),
             "updatedAt": bson_now(),
         };
@@ -49,6 +72,7 @@ impl SuperuserSeed {
             let hashed =
                 hash(plain_password, DEFAULT_COST).context("Failed to hash superuser password")?;
             doc.insert("password_hash", hashed);
+            doc.insert(SEED_PASSWORD_FIELD, true);
         }
 
         Ok(doc)
@@ -59,7 +83,38 @@ fn bson_now() -> mongodb::bson::DateTime {
     mongodb::bson::DateTime::now()
 }
 
-pub async fn bootstrap(config: &Config, mongo: &Database) -> Result<()> {
+pub async fn bootstrap(config: &Config, mongo: &Database, redis: &ConnectionManager) -> Result<()> {
+    seed(config, mongo).await?;
+
+    if config.superuser_rotate {
+        let rotated = rotate_passwords(mongo, redis).await?;
+        if rotated.is_empty() {
+            tracing::warn!("SUPERUSER_ROTATE is set but no seeded superuser found");
+        }
+        for superuser in &rotated {
+            if config.superuser_rotate_print_password {
+                tracing::warn!(
+                    email = %superuser.email,
+                    password = %superuser.password,
+                    "Superuser password rotated; store it now, it will not be shown again"
+                );
+            } else {
+                tracing::warn!(
+                    email = %superuser.email,
+                    "Superuser password rotated; set SUPERUSER_ROTATE_PRINT_PASSWORD=1 to print it \
+                     or reset it from another admin account"
+                );
+            }
+        }
+        if !rotated.is_empty() {
+            tracing::warn!("Unset SUPERUSER_ROTATE, otherwise the password rotates on every start");
+        }
+    }
+
+    Ok(())
+}
+
+async fn seed(config: &Config, mongo: &Database) -> Result<()> {
     tracing::debug!(
         "Checking for superuser seed file config: {:?}",
         config.superuser_seed_file
@@ -94,6 +149,7 @@ pub async fn bootstrap(config: &Config, mongo: &Database) -> Result<()> {
         serde_json::from_str(&contents).context("Failed to deserialize superuser seed payload")?;
 
     let email = seed.email.clone();
+    let seed_password = seed.password.clone();
     let doc = seed.into_document()?;
     let collection = mongo.collection::<Document>("users");
     tracing::info!("Bootstrapping superuser with email {}", email);
@@ -108,7 +164,163 @@ pub async fn bootstrap(config: &Config, mongo: &Database) -> Result<()> {
         tracing::info!("Superuser inserted; remove seed file to prevent rerun");
     } else {
         tracing::info!("Superuser already exists, seed skipped");
+        mark_existing(mongo, &email, seed_password.as_deref()).await?;
+    }
+
+    Ok(())
+}
+
+/// Пользователи, созданные до появления флагов: помечаем и сверяем пароль с seed-файлом.
+/// Флаг только выставляется - снимает его смена пароля
+async fn mark_existing(mongo: &Database, email: &str, seed_password: Option<&str>) -> Result<()> {
+    let collection = mongo.collection::<Document>("users");
+    let Some(user) = collection
+        .find_one(doc! { "email": email })
+        .await
+        .context("Failed to load superuser")?
+    else {
+        return Ok(());
+    };
+
+    let mut set = doc! { SEED_SUPERUSER_FIELD: true };
+    if let (Some(plain), Ok(stored)) = (seed_password, user.get_str("password_hash")) {
+        if verify(plain, stored).unwrap_or(false) {
+            tracing::warn!(
+                email,
+                "Superuser still uses the password from the seed file"
+            );
+            set.insert(SEED_PASSWORD_FIELD, true);
+        }
+    }
+
+    collection
+        .update_one(doc! { "email": email }, doc! { "$set": set })
+        .await
+        .context("Failed to mark seeded superuser")?;
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub struct RotatedSuperuser {
+    pub user_id: String,
+    pub email: String,
+    pub password: String,
+}
+
+/// Новый случайный пароль каждому суперпользователю из seed-файла; refresh-токены
+/// отзываются в MongoDB, access-токены - через отметку отзыва в Redis
+pub async fn rotate_passwords(
+    mongo: &Database,
+    redis: &ConnectionManager,
+) -> Result<Vec<RotatedSuperuser>> {
+    let users = mongo.collection::<Document>("users");
+    let seeded: Vec<Document> = users
+        .find(doc! { SEED_SUPERUSER_FIELD: true })
+        .await
+        .context("Failed to load seeded superusers")?
+        .try_collect()
+        .await
+        .context("Failed to load seeded superusers")?;
+
+    let mut rotated = Vec::with_capacity(seeded.len());
+    for user in seeded {
+        let id = user
+            .get_object_id("_id")
+            .context("Seeded superuser without _id")?;
+        let email = user.get_str("email").unwrap_or_default().to_string();
+        let password = generate_password();
+        let password_hash =
+            hash(&password, DEFAULT_COST).context("Failed to hash rotated password")?;
+
+        users
+            .update_one(
+                doc! { "_id": id },
+                doc! {
+                    "$set": {
+                        "password_hash": password_hash,
+                        SEED_PASSWORD_FIELD: true,
+                        "passwordRotatedAt": bson_now(),
+                        "updatedAt": bson_now(),
+                    }
+                },
+            )
+            .await
+            .context("Failed to store rotated password")?;
+
+        revoke_sessions(mongo, redis, id).await?;
+        rotated.push(RotatedSuperuser {
+            user_id: id.to_hex(),
+            email,
+            password,
+        });
     }
 
+    Ok(rotated)
+}
+
+async fn revoke_sessions(mongo: &Database, redis: &ConnectionManager, id: ObjectId) -> Result<()> {
+    let revoked = mongo
+        .collection::<Document>("refresh_tokens")
+        .update_many(
+            doc! { "userId": id, "revoked": false },
+            doc! { "$set": { "revoked": true } },
+        )
+        .await
+        .context("Failed to revoke superuser refresh tokens")?;
+    token_revocation::revoke_user_tokens(redis, &id.to_hex()).await?;
+
+    tracing::info!(
+        user_id = %id,
+        refresh_tokens = revoked.modified_count,
+        "Superuser sessions revoked after password rotation"
+    );
     Ok(())
 }
+
+fn generate_password() -> String {
+    rand::rng()
+        .sample_iter(&Alphanumeric)
+        .take(ROTATED_PASSWORD_LEN)
+        .map(char::from)
+        .collect()
+}
+
+/// Состояние учётных данных суперпользователей из seed-файла
+pub async fn status(mongo: &Database) -> Result<SuperuserStatusResponse> {
+    let superusers: Vec<SuperuserStatus> = mongo
+        .collection::<Document>("users")
+        .find(doc! { SEED_SUPERUSER_FIELD: true })
+        .sort(doc! { "createdAt": 1 })
+        .await
+        .context("Failed to load seeded superusers")?
+        .try_collect::<Vec<_>>()
+        .await
+        .context("Failed to load seeded superusers")?
+        .iter()
+        .map(superuser_status)
+        .collect();
+
+    Ok(SuperuserStatusResponse {
+        seed_password_in_use: superusers.iter().any(|user| user.seed_password_in_use),
+        superusers,
+        checked_at: Utc::now(),
+    })
+}
+
+fn superuser_status(user: &Document) -> SuperuserStatus {
+    let datetime = |key: &str| {
+        user.get_datetime(key)
+            .ok()
+            .and_then(|value| DateTime::from_timestamp_millis(value.timestamp_millis()))
+    };
+    SuperuserStatus {
+        id: user
+            .get_object_id("_id")
+            .map(|id| id.to_hex())
+            .unwrap_or_default(),
+        email: user.get_str("email").unwrap_or_default().to_string(),
+        seed_password_in_use: user.get_bool(SEED_PASSWORD_FIELD).unwrap_or(false),
+        last_login_at: datetime("lastLoginAt"),
+        password_rotated_at: datetime("passwordRotatedAt"),
+    }
+}
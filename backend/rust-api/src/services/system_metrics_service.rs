@@ -0,0 +1,361 @@
+use std::collections::HashMap;
+use std::sync::Mutex as StdMutex;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use mongodb::bson::{doc, Bson, DateTime as BsonDateTime, Document};
+use mongodb::Database;
+use redis::aio::ConnectionManager;
+use tokio::sync::Mutex;
+
+use crate::models::system_metrics::{
+    DatabaseMetrics, ProcessMetrics, RedisMetrics, SystemMetricsResponse,
+};
+
+/// Сколько держать в памяти данные MongoDB и Redis
+const STORAGE_CACHE_TTL: Duration = Duration::from_secs(10);
+
+/// Тиков в секунде для utime/stime в `/proc/self/stat` (USER_HZ на Linux всегда 100)
+const CLOCK_TICKS_PER_SECOND: f64 = 100.0;
+
+/// Метрики для `/admin/system/metrics`.
+///
+/// Запросы к MongoDB и Redis (dbStats, INFO, подсчёты, SCAN сессий) дорогие, поэтому их
+/// результат кэшируется на `STORAGE_CACHE_TTL`; ресурсы процесса и аптайм считаются
+/// при каждом запросе. Экземпляр живёт в `AppState`.
+pub struct SystemMetricsService {
+    storage: Mutex<Option<(Instant, StorageMetrics)>>,
+    last_cpu: StdMutex<Option<CpuSample>>,
+}
+
+#[derive(Debug, Clone)]
+struct StorageMetrics {
+    total_users: u64,
+    blocked_users: u64,
+    total_groups: u64,
+    total_incidents: u64,
+    open_incidents: u64,
+    critical_incidents: u64,
+    audit_events_24h: u64,
+    active_sessions: u64,
+    database: DatabaseMetrics,
+    redis: RedisMetrics,
+    collected_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CpuSample {
+    at: Instant,
+    cpu_seconds: f64,
+}
+
+impl Default for SystemMetricsService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SystemMetricsService {
+    pub fn new() -> Self {
+        Self {
+            storage: Mutex::new(None),
+            last_cpu: StdMutex::new(None),
+        }
+    }
+
+    pub async fn metrics(
+        &self,
+        mongo: &Database,
+        redis: &ConnectionManager,
+        start_time: Instant,
+    ) -> Result<SystemMetricsResponse> {
+        let storage = self.storage_metrics(mongo, redis).await?;
+        let uptime = start_time.elapsed();
+
+        Ok(SystemMetricsResponse {
+            uptime_seconds: uptime.as_secs(),
+            total_users: storage.total_users,
+            blocked_users: storage.blocked_users,
+            total_groups: storage.total_groups,
+            total_incidents: storage.total_incidents,
+            open_incidents: storage.open_incidents,
+            critical_incidents: storage.critical_incidents,
+            audit_events_24h: storage.audit_events_24h,
+            active_sessions: storage.active_sessions,
+            process: self.process_metrics(uptime),
+            database: storage.database,
+            redis: storage.redis,
+            collected_at: storage.collected_at,
+        })
+    }
+
+    /// Данные хранилищ из кэша; блокировка держится на время обновления, чтобы
+    /// одновременные запросы не запускали одни и те же тяжёлые команды
+    async fn storage_metrics(
+        &self,
+        mongo: &Database,
+        redis: &ConnectionManager,
+    ) -> Result<StorageMetrics> {
+        let mut cached = self.storage.lock().await;
+        if let Some((at, metrics)) = cached.as_ref() {
+            if at.elapsed() < STORAGE_CACHE_TTL {
+                return Ok(metrics.clone());
+            }
+        }
+
+        let metrics = collect_storage_metrics(mongo, redis).await?;
+        *cached = Some((Instant::now(), metrics.clone()));
+        Ok(metrics)
+    }
+
+    fn process_metrics(&self, uptime: Duration) -> ProcessMetrics {
+        let mut metrics = ProcessMetrics {
+            pid: std::process::id(),
+            ..ProcessMetrics::default()
+        };
+
+        if let Ok(status) = std::fs::read_to_string("/proc/self/status") {
+            let status = parse_proc_status(&status);
+            metrics.rss_bytes = status.get("VmRSS").copied();
+            metrics.threads = status.get("Threads").copied();
+        }
+
+        let Some(cpu_seconds) = std::fs::read_to_string("/proc/self/stat")
+            .ok()
+            .and_then(|stat| parse_cpu_seconds(&stat))
+        else {
+            return metrics;
+        };
+        metrics.cpu_seconds_total = Some(cpu_seconds);
+
+        let now = Instant::now();
+        let mut last = self.last_cpu.lock().unwrap_or_else(|e| e.into_inner());
+        let (cpu_delta, wall) = match *last {
+            Some(previous) => (
+                cpu_seconds - previous.cpu_seconds,
+                now.duration_since(previous.at),
+            ),
+            None => (cpu_seconds, uptime),
+        };
+        *last = Some(CpuSample {
+            at: now,
+            cpu_seconds,
+        });
+        if wall.as_secs_f64() > 0.0 {
+            metrics.cpu_percent = Some((cpu_delta.max(0.0) / wall.as_secs_f64() * 100.0).max(0.0));
+        }
+
+        metrics
+    }
+}
+
+async fn collect_storage_metrics(
+    mongo: &Database,
+    redis: &ConnectionManager,
+) -> Result<StorageMetrics> {
+    let users = mongo.collection::<Document>("users");
+    let groups = mongo.collection::<Document>("groups");
+    let incidents = mongo.collection::<Document>("incidents");
+    let audit = mongo.collection::<Document>("audit_log");
+
+    let total_users = users
+        .estimated_document_count()
+        .await
+        .context("Failed to count users")?;
+    let blocked_users = users
+        .count_documents(doc! { "is_blocked": true })
+        .await
+        .context("Failed to count blocked users")?;
+
+    let total_groups = groups
+        .estimated_document_count()
+        .await
+        .context("Failed to count groups")?;
+
+    let total_incidents = incidents
+        .estimated_document_count()
+        .await
+        .context("Failed to count incidents")?;
+    let open_incidents = incidents
+        .count_documents(doc! { "status": "open" })
+        .await
+        .context("Failed to count open incidents")?;
+    let critical_incidents = incidents
+        .count_documents(doc! { "severity": "critical", "status": "open" })
+        .await
+        .context("Failed to count critical incidents")?;
+
+    let last_24h = Utc::now() - chrono::Duration::hours(24);
+    let audit_events_24h = audit
+        .count_documents(doc! { "createdAt": { "$gte": BsonDateTime::from_millis(last_24h.timestamp_millis()) } })
+        .await
+        .context("Failed to count audit events")?;
+
+    let stats = mongo
+        .run_command(doc! { "dbStats": 1, "scale": 1 })
+        .await
+        .context("Failed to run dbStats")?;
+    let database = DatabaseMetrics {
+        name: mongo.name().to_string(),
+        collections: stat_number(&stats, "collections"),
+        documents: stat_number(&stats, "objects"),
+        data_size_bytes: stat_number(&stats, "dataSize"),
+        storage_size_bytes: stat_number(&stats, "storageSize"),
+        indexes: stat_number(&stats, "indexes"),
+        index_size_bytes: stat_number(&stats, "indexSize"),
+    };
+
+    let mut conn = redis.clone();
+    let memory: String = redis::cmd("INFO")
+        .arg("memory")
+        .query_async(&mut conn)
+        .await
+        .context("Failed to read Redis memory info")?;
+    let clients: String = redis::cmd("INFO")
+        .arg("clients")
+        .query_async(&mut conn)
+        .await
+        .context("Failed to read Redis clients info")?;
+    let memory = parse_redis_info(&memory);
+    let clients = parse_redis_info(&clients);
+    let redis_metrics = RedisMetrics {
+        used_memory_bytes: info_number(&memory, "used_memory"),
+        used_memory_peak_bytes: info_number(&memory, "used_memory_peak"),
+        max_memory_bytes: info_number(&memory, "maxmemory"),
+        connected_clients: info_number(&clients, "connected_clients"),
+        blocked_clients: info_number(&clients, "blocked_clients"),
+    };
+
+    let active_sessions = count_active_sessions(redis).await?;
+
+    Ok(StorageMetrics {
+        total_users,
+        blocked_users,
+        total_groups,
+        total_incidents,
+        open_incidents,
+        critical_incidents,
+        audit_events_24h,
+        active_sessions,
+        database,
+        redis: redis_metrics,
+        collected_at: Utc::now(),
+    })
+}
+
+async fn count_active_sessions(redis: &ConnectionManager) -> Result<u64> {
+    let mut conn = redis.clone();
+    let mut cursor = "0".to_string();
+    let mut total = 0u64;
+
+    loop {
+        let (next_cursor, keys): (String, Vec<String>) = redis::cmd("SCAN")
+            .arg(&cursor)
+            .arg("MATCH")
+            .arg("session:*")
+            .arg("COUNT")
+            .arg(1000)
+            .query_async(&mut conn)
+            .await
+            .context("Failed to scan Redis for sessions")?;
+
+        total += keys.len() as u64;
+
+        if next_cursor == "0" {
+            break;
+        }
+
+        cursor = next_cursor;
+    }
+
+    Ok(total)
+}
+
+/// dbStats возвращает числа как int32, int64 или double в зависимости от версии и размера
+fn stat_number(stats: &Document, key: &str) -> u64 {
+    match stats.get(key) {
+        Some(Bson::Int32(value)) => (*value).max(0) as u64,
+        Some(Bson::Int64(value)) => (*value).max(0) as u64,
+        Some(Bson::Double(value)) => value.max(0.0) as u64,
+        _ => 0,
+    }
+}
+
+/// Строки `key:value` из ответа `INFO`; заголовки секций (`# Memory`) пропускаются
+fn parse_redis_info(info: &str) -> HashMap<&str, &str> {
+    info.lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.trim_end().split_once(':'))
+        .collect()
+}
+
+fn info_number(info: &HashMap<&str, &str>, key: &str) -> u64 {
+    info.get(key)
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(0)
+}
+
+/// `VmRSS` (в байтах) и `Threads` из `/proc/self/status`
+fn parse_proc_status(status: &str) -> HashMap<&str, u64> {
+    status
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .filter(|(key, _)| matches!(*key, "VmRSS" | "Threads"))
+        .filter_map(|(key, value)| {
+            let mut parts = value.split_whitespace();
+            let number: u64 = parts.next()?.parse().ok()?;
+            match parts.next() {
+                Some("kB") => Some((key, number * 1024)),
+                _ => Some((key, number)),
+            }
+        })
+        .collect()
+}
+
+/// utime + stime из `/proc/self/stat` в секундах.
+///
+/// Имя процесса во втором поле может содержать пробелы, поэтому поля считаются
+/// после последней `)`: utime и stime - 14-е и 15-е поля строки.
+fn parse_cpu_seconds(stat: &str) -> Option<f64> {
+    let (_, rest) = stat.rsplit_once(')')?;
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+    Some((utime + stime) as f64 / CLOCK_TICKS_PER_SECOND)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proc_files_are_parsed() {
+        let status =
+            "Name:\ttrainingground\nVmPeak:\t  200000 kB\nVmRSS:\t   51200 kB\nThreads:\t12\n";
+        let parsed = parse_proc_status(status);
+        assert_eq!(parsed.get("VmRSS"), Some(&(51200 * 1024)));
+        assert_eq!(parsed.get("Threads"), Some(&12));
+        assert!(!parsed.contains_key("VmPeak"));
+
+        let stat =
+            "4242 (tokio worker) S 1 4242 4242 0 -1 4194560 1200 0 0 0 250 50 0 0 20 0 12 0 100 0";
+        assert_eq!(parse_cpu_seconds(stat), Some(3.0));
+        assert_eq!(parse_cpu_seconds("garbage"), None);
+    }
+
+    #[test]
+    fn redis_info_and_db_stats_numbers_are_read() {
+        let info = "# Memory\r\nused_memory:1048576\r\nused_memory_peak:2097152\r\nmaxmemory:0\r\n";
+        let parsed = parse_redis_info(info);
+        assert_eq!(info_number(&parsed, "used_memory"), 1_048_576);
+        assert_eq!(info_number(&parsed, "used_memory_peak"), 2_097_152);
+        assert_eq!(info_number(&parsed, "connected_clients"), 0);
+
+        let stats = doc! { "collections": 7, "dataSize": 1024.0, "indexSize": 4096_i64 };
+        assert_eq!(stat_number(&stats, "collections"), 7);
+        assert_eq!(stat_number(&stats, "dataSize"), 1024);
+        assert_eq!(stat_number(&stats, "indexSize"), 4096);
+        assert_eq!(stat_number(&stats, "storageSize"), 0);
+    }
+}
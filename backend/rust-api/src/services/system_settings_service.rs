@@ -1,19 +1,72 @@
+use std::time::{Duration, Instant};
+
 use anyhow::{anyhow, Context, Result};
 use chrono::Utc;
 use mongodb::{
     bson::{doc, from_document, to_document},
     Database,
 };
+use tokio::sync::RwLock;
 
 use crate::models::system_settings::{
-    AnticheatSettings, EmailSettings, SsoSettings, SystemSetting, SystemSettingsResponse,
-    YandexGptSettings,
+    AnticheatSettings, ConsentSettings, EmailSettings, OpenAiSettings, PiiSettings,
+    RateLimitOverrides, SsoSettings, SystemSetting, SystemSettingsResponse, YandexGptSettings,
 };
+use crate::utils::mongo_retry::retry_idempotent_write;
 
 const KEY_YANDEXGPT: &str = "yandexgpt";
+const KEY_OPENAI: &str = "openai";
 const KEY_SSO: &str = "sso";
 const KEY_EMAIL: &str = "email";
 const KEY_ANTICHEAT: &str = "anticheat";
+const KEY_CONSENT: &str = "consent";
+const KEY_PII: &str = "pii_patterns";
+const KEY_RATE_LIMITS: &str = "rate_limits";
+/// Сколько держать настройки античита в памяти; другие инстансы увидят правку не позже
+const ANTICHEAT_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Настройки античита для проверки ответов и сигналов. Экземпляр живёт в `AppState`,
+/// `PUT /admin/settings/anticheat` сбрасывает его сразу
+#[derive(Default)]
+pub struct AnticheatSettingsCache {
+    settings: RwLock<Option<(Instant, AnticheatSettings)>>,
+}
+
+impl AnticheatSettingsCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Текущие настройки. Если MongoDB недоступна или настройки не сохранены,
+    /// действуют значения по умолчанию
+    pub async fn get(&self, mongo: &Database) -> AnticheatSettings {
+        if let Some((loaded_at, settings)) = self.settings.read().await.as_ref() {
+            if loaded_at.elapsed() < ANTICHEAT_CACHE_TTL {
+                return settings.clone();
+            }
+        }
+
+        let settings = match SystemSettingsService::new(mongo.clone())
+            .get_anticheat_settings()
+            .await
+        {
+            Ok(settings) => settings.unwrap_or_default(),
+            Err(err) => {
+                tracing::warn!(
+                    "Anticheat settings lookup failed, using defaults: {:#}",
+                    err
+                );
+                return AnticheatSettings::default();
+            }
+        };
+        *self.settings.write().await = Some((Instant::now(), settings.clone()));
+        settings
+    }
+
+    pub async fn invalidate(&self) {
+        *self.settings.write().await = None;
+    }
+}
 
 pub struct SystemSettingsService {
     mongo: Database,
@@ -25,24 +78,52 @@ impl SystemSettingsService {
     }
 
     pub async fn get_email_settings(&self) -> Result<Option<EmailSettings>> {
+        self.get_setting(KEY_EMAIL).await
+    }
+
+    pub async fn get_yandexgpt_settings(&self) -> Result<Option<YandexGptSettings>> {
+        self.get_setting(KEY_YANDEXGPT).await
+    }
+
+    pub async fn get_openai_settings(&self) -> Result<Option<OpenAiSettings>> {
+        self.get_setting(KEY_OPENAI).await
+    }
+
+    pub async fn get_sso_settings(&self) -> Result<Option<SsoSettings>> {
+        self.get_setting(KEY_SSO).await
+    }
+
+    pub async fn get_anticheat_settings(&self) -> Result<Option<AnticheatSettings>> {
+        self.get_setting(KEY_ANTICHEAT).await
+    }
+
+    pub async fn get_pii_settings(&self) -> Result<Option<PiiSettings>> {
+        self.get_setting(KEY_PII).await
+    }
+
+    pub async fn get_rate_limit_overrides(&self) -> Result<Option<RateLimitOverrides>> {
+        self.get_setting(KEY_RATE_LIMITS).await
+    }
+
+    /// Consent settings, falling back to defaults (nothing required) when not configured.
+    pub async fn get_consent_settings(&self) -> Result<ConsentSettings> {
         let collection = self.mongo.collection::<SystemSetting>("system_settings");
         if let Some(setting) = collection
-            .find_one(doc! { "key": KEY_EMAIL })
+            .find_one(doc! { "key": KEY_CONSENT })
             .await
-            .context("Failed to query email settings")?
+            .context("Failed to query consent settings")?
         {
-            let parsed = from_document(setting.value)
-                .map_err(|e| anyhow!("Failed to parse email settings: {e}"))?;
-            Ok(Some(parsed))
+            from_document(setting.value)
+                .map_err(|e| anyhow!("Failed to parse consent settings: {e}"))
         } else {
-            Ok(None)
+            Ok(ConsentSettings::default())
         }
     }
 
     pub async fn get_all(&self) -> Result<SystemSettingsResponse> {
         let collection = self.mongo.collection::<SystemSetting>("system_settings");
         let mut cursor = collection
-            .find(doc! { "key": { "$in": [KEY_YANDEXGPT, KEY_SSO, KEY_EMAIL, KEY_ANTICHEAT] } })
+            .find(doc! { "key": { "$in": [KEY_YANDEXGPT, KEY_OPENAI, KEY_SSO, KEY_EMAIL, KEY_ANTICHEAT, KEY_CONSENT, KEY_PII, KEY_RATE_LIMITS] } })
             .await
             .context("Failed to query system settings")?;
 
@@ -56,22 +137,43 @@ impl SystemSettingsService {
                 .deserialize_current()
                 .context("Failed to deserialize system setting")?;
             match setting.key.as_str() {
+                // Секреты отдаются только в виде маски
                 KEY_YANDEXGPT => {
-                    response.yandexgpt = from_document(setting.value)
+                    let settings: YandexGptSettings = from_document(setting.value)
                         .map_err(|e| anyhow!("Failed to parse yandexgpt settings: {e}"))?;
+                    response.yandexgpt = Some((&settings).into());
+                }
+                KEY_OPENAI => {
+                    let settings: OpenAiSettings = from_document(setting.value)
+                        .map_err(|e| anyhow!("Failed to parse openai settings: {e}"))?;
+                    response.openai = Some((&settings).into());
                 }
                 KEY_SSO => {
-                    response.sso = from_document(setting.value)
+                    let settings: SsoSettings = from_document(setting.value)
                         .map_err(|e| anyhow!("Failed to parse sso settings: {e}"))?;
+                    response.sso = Some((&settings).into());
                 }
                 KEY_EMAIL => {
-                    response.email = from_document(setting.value)
+                    let settings: EmailSettings = from_document(setting.value)
                         .map_err(|e| anyhow!("Failed to parse email settings: {e}"))?;
+                    response.email = Some((&settings).into());
                 }
                 KEY_ANTICHEAT => {
                     response.anticheat = from_document(setting.value)
                         .map_err(|e| anyhow!("Failed to parse anticheat settings: {e}"))?;
                 }
+                KEY_CONSENT => {
+                    response.consent = from_document(setting.value)
+                        .map_err(|e| anyhow!("Failed to parse consent settings: {e}"))?;
+                }
+                KEY_PII => {
+                    response.pii = from_document(setting.value)
+                        .map_err(|e| anyhow!("Failed to parse pii settings: {e}"))?;
+                }
+                KEY_RATE_LIMITS => {
+                    response.rate_limits = from_document(setting.value)
+                        .map_err(|e| anyhow!("Failed to parse rate limit settings: {e}"))?;
+                }
                 _ => continue,
             }
         }
@@ -89,6 +191,16 @@ impl SystemSettingsService {
         Ok(settings)
     }
 
+    pub async fn update_openai(
+        &self,
+        settings: OpenAiSettings,
+        updated_by: &str,
+    ) -> Result<OpenAiSettings> {
+        self.upsert(KEY_OPENAI, "llm", &settings, updated_by)
+            .await?;
+        Ok(settings)
+    }
+
     pub async fn update_sso(&self, settings: SsoSettings, updated_by: &str) -> Result<SsoSettings> {
         self.upsert(KEY_SSO, "sso", &settings, updated_by).await?;
         Ok(settings)
@@ -114,6 +226,47 @@ impl SystemSettingsService {
         Ok(settings)
     }
 
+    pub async fn update_consent(
+        &self,
+        settings: ConsentSettings,
+        updated_by: &str,
+    ) -> Result<ConsentSettings> {
+        self.upsert(KEY_CONSENT, "consent", &settings, updated_by)
+            .await?;
+        Ok(settings)
+    }
+
+    pub async fn update_pii(&self, settings: PiiSettings, updated_by: &str) -> Result<PiiSettings> {
+        self.upsert(KEY_PII, "content", &settings, updated_by)
+            .await?;
+        Ok(settings)
+    }
+
+    pub async fn update_rate_limits(
+        &self,
+        overrides: RateLimitOverrides,
+        updated_by: &str,
+    ) -> Result<RateLimitOverrides> {
+        self.upsert(KEY_RATE_LIMITS, "rate_limit", &overrides, updated_by)
+            .await?;
+        Ok(overrides)
+    }
+
+    async fn get_setting<T: serde::de::DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        let collection = self.mongo.collection::<SystemSetting>("system_settings");
+        if let Some(setting) = collection
+            .find_one(doc! { "key": key })
+            .await
+            .with_context(|| format!("Failed to query {key} settings"))?
+        {
+            let parsed = from_document(setting.value)
+                .map_err(|e| anyhow!("Failed to parse {key} settings: {e}"))?;
+            Ok(Some(parsed))
+        } else {
+            Ok(None)
+        }
+    }
+
     async fn upsert<T: serde::Serialize>(
         &self,
         key: &str,
@@ -125,22 +278,24 @@ impl SystemSettingsService {
         let value_doc = to_document(value).context("Failed to serialize settings value")?;
         let now = mongodb::bson::DateTime::from_millis(Utc::now().timestamp_millis());
 
-        collection
-            .update_one(
-                doc! { "key": key },
-                doc! {
-                    "$set": {
-                        "key": key,
-                        "category": category,
-                        "value": value_doc,
-                        "updatedBy": updated_by,
-                        "updatedAt": now,
-                    }
-                },
-            )
-            .upsert(true)
-            .await
-            .context("Failed to upsert system setting")?;
+        retry_idempotent_write("system_settings.upsert", || {
+            collection
+                .update_one(
+                    doc! { "key": key },
+                    doc! {
+                        "$set": {
+                            "key": key,
+                            "category": category,
+                            "value": value_doc.clone(),
+                            "updatedBy": updated_by,
+                            "updatedAt": now,
+                        }
+                    },
+                )
+                .upsert(true)
+        })
+        .await
+        .context("Failed to upsert system setting")?;
 
         Ok(())
     }
@@ -0,0 +1,551 @@
+//! Подбор задания для сессии «практика слабых тем» (`POST /api/v1/sessions/auto`).
+//!
+//! Кандидаты - опубликованные шаблоны. Вес шаблона растёт с долей ошибок в его теме
+//! и падает с удалённостью сложности от текущего уровня ученика; шаблоны из последних
+//! `RECENT_SESSIONS_WINDOW` сессий не выбираются, пока есть другие. Без истории ответов
+//! шаблон выбирается равновероятно. Выбор принимает генератор случайных чисел снаружи,
+//! поэтому с фиксированным seed он воспроизводим.
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{Context, Result};
+use chrono::{Datelike, Utc};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, oid::ObjectId, Bson, Document},
+    Database,
+};
+use rand::{
+    distr::{weighted::WeightedIndex, Distribution},
+    seq::IndexedRandom,
+    Rng,
+};
+
+use crate::models::{
+    content::{
+        AgeBand, LevelDifficulty, LevelRecord, LevelStatus, TemplateDocument, TemplateStatus,
+        TopicRecord,
+    },
+    user::User,
+    ProgressSummary,
+};
+
+/// Сколько последних сессий ученика учитывается, чтобы не повторять шаблоны
+pub const RECENT_SESSIONS_WINDOW: i64 = 5;
+/// Во сколько раз сильнее тема с 0% верных ответов, чем полностью освоенная (1 + boost)
+pub const WEAKNESS_BOOST: f64 = 3.0;
+/// Процент, с которым считается тема без ответов: между слабыми и освоенными
+const UNTRIED_TOPIC_PERCENT: f64 = 50.0;
+/// Сколько опубликованных шаблонов случайно берётся в кандидаты за один подбор
+const CANDIDATE_SAMPLE_SIZE: i64 = 500;
+
+/// Нет ни одного доступного опубликованного шаблона (ответ 404)
+#[derive(Debug, thiserror::Error)]
+#[error("No published templates are available")]
+pub struct NoTemplatesAvailableError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelectionCandidate {
+    pub template_id: ObjectId,
+    pub level_id: ObjectId,
+    pub topic_id: ObjectId,
+    /// Ранг сложности: 0 (a1) - 3 (b2)
+    pub difficulty: u8,
+    /// У уровня есть пререквизиты - перед выбором его нужно проверить
+    pub has_prerequisites: bool,
+}
+
+/// История ученика, по которой взвешиваются кандидаты
+#[derive(Debug, Clone, Default)]
+pub struct LearnerProfile {
+    /// Процент верных ответов по темам
+    pub topic_percent: HashMap<ObjectId, f64>,
+    /// Шаблоны заданий из последних сессий
+    pub recent_templates: HashSet<ObjectId>,
+    /// Ранг сложности, вокруг которого подбираются шаблоны
+    pub target_difficulty: Option<u8>,
+}
+
+impl LearnerProfile {
+    pub fn has_history(&self) -> bool {
+        !self.topic_percent.is_empty() || !self.recent_templates.is_empty()
+    }
+}
+
+pub fn difficulty_rank(difficulty: LevelDifficulty) -> u8 {
+    match difficulty {
+        LevelDifficulty::A1 => 0,
+        LevelDifficulty::A2 => 1,
+        LevelDifficulty::B1 => 2,
+        LevelDifficulty::B2 => 3,
+    }
+}
+
+/// Сложность шаблона: своя (`a1`..`b2`), иначе сложность уровня
+fn template_difficulty(template: &TemplateDocument, level: &LevelRecord) -> u8 {
+    match template
+        .difficulty
+        .as_deref()
+        .map(str::to_lowercase)
+        .as_deref()
+    {
+        Some("a1") => 0,
+        Some("a2") => 1,
+        Some("b1") => 2,
+        Some("b2") => 3,
+        _ => difficulty_rank(level.difficulty),
+    }
+}
+
+/// Категория из документа шаблона; неизвестное значение считается отсутствующим
+fn document_age_band(template: &Document) -> Option<AgeBand> {
+    template
+        .get_str("age_band")
+        .ok()
+        .and_then(|band| band.parse().ok())
+}
+
+pub fn candidate_weight(candidate: &SelectionCandidate, profile: &LearnerProfile) -> f64 {
+    let percent = profile
+        .topic_percent
+        .get(&candidate.topic_id)
+        .copied()
+        .unwrap_or(UNTRIED_TOPIC_PERCENT)
+        .clamp(0.0, 100.0);
+    let weakness = 1.0 + WEAKNESS_BOOST * (100.0 - percent) / 100.0;
+    let distance = profile
+        .target_difficulty
+        .map(|target| target.abs_diff(candidate.difficulty))
+        .unwrap_or(0);
+    weakness / (1.0 + f64::from(distance))
+}
+
+/// Выбрать шаблон. `None` только для пустого списка кандидатов
+pub fn select_candidate<'a, R: Rng + ?Sized>(
+    candidates: &'a [SelectionCandidate],
+    profile: &LearnerProfile,
+    rng: &mut R,
+) -> Option<&'a SelectionCandidate> {
+    if !profile.has_history() {
+        return candidates.choose(rng);
+    }
+
+    let fresh: Vec<&SelectionCandidate> = candidates
+        .iter()
+        .filter(|candidate| !profile.recent_templates.contains(&candidate.template_id))
+        .collect();
+    // Все шаблоны недавно встречались - повтор лучше, чем пустой ответ
+    let pool = if fresh.is_empty() {
+        candidates.iter().collect()
+    } else {
+        fresh
+    };
+
+    let weights: Vec<f64> = pool
+        .iter()
+        .map(|candidate| candidate_weight(candidate, profile))
+        .collect();
+    let index = WeightedIndex::new(&weights).ok()?;
+    Some(pool[index.sample(rng)])
+}
+
+/// Ранг, вокруг которого подбираются задания: следующий за самым сложным пройденным
+/// уровнем, а если пройденных нет - самый простой из начатых
+fn target_difficulty(levels: &[(&LevelRecord, &ProgressSummary)]) -> Option<u8> {
+    let passed = levels
+        .iter()
+        .filter(|(level, progress)| progress.percentage >= f64::from(level.min_pass_percent))
+        .map(|(level, _)| difficulty_rank(level.difficulty))
+        .max();
+    match passed {
+        Some(rank) => Some((rank + 1).min(difficulty_rank(LevelDifficulty::B2))),
+        None => levels
+            .iter()
+            .map(|(level, _)| difficulty_rank(level.difficulty))
+            .min(),
+    }
+}
+
+pub struct TaskSelectionService {
+    mongo: Database,
+}
+
+impl TaskSelectionService {
+    pub fn new(mongo: Database) -> Self {
+        Self { mongo }
+    }
+
+    /// Возрастная категория ученика по году рождения (неизвестный год - самая строгая)
+    pub async fn student_age_band(&self, user_id: &str) -> Result<AgeBand> {
+        let birth_year = match ObjectId::parse_str(user_id) {
+            Ok(object_id) => self
+                .mongo
+                .collection::<User>("users")
+                .find_one(doc! { "_id": object_id })
+                .await
+                .context("Failed to load user")?
+                .and_then(|user| user.birth_year),
+            Err(_) => None,
+        };
+        Ok(AgeBand::from_birth_year(birth_year, Utc::now().year()))
+    }
+
+    /// Действующая возрастная категория шаблона с учётом темы
+    /// (`None` - без ограничений или шаблон не найден)
+    pub async fn template_age_band(&self, template_id: &ObjectId) -> Result<Option<AgeBand>> {
+        let Some(template) = self
+            .mongo
+            .collection::<Document>("templates")
+            .find_one(doc! { "_id": template_id })
+            .projection(doc! { "level_id": 1, "age_band": 1 })
+            .await
+            .context("Failed to load template")?
+        else {
+            return Ok(None);
+        };
+        let Ok(level_id) = template.get_object_id("level_id") else {
+            return Ok(document_age_band(&template));
+        };
+        let topic_bands = self.topic_age_bands(&[level_id]).await?;
+        Ok(AgeBand::effective(
+            document_age_band(&template),
+            topic_bands.get(&level_id).copied().flatten(),
+        ))
+    }
+
+    /// Опубликованные шаблоны уровня, доступные для категории. `None` - на уровне нет
+    /// шаблонов выше категории, и генератор может выбирать шаблон сам
+    pub async fn level_templates_for_band(
+        &self,
+        level_id: &ObjectId,
+        band: AgeBand,
+    ) -> Result<Option<Vec<ObjectId>>> {
+        let templates: Vec<Document> = self
+            .mongo
+            .collection::<Document>("templates")
+            .find(doc! {
+                "level_id": level_id,
+                "status": TemplateStatus::Published.as_str(),
+                "archived": { "$ne": true },
+            })
+            .projection(doc! { "age_band": 1 })
+            .sort(doc! { "_id": 1 })
+            .await
+            .context("Failed to query level templates")?
+            .try_collect()
+            .await
+            .context("Failed to read level templates")?;
+        let topic_band = self
+            .topic_age_bands(std::slice::from_ref(level_id))
+            .await?
+            .get(level_id)
+            .copied()
+            .flatten();
+
+        let allowed: Vec<ObjectId> = templates
+            .iter()
+            .filter(|template| {
+                band.allows(AgeBand::effective(document_age_band(template), topic_band))
+            })
+            .filter_map(|template| template.get_object_id("_id").ok())
+            .collect();
+        if allowed.len() == templates.len() {
+            return Ok(None);
+        }
+        Ok(Some(allowed))
+    }
+
+    /// Возрастная категория темы каждого из уровней
+    async fn topic_age_bands(
+        &self,
+        level_ids: &[ObjectId],
+    ) -> Result<HashMap<ObjectId, Option<AgeBand>>> {
+        let levels = self.load_levels(level_ids.to_vec()).await?;
+        let topic_ids: Vec<ObjectId> = levels.values().map(|level| level.topic_id).collect();
+        let topics: HashMap<ObjectId, Option<AgeBand>> = self
+            .mongo
+            .collection::<TopicRecord>("topics")
+            .find(doc! { "_id": { "$in": topic_ids } })
+            .await
+            .context("Failed to load topics")?
+            .try_collect::<Vec<_>>()
+            .await
+            .context("Failed to read topics")?
+            .into_iter()
+            .map(|topic| (topic.id, topic.age_band))
+            .collect();
+        Ok(levels
+            .values()
+            .map(|level| (level.id, topics.get(&level.topic_id).copied().flatten()))
+            .collect())
+    }
+
+    /// Опубликованные неархивные шаблоны активных уровней, подходящие по возрасту.
+    /// Из большого каталога берётся случайная выборка (`$sample`), чтобы в подбор
+    /// попадали все шаблоны, а не только самые старые
+    pub async fn load_candidates(&self, band: Option<AgeBand>) -> Result<Vec<SelectionCandidate>> {
+        let pipeline = vec![
+            doc! {
+                "$match": {
+                    "status": TemplateStatus::Published.as_str(),
+                    "archived": { "$ne": true },
+                }
+            },
+            doc! { "$sample": { "size": CANDIDATE_SAMPLE_SIZE } },
+        ];
+        let mut templates: Vec<TemplateDocument> = self
+            .mongo
+            .collection::<TemplateDocument>("templates")
+            .aggregate(pipeline)
+            .with_type::<TemplateDocument>()
+            .await
+            .context("Failed to sample published templates")?
+            .try_collect()
+            .await
+            .context("Failed to read published templates")?;
+        // `$sample` отдаёт шаблоны в случайном порядке; выбор с seed зависит от порядка
+        templates.sort_by_key(|template| template.id);
+
+        let level_ids: HashSet<ObjectId> = templates.iter().map(|t| t.level_id).collect();
+        let levels = self.load_levels(level_ids.into_iter().collect()).await?;
+        let topic_ids: Vec<ObjectId> = levels
+            .values()
+            .map(|level| level.topic_id)
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        let topics: HashMap<ObjectId, TopicRecord> = self
+            .mongo
+            .collection::<TopicRecord>("topics")
+            .find(doc! { "_id": { "$in": topic_ids } })
+            .await
+            .context("Failed to load topics")?
+            .try_collect::<Vec<_>>()
+            .await
+            .context("Failed to read topics")?
+            .into_iter()
+            .map(|topic| (topic.id, topic))
+            .collect();
+
+        Ok(templates
+            .iter()
+            .filter_map(|template| {
+                let level = levels.get(&template.level_id)?;
+                if level.status != LevelStatus::Active {
+                    return None;
+                }
+                if let Some(band) = band {
+                    let topic_band = topics.get(&level.topic_id).and_then(|t| t.age_band);
+                    if !band.allows(AgeBand::effective(template.age_band, topic_band)) {
+                        return None;
+                    }
+                }
+                Some(SelectionCandidate {
+                    template_id: template.id,
+                    level_id: level.id,
+                    topic_id: level.topic_id,
+                    difficulty: template_difficulty(template, level),
+                    has_prerequisites: !level.prerequisite_level_ids.is_empty(),
+                })
+            })
+            .collect())
+    }
+
+    pub async fn load_profile(&self, user_id: &str) -> Result<LearnerProfile> {
+        let progress: Vec<ProgressSummary> = self
+            .mongo
+            .collection::<ProgressSummary>("progress_summary_v2")
+            .find(doc! { "user_id": user_id, "attempts_total": { "$gt": 0 } })
+            .await
+            .context("Failed to load progress")?
+            .try_collect()
+            .await
+            .context("Failed to read progress")?;
+
+        // Старые строки прогресса ссылаются на задания, а не на уровни - они пропускаются
+        let level_ids: Vec<ObjectId> = progress
+            .iter()
+            .filter_map(|row| ObjectId::parse_str(&row.level_id).ok())
+            .collect();
+        let levels = self.load_levels(level_ids).await?;
+        let attempted: Vec<(&LevelRecord, &ProgressSummary)> = progress
+            .iter()
+            .filter_map(|row| {
+                let level = levels.get(&ObjectId::parse_str(&row.level_id).ok()?)?;
+                Some((level, row))
+            })
+            .collect();
+
+        // Процент темы - средний по её уровням с весом по числу попыток
+        let mut topic_totals: HashMap<ObjectId, (f64, f64)> = HashMap::new();
+        for (level, row) in &attempted {
+            let attempts = f64::from(row.attempts_total);
+            let entry = topic_totals.entry(level.topic_id).or_default();
+            entry.0 += row.percentage * attempts;
+            entry.1 += attempts;
+        }
+
+        Ok(LearnerProfile {
+            topic_percent: topic_totals
+                .into_iter()
+                .map(|(topic_id, (weighted, attempts))| (topic_id, weighted / attempts))
+                .collect(),
+            recent_templates: self.recent_templates(user_id).await?,
+            target_difficulty: target_difficulty(&attempted),
+        })
+    }
+
+    async fn recent_templates(&self, user_id: &str) -> Result<HashSet<ObjectId>> {
+        let sessions: Vec<Document> = self
+            .mongo
+            .collection::<Document>("sessions")
+            .find(doc! { "user_id": user_id })
+            .sort(doc! { "started_at": -1 })
+            .limit(RECENT_SESSIONS_WINDOW)
+            .projection(doc! { "task_id": 1 })
+            .await
+            .context("Failed to load recent sessions")?
+            .try_collect()
+            .await
+            .context("Failed to read recent sessions")?;
+        let task_ids: Vec<Bson> = sessions
+            .iter()
+            .filter_map(|session| session.get_str("task_id").ok())
+            .map(|task_id| match ObjectId::parse_str(task_id) {
+                Ok(object_id) => Bson::ObjectId(object_id),
+                Err(_) => Bson::String(task_id.to_string()),
+            })
+            .collect();
+        if task_ids.is_empty() {
+            return Ok(HashSet::new());
+        }
+
+        let tasks: Vec<Document> = self
+            .mongo
+            .collection::<Document>("tasks")
+            .find(doc! { "_id": { "$in": task_ids } })
+            .projection(doc! { "template_id": 1 })
+            .await
+            .context("Failed to load recent tasks")?
+            .try_collect()
+            .await
+            .context("Failed to read recent tasks")?;
+        Ok(tasks
+            .iter()
+            .filter_map(|task| task.get_object_id("template_id").ok())
+            .collect())
+    }
+
+    async fn load_levels(&self, ids: Vec<ObjectId>) -> Result<HashMap<ObjectId, LevelRecord>> {
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let levels: Vec<LevelRecord> = self
+            .mongo
+            .collection::<LevelRecord>("levels")
+            .find(doc! { "_id": { "$in": ids } })
+            .await
+            .context("Failed to load levels")?
+            .try_collect()
+            .await
+            .context("Failed to read levels")?;
+        Ok(levels.into_iter().map(|level| (level.id, level)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn candidate(topic_id: ObjectId, difficulty: u8) -> SelectionCandidate {
+        SelectionCandidate {
+            template_id: ObjectId::new(),
+            level_id: ObjectId::new(),
+            topic_id,
+            difficulty,
+            has_prerequisites: false,
+        }
+    }
+
+    fn draw_counts(
+        candidates: &[SelectionCandidate],
+        profile: &LearnerProfile,
+        seed: u64,
+    ) -> HashMap<ObjectId, usize> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut counts = HashMap::new();
+        for _ in 0..2000 {
+            let picked = select_candidate(candidates, profile, &mut rng).unwrap();
+            *counts.entry(picked.template_id).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn weak_topics_are_selected_more_often() {
+        let weak_topic = ObjectId::new();
+        let strong_topic = ObjectId::new();
+        let candidates = [candidate(weak_topic, 0), candidate(strong_topic, 0)];
+        let profile = LearnerProfile {
+            topic_percent: HashMap::from([(weak_topic, 20.0), (strong_topic, 95.0)]),
+            target_difficulty: Some(0),
+            ..LearnerProfile::default()
+        };
+
+        let counts = draw_counts(&candidates, &profile, 42);
+        let weak = counts[&candidates[0].template_id];
+        let strong = counts[&candidates[1].template_id];
+        // Веса 3.4 и 1.15: слабая тема должна выпадать почти в три раза чаще
+        assert!(weak > strong * 2, "weak={weak} strong={strong}");
+
+        // С тем же seed выбор повторяется
+        assert_eq!(counts, draw_counts(&candidates, &profile, 42));
+    }
+
+    #[test]
+    fn recent_templates_are_skipped_while_others_remain() {
+        let topic = ObjectId::new();
+        let candidates = [candidate(topic, 0), candidate(topic, 0)];
+        let mut profile = LearnerProfile {
+            recent_templates: HashSet::from([candidates[0].template_id]),
+            ..LearnerProfile::default()
+        };
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..50 {
+            let picked = select_candidate(&candidates, &profile, &mut rng).unwrap();
+            assert_eq!(picked.template_id, candidates[1].template_id);
+        }
+
+        profile.recent_templates.insert(candidates[1].template_id);
+        assert!(select_candidate(&candidates, &profile, &mut rng).is_some());
+    }
+
+    #[test]
+    fn difficulty_near_target_is_preferred() {
+        let topic = ObjectId::new();
+        let candidates = [candidate(topic, 1), candidate(topic, 3)];
+        let profile = LearnerProfile {
+            topic_percent: HashMap::from([(topic, 50.0)]),
+            target_difficulty: Some(1),
+            ..LearnerProfile::default()
+        };
+        assert!(
+            candidate_weight(&candidates[0], &profile)
+                > 2.0 * candidate_weight(&candidates[1], &profile)
+        );
+    }
+
+    #[test]
+    fn no_history_falls_back_to_uniform_choice() {
+        let candidates: Vec<SelectionCandidate> = (0..4)
+            .map(|rank| candidate(ObjectId::new(), rank))
+            .collect();
+        let counts = draw_counts(&candidates, &LearnerProfile::default(), 1);
+        assert_eq!(counts.len(), candidates.len());
+        assert!(counts.values().all(|count| *count > 350));
+
+        let mut rng = StdRng::seed_from_u64(1);
+        assert!(select_candidate(&[], &LearnerProfile::default(), &mut rng).is_none());
+    }
+}
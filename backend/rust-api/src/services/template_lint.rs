@@ -0,0 +1,473 @@
+//! Линтер шаблонов заданий: структурные проверки, которые иначе ловит модератор.
+//!
+//! Каждое правило - отдельный `LintRule`; новое правило добавляется в
+//! `TemplateLinter::default`. Находки уровня `error` не пускают шаблон на модерацию.
+
+use lazy_static::lazy_static;
+use mongodb::bson::{Bson, Document};
+use regex::Regex;
+
+use crate::models::content::{LevelDifficulty, LevelRecord, LintFinding, LintSeverity, RuleRecord};
+use crate::services::task_selection_service::difficulty_rank;
+
+/// Короче условие задания не сформулировать
+pub const MIN_CONTENT_LEN: usize = 10;
+/// Длиннее задание не помещается на экран ученика без прокрутки
+pub const MAX_CONTENT_LEN: usize = 2000;
+
+/// Плейсхолдеры генератора заданий (`template_generator/engine.py`)
+const PLACEHOLDER_KINDS: [&str; 4] = ["word", "example", "number", "option"];
+
+lazy_static! {
+    /// То же выражение, что у генератора: совпадения он подставляет, остальное выводит как есть
+    static ref PLACEHOLDER_RE: Regex =
+        Regex::new(r"\{\{\s*([a-zA-Z_]+)(?::([^}]+))?\s*\}\}").unwrap();
+}
+
+/// Шаблон вместе с уровнем и связанными правилами
+pub struct LintTarget<'a> {
+    pub content: &'a str,
+    pub params: &'a Document,
+    pub difficulty: Option<&'a str>,
+    /// `None`, если уровень удалён
+    pub level: Option<&'a LevelRecord>,
+    pub rules: Vec<&'a RuleRecord>,
+}
+
+pub trait LintRule: Send + Sync {
+    fn check(&self, target: &LintTarget<'_>, findings: &mut Vec<LintFinding>);
+}
+
+pub struct TemplateLinter {
+    rules: Vec<Box<dyn LintRule>>,
+}
+
+impl Default for TemplateLinter {
+    fn default() -> Self {
+        Self::new(vec![
+            Box::new(PlaceholderParams),
+            Box::new(ContentLength {
+                min: MIN_CONTENT_LEN,
+                max: MAX_CONTENT_LEN,
+            }),
+            Box::new(RuleExamples),
+            Box::new(DifficultyLabel),
+            Box::new(Formatting),
+        ])
+    }
+}
+
+impl TemplateLinter {
+    pub fn new(rules: Vec<Box<dyn LintRule>>) -> Self {
+        Self { rules }
+    }
+
+    pub fn lint(&self, target: &LintTarget<'_>) -> Vec<LintFinding> {
+        let mut findings = Vec::new();
+        for rule in &self.rules {
+            rule.check(target, &mut findings);
+        }
+        findings
+    }
+}
+
+pub fn has_errors(findings: &[LintFinding]) -> bool {
+    findings
+        .iter()
+        .any(|finding| finding.severity == LintSeverity::Error)
+}
+
+fn finding(code: &str, severity: LintSeverity, message: String) -> LintFinding {
+    LintFinding {
+        code: code.to_string(),
+        severity,
+        message,
+    }
+}
+
+/// Плейсхолдеры `{{...}}` известны генератору, а `{{option}}` есть из чего подставить
+pub struct PlaceholderParams;
+
+impl LintRule for PlaceholderParams {
+    fn check(&self, target: &LintTarget<'_>, findings: &mut Vec<LintFinding>) {
+        let options = match target.params.get("options") {
+            Some(Bson::Array(options)) => options.len(),
+            _ => 0,
+        };
+        for captures in PLACEHOLDER_RE.captures_iter(target.content) {
+            let placeholder = &captures[0];
+            let kind = captures[1].to_lowercase();
+            if !PLACEHOLDER_KINDS.contains(&kind.as_str()) {
+                findings.push(finding(
+                    "placeholder_unknown",
+                    LintSeverity::Error,
+                    format!("Unknown placeholder {}", placeholder),
+                ));
+                continue;
+            }
+            if kind != "option" {
+                continue;
+            }
+            if options == 0 {
+                findings.push(finding(
+                    "placeholder_missing_param",
+                    LintSeverity::Error,
+                    format!("{} requires a non-empty params.options", placeholder),
+                ));
+                continue;
+            }
+            let index = captures.get(2).map(|index| index.as_str().trim());
+            if let Some(index) = index {
+                if index
+                    .parse::<usize>()
+                    .map_or(true, |index| index >= options)
+                {
+                    findings.push(finding(
+                        "placeholder_option_index",
+                        LintSeverity::Error,
+                        format!(
+                            "{} does not match any of {} params.options",
+                            placeholder, options
+                        ),
+                    ));
+                }
+            }
+        }
+
+        let rest = PLACEHOLDER_RE.replace_all(target.content, "");
+        if rest.contains("{{") || rest.contains("}}") {
+            findings.push(finding(
+                "placeholder_malformed",
+                LintSeverity::Error,
+                "Content has unclosed or malformed {{...}} markers".to_string(),
+            ));
+        }
+    }
+}
+
+/// Длина условия в символах, без пробелов по краям
+pub struct ContentLength {
+    pub min: usize,
+    pub max: usize,
+}
+
+impl LintRule for ContentLength {
+    fn check(&self, target: &LintTarget<'_>, findings: &mut Vec<LintFinding>) {
+        let length = target.content.trim().chars().count();
+        if length < self.min {
+            findings.push(finding(
+                "content_too_short",
+                LintSeverity::Error,
+                format!(
+                    "Content is {} characters, at least {} required",
+                    length, self.min
+                ),
+            ));
+        } else if length > self.max {
+            findings.push(finding(
+                "content_too_long",
+                LintSeverity::Error,
+                format!(
+                    "Content is {} characters, at most {} allowed",
+                    length, self.max
+                ),
+            ));
+        }
+    }
+}
+
+/// Хотя бы у одного связанного правила есть пример: его показывают в подсказке
+pub struct RuleExamples;
+
+impl LintRule for RuleExamples {
+    fn check(&self, target: &LintTarget<'_>, findings: &mut Vec<LintFinding>) {
+        if target.rules.is_empty() {
+            findings.push(finding(
+                "rule_example_missing",
+                LintSeverity::Error,
+                "Template has no linked rules".to_string(),
+            ));
+            return;
+        }
+        let has_example = target.rules.iter().any(|rule| {
+            rule.examples
+                .iter()
+                .any(|example| !example.trim().is_empty())
+        });
+        if !has_example {
+            findings.push(finding(
+                "rule_example_missing",
+                LintSeverity::Error,
+                "None of the linked rules has an example".to_string(),
+            ));
+        }
+    }
+}
+
+/// Своя сложность шаблона - одна из `a1`..`b2` и не дальше соседней от сложности уровня
+pub struct DifficultyLabel;
+
+impl LintRule for DifficultyLabel {
+    fn check(&self, target: &LintTarget<'_>, findings: &mut Vec<LintFinding>) {
+        let Some(label) = target
+            .difficulty
+            .map(str::trim)
+            .filter(|label| !label.is_empty())
+        else {
+            return;
+        };
+        let Some(difficulty) = parse_difficulty(label) else {
+            findings.push(finding(
+                "difficulty_unknown",
+                LintSeverity::Error,
+                format!("Unknown difficulty '{}', expected A1, A2, B1 or B2", label),
+            ));
+            return;
+        };
+        let Some(level) = target.level else {
+            return;
+        };
+        let distance = difficulty_rank(difficulty).abs_diff(difficulty_rank(level.difficulty));
+        if distance == 0 {
+            return;
+        }
+        let severity = if distance > 1 {
+            LintSeverity::Error
+        } else {
+            LintSeverity::Warning
+        };
+        findings.push(finding(
+            "difficulty_mismatch",
+            severity,
+            format!(
+                "Template difficulty {} differs from level difficulty {}",
+                difficulty.as_str().to_uppercase(),
+                level.difficulty.as_str().to_uppercase()
+            ),
+        ));
+    }
+}
+
+fn parse_difficulty(label: &str) -> Option<LevelDifficulty> {
+    match label.to_lowercase().as_str() {
+        "a1" => Some(LevelDifficulty::A1),
+        "a2" => Some(LevelDifficulty::A2),
+        "b1" => Some(LevelDifficulty::B1),
+        "b2" => Some(LevelDifficulty::B2),
+        _ => None,
+    }
+}
+
+/// Двойные пробелы и незакрытая разметка markdown. Отступы в начале строки не считаются
+pub struct Formatting;
+
+impl LintRule for Formatting {
+    fn check(&self, target: &LintTarget<'_>, findings: &mut Vec<LintFinding>) {
+        let double_space = target
+            .content
+            .lines()
+            .position(|line| line.trim().contains("  "));
+        if let Some(line) = double_space {
+            findings.push(finding(
+                "double_space",
+                LintSeverity::Warning,
+                format!("Double space on line {}", line + 1),
+            ));
+        }
+
+        for marker in ["**", "__", "`"] {
+            if target.content.matches(marker).count() % 2 != 0 {
+                findings.push(finding(
+                    "markdown_unbalanced",
+                    LintSeverity::Warning,
+                    format!("Unbalanced markdown marker {}", marker),
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::content::{LevelStatus, RuleStatus};
+    use mongodb::bson::{doc, oid::ObjectId, DateTime as BsonDateTime};
+
+    fn level(difficulty: LevelDifficulty) -> LevelRecord {
+        let now = BsonDateTime::now();
+        LevelRecord {
+            id: ObjectId::new(),
+            topic_id: ObjectId::new(),
+            order: 1,
+            name: "Уровень".to_string(),
+            difficulty,
+            description: String::new(),
+            min_pass_percent: 80,
+            status: LevelStatus::Active,
+            prerequisite_level_ids: Vec::new(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn rule(examples: &[&str]) -> RuleRecord {
+        let now = BsonDateTime::now();
+        RuleRecord {
+            id: ObjectId::new(),
+            slug: "rule".to_string(),
+            name: "Правило".to_string(),
+            category: "orthography".to_string(),
+            description: String::new(),
+            examples: examples.iter().map(|example| example.to_string()).collect(),
+            exceptions: Vec::new(),
+            sources: Vec::new(),
+            status: RuleStatus::Active,
+            merged_into: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn codes(rule: &dyn LintRule, target: &LintTarget<'_>) -> Vec<(String, LintSeverity)> {
+        let mut findings = Vec::new();
+        rule.check(target, &mut findings);
+        findings
+            .into_iter()
+            .map(|finding| (finding.code, finding.severity))
+            .collect()
+    }
+
+    fn target<'a>(content: &'a str, params: &'a Document) -> LintTarget<'a> {
+        LintTarget {
+            content,
+            params,
+            difficulty: None,
+            level: None,
+            rules: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn placeholders_need_known_kind_and_options() {
+        let no_options = Document::new();
+        let options = doc! { "options": ["один", "два"] };
+        let error = |code: &str| vec![(code.to_string(), LintSeverity::Error)];
+
+        let ok = "Найдите {{word:noun:genitive}} и {{number:1:5}}, вариант {{ option:1 }}";
+        assert!(codes(&PlaceholderParams, &target(ok, &options)).is_empty());
+        assert_eq!(
+            codes(&PlaceholderParams, &target("Слово {{wrod}}", &options)),
+            error("placeholder_unknown")
+        );
+        assert_eq!(
+            codes(
+                &PlaceholderParams,
+                &target("Выберите {{option}}", &no_options)
+            ),
+            error("placeholder_missing_param")
+        );
+        assert_eq!(
+            codes(
+                &PlaceholderParams,
+                &target("Выберите {{option:2}}", &options)
+            ),
+            error("placeholder_option_index")
+        );
+        assert_eq!(
+            codes(&PlaceholderParams, &target("Слово {{word:noun", &options)),
+            error("placeholder_malformed")
+        );
+    }
+
+    #[test]
+    fn content_length_has_both_bounds() {
+        let params = Document::new();
+        let rule = ContentLength { min: 5, max: 10 };
+        assert_eq!(
+            codes(&rule, &target("  Пол  ", &params)),
+            vec![("content_too_short".to_string(), LintSeverity::Error)]
+        );
+        assert!(codes(&rule, &target("Пол..гать", &params)).is_empty());
+        assert_eq!(
+            codes(&rule, &target("Расположить", &params)),
+            vec![("content_too_long".to_string(), LintSeverity::Error)]
+        );
+    }
+
+    #[test]
+    fn linked_rules_need_an_example() {
+        let params = Document::new();
+        let missing = vec![("rule_example_missing".to_string(), LintSeverity::Error)];
+        let mut template = target("Пол..гать", &params);
+        assert_eq!(codes(&RuleExamples, &template), missing);
+
+        let without = [rule(&[" "]), rule(&[])];
+        template.rules = without.iter().collect();
+        assert_eq!(codes(&RuleExamples, &template), missing);
+
+        let with = [rule(&[]), rule(&["Полагать"])];
+        template.rules = with.iter().collect();
+        assert!(codes(&RuleExamples, &template).is_empty());
+    }
+
+    #[test]
+    fn difficulty_must_be_known_and_close_to_level() {
+        let params = Document::new();
+        let a1 = level(LevelDifficulty::A1);
+        let mut template = target("Пол..гать", &params);
+        template.level = Some(&a1);
+
+        for (label, expected) in [
+            (None, vec![]),
+            (Some("a1"), vec![]),
+            (
+                Some("A2"),
+                vec![("difficulty_mismatch".to_string(), LintSeverity::Warning)],
+            ),
+            (
+                Some("B1"),
+                vec![("difficulty_mismatch".to_string(), LintSeverity::Error)],
+            ),
+            (
+                Some("C1"),
+                vec![("difficulty_unknown".to_string(), LintSeverity::Error)],
+            ),
+        ] {
+            template.difficulty = label;
+            assert_eq!(codes(&DifficultyLabel, &template), expected, "{label:?}");
+        }
+    }
+
+    #[test]
+    fn formatting_flags_double_spaces_and_open_markers() {
+        let params = Document::new();
+        assert!(codes(
+            &Formatting,
+            &target("Список:\n    **важно** и `код`", &params)
+        )
+        .is_empty());
+        assert_eq!(
+            codes(&Formatting, &target("Два  пробела и **жирный", &params)),
+            vec![
+                ("double_space".to_string(), LintSeverity::Warning),
+                ("markdown_unbalanced".to_string(), LintSeverity::Warning),
+            ]
+        );
+    }
+
+    #[test]
+    fn default_linter_runs_every_rule() {
+        let params = Document::new();
+        let findings = TemplateLinter::default().lint(&target("{{foo}}", &params));
+        let codes: Vec<&str> = findings.iter().map(|f| f.code.as_str()).collect();
+        assert_eq!(
+            codes,
+            vec![
+                "placeholder_unknown",
+                "content_too_short",
+                "rule_example_missing"
+            ]
+        );
+        assert!(has_errors(&findings));
+    }
+}
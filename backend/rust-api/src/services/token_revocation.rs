@@ -0,0 +1,50 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use redis::{aio::ConnectionManager, AsyncCommands};
+
+use crate::services::auth_service::access_token_ttl_seconds;
+
+/// Метка отзыва access-токенов пользователя: значение - время отзыва (unix, секунды).
+/// Живёт столько же, сколько access-токен, - после этого все старые токены и так истекли
+pub fn revocation_key(user_id: &str) -> String {
+    format!("access_revoked:{}", user_id)
+}
+
+/// Токен выпущен не позже отзыва. Время в JWT с точностью до секунды, поэтому токен,
+/// выпущенный в ту же секунду, что и отзыв, тоже отклоняется
+pub fn is_revoked(issued_at: usize, revoked_at: i64) -> bool {
+    issued_at as i64 <= revoked_at
+}
+
+/// Отозвать все уже выданные access-токены пользователя (блокировка, удаление,
+/// принудительный выход). Refresh-токены отзываются отдельно, в MongoDB
+pub async fn revoke_user_tokens(redis: &ConnectionManager, user_id: &str) -> Result<i64> {
+    let revoked_at = Utc::now().timestamp();
+    let ttl = access_token_ttl_seconds().max(1) as u64;
+    let mut conn = redis.clone();
+    let _: () = conn
+        .set_ex(revocation_key(user_id), revoked_at, ttl)
+        .await
+        .context("Failed to store access token revocation")?;
+    Ok(revoked_at)
+}
+
+/// Время отзыва токенов пользователя, если они отзывались; один GET
+pub async fn revoked_at(redis: &ConnectionManager, user_id: &str) -> Result<Option<i64>> {
+    let mut conn = redis.clone();
+    conn.get(revocation_key(user_id))
+        .await
+        .context("Failed to read access token revocation")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_issued_up_to_revocation_are_rejected() {
+        assert!(is_revoked(1_000, 1_005));
+        assert!(is_revoked(1_005, 1_005));
+        assert!(!is_revoked(1_006, 1_005));
+    }
+}
@@ -1,12 +1,17 @@
 use crate::models::user::{
     BlockUserRequest, BulkUserActionError, BulkUserActionRequest, BulkUserActionResult,
     BulkUserOperation, CreateUserRequest, ListUsersQuery, UpdateUserRequest, User,
-    UserDetailResponse,
+    UserDetailResponse, UserRole,
 };
+use crate::services::group_service::GroupService;
+use crate::services::permission_service::ensure_assignable_role;
+use crate::services::token_revocation::revoke_user_tokens;
+use crate::utils::pagination::{Page, PageCursor};
 use anyhow::{anyhow, Context, Result};
 use bcrypt::{hash, DEFAULT_COST};
 use chrono::{Duration, Utc};
-use mongodb::bson::{doc, oid::ObjectId, DateTime as BsonDateTime, Regex};
+use futures::TryStreamExt;
+use mongodb::bson::{self, doc, oid::ObjectId, DateTime as BsonDateTime, Document, Regex};
 use mongodb::Database;
 use redis::aio::ConnectionManager;
 
@@ -33,6 +38,7 @@ impl UserManagementService {
         if existing_user.is_some() {
             return Err(anyhow!("User with this email already exists"));
         }
+        ensure_assignable_role(&self.mongo, &req.role).await?;
 
         // Хеширование пароля
         let password_hash = hash(&req.password, DEFAULT_COST).context("Failed to hash password")?;
@@ -44,6 +50,9 @@ impl UserManagementService {
             email: req.email.clone(),
             password_hash,
             name: req.name,
+            birth_year: req.birth_year.filter(|_| req.role == UserRole::Student),
+            timezone: None,
+            preferred_locale: None,
             role: req.role,
             group_ids: req.group_ids.unwrap_or_default(),
             is_blocked: false,
@@ -73,12 +82,19 @@ impl UserManagementService {
             .context("Failed to fetch created user")?
             .ok_or_else(|| anyhow!("User not found after creation"))?;
 
+        self.sync_group_counts(&created_user.group_ids).await?;
+
         Ok(UserDetailResponse::from(created_user))
     }
 
-    /// Получить список пользователей с фильтрами
-    pub async fn list_users(&self, query: ListUsersQuery) -> Result<Vec<UserDetailResponse>> {
-        let users_collection = self.mongo.collection::<User>("users");
+    /// Получить страницу пользователей с фильтрами: по `offset` или после курсора
+    /// в порядке `query.sort`
+    pub async fn list_users(
+        &self,
+        query: ListUsersQuery,
+        cursor: Option<&PageCursor>,
+    ) -> Result<Page<UserDetailResponse>> {
+        let users_collection = self.mongo.collection::<Document>("users");
 
         // Построение фильтра
         let mut filter = doc! {};
@@ -107,27 +123,35 @@ impl UserManagementService {
             );
         }
 
-        // Пагинация
-        let limit = query.limit.unwrap_or(50).min(100) as i64;
-        let offset = query.offset.unwrap_or(0) as u64;
-
-        // Запрос к MongoDB
-        let mut cursor = users_collection
-            .find(filter)
-            .skip(offset)
-            .limit(limit)
-            .await
-            .context("Failed to query users")?;
-
-        let mut users = Vec::new();
-        while cursor.advance().await.context("Failed to advance cursor")? {
-            let user = cursor
-                .deserialize_current()
-                .context("Failed to deserialize user")?;
-            users.push(UserDetailResponse::from(user));
+        // Пагинация: лишняя запись сверх лимита означает, что есть следующая страница
+        let sort = query.sort.unwrap_or_default().key();
+        let limit = query.limit.unwrap_or(50).min(100) as usize;
+        let mut find = users_collection
+            .find(sort.apply(filter, cursor))
+            .sort(sort.sort_document())
+            .limit(limit as i64 + 1);
+        if cursor.is_none() {
+            find = find.skip(query.offset.unwrap_or(0) as u64);
         }
 
-        Ok(users)
+        let documents: Vec<Document> = find
+            .await
+            .context("Failed to query users")?
+            .try_collect()
+            .await
+            .context("Failed to read users")?;
+        let (documents, next_cursor) = sort.split_page(documents, limit);
+
+        let items = documents
+            .into_iter()
+            .map(|document| {
+                bson::from_document::<User>(document)
+                    .map(UserDetailResponse::from)
+                    .context("Failed to deserialize user")
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Page { items, next_cursor })
     }
 
     /// Получить пользователя по ID
@@ -166,7 +190,10 @@ impl UserManagementService {
             update_doc.get_document_mut("$set")?.insert("name", name);
         }
 
+        let membership_changed = req.group_ids.is_some() || req.role.is_some();
+
         if let Some(role) = req.role {
+            ensure_assignable_role(&self.mongo, &role).await?;
             update_doc
                 .get_document_mut("$set")?
                 .insert("role", role.as_str());
@@ -184,16 +211,25 @@ impl UserManagementService {
                 .insert("is_blocked", is_blocked);
         }
 
-        // Обновление в MongoDB
-        let result = users_collection
-            .update_one(doc! { "_id": object_id }, update_doc)
-            .await
-            .context("Failed to update user")?;
+        if let Some(birth_year) = req.birth_year {
+            update_doc
+                .get_document_mut("$set")?
+                .insert("birthYear", birth_year);
+        }
 
-        if result.matched_count == 0 {
-            return Err(anyhow!("User not found"));
+        if let Some(timezone) = req.timezone {
+            update_doc
+                .get_document_mut("$set")?
+                .insert("timezone", timezone);
         }
 
+        // Обновление в MongoDB (возвращает документ до изменения)
+        let previous_user = users_collection
+            .find_one_and_update(doc! { "_id": object_id }, update_doc)
+            .await
+            .context("Failed to update user")?
+            .ok_or_else(|| anyhow!("User not found"))?;
+
         // Получение обновленного пользователя
         let updated_user = users_collection
             .find_one(doc! { "_id": object_id })
@@ -201,6 +237,12 @@ impl UserManagementService {
             .context("Failed to fetch updated user")?
             .ok_or_else(|| anyhow!("User not found after update"))?;
 
+        if membership_changed {
+            let mut affected = previous_user.group_ids;
+            affected.extend(updated_user.group_ids.iter().cloned());
+            self.sync_group_counts(&affected).await?;
+        }
+
         Ok(UserDetailResponse::from(updated_user))
     }
 
@@ -214,24 +256,21 @@ impl UserManagementService {
         let object_id = ObjectId::parse_str(user_id).context("Invalid user ID format")?;
 
         // Удаление пользователя
-        let result = users_collection
-            .delete_one(doc! { "_id": object_id })
+        let deleted_user = users_collection
+            .find_one_and_delete(doc! { "_id": object_id })
             .await
-            .context("Failed to delete user")?;
-
-        if result.deleted_count == 0 {
-            return Err(anyhow!("User not found"));
-        }
+            .context("Failed to delete user")?
+            .ok_or_else(|| anyhow!("User not found"))?;
 
         // Удаление всех refresh tokens пользователя
         let user_id_str = object_id.to_hex();
         refresh_tokens_collection
-            .delete_many(doc! { "userId": &user_id_str })
+            .delete_many(doc! { "userId": object_id })
             .await
             .context("Failed to delete refresh tokens")?;
+        revoke_user_tokens(&self.redis, &user_id_str).await?;
 
-        // @todo #A6-01:30min Удалить user_id из groups.student_ids если есть
-        //  Требуется после реализации добавления учеников в группу
+        self.sync_group_counts(&deleted_user.group_ids).await?;
 
         Ok(())
     }
@@ -283,15 +322,16 @@ impl UserManagementService {
             return Err(anyhow!("User not found"));
         }
 
-        // Отзыв всех refresh tokens (блокировка всех сессий)
+        // Отзыв всех refresh tokens и уже выданных access tokens (блокировка всех сессий)
         let user_id_str = object_id.to_hex();
         refresh_tokens_collection
             .update_many(
-                doc! { "userId": &user_id_str },
+                doc! { "userId": object_id },
                 doc! { "$set": { "revoked": true } },
             )
             .await
             .context("Failed to revoke refresh tokens")?;
+        revoke_user_tokens(&self.redis, &user_id_str).await?;
 
         // @todo #A6-01:1h Очистить Redis кеш для failed login attempts
         //  Требуется интеграция с Redis для очистки счетчиков
@@ -306,6 +346,35 @@ impl UserManagementService {
         Ok(UserDetailResponse::from(blocked_user))
     }
 
+    /// Принудительный выход: отозвать refresh tokens и уже выданные access tokens.
+    /// Возвращает число отозванных сессий
+    pub async fn force_logout(&self, user_id: &str) -> Result<u64> {
+        let object_id = ObjectId::parse_str(user_id).context("Invalid user ID format")?;
+        let exists = self
+            .mongo
+            .collection::<User>("users")
+            .count_documents(doc! { "_id": object_id })
+            .await
+            .context("Failed to find user")?;
+        if exists == 0 {
+            return Err(anyhow!("User not found"));
+        }
+
+        let user_id_str = object_id.to_hex();
+        let result = self
+            .mongo
+            .collection::<mongodb::bson::Document>("refresh_tokens")
+            .update_many(
+                doc! { "userId": object_id, "revoked": false },
+                doc! { "$set": { "revoked": true } },
+            )
+            .await
+            .context("Failed to revoke refresh tokens")?;
+        revoke_user_tokens(&self.redis, &user_id_str).await?;
+
+        Ok(result.modified_count)
+    }
+
     /// Разблокировать пользователя
     pub async fn unblock_user(&self, user_id: &str) -> Result<UserDetailResponse> {
         let users_collection = self.mongo.collection::<User>("users");
@@ -422,28 +491,37 @@ impl UserManagementService {
 
         let update_doc = doc! {
             "$set": {
-                "group_ids": group_ids,
+                "group_ids": &group_ids,
                 "updatedAt": BsonDateTime::from_millis(Utc::now().timestamp_millis()),
             }
         };
 
-        let result = users_collection
-            .update_one(doc! { "_id": object_id }, update_doc)
+        let previous_user = users_collection
+            .find_one_and_update(doc! { "_id": object_id }, update_doc)
             .await
-            .context("Failed to update user groups")?;
+            .context("Failed to update user groups")?
+            .ok_or_else(|| anyhow!("User not found"))?;
 
-        if result.matched_count == 0 {
-            return Err(anyhow!("User not found"));
-        }
+        let mut affected = previous_user.group_ids;
+        affected.extend(group_ids);
+        self.sync_group_counts(&affected).await
+    }
 
-        Ok(())
+    /// Пересчитать studentCount групп, состав которых мог измениться
+    async fn sync_group_counts(&self, group_ids: &[String]) -> Result<()> {
+        if group_ids.is_empty() {
+            return Ok(());
+        }
+        GroupService::new(self.mongo.clone())
+            .sync_student_counts(group_ids)
+            .await
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{config::Config, models::user::UserRole};
+    use crate::config::Config;
     use mongodb::{error::ErrorKind, options::ClientOptions};
     use redis::Client;
     use serial_test::serial;
@@ -496,6 +574,7 @@ mod tests {
             name: name.into(),
             role: UserRole::Student,
             group_ids: None,
+            birth_year: None,
         }
     }
 
@@ -507,6 +586,8 @@ mod tests {
             search: None,
             limit: None,
             offset: None,
+            sort: None,
+            cursor: None,
         }
     }
 
@@ -534,7 +615,11 @@ mod tests {
             "error should mention duplicate email: {err:?}"
         );
 
-        let list = service.list_users(base_query()).await.expect("list users");
+        let list = service
+            .list_users(base_query(), None)
+            .await
+            .expect("list users")
+            .items;
         assert_eq!(list.len(), 1);
         assert_eq!(list[0].email, email);
     }
@@ -576,7 +661,11 @@ mod tests {
 
         let mut query = base_query();
         query.search = Some("alice".into());
-        let results = service.list_users(query).await.expect("search results");
+        let results = service
+            .list_users(query, None)
+            .await
+            .expect("search results")
+            .items;
 
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].name, "Alice Example");
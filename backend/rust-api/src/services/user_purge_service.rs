@@ -0,0 +1,366 @@
+//! Удаление персональных данных ученика по запросу родителя (`POST /admin/users/{id}/purge`).
+//!
+//! Учётная запись не удаляется, а переезжает на новый случайный id (псевдоним) с
+//! заглушками вместо имени и email. На тот же псевдоним переписываются ответы,
+//! сессии, прогресс и членство в группах, поэтому статистика групп, уровней и тем не
+//! меняется. Токены, история входов, уведомления, письма в очереди рассылок и сигналы
+//! античита удаляются.
+//!
+//! Результат записывается в `user_purges` под исходным id. Пока очистка не завершена,
+//! там же хранится псевдоним: прерванный вызов можно повторить, и он продолжит с того
+//! же места. По завершении псевдоним стирается, а повторный вызов ничего не делает.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, oid::ObjectId, Bson, Document},
+    error::{ErrorKind, WriteFailure},
+    Collection, Database,
+};
+use redis::aio::ConnectionManager;
+
+use crate::{
+    models::user_purge::{PurgeUserResponse, UserPurgeRecord, UserPurgeStatus},
+    services::{
+        achievement_service::USER_ACHIEVEMENTS_COLLECTION,
+        anticheat_preview_service::SESSION_STATS_COLLECTION,
+        email_outbox_service::EMAIL_OUTBOX_COLLECTION,
+        login_history_service::LOGIN_HISTORY_COLLECTION,
+        notification_center_service::NOTIFICATIONS_COLLECTION,
+        review_service::REVIEW_QUEUE_COLLECTION, token_revocation::revoke_user_tokens,
+    },
+};
+
+pub const USER_PURGES_COLLECTION: &str = "user_purges";
+
+/// Имя очищенного пользователя (в том числе в лидербордах)
+pub const PURGED_USER_NAME: &str = "Deleted user";
+
+/// Ошибки, которые возвращаются клиенту как 400/404
+#[derive(Debug, thiserror::Error)]
+pub enum UserPurgeError {
+    #[error("User not found")]
+    UserNotFound,
+    #[error("Confirmation does not match the account email")]
+    ConfirmationMismatch,
+    #[error("Administrators cannot purge their own account")]
+    SelfPurge,
+}
+
+/// Коллекции, где у ответов и прогресса ученика переписывается `user_id` (hex)
+const REATTRIBUTED_BY_USER_ID: [&str; 6] = [
+    "attempt_records",
+    "sessions",
+    "hint_records",
+    "session_archive_stubs",
+    "incidents",
+    REVIEW_QUEUE_COLLECTION,
+];
+
+pub struct UserPurgeService {
+    mongo: Database,
+    redis: ConnectionManager,
+}
+
+impl UserPurgeService {
+    pub fn new(mongo: Database, redis: ConnectionManager) -> Self {
+        Self { mongo, redis }
+    }
+
+    fn purges(&self) -> Collection<UserPurgeRecord> {
+        self.mongo
+            .collection::<UserPurgeRecord>(USER_PURGES_COLLECTION)
+    }
+
+    /// Очистить пользователя. `confirm` сверяется с email без учёта регистра; для уже
+    /// очищенного пользователя сверять не с чем, и вызов просто возвращает пустой отчёт
+    pub async fn purge_user(
+        &self,
+        user_id: ObjectId,
+        confirm: &str,
+        purged_by: &str,
+    ) -> Result<PurgeUserResponse> {
+        if purged_by == user_id.to_hex() {
+            return Err(UserPurgeError::SelfPurge.into());
+        }
+        let existing = self
+            .purges()
+            .find_one(doc! { "_id": user_id })
+            .await
+            .context("Failed to load purge record")?;
+        if let Some(record) = &existing {
+            if record.status == UserPurgeStatus::Completed {
+                return Ok(PurgeUserResponse {
+                    user_id: user_id.to_hex(),
+                    already_purged: true,
+                    purged_at: record.purged_at.unwrap_or(record.started_at),
+                    removed: BTreeMap::new(),
+                    reattributed: BTreeMap::new(),
+                });
+            }
+        }
+
+        let user = self
+            .mongo
+            .collection::<Document>("users")
+            .find_one(doc! { "_id": user_id })
+            .await
+            .context("Failed to load user")?;
+        match &user {
+            Some(user) => {
+                let email = user.get_str("email").unwrap_or_default();
+                if !email.trim().eq_ignore_ascii_case(confirm.trim()) {
+                    return Err(UserPurgeError::ConfirmationMismatch.into());
+                }
+            }
+            // Учётная запись уже переехала на псевдоним в прерванном вызове
+            None if existing.is_some() => {}
+            None => return Err(UserPurgeError::UserNotFound.into()),
+        }
+
+        let mut record = match existing {
+            Some(record) if record.pseudonym_id.is_some() => record,
+            _ => self.start_purge(user_id, purged_by).await?,
+        };
+        let pseudonym_id = record
+            .pseudonym_id
+            .context("Purge record has no pseudonym")?;
+
+        let mut removed = BTreeMap::new();
+        let mut reattributed = BTreeMap::new();
+        self.remove_personal_data(user_id, &mut removed).await?;
+        self.reattribute(user_id, pseudonym_id, &mut reattributed)
+            .await?;
+        if let Some(user) = user {
+            self.move_account(user, pseudonym_id).await?;
+            reattributed.insert("users".to_string(), 1);
+        }
+        revoke_user_tokens(&self.redis, &user_id.to_hex()).await?;
+
+        let purged_at = Utc::now();
+        record.status = UserPurgeStatus::Completed;
+        record.pseudonym_id = None;
+        record.purged_at = Some(purged_at);
+        record.removed = removed.clone();
+        record.reattributed = reattributed.clone();
+        self.purges()
+            .replace_one(doc! { "_id": user_id }, &record)
+            .await
+            .context("Failed to complete purge record")?;
+
+        Ok(PurgeUserResponse {
+            user_id: user_id.to_hex(),
+            already_purged: false,
+            purged_at,
+            removed,
+            reattributed,
+        })
+    }
+
+    /// Записать начало очистки с новым псевдонимом. Если параллельный вызов успел
+    /// первым, берётся его псевдоним
+    async fn start_purge(&self, user_id: ObjectId, purged_by: &str) -> Result<UserPurgeRecord> {
+        let record = UserPurgeRecord {
+            user_id,
+            status: UserPurgeStatus::InProgress,
+            pseudonym_id: Some(ObjectId::new()),
+            purged_by: purged_by.to_string(),
+            started_at: Utc::now(),
+            purged_at: None,
+            removed: BTreeMap::new(),
+            reattributed: BTreeMap::new(),
+        };
+        match self.purges().insert_one(&record).await {
+            Ok(_) => Ok(record),
+            Err(err) if is_duplicate_key(&err) => self
+                .purges()
+                .find_one(doc! { "_id": user_id })
+                .await
+                .context("Failed to load purge record")?
+                .context("Purge record disappeared"),
+            Err(err) => Err(err).context("Failed to create purge record"),
+        }
+    }
+
+    /// Удалить данные, которые ничего не дают статистике
+    async fn remove_personal_data(
+        &self,
+        user_id: ObjectId,
+        removed: &mut BTreeMap<String, u64>,
+    ) -> Result<()> {
+        let hex = user_id.to_hex();
+        let targets = [
+            ("refresh_tokens", doc! { "userId": user_id }),
+            (LOGIN_HISTORY_COLLECTION, doc! { "userId": user_id }),
+            (NOTIFICATIONS_COLLECTION, doc! { "recipient_id": &hex }),
+            // Письма хранят адрес, имя и текст - отчёт о рассылке их лишится
+            (EMAIL_OUTBOX_COLLECTION, doc! { "recipient_id": user_id }),
+            ("anticheat_signals", doc! { "user_id": &hex }),
+            (SESSION_STATS_COLLECTION, doc! { "user_id": &hex }),
+        ];
+        for (collection, filter) in targets {
+            let result = self
+                .mongo
+                .collection::<Document>(collection)
+                .delete_many(filter)
+                .await
+                .with_context(|| format!("Failed to purge {}", collection))?;
+            removed.insert(collection.to_string(), result.deleted_count);
+        }
+        Ok(())
+    }
+
+    /// Переписать ответы, прогресс и членство в группах на псевдоним
+    async fn reattribute(
+        &self,
+        user_id: ObjectId,
+        pseudonym_id: ObjectId,
+        reattributed: &mut BTreeMap<String, u64>,
+    ) -> Result<()> {
+        let (hex, pseudonym) = (user_id.to_hex(), pseudonym_id.to_hex());
+
+        for collection in REATTRIBUTED_BY_USER_ID {
+            let result = self
+                .mongo
+                .collection::<Document>(collection)
+                .update_many(
+                    doc! { "user_id": &hex },
+                    doc! { "$set": { "user_id": &pseudonym } },
+                )
+                .await
+                .with_context(|| format!("Failed to reattribute {}", collection))?;
+            reattributed.insert(collection.to_string(), result.modified_count);
+        }
+
+        // Старая сводка хранит user_id то строкой, то ObjectId
+        let legacy = self.mongo.collection::<Document>("progress_summary");
+        let mut legacy_count = 0;
+        for (from, to) in [
+            (Bson::String(hex.clone()), Bson::String(pseudonym.clone())),
+            (Bson::ObjectId(user_id), Bson::ObjectId(pseudonym_id)),
+        ] {
+            legacy_count += legacy
+                .update_many(doc! { "user_id": from }, doc! { "$set": { "user_id": to } })
+                .await
+                .context("Failed to reattribute progress_summary")?
+                .modified_count;
+        }
+        reattributed.insert("progress_summary".to_string(), legacy_count);
+
+        // В `_id` строк сводки и достижений входит id ученика - документы пересоздаются
+        let progress = self.mongo.collection::<Document>("progress_summary_v2");
+        let mut rows: Vec<Document> = progress
+            .find(doc! { "user_id": &hex })
+            .await
+            .context("Failed to load progress rows")?
+            .try_collect()
+            .await
+            .context("Failed to read progress rows")?;
+        for row in &mut rows {
+            let old_id = row.get("_id").cloned().unwrap_or(Bson::Null);
+            let level_id = row.get_str("level_id").unwrap_or_default().to_string();
+            row.insert("_id", format!("{}:{}", pseudonym, level_id));
+            row.insert("user_id", &pseudonym);
+            self.rekey(&progress, old_id, row).await?;
+        }
+        reattributed.insert("progress_summary_v2".to_string(), rows.len() as u64);
+
+        let achievements = self
+            .mongo
+            .collection::<Document>(USER_ACHIEVEMENTS_COLLECTION);
+        let mut achievements_count = 0;
+        if let Some(mut state) = achievements
+            .find_one(doc! { "_id": &hex })
+            .await
+            .context("Failed to load achievements")?
+        {
+            state.insert("_id", &pseudonym);
+            self.rekey(&achievements, Bson::String(hex.clone()), &state)
+                .await?;
+            achievements_count = 1;
+        }
+        reattributed.insert(USER_ACHIEVEMENTS_COLLECTION.to_string(), achievements_count);
+
+        let groups = self
+            .mongo
+            .collection::<Document>("groups")
+            .update_many(
+                doc! { "student_ids": user_id },
+                doc! { "$set": { "student_ids.$": pseudonym_id } },
+            )
+            .await
+            .context("Failed to reattribute group members")?;
+        reattributed.insert("groups".to_string(), groups.modified_count);
+
+        let leaderboards = self
+            .mongo
+            .collection::<Document>("leaderboards")
+            .update_many(
+                doc! { "rankings.user_id": user_id },
+                doc! { "$set": {
+                    "rankings.$[entry].user_id": pseudonym_id,
+                    "rankings.$[entry].name": PURGED_USER_NAME,
+                } },
+            )
+            .array_filters(vec![doc! { "entry.user_id": user_id }])
+            .await
+            .context("Failed to reattribute leaderboards")?;
+        reattributed.insert("leaderboards".to_string(), leaderboards.modified_count);
+
+        Ok(())
+    }
+
+    /// Перенести учётную запись на псевдоним: группы и роль остаются, остальное -
+    /// заглушки. Сначала пишется новый документ, потом удаляется старый, чтобы
+    /// прерванный вызов не потерял учётную запись
+    async fn move_account(&self, user: Document, pseudonym_id: ObjectId) -> Result<()> {
+        let users = self.mongo.collection::<Document>("users");
+        let old_id = user.get("_id").cloned().unwrap_or(Bson::Null);
+        let now = mongodb::bson::DateTime::now();
+        let keep = |field: &str| user.get(field).cloned().unwrap_or(Bson::Null);
+
+        let tombstone = doc! {
+            "_id": pseudonym_id,
+            "email": format!("purged-{}@deleted.invalid", pseudonym_id.to_hex()),
+            // Не хэш: войти с ним невозможно
+            "password_hash": "!purged",
+            "name": PURGED_USER_NAME,
+            "role": keep("role"),
+            "group_ids": user.get("group_ids").cloned().unwrap_or(Bson::Array(Vec::new())),
+            "is_blocked": true,
+            "createdAt": keep("createdAt"),
+            "updatedAt": now,
+            "purgedAt": now,
+        };
+        self.rekey(&users, old_id, &tombstone).await
+    }
+
+    async fn rekey(
+        &self,
+        collection: &Collection<Document>,
+        old_id: Bson,
+        replacement: &Document,
+    ) -> Result<()> {
+        let new_id = replacement.get("_id").cloned().unwrap_or(Bson::Null);
+        collection
+            .replace_one(doc! { "_id": new_id }, replacement)
+            .upsert(true)
+            .await
+            .with_context(|| format!("Failed to write {}", collection.name()))?;
+        collection
+            .delete_one(doc! { "_id": old_id })
+            .await
+            .with_context(|| format!("Failed to delete from {}", collection.name()))?;
+        Ok(())
+    }
+}
+
+fn is_duplicate_key(err: &mongodb::error::Error) -> bool {
+    matches!(
+        err.kind.as_ref(),
+        ErrorKind::Write(WriteFailure::WriteError(write)) if write.code == 11000
+    )
+}
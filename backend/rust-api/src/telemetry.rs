@@ -0,0 +1,619 @@
+//! Выборка трейсов для OTLP-экспорта.
+//!
+//! Все спаны записываются (sampler AlwaysOn), а решение об экспорте принимается
+//! локально, когда закрывается корневой спан запроса: трейсы с ошибкой и медленные
+//! экспортируются всегда, health-check и scrape метрик - никогда, остальные - по доле
+//! `ratio`, детерминированно по trace id. Экспорт идёт в отдельном потоке через
+//! ограниченную очередь, поэтому мёртвый коллектор не тормозит обработку запросов.
+
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use opentelemetry::trace::{SpanId, Status, TraceId};
+use opentelemetry::Context;
+use opentelemetry_sdk::error::{OTelSdkError, OTelSdkResult};
+use opentelemetry_sdk::trace::{Span, SpanData, SpanExporter, SpanProcessor};
+use opentelemetry_sdk::Resource;
+use serde::{Deserialize, Serialize};
+
+use crate::config::TracingSettings;
+use crate::metrics::{TRACES_SAMPLED_TOTAL, TRACE_SPANS_DROPPED_TOTAL};
+
+/// Сколько незавершённых трейсов держим в памяти одновременно
+const MAX_PENDING_TRACES: usize = 4096;
+/// Лимит спанов в одном буферизуемом трейсе
+const MAX_SPANS_PER_TRACE: usize = 512;
+/// Буфер трейса, корень которого так и не закрылся, выбрасывается через это время
+const PENDING_TRACE_MAX_AGE: Duration = Duration::from_secs(120);
+/// Сколько спанов экспортер отправляет за один вызов
+const EXPORT_BATCH_SIZE: usize = 512;
+
+/// Правила выборки, которые можно менять без перезапуска
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SamplingRules {
+    pub ratio: f64,
+    pub slow_trace_ms: u64,
+    pub always_sample_errors: bool,
+}
+
+impl From<&TracingSettings> for SamplingRules {
+    fn from(settings: &TracingSettings) -> Self {
+        Self {
+            ratio: settings.sample_ratio,
+            slow_trace_ms: settings.slow_trace_ms,
+            always_sample_errors: settings.always_sample_errors,
+        }
+    }
+}
+
+impl Default for SamplingRules {
+    fn default() -> Self {
+        Self::from(&TracingSettings::default())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SamplingControl(Arc<RwLock<SamplingRules>>);
+
+impl SamplingControl {
+    pub fn new(rules: SamplingRules) -> Self {
+        Self(Arc::new(RwLock::new(rules)))
+    }
+
+    pub fn get(&self) -> SamplingRules {
+        *self
+            .0
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn set(&self, rules: SamplingRules) {
+        *self
+            .0
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = rules;
+    }
+}
+
+/// Общие для процесса правила выборки (их же меняет админский эндпоинт)
+pub fn sampling_control() -> &'static SamplingControl {
+    static CONTROL: OnceLock<SamplingControl> = OnceLock::new();
+    CONTROL.get_or_init(SamplingControl::default)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SamplingDecision {
+    Ignored,
+    Error,
+    Slow,
+    Ratio,
+    Dropped,
+}
+
+impl SamplingDecision {
+    pub fn exports(self) -> bool {
+        matches!(self, Self::Error | Self::Slow | Self::Ratio)
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Ignored => "ignored",
+            Self::Error => "error",
+            Self::Slow => "slow",
+            Self::Ratio => "ratio",
+            Self::Dropped => "dropped",
+        }
+    }
+}
+
+/// Решение по завершённому трейсу
+pub fn decide(
+    rules: &SamplingRules,
+    ignored: bool,
+    has_error: bool,
+    duration: Duration,
+    trace_id: TraceId,
+) -> SamplingDecision {
+    if ignored {
+        SamplingDecision::Ignored
+    } else if has_error && rules.always_sample_errors {
+        SamplingDecision::Error
+    } else if duration >= Duration::from_millis(rules.slow_trace_ms) {
+        SamplingDecision::Slow
+    } else if ratio_admits(rules.ratio, trace_id) {
+        SamplingDecision::Ratio
+    } else {
+        SamplingDecision::Dropped
+    }
+}
+
+/// Та же схема, что у TraceIdRatioBased: младшие 8 байт trace id против порога
+fn ratio_admits(ratio: f64, trace_id: TraceId) -> bool {
+    if ratio >= 1.0 {
+        return true;
+    }
+    if ratio <= 0.0 || ratio.is_nan() {
+        return false;
+    }
+    let bytes = trace_id.to_bytes();
+    let mut low = [0u8; 8];
+    low.copy_from_slice(&bytes[8..]);
+    let value = u64::from_be_bytes(low) >> 1;
+    let threshold = (ratio * (1u64 << 63) as f64) as u64;
+    value < threshold
+}
+
+fn is_root(span: &SpanData) -> bool {
+    span.parent_span_id == SpanId::INVALID || span.parent_span_is_remote
+}
+
+fn has_error_status(span: &SpanData) -> bool {
+    matches!(span.status, Status::Error { .. })
+}
+
+fn span_path(span: &SpanData) -> Option<String> {
+    span.attributes
+        .iter()
+        .find(|kv| matches!(kv.key.as_str(), "path" | "url.path" | "http.target"))
+        .map(|kv| kv.value.as_str().into_owned())
+}
+
+#[derive(Debug)]
+struct PendingTrace {
+    spans: Vec<SpanData>,
+    has_error: bool,
+    first_seen: Instant,
+}
+
+#[derive(Debug)]
+enum ExportMessage {
+    Spans(Vec<SpanData>),
+    Resource(Box<Resource>),
+    Flush(SyncSender<()>),
+    Shutdown(SyncSender<()>),
+}
+
+/// SpanProcessor с локальной (tail) выборкой и неблокирующим экспортом
+#[derive(Debug)]
+pub struct TailSamplingProcessor {
+    control: SamplingControl,
+    ignored_paths: Vec<String>,
+    pending: Mutex<HashMap<TraceId, PendingTrace>>,
+    sender: SyncSender<ExportMessage>,
+    worker: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl TailSamplingProcessor {
+    pub fn new<E>(exporter: E, settings: &TracingSettings, control: SamplingControl) -> Self
+    where
+        E: SpanExporter + 'static,
+    {
+        let (sender, receiver) = mpsc::sync_channel(settings.export_queue_size.max(1));
+        let log_interval = Duration::from_secs(settings.export_error_log_interval_secs);
+        let worker = std::thread::Builder::new()
+            .name("otel-tail-exporter".to_string())
+            .spawn(move || run_exporter(exporter, receiver, log_interval))
+            .expect("Failed to spawn trace export thread");
+
+        Self {
+            control,
+            ignored_paths: settings.ignored_paths.clone(),
+            pending: Mutex::new(HashMap::new()),
+            sender,
+            worker: Mutex::new(Some(worker)),
+        }
+    }
+
+    fn is_ignored(&self, root: &SpanData) -> bool {
+        span_path(root).is_some_and(|path| self.ignored_paths.contains(&path))
+    }
+
+    fn buffer(&self, span: SpanData) {
+        let mut pending = self.pending.lock().unwrap_or_else(|p| p.into_inner());
+        let trace_id = span.span_context.trace_id();
+
+        if !pending.contains_key(&trace_id) && pending.len() >= MAX_PENDING_TRACES {
+            TRACE_SPANS_DROPPED_TOTAL
+                .with_label_values(&["buffer_full"])
+                .inc();
+            return;
+        }
+
+        let entry = pending.entry(trace_id).or_insert_with(|| PendingTrace {
+            spans: Vec::new(),
+            has_error: false,
+            first_seen: Instant::now(),
+        });
+        entry.has_error |= has_error_status(&span);
+        if entry.spans.len() < MAX_SPANS_PER_TRACE {
+            entry.spans.push(span);
+        } else {
+            TRACE_SPANS_DROPPED_TOTAL
+                .with_label_values(&["buffer_full"])
+                .inc();
+        }
+    }
+
+    fn complete(&self, root: SpanData) {
+        let trace_id = root.span_context.trace_id();
+        let buffered = {
+            let mut pending = self.pending.lock().unwrap_or_else(|p| p.into_inner());
+            let buffered = pending.remove(&trace_id);
+            pending.retain(|_, trace| trace.first_seen.elapsed() < PENDING_TRACE_MAX_AGE);
+            buffered
+        };
+
+        let (mut spans, children_error) = buffered
+            .map(|trace| (trace.spans, trace.has_error))
+            .unwrap_or_default();
+        let duration = root
+            .end_time
+            .duration_since(root.start_time)
+            .unwrap_or_default();
+        let decision = decide(
+            &self.control.get(),
+            self.is_ignored(&root),
+            children_error || has_error_status(&root),
+            duration,
+            trace_id,
+        );
+        TRACES_SAMPLED_TOTAL
+            .with_label_values(&[decision.label()])
+            .inc();
+        if !decision.exports() {
+            return;
+        }
+
+        spans.push(root);
+        let count = spans.len() as u64;
+        match self.sender.try_send(ExportMessage::Spans(spans)) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => TRACE_SPANS_DROPPED_TOTAL
+                .with_label_values(&["queue_full"])
+                .inc_by(count),
+            Err(TrySendError::Disconnected(_)) => TRACE_SPANS_DROPPED_TOTAL
+                .with_label_values(&["shutdown"])
+                .inc_by(count),
+        }
+    }
+
+    fn send_and_wait(
+        &self,
+        message: impl FnOnce(SyncSender<()>) -> ExportMessage,
+        timeout: Duration,
+    ) -> OTelSdkResult {
+        let (ack_tx, ack_rx) = mpsc::sync_channel(1);
+        self.sender
+            .send(message(ack_tx))
+            .map_err(|_| OTelSdkError::AlreadyShutdown)?;
+        ack_rx
+            .recv_timeout(timeout)
+            .map_err(|_| OTelSdkError::Timeout(timeout))
+    }
+}
+
+impl SpanProcessor for TailSamplingProcessor {
+    fn on_start(&self, _span: &mut Span, _cx: &Context) {}
+
+    fn on_end(&self, span: SpanData) {
+        if is_root(&span) {
+            self.complete(span);
+        } else {
+            self.buffer(span);
+        }
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        self.send_and_wait(ExportMessage::Flush, Duration::from_secs(5))
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        let worker = self.worker.lock().unwrap_or_else(|p| p.into_inner()).take();
+        let Some(worker) = worker else {
+            return Err(OTelSdkError::AlreadyShutdown);
+        };
+        let result = self.send_and_wait(ExportMessage::Shutdown, timeout);
+        if result.is_ok() {
+            let _ = worker.join();
+        }
+        result
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        let _ = self
+            .sender
+            .send(ExportMessage::Resource(Box::new(resource.clone())));
+    }
+}
+
+/// Ошибки экспорта пишутся в лог не чаще раза за интервал, с числом пропущенных
+struct ExportErrorLog {
+    interval: Duration,
+    last_logged: Option<Instant>,
+    suppressed: u64,
+}
+
+impl ExportErrorLog {
+    fn record(&mut self, error: &OTelSdkError, dropped: usize) {
+        let due = self
+            .last_logged
+            .is_none_or(|at| at.elapsed() >= self.interval);
+        if due {
+            tracing::warn!(
+                error = %error,
+                dropped_spans = dropped,
+                suppressed_failures = self.suppressed,
+                "Trace export failed"
+            );
+            self.last_logged = Some(Instant::now());
+            self.suppressed = 0;
+        } else {
+            self.suppressed += 1;
+        }
+    }
+}
+
+fn run_exporter<E: SpanExporter>(
+    mut exporter: E,
+    receiver: Receiver<ExportMessage>,
+    log_interval: Duration,
+) {
+    let mut errors = ExportErrorLog {
+        interval: log_interval,
+        last_logged: None,
+        suppressed: 0,
+    };
+    let mut batch: Vec<SpanData> = Vec::new();
+
+    while let Ok(message) = receiver.recv() {
+        let mut pending = Some(message);
+        // Добираем всё, что уже лежит в очереди, в один вызов экспортера
+        while let Some(message) = pending.take() {
+            match message {
+                ExportMessage::Spans(spans) => {
+                    batch.extend(spans);
+                    if batch.len() >= EXPORT_BATCH_SIZE {
+                        export_batch(&exporter, &mut batch, &mut errors);
+                    }
+                    pending = receiver.try_recv().ok();
+                }
+                ExportMessage::Resource(resource) => {
+                    export_batch(&exporter, &mut batch, &mut errors);
+                    exporter.set_resource(&resource);
+                }
+                ExportMessage::Flush(ack) => {
+                    export_batch(&exporter, &mut batch, &mut errors);
+                    let _ = ack.send(());
+                }
+                ExportMessage::Shutdown(ack) => {
+                    export_batch(&exporter, &mut batch, &mut errors);
+                    let _ = ack.send(());
+                    return;
+                }
+            }
+        }
+        export_batch(&exporter, &mut batch, &mut errors);
+    }
+}
+
+fn export_batch<E: SpanExporter>(
+    exporter: &E,
+    batch: &mut Vec<SpanData>,
+    errors: &mut ExportErrorLog,
+) {
+    if batch.is_empty() {
+        return;
+    }
+    let spans = std::mem::take(batch);
+    let count = spans.len();
+    if let Err(error) = futures::executor::block_on(exporter.export(spans)) {
+        TRACE_SPANS_DROPPED_TOTAL
+            .with_label_values(&["export_failed"])
+            .inc_by(count as u64);
+        errors.record(&error, count);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+    use tracing::field;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[derive(Debug, Clone, Default)]
+    struct CapturingExporter {
+        spans: Arc<Mutex<Vec<SpanData>>>,
+        delay: Duration,
+        fail: bool,
+    }
+
+    impl CapturingExporter {
+        fn exported_paths(&self) -> Vec<String> {
+            self.spans
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|span| is_root(span))
+                .filter_map(span_path)
+                .collect()
+        }
+    }
+
+    impl SpanExporter for CapturingExporter {
+        async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+            std::thread::sleep(self.delay);
+            if self.fail {
+                return Err(OTelSdkError::InternalFailure(
+                    "collector unreachable".to_string(),
+                ));
+            }
+            self.spans.lock().unwrap().extend(batch);
+            Ok(())
+        }
+    }
+
+    fn strict_rules() -> SamplingRules {
+        SamplingRules {
+            ratio: 0.0,
+            slow_trace_ms: 60_000,
+            always_sample_errors: true,
+        }
+    }
+
+    /// Провайдер с tail-выборкой и tracing-подписчиком поверх него
+    fn harness(exporter: CapturingExporter, rules: SamplingRules) -> SdkTracerProvider {
+        let processor = TailSamplingProcessor::new(
+            exporter,
+            &TracingSettings::default(),
+            SamplingControl::new(rules),
+        );
+        SdkTracerProvider::builder()
+            .with_sampler(Sampler::AlwaysOn)
+            .with_span_processor(processor)
+            .build()
+    }
+
+    fn handle_request(provider: &SdkTracerProvider, path: &str, fail: bool) {
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!(
+                "http_request",
+                path = %path,
+                otel.status_code = field::Empty
+            );
+            let _guard = span.enter();
+            tracing::info_span!("mongo_query").in_scope(|| {});
+            if fail {
+                span.record("otel.status_code", "ERROR");
+            }
+        });
+    }
+
+    #[test]
+    fn failing_request_is_exported_with_zero_ratio() {
+        let exporter = CapturingExporter::default();
+        let provider = harness(exporter.clone(), strict_rules());
+
+        handle_request(&provider, "/api/v1/sessions/abc", true);
+        provider.force_flush().unwrap();
+
+        assert_eq!(exporter.exported_paths(), vec!["/api/v1/sessions/abc"]);
+        // Вместе с корнем уходят и дочерние спаны
+        assert_eq!(exporter.spans.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn fast_healthy_request_is_not_exported() {
+        let exporter = CapturingExporter::default();
+        let provider = harness(exporter.clone(), strict_rules());
+
+        handle_request(&provider, "/api/v1/student/courses", false);
+        provider.force_flush().unwrap();
+
+        assert!(exporter.spans.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn health_checks_are_never_exported() {
+        let exporter = CapturingExporter::default();
+        let rules = SamplingRules {
+            ratio: 1.0,
+            slow_trace_ms: 0,
+            always_sample_errors: true,
+        };
+        let provider = harness(exporter.clone(), rules);
+
+        handle_request(&provider, "/health", true);
+        handle_request(&provider, "/metrics", false);
+        provider.force_flush().unwrap();
+
+        assert!(exporter.spans.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn dead_collector_does_not_slow_requests() {
+        let exporter = CapturingExporter {
+            delay: Duration::from_millis(500),
+            fail: true,
+            ..CapturingExporter::default()
+        };
+        let rules = SamplingRules {
+            ratio: 1.0,
+            ..strict_rules()
+        };
+        let provider = harness(exporter, rules);
+
+        let started = Instant::now();
+        for _ in 0..50 {
+            handle_request(&provider, "/api/v1/student/courses", true);
+        }
+        assert!(
+            started.elapsed() < Duration::from_millis(250),
+            "request handling blocked on exporter: {:?}",
+            started.elapsed()
+        );
+    }
+
+    #[test]
+    fn decision_rules() {
+        let rules = strict_rules();
+        let trace_id = TraceId::from_bytes([7; 16]);
+        let fast = Duration::from_millis(5);
+
+        assert_eq!(
+            decide(&rules, true, true, fast, trace_id),
+            SamplingDecision::Ignored
+        );
+        assert_eq!(
+            decide(&rules, false, true, fast, trace_id),
+            SamplingDecision::Error
+        );
+        assert_eq!(
+            decide(&rules, false, false, Duration::from_secs(61), trace_id),
+            SamplingDecision::Slow
+        );
+        assert_eq!(
+            decide(&rules, false, false, fast, trace_id),
+            SamplingDecision::Dropped
+        );
+
+        let all = SamplingRules {
+            ratio: 1.0,
+            ..rules
+        };
+        assert_eq!(
+            decide(&all, false, false, fast, trace_id),
+            SamplingDecision::Ratio
+        );
+    }
+
+    #[test]
+    fn ratio_is_roughly_respected() {
+        let admitted = (0u64..10_000)
+            .filter(|i| {
+                let mut bytes = [0u8; 16];
+                bytes[8..].copy_from_slice(&i.wrapping_mul(0x9E37_79B9_7F4A_7C15).to_be_bytes());
+                ratio_admits(0.25, TraceId::from_bytes(bytes))
+            })
+            .count();
+        assert!((2_000..3_000).contains(&admitted), "admitted {}", admitted);
+    }
+
+    #[test]
+    fn runtime_rules_take_effect() {
+        let control = SamplingControl::new(strict_rules());
+        let updated = SamplingRules {
+            ratio: 0.5,
+            slow_trace_ms: 200,
+            always_sample_errors: false,
+        };
+        control.clone().set(updated);
+        assert_eq!(control.get(), updated);
+    }
+}
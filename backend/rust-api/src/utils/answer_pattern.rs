@@ -0,0 +1,148 @@
+//! Regex-шаблоны допустимых ответов задания.
+//!
+//! Крейт `regex` не использует backtracking, поэтому проверка ответа линейна по его
+//! длине. Опасны шаблоны, которые компилируются в огромный автомат (`(a{1000}){1000}`)
+//! или вложены слишком глубоко: их отсекают лимиты размера, вложенности и длины.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use lazy_static::lazy_static;
+use regex::{Regex, RegexBuilder};
+
+pub const MAX_ANSWER_PATTERN_LEN: usize = 512;
+const PATTERN_SIZE_LIMIT: usize = 256 * 1024;
+const PATTERN_DFA_SIZE_LIMIT: usize = 1024 * 1024;
+const PATTERN_NEST_LIMIT: u32 = 16;
+const PATTERN_CACHE_CAPACITY: usize = 1024;
+
+/// Шаблон ответа не компилируется или превышает лимиты (отдаётся клиенту как 400)
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid answer pattern: {reason}")]
+pub struct InvalidAnswerPatternError {
+    pub reason: String,
+}
+
+lazy_static! {
+    static ref PATTERN_CACHE: Mutex<HashMap<(String, bool), Arc<Regex>>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Скомпилированный шаблон, привязанный к началу и концу ответа. Результат кешируется:
+/// шаблон задания компилируется один раз, а не при каждом ответе
+pub fn compile_answer_pattern(
+    pattern: &str,
+    case_insensitive: bool,
+) -> Result<Arc<Regex>, InvalidAnswerPatternError> {
+    let key = (pattern.to_string(), case_insensitive);
+    if let Some(regex) = PATTERN_CACHE.lock().unwrap().get(&key) {
+        return Ok(regex.clone());
+    }
+
+    let regex = Arc::new(build(pattern, case_insensitive)?);
+    let mut cache = PATTERN_CACHE.lock().unwrap();
+    if cache.len() >= PATTERN_CACHE_CAPACITY {
+        cache.clear();
+    }
+    cache.insert(key, regex.clone());
+    Ok(regex)
+}
+
+/// Проверка шаблона при сохранении шаблона задания. Собирается без учёта регистра,
+/// как вне строгого режима: такой автомат больше, и лимиты проверяются по нему
+pub fn validate_answer_pattern(pattern: &str) -> Result<(), InvalidAnswerPatternError> {
+    build(pattern, true).map(|_| ())
+}
+
+fn build(pattern: &str, case_insensitive: bool) -> Result<Regex, InvalidAnswerPatternError> {
+    if pattern.trim().is_empty() {
+        return Err(InvalidAnswerPatternError {
+            reason: "pattern is empty".to_string(),
+        });
+    }
+    if pattern.len() > MAX_ANSWER_PATTERN_LEN {
+        return Err(InvalidAnswerPatternError {
+            reason: format!("pattern is longer than {} bytes", MAX_ANSWER_PATTERN_LEN),
+        });
+    }
+
+    // Шаблон разбирается сам по себе: иначе `a)|(b` после обёртки превратится
+    // в `^(?:a)|(b)$` и перестанет быть привязан к границам ответа
+    regex_syntax::ParserBuilder::new()
+        .case_insensitive(case_insensitive)
+        .nest_limit(PATTERN_NEST_LIMIT)
+        .build()
+        .parse(pattern)
+        .map_err(|error| InvalidAnswerPatternError {
+            reason: error.to_string(),
+        })?;
+
+    RegexBuilder::new(&format!("^(?:{})$", pattern))
+        .case_insensitive(case_insensitive)
+        .size_limit(PATTERN_SIZE_LIMIT)
+        .dfa_size_limit(PATTERN_DFA_SIZE_LIMIT)
+        .nest_limit(PATTERN_NEST_LIMIT)
+        .build()
+        .map_err(|error| InvalidAnswerPatternError {
+            reason: error.to_string(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pattern_is_anchored_and_cached() {
+        let regex = compile_answer_pattern(r"не\s?знаю\.?", false).unwrap();
+        assert!(regex.is_match("не знаю"));
+        assert!(regex.is_match("незнаю."));
+        assert!(!regex.is_match("я не знаю"));
+        assert!(!regex.is_match("не знаю!"));
+
+        let cached = compile_answer_pattern(r"не\s?знаю\.?", false).unwrap();
+        assert!(Arc::ptr_eq(&regex, &cached));
+
+        let insensitive = compile_answer_pattern("москва", true).unwrap();
+        assert!(insensitive.is_match("МОСКВА"));
+    }
+
+    #[test]
+    fn alternation_is_anchored_as_a_whole() {
+        let regex = compile_answer_pattern("да|нет", false).unwrap();
+        assert!(regex.is_match("да"));
+        assert!(regex.is_match("нет"));
+        assert!(!regex.is_match("данет"));
+        assert!(!regex.is_match("да, нет"));
+    }
+
+    #[test]
+    fn rejects_dangerous_and_invalid_patterns() {
+        for pattern in [
+            "(a{1000}){1000}",
+            r"\w{500}\w{500}\w{500}",
+            &format!("{}a{}", "(".repeat(20), ")".repeat(20)),
+            &"a".repeat(MAX_ANSWER_PATTERN_LEN + 1),
+            "(unclosed",
+            "",
+        ] {
+            assert!(
+                validate_answer_pattern(pattern).is_err(),
+                "pattern {pattern:?} should be rejected"
+            );
+        }
+
+        assert!(validate_answer_pattern("(a+)+b").is_ok());
+    }
+
+    #[test]
+    fn pattern_cannot_escape_the_anchors() {
+        for pattern in ["a)|(b", ".*)|(?:x", r"a\)|(b"] {
+            assert!(
+                validate_answer_pattern(pattern).is_err(),
+                "pattern {pattern:?} should be rejected"
+            );
+            assert!(compile_answer_pattern(pattern, false).is_err());
+        }
+    }
+}
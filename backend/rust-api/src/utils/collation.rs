@@ -0,0 +1,48 @@
+//! Сортировка строк по правилам русского алфавита
+
+/// Ключ сортировки имён: регистр не учитывается, `ё` сравнивается как `е`
+/// (в Unicode она стоит после `я`), а при прочих равных `е` идёт раньше `ё`
+pub fn russian_sort_key(value: &str) -> (Vec<char>, Vec<u32>, String) {
+    let lower: Vec<char> = value.chars().flat_map(char::to_lowercase).collect();
+    let primary = lower
+        .iter()
+        .map(|&c| if c == 'ё' { 'е' } else { c })
+        .collect();
+    let secondary = lower
+        .iter()
+        .map(|&c| match c {
+            'ё' => u32::from('е') * 2 + 1,
+            c => u32::from(c) * 2,
+        })
+        .collect();
+    (primary, secondary, value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn yo_sorts_with_ye_not_after_ya() {
+        let mut names = vec![
+            "Яковлева Ольга",
+            "Ёлкина Анна",
+            "Журавлёв Пётр",
+            "Елисеева Мария",
+            "елкина Анна",
+            "Елкина Анна",
+        ];
+        names.sort_by_key(|name| russian_sort_key(name));
+        assert_eq!(
+            names,
+            vec![
+                "Елисеева Мария",
+                "Елкина Анна",
+                "елкина Анна",
+                "Ёлкина Анна",
+                "Журавлёв Пётр",
+                "Яковлева Ольга",
+            ]
+        );
+    }
+}
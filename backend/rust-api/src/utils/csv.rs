@@ -0,0 +1,46 @@
+//! Экранирование полей CSV-выгрузок
+
+/// Escapes CSV field to prevent formula injection attacks.
+/// Prefixes dangerous characters (=, +, @, -, tab, newline) with a tab to neutralize them.
+/// Also wraps fields containing special characters in quotes.
+pub fn escape_csv_field(value: &str) -> String {
+    // Prevent formula injection by prefixing dangerous characters with tab
+    let sanitized = if value.starts_with(['=', '+', '@', '-', '\t', '\r', '\n']) {
+        format!("\t{}", value)
+    } else {
+        value.to_string()
+    };
+
+    // Escape quotes and wrap in quotes if contains special characters
+    if sanitized.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", sanitized.replace('"', "\"\""))
+    } else {
+        sanitized
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_escape_formula_injection() {
+        // Test formula injection prevention
+        assert_eq!(escape_csv_field("=1+1"), "\t=1+1");
+        assert_eq!(escape_csv_field("+cmd"), "\t+cmd");
+        assert_eq!(escape_csv_field("@SUM(A1)"), "\t@SUM(A1)");
+        assert_eq!(escape_csv_field("-2+3"), "\t-2+3");
+
+        // Test normal names
+        assert_eq!(escape_csv_field("Normal Name"), "Normal Name");
+        assert_eq!(escape_csv_field("John Doe"), "John Doe");
+        assert_eq!(escape_csv_field("Иван Иванов"), "Иван Иванов");
+
+        // Test special characters that need quoting
+        assert_eq!(escape_csv_field("Name, Jr."), "\"Name, Jr.\"");
+        assert_eq!(escape_csv_field("O\"Brien"), "\"O\"\"Brien\"");
+
+        // Test combination: formula + special chars
+        assert_eq!(escape_csv_field("=1+1, test"), "\"\t=1+1, test\"");
+    }
+}
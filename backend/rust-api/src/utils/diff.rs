@@ -0,0 +1,226 @@
+//! Построчный diff текста (LCS) и вывод в формате unified diff
+
+/// Выше этого числа ячеек таблицы LCS изменённая середина считается
+/// заменённой целиком, чтобы не тратить память на огромные тексты
+const MAX_LCS_CELLS: usize = 4_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffOp<'a> {
+    Equal(&'a str),
+    Delete(&'a str),
+    Insert(&'a str),
+}
+
+/// Построчный diff: последовательность операций, превращающая `old` в `new`
+pub fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<DiffOp<'a>> {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+
+    let prefix = old_lines
+        .iter()
+        .zip(&new_lines)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old_lines[prefix..]
+        .iter()
+        .rev()
+        .zip(new_lines[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let old_mid = &old_lines[prefix..old_lines.len() - suffix];
+    let new_mid = &new_lines[prefix..new_lines.len() - suffix];
+
+    let mut ops: Vec<DiffOp> = old_lines[..prefix]
+        .iter()
+        .map(|line| DiffOp::Equal(line))
+        .collect();
+    ops.extend(diff_middle(old_mid, new_mid));
+    ops.extend(
+        old_lines[old_lines.len() - suffix..]
+            .iter()
+            .map(|line| DiffOp::Equal(line)),
+    );
+    ops
+}
+
+fn diff_middle<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<DiffOp<'a>> {
+    let (n, m) = (old.len(), new.len());
+    if n == 0 || m == 0 || (n + 1) * (m + 1) > MAX_LCS_CELLS {
+        let mut ops: Vec<DiffOp> = old.iter().map(|line| DiffOp::Delete(line)).collect();
+        ops.extend(new.iter().map(|line| DiffOp::Insert(line)));
+        return ops;
+    }
+
+    // lcs[i][j] - длина LCS для old[i..] и new[j..]
+    let width = m + 1;
+    let mut lcs = vec![0u32; (n + 1) * width];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i * width + j] = if old[i] == new[j] {
+                lcs[(i + 1) * width + j + 1] + 1
+            } else {
+                lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut ops = Vec::with_capacity(n + m);
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if old[i] == new[j] {
+            ops.push(DiffOp::Equal(old[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[(i + 1) * width + j] >= lcs[i * width + j + 1] {
+            ops.push(DiffOp::Delete(old[i]));
+            i += 1;
+        } else {
+            ops.push(DiffOp::Insert(new[j]));
+            j += 1;
+        }
+    }
+    ops.extend(old[i..].iter().map(|line| DiffOp::Delete(line)));
+    ops.extend(new[j..].iter().map(|line| DiffOp::Insert(line)));
+    ops
+}
+
+/// Unified diff с `context` строками контекста; для одинаковых текстов - пустая строка
+pub fn unified_diff(
+    old: &str,
+    new: &str,
+    old_label: &str,
+    new_label: &str,
+    context: usize,
+) -> String {
+    let ops = diff_lines(old, new);
+    let changed: Vec<usize> = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, op)| !matches!(op, DiffOp::Equal(_)))
+        .map(|(index, _)| index)
+        .collect();
+    if changed.is_empty() {
+        return String::new();
+    }
+
+    // Диапазоны операций [start, end), соседние изменения склеиваются в один hunk
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &index in &changed {
+        let start = index.saturating_sub(context);
+        let end = (index + context + 1).min(ops.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    // Номера строк old/new перед каждой операцией
+    let mut positions = Vec::with_capacity(ops.len() + 1);
+    let (mut old_line, mut new_line) = (0usize, 0usize);
+    for op in &ops {
+        positions.push((old_line, new_line));
+        match op {
+            DiffOp::Equal(_) => {
+                old_line += 1;
+                new_line += 1;
+            }
+            DiffOp::Delete(_) => old_line += 1,
+            DiffOp::Insert(_) => new_line += 1,
+        }
+    }
+    positions.push((old_line, new_line));
+
+    let mut out = format!("--- {}\n+++ {}\n", old_label, new_label);
+    for (start, end) in ranges {
+        let (old_start, new_start) = positions[start];
+        let (old_end, new_end) = positions[end];
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(old_start, old_end - old_start),
+            hunk_range(new_start, new_end - new_start)
+        ));
+        for op in &ops[start..end] {
+            let (marker, line) = match op {
+                DiffOp::Equal(line) => (' ', line),
+                DiffOp::Delete(line) => ('-', line),
+                DiffOp::Insert(line) => ('+', line),
+            };
+            out.push(marker);
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn hunk_range(start: usize, len: usize) -> String {
+    // Пустой диапазон указывает на строку перед местом вставки/удаления
+    let first = if len == 0 { start } else { start + 1 };
+    format!("{},{}", first, len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_texts_have_no_diff() {
+        assert_eq!(unified_diff("a\nb", "a\nb", "v1", "v2", 3), "");
+        assert!(diff_lines("a\nb", "a\nb")
+            .iter()
+            .all(|op| matches!(op, DiffOp::Equal(_))));
+    }
+
+    #[test]
+    fn single_changed_line() {
+        let diff = unified_diff("a\nb\nc", "a\nB\nc", "v1", "v2", 3);
+        assert_eq!(diff, "--- v1\n+++ v2\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n");
+    }
+
+    #[test]
+    fn insertion_and_deletion() {
+        assert_eq!(
+            diff_lines("a\nc", "a\nb\nc"),
+            vec![DiffOp::Equal("a"), DiffOp::Insert("b"), DiffOp::Equal("c")]
+        );
+        assert_eq!(
+            unified_diff("", "new line", "v1", "v2", 3),
+            "--- v1\n+++ v2\n@@ -0,0 +1,1 @@\n+new line\n"
+        );
+        assert_eq!(
+            unified_diff("x\ny", "x", "v1", "v2", 0),
+            "--- v1\n+++ v2\n@@ -2,1 +1,0 @@\n-y\n"
+        );
+    }
+
+    #[test]
+    fn distant_changes_produce_separate_hunks() {
+        let old: Vec<String> = (1..=20).map(|n| format!("line {}", n)).collect();
+        let mut new = old.clone();
+        new[1] = "changed 2".to_string();
+        new[17] = "changed 18".to_string();
+
+        let diff = unified_diff(&old.join("\n"), &new.join("\n"), "v1", "v2", 2);
+        assert_eq!(diff.matches("@@ -").count(), 2);
+        assert!(diff.contains("@@ -1,4 +1,4 @@\n line 1\n-line 2\n+changed 2\n"));
+        assert!(diff.contains("@@ -16,5 +16,5 @@\n"));
+
+        // С большим контекстом изменения попадают в один hunk
+        let diff = unified_diff(&old.join("\n"), &new.join("\n"), "v1", "v2", 10);
+        assert_eq!(diff.matches("@@ -").count(), 1);
+    }
+
+    #[test]
+    fn lcs_keeps_moved_common_lines() {
+        let ops = diff_lines("a\nb\nc\nd", "b\nc\nx\nd");
+        let kept: Vec<&str> = ops
+            .iter()
+            .filter_map(|op| match op {
+                DiffOp::Equal(line) => Some(*line),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(kept, vec!["b", "c", "d"]);
+    }
+}
@@ -1,2 +1,10 @@
+pub mod answer_pattern;
+pub mod collation;
+pub mod csv;
+pub mod diff;
+pub mod mongo_retry;
+pub mod pagination;
 pub mod retry;
+pub mod secure_compare;
 pub mod time;
+pub mod user_agent;
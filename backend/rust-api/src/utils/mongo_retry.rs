@@ -0,0 +1,387 @@
+use lazy_static::lazy_static;
+use mongodb::error::{Error as MongoError, ErrorKind};
+use std::future::IntoFuture;
+use std::io::ErrorKind as IoErrorKind;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::Duration;
+
+use crate::metrics::{
+    MONGO_DEGRADED, MONGO_RETRIES_EXHAUSTED_TOTAL, MONGO_RETRIES_TOTAL,
+    MONGO_TRANSIENT_ERRORS_TOTAL,
+};
+
+/// Коды ошибок сервера, возникающие при выборах primary и плановом обслуживании кластера.
+const TRANSIENT_SERVER_CODES: &[i32] = &[
+    6,     // HostUnreachable
+    7,     // HostNotFound
+    89,    // NetworkTimeout
+    91,    // ShutdownInProgress
+    189,   // PrimarySteppedDown
+    9001,  // SocketException
+    10107, // NotWritablePrimary
+    11600, // InterruptedAtShutdown
+    11602, // InterruptedDueToReplStateChange
+    13435, // NotPrimaryNoSecondaryOk
+    13436, // NotPrimaryOrSecondary
+];
+
+/// Number of consecutive exhausted retries after which MongoDB is reported as degraded.
+const DEGRADED_THRESHOLD: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MongoErrorClass {
+    /// Temporary condition (election, network blip) - a single retry may succeed.
+    Transient,
+    /// Everything else: validation, duplicate keys, auth - retrying will not help.
+    Permanent,
+}
+
+/// Classifies a MongoDB error so that callers can decide whether a retry makes sense.
+pub fn classify(err: &MongoError) -> MongoErrorClass {
+    if err.contains_label("RetryableWriteError") || err.contains_label("TransientTransactionError")
+    {
+        return MongoErrorClass::Transient;
+    }
+
+    match err.kind.as_ref() {
+        ErrorKind::Command(command) if TRANSIENT_SERVER_CODES.contains(&command.code) => {
+            MongoErrorClass::Transient
+        }
+        ErrorKind::Io(io) => match io.kind() {
+            IoErrorKind::TimedOut
+            | IoErrorKind::ConnectionReset
+            | IoErrorKind::ConnectionAborted
+            | IoErrorKind::ConnectionRefused
+            | IoErrorKind::BrokenPipe
+            | IoErrorKind::UnexpectedEof => MongoErrorClass::Transient,
+            _ => MongoErrorClass::Permanent,
+        },
+        ErrorKind::ServerSelection { .. } | ErrorKind::ConnectionPoolCleared { .. } => {
+            MongoErrorClass::Transient
+        }
+        _ => MongoErrorClass::Permanent,
+    }
+}
+
+/// Tracks consecutive retry exhaustions and exposes the degraded indicator.
+pub struct MongoRetryPolicy {
+    backoff: Duration,
+    consecutive_exhaustions: AtomicU32,
+    degraded: AtomicBool,
+}
+
+lazy_static! {
+    static ref DEFAULT_POLICY: MongoRetryPolicy = MongoRetryPolicy::new(Duration::from_millis(100));
+}
+
+impl MongoRetryPolicy {
+    pub const fn new(backoff: Duration) -> Self {
+        Self {
+            backoff,
+            consecutive_exhaustions: AtomicU32::new(0),
+            degraded: AtomicBool::new(false),
+        }
+    }
+
+    pub fn is_degraded(&self) -> bool {
+        self.degraded.load(Ordering::Relaxed)
+    }
+
+    /// Idempotent read: one retry with backoff on transient errors.
+    pub async fn read<F, Fut, T>(&self, operation: &str, f: F) -> Result<T, MongoError>
+    where
+        F: FnMut() -> Fut,
+        Fut: IntoFuture<Output = Result<T, MongoError>>,
+    {
+        self.run(operation, f).await
+    }
+
+    /// Write that is safe to repeat (upsert keyed by a unique filter, `$set` by `_id`).
+    /// Inserts and `$inc` updates must go through `write_once` instead.
+    pub async fn idempotent_write<F, Fut, T>(&self, operation: &str, f: F) -> Result<T, MongoError>
+    where
+        F: FnMut() -> Fut,
+        Fut: IntoFuture<Output = Result<T, MongoError>>,
+    {
+        self.run(operation, f).await
+    }
+
+    /// Non-idempotent write (insert, `$inc`): exactly one attempt. Transient errors are
+    /// counted but never retried - a repeat could apply the write twice.
+    pub async fn write_once<F, Fut, T>(&self, operation: &str, f: F) -> Result<T, MongoError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: IntoFuture<Output = Result<T, MongoError>>,
+    {
+        let result = f().await;
+        match &result {
+            Ok(_) => self.record_success(),
+            Err(err) if classify(err) == MongoErrorClass::Transient => {
+                MONGO_TRANSIENT_ERRORS_TOTAL
+                    .with_label_values(&[operation])
+                    .inc();
+            }
+            Err(_) => {}
+        }
+        result
+    }
+
+    async fn run<F, Fut, T>(&self, operation: &str, mut f: F) -> Result<T, MongoError>
+    where
+        F: FnMut() -> Fut,
+        Fut: IntoFuture<Output = Result<T, MongoError>>,
+    {
+        let err = match f().await {
+            Ok(value) => {
+                self.record_success();
+                return Ok(value);
+            }
+            Err(err) => err,
+        };
+
+        if classify(&err) == MongoErrorClass::Permanent {
+            return Err(err);
+        }
+
+        MONGO_TRANSIENT_ERRORS_TOTAL
+            .with_label_values(&[operation])
+            .inc();
+
+        // Во время длительных выборов не усиливаем нагрузку повторными запросами.
+        if self.is_degraded() {
+            return Err(err);
+        }
+
+        MONGO_RETRIES_TOTAL.with_label_values(&[operation]).inc();
+        tracing::warn!(operation, "Transient MongoDB error, retrying once: {}", err);
+        tokio::time::sleep(self.backoff).await;
+
+        match f().await {
+            Ok(value) => {
+                self.record_success();
+                Ok(value)
+            }
+            Err(retry_err) => {
+                if classify(&retry_err) == MongoErrorClass::Transient {
+                    MONGO_TRANSIENT_ERRORS_TOTAL
+                        .with_label_values(&[operation])
+                        .inc();
+                    MONGO_RETRIES_EXHAUSTED_TOTAL
+                        .with_label_values(&[operation])
+                        .inc();
+                    self.record_exhaustion();
+                }
+                Err(retry_err)
+            }
+        }
+    }
+
+    fn record_success(&self) {
+        self.consecutive_exhaustions.store(0, Ordering::Relaxed);
+        if self.degraded.swap(false, Ordering::Relaxed) {
+            MONGO_DEGRADED.set(0);
+            tracing::info!("MongoDB recovered from degraded state");
+        }
+    }
+
+    fn record_exhaustion(&self) {
+        let count = self.consecutive_exhaustions.fetch_add(1, Ordering::Relaxed) + 1;
+        if count >= DEGRADED_THRESHOLD && !self.degraded.swap(true, Ordering::Relaxed) {
+            MONGO_DEGRADED.set(1);
+            tracing::error!(
+                consecutive_exhaustions = count,
+                "MongoDB marked as degraded after repeated transient failures"
+            );
+        }
+    }
+}
+
+/// Retries an idempotent read once on transient errors using the process-wide policy.
+pub async fn retry_read<F, Fut, T>(operation: &str, f: F) -> Result<T, MongoError>
+where
+    F: FnMut() -> Fut,
+    Fut: IntoFuture<Output = Result<T, MongoError>>,
+{
+    DEFAULT_POLICY.read(operation, f).await
+}
+
+/// Retries a clearly idempotent write once on transient errors using the process-wide policy.
+pub async fn retry_idempotent_write<F, Fut, T>(operation: &str, f: F) -> Result<T, MongoError>
+where
+    F: FnMut() -> Fut,
+    Fut: IntoFuture<Output = Result<T, MongoError>>,
+{
+    DEFAULT_POLICY.idempotent_write(operation, f).await
+}
+
+/// Runs a non-idempotent write once, counting transient errors, using the process-wide policy.
+pub async fn write_once<F, Fut, T>(operation: &str, f: F) -> Result<T, MongoError>
+where
+    F: FnOnce() -> Fut,
+    Fut: IntoFuture<Output = Result<T, MongoError>>,
+{
+    DEFAULT_POLICY.write_once(operation, f).await
+}
+
+/// Degraded indicator consumed by the health endpoint.
+pub fn is_degraded() -> bool {
+    DEFAULT_POLICY.is_degraded()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::doc;
+    use mongodb::error::CommandError;
+    use std::sync::atomic::AtomicUsize;
+
+    fn not_primary_error() -> MongoError {
+        let command: CommandError = mongodb::bson::from_document(doc! {
+            "code": 10107,
+            "codeName": "NotWritablePrimary",
+            "errmsg": "not primary",
+        })
+        .unwrap();
+        MongoError::from(ErrorKind::Command(command))
+    }
+
+    fn duplicate_key_error() -> MongoError {
+        let command: CommandError = mongodb::bson::from_document(doc! {
+            "code": 11000,
+            "codeName": "DuplicateKey",
+            "errmsg": "E11000 duplicate key error",
+        })
+        .unwrap();
+        MongoError::from(ErrorKind::Command(command))
+    }
+
+    fn test_policy() -> MongoRetryPolicy {
+        MongoRetryPolicy::new(Duration::from_millis(1))
+    }
+
+    #[test]
+    fn classify_separates_transient_and_permanent() {
+        assert_eq!(classify(&not_primary_error()), MongoErrorClass::Transient);
+        assert_eq!(classify(&duplicate_key_error()), MongoErrorClass::Permanent);
+
+        let timeout = MongoError::from(ErrorKind::Io(std::sync::Arc::new(std::io::Error::from(
+            IoErrorKind::TimedOut,
+        ))));
+        assert_eq!(classify(&timeout), MongoErrorClass::Transient);
+    }
+
+    #[tokio::test]
+    async fn read_retries_exactly_once_on_not_primary() {
+        let policy = test_policy();
+        let calls = AtomicUsize::new(0);
+        let retried_before = MONGO_RETRIES_TOTAL
+            .with_label_values(&["test.read_once"])
+            .get();
+
+        let result = policy
+            .read("test.read_once", || async {
+                if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                    Err(not_primary_error())
+                } else {
+                    Ok(42)
+                }
+            })
+            .await;
+
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(
+            MONGO_RETRIES_TOTAL
+                .with_label_values(&["test.read_once"])
+                .get(),
+            retried_before + 1
+        );
+        assert!(!policy.is_degraded());
+    }
+
+    #[tokio::test]
+    async fn non_idempotent_insert_is_not_retried() {
+        let policy = test_policy();
+        let calls = AtomicUsize::new(0);
+        let transient_before = MONGO_TRANSIENT_ERRORS_TOTAL
+            .with_label_values(&["test.insert"])
+            .get();
+        let retried_before = MONGO_RETRIES_TOTAL
+            .with_label_values(&["test.insert"])
+            .get();
+
+        let result = policy
+            .write_once("test.insert", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err::<(), _>(not_primary_error())
+            })
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        // Ошибка учтена как временная, но повтора не было
+        assert_eq!(
+            MONGO_TRANSIENT_ERRORS_TOTAL
+                .with_label_values(&["test.insert"])
+                .get(),
+            transient_before + 1
+        );
+        assert_eq!(
+            MONGO_RETRIES_TOTAL
+                .with_label_values(&["test.insert"])
+                .get(),
+            retried_before
+        );
+
+        // Постоянные ошибки не повторяются даже внутри обёртки для идемпотентных записей.
+        let calls = AtomicUsize::new(0);
+        let result = policy
+            .idempotent_write("test.permanent", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err::<(), _>(duplicate_key_error())
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn degraded_flips_after_consecutive_exhaustions() {
+        let policy = test_policy();
+        let exhausted_before = MONGO_RETRIES_EXHAUSTED_TOTAL
+            .with_label_values(&["test.degraded"])
+            .get();
+
+        for _ in 0..DEGRADED_THRESHOLD {
+            let result = policy
+                .read("test.degraded", || async {
+                    Err::<(), _>(not_primary_error())
+                })
+                .await;
+            assert!(result.is_err());
+        }
+
+        assert!(policy.is_degraded());
+        assert_eq!(
+            MONGO_RETRIES_EXHAUSTED_TOTAL
+                .with_label_values(&["test.degraded"])
+                .get(),
+            exhausted_before + DEGRADED_THRESHOLD as u64
+        );
+
+        // В деградированном состоянии повторов нет.
+        let calls = AtomicUsize::new(0);
+        let _ = policy
+            .read("test.degraded", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err::<(), _>(not_primary_error())
+            })
+            .await;
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Успешная операция снимает флаг.
+        let ok = policy.read("test.degraded", || async { Ok(1) }).await;
+        assert!(ok.is_ok());
+        assert!(!policy.is_degraded());
+    }
+}
@@ -0,0 +1,236 @@
+//! Keyset-пагинация больших списков: `cursor=` вместо `offset=`.
+//!
+//! Курсор - base64url от BSON с ключом сортировки и `_id` последней записи страницы.
+//! Следующая страница начинается строго после этой пары, поэтому MongoDB не
+//! просматривает пропущенные документы, а вставки во время обхода не сдвигают страницы.
+
+use axum::{
+    http::HeaderValue,
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use mongodb::bson::{self, doc, oid::ObjectId, Bson, Document};
+use serde::{Deserialize, Serialize};
+
+use crate::handlers::error::ErrorResponse;
+
+/// Заголовок ответа с курсором следующей страницы (нет заголовка - страница последняя)
+pub const NEXT_CURSOR_HEADER: &str = "x-next-cursor";
+
+/// Порядок списка: поле сортировки и направление; `_id` в том же направлении
+/// добавляется вторым ключом, чтобы порядок был строгим
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortKey {
+    /// Значение параметра `sort`, сохраняется в курсоре
+    pub name: &'static str,
+    pub field: &'static str,
+    pub direction: i32,
+}
+
+impl SortKey {
+    pub const fn new(name: &'static str, field: &'static str, direction: i32) -> Self {
+        Self {
+            name,
+            field,
+            direction,
+        }
+    }
+
+    pub fn sort_document(&self) -> Document {
+        if self.field == "_id" {
+            doc! { "_id": self.direction }
+        } else {
+            doc! { self.field: self.direction, "_id": self.direction }
+        }
+    }
+
+    /// Условие «после курсора» в порядке сортировки
+    pub fn after(&self, cursor: &PageCursor) -> Document {
+        let op = if self.direction < 0 { "$lt" } else { "$gt" };
+        if self.field == "_id" {
+            return doc! { "_id": { op: cursor.id } };
+        }
+        doc! {
+            "$or": [
+                { self.field: { op: cursor.key.clone() } },
+                { self.field: cursor.key.clone(), "_id": { op: cursor.id } },
+            ]
+        }
+    }
+
+    /// Добавить к фильтру условие курсора
+    pub fn apply(&self, filter: Document, cursor: Option<&PageCursor>) -> Document {
+        match cursor {
+            Some(cursor) if filter.is_empty() => self.after(cursor),
+            Some(cursor) => doc! { "$and": [filter, self.after(cursor)] },
+            None => filter,
+        }
+    }
+
+    /// Обрезать выборку `limit + 1` до страницы; лишняя запись означает, что есть
+    /// следующая страница, и курсор строится по последней записи страницы
+    pub fn split_page(
+        &self,
+        mut documents: Vec<Document>,
+        limit: usize,
+    ) -> (Vec<Document>, Option<String>) {
+        if documents.len() <= limit {
+            return (documents, None);
+        }
+        documents.truncate(limit);
+        let next = documents.last().and_then(|last| {
+            let id = last.get_object_id("_id").ok()?;
+            let key = last.get(self.field).cloned().unwrap_or(Bson::Null);
+            Some(
+                PageCursor {
+                    sort: self.name.to_string(),
+                    key,
+                    id,
+                }
+                .encode(),
+            )
+        });
+        (documents, next)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageCursor {
+    #[serde(rename = "s")]
+    pub sort: String,
+    #[serde(rename = "k")]
+    pub key: Bson,
+    #[serde(rename = "i")]
+    pub id: ObjectId,
+}
+
+impl PageCursor {
+    pub fn encode(&self) -> String {
+        // Сериализация структуры из строки, BSON-значения и ObjectId не падает
+        URL_SAFE_NO_PAD.encode(bson::to_vec(self).unwrap_or_default())
+    }
+
+    /// Разобрать курсор для порядка `sort`; курсор от другого порядка тоже ошибка
+    pub fn decode(raw: &str, sort: &SortKey) -> Result<Self, InvalidCursor> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(raw.trim())
+            .map_err(|_| InvalidCursor("cursor is not valid base64"))?;
+        let cursor: PageCursor =
+            bson::from_slice(&bytes).map_err(|_| InvalidCursor("cursor is malformed"))?;
+        if cursor.sort != sort.name {
+            return Err(InvalidCursor(
+                "cursor was issued for a different sort order",
+            ));
+        }
+        Ok(cursor)
+    }
+}
+
+/// Страница списка и курсор следующей
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Invalid cursor: {0}")]
+pub struct InvalidCursor(pub &'static str);
+
+impl From<InvalidCursor> for ErrorResponse {
+    fn from(err: InvalidCursor) -> Self {
+        ErrorResponse::bad_request("INVALID_CURSOR", err.to_string())
+    }
+}
+
+/// Страница в ответе: элементы в теле, курсор следующей страницы - в заголовке
+pub fn paged_response<T: Serialize>(page: Page<T>) -> Response {
+    let mut response = Json(page.items).into_response();
+    if let Some(cursor) = page
+        .next_cursor
+        .and_then(|cursor| HeaderValue::from_str(&cursor).ok())
+    {
+        response.headers_mut().insert(NEXT_CURSOR_HEADER, cursor);
+    }
+    response
+}
+
+/// `cursor` и `offset` взаимоисключающие
+pub fn ensure_single_mode(cursor: Option<&str>, offset: Option<u32>) -> Result<(), ErrorResponse> {
+    if cursor.is_some() && offset.is_some_and(|offset| offset > 0) {
+        return Err(ErrorResponse::bad_request(
+            "INVALID_PAGINATION",
+            "Use either cursor or offset, not both",
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BY_EMAIL: SortKey = SortKey::new("email", "email", 1);
+
+    #[test]
+    fn cursor_round_trips() {
+        let cursor = PageCursor {
+            sort: "email".to_string(),
+            key: Bson::String("a@test.com".to_string()),
+            id: ObjectId::new(),
+        };
+        assert_eq!(PageCursor::decode(&cursor.encode(), &BY_EMAIL), Ok(cursor));
+    }
+
+    #[test]
+    fn garbage_and_foreign_cursors_are_rejected() {
+        assert!(PageCursor::decode("not a cursor!", &BY_EMAIL).is_err());
+        assert!(PageCursor::decode(&URL_SAFE_NO_PAD.encode(b"garbage"), &BY_EMAIL).is_err());
+
+        let by_id = SortKey::new("id", "_id", 1);
+        let cursor = PageCursor {
+            sort: by_id.name.to_string(),
+            key: Bson::Null,
+            id: ObjectId::new(),
+        };
+        assert!(PageCursor::decode(&cursor.encode(), &BY_EMAIL).is_err());
+    }
+
+    #[test]
+    fn split_page_builds_cursor_from_last_kept_document() {
+        let documents: Vec<Document> = (0..3)
+            .map(|index| doc! { "_id": ObjectId::new(), "email": format!("{index}@test.com") })
+            .collect();
+        let (page, next) = BY_EMAIL.split_page(documents.clone(), 2);
+        assert_eq!(page.len(), 2);
+        let cursor = PageCursor::decode(&next.unwrap(), &BY_EMAIL).unwrap();
+        assert_eq!(cursor.key, Bson::String("1@test.com".to_string()));
+        assert_eq!(cursor.id, documents[1].get_object_id("_id").unwrap());
+
+        let (page, next) = BY_EMAIL.split_page(documents, 3);
+        assert_eq!(page.len(), 3);
+        assert!(next.is_none());
+    }
+
+    #[test]
+    fn descending_filter_uses_lt_with_id_tiebreak() {
+        let by_created = SortKey::new("created_at", "createdAt", -1);
+        let id = ObjectId::new();
+        let cursor = PageCursor {
+            sort: "created_at".to_string(),
+            key: Bson::Int32(5),
+            id,
+        };
+        assert_eq!(
+            by_created.apply(doc! { "role": "student" }, Some(&cursor)),
+            doc! { "$and": [
+                { "role": "student" },
+                { "$or": [
+                    { "createdAt": { "$lt": 5 } },
+                    { "createdAt": 5, "_id": { "$lt": id } },
+                ] },
+            ] }
+        );
+    }
+}
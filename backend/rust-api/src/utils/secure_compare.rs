@@ -0,0 +1,64 @@
+//! Сравнение секретов за время, не зависящее от содержимого
+
+/// Побайтовое сравнение без раннего выхода: время зависит только от длины
+/// более длинного аргумента, а не от позиции первого расхождения
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    let len = a.len().max(b.len());
+    let mut diff = (a.len() ^ b.len()) as u64;
+    for i in 0..len {
+        let x = a.get(i).copied().unwrap_or(0);
+        let y = b.get(i).copied().unwrap_or(0);
+        diff |= u64::from(x ^ y);
+    }
+    std::hint::black_box(diff) == 0
+}
+
+/// Строка похожа на bcrypt-хеш (`$2a$`, `$2b$`, `$2y$`)
+pub fn is_bcrypt_hash(value: &str) -> bool {
+    ["$2a$", "$2b$", "$2y$"]
+        .iter()
+        .any(|prefix| value.starts_with(prefix))
+}
+
+/// Проверка пароля против хранимого значения: bcrypt-хеша или открытого текста
+pub fn verify_secret(candidate: &str, stored: &str) -> bool {
+    if is_bcrypt_hash(stored) {
+        bcrypt::verify(candidate, stored).unwrap_or(false)
+    } else {
+        constant_time_eq(candidate.as_bytes(), stored.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn equal_slices_match() {
+        assert!(constant_time_eq(b"", b""));
+        assert!(constant_time_eq(b"prometheus", b"prometheus"));
+    }
+
+    #[test]
+    fn different_contents_or_lengths_do_not_match() {
+        assert!(!constant_time_eq(b"prometheus", b"prometheuS"));
+        assert!(!constant_time_eq(b"admin", b"admin2"));
+        assert!(!constant_time_eq(b"admin2", b"admin"));
+        // Хвост из нулей не маскирует разницу в длине
+        assert!(!constant_time_eq(b"abc", b"abc\0"));
+        assert!(!constant_time_eq(b"", b"\0"));
+    }
+
+    #[test]
+    fn verify_secret_supports_plaintext_and_bcrypt() {
+        assert!(verify_secret("s3cret", "s3cret"));
+        assert!(!verify_secret("wrong", "s3cret"));
+
+        let hash = bcrypt::hash("s3cret", 4).unwrap();
+        assert!(is_bcrypt_hash(&hash));
+        assert!(verify_secret("s3cret", &hash));
+        assert!(!verify_secret("wrong", &hash));
+        // Сам хеш в качестве пароля не подходит
+        assert!(!verify_secret(&hash, &hash));
+    }
+}
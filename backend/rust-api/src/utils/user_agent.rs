@@ -0,0 +1,108 @@
+//! Разбор User-Agent и IP для описания устройства в списке сессий
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+pub const UNKNOWN: &str = "Unknown";
+
+/// Браузер по User-Agent. Порядок проверок важен: Edge, Opera и Яндекс.Браузер
+/// тоже пишут `Chrome/`, а Chrome - `Safari/`
+pub fn parse_browser(user_agent: &str) -> &'static str {
+    const BROWSERS: &[(&str, &str)] = &[
+        ("Edg/", "Edge"),
+        ("Edge/", "Edge"),
+        ("OPR/", "Opera"),
+        ("YaBrowser/", "Yandex Browser"),
+        ("Firefox/", "Firefox"),
+        ("FxiOS/", "Firefox"),
+        ("CriOS/", "Chrome"),
+        ("Chrome/", "Chrome"),
+        ("Safari/", "Safari"),
+    ];
+    BROWSERS
+        .iter()
+        .find(|(marker, _)| user_agent.contains(marker))
+        .map(|(_, name)| *name)
+        .unwrap_or(UNKNOWN)
+}
+
+/// Операционная система по User-Agent. Android и ChromeOS проверяются раньше Linux,
+/// iOS - раньше macOS (iPad/iPhone пишут `like Mac OS X`)
+pub fn parse_os(user_agent: &str) -> &'static str {
+    const SYSTEMS: &[(&str, &str)] = &[
+        ("Windows", "Windows"),
+        ("Android", "Android"),
+        ("iPhone", "iOS"),
+        ("iPad", "iOS"),
+        ("CrOS", "ChromeOS"),
+        ("Mac OS X", "macOS"),
+        ("Macintosh", "macOS"),
+        ("Linux", "Linux"),
+    ];
+    SYSTEMS
+        .iter()
+        .find(|(marker, _)| user_agent.contains(marker))
+        .map(|(_, name)| *name)
+        .unwrap_or(UNKNOWN)
+}
+
+/// Усечённый IP для показа пользователю: у IPv4 обнуляется последний октет,
+/// у IPv6 остаются первые 48 бит. Из `X-Forwarded-For` берётся первый адрес
+pub fn truncate_ip(ip: &str) -> Option<String> {
+    let first = ip.split(',').next()?.trim();
+    match first.parse::<IpAddr>().ok()? {
+        IpAddr::V4(addr) => {
+            let [a, b, c, _] = addr.octets();
+            Some(Ipv4Addr::new(a, b, c, 0).to_string())
+        }
+        IpAddr::V6(addr) => {
+            let [a, b, c, ..] = addr.segments();
+            Some(Ipv6Addr::new(a, b, c, 0, 0, 0, 0, 0).to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHROME_WINDOWS: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+    const SAFARI_IPHONE: &str = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_1 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.1 Mobile/15E148 Safari/604.1";
+    const FIREFOX_LINUX: &str =
+        "Mozilla/5.0 (X11; Ubuntu; Linux x86_64; rv:121.0) Gecko/20100101 Firefox/121.0";
+    const EDGE_MAC: &str = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36 Edg/120.0.0.0";
+    const YANDEX_ANDROID: &str = "Mozilla/5.0 (Linux; Android 13; SM-A525F) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/118.0.0.0 YaBrowser/23.11.1.88.00 SA/3 Mobile Safari/537.36";
+
+    #[test]
+    fn parses_common_browsers_and_systems() {
+        assert_eq!(parse_browser(CHROME_WINDOWS), "Chrome");
+        assert_eq!(parse_os(CHROME_WINDOWS), "Windows");
+        assert_eq!(parse_browser(SAFARI_IPHONE), "Safari");
+        assert_eq!(parse_os(SAFARI_IPHONE), "iOS");
+        assert_eq!(parse_browser(FIREFOX_LINUX), "Firefox");
+        assert_eq!(parse_os(FIREFOX_LINUX), "Linux");
+        assert_eq!(parse_browser(EDGE_MAC), "Edge");
+        assert_eq!(parse_os(EDGE_MAC), "macOS");
+        assert_eq!(parse_browser(YANDEX_ANDROID), "Yandex Browser");
+        assert_eq!(parse_os(YANDEX_ANDROID), "Android");
+    }
+
+    #[test]
+    fn unknown_user_agent_falls_back() {
+        assert_eq!(parse_browser("curl/8.4.0"), UNKNOWN);
+        assert_eq!(parse_os("curl/8.4.0"), UNKNOWN);
+    }
+
+    #[test]
+    fn truncates_ip_addresses() {
+        assert_eq!(truncate_ip("203.0.113.57").as_deref(), Some("203.0.113.0"));
+        assert_eq!(
+            truncate_ip("198.51.100.7, 10.0.0.1").as_deref(),
+            Some("198.51.100.0")
+        );
+        assert_eq!(
+            truncate_ip("2001:db8:85a3::8a2e:370:7334").as_deref(),
+            Some("2001:db8:85a3::")
+        );
+        assert_eq!(truncate_ip("not-an-ip"), None);
+    }
+}
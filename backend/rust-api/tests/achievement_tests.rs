@@ -0,0 +1,117 @@
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use chrono::Utc;
+use mongodb::bson::oid::ObjectId;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use trainingground_api::{
+    create_router,
+    middlewares::auth::{JwtClaims, JwtService},
+    services::AppState,
+};
+use uuid::Uuid;
+
+mod common;
+
+fn student_jwt(state: &AppState, user_id: &str) -> String {
+    let now = Utc::now().timestamp() as usize;
+    JwtService::from_config(&state.config)
+        .generate_token(JwtClaims {
+            sub: user_id.to_string(),
+            role: "student".to_string(),
+            group_ids: Vec::new(),
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap()
+}
+
+async fn send(
+    app: &axum::Router,
+    method: &str,
+    uri: &str,
+    token: Option<&str>,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let csrf_token = Uuid::new_v4().to_string();
+    let mut request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .header("x-csrf-token", &csrf_token)
+        .header("cookie", format!("csrf_token={}", csrf_token));
+    if let Some(token) = token {
+        request = request.header("authorization", format!("Bearer {}", token));
+    }
+    let body = body.map(|body| Body::from(body.to_string()));
+
+    let response = app
+        .clone()
+        .oneshot(request.body(body.unwrap_or_else(Body::empty)).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or_default())
+}
+
+async fn complete_new_session(app: &axum::Router, token: &str, user_id: &str) {
+    let (status, session) = send(
+        app,
+        "POST",
+        "/api/v1/sessions",
+        Some(token),
+        Some(json!({ "user_id": user_id, "task_id": "test-task" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let session_id = session["session_id"].as_str().unwrap();
+
+    let (status, _) = send(
+        app,
+        "POST",
+        &format!("/api/v1/sessions/{}/complete", session_id),
+        None,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn test_first_completed_session_unlocks_achievement() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+    let user_id = ObjectId::new().to_hex();
+    let token = student_jwt(&state, &user_id);
+
+    let (status, body) = send(&app, "GET", "/api/v1/me/achievements", Some(&token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["current_streak"], 0);
+    assert_eq!(body["achievements"], json!([]));
+    assert_eq!(body["timezone"], state.config.sessions.default_timezone);
+
+    complete_new_session(&app, &token, &user_id).await;
+
+    let (status, body) = send(&app, "GET", "/api/v1/me/achievements", Some(&token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["current_streak"], 1);
+    assert_eq!(body["longest_streak"], 1);
+    let achievements = body["achievements"].as_array().unwrap();
+    assert_eq!(achievements.len(), 1);
+    assert_eq!(achievements[0]["kind"], "first_session");
+    assert!(achievements[0]["unlocked_at"].is_string());
+
+    // Вторая сессия в тот же день не удлиняет серию и не повторяет достижение
+    complete_new_session(&app, &token, &user_id).await;
+    let (_, body) = send(&app, "GET", "/api/v1/me/achievements", Some(&token), None).await;
+    assert_eq!(body["current_streak"], 1);
+    assert_eq!(body["achievements"].as_array().unwrap().len(), 1);
+
+    let (status, _) = send(&app, "GET", "/api/v1/me/achievements", None, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
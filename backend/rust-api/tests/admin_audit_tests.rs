@@ -1,17 +1,27 @@
+use std::sync::Arc;
+
 use axum::{
     body::{to_bytes, Body},
-    http::Request,
+    http::{Request, StatusCode},
 };
-use mongodb::bson::doc;
+use chrono::{Duration, SecondsFormat, Utc};
+use futures::StreamExt;
+use mongodb::bson::{doc, oid::ObjectId, Document};
 use serde_json::json;
 use tower::ServiceExt;
 use trainingground_api::{
     config::Config,
+    create_router,
+    middlewares::auth::{JwtClaims, JwtService},
     models::audit_log::{AuditEventType, AuditLog},
+    services::AppState,
 };
 
 mod common;
 
+/// Столько строк экспорт заведомо отдаёт несколькими батчами курсора
+const EXPORT_ROWS: usize = 3000;
+
 #[tokio::test]
 #[serial_test::serial]
 async fn test_list_audit_logs_returns_entries() {
@@ -69,6 +79,415 @@ async fn test_export_audit_logs_returns_csv() {
     assert!(csv.contains("block_user"));
 }
 
+#[tokio::test]
+#[serial_test::serial]
+async fn test_audit_filters_narrow_results() {
+    let app = common::create_test_app().await;
+    disable_rate_limit();
+    let (_admin_id, admin_token) = create_admin_with_token(&app).await;
+
+    let actor = format!("actor-{}", uuid::Uuid::new_v4());
+    let other_actor = format!("actor-{}", uuid::Uuid::new_v4());
+    let template_id = ObjectId::new().to_hex();
+    let flag_key = format!("flag_{}", uuid::Uuid::new_v4().simple());
+
+    // Старая запись: за пределами окна `from`
+    insert_raw_audit(content_entry(
+        &actor,
+        "template.delete",
+        "templates",
+        &ObjectId::new().to_hex(),
+        Some(days_ago_id(10)),
+    ))
+    .await;
+    for (action, target, target_id) in [
+        ("template.create", "templates", template_id.as_str()),
+        ("template.update", "templates", template_id.as_str()),
+        ("template.approve", "templates", "another-template"),
+        ("topic.create", "topics", "some-topic"),
+    ] {
+        insert_raw_audit(content_entry(&actor, action, target, target_id, None)).await;
+    }
+    insert_raw_audit(doc! {
+        "event_type": "block_user",
+        "user_id": &actor,
+        "success": true,
+        "details": "Blocked user someone for permanent - reason: spam",
+        "createdAt": mongodb::bson::DateTime::now(),
+    })
+    .await;
+    insert_raw_audit(doc! {
+        "entity_type": "feature_flag",
+        "entity_id": &flag_key,
+        "action": "update",
+        "admin_id": &actor,
+        "timestamp": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+    })
+    .await;
+    insert_raw_audit(content_entry(
+        &other_actor,
+        "template.create",
+        "templates",
+        &template_id,
+        None,
+    ))
+    .await;
+
+    let (total, entries) = list_audit(&app, &admin_token, &format!("actor_id={actor}")).await;
+    assert_eq!(total, 7);
+    assert_eq!(entries.len(), 7);
+    // Новые записи первыми; формат записи приведён к общему виду
+    assert_eq!(entries[0]["event_type"], "update");
+    assert_eq!(entries[0]["target"], "feature_flag");
+    assert_eq!(entries[6]["event_type"], "template.delete");
+    assert!(entries
+        .iter()
+        .all(|entry| entry["actor_id"] == actor.as_str()));
+
+    let (total, entries) = list_audit(
+        &app,
+        &admin_token,
+        &format!("actor_id={actor}&action=template.*"),
+    )
+    .await;
+    assert_eq!(total, 4);
+    assert!(entries.iter().all(|entry| entry["event_type"]
+        .as_str()
+        .unwrap()
+        .starts_with("template.")));
+
+    let (total, _) = list_audit(
+        &app,
+        &admin_token,
+        &format!("actor_id={actor}&action=template.create"),
+    )
+    .await;
+    assert_eq!(total, 1);
+
+    let (total, entries) = list_audit(
+        &app,
+        &admin_token,
+        &format!("target=templates&target_id={template_id}"),
+    )
+    .await;
+    assert_eq!(total, 3);
+    assert!(entries
+        .iter()
+        .any(|entry| entry["actor_id"] == other_actor.as_str()));
+
+    let (total, entries) = list_audit(
+        &app,
+        &admin_token,
+        &format!("target=feature_flag&target_id={flag_key}"),
+    )
+    .await;
+    assert_eq!(total, 1);
+    assert_eq!(entries[0]["actor_id"], actor.as_str());
+
+    let (total, _) = list_audit(
+        &app,
+        &admin_token,
+        &format!("actor_id={actor}&event_type=block_user"),
+    )
+    .await;
+    assert_eq!(total, 1);
+
+    let from = (Utc::now() - Duration::days(1)).to_rfc3339_opts(SecondsFormat::Secs, true);
+    let (total, entries) =
+        list_audit(&app, &admin_token, &format!("actor_id={actor}&from={from}")).await;
+    assert_eq!(total, 6);
+    assert!(entries
+        .iter()
+        .all(|entry| entry["event_type"] != "template.delete"));
+
+    let (total, page) = list_audit(
+        &app,
+        &admin_token,
+        &format!("actor_id={actor}&limit=3&offset=6"),
+    )
+    .await;
+    assert_eq!(total, 7);
+    assert_eq!(page.len(), 1);
+    assert_eq!(page[0]["event_type"], "template.delete");
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_audit_actor_summary_counts_actions() {
+    let app = common::create_test_app().await;
+    disable_rate_limit();
+    let (_admin_id, admin_token) = create_admin_with_token(&app).await;
+
+    let actor = format!("actor-{}", uuid::Uuid::new_v4());
+    for action in ["template.update", "template.update", "template.update"] {
+        insert_raw_audit(content_entry(&actor, action, "templates", "t-1", None)).await;
+    }
+    insert_raw_audit(content_entry(&actor, "topic.create", "topics", "x", None)).await;
+    insert_raw_audit(doc! {
+        "event_type": "delete_user",
+        "user_id": &actor,
+        "success": true,
+        "createdAt": mongodb::bson::DateTime::now(),
+    })
+    .await;
+    insert_raw_audit(content_entry(
+        &actor,
+        "template.update",
+        "templates",
+        "t-1",
+        Some(days_ago_id(45)),
+    ))
+    .await;
+
+    let summary = get_json(
+        &app,
+        &admin_token,
+        &format!("/admin/audit/actors/{actor}/summary"),
+    )
+    .await;
+    assert_eq!(summary["actor_id"], actor.as_str());
+    assert_eq!(summary["total"], 5);
+    let actions = summary["actions"].as_array().unwrap();
+    assert_eq!(actions[0]["action"], "template.update");
+    assert_eq!(actions[0]["count"], 3);
+    assert!(actions[0]["last_at"].is_string());
+    assert!(actions
+        .iter()
+        .any(|action| action["action"] == "delete_user" && action["count"] == 1));
+
+    // Запись 45-дневной давности попадает только в явно заданный период
+    let from = (Utc::now() - Duration::days(60)).to_rfc3339_opts(SecondsFormat::Secs, true);
+    let summary = get_json(
+        &app,
+        &admin_token,
+        &format!("/admin/audit/actors/{actor}/summary?from={from}"),
+    )
+    .await;
+    assert_eq!(summary["total"], 6);
+    assert_eq!(summary["actions"][0]["count"], 4);
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_export_streams_only_filtered_rows() {
+    let state = common::create_test_state().await;
+    let token = admin_jwt(&state);
+    let app = create_router(Arc::new(state));
+
+    let actor = format!("actor-{}", uuid::Uuid::new_v4());
+    let mut entries: Vec<Document> = (0..EXPORT_ROWS)
+        .map(|i| {
+            content_entry(
+                &actor,
+                "template.update",
+                "templates",
+                &format!("t-{i}"),
+                None,
+            )
+        })
+        .collect();
+    // Свободный текст: формула в причине и JSON в деталях
+    entries[0].insert("reason", "=HYPERLINK(\"http://evil\")");
+    entries[0].insert("details", doc! { "note": "a, \"b\"" });
+    insert_raw_audit_many(entries).await;
+    insert_raw_audit_many(
+        (0..5)
+            .map(|_| content_entry("someone-else", "template.update", "templates", "x", None))
+            .collect(),
+    )
+    .await;
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(format!("/admin/audit/export?actor_id={actor}"))
+                .header("authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Тело приходит частями: заголовок CSV раньше, чем прочитан курсор
+    let mut frames = response.into_body().into_data_stream();
+    let header = frames.next().await.unwrap().unwrap();
+    assert!(header.starts_with(b"timestamp,event_type,"));
+    let mut frame_count = 1;
+    let mut csv = String::from_utf8(header.to_vec()).unwrap();
+    while let Some(frame) = frames.next().await {
+        csv.push_str(std::str::from_utf8(&frame.unwrap()).unwrap());
+        frame_count += 1;
+    }
+    assert!(frame_count > 1);
+
+    let rows: Vec<&str> = csv.lines().skip(1).collect();
+    assert_eq!(rows.len(), EXPORT_ROWS);
+    assert!(rows.iter().all(|row| row.contains(&actor)));
+    assert!(csv.contains("\"\t=HYPERLINK(\"\"http://evil\"\")\""));
+    assert!(csv.contains(r#""{""note"":""a, \""b\""""}""#));
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_export_over_row_limit_is_rejected() {
+    let mut state = common::create_test_state().await;
+    state.config.audit.export_max_rows = 3;
+    let token = admin_jwt(&state);
+    let app = create_router(Arc::new(state));
+
+    let actor = format!("actor-{}", uuid::Uuid::new_v4());
+    insert_raw_audit_many(
+        (0..4)
+            .map(|i| content_entry(&actor, "topic.create", "topics", &format!("t-{i}"), None))
+            .collect(),
+    )
+    .await;
+
+    let export = |query: String| {
+        let app = app.clone();
+        let token = token.clone();
+        async move {
+            app.oneshot(
+                Request::builder()
+                    .method("GET")
+                    .uri(format!("/admin/audit/export?{query}"))
+                    .header("authorization", format!("Bearer {}", token))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+        }
+    };
+
+    let response = export(format!("actor_id={actor}")).await;
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["code"], "EXPORT_TOO_LARGE");
+
+    let response = export(format!("actor_id={actor}&target_id=t-1")).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(String::from_utf8(body.to_vec()).unwrap().lines().count(), 2);
+}
+
+fn admin_jwt(state: &AppState) -> String {
+    let now = Utc::now().timestamp() as usize;
+    JwtService::new(&state.config.jwt_secret)
+        .generate_token(JwtClaims {
+            sub: ObjectId::new().to_hex(),
+            role: "admin".to_string(),
+            group_ids: Vec::new(),
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap()
+}
+
+async fn insert_raw_audit_many(entries: Vec<Document>) {
+    let config = Config::load().unwrap();
+    let mongo_client = mongodb::Client::with_uri_str(&config.mongo_uri)
+        .await
+        .unwrap();
+    mongo_client
+        .database(&config.mongo_database)
+        .collection::<Document>("audit_log")
+        .insert_many(entries)
+        .await
+        .unwrap();
+}
+
+async fn list_audit(app: &axum::Router, token: &str, query: &str) -> (u64, Vec<serde_json::Value>) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(format!("/admin/audit?{}", query.replace('+', "%2B")))
+                .header("authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert!(response.status().is_success(), "{}", response.status());
+    let total = response.headers()["x-total-count"]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    (total, json.as_array().unwrap().clone())
+}
+
+async fn get_json(app: &axum::Router, token: &str, uri: &str) -> serde_json::Value {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(uri.replace('+', "%2B"))
+                .header("authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert!(response.status().is_success(), "{}", response.status());
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+/// Запись в формате журнала изменений контента
+fn content_entry(
+    actor: &str,
+    action: &str,
+    target: &str,
+    target_id: &str,
+    id: Option<ObjectId>,
+) -> Document {
+    let mut entry = doc! {
+        "actor_id": actor,
+        "actor_role": "content_admin",
+        "action": action,
+        "target": target,
+        "target_id": target_id,
+        "details": { "status": "draft" },
+        "created_at": mongodb::bson::DateTime::now(),
+    };
+    if let Some(id) = id {
+        entry.insert("_id", id);
+    }
+    entry
+}
+
+/// ObjectId с временем создания `days` дней назад
+fn days_ago_id(days: i64) -> ObjectId {
+    let at = Utc::now() - Duration::days(days);
+    let mut bytes = ObjectId::new().bytes();
+    bytes[..4].copy_from_slice(&(at.timestamp() as u32).to_be_bytes());
+    ObjectId::from_bytes(bytes)
+}
+
+async fn insert_raw_audit(entry: Document) {
+    let config = Config::load().unwrap();
+    let mongo_client = mongodb::Client::with_uri_str(&config.mongo_uri)
+        .await
+        .unwrap();
+    mongo_client
+        .database(&config.mongo_database)
+        .collection::<Document>("audit_log")
+        .insert_one(entry)
+        .await
+        .unwrap();
+}
+
 async fn create_admin_with_token(app: &axum::Router) -> (String, String) {
     let register_body = json!({
         "email": format!("audit-admin-{}@test.com", uuid::Uuid::new_v4()),
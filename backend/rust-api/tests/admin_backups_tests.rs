@@ -1,13 +1,59 @@
+use std::collections::HashMap;
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
 use axum::{
     body::{to_bytes, Body},
     http::{Request, StatusCode},
 };
+use flate2::read::GzDecoder;
+use mongodb::bson::{doc, Document};
 use serde_json::json;
 use tower::ServiceExt;
+use trainingground_api::{create_router, services::session_archive_service::ArchiveStorage};
 
 mod common;
 use uuid::Uuid;
 
+/// Хранилище в памяти вместо объектного
+#[derive(Default)]
+struct MockStorage {
+    objects: Mutex<HashMap<String, Vec<u8>>>,
+}
+
+#[async_trait]
+impl ArchiveStorage for MockStorage {
+    async fn put_bundle(&self, key: &str, bytes: Vec<u8>) -> anyhow::Result<()> {
+        self.objects.lock().unwrap().insert(key.to_string(), bytes);
+        Ok(())
+    }
+
+    async fn read_range(&self, key: &str, offset: u64, length: u64) -> anyhow::Result<Vec<u8>> {
+        let objects = self.objects.lock().unwrap();
+        let bytes = objects
+            .get(key)
+            .ok_or_else(|| anyhow::anyhow!("object {} not found", key))?;
+        Ok(bytes[offset as usize..(offset + length) as usize].to_vec())
+    }
+
+    async fn read_object(&self, key: &str) -> anyhow::Result<Vec<u8>> {
+        self.objects
+            .lock()
+            .unwrap()
+            .get(key)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("object {} not found", key))
+    }
+}
+
+async fn backup_app(storage: Arc<MockStorage>) -> axum::Router {
+    let mut state = common::create_test_state().await;
+    state.archive_storage = Some(storage);
+    create_router(Arc::new(state))
+}
+
 #[tokio::test]
 async fn test_list_backups_returns_data() {
     let app = common::create_test_app().await;
@@ -31,7 +77,7 @@ async fn test_list_backups_returns_data() {
 
 #[tokio::test]
 async fn test_create_backup_returns_record() {
-    let app = common::create_test_app().await;
+    let app = backup_app(Arc::new(MockStorage::default())).await;
     let (_admin_id, admin_token) = create_admin_with_token(&app).await;
     let (csrf_token, csrf_cookie) = get_csrf_token(&app).await;
 
@@ -56,15 +102,336 @@ async fn test_create_backup_returns_record() {
     assert_eq!(response.status(), StatusCode::CREATED);
     let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
     let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
-    assert_eq!(json["status"], "Completed");
+    assert_eq!(json["status"], "Pending");
+    // Без списка коллекций выгружается вся база, кроме самих записей о бэкапах
+    let collections = json["collections"].as_array().unwrap();
+    assert!(collections.iter().any(|name| name == "users"));
+    assert!(!collections.iter().any(|name| name == "backups"));
 }
 
 #[tokio::test]
+#[serial_test::serial]
 async fn test_restore_backup_returns_message() {
-    let app = common::create_test_app().await;
+    let app = backup_app(Arc::new(MockStorage::default())).await;
     let (_admin_id, admin_token) = create_admin_with_token(&app).await;
-    let (csrf_token, csrf_cookie) = get_csrf_token(&app).await;
 
+    let name = format!("backup_restore_{}", Uuid::new_v4().simple());
+    let collection = test_collection(&name).await;
+    collection.insert_one(doc! { "n": 1 }).await.unwrap();
+    let backup_id = create_completed_backup(&app, &admin_token, &[&name]).await;
+
+    let (status, restore_json) = post_restore(&app, &admin_token, &backup_id, None).await;
+    assert_eq!(status, StatusCode::OK, "{restore_json}");
+    assert!(
+        restore_json["message"]
+            .as_str()
+            .unwrap_or_default()
+            .contains("restore"),
+        "restore response should contain info message"
+    );
+
+    collection.drop().await.unwrap();
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_restore_dry_run_reports_changes_without_writing() {
+    let storage = Arc::new(MockStorage::default());
+    let app = backup_app(storage.clone()).await;
+    let (_admin_id, admin_token) = create_admin_with_token(&app).await;
+
+    let (first_name, first) = seeded_collection("backup_dry_a", 10).await;
+    let (second_name, _second) = seeded_collection("backup_dry_b", 5).await;
+    let backup_id = create_completed_backup(&app, &admin_token, &[&first_name, &second_name]).await;
+
+    // После бэкапа: 3 документа изменены, 2 удалены, 1 добавлен
+    first
+        .update_many(
+            doc! { "n": { "$lt": 3 } },
+            doc! { "$set": { "edited": true } },
+        )
+        .await
+        .unwrap();
+    first
+        .delete_many(doc! { "n": { "$in": [8, 9] } })
+        .await
+        .unwrap();
+    first.insert_one(doc! { "n": 100 }).await.unwrap();
+
+    let (status, plan) = post_restore(
+        &app,
+        &admin_token,
+        &backup_id,
+        Some(json!({ "dry_run": true })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{plan}");
+    assert_eq!(plan["dry_run"], true);
+    assert!(plan.get("rollback_key").is_none());
+
+    let collections = plan["collections"].as_array().unwrap();
+    let first_plan = collections
+        .iter()
+        .find(|c| c["name"] == first_name.as_str())
+        .unwrap();
+    assert_eq!(first_plan["documents_in_backup"], 10);
+    assert_eq!(first_plan["to_insert"], 2);
+    assert_eq!(first_plan["to_overwrite"], 3);
+    assert_eq!(first_plan["unchanged"], 5);
+    let second_plan = collections
+        .iter()
+        .find(|c| c["name"] == second_name.as_str())
+        .unwrap();
+    assert_eq!(second_plan["unchanged"], 5);
+    assert_eq!(second_plan["to_insert"], 0);
+
+    // Ничего не записано: ни в коллекцию, ни снимок для отката
+    assert_eq!(first.count_documents(doc! {}).await.unwrap(), 9);
+    assert_eq!(
+        first
+            .count_documents(doc! { "edited": true })
+            .await
+            .unwrap(),
+        3
+    );
+    assert!(!storage
+        .objects
+        .lock()
+        .unwrap()
+        .keys()
+        .any(|key| key.starts_with("rollback/")));
+
+    first.drop().await.unwrap();
+    test_collection(&second_name).await.drop().await.unwrap();
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_selective_restore_leaves_other_collections_untouched() {
+    let storage = Arc::new(MockStorage::default());
+    let app = backup_app(storage.clone()).await;
+    let (admin_id, admin_token) = create_admin_with_token(&app).await;
+
+    let (restored_name, restored) = seeded_collection("backup_sel_a", 6).await;
+    let (skipped_name, skipped) = seeded_collection("backup_sel_b", 4).await;
+    let backup_id =
+        create_completed_backup(&app, &admin_token, &[&restored_name, &skipped_name]).await;
+
+    for collection in [&restored, &skipped] {
+        collection
+            .update_many(doc! {}, doc! { "$set": { "edited": true } })
+            .await
+            .unwrap();
+        collection.delete_one(doc! { "n": 0 }).await.unwrap();
+        collection.insert_one(doc! { "n": 100 }).await.unwrap();
+    }
+
+    let (status, result) = post_restore(
+        &app,
+        &admin_token,
+        &backup_id,
+        Some(json!({ "collections": [restored_name] })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{result}");
+    assert_eq!(result["dry_run"], false);
+    assert_eq!(result["collections"].as_array().unwrap().len(), 1);
+    assert_eq!(result["collections"][0]["to_insert"], 1);
+    assert_eq!(result["collections"][0]["to_overwrite"], 5);
+
+    // Восстановленная коллекция вернулась к бэкапу; документ, созданный позже, остался
+    assert_eq!(
+        restored
+            .count_documents(doc! { "edited": true })
+            .await
+            .unwrap(),
+        0
+    );
+    assert_eq!(restored.count_documents(doc! {}).await.unwrap(), 7);
+    assert_eq!(restored.count_documents(doc! { "n": 0 }).await.unwrap(), 1);
+
+    // Вторая коллекция не тронута
+    assert_eq!(
+        skipped
+            .count_documents(doc! { "edited": true })
+            .await
+            .unwrap(),
+        4
+    );
+    assert_eq!(skipped.count_documents(doc! { "n": 0 }).await.unwrap(), 0);
+
+    // Снимок до восстановления - только для затронутой коллекции
+    let rollback_key = result["rollback_key"].as_str().unwrap().to_string();
+    assert!(rollback_key.starts_with("rollback/"));
+    let snapshot_key = format!("{rollback_key}{restored_name}/part-00001.ndjson.gz");
+    let snapshot = storage.objects.lock().unwrap()[&snapshot_key].clone();
+    let mut ndjson = String::new();
+    GzDecoder::new(snapshot.as_slice())
+        .read_to_string(&mut ndjson)
+        .unwrap();
+    assert_eq!(ndjson.lines().count(), 6);
+    assert!(!storage.objects.lock().unwrap().contains_key(&format!(
+        "{rollback_key}{skipped_name}/part-00001.ndjson.gz"
+    )));
+
+    let audit = test_collection("audit_log")
+        .await
+        .find_one(doc! {
+            "event_type": "restore_backup",
+            "user_id": &admin_id,
+            "details": { "$regex": rollback_key.as_str() },
+        })
+        .await
+        .unwrap();
+    assert!(audit.is_some(), "restore audit entry missing");
+
+    restored.drop().await.unwrap();
+    skipped.drop().await.unwrap();
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_restore_conflicts_while_another_restore_runs() {
+    let app = backup_app(Arc::new(MockStorage::default())).await;
+    let (_admin_id, admin_token) = create_admin_with_token(&app).await;
+
+    let (name, collection) = seeded_collection("backup_lock", 2).await;
+    let backup_id = create_completed_backup(&app, &admin_token, &[&name]).await;
+
+    let config = trainingground_api::config::Config::load().unwrap();
+    let mut redis = redis::Client::open(config.redis_uri)
+        .unwrap()
+        .get_multiplexed_async_connection()
+        .await
+        .unwrap();
+    let _: () = redis::cmd("SET")
+        .arg("lock:backup_restore")
+        .arg("other-restore")
+        .arg("PX")
+        .arg(60_000)
+        .query_async(&mut redis)
+        .await
+        .unwrap();
+
+    let (status, _) = post_restore(&app, &admin_token, &backup_id, None).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    // Dry-run ничего не пишет и блокировку не берёт
+    let (status, _) = post_restore(
+        &app,
+        &admin_token,
+        &backup_id,
+        Some(json!({ "dry_run": true })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let _: () = redis::cmd("DEL")
+        .arg("lock:backup_restore")
+        .query_async(&mut redis)
+        .await
+        .unwrap();
+    let (status, _) = post_restore(&app, &admin_token, &backup_id, None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    collection.drop().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_scoped_backup_reports_progress_until_complete() {
+    let storage = Arc::new(MockStorage::default());
+    let app = backup_app(storage.clone()).await;
+    let (admin_id, admin_token) = create_admin_with_token(&app).await;
+
+    let collection_name = format!("backup_scope_{}", Uuid::new_v4().simple());
+    let collection = test_collection(&collection_name).await;
+    let seeded: Vec<Document> = (0..25i64)
+        .map(|i| doc! { "n": i, "label": format!("doc-{i}") })
+        .collect();
+    collection.insert_many(seeded).await.unwrap();
+
+    let (status, created) = post_backup(
+        &app,
+        &admin_token,
+        json!({ "label": "scoped", "collections": [collection_name] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(created["collections"], json!([collection_name]));
+    let backup_id = created["id"].as_str().unwrap().to_string();
+
+    let mut backup = serde_json::Value::Null;
+    for _ in 0..50 {
+        backup = get_backup(&app, &admin_token, &backup_id).await;
+        if backup["status"] == "Completed" || backup["status"] == "Failed" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    assert_eq!(backup["status"], "Completed", "{backup}");
+    assert_eq!(backup["progress_percent"], 100.0);
+    assert_eq!(backup["created_by"], admin_id.as_str());
+    assert_eq!(backup["total_collections"], 1);
+    assert_eq!(backup["total_documents"], 25);
+    assert_eq!(backup["documents_written"], 25);
+    assert!(backup["finished_at"].is_object());
+
+    let progress = &backup["collections"][0];
+    assert_eq!(progress["name"], collection_name.as_str());
+    assert_eq!(progress["total_documents"], 25);
+    assert_eq!(progress["documents_written"], 25);
+    assert_eq!(progress["completed"], true);
+
+    let parts = progress["parts"].as_array().unwrap();
+    assert_eq!(parts.len(), 1);
+    let key = parts[0].as_str().unwrap();
+    assert_eq!(
+        key,
+        format!("backups/{backup_id}/{collection_name}/part-00001.ndjson.gz")
+    );
+
+    let bytes = storage.objects.lock().unwrap()[key].clone();
+    assert_eq!(backup["bytes_written"], bytes.len() as u64);
+    let mut ndjson = String::new();
+    GzDecoder::new(bytes.as_slice())
+        .read_to_string(&mut ndjson)
+        .unwrap();
+    let lines: Vec<serde_json::Value> = ndjson
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 25);
+    // Canonical Extended JSON: тип Int64 сохраняется
+    assert_eq!(lines[3]["n"], json!({ "$numberLong": "3" }));
+    assert_eq!(lines[3]["label"], "doc-3");
+
+    collection.drop().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_backup_rejects_unknown_collections() {
+    let app = backup_app(Arc::new(MockStorage::default())).await;
+    let (_admin_id, admin_token) = create_admin_with_token(&app).await;
+
+    let (status, _) = post_backup(
+        &app,
+        &admin_token,
+        json!({ "collections": ["users", "no_such_collection_for_backup"] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = post_backup(&app, &admin_token, json!({ "collections": [] })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+async fn post_backup(
+    app: &axum::Router,
+    token: &str,
+    payload: serde_json::Value,
+) -> (StatusCode, serde_json::Value) {
+    let (csrf_token, csrf_cookie) = get_csrf_token(app).await;
     let response = app
         .clone()
         .oneshot(
@@ -72,47 +439,101 @@ async fn test_restore_backup_returns_message() {
                 .method("POST")
                 .uri("/admin/backups")
                 .header("content-type", "application/json")
-                .header("authorization", format!("Bearer {}", admin_token))
+                .header("authorization", format!("Bearer {}", token))
                 .header("x-csrf-token", csrf_token.as_str())
                 .header("cookie", format!("csrf_token={}", csrf_cookie))
-                .body(Body::from(json!({ "label": "restore-me" }).to_string()))
+                .body(Body::from(payload.to_string()))
                 .unwrap(),
         )
         .await
         .unwrap();
-    assert_eq!(response.status(), StatusCode::CREATED);
+    let status = response.status();
     let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
-    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
-    let backup_id = json["id"].as_str().expect("backup id missing").to_string();
+    (status, serde_json::from_slice(&body).unwrap_or_default())
+}
 
-    let (csrf_token, csrf_cookie) = get_csrf_token(&app).await;
-    let restore_response = app
+async fn post_restore(
+    app: &axum::Router,
+    token: &str,
+    backup_id: &str,
+    payload: Option<serde_json::Value>,
+) -> (StatusCode, serde_json::Value) {
+    let (csrf_token, csrf_cookie) = get_csrf_token(app).await;
+    let mut request = Request::builder()
+        .method("POST")
+        .uri(format!("/admin/backups/{}/restore", backup_id))
+        .header("authorization", format!("Bearer {}", token))
+        .header("x-csrf-token", csrf_token.as_str())
+        .header("cookie", format!("csrf_token={}", csrf_cookie));
+    let body = match payload {
+        Some(payload) => {
+            request = request.header("content-type", "application/json");
+            Body::from(payload.to_string())
+        }
+        None => Body::empty(),
+    };
+
+    let response = app
+        .clone()
+        .oneshot(request.body(body).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or_default())
+}
+
+/// Создать бэкап выбранных коллекций и дождаться его завершения
+async fn create_completed_backup(app: &axum::Router, token: &str, collections: &[&str]) -> String {
+    let (status, created) = post_backup(app, token, json!({ "collections": collections })).await;
+    assert_eq!(status, StatusCode::CREATED, "{created}");
+    let backup_id = created["id"].as_str().unwrap().to_string();
+
+    for _ in 0..50 {
+        let backup = get_backup(app, token, &backup_id).await;
+        match backup["status"].as_str() {
+            Some("Completed") => return backup_id,
+            Some("Failed") => panic!("backup failed: {backup}"),
+            _ => tokio::time::sleep(Duration::from_millis(100)).await,
+        }
+    }
+    panic!("backup {backup_id} did not complete");
+}
+
+/// Временная коллекция с документами `{ n: 0..count }`
+async fn seeded_collection(prefix: &str, count: i64) -> (String, mongodb::Collection<Document>) {
+    let name = format!("{}_{}", prefix, Uuid::new_v4().simple());
+    let collection = test_collection(&name).await;
+    let documents: Vec<Document> = (0..count).map(|n| doc! { "n": n }).collect();
+    collection.insert_many(documents).await.unwrap();
+    (name, collection)
+}
+
+async fn get_backup(app: &axum::Router, token: &str, backup_id: &str) -> serde_json::Value {
+    let response = app
         .clone()
         .oneshot(
             Request::builder()
-                .method("POST")
-                .uri(format!("/admin/backups/{}/restore", backup_id))
-                .header("authorization", format!("Bearer {}", admin_token))
-                .header("x-csrf-token", csrf_token.as_str())
-                .header("cookie", format!("csrf_token={}", csrf_cookie))
+                .method("GET")
+                .uri(format!("/admin/backups/{}", backup_id))
+                .header("authorization", format!("Bearer {}", token))
                 .body(Body::empty())
                 .unwrap(),
         )
         .await
         .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
 
-    assert_eq!(restore_response.status(), StatusCode::OK);
-    let restore_body = to_bytes(restore_response.into_body(), usize::MAX)
+async fn test_collection(name: &str) -> mongodb::Collection<Document> {
+    let config = trainingground_api::config::Config::load().unwrap();
+    mongodb::Client::with_uri_str(&config.mongo_uri)
         .await
-        .unwrap();
-    let restore_json: serde_json::Value = serde_json::from_slice(&restore_body).unwrap();
-    assert!(
-        restore_json["message"]
-            .as_str()
-            .unwrap_or_default()
-            .contains("restore"),
-        "restore response should contain info message"
-    );
+        .unwrap()
+        .database(&config.mongo_database)
+        .collection(name)
 }
 
 async fn create_admin_with_token(app: &axum::Router) -> (String, String) {
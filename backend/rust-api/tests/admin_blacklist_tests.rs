@@ -0,0 +1,213 @@
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use chrono::Utc;
+use mongodb::bson::{doc, oid::ObjectId, DateTime, Document};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use trainingground_api::{
+    create_router,
+    middlewares::auth::{JwtClaims, JwtService},
+    services::AppState,
+};
+use uuid::Uuid;
+
+mod common;
+
+fn admin_jwt(state: &AppState) -> String {
+    let now = Utc::now().timestamp() as usize;
+    JwtService::from_config(&state.config)
+        .generate_token(JwtClaims {
+            sub: ObjectId::new().to_hex(),
+            role: "admin".to_string(),
+            group_ids: Vec::new(),
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap()
+}
+
+async fn send(
+    app: &axum::Router,
+    method: &str,
+    uri: &str,
+    token: &str,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let csrf_token = Uuid::new_v4().to_string();
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .header("authorization", format!("Bearer {}", token))
+        .header("x-csrf-token", &csrf_token)
+        .header("cookie", format!("csrf_token={}", csrf_token))
+        .header("x-request-nonce", Uuid::new_v4().to_string())
+        .header("x-request-timestamp", Utc::now().timestamp().to_string())
+        .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or_default())
+}
+
+/// Уровень и правило для шаблонов теста
+async fn insert_level_and_rule(state: &AppState) -> (ObjectId, ObjectId) {
+    let level_id = ObjectId::new();
+    let rule_id = ObjectId::new();
+    let now = DateTime::now();
+    state
+        .mongo
+        .collection::<Document>("levels")
+        .insert_one(doc! {
+            "_id": level_id,
+            "topic_id": ObjectId::new(),
+            "name": "Blacklist level",
+            "difficulty": "a1",
+            "description": "Blacklist level",
+            "min_pass_percent": 80,
+            "status": "active",
+            "order": 1,
+            "createdAt": now,
+            "updatedAt": now,
+        })
+        .await
+        .unwrap();
+    state
+        .mongo
+        .collection::<Document>("rules")
+        .insert_one(doc! {
+            "_id": rule_id,
+            "slug": format!("blacklist-rule-{}", Uuid::new_v4().simple()),
+            "name": "Blacklist rule",
+            "category": "orthography",
+            "description": "Blacklist rule",
+            "examples": ["Пример"],
+            "status": "active",
+            "createdAt": now,
+            "updatedAt": now,
+        })
+        .await
+        .unwrap();
+    (level_id, rule_id)
+}
+
+fn template_body(level_id: &ObjectId, rule_id: &ObjectId, content: &str) -> Value {
+    json!({
+        "slug": format!("blacklist-{}", Uuid::new_v4().simple()),
+        "level_id": level_id.to_hex(),
+        "rule_ids": [rule_id.to_hex()],
+        "params": {},
+        "metadata": {},
+        "content": content,
+    })
+}
+
+#[tokio::test]
+async fn test_blacklist_entry_blocks_then_warns_on_template_create() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+    let token = admin_jwt(&state);
+    let (level_id, rule_id) = insert_level_and_rule(&state).await;
+    // Уникальный термин, чтобы не задеть шаблоны параллельных тестов
+    let term = format!("запрет{}", Uuid::new_v4().simple());
+    let content = format!("Текст с {} внутри", term);
+
+    let (status, entry) = send(
+        &app,
+        "POST",
+        "/admin/content/blacklist",
+        &token,
+        Some(json!({ "term": term, "kind": "term", "severity": "block" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{entry}");
+    let entry_id = entry["id"].as_str().unwrap().to_string();
+
+    let (status, body) = send(
+        &app,
+        "POST",
+        "/admin/content/blacklist",
+        &token,
+        Some(json!({ "term": format!(" {} ", term) })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "INVALID_BLACKLIST_ENTRY");
+    let (status, body) = send(
+        &app,
+        "POST",
+        "/admin/content/blacklist",
+        &token,
+        Some(json!({ "term": "[unclosed", "kind": "regex" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "INVALID_BLACKLIST_ENTRY");
+
+    let (status, body) = send(
+        &app,
+        "POST",
+        "/admin/templates",
+        &token,
+        Some(template_body(&level_id, &rule_id, &content)),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "BLACKLIST_VIOLATION");
+    assert!(
+        body["message"]
+            .as_str()
+            .unwrap()
+            .contains(&format!("content:{}", term)),
+        "{body}"
+    );
+
+    let (status, entry) = send(
+        &app,
+        "PUT",
+        &format!("/admin/content/blacklist/{}", entry_id),
+        &token,
+        Some(json!({ "term": term, "kind": "term", "severity": "warn" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(entry["severity"], "warn");
+
+    let (status, template) = send(
+        &app,
+        "POST",
+        "/admin/templates",
+        &token,
+        Some(template_body(&level_id, &rule_id, &content)),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{template}");
+    assert_eq!(
+        template["blacklist_warnings"],
+        json!([format!("content:{}", term)])
+    );
+
+    let (status, issues) = send(&app, "POST", "/admin/templates/validate", &token, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let issue = issues
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|issue| issue["template_id"] == template["id"] && issue["severity"] == "warning")
+        .expect("blacklist warning should be reported");
+    assert!(issue["reason"]
+        .as_str()
+        .unwrap()
+        .starts_with("Blacklist warning"));
+
+    let uri = format!("/admin/content/blacklist/{}", entry_id);
+    let (status, _) = send(&app, "DELETE", &uri, &token, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = send(&app, "DELETE", &uri, &token, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,133 @@
+use anyhow::Result;
+use chrono::Utc;
+use redis::Client as RedisClient;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use mongodb::Client as MongoClient;
+use trainingground_api::{
+    config::Config,
+    middlewares::auth::JwtClaims,
+    models::content::{
+        LevelCreateRequest, LevelDifficulty, RuleCreateRequest, TemplateCreateRequest,
+        TopicCreateRequest,
+    },
+    models::content_search::ContentEntityType,
+    services::{
+        content_search_service::ContentSearchService, content_service::ContentService, AppState,
+    },
+};
+
+async fn build_test_state() -> Result<(Arc<AppState>, ContentService, JwtClaims)> {
+    dotenvy::from_filename(".env.test").ok();
+    let config = Config::load()?;
+    let mongo_client = MongoClient::with_uri_str(&config.mongo_uri).await?;
+    let redis_client = RedisClient::open(config.redis_uri.clone())?;
+    let state: Arc<AppState> =
+        Arc::new(AppState::new(config.clone(), mongo_client, redis_client).await?);
+    let now = Utc::now();
+    let claims = JwtClaims {
+        sub: Uuid::new_v4().to_string(),
+        role: "content_admin".to_string(),
+        group_ids: vec![],
+        iat: now.timestamp() as usize,
+        exp: (now.timestamp() + 3600) as usize,
+    };
+    Ok((state.clone(), ContentService::new(&state), claims))
+}
+
+#[tokio::test]
+async fn test_content_search_groups_hits_with_snippets() -> Result<()> {
+    let (state, service, claims) = build_test_state().await?;
+    // Уникальное слово, которого нет в остальном контенте
+    let token = format!("zephyr{}", &Uuid::new_v4().simple().to_string()[..10]);
+
+    let topic = service
+        .create_topic(
+            TopicCreateRequest {
+                slug: format!("topic-{}", Uuid::new_v4()),
+                name: "Search Topic".to_string(),
+                description: format!("Тема про {} и гласные", token),
+                icon_url: None,
+                status: None,
+                age_band: None,
+            },
+            &claims,
+        )
+        .await?;
+
+    let level = service
+        .create_level(
+            LevelCreateRequest {
+                topic_id: topic.id.to_string(),
+                name: "Level".to_string(),
+                difficulty: LevelDifficulty::B1,
+                description: "Test".to_string(),
+                min_pass_percent: None,
+                order: Some(1),
+                prerequisite_level_ids: vec![],
+            },
+            &claims,
+        )
+        .await?;
+
+    let rule = service
+        .create_rule(
+            RuleCreateRequest {
+                slug: format!("rule-{}", Uuid::new_v4()),
+                name: "Search Rule".to_string(),
+                category: "orthography".to_string(),
+                description: "Rule without the token".to_string(),
+                examples: vec![format!("Пример: {}", token)],
+                exceptions: vec![],
+                sources: vec![],
+                status: None,
+            },
+            &claims,
+        )
+        .await?;
+
+    let template = service
+        .create_template(
+            TemplateCreateRequest {
+                slug: format!("template-{}", Uuid::new_v4()),
+                level_id: level.id.to_string(),
+                rule_ids: vec![rule.id.to_string()],
+                params: serde_json::json!({}),
+                metadata: serde_json::json!({ "title": "Search template" }),
+                content: format!("Вставьте пропущенную букву: {} <b>здесь</b>", token),
+                difficulty: None,
+                source_refs: vec![],
+                age_band: None,
+                locale: None,
+                translations: Default::default(),
+            },
+            &claims,
+        )
+        .await?;
+
+    let search = ContentSearchService::new(state.mongo.clone());
+    let results = search.search(&token.to_uppercase(), Some(5)).await?;
+
+    assert_eq!(results.templates.len(), 1);
+    let hit = &results.templates[0];
+    assert_eq!(hit.entity_type, ContentEntityType::Template);
+    assert_eq!(hit.id, template.id);
+    assert_eq!(hit.title, "Search template");
+    assert_eq!(hit.field, "content");
+    assert!(hit.snippet.contains(&format!("<mark>{}</mark>", token)));
+    assert!(hit.snippet.contains("&lt;b&gt;"));
+    assert!(hit.score > 0.0);
+
+    assert_eq!(results.rules.len(), 1);
+    assert_eq!(results.rules[0].id, rule.id.to_string());
+    assert_eq!(results.rules[0].field, "examples");
+
+    assert_eq!(results.topics.len(), 1);
+    assert_eq!(results.topics[0].entity_type, ContentEntityType::Topic);
+    assert!(results.topics[0]
+        .snippet
+        .contains(&format!("<mark>{}</mark>", token)));
+
+    Ok(())
+}
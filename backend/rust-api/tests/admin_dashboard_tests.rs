@@ -0,0 +1,249 @@
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use mongodb::bson::{doc, oid::ObjectId, DateTime as BsonDateTime, Document};
+use redis::AsyncCommands;
+use serde_json::json;
+use tower::ServiceExt;
+use trainingground_api::{
+    create_router,
+    services::{
+        dashboard_service::{DASHBOARD_CACHE_KEY, DASHBOARD_CACHE_TTL_SECS},
+        AppState,
+    },
+};
+
+mod common;
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_dashboard_counts_and_cache() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+    let admin_token = create_admin_with_token(&app, &state).await;
+    let mut redis = state.redis.clone();
+    let _: () = redis.del(DASHBOARD_CACHE_KEY).await.unwrap();
+
+    let before = get_dashboard(&app, &admin_token).await;
+
+    let seeded = seed_entities(&state).await;
+
+    // Данные изменились, но сводка ещё из кэша
+    let cached = get_dashboard(&app, &admin_token).await;
+    assert_eq!(cached, before);
+    let ttl: i64 = redis.ttl(DASHBOARD_CACHE_KEY).await.unwrap();
+    assert!(
+        ttl > 0 && ttl <= DASHBOARD_CACHE_TTL_SECS as i64,
+        "unexpected ttl {ttl}"
+    );
+
+    // Истечение TTL
+    let _: () = redis.del(DASHBOARD_CACHE_KEY).await.unwrap();
+    let after = get_dashboard(&app, &admin_token).await;
+
+    let delta = |path: &[&str]| count(&after, path) - count(&before, path);
+    assert_eq!(delta(&["users", "total"]), 1);
+    assert_eq!(delta(&["users", "active"]), 1);
+    assert_eq!(delta(&["users", "blocked"]), 1);
+    assert_eq!(delta(&["groups"]), 1);
+    assert_eq!(delta(&["templates", "draft"]), 1);
+    assert_eq!(delta(&["templates", "published"]), 1);
+    assert_eq!(delta(&["open_incidents"]), 1);
+    assert_eq!(delta(&["pending_exports"]), 2);
+    assert_eq!(delta(&["sessions_started_today"]), 1);
+    assert!(after["queue_length"].is_u64());
+    assert_ne!(after["generated_at"], before["generated_at"]);
+
+    cleanup(&state, seeded).await;
+    let _: () = redis.del(DASHBOARD_CACHE_KEY).await.unwrap();
+}
+
+struct Seeded {
+    user_id: ObjectId,
+    group_id: ObjectId,
+    template_ids: Vec<ObjectId>,
+    incident_id: ObjectId,
+    export_ids: Vec<ObjectId>,
+    session_id: String,
+}
+
+async fn seed_entities(state: &AppState) -> Seeded {
+    let db = &state.mongo;
+    let now = BsonDateTime::now();
+
+    let user_id = ObjectId::new();
+    db.collection::<Document>("users")
+        .insert_one(doc! {
+            "_id": user_id,
+            "email": format!("dashboard-{}@test.com", user_id),
+            "name": "Dashboard Seed",
+            "role": "student",
+            "group_ids": [],
+            "is_blocked": true,
+            "lastLoginAt": now,
+            "createdAt": now,
+            "updatedAt": now,
+        })
+        .await
+        .unwrap();
+
+    let group_id = ObjectId::new();
+    db.collection::<Document>("groups")
+        .insert_one(doc! { "_id": group_id, "name": format!("Dashboard {}", group_id) })
+        .await
+        .unwrap();
+
+    let template_ids = vec![ObjectId::new(), ObjectId::new()];
+    db.collection::<Document>("templates")
+        .insert_many([
+            doc! { "_id": template_ids[0], "slug": format!("dashboard-{}", template_ids[0]), "status": "draft" },
+            doc! { "_id": template_ids[1], "slug": format!("dashboard-{}", template_ids[1]), "status": "published" },
+        ])
+        .await
+        .unwrap();
+
+    let incident_id = ObjectId::new();
+    db.collection::<Document>("incidents")
+        .insert_one(doc! { "_id": incident_id, "status": "open", "severity": "low" })
+        .await
+        .unwrap();
+
+    let export_ids = vec![ObjectId::new(), ObjectId::new(), ObjectId::new()];
+    db.collection::<Document>("report_exports")
+        .insert_many([
+            doc! { "_id": export_ids[0], "status": "pending" },
+            doc! { "_id": export_ids[1], "status": "processing" },
+            doc! { "_id": export_ids[2], "status": "ready" },
+        ])
+        .await
+        .unwrap();
+
+    let session_id = format!("dashboard-{}", ObjectId::new());
+    db.collection::<Document>("sessions")
+        .insert_one(doc! {
+            "_id": &session_id,
+            "user_id": user_id.to_hex(),
+            "task_id": "test-task",
+            "started_at": now,
+            "expires_at": now,
+            "status": "completed",
+        })
+        .await
+        .unwrap();
+
+    Seeded {
+        user_id,
+        group_id,
+        template_ids,
+        incident_id,
+        export_ids,
+        session_id,
+    }
+}
+
+async fn cleanup(state: &AppState, seeded: Seeded) {
+    let db = &state.mongo;
+    db.collection::<Document>("users")
+        .delete_one(doc! { "_id": seeded.user_id })
+        .await
+        .unwrap();
+    db.collection::<Document>("groups")
+        .delete_one(doc! { "_id": seeded.group_id })
+        .await
+        .unwrap();
+    db.collection::<Document>("templates")
+        .delete_many(doc! { "_id": { "$in": seeded.template_ids } })
+        .await
+        .unwrap();
+    db.collection::<Document>("incidents")
+        .delete_one(doc! { "_id": seeded.incident_id })
+        .await
+        .unwrap();
+    db.collection::<Document>("report_exports")
+        .delete_many(doc! { "_id": { "$in": seeded.export_ids } })
+        .await
+        .unwrap();
+    db.collection::<Document>("sessions")
+        .delete_one(doc! { "_id": seeded.session_id })
+        .await
+        .unwrap();
+}
+
+fn count(summary: &serde_json::Value, path: &[&str]) -> i64 {
+    path.iter()
+        .fold(summary, |value, key| &value[*key])
+        .as_i64()
+        .unwrap_or_else(|| panic!("{path:?} missing from {summary:?}"))
+}
+
+async fn get_dashboard(app: &axum::Router, token: &str) -> serde_json::Value {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/admin/dashboard")
+                .header("authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+async fn create_admin_with_token(app: &axum::Router, state: &AppState) -> String {
+    let email = format!("dashboard-admin-{}@test.com", uuid::Uuid::new_v4());
+    let password = "Admin123!@#";
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/auth/register")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    json!({ "email": email, "password": password, "name": "Dashboard Admin" })
+                        .to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    let user_id = ObjectId::parse_str(json["user"]["id"].as_str().unwrap()).unwrap();
+    state
+        .mongo
+        .collection::<Document>("users")
+        .update_one(
+            doc! { "_id": user_id },
+            doc! { "$set": { "role": "admin" } },
+        )
+        .await
+        .unwrap();
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/auth/login")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    json!({ "email": email, "password": password }).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    json["access_token"].as_str().unwrap().to_string()
+}
@@ -0,0 +1,331 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::Utc;
+use mongodb::bson::{doc, oid::ObjectId, Bson, Document};
+use redis::Client as RedisClient;
+use serde_json::json;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+use mongodb::Client as MongoClient;
+use trainingground_api::{
+    config::Config,
+    middlewares::auth::JwtClaims,
+    models::content::{
+        EmbeddingJobCancelOutcome, EmbeddingJobListQuery, EmbeddingJobRetryOutcome,
+        EmbeddingRebuildRequest, LevelCreateRequest, LevelDifficulty, RuleCreateRequest,
+        TemplateCreateRequest, TopicCreateRequest,
+    },
+    services::{
+        content_service::ContentService,
+        embedding_worker::{EmbeddingBackend, EmbeddingJobWorker},
+        AppState,
+    },
+};
+
+async fn build_test_state() -> Result<(Arc<AppState>, ContentService, JwtClaims)> {
+    dotenvy::from_filename(".env.test").ok();
+    let mut config = Config::load()?;
+    // Батч из одного шаблона, чтобы отмена срабатывала между шаблонами
+    config.content.embedding_batch_size = 1;
+    let mongo_client = MongoClient::with_uri_str(&config.mongo_uri).await?;
+    let redis_client = RedisClient::open(config.redis_uri.clone())?;
+    let state: Arc<AppState> =
+        Arc::new(AppState::new(config.clone(), mongo_client, redis_client).await?);
+    let now = Utc::now();
+    let claims = JwtClaims {
+        sub: Uuid::new_v4().to_string(),
+        role: "content_admin".to_string(),
+        group_ids: vec![],
+        iat: now.timestamp() as usize,
+        exp: (now.timestamp() + 3600) as usize,
+    };
+    Ok((state.clone(), ContentService::new(&state), claims))
+}
+
+/// Медленный бэкенд: первый батч ждёт сигнала от теста, шаблоны из `failing` не обрабатываются
+#[derive(Default)]
+struct SlowBackend {
+    gate_first_batch: AtomicBool,
+    started: Notify,
+    release: Notify,
+    failing: HashSet<ObjectId>,
+}
+
+#[async_trait]
+impl EmbeddingBackend for SlowBackend {
+    async fn embed_templates(&self, templates: &[Document]) -> Result<Vec<ObjectId>> {
+        if self.gate_first_batch.swap(false, Ordering::SeqCst) {
+            self.started.notify_one();
+            self.release.notified().await;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        Ok(templates
+            .iter()
+            .filter_map(|template| template.get_object_id("_id").ok())
+            .filter(|id| self.failing.contains(id))
+            .collect())
+    }
+}
+
+async fn create_templates(
+    service: &ContentService,
+    claims: &JwtClaims,
+    count: usize,
+) -> Result<Vec<String>> {
+    let topic = service
+        .create_topic(
+            TopicCreateRequest {
+                slug: format!("topic-{}", Uuid::new_v4()),
+                name: "Embedding Jobs Topic".to_string(),
+                description: "Topic for embedding jobs".to_string(),
+                icon_url: None,
+                status: None,
+                age_band: None,
+            },
+            claims,
+        )
+        .await?;
+    let level = service
+        .create_level(
+            LevelCreateRequest {
+                topic_id: topic.id.to_hex(),
+                name: "Embedding Jobs Level".to_string(),
+                difficulty: LevelDifficulty::A2,
+                description: "Level for embedding jobs".to_string(),
+                min_pass_percent: None,
+                order: None,
+                prerequisite_level_ids: vec![],
+            },
+            claims,
+        )
+        .await?;
+    let rule = service
+        .create_rule(
+            RuleCreateRequest {
+                slug: format!("rule-{}", Uuid::new_v4()),
+                name: "Embedding Jobs Rule".to_string(),
+                category: "Embedding".to_string(),
+                description: "Rule for embedding jobs".to_string(),
+                examples: vec![],
+                exceptions: vec![],
+                sources: vec![],
+                status: None,
+            },
+            claims,
+        )
+        .await?;
+
+    let mut ids = Vec::with_capacity(count);
+    for index in 0..count {
+        let template = service
+            .create_template(
+                TemplateCreateRequest {
+                    slug: format!("embed-job-{}-{}", index, Uuid::new_v4()),
+                    level_id: level.id.to_hex(),
+                    rule_ids: vec![rule.id.to_hex()],
+                    params: json!({ "type": "text_input" }),
+                    metadata: json!({ "correct_answer": "42" }),
+                    content: format!("Embedding job template {}", index),
+                    difficulty: Some("A2".to_string()),
+                    source_refs: vec![],
+                    age_band: None,
+                    locale: None,
+                    translations: Default::default(),
+                },
+                claims,
+            )
+            .await?;
+        ids.push(template.id);
+    }
+    Ok(ids)
+}
+
+async fn enqueue_selected(service: &ContentService, template_ids: &[String]) -> Result<ObjectId> {
+    let job = service
+        .rebuild_embeddings(EmbeddingRebuildRequest {
+            mode: "selected".to_string(),
+            template_ids: Some(template_ids.to_vec()),
+        })
+        .await?;
+    assert_eq!(job.status, "queued");
+    Ok(ObjectId::parse_str(&job.id)?)
+}
+
+#[tokio::test]
+async fn test_cancel_stops_running_job_between_batches() -> Result<()> {
+    let (state, service, claims) = build_test_state().await?;
+    let template_ids = create_templates(&service, &claims, 3).await?;
+    let job_id = enqueue_selected(&service, &template_ids).await?;
+
+    let backend = Arc::new(SlowBackend {
+        gate_first_batch: AtomicBool::new(true),
+        ..Default::default()
+    });
+    let worker = EmbeddingJobWorker::new(
+        state.mongo.clone(),
+        backend.clone(),
+        state.config.content.clone(),
+    );
+    let handle = tokio::spawn(async move { worker.run_job(&job_id).await });
+
+    // Первый батч в работе - запрашиваем отмену и отпускаем бэкенд
+    backend.started.notified().await;
+    let cancelling = match service.cancel_embedding_job(&job_id, &claims).await? {
+        EmbeddingJobCancelOutcome::Cancelling(summary) => summary,
+        other => panic!("Expected cancelling outcome, got {:?}", other),
+    };
+    assert_eq!(cancelling.status, "cancelling");
+    assert!(cancelling.cancel_requested_at.is_some());
+    assert_eq!(
+        cancelling.cancelled_by.as_deref(),
+        Some(claims.sub.as_str())
+    );
+    backend.release.notify_one();
+
+    let finished = handle.await??.expect("Job should have been queued");
+    assert_eq!(finished.status, "cancelled");
+    assert_eq!(finished.total, 3);
+    assert_eq!(finished.processed, 1);
+    assert_eq!(finished.failed_count, 0);
+    assert!(finished.finished_at.is_some());
+
+    match service.cancel_embedding_job(&job_id, &claims).await? {
+        EmbeddingJobCancelOutcome::NotActive(status) => assert_eq!(status, "cancelled"),
+        other => panic!("Expected not active outcome, got {:?}", other),
+    }
+    assert!(matches!(
+        service.retry_failed_embeddings(&job_id, &claims).await?,
+        EmbeddingJobRetryOutcome::NoFailedTemplates
+    ));
+    assert!(matches!(
+        service
+            .cancel_embedding_job(&ObjectId::new(), &claims)
+            .await?,
+        EmbeddingJobCancelOutcome::NotFound
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cancel_queued_job_finishes_it_immediately() -> Result<()> {
+    let (state, service, claims) = build_test_state().await?;
+    let template_ids = create_templates(&service, &claims, 1).await?;
+    let job_id = enqueue_selected(&service, &template_ids).await?;
+
+    match service.cancel_embedding_job(&job_id, &claims).await? {
+        EmbeddingJobCancelOutcome::Cancelling(summary) => {
+            assert_eq!(summary.status, "cancelled");
+            assert_eq!(summary.processed, 0);
+        }
+        other => panic!("Expected cancelled outcome, got {:?}", other),
+    }
+
+    // Отменённое задание воркер уже не берёт
+    let worker = EmbeddingJobWorker::new(
+        state.mongo.clone(),
+        Arc::new(SlowBackend::default()),
+        state.config.content.clone(),
+    );
+    assert!(worker.run_job(&job_id).await?.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_retry_failed_enqueues_only_failed_templates() -> Result<()> {
+    let (state, service, claims) = build_test_state().await?;
+    let template_ids = create_templates(&service, &claims, 2).await?;
+    let failing_id = ObjectId::parse_str(&template_ids[1])?;
+    let job_id = enqueue_selected(&service, &template_ids).await?;
+
+    match service.retry_failed_embeddings(&job_id, &claims).await? {
+        EmbeddingJobRetryOutcome::NotFinished(status) => assert_eq!(status, "queued"),
+        other => panic!("Expected not finished outcome, got {:?}", other),
+    }
+
+    let worker = EmbeddingJobWorker::new(
+        state.mongo.clone(),
+        Arc::new(SlowBackend {
+            failing: HashSet::from([failing_id]),
+            ..Default::default()
+        }),
+        state.config.content.clone(),
+    );
+    let finished = worker.run_job(&job_id).await?.expect("Job was queued");
+    assert_eq!(finished.status, "completed");
+    assert_eq!(finished.processed, 2);
+    assert_eq!(finished.failed_count, 1);
+
+    let retry = match service.retry_failed_embeddings(&job_id, &claims).await? {
+        EmbeddingJobRetryOutcome::Enqueued(summary) => summary,
+        other => panic!("Expected enqueued outcome, got {:?}", other),
+    };
+    assert_eq!(retry.mode, "retry_failed");
+    assert_eq!(retry.status, "queued");
+    assert_eq!(retry.total, 1);
+    assert_eq!(retry.retry_of, Some(job_id.to_hex()));
+
+    let retry_id = ObjectId::parse_str(&retry.id)?;
+    let record = state
+        .mongo
+        .collection::<Document>("embedding_jobs")
+        .find_one(doc! { "_id": retry_id })
+        .await?
+        .expect("Retry job record");
+    assert_eq!(
+        record.get_array("template_ids")?,
+        &[Bson::ObjectId(failing_id)]
+    );
+
+    let worker = EmbeddingJobWorker::new(
+        state.mongo.clone(),
+        Arc::new(SlowBackend::default()),
+        state.config.content.clone(),
+    );
+    let retried = worker.run_job(&retry_id).await?.expect("Retry was queued");
+    assert_eq!(retried.status, "completed");
+    assert_eq!(retried.processed, 1);
+    assert_eq!(retried.failed_count, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_list_embedding_jobs_is_paginated_newest_first() -> Result<()> {
+    let (_state, service, claims) = build_test_state().await?;
+    let template_ids = create_templates(&service, &claims, 1).await?;
+    let first = enqueue_selected(&service, &template_ids).await?;
+    let second = enqueue_selected(&service, &template_ids).await?;
+
+    let page = service
+        .list_embedding_jobs(EmbeddingJobListQuery {
+            limit: Some(1),
+            offset: Some(0),
+        })
+        .await?;
+    assert_eq!(page.limit, 1);
+    assert_eq!(page.jobs.len(), 1);
+    assert!(page.total >= 2);
+
+    let all = service
+        .list_embedding_jobs(EmbeddingJobListQuery {
+            limit: Some(100),
+            offset: None,
+        })
+        .await?;
+    let position = |id: &ObjectId| all.jobs.iter().position(|job| job.id == id.to_hex());
+    let first_position = position(&first).expect("First job listed");
+    let second_position = position(&second).expect("Second job listed");
+    assert!(second_position < first_position);
+
+    for job_id in [first, second] {
+        service.cancel_embedding_job(&job_id, &claims).await?;
+    }
+    Ok(())
+}
@@ -618,3 +618,703 @@ async fn test_export_groups_returns_csv() {
         "export CSV should contain header"
     );
 }
+
+/// Helper: запрос к admin API с CSRF, возвращает статус и JSON тела
+async fn send_admin(
+    app: &axum::Router,
+    admin_token: &str,
+    csrf: &(String, String),
+    method: &str,
+    uri: &str,
+    body: Option<serde_json::Value>,
+) -> (StatusCode, serde_json::Value) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .header("authorization", format!("Bearer {}", admin_token))
+        .header("x-csrf-token", csrf.0.as_str())
+        .header("cookie", format!("csrf_token={}", csrf.1))
+        .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+        .unwrap();
+
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or_default())
+}
+
+/// Helper: создать ученика через admin API
+async fn create_student(app: &axum::Router, admin_token: &str, csrf: &(String, String)) -> String {
+    let (status, body) = send_admin(
+        app,
+        admin_token,
+        csrf,
+        "POST",
+        "/admin/users",
+        Some(json!({
+            "email": format!("member+{}@test.com", Uuid::new_v4()),
+            "password": "Student123!@#",
+            "name": "Member Student",
+            "role": "student",
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "body: {}", body);
+    body["id"].as_str().unwrap().to_string()
+}
+
+/// Helper: studentCount группы и число учеников с этой группой в users.group_ids
+async fn stored_member_counts(group_id: &str) -> (i64, u64) {
+    let config = trainingground_api::config::Config::load().unwrap();
+    let mongo_client = mongodb::Client::with_uri_str(&config.mongo_uri)
+        .await
+        .unwrap();
+    let db = mongo_client.database(&config.mongo_database);
+    let group = db
+        .collection::<mongodb::bson::Document>("groups")
+        .find_one(mongodb::bson::doc! {
+            "_id": mongodb::bson::oid::ObjectId::parse_str(group_id).unwrap()
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    let stored = match group.get("studentCount") {
+        Some(mongodb::bson::Bson::Int32(count)) => i64::from(*count),
+        Some(mongodb::bson::Bson::Int64(count)) => *count,
+        other => panic!("unexpected studentCount: {other:?}"),
+    };
+    let actual = db
+        .collection::<mongodb::bson::Document>("users")
+        .count_documents(mongodb::bson::doc! { "group_ids": group_id, "role": "student" })
+        .await
+        .unwrap();
+    (stored, actual)
+}
+
+#[tokio::test]
+async fn test_group_members_add_remove_keep_counts_consistent() {
+    let app = common::create_test_app().await;
+    let (_admin_id, admin_token) = create_admin_with_token(&app).await;
+    let csrf = get_csrf_token(&app).await;
+
+    let (status, group) = send_admin(
+        &app,
+        &admin_token,
+        &csrf,
+        "POST",
+        "/admin/groups",
+        Some(json!({ "name": "Members Group", "school": "School №3" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let group_id = group["id"].as_str().unwrap().to_string();
+    let student_id = create_student(&app, &admin_token, &csrf).await;
+    let members_uri = format!("/admin/groups/{}/members", group_id);
+
+    // Добавление
+    let (status, body) = send_admin(
+        &app,
+        &admin_token,
+        &csrf,
+        "POST",
+        &members_uri,
+        Some(json!({ "user_id": student_id })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "body: {}", body);
+    assert_eq!(body["student_count"], 1);
+    assert_eq!(stored_member_counts(&group_id).await, (1, 1));
+
+    // Повторное добавление ничего не меняет
+    let (status, body) = send_admin(
+        &app,
+        &admin_token,
+        &csrf,
+        "POST",
+        &members_uri,
+        Some(json!({ "user_id": student_id })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["student_count"], 1);
+    assert_eq!(stored_member_counts(&group_id).await, (1, 1));
+
+    let (status, members) = send_admin(&app, &admin_token, &csrf, "GET", &members_uri, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let members = members.as_array().unwrap();
+    assert_eq!(members.len(), 1);
+    assert_eq!(members[0]["id"], student_id.as_str());
+
+    // Удаление
+    let member_uri = format!("{}/{}", members_uri, student_id);
+    let (status, _) = send_admin(&app, &admin_token, &csrf, "DELETE", &member_uri, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert_eq!(stored_member_counts(&group_id).await, (0, 0));
+
+    let (status, body) = send_admin(&app, &admin_token, &csrf, "DELETE", &member_uri, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], "GROUP_MEMBER_NOT_FOUND");
+
+    // Изменение group_ids через пользователя тоже обновляет счётчик
+    let (status, _) = send_admin(
+        &app,
+        &admin_token,
+        &csrf,
+        "PATCH",
+        &format!("/admin/users/{}", student_id),
+        Some(json!({ "group_ids": [group_id] })),
+    )
+    .await;
+    assert!(status.is_success());
+    assert_eq!(stored_member_counts(&group_id).await, (1, 1));
+
+    let (status, _) = send_admin(
+        &app,
+        &admin_token,
+        &csrf,
+        "DELETE",
+        &format!("/admin/users/{}", student_id),
+        None,
+    )
+    .await;
+    assert!(status.is_success());
+    assert_eq!(stored_member_counts(&group_id).await, (0, 0));
+}
+
+#[tokio::test]
+async fn test_group_member_capacity_is_enforced() {
+    let app = common::create_test_app().await;
+    let (_admin_id, admin_token) = create_admin_with_token(&app).await;
+    let csrf = get_csrf_token(&app).await;
+
+    let (status, group) = send_admin(
+        &app,
+        &admin_token,
+        &csrf,
+        "POST",
+        "/admin/groups",
+        Some(json!({ "name": "Small Group", "school": "School №4", "max_students": 1 })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(group["max_students"], 1);
+    let group_id = group["id"].as_str().unwrap().to_string();
+    let members_uri = format!("/admin/groups/{}/members", group_id);
+
+    let first = create_student(&app, &admin_token, &csrf).await;
+    let second = create_student(&app, &admin_token, &csrf).await;
+
+    let (status, _) = send_admin(
+        &app,
+        &admin_token,
+        &csrf,
+        "POST",
+        &members_uri,
+        Some(json!({ "user_id": first })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, body) = send_admin(
+        &app,
+        &admin_token,
+        &csrf,
+        "POST",
+        &members_uri,
+        Some(json!({ "user_id": second })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["code"], "GROUP_FULL");
+    assert_eq!(stored_member_counts(&group_id).await, (1, 1));
+}
+
+/// Helper: создать учителя с уникальным email и войти под ним (токен с текущими group_ids)
+async fn create_teacher_account(
+    app: &axum::Router,
+    admin_token: &str,
+    csrf: &(String, String),
+) -> (String, String) {
+    let email = format!("curator+{}@test.com", Uuid::new_v4());
+    let (status, body) = send_admin(
+        app,
+        admin_token,
+        csrf,
+        "POST",
+        "/admin/users",
+        Some(json!({
+            "email": email,
+            "password": "Teacher123!@#",
+            "name": "Curator Teacher",
+            "role": "teacher",
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "body: {}", body);
+    (body["id"].as_str().unwrap().to_string(), email)
+}
+
+async fn login(app: &axum::Router, email: &str, password: &str) -> String {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/auth/login")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    json!({ "email": email, "password": password }).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let response_json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    response_json["access_token"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn test_reassign_curator_updates_teacher_groups() {
+    let app = common::create_test_app().await;
+    let (_admin_id, admin_token) = create_admin_with_token(&app).await;
+    let csrf = get_csrf_token(&app).await;
+
+    let (first_id, _) = create_teacher_account(&app, &admin_token, &csrf).await;
+    let (second_id, _) = create_teacher_account(&app, &admin_token, &csrf).await;
+    let (_, group) = send_admin(
+        &app,
+        &admin_token,
+        &csrf,
+        "POST",
+        "/admin/groups",
+        Some(json!({ "name": "Curated Group", "school": "School №5" })),
+    )
+    .await;
+    let group_id = group["id"].as_str().unwrap().to_string();
+    let curator_uri = format!("/admin/groups/{}/curator", group_id);
+
+    for curator in [&first_id, &second_id] {
+        let (status, body) = send_admin(
+            &app,
+            &admin_token,
+            &csrf,
+            "PUT",
+            &curator_uri,
+            Some(json!({ "curator_id": curator })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "body: {}", body);
+        assert_eq!(body["curator_id"], curator.as_str());
+    }
+
+    let user_groups = |user_id: String| {
+        let app = app.clone();
+        let admin_token = admin_token.clone();
+        let csrf = csrf.clone();
+        async move {
+            let (_, user) = send_admin(
+                &app,
+                &admin_token,
+                &csrf,
+                "GET",
+                &format!("/admin/users/{}", user_id),
+                None,
+            )
+            .await;
+            user["group_ids"].clone()
+        }
+    };
+    assert_eq!(user_groups(first_id.clone()).await, json!([]));
+    assert_eq!(user_groups(second_id.clone()).await, json!([group_id]));
+
+    // Запись в аудите есть у обоих учителей
+    for teacher in [&first_id, &second_id] {
+        let (_, audit) = send_admin(
+            &app,
+            &admin_token,
+            &csrf,
+            "GET",
+            &format!(
+                "/admin/audit?event_type=reassign_curator&user_id={}",
+                teacher
+            ),
+            None,
+        )
+        .await;
+        assert!(
+            audit
+                .as_array()
+                .unwrap()
+                .iter()
+                .any(|entry| entry["details"].as_str().unwrap_or("").contains(&group_id)),
+            "missing curator audit entry for {teacher}: {audit}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_archived_group_hidden_but_stats_available() {
+    let app = common::create_test_app().await;
+    let (_admin_id, admin_token) = create_admin_with_token(&app).await;
+    let csrf = get_csrf_token(&app).await;
+
+    let (teacher_id, teacher_email) = create_teacher_account(&app, &admin_token, &csrf).await;
+    let (_, group) = send_admin(
+        &app,
+        &admin_token,
+        &csrf,
+        "POST",
+        "/admin/groups",
+        Some(json!({ "name": "Archived Group", "school": "School №6" })),
+    )
+    .await;
+    let group_id = group["id"].as_str().unwrap().to_string();
+    let (status, _) = send_admin(
+        &app,
+        &admin_token,
+        &csrf,
+        "PUT",
+        &format!("/admin/groups/{}/curator", group_id),
+        Some(json!({ "curator_id": teacher_id })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Снимок статистики группы, как его оставляет analytics worker
+    let config = trainingground_api::config::Config::load().unwrap();
+    let mongo_client = mongodb::Client::with_uri_str(&config.mongo_uri)
+        .await
+        .unwrap();
+    mongo_client
+        .database(&config.mongo_database)
+        .collection::<mongodb::bson::Document>("materialized_stats")
+        .insert_one(mongodb::bson::doc! {
+            "type": "group",
+            "entity_id": mongodb::bson::oid::ObjectId::parse_str(&group_id).unwrap(),
+            "metrics": { "avg_accuracy": 75.0 },
+            "calculatedAt": mongodb::bson::DateTime::now(),
+        })
+        .await
+        .unwrap();
+
+    let teacher_token = login(&app, &teacher_email, "Teacher123!@#").await;
+    let (_, groups) = send_admin(
+        &app,
+        &teacher_token,
+        &csrf,
+        "GET",
+        "/api/v1/teacher/groups",
+        None,
+    )
+    .await;
+    assert!(groups
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|group| group["id"] == group_id.as_str()));
+
+    let (status, archived) = send_admin(
+        &app,
+        &admin_token,
+        &csrf,
+        "POST",
+        &format!("/admin/groups/{}/archive", group_id),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(archived["archived_at"].is_string());
+
+    // Учитель и общий список групп архивную группу больше не видят
+    let (_, groups) = send_admin(
+        &app,
+        &teacher_token,
+        &csrf,
+        "GET",
+        "/api/v1/teacher/groups",
+        None,
+    )
+    .await;
+    assert!(!groups
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|group| group["id"] == group_id.as_str()));
+
+    let (_, listed) = send_admin(
+        &app,
+        &admin_token,
+        &csrf,
+        "GET",
+        "/admin/groups?search=Archived%20Group&include_archived=true&limit=100",
+        None,
+    )
+    .await;
+    assert!(listed
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|group| group["id"] == group_id.as_str()));
+
+    // Статистика и выгрузка продолжают работать
+    let (status, stats) = send_admin(
+        &app,
+        &teacher_token,
+        &csrf,
+        "GET",
+        &format!("/stats/groups/{}", group_id),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {}", stats);
+    assert_eq!(stats["group_id"], group_id.as_str());
+
+    let (status, export) = send_admin(
+        &app,
+        &teacher_token,
+        &csrf,
+        "POST",
+        &format!("/stats/groups/{}/export", group_id),
+        Some(json!({
+            "period": { "from": "2025-01-01T00:00:00Z", "to": "2025-12-31T00:00:00Z" },
+            "format": "csv",
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {}", export);
+    assert!(export["export_id"].is_string());
+}
+
+/// Helper: создать ученика с известным паролем и получить его токен
+async fn create_student_with_token(
+    app: &axum::Router,
+    admin_token: &str,
+    csrf: &(String, String),
+) -> (String, String) {
+    let email = format!("joiner+{}@test.com", Uuid::new_v4());
+    let (status, body) = send_admin(
+        app,
+        admin_token,
+        csrf,
+        "POST",
+        "/admin/users",
+        Some(json!({
+            "email": email,
+            "password": "Student123!@#",
+            "name": "Joining Student",
+            "role": "student",
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "body: {}", body);
+    let token = login(app, &email, "Student123!@#").await;
+    (body["id"].as_str().unwrap().to_string(), token)
+}
+
+/// Helper: группа с кодом приглашения; возвращает (group_id, code_id, code)
+async fn group_with_invite(
+    app: &axum::Router,
+    admin_token: &str,
+    csrf: &(String, String),
+    invite: serde_json::Value,
+) -> (String, String, String) {
+    let (_, group) = send_admin(
+        app,
+        admin_token,
+        csrf,
+        "POST",
+        "/admin/groups",
+        Some(json!({ "name": "Invite Group", "school": "School №7" })),
+    )
+    .await;
+    let group_id = group["id"].as_str().unwrap().to_string();
+
+    let (status, body) = send_admin(
+        app,
+        admin_token,
+        csrf,
+        "POST",
+        &format!("/admin/groups/{}/invite-codes", group_id),
+        Some(invite),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "body: {}", body);
+    (
+        group_id,
+        body["id"].as_str().unwrap().to_string(),
+        body["code"].as_str().unwrap().to_string(),
+    )
+}
+
+#[tokio::test]
+async fn test_join_group_with_invite_code_is_idempotent() {
+    let app = common::create_test_app().await;
+    let (_admin_id, admin_token) = create_admin_with_token(&app).await;
+    let csrf = get_csrf_token(&app).await;
+    let (group_id, _, code) =
+        group_with_invite(&app, &admin_token, &csrf, json!({ "max_uses": 5 })).await;
+    let (student_id, student_token) = create_student_with_token(&app, &admin_token, &csrf).await;
+
+    let (status, body) = send_admin(
+        &app,
+        &student_token,
+        &csrf,
+        "POST",
+        "/api/v1/groups/join",
+        Some(json!({ "code": code.to_lowercase() })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {}", body);
+    assert_eq!(body["joined"], true);
+    assert_eq!(body["group"]["id"], group_id.as_str());
+    assert_eq!(body["group"]["student_count"], 1);
+
+    // Повторный вход тем же кодом ничего не меняет и не расходует код
+    let (status, body) = send_admin(
+        &app,
+        &student_token,
+        &csrf,
+        "POST",
+        "/api/v1/groups/join",
+        Some(json!({ "code": code })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["joined"], false);
+    assert_eq!(body["group"]["student_count"], 1);
+    assert_eq!(stored_member_counts(&group_id).await, (1, 1));
+
+    let (_, codes) = send_admin(
+        &app,
+        &admin_token,
+        &csrf,
+        "GET",
+        &format!("/admin/groups/{}/invite-codes", group_id),
+        None,
+    )
+    .await;
+    assert_eq!(codes[0]["uses"], 1);
+
+    let (_, user) = send_admin(
+        &app,
+        &admin_token,
+        &csrf,
+        "GET",
+        &format!("/admin/users/{}", student_id),
+        None,
+    )
+    .await;
+    assert_eq!(user["group_ids"], json!([group_id]));
+}
+
+#[tokio::test]
+async fn test_exhausted_invite_code_is_rejected() {
+    let app = common::create_test_app().await;
+    let (_admin_id, admin_token) = create_admin_with_token(&app).await;
+    let csrf = get_csrf_token(&app).await;
+    let (group_id, _, code) =
+        group_with_invite(&app, &admin_token, &csrf, json!({ "max_uses": 1 })).await;
+    let (_, first_token) = create_student_with_token(&app, &admin_token, &csrf).await;
+    let (_, second_token) = create_student_with_token(&app, &admin_token, &csrf).await;
+
+    let (status, _) = send_admin(
+        &app,
+        &first_token,
+        &csrf,
+        "POST",
+        "/api/v1/groups/join",
+        Some(json!({ "code": code })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = send_admin(
+        &app,
+        &second_token,
+        &csrf,
+        "POST",
+        "/api/v1/groups/join",
+        Some(json!({ "code": code })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["code"], "INVITE_CODE_EXHAUSTED");
+    assert_eq!(stored_member_counts(&group_id).await, (1, 1));
+
+    // Исчерпанный код не показывается среди действующих
+    let (_, codes) = send_admin(
+        &app,
+        &admin_token,
+        &csrf,
+        "GET",
+        &format!("/admin/groups/{}/invite-codes", group_id),
+        None,
+    )
+    .await;
+    assert_eq!(codes, json!([]));
+}
+
+#[tokio::test]
+async fn test_expired_and_revoked_invite_codes_are_rejected() {
+    let app = common::create_test_app().await;
+    let (_admin_id, admin_token) = create_admin_with_token(&app).await;
+    let csrf = get_csrf_token(&app).await;
+    let (_, student_token) = create_student_with_token(&app, &admin_token, &csrf).await;
+
+    // Истёкший код
+    let (_, _, expired_code) = group_with_invite(&app, &admin_token, &csrf, json!({})).await;
+    let config = trainingground_api::config::Config::load().unwrap();
+    let mongo_client = mongodb::Client::with_uri_str(&config.mongo_uri)
+        .await
+        .unwrap();
+    mongo_client
+        .database(&config.mongo_database)
+        .collection::<mongodb::bson::Document>("group_invite_codes")
+        .update_one(
+            mongodb::bson::doc! { "code": &expired_code },
+            mongodb::bson::doc! { "$set": {
+                "expiresAt": mongodb::bson::DateTime::from_millis(
+                    chrono::Utc::now().timestamp_millis() - 60_000,
+                ),
+            } },
+        )
+        .await
+        .unwrap();
+
+    let (status, body) = send_admin(
+        &app,
+        &student_token,
+        &csrf,
+        "POST",
+        "/api/v1/groups/join",
+        Some(json!({ "code": expired_code })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::GONE);
+    assert_eq!(body["code"], "INVITE_CODE_EXPIRED");
+
+    // Отозванный код
+    let (group_id, code_id, revoked_code) =
+        group_with_invite(&app, &admin_token, &csrf, json!({})).await;
+    let revoke_uri = format!("/admin/groups/{}/invite-codes/{}", group_id, code_id);
+    let (status, _) = send_admin(&app, &admin_token, &csrf, "DELETE", &revoke_uri, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, body) = send_admin(
+        &app,
+        &student_token,
+        &csrf,
+        "POST",
+        "/api/v1/groups/join",
+        Some(json!({ "code": revoked_code })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::GONE);
+    assert_eq!(body["code"], "INVITE_CODE_REVOKED");
+    assert_eq!(stored_member_counts(&group_id).await, (0, 0));
+
+    let (status, _) = send_admin(&app, &admin_token, &csrf, "DELETE", &revoke_uri, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
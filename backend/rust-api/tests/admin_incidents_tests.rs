@@ -86,35 +86,132 @@ async fn test_update_incident_status() {
     let (csrf_token, csrf_cookie) = get_csrf_token(&app).await;
     let test_user_id = create_test_user(&app, &admin_token, &csrf_token, &csrf_cookie).await;
     let incident_id = insert_incident(&test_user_id, IncidentStatus::Open).await;
-
-    let (csrf_token_update, csrf_cookie_update) = get_csrf_token(&app).await;
-    let payload = json!({
-        "action": "resolve",
-        "note": "Checked by admin"
-    });
-
-    let response = app
-        .clone()
-        .oneshot(
-            Request::builder()
-                .method("PUT")
-                .uri(format!("/admin/incidents/{}", incident_id))
-                .header("authorization", format!("Bearer {}", admin_token))
-                .header("content-type", "application/json")
-                .header("x-csrf-token", &csrf_token_update)
-                .header("cookie", format!("csrf_token={}", csrf_cookie_update))
-                .body(Body::from(payload.to_string()))
-                .unwrap(),
-        )
-        .await
-        .unwrap();
-
-    assert_eq!(response.status(), StatusCode::OK);
-    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
-    let body_str = String::from_utf8(body.to_vec()).unwrap();
-    let json: serde_json::Value = serde_json::from_str(&body_str).unwrap();
+    let uri = format!("/admin/incidents/{}", incident_id);
+
+    // Без ответственного закрыть инцидент нельзя
+    let payload = json!({ "state": "resolved", "note": "Checked by admin" });
+    let (status, json) = send_json(&app, "PUT", &uri, &admin_token, payload).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(json["code"], "INCIDENT_UNASSIGNED");
+
+    // Взявший в работу становится ответственным
+    let (status, json) = send_json(
+        &app,
+        "PUT",
+        &uri,
+        &admin_token,
+        json!({ "state": "investigating" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["incident"]["status"], "investigating");
+    assert_eq!(json["incident"]["assigned_to"], admin_id);
+
+    // Прежний формат запроса по-прежнему работает
+    let payload = json!({ "action": "resolve", "note": "Checked by admin" });
+    let (status, json) = send_json(&app, "PUT", &uri, &admin_token, payload).await;
+    assert_eq!(status, StatusCode::OK);
     assert_eq!(json["incident"]["status"], "resolved");
     assert_eq!(json["incident"]["resolved_by"], admin_id);
+
+    let (status, json) = send_json(
+        &app,
+        "PUT",
+        &uri,
+        &admin_token,
+        json!({ "state": "investigating" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(json["code"], "INVALID_INCIDENT_TRANSITION");
+    assert_eq!(json["details"]["from"], "resolved");
+}
+
+#[tokio::test]
+async fn test_incident_comment_thread() {
+    let app = common::create_test_app().await;
+    let (admin_id, admin_token) = create_admin_with_token(&app).await;
+    let (csrf_token, csrf_cookie) = get_csrf_token(&app).await;
+    let test_user_id = create_test_user(&app, &admin_token, &csrf_token, &csrf_cookie).await;
+    let incident_id = insert_incident(&test_user_id, IncidentStatus::Open).await;
+    let comments_uri = format!("/admin/incidents/{}/comments", incident_id);
+
+    let (status, json) = send_json(
+        &app,
+        "POST",
+        &format!("/admin/incidents/{}/assign", incident_id),
+        &admin_token,
+        json!({ "assigned_to": admin_id }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["incident"]["assigned_to"], admin_id);
+
+    let (status, root) = send_json(
+        &app,
+        "POST",
+        &comments_uri,
+        &admin_token,
+        json!({ "body": "Похоже на вставку из буфера" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let root_id = root["id"].as_str().unwrap().to_string();
+
+    let (status, reply) = send_json(
+        &app,
+        "POST",
+        &comments_uri,
+        &admin_token,
+        json!({ "body": "Проверил запись сессии", "parent_id": root_id }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(reply["parent_id"], root_id);
+
+    let (status, json) = send_json(
+        &app,
+        "POST",
+        &comments_uri,
+        &admin_token,
+        json!({ "body": "Ответ в никуда", "parent_id": Uuid::new_v4().to_string() }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["code"], "INVALID_PARENT_COMMENT");
+
+    let (status, json) = send_json(
+        &app,
+        "GET",
+        &format!("/admin/incidents/{}", incident_id),
+        &admin_token,
+        serde_json::Value::Null,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let comments = json["comments"].as_array().expect("comments in detail");
+    assert_eq!(comments.len(), 2);
+    assert_eq!(comments[0]["id"], root_id);
+    assert_eq!(comments[1]["parent_id"], root_id);
+    assert_eq!(comments[1]["author_id"], admin_id);
+    assert!(json["incident"]["updated_at"].is_string());
+
+    let (status, json) = send_json(
+        &app,
+        "GET",
+        &format!(
+            "/admin/incidents?assigned_to={}&state=open&sort=updated_at",
+            admin_id
+        ),
+        &admin_token,
+        serde_json::Value::Null,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let listed = json.as_array().expect("response array");
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0]["incident"]["id"], incident_id);
+    assert!(listed[0].get("comments").is_none());
 }
 
 #[tokio::test]
@@ -239,6 +336,40 @@ async fn create_admin_with_token(app: &axum::Router) -> (String, String) {
     (user_id, access_token)
 }
 
+/// Запрос к админке с CSRF-токеном; `Null` отправляется без тела
+async fn send_json(
+    app: &axum::Router,
+    method: &str,
+    uri: &str,
+    token: &str,
+    body: serde_json::Value,
+) -> (StatusCode, serde_json::Value) {
+    let (csrf_token, csrf_cookie) = get_csrf_token(app).await;
+    let body = if body.is_null() {
+        Body::empty()
+    } else {
+        Body::from(body.to_string())
+    };
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("authorization", format!("Bearer {}", token))
+                .header("content-type", "application/json")
+                .header("x-csrf-token", &csrf_token)
+                .header("cookie", format!("csrf_token={}", csrf_cookie))
+                .body(body)
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or_default())
+}
+
 async fn get_csrf_token(app: &axum::Router) -> (String, String) {
     let response = app
         .clone()
@@ -319,6 +450,7 @@ async fn insert_incident(user_id: &str, status: IncidentStatus) -> String {
     let incident = IncidentRecord {
         id: Uuid::new_v4().to_string(),
         user_id: user_id.to_string(),
+        session_id: None,
         incident_type: IncidentType::SpeedViolation,
         severity: IncidentSeverity::Medium,
         details: IncidentDetails {
@@ -326,13 +458,19 @@ async fn insert_incident(user_id: &str, status: IncidentStatus) -> String {
             repeated_hits: None,
             time_window_seconds: Some(3600),
             additional_info: Some("Test incident".into()),
+            signal_counts: None,
         },
         timestamp: Utc::now(),
         action_taken: ActionTaken::Flagged,
         status,
+        assigned_to: None,
+        updated_at: None,
         resolved_by: None,
         resolved_at: None,
         resolution_note: None,
+        auto_block: None,
+        unblocked_by: None,
+        unblocked_at: None,
     };
 
     let doc = to_document(&incident).expect("serialize incident");
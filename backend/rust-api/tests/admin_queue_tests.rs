@@ -0,0 +1,124 @@
+use anyhow::Result;
+use chrono::Utc;
+use redis::Client as RedisClient;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use mongodb::Client as MongoClient;
+use trainingground_api::{
+    config::Config,
+    middlewares::auth::JwtClaims,
+    services::{content_service::ContentService, AppState},
+};
+
+/// Состояние с отдельным стримом, чтобы не пересекаться с реальными событиями
+async fn build_test_state() -> Result<(Arc<AppState>, ContentService, JwtClaims)> {
+    dotenvy::from_filename(".env.test").ok();
+    let mut config = Config::load()?;
+    config.content.stream_name = format!("test:content:changes:{}", Uuid::new_v4().simple());
+    let mongo_client = MongoClient::with_uri_str(&config.mongo_uri).await?;
+    let redis_client = RedisClient::open(config.redis_uri.clone())?;
+    let state: Arc<AppState> =
+        Arc::new(AppState::new(config.clone(), mongo_client, redis_client).await?);
+    let now = Utc::now();
+    let claims = JwtClaims {
+        sub: Uuid::new_v4().to_string(),
+        role: "admin".to_string(),
+        group_ids: vec![],
+        iat: now.timestamp() as usize,
+        exp: (now.timestamp() + 3600) as usize,
+    };
+    Ok((state.clone(), ContentService::new(&state), claims))
+}
+
+#[tokio::test]
+async fn test_unacked_entry_is_reported_as_stuck_and_can_be_claimed() -> Result<()> {
+    let (state, service, claims) = build_test_state().await?;
+    let stream = state.config.content.stream_name.clone();
+    let mut conn = state.redis.clone();
+
+    redis::cmd("XGROUP")
+        .arg("CREATE")
+        .arg(&stream)
+        .arg("explanations")
+        .arg("0")
+        .arg("MKSTREAM")
+        .query_async::<()>(&mut conn)
+        .await?;
+    let entry_id: String = redis::cmd("XADD")
+        .arg(&stream)
+        .arg("*")
+        .arg("template_id")
+        .arg("tpl-1")
+        .arg("action")
+        .arg("published")
+        .query_async(&mut conn)
+        .await?;
+    // Консьюмер читает событие и «падает», не отправив XACK
+    redis::cmd("XREADGROUP")
+        .arg("GROUP")
+        .arg("explanations")
+        .arg("worker-a")
+        .arg("COUNT")
+        .arg(1)
+        .arg("STREAMS")
+        .arg(&stream)
+        .arg(">")
+        .query_async::<redis::Value>(&mut conn)
+        .await?;
+
+    let status = service.queue_status(Some(0)).await?;
+    assert_eq!(status.length, 1);
+    assert_eq!(status.consumer_groups.len(), 1);
+    let group = &status.consumer_groups[0];
+    assert_eq!(group.name, "explanations");
+    assert_eq!(group.consumers, 1);
+    assert_eq!(group.pending, 1);
+    assert_eq!(group.last_delivered_id, entry_id);
+    assert_eq!(group.lag, Some(0));
+
+    assert_eq!(status.dead_letters.len(), 1);
+    let stuck = &status.dead_letters[0];
+    assert_eq!(stuck.id, entry_id);
+    assert_eq!(stuck.consumer, "worker-a");
+    assert_eq!(stuck.delivery_count, 1);
+    let payload = stuck.payload.as_ref().expect("entry still in stream");
+    assert_eq!(payload["template_id"], "tpl-1");
+
+    // С порогом в час свежее событие ещё не считается зависшим
+    let status = service.queue_status(Some(3600)).await?;
+    assert!(status.dead_letters.is_empty());
+    assert_eq!(status.consumer_groups[0].pending, 1);
+
+    let claimed = service
+        .claim_queue_entry(&entry_id, &claims)
+        .await?
+        .expect("entry is pending");
+    assert_eq!(claimed.group, "explanations");
+    assert!(claimed.consumer.starts_with("admin-claim-"));
+
+    let status = service.queue_status(Some(0)).await?;
+    assert_eq!(status.dead_letters[0].consumer, claimed.consumer);
+    assert_eq!(status.consumer_groups[0].consumers, 2);
+
+    assert!(service.claim_queue_entry("1-0", &claims).await?.is_none());
+
+    redis::cmd("DEL")
+        .arg(&stream)
+        .query_async::<()>(&mut conn)
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_queue_status_without_stream_has_no_groups() -> Result<()> {
+    let (_state, service, _claims) = build_test_state().await?;
+
+    let status = service.queue_status(None).await?;
+    assert_eq!(status.length, 0);
+    assert!(status.last_event.is_none());
+    assert!(status.consumer_groups.is_empty());
+    assert!(status.dead_letters.is_empty());
+    assert_eq!(status.dead_letter_idle_secs, 300);
+    Ok(())
+}
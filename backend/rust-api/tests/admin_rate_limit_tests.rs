@@ -0,0 +1,234 @@
+// Admin inspection and reset of rate limit counters
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use serde_json::json;
+use tower::ServiceExt;
+use trainingground_api::config::Config;
+
+mod common;
+
+/// Support can unblock a user who tripped the login limiter
+#[tokio::test]
+#[serial_test::serial]
+async fn test_admin_inspects_and_resets_login_limiter() {
+    flush_rate_limit_keys().await;
+
+    std::env::set_var("RATE_LIMIT_LOGIN_ATTEMPTS", "3");
+    std::env::set_var("RATE_LIMIT_DISABLED", "0");
+
+    let app = common::create_test_app().await;
+    let admin_token = create_admin_with_token(&app).await;
+
+    let test_ip = "192.168.8.10";
+    let email = format!("rate-reset-{}@example.com", uuid::Uuid::new_v4());
+    let password = "ValidPassword123!";
+    register(&app, &email, password).await;
+
+    for _ in 0..3 {
+        let status = login_with_ip(&app, &email, "WrongPassword", test_ip).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+    let status = login_with_ip(&app, &email, password, test_ip).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+
+    // Inspect
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(format!("/admin/rate-limits?key={}", test_ip))
+                .header("authorization", format!("Bearer {}", admin_token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = json_from_response(response).await;
+    let login_counter = body["counters"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|c| c["namespace"] == "login")
+        .unwrap_or_else(|| panic!("login counter missing: {body}"));
+    assert_eq!(login_counter["scope"], "ip");
+    assert_eq!(login_counter["count"], 3);
+    assert_eq!(login_counter["limit"], 3);
+    assert!(login_counter["ttl_secs"].as_i64().unwrap() > 0);
+
+    // Reset
+    let (csrf_token, csrf_cookie) = get_csrf_token(&app).await;
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri(format!("/admin/rate-limits/{}", test_ip))
+                .header("authorization", format!("Bearer {}", admin_token))
+                .header("x-csrf-token", &csrf_token)
+                .header("cookie", format!("csrf_token={}", csrf_cookie))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = json_from_response(response).await;
+    assert!(body["cleared"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|c| c["namespace"] == "login"));
+
+    let status = login_with_ip(&app, &email, password, test_ip).await;
+    assert_eq!(status, StatusCode::OK, "login should succeed after reset");
+
+    std::env::set_var("RATE_LIMIT_LOGIN_ATTEMPTS", "10");
+    flush_rate_limit_keys().await;
+}
+
+async fn flush_rate_limit_keys() {
+    let redis_uri = std::env::var("REDIS_URI")
+        .unwrap_or_else(|_| "redis://:changeMeRedis@127.0.0.1:6379/0".to_string());
+    let client = redis::Client::open(redis_uri).expect("Failed to connect to Redis for cleanup");
+    let mut conn = client
+        .get_connection_manager()
+        .await
+        .expect("Failed to get Redis connection");
+
+    let keys: Vec<String> = redis::cmd("KEYS")
+        .arg("ratelimit:*")
+        .query_async(&mut conn)
+        .await
+        .unwrap_or_default();
+
+    if !keys.is_empty() {
+        let _: () = redis::cmd("DEL")
+            .arg(&keys)
+            .query_async(&mut conn)
+            .await
+            .expect("Failed to delete rate limit keys");
+    }
+}
+
+async fn json_from_response(response: axum::response::Response) -> serde_json::Value {
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+async fn register(app: &axum::Router, email: &str, password: &str) -> String {
+    let body = json!({
+        "email": email,
+        "password": password,
+        "name": "Rate Limit Test",
+    });
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/auth/register")
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let json = json_from_response(response).await;
+    json["user"]["id"].as_str().unwrap().to_string()
+}
+
+async fn login_with_ip(app: &axum::Router, email: &str, password: &str, ip: &str) -> StatusCode {
+    let body = json!({
+        "email": email,
+        "password": password,
+    });
+
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/auth/login")
+                .header("content-type", "application/json")
+                .header("x-forwarded-for", ip)
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+        .status()
+}
+
+async fn get_csrf_token(app: &axum::Router) -> (String, String) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/api/v1/auth/csrf-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let csrf_cookie = response
+        .headers()
+        .get_all("set-cookie")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .find(|h| h.starts_with("csrf_token="))
+        .and_then(|pair| pair.split(';').next())
+        .and_then(|part| part.split('=').nth(1))
+        .unwrap_or("")
+        .to_string();
+
+    let json = json_from_response(response).await;
+    let csrf_token = json["csrf_token"].as_str().unwrap().to_string();
+    (csrf_token, csrf_cookie)
+}
+
+async fn create_admin_with_token(app: &axum::Router) -> String {
+    let email = format!("rate-admin-{}@test.com", uuid::Uuid::new_v4());
+    let password = "Admin123!@#";
+    let user_id = register(app, &email, password).await;
+    promote_user_to_admin(&user_id).await;
+
+    let login_body = json!({ "email": email, "password": password });
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/auth/login")
+                .header("content-type", "application/json")
+                .body(Body::from(login_body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let json = json_from_response(response).await;
+    json["access_token"].as_str().unwrap().to_string()
+}
+
+async fn promote_user_to_admin(user_id: &str) {
+    let config = Config::load().unwrap();
+    let mongo_client = mongodb::Client::with_uri_str(&config.mongo_uri)
+        .await
+        .unwrap();
+    mongo_client
+        .database(&config.mongo_database)
+        .collection::<mongodb::bson::Document>("users")
+        .update_one(
+            mongodb::bson::doc! { "_id": mongodb::bson::oid::ObjectId::parse_str(user_id).unwrap() },
+            mongodb::bson::doc! { "$set": { "role": "admin" } },
+        )
+        .await
+        .unwrap();
+}
@@ -1,14 +1,21 @@
 use anyhow::Result;
 use chrono::Utc;
 use redis::Client as RedisClient;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use uuid::Uuid;
 
+use mongodb::bson::{doc, oid::ObjectId, Document};
+use mongodb::event::{command::CommandEvent, EventHandler};
+use mongodb::options::ClientOptions;
 use mongodb::Client as MongoClient;
 use trainingground_api::{
     config::Config,
     middlewares::auth::JwtClaims,
-    models::content::{RuleCreateRequest, RuleUpdateRequest},
+    models::content::{
+        LevelCreateRequest, LevelDifficulty, RuleCreateRequest, RuleUpdateRequest,
+        TemplateCreateRequest, TemplateStatus, TopicCreateRequest,
+    },
     services::{content_service::ContentService, AppState},
 };
 
@@ -466,3 +473,208 @@ async fn test_rule_coverage_statistics() -> Result<()> {
 
     Ok(())
 }
+
+async fn create_coverage_rule(service: &ContentService, claims: &JwtClaims) -> Result<ObjectId> {
+    let rule = service
+        .create_rule(
+            RuleCreateRequest {
+                slug: format!("coverage-rule-{}", Uuid::new_v4()),
+                name: "Coverage Rule".to_string(),
+                category: "orthography".to_string(),
+                description: "Rule for coverage".to_string(),
+                examples: vec![],
+                exceptions: vec![],
+                sources: vec![],
+                status: None,
+            },
+            claims,
+        )
+        .await?;
+    Ok(rule.id)
+}
+
+/// Тема с одним уровнем; возвращает (topic_id, level_id)
+async fn create_coverage_level(
+    service: &ContentService,
+    claims: &JwtClaims,
+) -> Result<(ObjectId, ObjectId)> {
+    let topic = service
+        .create_topic(
+            TopicCreateRequest {
+                slug: format!("coverage-topic-{}", Uuid::new_v4()),
+                name: "Coverage Topic".to_string(),
+                description: "Topic for coverage".to_string(),
+                icon_url: None,
+                status: None,
+                age_band: None,
+            },
+            claims,
+        )
+        .await?;
+    let level = service
+        .create_level(
+            LevelCreateRequest {
+                topic_id: topic.id.to_string(),
+                name: "Coverage Level".to_string(),
+                difficulty: LevelDifficulty::A1,
+                description: "Level for coverage".to_string(),
+                min_pass_percent: None,
+                order: Some(1),
+                prerequisite_level_ids: vec![],
+            },
+            claims,
+        )
+        .await?;
+    Ok((topic.id, level.id))
+}
+
+async fn create_coverage_template(
+    service: &ContentService,
+    claims: &JwtClaims,
+    level_id: &ObjectId,
+    rule_ids: &[ObjectId],
+) -> Result<ObjectId> {
+    let template = service
+        .create_template(
+            TemplateCreateRequest {
+                slug: format!("coverage-template-{}", Uuid::new_v4()),
+                level_id: level_id.to_string(),
+                rule_ids: rule_ids.iter().map(ObjectId::to_string).collect(),
+                params: serde_json::json!({}),
+                metadata: serde_json::json!({}),
+                content: format!("Coverage template {}", Uuid::new_v4()),
+                difficulty: None,
+                source_refs: vec![],
+                age_band: None,
+                locale: None,
+                translations: Default::default(),
+            },
+            claims,
+        )
+        .await?;
+    Ok(ObjectId::parse_str(&template.id)?)
+}
+
+async fn coverage_counts(
+    service: &ContentService,
+    status: Option<TemplateStatus>,
+    topic_id: Option<ObjectId>,
+    rule_ids: &[ObjectId],
+) -> Result<Vec<i64>> {
+    let coverage: HashMap<String, i64> = service
+        .rule_coverage(status, topic_id)
+        .await?
+        .into_iter()
+        .map(|entry| (entry.rule_id, entry.linked_templates))
+        .collect();
+    Ok(rule_ids
+        .iter()
+        .map(|id| {
+            coverage
+                .get(&id.to_hex())
+                .copied()
+                .expect("rule missing from coverage")
+        })
+        .collect())
+}
+
+#[tokio::test]
+async fn test_rule_coverage_counts_overlapping_links() -> Result<()> {
+    let (state, service, claims) = build_test_state().await?;
+    let rules = [
+        create_coverage_rule(&service, &claims).await?,
+        create_coverage_rule(&service, &claims).await?,
+        create_coverage_rule(&service, &claims).await?,
+    ];
+    let (topic_a, level_a) = create_coverage_level(&service, &claims).await?;
+    let (_topic_b, level_b) = create_coverage_level(&service, &claims).await?;
+
+    let first = create_coverage_template(&service, &claims, &level_a, &rules[..2]).await?;
+    create_coverage_template(&service, &claims, &level_a, &rules[..1]).await?;
+    let third = create_coverage_template(&service, &claims, &level_b, &rules[..2]).await?;
+    create_coverage_template(&service, &claims, &level_b, &rules[1..2]).await?;
+    create_coverage_template(&service, &claims, &level_a, &rules[..1]).await?;
+
+    state
+        .mongo
+        .collection::<Document>("templates")
+        .update_many(
+            doc! { "_id": { "$in": [first, third] } },
+            doc! { "$set": { "status": "published" } },
+        )
+        .await?;
+
+    // Третье правило не связано ни с одним шаблоном, но присутствует в ответе
+    assert_eq!(
+        coverage_counts(&service, None, None, &rules).await?,
+        vec![4, 3, 0]
+    );
+    assert_eq!(
+        coverage_counts(&service, Some(TemplateStatus::Published), None, &rules).await?,
+        vec![2, 2, 0]
+    );
+    assert_eq!(
+        coverage_counts(&service, None, Some(topic_a), &rules).await?,
+        vec![3, 1, 0]
+    );
+    assert_eq!(
+        coverage_counts(
+            &service,
+            Some(TemplateStatus::Published),
+            Some(topic_a),
+            &rules
+        )
+        .await?,
+        vec![1, 1, 0]
+    );
+
+    let coverage = service.rule_coverage(None, None).await?;
+    let entry = coverage
+        .iter()
+        .find(|entry| entry.rule_id == rules[2].to_hex())
+        .unwrap();
+    assert_eq!(entry.name.as_deref(), Some("Coverage Rule"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rule_coverage_issues_two_commands() -> Result<()> {
+    dotenvy::from_filename(".env.test").ok();
+    let config = Config::load()?;
+    let seen: Arc<Mutex<Vec<(String, String)>>> = Arc::new(Mutex::new(Vec::new()));
+    let recorder = seen.clone();
+
+    let mut options = ClientOptions::parse(&config.mongo_uri).await?;
+    options.command_event_handler = Some(EventHandler::callback(move |event: CommandEvent| {
+        if let CommandEvent::Started(started) = event {
+            let target = started
+                .command
+                .get_str(&started.command_name)
+                .unwrap_or_default()
+                .to_string();
+            recorder
+                .lock()
+                .unwrap()
+                .push((started.command_name, target));
+        }
+    }));
+    let mongo_client = MongoClient::with_options(options)?;
+    let redis_client = RedisClient::open(config.redis_uri.clone())?;
+    let state = AppState::new(config, mongo_client, redis_client).await?;
+    let service = ContentService::new(&state);
+
+    seen.lock().unwrap().clear();
+    service.rule_coverage(None, None).await?;
+
+    let seen = seen.lock().unwrap().clone();
+    assert_eq!(
+        seen,
+        vec![
+            ("aggregate".to_string(), "templates".to_string()),
+            ("find".to_string(), "rules".to_string()),
+        ]
+    );
+
+    Ok(())
+}
@@ -2,9 +2,11 @@ use axum::{
     body::{to_bytes, Body},
     http::{Request, StatusCode},
 };
-use mongodb::bson::doc;
+use mongodb::bson::{doc, DateTime as BsonDateTime};
 use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
 use tower::ServiceExt;
+use trainingground_api::models::anticheat::{SessionTimingStats, WindowPeak};
 
 mod common;
 
@@ -46,7 +48,7 @@ async fn test_update_yandexgpt_settings() {
     let (csrf_token, csrf_cookie) = get_csrf_token(&app).await;
 
     let payload = json!({
-        "api_key": "test-key",
+        "api_key": "AQVN-test-key-1234",
         "folder_id": "folder",
         "model": "yandexgpt-lite",
         "temperature": 0.4,
@@ -86,25 +88,744 @@ async fn test_update_yandexgpt_settings() {
         .await
         .unwrap();
     let json = json_from_bytes(&body);
-    assert_eq!(json["yandexgpt"]["api_key"], "test-key");
+    assert_eq!(json["yandexgpt"]["api_key"], "••••1234");
+    assert_eq!(json["yandexgpt"]["has_secret"], true);
     assert_eq!(json["yandexgpt"]["model"], "yandexgpt-lite");
+    assert!(
+        !String::from_utf8_lossy(&body).contains("AQVN-test-key"),
+        "api key leaked"
+    );
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_update_email_settings_rejects_invalid_fields() {
+    let app = common::create_test_app().await;
+    set_rate_limit_disabled();
+    clear_system_settings().await;
+    let (_admin_id, admin_token) = create_admin_with_token(&app).await;
+
+    let (status, json) = put_settings(
+        &app,
+        &admin_token,
+        "email",
+        json!({
+            "server": "smtp example",
+            "port": 0,
+            "login": "mailer",
+            "from_email": "not-an-email",
+            "from_name": "TrainingGround",
+            "use_tls": true
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["code"], "VALIDATION_ERROR");
+    for field in ["server", "port", "from_email", "password"] {
+        assert!(
+            json["details"][field].is_array(),
+            "missing error for {field}: {json}"
+        );
+    }
+
+    let (status, json) = put_settings(
+        &app,
+        &admin_token,
+        "sso",
+        json!({
+            "enabled": true,
+            "provider": "yandex",
+            "client_id": "",
+            "client_secret": "sso-secret-value",
+            "redirect_uri": "not a url"
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(json["details"]["client_id"].is_array());
+    assert!(json["details"]["redirect_uri"].is_array());
+    assert!(json["details"].get("client_secret").is_none());
+
+    let settings = get_settings(&app, &admin_token).await;
+    assert!(settings["email"].is_null());
+    assert!(settings["sso"].is_null());
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_email_settings_round_trip_keeps_stored_password() {
+    let app = common::create_test_app().await;
+    set_rate_limit_disabled();
+    clear_system_settings().await;
+    let (_admin_id, admin_token) = create_admin_with_token(&app).await;
+
+    let (status, json) = put_settings(
+        &app,
+        &admin_token,
+        "email",
+        json!({
+            "server": "smtp.example.com",
+            "port": 587,
+            "login": "mailer",
+            "password": "smtp-password-5678",
+            "from_email": "noreply@example.com",
+            "from_name": "TrainingGround",
+            "use_tls": true
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{json}");
+    assert_eq!(json["password"], "••••5678");
+
+    // Клиент отправляет обратно то, что получил из GET, поменяв только порт
+    let mut email = get_settings(&app, &admin_token).await["email"].clone();
+    assert_eq!(email["password"], "••••5678");
+    assert_eq!(email["has_secret"], true);
+    email["port"] = json!(465);
+    let (status, json) = put_settings(&app, &admin_token, "email", email).await;
+    assert_eq!(status, StatusCode::OK, "{json}");
+    assert_eq!(json["port"], 465);
+
+    let stored = stored_setting("email").await;
+    assert_eq!(stored.get_str("password").unwrap(), "smtp-password-5678");
+    assert_eq!(stored.get_i32("port").unwrap(), 465);
+
+    // Тест подключения с маской в теле авторизуется сохранённым паролем
+    let smtp = FakeSmtpServer::start().await;
+    let mut email = get_settings(&app, &admin_token).await["email"].clone();
+    email["server"] = json!("127.0.0.1");
+    email["port"] = json!(smtp.port);
+    email["use_tls"] = json!(false);
+    std::env::set_var("EMAIL_SEND_DISABLED", "1");
+    let (status, json) = post_settings_test(
+        &app,
+        &admin_token,
+        "email",
+        Some(json!({ "recipient": "admin@example.com", "settings": email })),
+    )
+    .await;
+    std::env::remove_var("EMAIL_SEND_DISABLED");
+    assert_eq!(status, StatusCode::OK, "{json}");
+    assert_eq!(json["status"], "handshake_ok", "{json}");
+    assert_eq!(
+        smtp.credentials(),
+        Some(("mailer".to_string(), "smtp-password-5678".to_string()))
+    );
+
+    let (status, json) = post_settings_test(&app, &admin_token, "yandexgpt", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["success"], false);
 }
 
 #[tokio::test]
 #[serial_test::serial]
 async fn test_test_email_endpoint() {
+    let app = common::create_test_app().await;
+    set_rate_limit_disabled();
+    clear_system_settings().await;
+    let (_admin_id, admin_token) = create_admin_with_token(&app).await;
+
+    let (status, json) = post_settings_test(
+        &app,
+        &admin_token,
+        "email",
+        Some(json!({ "recipient": "admin@example.com" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["success"], false);
+    assert_eq!(json["status"], "not_configured");
+
+    let (status, json) = post_settings_test(
+        &app,
+        &admin_token,
+        "email",
+        Some(json!({ "recipient": "not-an-email" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(json["details"]["recipient"].is_array());
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_test_email_with_sending_disabled_stops_after_handshake() {
+    let app = common::create_test_app().await;
+    set_rate_limit_disabled();
+    clear_system_settings().await;
+    let (_admin_id, admin_token) = create_admin_with_token(&app).await;
+    let smtp = FakeSmtpServer::start().await;
+
+    std::env::set_var("EMAIL_SEND_DISABLED", "1");
+    let (status, json) = post_settings_test(
+        &app,
+        &admin_token,
+        "email",
+        Some(json!({
+            "recipient": "admin@example.com",
+            "settings": {
+                "server": "127.0.0.1",
+                "port": smtp.port,
+                "login": "mailer",
+                "password": "smtp-password",
+                "from_email": "noreply@example.com",
+                "from_name": "TrainingGround",
+                "use_tls": false
+            }
+        })),
+    )
+    .await;
+    std::env::remove_var("EMAIL_SEND_DISABLED");
+
+    assert_eq!(status, StatusCode::OK, "{json}");
+    assert_eq!(json["success"], true);
+    assert_eq!(json["status"], "handshake_ok");
+    let commands = smtp.commands();
+    assert!(
+        commands.iter().any(|c| c.starts_with("AUTH")),
+        "{commands:?}"
+    );
+    assert!(
+        !commands.iter().any(|c| c.starts_with("MAIL FROM")),
+        "test email must not be sent: {commands:?}"
+    );
+
+    // Закрытый порт - структурированная ошибка, а не 500
+    let closed_port = {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap().port()
+    };
+    let (status, json) = post_settings_test(
+        &app,
+        &admin_token,
+        "email",
+        Some(json!({
+            "recipient": "admin@example.com",
+            "settings": {
+                "server": "127.0.0.1",
+                "port": closed_port,
+                "login": "mailer",
+                "password": "smtp-password",
+                "from_email": "noreply@example.com",
+                "from_name": "TrainingGround",
+                "use_tls": false
+            }
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{json}");
+    assert_eq!(json["success"], false);
+    assert_eq!(json["status"], "connection_failed");
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_openai_settings_test_retries_rate_limit_with_stored_key() {
+    let app = common::create_test_app().await;
+    set_rate_limit_disabled();
+    clear_system_settings().await;
+    let (_admin_id, admin_token) = create_admin_with_token(&app).await;
+    let llm = FakeOpenAiServer::start(1).await;
+
+    let (status, json) = put_settings(
+        &app,
+        &admin_token,
+        "openai",
+        json!({
+            "base_url": "not a url",
+            "model": "",
+            "temperature": 3.0
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    for field in ["base_url", "model", "temperature"] {
+        assert!(json["details"][field].is_array(), "{field}: {json}");
+    }
+
+    let (status, json) = put_settings(
+        &app,
+        &admin_token,
+        "openai",
+        json!({
+            "base_url": format!("{}/v1/", llm.url),
+            "api_key": "sk-local-test-key-4321",
+            "model": "qwen2.5"
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{json}");
+    assert_eq!(json["api_key"], "••••4321");
+    assert_eq!(json["base_url"], format!("{}/v1", llm.url));
+
+    let settings = get_settings(&app, &admin_token).await;
+    assert_eq!(settings["openai"]["model"], "qwen2.5");
+    assert_eq!(settings["llm_provider"], "yandexgpt");
+
+    let (status, json) = post_settings_test(&app, &admin_token, "openai", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["success"], true, "{json}");
+    assert!(
+        json["message"].as_str().unwrap().contains("готово"),
+        "{json}"
+    );
+
+    let requests = llm.requests();
+    assert_eq!(requests.len(), 2, "429 must be retried once");
+    assert!(requests
+        .iter()
+        .all(|auth| auth == "Bearer sk-local-test-key-4321"));
+
+    let (status, json) = post_settings_test(&app, &admin_token, "openai", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["success"], true, "{json}");
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_jwt_keys_lists_ids_and_usage_without_secrets() {
+    let mut state = common::create_test_state().await;
+    state.config.jwt_keys = [
+        ("2024-06", "old-signing-secret-value"),
+        ("2024-09", "new-signing-secret-value"),
+    ]
+    .into_iter()
+    .map(|(kid, secret)| (kid.to_string(), secret.to_string()))
+    .collect();
+    state.config.jwt_active_key_id = Some("2024-09".to_string());
+    let app = trainingground_api::create_router(Arc::new(state));
+    set_rate_limit_disabled();
+    let (_admin_id, admin_token) = create_admin_with_token(&app).await;
+
+    let header = jsonwebtoken::decode_header(&admin_token).unwrap();
+    assert_eq!(header.kid.as_deref(), Some("2024-09"));
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/admin/settings/jwt-keys")
+                .header("authorization", format!("Bearer {}", admin_token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert!(
+        !String::from_utf8_lossy(&body).contains("signing-secret"),
+        "secret leaked"
+    );
+
+    let json = json_from_bytes(&body);
+    assert_eq!(json["active_kid"], "2024-09");
+    let keys = json["keys"].as_array().unwrap();
+    assert_eq!(keys.len(), 2, "{json}");
+    assert_eq!(keys[0]["kid"], "2024-06");
+    assert_eq!(keys[0]["active"], false);
+    assert_eq!(keys[1]["kid"], "2024-09");
+    assert!(keys[1]["issued"].as_u64().unwrap() >= 1, "{json}");
+    assert!(keys[1]["verified"].as_u64().unwrap() >= 1, "{json}");
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_update_anticheat_settings_reports_all_violations() {
+    let app = common::create_test_app().await;
+    set_rate_limit_disabled();
+    clear_system_settings().await;
+    let (_admin_id, admin_token) = create_admin_with_token(&app).await;
+    let (csrf_token, csrf_cookie) = get_csrf_token(&app).await;
+
+    let payload = json!({
+        "speed_threshold_seconds": 5,
+        "max_speed_hits": 10,
+        "max_repeated_hits": 8,
+        "block_duration_hours": 24,
+        "captcha_enabled": false,
+        "captcha_threshold": 3,
+        "suspicious_speed_hits": 15,
+        "time_window_seconds": 5,
+        "auto_block": true,
+        "auto_block_min_severity": null
+    });
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("PUT")
+                .uri("/admin/settings/anticheat")
+                .header("authorization", format!("Bearer {}", admin_token))
+                .header("content-type", "application/json")
+                .header("x-csrf-token", &csrf_token)
+                .header("cookie", format!("csrf_token={}", csrf_cookie))
+                .body(Body::from(payload.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json = json_from_bytes(&body);
+    assert_eq!(json["code"], "VALIDATION_ERROR");
+    for field in [
+        "suspicious_speed_hits",
+        "time_window_seconds",
+        "auto_block_min_severity",
+    ] {
+        assert!(
+            json["details"][field].is_array(),
+            "missing violation for {field}: {json}"
+        );
+    }
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_anticheat_preview_counts_seeded_aggregates() {
     let app = common::create_test_app().await;
     set_rate_limit_disabled();
     let (_admin_id, admin_token) = create_admin_with_token(&app).await;
     let (csrf_token, csrf_cookie) = get_csrf_token(&app).await;
+    seed_session_stats().await;
+
+    // Кандидат: блок от 8 ответов в час, подозрение от 4, повторы от 5
+    let payload = json!({
+        "lookback_days": 7,
+        "settings": {
+            "speed_threshold_seconds": 5,
+            "max_speed_hits": 8,
+            "max_repeated_hits": 5,
+            "block_duration_hours": 24,
+            "captcha_enabled": false,
+            "captcha_threshold": 3,
+            "suspicious_speed_hits": 4,
+            "time_window_seconds": 3600,
+            "auto_block": true,
+            "auto_block_min_severity": "critical"
+        }
+    });
 
     let response = app
         .clone()
         .oneshot(
             Request::builder()
                 .method("POST")
-                .uri("/admin/settings/test/email")
+                .uri("/admin/settings/anticheat/preview")
                 .header("authorization", format!("Bearer {}", admin_token))
+                .header("content-type", "application/json")
+                .header("x-csrf-token", &csrf_token)
+                .header("cookie", format!("csrf_token={}", csrf_cookie))
+                .body(Body::from(payload.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let raw = std::str::from_utf8(&body).unwrap().to_string();
+    let json = json_from_bytes(&body);
+
+    // preview-a: 9 в час -> блок; preview-b: 6 повторов -> блок; preview-c: 5 в час -> пометка;
+    // preview-d чистая; preview-old вне окна
+    assert_eq!(json["sessions_evaluated"], 4);
+    assert_eq!(json["flagged_sessions"], 3);
+    assert_eq!(json["flagged_users"], 2);
+    assert_eq!(json["blocked_sessions"], 2);
+    assert_eq!(json["blocked_users"], 2);
+    assert_eq!(json["truncated"], false);
+
+    let rules: Vec<&str> = json["by_rule"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["rule"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        rules,
+        vec!["speed_violation", "repeated_answers", "suspicious_pattern"]
+    );
+
+    // Примеры без исходных идентификаторов
+    assert_eq!(json["examples"].as_array().unwrap().len(), 3);
+    assert!(!raw.contains("preview-user-1") && !raw.contains("preview-a"));
+    assert!(json["examples"][0]["user_ref"]
+        .as_str()
+        .unwrap()
+        .starts_with("user-"));
+
+    clear_session_stats().await;
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_anticheat_preview_reads_only_aggregates() {
+    let _app = common::create_test_app().await;
+    seed_session_stats().await;
+
+    let config = trainingground_api::config::Config::load().unwrap();
+    let seen: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+    let recorder = seen.clone();
+
+    let mut options = mongodb::options::ClientOptions::parse(&config.mongo_uri)
+        .await
+        .unwrap();
+    options.command_event_handler = Some(mongodb::event::EventHandler::callback(
+        move |event: mongodb::event::command::CommandEvent| {
+            if let mongodb::event::command::CommandEvent::Started(started) = event {
+                let target = started
+                    .command
+                    .get_str(&started.command_name)
+                    .unwrap_or_default()
+                    .to_string();
+                recorder.lock().unwrap().push(target);
+            }
+        },
+    ));
+    let client = mongodb::Client::with_options(options).unwrap();
+    let service =
+        trainingground_api::services::anticheat_preview_service::AnticheatPreviewService::new(
+            client.database(&config.mongo_database),
+        );
+
+    let request: trainingground_api::models::anticheat::AnticheatPreviewRequest =
+        serde_json::from_value(json!({
+            "settings": {
+                "speed_threshold_seconds": 5,
+                "max_speed_hits": 10,
+                "max_repeated_hits": 8,
+                "block_duration_hours": 24,
+                "captcha_enabled": false,
+                "captcha_threshold": 3
+            }
+        }))
+        .unwrap();
+    service.preview(&request).await.unwrap();
+
+    let seen = seen.lock().unwrap().clone();
+    assert!(seen.iter().any(|c| c == "anticheat_session_stats"));
+    assert!(
+        !seen.iter().any(|c| c == "attempt_records"),
+        "preview must not touch raw answers: {seen:?}"
+    );
+
+    clear_session_stats().await;
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_rate_limit_update_applies_after_reload_without_new_router() {
+    let app = common::create_test_app().await;
+    set_rate_limit_disabled();
+    clear_system_settings().await;
+    let (_admin_id, admin_token) = create_admin_with_token(&app).await;
+
+    let (status, json) = put_settings(
+        &app,
+        &admin_token,
+        "rate-limits",
+        json!({ "login": { "limit": 2, "window_secs": 300 } }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{json}");
+    assert_eq!(json["login"]["limit"], 2);
+
+    // Невалидное окно отклоняется целиком
+    let (status, json) = put_settings(
+        &app,
+        &admin_token,
+        "rate-limits",
+        json!({ "register": { "limit": 0, "window_secs": 0 } }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{json}");
+    assert!(json["details"]["register"].is_array(), "{json}");
+
+    std::env::set_var("RATE_LIMIT_DISABLED", "0");
+    let run = uuid::Uuid::new_v4().as_bytes()[..2].to_vec();
+
+    // До перезагрузки действует лимит из конфигурации
+    let before_ip = format!("10.77.{}.{}", run[0], run[1]);
+    for _ in 0..3 {
+        let response = login_attempt(&app, &before_ip).await;
+        assert_ne!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()["x-ratelimit-limit"], "10");
+    }
+
+    let (status, json) = post_reload(&app, &admin_token).await;
+    assert_eq!(status, StatusCode::OK, "{json}");
+    assert_eq!(json["rate_limit"]["login"]["limit"], 2);
+    assert_eq!(json["rate_limit"]["login"]["window_secs"], 300);
+
+    // Тот же роутер применяет новый лимит
+    let after_ip = format!("10.78.{}.{}", run[0], run[1]);
+    for _ in 0..2 {
+        let response = login_attempt(&app, &after_ip).await;
+        assert_ne!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()["x-ratelimit-limit"], "2");
+    }
+    let response = login_attempt(&app, &after_ip).await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    set_rate_limit_disabled();
+
+    let json = get_settings(&app, &admin_token).await;
+    assert_eq!(json["rate_limits"]["login"]["limit"], 2);
+    assert_eq!(json["runtime"]["rate_limit"]["login"]["limit"], 2);
+    let reloadable: Vec<&str> = json["runtime"]["reloadable"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|setting| setting["key"].as_str().unwrap())
+        .collect();
+    for key in [
+        "rate_limit",
+        "anticheat_signals",
+        "hints",
+        "cors_allowed_origins",
+    ] {
+        assert!(reloadable.contains(&key), "{key} missing: {json}");
+    }
+
+    // Без переопределений перезагрузка возвращает значения из конфигурации
+    clear_system_settings().await;
+    let (status, json) = post_reload(&app, &admin_token).await;
+    assert_eq!(status, StatusCode::OK, "{json}");
+    assert_eq!(json["rate_limit"]["login"]["limit"], 10);
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_custom_pii_pattern_blocks_template_create() {
+    let app = common::create_test_app().await;
+    set_rate_limit_disabled();
+    clear_system_settings().await;
+    let (_admin_id, admin_token) = create_admin_with_token(&app).await;
+
+    let (status, json) = put_settings(
+        &app,
+        &admin_token,
+        "pii-patterns",
+        json!({ "patterns": [{ "name": "broken", "regex": "[unclosed", "severity": "block" }] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["code"], "VALIDATION_ERROR");
+    assert!(json["details"]["patterns"].is_array(), "{json}");
+
+    let patterns = json!({
+        "patterns": [
+            {
+                "name": "email",
+                "regex": "[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\\.[A-Za-z]{2,}",
+                "severity": "block"
+            },
+            { "name": "student_id", "regex": "\\bST-\\d{6}\\b", "severity": "block" }
+        ]
+    });
+    let (status, _) = put_settings(&app, &admin_token, "pii-patterns", patterns.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(get_settings(&app, &admin_token).await["pii"], patterns);
+
+    let level_id = mongodb::bson::oid::ObjectId::new();
+    let rule_id = mongodb::bson::oid::ObjectId::new();
+    let config = trainingground_api::config::Config::load().unwrap();
+    let db = mongodb::Client::with_uri_str(&config.mongo_uri)
+        .await
+        .unwrap()
+        .database(&config.mongo_database);
+    db.collection::<mongodb::bson::Document>("levels")
+        .insert_one(doc! {
+            "_id": level_id,
+            "topic_id": mongodb::bson::oid::ObjectId::new(),
+            "name": "PII level",
+            "difficulty": "A1",
+            "order": 1,
+        })
+        .await
+        .unwrap();
+    db.collection::<mongodb::bson::Document>("rules")
+        .insert_one(doc! {
+            "_id": rule_id,
+            "slug": format!("pii-rule-{}", uuid::Uuid::new_v4().simple()),
+            "name": "PII rule",
+        })
+        .await
+        .unwrap();
+
+    let (csrf_token, csrf_cookie) = get_csrf_token(&app).await;
+    let template = json!({
+        "slug": format!("pii-{}", uuid::Uuid::new_v4().simple()),
+        "level_id": level_id.to_hex(),
+        "rule_ids": [rule_id.to_hex()],
+        "params": {},
+        "metadata": { "title": "Работа ученика ST-123456" },
+        "content": "Пол..гать",
+    });
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/admin/templates")
+                .header("authorization", format!("Bearer {}", admin_token))
+                .header("content-type", "application/json")
+                .header("x-csrf-token", &csrf_token)
+                .header("cookie", format!("csrf_token={}", csrf_cookie))
+                .body(Body::from(template.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json = json_from_bytes(&body);
+    assert_eq!(json["code"], "PII_DETECTED");
+    assert!(
+        json["message"]
+            .as_str()
+            .unwrap()
+            .contains("metadata.title:student_id"),
+        "{json}"
+    );
+
+    clear_system_settings().await;
+}
+
+async fn login_attempt(app: &axum::Router, ip: &str) -> axum::response::Response {
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/auth/login")
+                .header("content-type", "application/json")
+                .header("x-forwarded-for", ip)
+                .body(Body::from(
+                    json!({
+                        "email": format!("reload-{}@example.com", uuid::Uuid::new_v4()),
+                        "password": "WrongPassword1!",
+                    })
+                    .to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+}
+
+async fn post_reload(app: &axum::Router, token: &str) -> (StatusCode, Value) {
+    let (csrf_token, csrf_cookie) = get_csrf_token(app).await;
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/admin/settings/reload")
+                .header("authorization", format!("Bearer {}", token))
                 .header("x-csrf-token", &csrf_token)
                 .header("cookie", format!("csrf_token={}", csrf_cookie))
                 .body(Body::empty())
@@ -112,8 +833,235 @@ async fn test_test_email_endpoint() {
         )
         .await
         .unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, json_from_bytes(&body))
+}
 
+async fn get_settings(app: &axum::Router, token: &str) -> Value {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/admin/settings")
+                .header("authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
     assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    json_from_bytes(&body)
+}
+
+async fn put_settings(
+    app: &axum::Router,
+    token: &str,
+    section: &str,
+    payload: Value,
+) -> (StatusCode, Value) {
+    let (csrf_token, csrf_cookie) = get_csrf_token(app).await;
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("PUT")
+                .uri(format!("/admin/settings/{}", section))
+                .header("authorization", format!("Bearer {}", token))
+                .header("content-type", "application/json")
+                .header("x-csrf-token", &csrf_token)
+                .header("cookie", format!("csrf_token={}", csrf_cookie))
+                .body(Body::from(payload.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, json_from_bytes(&body))
+}
+
+async fn post_settings_test(
+    app: &axum::Router,
+    token: &str,
+    section: &str,
+    payload: Option<Value>,
+) -> (StatusCode, Value) {
+    let (csrf_token, csrf_cookie) = get_csrf_token(app).await;
+    let mut request = Request::builder()
+        .method("POST")
+        .uri(format!("/admin/settings/test/{}", section))
+        .header("authorization", format!("Bearer {}", token))
+        .header("x-csrf-token", &csrf_token)
+        .header("cookie", format!("csrf_token={}", csrf_cookie));
+    let body = match payload {
+        Some(payload) => {
+            request = request.header("content-type", "application/json");
+            Body::from(payload.to_string())
+        }
+        None => Body::empty(),
+    };
+    let response = app
+        .clone()
+        .oneshot(request.body(body).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, json_from_bytes(&body))
+}
+
+/// Значение настройки как оно лежит в MongoDB (с секретом)
+async fn stored_setting(key: &str) -> mongodb::bson::Document {
+    let config = trainingground_api::config::Config::load().unwrap();
+    let client = mongodb::Client::with_uri_str(&config.mongo_uri)
+        .await
+        .unwrap();
+    let setting = client
+        .database(&config.mongo_database)
+        .collection::<mongodb::bson::Document>("system_settings")
+        .find_one(doc! { "key": key })
+        .await
+        .unwrap()
+        .unwrap();
+    setting.get_document("value").unwrap().clone()
+}
+
+/// Минимальный SMTP-сервер: принимает любую авторизацию и записывает команды
+struct FakeSmtpServer {
+    port: u16,
+    commands: Arc<Mutex<Vec<String>>>,
+}
+
+impl FakeSmtpServer {
+    async fn start() -> Self {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let commands = Arc::new(Mutex::new(Vec::new()));
+        let log = commands.clone();
+
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let log = log.clone();
+                tokio::spawn(async move {
+                    let (reader, mut writer) = stream.into_split();
+                    let mut lines = BufReader::new(reader).lines();
+                    writer.write_all(b"220 localhost ESMTP\r\n").await.ok();
+                    let mut in_data = false;
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        if in_data {
+                            if line == "." {
+                                in_data = false;
+                                writer.write_all(b"250 queued\r\n").await.ok();
+                            }
+                            continue;
+                        }
+                        log.lock().unwrap().push(line.clone());
+                        let command = line.to_ascii_uppercase();
+                        let reply: &[u8] = if command.starts_with("EHLO") {
+                            b"250-localhost\r\n250 AUTH PLAIN LOGIN\r\n"
+                        } else if command.starts_with("AUTH") {
+                            b"235 2.7.0 Authentication successful\r\n"
+                        } else if command.starts_with("DATA") {
+                            in_data = true;
+                            b"354 go ahead\r\n"
+                        } else if command.starts_with("QUIT") {
+                            writer.write_all(b"221 bye\r\n").await.ok();
+                            break;
+                        } else {
+                            b"250 ok\r\n"
+                        };
+                        writer.write_all(reply).await.ok();
+                    }
+                });
+            }
+        });
+
+        Self { port, commands }
+    }
+
+    fn commands(&self) -> Vec<String> {
+        self.commands.lock().unwrap().clone()
+    }
+
+    /// Логин и пароль из `AUTH PLAIN`
+    fn credentials(&self) -> Option<(String, String)> {
+        use base64::Engine;
+
+        let command = self
+            .commands()
+            .into_iter()
+            .find(|c| c.starts_with("AUTH PLAIN "))?;
+        let decoded = base64::engine::general_purpose::STANDARD
+            .decode(command.trim_start_matches("AUTH PLAIN "))
+            .ok()?;
+        let decoded = String::from_utf8(decoded).ok()?;
+        let mut parts = decoded.split('\0').skip(1);
+        Some((parts.next()?.to_string(), parts.next()?.to_string()))
+    }
+}
+
+/// OpenAI-совместимый сервер: первые `rate_limited` запросов получают 429,
+/// остальные - ответ "готово"; записывает заголовки Authorization
+struct FakeOpenAiServer {
+    url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl FakeOpenAiServer {
+    async fn start(rate_limited: usize) -> Self {
+        use axum::{http::HeaderMap, response::IntoResponse, routing::post, Json, Router};
+
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let log = requests.clone();
+        let router = Router::new().route(
+            "/v1/chat/completions",
+            post(move |headers: HeaderMap| {
+                let log = log.clone();
+                async move {
+                    let auth = headers
+                        .get("authorization")
+                        .and_then(|value| value.to_str().ok())
+                        .unwrap_or_default()
+                        .to_string();
+                    let seen = {
+                        let mut log = log.lock().unwrap();
+                        log.push(auth);
+                        log.len()
+                    };
+                    if seen <= rate_limited {
+                        return (
+                            StatusCode::TOO_MANY_REQUESTS,
+                            [("retry-after", "0")],
+                            "slow down",
+                        )
+                            .into_response();
+                    }
+                    Json(json!({
+                        "choices": [{ "message": { "role": "assistant", "content": "готово" } }],
+                        "usage": { "prompt_tokens": 9, "completion_tokens": 2 }
+                    }))
+                    .into_response()
+                }
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, router).await.ok();
+        });
+
+        Self { url, requests }
+    }
+
+    fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
 }
 
 async fn create_admin_with_token(app: &axum::Router) -> (String, String) {
@@ -233,3 +1181,59 @@ async fn clear_system_settings() {
         .await
         .unwrap();
 }
+
+fn session_stats(
+    session_id: &str,
+    user_id: &str,
+    per_hour: u32,
+    repeated: u32,
+    days_ago: i64,
+) -> SessionTimingStats {
+    let at = chrono::Utc::now() - chrono::Duration::days(days_ago);
+    let at = BsonDateTime::from_millis(at.timestamp_millis());
+    SessionTimingStats {
+        session_id: session_id.to_string(),
+        user_id: user_id.to_string(),
+        answers: per_hour.max(repeated),
+        peaks: vec![WindowPeak {
+            window_seconds: 3600,
+            answers: per_hour,
+        }],
+        max_repeated_answers: repeated,
+        first_answer_at: at,
+        last_answer_at: at,
+    }
+}
+
+async fn session_stats_collection() -> mongodb::Collection<SessionTimingStats> {
+    let config = trainingground_api::config::Config::load().unwrap();
+    let mongo_client = mongodb::Client::with_uri_str(&config.mongo_uri)
+        .await
+        .unwrap();
+    mongo_client
+        .database(&config.mongo_database)
+        .collection::<SessionTimingStats>("anticheat_session_stats")
+}
+
+async fn seed_session_stats() {
+    clear_session_stats().await;
+    session_stats_collection()
+        .await
+        .insert_many(vec![
+            session_stats("preview-a", "preview-user-1", 9, 1, 1),
+            session_stats("preview-b", "preview-user-2", 2, 6, 2),
+            session_stats("preview-c", "preview-user-1", 5, 1, 3),
+            session_stats("preview-d", "preview-user-3", 3, 2, 1),
+            session_stats("preview-old", "preview-user-3", 50, 50, 20),
+        ])
+        .await
+        .unwrap();
+}
+
+async fn clear_session_stats() {
+    session_stats_collection()
+        .await
+        .delete_many(doc! {})
+        .await
+        .unwrap();
+}
@@ -16,13 +16,104 @@ async fn test_get_system_metrics_returns_counts() {
 
     let (_admin_id, admin_token) = create_admin_with_token(&app).await;
 
+    let json = get_system_metrics(&app, &admin_token).await;
+
+    assert!(
+        json["total_users"].as_u64().is_some(),
+        "metrics payload missing total_users: {json:?}"
+    );
+    assert!(
+        json["uptime_seconds"].as_u64().unwrap_or_default() > 0,
+        "uptime_seconds should be positive"
+    );
+
+    for section in ["process", "database", "redis"] {
+        assert!(json[section].is_object(), "missing {section}: {json:?}");
+    }
+    assert!(json["process"]["pid"].as_u64().unwrap_or_default() > 0);
+    if cfg!(target_os = "linux") {
+        assert!(json["process"]["rss_bytes"].as_u64().unwrap_or_default() > 0);
+        assert!(json["process"]["cpu_seconds_total"].as_f64().is_some());
+    }
+    assert!(json["database"]["collections"].as_u64().unwrap_or_default() > 0);
+    assert!(json["database"]["storage_size_bytes"].as_u64().is_some());
+    assert!(json["database"]["index_size_bytes"].as_u64().is_some());
+    assert!(
+        json["redis"]["used_memory_bytes"]
+            .as_u64()
+            .unwrap_or_default()
+            > 0
+    );
+    assert!(
+        json["redis"]["connected_clients"]
+            .as_u64()
+            .unwrap_or_default()
+            > 0
+    );
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_system_metrics_cache_storage_stats() {
+    let app = common::create_test_app().await;
+    disable_rate_limit();
+
+    let (_admin_id, admin_token) = create_admin_with_token(&app).await;
+
+    let first = get_system_metrics(&app, &admin_token).await;
+    // Новый пользователь не должен попасть в счётчики, пока кэш не устарел
+    create_admin_with_token(&app).await;
+    let second = get_system_metrics(&app, &admin_token).await;
+
+    assert_eq!(first["collected_at"], second["collected_at"]);
+    assert_eq!(first["database"], second["database"]);
+    assert_eq!(first["redis"], second["redis"]);
+    assert_eq!(first["total_users"], second["total_users"]);
+    assert!(second["process"]["pid"].as_u64().is_some());
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_trace_sampling_rules_are_tunable_at_runtime() {
+    let app = common::create_test_app().await;
+    disable_rate_limit();
+
+    let (_admin_id, admin_token) = create_admin_with_token(&app).await;
+    let original = trainingground_api::telemetry::sampling_control().get();
+
+    let (status, body) = put_trace_sampling(
+        &app,
+        &admin_token,
+        json!({ "ratio": 1.5, "slow_trace_ms": 500, "always_sample_errors": true }),
+    )
+    .await;
+    assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "INVALID_SAMPLE_RATIO");
+
+    let (status, body) = put_trace_sampling(
+        &app,
+        &admin_token,
+        json!({ "ratio": 0.0, "slow_trace_ms": 250, "always_sample_errors": true }),
+    )
+    .await;
+    assert!(status.is_success(), "unexpected status: {}", status);
+    assert_eq!(body["slow_trace_ms"], 250);
+
+    let current = trainingground_api::telemetry::sampling_control().get();
+    assert_eq!(current.ratio, 0.0);
+    assert_eq!(current.slow_trace_ms, 250);
+
+    trainingground_api::telemetry::sampling_control().set(original);
+}
+
+async fn get_system_metrics(app: &axum::Router, token: &str) -> serde_json::Value {
     let response = app
         .clone()
         .oneshot(
             Request::builder()
                 .method("GET")
                 .uri("/admin/system/metrics")
-                .header("authorization", format!("Bearer {}", admin_token))
+                .header("authorization", format!("Bearer {}", token))
                 .body(Body::empty())
                 .unwrap(),
         )
@@ -36,16 +127,64 @@ async fn test_get_system_metrics_returns_counts() {
     );
 
     let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
-    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
 
-    assert!(
-        json["total_users"].as_u64().is_some(),
-        "metrics payload missing total_users: {json:?}"
-    );
-    assert!(
-        json["uptime_seconds"].as_u64().unwrap_or_default() > 0,
-        "uptime_seconds should be positive"
-    );
+async fn put_trace_sampling(
+    app: &axum::Router,
+    token: &str,
+    payload: serde_json::Value,
+) -> (axum::http::StatusCode, serde_json::Value) {
+    let (csrf_token, csrf_cookie) = get_csrf_token(app).await;
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("PUT")
+                .uri("/admin/system/trace-sampling")
+                .header("authorization", format!("Bearer {}", token))
+                .header("content-type", "application/json")
+                .header("x-csrf-token", &csrf_token)
+                .header("cookie", format!("csrf_token={}", csrf_cookie))
+                .body(Body::from(payload.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+async fn get_csrf_token(app: &axum::Router) -> (String, String) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/api/v1/auth/csrf-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let csrf_cookie = response
+        .headers()
+        .get_all("set-cookie")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .find(|h| h.starts_with("csrf_token="))
+        .and_then(|pair| pair.split(';').next())
+        .and_then(|part| part.split('=').nth(1))
+        .unwrap_or("")
+        .to_string();
+
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let csrf_token = json["csrf_token"].as_str().unwrap().to_string();
+    (csrf_token, csrf_cookie)
 }
 
 async fn create_admin_with_token(app: &axum::Router) -> (String, String) {
@@ -4,16 +4,26 @@ use redis::Client as RedisClient;
 use std::sync::Arc;
 use uuid::Uuid;
 
-use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{doc, oid::ObjectId, DateTime as BsonDateTime, Document};
 use mongodb::Client as MongoClient;
 use trainingground_api::{
     config::Config,
     middlewares::auth::JwtClaims,
     models::content::{
-        LevelCreateRequest, LevelDifficulty, RuleCreateRequest, TemplateCreateRequest,
-        TemplateListQuery, TemplateStatus, TemplateUpdateRequest, TopicCreateRequest,
+        AgeBand, ContentTreeQuery, ContentTreeTopic, LevelCreateRequest, LevelDifficulty,
+        ModerationQueueAge, ModerationQueueItem, ReviewerAssignOutcome, RuleCreateRequest,
+        RuleMergeOutcome, RuleRecord, RuleStatus, TemplateArchiveOutcome, TemplateBundle,
+        TemplateCreateRequest, TemplateImportOptions, TemplateImportOutcome, TemplateListQuery,
+        TemplatePublishOutcome, TemplateStatus, TemplateTranslationRequest, TemplateUpdateRequest,
+        TopicCreateRequest,
+    },
+    services::{
+        content_service::{
+            ContentService, DuplicateApprovalError, InvalidAnswersError, InvalidLocaleError,
+            TemplateLintError,
+        },
+        AppState,
     },
-    services::{content_service::ContentService, AppState},
 };
 
 async fn build_test_state() -> Result<(Arc<AppState>, ContentService, JwtClaims)> {
@@ -49,6 +59,7 @@ async fn test_create_template_with_valid_data() -> Result<()> {
                 description: "Topic for templates".to_string(),
                 icon_url: None,
                 status: None,
+                age_band: None,
             },
             &claims,
         )
@@ -63,6 +74,7 @@ async fn test_create_template_with_valid_data() -> Result<()> {
                 description: "Beginner level".to_string(),
                 min_pass_percent: Some(70),
                 order: Some(1),
+                prerequisite_level_ids: vec![],
             },
             &claims,
         )
@@ -98,6 +110,9 @@ async fn test_create_template_with_valid_data() -> Result<()> {
                 content: "Template content".to_string(),
                 difficulty: Some("A1".to_string()),
                 source_refs: vec![],
+                age_band: None,
+                locale: None,
+                translations: Default::default(),
             },
             &claims,
         )
@@ -122,6 +137,7 @@ async fn test_list_templates() -> Result<()> {
                 description: "Test".to_string(),
                 icon_url: None,
                 status: None,
+                age_band: None,
             },
             &claims,
         )
@@ -136,6 +152,7 @@ async fn test_list_templates() -> Result<()> {
                 description: "Test".to_string(),
                 min_pass_percent: None,
                 order: Some(1),
+                prerequisite_level_ids: vec![],
             },
             &claims,
         )
@@ -171,6 +188,9 @@ async fn test_list_templates() -> Result<()> {
                 content: "Content".to_string(),
                 difficulty: None,
                 source_refs: vec![],
+                age_band: None,
+                locale: None,
+                translations: Default::default(),
             },
             &claims,
         )
@@ -184,7 +204,9 @@ async fn test_list_templates() -> Result<()> {
             difficulty: None,
             version: None,
             q: None,
+            locale: None,
             limit: None,
+            include_archived: false,
         })
         .await?;
     assert!(!templates.is_empty());
@@ -192,6 +214,72 @@ async fn test_list_templates() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_template_age_band_cleared_by_null() -> Result<()> {
+    let (_state, service, claims) = build_test_state().await?;
+
+    let topic = service
+        .create_topic(
+            TopicCreateRequest {
+                slug: format!("topic-{}", Uuid::new_v4()),
+                name: "Test Topic".to_string(),
+                description: "Test".to_string(),
+                icon_url: None,
+                status: None,
+                age_band: None,
+            },
+            &claims,
+        )
+        .await?;
+    let level = service
+        .create_level(
+            LevelCreateRequest {
+                topic_id: topic.id.to_string(),
+                name: "Level".to_string(),
+                difficulty: LevelDifficulty::A1,
+                description: "Test".to_string(),
+                min_pass_percent: None,
+                order: Some(1),
+                prerequisite_level_ids: vec![],
+            },
+            &claims,
+        )
+        .await?;
+    let template = service
+        .create_template(
+            TemplateCreateRequest {
+                slug: format!("template-{}", Uuid::new_v4()),
+                level_id: level.id.to_string(),
+                rule_ids: vec![],
+                params: serde_json::json!({}),
+                metadata: serde_json::json!({}),
+                content: "Rated content".to_string(),
+                difficulty: None,
+                source_refs: vec![],
+                age_band: Some(AgeBand::Senior),
+                locale: None,
+                translations: Default::default(),
+            },
+            &claims,
+        )
+        .await?;
+    let template_id = template.id.parse::<ObjectId>()?;
+
+    let payload: TemplateUpdateRequest =
+        serde_json::from_value(serde_json::json!({ "age_band": null }))?;
+    let updated = service
+        .update_template(&template_id, payload, &claims)
+        .await?;
+    assert_eq!(updated.age_band, None);
+    // Категория не меняет содержимое - версия прежняя
+    assert_eq!(updated.version, template.version);
+
+    let detail = service.get_template(&template_id).await?.unwrap();
+    assert_eq!(detail.age_band, None);
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_update_template() -> Result<()> {
     let (_state, service, claims) = build_test_state().await?;
@@ -205,6 +293,7 @@ async fn test_update_template() -> Result<()> {
                 description: "Test".to_string(),
                 icon_url: None,
                 status: None,
+                age_band: None,
             },
             &claims,
         )
@@ -219,6 +308,7 @@ async fn test_update_template() -> Result<()> {
                 description: "Test".to_string(),
                 min_pass_percent: None,
                 order: Some(1),
+                prerequisite_level_ids: vec![],
             },
             &claims,
         )
@@ -252,6 +342,9 @@ async fn test_update_template() -> Result<()> {
                 content: "Original content".to_string(),
                 difficulty: None,
                 source_refs: vec![],
+                age_band: None,
+                locale: None,
+                translations: Default::default(),
             },
             &claims,
         )
@@ -268,6 +361,9 @@ async fn test_update_template() -> Result<()> {
                 content: Some("Updated content".to_string()),
                 difficulty: Some("B1".to_string()),
                 source_refs: None,
+                age_band: None,
+                locale: None,
+                translations: Default::default(),
             },
             &claims,
         )
@@ -298,6 +394,7 @@ async fn test_delete_template() -> Result<()> {
                 description: "Test".to_string(),
                 icon_url: None,
                 status: None,
+                age_band: None,
             },
             &claims,
         )
@@ -312,6 +409,7 @@ async fn test_delete_template() -> Result<()> {
                 description: "Test".to_string(),
                 min_pass_percent: None,
                 order: Some(1),
+                prerequisite_level_ids: vec![],
             },
             &claims,
         )
@@ -325,7 +423,7 @@ async fn test_delete_template() -> Result<()> {
                 name: "Test Rule".to_string(),
                 category: "orthography".to_string(),
                 description: "Rule for testing".to_string(),
-                examples: vec![],
+                examples: vec!["Пример".to_string()],
                 exceptions: vec![],
                 sources: vec![],
                 status: None,
@@ -345,6 +443,9 @@ async fn test_delete_template() -> Result<()> {
                 content: "Content to deprecate".to_string(),
                 difficulty: None,
                 source_refs: vec![],
+                age_band: None,
+                locale: None,
+                translations: Default::default(),
             },
             &claims,
         )
@@ -361,39 +462,32 @@ async fn test_delete_template() -> Result<()> {
                 content: None,
                 difficulty: None,
                 source_refs: None,
+                age_band: None,
+                locale: None,
+                translations: Default::default(),
             },
             &claims,
         )
         .await?;
 
+    // Одобрения ставятся только через модерацию, не через PUT шаблона
+    let template_id = template.id.parse::<ObjectId>()?;
+    for status in ["reviewedonce", "ready"] {
+        let err = service
+            .update_template(&template_id, status_update(status), &claims)
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Invalid template status transition"));
+    }
+    service.approve_template(&template_id, &claims).await?;
+    let second_reviewer = JwtClaims {
+        sub: Uuid::new_v4().to_string(),
+        ..claims.clone()
+    };
     service
-        .update_template(
-            &template.id.parse::<ObjectId>()?,
-            TemplateUpdateRequest {
-                status: Some("reviewedonce".to_string()),
-                params: None,
-                metadata: None,
-                content: None,
-                difficulty: None,
-                source_refs: None,
-            },
-            &claims,
-        )
-        .await?;
-
-    service
-        .update_template(
-            &template.id.parse::<ObjectId>()?,
-            TemplateUpdateRequest {
-                status: Some("ready".to_string()),
-                params: None,
-                metadata: None,
-                content: None,
-                difficulty: None,
-                source_refs: None,
-            },
-            &claims,
-        )
+        .approve_template(&template_id, &second_reviewer)
         .await?;
 
     service
@@ -406,6 +500,9 @@ async fn test_delete_template() -> Result<()> {
                 content: None,
                 difficulty: None,
                 source_refs: None,
+                age_band: None,
+                locale: None,
+                translations: Default::default(),
             },
             &claims,
         )
@@ -422,6 +519,9 @@ async fn test_delete_template() -> Result<()> {
                 content: None,
                 difficulty: None,
                 source_refs: None,
+                age_band: None,
+                locale: None,
+                translations: Default::default(),
             },
             &claims,
         )
@@ -478,6 +578,7 @@ async fn test_template_with_rule_references() -> Result<()> {
                 description: "Test".to_string(),
                 icon_url: None,
                 status: None,
+                age_band: None,
             },
             &claims,
         )
@@ -492,6 +593,7 @@ async fn test_template_with_rule_references() -> Result<()> {
                 description: "Test".to_string(),
                 min_pass_percent: None,
                 order: Some(1),
+                prerequisite_level_ids: vec![],
             },
             &claims,
         )
@@ -509,6 +611,9 @@ async fn test_template_with_rule_references() -> Result<()> {
                 content: "Template with multiple rules".to_string(),
                 difficulty: None,
                 source_refs: vec![],
+                age_band: None,
+                locale: None,
+                translations: Default::default(),
             },
             &claims,
         )
@@ -538,6 +643,7 @@ async fn test_template_with_params() -> Result<()> {
                 description: "Test".to_string(),
                 icon_url: None,
                 status: None,
+                age_band: None,
             },
             &claims,
         )
@@ -552,6 +658,7 @@ async fn test_template_with_params() -> Result<()> {
                 description: "Test".to_string(),
                 min_pass_percent: None,
                 order: Some(1),
+                prerequisite_level_ids: vec![],
             },
             &claims,
         )
@@ -566,6 +673,7 @@ async fn test_template_with_params() -> Result<()> {
                 description: "Test".to_string(),
                 min_pass_percent: None,
                 order: Some(2),
+                prerequisite_level_ids: vec![],
             },
             &claims,
         )
@@ -605,6 +713,9 @@ async fn test_template_with_params() -> Result<()> {
                 content: "Template with params".to_string(),
                 difficulty: None,
                 source_refs: vec![],
+                age_band: None,
+                locale: None,
+                translations: Default::default(),
             },
             &claims,
         )
@@ -624,6 +735,92 @@ async fn test_template_with_params() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_template_answers_pattern_is_validated() -> Result<()> {
+    let (_state, service, claims) = build_test_state().await?;
+
+    let topic = service
+        .create_topic(
+            TopicCreateRequest {
+                slug: format!("topic-{}", Uuid::new_v4()),
+                name: "Answers Topic".to_string(),
+                description: "Test".to_string(),
+                icon_url: None,
+                status: None,
+                age_band: None,
+            },
+            &claims,
+        )
+        .await?;
+    let level = service
+        .create_level(
+            LevelCreateRequest {
+                topic_id: topic.id.to_string(),
+                name: "Level".to_string(),
+                difficulty: LevelDifficulty::A1,
+                description: "Test".to_string(),
+                min_pass_percent: None,
+                order: Some(1),
+                prerequisite_level_ids: vec![],
+            },
+            &claims,
+        )
+        .await?;
+    let request = |params: serde_json::Value| TemplateCreateRequest {
+        slug: format!("template-{}", Uuid::new_v4()),
+        level_id: level.id.to_string(),
+        rule_ids: vec![],
+        params,
+        metadata: serde_json::json!({}),
+        content: "Напишите «не знаю»".to_string(),
+        difficulty: None,
+        source_refs: vec![],
+        age_band: None,
+        locale: None,
+        translations: Default::default(),
+    };
+
+    let error = service
+        .create_template(
+            request(serde_json::json!({ "answers": { "pattern": "(a{1000}){1000}" } })),
+            &claims,
+        )
+        .await
+        .unwrap_err();
+    assert!(error.downcast_ref::<InvalidAnswersError>().is_some());
+
+    let template = service
+        .create_template(
+            request(serde_json::json!({
+                "answers": { "accepted": ["не знаю", "не знаю."], "pattern": r"не\s?знаю\.?" }
+            })),
+            &claims,
+        )
+        .await?;
+
+    let error = service
+        .update_template(
+            &template.id.parse::<ObjectId>()?,
+            TemplateUpdateRequest {
+                status: None,
+                params: Some(serde_json::json!({ "answers": { "accepted": "не знаю" } })),
+                metadata: None,
+                content: None,
+                difficulty: None,
+                source_refs: None,
+                age_band: None,
+                locale: None,
+                translations: Default::default(),
+            },
+            &claims,
+        )
+        .await
+        .unwrap_err();
+    assert!(error.downcast_ref::<InvalidAnswersError>().is_some());
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_template_with_metadata() -> Result<()> {
     let (_state, service, claims) = build_test_state().await?;
@@ -637,6 +834,7 @@ async fn test_template_with_metadata() -> Result<()> {
                 description: "Test".to_string(),
                 icon_url: None,
                 status: None,
+                age_band: None,
             },
             &claims,
         )
@@ -651,6 +849,7 @@ async fn test_template_with_metadata() -> Result<()> {
                 description: "Test".to_string(),
                 min_pass_percent: None,
                 order: Some(1),
+                prerequisite_level_ids: vec![],
             },
             &claims,
         )
@@ -690,6 +889,9 @@ async fn test_template_with_metadata() -> Result<()> {
                 content: "Template with metadata".to_string(),
                 difficulty: None,
                 source_refs: vec![],
+                age_band: None,
+                locale: None,
+                translations: Default::default(),
             },
             &claims,
         )
@@ -722,6 +924,7 @@ async fn test_template_status_transitions() -> Result<()> {
                 description: "Test".to_string(),
                 icon_url: None,
                 status: None,
+                age_band: None,
             },
             &claims,
         )
@@ -736,6 +939,7 @@ async fn test_template_status_transitions() -> Result<()> {
                 description: "Test".to_string(),
                 min_pass_percent: None,
                 order: Some(1),
+                prerequisite_level_ids: vec![],
             },
             &claims,
         )
@@ -749,7 +953,7 @@ async fn test_template_status_transitions() -> Result<()> {
                 name: "Test Rule".to_string(),
                 category: "orthography".to_string(),
                 description: "Rule for testing".to_string(),
-                examples: vec![],
+                examples: vec!["Пример".to_string()],
                 exceptions: vec![],
                 sources: vec![],
                 status: None,
@@ -769,6 +973,9 @@ async fn test_template_status_transitions() -> Result<()> {
                 content: "Template for status test".to_string(),
                 difficulty: None,
                 source_refs: vec![],
+                age_band: None,
+                locale: None,
+                translations: Default::default(),
             },
             &claims,
         )
@@ -788,6 +995,9 @@ async fn test_template_status_transitions() -> Result<()> {
                 content: None,
                 difficulty: None,
                 source_refs: None,
+                age_band: None,
+                locale: None,
+                translations: Default::default(),
             },
             &claims,
         )
@@ -810,6 +1020,7 @@ async fn test_template_with_difficulty_levels() -> Result<()> {
                 description: "Test".to_string(),
                 icon_url: None,
                 status: None,
+                age_band: None,
             },
             &claims,
         )
@@ -844,6 +1055,7 @@ async fn test_template_with_difficulty_levels() -> Result<()> {
                     description: "Test".to_string(),
                     min_pass_percent: None,
                     order: Some((idx + 1) as i32),
+                    prerequisite_level_ids: vec![],
                 },
                 &claims,
             )
@@ -860,6 +1072,9 @@ async fn test_template_with_difficulty_levels() -> Result<()> {
                     content: format!("Template for {}", diff),
                     difficulty: Some(diff.to_string()),
                     source_refs: vec![],
+                    age_band: None,
+                    locale: None,
+                    translations: Default::default(),
                 },
                 &claims,
             )
@@ -884,6 +1099,7 @@ async fn test_template_with_source_references() -> Result<()> {
                 description: "Test".to_string(),
                 icon_url: None,
                 status: None,
+                age_band: None,
             },
             &claims,
         )
@@ -898,6 +1114,7 @@ async fn test_template_with_source_references() -> Result<()> {
                 description: "Test".to_string(),
                 min_pass_percent: None,
                 order: Some(1),
+                prerequisite_level_ids: vec![],
             },
             &claims,
         )
@@ -936,6 +1153,9 @@ async fn test_template_with_source_references() -> Result<()> {
                 content: "Template with sources".to_string(),
                 difficulty: None,
                 source_refs: source_refs.clone(),
+                age_band: None,
+                locale: None,
+                translations: Default::default(),
             },
             &claims,
         )
@@ -959,6 +1179,7 @@ async fn test_template_content_preservation() -> Result<()> {
                 description: "Test".to_string(),
                 icon_url: None,
                 status: None,
+                age_band: None,
             },
             &claims,
         )
@@ -973,6 +1194,7 @@ async fn test_template_content_preservation() -> Result<()> {
                 description: "Test".to_string(),
                 min_pass_percent: None,
                 order: Some(1),
+                prerequisite_level_ids: vec![],
             },
             &claims,
         )
@@ -1010,6 +1232,9 @@ async fn test_template_content_preservation() -> Result<()> {
                 content: long_content.to_string(),
                 difficulty: None,
                 source_refs: vec![],
+                age_band: None,
+                locale: None,
+                translations: Default::default(),
             },
             &claims,
         )
@@ -1025,3 +1250,1186 @@ async fn test_template_content_preservation() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_bulk_status_publishes_ready_templates_and_reports_failures() -> Result<()> {
+    let (_state, service, claims) = build_test_state().await?;
+
+    let ready = create_template_fixture(&service, &claims).await?;
+    review_to_ready(&service, &ready, &claims).await?;
+    // Черновик нельзя опубликовать в обход ревью
+    let draft = create_template_fixture(&service, &claims).await?;
+
+    let result = service
+        .bulk_update_template_status(
+            vec![
+                ready.to_hex(),
+                draft.to_hex(),
+                "not-an-object-id".to_string(),
+            ],
+            TemplateStatus::Published,
+            Some("Content review".to_string()),
+            &claims,
+        )
+        .await?;
+
+    assert_eq!(result.processed, 1);
+    assert_eq!(result.failed.len(), 2);
+    assert_eq!(result.failed[0].id, draft.to_hex());
+    assert!(result.failed[0]
+        .error
+        .contains("Invalid template status transition"));
+    assert_eq!(result.failed[1].id, "not-an-object-id");
+
+    // Успешный переход не откатывается из-за ошибок в пакете
+    let published = service.get_template(&ready).await?.unwrap();
+    assert_eq!(published.status, TemplateStatus::Published);
+    let untouched = service.get_template(&draft).await?.unwrap();
+    assert_eq!(untouched.status, TemplateStatus::Draft);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_template_version_diff_shows_changed_line() -> Result<()> {
+    let (_state, service, claims) = build_test_state().await?;
+    let template = create_template_fixture(&service, &claims).await?;
+
+    let first_edit = TemplateUpdateRequest {
+        status: None,
+        params: None,
+        metadata: None,
+        content: Some("Line one\nLine two\nLine three".to_string()),
+        difficulty: None,
+        source_refs: None,
+        age_band: None,
+        locale: None,
+        translations: Default::default(),
+    };
+    service
+        .update_template(&template, first_edit, &claims)
+        .await?;
+
+    let second_edit = TemplateUpdateRequest {
+        status: None,
+        params: None,
+        metadata: None,
+        content: Some("Line one\nLine 2 rewritten\nLine three".to_string()),
+        difficulty: Some("hard".to_string()),
+        source_refs: None,
+        age_band: None,
+        locale: None,
+        translations: Default::default(),
+    };
+    service
+        .update_template(&template, second_edit, &claims)
+        .await?;
+
+    let diff = service
+        .template_version_diff(&template, 3)
+        .await?
+        .expect("version 3 exists");
+    assert!(diff.available);
+    assert_eq!(diff.previous_version, Some(2));
+    let text = diff.diff.unwrap();
+    assert!(text.contains("-Line two\n+Line 2 rewritten\n"));
+    assert!(text.contains(" Line one\n"));
+    assert_eq!(diff.changed_fields.len(), 1);
+    assert_eq!(diff.changed_fields[0].field, "difficulty");
+
+    // Первая версия сравнивается с пустым шаблоном
+    let initial = service
+        .template_version_diff(&template, 1)
+        .await?
+        .expect("version 1 exists");
+    assert_eq!(initial.previous_version, None);
+    assert!(initial
+        .diff
+        .unwrap()
+        .contains("+Template for bulk status test"));
+
+    assert!(service
+        .template_version_diff(&template, 42)
+        .await?
+        .is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_archive_template_hides_it_until_unarchived() -> Result<()> {
+    let (state, service, claims) = build_test_state().await?;
+    let template = create_template_fixture(&service, &claims).await?;
+    let slug = service.get_template(&template).await?.unwrap().slug;
+    let by_slug = |include_archived| TemplateListQuery {
+        q: Some(slug.clone()),
+        include_archived,
+        ..Default::default()
+    };
+
+    // Активная сессия по задаче из шаблона блокирует архивацию
+    let task_id = ObjectId::new();
+    let session_id = Uuid::new_v4().to_string();
+    state
+        .mongo
+        .collection::<Document>("tasks")
+        .insert_one(doc! {
+            "_id": task_id,
+            "template_id": template,
+            "session_id": &session_id,
+            "content": { "text": "Task" },
+            "correct_answer": "a",
+            "createdAt": BsonDateTime::now(),
+        })
+        .await?;
+    let sessions = state.mongo.collection::<Document>("sessions");
+    sessions
+        .insert_one(doc! {
+            "_id": &session_id,
+            "user_id": "archive-test-user",
+            "task_id": task_id.to_hex(),
+            "started_at": BsonDateTime::now(),
+            "expires_at": BsonDateTime::from_millis(Utc::now().timestamp_millis() + 3_600_000),
+            "status": "active",
+            "hints_used": 0,
+            "score": 0,
+        })
+        .await?;
+
+    match service.archive_template(&template, &claims).await? {
+        TemplateArchiveOutcome::Blocked(references) => {
+            assert_eq!(references.len(), 1);
+            assert_eq!(references[0].kind, "session");
+            assert_eq!(references[0].id, session_id);
+        }
+        other => panic!("expected blocked archive, got {:?}", other),
+    }
+    assert!(!service.get_template(&template).await?.unwrap().archived);
+
+    // Завершённая сессия больше не мешает, но открытое домашнее задание с шаблоном - да
+    sessions
+        .update_one(
+            doc! { "_id": &session_id },
+            doc! { "$set": { "status": "completed" } },
+        )
+        .await?;
+    let assignment_id = ObjectId::new();
+    let assignments = state.mongo.collection::<Document>("assignments");
+    assignments
+        .insert_one(doc! {
+            "_id": assignment_id,
+            "groupId": ObjectId::new(),
+            "templateIds": [template],
+            "dueAt": BsonDateTime::from_millis(Utc::now().timestamp_millis() + 86_400_000),
+            "allowLate": false,
+            "minScore": 0,
+            "createdBy": "archive-test-teacher",
+            "createdAt": BsonDateTime::now(),
+            "updatedAt": BsonDateTime::now(),
+        })
+        .await?;
+
+    match service.archive_template(&template, &claims).await? {
+        TemplateArchiveOutcome::Blocked(references) => {
+            assert_eq!(references.len(), 1);
+            assert_eq!(references[0].kind, "assignment");
+            assert_eq!(references[0].id, assignment_id.to_hex());
+        }
+        other => panic!("expected blocked archive, got {:?}", other),
+    }
+
+    // Задание с прошедшим сроком без сдачи после срока закрыто
+    assignments
+        .update_one(
+            doc! { "_id": assignment_id },
+            doc! { "$set": { "dueAt": BsonDateTime::from_millis(Utc::now().timestamp_millis() - 60_000) } },
+        )
+        .await?;
+
+    match service.archive_template(&template, &claims).await? {
+        TemplateArchiveOutcome::Archived(summary) => assert!(summary.archived),
+        other => panic!("expected archived template, got {:?}", other),
+    }
+    assert!(service.list_templates(by_slug(false)).await?.is_empty());
+    let archived = service.list_templates(by_slug(true)).await?;
+    assert_eq!(archived.len(), 1);
+    assert!(archived[0].archived);
+
+    let audit = state
+        .mongo
+        .collection::<Document>("audit_log")
+        .find_one(doc! { "action": "template.archive", "target_id": template.to_hex() })
+        .await?;
+    assert!(audit.is_some());
+
+    match service.unarchive_template(&template, &claims).await? {
+        TemplateArchiveOutcome::Archived(summary) => assert!(!summary.archived),
+        other => panic!("expected unarchived template, got {:?}", other),
+    }
+    let visible = service.list_templates(by_slug(false)).await?;
+    assert_eq!(visible.len(), 1);
+    assert_eq!(visible[0].id, template.to_hex());
+
+    assert!(matches!(
+        service.archive_template(&ObjectId::new(), &claims).await?,
+        TemplateArchiveOutcome::NotFound
+    ));
+
+    sessions.delete_one(doc! { "_id": &session_id }).await?;
+    assignments
+        .delete_one(doc! { "_id": assignment_id })
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_merge_rule_repoints_templates_without_duplicates() -> Result<()> {
+    let (_state, service, claims) = build_test_state().await?;
+    let source = create_rule_fixture(&service, &claims, "Duplicate rule").await?;
+    let target = create_rule_fixture(&service, &claims, "Canonical rule").await?;
+
+    let topic = service
+        .create_topic(
+            TopicCreateRequest {
+                slug: format!("topic-{}", Uuid::new_v4()),
+                name: "Merge Topic".to_string(),
+                description: "Test".to_string(),
+                icon_url: None,
+                status: None,
+                age_band: None,
+            },
+            &claims,
+        )
+        .await?;
+    let level = service
+        .create_level(
+            LevelCreateRequest {
+                topic_id: topic.id.to_string(),
+                name: "Level".to_string(),
+                difficulty: LevelDifficulty::B1,
+                description: "Test".to_string(),
+                min_pass_percent: None,
+                order: Some(1),
+                prerequisite_level_ids: vec![],
+            },
+            &claims,
+        )
+        .await?;
+
+    let mut templates = Vec::new();
+    for rule_ids in [vec![source.id], vec![source.id, target.id], vec![target.id]] {
+        let template = service
+            .create_template(
+                TemplateCreateRequest {
+                    slug: format!("template-{}", Uuid::new_v4()),
+                    level_id: level.id.to_string(),
+                    rule_ids: rule_ids.iter().map(|id| id.to_hex()).collect(),
+                    params: serde_json::json!({}),
+                    metadata: serde_json::json!({}),
+                    content: "Template for rule merge".to_string(),
+                    difficulty: None,
+                    source_refs: vec![],
+                    age_band: None,
+                    locale: None,
+                    translations: Default::default(),
+                },
+                &claims,
+            )
+            .await?;
+        templates.push(template.id.parse::<ObjectId>()?);
+    }
+
+    assert!(matches!(
+        service.merge_rule(&source.id, &source.id, &claims).await?,
+        RuleMergeOutcome::SameRule
+    ));
+
+    let result = match service.merge_rule(&source.id, &target.id, &claims).await? {
+        RuleMergeOutcome::Merged(result) => result,
+        other => panic!("expected merge, got {:?}", other),
+    };
+    assert_eq!(result.templates_updated, 2);
+
+    for template in &templates {
+        let detail = service.get_template(template).await?.unwrap();
+        assert_eq!(detail.rule_ids, vec![target.id.to_hex()]);
+    }
+
+    let merged = service
+        .list_rules()
+        .await?
+        .into_iter()
+        .find(|rule| rule.id == source.id)
+        .unwrap();
+    assert_eq!(merged.status, RuleStatus::Deprecated);
+    assert_eq!(merged.merged_into, Some(target.id));
+
+    // Влитое правило стало deprecated и не может быть целью
+    assert!(matches!(
+        service.merge_rule(&target.id, &source.id, &claims).await?,
+        RuleMergeOutcome::TargetDeprecated
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_content_tree_counts_templates_by_status() -> Result<()> {
+    let (state, service, claims) = build_test_state().await?;
+    let topic = service
+        .create_topic(
+            TopicCreateRequest {
+                slug: format!("topic-{}", Uuid::new_v4()),
+                name: "Tree Topic".to_string(),
+                description: "Test".to_string(),
+                icon_url: None,
+                status: None,
+                age_band: None,
+            },
+            &claims,
+        )
+        .await?;
+
+    let mut levels = Vec::new();
+    for order in 1..=2 {
+        let level = service
+            .create_level(
+                LevelCreateRequest {
+                    topic_id: topic.id.to_string(),
+                    name: format!("Level {}", order),
+                    difficulty: LevelDifficulty::A1,
+                    description: "Test".to_string(),
+                    min_pass_percent: None,
+                    order: Some(order),
+                    prerequisite_level_ids: vec![],
+                },
+                &claims,
+            )
+            .await?;
+        levels.push(level.id);
+    }
+
+    // Первый уровень: 2 черновика и 1 опубликованный, второй: 1 черновик
+    let templates = state.mongo.collection::<Document>("templates");
+    for (level_id, status) in [
+        (levels[0], "draft"),
+        (levels[0], "draft"),
+        (levels[0], "published"),
+        (levels[1], "draft"),
+    ] {
+        let template = service
+            .create_template(
+                TemplateCreateRequest {
+                    slug: format!("template-{}", Uuid::new_v4()),
+                    level_id: level_id.to_hex(),
+                    rule_ids: vec![],
+                    params: serde_json::json!({}),
+                    metadata: serde_json::json!({}),
+                    content: "Template for content tree".to_string(),
+                    difficulty: None,
+                    source_refs: vec![],
+                    age_band: None,
+                    locale: None,
+                    translations: Default::default(),
+                },
+                &claims,
+            )
+            .await?;
+        templates
+            .update_one(
+                doc! { "_id": template.id.parse::<ObjectId>()? },
+                doc! { "$set": { "status": status } },
+            )
+            .await?;
+    }
+
+    let find_topic = |tree: Vec<ContentTreeTopic>| {
+        tree.into_iter()
+            .find(|node| node.id == topic.id.to_hex())
+            .expect("topic present in tree")
+    };
+
+    let node = find_topic(service.content_tree(ContentTreeQuery::default()).await?);
+    assert_eq!(node.levels.len(), 2);
+    assert_eq!(node.levels[0].template_counts["draft"], 2);
+    assert_eq!(node.levels[0].template_counts["published"], 1);
+    assert_eq!(node.levels[1].template_total, 1);
+    assert_eq!(node.template_counts["draft"], 3);
+    assert_eq!(node.template_total, 4);
+
+    // Устаревший уровень скрыт, пока не запрошен include_deprecated
+    state
+        .mongo
+        .collection::<Document>("levels")
+        .update_one(
+            doc! { "_id": levels[1] },
+            doc! { "$set": { "status": "deprecated" } },
+        )
+        .await?;
+    let node = find_topic(service.content_tree(ContentTreeQuery::default()).await?);
+    assert_eq!(node.levels.len(), 1);
+    assert_eq!(node.template_total, 3);
+
+    let node = find_topic(
+        service
+            .content_tree(ContentTreeQuery {
+                include_deprecated: true,
+            })
+            .await?,
+    );
+    assert_eq!(node.levels.len(), 2);
+
+    Ok(())
+}
+
+async fn create_rule_fixture(
+    service: &ContentService,
+    claims: &JwtClaims,
+    name: &str,
+) -> Result<RuleRecord> {
+    service
+        .create_rule(
+            RuleCreateRequest {
+                slug: format!("rule-{}", Uuid::new_v4()),
+                name: name.to_string(),
+                category: "orthography".to_string(),
+                description: "Rule for merge test".to_string(),
+                examples: vec![],
+                exceptions: vec![],
+                sources: vec![],
+                status: None,
+            },
+            claims,
+        )
+        .await
+}
+
+/// Документ без идентификаторов, ссылок и отметок времени
+fn strip_ids(mut document: Document) -> Document {
+    for key in [
+        "_id",
+        "topic_id",
+        "level_id",
+        "rule_ids",
+        "createdAt",
+        "updatedAt",
+        "created_at",
+        "updated_at",
+    ] {
+        document.remove(key);
+    }
+    document
+}
+
+async fn load_by_ids(
+    state: &AppState,
+    collection: &str,
+    ids: &[ObjectId],
+) -> Result<Vec<Document>> {
+    let mut documents = Vec::new();
+    for id in ids {
+        let document = state
+            .mongo
+            .collection::<Document>(collection)
+            .find_one(doc! { "_id": id })
+            .await?
+            .expect("document exists");
+        documents.push(document);
+    }
+    Ok(documents)
+}
+
+#[tokio::test]
+async fn test_template_bundle_round_trip() -> Result<()> {
+    let (state, service, claims) = build_test_state().await?;
+    let suffix = Uuid::new_v4().simple().to_string();
+
+    let topic = service
+        .create_topic(
+            TopicCreateRequest {
+                slug: format!("bundle-topic-{}", suffix),
+                name: "Корни с чередованием".to_string(),
+                description: "Тема для пакета".to_string(),
+                icon_url: None,
+                status: None,
+                age_band: None,
+            },
+            &claims,
+        )
+        .await?;
+    let level = service
+        .create_level(
+            LevelCreateRequest {
+                topic_id: topic.id.to_hex(),
+                name: "Лаг-лож".to_string(),
+                difficulty: LevelDifficulty::A2,
+                description: "Корни лаг/лож".to_string(),
+                min_pass_percent: Some(75),
+                order: Some(2),
+                prerequisite_level_ids: vec![],
+            },
+            &claims,
+        )
+        .await?;
+    let mut rule_ids = Vec::new();
+    for name in ["a", "b"] {
+        let rule = service
+            .create_rule(
+                RuleCreateRequest {
+                    slug: format!("bundle-rule-{}-{}", name, suffix),
+                    name: format!("Правило {}", name),
+                    category: "orthography".to_string(),
+                    description: "Правило для пакета".to_string(),
+                    examples: vec!["положить".to_string()],
+                    exceptions: vec!["полог".to_string()],
+                    sources: vec!["Розенталь".to_string()],
+                    status: None,
+                },
+                &claims,
+            )
+            .await?;
+        rule_ids.push(rule.id);
+    }
+    let mut template_ids = Vec::new();
+    for (index, rules) in [vec![rule_ids[1], rule_ids[0]], vec![rule_ids[0]]]
+        .into_iter()
+        .enumerate()
+    {
+        let template = service
+            .create_template(
+                TemplateCreateRequest {
+                    slug: format!("bundle-template-{}-{}", index, suffix),
+                    level_id: level.id.to_hex(),
+                    rule_ids: rules.iter().map(|id| id.to_hex()).collect(),
+                    params: serde_json::json!({
+                        "answers": { "accepted": ["о"] },
+                        "points": 2,
+                    }),
+                    metadata: serde_json::json!({ "author": "methodist" }),
+                    content: format!("Пол..жить {}", index),
+                    difficulty: Some("A2".to_string()),
+                    source_refs: vec!["textbook:7".to_string()],
+                    age_band: None,
+                    locale: None,
+                    translations: Default::default(),
+                },
+                &claims,
+            )
+            .await?;
+        template_ids.push(template.id.parse::<ObjectId>()?);
+    }
+
+    let bundle = service.export_templates(&template_ids).await?;
+    // Пакет переживает сериализацию в JSON
+    let bundle: TemplateBundle = serde_json::from_value(serde_json::to_value(&bundle)?)?;
+    assert_eq!(bundle.topics.len(), 1);
+    assert_eq!(bundle.levels.len(), 1);
+    assert_eq!(bundle.rules.len(), 2);
+    assert_eq!(bundle.templates.len(), 2);
+
+    let topics_before = load_by_ids(&state, "topics", &[topic.id]).await?;
+    let levels_before = load_by_ids(&state, "levels", &[level.id]).await?;
+    let rules_before = load_by_ids(&state, "rules", &rule_ids).await?;
+    let templates_before = load_by_ids(&state, "templates", &template_ids).await?;
+    for (collection, ids) in [
+        ("topics", vec![topic.id]),
+        ("levels", vec![level.id]),
+        ("rules", rule_ids.clone()),
+        ("templates", template_ids.clone()),
+    ] {
+        state
+            .mongo
+            .collection::<Document>(collection)
+            .delete_many(doc! { "_id": { "$in": ids } })
+            .await?;
+    }
+
+    // Без create_missing ссылки на удалённые сущности не разрешаются
+    let report = service
+        .import_templates(bundle.clone(), TemplateImportOptions::default(), &claims)
+        .await?;
+    assert!(report
+        .items
+        .iter()
+        .all(|item| item.outcome == TemplateImportOutcome::Failed));
+    assert!(report.created_topics.is_empty());
+
+    let create_missing = TemplateImportOptions {
+        create_missing: true,
+        overwrite: false,
+    };
+    let report = service
+        .import_templates(bundle.clone(), create_missing, &claims)
+        .await?;
+    assert_eq!(report.created_topics, vec![topic.slug.clone()]);
+    assert_eq!(report.created_levels.len(), 1);
+    assert_eq!(report.created_rules.len(), 2);
+    assert!(report
+        .items
+        .iter()
+        .all(|item| item.outcome == TemplateImportOutcome::Created));
+
+    let new_topic = service
+        .list_topics()
+        .await?
+        .into_iter()
+        .find(|candidate| candidate.slug == topic.slug)
+        .expect("topic imported");
+    let new_levels = service.list_levels_for_topic(&new_topic.id).await?;
+    assert_eq!(new_levels.len(), 1);
+    let mut new_rule_ids = Vec::new();
+    for rule in &rules_before {
+        let slug = rule.get_str("slug")?;
+        let imported = service
+            .list_rules()
+            .await?
+            .into_iter()
+            .find(|candidate| candidate.slug == slug)
+            .expect("rule imported");
+        new_rule_ids.push(imported.id);
+    }
+    let new_template_ids: Vec<ObjectId> = report
+        .items
+        .iter()
+        .map(|item| item.template_id.as_deref().unwrap().parse())
+        .collect::<Result<_, _>>()?;
+
+    let strip = |documents: Vec<Document>| documents.into_iter().map(strip_ids).collect::<Vec<_>>();
+    assert_eq!(
+        strip(load_by_ids(&state, "topics", &[new_topic.id]).await?),
+        strip(topics_before)
+    );
+    assert_eq!(
+        strip(load_by_ids(&state, "levels", &[new_levels[0].id]).await?),
+        strip(levels_before)
+    );
+    assert_eq!(
+        strip(load_by_ids(&state, "rules", &new_rule_ids).await?),
+        strip(rules_before)
+    );
+    let templates_after = load_by_ids(&state, "templates", &new_template_ids).await?;
+    assert_eq!(strip(templates_after.clone()), strip(templates_before));
+    // Ссылки указывают на новые уровень и правила в исходном порядке
+    assert_eq!(
+        templates_after[0].get_object_id("level_id")?,
+        new_levels[0].id
+    );
+    assert_eq!(
+        templates_after[0].get_array("rule_ids")?,
+        &vec![new_rule_ids[1].into(), new_rule_ids[0].into()]
+    );
+
+    // Повторный импорт: slug уже занят
+    let report = service
+        .import_templates(bundle.clone(), create_missing, &claims)
+        .await?;
+    assert!(report
+        .items
+        .iter()
+        .all(|item| item.outcome == TemplateImportOutcome::Skipped));
+    assert!(report.created_rules.is_empty());
+
+    // overwrite обновляет шаблон через обычный путь: новая версия, статус draft
+    let mut changed = bundle.clone();
+    changed.templates[0].content = "Пол..гать".to_string();
+    let report = service
+        .import_templates(
+            changed,
+            TemplateImportOptions {
+                create_missing: false,
+                overwrite: true,
+            },
+            &claims,
+        )
+        .await?;
+    assert_eq!(report.items[0].outcome, TemplateImportOutcome::Updated);
+    assert_eq!(report.items[0].version, Some(2));
+    let updated = service.get_template(&new_template_ids[0]).await?.unwrap();
+    assert_eq!(updated.content, "Пол..гать");
+    assert_eq!(updated.status, TemplateStatus::Draft);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_template_locales_are_unique_per_level_and_filterable() -> Result<()> {
+    let (_state, service, claims) = build_test_state().await?;
+    let base_id = create_template_fixture(&service, &claims).await?;
+    let base = service.get_template(&base_id).await?.unwrap();
+    assert_eq!(base.locale, "ru");
+    let level_id = base.level.as_ref().unwrap().id.clone();
+
+    let english = |translations| TemplateCreateRequest {
+        slug: base.slug.clone(),
+        level_id: level_id.clone(),
+        rule_ids: base.rule_ids.clone(),
+        params: serde_json::json!({}),
+        metadata: serde_json::json!({ "title": "Commas" }),
+        content: "Put the commas in".to_string(),
+        difficulty: None,
+        source_refs: vec![],
+        age_band: None,
+        locale: Some("en".to_string()),
+        translations,
+    };
+
+    // Тот же slug на другом языке допустим
+    let created = service
+        .create_template(english(Default::default()), &claims)
+        .await?;
+    assert_eq!(created.locale, "en");
+
+    let duplicate = service
+        .create_template(english(Default::default()), &claims)
+        .await;
+    assert!(duplicate.is_err());
+
+    let mut invalid = english(Default::default());
+    invalid.slug = format!("template-{}", Uuid::new_v4());
+    invalid.locale = Some("English".to_string());
+    let error = service.create_template(invalid, &claims).await.unwrap_err();
+    assert!(error.downcast_ref::<InvalidLocaleError>().is_some());
+
+    // Переводы проверяются на персональные данные так же, как основное содержимое
+    let mut with_pii = english(
+        [(
+            "de".to_string(),
+            TemplateTranslationRequest {
+                content: Some("Schreiben Sie an test@example.com".to_string()),
+                metadata: None,
+            },
+        )]
+        .into_iter()
+        .collect(),
+    );
+    with_pii.slug = format!("template-{}", Uuid::new_v4());
+    let error = service
+        .create_template(with_pii, &claims)
+        .await
+        .unwrap_err();
+    assert!(format!("{:#}", error).contains("PII detected"));
+
+    let list = |locale: &str| TemplateListQuery {
+        status: None,
+        topic_id: None,
+        level_id: Some(level_id.clone()),
+        difficulty: None,
+        version: None,
+        q: None,
+        locale: Some(locale.to_string()),
+        limit: None,
+        include_archived: false,
+    };
+    let english_only = service.list_templates(list("en")).await?;
+    assert_eq!(english_only.len(), 1);
+    assert_eq!(english_only[0].id, created.id);
+    let russian_only = service.list_templates(list("ru")).await?;
+    assert_eq!(russian_only.len(), 1);
+    assert_eq!(russian_only[0].id, base_id.to_hex());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_lint_errors_block_submission_but_not_draft_save() -> Result<()> {
+    let (_state, service, claims) = build_test_state().await?;
+    let topic = service
+        .create_topic(
+            TopicCreateRequest {
+                slug: format!("topic-{}", Uuid::new_v4()),
+                name: "Lint Topic".to_string(),
+                description: "Test".to_string(),
+                icon_url: None,
+                status: None,
+                age_band: None,
+            },
+            &claims,
+        )
+        .await?;
+    let level = service
+        .create_level(
+            LevelCreateRequest {
+                topic_id: topic.id.to_string(),
+                name: "Lint Level".to_string(),
+                difficulty: LevelDifficulty::A1,
+                description: "Test".to_string(),
+                min_pass_percent: None,
+                order: Some(1),
+                prerequisite_level_ids: vec![],
+            },
+            &claims,
+        )
+        .await?;
+    let rule = service
+        .create_rule(
+            RuleCreateRequest {
+                slug: format!("rule-{}", Uuid::new_v4()),
+                name: "Lint Rule".to_string(),
+                category: "orthography".to_string(),
+                description: "Rule for testing".to_string(),
+                examples: vec!["Нет книги".to_string()],
+                exceptions: vec![],
+                sources: vec![],
+                status: None,
+            },
+            &claims,
+        )
+        .await?;
+
+    // Черновик с `{{option}}` без params.options сохраняется вместе с находкой
+    let template = service
+        .create_template(
+            TemplateCreateRequest {
+                slug: format!("template-{}", Uuid::new_v4()),
+                level_id: level.id.to_string(),
+                rule_ids: vec![rule.id.to_string()],
+                params: serde_json::json!({}),
+                metadata: serde_json::json!({}),
+                content: "Выберите форму: нет {{option:0}}".to_string(),
+                difficulty: Some("A1".to_string()),
+                source_refs: vec![],
+                age_band: None,
+                locale: None,
+                translations: Default::default(),
+            },
+            &claims,
+        )
+        .await?;
+    assert_eq!(template.status, TemplateStatus::Draft);
+    assert_eq!(template.lint_findings.len(), 1);
+    assert_eq!(template.lint_findings[0].code, "placeholder_missing_param");
+    let template_id = template.id.parse::<ObjectId>()?;
+
+    let error = service
+        .submit_template_for_moderation(&template_id, &claims)
+        .await
+        .unwrap_err();
+    let lint = error
+        .downcast_ref::<TemplateLintError>()
+        .expect("submission should fail with lint errors");
+    assert!(lint.0[0].starts_with("placeholder_missing_param"));
+    let error = service
+        .update_template(&template_id, status_update("pendingreview"), &claims)
+        .await
+        .unwrap_err();
+    assert!(error.downcast_ref::<TemplateLintError>().is_some());
+
+    let report = service.lint_template(&template_id).await?.unwrap();
+    assert_eq!(report.findings, template.lint_findings);
+    let issues = service.validate_all_templates().await?;
+    assert!(issues.iter().any(|issue| issue.template_id == template.id
+        && issue.severity == "error"
+        && issue.reason.starts_with("Lint placeholder_missing_param")));
+
+    let mut fix = status_update("draft");
+    fix.status = None;
+    fix.params = Some(serde_json::json!({ "options": ["книги", "книга"] }));
+    let fixed = service.update_template(&template_id, fix, &claims).await?;
+    assert!(fixed.lint_findings.is_empty());
+    let pending = service
+        .submit_template_for_moderation(&template_id, &claims)
+        .await?;
+    assert_eq!(pending.status, TemplateStatus::PendingReview);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_second_approval_requires_a_different_reviewer() -> Result<()> {
+    let (_state, service, claims) = build_test_state().await?;
+    let template_id = create_template_fixture(&service, &claims).await?;
+    service
+        .submit_template_for_moderation(&template_id, &claims)
+        .await?;
+    let reviewed = service.approve_template(&template_id, &claims).await?;
+    assert_eq!(reviewed.status, TemplateStatus::ReviewedOnce);
+
+    // Тот же модератор не даёт второе одобрение и не может быть на него назначен
+    let error = service
+        .approve_template(&template_id, &claims)
+        .await
+        .unwrap_err();
+    assert!(error.downcast_ref::<DuplicateApprovalError>().is_some());
+    let unchanged = service.get_template(&template_id).await?.unwrap();
+    assert_eq!(unchanged.status, TemplateStatus::ReviewedOnce);
+    assert_eq!(unchanged.reviewers, vec![claims.sub.clone()]);
+    assert!(unchanged.status_changed_at.is_some());
+    let outcome = service
+        .assign_reviewer(&template_id, &claims.sub, &claims)
+        .await?;
+    assert!(matches!(outcome, ReviewerAssignOutcome::AlreadyApproved));
+
+    let second_reviewer = JwtClaims {
+        sub: Uuid::new_v4().to_string(),
+        ..claims.clone()
+    };
+    let ready = service
+        .approve_template(&template_id, &second_reviewer)
+        .await?;
+    assert_eq!(ready.status, TemplateStatus::Ready);
+    assert_eq!(ready.reviewers, vec![claims.sub, second_reviewer.sub]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_moderation_queue_filters_by_topic_reviewer_and_age() -> Result<()> {
+    let (state, service, claims) = build_test_state().await?;
+    let fresh = create_template_fixture(&service, &claims).await?;
+    let stale = create_template_fixture(&service, &claims).await?;
+    for template_id in [fresh, stale] {
+        service
+            .submit_template_for_moderation(&template_id, &claims)
+            .await?;
+    }
+    let reviewer = Uuid::new_v4().to_string();
+    match service.assign_reviewer(&fresh, &reviewer, &claims).await? {
+        ReviewerAssignOutcome::Assigned(summary) => {
+            assert_eq!(summary.assigned_reviewer, Some(reviewer.clone()))
+        }
+        other => panic!("unexpected assignment outcome: {:?}", other),
+    }
+    // Второй шаблон ждёт модерации двое суток
+    let two_days_ago =
+        BsonDateTime::from_millis(BsonDateTime::now().timestamp_millis() - 2 * 24 * 60 * 60 * 1000);
+    state
+        .mongo
+        .collection::<Document>("templates")
+        .update_one(
+            doc! { "_id": stale },
+            doc! { "$set": { "status_changed_at": two_days_ago } },
+        )
+        .await?;
+
+    // Каждая фикстура - в своей теме, фильтр по теме отделяет их от остальной очереди
+    let fresh_topic = template_topic(&service, &fresh).await?;
+    let stale_topic = template_topic(&service, &stale).await?;
+
+    let by_topic = service
+        .moderation_queue(Some(fresh_topic), None, None, None)
+        .await?;
+    assert_eq!(queued_ids(by_topic), vec![fresh.to_hex()]);
+    let assigned = service
+        .moderation_queue(Some(fresh_topic), None, Some(&reviewer), None)
+        .await?;
+    assert_eq!(queued_ids(assigned), vec![fresh.to_hex()]);
+    let unassigned = service
+        .moderation_queue(Some(fresh_topic), None, Some("none"), None)
+        .await?;
+    assert!(unassigned.is_empty());
+
+    let waiting = service
+        .moderation_queue(
+            Some(stale_topic),
+            Some(ModerationQueueAge::OneToThreeDays),
+            Some("none"),
+            None,
+        )
+        .await?;
+    assert_eq!(waiting.len(), 1);
+    assert_eq!(waiting[0].template.id, stale.to_hex());
+    assert_eq!(waiting[0].age, ModerationQueueAge::OneToThreeDays);
+    assert!(waiting[0].time_in_queue_secs >= 2 * 24 * 60 * 60);
+    let recent = service
+        .moderation_queue(
+            Some(stale_topic),
+            Some(ModerationQueueAge::UnderOneDay),
+            None,
+            None,
+        )
+        .await?;
+    assert!(recent.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rollback_publish_restores_first_published_snapshot() -> Result<()> {
+    let (state, service, claims) = build_test_state().await?;
+    let template_id = create_template_fixture(&service, &claims).await?;
+    let first_content = service.get_template(&template_id).await?.unwrap().content;
+
+    review_to_ready(&service, &template_id, &claims).await?;
+    match service.publish_template(&template_id, &claims).await? {
+        TemplatePublishOutcome::Published(summary) => {
+            assert_eq!(summary.status, TemplateStatus::Published);
+            assert_eq!(summary.version, 1);
+        }
+        other => panic!("unexpected publish outcome: {:?}", other),
+    }
+    // Опубликованный шаблон повторно не публикуется
+    let outcome = service.publish_template(&template_id, &claims).await?;
+    assert!(matches!(
+        outcome,
+        TemplatePublishOutcome::NotReady(TemplateStatus::Published)
+    ));
+
+    // Правка содержимого возвращает шаблон в черновик версии 2, её снова ревьюят и публикуют
+    let mut edit = status_update("draft");
+    edit.status = None;
+    edit.content = Some("Template content after the first publication".to_string());
+    let edited = service.update_template(&template_id, edit, &claims).await?;
+    assert_eq!(edited.version, 2);
+    review_to_ready(&service, &template_id, &claims).await?;
+    service.publish_template(&template_id, &claims).await?;
+    let second = service.get_template(&template_id).await?.unwrap();
+    assert_eq!(
+        second.content,
+        "Template content after the first publication"
+    );
+    assert!(second.published_at.is_some());
+
+    let published_versions = state.mongo.collection::<Document>("published_versions");
+    let first_record = published_versions
+        .find_one(doc! { "template_id": template_id, "version": 1 })
+        .await?
+        .expect("first publication snapshot");
+    assert_eq!(
+        first_record.get_document("snapshot")?.get_str("content")?,
+        first_content
+    );
+
+    match service
+        .rollback_published_version(&template_id, 1, &claims)
+        .await?
+    {
+        TemplatePublishOutcome::Published(summary) => {
+            assert_eq!(summary.status, TemplateStatus::Published);
+            assert_eq!(summary.version, 3);
+        }
+        other => panic!("unexpected rollback outcome: {:?}", other),
+    }
+    let restored = service.get_template(&template_id).await?.unwrap();
+    assert_eq!(restored.content, first_content);
+    assert_eq!(restored.status, TemplateStatus::Published);
+
+    let rollback_record = published_versions
+        .find_one(doc! { "template_id": template_id, "version": 3 })
+        .await?
+        .expect("rollback snapshot");
+    assert_eq!(rollback_record.get_i32("restored_from")?, 1);
+    let republished = state
+        .mongo
+        .collection::<Document>("content_outbox")
+        .find_one(doc! { "template_id": template_id, "event": "republished" })
+        .await?;
+    assert!(republished.is_some());
+
+    let missing = service
+        .rollback_published_version(&template_id, 99, &claims)
+        .await?;
+    assert!(matches!(missing, TemplatePublishOutcome::VersionNotFound));
+
+    Ok(())
+}
+
+async fn review_to_ready(
+    service: &ContentService,
+    template_id: &ObjectId,
+    claims: &JwtClaims,
+) -> Result<()> {
+    service
+        .submit_template_for_moderation(template_id, claims)
+        .await?;
+    service.approve_template(template_id, claims).await?;
+    let second_reviewer = JwtClaims {
+        sub: Uuid::new_v4().to_string(),
+        ..claims.clone()
+    };
+    let ready = service
+        .approve_template(template_id, &second_reviewer)
+        .await?;
+    assert_eq!(ready.status, TemplateStatus::Ready);
+    Ok(())
+}
+
+async fn template_topic(service: &ContentService, template_id: &ObjectId) -> Result<ObjectId> {
+    let detail = service.get_template(template_id).await?.unwrap();
+    Ok(ObjectId::parse_str(detail.topic.unwrap().id)?)
+}
+
+fn queued_ids(items: Vec<ModerationQueueItem>) -> Vec<String> {
+    items.into_iter().map(|item| item.template.id).collect()
+}
+
+fn status_update(status: &str) -> TemplateUpdateRequest {
+    TemplateUpdateRequest {
+        status: Some(status.to_string()),
+        params: None,
+        metadata: None,
+        content: None,
+        difficulty: None,
+        source_refs: None,
+        age_band: None,
+        locale: None,
+        translations: Default::default(),
+    }
+}
+
+async fn create_template_fixture(service: &ContentService, claims: &JwtClaims) -> Result<ObjectId> {
+    let topic = service
+        .create_topic(
+            TopicCreateRequest {
+                slug: format!("topic-{}", Uuid::new_v4()),
+                name: "Bulk Topic".to_string(),
+                description: "Test".to_string(),
+                icon_url: None,
+                status: None,
+                age_band: None,
+            },
+            claims,
+        )
+        .await?;
+
+    let level = service
+        .create_level(
+            LevelCreateRequest {
+                topic_id: topic.id.to_string(),
+                name: "Level".to_string(),
+                difficulty: LevelDifficulty::B1,
+                description: "Test".to_string(),
+                min_pass_percent: None,
+                order: Some(1),
+                prerequisite_level_ids: vec![],
+            },
+            claims,
+        )
+        .await?;
+
+    let rule = service
+        .create_rule(
+            RuleCreateRequest {
+                slug: format!("rule-{}", Uuid::new_v4()),
+                name: "Bulk Rule".to_string(),
+                category: "orthography".to_string(),
+                description: "Rule for testing".to_string(),
+                examples: vec!["Пример".to_string()],
+                exceptions: vec![],
+                sources: vec![],
+                status: None,
+            },
+            claims,
+        )
+        .await?;
+
+    let template = service
+        .create_template(
+            TemplateCreateRequest {
+                slug: format!("template-{}", Uuid::new_v4()),
+                level_id: level.id.to_string(),
+                rule_ids: vec![rule.id.to_string()],
+                params: serde_json::json!({}),
+                metadata: serde_json::json!({}),
+                content: "Template for bulk status test".to_string(),
+                difficulty: None,
+                source_refs: vec![],
+                age_band: None,
+                locale: None,
+                translations: Default::default(),
+            },
+            claims,
+        )
+        .await?;
+
+    Ok(template.id.parse::<ObjectId>()?)
+}
@@ -9,10 +9,13 @@ use trainingground_api::{
     config::Config,
     middlewares::auth::JwtClaims,
     models::content::{
-        LevelCreateRequest, LevelDifficulty, LevelReorderRequest, LevelStatus, LevelUpdateRequest,
-        TopicCreateRequest, TopicStatus, TopicUpdateRequest,
+        AgeBand, LevelCreateRequest, LevelDifficulty, LevelReorderRequest, LevelStatus,
+        LevelUpdateRequest, TopicCreateRequest, TopicStatus, TopicUpdateRequest,
+    },
+    services::{
+        content_service::{ContentService, LevelPrerequisiteError},
+        AppState,
     },
-    services::{content_service::ContentService, AppState},
 };
 
 async fn build_test_state() -> Result<(Arc<AppState>, ContentService, JwtClaims)> {
@@ -47,6 +50,7 @@ async fn test_create_topic_with_valid_data() -> Result<()> {
                 description: "Правила орфографии русского языка".to_string(),
                 icon_url: Some("https://example.com/icon.png".to_string()),
                 status: None,
+                age_band: None,
             },
             &claims,
         )
@@ -70,6 +74,7 @@ async fn test_create_topic_with_invalid_slug() -> Result<()> {
                 description: "Test".to_string(),
                 icon_url: None,
                 status: None,
+                age_band: None,
             },
             &claims,
         )
@@ -92,6 +97,7 @@ async fn test_list_topics() -> Result<()> {
                 description: "Test".to_string(),
                 icon_url: None,
                 status: None,
+                age_band: None,
             },
             &claims,
         )
@@ -105,6 +111,7 @@ async fn test_list_topics() -> Result<()> {
                 description: "Test".to_string(),
                 icon_url: None,
                 status: None,
+                age_band: None,
             },
             &claims,
         )
@@ -130,6 +137,7 @@ async fn test_update_topic() -> Result<()> {
                 description: "Original description".to_string(),
                 icon_url: None,
                 status: None,
+                age_band: None,
             },
             &claims,
         )
@@ -143,6 +151,7 @@ async fn test_update_topic() -> Result<()> {
                 description: Some("Updated description".to_string()),
                 icon_url: None,
                 status: None,
+                age_band: None,
             },
             &claims,
         )
@@ -154,6 +163,39 @@ async fn test_update_topic() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_topic_age_band_cleared_by_null() -> Result<()> {
+    let (_state, service, claims) = build_test_state().await?;
+
+    let topic = service
+        .create_topic(
+            TopicCreateRequest {
+                slug: format!("topic-{}", Uuid::new_v4()),
+                name: "Rated Topic".to_string(),
+                description: "Test".to_string(),
+                icon_url: None,
+                status: None,
+                age_band: Some(AgeBand::Senior),
+            },
+            &claims,
+        )
+        .await?;
+    assert_eq!(topic.age_band, Some(AgeBand::Senior));
+
+    // Поле не передано - категория остаётся
+    let payload: TopicUpdateRequest =
+        serde_json::from_value(serde_json::json!({ "name": "Renamed" }))?;
+    let updated = service.update_topic(&topic.id, payload, &claims).await?;
+    assert_eq!(updated.age_band, Some(AgeBand::Senior));
+
+    let payload: TopicUpdateRequest =
+        serde_json::from_value(serde_json::json!({ "age_band": null }))?;
+    let cleared = service.update_topic(&topic.id, payload, &claims).await?;
+    assert_eq!(cleared.age_band, None);
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_deactivate_topic() -> Result<()> {
     let (_state, service, claims) = build_test_state().await?;
@@ -166,6 +208,7 @@ async fn test_deactivate_topic() -> Result<()> {
                 description: "Test".to_string(),
                 icon_url: None,
                 status: None,
+                age_band: None,
             },
             &claims,
         )
@@ -179,6 +222,7 @@ async fn test_deactivate_topic() -> Result<()> {
                 description: None,
                 icon_url: None,
                 status: Some(TopicStatus::Deprecated),
+                age_band: None,
             },
             &claims,
         )
@@ -201,6 +245,7 @@ async fn test_delete_topic() -> Result<()> {
                 description: "Test".to_string(),
                 icon_url: None,
                 status: None,
+                age_band: None,
             },
             &claims,
         )
@@ -226,6 +271,7 @@ async fn test_create_level_with_valid_data() -> Result<()> {
                 description: "Test".to_string(),
                 icon_url: None,
                 status: None,
+                age_band: None,
             },
             &claims,
         )
@@ -240,6 +286,7 @@ async fn test_create_level_with_valid_data() -> Result<()> {
                 description: "Basic level for beginners".to_string(),
                 min_pass_percent: Some(75),
                 order: None,
+                prerequisite_level_ids: vec![],
             },
             &claims,
         )
@@ -263,6 +310,7 @@ async fn test_list_levels_for_topic() -> Result<()> {
                 description: "Test".to_string(),
                 icon_url: None,
                 status: None,
+                age_band: None,
             },
             &claims,
         )
@@ -278,6 +326,7 @@ async fn test_list_levels_for_topic() -> Result<()> {
                 description: String::new(),
                 min_pass_percent: None,
                 order: None,
+                prerequisite_level_ids: vec![],
             },
             &claims,
         )
@@ -292,6 +341,7 @@ async fn test_list_levels_for_topic() -> Result<()> {
                 description: String::new(),
                 min_pass_percent: None,
                 order: None,
+                prerequisite_level_ids: vec![],
             },
             &claims,
         )
@@ -316,6 +366,7 @@ async fn test_update_level() -> Result<()> {
                 description: "Test".to_string(),
                 icon_url: None,
                 status: None,
+                age_band: None,
             },
             &claims,
         )
@@ -330,6 +381,7 @@ async fn test_update_level() -> Result<()> {
                 description: "Original".to_string(),
                 min_pass_percent: Some(80),
                 order: None,
+                prerequisite_level_ids: vec![],
             },
             &claims,
         )
@@ -344,6 +396,7 @@ async fn test_update_level() -> Result<()> {
                 min_pass_percent: Some(75),
                 difficulty: None,
                 status: None,
+                prerequisite_level_ids: None,
             },
             &claims,
         )
@@ -367,6 +420,7 @@ async fn test_delete_level() -> Result<()> {
                 description: "Test".to_string(),
                 icon_url: None,
                 status: None,
+                age_band: None,
             },
             &claims,
         )
@@ -381,6 +435,7 @@ async fn test_delete_level() -> Result<()> {
                 description: String::new(),
                 min_pass_percent: None,
                 order: None,
+                prerequisite_level_ids: vec![],
             },
             &claims,
         )
@@ -403,6 +458,7 @@ async fn test_reorder_levels() -> Result<()> {
                 description: "Test".to_string(),
                 icon_url: None,
                 status: None,
+                age_band: None,
             },
             &claims,
         )
@@ -418,6 +474,7 @@ async fn test_reorder_levels() -> Result<()> {
                 description: String::new(),
                 min_pass_percent: None,
                 order: None,
+                prerequisite_level_ids: vec![],
             },
             &claims,
         )
@@ -432,6 +489,7 @@ async fn test_reorder_levels() -> Result<()> {
                 description: String::new(),
                 min_pass_percent: None,
                 order: None,
+                prerequisite_level_ids: vec![],
             },
             &claims,
         )
@@ -446,6 +504,7 @@ async fn test_reorder_levels() -> Result<()> {
                 description: String::new(),
                 min_pass_percent: None,
                 order: None,
+                prerequisite_level_ids: vec![],
             },
             &claims,
         )
@@ -484,6 +543,7 @@ async fn test_level_default_min_pass_percent() -> Result<()> {
                 description: "Test".to_string(),
                 icon_url: None,
                 status: None,
+                age_band: None,
             },
             &claims,
         )
@@ -498,6 +558,7 @@ async fn test_level_default_min_pass_percent() -> Result<()> {
                 description: String::new(),
                 min_pass_percent: None,
                 order: None,
+                prerequisite_level_ids: vec![],
             },
             &claims,
         )
@@ -520,6 +581,7 @@ async fn test_create_multiple_difficulty_levels() -> Result<()> {
                 description: "Test".to_string(),
                 icon_url: None,
                 status: None,
+                age_band: None,
             },
             &claims,
         )
@@ -542,6 +604,7 @@ async fn test_create_multiple_difficulty_levels() -> Result<()> {
                     description: String::new(),
                     min_pass_percent: None,
                     order: None,
+                    prerequisite_level_ids: vec![],
                 },
                 &claims,
             )
@@ -552,3 +615,84 @@ async fn test_create_multiple_difficulty_levels() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_level_prerequisites_are_validated() -> Result<()> {
+    let (_state, service, claims) = build_test_state().await?;
+
+    let mut topic_ids = Vec::new();
+    for _ in 0..2 {
+        let topic = service
+            .create_topic(
+                TopicCreateRequest {
+                    slug: format!("topic-{}", Uuid::new_v4()),
+                    name: "Prerequisite Topic".to_string(),
+                    description: "Test".to_string(),
+                    icon_url: None,
+                    status: None,
+                    age_band: None,
+                },
+                &claims,
+            )
+            .await?;
+        topic_ids.push(topic.id.to_string());
+    }
+
+    let level_request =
+        |topic_id: &str, name: &str, prerequisites: Vec<String>| LevelCreateRequest {
+            topic_id: topic_id.to_string(),
+            name: name.to_string(),
+            difficulty: LevelDifficulty::A1,
+            description: "Test".to_string(),
+            min_pass_percent: None,
+            order: None,
+            prerequisite_level_ids: prerequisites,
+        };
+
+    let first = service
+        .create_level(level_request(&topic_ids[0], "First", vec![]), &claims)
+        .await?;
+    let second = service
+        .create_level(
+            level_request(&topic_ids[0], "Second", vec![first.id.to_hex()]),
+            &claims,
+        )
+        .await?;
+    assert_eq!(second.prerequisite_level_ids, vec![first.id]);
+
+    // Пререквизит из другой темы
+    let err = service
+        .create_level(
+            level_request(&topic_ids[1], "Foreign", vec![first.id.to_hex()]),
+            &claims,
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<LevelPrerequisiteError>(),
+        Some(LevelPrerequisiteError::OtherTopic(_))
+    ));
+
+    // First -> Second -> First
+    let err = service
+        .update_level(
+            &first.id,
+            LevelUpdateRequest {
+                name: None,
+                description: None,
+                difficulty: None,
+                min_pass_percent: None,
+                status: None,
+                prerequisite_level_ids: Some(vec![second.id.to_hex()]),
+            },
+            &claims,
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<LevelPrerequisiteError>(),
+        Some(LevelPrerequisiteError::Cycle(_))
+    ));
+
+    Ok(())
+}
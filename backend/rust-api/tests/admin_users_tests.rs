@@ -747,6 +747,272 @@ async fn test_block_and_unblock_user() {
     assert!(response_json["block_reason"].is_null());
 }
 
+/// Helper: зарегистрировать ученика и залогиниться; возвращает (user_id, access_token)
+async fn register_and_login(app: &axum::Router) -> (String, String) {
+    let email = format!("revoke-{}@test.com", Uuid::new_v4());
+    let credentials = json!({ "email": email, "password": "Student123!@#" });
+    let register_body = json!({
+        "email": email,
+        "password": "Student123!@#",
+        "name": "Revoked Student",
+    });
+
+    let mut user_id = String::new();
+    let mut token = String::new();
+    for (uri, body) in [
+        ("/api/v1/auth/register", register_body),
+        ("/api/v1/auth/login", credentials),
+    ] {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(uri)
+                    .header("content-type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        if let Some(id) = json["user"]["id"].as_str() {
+            user_id = id.to_string();
+        }
+        if let Some(access_token) = json["access_token"].as_str() {
+            token = access_token.to_string();
+        }
+    }
+
+    (user_id, token)
+}
+
+async fn get_me_status(app: &axum::Router, token: &str) -> StatusCode {
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .uri("/api/v1/auth/me")
+                .header("authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+        .status()
+}
+
+async fn admin_post(
+    app: &axum::Router,
+    admin_token: &str,
+    uri: &str,
+    body: Option<serde_json::Value>,
+) -> (StatusCode, serde_json::Value) {
+    let (csrf_token, csrf_cookie) = get_csrf_token(app).await;
+    let request = Request::builder()
+        .method("POST")
+        .uri(uri)
+        .header("content-type", "application/json")
+        .header("authorization", format!("Bearer {}", admin_token))
+        .header("x-csrf-token", &csrf_token)
+        .header("cookie", format!("csrf_token={}", csrf_cookie))
+        .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json = serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null);
+    (status, json)
+}
+
+#[tokio::test]
+async fn test_blocking_user_revokes_issued_access_token() {
+    let app = common::create_test_app().await;
+    let (_admin_id, admin_token, _) = create_admin_with_token(&app).await;
+    let (user_id, token) = register_and_login(&app).await;
+    assert_eq!(get_me_status(&app, &token).await, StatusCode::OK);
+
+    let (status, _) = admin_post(
+        &app,
+        &admin_token,
+        &format!("/admin/users/{}/block", user_id),
+        Some(json!({ "reason": "Revocation test", "duration_hours": 1 })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    assert_eq!(get_me_status(&app, &token).await, StatusCode::UNAUTHORIZED);
+    // Админ, заблокировавший пользователя, продолжает работать
+    assert_eq!(get_me_status(&app, &admin_token).await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_force_logout_revokes_sessions() {
+    let app = common::create_test_app().await;
+    let (_admin_id, admin_token, _) = create_admin_with_token(&app).await;
+    let (user_id, token) = register_and_login(&app).await;
+    assert_eq!(get_me_status(&app, &token).await, StatusCode::OK);
+
+    let (status, json) = admin_post(
+        &app,
+        &admin_token,
+        &format!("/admin/users/{}/force-logout", user_id),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{json}");
+    assert!(json["revoked_sessions"].as_u64().unwrap() >= 1, "{json}");
+    assert_eq!(get_me_status(&app, &token).await, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = admin_post(
+        &app,
+        &admin_token,
+        &format!(
+            "/admin/users/{}/force-logout",
+            mongodb::bson::oid::ObjectId::new()
+        ),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+/// Helper: заблокировать пользователя на час и сдвинуть `blockedUntil` в прошлое
+async fn block_with_expired_deadline(
+    app: &axum::Router,
+    admin_token: &str,
+    user_id: &str,
+) -> mongodb::Database {
+    use mongodb::bson::{doc, oid::ObjectId, DateTime as BsonDateTime};
+    use trainingground_api::config::Config;
+
+    let (csrf_token, csrf_cookie) = get_csrf_token(app).await;
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(format!("/admin/users/{}/block", user_id))
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {}", admin_token))
+                .header("x-csrf-token", &csrf_token)
+                .header("cookie", format!("csrf_token={}", csrf_cookie))
+                .body(Body::from(
+                    json!({ "reason": "Temporary", "duration_hours": 1 }).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["block_active"], true);
+
+    let config = Config::load().expect("config");
+    let mongo_client = mongodb::Client::with_uri_str(&config.mongo_uri)
+        .await
+        .expect("mongo");
+    let db = mongo_client.database(&config.mongo_database);
+    let expired_at = chrono::Utc::now() - chrono::Duration::minutes(5);
+    db.collection::<mongodb::bson::Document>("users")
+        .update_one(
+            doc! { "_id": ObjectId::parse_str(user_id).unwrap() },
+            doc! { "$set": { "blockedUntil": BsonDateTime::from_millis(expired_at.timestamp_millis()) } },
+        )
+        .await
+        .expect("expire block");
+    db
+}
+
+#[tokio::test]
+async fn test_expired_block_is_lifted_on_login() {
+    use mongodb::bson::{doc, oid::ObjectId};
+
+    let app = common::create_test_app().await;
+    let (_admin_id, admin_token, _) = create_admin_with_token(&app).await;
+    let (csrf_token, csrf_cookie) = get_csrf_token(&app).await;
+    let user = admin_create_user(&app, &admin_token, &csrf_token, &csrf_cookie, None).await;
+    let db = block_with_expired_deadline(&app, &admin_token, &user.id).await;
+    let users = db.collection::<mongodb::bson::Document>("users");
+
+    // Флаг ещё стоит, но блокировка уже не действует
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(format!("/admin/users/{}", user.id))
+                .header("authorization", format!("Bearer {}", admin_token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["is_blocked"], true);
+    assert_eq!(json["block_active"], false);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/auth/login")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    json!({ "email": user.email, "password": "Bulk123!@#" }).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let stored = users
+        .find_one(doc! { "_id": ObjectId::parse_str(&user.id).unwrap() })
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!stored.get_bool("is_blocked").unwrap());
+    assert!(stored.get("blockedUntil").is_none());
+    assert!(stored.get("blockReason").is_none());
+}
+
+#[tokio::test]
+async fn test_block_sweep_releases_expired_blocks() {
+    use mongodb::bson::{doc, oid::ObjectId};
+    use trainingground_api::{
+        config::AccountSettings, services::block_expiry_worker::BlockExpiryWorker,
+    };
+
+    let app = common::create_test_app().await;
+    let (_admin_id, admin_token, _) = create_admin_with_token(&app).await;
+    let (csrf_token, csrf_cookie) = get_csrf_token(&app).await;
+    let user = admin_create_user(&app, &admin_token, &csrf_token, &csrf_cookie, None).await;
+    let db = block_with_expired_deadline(&app, &admin_token, &user.id).await;
+    let users = db.collection::<mongodb::bson::Document>("users");
+
+    let worker = BlockExpiryWorker::new(db.clone(), AccountSettings::default());
+    assert!(worker.sweep().await.unwrap() >= 1);
+
+    let stored = users
+        .find_one(doc! { "_id": ObjectId::parse_str(&user.id).unwrap() })
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!stored.get_bool("is_blocked").unwrap());
+    assert!(stored.get("blockedUntil").is_none());
+
+    let audit = db
+        .collection::<mongodb::bson::Document>("audit_log")
+        .find_one(doc! { "event_type": "auto_unblock_user", "user_id": &user.id })
+        .await
+        .unwrap();
+    assert!(audit.is_some());
+}
+
 #[tokio::test]
 async fn test_delete_user() {
     let app = common::create_test_app().await;
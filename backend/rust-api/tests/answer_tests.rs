@@ -5,8 +5,18 @@ use axum::{
     body::Body,
     http::{Request, StatusCode},
 };
+use futures::StreamExt;
+use mongodb::bson::{doc, oid::ObjectId, Document};
 use serde_json::json;
+use std::sync::Arc;
 use tower::ServiceExt;
+use trainingground_api::{
+    config::Config,
+    create_router,
+    middlewares::auth::{JwtClaims, JwtService},
+    models::ProgressSummary,
+    services::session_events::session_events_channel,
+};
 use uuid::Uuid;
 
 #[tokio::test]
@@ -22,6 +32,10 @@ async fn test_submit_correct_answer() {
             Request::builder()
                 .method("POST")
                 .uri("/api/v1/sessions")
+                .header(
+                    "authorization",
+                    format!("Bearer {}", student_token(&user_id)),
+                )
                 .header("content-type", "application/json")
                 .header("x-csrf-token", &csrf_token)
                 .header("cookie", format!("csrf_token={}", csrf_cookie))
@@ -97,6 +111,10 @@ async fn test_submit_incorrect_answer() {
             Request::builder()
                 .method("POST")
                 .uri("/api/v1/sessions")
+                .header(
+                    "authorization",
+                    format!("Bearer {}", student_token(&user_id)),
+                )
                 .header("content-type", "application/json")
                 .header("x-csrf-token", &csrf_token)
                 .header("cookie", format!("csrf_token={}", csrf_cookie))
@@ -169,6 +187,10 @@ async fn test_combo_bonus() {
             Request::builder()
                 .method("POST")
                 .uri("/api/v1/sessions")
+                .header(
+                    "authorization",
+                    format!("Bearer {}", student_token(&user_id)),
+                )
                 .header("content-type", "application/json")
                 .header("x-csrf-token", &csrf_token)
                 .header("cookie", format!("csrf_token={}", csrf_cookie))
@@ -260,6 +282,396 @@ async fn test_combo_bonus() {
     assert_eq!(json["total_score"], 10 + 10 + 15 + 15); // 10+10+15(combo)+15(combo) = 50
 }
 
+#[tokio::test]
+async fn test_answer_normalization_respects_strict_mode() {
+    let state = common::create_test_state().await;
+    let tasks = state.mongo.collection::<Document>("tasks");
+    let app = create_router(Arc::new(state));
+
+    let lenient_task = format!("yo-task-{}", Uuid::new_v4());
+    let strict_task = format!("yo-task-strict-{}", Uuid::new_v4());
+    for (task_id, strict_mode) in [(&lenient_task, false), (&strict_task, true)] {
+        tasks
+            .insert_one(doc! {
+                "_id": task_id,
+                "title": "Ёлка",
+                "description": "Напишите слово «ёлка»",
+                "correct_answer": "ёлка",
+                "time_limit_seconds": 300,
+                "strict_mode": strict_mode,
+            })
+            .await
+            .unwrap();
+    }
+
+    let json = submit_answer_to_task(&app, &lenient_task, json!({ "answer": " Елка\u{a0}" })).await;
+    assert_eq!(json["correct"], true);
+    assert_eq!(
+        json["normalization"],
+        json!(["whitespace", "case_fold", "yo_to_ye"])
+    );
+
+    let json = submit_answer_to_task(&app, &strict_task, json!({ "answer": " Елка\u{a0}" })).await;
+    assert_eq!(json["correct"], false);
+    assert_eq!(json["normalization"], json!(["whitespace"]));
+
+    let json = submit_answer_to_task(&app, &strict_task, json!({ "answer": "ёлка" })).await;
+    assert_eq!(json["correct"], true);
+    assert!(json.get("normalization").is_none());
+}
+
+#[tokio::test]
+async fn test_multi_part_answer_gets_partial_credit() {
+    let state = common::create_test_state().await;
+    let mongo = state.mongo.clone();
+    let app = create_router(Arc::new(state));
+
+    let task_id = format!("parts-task-{}", Uuid::new_v4());
+    mongo
+        .collection::<Document>("tasks")
+        .insert_one(doc! {
+            "_id": &task_id,
+            "title": "Пропущенные буквы",
+            "description": "Вставьте буквы: г..ра, л..сной, пр..рода",
+            "time_limit_seconds": 300,
+            "scoring": "per_part",
+            "answers": {
+                "parts": [
+                    { "accepted": ["о"] },
+                    { "accepted": ["е"] },
+                    { "accepted": ["и"] },
+                ],
+            },
+        })
+        .await
+        .unwrap();
+
+    let user_id = format!("test-user-{}", Uuid::new_v4());
+    let (status, json) = submit_to_session(
+        &app,
+        &user_id,
+        &task_id,
+        json!({ "parts": ["о", "и", "И"] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["correct"], false);
+    assert_eq!(json["score_awarded"], 6); // 2 из 3 частей: 10 * 2 / 3, вниз
+    assert_eq!(json["current_streak"], 0);
+    assert_eq!(
+        json["parts"],
+        json!([
+            { "index": 0, "correct": true },
+            { "index": 1, "correct": false },
+            { "index": 2, "correct": true },
+        ])
+    );
+
+    let summary = mongo
+        .collection::<ProgressSummary>("progress_summary_v2")
+        .find_one(doc! { "_id": format!("{}:{}", user_id, task_id) })
+        .await
+        .unwrap()
+        .expect("progress summary");
+    assert_eq!(summary.correct_count, 0);
+    assert!((summary.percentage - 200.0 / 3.0).abs() < 0.001);
+
+    let (status, json) =
+        submit_to_session(&app, &user_id, &task_id, json!({ "parts": ["о", "е"] })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(json["code"], "INVALID_ANSWER_FORMAT");
+    assert_eq!(json["details"]["expected_parts"], 3);
+}
+
+#[tokio::test]
+async fn test_attempt_limit_auto_completes_session_with_best_score() {
+    let state = common::create_test_state().await;
+    let mongo = state.mongo.clone();
+    let redis_uri = state.config.redis_uri.clone();
+    let app = create_router(Arc::new(state));
+
+    // Лимит задан в `params` шаблона, в самом задании его нет
+    let template_id = ObjectId::new();
+    mongo
+        .collection::<Document>("templates")
+        .insert_one(doc! {
+            "_id": template_id,
+            "slug": format!("attempts-{}", template_id.to_hex()),
+            "params": { "max_attempts": 2, "scoring": "per_part" },
+        })
+        .await
+        .unwrap();
+    let task_id = format!("attempts-task-{}", Uuid::new_v4());
+    mongo
+        .collection::<Document>("tasks")
+        .insert_one(doc! {
+            "_id": &task_id,
+            "template_id": template_id,
+            "title": "Пропущенные буквы",
+            "description": "Вставьте буквы: г..ра, л..сной, пр..рода",
+            "time_limit_seconds": 300,
+            "answers": {
+                "parts": [
+                    { "accepted": ["о"] },
+                    { "accepted": ["е"] },
+                    { "accepted": ["и"] },
+                ],
+            },
+        })
+        .await
+        .unwrap();
+
+    let user_id = format!("test-user-{}", Uuid::new_v4());
+    let session_id = create_session(&app, &user_id, &task_id).await;
+
+    let (status, json) = post_answer(
+        &app,
+        &session_id,
+        json!({ "parts": ["о", "е", "а"], "idempotency_key": format!("{}:1", session_id) }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["score_awarded"], 6);
+    assert_eq!(json["max_attempts"], 2);
+    assert_eq!(json["remaining_attempts"], 1);
+    assert_eq!(json["session_completed"], false);
+
+    // Счётчик попыток живёт в документе идущей сессии
+    let record = mongo
+        .collection::<Document>("sessions")
+        .find_one(doc! { "_id": &session_id })
+        .await
+        .unwrap()
+        .expect("active session record");
+    assert_eq!(record.get_str("status").unwrap(), "active");
+    assert_eq!(record.get_i32("attempts_used").unwrap(), 1);
+
+    let client = redis::Client::open(redis_uri).unwrap();
+    let mut pubsub = client.get_async_pubsub().await.unwrap();
+    pubsub
+        .subscribe(session_events_channel(&session_id))
+        .await
+        .unwrap();
+
+    let (status, json) = post_answer(
+        &app,
+        &session_id,
+        json!({ "parts": ["о", "и", "а"], "idempotency_key": format!("{}:2", session_id) }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["score_awarded"], 3);
+    assert_eq!(json["remaining_attempts"], 0);
+    assert_eq!(json["session_completed"], true);
+
+    // Перед завершающим событием приходит достижение за первую сессию
+    let event = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+        let mut messages = pubsub.on_message();
+        loop {
+            let message = messages.next().await.unwrap();
+            let logged: serde_json::Value =
+                serde_json::from_str(&message.get_payload::<String>().unwrap()).unwrap();
+            if logged["event"]["type"] == "attempts-exhausted" {
+                return logged["event"].clone();
+            }
+        }
+    })
+    .await
+    .expect("attempts-exhausted event");
+    assert_eq!(event["max_attempts"], 2);
+    assert_eq!(event["score"], 6);
+
+    // Итог - лучшая попытка (6), а не сумма попыток (9)
+    let record = mongo
+        .collection::<Document>("sessions")
+        .find_one(doc! { "_id": &session_id })
+        .await
+        .unwrap()
+        .expect("completed session record");
+    assert_eq!(record.get_str("status").unwrap(), "completed");
+    assert_eq!(record.get_i32("score").unwrap(), 6);
+    assert_eq!(record.get_i32("attempts_used").unwrap(), 2);
+
+    let (status, json) = post_answer(
+        &app,
+        &session_id,
+        json!({ "parts": ["о", "е", "и"], "idempotency_key": format!("{}:3", session_id) }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(json["code"], "SESSION_NOT_FOUND");
+}
+
+#[tokio::test]
+async fn test_fast_answers_raise_low_severity_signal() {
+    let state = common::create_test_state().await;
+    let mongo = state.mongo.clone();
+    let app = create_router(Arc::new(state));
+
+    // Порог для b2 по умолчанию - 3 с, ответы подряд без пауз заметно быстрее
+    let task_id = format!("fast-task-{}", Uuid::new_v4());
+    mongo
+        .collection::<Document>("tasks")
+        .insert_one(doc! {
+            "_id": &task_id,
+            "title": "Ёлка",
+            "description": "Напишите слово «ёлка»",
+            "correct_answer": "ёлка",
+            "difficulty": "b2",
+            "time_limit_seconds": 300,
+        })
+        .await
+        .unwrap();
+
+    let user_id = format!("test-user-{}", Uuid::new_v4());
+    let session_id = create_session(&app, &user_id, &task_id).await;
+    for attempt in 1..=3 {
+        let (status, _) = post_answer(
+            &app,
+            &session_id,
+            json!({ "answer": "ёлка", "idempotency_key": format!("{}:{}", session_id, attempt) }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    let attempts = mongo
+        .collection::<Document>("attempt_records")
+        .find(doc! { "session_id": &session_id })
+        .await
+        .unwrap()
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(attempts.len(), 3);
+    for attempt in attempts {
+        let timing = attempt.unwrap().get_document("timing").unwrap().clone();
+        assert!(timing.get_i64("since_previous_ms").unwrap() >= 0);
+        assert!(timing.get_i64("since_session_start_ms").unwrap() >= 0);
+    }
+
+    let signals = mongo
+        .collection::<Document>("anticheat_signals")
+        .find(doc! { "session_id": &session_id, "type": "fast_answers" })
+        .await
+        .unwrap()
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(signals.len(), 1, "signal is recorded once per session");
+    let signal = signals.into_iter().next().unwrap().unwrap();
+    assert_eq!(signal.get_str("severity").unwrap(), "low");
+    let payload = signal.get_document("payload").unwrap();
+    assert_eq!(payload.get_i64("threshold_ms").unwrap(), 3000);
+    assert_eq!(payload.get_str("difficulty").unwrap(), "b2");
+
+    // Слабый сигнал инцидента не открывает
+    let incidents = mongo
+        .collection::<Document>("incidents")
+        .count_documents(doc! { "session_id": &session_id })
+        .await
+        .unwrap();
+    assert_eq!(incidents, 0);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(format!("/api/v1/sessions/{}", session_id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["id"], session_id.as_str());
+    assert_eq!(json["answer_timing"]["answers"], 3);
+}
+
+/// Создаёт сессию по заданию и отправляет в неё один ответ
+async fn submit_answer_to_task(
+    app: &axum::Router,
+    task_id: &str,
+    body: serde_json::Value,
+) -> serde_json::Value {
+    let user_id = format!("test-user-{}", Uuid::new_v4());
+    let (status, json) = submit_to_session(app, &user_id, task_id, body).await;
+    assert_eq!(status, StatusCode::OK);
+    json
+}
+
+async fn submit_to_session(
+    app: &axum::Router,
+    user_id: &str,
+    task_id: &str,
+    answer_body: serde_json::Value,
+) -> (StatusCode, serde_json::Value) {
+    let session_id = create_session(app, user_id, task_id).await;
+    post_answer(app, &session_id, answer_body).await
+}
+
+async fn create_session(app: &axum::Router, user_id: &str, task_id: &str) -> String {
+    let (csrf_token, csrf_cookie) = get_csrf_token(app).await;
+    let create_response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/sessions")
+                .header(
+                    "authorization",
+                    format!("Bearer {}", student_token(user_id)),
+                )
+                .header("content-type", "application/json")
+                .header("x-csrf-token", &csrf_token)
+                .header("cookie", format!("csrf_token={}", csrf_cookie))
+                .body(Body::from(
+                    serde_json::to_string(&json!({
+                        "user_id": user_id,
+                        "task_id": task_id,
+                        "group_id": null
+                    }))
+                    .unwrap(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(create_response.status(), StatusCode::CREATED);
+    let body = to_bytes(create_response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    json["session_id"].as_str().unwrap().to_string()
+}
+
+async fn post_answer(
+    app: &axum::Router,
+    session_id: &str,
+    answer_body: serde_json::Value,
+) -> (StatusCode, serde_json::Value) {
+    let (csrf_token, csrf_cookie) = get_csrf_token(app).await;
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(format!("/api/v1/sessions/{}/answers", session_id))
+                .header("content-type", "application/json")
+                .header("x-csrf-token", &csrf_token)
+                .header("cookie", format!("csrf_token={}", csrf_cookie))
+                .body(Body::from(answer_body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
 async fn get_csrf_token(app: &axum::Router) -> (String, String) {
     let response = app
         .clone()
@@ -291,3 +703,18 @@ async fn get_csrf_token(app: &axum::Router) -> (String, String) {
 
     (csrf_token, csrf_cookie)
 }
+
+/// Токен ученика: сессия открывается только для пользователя из токена
+fn student_token(user_id: &str) -> String {
+    let config = Config::load().expect("Failed to load test configuration");
+    let now = chrono::Utc::now().timestamp() as usize;
+    JwtService::from_config(&config)
+        .generate_token(JwtClaims {
+            sub: user_id.to_string(),
+            role: "student".to_string(),
+            group_ids: Vec::new(),
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap()
+}
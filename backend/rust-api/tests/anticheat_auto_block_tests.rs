@@ -0,0 +1,214 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use chrono::Utc;
+use mongodb::bson::{doc, oid::ObjectId, DateTime as BsonDateTime, Document};
+use tower::ServiceExt;
+use trainingground_api::{
+    create_router,
+    middlewares::auth::{JwtClaims, JwtService},
+    models::{
+        anticheat::{ActionTaken, ClientSignal, IncidentSeverity, ListIncidentsQuery},
+        system_settings::{AnticheatSettings, AnticheatSignalThresholds},
+    },
+    services::{
+        anticheat_service::AnticheatService, incidents_service::IncidentsService, AppState,
+    },
+};
+use uuid::Uuid;
+
+async fn insert_user(state: &AppState, role: &str, group_ids: &[String]) -> String {
+    let now = BsonDateTime::now();
+    state
+        .mongo
+        .collection::<Document>("users")
+        .insert_one(doc! {
+            "email": format!("autoblock+{}@test.com", Uuid::new_v4()),
+            "password_hash": "hash",
+            "name": "Auto Block",
+            "role": role,
+            "group_ids": group_ids,
+            "is_blocked": false,
+            "createdAt": now,
+            "updatedAt": now,
+        })
+        .await
+        .unwrap()
+        .inserted_id
+        .as_object_id()
+        .unwrap()
+        .to_hex()
+}
+
+async fn user_document(state: &AppState, user_id: &str) -> Document {
+    state
+        .mongo
+        .collection::<Document>("users")
+        .find_one(doc! { "_id": ObjectId::parse_str(user_id).unwrap() })
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+fn paste_signals(count: usize) -> Vec<ClientSignal> {
+    let signals: Vec<_> = (0..count)
+        .map(|index| {
+            serde_json::json!({
+                "type": "paste",
+                "timestamp": Utc::now().to_rfc3339(),
+                "payload": { "index": index },
+            })
+        })
+        .collect();
+    serde_json::from_value(serde_json::Value::Array(signals)).unwrap()
+}
+
+/// Пороги, при которых любой второй сигнал вставки открывает инцидент
+fn strict_settings() -> AnticheatSettings {
+    AnticheatSettings {
+        signal_thresholds: Some(AnticheatSignalThresholds {
+            tab_switch: 1,
+            paste: 1,
+            devtools: 1,
+        }),
+        auto_block: true,
+        auto_block_min_severity: Some(IncidentSeverity::Medium),
+        block_duration_hours: 2,
+        ..AnticheatSettings::default()
+    }
+}
+
+async fn wait_for_incident(state: &AppState, user_id: &str) -> serde_json::Value {
+    let service = IncidentsService::new(state.mongo.clone());
+    for _ in 0..20 {
+        let incidents = service
+            .list_incidents(ListIncidentsQuery {
+                incident_type: None,
+                severity: None,
+                status: None,
+                user_id: Some(user_id.to_string()),
+                session_id: None,
+                assigned_to: None,
+                sort: None,
+                limit: None,
+                offset: None,
+            })
+            .await
+            .unwrap();
+        if let Some(incident) = incidents.into_iter().next() {
+            return serde_json::to_value(incident.incident).unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("incident for user {} was not recorded", user_id);
+}
+
+#[tokio::test]
+async fn signals_over_threshold_auto_block_user() {
+    let state = common::create_test_state().await;
+    let user_id = insert_user(&state, "student", &[]).await;
+    let session_id = Uuid::new_v4().to_string();
+
+    let response = AnticheatService::new(state.mongo.clone(), state.redis.clone())
+        .with_settings(strict_settings())
+        .ingest_signals(
+            &session_id,
+            &user_id,
+            paste_signals(2),
+            &state.config.anticheat_signals,
+        )
+        .await
+        .unwrap();
+    assert!(response.incident_id.is_some());
+
+    let user = user_document(&state, &user_id).await;
+    assert!(user.get_bool("is_blocked").unwrap());
+    assert!(user.get("blockedUntil").is_some());
+
+    let incident = wait_for_incident(&state, &user_id).await;
+    assert_eq!(incident["id"], response.incident_id.unwrap().as_str());
+    assert_eq!(
+        incident["action_taken"],
+        serde_json::to_value(ActionTaken::Blocked).unwrap()
+    );
+    assert!(incident["auto_block"]["blocked_until"].is_string());
+
+    // Администратор снимает блокировку, инцидент это запоминает
+    let admin_id = insert_user(&state, "admin", &[]).await;
+    let now = Utc::now().timestamp() as usize;
+    let admin_token = JwtService::new(&state.config.jwt_secret)
+        .generate_token(JwtClaims {
+            sub: admin_id.clone(),
+            role: "admin".to_string(),
+            group_ids: vec![],
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap();
+    let incident_id = incident["id"].as_str().unwrap().to_string();
+    let state = Arc::new(state);
+    let app = create_router(state.clone());
+    let csrf_token = Uuid::new_v4().to_string();
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(format!("/admin/incidents/{}/unblock", incident_id))
+                .header("authorization", format!("Bearer {}", admin_token))
+                .header("x-csrf-token", &csrf_token)
+                .header("cookie", format!("csrf_token={}", csrf_token))
+                .header("x-request-nonce", Uuid::new_v4().to_string())
+                .header("x-request-timestamp", Utc::now().timestamp().to_string())
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(status, StatusCode::OK, "{}", String::from_utf8_lossy(&body));
+
+    let user = user_document(&state, &user_id).await;
+    assert!(!user.get_bool("is_blocked").unwrap());
+    let incident = wait_for_incident(&state, &user_id).await;
+    assert_eq!(incident["unblocked_by"], admin_id.as_str());
+    assert!(incident["unblocked_at"].is_string());
+}
+
+#[tokio::test]
+async fn whitelisted_group_is_only_flagged() {
+    let state = common::create_test_state().await;
+    let group_id = ObjectId::new().to_hex();
+    let user_id = insert_user(&state, "student", std::slice::from_ref(&group_id)).await;
+
+    let settings = AnticheatSettings {
+        whitelisted_group_ids: vec![group_id],
+        ..strict_settings()
+    };
+    let response = AnticheatService::new(state.mongo.clone(), state.redis.clone())
+        .with_settings(settings)
+        .ingest_signals(
+            &Uuid::new_v4().to_string(),
+            &user_id,
+            paste_signals(2),
+            &state.config.anticheat_signals,
+        )
+        .await
+        .unwrap();
+    assert!(response.incident_id.is_some());
+
+    let user = user_document(&state, &user_id).await;
+    assert!(!user.get_bool("is_blocked").unwrap());
+    let incident = wait_for_incident(&state, &user_id).await;
+    assert_eq!(
+        incident["action_taken"],
+        serde_json::to_value(ActionTaken::Flagged).unwrap()
+    );
+    assert!(incident["auto_block"].is_null());
+}
@@ -0,0 +1,183 @@
+mod common;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use chrono::{Duration as ChronoDuration, Utc};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use tower::ServiceExt;
+use trainingground_api::{
+    create_router,
+    middlewares::auth::{JwtClaims, JwtService},
+    models::{anticheat::ListIncidentsQuery, Session, SessionMode, SessionStatus},
+    services::{incidents_service::IncidentsService, AppState},
+};
+use uuid::Uuid;
+
+fn token_for(state: &AppState, user_id: &str) -> String {
+    let now = Utc::now().timestamp() as usize;
+    JwtService::new(&state.config.jwt_secret)
+        .generate_token(JwtClaims {
+            sub: user_id.to_string(),
+            role: "student".to_string(),
+            group_ids: vec![],
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap()
+}
+
+async fn insert_session(state: &AppState, user_id: &str) -> Session {
+    let now = Utc::now();
+    let session = Session {
+        id: Uuid::new_v4().to_string(),
+        user_id: user_id.to_string(),
+        task_id: "test-task".to_string(),
+        group_id: None,
+        started_at: now,
+        expires_at: now + ChronoDuration::seconds(300),
+        status: SessionStatus::Active,
+        hints_used: 0,
+        score: 0,
+        level_id: None,
+        mode: SessionMode::Normal,
+    };
+    redis::cmd("SETEX")
+        .arg(format!("session:{}", session.id))
+        .arg(3600)
+        .arg(serde_json::to_string(&session).unwrap())
+        .query_async::<()>(&mut state.redis.clone())
+        .await
+        .unwrap();
+    session
+}
+
+async fn post_signals(
+    app: &Router,
+    session_id: &str,
+    token: &str,
+    body: serde_json::Value,
+) -> (StatusCode, serde_json::Value) {
+    let csrf_token = Uuid::new_v4().to_string();
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(format!("/api/v1/sessions/{}/signals", session_id))
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {}", token))
+                .header("x-csrf-token", &csrf_token)
+                .header("cookie", format!("csrf_token={}", csrf_token))
+                .header("x-request-nonce", Uuid::new_v4().to_string())
+                .header("x-request-timestamp", Utc::now().timestamp().to_string())
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or_default())
+}
+
+fn signals(signal_type: &str, count: usize) -> serde_json::Value {
+    let signals: Vec<_> = (0..count)
+        .map(|index| {
+            json!({
+                "type": signal_type,
+                "timestamp": Utc::now(),
+                "payload": { "length": 10 + index },
+            })
+        })
+        .collect();
+    json!({ "signals": signals })
+}
+
+#[tokio::test]
+async fn test_signals_over_threshold_open_incident() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+    let user_id = format!("signals-user-{}", Uuid::new_v4());
+    let token = token_for(&state, &user_id);
+    let session = insert_session(&state, &user_id).await;
+    let threshold = state.config.anticheat_signals.paste_threshold as usize;
+
+    // На пороге инцидента ещё нет
+    let (status, body) = post_signals(&app, &session.id, &token, signals("paste", threshold)).await;
+    assert_eq!(status, StatusCode::ACCEPTED, "body: {}", body);
+    assert_eq!(body["accepted"], threshold);
+    assert!(body.get("incident_id").is_none());
+
+    let (status, body) = post_signals(&app, &session.id, &token, signals("paste", 1)).await;
+    assert_eq!(status, StatusCode::ACCEPTED, "body: {}", body);
+    assert_eq!(body["counts"]["paste"], threshold + 1);
+    let incident_id = body["incident_id"]
+        .as_str()
+        .expect("incident opened")
+        .to_string();
+
+    // Повторное превышение того же порога не открывает второй инцидент
+    let (_, body) = post_signals(&app, &session.id, &token, signals("paste", 1)).await;
+    assert!(body.get("incident_id").is_none());
+
+    // Инцидент может сохраняться в фоне (ANTICHEAT_WRITE_ASYNC)
+    let incidents = IncidentsService::new(state.mongo.clone());
+    let mut listed = Vec::new();
+    for _ in 0..50 {
+        listed = incidents
+            .list_incidents(ListIncidentsQuery {
+                incident_type: None,
+                severity: None,
+                status: None,
+                user_id: Some(user_id.clone()),
+                session_id: Some(session.id.clone()),
+                assigned_to: None,
+                sort: None,
+                limit: None,
+                offset: None,
+            })
+            .await
+            .unwrap();
+        if !listed.is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(listed.len(), 1);
+    let incident = &listed[0].incident;
+    assert_eq!(incident.id, incident_id);
+    assert_eq!(incident.session_id.as_deref(), Some(session.id.as_str()));
+    let counts = incident.details.signal_counts.as_ref().unwrap();
+    assert_eq!(counts["paste"] as usize, threshold + 1);
+}
+
+#[tokio::test]
+async fn test_signals_for_foreign_session_are_forbidden() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+    let owner_id = format!("signals-owner-{}", Uuid::new_v4());
+    let session = insert_session(&state, &owner_id).await;
+    let intruder_token = token_for(&state, &format!("signals-intruder-{}", Uuid::new_v4()));
+
+    let (status, body) =
+        post_signals(&app, &session.id, &intruder_token, signals("tab_switch", 1)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["code"], "SESSION_FORBIDDEN");
+}
+
+#[tokio::test]
+async fn test_unknown_signal_type_is_rejected() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+    let user_id = format!("signals-user-{}", Uuid::new_v4());
+    let token = token_for(&state, &user_id);
+    let session = insert_session(&state, &user_id).await;
+
+    let (status, _) = post_signals(&app, &session.id, &token, signals("screenshot", 1)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
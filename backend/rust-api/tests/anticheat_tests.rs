@@ -7,6 +7,10 @@ use axum::{
 };
 use serde_json::json;
 use tower::ServiceExt;
+use trainingground_api::{
+    config::Config,
+    middlewares::auth::{JwtClaims, JwtService},
+};
 use uuid::Uuid;
 
 #[tokio::test]
@@ -22,6 +26,10 @@ async fn test_normal_usage_not_flagged() {
             Request::builder()
                 .method("POST")
                 .uri("/api/v1/sessions")
+                .header(
+                    "authorization",
+                    format!("Bearer {}", student_token(&user_id)),
+                )
                 .header("content-type", "application/json")
                 .header("x-csrf-token", &csrf_token)
                 .header("cookie", format!("csrf_token={}", csrf_cookie))
@@ -86,6 +94,10 @@ async fn test_speed_violation_detection() {
             Request::builder()
                 .method("POST")
                 .uri("/api/v1/sessions")
+                .header(
+                    "authorization",
+                    format!("Bearer {}", student_token(&user_id)),
+                )
                 .header("content-type", "application/json")
                 .header("x-csrf-token", &csrf_token)
                 .header("cookie", format!("csrf_token={}", csrf_cookie))
@@ -159,6 +171,10 @@ async fn test_repeated_answers_detection() {
             Request::builder()
                 .method("POST")
                 .uri("/api/v1/sessions")
+                .header(
+                    "authorization",
+                    format!("Bearer {}", student_token(&user_id)),
+                )
                 .header("content-type", "application/json")
                 .header("x-csrf-token", &csrf_token)
                 .header("cookie", format!("csrf_token={}", csrf_cookie))
@@ -249,3 +265,18 @@ async fn get_csrf_token(app: &axum::Router) -> (String, String) {
 
     (csrf_token, csrf_cookie)
 }
+
+/// Токен ученика: сессия открывается только для пользователя из токена
+fn student_token(user_id: &str) -> String {
+    let config = Config::load().expect("Failed to load test configuration");
+    let now = chrono::Utc::now().timestamp() as usize;
+    JwtService::from_config(&config)
+        .generate_token(JwtClaims {
+            sub: user_id.to_string(),
+            role: "student".to_string(),
+            group_ids: Vec::new(),
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap()
+}
@@ -0,0 +1,434 @@
+mod common;
+
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use chrono::{Duration, Utc};
+use mongodb::bson::{doc, oid::ObjectId, DateTime, Document};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use trainingground_api::{
+    create_router,
+    middlewares::auth::{JwtClaims, JwtService},
+    services::{answer_service::session_score_key, AppState},
+};
+use uuid::Uuid;
+
+fn jwt(state: &AppState, user_id: &ObjectId, role: &str, group_ids: Vec<String>) -> String {
+    let now = Utc::now().timestamp() as usize;
+    JwtService::from_config(&state.config)
+        .generate_token(JwtClaims {
+            sub: user_id.to_hex(),
+            role: role.to_string(),
+            group_ids,
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap()
+}
+
+async fn send(
+    app: &axum::Router,
+    method: &str,
+    uri: &str,
+    token: Option<&str>,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let csrf_token = Uuid::new_v4().to_string();
+    let mut request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .header("x-csrf-token", &csrf_token)
+        .header("cookie", format!("csrf_token={}", csrf_token));
+    if let Some(token) = token {
+        request = request.header("authorization", format!("Bearer {}", token));
+    }
+    let body = body.map(|body| Body::from(body.to_string()));
+
+    let response = app
+        .clone()
+        .oneshot(request.body(body.unwrap_or_else(Body::empty)).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or_default())
+}
+
+/// Группа с одним учеником
+async fn insert_group(state: &AppState, student_id: &ObjectId) -> ObjectId {
+    let now = DateTime::now();
+    let group_id = ObjectId::new();
+    state
+        .mongo
+        .collection::<Document>("groups")
+        .insert_one(doc! {
+            "_id": group_id,
+            "name": "7А",
+            "school": "Школа 1",
+            "createdAt": now,
+            "updatedAt": now,
+        })
+        .await
+        .unwrap();
+    state
+        .mongo
+        .collection::<Document>("users")
+        .insert_one(doc! {
+            "_id": student_id,
+            "name": "Анна Иванова",
+            "email": format!("{}@test.local", student_id.to_hex()),
+            "role": "student",
+            "group_ids": [group_id.to_hex()],
+        })
+        .await
+        .unwrap();
+    group_id
+}
+
+/// Шаблон без уровня с сохранённым заданием (генератор в тестах не вызывается)
+async fn insert_template(state: &AppState) -> ObjectId {
+    let now = DateTime::now();
+    let template_id = ObjectId::new();
+    state
+        .mongo
+        .collection::<Document>("templates")
+        .insert_one(doc! {
+            "_id": template_id,
+            "slug": format!("assignment-template-{}", Uuid::new_v4().simple()),
+            "status": "published",
+            "version": 1,
+            "createdAt": now,
+            "updatedAt": now,
+        })
+        .await
+        .unwrap();
+    state
+        .mongo
+        .collection::<Document>("tasks")
+        .insert_one(doc! {
+            "template_id": template_id,
+            "title": "Домашнее задание",
+            "description": "Сколько будет 2 + 2?",
+            "time_limit_seconds": 300,
+            "correct_answer": "4",
+            "createdAt": now,
+        })
+        .await
+        .unwrap();
+    template_id
+}
+
+/// Сессия по заданию с заданным итоговым счётом
+async fn complete_assignment_session(
+    app: &axum::Router,
+    state: &AppState,
+    student_id: &ObjectId,
+    assignment_id: &str,
+    score: i32,
+) -> Value {
+    let student = jwt(state, student_id, "student", Vec::new());
+    let (status, session) = send(
+        app,
+        "POST",
+        "/api/v1/sessions",
+        Some(&student),
+        Some(json!({ "user_id": student_id.to_hex(), "assignment_id": assignment_id })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "{session}");
+    let session_id = session["session_id"].as_str().unwrap();
+
+    let mut conn = state.redis.clone();
+    let _: () = redis::cmd("SET")
+        .arg(session_score_key(session_id))
+        .arg(score)
+        .query_async(&mut conn)
+        .await
+        .unwrap();
+
+    let (status, _) = send(
+        app,
+        "POST",
+        &format!("/api/v1/sessions/{}/complete", session_id),
+        None,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    session
+}
+
+#[tokio::test]
+async fn test_assignment_lifecycle() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+
+    let student_id = ObjectId::new();
+    let group_id = insert_group(&state, &student_id).await;
+    let first = insert_template(&state).await;
+    let second = insert_template(&state).await;
+    let teacher = jwt(&state, &ObjectId::new(), "teacher", vec![group_id.to_hex()]);
+    let student = jwt(&state, &student_id, "student", vec![group_id.to_hex()]);
+    let base = format!("/api/v1/teacher/groups/{}/assignments", group_id.to_hex());
+
+    let due_at = Utc::now() + Duration::days(3);
+    let (status, assignment) = send(
+        &app,
+        "POST",
+        &base,
+        Some(&teacher),
+        Some(json!({
+            "template_ids": [first.to_hex(), second.to_hex()],
+            "due_at": due_at,
+            "instructions": "Повторить безударные гласные",
+            "min_score": 5,
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "{assignment}");
+    let assignment_id = assignment["id"].as_str().unwrap().to_string();
+
+    let (status, list) = send(&app, "GET", &base, Some(&teacher), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(list.as_array().unwrap().len(), 1);
+
+    let (status, mine) = send(&app, "GET", "/api/v1/assignments", Some(&student), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(mine["assignments"][0]["id"], assignment_id);
+    assert_eq!(mine["assignments"][0]["status"], "pending");
+
+    // Счёт ниже проходного шаблон не засчитывает
+    let session = complete_assignment_session(&app, &state, &student_id, &assignment_id, 2).await;
+    let task_id = ObjectId::parse_str(session["task"]["id"].as_str().unwrap()).unwrap();
+    let task = state
+        .mongo
+        .collection::<Document>("tasks")
+        .find_one(doc! { "_id": task_id })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(task.get_object_id("template_id").unwrap(), first);
+
+    complete_assignment_session(&app, &state, &student_id, &assignment_id, 10).await;
+    let (_, mine) = send(&app, "GET", "/api/v1/assignments", Some(&student), None).await;
+    let items = mine["assignments"][0]["items"].as_array().unwrap();
+    assert_eq!(items[0]["completed"], true);
+    assert_eq!(items[1]["completed"], false);
+    assert_eq!(mine["assignments"][0]["status"], "pending");
+
+    complete_assignment_session(&app, &state, &student_id, &assignment_id, 10).await;
+    let (_, mine) = send(&app, "GET", "/api/v1/assignments", Some(&student), None).await;
+    assert_eq!(mine["assignments"][0]["status"], "completed");
+
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/api/v1/sessions",
+        Some(&student),
+        Some(json!({ "user_id": student_id.to_hex(), "assignment_id": assignment_id })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let (status, progress) = send(
+        &app,
+        "GET",
+        &format!("{}/{}/progress", base, assignment_id),
+        Some(&teacher),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{progress}");
+    assert_eq!(progress["total_items"], 2);
+    assert_eq!(progress["completed_students"], 1);
+    assert_eq!(progress["students"][0]["student_id"], student_id.to_hex());
+    assert_eq!(progress["students"][0]["completed_items"], 2);
+
+    // Чужой учитель задания группы не видит
+    let stranger = jwt(&state, &ObjectId::new(), "teacher", Vec::new());
+    let (status, _) = send(&app, "GET", &base, Some(&stranger), None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_assignment_deadline_enforcement() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+
+    let student_id = ObjectId::new();
+    let group_id = insert_group(&state, &student_id).await;
+    let template_id = insert_template(&state).await;
+    let teacher = jwt(&state, &ObjectId::new(), "teacher", vec![group_id.to_hex()]);
+    let base = format!("/api/v1/teacher/groups/{}/assignments", group_id.to_hex());
+
+    let (status, body) = send(
+        &app,
+        "POST",
+        &base,
+        Some(&teacher),
+        Some(json!({
+            "template_ids": [template_id.to_hex()],
+            "due_at": Utc::now() - Duration::hours(1),
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "INVALID_DUE_DATE");
+
+    let (status, assignment) = send(
+        &app,
+        "POST",
+        &base,
+        Some(&teacher),
+        Some(json!({
+            "template_ids": [template_id.to_hex()],
+            "due_at": Utc::now() + Duration::hours(1),
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "{assignment}");
+    let assignment_id = assignment["id"].as_str().unwrap().to_string();
+    let assignment_obj = ObjectId::parse_str(&assignment_id).unwrap();
+
+    // Срок прошёл: без allow_late сессию начать нельзя
+    let past = DateTime::from_millis((Utc::now() - Duration::hours(2)).timestamp_millis());
+    state
+        .mongo
+        .collection::<Document>("assignments")
+        .update_one(
+            doc! { "_id": assignment_obj },
+            doc! { "$set": { "dueAt": past } },
+        )
+        .await
+        .unwrap();
+    let student = jwt(&state, &student_id, "student", vec![group_id.to_hex()]);
+    let start = json!({ "user_id": student_id.to_hex(), "assignment_id": assignment_id });
+    let (status, body) = send(
+        &app,
+        "POST",
+        "/api/v1/sessions",
+        Some(&student),
+        Some(start.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["code"], "ASSIGNMENT_PAST_DUE");
+
+    // Ученик чужой группы тоже получает 403
+    let outsider_id = ObjectId::new();
+    let outsider_token = jwt(&state, &outsider_id, "student", Vec::new());
+    let outsider = json!({ "user_id": outsider_id.to_hex(), "assignment_id": assignment_id });
+    let (status, body) = send(
+        &app,
+        "POST",
+        "/api/v1/sessions",
+        Some(&outsider_token),
+        Some(outsider),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["code"], "NOT_GROUP_MEMBER");
+
+    // Без токена и от чужого имени сессию не начать
+    let (status, _) = send(&app, "POST", "/api/v1/sessions", None, Some(start.clone())).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, body) = send(
+        &app,
+        "POST",
+        "/api/v1/sessions",
+        Some(&outsider_token),
+        Some(start),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["code"], "USER_MISMATCH");
+
+    // Срок можно только продлить
+    let item = format!("{}/{}", base, assignment_id);
+    let (status, body) = send(
+        &app,
+        "PATCH",
+        &item,
+        Some(&teacher),
+        Some(json!({ "due_at": Utc::now() - Duration::hours(3) })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "DEADLINE_NOT_EXTENDED");
+
+    // Приём после срока: сессия разрешена, выполнение помечается как просроченное
+    let (status, body) = send(
+        &app,
+        "PATCH",
+        &item,
+        Some(&teacher),
+        Some(json!({ "due_at": Utc::now() - Duration::minutes(30), "allow_late": true })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["allow_late"], true);
+    complete_assignment_session(&app, &state, &student_id, &assignment_id, 10).await;
+
+    let (_, progress) = send(
+        &app,
+        "GET",
+        &format!("{}/progress", item),
+        Some(&teacher),
+        None,
+    )
+    .await;
+    assert_eq!(progress["students"][0]["status"], "completed");
+    assert_eq!(progress["students"][0]["late_items"], 1);
+}
+
+#[tokio::test]
+async fn test_assignment_rejects_unpublished_templates() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+
+    let student_id = ObjectId::new();
+    let group_id = insert_group(&state, &student_id).await;
+    let published = insert_template(&state).await;
+    let draft = insert_template(&state).await;
+    let archived = insert_template(&state).await;
+    let templates = state.mongo.collection::<Document>("templates");
+    templates
+        .update_one(
+            doc! { "_id": draft },
+            doc! { "$set": { "status": "draft" } },
+        )
+        .await
+        .unwrap();
+    templates
+        .update_one(
+            doc! { "_id": archived },
+            doc! { "$set": { "archived": true } },
+        )
+        .await
+        .unwrap();
+    let teacher = jwt(&state, &ObjectId::new(), "teacher", vec![group_id.to_hex()]);
+    let base = format!("/api/v1/teacher/groups/{}/assignments", group_id.to_hex());
+
+    let (status, body) = send(
+        &app,
+        "POST",
+        &base,
+        Some(&teacher),
+        Some(json!({
+            "template_ids": [published.to_hex(), draft.to_hex(), archived.to_hex()],
+            "due_at": Utc::now() + Duration::days(1),
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "UNPUBLISHED_TEMPLATES");
+    assert_eq!(
+        body["details"]["template_ids"],
+        json!([draft.to_hex(), archived.to_hex()])
+    );
+}
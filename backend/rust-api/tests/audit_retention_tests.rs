@@ -0,0 +1,391 @@
+use std::collections::HashMap;
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+use std::time::Duration as StdDuration;
+
+use async_trait::async_trait;
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+use flate2::read::GzDecoder;
+use mongodb::bson::{doc, oid::ObjectId, Document};
+use rand::Rng;
+use serde_json::Value;
+use tower::ServiceExt;
+use trainingground_api::{
+    config::AuditSettings,
+    create_router,
+    middlewares::auth::{JwtClaims, JwtService},
+    models::audit_archive::{AuditArchiveFile, AuditArchiveRun, AuditArchiveStatus},
+    services::{
+        audit_archive_service::{archive_key, AuditArchiveService},
+        audit_retention_worker::AuditRetentionWorker,
+        reporting_service::ExportLinkSigner,
+        session_archive_service::ArchiveStorage,
+        AppState,
+    },
+};
+
+mod common;
+
+/// In-memory storage that keeps every uploaded object
+#[derive(Default)]
+struct MockArchiveStorage {
+    objects: Mutex<HashMap<String, Vec<u8>>>,
+}
+
+impl MockArchiveStorage {
+    fn object(&self, key: &str) -> Option<Vec<u8>> {
+        self.objects.lock().unwrap().get(key).cloned()
+    }
+
+    fn lines(&self, key: &str) -> Vec<Value> {
+        let bytes = self.object(key).expect("archive uploaded");
+        let mut text = String::new();
+        GzDecoder::new(bytes.as_slice())
+            .read_to_string(&mut text)
+            .unwrap();
+        text.lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+}
+
+#[async_trait]
+impl ArchiveStorage for MockArchiveStorage {
+    async fn put_bundle(&self, key: &str, bytes: Vec<u8>) -> anyhow::Result<()> {
+        self.objects.lock().unwrap().insert(key.to_string(), bytes);
+        Ok(())
+    }
+
+    async fn read_range(&self, key: &str, offset: u64, length: u64) -> anyhow::Result<Vec<u8>> {
+        let objects = self.objects.lock().unwrap();
+        let bytes = objects
+            .get(key)
+            .ok_or_else(|| anyhow::anyhow!("object {} not found", key))?;
+        Ok(bytes[offset as usize..(offset + length) as usize].to_vec())
+    }
+
+    async fn read_object(&self, key: &str) -> anyhow::Result<Vec<u8>> {
+        self.objects
+            .lock()
+            .unwrap()
+            .get(key)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("object {} not found", key))
+    }
+}
+
+struct MockLinkSigner;
+
+impl ExportLinkSigner for MockLinkSigner {
+    fn presigned_download_url(&self, key: &str, ttl: StdDuration) -> anyhow::Result<String> {
+        Ok(format!(
+            "https://storage.test/{}?expires={}",
+            key,
+            ttl.as_secs()
+        ))
+    }
+}
+
+/// Случайные старые сутки, чтобы тесты не пересекались с архивом прошлых прогонов
+fn random_old_day() -> NaiveDate {
+    let base = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
+    base + Duration::days(rand::rng().random_range(0..7000))
+}
+
+/// ObjectId, созданный в заданный момент суток `day`
+fn object_id_on(day: NaiveDate, hour: u32) -> ObjectId {
+    let at: DateTime<Utc> = day
+        .and_time(NaiveTime::from_hms_opt(hour, 0, 0).unwrap())
+        .and_utc();
+    let mut bytes = [0u8; 12];
+    bytes[..4].copy_from_slice(&(at.timestamp() as u32).to_be_bytes());
+    rand::rng().fill(&mut bytes[4..]);
+    ObjectId::from_bytes(bytes)
+}
+
+async fn seed_entry(state: &AppState, id: ObjectId, marker: &str) {
+    state
+        .mongo
+        .collection::<Document>("audit_log")
+        .insert_one(doc! {
+            "_id": id,
+            "event_type": "login",
+            "success": true,
+            "details": marker,
+        })
+        .await
+        .unwrap();
+}
+
+async fn entries_left(state: &AppState, ids: &[ObjectId]) -> u64 {
+    state
+        .mongo
+        .collection::<Document>("audit_log")
+        .count_documents(doc! { "_id": { "$in": ids.to_vec() } })
+        .await
+        .unwrap()
+}
+
+async fn reset_archive_days(state: &AppState, days: &[NaiveDate]) {
+    let labels: Vec<String> = days
+        .iter()
+        .map(|day| day.format("%Y-%m-%d").to_string())
+        .collect();
+    state
+        .mongo
+        .collection::<Document>("audit_archives")
+        .delete_many(doc! { "day": { "$in": labels } })
+        .await
+        .unwrap();
+}
+
+async fn load_file(state: &AppState, key: &str) -> Option<AuditArchiveFile> {
+    state
+        .mongo
+        .collection::<AuditArchiveFile>("audit_archives")
+        .find_one(doc! { "_id": key })
+        .await
+        .unwrap()
+}
+
+fn worker(state: &AppState, storage: Arc<MockArchiveStorage>) -> AuditRetentionWorker {
+    AuditRetentionWorker::new(
+        AuditArchiveService::new(state.mongo.clone()),
+        storage,
+        state.redis.clone(),
+        AuditSettings {
+            retention_days: 1,
+            batch_size: 2,
+            max_days_per_run: 10_000,
+            ..Default::default()
+        },
+    )
+}
+
+fn admin_token(state: &AppState) -> String {
+    let now = Utc::now().timestamp() as usize;
+    JwtService::new(&state.config.jwt_secret)
+        .generate_token(JwtClaims {
+            sub: ObjectId::new().to_hex(),
+            role: "admin".to_string(),
+            group_ids: Vec::new(),
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap()
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_retention_archives_old_days_and_keeps_recent_entries() {
+    let state = common::create_test_state().await;
+    let storage = Arc::new(MockArchiveStorage::default());
+    let day = random_old_day();
+    let next_day = day + Duration::days(1);
+    reset_archive_days(&state, &[day, next_day]).await;
+
+    let marker = format!("retention-{}", uuid::Uuid::new_v4());
+    let old_ids = [
+        object_id_on(day, 1),
+        object_id_on(day, 12),
+        object_id_on(day, 23),
+    ];
+    for id in old_ids {
+        seed_entry(&state, id, &marker).await;
+    }
+    let next_day_id = object_id_on(next_day, 6);
+    seed_entry(&state, next_day_id, &marker).await;
+    let recent_id = ObjectId::new();
+    seed_entry(&state, recent_id, &marker).await;
+
+    let run = worker(&state, storage.clone())
+        .run_once()
+        .await
+        .unwrap()
+        .expect("lock is free");
+    assert!(run.error.is_none(), "{:?}", run.error);
+    assert!(run.days_archived >= 2);
+    assert!(run.entries_archived >= 4);
+    assert!(run.entries_deleted >= 4);
+
+    // Сутки - отдельный файл audit/YYYY/MM/DD.ndjson.gz со всеми записями
+    let key = archive_key(day, 1);
+    assert!(key.starts_with(&format!("audit/{}", day.format("%Y/%m/%d"))));
+    let lines = storage.lines(&key);
+    assert_eq!(lines.len(), 3);
+    for (line, id) in lines.iter().zip(old_ids) {
+        assert_eq!(line["_id"]["$oid"], id.to_hex());
+        assert_eq!(line["details"], marker.as_str());
+    }
+    assert_eq!(storage.lines(&archive_key(next_day, 1)).len(), 1);
+
+    let file = load_file(&state, &key).await.expect("archive recorded");
+    assert_eq!(file.status, AuditArchiveStatus::Completed);
+    assert_eq!(file.entry_count, 3);
+    assert_eq!(file.run_id, run.id);
+
+    assert_eq!(entries_left(&state, &old_ids).await, 0);
+    assert_eq!(entries_left(&state, &[next_day_id]).await, 0);
+    assert_eq!(entries_left(&state, &[recent_id]).await, 1);
+
+    let recorded = state
+        .mongo
+        .collection::<AuditArchiveRun>("audit_archive_runs")
+        .find_one(doc! { "_id": run.id })
+        .await
+        .unwrap()
+        .expect("run summary stored");
+    assert!(recorded.finished_at.is_some());
+    assert_eq!(recorded.entries_archived, run.entries_archived);
+
+    // Повторный запуск ничего не выгружает заново
+    let rerun = worker(&state, storage.clone())
+        .run_once()
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(rerun.files_written, 0);
+    assert_eq!(storage.lines(&key).len(), 3);
+
+    reset_archive_days(&state, &[day, next_day]).await;
+    state
+        .mongo
+        .collection::<Document>("audit_log")
+        .delete_many(doc! { "details": &marker })
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_retention_resumes_after_crash_without_losing_entries() {
+    let state = common::create_test_state().await;
+    let storage = Arc::new(MockArchiveStorage::default());
+    let day = random_old_day();
+    reset_archive_days(&state, &[day]).await;
+
+    let marker = format!("retention-crash-{}", uuid::Uuid::new_v4());
+    let ids = [object_id_on(day, 2), object_id_on(day, 3)];
+    for id in ids {
+        seed_entry(&state, id, &marker).await;
+    }
+
+    // Прошлый запуск загрузил файл, но упал до удаления записей
+    let key = archive_key(day, 1);
+    storage
+        .put_bundle(&key, b"uploaded".to_vec())
+        .await
+        .unwrap();
+    state
+        .mongo
+        .collection::<AuditArchiveFile>("audit_archives")
+        .insert_one(AuditArchiveFile {
+            key: key.clone(),
+            day: day.format("%Y-%m-%d").to_string(),
+            part: 1,
+            status: AuditArchiveStatus::Uploaded,
+            entry_count: 2,
+            size_bytes: 8,
+            run_id: ObjectId::new(),
+            archived_at: Utc::now(),
+            completed_at: None,
+        })
+        .await
+        .unwrap();
+
+    let run = worker(&state, storage.clone())
+        .run_once()
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(run.error.is_none(), "{:?}", run.error);
+    assert_eq!(entries_left(&state, &ids).await, 0);
+    // Файл не перезаписан и не продублирован
+    assert_eq!(storage.object(&key).unwrap(), b"uploaded".to_vec());
+    assert!(storage.object(&archive_key(day, 2)).is_none());
+    assert_eq!(
+        load_file(&state, &key).await.unwrap().status,
+        AuditArchiveStatus::Completed
+    );
+
+    // Запись, появившаяся в уже заархивированных сутках, уходит в следующий файл
+    let late_id = object_id_on(day, 4);
+    seed_entry(&state, late_id, &marker).await;
+    worker(&state, storage.clone())
+        .run_once()
+        .await
+        .unwrap()
+        .unwrap();
+    let late_key = archive_key(day, 2);
+    let lines = storage.lines(&late_key);
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0]["_id"]["$oid"], late_id.to_hex());
+    assert_eq!(storage.object(&key).unwrap(), b"uploaded".to_vec());
+
+    reset_archive_days(&state, &[day]).await;
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_admin_lists_audit_archives_with_download_links() {
+    let mut state = common::create_test_state().await;
+    let storage = Arc::new(MockArchiveStorage::default());
+    state.export_links = Some(Arc::new(MockLinkSigner));
+    let day = random_old_day();
+    reset_archive_days(&state, &[day]).await;
+    seed_entry(
+        &state,
+        object_id_on(day, 9),
+        &format!("retention-list-{}", uuid::Uuid::new_v4()),
+    )
+    .await;
+    worker(&state, storage.clone())
+        .run_once()
+        .await
+        .unwrap()
+        .unwrap();
+
+    let token = admin_token(&state);
+    let app = create_router(Arc::new(state));
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/admin/audit/archives?limit=1000")
+                .header("authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value =
+        serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+
+    let key = archive_key(day, 1);
+    let archive = body
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|archive| archive["key"] == key.as_str())
+        .expect("archive listed");
+    assert_eq!(archive["day"], day.format("%Y-%m-%d").to_string());
+    assert_eq!(archive["status"], "completed");
+    assert_eq!(archive["entry_count"], 1);
+    assert!(archive["download_url"]
+        .as_str()
+        .unwrap()
+        .starts_with(&format!("https://storage.test/{}", key)));
+
+    let mongo = common::create_test_state().await.mongo;
+    mongo
+        .collection::<Document>("audit_archives")
+        .delete_many(doc! { "_id": &key })
+        .await
+        .unwrap();
+}
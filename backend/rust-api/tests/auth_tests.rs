@@ -199,7 +199,10 @@ async fn test_register_duplicate_email() {
     // Second registration with same email should fail
     let (status, body, _) = register_user(&app, &email, "Password456!", "User 2").await;
     assert_eq!(status, StatusCode::BAD_REQUEST);
-    assert!(body.contains("already exists") || body.contains("duplicate"));
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(json["code"], "REGISTRATION_FAILED");
+    let message = json["message"].as_str().unwrap();
+    assert!(message.contains("already exists") || message.contains("duplicate"));
 }
 
 #[tokio::test]
@@ -227,8 +230,29 @@ async fn test_register_invalid_email() {
     assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 
     let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
-    let body_str = String::from_utf8(body.to_vec()).unwrap();
-    assert!(body_str.contains("email") || body_str.contains("Validation"));
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["code"], "VALIDATION_ERROR");
+    assert_eq!(json["details"]["email"], json!(["Invalid email format"]));
+}
+
+#[tokio::test]
+async fn test_register_validation_returns_field_errors() {
+    let app = common::create_test_app().await;
+
+    let (status, body, _) = register_user(&app, "not-an-email", "short", "").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(json["code"], "VALIDATION_ERROR");
+    assert_eq!(json["message"], "Validation failed");
+
+    let details = json["details"].as_object().unwrap();
+    assert_eq!(details["email"], json!(["Invalid email format"]));
+    assert_eq!(
+        details["password"],
+        json!(["Password must be at least 8 characters"])
+    );
+    assert!(details.contains_key("name"));
 }
 
 #[tokio::test]
@@ -436,6 +460,44 @@ async fn test_get_current_user() {
     assert_eq!(json["name"], "Me Test");
 }
 
+#[tokio::test]
+async fn test_update_current_user_preferred_locale() {
+    let app = common::create_test_app().await;
+
+    let email = format!("test-locale-{}@example.com", uuid::Uuid::new_v4());
+    let (_, body, _) = register_user(&app, &email, "SecurePassword123!", "Locale Test").await;
+    let access_token = extract_access_token(&body).expect("access_token not found");
+
+    let patch = |locale: &str| {
+        let csrf_token = uuid::Uuid::new_v4().to_string();
+        Request::builder()
+            .method("PATCH")
+            .uri("/api/v1/auth/me")
+            .header(header::AUTHORIZATION, format!("Bearer {}", access_token))
+            .header(header::CONTENT_TYPE, "application/json")
+            .header("x-csrf-token", &csrf_token)
+            .header(header::COOKIE, format!("csrf_token={}", csrf_token))
+            .header("x-request-nonce", uuid::Uuid::new_v4().to_string())
+            .header(
+                "x-request-timestamp",
+                chrono::Utc::now().timestamp().to_string(),
+            )
+            .body(Body::from(
+                json!({ "preferred_locale": locale }).to_string(),
+            ))
+            .unwrap()
+    };
+
+    let response = app.clone().oneshot(patch("en")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["preferred_locale"], "en");
+
+    let response = app.clone().oneshot(patch("English")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
 #[tokio::test]
 async fn test_get_current_user_without_token() {
     let app = common::create_test_app().await;
@@ -579,3 +641,155 @@ async fn test_admin_can_access_admin_routes() {
 
     assert_eq!(response.status(), StatusCode::OK);
 }
+
+async fn login_with_user_agent(
+    app: &axum::Router,
+    email: &str,
+    password: &str,
+    user_agent: &str,
+) -> (String, String) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/auth/login")
+                .header("content-type", "application/json")
+                .header(header::USER_AGENT, user_agent)
+                .header("x-forwarded-for", "203.0.113.57")
+                .body(Body::from(
+                    json!({ "email": email, "password": password }).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let cookies: Vec<String> = response
+        .headers()
+        .get_all("set-cookie")
+        .iter()
+        .filter_map(|v| v.to_str().ok().map(|s| s.to_string()))
+        .collect();
+    let refresh_token = extract_refresh_token_cookie(&cookies).expect("refresh_token missing");
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let access_token =
+        extract_access_token(std::str::from_utf8(&body).unwrap()).expect("access_token missing");
+
+    (access_token, refresh_token)
+}
+
+async fn refresh_status(app: &axum::Router, refresh_token: &str) -> StatusCode {
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/auth/refresh")
+                .header(header::COOKIE, format!("refresh_token={}", refresh_token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+        .status()
+}
+
+#[tokio::test]
+async fn test_revoke_single_session() {
+    let app = common::create_test_app().await;
+
+    let email = format!(
+        "test-device-sessions-{}@example.com",
+        chrono::Utc::now().timestamp_nanos_opt().unwrap()
+    );
+    let password = "SecurePassword123!";
+    let (status, _, _) = register_user(&app, &email, password, "Sessions Test").await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (_, phone_refresh) = login_with_user_agent(
+        &app,
+        &email,
+        password,
+        "Mozilla/5.0 (iPhone; CPU iPhone OS 17_1 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.1 Mobile/15E148 Safari/604.1",
+    )
+    .await;
+    let (access_token, desktop_refresh) = login_with_user_agent(
+        &app,
+        &email,
+        password,
+        "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+    )
+    .await;
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/api/v1/auth/sessions")
+                .header(header::AUTHORIZATION, format!("Bearer {}", access_token))
+                .header(header::COOKIE, format!("refresh_token={}", desktop_refresh))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let sessions: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+
+    let current = sessions
+        .iter()
+        .find(|s| s["is_current"] == true)
+        .expect("current session missing");
+    assert_eq!(current["device"]["browser"], "Chrome");
+    assert_eq!(current["device"]["os"], "Windows");
+    assert_eq!(current["device"]["ip"], "203.0.113.0");
+    let phone = sessions
+        .iter()
+        .find(|s| s["device"]["os"] == "iOS")
+        .expect("phone session missing");
+    assert_eq!(phone["is_current"], false);
+    let phone_session_id = phone["id"].as_str().unwrap().to_string();
+
+    let (csrf_token, csrf_cookie) = fetch_csrf_token(&app).await;
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(format!("/api/v1/auth/sessions/{}/revoke", phone_session_id))
+                .header(header::AUTHORIZATION, format!("Bearer {}", access_token))
+                .header("x-csrf-token", &csrf_token)
+                .header(header::COOKIE, format!("csrf_token={}", csrf_cookie))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    assert_eq!(
+        refresh_status(&app, &phone_refresh).await,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(refresh_status(&app, &desktop_refresh).await, StatusCode::OK);
+
+    // Повторный отзыв той же сессии - 404
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(format!("/api/v1/auth/sessions/{}/revoke", phone_session_id))
+                .header(header::AUTHORIZATION, format!("Bearer {}", access_token))
+                .header("x-csrf-token", &csrf_token)
+                .header(header::COOKIE, format!("csrf_token={}", csrf_cookie))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,132 @@
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use chrono::Utc;
+use mongodb::bson::oid::ObjectId;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use trainingground_api::{
+    create_router,
+    middlewares::auth::{JwtClaims, JwtService},
+    services::{content_service::MAX_JSON_DEPTH, AppState},
+};
+use uuid::Uuid;
+
+mod common;
+
+fn admin_jwt(state: &AppState) -> String {
+    let now = Utc::now().timestamp() as usize;
+    JwtService::from_config(&state.config)
+        .generate_token(JwtClaims {
+            sub: ObjectId::new().to_hex(),
+            role: "admin".to_string(),
+            group_ids: Vec::new(),
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap()
+}
+
+async fn post_json(
+    app: &axum::Router,
+    uri: &str,
+    token: Option<&str>,
+    body: String,
+) -> (StatusCode, Value) {
+    let csrf_token = Uuid::new_v4().to_string();
+    let mut request = Request::builder()
+        .method("POST")
+        .uri(uri)
+        .header("content-type", "application/json")
+        .header("x-csrf-token", &csrf_token)
+        .header("cookie", format!("csrf_token={}", csrf_token))
+        .header("x-request-nonce", Uuid::new_v4().to_string())
+        .header("x-request-timestamp", Utc::now().timestamp().to_string());
+    if let Some(token) = token {
+        request = request.header("authorization", format!("Bearer {}", token));
+    }
+
+    let response = app
+        .clone()
+        .oneshot(request.body(Body::from(body)).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or_default())
+}
+
+fn template_body(params: Value, content: String) -> String {
+    json!({
+        "slug": format!("limit-{}", Uuid::new_v4().simple()),
+        "level_id": ObjectId::new().to_hex(),
+        "rule_ids": [],
+        "params": params,
+        "content": content,
+    })
+    .to_string()
+}
+
+#[tokio::test]
+async fn test_oversized_login_body_returns_413() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+    let password = "x".repeat(state.config.body_limits.auth_bytes + 1);
+    let body = json!({ "email": "big@test.com", "password": password }).to_string();
+
+    let (status, body) = post_json(&app, "/api/v1/auth/login", None, body).await;
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(body["code"], "PAYLOAD_TOO_LARGE");
+}
+
+#[tokio::test]
+async fn test_oversized_template_body_returns_413() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+    let token = admin_jwt(&state);
+
+    // Шаблону разрешено больше остальных `/admin`, но не больше content-лимита
+    let content = "a".repeat(state.config.body_limits.content_bytes + 1);
+    let (status, body) = post_json(
+        &app,
+        "/admin/templates",
+        Some(&token),
+        template_body(json!({}), content),
+    )
+    .await;
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(body["code"], "PAYLOAD_TOO_LARGE");
+
+    // Тот же размер тела, что проходит для шаблонов, не проходит в другие разделы
+    let name = "a".repeat(state.config.body_limits.default_bytes + 1);
+    let (status, body) = post_json(
+        &app,
+        "/admin/groups",
+        Some(&token),
+        json!({ "name": name, "school": "School" }).to_string(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(body["code"], "PAYLOAD_TOO_LARGE");
+}
+
+#[tokio::test]
+async fn test_deeply_nested_template_params_return_400() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+    let token = admin_jwt(&state);
+
+    let params = (0..MAX_JSON_DEPTH + 10).fold(json!(1), |inner, _| json!({ "nested": inner }));
+    let (status, body) = post_json(
+        &app,
+        "/admin/templates",
+        Some(&token),
+        template_body(params, "Текст шаблона".to_string()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "JSON_TOO_DEEP");
+}
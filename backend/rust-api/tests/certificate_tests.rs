@@ -0,0 +1,204 @@
+mod common;
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Request, StatusCode},
+    response::Response,
+};
+use chrono::Utc;
+use mongodb::bson::{doc, oid::ObjectId, DateTime, Document};
+use serde_json::Value;
+use tower::ServiceExt;
+use trainingground_api::{
+    create_router,
+    middlewares::auth::{JwtClaims, JwtService},
+    models::ProgressSummary,
+    services::{certificate_service::CertificateStorage, AppState},
+};
+use uuid::Uuid;
+
+/// Хранилище в памяти: считает записи, чтобы проверить, что повтор не перерисовывает PDF
+#[derive(Default)]
+struct MemoryStorage {
+    objects: Mutex<HashMap<String, Vec<u8>>>,
+    stores: Mutex<u32>,
+}
+
+#[async_trait]
+impl CertificateStorage for MemoryStorage {
+    async fn load(&self, key: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        Ok(self.objects.lock().unwrap().get(key).cloned())
+    }
+
+    async fn store(&self, key: &str, bytes: Vec<u8>) -> anyhow::Result<()> {
+        *self.stores.lock().unwrap() += 1;
+        self.objects.lock().unwrap().insert(key.to_string(), bytes);
+        Ok(())
+    }
+}
+
+fn student_jwt(state: &AppState, user_id: &ObjectId) -> String {
+    let now = Utc::now().timestamp() as usize;
+    JwtService::from_config(&state.config)
+        .generate_token(JwtClaims {
+            sub: user_id.to_hex(),
+            role: "student".to_string(),
+            group_ids: Vec::new(),
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap()
+}
+
+async fn get(app: &axum::Router, token: &str, uri: &str) -> Response {
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(uri)
+                .header("authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+}
+
+/// Тема и уровень с проходным процентом 80
+async fn insert_level(state: &AppState, name: &str) -> ObjectId {
+    let now = DateTime::now();
+    let topic_id = ObjectId::new();
+    let level_id = ObjectId::new();
+    state
+        .mongo
+        .collection::<Document>("topics")
+        .insert_one(doc! {
+            "_id": topic_id,
+            "slug": format!("certificate-topic-{}", Uuid::new_v4().simple()),
+            "name": "Орфография",
+            "description": "",
+            "icon_url": null,
+            "sort_order": 0,
+            "status": "active",
+            "createdAt": now,
+            "updatedAt": now,
+        })
+        .await
+        .unwrap();
+    state
+        .mongo
+        .collection::<Document>("levels")
+        .insert_one(doc! {
+            "_id": level_id,
+            "topic_id": topic_id,
+            "order": 1,
+            "name": name,
+            "difficulty": "a1",
+            "description": "",
+            "min_pass_percent": 80,
+            "status": "active",
+            "createdAt": now,
+            "updatedAt": now,
+        })
+        .await
+        .unwrap();
+    level_id
+}
+
+async fn insert_progress(
+    state: &AppState,
+    user_id: &ObjectId,
+    level_id: &ObjectId,
+    percentage: f64,
+) {
+    let user_id = user_id.to_hex();
+    let level_id = level_id.to_hex();
+    state
+        .mongo
+        .collection::<ProgressSummary>("progress_summary_v2")
+        .insert_one(ProgressSummary {
+            id: format!("{}:{}", user_id, level_id),
+            user_id,
+            level_id,
+            attempts_total: 10,
+            correct_count: 9,
+            credit: None,
+            percentage,
+            score: 90,
+            updated_at: Utc::now(),
+        })
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_certificates_are_listed_and_rendered_for_passed_levels() {
+    let storage = Arc::new(MemoryStorage::default());
+    let mut state = common::create_test_state().await;
+    state.certificate_storage = Some(storage.clone());
+    let state = Arc::new(state);
+    let app = create_router(state.clone());
+
+    let user_id = ObjectId::new();
+    state
+        .mongo
+        .collection::<Document>("users")
+        .insert_one(doc! {
+            "_id": user_id,
+            "name": "Анна Иванова",
+            "email": format!("{}@test.local", user_id.to_hex()),
+        })
+        .await
+        .unwrap();
+    let passed = insert_level(&state, "Безударные гласные").await;
+    let failed = insert_level(&state, "Приставки").await;
+    insert_progress(&state, &user_id, &passed, 92.5).await;
+    insert_progress(&state, &user_id, &failed, 60.0).await;
+    let token = student_jwt(&state, &user_id);
+
+    let response = get(&app, &token, "/api/v1/me/certificates").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: Value = serde_json::from_slice(&bytes).unwrap();
+    let certificates = body["certificates"].as_array().unwrap();
+    assert_eq!(certificates.len(), 1, "{body}");
+    assert_eq!(certificates[0]["level_id"], passed.to_hex());
+    assert_eq!(certificates[0]["level_name"], "Безударные гласные");
+    assert_eq!(certificates[0]["topic_name"], "Орфография");
+    assert_eq!(certificates[0]["percentage"], 92.5);
+
+    let uri = format!("/api/v1/me/certificates/{}/pdf", passed.to_hex());
+    let response = get(&app, &token, &uri).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "application/pdf");
+    let first = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert!(first.starts_with(b"%PDF"));
+
+    // Повторное скачивание берёт PDF из хранилища
+    let response = get(&app, &token, &uri).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let second = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(first, second);
+    assert_eq!(*storage.stores.lock().unwrap(), 1);
+
+    let uri = format!("/api/v1/me/certificates/{}/pdf", failed.to_hex());
+    let response = get(&app, &token, &uri).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body["code"], "CERTIFICATE_NOT_EARNED");
+
+    // Чужой пройденный уровень тоже не отдаётся
+    let other = student_jwt(&state, &ObjectId::new());
+    let uri = format!("/api/v1/me/certificates/{}/pdf", passed.to_hex());
+    assert_eq!(
+        get(&app, &other, &uri).await.status(),
+        StatusCode::FORBIDDEN
+    );
+}
@@ -0,0 +1,176 @@
+use chrono::Utc;
+use mongodb::bson::{doc, oid::ObjectId};
+use reqwest::StatusCode;
+use serde_json::json;
+use std::sync::Arc;
+use trainingground_api::{
+    client::{ApiClient, ClientError},
+    create_router,
+    models::{
+        answer::SubmitAnswerRequest,
+        reporting::{GroupStatsResponse, MaterializedStat, StatType},
+        CreateSessionRequest, SessionMode,
+    },
+};
+use uuid::Uuid;
+
+mod common;
+
+const PASSWORD: &str = "Client123!@#";
+
+/// Поднимает роутер на случайном порту и возвращает его адрес
+async fn spawn_server() -> String {
+    let app = create_router(Arc::new(common::create_test_state().await));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+/// Регистрирует ученика; возвращает email и refresh-токен из cookie
+async fn register(base_url: &str) -> (String, String) {
+    let email = format!("client-user-{}@test.com", Uuid::new_v4());
+    let response = reqwest::Client::new()
+        .post(format!("{}/api/v1/auth/register", base_url))
+        .json(&json!({ "email": email, "password": PASSWORD, "name": "Client User" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let refresh_token = response
+        .headers()
+        .get_all("set-cookie")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .find_map(|cookie| cookie.strip_prefix("refresh_token="))
+        .and_then(|rest| rest.split(';').next())
+        .expect("refresh_token cookie")
+        .to_string();
+    (email, refresh_token)
+}
+
+#[test]
+fn reporting_models_round_trip_through_json() {
+    let response = json!({
+        "group_id": ObjectId::new().to_hex(),
+        "stats": MaterializedStat {
+            id: ObjectId::new(),
+            stat_type: StatType::Group,
+            entity_id: ObjectId::new(),
+            metrics: doc! { "avg_accuracy": 87.5, "total_attempts": 12 },
+            calculated_at: Utc::now(),
+        },
+        "leaderboard": null,
+    });
+
+    let parsed: GroupStatsResponse = serde_json::from_value(response).unwrap();
+    assert!(matches!(parsed.stats.stat_type, StatType::Group));
+    assert_eq!(parsed.stats.metrics.get_f64("avg_accuracy").unwrap(), 87.5);
+    assert!(parsed.leaderboard.is_none());
+}
+
+#[tokio::test]
+async fn test_client_session_flow() {
+    let base_url = spawn_server().await;
+    let (email, _) = register(&base_url).await;
+
+    let client = ApiClient::new(&base_url);
+    let user = client.login(&email, PASSWORD).await.unwrap();
+    assert_eq!(user.email, email);
+    assert_eq!(client.current_user().await.unwrap().id, user.id);
+
+    let session = client
+        .create_session(&CreateSessionRequest {
+            user_id: user.id.clone(),
+            task_id: "test-task".to_string(),
+            group_id: None,
+            level_id: None,
+            session_duration_seconds: None,
+            mode: SessionMode::Normal,
+            assignment_id: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(session.task.id, "test-task");
+
+    let answer = client
+        .submit_answer(
+            &session.session_id,
+            &SubmitAnswerRequest {
+                answer: "42".to_string(),
+                idempotency_key: None,
+                parts: None,
+            },
+        )
+        .await
+        .unwrap();
+    assert!(answer.correct);
+
+    client.complete_session(&session.session_id).await.unwrap();
+    client.logout().await.unwrap();
+    assert!(client.access_token().await.is_none());
+}
+
+#[tokio::test]
+async fn test_client_refreshes_rejected_access_token() {
+    let base_url = spawn_server().await;
+    let (email, refresh_token) = register(&base_url).await;
+
+    // Access-токен недействителен - клиент обновляет его по refresh-токену и повторяет запрос
+    let client = ApiClient::with_tokens(&base_url, "expired-token", Some(refresh_token));
+    let user = client.current_user().await.unwrap();
+    assert_eq!(user.email, email);
+    assert_ne!(
+        client.access_token().await.as_deref(),
+        Some("expired-token")
+    );
+
+    let without_refresh = ApiClient::with_tokens(&base_url, "expired-token", None);
+    let error = without_refresh.current_user().await.unwrap_err();
+    assert_eq!(error.status(), Some(StatusCode::UNAUTHORIZED));
+}
+
+#[tokio::test]
+async fn test_client_surfaces_typed_errors() {
+    let base_url = spawn_server().await;
+    let (email, _) = register(&base_url).await;
+    let client = ApiClient::new(&base_url);
+    let user = client.login(&email, PASSWORD).await.unwrap();
+
+    // Ошибка в формате ErrorResponse
+    let error = client
+        .submit_answer(
+            &format!("missing-{}", Uuid::new_v4()),
+            &SubmitAnswerRequest {
+                answer: "42".to_string(),
+                idempotency_key: None,
+                parts: None,
+            },
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(error.status(), Some(StatusCode::NOT_FOUND));
+    assert_eq!(error.code(), Some("SESSION_NOT_FOUND"));
+
+    // Ошибка-строка отчётов: кода нет, сообщение сохраняется
+    let error = client.user_stats(&user.id).await.unwrap_err();
+    match error {
+        ClientError::Api {
+            status,
+            code,
+            message,
+            ..
+        } => {
+            assert_eq!(status, StatusCode::FORBIDDEN);
+            assert!(code.is_none());
+            assert_eq!(message, "User does not belong to your groups");
+        }
+        other => panic!("unexpected error: {other:?}"),
+    }
+
+    let error = client.login(&email, "wrong-password").await.unwrap_err();
+    assert_eq!(error.status(), Some(StatusCode::UNAUTHORIZED));
+}
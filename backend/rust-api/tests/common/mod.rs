@@ -3,7 +3,16 @@ use mongodb::bson::doc;
 use std::sync::Arc;
 use trainingground_api::{config::Config, create_router, services::AppState};
 
+// Not every test binary builds the default router (see `create_test_state`)
+#[allow(dead_code)]
 pub async fn create_test_app() -> Router {
+    // Build test router (same as main app)
+    create_router(Arc::new(create_test_state().await))
+}
+
+/// App state for tests that need to adjust it (e.g. swap in a mock storage) before
+/// building the router with `create_router`.
+pub async fn create_test_state() -> AppState {
     // Initialize tracing for tests
     let _ = tracing_subscriber::fmt()
         .with_test_writer()
@@ -42,19 +51,16 @@ pub async fn create_test_app() -> Router {
     eprintln!("Redis client created, attempting connection...");
 
     // Create app state (connection is established inside)
-    let app_state = Arc::new(
-        AppState::new(config.clone(), mongo_client.clone(), redis_client)
-            .await
-            .expect("Failed to initialize test app state"),
-    );
+    let app_state = AppState::new(config.clone(), mongo_client.clone(), redis_client)
+        .await
+        .expect("Failed to initialize test app state");
 
     eprintln!("AppState initialized successfully");
 
     // Seed test data
     seed_test_data(&mongo_client, &config.mongo_database).await;
 
-    // Build test router (same as main app)
-    create_router(app_state)
+    app_state
 }
 
 async fn seed_test_data(mongo_client: &mongodb::Client, db_name: &str) {
@@ -0,0 +1,789 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use chrono::{Datelike, Utc};
+use mongodb::bson::{doc, oid::ObjectId, DateTime as BsonDateTime};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+mod common;
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_session_blocked_without_consent_and_allowed_after_recording() {
+    let app = common::create_test_app().await;
+    set_rate_limit_disabled();
+    clear_consent_state().await;
+    let (_admin_id, admin_token) = create_admin_with_token(&app).await;
+    let (student_id, student_token) = register_student(&app, None).await;
+
+    update_consent_settings(&app, &admin_token, json!(["data_processing"]), "1", false).await;
+
+    // Без согласия ученик может войти, но сессию начать не может
+    let (status, body) = start_student_session(&app, &student_token).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["code"], "CONSENT_REQUIRED");
+    assert_eq!(body["details"]["missing"], json!(["data_processing"]));
+
+    let (status, body) = create_legacy_session(&app, &student_id, &student_token).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["code"], "CONSENT_REQUIRED");
+    assert_eq!(body["details"]["missing"], json!(["data_processing"]));
+
+    let response = record_consent(&app, &admin_token, &student_id, "data_processing").await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let (status, _) = create_legacy_session(&app, &student_id, &student_token).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    clear_consent_state().await;
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_revoked_consent_blocks_sessions_again() {
+    let app = common::create_test_app().await;
+    set_rate_limit_disabled();
+    clear_consent_state().await;
+    let (_admin_id, admin_token) = create_admin_with_token(&app).await;
+    let (student_id, student_token) = register_student(&app, None).await;
+
+    update_consent_settings(&app, &admin_token, json!(["data_processing"]), "1", false).await;
+    record_consent(&app, &admin_token, &student_id, "data_processing").await;
+
+    let (status, _) = create_legacy_session(&app, &student_id, &student_token).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (csrf_token, csrf_cookie) = get_csrf_token(&app).await;
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri(format!(
+                    "/admin/users/{}/consents/data_processing",
+                    student_id
+                ))
+                .header("authorization", format!("Bearer {}", admin_token))
+                .header("x-csrf-token", &csrf_token)
+                .header("cookie", format!("csrf_token={}", csrf_cookie))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let (status, _) = create_legacy_session(&app, &student_id, &student_token).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Отозванное согласие остаётся в истории
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(format!("/admin/users/{}/consents", student_id))
+                .header("authorization", format!("Bearer {}", admin_token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json = json_from_bytes(&body);
+    assert_eq!(json["consents"].as_array().unwrap().len(), 1);
+    assert!(!json["consents"][0]["revoked_at"].is_null());
+    assert_eq!(json["missing"], json!(["data_processing"]));
+
+    clear_consent_state().await;
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_document_version_bump_requires_reconsent() {
+    let app = common::create_test_app().await;
+    set_rate_limit_disabled();
+    clear_consent_state().await;
+    let (_admin_id, admin_token) = create_admin_with_token(&app).await;
+    let (student_id, student_token) = register_student(&app, None).await;
+
+    update_consent_settings(&app, &admin_token, json!(["data_processing"]), "1", true).await;
+    record_consent(&app, &admin_token, &student_id, "data_processing").await;
+
+    // Смена версии без повторного согласия не блокирует ученика
+    update_consent_settings(&app, &admin_token, json!(["data_processing"]), "2", false).await;
+    let (status, _) = create_legacy_session(&app, &student_id, &student_token).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    update_consent_settings(&app, &admin_token, json!(["data_processing"]), "2", true).await;
+    let (status, _) = create_legacy_session(&app, &student_id, &student_token).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Новое согласие записывается на текущую версию документа
+    let response = record_consent(&app, &admin_token, &student_id, "data_processing").await;
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(json_from_bytes(&body)["document_version"], "2");
+
+    let (status, _) = create_legacy_session(&app, &student_id, &student_token).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    clear_consent_state().await;
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_group_consent_coverage_report() {
+    let app = common::create_test_app().await;
+    set_rate_limit_disabled();
+    clear_consent_state().await;
+    let (_admin_id, admin_token) = create_admin_with_token(&app).await;
+
+    update_consent_settings(
+        &app,
+        &admin_token,
+        json!(["data_processing", "photo"]),
+        "1",
+        false,
+    )
+    .await;
+
+    let group_id = insert_group().await;
+    let mut students = Vec::new();
+    for _ in 0..4 {
+        let (student_id, _) = register_student(&app, None).await;
+        assign_group(&student_id, &group_id).await;
+        students.push(student_id);
+    }
+
+    // Первые два ученика - оба согласия, третий - только одно, четвёртый - ни одного
+    for student_id in &students[..2] {
+        record_consent(&app, &admin_token, student_id, "data_processing").await;
+        record_consent(&app, &admin_token, student_id, "photo").await;
+    }
+    record_consent(&app, &admin_token, &students[2], "data_processing").await;
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(format!("/admin/groups/{}/consent-coverage", group_id))
+                .header("authorization", format!("Bearer {}", admin_token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json = json_from_bytes(&body);
+
+    assert_eq!(json["total_students"], 4);
+    assert_eq!(json["fully_covered"], 2);
+    assert_eq!(json["coverage_percent"], 50.0);
+    assert_eq!(json["by_consent"][0]["consent_type"], "data_processing");
+    assert_eq!(json["by_consent"][0]["granted"], 3);
+    assert_eq!(json["by_consent"][1]["consent_type"], "photo");
+    assert_eq!(json["by_consent"][1]["granted"], 2);
+    let missing: Vec<&str> = json["missing_students"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|value| value.as_str().unwrap())
+        .collect();
+    assert_eq!(missing.len(), 2);
+    assert!(missing.contains(&students[2].as_str()));
+    assert!(missing.contains(&students[3].as_str()));
+
+    clear_consent_state().await;
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_courses_filtered_by_student_age_band() {
+    let app = common::create_test_app().await;
+    set_rate_limit_disabled();
+    clear_consent_state().await;
+
+    let current_year = Utc::now().year();
+    let (_young_id, young_token) = register_student(&app, Some(current_year - 8)).await;
+    let (_older_id, older_token) = register_student(&app, Some(current_year - 16)).await;
+    // Без года рождения действует самая строгая категория
+    let (_unknown_id, unknown_token) = register_student(&app, None).await;
+
+    let topic_id = insert_topic(Some("middle")).await;
+    let level_id = insert_level(&topic_id).await;
+    let topic_rated_template = insert_published_template(&level_id, None).await;
+    let senior_template = insert_published_template(&level_id, Some("senior")).await;
+
+    let young_courses = list_course_ids(&app, &young_token).await;
+    assert!(!young_courses.contains(&topic_rated_template));
+    assert!(!young_courses.contains(&senior_template));
+
+    let unknown_courses = list_course_ids(&app, &unknown_token).await;
+    assert!(!unknown_courses.contains(&topic_rated_template));
+
+    let older_courses = list_course_ids(&app, &older_token).await;
+    assert!(older_courses.contains(&topic_rated_template));
+    assert!(older_courses.contains(&senior_template));
+
+    // Прямой запуск сессии по неподходящему шаблону тоже запрещён
+    let (csrf_token, csrf_cookie) = get_csrf_token(&app).await;
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/student/sessions")
+                .header("authorization", format!("Bearer {}", young_token))
+                .header("content-type", "application/json")
+                .header("x-csrf-token", &csrf_token)
+                .header("cookie", format!("csrf_token={}", csrf_cookie))
+                .body(Body::from(
+                    json!({ "template_id": senior_template }).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_legacy_session_respects_age_band() {
+    let app = common::create_test_app().await;
+    set_rate_limit_disabled();
+    clear_consent_state().await;
+
+    let current_year = Utc::now().year();
+    let (young_id, young_token) = register_student(&app, Some(current_year - 8)).await;
+
+    // На уровне только шаблон для старших - генератору нечего выбрать
+    let topic_id = insert_topic(None).await;
+    let level_id = insert_level(&topic_id).await;
+    insert_published_template(&level_id, Some("senior")).await;
+
+    let (csrf_token, csrf_cookie) = get_csrf_token(&app).await;
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/sessions")
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {}", young_token))
+                .header("x-csrf-token", &csrf_token)
+                .header("cookie", format!("csrf_token={}", csrf_cookie))
+                .body(Body::from(
+                    json!({
+                        "user_id": young_id,
+                        "task_id": "test-task",
+                        "level_id": level_id.to_hex(),
+                    })
+                    .to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(json_from_bytes(&body)["code"], "AGE_BAND_RESTRICTED");
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_guardian_link_records_and_revokes_consent() {
+    let app = common::create_test_app().await;
+    set_rate_limit_disabled();
+    clear_consent_state().await;
+    let (_admin_id, admin_token) = create_admin_with_token(&app).await;
+    let (student_id, student_token) = register_student(&app, None).await;
+
+    update_consent_settings(&app, &admin_token, json!(["data_processing"]), "1", false).await;
+
+    let (csrf_token, csrf_cookie) = get_csrf_token(&app).await;
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(format!(
+                    "/admin/users/{}/consents/guardian-link",
+                    student_id
+                ))
+                .header("authorization", format!("Bearer {}", admin_token))
+                .header("content-type", "application/json")
+                .header("x-csrf-token", &csrf_token)
+                .header("cookie", format!("csrf_token={}", csrf_cookie))
+                .body(Body::from("{}"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let link = json_from_bytes(&body);
+    let token = link["token"].as_str().unwrap().to_string();
+
+    let (status, body) = guardian_request(&app, "GET", &token, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["missing"], json!(["data_processing"]));
+
+    let (status, body) = guardian_request(
+        &app,
+        "POST",
+        &token,
+        Some(json!({ "consent_type": "data_processing", "guardian_name": "Parent Link" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body["granted_by"], "guardian");
+    assert_eq!(body["guardian_name"], "Parent Link");
+
+    let (status, _) = create_legacy_session(&app, &student_id, &student_token).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, _) =
+        guardian_request(&app, "DELETE", &format!("{}/data_processing", token), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, body) = create_legacy_session(&app, &student_id, &student_token).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["code"], "CONSENT_REQUIRED");
+
+    let (status, body) = guardian_request(&app, "GET", "unknown-token", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], "GUARDIAN_LINK_NOT_FOUND");
+
+    // Истёкшая ссылка больше не работает
+    test_db()
+        .await
+        .collection::<mongodb::bson::Document>("guardian_consent_links")
+        .update_one(
+            doc! { "_id": ObjectId::parse_str(link["id"].as_str().unwrap()).unwrap() },
+            doc! { "$set": { "expiresAt": BsonDateTime::from_millis(0) } },
+        )
+        .await
+        .unwrap();
+    let (status, body) = guardian_request(&app, "GET", &token, None).await;
+    assert_eq!(status, StatusCode::GONE);
+    assert_eq!(body["code"], "GUARDIAN_LINK_EXPIRED");
+
+    clear_consent_state().await;
+}
+
+async fn guardian_request(
+    app: &axum::Router,
+    method: &str,
+    path: &str,
+    payload: Option<Value>,
+) -> (StatusCode, Value) {
+    let mut request = Request::builder()
+        .method(method)
+        .uri(format!("/api/v1/guardian/consents/{}", path));
+    let body = match payload {
+        Some(payload) => {
+            request = request.header("content-type", "application/json");
+            Body::from(payload.to_string())
+        }
+        None => Body::empty(),
+    };
+    let response = app
+        .clone()
+        .oneshot(request.body(body).unwrap())
+        .await
+        .unwrap();
+
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json = serde_json::from_slice(&body).unwrap_or(Value::Null);
+    (status, json)
+}
+
+async fn start_student_session(app: &axum::Router, token: &str) -> (StatusCode, Value) {
+    let (csrf_token, csrf_cookie) = get_csrf_token(app).await;
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/student/sessions")
+                .header("authorization", format!("Bearer {}", token))
+                .header("content-type", "application/json")
+                .header("x-csrf-token", &csrf_token)
+                .header("cookie", format!("csrf_token={}", csrf_cookie))
+                .body(Body::from(
+                    json!({ "template_id": ObjectId::new().to_hex() }).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, json_from_bytes(&body))
+}
+
+async fn create_legacy_session(
+    app: &axum::Router,
+    user_id: &str,
+    token: &str,
+) -> (StatusCode, Value) {
+    let (csrf_token, csrf_cookie) = get_csrf_token(app).await;
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/sessions")
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {}", token))
+                .header("x-csrf-token", &csrf_token)
+                .header("cookie", format!("csrf_token={}", csrf_cookie))
+                .body(Body::from(
+                    json!({ "user_id": user_id, "task_id": "test-task" }).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json = serde_json::from_slice(&body)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&body).to_string()));
+    (status, json)
+}
+
+async fn list_course_ids(app: &axum::Router, token: &str) -> Vec<String> {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/api/v1/student/courses")
+                .header("authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    json_from_bytes(&body)["courses"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|course| course["id"].as_str().unwrap().to_string())
+        .collect()
+}
+
+async fn update_consent_settings(
+    app: &axum::Router,
+    admin_token: &str,
+    required_consents: Value,
+    document_version: &str,
+    require_reconsent: bool,
+) {
+    let (csrf_token, csrf_cookie) = get_csrf_token(app).await;
+    let payload = json!({
+        "required_consents": required_consents,
+        "document_version": document_version,
+        "require_reconsent_on_version_bump": require_reconsent,
+    });
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("PUT")
+                .uri("/admin/settings/consent")
+                .header("authorization", format!("Bearer {}", admin_token))
+                .header("content-type", "application/json")
+                .header("x-csrf-token", &csrf_token)
+                .header("cookie", format!("csrf_token={}", csrf_cookie))
+                .body(Body::from(payload.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+async fn record_consent(
+    app: &axum::Router,
+    admin_token: &str,
+    student_id: &str,
+    consent_type: &str,
+) -> axum::response::Response {
+    let (csrf_token, csrf_cookie) = get_csrf_token(app).await;
+    let payload = json!({
+        "consent_type": consent_type,
+        "granted_by": "guardian",
+        "guardian_name": "Parent Test",
+    });
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(format!("/admin/users/{}/consents", student_id))
+                .header("authorization", format!("Bearer {}", admin_token))
+                .header("content-type", "application/json")
+                .header("x-csrf-token", &csrf_token)
+                .header("cookie", format!("csrf_token={}", csrf_cookie))
+                .body(Body::from(payload.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    response
+}
+
+async fn register_student(app: &axum::Router, birth_year: Option<i32>) -> (String, String) {
+    let body = json!({
+        "email": format!("consent-student-{}@test.com", uuid::Uuid::new_v4()),
+        "password": "Student123!@#",
+        "name": "Consent Student",
+        "birth_year": birth_year,
+    });
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/auth/register")
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json = json_from_bytes(&bytes);
+    let user_id = json["user"]["id"].as_str().unwrap().to_string();
+    let token = json["access_token"].as_str().unwrap().to_string();
+    (user_id, token)
+}
+
+async fn create_admin_with_token(app: &axum::Router) -> (String, String) {
+    let body = json!({
+        "email": format!("consent-admin-{}@test.com", uuid::Uuid::new_v4()),
+        "password": "Admin123!@#",
+        "name": "Admin Consent",
+    });
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/auth/register")
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json = json_from_bytes(&bytes);
+    let user_id = json["user"]["id"].as_str().unwrap().to_string();
+
+    promote_user_to_admin(&user_id).await;
+
+    let login_body = json!({
+        "email": json["user"]["email"].as_str().unwrap(),
+        "password": "Admin123!@#",
+    });
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/auth/login")
+                .header("content-type", "application/json")
+                .body(Body::from(login_body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json = json_from_bytes(&bytes);
+    let token = json["access_token"].as_str().unwrap().to_string();
+
+    (user_id, token)
+}
+
+async fn test_db() -> mongodb::Database {
+    let config = trainingground_api::config::Config::load().unwrap();
+    let mongo_client = mongodb::Client::with_uri_str(&config.mongo_uri)
+        .await
+        .unwrap();
+    mongo_client.database(&config.mongo_database)
+}
+
+async fn promote_user_to_admin(user_id: &str) {
+    test_db()
+        .await
+        .collection::<mongodb::bson::Document>("users")
+        .update_one(
+            doc! { "_id": ObjectId::parse_str(user_id).unwrap() },
+            doc! { "$set": { "role": "admin" } },
+        )
+        .await
+        .unwrap();
+}
+
+async fn assign_group(user_id: &str, group_id: &str) {
+    test_db()
+        .await
+        .collection::<mongodb::bson::Document>("users")
+        .update_one(
+            doc! { "_id": ObjectId::parse_str(user_id).unwrap() },
+            doc! { "$set": { "group_ids": [group_id] } },
+        )
+        .await
+        .unwrap();
+}
+
+async fn insert_group() -> String {
+    let now = BsonDateTime::now();
+    let result = test_db()
+        .await
+        .collection::<mongodb::bson::Document>("groups")
+        .insert_one(doc! {
+            "name": format!("Consent group {}", uuid::Uuid::new_v4()),
+            "school": "Test School",
+            "createdAt": now,
+            "updatedAt": now,
+        })
+        .await
+        .unwrap();
+    result.inserted_id.as_object_id().unwrap().to_hex()
+}
+
+async fn insert_topic(age_band: Option<&str>) -> ObjectId {
+    let now = BsonDateTime::now();
+    let result = test_db()
+        .await
+        .collection::<mongodb::bson::Document>("topics")
+        .insert_one(doc! {
+            "slug": format!("consent-topic-{}", uuid::Uuid::new_v4()),
+            "name": "Age rated topic",
+            "description": "Topic with age rating",
+            "icon_url": null,
+            "sort_order": 0,
+            "status": "active",
+            "age_band": age_band,
+            "createdAt": now,
+            "updatedAt": now,
+        })
+        .await
+        .unwrap();
+    result.inserted_id.as_object_id().unwrap()
+}
+
+async fn insert_level(topic_id: &ObjectId) -> ObjectId {
+    let now = BsonDateTime::now();
+    let result = test_db()
+        .await
+        .collection::<mongodb::bson::Document>("levels")
+        .insert_one(doc! {
+            "topic_id": topic_id,
+            "order": 1,
+            "name": "Age rated level",
+            "difficulty": "a1",
+            "description": "Level for age band tests",
+            "min_pass_percent": 80,
+            "status": "active",
+            "createdAt": now,
+            "updatedAt": now,
+        })
+        .await
+        .unwrap();
+    result.inserted_id.as_object_id().unwrap()
+}
+
+async fn insert_published_template(level_id: &ObjectId, age_band: Option<&str>) -> String {
+    let now = BsonDateTime::now();
+    let result = test_db()
+        .await
+        .collection::<mongodb::bson::Document>("templates")
+        .insert_one(doc! {
+            "slug": format!("consent-template-{}", uuid::Uuid::new_v4()),
+            "level_id": level_id,
+            "content": "Rated template",
+            "age_band": age_band,
+            "status": "published",
+            "version": 1,
+            "published_at": now,
+            "createdAt": now,
+            "updatedAt": now,
+        })
+        .await
+        .unwrap();
+    result.inserted_id.as_object_id().unwrap().to_hex()
+}
+
+async fn get_csrf_token(app: &axum::Router) -> (String, String) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/api/v1/auth/csrf-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let csrf_cookie = response
+        .headers()
+        .get_all("set-cookie")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .find(|h| h.starts_with("csrf_token="))
+        .and_then(|pair| pair.split(';').next())
+        .and_then(|part| part.split('=').nth(1))
+        .unwrap_or("")
+        .to_string();
+
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json = json_from_bytes(&body);
+    let csrf_token = json["csrf_token"].as_str().unwrap().to_string();
+    (csrf_token, csrf_cookie)
+}
+
+fn json_from_bytes(bytes: &[u8]) -> Value {
+    let body_str = std::str::from_utf8(bytes).unwrap();
+    serde_json::from_str(body_str).unwrap()
+}
+
+fn set_rate_limit_disabled() {
+    std::env::set_var("RATE_LIMIT_DISABLED", "1");
+}
+
+/// Сбрасывает настройки согласий, чтобы не влиять на другие тесты сессий
+async fn clear_consent_state() {
+    test_db()
+        .await
+        .collection::<mongodb::bson::Document>("system_settings")
+        .delete_many(doc! { "key": "consent" })
+        .await
+        .unwrap();
+}
@@ -0,0 +1,202 @@
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::Result;
+use chrono::Utc;
+use mongodb::bson::{doc, oid::ObjectId, Document};
+use uuid::Uuid;
+
+use trainingground_api::{
+    middlewares::auth::JwtClaims,
+    models::content::{
+        LevelCreateRequest, LevelDifficulty, LevelUpdateRequest, RuleCreateRequest,
+        TemplateCreateRequest, TemplateListQuery, TemplateSummary, TopicCreateRequest,
+    },
+    services::{
+        content_cache::{content_cache_key, ContentCache, ContentCacheKind},
+        content_service::ContentService,
+    },
+};
+
+fn claims() -> JwtClaims {
+    let now = Utc::now().timestamp() as usize;
+    JwtClaims {
+        sub: Uuid::new_v4().to_string(),
+        role: "content_admin".to_string(),
+        group_ids: vec![],
+        iat: now,
+        exp: now + 3600,
+    }
+}
+
+async fn cache_exists(redis: &redis::aio::ConnectionManager, key: &str) -> bool {
+    redis::cmd("EXISTS")
+        .arg(key)
+        .query_async::<bool>(&mut redis.clone())
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn second_lookup_is_served_from_cache() -> Result<()> {
+    let state = common::create_test_state().await;
+    let cache = ContentCache::new(state.redis.clone());
+    let ids = [ObjectId::new(), ObjectId::new()];
+    let loads = AtomicUsize::new(0);
+    let load = |missing: Vec<ObjectId>| {
+        loads.fetch_add(missing.len(), Ordering::SeqCst);
+        async move {
+            Ok(missing
+                .into_iter()
+                .map(|id| (id, doc! { "name": id.to_hex() }))
+                .collect())
+        }
+    };
+
+    let first = cache
+        .get_many::<Document, _, _>(ContentCacheKind::Topic, &ids, load)
+        .await?;
+    assert_eq!(first.len(), 2);
+    assert_eq!(loads.load(Ordering::SeqCst), 2);
+
+    let second = cache
+        .get_many::<Document, _, _>(ContentCacheKind::Topic, &ids, load)
+        .await?;
+    assert_eq!(second, first);
+    assert_eq!(
+        loads.load(Ordering::SeqCst),
+        2,
+        "cached entries must not be reloaded"
+    );
+
+    cache.invalidate(ContentCacheKind::Topic, &ids[..1]).await?;
+    cache
+        .get_many::<Document, _, _>(ContentCacheKind::Topic, &ids, load)
+        .await?;
+    assert_eq!(
+        loads.load(Ordering::SeqCst),
+        3,
+        "only the invalidated entry is reloaded"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn level_update_invalidates_cached_template_listing() -> Result<()> {
+    let state = common::create_test_state().await;
+    let service = ContentService::new(&state);
+    let claims = claims();
+
+    let topic = service
+        .create_topic(
+            TopicCreateRequest {
+                slug: format!("cache-topic-{}", Uuid::new_v4()),
+                name: "Cache Topic".to_string(),
+                description: "Test".to_string(),
+                icon_url: None,
+                status: None,
+                age_band: None,
+            },
+            &claims,
+        )
+        .await?;
+    let level = service
+        .create_level(
+            LevelCreateRequest {
+                topic_id: topic.id.to_string(),
+                name: "Уровень 1".to_string(),
+                difficulty: LevelDifficulty::A1,
+                description: "Test".to_string(),
+                min_pass_percent: None,
+                order: Some(1),
+                prerequisite_level_ids: vec![],
+            },
+            &claims,
+        )
+        .await?;
+    let rule = service
+        .create_rule(
+            RuleCreateRequest {
+                slug: format!("cache-rule-{}", Uuid::new_v4()),
+                name: "Cache Rule".to_string(),
+                category: "orthography".to_string(),
+                description: "Test".to_string(),
+                examples: vec![],
+                exceptions: vec![],
+                sources: vec![],
+                status: None,
+            },
+            &claims,
+        )
+        .await?;
+    service
+        .create_template(
+            TemplateCreateRequest {
+                slug: format!("cache-template-{}", Uuid::new_v4()),
+                level_id: level.id.to_string(),
+                rule_ids: vec![rule.id.to_string()],
+                params: serde_json::json!({}),
+                metadata: serde_json::json!({}),
+                content: "Template for cache test".to_string(),
+                difficulty: None,
+                source_refs: vec![],
+                age_band: None,
+                locale: None,
+                translations: Default::default(),
+            },
+            &claims,
+        )
+        .await?;
+
+    let query = || TemplateListQuery {
+        level_id: Some(level.id.to_hex()),
+        ..TemplateListQuery::default()
+    };
+    let level_name =
+        |templates: &[TemplateSummary]| templates[0].level.as_ref().map(|level| level.name.clone());
+
+    let listed = service.list_templates(query()).await?;
+    assert_eq!(level_name(&listed).as_deref(), Some("Уровень 1"));
+    let level_key = content_cache_key(ContentCacheKind::Level, &level.id);
+    assert!(cache_exists(&state.redis, &level_key).await);
+    assert!(
+        cache_exists(
+            &state.redis,
+            &content_cache_key(ContentCacheKind::Topic, &topic.id)
+        )
+        .await
+    );
+
+    // Правка в обход ContentService не видна: уровень читается из кэша, а не из MongoDB
+    state
+        .mongo
+        .collection::<Document>("levels")
+        .update_one(
+            doc! { "_id": level.id },
+            doc! { "$set": { "name": "Изменено напрямую" } },
+        )
+        .await?;
+    let listed = service.list_templates(query()).await?;
+    assert_eq!(level_name(&listed).as_deref(), Some("Уровень 1"));
+
+    service
+        .update_level(
+            &level.id,
+            LevelUpdateRequest {
+                name: Some("Уровень 1 (новый)".to_string()),
+                description: None,
+                difficulty: None,
+                min_pass_percent: None,
+                status: None,
+                prerequisite_level_ids: None,
+            },
+            &claims,
+        )
+        .await?;
+    assert!(!cache_exists(&state.redis, &level_key).await);
+
+    let listed = service.list_templates(query()).await?;
+    assert_eq!(level_name(&listed).as_deref(), Some("Уровень 1 (новый)"));
+    Ok(())
+}
@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::Utc;
+use mongodb::bson::{doc, Document};
+use mongodb::Client as MongoClient;
+use redis::Client as RedisClient;
+use trainingground_api::{
+    config::Config,
+    middlewares::auth::JwtClaims,
+    models::content::{
+        LevelCreateRequest, LevelDifficulty, TemplateCreateRequest, TemplateUpdateRequest,
+        TopicCreateRequest,
+    },
+    services::{
+        content_outbox::{ContentOutboxDispatcher, CONTENT_OUTBOX_COLLECTION},
+        content_service::ContentService,
+        AppState,
+    },
+};
+use uuid::Uuid;
+
+/// Отдельный стрим; записи outbox, закреплённые за "упавшим" диспетчером, сразу
+/// доступны следующему
+async fn build_test_state() -> Result<(Arc<AppState>, ContentService, JwtClaims)> {
+    dotenvy::from_filename(".env.test").ok();
+    let mut config = Config::load()?;
+    config.content.stream_name = format!("test:content:changes:{}", Uuid::new_v4().simple());
+    config.content.outbox_lease_secs = 0;
+    let mongo_client = MongoClient::with_uri_str(&config.mongo_uri).await?;
+    let redis_client = RedisClient::open(config.redis_uri.clone())?;
+    let state = Arc::new(AppState::new(config, mongo_client, redis_client).await?);
+    let now = Utc::now();
+    let claims = JwtClaims {
+        sub: Uuid::new_v4().to_string(),
+        role: "content_admin".to_string(),
+        group_ids: vec![],
+        iat: now.timestamp() as usize,
+        exp: (now.timestamp() + 3600) as usize,
+    };
+    Ok((state.clone(), ContentService::new(&state), claims))
+}
+
+/// Диспетчер на отдельном процессе: новый экземпляр на каждый "запуск"
+fn dispatcher(state: &AppState) -> ContentOutboxDispatcher {
+    ContentOutboxDispatcher::new(
+        state.mongo.clone(),
+        state.redis.clone(),
+        &state.config.content,
+    )
+}
+
+async fn stream_events(state: &AppState, template_id: &str) -> Result<Vec<(String, String)>> {
+    let mut conn = state.redis.clone();
+    let entries: Vec<(String, HashMap<String, String>)> = redis::cmd("XRANGE")
+        .arg(&state.config.content.stream_name)
+        .arg("-")
+        .arg("+")
+        .query_async(&mut conn)
+        .await?;
+    Ok(entries
+        .into_iter()
+        .filter(|(_, fields)| fields.get("template_id").map(String::as_str) == Some(template_id))
+        .map(|(id, fields)| (id, fields["action"].clone()))
+        .collect())
+}
+
+async fn audit_count(state: &AppState, template_id: &str, action: &str) -> Result<u64> {
+    Ok(state
+        .mongo
+        .collection::<Document>("audit_log")
+        .count_documents(doc! { "target_id": template_id, "action": action })
+        .await?)
+}
+
+#[tokio::test]
+async fn outbox_entries_survive_dispatcher_crash_and_are_delivered_once() -> Result<()> {
+    let (state, service, claims) = build_test_state().await?;
+    let outbox = state
+        .mongo
+        .collection::<Document>(CONTENT_OUTBOX_COLLECTION);
+    // Записи, оставшиеся от других тестов без диспетчера
+    dispatcher(&state).run_once().await?;
+
+    let topic = service
+        .create_topic(
+            TopicCreateRequest {
+                slug: format!("outbox-topic-{}", Uuid::new_v4()),
+                name: "Outbox".to_string(),
+                description: "Outbox topic".to_string(),
+                icon_url: None,
+                status: None,
+                age_band: None,
+            },
+            &claims,
+        )
+        .await?;
+    let level = service
+        .create_level(
+            LevelCreateRequest {
+                topic_id: topic.id.to_string(),
+                name: "Outbox level".to_string(),
+                difficulty: LevelDifficulty::A1,
+                description: "Outbox level".to_string(),
+                min_pass_percent: Some(70),
+                order: Some(1),
+                prerequisite_level_ids: vec![],
+            },
+            &claims,
+        )
+        .await?;
+    let template = service
+        .create_template(
+            TemplateCreateRequest {
+                slug: format!("outbox-template-{}", Uuid::new_v4()),
+                level_id: level.id.to_string(),
+                rule_ids: vec![],
+                params: serde_json::json!({}),
+                metadata: serde_json::json!({}),
+                content: "Outbox template content".to_string(),
+                difficulty: Some("A1".to_string()),
+                source_refs: vec![],
+                age_band: None,
+                locale: None,
+                translations: Default::default(),
+            },
+            &claims,
+        )
+        .await?;
+    let template_id = template.id.clone();
+    let template_oid = mongodb::bson::oid::ObjectId::parse_str(&template_id)?;
+
+    // Шаблон и запись outbox уже в базе, аудит появится только после диспетчера
+    let pending = outbox
+        .find_one(doc! { "template_id": template_oid })
+        .await?
+        .expect("outbox entry written with the template");
+    assert_eq!(pending.get_str("status")?, "pending");
+    assert_eq!(
+        audit_count(&state, &template_id, "template.create").await?,
+        0
+    );
+
+    // Диспетчер забирает запись и падает до публикации
+    let claimed = dispatcher(&state)
+        .claim_next()
+        .await?
+        .expect("entry is available");
+    assert_eq!(claimed.get_object_id("_id")?, pending.get_object_id("_id")?);
+
+    // После перезапуска запись доставляется
+    assert_eq!(dispatcher(&state).run_once().await?, 1);
+    assert_eq!(
+        audit_count(&state, &template_id, "template.create").await?,
+        1
+    );
+    let entry = outbox
+        .find_one(doc! { "_id": pending.get_object_id("_id")? })
+        .await?
+        .unwrap();
+    assert_eq!(entry.get_str("status")?, "done");
+    assert_eq!(entry.get_i32("attempts")?, 2);
+
+    // Путь до публикации: модерация, затем публикация с событием в стриме
+    service
+        .submit_template_for_moderation(&template_oid, &claims)
+        .await?;
+    service.approve_template(&template_oid, &claims).await?;
+    let second_reviewer = JwtClaims {
+        sub: Uuid::new_v4().to_string(),
+        ..claims.clone()
+    };
+    service
+        .approve_template(&template_oid, &second_reviewer)
+        .await?;
+    assert_eq!(dispatcher(&state).run_once().await?, 3);
+    service
+        .update_template(
+            &template_oid,
+            TemplateUpdateRequest {
+                status: Some("published".to_string()),
+                params: None,
+                metadata: None,
+                content: None,
+                difficulty: None,
+                source_refs: None,
+                age_band: None,
+                locale: None,
+                translations: Default::default(),
+            },
+            &claims,
+        )
+        .await?;
+    assert!(stream_events(&state, &template_id).await?.is_empty());
+
+    // Диспетчер публикует событие и падает, не закрыв запись
+    let crashed = dispatcher(&state);
+    let claimed = crashed.claim_next().await?.expect("publish entry");
+    let stream_id = crashed.publish(&claimed).await?.expect("event published");
+    drop(crashed);
+
+    // Следующий диспетчер повторяет публикацию, но событие и аудит не дублируются
+    assert_eq!(dispatcher(&state).run_once().await?, 1);
+    let events = stream_events(&state, &template_id).await?;
+    assert_eq!(events, vec![(stream_id.clone(), "published".to_string())]);
+    assert_eq!(
+        audit_count(&state, &template_id, "template.update").await?,
+        1
+    );
+    let entry = outbox
+        .find_one(doc! { "_id": claimed.get_object_id("_id")? })
+        .await?
+        .unwrap();
+    assert_eq!(entry.get_str("status")?, "done");
+    assert_eq!(entry.get_str("streamId")?, stream_id);
+
+    assert_eq!(dispatcher(&state).run_once().await?, 0);
+    assert_eq!(
+        outbox
+            .count_documents(doc! { "template_id": template_oid, "status": { "$ne": "done" } })
+            .await?,
+        0
+    );
+
+    let mut conn = state.redis.clone();
+    redis::cmd("DEL")
+        .arg(&state.config.content.stream_name)
+        .query_async::<()>(&mut conn)
+        .await?;
+    Ok(())
+}
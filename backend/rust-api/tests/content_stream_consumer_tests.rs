@@ -0,0 +1,252 @@
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use mongodb::bson::{doc, oid::ObjectId, Document};
+use mongodb::Client as MongoClient;
+use redis::Client as RedisClient;
+use trainingground_api::{
+    config::Config,
+    models::content::ContentChangeEvent,
+    services::{
+        content_cache::{content_cache_key, ContentCacheKind},
+        content_service::ContentService,
+        content_stream_consumer::{
+            ContentEventHandler, ContentStreamConsumer, EmbeddingEnqueueHandler,
+        },
+        AppState,
+    },
+};
+use uuid::Uuid;
+
+/// Записывает id полученных событий; `fail` - всегда отвечать ошибкой
+#[derive(Default)]
+struct RecordingHandler {
+    seen: Mutex<Vec<String>>,
+    fail: bool,
+}
+
+#[async_trait]
+impl ContentEventHandler for RecordingHandler {
+    fn name(&self) -> &'static str {
+        "recording"
+    }
+
+    async fn handle(&self, event: &ContentChangeEvent) -> Result<()> {
+        self.seen.lock().unwrap().push(event.id.clone());
+        if self.fail {
+            return Err(anyhow!("search index is down"));
+        }
+        Ok(())
+    }
+}
+
+/// Состояние с отдельными стримами; повтор без паузы и короткое ожидание XREADGROUP
+async fn build_test_state() -> Result<Arc<AppState>> {
+    dotenvy::from_filename(".env.test").ok();
+    let mut config = Config::load()?;
+    let suffix = Uuid::new_v4().simple();
+    config.content.stream_name = format!("test:content:changes:{}", suffix);
+    config.content.dead_letter_stream = format!("test:content:dlq:{}", suffix);
+    config.content.consumer_max_attempts = 3;
+    config.content.consumer_retry_idle_secs = 0;
+    config.content.consumer_block_ms = 50;
+    let mongo_client = MongoClient::with_uri_str(&config.mongo_uri).await?;
+    let redis_client = RedisClient::open(config.redis_uri.clone())?;
+    Ok(Arc::new(
+        AppState::new(config, mongo_client, redis_client).await?,
+    ))
+}
+
+fn consumer(state: &AppState, name: &str, handler: Arc<RecordingHandler>) -> ContentStreamConsumer {
+    let mut settings = state.config.content.clone();
+    settings.consumer_name = name.to_string();
+    ContentStreamConsumer::new(state.redis.clone(), settings).with_handler(handler)
+}
+
+async fn publish(state: &AppState, template_id: &ObjectId, action: &str) -> Result<String> {
+    let mut conn = state.redis.clone();
+    Ok(redis::cmd("XADD")
+        .arg(&state.config.content.stream_name)
+        .arg("*")
+        .arg("template_id")
+        .arg(template_id.to_hex())
+        .arg("action")
+        .arg(action)
+        .query_async(&mut conn)
+        .await?)
+}
+
+async fn pending_count(state: &AppState) -> Result<i64> {
+    let mut conn = state.redis.clone();
+    let summary: (i64, Option<String>, Option<String>, redis::Value) = redis::cmd("XPENDING")
+        .arg(&state.config.content.stream_name)
+        .arg(&state.config.content.consumer_group)
+        .query_async(&mut conn)
+        .await?;
+    Ok(summary.0)
+}
+
+async fn cleanup(state: &AppState) -> Result<()> {
+    let mut conn = state.redis.clone();
+    redis::cmd("DEL")
+        .arg(&state.config.content.stream_name)
+        .arg(&state.config.content.dead_letter_stream)
+        .query_async::<()>(&mut conn)
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn events_are_dispatched_acked_and_published_templates_enqueue_embeddings() -> Result<()> {
+    let state = build_test_state().await?;
+    let recorder = Arc::new(RecordingHandler::default());
+    let consumer = ContentStreamConsumer::from_state(&state, state.redis.clone())
+        .with_handler(recorder.clone());
+    consumer.ensure_group().await?;
+    // Повторный вызов (перезапуск) не падает на BUSYGROUP
+    consumer.ensure_group().await?;
+
+    let template_id = ObjectId::new();
+    let mut conn = state.redis.clone();
+    let cache_key = content_cache_key(ContentCacheKind::Template, &template_id);
+    redis::cmd("SET")
+        .arg(&cache_key)
+        .arg("stale")
+        .query_async::<()>(&mut conn)
+        .await?;
+
+    let published = publish(&state, &template_id, "published").await?;
+    let reindex = publish(&state, &template_id, "reindex").await?;
+
+    let tick = consumer.run_once().await?;
+    assert_eq!(tick.processed, 2);
+    assert_eq!(tick.failed, 0);
+    assert_eq!(
+        *recorder.seen.lock().unwrap(),
+        vec![published.clone(), reindex]
+    );
+    assert_eq!(pending_count(&state).await?, 0);
+    let cached: Option<String> = redis::cmd("GET")
+        .arg(&cache_key)
+        .query_async(&mut conn)
+        .await?;
+    assert!(cached.is_none());
+
+    // Задание ставится только на публикацию, повторная доставка его не дублирует
+    let jobs = state.mongo.collection::<Document>("embedding_jobs");
+    let filter = doc! { "template_ids": template_id };
+    assert_eq!(jobs.count_documents(filter.clone()).await?, 1);
+    let redelivered = ContentChangeEvent {
+        id: published.clone(),
+        template_id: template_id.to_hex(),
+        action: "published".to_string(),
+        version: None,
+        timestamp: None,
+    };
+    EmbeddingEnqueueHandler::new(state.mongo.clone())
+        .handle(&redelivered)
+        .await?;
+    assert_eq!(jobs.count_documents(filter.clone()).await?, 1);
+    let job = jobs.find_one(filter).await?.expect("job enqueued");
+    assert_eq!(job.get_str("status")?, "queued");
+    assert_eq!(job.get_str("sourceEvent")?, published);
+
+    // Новых событий нет - проход пустой
+    let tick = consumer.run_once().await?;
+    assert_eq!(tick.processed, 0);
+
+    cleanup(&state).await
+}
+
+#[tokio::test]
+async fn failing_events_are_retried_then_moved_to_dead_letter_stream() -> Result<()> {
+    let state = build_test_state().await?;
+    let recorder = Arc::new(RecordingHandler {
+        fail: true,
+        ..Default::default()
+    });
+    let consumer = consumer(&state, "worker-a", recorder.clone());
+    consumer.ensure_group().await?;
+
+    let template_id = ObjectId::new();
+    let event_id = publish(&state, &template_id, "published").await?;
+
+    // Первая доставка и один повтор: событие остаётся неподтверждённым
+    for _ in 0..2 {
+        let tick = consumer.run_once().await?;
+        assert_eq!(tick.failed, 1);
+        assert_eq!(pending_count(&state).await?, 1);
+    }
+
+    let tick = consumer.run_once().await?;
+    assert_eq!(tick.failed, 0);
+    assert_eq!(tick.dead_lettered, 1);
+    assert_eq!(recorder.seen.lock().unwrap().len(), 3);
+    assert_eq!(pending_count(&state).await?, 0);
+
+    // Больше событие не выдаётся
+    let tick = consumer.run_once().await?;
+    assert_eq!(tick, Default::default());
+    assert_eq!(recorder.seen.lock().unwrap().len(), 3);
+
+    let status = ContentService::new(&state).queue_status(Some(0)).await?;
+    let dead_letters = &status.dead_letter_stream;
+    assert_eq!(dead_letters.name, state.config.content.dead_letter_stream);
+    assert_eq!(dead_letters.length, 1);
+    let entry = &dead_letters.entries[0];
+    assert_eq!(entry.event.id, event_id);
+    assert_eq!(entry.event.template_id, template_id.to_hex());
+    assert_eq!(entry.event.action, "published");
+    assert_eq!(entry.group, state.config.content.consumer_group);
+    assert_eq!(entry.attempts, 3);
+    let error = entry.error.as_deref().expect("handler error recorded");
+    assert!(error.contains("recording handler failed"), "{error}");
+    assert!(error.contains("search index is down"), "{error}");
+    assert!(status.dead_letters.is_empty());
+
+    cleanup(&state).await
+}
+
+#[tokio::test]
+async fn consumer_resumes_after_restart_and_picks_up_unacked_events() -> Result<()> {
+    let state = build_test_state().await?;
+    let first = Arc::new(RecordingHandler::default());
+    consumer(&state, "worker-a", first.clone())
+        .ensure_group()
+        .await?;
+
+    let template_id = ObjectId::new();
+    let handled = publish(&state, &template_id, "published").await?;
+    consumer(&state, "worker-a", first.clone())
+        .run_once()
+        .await?;
+
+    // Консьюмер забирает событие и падает, не отправив XACK
+    let lost = publish(&state, &template_id, "updated").await?;
+    let mut conn = state.redis.clone();
+    redis::cmd("XREADGROUP")
+        .arg("GROUP")
+        .arg(&state.config.content.consumer_group)
+        .arg("worker-a")
+        .arg("STREAMS")
+        .arg(&state.config.content.stream_name)
+        .arg(">")
+        .query_async::<redis::Value>(&mut conn)
+        .await?;
+    // Пока консьюмера нет, приходит ещё одно событие
+    let fresh = publish(&state, &template_id, "archived").await?;
+
+    let restarted = Arc::new(RecordingHandler::default());
+    let tick = consumer(&state, "worker-b", restarted.clone())
+        .run_once()
+        .await?;
+    assert_eq!(tick.processed, 2);
+    // Уже подтверждённое событие повторно не выдаётся
+    let seen = restarted.seen.lock().unwrap().clone();
+    assert_eq!(seen, vec![lost, fresh]);
+    assert!(!seen.contains(&handled));
+    assert_eq!(pending_count(&state).await?, 0);
+
+    cleanup(&state).await
+}
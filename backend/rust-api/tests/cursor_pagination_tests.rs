@@ -0,0 +1,309 @@
+mod common;
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use chrono::{Duration, Utc};
+use mongodb::bson::{doc, oid::ObjectId, DateTime as BsonDateTime, Document};
+use serde_json::Value;
+use tower::ServiceExt;
+use trainingground_api::{
+    create_router,
+    middlewares::auth::{JwtClaims, JwtService},
+    services::AppState,
+};
+use uuid::Uuid;
+
+fn admin_token(state: &AppState) -> String {
+    let now = Utc::now().timestamp() as usize;
+    JwtService::new(&state.config.jwt_secret)
+        .generate_token(JwtClaims {
+            sub: ObjectId::new().to_hex(),
+            role: "admin".to_string(),
+            group_ids: vec![],
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap()
+}
+
+/// Пользователи с общей меткой в email; по 10 человек на одну секунду `createdAt`,
+/// чтобы порядок внутри одинаковых значений решал `_id`
+fn user_documents(tag: &str, from: usize, count: usize) -> Vec<Document> {
+    let base = Utc::now() - Duration::hours(1);
+    (from..from + count)
+        .map(|index| {
+            let created_at = base + Duration::seconds((index / 10) as i64);
+            let created_at = BsonDateTime::from_millis(created_at.timestamp_millis());
+            doc! {
+                "email": format!("{}-{:03}@test.com", tag, index),
+                "password_hash": "hash",
+                "name": format!("Cursor {:03}", index),
+                "role": "student",
+                "group_ids": [],
+                "is_blocked": false,
+                "createdAt": created_at,
+                "updatedAt": created_at,
+            }
+        })
+        .collect()
+}
+
+async fn get(app: &Router, uri: &str, token: &str) -> (StatusCode, Option<String>, Value) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(uri)
+                .header("authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let cursor = response
+        .headers()
+        .get("x-next-cursor")
+        .map(|value| value.to_str().unwrap().to_string());
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (
+        status,
+        cursor,
+        serde_json::from_slice(&bytes).unwrap_or_default(),
+    )
+}
+
+/// Обойти список по курсорам; `between_pages` вызывается после каждой страницы
+async fn collect_emails<F, Fut>(
+    app: &Router,
+    token: &str,
+    base_uri: &str,
+    mut between_pages: F,
+) -> Vec<String>
+where
+    F: FnMut(usize) -> Fut,
+    Fut: std::future::Future<Output = ()>,
+{
+    let mut emails = Vec::new();
+    let mut cursor: Option<String> = None;
+    for page in 0.. {
+        let uri = match &cursor {
+            Some(cursor) => format!("{}&cursor={}", base_uri, cursor),
+            None => base_uri.to_string(),
+        };
+        let (status, next, body) = get(app, &uri, token).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        emails.extend(
+            body.as_array()
+                .unwrap()
+                .iter()
+                .map(|user| user["email"].as_str().unwrap().to_string()),
+        );
+        between_pages(page).await;
+        match next {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+        assert!(page < 20, "pagination does not terminate");
+    }
+    emails
+}
+
+#[tokio::test]
+async fn cursor_pagination_walks_users_without_gaps() {
+    let state = common::create_test_state().await;
+    let tag = format!("cursorpage-{}", Uuid::new_v4().simple());
+    let users = state.mongo.collection::<Document>("users");
+    users
+        .insert_many(user_documents(&tag, 0, 250))
+        .await
+        .unwrap();
+    let seeded: HashSet<String> = (0..250)
+        .map(|index| format!("{}-{:03}@test.com", tag, index))
+        .collect();
+
+    let token = admin_token(&state);
+    let app = create_router(Arc::new(state));
+
+    // Новые записи во время обхода: при сортировке от новых к старым они оказываются
+    // перед курсором и не сдвигают страницы
+    let inserted = std::sync::atomic::AtomicUsize::new(0);
+    let newest_first = collect_emails(
+        &app,
+        &token,
+        &format!("/admin/users?search={}&sort=-created_at&limit=40", tag),
+        |page| {
+            let users = users.clone();
+            let tag = tag.clone();
+            let from = 1000 + inserted.fetch_add(3, std::sync::atomic::Ordering::SeqCst);
+            async move {
+                if page < 3 {
+                    let mut fresh = user_documents(&tag, from, 3);
+                    for user in &mut fresh {
+                        user.insert("createdAt", BsonDateTime::now());
+                    }
+                    users.insert_many(fresh).await.unwrap();
+                }
+            }
+        },
+    )
+    .await;
+    assert_eq!(newest_first.len(), 250, "no duplicates or extra entries");
+    assert_eq!(newest_first.iter().cloned().collect::<HashSet<_>>(), seeded);
+    let mut expected: Vec<String> = seeded.iter().cloned().collect();
+    expected.sort_by(|a, b| b.cmp(a));
+    // Внутри одной секунды `createdAt` порядок задаёт `_id`, а он растёт вместе с индексом
+    assert_eq!(newest_first, expected);
+
+    // По возрастанию новые записи попадают в конец обхода, каждая ровно один раз
+    let by_email = collect_emails(
+        &app,
+        &token,
+        &format!("/admin/users?search={}&sort=email&limit=100", tag),
+        |_| async {},
+    )
+    .await;
+    let unique: HashSet<&String> = by_email.iter().collect();
+    assert_eq!(unique.len(), by_email.len());
+    assert_eq!(by_email.len(), 250 + 9);
+    let mut sorted = by_email.clone();
+    sorted.sort();
+    assert_eq!(by_email, sorted);
+
+    // Смещение по-прежнему работает для небольших страниц
+    let (status, _, page) = get(
+        &app,
+        &format!("/admin/users?search={}&sort=email&limit=5&offset=5", tag),
+        &token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let emails: Vec<&str> = page
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|user| user["email"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        emails,
+        by_email[5..10]
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+    );
+}
+
+#[tokio::test]
+async fn invalid_cursors_are_rejected() {
+    let state = common::create_test_state().await;
+    let tag = format!("cursorbad-{}", Uuid::new_v4().simple());
+    state
+        .mongo
+        .collection::<Document>("users")
+        .insert_many(user_documents(&tag, 0, 3))
+        .await
+        .unwrap();
+    let token = admin_token(&state);
+    let app = create_router(Arc::new(state));
+
+    let (status, cursor, _) = get(
+        &app,
+        &format!("/admin/users?search={}&sort=email&limit=2", tag),
+        &token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let cursor = cursor.expect("first page is full");
+
+    for uri in [
+        format!("/admin/users?search={}&cursor=garbage!!", tag),
+        format!("/admin/users?search={}&cursor=Z2FyYmFnZQ", tag),
+        // Курсор от другой сортировки
+        format!("/admin/users?search={}&sort=name&cursor={}", tag, cursor),
+        format!(
+            "/admin/users?search={}&sort=email&cursor={}&offset=10",
+            tag, cursor
+        ),
+    ] {
+        let (status, _, body) = get(&app, &uri, &token).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}: {body}");
+    }
+
+    let (status, next, page) = get(
+        &app,
+        &format!("/admin/users?search={}&sort=email&cursor={}", tag, cursor),
+        &token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{page}");
+    assert_eq!(page.as_array().unwrap().len(), 1);
+    assert!(next.is_none());
+}
+
+#[tokio::test]
+async fn audit_log_supports_cursor_pagination() {
+    let state = common::create_test_state().await;
+    let actor_id = ObjectId::new().to_hex();
+    let entries: Vec<Document> = (0..5)
+        .map(|index| {
+            doc! {
+                "action": "template.update",
+                "actor_id": &actor_id,
+                "target": "templates",
+                "target_id": format!("target-{}", index),
+                "createdAt": BsonDateTime::now(),
+            }
+        })
+        .collect();
+    state
+        .mongo
+        .collection::<Document>("audit_log")
+        .insert_many(entries)
+        .await
+        .unwrap();
+    let token = admin_token(&state);
+    let app = create_router(Arc::new(state));
+
+    let mut targets = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let uri = match &cursor {
+            Some(cursor) => format!(
+                "/admin/audit?actor_id={}&limit=2&cursor={}",
+                actor_id, cursor
+            ),
+            None => format!("/admin/audit?actor_id={}&limit=2", actor_id),
+        };
+        let (status, next, body) = get(&app, &uri, &token).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+        targets.extend(
+            body.as_array()
+                .unwrap()
+                .iter()
+                .map(|entry| entry["target_id"].as_str().unwrap().to_string()),
+        );
+        match next {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+    assert_eq!(
+        targets,
+        vec!["target-4", "target-3", "target-2", "target-1", "target-0"]
+    );
+
+    let (status, _, _) = get(
+        &app,
+        &format!("/admin/audit?actor_id={}&cursor=!!!", actor_id),
+        &token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
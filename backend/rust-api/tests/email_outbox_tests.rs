@@ -0,0 +1,260 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use chrono::{Duration, Utc};
+use mongodb::bson::{doc, oid::ObjectId};
+use serde_json::Value;
+use tower::ServiceExt;
+use trainingground_api::{
+    config::EmailOutboxSettings,
+    create_router,
+    middlewares::auth::{JwtClaims, JwtService},
+    models::notification::{EmailDeliveryStatus, EmailOutboxMessage, SentNotification},
+    services::{
+        email_outbox_service::{EmailOutboxService, OutgoingEmail},
+        email_worker::{EmailTickSummary, EmailTransport, EmailWorker},
+        AppState,
+    },
+    utils::time::chrono_to_bson,
+};
+
+/// SMTP, который отклоняет письма выбранной рассылки и запоминает остальные
+struct FakeTransport {
+    failing_notification: ObjectId,
+    delivered: Mutex<Vec<String>>,
+}
+
+#[async_trait]
+impl EmailTransport for FakeTransport {
+    async fn send(&self, message: &EmailOutboxMessage) -> anyhow::Result<()> {
+        if message.notification_id == self.failing_notification {
+            return Err(anyhow!("554 relay unavailable"));
+        }
+        self.delivered
+            .lock()
+            .unwrap()
+            .push(message.recipient_email.clone());
+        Ok(())
+    }
+}
+
+fn worker(state: &AppState, transport: Arc<FakeTransport>, max_attempts: u32) -> EmailWorker {
+    // Без задержки повтор доступен уже на следующем тике; большой батч забирает
+    // и письма, оставшиеся в базе от других тестов
+    let settings = EmailOutboxSettings {
+        batch_size: 1000,
+        max_attempts,
+        retry_base_secs: 0,
+        ..EmailOutboxSettings::default()
+    };
+    EmailWorker::new(state.mongo.clone(), transport, settings)
+}
+
+fn email(name: &str) -> OutgoingEmail {
+    OutgoingEmail {
+        recipient_id: ObjectId::new(),
+        recipient_email: format!("{}-{}@test.com", name, ObjectId::new().to_hex()),
+        recipient_name: name.to_string(),
+        subject: "Домашнее задание".to_string(),
+        body: "Не забудьте пройти уровень".to_string(),
+    }
+}
+
+#[tokio::test]
+async fn failing_email_is_retried_then_marked_failed() {
+    let state = common::create_test_state().await;
+    let outbox = EmailOutboxService::new(state.mongo.clone());
+    let failing = ObjectId::new();
+    let healthy = ObjectId::new();
+    outbox
+        .enqueue(failing, vec![email("Анна")], false)
+        .await
+        .unwrap();
+    outbox
+        .enqueue(healthy, vec![email("Борис")], false)
+        .await
+        .unwrap();
+
+    let transport = Arc::new(FakeTransport {
+        failing_notification: failing,
+        delivered: Mutex::new(Vec::new()),
+    });
+    let worker = worker(&state, transport.clone(), 2);
+
+    let first = worker.process_pending().await.unwrap();
+    assert!(first.sent >= 1);
+    assert!(first.retried >= 1);
+    let messages = outbox.list_for_notification(&failing).await.unwrap();
+    assert_eq!(messages[0].status, EmailDeliveryStatus::Queued);
+    assert_eq!(messages[0].attempts, 1);
+    assert_eq!(
+        messages[0].last_error.as_deref(),
+        Some("554 relay unavailable")
+    );
+
+    let second = worker.process_pending().await.unwrap();
+    assert!(second.failed >= 1);
+    let messages = outbox.list_for_notification(&failing).await.unwrap();
+    assert_eq!(messages[0].status, EmailDeliveryStatus::Failed);
+    assert_eq!(messages[0].attempts, 2);
+
+    // Сбой одной рассылки не мешает другой
+    let delivered = outbox.list_for_notification(&healthy).await.unwrap();
+    assert_eq!(delivered[0].status, EmailDeliveryStatus::Sent);
+    assert!(delivered[0].sent_at.is_some());
+    assert!(transport
+        .delivered
+        .lock()
+        .unwrap()
+        .contains(&delivered[0].recipient_email));
+
+    // Письмо в статусе failed больше не берётся
+    let third = worker.process_pending().await.unwrap();
+    assert_eq!(third, EmailTickSummary::default());
+}
+
+#[tokio::test]
+async fn email_of_crashed_worker_is_reclaimed_after_lease() {
+    let state = common::create_test_state().await;
+    let outbox = EmailOutboxService::new(state.mongo.clone());
+    let crashed = ObjectId::new();
+    let busy = ObjectId::new();
+    outbox
+        .enqueue(crashed, vec![email("Вера")], false)
+        .await
+        .unwrap();
+    outbox
+        .enqueue(busy, vec![email("Глеб")], false)
+        .await
+        .unwrap();
+
+    // Оба письма взяты воркером в `sending`; у первого аренда уже истекла
+    let collection = state.mongo.collection::<EmailOutboxMessage>("email_outbox");
+    for (notification_id, lease_until) in [
+        (crashed, Utc::now() - Duration::minutes(1)),
+        (busy, Utc::now() + Duration::minutes(10)),
+    ] {
+        collection
+            .update_one(
+                doc! { "notification_id": notification_id },
+                doc! {
+                    "$set": {
+                        "status": "sending",
+                        "attempts": 1,
+                        "leaseUntil": chrono_to_bson(lease_until),
+                    },
+                },
+            )
+            .await
+            .unwrap();
+    }
+
+    let transport = Arc::new(FakeTransport {
+        failing_notification: ObjectId::new(),
+        delivered: Mutex::new(Vec::new()),
+    });
+    worker(&state, transport, 5)
+        .process_pending()
+        .await
+        .unwrap();
+
+    let reclaimed = outbox.list_for_notification(&crashed).await.unwrap();
+    assert_eq!(reclaimed[0].status, EmailDeliveryStatus::Sent);
+    assert_eq!(reclaimed[0].attempts, 2);
+    assert!(reclaimed[0].lease_until.is_none());
+
+    // Чужая действующая аренда не перехватывается
+    let leased = outbox.list_for_notification(&busy).await.unwrap();
+    assert_eq!(leased[0].status, EmailDeliveryStatus::Sending);
+    assert_eq!(leased[0].attempts, 1);
+}
+
+#[tokio::test]
+async fn teacher_sees_per_recipient_delivery_status() {
+    let state = common::create_test_state().await;
+    let teacher_id = ObjectId::new();
+    let notification_id = ObjectId::new();
+    state
+        .mongo
+        .collection::<SentNotification>("sent_notifications")
+        .insert_one(SentNotification {
+            id: notification_id,
+            teacher_id,
+            template_id: ObjectId::new(),
+            recipients: vec![ObjectId::new(), ObjectId::new()],
+            subject: "Домашнее задание".to_string(),
+            body: "Текст".to_string(),
+            sent_at: Utc::now(),
+            status: "skipped".to_string(),
+        })
+        .await
+        .unwrap();
+    // Как при EMAIL_SEND_DISABLED: воркер соседнего теста такие письма не заберёт
+    EmailOutboxService::new(state.mongo.clone())
+        .enqueue(notification_id, vec![email("Анна"), email("Борис")], true)
+        .await
+        .unwrap();
+
+    let now = Utc::now().timestamp() as usize;
+    let token = JwtService::from_config(&state.config)
+        .generate_token(JwtClaims {
+            sub: teacher_id.to_hex(),
+            role: "teacher".to_string(),
+            group_ids: Vec::new(),
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap();
+    let other_teacher = JwtService::from_config(&state.config)
+        .generate_token(JwtClaims {
+            sub: ObjectId::new().to_hex(),
+            role: "teacher".to_string(),
+            group_ids: Vec::new(),
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap();
+    let app = create_router(Arc::new(state));
+
+    let get_status = |token: String| {
+        let app = app.clone();
+        async move {
+            let response = app
+                .oneshot(
+                    Request::builder()
+                        .uri(format!(
+                            "/api/v1/teacher/notifications/{}/status",
+                            notification_id.to_hex()
+                        ))
+                        .header("authorization", format!("Bearer {}", token))
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            let status = response.status();
+            let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let body: Value = serde_json::from_slice(&bytes).unwrap_or_default();
+            (status, body)
+        }
+    };
+
+    let (status, body) = get_status(token).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["status"], "skipped");
+    let recipients = body["recipients"].as_array().unwrap();
+    assert_eq!(recipients.len(), 2);
+    assert_eq!(recipients[0]["name"], "Анна");
+    assert_eq!(recipients[0]["status"], "skipped");
+    assert_eq!(recipients[0]["attempts"], 0);
+
+    let (status, _) = get_status(other_teacher).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
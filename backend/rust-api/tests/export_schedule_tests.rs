@@ -0,0 +1,259 @@
+mod common;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use chrono::{Datelike, Utc};
+use mongodb::bson::{doc, oid::ObjectId, Document};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tower::ServiceExt;
+use trainingground_api::{
+    create_router,
+    middlewares::auth::{JwtClaims, JwtService},
+    services::{
+        export_schedule_worker::ExportScheduleWorker, reporting_service::ReportingService, AppState,
+    },
+};
+
+fn teacher_token(state: &AppState, teacher_id: &ObjectId, group_id: &ObjectId) -> String {
+    let now = Utc::now().timestamp() as usize;
+    JwtService::new(&state.config.jwt_secret)
+        .generate_token(JwtClaims {
+            sub: teacher_id.to_hex(),
+            role: "teacher".to_string(),
+            group_ids: vec![group_id.to_hex()],
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap()
+}
+
+async fn get_csrf_token(app: &Router) -> (String, String) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/api/v1/auth/csrf-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let csrf_cookie = response
+        .headers()
+        .get_all("set-cookie")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .find(|s| s.starts_with("csrf_token="))
+        .and_then(|s| s.split(';').next())
+        .and_then(|part| part.split('=').nth(1))
+        .unwrap_or_default()
+        .to_string();
+
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    (
+        json["csrf_token"].as_str().unwrap().to_string(),
+        csrf_cookie,
+    )
+}
+
+async fn send(
+    app: &Router,
+    token: &str,
+    csrf: &(String, String),
+    method: &str,
+    uri: &str,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let mut builder = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("authorization", format!("Bearer {}", token))
+        .header("x-csrf-token", &csrf.0)
+        .header("cookie", format!("csrf_token={}", csrf.1));
+    let body = match body {
+        Some(value) => {
+            builder = builder.header("content-type", "application/json");
+            Body::from(value.to_string())
+        }
+        None => Body::empty(),
+    };
+
+    let response = app
+        .clone()
+        .oneshot(builder.body(body).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+    (status, json)
+}
+
+#[tokio::test]
+async fn test_export_schedule_crud_validates_input() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+    let csrf = get_csrf_token(&app).await;
+    let teacher_id = ObjectId::new();
+    let group_id = ObjectId::new();
+    let token = teacher_token(&state, &teacher_id, &group_id);
+    let uri = format!("/stats/groups/{}/export-schedules", group_id.to_hex());
+
+    // Учитель без доступа к группе расписание не создаёт
+    let outsider = teacher_token(&state, &teacher_id, &ObjectId::new());
+    let (status, _) = send(
+        &app,
+        &outsider,
+        &csrf,
+        "POST",
+        &uri,
+        Some(json!({
+            "format": "pdf",
+            "cadence": "weekly",
+            "day_of_week": 1,
+            "recipients": ["teacher@example.com"],
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = send(
+        &app,
+        &token,
+        &csrf,
+        "POST",
+        &uri,
+        Some(json!({
+            "format": "pdf",
+            "cadence": "weekly",
+            "day_of_week": 8,
+            "recipients": ["teacher@example.com"],
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = send(
+        &app,
+        &token,
+        &csrf,
+        "POST",
+        &uri,
+        Some(json!({
+            "format": "pdf",
+            "cadence": "weekly",
+            "day_of_week": 1,
+            "recipients": ["not-an-email"],
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, created) = send(
+        &app,
+        &token,
+        &csrf,
+        "POST",
+        &uri,
+        Some(json!({
+            "format": "xlsx",
+            "cadence": "monthly",
+            "day_of_week": 1,
+            "recipients": ["Teacher@Example.com", "teacher@example.com"],
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(created["recipients"], json!(["teacher@example.com"]));
+    let schedule_id = created["schedule_id"].as_str().unwrap().to_string();
+
+    // Чужое расписание учителю не видно и не удаляется
+    let other_token = teacher_token(&state, &ObjectId::new(), &group_id);
+    let (status, listed) = send(&app, &other_token, &csrf, "GET", &uri, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(listed, json!([]));
+    let delete_uri = format!("{}/{}", uri, schedule_id);
+    let (status, _) = send(&app, &other_token, &csrf, "DELETE", &delete_uri, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, listed) = send(&app, &token, &csrf, "GET", &uri, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+
+    let (status, _) = send(&app, &token, &csrf, "DELETE", &delete_uri, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (_, listed) = send(&app, &token, &csrf, "GET", &uri, None).await;
+    assert_eq!(listed, json!([]));
+}
+
+#[tokio::test]
+async fn test_due_schedule_enqueues_export_once_per_period() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+    let csrf = get_csrf_token(&app).await;
+    let teacher_id = ObjectId::new();
+    let group_id = ObjectId::new();
+    let token = teacher_token(&state, &teacher_id, &group_id);
+
+    let today = Utc::now().weekday().number_from_monday();
+    let (status, created) = send(
+        &app,
+        &token,
+        &csrf,
+        "POST",
+        &format!("/stats/groups/{}/export-schedules", group_id.to_hex()),
+        Some(json!({
+            "format": "pdf",
+            "cadence": "weekly",
+            "day_of_week": today,
+            "recipients": ["curator@example.com"],
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert!(created["last_run_at"].is_null());
+    let schedule_id = ObjectId::parse_str(created["schedule_id"].as_str().unwrap()).unwrap();
+
+    let worker = ExportScheduleWorker::new(
+        ReportingService::new(state.mongo.clone(), state.redis.clone()),
+        None,
+        state.config.clone(),
+    );
+    assert!(worker.tick(Utc::now()).await.unwrap() >= 1);
+
+    let schedules = state
+        .mongo
+        .collection::<Document>("report_export_schedules");
+    let stored = schedules
+        .find_one(doc! { "_id": schedule_id })
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(stored.get_datetime("lastRunAt").is_ok());
+    let export_id = stored.get_object_id("lastExportId").unwrap();
+
+    let exports = state.mongo.collection::<Document>("report_exports");
+    let export = exports
+        .find_one(doc! { "_id": export_id })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(export.get_str("status").unwrap(), "pending");
+    assert_eq!(export.get_object_id("group_id").unwrap(), group_id);
+    assert_eq!(export.get_object_id("teacher_id").unwrap(), teacher_id);
+
+    // Повторный проход в том же периоде (например, после перезапуска) выгрузку не дублирует
+    worker.tick(Utc::now()).await.unwrap();
+    let count = exports
+        .count_documents(doc! { "group_id": group_id })
+        .await
+        .unwrap();
+    assert_eq!(count, 1);
+}
@@ -0,0 +1,192 @@
+mod common;
+
+use std::sync::{
+    atomic::{AtomicU32, Ordering},
+    Arc, Mutex,
+};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use mongodb::bson::{doc, oid::ObjectId, Document};
+use trainingground_api::{
+    models::reporting::{
+        ExportFormat, ExportLocale, ExportScope, ExportStatus, NewReportExport, ReportExport,
+        ReportFilters, TimeRange,
+    },
+    services::{
+        export_worker::{ExportStorage, ExportWorker},
+        reporting_service::ReportingService,
+        AppState,
+    },
+};
+
+/// Хранилище, которое отклоняет первые `failures` загрузок выбранной выгрузки
+struct FlakyStorage {
+    export_id: String,
+    failures: AtomicU32,
+    uploaded: Mutex<Vec<String>>,
+}
+
+impl FlakyStorage {
+    fn new(export_id: &ObjectId, failures: u32) -> Arc<Self> {
+        Arc::new(Self {
+            export_id: export_id.to_hex(),
+            failures: AtomicU32::new(failures),
+            uploaded: Mutex::new(Vec::new()),
+        })
+    }
+}
+
+#[async_trait]
+impl ExportStorage for FlakyStorage {
+    fn export_key(
+        &self,
+        scope: ExportScope,
+        owner_id: &str,
+        export_id: &str,
+        extension: &str,
+    ) -> String {
+        format!(
+            "{}/{}/export-{}.{}",
+            scope.storage_prefix(),
+            owner_id,
+            export_id,
+            extension
+        )
+    }
+
+    async fn upload_export(
+        &self,
+        key: &str,
+        _bytes: Vec<u8>,
+        _content_type: &str,
+    ) -> anyhow::Result<()> {
+        if key.contains(&self.export_id)
+            && self
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| {
+                    left.checked_sub(1)
+                })
+                .is_ok()
+        {
+            return Err(anyhow!("storage unavailable"));
+        }
+        self.uploaded.lock().unwrap().push(key.to_string());
+        Ok(())
+    }
+}
+
+async fn seed_export(state: &AppState) -> ReportExport {
+    let now = Utc::now();
+    ReportingService::new(state.mongo.clone(), state.redis.clone())
+        .create_export_request(NewReportExport {
+            scope: ExportScope::Group,
+            subject_id: ObjectId::new(),
+            teacher_id: ObjectId::new(),
+            format: ExportFormat::Csv,
+            filters: ReportFilters {
+                topic_ids: Vec::new(),
+                period: TimeRange {
+                    from: now - Duration::days(7),
+                    to: now,
+                },
+                fields: Vec::new(),
+                reveal_user_ids: false,
+                locale: ExportLocale::default(),
+            },
+            expires_at: now + Duration::hours(24),
+        })
+        .await
+        .unwrap()
+}
+
+fn worker(state: &AppState, storage: Arc<FlakyStorage>, max_attempts: u32) -> ExportWorker {
+    let mut config = state.config.clone();
+    // Без задержки повтор доступен уже на следующем тике; большой батч забирает
+    // и выгрузки, оставшиеся в базе от других тестов
+    config.reporting.export_retry_base_secs = 0;
+    config.reporting.export_max_attempts = max_attempts;
+    config.reporting.export_concurrency = 1000;
+    ExportWorker::new(
+        ReportingService::new(state.mongo.clone(), state.redis.clone()),
+        storage,
+        config,
+    )
+}
+
+async fn load(state: &AppState, export_id: &ObjectId) -> ReportExport {
+    ReportingService::new(state.mongo.clone(), state.redis.clone())
+        .get_export_by_id(export_id)
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+async fn next_attempt_at_is_set(state: &AppState, export_id: &ObjectId) -> bool {
+    state
+        .mongo
+        .collection::<Document>("report_exports")
+        .find_one(doc! { "_id": export_id })
+        .await
+        .unwrap()
+        .unwrap()
+        .get_datetime("nextAttemptAt")
+        .is_ok()
+}
+
+#[tokio::test]
+async fn test_export_retries_after_upload_failures() {
+    let state = common::create_test_state().await;
+    let export = seed_export(&state).await;
+    let storage = FlakyStorage::new(&export.id, 2);
+    let worker = worker(&state, storage.clone(), 5);
+
+    for attempt in 1..=2 {
+        let summary = worker.process_pending().await.unwrap();
+        assert!(summary.retried >= 1);
+
+        let stored = load(&state, &export.id).await;
+        assert_eq!(stored.status, ExportStatus::Pending);
+        assert_eq!(stored.attempts, attempt);
+        assert!(stored
+            .last_error
+            .as_deref()
+            .unwrap()
+            .contains("storage unavailable"));
+        assert!(next_attempt_at_is_set(&state, &export.id).await);
+    }
+
+    let summary = worker.process_pending().await.unwrap();
+    assert!(summary.completed >= 1);
+
+    let ready = load(&state, &export.id).await;
+    assert_eq!(ready.status, ExportStatus::Ready);
+    assert_eq!(ready.attempts, 3);
+    assert!(ready.completed_at.is_some());
+    assert!(!next_attempt_at_is_set(&state, &export.id).await);
+    let key = ready.storage_key.unwrap();
+    assert!(storage.uploaded.lock().unwrap().contains(&key));
+}
+
+#[tokio::test]
+async fn test_export_marked_failed_after_max_attempts() {
+    let state = common::create_test_state().await;
+    let export = seed_export(&state).await;
+    let storage = FlakyStorage::new(&export.id, u32::MAX);
+    let worker = worker(&state, storage, 2);
+
+    worker.process_pending().await.unwrap();
+    let summary = worker.process_pending().await.unwrap();
+    assert!(summary.failed >= 1);
+
+    let failed = load(&state, &export.id).await;
+    assert_eq!(failed.status, ExportStatus::Failed);
+    assert_eq!(failed.attempts, 2);
+    assert!(failed.error.unwrap().contains("storage unavailable"));
+    assert!(failed.completed_at.is_some());
+
+    // Исчерпанная выгрузка больше не берётся в работу
+    worker.process_pending().await.unwrap();
+    assert_eq!(load(&state, &export.id).await.attempts, 2);
+}
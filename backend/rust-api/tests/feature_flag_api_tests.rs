@@ -0,0 +1,511 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use mongodb::bson::{doc, Document};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use trainingground_api::{
+    config::Config,
+    middlewares::auth::{JwtClaims, JwtService},
+    services::feature_flag_service::invalidate_flag_cache,
+};
+
+mod common;
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_user_feature_flags_apply_targeting() {
+    let app = common::create_test_app().await;
+    std::env::set_var("RATE_LIMIT_DISABLED", "1");
+    let (token, user_id) = register_student(&app).await;
+
+    let teachers_only = unique_flag_key("teachers_only");
+    let for_this_user = unique_flag_key("for_this_user");
+    let disabled = unique_flag_key("disabled");
+    insert_flag(doc! {
+        "flag_key": &teachers_only,
+        "enabled": true,
+        "enabled_for_roles": ["teacher"],
+        "rollout_percentage": 0,
+    })
+    .await;
+    insert_flag(doc! {
+        "flag_key": &for_this_user,
+        "enabled": true,
+        "enabled_for_user_ids": [&user_id],
+        "rollout_percentage": 0,
+    })
+    .await;
+    insert_flag(doc! {
+        "flag_key": &disabled,
+        "enabled": false,
+    })
+    .await;
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/api/v1/feature-flags")
+                .header("authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = json_from_response(response).await;
+    assert_eq!(body["flags"][&teachers_only], false);
+    assert_eq!(body["flags"][&for_this_user], true);
+    assert_eq!(body["flags"][&disabled], false);
+
+    delete_flags(&[&teachers_only, &for_this_user, &disabled]).await;
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_user_feature_flags_require_auth() {
+    let app = common::create_test_app().await;
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/api/v1/feature-flags")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_admin_update_validates_targeting() {
+    let app = common::create_test_app().await;
+    std::env::set_var("RATE_LIMIT_DISABLED", "1");
+    let (admin_token, _) = create_admin_with_token(&app).await;
+    let (csrf_token, csrf_cookie) = get_csrf_token(&app).await;
+
+    let flag_key = unique_flag_key("rollout");
+    insert_flag(doc! { "flag_key": &flag_key, "enabled": false }).await;
+
+    let put = |payload: Value| {
+        Request::builder()
+            .method("PUT")
+            .uri(format!("/admin/feature-flags/{}", flag_key))
+            .header("authorization", format!("Bearer {}", admin_token))
+            .header("content-type", "application/json")
+            .header("x-csrf-token", &csrf_token)
+            .header("cookie", format!("csrf_token={}", csrf_cookie))
+            .body(Body::from(payload.to_string()))
+            .unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(put(json!({
+            "flag_key": &flag_key,
+            "enabled": true,
+            "scope": "global",
+            "rollout_percentage": 150,
+            "enabled_for_roles": ["superuser"],
+        })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = json_from_response(response).await;
+    assert!(body["details"]["rollout_percentage"].is_array());
+    assert!(body["details"]["enabled_for_roles"].is_array());
+
+    let response = app
+        .clone()
+        .oneshot(put(json!({
+            "flag_key": &flag_key,
+            "enabled": true,
+            "scope": "global",
+            "rollout_percentage": 25,
+            "enabled_for_roles": ["teacher"],
+            "change_reason": "Teachers first",
+        })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let stored = collection("feature_flags")
+        .await
+        .find_one(doc! { "flag_key": &flag_key })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored.get_i32("rollout_percentage").unwrap(), 25);
+    assert_eq!(stored.get_i32("version").unwrap(), 2);
+    assert!(stored.get_datetime("updated_at").is_ok());
+
+    delete_flags(&[&flag_key]).await;
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_flag_history_records_each_flip_in_order() {
+    let app = common::create_test_app().await;
+    std::env::set_var("RATE_LIMIT_DISABLED", "1");
+    let (admin_token, admin_id) = create_admin_with_token(&app).await;
+    let (csrf_token, csrf_cookie) = get_csrf_token(&app).await;
+
+    let flag_key = unique_flag_key("history");
+    insert_flag(doc! { "flag_key": &flag_key, "enabled": false }).await;
+
+    for (enabled, reason) in [(true, "Turn on"), (false, "Roll back")] {
+        let payload = json!({
+            "flag_key": &flag_key,
+            "enabled": enabled,
+            "scope": "global",
+            "change_reason": reason,
+        });
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri(format!("/admin/feature-flags/{}", flag_key))
+                    .header("authorization", format!("Bearer {}", admin_token))
+                    .header("content-type", "application/json")
+                    .header("x-csrf-token", &csrf_token)
+                    .header("cookie", format!("csrf_token={}", csrf_cookie))
+                    .body(Body::from(payload.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(format!(
+                    "/admin/feature-flags/{}/history?limit=10",
+                    flag_key
+                ))
+                .header("authorization", format!("Bearer {}", admin_token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = json_from_response(response).await;
+    assert_eq!(body["total"], 2);
+    let entries = body["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+
+    // Newest first
+    assert_eq!(entries[0]["version"], 3);
+    assert_eq!(entries[0]["change_reason"], "Roll back");
+    assert_eq!(entries[0]["old"]["enabled"], true);
+    assert_eq!(entries[0]["new"]["enabled"], false);
+    assert_eq!(entries[0]["actor_id"], admin_id.as_str());
+
+    assert_eq!(entries[1]["version"], 2);
+    assert_eq!(entries[1]["old"]["enabled"], false);
+    assert_eq!(entries[1]["old"]["version"], 1);
+    assert_eq!(entries[1]["new"]["enabled"], true);
+    assert_eq!(entries[1]["new"]["version"], 2);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(format!(
+                    "/admin/feature-flags/{}/history?limit=1&offset=1",
+                    flag_key
+                ))
+                .header("authorization", format!("Bearer {}", admin_token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = json_from_response(response).await;
+    assert_eq!(body["total"], 2);
+    assert_eq!(body["entries"][0]["version"], 2);
+
+    delete_flags(&[&flag_key]).await;
+    collection("feature_flag_history")
+        .await
+        .delete_many(doc! { "flag_key": &flag_key })
+        .await
+        .unwrap();
+}
+
+/// Подсказки закрываются флагом `hints_enabled` для владельца сессии, а правка
+/// флага через админку сбрасывает закэшированное определение
+#[tokio::test]
+#[serial_test::serial]
+async fn test_hints_are_gated_by_flag_and_update_drops_cache() {
+    let app = common::create_test_app().await;
+    std::env::set_var("RATE_LIMIT_DISABLED", "1");
+    let (admin_token, _) = create_admin_with_token(&app).await;
+    let (csrf_token, csrf_cookie) = get_csrf_token(&app).await;
+    let flags = collection("feature_flags").await;
+    let saved = flags
+        .find_one_and_delete(doc! { "flag_key": "hints_enabled" })
+        .await
+        .unwrap();
+    insert_flag(doc! {
+        "flag_key": "hints_enabled",
+        "enabled": true,
+        "enabled_for_roles": ["teacher"],
+        "rollout_percentage": 0,
+    })
+    .await;
+
+    let user_id = format!("flags-hint-user-{}", uuid::Uuid::new_v4());
+    let config = Config::load().unwrap();
+    let now = chrono::Utc::now().timestamp() as usize;
+    let student_token = JwtService::from_config(&config)
+        .generate_token(JwtClaims {
+            sub: user_id.clone(),
+            role: "student".to_string(),
+            group_ids: Vec::new(),
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap();
+    let post = |uri: String, token: Option<&str>, payload: Value| {
+        let mut request = Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("content-type", "application/json")
+            .header("x-csrf-token", &csrf_token)
+            .header("cookie", format!("csrf_token={}", csrf_cookie));
+        if let Some(token) = token {
+            request = request.header("authorization", format!("Bearer {}", token));
+        }
+        request.body(Body::from(payload.to_string())).unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(post(
+            "/api/v1/sessions".to_string(),
+            Some(&student_token),
+            json!({ "user_id": &user_id, "task_id": "test-task", "group_id": null }),
+        ))
+        .await
+        .unwrap();
+    let session = json_from_response(response).await;
+    let hints_uri = format!(
+        "/api/v1/sessions/{}/hints",
+        session["session_id"].as_str().unwrap()
+    );
+
+    let response = app
+        .clone()
+        .oneshot(post(
+            hints_uri.clone(),
+            None,
+            json!({ "idempotency_key": null }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(json_from_response(response).await["code"], "HINTS_DISABLED");
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("PUT")
+                .uri("/admin/feature-flags/hints_enabled")
+                .header("authorization", format!("Bearer {}", admin_token))
+                .header("content-type", "application/json")
+                .header("x-csrf-token", &csrf_token)
+                .header("cookie", format!("csrf_token={}", csrf_cookie))
+                .body(Body::from(
+                    json!({
+                        "flag_key": "hints_enabled",
+                        "enabled": true,
+                        "scope": "global",
+                        "enabled_for_roles": ["teacher"],
+                        "enabled_for_user_ids": [&user_id],
+                        "rollout_percentage": 0,
+                        "change_reason": "Early access",
+                    })
+                    .to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .clone()
+        .oneshot(post(hints_uri, None, json!({ "idempotency_key": null })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let state = common::create_test_state().await;
+    flags
+        .delete_many(doc! { "flag_key": "hints_enabled" })
+        .await
+        .unwrap();
+    if let Some(saved) = saved {
+        flags.insert_one(saved).await.unwrap();
+    }
+    invalidate_flag_cache(&state.redis, "hints_enabled").await;
+}
+
+fn unique_flag_key(prefix: &str) -> String {
+    format!("test_{}_{}", prefix, uuid::Uuid::new_v4().simple())
+}
+
+async fn collection(name: &str) -> mongodb::Collection<Document> {
+    let config = Config::load().unwrap();
+    let mongo_client = mongodb::Client::with_uri_str(&config.mongo_uri)
+        .await
+        .unwrap();
+    mongo_client
+        .database(&config.mongo_database)
+        .collection::<Document>(name)
+}
+
+async fn insert_flag(mut flag: Document) {
+    flag.insert("description", "Targeting test");
+    flag.insert("version", 1);
+    flag.insert("updated_at", mongodb::bson::DateTime::now());
+    if !flag.contains_key("scope") {
+        flag.insert("scope", "global");
+    }
+    collection("feature_flags")
+        .await
+        .insert_one(flag)
+        .await
+        .unwrap();
+}
+
+async fn delete_flags(flag_keys: &[&str]) {
+    collection("feature_flags")
+        .await
+        .delete_many(doc! { "flag_key": { "$in": flag_keys } })
+        .await
+        .unwrap();
+}
+
+async fn json_from_response(response: axum::response::Response) -> Value {
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+async fn register(app: &axum::Router, email: &str, password: &str) -> Value {
+    let body = json!({
+        "email": email,
+        "password": password,
+        "name": "Feature Flag User",
+    });
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/auth/register")
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    json_from_response(response).await
+}
+
+async fn register_student(app: &axum::Router) -> (String, String) {
+    let email = format!("flags-student-{}@test.com", uuid::Uuid::new_v4());
+    let json = register(app, &email, "Student123!@#").await;
+    (
+        json["access_token"].as_str().unwrap().to_string(),
+        json["user"]["id"].as_str().unwrap().to_string(),
+    )
+}
+
+async fn get_csrf_token(app: &axum::Router) -> (String, String) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/api/v1/auth/csrf-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let csrf_cookie = response
+        .headers()
+        .get_all("set-cookie")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .find(|h| h.starts_with("csrf_token="))
+        .and_then(|pair| pair.split(';').next())
+        .and_then(|part| part.split('=').nth(1))
+        .unwrap_or("")
+        .to_string();
+
+    let json = json_from_response(response).await;
+    let csrf_token = json["csrf_token"].as_str().unwrap().to_string();
+    (csrf_token, csrf_cookie)
+}
+
+async fn create_admin_with_token(app: &axum::Router) -> (String, String) {
+    let email = format!("flags-admin-{}@test.com", uuid::Uuid::new_v4());
+    let password = "Admin123!@#";
+    let json = register(app, &email, password).await;
+    let user_id = json["user"]["id"].as_str().unwrap().to_string();
+    promote_user_to_admin(&user_id).await;
+
+    let login_body = json!({ "email": email, "password": password });
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/auth/login")
+                .header("content-type", "application/json")
+                .body(Body::from(login_body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let json = json_from_response(response).await;
+    (json["access_token"].as_str().unwrap().to_string(), user_id)
+}
+
+async fn promote_user_to_admin(user_id: &str) {
+    let config = Config::load().unwrap();
+    let mongo_client = mongodb::Client::with_uri_str(&config.mongo_uri)
+        .await
+        .unwrap();
+    mongo_client
+        .database(&config.mongo_database)
+        .collection::<Document>("users")
+        .update_one(
+            doc! { "_id": mongodb::bson::oid::ObjectId::parse_str(user_id).unwrap() },
+            doc! { "$set": { "role": "admin" } },
+        )
+        .await
+        .unwrap();
+}
@@ -0,0 +1,197 @@
+mod common;
+
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Request, StatusCode},
+    response::Response,
+};
+use chrono::{TimeZone, Utc};
+use mongodb::bson::{doc, oid::ObjectId, DateTime, Document};
+use tower::ServiceExt;
+use trainingground_api::{
+    create_router,
+    middlewares::auth::{JwtClaims, JwtService},
+    services::AppState,
+};
+use uuid::Uuid;
+
+fn teacher_jwt(state: &AppState, group_id: &ObjectId) -> String {
+    let now = Utc::now().timestamp() as usize;
+    JwtService::from_config(&state.config)
+        .generate_token(JwtClaims {
+            sub: ObjectId::new().to_hex(),
+            role: "teacher".to_string(),
+            group_ids: vec![group_id.to_hex()],
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap()
+}
+
+async fn get(app: &axum::Router, token: &str, uri: &str) -> Response {
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(uri)
+                .header("authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+}
+
+fn march(day: u32) -> DateTime {
+    DateTime::from_millis(
+        Utc.with_ymd_and_hms(2026, 3, day, 10, 0, 0)
+            .unwrap()
+            .timestamp_millis(),
+    )
+}
+
+async fn insert_student(state: &AppState, group_id: &ObjectId, name: &str) -> ObjectId {
+    let id = ObjectId::new();
+    state
+        .mongo
+        .collection::<Document>("users")
+        .insert_one(doc! {
+            "_id": id,
+            "name": name,
+            "email": format!("{}@test.local", id.to_hex()),
+            "role": "student",
+            "group_ids": [group_id.to_hex()],
+        })
+        .await
+        .unwrap();
+    id
+}
+
+/// Шаблон с одним заданием; сессии ссылаются на задание
+async fn insert_template(state: &AppState, slug: &str) -> ObjectId {
+    let now = DateTime::now();
+    let template_id = ObjectId::new();
+    let task_id = ObjectId::new();
+    state
+        .mongo
+        .collection::<Document>("templates")
+        .insert_one(doc! {
+            "_id": template_id,
+            "slug": slug,
+            "status": "published",
+            "version": 1,
+            "createdAt": now,
+            "updatedAt": now,
+        })
+        .await
+        .unwrap();
+    state
+        .mongo
+        .collection::<Document>("tasks")
+        .insert_one(doc! {
+            "_id": task_id,
+            "template_id": template_id,
+            "title": "Задание",
+            "createdAt": now,
+        })
+        .await
+        .unwrap();
+    task_id
+}
+
+async fn insert_session(
+    state: &AppState,
+    user_id: &ObjectId,
+    task_id: &ObjectId,
+    score: i32,
+    completed_at: DateTime,
+) {
+    state
+        .mongo
+        .collection::<Document>("sessions")
+        .insert_one(doc! {
+            "_id": Uuid::new_v4().to_string(),
+            "user_id": user_id.to_hex(),
+            "task_id": task_id.to_hex(),
+            "started_at": completed_at,
+            "expires_at": completed_at,
+            "completed_at": completed_at,
+            "status": "completed",
+            "hints_used": 0,
+            "score": score,
+            "mode": "normal",
+        })
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_gradebook_csv_pivots_best_scores_per_template() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+
+    let group_id = ObjectId::new();
+    // В алфавите «ё» идёт вместе с «е», а не после «я»
+    let yolkina = insert_student(&state, &group_id, "Ёлкина Анна").await;
+    let eliseeva = insert_student(&state, &group_id, "Елисеева Мария").await;
+    let suffix = Uuid::new_v4().simple().to_string();
+    let first_slug = format!("gb-a-{}", suffix);
+    let second_slug = format!("gb-b-{}", suffix);
+    let first = insert_template(&state, &first_slug).await;
+    let second = insert_template(&state, &second_slug).await;
+
+    insert_session(&state, &eliseeva, &first, 60, march(2)).await;
+    insert_session(&state, &eliseeva, &first, 85, march(3)).await;
+    insert_session(&state, &eliseeva, &second, 70, march(4)).await;
+    insert_session(&state, &yolkina, &first, 90, march(5)).await;
+    // Вне периода
+    let january = DateTime::from_millis(
+        Utc.with_ymd_and_hms(2026, 1, 10, 10, 0, 0)
+            .unwrap()
+            .timestamp_millis(),
+    );
+    insert_session(&state, &yolkina, &second, 100, january).await;
+
+    let token = teacher_jwt(&state, &group_id);
+    let uri = format!(
+        "/api/v1/teacher/groups/{}/gradebook.csv?from=2026-03-01T00:00:00Z&to=2026-03-31T23:59:59Z",
+        group_id.to_hex()
+    );
+    let response = get(&app, &token, &uri).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        "text/csv; charset=utf-8"
+    );
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let csv = String::from_utf8(bytes.to_vec()).unwrap();
+
+    let expected = format!(
+        "student_id,student_name,{a} best_score,{a} completed_at,{b} best_score,{b} completed_at\n\
+         {eliseeva},Елисеева Мария,85,2026-03-03,70,2026-03-04\n\
+         {yolkina},Ёлкина Анна,90,2026-03-05,,\n",
+        a = first_slug,
+        b = second_slug,
+        eliseeva = eliseeva.to_hex(),
+        yolkina = yolkina.to_hex(),
+    );
+    assert_eq!(csv, expected);
+
+    let uri = format!(
+        "/api/v1/teacher/groups/{}/gradebook.csv?from=2026-04-01T00:00:00Z&to=2026-03-01T00:00:00Z",
+        group_id.to_hex()
+    );
+    assert_eq!(
+        get(&app, &token, &uri).await.status(),
+        StatusCode::BAD_REQUEST
+    );
+
+    let stranger = teacher_jwt(&state, &ObjectId::new());
+    let uri = format!("/api/v1/teacher/groups/{}/gradebook.csv", group_id.to_hex());
+    assert_eq!(
+        get(&app, &stranger, &uri).await.status(),
+        StatusCode::FORBIDDEN
+    );
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use std::{sync::Arc, time::Duration};
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use serde_json::Value;
+use tower::ServiceExt;
+use trainingground_api::{config::ObjectStorageSettings, create_router};
+
+/// Пауза Redis дольше таймаута PING в проверке готовности (500 мс)
+const REDIS_PAUSE_MS: u64 = 1500;
+
+async fn probe(app: &Router, uri: &str) -> (StatusCode, Value) {
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn test_ready_waits_for_startup_to_mark_the_state() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+
+    let (status, body) = probe(&app, "/health/ready").await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["checks"]["initialized"], false);
+
+    state.mark_ready();
+    let (status, body) = probe(&app, "/health/ready").await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["checks"]["initialized"], true);
+}
+
+#[tokio::test]
+async fn test_ready_requires_object_storage_client_when_configured() {
+    let mut state = common::create_test_state().await;
+    state.config.object_storage = Some(ObjectStorageSettings {
+        bucket: "reports".to_string(),
+        region: "ru-central1".to_string(),
+        endpoint: Some("not a url".to_string()),
+        access_key: "key".to_string(),
+        secret_key: "secret".to_string(),
+        reports_prefix: "reports".to_string(),
+        presign_ttl_secs: 3600,
+        sse: None,
+        sse_kms_key_id: None,
+        max_attempts: 4,
+        retry_base_delay_ms: 200,
+        request_timeout_secs: 120,
+        multipart_threshold_bytes: 32 * 1024 * 1024,
+        multipart_part_size_bytes: 8 * 1024 * 1024,
+    });
+    state.object_storage = None;
+    state.mark_ready();
+    let app = create_router(Arc::new(state));
+
+    let (status, body) = probe(&app, "/health/ready").await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["status"], "not_ready");
+    assert_eq!(body["checks"]["initialized"], true);
+    assert_eq!(body["checks"]["object_storage_client"], false);
+
+    // Liveness от конфигурации не зависит
+    let (status, body) = probe(&app, "/health/live").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "alive");
+}
+
+#[tokio::test]
+async fn test_ready_fails_while_redis_is_unreachable_but_live_stays_up() {
+    let state = common::create_test_state().await;
+    state.mark_ready();
+    let redis_uri = state.config.redis_uri.clone();
+    let app = create_router(Arc::new(state));
+
+    let (status, body) = probe(&app, "/health/ready").await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["status"], "ready");
+    assert_eq!(body["checks"]["redis"]["status"], "healthy");
+
+    // CLIENT PAUSE замораживает все команды: для API Redis недоступен
+    let mut admin = redis::Client::open(redis_uri)
+        .unwrap()
+        .get_multiplexed_async_connection()
+        .await
+        .unwrap();
+    let _: () = redis::cmd("CLIENT")
+        .arg("PAUSE")
+        .arg(REDIS_PAUSE_MS)
+        .query_async(&mut admin)
+        .await
+        .unwrap();
+
+    let (status, body) = probe(&app, "/health/ready").await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["status"], "not_ready");
+    assert_eq!(body["checks"]["redis"]["status"], "unhealthy");
+
+    let (status, _) = probe(&app, "/health/live").await;
+    assert_eq!(status, StatusCode::OK);
+
+    tokio::time::sleep(Duration::from_millis(REDIS_PAUSE_MS)).await;
+    let (status, body) = probe(&app, "/health/ready").await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+}
@@ -0,0 +1,115 @@
+mod common;
+
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use chrono::{Duration, Utc};
+use serde_json::Value;
+use tower::ServiceExt;
+use trainingground_api::{
+    create_router,
+    services::reporting_service::{ReportingService, EXPORT_WORKER_HEARTBEAT_KEY},
+};
+
+async fn get_health(app: &Router, uri: &str) -> (StatusCode, Value) {
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+fn expected_code(body: &Value) -> StatusCode {
+    match body["status"].as_str() {
+        Some("healthy") | Some("degraded") => StatusCode::OK,
+        _ => StatusCode::SERVICE_UNAVAILABLE,
+    }
+}
+
+#[tokio::test]
+async fn test_health_reports_all_dependencies() {
+    let state = common::create_test_state().await;
+    let storage_configured = state.object_storage.is_some();
+    let app = create_router(Arc::new(state));
+
+    let (status, body) = get_health(&app, "/health").await;
+    assert_eq!(status, expected_code(&body), "{body}");
+    assert_eq!(body["service"], "trainingground-api");
+
+    let dependencies = &body["dependencies"];
+    assert_eq!(dependencies["mongodb"]["status"], "healthy");
+    assert_eq!(dependencies["redis"]["status"], "healthy");
+    assert!(dependencies["export_worker"]["status"].is_string());
+    if !storage_configured {
+        assert_eq!(dependencies["object_storage"]["status"], "not_configured");
+    }
+}
+
+#[tokio::test]
+async fn test_health_reports_active_jwt_key() {
+    let mut state = common::create_test_state().await;
+    state.config.jwt_keys = [("2024-09".to_string(), "rotated-secret".to_string())].into();
+    state.config.jwt_active_key_id = Some("2024-09".to_string());
+    let app = create_router(Arc::new(state));
+
+    let (_, body) = get_health(&app, "/health").await;
+    assert_eq!(body["jwt"]["active_kid"], "2024-09", "{body}");
+    assert!(!body.to_string().contains("rotated-secret"));
+}
+
+#[tokio::test]
+async fn test_health_non_verbose_returns_only_status() {
+    let app = common::create_test_app().await;
+
+    let (status, body) = get_health(&app, "/health?verbose=false").await;
+    assert_eq!(status, expected_code(&body));
+    let fields = body.as_object().unwrap();
+    assert_eq!(fields.len(), 1, "{body}");
+    assert!(fields.contains_key("status"));
+}
+
+#[tokio::test]
+async fn test_health_tracks_export_worker_heartbeat() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+    let reporting = ReportingService::new(state.mongo.clone(), state.redis.clone());
+
+    reporting
+        .record_export_worker_heartbeat(Utc::now())
+        .await
+        .unwrap();
+    let (_, body) = get_health(&app, "/health").await;
+    let worker = &body["dependencies"]["export_worker"];
+    assert_eq!(worker["status"], "healthy", "{body}");
+    assert!(worker["last_heartbeat"].is_string());
+
+    // Воркер пропустил несколько тиков - зависимость и общий статус деградируют,
+    // но реплика остаётся в балансировке: 503 только без Mongo или Redis
+    let interval = state.config.reporting.export_worker_interval_secs as i64;
+    reporting
+        .record_export_worker_heartbeat(Utc::now() - Duration::seconds(interval * 10))
+        .await
+        .unwrap();
+    let (status, body) = get_health(&app, "/health").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "degraded");
+    assert_eq!(body["dependencies"]["export_worker"]["status"], "degraded");
+
+    let (status, body) = get_health(&app, "/health?verbose=false").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, serde_json::json!({ "status": "degraded" }));
+
+    let mut conn = state.redis.clone();
+    let _: () = redis::cmd("DEL")
+        .arg(EXPORT_WORKER_HEARTBEAT_KEY)
+        .query_async(&mut conn)
+        .await
+        .unwrap();
+}
@@ -5,8 +5,15 @@ use axum::{
     body::Body,
     http::{Request, StatusCode},
 };
+use mongodb::bson::{doc, oid::ObjectId, Document};
 use serde_json::json;
+use std::sync::Arc;
 use tower::ServiceExt;
+use trainingground_api::{
+    config::Config,
+    create_router,
+    middlewares::auth::{JwtClaims, JwtService},
+};
 use uuid::Uuid;
 
 #[tokio::test]
@@ -24,6 +31,10 @@ async fn test_request_hint_success() {
             Request::builder()
                 .method("POST")
                 .uri("/api/v1/sessions")
+                .header(
+                    "authorization",
+                    format!("Bearer {}", student_token(&user_id)),
+                )
                 .header("content-type", "application/json")
                 .header("x-csrf-token", &csrf_token)
                 .header("cookie", format!("csrf_token={}", csrf_cookie))
@@ -106,6 +117,10 @@ async fn test_hint_limit_enforcement() {
             Request::builder()
                 .method("POST")
                 .uri("/api/v1/sessions")
+                .header(
+                    "authorization",
+                    format!("Bearer {}", student_token(&user_id)),
+                )
                 .header("content-type", "application/json")
                 .header("x-csrf-token", &csrf_token)
                 .header("cookie", format!("csrf_token={}", csrf_cookie))
@@ -198,6 +213,10 @@ async fn test_hint_cost_deduction() {
             Request::builder()
                 .method("POST")
                 .uri("/api/v1/sessions")
+                .header(
+                    "authorization",
+                    format!("Bearer {}", student_token(&user_id)),
+                )
                 .header("content-type", "application/json")
                 .header("x-csrf-token", &csrf_token)
                 .header("cookie", format!("csrf_token={}", csrf_cookie))
@@ -280,6 +299,127 @@ async fn test_hint_cost_deduction() {
     assert_eq!(new_score, score_before - 5);
 }
 
+#[tokio::test]
+async fn test_hint_budget_penalties_reduce_completed_session_score() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+    let user_id = format!("hint-budget-user-{}", Uuid::new_v4());
+    let token = student_token(&user_id);
+
+    // Задание со своим бюджетом: 2 подсказки, штрафы 10% и 30%
+    let task_id = ObjectId::new();
+    state
+        .mongo
+        .collection::<Document>("tasks")
+        .insert_one(doc! {
+            "_id": task_id,
+            "title": "Hint budget task",
+            "description": "Task with its own hint policy",
+            "correct_answer": "42",
+            "time_limit_seconds": 300,
+            "max_hints": 2,
+            "hint_penalties": [10, 30],
+        })
+        .await
+        .unwrap();
+
+    let (status, json) = post_json(
+        &app,
+        &token,
+        "/api/v1/sessions".to_string(),
+        json!({ "user_id": user_id, "task_id": task_id.to_hex(), "group_id": null }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let session_id = json["session_id"].as_str().unwrap().to_string();
+
+    let (status, json) = post_json(
+        &app,
+        &token,
+        format!("/api/v1/sessions/{}/answers", session_id),
+        json!({ "answer": "42", "idempotency_key": null }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["score_awarded"], 10);
+
+    let hint_uri = format!("/api/v1/sessions/{}/hints", session_id);
+    let (status, json) = post_json(&app, &token, hint_uri.clone(), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["hints_used"], 1);
+    assert_eq!(json["remaining_hints"], 1);
+    assert_eq!(json["penalty_percent"], 10);
+    assert_eq!(json["total_penalty_percent"], 10);
+    assert_eq!(json["next_penalty_percent"], 30);
+
+    let (status, json) = post_json(&app, &token, hint_uri.clone(), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["remaining_hints"], 0);
+    assert_eq!(json["total_penalty_percent"], 40);
+    assert!(json["next_penalty_percent"].is_null());
+
+    let (status, json) = post_json(&app, &token, hint_uri, json!({})).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(json["code"], "HINT_LIMIT_REACHED");
+    assert_eq!(json["details"]["max_hints"], 2);
+
+    let (status, _) = post_json(
+        &app,
+        &token,
+        format!("/api/v1/sessions/{}/complete", session_id),
+        json!({}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    // 10 очков минус 40% штрафа за две подсказки
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(format!("/api/v1/sessions/{}", session_id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let session: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(session["status"], "completed");
+    assert_eq!(session["hints_used"], 2);
+    assert_eq!(session["score"], 6);
+}
+
+async fn post_json(
+    app: &axum::Router,
+    token: &str,
+    uri: String,
+    body: serde_json::Value,
+) -> (StatusCode, serde_json::Value) {
+    let (csrf_token, csrf_cookie) = get_csrf_token(app).await;
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {}", token))
+                .header("x-csrf-token", &csrf_token)
+                .header("cookie", format!("csrf_token={}", csrf_cookie))
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json = serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null);
+    (status, json)
+}
+
 async fn get_csrf_token(app: &axum::Router) -> (String, String) {
     let response = app
         .clone()
@@ -311,3 +451,18 @@ async fn get_csrf_token(app: &axum::Router) -> (String, String) {
 
     (csrf_token, csrf_cookie)
 }
+
+/// Токен ученика: сессия открывается только для пользователя из токена
+fn student_token(user_id: &str) -> String {
+    let config = Config::load().expect("Failed to load test configuration");
+    let now = chrono::Utc::now().timestamp() as usize;
+    JwtService::from_config(&config)
+        .generate_token(JwtClaims {
+            sub: user_id.to_string(),
+            role: "student".to_string(),
+            group_ids: Vec::new(),
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap()
+}
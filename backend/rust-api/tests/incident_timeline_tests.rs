@@ -0,0 +1,214 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use mongodb::bson::oid::ObjectId;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use trainingground_api::{
+    create_router,
+    middlewares::auth::{JwtClaims, JwtService},
+    models::{
+        answer::AttemptRecord,
+        anticheat::ClientSignal,
+        hint::{HintRecord, HintSource},
+        system_settings::{AnticheatSettings, AnticheatSignalThresholds},
+        Session, SessionMode, SessionStatus,
+    },
+    services::{anticheat_service::AnticheatService, AppState},
+};
+use uuid::Uuid;
+
+async fn insert_session(state: &AppState, user_id: &str, started_at: DateTime<Utc>) -> Session {
+    let session = Session {
+        id: Uuid::new_v4().to_string(),
+        user_id: user_id.to_string(),
+        task_id: "timeline-task".to_string(),
+        group_id: None,
+        started_at,
+        expires_at: started_at + ChronoDuration::seconds(3600),
+        status: SessionStatus::Active,
+        hints_used: 1,
+        score: 0,
+        level_id: None,
+        mode: SessionMode::Normal,
+    };
+    redis::cmd("SETEX")
+        .arg(format!("session:{}", session.id))
+        .arg(3600)
+        .arg(serde_json::to_string(&session).unwrap())
+        .query_async::<()>(&mut state.redis.clone())
+        .await
+        .unwrap();
+    session
+}
+
+async fn insert_attempt(state: &AppState, session: &Session, at: DateTime<Utc>, score: i32) {
+    state
+        .mongo
+        .collection::<AttemptRecord>("attempt_records")
+        .insert_one(AttemptRecord {
+            id: Uuid::new_v4().to_string(),
+            session_id: session.id.clone(),
+            user_id: session.user_id.clone(),
+            task_id: session.task_id.clone(),
+            answer: "ответ".to_string(),
+            correct: score > 0,
+            score,
+            timestamp: at,
+            reason: None,
+            credit: None,
+            timing: None,
+        })
+        .await
+        .unwrap();
+}
+
+async fn get(app: &Router, uri: &str, token: &str) -> (StatusCode, Value) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(uri)
+                .header("authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or_default())
+}
+
+#[tokio::test]
+async fn timeline_orders_session_events() {
+    let state = common::create_test_state().await;
+    let user_id = ObjectId::new().to_hex();
+    // Целые секунды: время сигналов хранится с точностью до миллисекунд
+    let started_at = DateTime::from_timestamp(Utc::now().timestamp() - 600, 0).unwrap();
+    let offset = |seconds: i64| started_at + ChronoDuration::seconds(seconds);
+    let session = insert_session(&state, &user_id, started_at).await;
+
+    insert_attempt(&state, &session, offset(60), 0).await;
+    state
+        .mongo
+        .collection::<HintRecord>("hint_records")
+        .insert_one(HintRecord {
+            id: Uuid::new_v4().to_string(),
+            session_id: session.id.clone(),
+            user_id: user_id.clone(),
+            task_id: session.task_id.clone(),
+            hint_text: "Проверьте окончание".to_string(),
+            cost: 5,
+            timestamp: offset(75),
+            source: HintSource::Rule,
+        })
+        .await
+        .unwrap();
+    insert_attempt(&state, &session, offset(90), 10).await;
+
+    let signals: Vec<ClientSignal> = serde_json::from_value(json!([
+        { "type": "paste", "timestamp": offset(120).to_rfc3339(), "payload": { "length": 40 } },
+        { "type": "paste", "timestamp": offset(400).to_rfc3339(), "payload": { "length": 12 } },
+    ]))
+    .unwrap();
+    let settings = AnticheatSettings {
+        signal_thresholds: Some(AnticheatSignalThresholds {
+            tab_switch: 1,
+            paste: 1,
+            devtools: 1,
+        }),
+        auto_block: false,
+        ..AnticheatSettings::default()
+    };
+    let batch = AnticheatService::new(state.mongo.clone(), state.redis.clone())
+        .with_settings(settings)
+        .ingest_signals(
+            &session.id,
+            &user_id,
+            signals,
+            &state.config.anticheat_signals,
+        )
+        .await
+        .unwrap();
+    let incident_id = batch.incident_id.expect("signals should open an incident");
+
+    let now = Utc::now().timestamp() as usize;
+    let admin_token = JwtService::new(&state.config.jwt_secret)
+        .generate_token(JwtClaims {
+            sub: ObjectId::new().to_hex(),
+            role: "admin".to_string(),
+            group_ids: vec![],
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap();
+    let app = create_router(Arc::new(state));
+    let uri = format!(
+        "/admin/incidents/{}/timeline?inactivity_seconds=120",
+        incident_id
+    );
+
+    // Инцидент может сохраняться в фоне
+    let mut response = get(&app, &uri, &admin_token).await;
+    for _ in 0..20 {
+        if response.0 != StatusCode::NOT_FOUND {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        response = get(&app, &uri, &admin_token).await;
+    }
+    let (status, timeline) = response;
+    assert_eq!(status, StatusCode::OK, "{timeline}");
+    assert_eq!(timeline["session_id"], session.id.as_str());
+    assert_eq!(timeline["truncated"], false);
+
+    let events = timeline["events"].as_array().unwrap();
+    let types: Vec<_> = events
+        .iter()
+        .map(|event| event["type"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        types,
+        vec![
+            "session_started",
+            "answer",
+            "hint",
+            "answer",
+            "signal",
+            "inactivity",
+            "signal"
+        ]
+    );
+    let offsets: Vec<_> = events
+        .iter()
+        .map(|event| event["offset_ms"].as_i64().unwrap())
+        .collect();
+    assert_eq!(
+        offsets,
+        vec![0, 60_000, 75_000, 90_000, 120_000, 120_000, 400_000]
+    );
+    assert_eq!(events[3]["total_score"], 10);
+    assert_eq!(events[5]["duration_seconds"], 280);
+    assert_eq!(events[6]["payload"]["length"], 12);
+
+    let (status, body) = get(
+        &app,
+        &format!(
+            "/admin/incidents/{}/timeline?inactivity_seconds=0",
+            incident_id
+        ),
+        &admin_token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+}
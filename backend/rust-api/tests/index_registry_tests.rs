@@ -0,0 +1,97 @@
+mod common;
+
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use chrono::Utc;
+use futures::TryStreamExt;
+use mongodb::{bson::oid::ObjectId, IndexModel};
+use serde_json::Value;
+use tower::ServiceExt;
+use trainingground_api::{
+    create_router,
+    middlewares::auth::{JwtClaims, JwtService},
+    models::db_index::IndexState,
+    services::index_registry::{declared_indexes, ensure_indexes},
+};
+
+#[tokio::test]
+async fn declared_indexes_exist_after_startup() {
+    let state = common::create_test_state().await;
+
+    // Повторный запуск ничего не создаёт заново
+    let report = ensure_indexes(&state.mongo).await;
+    assert!(report.is_healthy(), "{:#?}", report.indexes);
+    assert!(report
+        .indexes
+        .iter()
+        .all(|index| index.state == IndexState::Present));
+
+    for spec in declared_indexes() {
+        let existing: Vec<IndexModel> = state
+            .mongo
+            .collection::<mongodb::bson::Document>(spec.collection)
+            .list_indexes()
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        let name = spec.name();
+        assert!(
+            existing.iter().any(|index| index
+                .options
+                .as_ref()
+                .and_then(|options| options.name.as_deref())
+                == Some(name.as_str())),
+            "index {}.{} is missing",
+            spec.collection,
+            name
+        );
+    }
+}
+
+#[tokio::test]
+async fn operators_can_read_index_report() {
+    let state = common::create_test_state().await;
+    let now = Utc::now().timestamp() as usize;
+    let token = JwtService::new(&state.config.jwt_secret)
+        .generate_token(JwtClaims {
+            sub: ObjectId::new().to_hex(),
+            role: "admin".to_string(),
+            group_ids: vec![],
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap();
+    let app = create_router(Arc::new(state));
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/admin/system/indexes")
+                .header("authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let report: Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(status, StatusCode::OK, "{report}");
+
+    let email = report["indexes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|index| index["collection"] == "users" && index["name"] == "email_1")
+        .expect("users.email index is declared");
+    assert_eq!(email["unique"], true);
+    assert_eq!(email["state"], "present");
+    assert!(report["drift"].is_array());
+}
@@ -0,0 +1,204 @@
+use std::time::Duration;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Request, StatusCode},
+};
+use serde_json::json;
+use tower::ServiceExt;
+use trainingground_api::config::Config;
+
+mod common;
+
+const PASSWORD: &str = "Student123!@#";
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_login_updates_profile_and_history() {
+    let app = common::create_test_app().await;
+    let email = format!("login-history-{}@test.com", uuid::Uuid::new_v4());
+    let user_id = register(&app, &email).await;
+
+    let first = login(&app, &email, "FirstBrowser/1.0").await;
+    let first_login_at = first["user"]["last_login_at"]
+        .as_str()
+        .expect("login response should carry last_login_at")
+        .to_string();
+
+    // lastLoginAt хранится с точностью до миллисекунды
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    let second = login(&app, &email, "SecondBrowser/2.0").await;
+    let token = second["access_token"].as_str().unwrap().to_string();
+    let second_login_at = second["user"]["last_login_at"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    assert!(
+        parse_time(&second_login_at) > parse_time(&first_login_at),
+        "{second_login_at} should be after {first_login_at}"
+    );
+
+    let (status, profile) = get_json(&app, "/api/v1/auth/me", &token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        parse_time(profile["last_login_at"].as_str().unwrap()),
+        parse_time(&second_login_at)
+    );
+
+    let admin_token = admin_token(&app).await;
+    let (status, logins) = get_json(
+        &app,
+        &format!("/admin/users/{}/logins", user_id),
+        &admin_token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{logins:?}");
+    let logins = logins.as_array().unwrap();
+    assert_eq!(logins.len(), 2);
+    assert_eq!(logins[0]["user_agent"], "SecondBrowser/2.0");
+    assert_eq!(logins[1]["user_agent"], "FirstBrowser/1.0");
+    assert!(logins.iter().all(|entry| entry["method"] == "password"));
+
+    let (status, admin_view) =
+        get_json(&app, &format!("/admin/users/{}", user_id), &admin_token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        parse_time(admin_view["last_login_at"].as_str().unwrap()),
+        parse_time(&second_login_at)
+    );
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_login_history_paginates_with_cursor() {
+    let app = common::create_test_app().await;
+    let email = format!("login-history-{}@test.com", uuid::Uuid::new_v4());
+    let user_id = register(&app, &email).await;
+    for attempt in 0..3 {
+        login(&app, &email, &format!("Browser/{attempt}")).await;
+    }
+
+    let admin_token = admin_token(&app).await;
+    let response = get(
+        &app,
+        &format!("/admin/users/{}/logins?limit=2", user_id),
+        &admin_token,
+    )
+    .await;
+    let cursor = response
+        .headers()
+        .get("x-next-cursor")
+        .expect("first page should have a cursor")
+        .to_str()
+        .unwrap()
+        .to_string();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let first_page: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(first_page.as_array().unwrap().len(), 2);
+    assert_eq!(first_page[0]["user_agent"], "Browser/2");
+
+    let response = get(
+        &app,
+        &format!("/admin/users/{}/logins?limit=2&cursor={}", user_id, cursor),
+        &admin_token,
+    )
+    .await;
+    assert!(response.headers().get("x-next-cursor").is_none());
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let second_page: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(second_page.as_array().unwrap().len(), 1);
+    assert_eq!(second_page[0]["user_agent"], "Browser/0");
+}
+
+async fn register(app: &axum::Router, email: &str) -> String {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/auth/register")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    json!({ "email": email, "password": PASSWORD, "name": "Login History" })
+                        .to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    json["user"]["id"].as_str().unwrap().to_string()
+}
+
+async fn login(app: &axum::Router, email: &str, user_agent: &str) -> serde_json::Value {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/auth/login")
+                .header("content-type", "application/json")
+                .header(header::USER_AGENT, user_agent)
+                .body(Body::from(
+                    json!({ "email": email, "password": PASSWORD }).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+async fn admin_token(app: &axum::Router) -> String {
+    let email = format!("login-history-admin-{}@test.com", uuid::Uuid::new_v4());
+    let user_id = register(app, &email).await;
+    promote_user_to_admin(&user_id).await;
+    login(app, &email, "AdminBrowser/1.0").await["access_token"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+async fn promote_user_to_admin(user_id: &str) {
+    let config = Config::load().unwrap();
+    let mongo_client = mongodb::Client::with_uri_str(&config.mongo_uri)
+        .await
+        .unwrap();
+    mongo_client
+        .database(&config.mongo_database)
+        .collection::<mongodb::bson::Document>("users")
+        .update_one(
+            mongodb::bson::doc! { "_id": mongodb::bson::oid::ObjectId::parse_str(user_id).unwrap() },
+            mongodb::bson::doc! { "$set": { "role": "admin" } },
+        )
+        .await
+        .unwrap();
+}
+
+async fn get(app: &axum::Router, uri: &str, token: &str) -> axum::response::Response {
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(uri)
+                .header("authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+}
+
+async fn get_json(app: &axum::Router, uri: &str, token: &str) -> (StatusCode, serde_json::Value) {
+    let response = get(app, uri, token).await;
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or_default())
+}
+
+fn parse_time(value: &str) -> chrono::DateTime<chrono::Utc> {
+    value.parse().unwrap()
+}
@@ -0,0 +1,154 @@
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Request, StatusCode},
+};
+use chrono::Utc;
+use mongodb::bson::oid::ObjectId;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use trainingground_api::{
+    create_router,
+    middlewares::auth::{JwtClaims, JwtService},
+    services::{maintenance_mode::MAINTENANCE_MODE_KEY, AppState},
+};
+use uuid::Uuid;
+
+mod common;
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_maintenance_mode_blocks_students_but_not_admins() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+    clear_mode(&state).await;
+    let admin = jwt(&state, "admin");
+    let student = jwt(&state, "student");
+
+    let (status, body) = post_mode(
+        &app,
+        &admin,
+        json!({ "enabled": true, "message": "Migrating, back soon", "retry_after_seconds": 120 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["allow_roles"], json!(["admin"]));
+
+    let response = get(&app, Some(&student), "/api/v1/notifications").await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.headers()[header::RETRY_AFTER], "120");
+    let body = json_body(response).await;
+    assert_eq!(body["code"], "MAINTENANCE");
+    assert_eq!(body["message"], "Migrating, back soon");
+
+    // Без токена закрыто всё, кроме проб и входа
+    let response = get(&app, None, "/api/feature-flags").await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let response = get(&app, None, "/health").await;
+    assert_ne!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(json_body(response).await["maintenance"], true);
+
+    let response = get(&app, Some(&admin), "/admin/system/maintenance").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(json_body(response).await["enabled"], true);
+
+    let (status, body) = post_mode(&app, &admin, json!({ "enabled": false })).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let response = get(&app, Some(&student), "/api/v1/notifications").await;
+    assert_ne!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let response = get(&app, None, "/health").await;
+    assert_eq!(json_body(response).await["maintenance"], false);
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_maintenance_mode_rejects_self_lockout() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+    clear_mode(&state).await;
+    let admin = jwt(&state, "admin");
+
+    let (status, body) = post_mode(
+        &app,
+        &admin,
+        json!({ "enabled": true, "allow_roles": ["teacher"] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+    assert_eq!(body["code"], "MAINTENANCE_LOCKOUT");
+
+    let (status, body) = post_mode(
+        &app,
+        &admin,
+        json!({ "enabled": true, "retry_after_seconds": 0 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+
+    // Ни один отклонённый запрос режим не включил
+    let teacher = jwt(&state, "teacher");
+    let response = get(&app, Some(&teacher), "/api/v1/notifications").await;
+    assert_ne!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+}
+
+async fn clear_mode(state: &AppState) {
+    let mut conn = state.redis.clone();
+    let _: () = redis::cmd("DEL")
+        .arg(MAINTENANCE_MODE_KEY)
+        .query_async(&mut conn)
+        .await
+        .unwrap();
+}
+
+fn jwt(state: &AppState, role: &str) -> String {
+    let now = Utc::now().timestamp() as usize;
+    JwtService::from_config(&state.config)
+        .generate_token(JwtClaims {
+            sub: ObjectId::new().to_hex(),
+            role: role.to_string(),
+            group_ids: Vec::new(),
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap()
+}
+
+async fn get(app: &axum::Router, token: Option<&str>, uri: &str) -> axum::response::Response {
+    let mut request = Request::builder().method("GET").uri(uri);
+    if let Some(token) = token {
+        request = request.header("authorization", format!("Bearer {}", token));
+    }
+    app.clone()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+}
+
+async fn post_mode(app: &axum::Router, token: &str, body: Value) -> (StatusCode, Value) {
+    let csrf_token = Uuid::new_v4().to_string();
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/admin/system/maintenance")
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {}", token))
+                .header("x-csrf-token", &csrf_token)
+                .header("cookie", format!("csrf_token={}", csrf_token))
+                .header("x-request-nonce", Uuid::new_v4().to_string())
+                .header("x-request-timestamp", Utc::now().timestamp().to_string())
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    (status, json_body(response).await)
+}
+
+async fn json_body(response: axum::response::Response) -> Value {
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap_or_default()
+}
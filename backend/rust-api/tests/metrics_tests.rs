@@ -0,0 +1,252 @@
+mod common;
+
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    response::Response,
+    Router,
+};
+use base64::{engine::general_purpose, Engine};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use trainingground_api::{
+    config::MetricsSettings,
+    create_router,
+    middlewares::auth::{JwtClaims, JwtService},
+    services::AppState,
+};
+use uuid::Uuid;
+
+const METRICS_USER: &str = "prometheus";
+const METRICS_PASSWORD: &str = "scrape-secret";
+
+/// Приложение с известной учёткой `/metrics`; пароль можно задать bcrypt-хешем
+async fn create_metrics_app(stored_password: String) -> Router {
+    create_router(create_metrics_state(stored_password).await)
+}
+
+async fn create_metrics_state(stored_password: String) -> Arc<AppState> {
+    let mut state = common::create_test_state().await;
+    state.config.metrics = MetricsSettings {
+        username: METRICS_USER.to_string(),
+        password: stored_password,
+    };
+    Arc::new(state)
+}
+
+fn student_jwt(state: &AppState, user_id: &str) -> String {
+    let now = chrono::Utc::now().timestamp() as usize;
+    JwtService::from_config(&state.config)
+        .generate_token(JwtClaims {
+            sub: user_id.to_string(),
+            role: "student".to_string(),
+            group_ids: Vec::new(),
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap()
+}
+
+fn basic_auth(username: &str, password: &str) -> String {
+    let credentials = format!("{}:{}", username, password);
+    format!("Basic {}", general_purpose::STANDARD.encode(credentials))
+}
+
+async fn get_metrics(app: &Router, authorization: Option<String>) -> Response {
+    let mut request = Request::builder().method("GET").uri("/metrics");
+    if let Some(authorization) = authorization {
+        request = request.header("authorization", authorization);
+    }
+    app.clone()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+}
+
+async fn scrape(app: &Router) -> String {
+    let response = get_metrics(app, Some(basic_auth(METRICS_USER, METRICS_PASSWORD))).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+/// Значение серии из текстового формата Prometheus; отсутствующая серия - ноль
+fn metric_value(text: &str, series: &str) -> f64 {
+    text.lines()
+        .filter(|line| !line.starts_with('#'))
+        .find_map(|line| {
+            let (name, value) = line.rsplit_once(' ')?;
+            (name == series).then(|| value.parse().ok())?
+        })
+        .unwrap_or(0.0)
+}
+
+async fn get_csrf_token(app: &Router) -> (String, String) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/api/v1/auth/csrf-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let csrf_cookie = response
+        .headers()
+        .get_all("set-cookie")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .find(|s| s.starts_with("csrf_token="))
+        .and_then(|s| s.split(';').next())
+        .and_then(|part| part.split('=').nth(1))
+        .unwrap_or_default()
+        .to_string();
+
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    (
+        json["csrf_token"].as_str().unwrap().to_string(),
+        csrf_cookie,
+    )
+}
+
+#[tokio::test]
+async fn test_metrics_rejects_missing_header() {
+    let app = create_metrics_app(METRICS_PASSWORD.to_string()).await;
+    let response = get_metrics(&app, None).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = get_metrics(&app, Some("Bearer token".to_string())).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_metrics_rejects_wrong_credentials() {
+    let app = create_metrics_app(METRICS_PASSWORD.to_string()).await;
+    let response = get_metrics(&app, Some(basic_auth(METRICS_USER, "wrong"))).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = get_metrics(&app, Some(basic_auth("admin", METRICS_PASSWORD))).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // Старая учётка по умолчанию больше не подходит
+    let response = get_metrics(&app, Some(basic_auth("admin", "changeme"))).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_metrics_accepts_correct_credentials() {
+    let app = create_metrics_app(METRICS_PASSWORD.to_string()).await;
+    let response = get_metrics(&app, Some(basic_auth(METRICS_USER, METRICS_PASSWORD))).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_metrics_accepts_bcrypt_hashed_password() {
+    let hash = bcrypt::hash(METRICS_PASSWORD, 4).unwrap();
+    let app = create_metrics_app(hash.clone()).await;
+
+    let response = get_metrics(&app, Some(basic_auth(METRICS_USER, METRICS_PASSWORD))).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = get_metrics(&app, Some(basic_auth(METRICS_USER, &hash))).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_session_flow_increments_business_metrics() {
+    std::env::set_var("RATE_LIMIT_DISABLED", "1");
+    let state = create_metrics_state(METRICS_PASSWORD.to_string()).await;
+    let app = create_router(state.clone());
+    let user_id = format!("metrics-user-{}", Uuid::new_v4());
+    let token = student_jwt(&state, &user_id);
+    let (csrf_token, csrf_cookie) = get_csrf_token(&app).await;
+    let post = |uri: String, body: Value| {
+        Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("content-type", "application/json")
+            .header("authorization", format!("Bearer {}", token))
+            .header("x-csrf-token", &csrf_token)
+            .header("cookie", format!("csrf_token={}", csrf_cookie))
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let read_json = |response: Response| async move {
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice::<Value>(&bytes).unwrap()
+    };
+
+    let before = scrape(&app).await;
+
+    let response = app
+        .clone()
+        .oneshot(post(
+            "/api/v1/sessions".to_string(),
+            json!({
+                "user_id": user_id,
+                "task_id": "test-task",
+            }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let session_id = read_json(response).await["session_id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let response = app
+        .clone()
+        .oneshot(post(
+            format!("/api/v1/sessions/{}/hints", session_id),
+            json!({ "idempotency_key": null }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let provider = read_json(response).await["provider"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let response = app
+        .clone()
+        .oneshot(post(
+            format!("/api/v1/sessions/{}/answers", session_id),
+            json!({ "answer": "42", "idempotency_key": format!("{}:metrics", session_id) }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .clone()
+        .oneshot(post(
+            format!("/api/v1/sessions/{}/complete", session_id),
+            json!({}),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let after = scrape(&app).await;
+    let delta = |series: &str| metric_value(&after, series) - metric_value(&before, series);
+
+    assert!(delta(r#"sessions_total{status="started"}"#) >= 1.0);
+    assert!(delta(r#"sessions_total{status="completed"}"#) >= 1.0);
+    assert!(delta(r#"answers_submitted_total{correct="true"}"#) >= 1.0);
+    assert!(
+        delta(&format!(
+            r#"hints_requested_total{{provider="{}"}}"#,
+            provider
+        )) >= 1.0
+    );
+    // Сессия создана и завершена - число активных вернулось к исходному
+    assert_eq!(delta("active_sessions"), 0.0);
+}
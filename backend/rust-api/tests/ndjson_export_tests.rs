@@ -0,0 +1,467 @@
+mod common;
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use chrono::{Duration, Utc};
+use mongodb::bson::{doc, oid::ObjectId, Document};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use trainingground_api::{
+    create_router,
+    middlewares::auth::{JwtClaims, JwtService},
+    models::{
+        answer::AttemptRecord,
+        reporting::{
+            ExportFormat, ExportLocale, ExportScope, ExportStatus, NewReportExport, ReportFilters,
+            TimeRange,
+        },
+    },
+    services::{
+        export_worker::{pseudonymize_user_id, ExportStorage, ExportWorker},
+        reporting_service::ReportingService,
+        AppState,
+    },
+};
+
+const PART_SIZE: usize = 64 * 1024;
+const ANSWERS: usize = 3000;
+
+/// Хранилище в памяти с составной загрузкой: запоминает размер каждой части
+#[derive(Default)]
+struct MultipartStorage {
+    files: Mutex<HashMap<String, Vec<u8>>>,
+    uploads: Mutex<HashMap<String, Vec<Vec<u8>>>>,
+    part_sizes: Mutex<HashMap<String, Vec<usize>>>,
+}
+
+#[async_trait]
+impl ExportStorage for MultipartStorage {
+    fn export_key(
+        &self,
+        scope: ExportScope,
+        owner_id: &str,
+        export_id: &str,
+        extension: &str,
+    ) -> String {
+        format!(
+            "{}/{}/export-{}.{}",
+            scope.storage_prefix(),
+            owner_id,
+            export_id,
+            extension
+        )
+    }
+
+    async fn upload_export(
+        &self,
+        key: &str,
+        bytes: Vec<u8>,
+        _content_type: &str,
+    ) -> anyhow::Result<()> {
+        self.files.lock().unwrap().insert(key.to_string(), bytes);
+        Ok(())
+    }
+
+    async fn begin_multipart(&self, key: &str, _content_type: &str) -> anyhow::Result<String> {
+        let upload_id = format!("upload-{}", key);
+        self.uploads
+            .lock()
+            .unwrap()
+            .insert(upload_id.clone(), Vec::new());
+        Ok(upload_id)
+    }
+
+    async fn upload_part(
+        &self,
+        key: &str,
+        upload_id: &str,
+        part_number: u32,
+        bytes: Vec<u8>,
+    ) -> anyhow::Result<String> {
+        self.part_sizes
+            .lock()
+            .unwrap()
+            .entry(key.to_string())
+            .or_default()
+            .push(bytes.len());
+        let mut uploads = self.uploads.lock().unwrap();
+        let parts = uploads
+            .get_mut(upload_id)
+            .ok_or_else(|| anyhow!("unknown upload"))?;
+        assert_eq!(parts.len() as u32 + 1, part_number);
+        parts.push(bytes);
+        Ok(format!("\"etag-{}\"", part_number))
+    }
+
+    async fn complete_multipart(
+        &self,
+        key: &str,
+        upload_id: &str,
+        etags: &[String],
+    ) -> anyhow::Result<()> {
+        let parts = self
+            .uploads
+            .lock()
+            .unwrap()
+            .remove(upload_id)
+            .ok_or_else(|| anyhow!("unknown upload"))?;
+        assert_eq!(parts.len(), etags.len());
+        self.files
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), parts.concat());
+        Ok(())
+    }
+}
+
+struct SeededGroup {
+    group_id: ObjectId,
+    students: Vec<ObjectId>,
+    outsider: ObjectId,
+}
+
+/// Группа из 5 учеников с `ANSWERS` ответами за последние сутки, плюс ответы
+/// вне периода и ответы ученика из другой группы - они в выгрузку не попадают
+async fn seed_group(state: &AppState) -> SeededGroup {
+    let group_id = ObjectId::new();
+    let students: Vec<ObjectId> = (0..5).map(|_| ObjectId::new()).collect();
+    let outsider = ObjectId::new();
+
+    let mut users: Vec<Document> = students
+        .iter()
+        .enumerate()
+        .map(|(index, id)| {
+            doc! {
+                "_id": id,
+                "email": format!("ndjson-{}@test.com", id.to_hex()),
+                "password_hash": "hash",
+                "name": format!("Ученик {}", index),
+                "role": "student",
+                "group_ids": [group_id.to_hex()],
+            }
+        })
+        .collect();
+    users.push(doc! {
+        "_id": outsider,
+        "email": format!("ndjson-{}@test.com", outsider.to_hex()),
+        "password_hash": "hash",
+        "name": "Чужой",
+        "role": "student",
+        "group_ids": [ObjectId::new().to_hex()],
+    });
+    state
+        .mongo
+        .collection::<Document>("users")
+        .insert_many(users)
+        .await
+        .unwrap();
+
+    let now = Utc::now();
+    let attempt = |user_id: &ObjectId, index: usize, at| AttemptRecord {
+        id: ObjectId::new().to_hex(),
+        session_id: format!("session-{}", index % 40),
+        user_id: user_id.to_hex(),
+        task_id: format!("task-{}", index),
+        answer: format!("ответ {}", index),
+        correct: !index.is_multiple_of(3),
+        score: if index.is_multiple_of(3) { 0 } else { 10 },
+        timestamp: at,
+        reason: None,
+        credit: None,
+        timing: None,
+    };
+    let mut attempts: Vec<AttemptRecord> = (0..ANSWERS)
+        .map(|index| {
+            attempt(
+                &students[index % students.len()],
+                index,
+                now - Duration::minutes(index as i64 % 600),
+            )
+        })
+        .collect();
+    attempts.extend((0..20).map(|index| attempt(&students[0], index, now - Duration::days(3))));
+    attempts.extend((0..20).map(|index| attempt(&outsider, index, now)));
+    state
+        .mongo
+        .collection::<AttemptRecord>("attempt_records")
+        .insert_many(attempts)
+        .await
+        .unwrap();
+
+    SeededGroup {
+        group_id,
+        students,
+        outsider,
+    }
+}
+
+async fn run_ndjson_export(
+    state: &AppState,
+    storage: Arc<MultipartStorage>,
+    group_id: &ObjectId,
+    fields: Vec<String>,
+    reveal_user_ids: bool,
+) -> Vec<u8> {
+    let now = Utc::now();
+    let reporting = ReportingService::new(state.mongo.clone(), state.redis.clone());
+    let export = reporting
+        .create_export_request(NewReportExport {
+            scope: ExportScope::Group,
+            subject_id: *group_id,
+            teacher_id: ObjectId::new(),
+            format: ExportFormat::Ndjson,
+            filters: ReportFilters {
+                topic_ids: Vec::new(),
+                period: TimeRange {
+                    from: now - Duration::days(1),
+                    to: now,
+                },
+                fields,
+                reveal_user_ids,
+                locale: ExportLocale::default(),
+            },
+            expires_at: now + Duration::hours(24),
+        })
+        .await
+        .unwrap();
+
+    let mut config = state.config.clone();
+    // Большой батч забирает и выгрузки, оставшиеся в базе от других тестов
+    config.reporting.export_concurrency = 1000;
+    config.reporting.export_part_size_bytes = PART_SIZE;
+    let worker = ExportWorker::new(
+        ReportingService::new(state.mongo.clone(), state.redis.clone()),
+        storage.clone(),
+        config,
+    );
+    worker.process_pending().await.unwrap();
+
+    let export = reporting
+        .get_export_by_id(&export.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        export.status,
+        ExportStatus::Ready,
+        "{:?}",
+        export.last_error
+    );
+    let key = export.storage_key.unwrap();
+    assert!(key.starts_with(&format!("groups/{}/", group_id.to_hex())));
+    assert!(key.ends_with(".ndjson"));
+
+    // Память ограничена частью: файл пришёл несколькими частями, каждая не больше
+    // размера части плюс одна строка
+    let part_sizes = storage.part_sizes.lock().unwrap()[&key].clone();
+    assert!(part_sizes.len() > 1, "{part_sizes:?}");
+    assert!(
+        part_sizes.iter().all(|size| *size < PART_SIZE + 1024),
+        "{part_sizes:?}"
+    );
+
+    let files = storage.files.lock().unwrap();
+    files.get(&key).expect("NDJSON uploaded").clone()
+}
+
+fn parse_lines(bytes: &[u8]) -> Vec<Value> {
+    std::str::from_utf8(bytes)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn ndjson_export_streams_pseudonymized_answers() {
+    let state = common::create_test_state().await;
+    let group = seed_group(&state).await;
+    let storage = Arc::new(MultipartStorage::default());
+
+    let bytes = run_ndjson_export(&state, storage, &group.group_id, Vec::new(), false).await;
+    let rows = parse_lines(&bytes);
+    assert_eq!(rows.len(), ANSWERS);
+
+    let text = String::from_utf8(bytes).unwrap();
+    for student in group.students.iter().chain([&group.outsider]) {
+        assert!(!text.contains(&student.to_hex()), "real user id leaked");
+    }
+    assert!(!text.contains("Ученик"), "names are not exported");
+
+    let expected: HashSet<String> = group
+        .students
+        .iter()
+        .map(|id| pseudonymize_user_id(&state.config.jwt_secret, &group.group_id, &id.to_hex()))
+        .collect();
+    let pseudonyms: HashSet<String> = rows
+        .iter()
+        .map(|row| row["user_id"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(pseudonyms, expected);
+    assert!(rows.iter().all(|row| row.get("user_name").is_none()));
+    assert!(rows.iter().all(|row| row["task_id"].is_string()));
+}
+
+#[tokio::test]
+async fn ndjson_export_for_admin_keeps_ids_and_selected_fields() {
+    let state = common::create_test_state().await;
+    let group = seed_group(&state).await;
+    let storage = Arc::new(MultipartStorage::default());
+    let fields = vec![
+        "user_id".to_string(),
+        "user_name".to_string(),
+        "correct".to_string(),
+    ];
+
+    let bytes = run_ndjson_export(&state, storage, &group.group_id, fields, true).await;
+    let rows = parse_lines(&bytes);
+    assert_eq!(rows.len(), ANSWERS);
+
+    let students: HashSet<String> = group.students.iter().map(|id| id.to_hex()).collect();
+    for row in &rows {
+        let keys: HashSet<&str> = row
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        assert_eq!(keys, HashSet::from(["user_id", "user_name", "correct"]));
+        assert!(students.contains(row["user_id"].as_str().unwrap()));
+        assert!(row["user_name"].as_str().unwrap().starts_with("Ученик"));
+    }
+}
+
+fn token(state: &AppState, role: &str, group_ids: Vec<String>) -> String {
+    let now = Utc::now().timestamp() as usize;
+    JwtService::new(&state.config.jwt_secret)
+        .generate_token(JwtClaims {
+            sub: ObjectId::new().to_hex(),
+            role: role.to_string(),
+            group_ids,
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap()
+}
+
+async fn get_csrf_token(app: &Router) -> (String, String) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/api/v1/auth/csrf-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let csrf_cookie = response
+        .headers()
+        .get_all("set-cookie")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .find(|s| s.starts_with("csrf_token="))
+        .and_then(|s| s.split(';').next())
+        .and_then(|part| part.split('=').nth(1))
+        .unwrap_or_default()
+        .to_string();
+
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    (
+        json["csrf_token"].as_str().unwrap().to_string(),
+        csrf_cookie,
+    )
+}
+
+async fn request_export(
+    app: &Router,
+    token: &str,
+    csrf: &(String, String),
+    uri: &str,
+    body: Value,
+) -> (StatusCode, Value) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("authorization", format!("Bearer {}", token))
+                .header("x-csrf-token", &csrf.0)
+                .header("cookie", format!("csrf_token={}", csrf.1))
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (
+        status,
+        serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+    )
+}
+
+#[tokio::test]
+async fn ndjson_export_request_validates_fields_and_records_requester() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+    let csrf = get_csrf_token(&app).await;
+    let group_id = ObjectId::new();
+    let uri = format!("/stats/groups/{}/export", group_id.to_hex());
+    let now = Utc::now();
+    let period = json!({ "from": now - Duration::days(7), "to": now });
+
+    let teacher = token(&state, "teacher", vec![group_id.to_hex()]);
+    let invalid = [
+        json!({ "format": "csv", "period": period, "fields": ["user_id"] }),
+        json!({ "format": "ndjson", "period": period, "fields": ["password_hash"] }),
+        json!({ "format": "ndjson", "period": period, "fields": [] }),
+    ];
+    for body in invalid {
+        let (status, response) = request_export(&app, &teacher, &csrf, &uri, body.clone()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{body}: {response}");
+    }
+
+    // Личный отчёт в NDJSON не собирается
+    let admin = token(&state, "admin", Vec::new());
+    let (status, _) = request_export(
+        &app,
+        &admin,
+        &csrf,
+        &format!("/stats/users/{}/export", ObjectId::new().to_hex()),
+        json!({ "format": "ndjson", "period": period }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let reporting = ReportingService::new(state.mongo.clone(), state.redis.clone());
+    for (requester, reveal) in [(&teacher, false), (&admin, true)] {
+        let body = json!({ "format": "ndjson", "period": period, "fields": ["user_id", "score"] });
+        let (status, response) = request_export(&app, requester, &csrf, &uri, body).await;
+        assert_eq!(status, StatusCode::OK, "{response}");
+        let export_id = ObjectId::parse_str(response["export_id"].as_str().unwrap()).unwrap();
+        let export = reporting
+            .get_export_by_id(&export_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(export.format, ExportFormat::Ndjson);
+        assert_eq!(export.filters.fields, vec!["user_id", "score"]);
+        assert_eq!(export.filters.reveal_user_ids, reveal);
+    }
+}
@@ -0,0 +1,212 @@
+mod common;
+
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use chrono::Utc;
+use mongodb::bson::{doc, oid::ObjectId, DateTime as BsonDateTime, Document};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use trainingground_api::{
+    create_router,
+    middlewares::auth::{JwtClaims, JwtService},
+    services::AppState,
+};
+use uuid::Uuid;
+
+fn token(state: &AppState, user_id: &ObjectId, role: &str, group_ids: Vec<String>) -> String {
+    let now = Utc::now().timestamp() as usize;
+    JwtService::new(&state.config.jwt_secret)
+        .generate_token(JwtClaims {
+            sub: user_id.to_hex(),
+            role: role.to_string(),
+            group_ids,
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap()
+}
+
+async fn insert_user(state: &AppState, name: &str, role: &str, group_ids: &[String]) -> ObjectId {
+    let now = BsonDateTime::now();
+    state
+        .mongo
+        .collection::<Document>("users")
+        .insert_one(doc! {
+            "email": format!("inbox+{}@test.com", Uuid::new_v4()),
+            "password_hash": "hash",
+            "name": name,
+            "role": role,
+            "group_ids": group_ids,
+            "is_blocked": false,
+            "createdAt": now,
+            "updatedAt": now,
+        })
+        .await
+        .unwrap()
+        .inserted_id
+        .as_object_id()
+        .unwrap()
+}
+
+async fn call(
+    app: &Router,
+    method: &str,
+    uri: &str,
+    token: &str,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let csrf_token = Uuid::new_v4().to_string();
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .header("authorization", format!("Bearer {}", token))
+        .header("x-csrf-token", &csrf_token)
+        .header("cookie", format!("csrf_token={}", csrf_token))
+        .header("x-request-nonce", Uuid::new_v4().to_string())
+        .header("x-request-timestamp", Utc::now().timestamp().to_string())
+        .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or_default())
+}
+
+#[tokio::test]
+async fn teacher_message_lands_in_student_notifications() {
+    let state = common::create_test_state().await;
+    let now = BsonDateTime::now();
+    let group_id = state
+        .mongo
+        .collection::<Document>("groups")
+        .insert_one(doc! {
+            "name": "8А",
+            "school": "School №3",
+            "studentCount": 2,
+            "createdAt": now,
+            "updatedAt": now,
+        })
+        .await
+        .unwrap()
+        .inserted_id
+        .as_object_id()
+        .unwrap()
+        .to_hex();
+    let groups = std::slice::from_ref(&group_id);
+    let teacher_id = insert_user(&state, "Ольга Сергеевна", "teacher", &[]).await;
+    let anna = insert_user(&state, "Анна", "student", groups).await;
+    let boris = insert_user(&state, "Борис", "student", groups).await;
+
+    let teacher = token(&state, &teacher_id, "teacher", vec![group_id.clone()]);
+    let anna_token = token(&state, &anna, "student", vec![group_id.clone()]);
+    let boris_token = token(&state, &boris, "student", vec![group_id.clone()]);
+    let app = create_router(Arc::new(state));
+
+    let (status, template) = call(
+        &app,
+        "POST",
+        "/api/v1/teacher/notifications/templates",
+        &teacher,
+        Some(json!({
+            "name": "Контрольная",
+            "subject": "{group_name}: контрольная в пятницу",
+            "body": "{student_name}, повторите орфограммы. {teacher_name}",
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{template}");
+
+    for _ in 0..2 {
+        let (status, sent) = call(
+            &app,
+            "POST",
+            "/api/v1/teacher/notifications/send",
+            &teacher,
+            Some(json!({ "groupId": group_id, "templateId": template["id"] })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{sent}");
+    }
+
+    let (status, inbox) = call(&app, "GET", "/api/v1/notifications", &anna_token, None).await;
+    assert_eq!(status, StatusCode::OK, "{inbox}");
+    assert_eq!(inbox["total"], 2);
+    assert_eq!(inbox["unreadCount"], 2);
+    let first = &inbox["items"][0];
+    assert_eq!(first["type"], "teacher_message");
+    assert_eq!(first["title"], "8А: контрольная в пятницу");
+    assert_eq!(first["body"], "Анна, повторите орфограммы. Ольга Сергеевна");
+    assert_eq!(first["read"], false);
+
+    let (status, page) = call(
+        &app,
+        "GET",
+        "/api/v1/notifications?limit=1&offset=1",
+        &anna_token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{page}");
+    assert_eq!(page["items"].as_array().unwrap().len(), 1);
+    assert_eq!(page["total"], 2);
+
+    // Чужое уведомление отметить нельзя
+    let first_id = first["id"].as_str().unwrap().to_string();
+    let (status, _) = call(
+        &app,
+        "POST",
+        &format!("/api/v1/notifications/{}/read", first_id),
+        &boris_token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, read) = call(
+        &app,
+        "POST",
+        &format!("/api/v1/notifications/{}/read", first_id),
+        &anna_token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{read}");
+    assert_eq!(read["read"], true);
+
+    let (_, unread) = call(
+        &app,
+        "GET",
+        "/api/v1/notifications?unread_only=true",
+        &anna_token,
+        None,
+    )
+    .await;
+    assert_eq!(unread["total"], 1);
+    assert_eq!(unread["unreadCount"], 1);
+    assert_ne!(unread["items"][0]["id"], first_id.as_str());
+
+    let (status, all) = call(
+        &app,
+        "POST",
+        "/api/v1/notifications/read-all",
+        &anna_token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{all}");
+    assert_eq!(all["updated"], 1);
+
+    let (_, inbox) = call(&app, "GET", "/api/v1/notifications", &anna_token, None).await;
+    assert_eq!(inbox["unreadCount"], 0);
+    assert_eq!(inbox["total"], 2);
+
+    // Отметки Анны не трогают уведомления Бориса
+    let (_, boris_inbox) = call(&app, "GET", "/api/v1/notifications", &boris_token, None).await;
+    assert_eq!(boris_inbox["unreadCount"], 2);
+}
@@ -0,0 +1,249 @@
+mod common;
+
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use chrono::{TimeZone, Utc};
+use mongodb::bson::{doc, oid::ObjectId, DateTime as BsonDateTime, Document};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use trainingground_api::{
+    create_router,
+    middlewares::auth::{JwtClaims, JwtService},
+    services::AppState,
+};
+use uuid::Uuid;
+
+fn teacher_token(state: &AppState, teacher_id: &ObjectId, group_ids: Vec<String>) -> String {
+    let now = Utc::now().timestamp() as usize;
+    JwtService::new(&state.config.jwt_secret)
+        .generate_token(JwtClaims {
+            sub: teacher_id.to_hex(),
+            role: "teacher".to_string(),
+            group_ids,
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap()
+}
+
+async fn insert_user(state: &AppState, name: &str, role: &str, group_ids: &[String]) -> ObjectId {
+    let now = BsonDateTime::now();
+    state
+        .mongo
+        .collection::<Document>("users")
+        .insert_one(doc! {
+            "email": format!("templates+{}@test.com", Uuid::new_v4()),
+            "password_hash": "hash",
+            "name": name,
+            "role": role,
+            "group_ids": group_ids,
+            "is_blocked": false,
+            "createdAt": now,
+            "updatedAt": now,
+        })
+        .await
+        .unwrap()
+        .inserted_id
+        .as_object_id()
+        .unwrap()
+}
+
+async fn call(
+    app: &Router,
+    method: &str,
+    uri: &str,
+    token: &str,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let csrf_token = Uuid::new_v4().to_string();
+    let request = Request::builder()
+        .method(method)
+        .uri(format!("/api/v1/teacher{}", uri))
+        .header("content-type", "application/json")
+        .header("authorization", format!("Bearer {}", token))
+        .header("x-csrf-token", &csrf_token)
+        .header("cookie", format!("csrf_token={}", csrf_token))
+        .header("x-request-nonce", Uuid::new_v4().to_string())
+        .header("x-request-timestamp", Utc::now().timestamp().to_string())
+        .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or_default())
+}
+
+#[tokio::test]
+async fn preview_renders_template_for_student() {
+    let state = common::create_test_state().await;
+    let now = BsonDateTime::now();
+    let group_id = state
+        .mongo
+        .collection::<Document>("groups")
+        .insert_one(doc! {
+            "name": "7Б",
+            "school": "School №8",
+            "studentCount": 1,
+            "createdAt": now,
+            "updatedAt": now,
+        })
+        .await
+        .unwrap()
+        .inserted_id
+        .as_object_id()
+        .unwrap()
+        .to_hex();
+    let teacher_id = insert_user(&state, "Мария Ивановна", "teacher", &[]).await;
+    let student_id = insert_user(
+        &state,
+        "Анна Смирнова",
+        "student",
+        std::slice::from_ref(&group_id),
+    )
+    .await;
+    let last_activity = Utc.with_ymd_and_hms(2026, 3, 9, 10, 0, 0).unwrap();
+    state
+        .mongo
+        .collection::<Document>("progress_summary")
+        .insert_one(doc! {
+            "_id": format!("templates-{}", Uuid::new_v4()),
+            "user_id": student_id.to_hex(),
+            "level_id": format!("level-{}", Uuid::new_v4()),
+            "attempts_total": 5,
+            "correct_count": 4,
+            "percentage": 80.0,
+            "score": 42,
+            "updated_at": BsonDateTime::from_millis(last_activity.timestamp_millis()),
+        })
+        .await
+        .unwrap();
+
+    let token = teacher_token(&state, &teacher_id, vec![group_id.clone()]);
+    let stranger = teacher_token(&state, &ObjectId::new(), vec![group_id.clone()]);
+    let app = create_router(Arc::new(state));
+
+    // Опечатка в переменной отклоняется при сохранении
+    let (status, body) = call(
+        &app,
+        "POST",
+        "/notifications/templates",
+        &token,
+        Some(json!({
+            "name": "Напоминание",
+            "subject": "{group_name}: пора заниматься",
+            "body": "Здравствуйте, {student_nmae}! {teacher}",
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+    assert_eq!(body["code"], "UNKNOWN_TEMPLATE_VARIABLES");
+    assert_eq!(
+        body["details"]["unknown"],
+        json!(["student_nmae", "teacher"])
+    );
+
+    let (status, created) = call(
+        &app,
+        "POST",
+        "/notifications/templates",
+        &token,
+        Some(json!({
+            "name": "Напоминание",
+            "subject": "{group_name}: пора заниматься",
+            "body": "Здравствуйте, {student_name}!",
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{created}");
+    let template_id = created["id"].as_str().unwrap().to_string();
+
+    let (status, updated) = call(
+        &app,
+        "PUT",
+        &format!("/notifications/templates/{}", template_id),
+        &token,
+        Some(json!({
+            "body": "Здравствуйте, {student_name}! Последний результат: {last_score} баллов \
+                     ({last_activity_date}). {teacher_name}",
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{updated}");
+    assert_eq!(updated["subject"], "{group_name}: пора заниматься");
+
+    let (status, preview) = call(
+        &app,
+        "POST",
+        &format!("/notifications/templates/{}/preview", template_id),
+        &token,
+        Some(json!({ "groupId": group_id, "studentId": student_id.to_hex() })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{preview}");
+    assert_eq!(preview["subject"], "7Б: пора заниматься");
+    assert_eq!(
+        preview["body"],
+        "Здравствуйте, Анна Смирнова! Последний результат: 42 баллов (09.03.2026). Мария Ивановна"
+    );
+    assert_eq!(preview["studentId"], student_id.to_hex());
+
+    // Без ученика - вымышленный получатель
+    let (status, sample) = call(
+        &app,
+        "POST",
+        &format!("/notifications/templates/{}/preview", template_id),
+        &token,
+        Some(json!({})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{sample}");
+    assert!(sample["body"]
+        .as_str()
+        .unwrap()
+        .starts_with("Здравствуйте, Иван Петров!"));
+    assert!(sample["studentId"].is_null());
+
+    // Чужой шаблон не виден и не удаляется
+    let (status, _) = call(
+        &app,
+        "POST",
+        &format!("/notifications/templates/{}/preview", template_id),
+        &stranger,
+        Some(json!({})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = call(
+        &app,
+        "DELETE",
+        &format!("/notifications/templates/{}", template_id),
+        &stranger,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = call(
+        &app,
+        "DELETE",
+        &format!("/notifications/templates/{}", template_id),
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = call(
+        &app,
+        "PUT",
+        &format!("/notifications/templates/{}", template_id),
+        &token,
+        Some(json!({ "name": "Другое" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,367 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+use axum::{
+    body::Bytes,
+    extract::{DefaultBodyLimit, State},
+    http::{HeaderMap, Method, StatusCode, Uri},
+    response::{IntoResponse, Response},
+    Router,
+};
+use trainingground_api::{
+    config::ObjectStorageSettings, services::object_storage::ObjectStorageClient,
+};
+
+const BUCKET: &str = "reports";
+const MIB: usize = 1024 * 1024;
+
+/// Минимальный S3 в памяти: объекты, составные загрузки, листинг по две записи
+/// на страницу и подмешиваемые сбои
+#[derive(Default)]
+struct MockS3 {
+    objects: BTreeMap<String, Vec<u8>>,
+    uploads: HashMap<String, BTreeMap<u32, Vec<u8>>>,
+    /// Сколько ближайших PUT ответить 503
+    fail_puts: usize,
+    /// Сколько ближайших GET объекта ответить 503
+    fail_gets: usize,
+    gets: usize,
+    /// Подписанные заголовки последнего GET объекта (`SignedHeaders=` из Authorization)
+    signed_get_headers: String,
+    /// На этот номер части отвечать 400
+    reject_part: Option<u32>,
+    puts: usize,
+    aborted: Vec<String>,
+    sse_headers: Vec<Option<String>>,
+}
+
+type Shared = Arc<Mutex<MockS3>>;
+
+async fn start_mock() -> (Shared, String) {
+    let state: Shared = Arc::default();
+    let app = Router::new()
+        .fallback(handle)
+        .layer(DefaultBodyLimit::disable())
+        .with_state(state.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.ok();
+    });
+    (state, format!("http://{}", addr))
+}
+
+async fn handle(
+    State(state): State<Shared>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let query: HashMap<String, String> =
+        url::form_urlencoded::parse(uri.query().unwrap_or_default().as_bytes())
+            .into_owned()
+            .collect();
+    let path = percent_encoding::percent_decode_str(uri.path())
+        .decode_utf8_lossy()
+        .to_string();
+    let key = path
+        .trim_start_matches(&format!("/{}", BUCKET))
+        .trim_start_matches('/')
+        .to_string();
+    let mut s3 = state.lock().unwrap();
+
+    match method {
+        Method::PUT => {
+            s3.puts += 1;
+            if s3.fail_puts > 0 {
+                s3.fail_puts -= 1;
+                return (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "<Error><Message>SlowDown</Message></Error>",
+                )
+                    .into_response();
+            }
+            if let Some(upload_id) = query.get("uploadId") {
+                let part: u32 = query["partNumber"].parse().unwrap();
+                if s3.reject_part == Some(part) {
+                    return (
+                        StatusCode::BAD_REQUEST,
+                        "<Error><Message>EntityTooSmall</Message></Error>",
+                    )
+                        .into_response();
+                }
+                s3.uploads
+                    .get_mut(upload_id)
+                    .unwrap()
+                    .insert(part, body.to_vec());
+                return (StatusCode::OK, [("etag", format!("\"etag-{}\"", part))]).into_response();
+            }
+            let sse = headers
+                .get("x-amz-server-side-encryption")
+                .map(|value| value.to_str().unwrap().to_string());
+            s3.sse_headers.push(sse);
+            s3.objects.insert(key, body.to_vec());
+            StatusCode::OK.into_response()
+        }
+        Method::POST if query.contains_key("uploads") => {
+            let sse = headers
+                .get("x-amz-server-side-encryption")
+                .map(|value| value.to_str().unwrap().to_string());
+            s3.sse_headers.push(sse);
+            let upload_id = format!("upload-{}", s3.uploads.len() + 1);
+            s3.uploads.insert(upload_id.clone(), BTreeMap::new());
+            format!(
+                "<InitiateMultipartUploadResult><UploadId>{}</UploadId></InitiateMultipartUploadResult>",
+                upload_id
+            )
+            .into_response()
+        }
+        Method::POST => {
+            let parts = s3.uploads.remove(&query["uploadId"]).unwrap();
+            let requested = String::from_utf8(body.to_vec()).unwrap();
+            assert_eq!(requested.matches("<Part>").count(), parts.len());
+            s3.objects
+                .insert(key, parts.into_values().flatten().collect());
+            "<CompleteMultipartUploadResult/>".into_response()
+        }
+        Method::DELETE => {
+            if let Some(upload_id) = query.get("uploadId") {
+                s3.uploads.remove(upload_id);
+                s3.aborted.push(upload_id.clone());
+            } else {
+                s3.objects.remove(&key);
+            }
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Method::GET if query.get("list-type").map(String::as_str) == Some("2") => {
+            let prefix = query.get("prefix").cloned().unwrap_or_default();
+            let after = query.get("continuation-token").cloned().unwrap_or_default();
+            let matching: Vec<&String> = s3
+                .objects
+                .keys()
+                .filter(|key| key.starts_with(&prefix) && key.as_str() > after.as_str())
+                .collect();
+            let page = &matching[..matching.len().min(2)];
+            let truncated = matching.len() > page.len();
+            let contents: String = page
+                .iter()
+                .map(|key| format!("<Contents><Key>{}</Key></Contents>", key))
+                .collect();
+            let next = if truncated {
+                format!(
+                    "<NextContinuationToken>{}</NextContinuationToken>",
+                    page.last().unwrap()
+                )
+            } else {
+                String::new()
+            };
+            format!(
+                "<ListBucketResult><IsTruncated>{}</IsTruncated>{}{}</ListBucketResult>",
+                truncated, contents, next
+            )
+            .into_response()
+        }
+        Method::GET => {
+            s3.gets += 1;
+            if s3.fail_gets > 0 {
+                s3.fail_gets -= 1;
+                return StatusCode::SERVICE_UNAVAILABLE.into_response();
+            }
+            s3.signed_get_headers = headers["authorization"]
+                .to_str()
+                .unwrap()
+                .split("SignedHeaders=")
+                .nth(1)
+                .and_then(|rest| rest.split(',').next())
+                .unwrap_or_default()
+                .to_string();
+            let Some(object) = s3.objects.get(&key).cloned() else {
+                return StatusCode::NOT_FOUND.into_response();
+            };
+            match headers.get("range").map(|value| value.to_str().unwrap()) {
+                Some(range) => {
+                    let (start, end) = range.trim_start_matches("bytes=").split_once('-').unwrap();
+                    let (start, end): (usize, usize) =
+                        (start.parse().unwrap(), end.parse().unwrap());
+                    (StatusCode::PARTIAL_CONTENT, object[start..=end].to_vec()).into_response()
+                }
+                None => object.into_response(),
+            }
+        }
+        _ => StatusCode::NOT_IMPLEMENTED.into_response(),
+    }
+}
+
+fn client(endpoint: String, sse: Option<&str>) -> ObjectStorageClient {
+    // http-endpoint разрешён вне production
+    std::env::set_var("APP_ENV", "test");
+    ObjectStorageClient::new(ObjectStorageSettings {
+        bucket: BUCKET.into(),
+        region: "ru-central1".into(),
+        endpoint: Some(endpoint),
+        access_key: "key".into(),
+        secret_key: "secret".into(),
+        reports_prefix: "tg".into(),
+        presign_ttl_secs: ObjectStorageSettings::DEFAULT_PRESIGN_TTL_SECS,
+        sse: sse.map(str::to_string),
+        sse_kms_key_id: None,
+        max_attempts: 3,
+        retry_base_delay_ms: 1,
+        request_timeout_secs: 10,
+        multipart_threshold_bytes: 6 * MIB,
+        multipart_part_size_bytes: 5 * MIB,
+    })
+    .unwrap()
+}
+
+#[tokio::test]
+async fn uploads_retry_transient_errors_with_sse_headers() {
+    let (s3, endpoint) = start_mock().await;
+    s3.lock().unwrap().fail_puts = 2;
+    let storage = client(endpoint, Some("AES256"));
+
+    storage
+        .upload_bytes("exports/a.csv", b"a,b".to_vec(), "text/csv")
+        .await
+        .unwrap();
+
+    let s3 = s3.lock().unwrap();
+    assert_eq!(s3.puts, 3);
+    assert_eq!(s3.objects["tg/exports/a.csv"], b"a,b");
+    assert_eq!(s3.sse_headers, vec![Some("AES256".to_string())]);
+}
+
+#[tokio::test]
+async fn upload_gives_up_after_attempt_budget() {
+    let (s3, endpoint) = start_mock().await;
+    s3.lock().unwrap().fail_puts = 10;
+    let storage = client(endpoint, None);
+
+    let err = storage
+        .upload_bytes("exports/b.csv", b"x".to_vec(), "text/csv")
+        .await
+        .unwrap_err();
+
+    assert!(format!("{err:#}").contains("3 attempt"), "{err:#}");
+    assert_eq!(s3.lock().unwrap().puts, 3);
+}
+
+#[tokio::test]
+async fn large_payloads_are_uploaded_in_parts() {
+    let (s3, endpoint) = start_mock().await;
+    let storage = client(endpoint, Some("aws:kms"));
+    let payload: Vec<u8> = (0..12 * MIB).map(|index| (index % 251) as u8).collect();
+
+    storage
+        .upload_bytes(
+            "exports/big.ndjson",
+            payload.clone(),
+            "application/x-ndjson",
+        )
+        .await
+        .unwrap();
+
+    let s3 = s3.lock().unwrap();
+    assert_eq!(s3.objects["tg/exports/big.ndjson"], payload);
+    // 5 + 5 + 2 МиБ
+    assert_eq!(s3.puts, 3);
+    // SSE задаётся при создании составной загрузки, а не на каждой части
+    assert_eq!(s3.sse_headers, vec![Some("aws:kms".to_string())]);
+    assert!(s3.uploads.is_empty());
+}
+
+#[tokio::test]
+async fn failed_multipart_upload_is_aborted() {
+    let (s3, endpoint) = start_mock().await;
+    s3.lock().unwrap().reject_part = Some(2);
+    let storage = client(endpoint, None);
+
+    let result = storage
+        .upload_bytes(
+            "exports/broken.ndjson",
+            vec![7; 11 * MIB],
+            "application/x-ndjson",
+        )
+        .await;
+
+    assert!(result.is_err());
+    let s3 = s3.lock().unwrap();
+    assert_eq!(s3.aborted, vec!["upload-1".to_string()]);
+    assert!(s3.uploads.is_empty());
+    assert!(!s3.objects.contains_key("tg/exports/broken.ndjson"));
+    // 400 не повторяется: первая часть и одна попытка второй
+    assert_eq!(s3.puts, 2);
+}
+
+#[tokio::test]
+async fn list_prefix_pages_through_keys_and_delete_removes_objects() {
+    let (s3, endpoint) = start_mock().await;
+    let storage = client(endpoint, None);
+    for name in ["1", "2", "3", "4", "5"] {
+        storage
+            .upload_bytes(
+                &format!("archives/{}.gz", name),
+                vec![1],
+                "application/gzip",
+            )
+            .await
+            .unwrap();
+    }
+    storage
+        .upload_bytes("archives-old/6.gz", vec![1], "application/gzip")
+        .await
+        .unwrap();
+
+    let keys = storage.list_prefix("archives/").await.unwrap();
+    assert_eq!(
+        keys,
+        vec![
+            "archives/1.gz",
+            "archives/2.gz",
+            "archives/3.gz",
+            "archives/4.gz",
+            "archives/5.gz"
+        ]
+    );
+
+    storage.delete_object("archives/3.gz").await.unwrap();
+    assert_eq!(storage.list_prefix("archives/").await.unwrap().len(), 4);
+    assert!(!s3.lock().unwrap().objects.contains_key("tg/archives/3.gz"));
+}
+
+#[tokio::test]
+async fn range_downloads_are_signed_and_retried() {
+    let (s3, endpoint) = start_mock().await;
+    let storage = client(endpoint, None);
+    storage
+        .upload_bytes(
+            "archives/day.ndjson",
+            b"0123456789".to_vec(),
+            "application/x-ndjson",
+        )
+        .await
+        .unwrap();
+    s3.lock().unwrap().fail_gets = 1;
+
+    let bytes = storage
+        .get_object_range("archives/day.ndjson", 2, 4)
+        .await
+        .unwrap();
+    assert_eq!(bytes, b"2345");
+    {
+        let s3 = s3.lock().unwrap();
+        assert_eq!(s3.gets, 2);
+        assert_eq!(
+            s3.signed_get_headers,
+            "host;range;x-amz-content-sha256;x-amz-date"
+        );
+    }
+
+    let whole = storage.get_object("archives/day.ndjson").await.unwrap();
+    assert_eq!(whole, b"0123456789");
+    assert_eq!(
+        s3.lock().unwrap().signed_get_headers,
+        "host;x-amz-content-sha256;x-amz-date"
+    );
+}
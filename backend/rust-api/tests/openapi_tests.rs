@@ -0,0 +1,125 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use serde_json::Value;
+use tower::ServiceExt;
+use trainingground_api::openapi::{ApiDoc, OPENAPI_JSON_PATH};
+use utoipa::OpenApi;
+
+mod common;
+
+fn spec_json() -> Value {
+    serde_json::to_value(ApiDoc::openapi()).expect("spec serializes to JSON")
+}
+
+#[test]
+fn spec_covers_main_endpoints() {
+    let spec = spec_json();
+    let paths = spec["paths"].as_object().expect("paths object");
+
+    for path in [
+        "/api/v1/auth/login",
+        "/api/v1/auth/refresh",
+        "/api/v1/auth/sessions",
+        "/api/v1/auth/sessions/{session_id}/revoke",
+        "/api/v1/sessions",
+        "/api/v1/sessions/auto",
+        "/api/v1/sessions/{id}/answers",
+        "/api/v1/notifications",
+        "/api/v1/notifications/{id}/read",
+        "/api/v1/review/next",
+        "/api/v1/me/certificates/{level_id}/pdf",
+        "/api/v1/assignments",
+        "/api/v1/teacher/groups/{group_id}/assignments/{assignment_id}/progress",
+        "/stats/groups/{id}",
+        "/admin/users",
+        "/admin/users/{id}/block",
+        "/admin/groups",
+        "/admin/groups/{id}/members",
+        "/admin/roles/{role}",
+        "/admin/templates",
+    ] {
+        assert!(paths.contains_key(path), "spec has no path {path}");
+    }
+}
+
+#[test]
+fn spec_declares_security_schemes_and_schemas() {
+    let spec = spec_json();
+    let components = &spec["components"];
+
+    for scheme in ["bearer_auth", "csrf_token", "refresh_cookie"] {
+        assert!(
+            components["securitySchemes"].get(scheme).is_some(),
+            "spec has no security scheme {scheme}"
+        );
+    }
+    for schema in [
+        "ErrorResponse",
+        "UserProfile",
+        "GroupResponse",
+        "TemplateSummary",
+        "ActiveSession",
+        "GroupStatsResponse",
+    ] {
+        assert!(
+            components["schemas"].get(schema).is_some(),
+            "spec has no schema {schema}"
+        );
+    }
+
+    // Запись требует CSRF-токен, чтение - только bearer
+    let users = &spec["paths"]["/admin/users"];
+    assert!(users["get"]["security"].to_string().contains("bearer_auth"));
+    assert!(users["post"]["security"].to_string().contains("csrf_token"));
+}
+
+#[test]
+fn paginated_endpoints_declare_next_cursor_header() {
+    let spec = spec_json();
+    for path in ["/admin/users", "/admin/users/{id}/logins", "/admin/audit"] {
+        let headers = &spec["paths"][path]["get"]["responses"]["200"]["headers"];
+        assert!(
+            headers.get("x-next-cursor").is_some(),
+            "{path} does not declare x-next-cursor: {headers}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_openapi_json_is_public() {
+    let app = common::create_test_app().await;
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri(OPENAPI_JSON_PATH)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let spec: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(spec["info"]["title"], "TrainingGround API");
+    assert!(spec["paths"].get("/api/v1/auth/login").is_some());
+}
+
+#[tokio::test]
+async fn test_swagger_ui_requires_auth() {
+    let app = common::create_test_app().await;
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api-docs/swagger-ui/")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,291 @@
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use chrono::Utc;
+use mongodb::bson::oid::ObjectId;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use trainingground_api::{
+    create_router,
+    middlewares::auth::{JwtClaims, JwtService},
+    services::AppState,
+};
+use uuid::Uuid;
+
+mod common;
+
+fn jwt(state: &AppState, role: &str) -> String {
+    let now = Utc::now().timestamp() as usize;
+    JwtService::from_config(&state.config)
+        .generate_token(JwtClaims {
+            sub: ObjectId::new().to_hex(),
+            role: role.to_string(),
+            group_ids: Vec::new(),
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap()
+}
+
+/// Уникальное имя роли, чтобы тесты не мешали друг другу в общей базе
+fn custom_role(prefix: &str) -> String {
+    format!("{}_{}", prefix, &Uuid::new_v4().simple().to_string()[..8])
+}
+
+async fn get(app: &axum::Router, token: &str, uri: &str) -> StatusCode {
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(uri)
+                .header("authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+        .status()
+}
+
+async fn put_role(
+    app: &axum::Router,
+    token: &str,
+    role: &str,
+    permissions: &[&str],
+) -> (StatusCode, Value) {
+    let csrf_token = Uuid::new_v4().to_string();
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("PUT")
+                .uri(format!("/admin/roles/{}", role))
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {}", token))
+                .header("x-csrf-token", &csrf_token)
+                .header("cookie", format!("csrf_token={}", csrf_token))
+                .header("x-request-nonce", Uuid::new_v4().to_string())
+                .header("x-request-timestamp", Utc::now().timestamp().to_string())
+                .body(Body::from(
+                    json!({ "permissions": permissions }).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or_default())
+}
+
+#[tokio::test]
+async fn custom_role_gets_only_granted_permissions() {
+    let state = common::create_test_state().await;
+    let admin = jwt(&state, "admin");
+    let role = custom_role("template_editor");
+    let editor = jwt(&state, &role);
+    let app = create_router(Arc::new(state));
+
+    // До настройки роль без разрешений
+    assert_eq!(
+        get(&app, &editor, "/admin/templates").await,
+        StatusCode::FORBIDDEN
+    );
+
+    let (status, body) = put_role(&app, &admin, &role, &["content.moderate"]).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["permissions"], json!(["content.moderate"]));
+    assert_eq!(body["built_in"], false);
+    assert_eq!(body["customized"], true);
+
+    assert_eq!(get(&app, &editor, "/admin/templates").await, StatusCode::OK);
+    assert_eq!(
+        get(&app, &editor, "/admin/users").await,
+        StatusCode::FORBIDDEN
+    );
+    assert_eq!(
+        get(&app, &editor, "/admin/feature-flags").await,
+        StatusCode::FORBIDDEN
+    );
+
+    let (status, _) = put_role(&app, &editor, &role, &["content.moderate"]).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "editor cannot manage roles");
+}
+
+async fn post_empty(app: &axum::Router, token: &str, uri: &str) -> StatusCode {
+    let csrf_token = Uuid::new_v4().to_string();
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("authorization", format!("Bearer {}", token))
+                .header("x-csrf-token", &csrf_token)
+                .header("cookie", format!("csrf_token={}", csrf_token))
+                .header("x-request-nonce", Uuid::new_v4().to_string())
+                .header("x-request-timestamp", Utc::now().timestamp().to_string())
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+        .status()
+}
+
+#[tokio::test]
+async fn publish_rollback_needs_its_own_permission() {
+    let state = common::create_test_state().await;
+    let admin = jwt(&state, "admin");
+    let content_admin = jwt(&state, "content_admin");
+    let role = custom_role("release_manager");
+    let release_manager = jwt(&state, &role);
+    let app = create_router(Arc::new(state));
+    let uri = format!(
+        "/admin/templates/{}/rollback-publish/1",
+        ObjectId::new().to_hex()
+    );
+
+    // content.moderate не даёт отката публикации в обход ревью
+    assert_eq!(
+        post_empty(&app, &content_admin, &uri).await,
+        StatusCode::FORBIDDEN
+    );
+
+    let (status, body) = put_role(&app, &admin, &role, &["content.rollback"]).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    // Разрешение пропускает к обработчику: шаблона нет
+    assert_eq!(
+        post_empty(&app, &release_manager, &uri).await,
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(post_empty(&app, &admin, &uri).await, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn roles_cannot_escalate_or_lock_out_admin() {
+    let state = common::create_test_state().await;
+    let admin = jwt(&state, "admin");
+    let role = custom_role("user_manager");
+    let manager = jwt(&state, &role);
+    let app = create_router(Arc::new(state));
+
+    let (status, _) = put_role(&app, &admin, &role, &["users.manage"]).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Управляющий пользователями не может выдать системные настройки, которых нет у него самого
+    let other = custom_role("ops");
+    let (status, body) = put_role(&app, &manager, &other, &["settings.manage"]).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["code"], "PERMISSION_ESCALATION");
+
+    let (status, body) = put_role(&app, &admin, "admin", &["content.moderate"]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "ADMIN_LOCKOUT");
+
+    let (status, body) = put_role(&app, &admin, "Bad.Role", &["reports.view"]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "INVALID_ROLE_NAME");
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/admin/roles")
+                .header("authorization", format!("Bearer {}", admin))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let roles: Value = serde_json::from_slice(&bytes).unwrap();
+    let listed = roles
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|r| r["role"] == role.as_str())
+        .expect("custom role is listed");
+    assert_eq!(listed["permissions"], json!(["users.manage"]));
+    assert!(roles
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|r| r["role"] == "content_moderator" && r["built_in"] == true));
+}
+
+async fn post_user(app: &axum::Router, token: &str, role: &str) -> (StatusCode, Value) {
+    let csrf_token = Uuid::new_v4().to_string();
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/admin/users")
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {}", token))
+                .header("x-csrf-token", &csrf_token)
+                .header("cookie", format!("csrf_token={}", csrf_token))
+                .body(Body::from(
+                    json!({
+                        "email": format!("role-{}@test.com", Uuid::new_v4()),
+                        "password": "Role123!@#",
+                        "name": "Role User",
+                        "role": role,
+                    })
+                    .to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or_default())
+}
+
+#[tokio::test]
+async fn configured_custom_role_can_be_assigned() {
+    let state = common::create_test_state().await;
+    let admin = jwt(&state, "admin");
+    let role = custom_role("reviewer");
+    let app = create_router(Arc::new(state));
+
+    // Роль без документа в role_permissions назначить нельзя
+    let (status, body) = post_user(&app, &admin, &role).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+
+    let (status, _) = put_role(&app, &admin, &role, &["content.moderate"]).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = post_user(&app, &admin, &role).await;
+    assert_eq!(status, StatusCode::CREATED, "{body}");
+    assert_eq!(body["role"], role.as_str());
+}
+
+#[tokio::test]
+async fn report_access_follows_role_permissions() {
+    let state = common::create_test_state().await;
+    let admin = jwt(&state, "admin");
+    let role = custom_role("analyst");
+    let analyst = jwt(&state, &role);
+    let app = create_router(Arc::new(state));
+    let group_stats = format!("/stats/groups/{}", ObjectId::new().to_hex());
+
+    assert_eq!(
+        get(&app, &analyst, &group_stats).await,
+        StatusCode::FORBIDDEN
+    );
+
+    // Отчёты по любой группе - по разрешению, а не по имени роли `admin`
+    let (status, _) = put_role(&app, &admin, &role, &["users.manage"]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_ne!(
+        get(&app, &analyst, &group_stats).await,
+        StatusCode::FORBIDDEN
+    );
+}
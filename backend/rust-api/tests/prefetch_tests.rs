@@ -0,0 +1,87 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Request, StatusCode},
+};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+mod common;
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_prefetch_manifest_for_new_student() {
+    let app = common::create_test_app().await;
+    std::env::set_var("RATE_LIMIT_DISABLED", "1");
+    let token = register_student(&app).await;
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/api/v1/prefetch-manifest")
+                .header("authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::CACHE_CONTROL],
+        "private, max-age=60"
+    );
+
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body["version"], 1);
+    assert_eq!(body["role"], "student");
+    assert_eq!(body["resources"][0]["type"], "api");
+    assert_eq!(body["resources"][0]["route"], "/api/v1/student/courses");
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_prefetch_manifest_requires_auth() {
+    let app = common::create_test_app().await;
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/api/v1/prefetch-manifest")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+async fn register_student(app: &axum::Router) -> String {
+    let body = json!({
+        "email": format!("prefetch-student-{}@test.com", uuid::Uuid::new_v4()),
+        "password": "Student123!@#",
+        "name": "Prefetch Student",
+    });
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/auth/register")
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&bytes).unwrap();
+    json["access_token"].as_str().unwrap().to_string()
+}
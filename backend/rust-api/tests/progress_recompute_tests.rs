@@ -0,0 +1,344 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use chrono::Utc;
+use mongodb::bson::{doc, oid::ObjectId, Document};
+use redis::AsyncCommands;
+use serde_json::json;
+use tower::ServiceExt;
+use trainingground_api::{
+    create_router,
+    models::{
+        answer::{AttemptFailureReason, AttemptRecord},
+        ProgressSummary,
+    },
+    services::{maintenance_service::RECOMPUTE_LOCK_KEY, AppState},
+};
+
+mod common;
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_recompute_corrects_drifted_rows_and_reports_drift() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+    let admin_token = create_admin_with_token(&app, &state).await;
+    let user_id = ObjectId::new().to_hex();
+    let level_id = format!("level-{}", ObjectId::new());
+    seed_answers(&state, &user_id, &level_id).await;
+
+    // Строка разошлась с ответами, второй строки (уровень из задания) нет вовсе
+    let progress = state
+        .mongo
+        .collection::<ProgressSummary>("progress_summary_v2");
+    progress
+        .insert_one(ProgressSummary {
+            id: format!("{}:{}", user_id, level_id),
+            user_id: user_id.clone(),
+            level_id: level_id.clone(),
+            attempts_total: 7,
+            correct_count: 2,
+            credit: Some(2.0),
+            percentage: 10.0,
+            score: 99,
+            updated_at: Utc::now(),
+        })
+        .await
+        .unwrap();
+
+    let job = run_recompute(&app, &admin_token, json!({ "user_id": user_id })).await;
+    assert_eq!(job["status"], "completed", "{job}");
+    assert_eq!(job["scope"]["user_id"], user_id);
+    let drift = &job["drift"];
+    assert_eq!(drift["users_scanned"], 1);
+    assert_eq!(drift["users_skipped"], 0);
+    assert_eq!(drift["rows_checked"], 2);
+    assert_eq!(drift["rows_drifted"], 2);
+    assert_eq!(drift["rows_created"], 1);
+    assert_eq!(drift["attempts_total"]["rows"], 2);
+    assert_eq!(drift["attempts_total"]["total_abs_delta"], 5.0);
+    assert_eq!(drift["correct_count"]["rows"], 1);
+    assert_eq!(drift["score"]["max_abs_delta"], 74.0);
+    assert_eq!(drift["score"]["total_abs_delta"], 84.0);
+
+    let corrected = progress
+        .find_one(doc! { "_id": format!("{}:{}", user_id, level_id) })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(corrected.attempts_total, 3);
+    assert_eq!(corrected.correct_count, 2);
+    assert_eq!(corrected.credit, Some(2.5));
+    assert!((corrected.percentage - 250.0 / 3.0).abs() < 1e-9);
+    assert_eq!(corrected.score, 25);
+
+    let created = progress
+        .find_one(doc! { "_id": format!("{}:task-b", user_id) })
+        .await
+        .unwrap()
+        .expect("missing row is created");
+    assert_eq!(created.attempts_total, 1);
+    assert_eq!(created.score, 10);
+
+    // Повторный запуск расхождений уже не находит
+    let job = run_recompute(&app, &admin_token, json!({ "user_id": user_id })).await;
+    assert_eq!(job["drift"]["rows_checked"], 2);
+    assert_eq!(job["drift"]["rows_drifted"], 0);
+
+    cleanup(&state, &user_id).await;
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_recompute_is_rejected_while_another_run_holds_the_lock() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+    let admin_token = create_admin_with_token(&app, &state).await;
+    let mut redis = state.redis.clone();
+    let _: () = redis
+        .set_ex(RECOMPUTE_LOCK_KEY, "other-run", 60)
+        .await
+        .unwrap();
+
+    let (status, body) = post_recompute(&app, &admin_token, json!({})).await;
+    assert_eq!(status, StatusCode::CONFLICT, "{body}");
+    assert_eq!(body["code"], "RECOMPUTE_IN_PROGRESS");
+
+    let _: () = redis.del(RECOMPUTE_LOCK_KEY).await.unwrap();
+
+    let (status, _) = post_recompute(
+        &app,
+        &admin_token,
+        json!({ "user_id": ObjectId::new().to_hex(), "group_id": ObjectId::new().to_hex() }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, body) = post_recompute(
+        &app,
+        &admin_token,
+        json!({ "group_id": ObjectId::new().to_hex() }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "{body}");
+}
+
+/// Ответы ученика: три на уровне сессии (один частично верный) и таймаут,
+/// который сводку не меняет, плюс ответ в сессии без уровня
+async fn seed_answers(state: &AppState, user_id: &str, level_id: &str) {
+    let with_level = format!("recompute-{}", ObjectId::new());
+    let without_level = format!("recompute-{}", ObjectId::new());
+    state
+        .mongo
+        .collection::<Document>("sessions")
+        .insert_many([
+            doc! { "_id": &with_level, "user_id": user_id, "task_id": "task-a", "level_id": level_id },
+            doc! { "_id": &without_level, "user_id": user_id, "task_id": "task-b" },
+        ])
+        .await
+        .unwrap();
+
+    let attempt = |session_id: &str,
+                   task_id: &str,
+                   correct: bool,
+                   score: i32,
+                   credit: Option<f64>,
+                   reason: Option<AttemptFailureReason>| AttemptRecord {
+        id: uuid::Uuid::new_v4().to_string(),
+        session_id: session_id.to_string(),
+        user_id: user_id.to_string(),
+        task_id: task_id.to_string(),
+        answer: "answer".to_string(),
+        correct,
+        score,
+        timestamp: Utc::now(),
+        reason,
+        credit,
+        timing: None,
+    };
+    state
+        .mongo
+        .collection::<AttemptRecord>("attempt_records")
+        .insert_many([
+            attempt(&with_level, "task-a", true, 10, None, None),
+            attempt(&with_level, "task-a", true, 10, Some(1.0), None),
+            attempt(
+                &with_level,
+                "task-a",
+                false,
+                5,
+                Some(0.5),
+                Some(AttemptFailureReason::WrongAnswer),
+            ),
+            attempt(
+                &with_level,
+                "task-a",
+                false,
+                0,
+                None,
+                Some(AttemptFailureReason::Timeout),
+            ),
+            attempt(&without_level, "task-b", true, 10, Some(1.0), None),
+        ])
+        .await
+        .unwrap();
+}
+
+async fn cleanup(state: &AppState, user_id: &str) {
+    let db = &state.mongo;
+    for collection in ["sessions", "attempt_records", "progress_summary_v2"] {
+        db.collection::<Document>(collection)
+            .delete_many(doc! { "user_id": user_id })
+            .await
+            .unwrap();
+    }
+}
+
+/// Запустить пересчёт и дождаться его завершения
+async fn run_recompute(
+    app: &axum::Router,
+    token: &str,
+    scope: serde_json::Value,
+) -> serde_json::Value {
+    let (status, job) = post_recompute(app, token, scope).await;
+    assert_eq!(status, StatusCode::ACCEPTED, "{job}");
+    let uri = format!("/admin/maintenance/jobs/{}", job["id"].as_str().unwrap());
+
+    for _ in 0..100 {
+        let job = get_json(app, token, &uri).await;
+        if job["status"] == "completed" || job["status"] == "failed" {
+            return job;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("recompute job did not finish");
+}
+
+async fn post_recompute(
+    app: &axum::Router,
+    token: &str,
+    scope: serde_json::Value,
+) -> (StatusCode, serde_json::Value) {
+    let (csrf_token, csrf_cookie) = get_csrf_token(app).await;
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/admin/maintenance/recompute-progress")
+                .header("authorization", format!("Bearer {}", token))
+                .header("content-type", "application/json")
+                .header("x-csrf-token", &csrf_token)
+                .header("cookie", format!("csrf_token={}", csrf_cookie))
+                .body(Body::from(scope.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or_default())
+}
+
+async fn get_json(app: &axum::Router, token: &str, uri: &str) -> serde_json::Value {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(uri)
+                .header("authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+async fn get_csrf_token(app: &axum::Router) -> (String, String) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/api/v1/auth/csrf-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let csrf_cookie = response
+        .headers()
+        .get_all("set-cookie")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .find(|cookie| cookie.starts_with("csrf_token="))
+        .and_then(|cookie| cookie.split(';').next())
+        .and_then(|pair| pair.split('=').nth(1))
+        .unwrap_or_default()
+        .to_string();
+
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    (
+        json["csrf_token"].as_str().unwrap().to_string(),
+        csrf_cookie,
+    )
+}
+
+async fn create_admin_with_token(app: &axum::Router, state: &AppState) -> String {
+    let email = format!("recompute-admin-{}@test.com", uuid::Uuid::new_v4());
+    let password = "Admin123!@#";
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/auth/register")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    json!({ "email": email, "password": password, "name": "Recompute Admin" })
+                        .to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    let user_id = ObjectId::parse_str(json["user"]["id"].as_str().unwrap()).unwrap();
+    state
+        .mongo
+        .collection::<Document>("users")
+        .update_one(
+            doc! { "_id": user_id },
+            doc! { "$set": { "role": "admin" } },
+        )
+        .await
+        .unwrap();
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/auth/login")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    json!({ "email": email, "password": password }).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    json["access_token"].as_str().unwrap().to_string()
+}
@@ -1,10 +1,17 @@
 // Rate limiting verification tests
 use axum::{
-    body::Body,
+    body::{to_bytes, Body},
     http::{Request, StatusCode},
 };
 use serde_json::json;
 use tower::ServiceExt;
+use trainingground_api::{
+    create_router,
+    middlewares::{
+        auth::{JwtClaims, JwtService},
+        rate_limit::check_sliding_window,
+    },
+};
 
 mod common;
 
@@ -76,6 +83,40 @@ async fn login_with_ip(app: &axum::Router, email: &str, password: &str, ip: &str
     response.status()
 }
 
+/// Helper to make a login request and keep the full response (headers + body)
+async fn login_response_with_ip(
+    app: &axum::Router,
+    email: &str,
+    password: &str,
+    ip: &str,
+) -> axum::response::Response {
+    let request_body = json!({
+        "email": email,
+        "password": password,
+    });
+
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/auth/login")
+                .header("content-type", "application/json")
+                .header("x-forwarded-for", ip)
+                .body(Body::from(request_body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+}
+
+fn header_value(response: &axum::response::Response, name: &str) -> u64 {
+    response.headers()[name]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap_or_else(|_| panic!("{} should be numeric", name))
+}
+
 /// Helper to make a register request with custom IP header
 async fn register_with_ip(
     app: &axum::Router,
@@ -534,3 +575,227 @@ async fn test_rate_limit_window_expiration() {
         status
     );
 }
+
+/// Rate limit headers count down and the 429 body is structured JSON
+#[tokio::test]
+#[serial_test::serial]
+async fn test_login_rate_limit_headers_and_429_body() {
+    flush_rate_limit_keys().await;
+
+    std::env::set_var("RATE_LIMIT_LOGIN_ATTEMPTS", "3");
+    std::env::set_var("RATE_LIMIT_DISABLED", "0");
+
+    let app = common::create_test_app().await;
+    let test_ip = "192.168.7.10";
+    let email = format!(
+        "rate-headers-{}@example.com",
+        chrono::Utc::now().timestamp_millis()
+    );
+
+    for expected_remaining in [2, 1, 0] {
+        let response = login_response_with_ip(&app, &email, "WrongPassword", test_ip).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(header_value(&response, "x-ratelimit-limit"), 3);
+        assert_eq!(
+            header_value(&response, "x-ratelimit-remaining"),
+            expected_remaining
+        );
+        let reset = header_value(&response, "x-ratelimit-reset");
+        assert!(reset > 0 && reset <= 300, "unexpected reset: {}", reset);
+    }
+
+    let response = login_response_with_ip(&app, &email, "WrongPassword", test_ip).await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(header_value(&response, "x-ratelimit-remaining"), 0);
+    let retry_after = header_value(&response, "retry-after");
+    assert!(retry_after > 0 && retry_after <= 300);
+
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body["code"], "RATE_LIMITED");
+    assert_eq!(body["details"]["retry_after_seconds"], retry_after);
+
+    std::env::set_var("RATE_LIMIT_LOGIN_ATTEMPTS", "10");
+    flush_rate_limit_keys().await;
+}
+
+/// Скользящее окно не пропускает двойной всплеск на границе окна
+#[tokio::test]
+#[serial_test::serial]
+async fn test_sliding_window_rejects_boundary_burst() {
+    flush_rate_limit_keys().await;
+
+    std::env::set_var("RATE_LIMIT_ALGORITHM", "sliding");
+    std::env::set_var("RATE_LIMIT_LOGIN_ATTEMPTS", "3");
+    std::env::set_var("RATE_LIMIT_LOGIN_WINDOW_SECS", "2");
+    std::env::set_var("RATE_LIMIT_DISABLED", "0");
+
+    let app = common::create_test_app().await;
+    let test_ip = "192.168.7.20";
+    let email = format!(
+        "sliding-burst-{}@example.com",
+        chrono::Utc::now().timestamp_millis()
+    );
+
+    // t=0: первый запрос открывает окно фиксированного счётчика
+    let status = login_with_ip(&app, &email, "WrongPassword", test_ip).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // t=1.5s: ещё два запроса в конце окна
+    tokio::time::sleep(tokio::time::Duration::from_millis(1500)).await;
+    for _ in 0..2 {
+        let status = login_with_ip(&app, &email, "WrongPassword", test_ip).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    // t=2.3s: фиксированное окно сбросилось бы и пропустило ещё три запроса,
+    // скользящее освобождает только слот первого запроса
+    tokio::time::sleep(tokio::time::Duration::from_millis(800)).await;
+    let status = login_with_ip(&app, &email, "WrongPassword", test_ip).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let status = login_with_ip(&app, &email, "WrongPassword", test_ip).await;
+    assert_eq!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS,
+        "Burst across the window boundary should be rejected"
+    );
+
+    std::env::remove_var("RATE_LIMIT_ALGORITHM");
+    std::env::remove_var("RATE_LIMIT_LOGIN_WINDOW_SECS");
+    std::env::set_var("RATE_LIMIT_LOGIN_ATTEMPTS", "10");
+    flush_rate_limit_keys().await;
+}
+
+async fn redis_connection() -> redis::aio::ConnectionManager {
+    let redis_uri = std::env::var("REDIS_URI")
+        .unwrap_or_else(|_| "redis://:changeMeRedis@127.0.0.1:6379/0".to_string());
+    redis::Client::open(redis_uri)
+        .expect("Failed to connect to Redis")
+        .get_connection_manager()
+        .await
+        .expect("Failed to get Redis connection")
+}
+
+/// Скрипт скользящего окна в Redis на ручных часах: всплеск на границе окна
+/// отклоняется, слот освобождается ровно через окно после первого запроса
+#[tokio::test]
+#[serial_test::serial]
+async fn test_sliding_window_script_rejects_boundary_burst() {
+    let redis = redis_connection().await;
+    let key = format!("ratelimit:test:{}", uuid::Uuid::new_v4());
+    let start = 1_000_000u64;
+
+    // 10 запросов в конце первой минуты
+    for _ in 0..10 {
+        let status = check_sliding_window(&redis, &key, 10, 60, start + 59_000)
+            .await
+            .unwrap();
+        assert!(status.allowed);
+    }
+
+    // Через 2 секунды фиксированное окно уже сбросилось бы
+    let status = check_sliding_window(&redis, &key, 10, 60, start + 61_000)
+        .await
+        .unwrap();
+    assert!(!status.allowed);
+    assert_eq!(status.remaining, 0);
+    assert_eq!(status.reset_after_secs, 58);
+
+    let status = check_sliding_window(&redis, &key, 10, 60, start + 119_000)
+        .await
+        .unwrap();
+    assert!(status.allowed);
+
+    flush_rate_limit_keys().await;
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_sliding_window_script_remaining_and_reset() {
+    let redis = redis_connection().await;
+    let key = format!("ratelimit:test:{}", uuid::Uuid::new_v4());
+    let start = 50_000u64;
+
+    let first = check_sliding_window(&redis, &key, 3, 10, start)
+        .await
+        .unwrap();
+    assert_eq!((first.remaining, first.reset_after_secs), (2, 10));
+
+    let second = check_sliding_window(&redis, &key, 3, 10, start + 4_500)
+        .await
+        .unwrap();
+    assert_eq!((second.remaining, second.reset_after_secs), (1, 6));
+
+    // Отметка ровно на границе окна уже не считается
+    let third = check_sliding_window(&redis, &key, 3, 10, start + 10_000)
+        .await
+        .unwrap();
+    assert!(third.allowed);
+    assert_eq!((third.remaining, third.reset_after_secs), (1, 5));
+
+    flush_rate_limit_keys().await;
+}
+
+/// Лимит сессий считается по пользователю из токена, а не по общему IP
+#[tokio::test]
+#[serial_test::serial]
+async fn test_sessions_rate_limit_is_per_user_behind_shared_ip() {
+    flush_rate_limit_keys().await;
+
+    std::env::set_var("RATE_LIMIT_PER_USER", "2");
+    std::env::set_var("RATE_LIMIT_PER_IP", "100");
+    std::env::set_var("RATE_LIMIT_DISABLED", "0");
+
+    let state = common::create_test_state().await;
+    let jwt = JwtService::from_config(&state.config);
+    let app = create_router(std::sync::Arc::new(state));
+    let now = chrono::Utc::now().timestamp() as usize;
+    let token = |sub: &str| {
+        jwt.generate_token(JwtClaims {
+            sub: sub.to_string(),
+            role: "student".to_string(),
+            group_ids: vec![],
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap()
+    };
+    let get_session = |token: String| {
+        let app = app.clone();
+        async move {
+            app.oneshot(
+                Request::builder()
+                    .uri(format!("/api/v1/sessions/{}", uuid::Uuid::new_v4()))
+                    .header("authorization", format!("Bearer {}", token))
+                    .header("x-forwarded-for", "192.168.7.30")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+            .status()
+        }
+    };
+
+    let (first, second) = (
+        token(&uuid::Uuid::new_v4().to_string()),
+        token(&uuid::Uuid::new_v4().to_string()),
+    );
+    for _ in 0..2 {
+        assert_ne!(
+            get_session(first.clone()).await,
+            StatusCode::TOO_MANY_REQUESTS
+        );
+    }
+    assert_eq!(get_session(first).await, StatusCode::TOO_MANY_REQUESTS);
+    assert_ne!(
+        get_session(second).await,
+        StatusCode::TOO_MANY_REQUESTS,
+        "Another user behind the same IP has its own counter"
+    );
+
+    std::env::remove_var("RATE_LIMIT_PER_USER");
+    std::env::remove_var("RATE_LIMIT_PER_IP");
+    flush_rate_limit_keys().await;
+}
@@ -0,0 +1,432 @@
+mod common;
+
+use std::{sync::Arc, time::Duration};
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use chrono::Utc;
+use mongodb::bson::{doc, oid::ObjectId, Document};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use trainingground_api::{
+    create_router,
+    middlewares::auth::{JwtClaims, JwtService},
+    models::reporting::{
+        ExportFormat, ExportLocale, ExportScope, ExportStatus, NewReportExport, ReportExport,
+        ReportFilters, TimeRange,
+    },
+    services::{
+        reporting_service::{ExportLinkSigner, ReportingService},
+        AppState,
+    },
+};
+
+/// Подписывает ссылки предсказуемо, чтобы тест мог сравнить URL целиком
+struct StubSigner;
+
+impl ExportLinkSigner for StubSigner {
+    fn presigned_download_url(&self, key: &str, ttl: Duration) -> anyhow::Result<String> {
+        Ok(format!(
+            "https://downloads.test/{}?expires={}",
+            key,
+            ttl.as_secs()
+        ))
+    }
+}
+
+async fn stubbed_state() -> Arc<AppState> {
+    let mut state = common::create_test_state().await;
+    state.export_links = Some(Arc::new(StubSigner));
+    Arc::new(state)
+}
+
+fn token(state: &AppState, role: &str, group_ids: Vec<String>) -> String {
+    token_for(state, &ObjectId::new(), role, group_ids)
+}
+
+fn token_for(state: &AppState, user_id: &ObjectId, role: &str, group_ids: Vec<String>) -> String {
+    let now = Utc::now().timestamp() as usize;
+    JwtService::new(&state.config.jwt_secret)
+        .generate_token(JwtClaims {
+            sub: user_id.to_hex(),
+            role: role.to_string(),
+            group_ids,
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap()
+}
+
+async fn seed_export(state: &AppState, group_id: ObjectId, ready: bool) -> ReportExport {
+    let service = ReportingService::new(state.mongo.clone(), state.redis.clone());
+    let now = Utc::now();
+    let export = service
+        .create_export_request(NewReportExport {
+            scope: ExportScope::Group,
+            subject_id: group_id,
+            teacher_id: ObjectId::new(),
+            format: ExportFormat::Pdf,
+            filters: ReportFilters {
+                topic_ids: Vec::new(),
+                period: TimeRange {
+                    from: now - chrono::Duration::days(7),
+                    to: now,
+                },
+                fields: Vec::new(),
+                reveal_user_ids: false,
+                locale: ExportLocale::default(),
+            },
+            expires_at: now + chrono::Duration::hours(24),
+        })
+        .await
+        .unwrap();
+
+    if ready {
+        let key = format!("groups/{}/{}.pdf", group_id.to_hex(), export.id.to_hex());
+        service
+            .update_export_status(&export.id, ExportStatus::Ready, Some(&key), None)
+            .await
+            .unwrap();
+    }
+    export
+}
+
+async fn get(app: &Router, token: &str, uri: &str) -> (StatusCode, Value) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(uri)
+                .header("authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+async fn get_csrf_token(app: &Router) -> (String, String) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/api/v1/auth/csrf-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let csrf_cookie = response
+        .headers()
+        .get_all("set-cookie")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .find(|s| s.starts_with("csrf_token="))
+        .and_then(|s| s.split(';').next())
+        .and_then(|part| part.split('=').nth(1))
+        .unwrap_or_default()
+        .to_string();
+
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    (
+        json["csrf_token"].as_str().unwrap().to_string(),
+        csrf_cookie,
+    )
+}
+
+async fn request_export(
+    app: &Router,
+    token: &str,
+    csrf: &(String, String),
+    group_id: &ObjectId,
+    body: Value,
+) -> (StatusCode, Value) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(format!("/stats/groups/{}/export", group_id.to_hex()))
+                .header("authorization", format!("Bearer {}", token))
+                .header("x-csrf-token", &csrf.0)
+                .header("cookie", format!("csrf_token={}", csrf.1))
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (
+        status,
+        serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+    )
+}
+
+#[tokio::test]
+async fn test_ready_export_returns_signed_download_url() {
+    let state = stubbed_state().await;
+    let app = create_router(state.clone());
+    let group_id = ObjectId::new();
+    let export = seed_export(&state, group_id, true).await;
+    let uri = format!("/stats/exports/{}", export.id.to_hex());
+
+    let teacher = token(&state, "teacher", vec![group_id.to_hex()]);
+    let (status, body) = get(&app, &teacher, &uri).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "ready");
+    let ttl = state
+        .config
+        .object_storage
+        .as_ref()
+        .map(|settings| settings.presign_ttl_secs)
+        .unwrap_or(3600);
+    assert_eq!(
+        body["download_url"],
+        format!(
+            "https://downloads.test/groups/{}/{}.pdf?expires={}",
+            group_id.to_hex(),
+            export.id.to_hex(),
+            ttl
+        )
+    );
+
+    let admin = token(&state, "admin", Vec::new());
+    let (status, body) = get(&app, &admin, &uri).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["download_url"].is_string());
+
+    // Учитель другой группы и ученик ссылку не получают
+    let outsider = token(&state, "teacher", vec![ObjectId::new().to_hex()]);
+    let (status, _) = get(&app, &outsider, &uri).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let student = token(&state, "student", vec![group_id.to_hex()]);
+    let (status, _) = get(&app, &student, &uri).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = get(
+        &app,
+        &teacher,
+        &format!("/stats/exports/{}", ObjectId::new().to_hex()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_pending_export_has_no_download_url() {
+    let state = stubbed_state().await;
+    let app = create_router(state.clone());
+    let group_id = ObjectId::new();
+    let export = seed_export(&state, group_id, false).await;
+
+    let teacher = token(&state, "teacher", vec![group_id.to_hex()]);
+    let (status, body) = get(
+        &app,
+        &teacher,
+        &format!("/stats/exports/{}", export.id.to_hex()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "pending");
+    assert!(body["download_url"].is_null());
+}
+
+#[tokio::test]
+async fn test_group_exports_are_paginated() {
+    let state = stubbed_state().await;
+    let app = create_router(state.clone());
+    let group_id = ObjectId::new();
+    for ready in [true, false, true] {
+        seed_export(&state, group_id, ready).await;
+    }
+    // Выгрузка другой группы в список не попадает
+    seed_export(&state, ObjectId::new(), true).await;
+
+    let teacher = token(&state, "teacher", vec![group_id.to_hex()]);
+    let uri = format!("/stats/groups/{}/exports", group_id.to_hex());
+
+    let (status, body) = get(&app, &teacher, &format!("{}?limit=2", uri)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["total"], 3);
+    assert_eq!(body["limit"], 2);
+    assert_eq!(body["exports"].as_array().unwrap().len(), 2);
+
+    let (status, body) = get(&app, &teacher, &format!("{}?limit=2&offset=2", uri)).await;
+    assert_eq!(status, StatusCode::OK);
+    let page = body["exports"].as_array().unwrap();
+    assert_eq!(page.len(), 1);
+    // Новые первыми: на второй странице самая старая, готовая выгрузка
+    assert_eq!(page[0]["status"], "ready");
+    assert!(page[0]["download_url"]
+        .as_str()
+        .unwrap()
+        .starts_with("https://downloads.test/"));
+
+    let outsider = token(&state, "teacher", vec![ObjectId::new().to_hex()]);
+    let (status, _) = get(&app, &outsider, &uri).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_teacher_exports_own_group_with_default_period() {
+    let state = stubbed_state().await;
+    let app = create_router(state.clone());
+    let csrf = get_csrf_token(&app).await;
+
+    // Учитель - куратор группы, но группы нет в его токене
+    let teacher_id = ObjectId::new();
+    let group_id = ObjectId::new();
+    state
+        .mongo
+        .collection::<Document>("groups")
+        .insert_one(doc! { "_id": group_id, "name": "8Б", "curatorId": teacher_id })
+        .await
+        .unwrap();
+    let teacher = token_for(&state, &teacher_id, "teacher", Vec::new());
+
+    let (status, body) = request_export(
+        &app,
+        &teacher,
+        &csrf,
+        &group_id,
+        json!({ "format": "xlsx" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["status"], "pending");
+    let export_id = ObjectId::parse_str(body["export_id"].as_str().unwrap()).unwrap();
+    assert_eq!(
+        body["status_url"],
+        format!("/stats/exports/{}", export_id.to_hex())
+    );
+
+    let export = ReportingService::new(state.mongo.clone(), state.redis.clone())
+        .get_export_by_id(&export_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(export.format, ExportFormat::Xlsx);
+    assert_eq!(export.teacher_id, teacher_id);
+    let period = &export.filters.period;
+    assert_eq!(period.to - period.from, chrono::Duration::days(30));
+    assert!(period.to >= Utc::now());
+
+    // По ссылке из ответа учитель видит статус своей выгрузки
+    let (status, body) = get(&app, &teacher, body["status_url"].as_str().unwrap()).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["export_id"], export_id.to_hex());
+}
+
+#[tokio::test]
+async fn test_export_of_foreign_group_is_rejected() {
+    let state = stubbed_state().await;
+    let app = create_router(state.clone());
+    let csrf = get_csrf_token(&app).await;
+    let group_id = ObjectId::new();
+    state
+        .mongo
+        .collection::<Document>("groups")
+        .insert_one(doc! { "_id": group_id, "name": "9В", "curatorId": ObjectId::new() })
+        .await
+        .unwrap();
+
+    let body = json!({ "format": "csv" });
+    let outsider = token(&state, "teacher", vec![ObjectId::new().to_hex()]);
+    let (status, _) = request_export(&app, &outsider, &csrf, &group_id, body.clone()).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let student = token(&state, "student", vec![group_id.to_hex()]);
+    let (status, _) = request_export(&app, &student, &csrf, &group_id, body).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Перевёрнутый период отклоняется ещё до постановки в очередь
+    let teacher = token(&state, "teacher", vec![group_id.to_hex()]);
+    let now = Utc::now();
+    let (status, _) = request_export(
+        &app,
+        &teacher,
+        &csrf,
+        &group_id,
+        json!({ "format": "pdf", "period": { "from": now, "to": now - chrono::Duration::days(1) } }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_duplicate_pending_export_is_suppressed() {
+    let state = stubbed_state().await;
+    let app = create_router(state.clone());
+    let csrf = get_csrf_token(&app).await;
+    let group_id = ObjectId::new();
+    let teacher = token(&state, "teacher", vec![group_id.to_hex()]);
+    let now = Utc::now();
+    let period = json!({ "from": now - chrono::Duration::days(7), "to": now });
+
+    let (status, first) = request_export(
+        &app,
+        &teacher,
+        &csrf,
+        &group_id,
+        json!({ "format": "pdf", "period": period }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{first}");
+
+    let (status, duplicate) = request_export(
+        &app,
+        &teacher,
+        &csrf,
+        &group_id,
+        json!({ "format": "pdf", "period": period }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT, "{duplicate}");
+    assert_eq!(duplicate["code"], "EXPORT_ALREADY_PENDING");
+    assert_eq!(duplicate["details"]["export_id"], first["export_id"]);
+    assert_eq!(duplicate["details"]["status_url"], first["status_url"]);
+
+    // Другой формат - отдельная выгрузка
+    let (status, body) = request_export(
+        &app,
+        &teacher,
+        &csrf,
+        &group_id,
+        json!({ "format": "csv", "period": period }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    // Собранная выгрузка больше не считается дублем
+    let export_id = ObjectId::parse_str(first["export_id"].as_str().unwrap()).unwrap();
+    ReportingService::new(state.mongo.clone(), state.redis.clone())
+        .update_export_status(
+            &export_id,
+            ExportStatus::Ready,
+            Some("groups/done.pdf"),
+            None,
+        )
+        .await
+        .unwrap();
+    let (status, body) = request_export(
+        &app,
+        &teacher,
+        &csrf,
+        &group_id,
+        json!({ "format": "pdf", "period": period }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_ne!(body["export_id"], first["export_id"]);
+}
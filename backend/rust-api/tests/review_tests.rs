@@ -0,0 +1,259 @@
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use chrono::Utc;
+use mongodb::bson::{doc, oid::ObjectId, Document};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use trainingground_api::{
+    create_router,
+    middlewares::auth::{JwtClaims, JwtService},
+    models::review::ReviewItem,
+    services::{
+        review_service::{GRADUATION_REPETITIONS, REVIEW_QUEUE_COLLECTION},
+        AppState,
+    },
+};
+use uuid::Uuid;
+
+mod common;
+
+fn student_jwt(state: &AppState, user_id: &str) -> String {
+    let now = Utc::now().timestamp() as usize;
+    JwtService::from_config(&state.config)
+        .generate_token(JwtClaims {
+            sub: user_id.to_string(),
+            role: "student".to_string(),
+            group_ids: Vec::new(),
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap()
+}
+
+async fn send(
+    app: &axum::Router,
+    method: &str,
+    uri: &str,
+    token: Option<&str>,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let csrf_token = Uuid::new_v4().to_string();
+    let mut request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .header("x-csrf-token", &csrf_token)
+        .header("cookie", format!("csrf_token={}", csrf_token));
+    if let Some(token) = token {
+        request = request.header("authorization", format!("Bearer {}", token));
+    }
+    let body = body.map(|body| Body::from(body.to_string()));
+
+    let response = app
+        .clone()
+        .oneshot(request.body(body.unwrap_or_else(Body::empty)).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or_default())
+}
+
+/// Шаблон и сгенерированное по нему задание с ответом "42"
+async fn insert_template_task(state: &AppState) -> (ObjectId, ObjectId) {
+    let template_id = ObjectId::new();
+    let task_id = ObjectId::new();
+    state
+        .mongo
+        .collection::<Document>("templates")
+        .insert_one(doc! {
+            "_id": template_id,
+            "slug": format!("review-{}", Uuid::new_v4().simple()),
+            "level_id": ObjectId::new(),
+            "params": {},
+        })
+        .await
+        .unwrap();
+    state
+        .mongo
+        .collection::<Document>("tasks")
+        .insert_one(doc! {
+            "_id": task_id,
+            "template_id": template_id,
+            "title": "Задание на повторение",
+            "description": "Сколько будет 6 * 7?",
+            "time_limit_seconds": 300,
+            "correct_answer": "42",
+        })
+        .await
+        .unwrap();
+    (template_id, task_id)
+}
+
+async fn answer_in_new_session(
+    app: &axum::Router,
+    token: &str,
+    session_body: Value,
+    answer: &str,
+) -> (StatusCode, Value) {
+    let (status, session) = send(
+        app,
+        "POST",
+        "/api/v1/sessions",
+        Some(token),
+        Some(session_body),
+    )
+    .await;
+    if status != StatusCode::CREATED {
+        return (status, session);
+    }
+    let session_id = session["session_id"].as_str().unwrap();
+    let (status, body) = send(
+        app,
+        "POST",
+        &format!("/api/v1/sessions/{}/answers", session_id),
+        None,
+        Some(json!({ "answer": answer })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    (
+        StatusCode::CREATED,
+        json!({ "session": session, "answer": body }),
+    )
+}
+
+#[tokio::test]
+async fn test_review_queue_lifecycle() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+    let user_id = ObjectId::new().to_hex();
+    let token = student_jwt(&state, &user_id);
+    let (template_id, task_id) = insert_template_task(&state).await;
+
+    // Верный ответ в очередь не попадает
+    let (status, _) = answer_in_new_session(
+        &app,
+        &token,
+        json!({ "user_id": user_id, "task_id": task_id.to_hex() }),
+        "42",
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, body) = send(&app, "GET", "/api/v1/review/next", Some(&token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["total_due"], 0);
+
+    // Ошибка ставит шаблон в очередь, повторять можно сразу
+    let (status, _) = answer_in_new_session(
+        &app,
+        &token,
+        json!({ "user_id": user_id, "task_id": task_id.to_hex() }),
+        "41",
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, body) = send(&app, "GET", "/api/v1/review/next", Some(&token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["total_due"], 1);
+    assert_eq!(body["items"][0]["template_id"], template_id.to_hex());
+    assert_eq!(body["items"][0]["task_id"], task_id.to_hex());
+    assert_eq!(body["items"][0]["repetitions"], 0);
+
+    // Сессия повторения берёт задание из очереди; расписание двигает проверенный ответ
+    let review_session = json!({ "user_id": user_id, "mode": "review" });
+    let (status, body) = answer_in_new_session(&app, &token, review_session.clone(), "41").await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body["session"]["task"]["id"], task_id.to_hex());
+    let stored = review_item(&state, &user_id, template_id).await.unwrap();
+    assert_eq!(stored.lapses, 2);
+    assert_eq!(stored.interval_days, 1);
+    assert!(stored.due_at > Utc::now());
+    let (_, body) = send(&app, "GET", "/api/v1/review/next", Some(&token), None).await;
+    assert_eq!(body["total_due"], 0);
+
+    // Клиент больше не сообщает результат сам
+    let (status, _) = send(
+        &app,
+        "POST",
+        &format!("/api/v1/review/{}/result", template_id.to_hex()),
+        Some(&token),
+        Some(json!({ "correct": true })),
+    )
+    .await;
+    assert!(status == StatusCode::NOT_FOUND || status == StatusCode::METHOD_NOT_ALLOWED);
+
+    // Несколько верных повторений подряд убирают шаблон из очереди
+    let mut intervals = Vec::new();
+    for _ in 0..GRADUATION_REPETITIONS {
+        make_due(&state, &user_id, template_id).await;
+        let (status, _) = answer_in_new_session(&app, &token, review_session.clone(), "42").await;
+        assert_eq!(status, StatusCode::CREATED);
+        if let Some(item) = review_item(&state, &user_id, template_id).await {
+            intervals.push(item.interval_days);
+        }
+    }
+    assert_eq!(intervals.len(), GRADUATION_REPETITIONS as usize - 1);
+    assert_eq!(intervals[..2], [1, 6]);
+    assert!(intervals.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!(review_item(&state, &user_id, template_id).await.is_none());
+
+    let (status, body) = send(
+        &app,
+        "POST",
+        "/api/v1/sessions",
+        Some(&token),
+        Some(review_session),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], "REVIEW_QUEUE_EMPTY");
+}
+
+async fn review_item(state: &AppState, user_id: &str, template_id: ObjectId) -> Option<ReviewItem> {
+    state
+        .mongo
+        .collection::<ReviewItem>(REVIEW_QUEUE_COLLECTION)
+        .find_one(doc! { "user_id": user_id, "template_id": template_id })
+        .await
+        .unwrap()
+}
+
+/// Срок повторения наступил: следующая сессия повторения возьмёт шаблон
+async fn make_due(state: &AppState, user_id: &str, template_id: ObjectId) {
+    state
+        .mongo
+        .collection::<Document>(REVIEW_QUEUE_COLLECTION)
+        .update_one(
+            doc! { "user_id": user_id, "template_id": template_id },
+            doc! { "$set": { "due_at": mongodb::bson::DateTime::now() } },
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_review_session_requires_due_items() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+    let user_id = ObjectId::new().to_hex();
+    let token = student_jwt(&state, &user_id);
+
+    let (status, body) = send(
+        &app,
+        "POST",
+        "/api/v1/sessions",
+        Some(&token),
+        Some(json!({ "user_id": user_id, "mode": "review" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], "REVIEW_QUEUE_EMPTY");
+
+    let (status, _) = send(&app, "GET", "/api/v1/review/next", None, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,570 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use chrono::{Duration, Utc};
+use mongodb::bson::{doc, oid::ObjectId, DateTime as BsonDateTime, Document};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use trainingground_api::{
+    config::ArchiveSettings,
+    create_router,
+    models::{
+        answer::AttemptRecord,
+        session_archive::{SessionArchiveStub, SessionRecord},
+        SessionMode, SessionStatus,
+    },
+    services::{
+        archive_worker::ArchiveWorker,
+        session_archive_service::{ArchiveStorage, SessionArchiveService},
+        AppState,
+    },
+};
+
+mod common;
+
+/// In-memory storage that records every ranged read
+#[derive(Default)]
+struct MockArchiveStorage {
+    objects: Mutex<HashMap<String, Vec<u8>>>,
+    reads: Mutex<Vec<(String, u64, u64)>>,
+}
+
+impl MockArchiveStorage {
+    fn object_len(&self, key: &str) -> usize {
+        self.objects.lock().unwrap()[key].len()
+    }
+
+    fn reads(&self) -> Vec<(String, u64, u64)> {
+        self.reads.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl ArchiveStorage for MockArchiveStorage {
+    async fn put_bundle(&self, key: &str, bytes: Vec<u8>) -> anyhow::Result<()> {
+        self.objects.lock().unwrap().insert(key.to_string(), bytes);
+        Ok(())
+    }
+
+    async fn read_range(&self, key: &str, offset: u64, length: u64) -> anyhow::Result<Vec<u8>> {
+        self.reads
+            .lock()
+            .unwrap()
+            .push((key.to_string(), offset, length));
+        let objects = self.objects.lock().unwrap();
+        let bytes = objects
+            .get(key)
+            .ok_or_else(|| anyhow::anyhow!("object {} not found", key))?;
+        Ok(bytes[offset as usize..(offset + length) as usize].to_vec())
+    }
+
+    async fn read_object(&self, key: &str) -> anyhow::Result<Vec<u8>> {
+        self.objects
+            .lock()
+            .unwrap()
+            .get(key)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("object {} not found", key))
+    }
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_archive_moves_old_sessions_and_keeps_aggregates() {
+    let state = common::create_test_state().await;
+    let db = state.mongo.clone();
+    let storage = Arc::new(MockArchiveStorage::default());
+
+    let group_a = ObjectId::new().to_hex();
+    let group_b = ObjectId::new().to_hex();
+    let old_a1 = seed_session(&db, Some(&group_a), 400, 2).await;
+    let old_a2 = seed_session(&db, Some(&group_a), 400, 1).await;
+    let old_b = seed_session(&db, Some(&group_b), 500, 3).await;
+    let recent = seed_session(&db, Some(&group_a), 10, 1).await;
+
+    let user_id = format!("archive-user-{}", uuid::Uuid::new_v4());
+    let progress = doc! {
+        "_id": format!("progress-{}", user_id),
+        "user_id": &user_id,
+        "level_id": "archive-level",
+        "attempts_total": 6,
+        "correct_count": 4,
+        "score": 40,
+    };
+    db.collection::<Document>("progress_summary")
+        .insert_one(progress.clone())
+        .await
+        .unwrap();
+
+    let worker = archive_worker(&state, storage.clone(), 2);
+    let summary = worker.run_once().await.unwrap().expect("lock is free");
+    assert!(summary.sessions_archived >= 3);
+    assert!(summary.attempts_archived >= 6);
+    assert!(summary.batches >= 2, "batch size 2 must split the run");
+
+    for (session_id, attempts) in [(&old_a1, 2), (&old_a2, 1), (&old_b, 3)] {
+        let stub = load_stub(&db, session_id).await.expect("stub stored");
+        assert_eq!(stub.attempt_count, attempts);
+        assert!(stub.length > 0);
+        assert_eq!(
+            count(&db, "sessions", doc! { "_id": session_id.as_str() }).await,
+            0
+        );
+        assert_eq!(
+            count(
+                &db,
+                "attempt_records",
+                doc! { "session_id": session_id.as_str() }
+            )
+            .await,
+            0
+        );
+    }
+
+    // Бандлы разложены по месяцу и группе; сессии одной группы/месяца - в одном бандле
+    let stub_a1 = load_stub(&db, &old_a1).await.unwrap();
+    let stub_a2 = load_stub(&db, &old_a2).await.unwrap();
+    let stub_b = load_stub(&db, &old_b).await.unwrap();
+    assert!(stub_a1.archive_key.contains(&format!("/{}/", group_a)));
+    assert!(stub_b.archive_key.contains(&format!("/{}/", group_b)));
+    assert!(stub_a1
+        .archive_key
+        .contains(&stub_a1.started_at.format("%Y-%m").to_string()));
+    assert_ne!(stub_a1.archive_key, stub_b.archive_key);
+    if stub_a1.archive_key == stub_a2.archive_key {
+        assert_ne!(stub_a1.offset, stub_a2.offset);
+    }
+
+    // Свежая сессия и агрегаты не тронуты
+    assert_eq!(
+        count(&db, "sessions", doc! { "_id": recent.as_str() }).await,
+        1
+    );
+    let stored_progress = db
+        .collection::<Document>("progress_summary")
+        .find_one(doc! { "user_id": &user_id })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored_progress, progress);
+
+    cleanup(&db, &[&old_a1, &old_a2, &old_b, &recent]).await;
+    db.collection::<Document>("progress_summary")
+        .delete_many(doc! { "user_id": &user_id })
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_archive_run_skipped_while_lock_is_held() {
+    let state = common::create_test_state().await;
+    let db = state.mongo.clone();
+    let storage = Arc::new(MockArchiveStorage::default());
+    let session_id = seed_session(&db, None, 400, 1).await;
+
+    let mut conn = state.redis.clone();
+    redis::cmd("SET")
+        .arg("lock:session_archive")
+        .arg("another-worker")
+        .arg("PX")
+        .arg(60_000)
+        .query_async::<()>(&mut conn)
+        .await
+        .unwrap();
+
+    let worker = archive_worker(&state, storage.clone(), 10);
+    assert!(worker.run_once().await.unwrap().is_none());
+    assert_eq!(
+        count(&db, "sessions", doc! { "_id": session_id.as_str() }).await,
+        1
+    );
+
+    redis::cmd("DEL")
+        .arg("lock:session_archive")
+        .query_async::<()>(&mut conn)
+        .await
+        .unwrap();
+
+    // После освобождения блокировки запуск проходит и сам её отпускает
+    assert!(worker.run_once().await.unwrap().is_some());
+    assert!(load_stub(&db, &session_id).await.is_some());
+    let lock: Option<String> = redis::cmd("GET")
+        .arg("lock:session_archive")
+        .query_async(&mut conn)
+        .await
+        .unwrap();
+    assert!(lock.is_none());
+
+    cleanup(&db, &[&session_id]).await;
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_rehydrate_reads_only_the_session_range() {
+    let mut state = common::create_test_state().await;
+    let db = state.mongo.clone();
+    let storage = Arc::new(MockArchiveStorage::default());
+    state.archive_storage = Some(storage.clone() as Arc<dyn ArchiveStorage>);
+
+    let group_id = ObjectId::new().to_hex();
+    let first = seed_session(&db, Some(&group_id), 400, 2).await;
+    let second = seed_session(&db, Some(&group_id), 400, 4).await;
+    archive_worker(&state, storage.clone(), 10)
+        .run_once()
+        .await
+        .unwrap()
+        .unwrap();
+
+    let app = create_router(Arc::new(state));
+
+    let (status, body) = get_session(&app, &second).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["code"], "ARCHIVED_PENDING_REHYDRATION");
+    assert!(body["details"]["rehydrate"]
+        .as_str()
+        .unwrap()
+        .contains(&format!("/admin/sessions/{}/rehydrate", second)));
+
+    let (_admin_id, admin_token) = create_admin_with_token(&app).await;
+    let (status, body) = rehydrate(&app, &admin_token, &second).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["session_id"], second.as_str());
+    assert_eq!(body["attempt_count"], 4);
+
+    let stub = load_stub(&db, &second).await.unwrap();
+    let reads = storage.reads();
+    assert_eq!(reads.len(), 1);
+    assert_eq!(
+        reads[0],
+        (stub.archive_key.clone(), stub.offset, stub.length)
+    );
+    assert!((stub.length as usize) < storage.object_len(&stub.archive_key));
+
+    let (status, body) = get_session(&app, &second).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["id"], second.as_str());
+    assert_eq!(body["status"], "completed");
+
+    let restored = db
+        .collection::<Document>("sessions_rehydrated")
+        .find_one(doc! { "_id": second.as_str() })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(restored.get_array("attempts").unwrap().len(), 4);
+
+    // Неизвестная сессия - 404 как раньше
+    let (status, body) = rehydrate(&app, &admin_token, "missing-session").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], "ARCHIVED_SESSION_NOT_FOUND");
+
+    cleanup(&db, &[&first, &second]).await;
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_expired_rehydrated_copy_is_cleaned_up() {
+    let mut state = common::create_test_state().await;
+    let db = state.mongo.clone();
+    let storage = Arc::new(MockArchiveStorage::default());
+    state.archive_storage = Some(storage.clone() as Arc<dyn ArchiveStorage>);
+
+    let session_id = seed_session(&db, None, 400, 1).await;
+    archive_worker(&state, storage.clone(), 10)
+        .run_once()
+        .await
+        .unwrap()
+        .unwrap();
+
+    let service = SessionArchiveService::new(db.clone());
+    service
+        .rehydrate(storage.as_ref(), &session_id, Duration::hours(1))
+        .await
+        .unwrap();
+
+    let ttl_index = db
+        .collection::<Document>("sessions_rehydrated")
+        .list_indexes()
+        .await
+        .unwrap();
+    let indexes: Vec<_> = futures::TryStreamExt::try_collect(ttl_index).await.unwrap();
+    assert!(indexes.iter().any(|index| {
+        index.keys == doc! { "expires_at": 1 }
+            && index
+                .options
+                .as_ref()
+                .and_then(|options| options.expire_after)
+                .is_some()
+    }));
+
+    // Копия истекла: чтение снова требует восстановления, очистка её удаляет
+    db.collection::<Document>("sessions_rehydrated")
+        .update_one(
+            doc! { "_id": session_id.as_str() },
+            doc! { "$set": { "expires_at": BsonDateTime::from_millis(
+                (Utc::now() - Duration::minutes(5)).timestamp_millis()
+            ) } },
+        )
+        .await
+        .unwrap();
+
+    let app = create_router(Arc::new(state));
+    let (status, _) = get_session(&app, &session_id).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    assert!(service.purge_expired_rehydrated().await.unwrap() >= 1);
+    assert_eq!(
+        count(
+            &db,
+            "sessions_rehydrated",
+            doc! { "_id": session_id.as_str() }
+        )
+        .await,
+        0
+    );
+    assert!(load_stub(&db, &session_id).await.is_some());
+
+    cleanup(&db, &[&session_id]).await;
+}
+
+fn archive_worker(
+    state: &AppState,
+    storage: Arc<MockArchiveStorage>,
+    batch_size: i64,
+) -> ArchiveWorker {
+    let settings = ArchiveSettings {
+        session_age_days: 365,
+        batch_size,
+        ..ArchiveSettings::default()
+    };
+    ArchiveWorker::new(
+        SessionArchiveService::new(state.mongo.clone()),
+        storage,
+        state.redis.clone(),
+        settings,
+    )
+}
+
+async fn seed_session(
+    db: &mongodb::Database,
+    group_id: Option<&str>,
+    age_days: i64,
+    attempts: usize,
+) -> String {
+    let session_id = format!("archive-session-{}", uuid::Uuid::new_v4());
+    let started_at = Utc::now() - Duration::days(age_days);
+    let record = SessionRecord {
+        id: session_id.clone(),
+        user_id: "archive-user".into(),
+        task_id: "test-task".into(),
+        group_id: group_id.map(str::to_string),
+        level_id: None,
+        started_at,
+        expires_at: started_at + Duration::hours(1),
+        completed_at: Some(started_at + Duration::minutes(30)),
+        status: SessionStatus::Completed,
+        hints_used: 0,
+        score: 10 * attempts as i32,
+        mode: SessionMode::Normal,
+        attempts_used: None,
+    };
+    db.collection::<SessionRecord>("sessions")
+        .insert_one(&record)
+        .await
+        .unwrap();
+
+    for n in 0..attempts {
+        let attempt = AttemptRecord {
+            id: format!("{}-attempt-{}", session_id, n),
+            session_id: session_id.clone(),
+            user_id: "archive-user".into(),
+            task_id: "test-task".into(),
+            answer: "42".into(),
+            correct: true,
+            score: 10,
+            timestamp: started_at + Duration::minutes(n as i64),
+            reason: None,
+            credit: None,
+            timing: None,
+        };
+        db.collection::<AttemptRecord>("attempt_records")
+            .insert_one(&attempt)
+            .await
+            .unwrap();
+    }
+
+    session_id
+}
+
+async fn load_stub(db: &mongodb::Database, session_id: &str) -> Option<SessionArchiveStub> {
+    db.collection::<SessionArchiveStub>("session_archive_stubs")
+        .find_one(doc! { "_id": session_id })
+        .await
+        .unwrap()
+}
+
+async fn count(db: &mongodb::Database, collection: &str, filter: Document) -> u64 {
+    db.collection::<Document>(collection)
+        .count_documents(filter)
+        .await
+        .unwrap()
+}
+
+async fn cleanup(db: &mongodb::Database, session_ids: &[&String]) {
+    let ids: Vec<&str> = session_ids.iter().map(|id| id.as_str()).collect();
+    for collection in ["sessions", "session_archive_stubs", "sessions_rehydrated"] {
+        db.collection::<Document>(collection)
+            .delete_many(doc! { "_id": { "$in": &ids } })
+            .await
+            .unwrap();
+    }
+    db.collection::<Document>("attempt_records")
+        .delete_many(doc! { "session_id": { "$in": &ids } })
+        .await
+        .unwrap();
+}
+
+async fn get_session(app: &axum::Router, session_id: &str) -> (StatusCode, Value) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(format!("/api/v1/sessions/{}", session_id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, json_from_bytes(&bytes))
+}
+
+async fn rehydrate(app: &axum::Router, admin_token: &str, session_id: &str) -> (StatusCode, Value) {
+    let (csrf_token, csrf_cookie) = get_csrf_token(app).await;
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(format!("/admin/sessions/{}/rehydrate", session_id))
+                .header("authorization", format!("Bearer {}", admin_token))
+                .header("x-csrf-token", &csrf_token)
+                .header("cookie", format!("csrf_token={}", csrf_cookie))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, json_from_bytes(&bytes))
+}
+
+async fn create_admin_with_token(app: &axum::Router) -> (String, String) {
+    let body = json!({
+        "email": format!("archive-admin-{}@test.com", uuid::Uuid::new_v4()),
+        "password": "Admin123!@#",
+        "name": "Admin Archive",
+    });
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/auth/register")
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json = json_from_bytes(&bytes);
+    let user_id = json["user"]["id"].as_str().unwrap().to_string();
+
+    promote_user_to_admin(&user_id).await;
+
+    let login_body = json!({
+        "email": json["user"]["email"].as_str().unwrap(),
+        "password": "Admin123!@#",
+    });
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/auth/login")
+                .header("content-type", "application/json")
+                .body(Body::from(login_body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json = json_from_bytes(&bytes);
+    let token = json["access_token"].as_str().unwrap().to_string();
+
+    (user_id, token)
+}
+
+async fn promote_user_to_admin(user_id: &str) {
+    let config = trainingground_api::config::Config::load().unwrap();
+    let mongo_client = mongodb::Client::with_uri_str(&config.mongo_uri)
+        .await
+        .unwrap();
+    mongo_client
+        .database(&config.mongo_database)
+        .collection::<Document>("users")
+        .update_one(
+            doc! { "_id": ObjectId::parse_str(user_id).unwrap() },
+            doc! { "$set": { "role": "admin" } },
+        )
+        .await
+        .unwrap();
+}
+
+async fn get_csrf_token(app: &axum::Router) -> (String, String) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/api/v1/auth/csrf-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let csrf_cookie = response
+        .headers()
+        .get_all("set-cookie")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .find(|h| h.starts_with("csrf_token="))
+        .and_then(|pair| pair.split(';').next())
+        .and_then(|part| part.split('=').nth(1))
+        .unwrap_or("")
+        .to_string();
+
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json = json_from_bytes(&body);
+    let csrf_token = json["csrf_token"].as_str().unwrap().to_string();
+    (csrf_token, csrf_cookie)
+}
+
+fn json_from_bytes(bytes: &[u8]) -> Value {
+    let body_str = std::str::from_utf8(bytes).unwrap();
+    serde_json::from_str(body_str).unwrap()
+}
@@ -2,8 +2,12 @@ use axum::{
     body::{to_bytes, Body},
     http::{Request, StatusCode},
 };
+use mongodb::bson::{doc, oid::ObjectId, DateTime as BsonDateTime, Document};
 use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
 use tower::ServiceExt;
+use trainingground_api::{config::Config, create_router};
 use uuid::Uuid;
 
 mod common;
@@ -88,6 +92,245 @@ async fn test_create_session_unknown_task_returns_404() {
     assert_eq!(response.status(), StatusCode::NOT_FOUND);
 }
 
+#[tokio::test]
+async fn test_create_session_requires_passed_prerequisites() {
+    disable_rate_limit();
+    let app = common::create_test_app().await;
+    let (user_id, token) = create_user_and_login(&app).await;
+    let (csrf_token, csrf_cookie) = get_csrf_token(&app).await;
+
+    // Уровень `locked` требует пройти `basics` минимум на 80%
+    let topic_id = ObjectId::new();
+    let basics_id = ObjectId::new();
+    let locked_id = ObjectId::new();
+    let levels = collection("levels").await;
+    for (level_id, name, order, prerequisites) in [
+        (basics_id, "Basics", 1, vec![]),
+        (locked_id, "Advanced", 2, vec![basics_id]),
+    ] {
+        levels
+            .insert_one(doc! {
+                "_id": level_id,
+                "topic_id": topic_id,
+                "order": order,
+                "name": name,
+                "difficulty": "a1",
+                "description": "Prerequisite test",
+                "min_pass_percent": 80,
+                "status": "active",
+                "prerequisite_level_ids": prerequisites,
+                "created_at": BsonDateTime::now(),
+                "updated_at": BsonDateTime::now(),
+            })
+            .await
+            .unwrap();
+    }
+
+    let task_id = ObjectId::new();
+    collection("tasks")
+        .await
+        .insert_one(doc! {
+            "_id": task_id,
+            "template_id": ObjectId::new(),
+            "session_id": Uuid::new_v4().to_string(),
+            "title": "Advanced task",
+            "description": "Task on a locked level",
+            "time_limit_seconds": 300,
+            "level_id": locked_id,
+            "content": { "text": "Task" },
+            "correct_answer": "a",
+            "createdAt": BsonDateTime::now(),
+        })
+        .await
+        .unwrap();
+
+    let create_session = || {
+        Request::builder()
+            .method("POST")
+            .uri("/api/v1/sessions")
+            .header("content-type", "application/json")
+            .header("authorization", format!("Bearer {}", token))
+            .header("x-csrf-token", &csrf_token)
+            .header("cookie", format!("csrf_token={}", csrf_cookie))
+            .body(Body::from(
+                json!({ "user_id": &user_id, "task_id": task_id.to_hex() }).to_string(),
+            ))
+            .unwrap()
+    };
+
+    let response = app.clone().oneshot(create_session()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(json["code"], "LEVEL_LOCKED");
+    let missing = json["details"]["missing"].as_array().unwrap();
+    assert_eq!(missing.len(), 1);
+    assert_eq!(missing[0]["level_id"], basics_id.to_hex());
+    assert_eq!(missing[0]["min_pass_percent"], 80);
+
+    // После прохождения `basics` уровень открывается
+    collection("progress_summary_v2")
+        .await
+        .insert_one(doc! {
+            "_id": format!("{}:{}", user_id, basics_id.to_hex()),
+            "user_id": &user_id,
+            "level_id": basics_id.to_hex(),
+            "attempts_total": 5,
+            "correct_count": 5,
+            "percentage": 100.0,
+            "score": 50,
+            "updated_at": chrono::Utc::now().to_rfc3339(),
+        })
+        .await
+        .unwrap();
+
+    let response = app.clone().oneshot(create_session()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    // Другой ученик не обходит замок, подставив user_id прошедшего `basics`
+    let (_, other_token) = create_user_and_login(&app).await;
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/sessions")
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {}", other_token))
+                .header("x-csrf-token", &csrf_token)
+                .header("cookie", format!("csrf_token={}", csrf_cookie))
+                .body(Body::from(
+                    json!({ "user_id": &user_id, "task_id": task_id.to_hex() }).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(json["code"], "USER_MISMATCH");
+
+    levels
+        .delete_many(doc! { "topic_id": topic_id })
+        .await
+        .unwrap();
+    collection("tasks")
+        .await
+        .delete_one(doc! { "_id": task_id })
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_expired_session_rejects_answers_and_finalizes_as_expired() {
+    disable_rate_limit();
+    let mut state = common::create_test_state().await;
+    state.config.sessions.grace_seconds = 2;
+    let app = create_router(Arc::new(state));
+    let (user_id, token) = create_user_and_login(&app).await;
+    let (csrf_token, csrf_cookie) = get_csrf_token(&app).await;
+    let post = |uri: String, body: serde_json::Value| {
+        Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("content-type", "application/json")
+            .header("authorization", format!("Bearer {}", token))
+            .header("x-csrf-token", &csrf_token)
+            .header("cookie", format!("csrf_token={}", csrf_cookie))
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let read_json = |response: axum::response::Response| async move {
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice::<serde_json::Value>(&bytes).unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(post(
+            "/api/v1/sessions".to_string(),
+            json!({
+                "user_id": user_id,
+                "task_id": "test-task",
+                "session_duration_seconds": 1,
+            }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let session_id = read_json(response).await["session_id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let answer = |key: &str| {
+        post(
+            format!("/api/v1/sessions/{}/answers", session_id),
+            json!({ "answer": "42", "idempotency_key": format!("{}:{}", session_id, key) }),
+        )
+    };
+
+    // Сессия уже истекла, но льготный период ещё идёт - ответ засчитывается
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    let response = app.clone().oneshot(answer("grace")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(read_json(response).await["score_awarded"], 10);
+
+    tokio::time::sleep(Duration::from_millis(2000)).await;
+    let response = app.clone().oneshot(answer("late")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let json = read_json(response).await;
+    assert_eq!(json["code"], "SESSION_EXPIRED");
+    assert_eq!(json["details"]["grace_seconds"], 2);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(format!("/api/v1/sessions/{}", session_id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(read_json(response).await["status"], "expired");
+
+    // Завершение истёкшей сессии фиксирует её как expired со счётом до дедлайна
+    let response = app
+        .clone()
+        .oneshot(post(
+            format!("/api/v1/sessions/{}/complete", session_id),
+            json!({}),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let json = read_json(response).await;
+    assert_eq!(json["code"], "SESSION_EXPIRED");
+    assert_eq!(json["details"]["status"], "expired");
+    assert_eq!(json["details"]["score"], 10);
+
+    let record = collection("sessions")
+        .await
+        .find_one(doc! { "_id": &session_id })
+        .await
+        .unwrap()
+        .expect("expired session persisted");
+    assert_eq!(record.get_str("status").unwrap(), "expired");
+    assert_eq!(record.get_i32("score").unwrap(), 10);
+}
+
+async fn collection(name: &str) -> mongodb::Collection<Document> {
+    let config = Config::load().unwrap();
+    let mongo_client = mongodb::Client::with_uri_str(&config.mongo_uri)
+        .await
+        .unwrap();
+    mongo_client
+        .database(&config.mongo_database)
+        .collection::<Document>(name)
+}
+
 async fn create_user_and_login(app: &axum::Router) -> (String, String) {
     let email = format!("session-user-{}@test.com", Uuid::new_v4());
     let register_body = json!({
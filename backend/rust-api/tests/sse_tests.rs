@@ -0,0 +1,161 @@
+mod common;
+
+use chrono::{Duration as ChronoDuration, Utc};
+use std::sync::Arc;
+use std::time::Duration;
+use trainingground_api::{
+    create_router,
+    models::{
+        timer::{SessionExpired, TimerEvent},
+        Session, SessionMode, SessionStatus,
+    },
+    services::session_events::{session_event_log_key, session_event_seq_key, SessionEventLog},
+};
+use uuid::Uuid;
+
+#[derive(Debug)]
+struct SseEvent {
+    id: Option<u64>,
+    event: String,
+    data: serde_json::Value,
+}
+
+/// Разбирает блок SSE; комментарии (`:keepalive`) пропускаются
+fn parse_block(block: &str) -> Option<SseEvent> {
+    let mut id = None;
+    let mut event = None;
+    let mut data = String::new();
+    for line in block.lines() {
+        if let Some(value) = line.strip_prefix("id:") {
+            id = value.trim().parse().ok();
+        } else if let Some(value) = line.strip_prefix("event:") {
+            event = Some(value.trim().to_string());
+        } else if let Some(value) = line.strip_prefix("data:") {
+            data.push_str(value.trim());
+        }
+    }
+    Some(SseEvent {
+        id,
+        event: event?,
+        data: serde_json::from_str(&data).ok()?,
+    })
+}
+
+/// Читает события, пока их не станет `limit` или сервер не закроет стрим
+async fn read_events(response: &mut reqwest::Response, limit: usize) -> Vec<SseEvent> {
+    let mut buffer = String::new();
+    let mut events = Vec::new();
+    loop {
+        while let Some(end) = buffer.find("\n\n") {
+            let block: String = buffer.drain(..end + 2).collect();
+            events.extend(parse_block(&block));
+            if events.len() >= limit {
+                return events;
+            }
+        }
+        let chunk = tokio::time::timeout(Duration::from_secs(5), response.chunk())
+            .await
+            .expect("SSE chunk in time")
+            .expect("SSE body readable");
+        match chunk {
+            Some(bytes) => buffer.push_str(&String::from_utf8_lossy(&bytes)),
+            None => return events,
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_reconnect_with_last_event_id_replays_missed_events() {
+    std::env::set_var("SSE_TICK_INTERVAL_MS", "50");
+    let state = common::create_test_state().await;
+    let mut redis = state.redis.clone();
+    let log = SessionEventLog::new(state.redis.clone(), state.config.sessions.event_buffer_size);
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let app = create_router(Arc::new(state));
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let now = Utc::now();
+    let session = Session {
+        id: Uuid::new_v4().to_string(),
+        user_id: format!("sse-user-{}", Uuid::new_v4()),
+        task_id: "test-task".to_string(),
+        group_id: None,
+        started_at: now,
+        expires_at: now + ChronoDuration::seconds(300),
+        status: SessionStatus::Active,
+        hints_used: 0,
+        score: 0,
+        level_id: None,
+        mode: SessionMode::Normal,
+    };
+    redis::cmd("SETEX")
+        .arg(format!("session:{}", session.id))
+        .arg(3600)
+        .arg(serde_json::to_string(&session).unwrap())
+        .query_async::<()>(&mut redis)
+        .await
+        .unwrap();
+
+    let client = reqwest::Client::new();
+    let stream_url = format!("{}/api/v1/sessions/{}/stream", base_url, session.id);
+
+    let mut response = client.get(&stream_url).send().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let received = read_events(&mut response, 3).await;
+    assert_eq!(
+        received.iter().map(|event| event.id).collect::<Vec<_>>(),
+        vec![Some(1), Some(2), Some(3)]
+    );
+    assert!(received.iter().all(|event| event.event == "timer-tick"));
+    // Обрыв сети: клиент пропускает всё, что случится до переподключения
+    drop(response);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let expired = log
+        .publish(
+            &session.id,
+            &TimerEvent::Expired(SessionExpired {
+                session_id: session.id.clone(),
+                expires_at: session.expires_at,
+                timestamp: Utc::now(),
+            }),
+        )
+        .await
+        .unwrap();
+    assert!(expired.id > 3);
+
+    let mut response = client
+        .get(&stream_url)
+        .header("Last-Event-ID", "3")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let replayed = read_events(&mut response, usize::MAX).await;
+
+    // Пропущенные события приходят по порядку, начиная сразу после Last-Event-ID
+    let ids: Vec<u64> = replayed.iter().filter_map(|event| event.id).collect();
+    assert_eq!(ids, (4..=expired.id).collect::<Vec<_>>());
+    let last = replayed.last().unwrap();
+    assert_eq!(last.event, "expired");
+    assert_eq!(last.data["session_id"], session.id);
+
+    let ttl: i64 = redis::cmd("TTL")
+        .arg(session_event_log_key(&session.id))
+        .query_async(&mut redis)
+        .await
+        .unwrap();
+    assert!(ttl > 0 && ttl <= 3600);
+
+    redis::cmd("DEL")
+        .arg(format!("session:{}", session.id))
+        .arg(session_event_seq_key(&session.id))
+        .arg(session_event_log_key(&session.id))
+        .query_async::<()>(&mut redis)
+        .await
+        .unwrap();
+}
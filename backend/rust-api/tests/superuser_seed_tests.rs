@@ -0,0 +1,269 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Request, StatusCode},
+};
+use mongodb::bson::{doc, Document};
+use serde_json::json;
+use tower::ServiceExt;
+use trainingground_api::{create_router, services::superuser_seed, services::AppState};
+
+mod common;
+
+const SEED_PASSWORD: &str = "Seed-Password-123";
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_seed_password_flag_cleared_by_change_password() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+    let (email, seed_file) = write_seed_file();
+
+    let mut config = state.config.clone();
+    config.superuser_seed_file = Some(seed_file.display().to_string());
+    superuser_seed::bootstrap(&config, &state.mongo, &state.redis)
+        .await
+        .unwrap();
+
+    let (status, access_token, _) = login(&app, &email, SEED_PASSWORD).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let superuser = superuser_status(&app, &access_token, &email).await;
+    assert_eq!(superuser["seed_password_in_use"], true);
+    assert!(
+        superuser["last_login_at"].is_string(),
+        "last login missing: {superuser:?}"
+    );
+
+    let status = change_password(&app, &access_token, SEED_PASSWORD, "Owner-Chosen-456").await;
+    assert_eq!(status, StatusCode::OK);
+
+    let superuser = superuser_status(&app, &access_token, &email).await;
+    assert_eq!(superuser["seed_password_in_use"], false);
+
+    // Повторный запуск с тем же seed-файлом не возвращает флаг: пароль уже другой
+    superuser_seed::bootstrap(&config, &state.mongo, &state.redis)
+        .await
+        .unwrap();
+    let superuser = superuser_status(&app, &access_token, &email).await;
+    assert_eq!(superuser["seed_password_in_use"], false);
+
+    cleanup(&state, &email, &seed_file).await;
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_rotation_revokes_existing_sessions() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+    let (email, seed_file) = write_seed_file();
+
+    let mut config = state.config.clone();
+    config.superuser_seed_file = Some(seed_file.display().to_string());
+    superuser_seed::bootstrap(&config, &state.mongo, &state.redis)
+        .await
+        .unwrap();
+
+    let (status, old_access_token, old_refresh_token) = login(&app, &email, SEED_PASSWORD).await;
+    assert_eq!(status, StatusCode::OK);
+    let old_refresh_token = old_refresh_token.expect("refresh_token cookie missing");
+
+    let rotated = superuser_seed::rotate_passwords(&state.mongo, &state.redis)
+        .await
+        .unwrap();
+    let new_password = rotated
+        .iter()
+        .find(|superuser| superuser.email == email)
+        .map(|superuser| superuser.password.clone())
+        .expect("seeded superuser was not rotated");
+    assert_ne!(new_password, SEED_PASSWORD);
+
+    assert_eq!(
+        refresh_status(&app, &old_refresh_token).await,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        get_status(&app, &old_access_token).await.0,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        login(&app, &email, SEED_PASSWORD).await.0,
+        StatusCode::UNAUTHORIZED
+    );
+
+    // Отзыв access-токенов с точностью до секунды
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    let (status, access_token, _) = login(&app, &email, &new_password).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let superuser = superuser_status(&app, &access_token, &email).await;
+    assert_eq!(superuser["seed_password_in_use"], true);
+    assert!(superuser["password_rotated_at"].is_string());
+
+    cleanup(&state, &email, &seed_file).await;
+}
+
+fn write_seed_file() -> (String, std::path::PathBuf) {
+    let email = format!("superuser-{}@test.com", uuid::Uuid::new_v4());
+    let path = std::env::temp_dir().join(format!("superuser-seed-{}.json", uuid::Uuid::new_v4()));
+    let seed = json!({
+        "email": email,
+        "name": "Seeded Superuser",
+        "role": "admin",
+        "password": SEED_PASSWORD,
+    });
+    std::fs::write(&path, seed.to_string()).unwrap();
+    (email, path)
+}
+
+async fn cleanup(state: &AppState, email: &str, seed_file: &std::path::Path) {
+    let _ = std::fs::remove_file(seed_file);
+    state
+        .mongo
+        .collection::<Document>("users")
+        .delete_one(doc! { "email": email })
+        .await
+        .unwrap();
+}
+
+async fn login(
+    app: &axum::Router,
+    email: &str,
+    password: &str,
+) -> (StatusCode, String, Option<String>) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/auth/login")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    json!({ "email": email, "password": password }).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let status = response.status();
+    let refresh_token = response
+        .headers()
+        .get_all(header::SET_COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .find_map(|cookie| cookie.strip_prefix("refresh_token="))
+        .and_then(|cookie| cookie.split(';').next())
+        .map(|value| value.to_string());
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap_or_default();
+    let access_token = json["access_token"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+
+    (status, access_token, refresh_token)
+}
+
+async fn refresh_status(app: &axum::Router, refresh_token: &str) -> StatusCode {
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/auth/refresh")
+                .header(header::COOKIE, format!("refresh_token={}", refresh_token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+        .status()
+}
+
+async fn get_status(app: &axum::Router, token: &str) -> (StatusCode, serde_json::Value) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/admin/system/superuser-status")
+                .header("authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or_default())
+}
+
+async fn superuser_status(app: &axum::Router, token: &str, email: &str) -> serde_json::Value {
+    let (status, json) = get_status(app, token).await;
+    assert_eq!(status, StatusCode::OK, "superuser status failed: {json:?}");
+    json["superusers"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|superuser| superuser["email"] == email)
+        .cloned()
+        .unwrap_or_else(|| panic!("{email} missing from superuser status: {json:?}"))
+}
+
+async fn change_password(
+    app: &axum::Router,
+    token: &str,
+    old_password: &str,
+    new_password: &str,
+) -> StatusCode {
+    let (csrf_token, csrf_cookie) = get_csrf_token(app).await;
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/auth/change-password")
+                .header("authorization", format!("Bearer {}", token))
+                .header("content-type", "application/json")
+                .header("x-csrf-token", &csrf_token)
+                .header("cookie", format!("csrf_token={}", csrf_cookie))
+                .body(Body::from(
+                    json!({ "old_password": old_password, "new_password": new_password })
+                        .to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+        .status()
+}
+
+async fn get_csrf_token(app: &axum::Router) -> (String, String) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/api/v1/auth/csrf-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let csrf_cookie = response
+        .headers()
+        .get_all(header::SET_COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .find(|cookie| cookie.starts_with("csrf_token="))
+        .and_then(|cookie| cookie.split(';').next())
+        .and_then(|pair| pair.split('=').nth(1))
+        .unwrap_or_default()
+        .to_string();
+
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    let csrf_token = json["csrf_token"].as_str().unwrap().to_string();
+    (csrf_token, csrf_cookie)
+}
@@ -0,0 +1,161 @@
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use chrono::Utc;
+use mongodb::bson::{doc, oid::ObjectId, DateTime, Document};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use trainingground_api::{
+    create_router,
+    middlewares::auth::{JwtClaims, JwtService},
+    services::AppState,
+};
+use uuid::Uuid;
+
+mod common;
+
+fn jwt(state: &AppState, user_id: &str, role: &str) -> String {
+    let now = Utc::now().timestamp() as usize;
+    JwtService::from_config(&state.config)
+        .generate_token(JwtClaims {
+            sub: user_id.to_string(),
+            role: role.to_string(),
+            group_ids: Vec::new(),
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap()
+}
+
+async fn post_auto(app: &axum::Router, token: Option<&str>) -> (StatusCode, Value) {
+    let csrf_token = Uuid::new_v4().to_string();
+    let mut request = Request::builder()
+        .method("POST")
+        .uri("/api/v1/sessions/auto")
+        .header("content-type", "application/json")
+        .header("x-csrf-token", &csrf_token)
+        .header("cookie", format!("csrf_token={}", csrf_token))
+        .header("x-request-nonce", Uuid::new_v4().to_string())
+        .header("x-request-timestamp", Utc::now().timestamp().to_string());
+    if let Some(token) = token {
+        request = request.header("authorization", format!("Bearer {}", token));
+    }
+
+    let response = app
+        .clone()
+        .oneshot(request.body(Body::from(json!({}).to_string())).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or_default())
+}
+
+/// Тема, уровень, опубликованный шаблон и сохранённое по нему задание
+async fn insert_published_template(state: &AppState) -> ObjectId {
+    let now = DateTime::now();
+    let topic_id = ObjectId::new();
+    let level_id = ObjectId::new();
+    let template_id = ObjectId::new();
+    let suffix = Uuid::new_v4().simple().to_string();
+
+    state
+        .mongo
+        .collection::<Document>("topics")
+        .insert_one(doc! {
+            "_id": topic_id,
+            "slug": format!("auto-topic-{}", suffix),
+            "name": "Тема для подбора",
+            "description": "",
+            "icon_url": null,
+            "sort_order": 0,
+            "status": "active",
+            "createdAt": now,
+            "updatedAt": now,
+        })
+        .await
+        .unwrap();
+    state
+        .mongo
+        .collection::<Document>("levels")
+        .insert_one(doc! {
+            "_id": level_id,
+            "topic_id": topic_id,
+            "order": 1,
+            "name": "Уровень для подбора",
+            "difficulty": "a1",
+            "description": "",
+            "min_pass_percent": 80,
+            "status": "active",
+            "createdAt": now,
+            "updatedAt": now,
+        })
+        .await
+        .unwrap();
+    state
+        .mongo
+        .collection::<Document>("templates")
+        .insert_one(doc! {
+            "_id": template_id,
+            "slug": format!("auto-template-{}", suffix),
+            "level_id": level_id,
+            "status": "published",
+            "version": 1,
+            "createdAt": now,
+            "updatedAt": now,
+        })
+        .await
+        .unwrap();
+    state
+        .mongo
+        .collection::<Document>("tasks")
+        .insert_one(doc! {
+            "template_id": template_id,
+            "title": "Подобранное задание",
+            "description": "Сколько будет 2 + 2?",
+            "time_limit_seconds": 300,
+            "correct_answer": "4",
+            "createdAt": now,
+        })
+        .await
+        .unwrap();
+    template_id
+}
+
+#[tokio::test]
+async fn test_auto_session_picks_published_template() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+    insert_published_template(&state).await;
+    let user_id = ObjectId::new().to_hex();
+    let token = jwt(&state, &user_id, "admin");
+
+    let (status, body) = post_auto(&app, Some(&token)).await;
+    assert_eq!(status, StatusCode::CREATED, "{body}");
+    assert!(body["session_id"].is_string());
+
+    // Задание сессии создано по опубликованному шаблону
+    let task_id = ObjectId::parse_str(body["task"]["id"].as_str().unwrap()).unwrap();
+    let task = state
+        .mongo
+        .collection::<Document>("tasks")
+        .find_one(doc! { "_id": task_id })
+        .await
+        .unwrap()
+        .unwrap();
+    let template_id = task.get_object_id("template_id").unwrap();
+    let template = state
+        .mongo
+        .collection::<Document>("templates")
+        .find_one(doc! { "_id": template_id })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(template.get_str("status").unwrap(), "published");
+
+    let (status, _) = post_auto(&app, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
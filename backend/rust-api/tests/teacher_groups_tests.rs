@@ -0,0 +1,137 @@
+mod common;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use chrono::{Duration, Utc};
+use mongodb::bson::{doc, oid::ObjectId, DateTime as BsonDateTime, Document};
+use std::sync::Arc;
+use tower::ServiceExt;
+use trainingground_api::{
+    create_router,
+    middlewares::auth::{JwtClaims, JwtService},
+    services::AppState,
+};
+use uuid::Uuid;
+
+fn teacher_token(state: &AppState, group_ids: Vec<String>) -> String {
+    let now = Utc::now().timestamp() as usize;
+    JwtService::new(&state.config.jwt_secret)
+        .generate_token(JwtClaims {
+            sub: ObjectId::new().to_hex(),
+            role: "teacher".to_string(),
+            group_ids,
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap()
+}
+
+async fn insert_student(state: &AppState, group_ids: &[String]) -> String {
+    let now = BsonDateTime::now();
+    let inserted = state
+        .mongo
+        .collection::<Document>("users")
+        .insert_one(doc! {
+            "email": format!("health+{}@test.com", Uuid::new_v4()),
+            "password_hash": "hash",
+            "name": "Health Student",
+            "role": "student",
+            "group_ids": group_ids,
+            "is_blocked": false,
+            "createdAt": now,
+            "updatedAt": now,
+        })
+        .await
+        .unwrap();
+    inserted.inserted_id.as_object_id().unwrap().to_hex()
+}
+
+async fn insert_progress(state: &AppState, user_id: &str, percentage: f64, days_ago: i64) {
+    let updated_at = Utc::now() - Duration::days(days_ago);
+    state
+        .mongo
+        .collection::<Document>("progress_summary")
+        .insert_one(doc! {
+            "_id": format!("health-{}", Uuid::new_v4()),
+            "user_id": user_id,
+            "level_id": format!("level-{}", Uuid::new_v4()),
+            "attempts_total": 4,
+            "correct_count": 2,
+            "percentage": percentage,
+            "score": 20,
+            "updated_at": BsonDateTime::from_millis(updated_at.timestamp_millis()),
+        })
+        .await
+        .unwrap();
+}
+
+async fn get_groups(app: &Router, token: &str, query: &str) -> serde_json::Value {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(format!("/api/v1/teacher/groups{}", query))
+                .header("authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn test_teacher_groups_include_health_stats() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+
+    let now = BsonDateTime::now();
+    let group_id = state
+        .mongo
+        .collection::<Document>("groups")
+        .insert_one(doc! {
+            "name": "Health Group",
+            "school": "School №8",
+            "studentCount": 2,
+            "createdAt": now,
+            "updatedAt": now,
+        })
+        .await
+        .unwrap()
+        .inserted_id
+        .as_object_id()
+        .unwrap()
+        .to_hex();
+
+    let active = insert_student(&state, std::slice::from_ref(&group_id)).await;
+    let idle = insert_student(&state, std::slice::from_ref(&group_id)).await;
+    let outsider = insert_student(&state, &[]).await;
+    insert_progress(&state, &active, 80.0, 1).await;
+    insert_progress(&state, &active, 60.0, 2).await;
+    insert_progress(&state, &idle, 40.0, 10).await;
+    insert_progress(&state, &outsider, 100.0, 0).await;
+
+    let token = teacher_token(&state, vec![group_id.clone()]);
+
+    // Без include ответ прежний: только поля группы
+    let groups = get_groups(&app, &token, "").await;
+    let group = &groups.as_array().unwrap()[0];
+    assert_eq!(group["id"], group_id.as_str());
+    assert_eq!(group["student_count"], 2);
+    assert!(group.get("avg_accuracy").is_none());
+    assert!(group.get("active_students_last_7d").is_none());
+
+    let groups = get_groups(&app, &token, "?include=stats").await;
+    let group = &groups.as_array().unwrap()[0];
+    assert_eq!(group["id"], group_id.as_str());
+    assert_eq!(group["student_count"], 2);
+    assert_eq!(group["avg_accuracy"], 60.0);
+    assert_eq!(group["active_students_last_7d"], 1);
+    assert!(group["last_activity_at"].is_string());
+}
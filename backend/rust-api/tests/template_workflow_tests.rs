@@ -48,6 +48,7 @@ async fn integration_template_workflow_approval() -> Result<()> {
                 description: "Topic for template workflow test".to_string(),
                 icon_url: None,
                 status: None,
+                age_band: None,
             },
             &claims,
         )
@@ -62,6 +63,7 @@ async fn integration_template_workflow_approval() -> Result<()> {
                 description: "Level used in integration test".to_string(),
                 min_pass_percent: Some(70),
                 order: None,
+                prerequisite_level_ids: vec![],
             },
             &claims,
         )
@@ -95,6 +97,9 @@ async fn integration_template_workflow_approval() -> Result<()> {
                 content: "What is the answer to life?".to_string(),
                 difficulty: Some("A1".to_string()),
                 source_refs: vec!["integration-test".to_string()],
+                age_band: None,
+                locale: None,
+                translations: Default::default(),
             },
             &claims,
         )
@@ -127,10 +132,15 @@ async fn integration_template_workflow_approval() -> Result<()> {
         trainingground_api::models::content::TemplateStatus::ReviewedOnce
     );
 
+    // Второе одобрение - от другого модератора
+    let second_reviewer = JwtClaims {
+        sub: Uuid::new_v4().to_string(),
+        ..claims.clone()
+    };
     let ready = service
         .approve_template(
             &mongodb::bson::oid::ObjectId::parse_str(&template.id)?,
-            &claims,
+            &second_reviewer,
         )
         .await?;
     assert_eq!(
@@ -153,6 +163,7 @@ async fn integration_embeddings_rebuild_selected_templates() -> Result<()> {
                 description: "Topic for embeddings test".to_string(),
                 icon_url: None,
                 status: None,
+                age_band: None,
             },
             &claims,
         )
@@ -167,6 +178,7 @@ async fn integration_embeddings_rebuild_selected_templates() -> Result<()> {
                 description: "Level for embeddings test".to_string(),
                 min_pass_percent: Some(75),
                 order: None,
+                prerequisite_level_ids: vec![],
             },
             &claims,
         )
@@ -200,6 +212,9 @@ async fn integration_embeddings_rebuild_selected_templates() -> Result<()> {
                 content: "Compute embeddings".to_string(),
                 difficulty: Some("A2".to_string()),
                 source_refs: vec!["embedding-test".to_string()],
+                age_band: None,
+                locale: None,
+                translations: Default::default(),
             },
             &claims,
         )
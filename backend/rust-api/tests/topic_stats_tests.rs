@@ -0,0 +1,189 @@
+mod common;
+
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use chrono::Utc;
+use mongodb::bson::{doc, oid::ObjectId, Document};
+use serde_json::Value;
+use tower::ServiceExt;
+use trainingground_api::{
+    create_router,
+    middlewares::auth::{JwtClaims, JwtService},
+    models::{group::CreateGroupRequest, reporting::StatType, ProgressSummary},
+    services::{group_service::GroupService, reporting_service::ReportingService, AppState},
+};
+
+fn teacher_token(state: &AppState, group_ids: &[&str]) -> String {
+    let now = Utc::now().timestamp() as usize;
+    JwtService::new(&state.config.jwt_secret)
+        .generate_token(JwtClaims {
+            sub: ObjectId::new().to_hex(),
+            role: "teacher".to_string(),
+            group_ids: group_ids.iter().map(|id| id.to_string()).collect(),
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap()
+}
+
+async fn get(app: &Router, token: &str, uri: &str) -> (StatusCode, Value) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(uri)
+                .header("authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+/// Группа с учениками, у каждого из которых одна строка прогресса по уровню темы
+async fn seed_group(state: &AppState, name: &str, level_id: &ObjectId, results: &[f64]) -> String {
+    let group = GroupService::new(state.mongo.clone())
+        .create_group(CreateGroupRequest {
+            name: name.to_string(),
+            school: "Школа №1".to_string(),
+            curator_id: None,
+            description: None,
+            max_students: None,
+        })
+        .await
+        .unwrap();
+
+    for percentage in results {
+        let student_id = ObjectId::new();
+        state
+            .mongo
+            .collection::<Document>("users")
+            .insert_one(doc! {
+                "_id": student_id,
+                "email": format!("{}@example.com", student_id.to_hex()),
+                "role": "student",
+                "group_ids": [group.id.clone()],
+            })
+            .await
+            .unwrap();
+        state
+            .mongo
+            .collection::<ProgressSummary>("progress_summary")
+            .insert_one(ProgressSummary {
+                id: format!("{}:{}", student_id.to_hex(), level_id.to_hex()),
+                user_id: student_id.to_hex(),
+                level_id: level_id.to_hex(),
+                attempts_total: 10,
+                correct_count: (*percentage / 10.0) as u32,
+                credit: None,
+                percentage: *percentage,
+                score: 100,
+                updated_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+    }
+    group.id
+}
+
+async fn seed_topic(state: &AppState) -> (ObjectId, ObjectId) {
+    let topic_id = ObjectId::new();
+    let level_id = ObjectId::new();
+    state
+        .mongo
+        .collection::<Document>("levels")
+        .insert_one(doc! { "_id": level_id, "topic_id": topic_id, "name": "Уровень 1" })
+        .await
+        .unwrap();
+    ReportingService::new(state.mongo.clone(), state.redis.clone())
+        .upsert_materialized_stat(
+            StatType::Topic,
+            &topic_id,
+            doc! { "avg_accuracy": 60.0, "total_attempts": 40 },
+        )
+        .await
+        .unwrap();
+    (topic_id, level_id)
+}
+
+#[tokio::test]
+async fn test_topic_stats_compare_groups() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+    let (topic_id, level_id) = seed_topic(&state).await;
+    let strong = seed_group(&state, "Сильный класс", &level_id, &[90.0, 100.0]).await;
+    let weak = seed_group(&state, "Слабый класс", &level_id, &[20.0, 40.0]).await;
+    let token = teacher_token(&state, &[&strong, &weak]);
+
+    // Без group_id ответ прежний
+    let (status, body) = get(&app, &token, &format!("/stats/topics/{}", topic_id)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert!(body["stats"].is_object());
+    assert!(body.get("by_group").is_none());
+
+    let uri = format!(
+        "/stats/topics/{}?group_id={}&group_id={}",
+        topic_id, strong, weak
+    );
+    let (status, body) = get(&app, &token, &uri).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["topic_id"], topic_id.to_hex());
+    assert!(body["overall"].is_object());
+
+    let by_group = body["by_group"].as_array().unwrap();
+    assert_eq!(by_group.len(), 2);
+    assert_eq!(by_group[0]["group_id"], strong.as_str());
+    assert_eq!(by_group[0]["group_name"], "Сильный класс");
+    assert_eq!(by_group[0]["avg_percentage"], 95.0);
+    assert_eq!(by_group[0]["total_attempts"], 20);
+    assert_eq!(by_group[1]["group_id"], weak.as_str());
+    assert_eq!(by_group[1]["avg_percentage"], 30.0);
+    assert_eq!(by_group[1]["total_attempts"], 20);
+}
+
+#[tokio::test]
+async fn test_topic_stats_comparison_requires_access_to_every_group() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+    let (topic_id, level_id) = seed_topic(&state).await;
+    let own = seed_group(&state, "Свой класс", &level_id, &[80.0]).await;
+    let foreign = seed_group(&state, "Чужой класс", &level_id, &[50.0]).await;
+    let token = teacher_token(&state, &[&own]);
+
+    let uri = format!(
+        "/stats/topics/{}?group_id={}&group_id={}",
+        topic_id, own, foreign
+    );
+    let (status, _) = get(&app, &token, &uri).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Не больше пяти групп и только корректные id
+    let too_many = (0..6)
+        .map(|_| format!("group_id={}", ObjectId::new()))
+        .collect::<Vec<_>>()
+        .join("&");
+    let (status, _) = get(
+        &app,
+        &token,
+        &format!("/stats/topics/{}?{}", topic_id, too_many),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = get(
+        &app,
+        &token,
+        &format!("/stats/topics/{}?group_id=oops", topic_id),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
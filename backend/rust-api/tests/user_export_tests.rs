@@ -0,0 +1,290 @@
+mod common;
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use chrono::{Duration, Utc};
+use mongodb::bson::{doc, oid::ObjectId, Document};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use trainingground_api::{
+    create_router,
+    middlewares::auth::{JwtClaims, JwtService},
+    models::{
+        reporting::{ExportScope, ExportStatus},
+        ProgressSummary,
+    },
+    services::{
+        export_worker::{ExportStorage, ExportWorker},
+        reporting_service::ReportingService,
+        AppState,
+    },
+};
+
+/// Хранилище в памяти: запоминает загруженные файлы по ключу
+#[derive(Default)]
+struct MemoryStorage {
+    files: Mutex<HashMap<String, Vec<u8>>>,
+}
+
+#[async_trait]
+impl ExportStorage for MemoryStorage {
+    fn export_key(
+        &self,
+        scope: ExportScope,
+        owner_id: &str,
+        export_id: &str,
+        extension: &str,
+    ) -> String {
+        format!(
+            "{}/{}/export-{}.{}",
+            scope.storage_prefix(),
+            owner_id,
+            export_id,
+            extension
+        )
+    }
+
+    async fn upload_export(
+        &self,
+        key: &str,
+        bytes: Vec<u8>,
+        _content_type: &str,
+    ) -> anyhow::Result<()> {
+        self.files.lock().unwrap().insert(key.to_string(), bytes);
+        Ok(())
+    }
+}
+
+fn token(state: &AppState, user_id: &ObjectId, role: &str, group_ids: Vec<String>) -> String {
+    let now = Utc::now().timestamp() as usize;
+    JwtService::new(&state.config.jwt_secret)
+        .generate_token(JwtClaims {
+            sub: user_id.to_hex(),
+            role: role.to_string(),
+            group_ids,
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap()
+}
+
+async fn get_csrf_token(app: &Router) -> (String, String) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/api/v1/auth/csrf-token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let csrf_cookie = response
+        .headers()
+        .get_all("set-cookie")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .find(|s| s.starts_with("csrf_token="))
+        .and_then(|s| s.split(';').next())
+        .and_then(|part| part.split('=').nth(1))
+        .unwrap_or_default()
+        .to_string();
+
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    (
+        json["csrf_token"].as_str().unwrap().to_string(),
+        csrf_cookie,
+    )
+}
+
+async fn request_export(
+    app: &Router,
+    token: &str,
+    csrf: &(String, String),
+    user_id: &ObjectId,
+    format: &str,
+) -> (StatusCode, Value) {
+    let now = Utc::now();
+    let body = json!({
+        "format": format,
+        "period": { "from": now - Duration::days(30), "to": now },
+    });
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(format!("/stats/users/{}/export", user_id.to_hex()))
+                .header("authorization", format!("Bearer {}", token))
+                .header("x-csrf-token", &csrf.0)
+                .header("cookie", format!("csrf_token={}", csrf.1))
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (
+        status,
+        serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+    )
+}
+
+async fn seed_progress(state: &AppState, user_id: &ObjectId) {
+    let progress = state
+        .mongo
+        .collection::<ProgressSummary>("progress_summary");
+    for (day, score) in [(3, 40), (2, 55), (1, 70)] {
+        let level_id = ObjectId::new().to_hex();
+        progress
+            .insert_one(ProgressSummary {
+                id: format!("{}:{}", user_id.to_hex(), level_id),
+                user_id: user_id.to_hex(),
+                level_id,
+                attempts_total: 10,
+                correct_count: 8,
+                credit: None,
+                percentage: 80.0,
+                score,
+                updated_at: Utc::now() - Duration::days(day),
+            })
+            .await
+            .unwrap();
+    }
+
+    state
+        .mongo
+        .collection::<Document>("hint_records")
+        .insert_one(doc! {
+            "_id": ObjectId::new().to_hex(),
+            "session_id": "session",
+            "user_id": user_id.to_hex(),
+            "task_id": "task",
+            "hint_text": "hint",
+            "cost": 5,
+            "timestamp": Utc::now().to_rfc3339(),
+            "source": "rule",
+        })
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_student_pdf_export_is_built_and_stored_under_user_prefix() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+    let csrf = get_csrf_token(&app).await;
+    let student_id = ObjectId::new();
+    seed_progress(&state, &student_id).await;
+
+    let student = token(&state, &student_id, "student", Vec::new());
+    let (status, body) = request_export(&app, &student, &csrf, &student_id, "pdf").await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let export_id = ObjectId::parse_str(body["export_id"].as_str().unwrap()).unwrap();
+
+    let storage = Arc::new(MemoryStorage::default());
+    let mut config = state.config.clone();
+    // Большой батч забирает и выгрузки, оставшиеся в базе от других тестов
+    config.reporting.export_concurrency = 1000;
+    let worker = ExportWorker::new(
+        ReportingService::new(state.mongo.clone(), state.redis.clone()),
+        storage.clone(),
+        config,
+    );
+    let summary = worker.process_pending().await.unwrap();
+    assert!(summary.completed >= 1);
+
+    let export = ReportingService::new(state.mongo.clone(), state.redis.clone())
+        .get_export_by_id(&export_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(export.status, ExportStatus::Ready);
+    assert_eq!(export.scope, ExportScope::User);
+    assert_eq!(export.user_id, Some(student_id));
+
+    let key = export.storage_key.unwrap();
+    assert!(key.starts_with(&format!("users/{}/", student_id.to_hex())));
+    assert!(key.ends_with(".pdf"));
+    let files = storage.files.lock().unwrap();
+    let pdf = files.get(&key).expect("PDF uploaded");
+    assert!(!pdf.is_empty());
+    assert!(pdf.starts_with(b"%PDF"));
+}
+
+#[tokio::test]
+async fn test_user_export_access_rules() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+    let csrf = get_csrf_token(&app).await;
+    let student_id = ObjectId::new();
+    let group_id = ObjectId::new();
+    state
+        .mongo
+        .collection::<Document>("groups")
+        .insert_one(doc! { "_id": group_id, "name": "7А", "student_ids": [student_id] })
+        .await
+        .unwrap();
+
+    // Чужой ученик и учитель другой группы получают 403
+    let classmate = token(&state, &ObjectId::new(), "student", vec![group_id.to_hex()]);
+    let (status, _) = request_export(&app, &classmate, &csrf, &student_id, "csv").await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let outsider = token(
+        &state,
+        &ObjectId::new(),
+        "teacher",
+        vec![ObjectId::new().to_hex()],
+    );
+    let (status, _) = request_export(&app, &outsider, &csrf, &student_id, "csv").await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Учитель группы ученика выгружает отчёт и видит его статус
+    let teacher = token(&state, &ObjectId::new(), "teacher", vec![group_id.to_hex()]);
+    let (status, body) = request_export(&app, &teacher, &csrf, &student_id, "xlsx").await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let export_id = body["export_id"].as_str().unwrap();
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(format!("/stats/exports/{}", export_id))
+                .header("authorization", format!("Bearer {}", teacher))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(format!("/stats/exports/{}", export_id))
+                .header("authorization", format!("Bearer {}", outsider))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
@@ -0,0 +1,399 @@
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use chrono::Utc;
+use futures::TryStreamExt;
+use mongodb::bson::{doc, oid::ObjectId, Document};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use trainingground_api::{
+    create_router,
+    middlewares::auth::{JwtClaims, JwtService},
+    services::AppState,
+};
+use uuid::Uuid;
+
+mod common;
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_purge_removes_personal_data_and_keeps_group_aggregates() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+    let admin = jwt(&state, "admin");
+    let (user_id, email) = register_and_login(&app).await;
+    let group_id = seed_student_data(&state, &user_id).await;
+    let before = group_aggregates(&state, group_id).await;
+    assert_eq!(before, (2, 15));
+
+    let (status, body) = purge(&app, &admin, &user_id, "someone-else@test.com").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+    assert_eq!(body["code"], "PURGE_CONFIRMATION_MISMATCH");
+
+    let (status, report) = purge(&app, &admin, &user_id, &email.to_uppercase()).await;
+    assert_eq!(status, StatusCode::OK, "{report}");
+    assert_eq!(report["already_purged"], false);
+    assert!(report["removed"]["refresh_tokens"].as_u64().unwrap() >= 1);
+    assert!(report["removed"]["login_history"].as_u64().unwrap() >= 1);
+    assert_eq!(report["removed"]["notifications"], 1);
+    assert_eq!(report["removed"]["email_outbox"], 1);
+    assert_eq!(report["removed"]["anticheat_signals"], 1);
+    assert_eq!(report["reattributed"]["attempt_records"], 2);
+    assert_eq!(report["reattributed"]["progress_summary_v2"], 1);
+    assert_eq!(report["reattributed"]["groups"], 1);
+    assert_eq!(report["reattributed"]["users"], 1);
+
+    // Персональных полей не осталось, учётная запись - заглушка под псевдонимом
+    let users = state.mongo.collection::<Document>("users");
+    let object_id = ObjectId::parse_str(&user_id).unwrap();
+    assert!(users
+        .find_one(doc! { "_id": object_id })
+        .await
+        .unwrap()
+        .is_none());
+    assert!(users
+        .find_one(doc! { "email": &email })
+        .await
+        .unwrap()
+        .is_none());
+    let tombstone = users
+        .find_one(doc! { "group_ids": group_id.to_hex() })
+        .await
+        .unwrap()
+        .expect("account moved to pseudonym");
+    assert_eq!(tombstone.get_str("name").unwrap(), "Deleted user");
+    assert!(!tombstone.contains_key("birthYear"));
+    assert!(tombstone.get_bool("is_blocked").unwrap());
+    let pseudonym = tombstone.get_object_id("_id").unwrap();
+    assert_ne!(pseudonym, object_id);
+
+    for (collection, filter) in [
+        ("refresh_tokens", doc! { "userId": object_id }),
+        ("login_history", doc! { "userId": object_id }),
+        ("notifications", doc! { "recipient_id": &user_id }),
+        ("email_outbox", doc! { "recipient_id": object_id }),
+        ("email_outbox", doc! { "recipient_email": &email }),
+        ("anticheat_signals", doc! { "user_id": &user_id }),
+        ("attempt_records", doc! { "user_id": &user_id }),
+        ("progress_summary_v2", doc! { "user_id": &user_id }),
+    ] {
+        let left = state
+            .mongo
+            .collection::<Document>(collection)
+            .count_documents(filter)
+            .await
+            .unwrap();
+        assert_eq!(left, 0, "{collection} still references the user");
+    }
+    let row = state
+        .mongo
+        .collection::<Document>("progress_summary_v2")
+        .find_one(doc! { "_id": format!("{}:purge-level", pseudonym.to_hex()) })
+        .await
+        .unwrap()
+        .expect("progress row moved to pseudonym");
+    assert_eq!(row.get_i32("score").unwrap(), 15);
+
+    assert_eq!(group_aggregates(&state, group_id).await, before);
+
+    // Связь с псевдонимом после завершения не хранится
+    let record = state
+        .mongo
+        .collection::<Document>("user_purges")
+        .find_one(doc! { "_id": object_id })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(record.get_str("status").unwrap(), "completed");
+    assert!(!record.contains_key("pseudonym_id"));
+
+    // Повторный вызов ничего не меняет и не пишет аудит
+    let (status, repeat) = purge(&app, &admin, &user_id, &email).await;
+    assert_eq!(status, StatusCode::OK, "{repeat}");
+    assert_eq!(repeat["already_purged"], true);
+    assert_eq!(repeat["removed"], json!({}));
+    assert_eq!(repeat["reattributed"], json!({}));
+    assert_eq!(group_aggregates(&state, group_id).await, before);
+
+    let audit_entries = state
+        .mongo
+        .collection::<Document>("audit_log")
+        .count_documents(doc! {
+            "event_type": "purge_user",
+            "details": { "$regex": &user_id },
+        })
+        .await
+        .unwrap();
+    assert_eq!(audit_entries, 1);
+
+    cleanup(&state, group_id, pseudonym).await;
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_purge_requires_separate_permission() {
+    let state = Arc::new(common::create_test_state().await);
+    let app = create_router(state.clone());
+    let admin = jwt(&state, "admin");
+    let role = format!("support_{}", &Uuid::new_v4().simple().to_string()[..8]);
+    let (user_id, email) = register_and_login(&app).await;
+
+    let (status, body) = send(
+        &app,
+        &admin,
+        "PUT",
+        &format!("/admin/roles/{}", role),
+        json!({ "permissions": ["users.manage"] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    let support = jwt(&state, &role);
+    let (status, body) = purge(&app, &support, &user_id, &email).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{body}");
+    assert_eq!(body["code"], "PERMISSION_DENIED");
+
+    let (status, _) = purge(&app, &admin, &ObjectId::new().to_hex(), &email).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Пользователь на месте
+    let object_id = ObjectId::parse_str(&user_id).unwrap();
+    let user = state
+        .mongo
+        .collection::<Document>("users")
+        .find_one(doc! { "_id": object_id })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(user.get_str("email").unwrap(), email);
+}
+
+/// Группа с учеником, его ответы, прогресс, уведомление, письмо и сигнал античита
+async fn seed_student_data(state: &AppState, user_id: &str) -> ObjectId {
+    let db = &state.mongo;
+    let object_id = ObjectId::parse_str(user_id).unwrap();
+    let group_id = ObjectId::new();
+    db.collection::<Document>("groups")
+        .insert_one(doc! { "_id": group_id, "name": "Purge group", "student_ids": [object_id] })
+        .await
+        .unwrap();
+    db.collection::<Document>("users")
+        .update_one(
+            doc! { "_id": object_id },
+            doc! { "$set": { "group_ids": [group_id.to_hex()], "birthYear": 2012 } },
+        )
+        .await
+        .unwrap();
+
+    let session_id = format!("purge-{}", ObjectId::new());
+    let attempt = |score: i32| {
+        doc! {
+            "_id": Uuid::new_v4().to_string(),
+            "session_id": &session_id,
+            "user_id": user_id,
+            "task_id": "purge-task",
+            "answer": "answer",
+            "correct": score > 0,
+            "score": score,
+            "timestamp": Utc::now().to_rfc3339(),
+        }
+    };
+    db.collection::<Document>("attempt_records")
+        .insert_many([attempt(10), attempt(5)])
+        .await
+        .unwrap();
+    db.collection::<Document>("progress_summary_v2")
+        .insert_one(doc! {
+            "_id": format!("{}:purge-level", user_id),
+            "user_id": user_id,
+            "level_id": "purge-level",
+            "attempts_total": 2,
+            "correct_count": 2,
+            "percentage": 100.0,
+            "score": 15,
+            "updated_at": Utc::now().to_rfc3339(),
+        })
+        .await
+        .unwrap();
+    db.collection::<Document>("notifications")
+        .insert_one(doc! {
+            "_id": ObjectId::new(),
+            "recipient_id": user_id,
+            "type": "teacher_message",
+            "title": "Привет",
+            "body": "Сообщение ученику",
+            "read": false,
+            "createdAt": mongodb::bson::DateTime::now(),
+        })
+        .await
+        .unwrap();
+    let email = db
+        .collection::<Document>("users")
+        .find_one(doc! { "_id": object_id })
+        .await
+        .unwrap()
+        .unwrap()
+        .get_str("email")
+        .unwrap()
+        .to_string();
+    db.collection::<Document>("email_outbox")
+        .insert_one(doc! {
+            "_id": ObjectId::new(),
+            "notification_id": ObjectId::new(),
+            "recipient_id": object_id,
+            "recipient_email": email,
+            "recipient_name": "Purge student",
+            "subject": "Привет",
+            "body": "Письмо ученику",
+            "status": "sent",
+            "attempts": 1,
+            "createdAt": mongodb::bson::DateTime::now(),
+        })
+        .await
+        .unwrap();
+    db.collection::<Document>("anticheat_signals")
+        .insert_one(doc! { "_id": ObjectId::new(), "session_id": &session_id, "user_id": user_id })
+        .await
+        .unwrap();
+    group_id
+}
+
+/// Попытки и баллы учеников группы - так группу видят отчёты
+async fn group_aggregates(state: &AppState, group_id: ObjectId) -> (u64, i64) {
+    let db = &state.mongo;
+    let students: Vec<String> = db
+        .collection::<Document>("users")
+        .find(doc! { "group_ids": group_id.to_hex() })
+        .await
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap()
+        .iter()
+        .map(|user| user.get_object_id("_id").unwrap().to_hex())
+        .collect();
+    let attempts = db
+        .collection::<Document>("attempt_records")
+        .count_documents(doc! { "user_id": { "$in": &students } })
+        .await
+        .unwrap();
+    let score = db
+        .collection::<Document>("progress_summary_v2")
+        .find(doc! { "user_id": { "$in": &students } })
+        .await
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap()
+        .iter()
+        .map(|row| row.get_i32("score").unwrap() as i64)
+        .sum();
+    (attempts, score)
+}
+
+async fn cleanup(state: &AppState, group_id: ObjectId, pseudonym: ObjectId) {
+    let db = &state.mongo;
+    let hex = pseudonym.to_hex();
+    db.collection::<Document>("groups")
+        .delete_one(doc! { "_id": group_id })
+        .await
+        .unwrap();
+    db.collection::<Document>("users")
+        .delete_one(doc! { "_id": pseudonym })
+        .await
+        .unwrap();
+    for collection in ["attempt_records", "progress_summary_v2"] {
+        db.collection::<Document>(collection)
+            .delete_many(doc! { "user_id": &hex })
+            .await
+            .unwrap();
+    }
+}
+
+fn jwt(state: &AppState, role: &str) -> String {
+    let now = Utc::now().timestamp() as usize;
+    JwtService::from_config(&state.config)
+        .generate_token(JwtClaims {
+            sub: ObjectId::new().to_hex(),
+            role: role.to_string(),
+            group_ids: Vec::new(),
+            iat: now,
+            exp: now + 3600,
+        })
+        .unwrap()
+}
+
+/// Зарегистрировать ученика и войти: появляются refresh-токен и запись истории входов
+async fn register_and_login(app: &axum::Router) -> (String, String) {
+    let email = format!("purge-{}@test.com", Uuid::new_v4());
+    let password = "Student123!@#";
+    let (status, body) = send(
+        app,
+        "",
+        "POST",
+        "/api/v1/auth/register",
+        json!({ "email": email, "password": password, "name": "Purge Student" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "{body}");
+    let user_id = body["user"]["id"].as_str().unwrap().to_string();
+
+    let (status, body) = send(
+        app,
+        "",
+        "POST",
+        "/api/v1/auth/login",
+        json!({ "email": email, "password": password }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    (user_id, email)
+}
+
+async fn purge(
+    app: &axum::Router,
+    token: &str,
+    user_id: &str,
+    confirm: &str,
+) -> (StatusCode, Value) {
+    send(
+        app,
+        token,
+        "POST",
+        &format!("/admin/users/{}/purge", user_id),
+        json!({ "confirm": confirm }),
+    )
+    .await
+}
+
+async fn send(
+    app: &axum::Router,
+    token: &str,
+    method: &str,
+    uri: &str,
+    body: Value,
+) -> (StatusCode, Value) {
+    let csrf_token = Uuid::new_v4().to_string();
+    let mut request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .header("x-csrf-token", &csrf_token)
+        .header("cookie", format!("csrf_token={}", csrf_token))
+        .header("x-request-nonce", Uuid::new_v4().to_string())
+        .header("x-request-timestamp", Utc::now().timestamp().to_string());
+    if !token.is_empty() {
+        request = request.header("authorization", format!("Bearer {}", token));
+    }
+    let response = app
+        .clone()
+        .oneshot(request.body(Body::from(body.to_string())).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or_default())
+}
@@ -397,6 +397,7 @@ export class AppHeader extends LitElement {
     const labels: Record<string, string> = {
       student: 'Студент',
       teacher: 'Учитель',
+      content_moderator: 'Модератор контента',
       content_admin: 'Админ контента',
       admin: 'Администратор',
     };
@@ -432,7 +433,7 @@ export class AppHeader extends LitElement {
         href: '/teacher/notifications',
         active: currentPath.startsWith('/teacher/notifications'),
       });
-    } else if (role === 'content_admin') {
+    } else if (role === 'content_admin' || role === 'content_moderator') {
       // Content admin navigation
       items.push({
         label: 'Шаблоны',
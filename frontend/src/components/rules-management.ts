@@ -339,7 +339,7 @@ export class RulesManagement extends LitElement {
         ${this.coverage.map(
           (entry) => html`
             <div class="coverage-card">
-              <strong>${entry.rule_id}</strong>
+              <strong>${entry.name ?? entry.rule_id}</strong>
               <span>Шаблонов: ${entry.linked_templates}</span>
             </div>
           `,
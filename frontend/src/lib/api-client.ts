@@ -6,6 +6,7 @@ import type {
   AdminTemplateSummary,
   AdminTemplateUpdatePayload,
   AnticheatSettings,
+  AssignIncidentRequest,
   AuditLogEntry,
   AuditLogQueryParams,
   BackupCreateRequest,
@@ -15,23 +16,39 @@ import type {
   BlockUserRequest,
   BulkUserActionRequest,
   BulkUserActionResult,
+  ClientSignal,
+  CreateExportSchedulePayload,
   CreateGroupRequest,
+  CreateIncidentCommentRequest,
+  CreateInviteCodeRequest,
   CreateNotificationTemplatePayload,
   CreateSessionPayload,
   CreateSessionResponse,
   CreateUserRequest,
+  DashboardSummary,
   EmailSettings,
+  EmailTestResponse,
   EmbeddingConsistencyReport,
+  EmbeddingJobList,
   EmbeddingJobSummary,
   EmbeddingRebuildPayload,
   ExportRequestPayload,
   ExportResponsePayload,
+  ExportSchedule,
   ExportStatusPayload,
   FeatureFlagRecord,
   FeatureFlagUpdatePayload,
+  GroupExportList,
+  GroupInviteCode,
   GroupResponse,
   GroupStatsResponse,
+  InAppNotification,
+  InAppNotificationList,
+  InAppNotificationListQuery,
+  IncidentComment,
+  IncidentTimeline,
   IncidentWithUser,
+  JoinGroupResponse,
   LevelCreatePayload,
   LevelReorderPayload,
   LevelSummary,
@@ -39,21 +56,32 @@ import type {
   ListGroupsQuery,
   ListIncidentsQuery,
   ListUsersQuery,
+  LoginHistoryEntry,
+  MaintenanceMode,
+  NotificationDeliveryResponse,
   NotificationHistoryEntry,
   NotificationTemplate,
+  NotificationTemplatePreview,
+  NotificationTemplatePreviewPayload,
+  OpenAiSettings,
+  PurgeUserResponse,
   QueueStatus,
+  RateLimitOverrides,
   RecommendationEntry,
   RequestHintPayload,
   RequestHintResponse,
   ResetPasswordResponse,
   RuleCoverage,
+  RuleCoverageFilters,
   RuleCreatePayload,
   RuleSummary,
   RuleUpdatePayload,
+  RuntimeSettings,
   SendNotificationPayload,
   SendNotificationResponse,
   SessionResponse,
   SettingsTestResponse,
+  SignalBatchResponse,
   SsoSettings,
   StudentCoursesResponse,
   StudentStatsResponse,
@@ -61,6 +89,7 @@ import type {
   SubmitAnswerResponse,
   SystemMetrics,
   SystemSettingsResponse,
+  TeacherGroupResponse,
   TeacherStudentDetail,
   TeacherStudentSummary,
   TemplateDuplicate,
@@ -77,6 +106,7 @@ import type {
   TopicUpdatePayload,
   UpdateGroupRequest,
   UpdateIncidentRequest,
+  UpdateNotificationTemplatePayload,
   UpdateUserRequest,
   UserDetailResponse,
   YandexGptSettings,
@@ -223,6 +253,16 @@ export class ApiClient {
     });
   }
 
+  async sendSignals(sessionId: string, signals: ClientSignal[]) {
+    return this.request<SignalBatchResponse>(
+      `${API_BASE}/sessions/${sessionId}/signals`,
+      {
+        method: 'POST',
+        body: JSON.stringify({ signals }),
+      },
+    );
+  }
+
   async listStudentCourses() {
     return this.request<StudentCoursesResponse>(`${STUDENT_BASE}/courses`);
   }
@@ -231,6 +271,40 @@ export class ApiClient {
     return this.request<StudentStatsResponse>(`${STUDENT_BASE}/stats`);
   }
 
+  async listNotifications(query?: InAppNotificationListQuery) {
+    const params = new URLSearchParams();
+    if (query?.unread_only) params.set('unread_only', 'true');
+    if (query?.limit) params.set('limit', String(query.limit));
+    if (query?.offset) params.set('offset', String(query.offset));
+    if (query?.sort) params.set('sort', query.sort);
+    if (query?.cursor) params.set('cursor', query.cursor);
+
+    const queryString = params.toString();
+    return this.request<InAppNotificationList>(
+      `${API_BASE}/notifications${queryString ? `?${queryString}` : ''}`,
+    );
+  }
+
+  async markNotificationRead(notificationId: string) {
+    return this.request<InAppNotification>(
+      `${API_BASE}/notifications/${notificationId}/read`,
+      { method: 'POST' },
+    );
+  }
+
+  async markAllNotificationsRead() {
+    return this.request<{ updated: number }>(`${API_BASE}/notifications/read-all`, {
+      method: 'POST',
+    });
+  }
+
+  async joinGroup(code: string) {
+    return this.request<JoinGroupResponse>(`${API_BASE}/groups/join`, {
+      method: 'POST',
+      body: JSON.stringify({ code }),
+    });
+  }
+
   async startStudentSession(templateId: string) {
     return this.request<CreateSessionResponse>(`${STUDENT_BASE}/sessions`, {
       method: 'POST',
@@ -369,10 +443,57 @@ export class ApiClient {
     });
   }
 
+  async requestUserExport(userId: string, payload: ExportRequestPayload) {
+    return this.request<ExportResponsePayload>(`${STATS_BASE}/users/${userId}/export`, {
+      method: 'POST',
+      body: JSON.stringify(payload),
+    });
+  }
+
   async getExportStatus(exportId: string) {
     return this.request<ExportStatusPayload>(`${STATS_BASE}/exports/${exportId}`);
   }
 
+  async listGroupExports(
+    groupId: string,
+    params: { limit?: number; offset?: number } = {},
+  ) {
+    const query = new URLSearchParams();
+    if (typeof params.limit === 'number') {
+      query.append('limit', params.limit.toString());
+    }
+    if (typeof params.offset === 'number') {
+      query.append('offset', params.offset.toString());
+    }
+    const suffix = query.toString() ? `?${query.toString()}` : '';
+    return this.request<GroupExportList>(
+      `${STATS_BASE}/groups/${groupId}/exports${suffix}`,
+    );
+  }
+
+  async listExportSchedules(groupId: string) {
+    return this.request<ExportSchedule[]>(
+      `${STATS_BASE}/groups/${groupId}/export-schedules`,
+    );
+  }
+
+  async createExportSchedule(groupId: string, payload: CreateExportSchedulePayload) {
+    return this.request<ExportSchedule>(
+      `${STATS_BASE}/groups/${groupId}/export-schedules`,
+      {
+        method: 'POST',
+        body: JSON.stringify(payload),
+      },
+    );
+  }
+
+  async deleteExportSchedule(groupId: string, scheduleId: string) {
+    return this.request<void>(
+      `${STATS_BASE}/groups/${groupId}/export-schedules/${scheduleId}`,
+      { method: 'DELETE' },
+    );
+  }
+
   async listAdminTemplates(filters: TemplateFilterParams = {}) {
     const query = new URLSearchParams();
     if (filters.status) {
@@ -535,6 +656,32 @@ export class ApiClient {
     return this.request<EmbeddingJobSummary>(`${ADMIN_BASE}/embeddings/progress`);
   }
 
+  async listEmbeddingJobs(params: { limit?: number; offset?: number } = {}) {
+    const query = new URLSearchParams();
+    if (params.limit !== undefined) {
+      query.append('limit', String(params.limit));
+    }
+    if (params.offset !== undefined) {
+      query.append('offset', String(params.offset));
+    }
+    const suffix = query.toString() ? `?${query.toString()}` : '';
+    return this.request<EmbeddingJobList>(`${ADMIN_BASE}/embeddings/jobs${suffix}`);
+  }
+
+  async cancelEmbeddingJob(id: string) {
+    return this.request<EmbeddingJobSummary>(
+      `${ADMIN_BASE}/embeddings/jobs/${id}/cancel`,
+      { method: 'POST' },
+    );
+  }
+
+  async retryFailedEmbeddings(id: string) {
+    return this.request<EmbeddingJobSummary>(
+      `${ADMIN_BASE}/embeddings/jobs/${id}/retry-failed`,
+      { method: 'POST' },
+    );
+  }
+
   async getEmbeddingConsistency() {
     return this.request<EmbeddingConsistencyReport>(
       `${ADMIN_BASE}/embeddings/consistency`,
@@ -620,8 +767,16 @@ export class ApiClient {
     });
   }
 
-  async getRuleCoverage() {
-    return this.request<RuleCoverage[]>(`${ADMIN_BASE}/rules/coverage`);
+  async getRuleCoverage(filters: RuleCoverageFilters = {}) {
+    const query = new URLSearchParams();
+    if (filters.status) {
+      query.append('status', filters.status);
+    }
+    if (filters.topic_id) {
+      query.append('topic_id', filters.topic_id);
+    }
+    const suffix = query.toString() ? `?${query.toString()}` : '';
+    return this.request<RuleCoverage[]>(`${ADMIN_BASE}/rules/coverage${suffix}`);
   }
 
   async getEmbeddingQueueStatus() {
@@ -691,6 +846,51 @@ export class ApiClient {
     });
   }
 
+  /** Счётчики для главной страницы админки (кэш 30 секунд) */
+  async getDashboard() {
+    return this.request<DashboardSummary>(`${ADMIN_BASE}/dashboard`);
+  }
+
+  async getMaintenanceMode() {
+    return this.request<MaintenanceMode>(`${ADMIN_BASE}/system/maintenance`);
+  }
+
+  /** Включить/выключить режим обслуживания (503 для всех ролей не из `allow_roles`) */
+  async setMaintenanceMode(payload: {
+    enabled: boolean;
+    message?: string;
+    allow_roles?: string[];
+    retry_after_seconds?: number;
+  }) {
+    return this.request<MaintenanceMode>(`${ADMIN_BASE}/system/maintenance`, {
+      method: 'POST',
+      body: JSON.stringify(payload),
+    });
+  }
+
+  /** История входов пользователя, новые первыми */
+  async listUserLogins(userId: string, limit = 20) {
+    return this.request<LoginHistoryEntry[]>(
+      `${ADMIN_BASE}/users/${userId}/logins?limit=${limit}`,
+    );
+  }
+
+  /** Завершить все сессии пользователя, включая уже выданные access-токены */
+  async forceLogoutUser(userId: string) {
+    return this.request<{ status: string; revoked_sessions: number }>(
+      `${ADMIN_BASE}/users/${userId}/force-logout`,
+      { method: 'POST' },
+    );
+  }
+
+  /** Необратимо удалить персональные данные; `confirm` - email учётной записи */
+  async purgeUser(userId: string, confirm: string) {
+    return this.request<PurgeUserResponse>(`${ADMIN_BASE}/users/${userId}/purge`, {
+      method: 'POST',
+      body: JSON.stringify({ confirm }),
+    });
+  }
+
   async bulkUserAction(payload: BulkUserActionRequest) {
     return this.request<BulkUserActionResult>(`${ADMIN_BASE}/users/bulk`, {
       method: 'POST',
@@ -710,6 +910,7 @@ export class ApiClient {
     if (query?.search) params.set('search', query.search);
     if (query?.school) params.set('school', query.school);
     if (query?.curator_id) params.set('curator_id', query.curator_id);
+    if (query?.include_archived) params.set('include_archived', 'true');
     if (query?.limit) params.set('limit', String(query.limit));
     if (query?.offset) params.set('offset', String(query.offset));
 
@@ -742,6 +943,58 @@ export class ApiClient {
     });
   }
 
+  async archiveGroup(groupId: string) {
+    return this.request<GroupResponse>(`${ADMIN_BASE}/groups/${groupId}/archive`, {
+      method: 'POST',
+    });
+  }
+
+  async reassignGroupCurator(groupId: string, curatorId: string | null) {
+    return this.request<GroupResponse>(`${ADMIN_BASE}/groups/${groupId}/curator`, {
+      method: 'PUT',
+      body: JSON.stringify({ curator_id: curatorId }),
+    });
+  }
+
+  async listGroupInviteCodes(groupId: string) {
+    return this.request<GroupInviteCode[]>(
+      `${ADMIN_BASE}/groups/${groupId}/invite-codes`,
+    );
+  }
+
+  async createGroupInviteCode(groupId: string, payload: CreateInviteCodeRequest = {}) {
+    return this.request<GroupInviteCode>(`${ADMIN_BASE}/groups/${groupId}/invite-codes`, {
+      method: 'POST',
+      body: JSON.stringify(payload),
+    });
+  }
+
+  async revokeGroupInviteCode(groupId: string, codeId: string) {
+    return this.request<void>(
+      `${ADMIN_BASE}/groups/${groupId}/invite-codes/${codeId}`,
+      { method: 'DELETE' },
+    );
+  }
+
+  async listGroupMembers(groupId: string) {
+    return this.request<TeacherStudentSummary[]>(
+      `${ADMIN_BASE}/groups/${groupId}/members`,
+    );
+  }
+
+  async addGroupMember(groupId: string, userId: string) {
+    return this.request<GroupResponse>(`${ADMIN_BASE}/groups/${groupId}/members`, {
+      method: 'POST',
+      body: JSON.stringify({ user_id: userId }),
+    });
+  }
+
+  async removeGroupMember(groupId: string, userId: string) {
+    return this.request<void>(`${ADMIN_BASE}/groups/${groupId}/members/${userId}`, {
+      method: 'DELETE',
+    });
+  }
+
   async getSystemSettings() {
     return this.request<SystemSettingsResponse>(`${ADMIN_BASE}/settings`);
   }
@@ -753,6 +1006,13 @@ export class ApiClient {
     });
   }
 
+  async updateOpenAiSettings(payload: OpenAiSettings) {
+    return this.request<OpenAiSettings>(`${ADMIN_BASE}/settings/openai`, {
+      method: 'PUT',
+      body: JSON.stringify(payload),
+    });
+  }
+
   async updateSsoSettings(payload: SsoSettings) {
     return this.request<SsoSettings>(`${ADMIN_BASE}/settings/sso`, {
       method: 'PUT',
@@ -774,21 +1034,41 @@ export class ApiClient {
     });
   }
 
+  async updateRateLimitSettings(payload: RateLimitOverrides) {
+    return this.request<RateLimitOverrides>(`${ADMIN_BASE}/settings/rate-limits`, {
+      method: 'PUT',
+      body: JSON.stringify(payload),
+    });
+  }
+
+  async reloadRuntimeSettings() {
+    return this.request<RuntimeSettings>(`${ADMIN_BASE}/settings/reload`, {
+      method: 'POST',
+    });
+  }
+
   async testYandexGptSettings() {
     return this.request<SettingsTestResponse>(`${ADMIN_BASE}/settings/test/yandexgpt`, {
       method: 'POST',
     });
   }
 
+  async testOpenAiSettings() {
+    return this.request<SettingsTestResponse>(`${ADMIN_BASE}/settings/test/openai`, {
+      method: 'POST',
+    });
+  }
+
   async testSsoSettings() {
     return this.request<SettingsTestResponse>(`${ADMIN_BASE}/settings/test/sso`, {
       method: 'POST',
     });
   }
 
-  async testEmailSettings() {
-    return this.request<SettingsTestResponse>(`${ADMIN_BASE}/settings/test/email`, {
+  async testEmailSettings(recipient: string) {
+    return this.request<EmailTestResponse>(`${ADMIN_BASE}/settings/test/email`, {
       method: 'POST',
+      body: JSON.stringify({ recipient }),
     });
   }
 
@@ -800,6 +1080,10 @@ export class ApiClient {
     return this.request<BackupRecord[]>(`${ADMIN_BASE}/backups`);
   }
 
+  async getBackup(backupId: string) {
+    return this.request<BackupRecord>(`${ADMIN_BASE}/backups/${backupId}`);
+  }
+
   async createBackup(payload: BackupCreateRequest) {
     return this.request<BackupCreateResponse>(`${ADMIN_BASE}/backups`, {
       method: 'POST',
@@ -825,8 +1109,9 @@ export class ApiClient {
     return response.blob();
   }
 
-  async listTeacherGroups() {
-    return this.request<GroupResponse[]>(`${TEACHER_BASE}/groups`);
+  async listTeacherGroups(options?: { includeStats?: boolean }) {
+    const query = options?.includeStats ? '?include=stats' : '';
+    return this.request<TeacherGroupResponse[]>(`${TEACHER_BASE}/groups${query}`);
   }
 
   async listTeacherGroupStudents(groupId: string) {
@@ -877,6 +1162,38 @@ export class ApiClient {
     });
   }
 
+  async updateTeacherNotificationTemplate(
+    templateId: string,
+    payload: UpdateNotificationTemplatePayload,
+  ) {
+    return this.request<NotificationTemplate>(
+      `${TEACHER_BASE}/notifications/templates/${templateId}`,
+      {
+        method: 'PUT',
+        body: JSON.stringify(payload),
+      },
+    );
+  }
+
+  async deleteTeacherNotificationTemplate(templateId: string) {
+    return this.request<void>(`${TEACHER_BASE}/notifications/templates/${templateId}`, {
+      method: 'DELETE',
+    });
+  }
+
+  async previewTeacherNotificationTemplate(
+    templateId: string,
+    payload: NotificationTemplatePreviewPayload,
+  ) {
+    return this.request<NotificationTemplatePreview>(
+      `${TEACHER_BASE}/notifications/templates/${templateId}/preview`,
+      {
+        method: 'POST',
+        body: JSON.stringify(payload),
+      },
+    );
+  }
+
   async sendTeacherNotification(payload: SendNotificationPayload) {
     return this.request<SendNotificationResponse>(`${TEACHER_BASE}/notifications/send`, {
       method: 'POST',
@@ -890,6 +1207,12 @@ export class ApiClient {
     );
   }
 
+  async getTeacherNotificationStatus(notificationId: string) {
+    return this.request<NotificationDeliveryResponse>(
+      `${TEACHER_BASE}/notifications/${notificationId}/status`,
+    );
+  }
+
   async listIncidents(query?: ListIncidentsQuery) {
     const queryString = this.buildIncidentQueryString(query);
     return this.request<IncidentWithUser[]>(`${ADMIN_BASE}/incidents${queryString}`);
@@ -899,6 +1222,14 @@ export class ApiClient {
     return this.request<IncidentWithUser>(`${ADMIN_BASE}/incidents/${incidentId}`);
   }
 
+  async getIncidentTimeline(incidentId: string, inactivitySeconds?: number) {
+    const query =
+      inactivitySeconds === undefined ? '' : `?inactivity_seconds=${inactivitySeconds}`;
+    return this.request<IncidentTimeline>(
+      `${ADMIN_BASE}/incidents/${incidentId}/timeline${query}`,
+    );
+  }
+
   async updateIncident(incidentId: string, payload: UpdateIncidentRequest) {
     return this.request<IncidentWithUser>(`${ADMIN_BASE}/incidents/${incidentId}`, {
       method: 'PUT',
@@ -906,6 +1237,26 @@ export class ApiClient {
     });
   }
 
+  async assignIncident(incidentId: string, payload: AssignIncidentRequest) {
+    return this.request<IncidentWithUser>(
+      `${ADMIN_BASE}/incidents/${incidentId}/assign`,
+      {
+        method: 'POST',
+        body: JSON.stringify(payload),
+      },
+    );
+  }
+
+  async addIncidentComment(incidentId: string, payload: CreateIncidentCommentRequest) {
+    return this.request<IncidentComment>(
+      `${ADMIN_BASE}/incidents/${incidentId}/comments`,
+      {
+        method: 'POST',
+        body: JSON.stringify(payload),
+      },
+    );
+  }
+
   async unblockIncidentUser(incidentId: string) {
     return this.request<UserDetailResponse>(
       `${ADMIN_BASE}/incidents/${incidentId}/unblock`,
@@ -941,12 +1292,18 @@ export class ApiClient {
     const params = new URLSearchParams();
     if (query.event_type) params.set('event_type', query.event_type);
     if (query.user_id) params.set('user_id', query.user_id);
+    if (query.actor_id) params.set('actor_id', query.actor_id);
+    if (query.action) params.set('action', query.action);
+    if (query.target) params.set('target', query.target);
+    if (query.target_id) params.set('target_id', query.target_id);
+    if (query.session_id) params.set('session_id', query.session_id);
     if (typeof query.success === 'boolean') params.set('success', String(query.success));
     if (query.search) params.set('search', query.search);
     if (query.from) params.set('from', query.from);
     if (query.to) params.set('to', query.to);
     if (typeof query.limit === 'number') params.set('limit', String(query.limit));
     if (typeof query.offset === 'number') params.set('offset', String(query.offset));
+    if (query.cursor) params.set('cursor', query.cursor);
 
     const queryString = params.toString();
     return queryString ? `?${queryString}` : '';
@@ -959,6 +1316,9 @@ export class ApiClient {
     if (query.severity) params.set('severity', query.severity);
     if (query.status) params.set('status', query.status);
     if (query.user_id) params.set('user_id', query.user_id);
+    if (query.session_id) params.set('session_id', query.session_id);
+    if (query.assigned_to) params.set('assigned_to', query.assigned_to);
+    if (query.sort) params.set('sort', query.sort);
     if (typeof query.limit === 'number') params.set('limit', String(query.limit));
     if (typeof query.offset === 'number') params.set('offset', String(query.offset));
     const queryString = params.toString();
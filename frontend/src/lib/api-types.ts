@@ -17,12 +17,33 @@ export interface SessionResponse {
   status: SessionStatus;
   hints_used: number;
   score: number;
+  mode?: SessionMode;
+  answer_timing?: AnswerTimingSummary;
 }
 
+/** Время ответов по часам сервера, мс */
+export interface AnswerTimingSummary {
+  answers: number;
+  median_latency_ms: number;
+  fastest_latency_ms: number;
+}
+
+export type SessionMode = 'normal' | 'review';
+
 export interface CreateSessionPayload {
   user_id: string;
-  task_id: string;
+  /** Не нужен при `mode: 'review'` */
+  task_id?: string;
+  group_id?: string;
+  mode?: SessionMode;
+  /** Сессия по домашнему заданию: шаблон выбирает сервер, `task_id` не нужен */
+  assignment_id?: string;
+}
+
+/** `POST /api/v1/sessions/auto`: ученик берётся из токена, задание подбирает сервер */
+export interface AutoSessionPayload {
   group_id?: string;
+  session_duration_seconds?: number;
 }
 
 export interface CreateSessionResponse {
@@ -66,6 +87,7 @@ export interface StudentStatsResponse {
 export interface SubmitAnswerPayload {
   answer: string;
   idempotency_key?: string;
+  parts?: string[];
 }
 
 export interface SubmitAnswerResponse {
@@ -75,6 +97,34 @@ export interface SubmitAnswerResponse {
   total_score: number;
   current_streak: number;
   feedback?: string;
+  normalization?: NormalizationStep[];
+  parts?: AnswerPartResult[];
+  max_attempts?: number | null;
+  remaining_attempts?: number | null;
+  session_completed?: boolean;
+}
+
+export interface AnswerPartResult {
+  index: number;
+  correct: boolean;
+}
+
+export type NormalizationStep = 'nfc' | 'whitespace' | 'dashes' | 'case_fold' | 'yo_to_ye';
+
+export interface ReviewItem {
+  template_id: string;
+  task_id: string;
+  interval_days: number;
+  ease_factor: number;
+  repetitions: number;
+  lapses: number;
+  last_seen: string;
+  due_at: string;
+}
+
+export interface ReviewNextResponse {
+  items: ReviewItem[];
+  total_due: number;
 }
 
 export interface RequestHintPayload {
@@ -86,13 +136,32 @@ export interface RequestHintPayload {
   language?: string;
 }
 
+export type SignalType = 'tab_switch' | 'paste' | 'devtools';
+
+export interface ClientSignal {
+  type: SignalType;
+  timestamp: string;
+  payload?: Record<string, unknown>;
+}
+
+export interface SignalBatchResponse {
+  accepted: number;
+  counts: Record<SignalType, number>;
+  incident_id?: string;
+}
+
 export interface RequestHintResponse {
   hint: string;
   hint_text: string;
   hints_used: number;
   hints_remaining: number;
+  remaining_hints: number | null;
   cost: number;
   new_score: number;
+  penalty_percent: number;
+  total_penalty_percent: number;
+  next_penalty_percent: number | null;
+  provider: 'cache' | 'llm' | 'rule' | 'generic';
 }
 
 export interface TimerTickEvent {
@@ -111,7 +180,115 @@ export interface TimeExpiredEvent {
   message: string;
 }
 
-export type TimerEvent = TimerTickEvent | TimeExpiredEvent;
+export interface SessionExpiredEvent {
+  type: 'expired';
+  session_id: string;
+  expires_at: string;
+  timestamp: string;
+}
+
+export type AchievementKind = 'first_session' | 'hundred_correct_answers' | 'seven_day_streak';
+
+export interface AchievementUnlockedEvent {
+  type: 'achievement-unlocked';
+  session_id: string;
+  achievement: AchievementKind;
+  unlocked_at: string;
+}
+
+export interface AttemptsExhaustedEvent {
+  type: 'attempts-exhausted';
+  session_id: string;
+  max_attempts: number;
+  score: number;
+  timestamp: string;
+}
+
+export type TimerEvent =
+  | TimerTickEvent
+  | TimeExpiredEvent
+  | SessionExpiredEvent
+  | AchievementUnlockedEvent
+  | AttemptsExhaustedEvent;
+
+export interface AchievementsResponse {
+  current_streak: number;
+  longest_streak: number;
+  last_active_day: string | null;
+  timezone: string;
+  achievements: Array<{ kind: AchievementKind; unlocked_at: string }>;
+}
+
+/** Сертификат за уровень; PDF - `GET /api/v1/me/certificates/{level_id}/pdf` */
+export interface Certificate {
+  level_id: string;
+  level_name: string;
+  topic_id: string;
+  topic_name: string;
+  percentage: number;
+  min_pass_percent: number;
+  earned_at: string;
+}
+
+export interface CertificatesResponse {
+  certificates: Certificate[];
+}
+
+export type AssignmentStatus = 'pending' | 'completed' | 'overdue';
+
+/** Домашнее задание группы (`/api/v1/teacher/groups/{group_id}/assignments`) */
+export interface Assignment {
+  id: string;
+  group_id: string;
+  template_ids: string[];
+  due_at: string;
+  instructions: string;
+  allow_late: boolean;
+  min_score: number;
+  created_by: string;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface CreateAssignmentPayload {
+  template_ids: string[];
+  due_at: string;
+  instructions?: string;
+  allow_late?: boolean;
+  min_score?: number;
+}
+
+/** `PATCH`: срок можно только продлить */
+export interface ExtendAssignmentPayload {
+  due_at: string;
+  allow_late?: boolean;
+}
+
+export interface AssignmentItemStatus {
+  template_id: string;
+  completed: boolean;
+  completed_at: string | null;
+  late: boolean;
+}
+
+/** `GET /api/v1/assignments` */
+export interface StudentAssignmentsResponse {
+  assignments: Array<Assignment & { status: AssignmentStatus; items: AssignmentItemStatus[] }>;
+}
+
+export interface AssignmentProgressResponse {
+  assignment: Assignment;
+  total_items: number;
+  completed_students: number;
+  students: Array<{
+    student_id: string;
+    name: string;
+    status: AssignmentStatus;
+    completed_items: number;
+    late_items: number;
+    last_completed_at: string | null;
+  }>;
+}
 
 export interface AnalyticsEnvelope {
   sessionId: string;
@@ -168,29 +345,81 @@ export interface GroupStatsResponse {
 
 export interface ExportRequestPayload {
   topic_ids: string[];
-  period: {
+  /** По умолчанию - последние 30 дней */
+  period?: {
     from: string;
     to: string;
   };
-  format: 'csv' | 'pdf' | 'xlsx';
-}
+  /** `ndjson` - сырые ответы группы построчно, только для выгрузки группы */
+  format: 'csv' | 'pdf' | 'xlsx' | 'ndjson';
+  /** Белый список полей строки, только для `ndjson` */
+  fields?: NdjsonExportField[];
+  /** Язык подписей, чисел и дат в файле; по умолчанию `ru` */
+  locale?: ExportLocale;
+}
+
+export type ExportLocale = 'ru' | 'en';
+
+export type NdjsonExportField =
+  | 'user_id'
+  | 'user_name'
+  | 'session_id'
+  | 'task_id'
+  | 'answer'
+  | 'correct'
+  | 'score'
+  | 'timestamp'
+  | 'reason';
 
 export interface ExportResponsePayload {
   export_id: string;
   status: string;
   expires_at: string;
+  /** `/stats/exports/{export_id}` - опрос статуса */
+  status_url: string;
 }
 
 export interface ExportStatusPayload {
   export_id: string;
   status: 'pending' | 'processing' | 'ready' | 'failed';
-  format: 'csv' | 'pdf' | 'xlsx';
+  format: 'csv' | 'pdf' | 'xlsx' | 'ndjson';
+  created_at: string;
   expires_at: string;
   completed_at?: string | null;
   download_url?: string | null;
   error?: string | null;
 }
 
+export interface GroupExportList {
+  group_id: string;
+  total: number;
+  limit: number;
+  offset: number;
+  exports: ExportStatusPayload[];
+}
+
+export type ExportScheduleCadence = 'weekly' | 'monthly';
+
+export interface CreateExportSchedulePayload {
+  format: 'csv' | 'pdf' | 'xlsx';
+  cadence: ExportScheduleCadence;
+  /** 1 = понедельник … 7 = воскресенье */
+  day_of_week: number;
+  recipients: string[];
+}
+
+export interface ExportSchedule {
+  schedule_id: string;
+  group_id: string;
+  format: 'csv' | 'pdf' | 'xlsx';
+  cadence: ExportScheduleCadence;
+  day_of_week: number;
+  recipients: string[];
+  last_run_at?: string | null;
+  last_export_id?: string | null;
+  created_at: string;
+}
+
 export type AdminTemplateStatus =
   | 'draft'
   | 'pending_review'
@@ -305,6 +534,13 @@ export interface RuleUpdatePayload {
 export interface RuleCoverage {
   rule_id: string;
   linked_templates: number;
+  slug?: string;
+  name?: string;
+}
+
+export interface RuleCoverageFilters {
+  status?: AdminTemplateStatus;
+  topic_id?: string;
 }
 
 export interface AdminTemplateDetail extends AdminTemplateSummary {
@@ -410,7 +646,19 @@ export interface EmbeddingJobSummary {
   status: string;
   total: number;
   processed: number;
+  failed_count: number;
   created_at: string;
+  cancel_requested_at?: string | null;
+  cancelled_by?: string | null;
+  finished_at?: string | null;
+  retry_of?: string | null;
+}
+
+export interface EmbeddingJobList {
+  total: number;
+  limit: number;
+  offset: number;
+  jobs: EmbeddingJobSummary[];
 }
 
 export interface EmbeddingRebuildPayload {
@@ -427,6 +675,49 @@ export interface EmbeddingConsistencyReport {
 export interface QueueStatus {
   length: number;
   last_event?: ContentChangeEvent;
+  consumer_groups: QueueConsumerGroup[];
+  dead_letter_idle_secs: number;
+  dead_letters: QueueDeadLetter[];
+  dead_letter_stream: QueueDeadLetterStream;
+}
+
+export interface QueueConsumerGroup {
+  name: string;
+  consumers: number;
+  idle_consumers: number;
+  pending: number;
+  last_delivered_id: string;
+  lag?: number | null;
+}
+
+export interface QueueDeadLetter {
+  group: string;
+  id: string;
+  consumer: string;
+  idle_ms: number;
+  delivery_count: number;
+  payload?: Record<string, string> | null;
+}
+
+export interface QueueDeadLetterStream {
+  name: string;
+  length: number;
+  entries: DeadLetteredEvent[];
+}
+
+export interface DeadLetteredEvent {
+  id: string;
+  group: string;
+  attempts: number;
+  error?: string | null;
+  failed_at?: string | null;
+  event: ContentChangeEvent;
+}
+
+export interface QueueClaimResult {
+  entry_id: string;
+  group: string;
+  consumer: string;
 }
 
 export interface ContentChangeEvent {
@@ -450,7 +741,50 @@ export interface FeatureFlagUpdatePayload {
   enabled: boolean;
 }
 
-export type UserRole = 'student' | 'teacher' | 'content_admin' | 'admin';
+export type UserRole =
+  | 'student'
+  | 'teacher'
+  | 'content_moderator'
+  | 'content_admin'
+  | 'admin';
+
+export type Permission =
+  | 'users.manage'
+  | 'users.purge'
+  | 'content.moderate'
+  | 'content.rollback'
+  | 'reports.view'
+  | 'settings.manage';
+
+/** Режим обслуживания: 503 для ролей не из `allow_roles` */
+export interface MaintenanceMode {
+  enabled: boolean;
+  message: string;
+  allow_roles: string[];
+  retry_after_seconds: number;
+  updated_by?: string;
+  updated_at?: string;
+}
+
+/** Отчёт `POST /admin/users/{id}/purge` по коллекциям */
+export interface PurgeUserResponse {
+  user_id: string;
+  already_purged: boolean;
+  purged_at: string;
+  removed: Record<string, number>;
+  reattributed: Record<string, number>;
+}
+
+export interface RolePermissions {
+  role: string;
+  permissions: Permission[];
+  built_in: boolean;
+  customized: boolean;
+}
+
+export interface UpdateRolePermissionsPayload {
+  permissions: Permission[];
+}
 
 export interface UserDetailResponse {
   id: string;
@@ -459,6 +793,7 @@ export interface UserDetailResponse {
   role: UserRole;
   group_ids: string[];
   is_blocked: boolean;
+  block_active: boolean;
   blocked_until?: string;
   block_reason?: string;
   created_at: string;
@@ -466,6 +801,32 @@ export interface UserDetailResponse {
   last_login_at?: string;
 }
 
+export interface DashboardSummary {
+  users: { total: number; active: number; blocked: number };
+  groups: number;
+  templates: {
+    draft: number;
+    pending_review: number;
+    reviewed_once: number;
+    ready: number;
+    published: number;
+    deprecated: number;
+  };
+  open_incidents: number;
+  pending_exports: number;
+  queue_length: number;
+  sessions_started_today: number;
+  generated_at: string;
+}
+
+export interface LoginHistoryEntry {
+  id: string;
+  at: string;
+  ip?: string | null;
+  user_agent?: string | null;
+  method: 'password' | 'sso';
+}
+
 export interface CreateUserRequest {
   email: string;
   password: string;
@@ -513,8 +874,13 @@ export interface ListUsersQuery {
   search?: string;
   limit?: number;
   offset?: number;
+  sort?: UserSort;
+  /** Value of `X-Next-Cursor` from the previous page; replaces `offset` */
+  cursor?: string;
 }
 
+export type UserSort = 'created_at' | '-created_at' | 'email' | 'name';
+
 export interface GroupResponse {
   id: string;
   name: string;
@@ -523,6 +889,8 @@ export interface GroupResponse {
   curator_name?: string;
   description?: string;
   student_count: number;
+  max_students?: number;
+  archived_at?: string;
   created_at: string;
   updated_at: string;
 }
@@ -563,6 +931,20 @@ export interface CreateNotificationTemplatePayload {
   body: string;
 }
 
+export type UpdateNotificationTemplatePayload = Partial<CreateNotificationTemplatePayload>;
+
+/** Без studentId письмо строится для вымышленного ученика */
+export interface NotificationTemplatePreviewPayload {
+  groupId?: string;
+  studentId?: string;
+}
+
+export interface NotificationTemplatePreview {
+  subject: string;
+  body: string;
+  studentId: string | null;
+}
+
 export interface SendNotificationPayload {
   group_id: string;
   template_id: string;
@@ -570,18 +952,66 @@ export interface SendNotificationPayload {
 }
 
 export interface SendNotificationResponse {
-  sent: number;
-  email_disabled: boolean;
+  notificationId: string;
+  queued: number;
+  emailDisabled: boolean;
+}
+
+export type EmailDeliveryStatus = 'queued' | 'sending' | 'sent' | 'failed' | 'skipped';
+
+export interface RecipientDeliveryStatus {
+  studentId: string;
+  email: string;
+  name: string;
+  status: EmailDeliveryStatus;
+  attempts: number;
+  lastError?: string;
+  sentAt?: string;
 }
 
 export interface NotificationHistoryEntry {
   id: string;
-  template_id: string;
-  template_name?: string | null;
+  templateId: string;
+  templateName?: string | null;
   subject: string;
   sent_at: string;
   recipientsCount: number;
   status: string;
+  deliveries: RecipientDeliveryStatus[];
+}
+
+export interface NotificationDeliveryResponse {
+  id: string;
+  status: string;
+  recipients: RecipientDeliveryStatus[];
+}
+
+export type InAppNotificationKind =
+  | 'teacher_message'
+  | 'achievement_unlocked'
+  | 'session_expiring';
+
+export interface InAppNotification {
+  id: string;
+  type: InAppNotificationKind;
+  title: string;
+  body: string;
+  read: boolean;
+  createdAt: string;
+}
+
+export interface InAppNotificationListQuery {
+  unread_only?: boolean;
+  limit?: number;
+  offset?: number;
+}
+
+export interface InAppNotificationList {
+  total: number;
+  unreadCount: number;
+  limit: number;
+  offset: number;
+  items: InAppNotification[];
 }
 
 export interface TeacherStudentSummary {
@@ -598,6 +1028,7 @@ export interface TeacherStudentSummary {
 export interface TeacherStudentDetail {
   summary: TeacherStudentSummary;
   progress: ProgressSummary[];
+  answer_timing?: AnswerTimingSummary | null;
 }
 
 export interface CreateGroupRequest {
@@ -605,6 +1036,33 @@ export interface CreateGroupRequest {
   school: string;
   curator_id?: string;
   description?: string;
+  max_students?: number;
+}
+
+export interface TeacherGroupResponse extends GroupResponse {
+  avg_accuracy?: number | null;
+  active_students_last_7d?: number;
+  last_activity_at?: string | null;
+}
+
+export interface GroupInviteCode {
+  id: string;
+  group_id: string;
+  code: string;
+  max_uses: number;
+  uses: number;
+  expires_at: string;
+  created_at: string;
+}
+
+export interface CreateInviteCodeRequest {
+  expires_in_hours?: number;
+  max_uses?: number;
+}
+
+export interface JoinGroupResponse {
+  group: GroupResponse;
+  joined: boolean;
 }
 
 export interface UpdateGroupRequest {
@@ -612,29 +1070,46 @@ export interface UpdateGroupRequest {
   school?: string;
   curator_id?: string;
   description?: string;
+  max_students?: number;
 }
 
 export interface ListGroupsQuery {
   search?: string;
   school?: string;
   curator_id?: string;
+  include_archived?: boolean;
   limit?: number;
   offset?: number;
 }
 
 export interface YandexGptSettings {
+  /** В ответах - маска вида ••••1234; отправленная обратно маска оставляет ключ без изменений */
   api_key: string;
+  has_secret?: boolean;
   folder_id: string;
   model: string;
   temperature: number;
   max_tokens: number;
 }
 
+/** OpenAI-совместимый сервер (OpenAI, vLLM, Ollama); ключ необязателен */
+export interface OpenAiSettings {
+  base_url: string;
+  api_key?: string;
+  has_secret?: boolean;
+  model: string;
+  temperature: number;
+  max_tokens: number;
+}
+
+export type LlmProviderKind = 'yandexgpt' | 'openai';
+
 export interface SsoSettings {
   enabled: boolean;
   provider: string;
   client_id: string;
   client_secret: string;
+  has_secret?: boolean;
   redirect_uri: string;
 }
 
@@ -643,6 +1118,7 @@ export interface EmailSettings {
   port: number;
   login: string;
   password: string;
+  has_secret?: boolean;
   from_email: string;
   from_name: string;
   use_tls: boolean;
@@ -655,13 +1131,67 @@ export interface AnticheatSettings {
   block_duration_hours: number;
   captcha_enabled: boolean;
   captcha_threshold: number;
+  suspicious_speed_hits?: number;
+  time_window_seconds?: number;
+  auto_block?: boolean;
+  auto_block_min_severity?: 'low' | 'medium' | 'high' | 'critical' | null;
+  /** Пороги сигналов клиента; без них действуют пороги из конфигурации сервера */
+  signal_thresholds?: AnticheatSignalThresholds | null;
+  /** Группы, ученики которых не блокируются автоматически */
+  whitelisted_group_ids?: string[];
+  /** Пороги медианы времени ответа по сложности (мс, 0 - выключено) */
+  answer_latency?: AnswerLatencyThresholds;
+}
+
+export interface AnswerLatencyThresholds {
+  a1_ms: number;
+  a2_ms: number;
+  b1_ms: number;
+  b2_ms: number;
+  min_answers: number;
+}
+
+export interface AnticheatSignalThresholds {
+  tab_switch: number;
+  paste: number;
+  devtools: number;
 }
 
 export interface SystemSettingsResponse {
+  /** Провайдер LLM для подсказок, задаётся в конфигурации сервера */
+  llm_provider?: LlmProviderKind;
   yandexgpt?: YandexGptSettings;
+  openai?: OpenAiSettings;
   sso?: SsoSettings;
   email?: EmailSettings;
   anticheat?: AnticheatSettings;
+  /** Сохранённые лимиты запросов, действуют после перезагрузки настроек */
+  rate_limits?: RateLimitOverrides;
+  /** Действующий снимок перезагружаемых настроек */
+  runtime?: RuntimeSettings;
+}
+
+export interface RateLimitWindow {
+  limit: number;
+  window_secs: number;
+}
+
+export interface RateLimitOverrides {
+  per_user?: RateLimitWindow | null;
+  per_ip?: RateLimitWindow | null;
+  admin_per_user?: RateLimitWindow | null;
+  admin_per_ip?: RateLimitWindow | null;
+  login?: RateLimitWindow | null;
+  register?: RateLimitWindow | null;
+}
+
+export interface RuntimeSettings {
+  loaded_at: string;
+  reloadable: { key: string; source: string }[];
+  rate_limit: Record<string, unknown>;
+  anticheat_signals: Record<string, unknown>;
+  hints: Record<string, unknown>;
+  cors_allowed_origins: string[];
 }
 
 export interface SettingsTestResponse {
@@ -669,6 +1199,24 @@ export interface SettingsTestResponse {
   message?: string;
 }
 
+export type EmailTestStatus =
+  | 'sent'
+  | 'handshake_ok'
+  | 'not_configured'
+  | 'invalid_address'
+  | 'dns_resolution_failed'
+  | 'connection_failed'
+  | 'tls_error'
+  | 'auth_rejected'
+  | 'recipient_refused'
+  | 'send_failed'
+  | 'timeout';
+
+export interface EmailTestResponse extends SettingsTestResponse {
+  status: EmailTestStatus;
+  smtp_code?: number;
+}
+
 export interface SystemMetrics {
   uptime_seconds: number;
   total_users: number;
@@ -679,30 +1227,82 @@ export interface SystemMetrics {
   critical_incidents: number;
   audit_events_24h: number;
   active_sessions: number;
+  process: ProcessMetrics;
+  database: DatabaseMetrics;
+  redis: RedisMetrics;
+  collected_at: string;
+}
+
+export interface ProcessMetrics {
+  pid: number;
+  rss_bytes?: number | null;
+  threads?: number | null;
+  cpu_seconds_total?: number | null;
+  cpu_percent?: number | null;
+}
+
+export interface DatabaseMetrics {
+  name: string;
+  collections: number;
+  documents: number;
+  data_size_bytes: number;
+  storage_size_bytes: number;
+  indexes: number;
+  index_size_bytes: number;
+}
+
+export interface RedisMetrics {
+  used_memory_bytes: number;
+  used_memory_peak_bytes: number;
+  max_memory_bytes: number;
+  connected_clients: number;
+  blocked_clients: number;
+}
+
+export type BackupStatus = 'Pending' | 'Running' | 'Completed' | 'Failed';
+
+export interface BackupCollectionProgress {
+  name: string;
+  total_documents: number;
+  documents_written: number;
+  bytes_written: number;
+  parts: string[];
+  completed: boolean;
 }
 
 export interface BackupRecord {
   id?: string;
   label: string;
-  status: 'Pending' | 'Completed' | 'Failed';
+  status: BackupStatus;
   storage_path?: string;
   error?: string;
   created_at: string;
+  created_by?: string;
+  total_collections?: number;
+  collections?: BackupCollectionProgress[];
+  total_documents?: number;
+  documents_written?: number;
+  bytes_written?: number;
+  progress_percent?: number;
+  finished_at?: string;
 }
 
 export interface BackupCreateRequest {
   label?: string;
+  /** Collections to dump; the whole database when omitted */
+  collections?: string[];
 }
 
 export interface BackupCreateResponse {
   id: string;
-  status: 'Pending' | 'Completed' | 'Failed';
+  status: BackupStatus;
   storage_path?: string;
+  collections: string[];
 }
 
 export interface BackupRestoreResponse {
   id: string;
-  status: 'Pending' | 'Completed' | 'Failed';
+  status: BackupStatus;
   storage_path?: string;
   message: string;
 }
@@ -724,9 +1324,22 @@ export type AuditEventType =
   | 'delete_user'
   | 'block_user'
   | 'unblock_user'
+  | 'force_logout'
+  | 'auto_unblock_user'
+  | 'purge_user'
   | 'create_group'
   | 'update_group'
-  | 'delete_group';
+  | 'delete_group'
+  | 'add_group_member'
+  | 'remove_group_member'
+  | 'archive_group'
+  | 'reassign_curator'
+  | 'create_group_invite'
+  | 'revoke_group_invite'
+  | 'join_group'
+  | 'update_incident'
+  | 'assign_incident'
+  | 'comment_incident';
 
 export interface AuditLogEntry {
   id?: string;
@@ -744,19 +1357,30 @@ export interface AuditLogEntry {
 export interface AuditLogQueryParams {
   event_type?: AuditEventType;
   user_id?: string;
+  actor_id?: string;
+  /** Exact action or a prefix ending with `*`, e.g. `template.*` */
+  action?: string;
+  target?: string;
+  target_id?: string;
   success?: boolean;
   search?: string;
   from?: string;
   to?: string;
   limit?: number;
   offset?: number;
+  /** Value of `X-Next-Cursor` from the previous page; replaces `offset` */
+  cursor?: string;
 }
 
-export type IncidentType = 'speed_violation' | 'repeated_answers' | 'suspicious_pattern';
+export type IncidentType =
+  | 'speed_violation'
+  | 'repeated_answers'
+  | 'suspicious_pattern'
+  | 'client_signals';
 
 export type IncidentSeverity = 'low' | 'medium' | 'high' | 'critical';
 
-export type IncidentStatus = 'open' | 'resolved' | 'false_positive';
+export type IncidentStatus = 'open' | 'investigating' | 'resolved' | 'dismissed';
 
 export type IncidentActionTaken = 'none' | 'flagged' | 'suspended' | 'blocked';
 
@@ -765,20 +1389,33 @@ export interface IncidentDetails {
   repeated_hits?: number;
   time_window_seconds?: number;
   additional_info?: string;
+  signal_counts?: Partial<Record<SignalType, number>>;
 }
 
 export interface IncidentRecord {
   id: string;
   user_id: string;
+  session_id?: string;
   incident_type: IncidentType;
   severity: IncidentSeverity;
   details: IncidentDetails;
   timestamp: string;
   action_taken: IncidentActionTaken;
   status: IncidentStatus;
+  assigned_to?: string | null;
+  updated_at?: string | null;
   resolved_by?: string | null;
   resolved_at?: string | null;
   resolution_note?: string | null;
+  /** Автоблокировка пользователя по этому инциденту */
+  auto_block?: IncidentAutoBlock;
+  unblocked_by?: string;
+  unblocked_at?: string;
+}
+
+export interface IncidentAutoBlock {
+  blocked_at: string;
+  blocked_until: string;
 }
 
 export interface IncidentUserInfo {
@@ -789,9 +1426,51 @@ export interface IncidentUserInfo {
   is_blocked: boolean;
 }
 
+interface TimelineEventBase {
+  timestamp: string;
+  /** Миллисекунды от начала сессии */
+  offset_ms: number;
+}
+
+export type TimelineEventDetails =
+  | { type: 'session_started' }
+  | {
+      type: 'answer';
+      task_id: string;
+      correct: boolean;
+      score: number;
+      total_score: number;
+    }
+  | { type: 'hint'; task_id: string; cost: number }
+  | { type: 'signal'; signal_type: SignalType | 'fast_answers'; payload: unknown }
+  | { type: 'inactivity'; duration_seconds: number }
+  | { type: 'session_ended'; status: 'active' | 'completed' | 'expired' | 'abandoned' };
+
+export type TimelineEvent = TimelineEventBase & TimelineEventDetails;
+
+export interface IncidentTimeline {
+  incident_id: string;
+  session_id: string;
+  user_id: string;
+  started_at?: string | null;
+  inactivity_seconds: number;
+  truncated: boolean;
+  events: TimelineEvent[];
+}
+
+export interface IncidentComment {
+  id: string;
+  incident_id: string;
+  author_id: string;
+  body: string;
+  parent_id?: string;
+  created_at: string;
+}
+
 export interface IncidentWithUser {
   incident: IncidentRecord;
   user?: IncidentUserInfo | null;
+  comments?: IncidentComment[];
 }
 
 export interface ListIncidentsQuery {
@@ -799,13 +1478,24 @@ export interface ListIncidentsQuery {
   severity?: IncidentSeverity;
   status?: IncidentStatus;
   user_id?: string;
+  session_id?: string;
+  assigned_to?: string;
+  sort?: 'timestamp' | 'updated_at';
   limit?: number;
   offset?: number;
 }
 
-export type IncidentResolutionAction = 'resolve' | 'false_positive';
-
 export interface UpdateIncidentRequest {
-  action: IncidentResolutionAction;
+  state: IncidentStatus;
   note?: string;
+  force?: boolean;
+}
+
+export interface AssignIncidentRequest {
+  assigned_to: string | null;
+}
+
+export interface CreateIncidentCommentRequest {
+  body: string;
+  parent_id?: string;
 }
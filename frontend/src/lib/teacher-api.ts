@@ -6,11 +6,14 @@
 import { ApiClient } from './api-client';
 import type {
   ActivityEntry,
+  CreateExportSchedulePayload,
   CreateNotificationTemplatePayload,
   ExportRequestPayload,
   ExportResponsePayload,
+  ExportSchedule,
   ExportStatusPayload,
   GroupResponse,
+  NotificationDeliveryResponse,
   NotificationHistoryEntry,
   NotificationTemplate,
   RecommendationEntry,
@@ -109,6 +112,15 @@ export class TeacherApi {
     return this.client.listTeacherNotificationHistory();
   }
 
+  /**
+   * Доставка рассылки по каждому получателю
+   */
+  async getNotificationStatus(
+    notificationId: string,
+  ): Promise<NotificationDeliveryResponse> {
+    return this.client.getTeacherNotificationStatus(notificationId);
+  }
+
   /**
    * Запросить экспорт (отчёт) группы
    */
@@ -119,6 +131,16 @@ export class TeacherApi {
     return this.client.requestGroupExport(groupId, payload);
   }
 
+  /**
+   * Запросить личный отчёт ученика
+   */
+  async requestStudentExport(
+    studentId: string,
+    payload: ExportRequestPayload,
+  ): Promise<ExportResponsePayload> {
+    return this.client.requestUserExport(studentId, payload);
+  }
+
   /**
    * Получить статус экспорта
    */
@@ -126,6 +148,30 @@ export class TeacherApi {
     return this.client.getExportStatus(exportId);
   }
 
+  /**
+   * Получить расписания регулярных выгрузок группы
+   */
+  async getExportSchedules(groupId: string): Promise<ExportSchedule[]> {
+    return this.client.listExportSchedules(groupId);
+  }
+
+  /**
+   * Создать расписание еженедельной или ежемесячной выгрузки
+   */
+  async createExportSchedule(
+    groupId: string,
+    payload: CreateExportSchedulePayload,
+  ): Promise<ExportSchedule> {
+    return this.client.createExportSchedule(groupId, payload);
+  }
+
+  /**
+   * Удалить расписание выгрузки
+   */
+  async deleteExportSchedule(groupId: string, scheduleId: string): Promise<void> {
+    return this.client.deleteExportSchedule(groupId, scheduleId);
+  }
+
   /**
    * Получить студентов, которые не заходили более N дней
    */
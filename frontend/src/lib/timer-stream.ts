@@ -25,6 +25,9 @@ export class TimerStream {
     this.eventSource.addEventListener('time-expired', (evt) => {
       this.handleEvent(evt as MessageEvent<string>);
     });
+    this.eventSource.addEventListener('expired', (evt) => {
+      this.handleEvent(evt as MessageEvent<string>);
+    });
     this.eventSource.onerror = () => {
       console.warn('Timer SSE disconnected, retrying in 2s');
       setTimeout(() => {
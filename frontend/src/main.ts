@@ -147,7 +147,7 @@ const isAdminConsole = pathname.startsWith('/admin-console') || pathname === '/a
     console.log('[Router] /admin or /admin-console route matched');
     if (!requireAuth()) return;
     console.log('[Router] Auth check passed, checking role...');
-    if (!requireRole(['admin', 'content_admin'])) return;
+    if (!requireRole(['admin', 'content_admin', 'content_moderator'])) return;
     console.log('[Router] Role check passed, loading admin console...');
 
     import('./pages/admin-console').then(() => {
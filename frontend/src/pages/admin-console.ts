@@ -39,37 +39,37 @@ const TAB_DEFINITIONS: ReadonlyArray<{
     id: 'templates',
     label: 'Шаблоны',
     description: 'Создание, ревью и версии',
-    roles: ['admin', 'content_admin'],
+    roles: ['admin', 'content_admin', 'content_moderator'],
   },
   {
     id: 'topics',
     label: 'Темы и уровни',
     description: 'Маршруты обучения',
-    roles: ['admin', 'content_admin'],
+    roles: ['admin', 'content_admin', 'content_moderator'],
   },
   {
     id: 'rules',
     label: 'Правила',
     description: 'Описания, примеры и покрытие',
-    roles: ['admin', 'content_admin'],
+    roles: ['admin', 'content_admin', 'content_moderator'],
   },
   {
     id: 'quality',
     label: 'Качество',
     description: 'Метрики, валидатор и дубликаты',
-    roles: ['admin', 'content_admin'],
+    roles: ['admin', 'content_admin', 'content_moderator'],
   },
   {
     id: 'embeddings',
     label: 'Эмбеддинги',
     description: 'Очередь и консистентность',
-    roles: ['admin', 'content_admin'],
+    roles: ['admin', 'content_admin', 'content_moderator'],
   },
   {
     id: 'enrichment',
     label: 'Обогащение',
     description: 'Генерация и проверка заданий',
-    roles: ['admin', 'content_admin'],
+    roles: ['admin', 'content_admin', 'content_moderator'],
   },
   {
     id: 'feature-flags',
@@ -358,7 +358,7 @@ export class AdminConsole extends LitElement {
     // Установить дефолтный таб в зависимости от роли
     const user = authService.getUser();
     const userRole = user?.role ?? 'student';
-    if (userRole === 'content_admin') {
+    if (userRole === 'content_admin' || userRole === 'content_moderator') {
       this.activeTab = 'templates';
     } else {
       this.activeTab = 'dashboard';
@@ -498,6 +498,27 @@ export class AdminConsole extends LitElement {
         value: this.formatUptime(metrics.uptime_seconds),
         sub: 'с момента запуска',
       },
+      {
+        label: 'Память API',
+        value:
+          metrics.process.rss_bytes != null
+            ? this.formatBytes(metrics.process.rss_bytes)
+            : '—',
+        sub:
+          metrics.process.cpu_percent != null
+            ? `CPU ${metrics.process.cpu_percent.toFixed(1)}%`
+            : 'CPU недоступен',
+      },
+      {
+        label: 'MongoDB',
+        value: this.formatBytes(metrics.database.storage_size_bytes),
+        sub: `${metrics.database.collections} коллекций, индексы ${this.formatBytes(metrics.database.index_size_bytes)}`,
+      },
+      {
+        label: 'Redis',
+        value: this.formatBytes(metrics.redis.used_memory_bytes),
+        sub: `${metrics.redis.connected_clients} клиентов`,
+      },
     ];
 
     return html`
@@ -544,6 +565,17 @@ export class AdminConsole extends LitElement {
     return `${minutes}м`;
   }
 
+  private formatBytes(bytes: number) {
+    const units = ['Б', 'КБ', 'МБ', 'ГБ'];
+    let value = bytes;
+    let unit = 0;
+    while (value >= 1024 && unit < units.length - 1) {
+      value /= 1024;
+      unit += 1;
+    }
+    return `${value.toFixed(unit === 0 ? 0 : 1)} ${units[unit]}`;
+  }
+
   private async handleMetricsRefresh() {
     await this.refreshData();
   }
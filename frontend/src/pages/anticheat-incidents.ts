@@ -1,7 +1,7 @@
 import '@/components/app-header';
 import { ApiClient } from '@/lib/api-client';
 import type {
-  IncidentResolutionAction,
+  IncidentComment,
   IncidentSeverity,
   IncidentStatus,
   IncidentType,
@@ -28,6 +28,7 @@ const INCIDENT_TYPE_LABELS: Record<IncidentType, string> = {
   speed_violation: 'Speed Hack',
   repeated_answers: 'Повторяющиеся ответы',
   suspicious_pattern: 'Подозрительные действия',
+  client_signals: 'Сигналы клиента',
 };
 
 const INCIDENT_SEVERITY_LABELS: Record<IncidentSeverity, string> = {
@@ -39,8 +40,16 @@ const INCIDENT_SEVERITY_LABELS: Record<IncidentSeverity, string> = {
 
 const INCIDENT_STATUS_LABELS: Record<IncidentStatus, string> = {
   open: 'Открыт',
+  investigating: 'В работе',
   resolved: 'Решен',
-  false_positive: 'Ложное срабатывание',
+  dismissed: 'Ложное срабатывание',
+};
+
+const TRANSITION_NOTICES: Record<IncidentStatus, string> = {
+  open: 'Инцидент возвращен в очередь',
+  investigating: 'Инцидент взят в работу',
+  resolved: 'Инцидент отмечен как решенный',
+  dismissed: 'Инцидент отмечен как ложное срабатывание',
 };
 
 @customElement('anticheat-incidents-page')
@@ -201,16 +210,41 @@ export class AnticheatIncidentsPage extends LitElement {
       color: #7f8c8d;
     }
 
+    .status-chip.investigating {
+      background: rgba(52, 152, 219, 0.2);
+      color: #2980b9;
+    }
+
     .status-chip.resolved {
       background: rgba(46, 204, 113, 0.2);
       color: #27ae60;
     }
 
-    .status-chip.false_positive {
+    .status-chip.dismissed {
       background: rgba(241, 196, 15, 0.2);
       color: #b9770e;
     }
 
+    .comments {
+      display: flex;
+      flex-direction: column;
+      gap: var(--spacing-sm);
+    }
+
+    .comment {
+      border-left: 3px solid var(--border-color);
+      padding: var(--spacing-xs) var(--spacing-md);
+    }
+
+    .comment.reply {
+      margin-left: var(--spacing-lg);
+    }
+
+    .comment p {
+      margin: var(--spacing-xs) 0;
+      white-space: pre-wrap;
+    }
+
     .actions {
       display: flex;
       gap: var(--spacing-sm);
@@ -358,7 +392,10 @@ export class AnticheatIncidentsPage extends LitElement {
   @state() declare private hasNextPage: boolean;
   @state() declare private selectedIncident?: IncidentWithUser;
   @state() declare private resolutionNote: string;
-  @state() declare private actionLoading: 'resolve' | 'false_positive' | 'unblock' | null;
+  @state() declare private actionLoading: IncidentStatus | 'unblock' | 'comment' | null;
+  @state() declare private comments: IncidentComment[];
+  @state() declare private commentDraft: string;
+  @state() declare private replyTo?: IncidentComment;
   @state() declare private notice?: Notice;
 
   constructor() {
@@ -380,6 +417,9 @@ export class AnticheatIncidentsPage extends LitElement {
     this.selectedIncident = undefined;
     this.resolutionNote = '';
     this.actionLoading = null;
+    this.comments = [];
+    this.commentDraft = '';
+    this.replyTo = undefined;
     this.notice = undefined;
   }
 
@@ -426,8 +466,31 @@ export class AnticheatIncidentsPage extends LitElement {
   }
 
   private selectIncident(incident: IncidentWithUser) {
+    const switched = this.selectedIncident?.incident.id !== incident.incident.id;
     this.selectedIncident = incident;
     this.resolutionNote = incident.incident.resolution_note ?? '';
+    if (switched) {
+      this.commentDraft = '';
+      this.replyTo = undefined;
+    }
+    if (incident.comments) {
+      this.comments = incident.comments;
+    } else {
+      if (switched) this.comments = [];
+      // Обсуждение приходит только в карточке инцидента
+      void this.loadComments(incident.incident.id);
+    }
+  }
+
+  private async loadComments(incidentId: string) {
+    try {
+      const detail = await this.client.getIncident(incidentId);
+      if (this.selectedIncident?.incident.id === incidentId) {
+        this.comments = detail.comments ?? [];
+      }
+    } catch (error) {
+      console.error('Failed to load incident comments', error);
+    }
   }
 
   private updateFilter(field: keyof FilterState, value: string) {
@@ -459,16 +522,16 @@ export class AnticheatIncidentsPage extends LitElement {
     this.loadIncidents();
   }
 
-  private async handleResolution(action: IncidentResolutionAction) {
+  private async handleTransition(state: IncidentStatus) {
     if (!this.selectedIncident) return;
-    this.actionLoading = action;
+    this.actionLoading = state;
     this.notice = undefined;
 
     try {
       const updated = await this.client.updateIncident(
         this.selectedIncident.incident.id,
         {
-          action,
+          state,
           note: this.resolutionNote.trim() || undefined,
         },
       );
@@ -477,13 +540,7 @@ export class AnticheatIncidentsPage extends LitElement {
         record.incident.id === updated.incident.id ? updated : record,
       );
       this.selectIncident(updated);
-      this.notice = {
-        type: 'success',
-        message:
-          action === 'resolve'
-            ? 'Инцидент отмечен как решенный'
-            : 'Инцидент отмечен как ложное срабатывание',
-      };
+      this.notice = { type: 'success', message: TRANSITION_NOTICES[state] };
     } catch (error) {
       console.error('Failed to update incident', error);
       this.notice = {
@@ -495,6 +552,33 @@ export class AnticheatIncidentsPage extends LitElement {
     }
   }
 
+  private async handleAddComment() {
+    if (!this.selectedIncident) return;
+    const body = this.commentDraft.trim();
+    if (!body) return;
+    this.actionLoading = 'comment';
+    this.notice = undefined;
+
+    try {
+      const comment = await this.client.addIncidentComment(
+        this.selectedIncident.incident.id,
+        { body, parent_id: this.replyTo?.id },
+      );
+      this.comments = [...this.comments, comment];
+      this.commentDraft = '';
+      this.replyTo = undefined;
+    } catch (error) {
+      console.error('Failed to add incident comment', error);
+      this.notice = {
+        type: 'error',
+        message:
+          error instanceof Error ? error.message : 'Не удалось добавить комментарий',
+      };
+    } finally {
+      this.actionLoading = null;
+    }
+  }
+
   private async handleUnblock() {
     if (!this.selectedIncident) return;
     if (!this.selectedIncident.user || !this.selectedIncident.user.is_blocked) return;
@@ -547,6 +631,39 @@ export class AnticheatIncidentsPage extends LitElement {
     `;
   }
 
+  /** Ответы выводятся сразу под комментарием, на который отвечают */
+  private renderComments() {
+    const replies = new Map<string, IncidentComment[]>();
+    for (const comment of this.comments) {
+      if (!comment.parent_id) continue;
+      const siblings = replies.get(comment.parent_id) ?? [];
+      replies.set(comment.parent_id, [...siblings, comment]);
+    }
+    const ids = new Set(this.comments.map((comment) => comment.id));
+    const roots = this.comments.filter(
+      (comment) => !comment.parent_id || !ids.has(comment.parent_id),
+    );
+
+    const renderThread = (comment: IncidentComment, depth: number): unknown => html`
+      <div class="comment ${depth > 0 ? 'reply' : ''}">
+        <div class="meta">
+          ${comment.author_id} · ${this.formatDate(comment.created_at)}
+        </div>
+        <p>${comment.body}</p>
+        <button class="ghost" @click=${() => (this.replyTo = comment)}>Ответить</button>
+        ${(replies.get(comment.id) ?? []).map((reply) => renderThread(reply, depth + 1))}
+      </div>
+    `;
+
+    return html`
+      <div class="comments">
+        ${roots.length === 0
+          ? html`<p class="meta">Комментариев пока нет</p>`
+          : roots.map((comment) => renderThread(comment, 0))}
+      </div>
+    `;
+  }
+
   private formatDate(value: string) {
     return new Date(value).toLocaleString('ru-RU', {
       dateStyle: 'medium',
@@ -659,6 +776,7 @@ export class AnticheatIncidentsPage extends LitElement {
 
     const incident = this.selectedIncident.incident;
     const user = this.selectedIncident.user;
+    const closed = incident.status === 'resolved' || incident.status === 'dismissed';
 
     return html`
       <div class="detail-header">
@@ -687,6 +805,10 @@ export class AnticheatIncidentsPage extends LitElement {
           <span>Блокировка</span>
           <strong>${user?.is_blocked ? 'Активна' : 'Нет'}</strong>
         </div>
+        <div class="detail-item">
+          <span>Ответственный</span>
+          <strong>${incident.assigned_to ?? 'Не назначен'}</strong>
+        </div>
       </div>
 
       <section>
@@ -712,32 +834,86 @@ export class AnticheatIncidentsPage extends LitElement {
       </section>
 
       <section>
-        <h3>Комментарий</h3>
+        <h3>Обсуждение</h3>
+        ${this.renderComments()}
+        ${this.replyTo
+          ? html`<p class="meta">
+              Ответ на комментарий ${this.replyTo.author_id}
+              <button class="ghost" @click=${() => (this.replyTo = undefined)}>
+                Отмена
+              </button>
+            </p>`
+          : null}
+        <textarea
+          .value=${this.commentDraft}
+          placeholder="Напишите комментарий"
+          @input=${(event: Event) =>
+            (this.commentDraft = (event.target as HTMLTextAreaElement).value)}
+        ></textarea>
+        <button
+          class="secondary"
+          ?disabled=${!this.commentDraft.trim() || this.actionLoading === 'comment'}
+          @click=${this.handleAddComment}
+        >
+          ${this.actionLoading === 'comment' ? 'Отправка...' : 'Добавить комментарий'}
+        </button>
+      </section>
+
+      <section>
+        <h3>Итог разбора</h3>
         <textarea
           .value=${this.resolutionNote}
-          placeholder="Добавьте примечание к инциденту"
+          placeholder="Примечание при закрытии инцидента"
           @input=${(event: Event) =>
             (this.resolutionNote = (event.target as HTMLTextAreaElement).value)}
         ></textarea>
+        ${!closed && !incident.assigned_to
+          ? html`<p class="meta">
+              Чтобы закрыть инцидент, сначала возьмите его в работу
+            </p>`
+          : null}
       </section>
 
       <section class="actions">
-        <button
-          class="primary"
-          ?disabled=${this.actionLoading === 'resolve'}
-          @click=${() => this.handleResolution('resolve')}
-        >
-          ${this.actionLoading === 'resolve' ? 'Сохранение...' : 'Отметить как решено'}
-        </button>
-        <button
-          class="secondary"
-          ?disabled=${this.actionLoading === 'false_positive'}
-          @click=${() => this.handleResolution('false_positive')}
-        >
-          ${this.actionLoading === 'false_positive'
-            ? 'Сохранение...'
-            : 'Ложное срабатывание'}
-        </button>
+        ${incident.status === 'open'
+          ? html`<button
+              class="primary"
+              ?disabled=${this.actionLoading === 'investigating'}
+              @click=${() => this.handleTransition('investigating')}
+            >
+              ${this.actionLoading === 'investigating'
+                ? 'Сохранение...'
+                : 'Взять в работу'}
+            </button>`
+          : null}
+        ${closed
+          ? html`<button
+              class="secondary"
+              ?disabled=${this.actionLoading === 'open'}
+              @click=${() => this.handleTransition('open')}
+            >
+              ${this.actionLoading === 'open' ? 'Сохранение...' : 'Переоткрыть'}
+            </button>`
+          : html`
+              <button
+                class="primary"
+                ?disabled=${!incident.assigned_to || this.actionLoading === 'resolved'}
+                @click=${() => this.handleTransition('resolved')}
+              >
+                ${this.actionLoading === 'resolved'
+                  ? 'Сохранение...'
+                  : 'Отметить как решено'}
+              </button>
+              <button
+                class="secondary"
+                ?disabled=${!incident.assigned_to || this.actionLoading === 'dismissed'}
+                @click=${() => this.handleTransition('dismissed')}
+              >
+                ${this.actionLoading === 'dismissed'
+                  ? 'Сохранение...'
+                  : 'Ложное срабатывание'}
+              </button>
+            `}
         <button
           class="ghost"
           ?disabled=${!user?.is_blocked || this.actionLoading === 'unblock'}
@@ -652,7 +652,9 @@ export class SystemSettingsPage extends LitElement {
     event.preventDefault();
     const form = event.currentTarget as HTMLFormElement;
     const data = new FormData(form);
+    // Поля без формы (пороги сигналов, белый список групп) сохраняются как есть
     const payload: AnticheatSettings = {
+      ...this.anticheatSettings,
       speed_threshold_seconds: Number(data.get('speed_threshold_seconds') ?? 5),
       max_speed_hits: Number(data.get('max_speed_hits') ?? 10),
       max_repeated_hits: Number(data.get('max_repeated_hits') ?? 8),
@@ -698,9 +700,10 @@ export class SystemSettingsPage extends LitElement {
     await this.runTest(
       () => {
         this.testingEmail = true;
-        return this.apiClient.testEmailSettings();
+        const recipient = authService.getUser()?.email ?? this.emailSettings.from_email;
+        return this.apiClient.testEmailSettings(recipient);
       },
-      'Тестовое письмо отправлено (логика заглушки)',
+      'Тестовое письмо отправлено',
       () => (this.testingEmail = false),
     );
   }
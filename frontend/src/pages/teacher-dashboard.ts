@@ -7,9 +7,9 @@ import type {
   ActivityEntry,
   ExportRequestPayload,
   ExportStatusPayload,
-  GroupResponse,
   GroupStatsResponse,
   RecommendationEntry,
+  TeacherGroupResponse,
   TopicAnalyticsEntry,
 } from '@/lib/api-types';
 import { authService } from '@/lib/auth-service';
@@ -373,8 +373,8 @@ export class TeacherDashboard extends LitElement {
   @state() declare private exportStatus?: ExportStatusPayload;
   @state() declare private lastUpdated?: string;
   @state() declare private groupId: string | null;
-  @state() declare private groups: GroupResponse[];
-  @state() declare private selectedGroup?: GroupResponse;
+  @state() declare private groups: TeacherGroupResponse[];
+  @state() declare private selectedGroup?: TeacherGroupResponse;
   @state() declare private groupLoading: boolean;
   @state() declare private analyticsLoading: boolean;
   @state() declare private analyticsError?: string;
@@ -492,6 +492,18 @@ export class TeacherDashboard extends LitElement {
           <span>Студентов</span>
           <strong>${this.selectedGroup.student_count}</strong>
         </div>
+        <div>
+          <span>Активны за 7 дней</span>
+          <strong>${this.selectedGroup.active_students_last_7d ?? '—'}</strong>
+        </div>
+        <div>
+          <span>Последняя активность</span>
+          <strong>
+            ${this.selectedGroup.last_activity_at
+              ? new Date(this.selectedGroup.last_activity_at).toLocaleString()
+              : '—'}
+          </strong>
+        </div>
         <div>
           <span>Описание</span>
           <strong>${this.selectedGroup.description ?? '—'}</strong>
@@ -642,7 +654,7 @@ export class TeacherDashboard extends LitElement {
     this.groupLoading = true;
     this.error = undefined;
     try {
-      this.groups = await this.client.listTeacherGroups();
+      this.groups = await this.client.listTeacherGroups({ includeStats: true });
       const params = new URLSearchParams(window.location.search);
       const requested = params.get('groupId');
       const candidate =
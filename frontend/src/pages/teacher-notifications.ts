@@ -370,7 +370,7 @@ export class TeacherNotificationsPage extends LitElement {
                       (entry) => html`
                         <tr>
                           <td>${new Date(entry.sent_at).toLocaleString()}</td>
-                          <td>${entry.template_name ?? '—'}</td>
+                          <td>${entry.templateName ?? '—'}</td>
                           <td>${entry.subject}</td>
                           <td>${entry.recipientsCount}</td>
                           <td>${entry.status}</td>
@@ -431,9 +431,9 @@ export class TeacherNotificationsPage extends LitElement {
         group_id: this.selectedGroupId,
         template_id: this.selectedTemplateId,
       });
-      this.statusMessage = response.email_disabled
+      this.statusMessage = response.emailDisabled
         ? 'Отправка отключена в настройках сервера.'
-        : `Отправлено ученикам: ${response.sent}`;
+        : `Писем поставлено в очередь: ${response.queued}`;
       await this.loadHistory();
     } catch (error) {
       this.statusMessage = `Ошибка отправки: ${(error as Error).message}`;
@@ -5,12 +5,17 @@ import { lessonStore, type ScoreState } from '@/lib/session-store';
 import { LitElement, css, html } from 'lit';
 import { customElement, state } from 'lit/decorators.js';
 
+interface SessionDevice {
+  browser: string;
+  os: string;
+  ip?: string | null;
+}
+
 interface ActiveSession {
   id: string;
+  device: SessionDevice;
   created_at: string;
   last_used_at: string;
-  user_agent?: string;
-  ip?: string;
   is_current: boolean;
 }
 
@@ -547,6 +552,46 @@ export class UserProfile extends LitElement {
     }
   }
 
+  private async handleRevokeSession(sessionId: string) {
+    if (!confirm('Завершить эту сессию?')) {
+      return;
+    }
+
+    this.loading = true;
+    this.error = '';
+    this.success = '';
+
+    try {
+      const token = authService.getToken();
+
+      if (!token) {
+        throw new Error('Not authenticated');
+      }
+
+      const response = await fetch(
+        `/api/v1/auth/sessions/${encodeURIComponent(sessionId)}/revoke`,
+        {
+          method: 'POST',
+          headers: {
+            Authorization: `Bearer ${token}`,
+          },
+          credentials: 'include',
+        },
+      );
+
+      if (!response.ok) {
+        throw new Error('Failed to revoke session');
+      }
+
+      this.success = 'Сессия завершена';
+      await this.loadSessions();
+    } catch (err) {
+      this.error = err instanceof Error ? err.message : 'Не удалось завершить сессию';
+    } finally {
+      this.loading = false;
+    }
+  }
+
   private formatDate(dateString: string): string {
     try {
       const date = new Date(dateString);
@@ -562,16 +607,9 @@ export class UserProfile extends LitElement {
     }
   }
 
-  private parseUserAgent(ua?: string): string {
-    if (!ua) return 'Неизвестное устройство';
-
-    // Simple user agent parsing
-    if (ua.includes('Chrome')) return 'Chrome';
-    if (ua.includes('Firefox')) return 'Firefox';
-    if (ua.includes('Safari')) return 'Safari';
-    if (ua.includes('Edge')) return 'Edge';
-
-    return 'Браузер';
+  private formatDevice(device: SessionDevice): string {
+    const parts = [device.browser, device.os].filter((part) => part !== 'Unknown');
+    return parts.length > 0 ? parts.join(', ') : 'Неизвестное устройство';
   }
 
   render() {
@@ -669,14 +707,24 @@ export class UserProfile extends LitElement {
                       <div class="session-item ${session.is_current ? 'current' : ''}">
                         <div class="session-header">
                           <div class="session-device">
-                            ${this.parseUserAgent(session.user_agent)}
+                            ${this.formatDevice(session.device)}
                           </div>
                           ${session.is_current
                             ? html`<span class="session-badge">Текущая</span>`
-                            : ''}
+                            : html`
+                                <button
+                                  class="secondary"
+                                  @click=${() => this.handleRevokeSession(session.id)}
+                                  ?disabled=${this.loading}
+                                >
+                                  Завершить
+                                </button>
+                              `}
                         </div>
                         <div class="session-info">
-                          ${session.ip ? html`<div>IP: ${session.ip}</div>` : ''}
+                          ${session.device.ip
+                            ? html`<div>IP: ${session.device.ip}</div>`
+                            : ''}
                           <div>Создана: ${this.formatDate(session.created_at)}</div>
                           <div>
                             Последняя активность: ${this.formatDate(session.last_used_at)}
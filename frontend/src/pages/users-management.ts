@@ -629,6 +629,7 @@ export class UsersManagement extends LitElement {
             <option value="">Все</option>
             <option value="student">Student</option>
             <option value="teacher">Teacher</option>
+            <option value="content_moderator">Content Moderator</option>
             <option value="content_admin">Content Admin</option>
             <option value="admin">Admin</option>
           </select>
@@ -704,8 +705,8 @@ export class UsersManagement extends LitElement {
                 <td>${user.name}</td>
                 <td>${user.role}</td>
                 <td>
-                  <span class="badge ${user.is_blocked ? 'blocked' : 'active'}">
-                    ${user.is_blocked ? 'Заблокирован' : 'Активен'}
+                  <span class="badge ${user.block_active ? 'blocked' : 'active'}">
+                    ${user.block_active ? 'Заблокирован' : 'Активен'}
                   </span>
                 </td>
                 <td>${this.formatDate(user.created_at)}</td>
@@ -795,6 +796,7 @@ export class UsersManagement extends LitElement {
                 <select name="role" required>
                   <option value="student">Student</option>
                   <option value="teacher">Teacher</option>
+                  <option value="content_moderator">Content Moderator</option>
                   <option value="content_admin">Content Admin</option>
                   <option value="admin">Admin</option>
                 </select>
@@ -849,6 +851,7 @@ export class UsersManagement extends LitElement {
                 <select name="role" .value=${this.selectedUser.role} required>
                   <option value="student">Student</option>
                   <option value="teacher">Teacher</option>
+                  <option value="content_moderator">Content Moderator</option>
                   <option value="content_admin">Content Admin</option>
                   <option value="admin">Admin</option>
                 </select>
@@ -5,6 +5,8 @@
  * Provides composable-like functions for easy integration into components.
  */
 
+import { authService } from '@/lib/auth-service';
+
 /**
  * Represents a single feature flag
  */
@@ -15,10 +17,10 @@ export interface FeatureFlag {
 }
 
 /**
- * Feature flags response from API
+ * Feature flags response from API: flag_key -> enabled, resolved for the current user
  */
 export interface FeatureFlagsResponse {
-  flags: FeatureFlag[];
+  flags: Record<string, boolean>;
 }
 
 /**
@@ -49,7 +51,6 @@ class FeatureFlagsService {
   };
 
   private userId: string | null = null;
-  private groupId: string | null = null;
   private refreshInterval: number | null = null;
   private listeners: Set<StateListener> = new Set();
 
@@ -61,9 +62,14 @@ class FeatureFlagsService {
   /**
    * Initialize service with user context
    */
-  initialize(userId: string | null, groupId: string | null): void {
+  initialize(userId: string | null, _groupId: string | null): void {
+    // Flags are resolved per user (groups and role come from the JWT), so drop
+    // the previous user's values on account switch
+    if (this.userId !== userId) {
+      this.state.flags.clear();
+      this.state.lastUpdated = null;
+    }
     this.userId = userId;
-    this.groupId = groupId;
   }
 
   /**
@@ -74,27 +80,22 @@ class FeatureFlagsService {
       return; // Prevent concurrent requests
     }
 
+    const token = authService.getToken();
+    if (!token) {
+      return; // Flags are resolved per user, wait for authentication
+    }
+
     try {
       this.state.loading = true;
       this.state.error = null;
 
-      const params = new URLSearchParams();
-      if (this.userId) {
-        params.append('user_id', this.userId);
-      }
-      if (this.groupId) {
-        params.append('group_id', this.groupId);
-      }
-
-      const response = await fetch(
-        `${API_BASE_URL}/api/feature-flags?${params.toString()}`,
-        {
-          method: 'GET',
-          headers: {
-            'Content-Type': 'application/json',
-          },
+      const response = await fetch(`${API_BASE_URL}/api/v1/feature-flags`, {
+        method: 'GET',
+        headers: {
+          'Content-Type': 'application/json',
+          Authorization: `Bearer ${token}`,
         },
-      );
+      });
 
       if (!response.ok) {
         throw new Error(`API error: ${response.statusText}`);
@@ -104,8 +105,8 @@ class FeatureFlagsService {
 
       // Update state
       this.state.flags.clear();
-      for (const flag of data.flags) {
-        this.state.flags.set(flag.flag_key, flag);
+      for (const [flagKey, enabled] of Object.entries(data.flags)) {
+        this.state.flags.set(flagKey, { flag_key: flagKey, enabled, config: {} });
       }
 
       this.state.lastUpdated = Date.now();
@@ -117,7 +118,7 @@ class FeatureFlagsService {
       this.notifyListeners();
 
       console.log('[FeatureFlags] Fetched and cached flags', {
-        count: data.flags.length,
+        count: this.state.flags.size,
         flags: Array.from(this.state.flags.keys()),
       });
     } catch (error) {
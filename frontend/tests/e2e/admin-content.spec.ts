@@ -109,6 +109,30 @@ const templates: TemplateRecord[] = [];
           active_sessions: 50,
           audit_events_24h: 500,
           uptime_seconds: 86400,
+          process: {
+            pid: 1,
+            rss_bytes: 134217728,
+            threads: 12,
+            cpu_seconds_total: 42,
+            cpu_percent: 3.5,
+          },
+          database: {
+            name: 'trainingground',
+            collections: 25,
+            documents: 12000,
+            data_size_bytes: 52428800,
+            storage_size_bytes: 41943040,
+            indexes: 60,
+            index_size_bytes: 8388608,
+          },
+          redis: {
+            used_memory_bytes: 16777216,
+            used_memory_peak_bytes: 20971520,
+            max_memory_bytes: 0,
+            connected_clients: 8,
+            blocked_clients: 0,
+          },
+          collected_at: '2024-01-01T00:00:00Z',
         }),
       });
     });
@@ -99,6 +99,9 @@ db.createCollection('templates', {
         params: { bsonType: 'object' },
         version: { bsonType: 'int', minimum: 1 },
         active: { bsonType: 'bool' },
+        archived: { bsonType: 'bool' },
+        locale: { bsonType: 'string' },
+        translations: { bsonType: 'object' },
         createdAt: { bsonType: 'date' }
       }
     }
@@ -258,6 +261,12 @@ db.createCollection('feature_flags', {
         enabled: { bsonType: 'bool' },
         scope: { enum: ['global', 'group', 'user'] },
         target_ids: { bsonType: 'array', items: { bsonType: 'string' } },
+        enabled_for_roles: {
+          bsonType: 'array',
+          items: { enum: ['student', 'teacher', 'content_admin', 'admin'] }
+        },
+        enabled_for_user_ids: { bsonType: 'array', items: { bsonType: 'string' } },
+        rollout_percentage: { bsonType: ['int', 'null'], minimum: 0, maximum: 100 },
         config: { bsonType: 'object' },
         version: { bsonType: 'int', minimum: 1 },
         updated_at: { bsonType: 'date' },
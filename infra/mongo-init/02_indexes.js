@@ -15,6 +15,9 @@ print('[OK] Users indexes created');
 // === GROUPS ===
 db.groups.createIndex({ teacher_id: 1 });
 db.groups.createIndex({ student_ids: 1 });
+// Коды приглашения: поиск по коду при вступлении и список действующих кодов группы
+db.group_invite_codes.createIndex({ code: 1 }, { unique: true });
+db.group_invite_codes.createIndex({ groupId: 1, createdAt: -1 });
 print('[OK] Groups indexes created');
 
 // === TOPICS ===
@@ -32,6 +35,8 @@ db.templates.createIndex({ level_id: 1, active: 1 });
 db.templates.createIndex({ rule_ids: 1 });
 db.templates.createIndex({ version: 1 });
 db.templates.createIndex({ createdAt: 1 });
+// Versions history and diff: /admin/templates/{id}/versions[/{version}/diff]
+db.template_versions.createIndex({ template_id: 1, version: -1 });
 print('[OK] Templates indexes created');
 
 // === TASKS (TTL: 30 days) ===
@@ -78,6 +83,8 @@ db.feature_flags.createIndex({ scope: 1 });
 db.feature_flags.createIndex({ enabled: 1 });
 db.feature_flags.createIndex({ updated_at: -1 });
 db.feature_flags.createIndex({ scope: 1, target_ids: 1 });
+// History of changes: GET /admin/feature-flags/{flag_key}/history (newest first)
+db.feature_flag_history.createIndex({ flag_key: 1, version: -1 });
 print('[OK] Feature flags indexes created');
 
 // === MATERIALIZED STATS ===
@@ -101,4 +108,27 @@ db.leaderboards.createIndex({ scope: 1, scope_id: 1 }, { unique: true, sparse: t
 db.leaderboards.createIndex({ generatedAt: 1 }, { expireAfterSeconds: 86400 }); // 24 hours
 print('[OK] Leaderboards indexes created (TTL: 24 hours)');
 
+// === REPORT EXPORT SCHEDULES ===
+db.report_export_schedules.createIndex({ group_id: 1, teacher_id: 1, createdAt: 1 });
+print('[OK] Report export schedule indexes created');
+
+// === SESSION ARCHIVE ===
+db.sessions.createIndex({ started_at: 1 });
+db.attempt_records.createIndex({ session_id: 1 });
+db.session_archive_stubs.createIndex({ archive_key: 1 });
+db.sessions_rehydrated.createIndex({ expires_at: 1 }, { expireAfterSeconds: 0 });
+print('[OK] Session archive indexes created (rehydrated copies expire by expires_at)');
+
+// === ANTICHEAT SETTINGS PREVIEW ===
+// analytics worker ищет сессии с новыми попытками по timestamp
+db.attempt_records.createIndex({ timestamp: -1 });
+// Агрегаты хранятся 35 дней: с запасом покрывают максимальное окно предпросмотра (30 дней)
+db.anticheat_session_stats.createIndex({ last_answer_at: 1 }, { expireAfterSeconds: 35 * 24 * 3600 });
+print('[OK] Anticheat session stats indexes created (TTL: 35 days)');
+
+// === PREFETCH MANIFEST (existence checks only) ===
+db.progress_summary_v2.createIndex({ user_id: 1, percentage: 1 });
+db.sessions.createIndex({ user_id: 1, completed_at: -1 });
+print('[OK] Prefetch signal indexes created');
+
 print('[SUCCESS] All indexes created successfully');
@@ -63,6 +63,8 @@ call :check_metric "db_operations_total" "Database operations"
 call :check_metric "cache_operations_total" "Cache operations"
 call :check_metric "sessions_total" "Sessions total"
 call :check_metric "answers_submitted_total" "Answers submitted"
+call :check_metric "hints_requested_total" "Hints requested"
+call :check_metric "active_sessions" "Active sessions"
 call :check_metric "anticheat_violations_total" "Anticheat violations"
 
 REM 5. Проверка SLA rules
@@ -127,6 +127,18 @@ else
     ((FAILED++))
 fi
 
+if check_metric "hints_requested_total" "Hints requested"; then
+    ((PASSED++))
+else
+    ((FAILED++))
+fi
+
+if check_metric "active_sessions" "Active sessions"; then
+    ((PASSED++))
+else
+    ((FAILED++))
+fi
+
 # Anticheat metrics
 if check_metric "anticheat_violations_total" "Anticheat violations"; then
     ((PASSED++))
@@ -24,7 +24,7 @@ rem disable rate limits for performance run
 set RATE_LIMIT_DISABLED=1
 set RATE_LIMIT_PER_IP=10000
 set HINTS_MAX_PER_SESSION=0
-set HINTS_PYTHON_API_ENABLED=0
+set HINTS_LLM_ENABLED=0
 set SSE_MAX_STREAM_SECONDS=20
 set SSE_TICK_INTERVAL_MS=5
 